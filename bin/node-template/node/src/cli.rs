@@ -8,4 +8,9 @@ pub struct Cli {
 
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// The first block authored with BABE after Aura handed over block production,
+	/// overriding the block reported by the runtime.
+	#[structopt(long = "babe-bootstrap-block", value_name = "BLOCK")]
+	pub babe_bootstrap_block: Option<u32>,
 }
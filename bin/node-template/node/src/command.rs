@@ -23,6 +23,7 @@ use crate::cli::Cli;
 /// Parse and run command line arguments
 pub fn run(version: VersionInfo) -> sc_cli::Result<()> {
 	let opt = sc_cli::from_args::<Cli>(&version);
+	let bootstrap_override = opt.babe_bootstrap_block;
 
 	let mut config = sc_service::Configuration::from_version(&version);
 
@@ -32,7 +33,7 @@ pub fn run(version: VersionInfo) -> sc_cli::Result<()> {
 			subcommand.update_config(&mut config, chain_spec::load_spec, &version)?;
 			subcommand.run(
 				config,
-				|config: _| Ok(new_full_start!(config).0),
+				|config: _| Ok(new_full_start!(config, bootstrap_override).0),
 			)
 		},
 		None => {
@@ -41,7 +42,7 @@ pub fn run(version: VersionInfo) -> sc_cli::Result<()> {
 			opt.run.run(
 				config,
 				service::new_light,
-				service::new_full,
+				|config| service::new_full(config, bootstrap_override),
 				&version,
 			)
 		},
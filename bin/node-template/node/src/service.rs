@@ -4,13 +4,16 @@ use std::sync::Arc;
 use std::time::Duration;
 use sc_client::LongestChain;
 use sc_client_api::ExecutorProvider;
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
+use node_template_runtime::{self, opaque::Block, RuntimeApi, BlockNumber};
 use sc_service::{error::{Error as ServiceError}, AbstractService, Configuration, ServiceBuilder};
 use sp_inherents::InherentDataProviders;
 use sc_executor::native_executor_instance;
 pub use sc_executor::NativeExecutor;
 use sp_consensus_aura::sr25519::{AuthorityPair as AuraPair};
+use sc_consensus_babe::migration;
 use sc_finality_grandpa::{self, FinalityProofProvider as GrandpaFinalityProofProvider, StorageAndProofProvider};
+use futures::FutureExt;
+use log::info;
 
 // Our native executor instance.
 native_executor_instance!(
@@ -19,12 +22,25 @@ native_executor_instance!(
 	node_template_runtime::native_version,
 );
 
+/// The consensus engine authoring the chain.
+///
+/// Aura authors the chain until it hands over block production to BABE, see
+/// `sc_consensus_babe::migration`.
+pub enum Consensus<AuraImport, BabeImport> {
+	/// Aura, with its block import.
+	Aura(AuraImport),
+	/// BABE, with its block import and link, verifying the blocks authored before with Aura.
+	Babe(BabeImport, sc_consensus_babe::BabeLink<Block>),
+}
+
 /// Starts a `ServiceBuilder` for a full service.
 ///
 /// Use this macro if you don't actually need the full service, but just the builder in order to
 /// be able to perform chain operations.
+///
+/// `$bootstrap_override` overrides the first BABE block reported by the runtime.
 macro_rules! new_full_start {
-	($config:expr) => {{
+	($config:expr, $bootstrap_override:expr) => {{
 		use std::sync::Arc;
 		let mut import_setup = None;
 		let inherent_data_providers = sp_inherents::InherentDataProviders::new();
@@ -52,20 +68,52 @@ macro_rules! new_full_start {
 				let (grandpa_block_import, grandpa_link) =
 					sc_finality_grandpa::block_import(client.clone(), &(client.clone() as Arc<_>), select_chain)?;
 
-				let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
-					grandpa_block_import.clone(), client.clone(),
-				);
+				let bootstrap = sc_consensus_babe::migration::bootstrap_block(&*client, $bootstrap_override)?;
+				let (import_queue, consensus) = match bootstrap {
+					None => {
+						let aura_block_import = sc_consensus_aura::AuraBlockImport::<_, _, _, AuraPair>::new(
+							grandpa_block_import.clone(), client.clone(),
+						);
 
-				let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair>(
-					sc_consensus_aura::slot_duration(&*client)?,
-					aura_block_import,
-					Some(Box::new(grandpa_block_import.clone())),
-					None,
-					client,
-					inherent_data_providers.clone(),
-				)?;
+						let import_queue = sc_consensus_aura::import_queue::<_, _, _, AuraPair>(
+							sc_consensus_aura::slot_duration(&*client)?,
+							aura_block_import,
+							Some(Box::new(grandpa_block_import.clone())),
+							None,
+							client,
+							inherent_data_providers.clone(),
+						)?;
+
+						(import_queue, crate::service::Consensus::Aura(grandpa_block_import.clone()))
+					},
+					Some(bootstrap) => {
+						let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+							sc_consensus_babe::Config::get_or_compute_bootstrapped(&*client, bootstrap)?,
+							grandpa_block_import.clone(),
+							client.clone(),
+						)?;
+
+						let aura_verifier = sc_consensus_aura::verifier::<_, _, AuraPair>(
+							sc_consensus_aura::slot_duration(&*client)?,
+							client.clone(),
+							inherent_data_providers.clone(),
+						)?;
+
+						let import_queue = sc_consensus_babe::migration::import_queue(
+							babe_link.clone(),
+							babe_block_import.clone(),
+							aura_verifier,
+							Some(Box::new(grandpa_block_import.clone())),
+							None,
+							client,
+							inherent_data_providers.clone(),
+						)?;
+
+						(import_queue, crate::service::Consensus::Babe(babe_block_import, babe_link))
+					},
+				};
 
-				import_setup = Some((grandpa_block_import, grandpa_link));
+				import_setup = Some((consensus, grandpa_link));
 
 				Ok(import_queue)
 			})?;
@@ -75,22 +123,25 @@ macro_rules! new_full_start {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(config: Configuration)
+///
+/// `bootstrap_override` overrides the first BABE block reported by the runtime.
+pub fn new_full(config: Configuration, bootstrap_override: Option<BlockNumber>)
 	-> Result<impl AbstractService, ServiceError>
 {
 	let is_authority = config.roles.is_authority();
 	let force_authoring = config.force_authoring;
 	let name = config.name.clone();
 	let disable_grandpa = config.disable_grandpa;
+	let grandpa_restart_on_stall = config.grandpa_restart_on_stall;
 
 	// sentry nodes announce themselves as authorities to the network
 	// and should run the same protocols authorities do, but it should
 	// never actively participate in any consensus process.
 	let participates_in_consensus = is_authority && !config.sentry_mode;
 
-	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config);
+	let (builder, mut import_setup, inherent_data_providers) = new_full_start!(config, bootstrap_override);
 
-	let (block_import, grandpa_link) =
+	let (consensus, grandpa_link) =
 		import_setup.take()
 			.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
@@ -102,6 +153,18 @@ pub fn new_full(config: Configuration)
 		})?
 		.build()?;
 
+	if let Consensus::Aura(_) = consensus {
+		// the blocks following the hand over are authored and verified with BABE, which
+		// the node picks up on restart.
+		let bootstrap = migration::await_bootstrap(service.client(), bootstrap_override)
+			.map(|bootstrap| if let Some(bootstrap) = bootstrap {
+				info!("Aura handed over block production to BABE at block {}, restart the node", bootstrap);
+			});
+
+		// the task is considered essential, i.e. the service shuts down once BABE takes over.
+		service.spawn_essential_task("babe-bootstrap", bootstrap);
+	}
+
 	if participates_in_consensus {
 		let proposer = sc_basic_authorship::ProposerFactory::new(
 			service.client(),
@@ -115,22 +178,44 @@ pub fn new_full(config: Configuration)
 		let can_author_with =
 			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
 
-		let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
-			sc_consensus_aura::slot_duration(&*client)?,
-			client,
-			select_chain,
-			block_import,
-			proposer,
-			service.network(),
-			inherent_data_providers.clone(),
-			force_authoring,
-			service.keystore(),
-			can_author_with,
-		)?;
+		match consensus {
+			Consensus::Aura(block_import) => {
+				let aura = sc_consensus_aura::start_aura::<_, _, _, _, _, AuraPair, _, _, _>(
+					sc_consensus_aura::slot_duration(&*client)?,
+					client,
+					select_chain,
+					block_import,
+					proposer,
+					service.network(),
+					inherent_data_providers.clone(),
+					force_authoring,
+					service.keystore(),
+					can_author_with,
+				)?;
+
+				// the AURA authoring task is considered essential, i.e. if it
+				// fails we take down the service with it.
+				service.spawn_essential_task("aura", aura);
+			},
+			Consensus::Babe(block_import, babe_link) => {
+				let babe = sc_consensus_babe::start_babe(sc_consensus_babe::BabeParams {
+					keystore: service.keystore(),
+					client,
+					select_chain,
+					env: proposer,
+					block_import,
+					sync_oracle: service.network(),
+					inherent_data_providers: inherent_data_providers.clone(),
+					force_authoring,
+					babe_link,
+					can_author_with,
+				})?;
 
-		// the AURA authoring task is considered essential, i.e. if it
-		// fails we take down the service with it.
-		service.spawn_essential_task("aura", aura);
+				// the BABE authoring task is considered essential, i.e. if it
+				// fails we take down the service with it.
+				service.spawn_essential_task("babe", babe);
+			},
+		}
 	}
 
 	// if the node isn't actively participating in consensus then it doesn't
@@ -166,7 +251,12 @@ pub fn new_full(config: Configuration)
 			inherent_data_providers: inherent_data_providers.clone(),
 			telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
 			voting_rule: sc_finality_grandpa::VotingRulesBuilder::default().build(),
-			prometheus_registry: service.prometheus_registry()
+			prometheus_registry: service.prometheus_registry(),
+			shared_voter_state: sc_finality_grandpa::SharedVoterState::empty(),
+			stall_detection: Some(sc_finality_grandpa::StallDetection {
+				restart_voter: grandpa_restart_on_stall,
+				..Default::default()
+			}),
 		};
 
 		// the GRANDPA voter task is considered infallible, i.e.
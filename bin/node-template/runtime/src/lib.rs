@@ -98,7 +98,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 1,
+	spec_version: 2,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -112,6 +112,12 @@ pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
 
+/// The length of a BABE epoch, once BABE took over block production from Aura.
+pub const EPOCH_DURATION_IN_SLOTS: u64 = 10 * MINUTES as u64;
+
+/// 1 in 4 BABE slots (on average, not counting collisions) will have a primary block.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -170,19 +176,47 @@ impl system::Trait for Runtime {
 	type ModuleToIndex = ModuleToIndex;
 }
 
+parameter_types! {
+	/// The sr25519 signature verification timed by `benchmark calibrate` on its reference hardware.
+	pub const DoughnutVerificationWeight: Weight = 48_000;
+	/// Hashing the payload twice, for the signature and the revocation check, at 1.2µs per KiB
+	/// on the same hardware, rounded up.
+	pub const DoughnutVerificationWeightPerByte: Weight = 3;
+}
+
 impl DoughnutRuntime for Runtime {
 	type AccountId = <Self as system::Trait>::AccountId;
+	type PublicKeyAccount = ConvertInto;
 	type Call = Call;
 	type Doughnut = <Self as system::Trait>::Doughnut;
 	type TimestampProvider = timestamp::Module<Runtime>;
+	type VerificationWeight = DoughnutVerificationWeight;
+	type VerificationWeightPerByte = DoughnutVerificationWeightPerByte;
+	type RevocationRegistry = ();
 }
 
 impl aura::Trait for Runtime {
 	type AuthorityId = AuraId;
+	/// Aura can hand over block production to BABE with `schedule_hand_over`.
+	type HandOver = Babe;
+}
+
+parameter_types! {
+	pub const EpochDuration: u64 = EPOCH_DURATION_IN_SLOTS;
+	pub const ExpectedBlockTime: u64 = MILLISECS_PER_BLOCK;
+}
+
+impl babe::Trait for Runtime {
+	type EpochDuration = EpochDuration;
+	type ExpectedBlockTime = ExpectedBlockTime;
+	/// There's no session module changing the authorities.
+	type EpochChangeTrigger = babe::SameAuthoritiesForever;
+	type HandleEquivocation = ();
 }
 
 impl grandpa::Trait for Runtime {
 	type Event = Event;
+	type HandleEquivocation = ();
 }
 
 parameter_types! {
@@ -222,12 +256,12 @@ parameter_types! {
 }
 
 impl transaction_payment::Trait for Runtime {
-	type Currency = balances::Module<Runtime>;
-	type OnTransactionPayment = ();
+	type OnChargeTransaction = transaction_payment::CurrencyAdapter<balances::Module<Runtime>, ()>;
 	type TransactionBaseFee = TransactionBaseFee;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = ConvertInto;
 	type FeeMultiplierUpdate = ();
+	type OperationalFeeMultiplierUpdate = ();
 }
 
 impl sudo::Trait for Runtime {
@@ -249,13 +283,15 @@ construct_runtime!(
 		System: system::{Module, Call, Storage, Config, Event},
 		RandomnessCollectiveFlip: randomness_collective_flip::{Module, Call, Storage},
 		Timestamp: timestamp::{Module, Call, Storage, Inherent},
-		Aura: aura::{Module, Config<T>, Inherent(Timestamp)},
+		Aura: aura::{Module, Call, Storage, Config<T>, Inherent(Timestamp)},
 		Grandpa: grandpa::{Module, Call, Storage, Config, Event},
 		Balances: balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: transaction_payment::{Module, Storage},
 		Sudo: sudo::{Module, Call, Config<T>, Storage, Event<T>},
 		// Used for the module template in `./template.rs`
 		TemplateModule: template::{Module, Call, Storage, Event<T>},
+		// Takes over block production from Aura, see `aura::Module::schedule_hand_over`.
+		Babe: babe::{Module, Call, Storage, Inherent(Timestamp)},
 	}
 );
 
@@ -269,16 +305,17 @@ pub type Block = generic::Block<Header, UncheckedExtrinsic>;
 pub type SignedBlock = generic::SignedBlock<Block>;
 /// BlockId type as expected by this runtime.
 pub type BlockId = generic::BlockId<Block>;
-/// The SignedExtension to the basic transaction logic.
-pub type SignedExtra = (
-	Option<PlugDoughnut<Runtime>>,
-	system::CheckVersion<Runtime>,
-	system::CheckGenesis<Runtime>,
-	system::CheckEra<Runtime>,
-	system::CheckNonce<Runtime>,
-	system::CheckWeight<Runtime>,
-	transaction_payment::ChargeTransactionPayment<Runtime>
-);
+frame_support::compose_signed_extra! {
+	/// The SignedExtension to the basic transaction logic.
+	pub type SignedExtra where Doughnut = PlugDoughnut<Runtime> {
+		check_version: system::CheckVersion<Runtime>,
+		check_genesis: system::CheckGenesis<Runtime>,
+		check_era: system::CheckEra<Runtime>,
+		check_nonce: system::CheckNonce<Runtime>,
+		check_weight: system::CheckWeight<Runtime>,
+		charge_transaction_payment: transaction_payment::ChargeTransactionPayment<Runtime>,
+	}
+}
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// Extrinsic type that has already been checked.
@@ -356,6 +393,27 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_consensus_babe::BabeApi<Block> for Runtime {
+		fn configuration() -> sp_consensus_babe::BabeConfiguration {
+			sp_consensus_babe::BabeConfiguration {
+				slot_duration: Babe::slot_duration(),
+				epoch_length: EpochDuration::get(),
+				c: PRIMARY_PROBABILITY,
+				genesis_authorities: Babe::authorities(),
+				randomness: Babe::randomness(),
+				secondary_slots: true,
+			}
+		}
+
+		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
+			Babe::current_epoch_start()
+		}
+
+		fn bootstrap_block() -> Option<BlockNumber> {
+			Babe::bootstrap_block()
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			opaque::SessionKeys::generate(seed)
@@ -366,6 +424,14 @@ impl_runtime_apis! {
 		) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 			opaque::SessionKeys::decode_into_raw_public_keys(&encoded)
 		}
+
+		fn generate_session_keys_with_proof(
+			_owner: Vec<u8>,
+			_seed: Option<Vec<u8>>,
+		) -> Option<(Vec<u8>, Vec<u8>)> {
+			// without the session pallet, there is no ownership message to prove possession with
+			None
+		}
 	}
 
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
@@ -26,6 +26,20 @@ pub struct Cli {
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub run: RunCmd,
+
+	/// Seal blocks on demand instead of authoring them with BABE, for development chains.
+	///
+	/// `manual` seals a block on each `engine_createBlock` RPC call, `instant` seals a block as
+	/// soon as a transaction is submitted. Blocks are finalized with `engine_finalizeBlock`.
+	#[structopt(long = "sealing", value_name = "MODE", requires = "dev", conflicts_with = "light")]
+	pub sealing: Option<crate::Sealing>,
+
+	/// Author blocks with the proof-of-authority engine instead of BABE, for private networks.
+	///
+	/// The block authors are admitted on chain by the network privacy module and sign their
+	/// blocks with their `poa_` key.
+	#[structopt(long = "poa", conflicts_with_all = &["sealing", "light"])]
+	pub poa: bool,
 }
 
 /// Possible subcommands of the main binary.
@@ -52,9 +66,24 @@ pub enum Subcommand {
 	/// The custom benchmark subcommmand benchmarking runtime pallets.
 	#[structopt(
 		name = "benchmark",
-		about = "Benchmark runtime pallets."
+		about = "Benchmark runtime pallets and the verification of doughnuts."
 	)]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// The custom calibrate subcommmand suggesting weight limits and fees for this hardware.
+	#[structopt(
+		name = "calibrate",
+		about = "Time a standard suite on this machine and suggest the block weight limit and \
+		the weight to fee coefficient."
+	)]
+	Calibrate(frame_benchmarking_cli::CalibrateCmd),
+
+	/// The custom doughnut subcommmand for generating, decoding and verifying doughnuts.
+	#[structopt(
+		name = "doughnut",
+		about = "Generate, inspect or verify doughnuts."
+	)]
+	Doughnut(node_doughnut::cli::DoughnutCmd),
 }
 
 /// The `factory` command used to generate transactions.
@@ -34,12 +34,30 @@ where
 		None => {
 			opt.run.init(&version)?;
 			opt.run.update_config(&mut config, load_spec, &version)?;
-			opt.run.run(
-				config,
-				service::new_light,
-				service::new_full,
-				&version,
-			)
+			// BABE can't carry on from a block without the epochs before it.
+			if opt.run.network_config.sync == sc_cli::SyncMode::Warp && !opt.poa {
+				return Err("Warp sync requires --poa".to_string().into())
+			}
+			match opt.sealing {
+				Some(sealing) => opt.run.run(
+					config,
+					service::new_light,
+					|config| service::new_manual_seal(config, sealing),
+					&version,
+				),
+				None if opt.poa => opt.run.run(
+					config,
+					service::new_light,
+					service::new_poa,
+					&version,
+				),
+				None => opt.run.run(
+					config,
+					service::new_light,
+					service::new_full,
+					&version,
+				),
+			}
 		},
 		Some(Subcommand::Inspect(cmd)) => {
 			cmd.init(&version)?;
@@ -58,6 +76,17 @@ where
 
 			cmd.run::<node_runtime::Block, node_executor::Executor>(config)
 		},
+		Some(Subcommand::Calibrate(cmd)) => {
+			cmd.init(&version)?;
+
+			cmd.run::<node_runtime::Block>()
+		},
+		Some(Subcommand::Doughnut(cmd)) => {
+			cmd.init(&version)?;
+			cmd.update_config(&mut config, load_spec, &version)?;
+
+			cmd.run(&config)
+		},
 		Some(Subcommand::Factory(cli_args)) => {
 			cli_args.shared_params.init(&version)?;
 			cli_args.shared_params.update_config(&mut config, load_spec, &version)?;
@@ -32,6 +32,7 @@ pub mod chain_spec;
 
 #[macro_use]
 mod service;
+mod pre_verifier;
 #[cfg(feature = "browser")]
 mod browser;
 #[cfg(feature = "cli")]
@@ -47,6 +48,7 @@ pub use browser::*;
 pub use cli::*;
 #[cfg(feature = "cli")]
 pub use command::*;
+pub use service::Sealing;
 
 /// The chain specification option.
 #[derive(Clone, Debug, PartialEq)]
@@ -0,0 +1,158 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pre-verification of the signatures of the queued blocks.
+//!
+//! The signatures of the extrinsics and of their doughnuts are verified by the workers of the
+//! import queue while the previous blocks are imported. The valid ones are cached, and the block
+//! execution only verifies the signatures missing from the cache.
+
+use std::sync::Arc;
+use codec::{Decode, Encode};
+use node_primitives::{Block, BlockNumber};
+use node_runtime::{
+	SignedExtraAdditionalSigned, SignedExtraParts, SignedPayload, UncheckedExtrinsic, VERSION,
+};
+use sc_client_api::execution_extensions::ExtensionsFactory;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::{BlockOrigin, import_queue::{IncomingBlock, PreVerifier}};
+use sp_core::{ed25519, sr25519, offchain::Capabilities};
+use sp_externalities::Extensions;
+use sp_io::{SignatureCache, SignatureCacheExt};
+use sp_runtime::{
+	MultiSignature,
+	generic::{BlockId, Era},
+	traits::{Header as _, PlugDoughnutApi},
+};
+
+/// Registers the signature cache with the externalities of every runtime call, so the block
+/// execution skips the signatures verified by the `SignaturePreVerifier`.
+pub struct SignatureCacheExtensions(pub Arc<SignatureCache>);
+
+impl ExtensionsFactory for SignatureCacheExtensions {
+	fn extensions_for(&self, _capabilities: Capabilities) -> Extensions {
+		let mut extensions = Extensions::new();
+		extensions.register(SignatureCacheExt(self.0.clone()));
+		extensions
+	}
+}
+
+/// Verifies the signatures of the extrinsics of the queued blocks and of their doughnuts, caching
+/// the valid ones.
+///
+/// The payload signed by an extrinsic depends on the runtime version and on the hashes of the
+/// genesis and era birth blocks, which are taken from the native runtime and the imported blocks.
+/// Signatures are not cached when they can't be computed, e.g. the era birth block isn't imported
+/// yet, or when they don't verify, e.g. the block is built by another runtime version. The block
+/// execution then verifies them as usual.
+pub struct SignaturePreVerifier<C> {
+	client: Arc<C>,
+	cache: Arc<SignatureCache>,
+}
+
+impl<C: HeaderBackend<Block>> SignaturePreVerifier<C> {
+	/// Create a pre-verifier looking up block hashes in `client` and caching signatures in
+	/// `cache`.
+	pub fn new(client: Arc<C>, cache: Arc<SignatureCache>) -> Self {
+		SignaturePreVerifier { client, cache }
+	}
+
+	/// The hash of the imported block with the given number.
+	fn block_hash(&self, number: u64) -> Option<node_primitives::Hash> {
+		self.client.hash(number as BlockNumber).ok().flatten()
+	}
+
+	/// Verify the signatures of `uxt`, included in the block `number`.
+	fn pre_verify_extrinsic(&self, number: BlockNumber, uxt: UncheckedExtrinsic) {
+		let (signer, signature, extra) = match uxt.signature {
+			Some(signed) => signed,
+			None => return,
+		};
+		let parts = SignedExtraParts::from(extra.clone());
+
+		if let Some(doughnut) = &parts.doughnut {
+			let issuer = doughnut.issuer();
+			let issuer: &[u8; 32] = issuer.as_ref();
+			let payload = doughnut.payload();
+			match doughnut.signature_version() {
+				0 => {
+					let signature = sr25519::Signature::from_raw(doughnut.signature());
+					self.cache.sr25519_verify(&signature, &payload, &sr25519::Public::from_raw(*issuer));
+				},
+				1 => {
+					let signature = ed25519::Signature::from_raw(doughnut.signature());
+					self.cache.ed25519_verify(&signature, &payload, &ed25519::Public::from_raw(*issuer));
+				},
+				_ => {},
+			}
+		}
+
+		let era = match Era::decode(&mut &parts.check_era.encode()[..]) {
+			Ok(era) => era,
+			Err(_) => return,
+		};
+		let (genesis_hash, birth_hash) = match (
+			self.block_hash(0),
+			self.block_hash(era.birth(number as u64)),
+		) {
+			(Some(genesis_hash), Some(birth_hash)) => (genesis_hash, birth_hash),
+			_ => return,
+		};
+		let additional_signed = SignedExtraAdditionalSigned {
+			check_version: VERSION.spec_version,
+			check_genesis: genesis_hash,
+			check_era: birth_hash,
+			check_nonce: (),
+			check_weight: (),
+			charge_transaction_payment: (),
+			check_block_gas_limit: (),
+		}.into_additional_signed();
+
+		let signer: &[u8; 32] = signer.as_ref();
+		SignedPayload::from_raw(uxt.function, extra, additional_signed).using_encoded(|payload| {
+			match signature {
+				MultiSignature::Ed25519(signature) => {
+					self.cache.ed25519_verify(&signature, payload, &ed25519::Public::from_raw(*signer));
+				},
+				MultiSignature::Sr25519(signature) => {
+					self.cache.sr25519_verify(&signature, payload, &sr25519::Public::from_raw(*signer));
+				},
+				MultiSignature::Ecdsa(_) | MultiSignature::Bls12381(_) | MultiSignature::Custom(_) => {},
+			}
+		});
+	}
+}
+
+impl<C: HeaderBackend<Block>> PreVerifier<Block> for SignaturePreVerifier<C> {
+	fn pre_verify(&self, _origin: &BlockOrigin, block: &IncomingBlock<Block>) {
+		let (header, body) = match (&block.header, &block.body) {
+			(Some(header), Some(body)) => (header, body),
+			_ => return,
+		};
+		// The block is already imported, it won't be executed again.
+		if let Ok(Some(_)) = self.client.header(BlockId::Hash(block.hash)) {
+			if !block.import_existing {
+				return
+			}
+		}
+
+		for xt in body {
+			if let Ok(uxt) = UncheckedExtrinsic::decode(&mut &xt.encode()[..]) {
+				self.pre_verify_extrinsic(*header.number(), uxt);
+			}
+		}
+	}
+}
@@ -59,7 +59,13 @@ macro_rules! new_full_start {
 			.with_transaction_pool(|builder| {
 				let pool_api = sc_transaction_pool::FullChainApi::new(
 					builder.client().clone()
-				);
+				)
+					.with_doughnut_issuer(Box::new(crate::service::doughnut_issuer))
+					.with_origin_switches(Box::new(crate::service::origin_switches))
+					.with_delegation_metrics(
+						builder.prometheus_registry(),
+						node_runtime::doughnut_error_code::VALIDATION_REVOKED,
+					);
 				Ok(sc_transaction_pool::BasicPool::new(
 					builder.config().transaction_pool.clone(),
 					std::sync::Arc::new(pool_api),
@@ -82,6 +88,16 @@ macro_rules! new_full_start {
 					client.clone(),
 				)?;
 
+				// Signatures of the queued blocks are verified ahead of their execution.
+				let signature_cache = Arc::new(sp_io::SignatureCache::default());
+				sc_client_api::ExecutorProvider::execution_extensions(&*client).set_extensions_factory(
+					Box::new(crate::pre_verifier::SignatureCacheExtensions(signature_cache.clone())),
+				);
+				let pre_verifier = crate::pre_verifier::SignaturePreVerifier::new(
+					client.clone(),
+					signature_cache,
+				);
+
 				let import_queue = sc_consensus_babe::import_queue(
 					babe_link.clone(),
 					block_import.clone(),
@@ -89,13 +105,15 @@ macro_rules! new_full_start {
 					None,
 					client,
 					inherent_data_providers.clone(),
-				)?;
+				)?.with_pre_verifier(Arc::new(pre_verifier), num_cpus::get());
 
-				import_setup = Some((block_import, grandpa_link, babe_link));
+				let shared_voter_state = grandpa::SharedVoterState::empty();
+
+				import_setup = Some((block_import, grandpa_link, babe_link, shared_voter_state));
 				Ok(import_queue)
 			})?
 			.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
-				let babe_link = import_setup.as_ref().map(|s| &s.2)
+				let (babe_link, shared_voter_state) = import_setup.as_ref().map(|s| (&s.2, &s.3))
 					.expect("BabeLink is present for full services or set up failed; qed.");
 				let deps = node_rpc::FullDeps {
 					client: builder.client().clone(),
@@ -106,9 +124,18 @@ macro_rules! new_full_start {
 						keystore: builder.keystore(),
 						babe_config: sc_consensus_babe::BabeLink::config(babe_link).clone(),
 						shared_epoch_changes: sc_consensus_babe::BabeLink::epoch_changes(babe_link).clone()
-					}
+					},
+					grandpa: node_rpc::GrandpaDeps {
+						shared_voter_state: shared_voter_state.clone(),
+						backend: builder.backend().clone(),
+					},
 				};
-				Ok(node_rpc::create_full(deps))
+				let mut io = node_rpc::create_full(deps);
+				io.extend_with(sc_rpc::debug::DebugApi::to_delegate(
+					sc_rpc::debug::Debug::new(builder.client().clone())
+						.with_doughnut_issuer(Box::new(crate::service::doughnut_issuer))
+				));
+				Ok(io)
 			})?;
 
 		(builder, import_setup, inherent_data_providers)
@@ -130,13 +157,21 @@ macro_rules! new_full {
 			force_authoring,
 			name,
 			disable_grandpa,
+			grandpa_restart_on_stall,
 			sentry_nodes,
+			shadow_executor,
 		) = (
 			$config.roles.is_authority(),
 			$config.force_authoring,
 			$config.name.clone(),
 			$config.disable_grandpa,
+			$config.grandpa_restart_on_stall,
 			$config.network.sentry_nodes.clone(),
+			node_executor::NativeExecutor::<node_executor::Executor>::new(
+				$config.wasm_method,
+				$config.default_heap_pages,
+				$config.max_runtime_instances,
+			),
 		);
 
 		// sentry nodes announce themselves as authorities to the network
@@ -145,6 +180,7 @@ macro_rules! new_full {
 		let participates_in_consensus = is_authority && !$config.sentry_mode;
 
 		let (builder, mut import_setup, inherent_data_providers) = new_full_start!($config);
+		let backend = builder.backend().clone();
 
 		let service = builder
 			.with_finality_proof_provider(|client, backend| {
@@ -154,16 +190,27 @@ macro_rules! new_full {
 			})?
 			.build()?;
 
-		let (block_import, grandpa_link, babe_link) = import_setup.take()
+		let (block_import, grandpa_link, babe_link, shared_voter_state) = import_setup.take()
 				.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
 
 		($with_startup_data)(&block_import, &babe_link);
 
+		// the imported blocks are executed against a runtime upgrade waiting to be enacted too,
+		// to report a pending runtime diverging from the current one before it's enacted.
+		let upgrade_shadow = sc_upgrade_shadow::ShadowExecution::new(
+			service.client(),
+			backend,
+			shadow_executor,
+			Box::new(service.spawn_task_handle()),
+			sc_upgrade_shadow::DEFAULT_MAX_BLOCKS,
+		);
+		service.spawn_task("upgrade-shadow", upgrade_shadow.run());
+
 		if participates_in_consensus {
 			let proposer = sc_basic_authorship::ProposerFactory::new(
 				service.client(),
 				service.transaction_pool()
-			);
+			).with_doughnut_batching(true);
 
 			let client = service.client();
 			let select_chain = service.select_chain()
@@ -238,6 +285,11 @@ macro_rules! new_full {
 				telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
 				voting_rule: grandpa::VotingRulesBuilder::default().build(),
 				prometheus_registry: service.prometheus_registry(),
+				shared_voter_state,
+				stall_detection: Some(grandpa::StallDetection {
+					restart_voter: grandpa_restart_on_stall,
+					..Default::default()
+				}),
 			};
 
 			// the GRANDPA voter task is considered infallible, i.e.
@@ -297,6 +349,361 @@ pub fn new_full(config: Configuration)
 	new_full!(config).map(|(service, _)| service)
 }
 
+/// How a development node seals blocks, instead of authoring them in BABE slots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sealing {
+	/// Blocks are sealed on demand with the `engine_createBlock` RPC.
+	Manual,
+	/// A block is sealed as soon as a transaction enters the pool.
+	Instant,
+}
+
+impl std::str::FromStr for Sealing {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"manual" => Ok(Sealing::Manual),
+			"instant" => Ok(Sealing::Instant),
+			_ => Err(format!("Unknown sealing mode {}, expected manual or instant", s)),
+		}
+	}
+}
+
+/// The issuer of the doughnut of an extrinsic, if any.
+pub(crate) fn doughnut_issuer(uxt: &<Block as BlockT>::Extrinsic) -> Option<Vec<u8>> {
+	use codec::{Decode, Encode};
+	use sp_runtime::traits::PlugDoughnutApi;
+	let uxt = node_runtime::UncheckedExtrinsic::decode(&mut &uxt.encode()[..]).ok()?;
+	let (_, _, (doughnut, ..)) = uxt.signature?;
+	doughnut.map(|d| d.issuer().as_ref().to_vec())
+}
+
+/// The number of calls of an extrinsic dispatched with the origin switched to a doughnut issuer:
+/// the extrinsic itself if it carries a doughnut, and the calls of delegated batches carrying one.
+pub(crate) fn origin_switches(uxt: &<Block as BlockT>::Extrinsic) -> u32 {
+	use codec::{Decode, Encode};
+	use node_runtime::{Call, UtilityCall};
+
+	fn call_origin_switches(call: &Call) -> u32 {
+		match call {
+			Call::Utility(UtilityCall::batch(calls)) => calls.iter().map(call_origin_switches).sum(),
+			Call::Utility(UtilityCall::batch_delegated(calls)) => calls.iter()
+				.map(|(call, doughnut)| doughnut.is_some() as u32 + call_origin_switches(call))
+				.sum(),
+			Call::Utility(UtilityCall::as_sub(_, call)) => call_origin_switches(call),
+			_ => 0,
+		}
+	}
+
+	let uxt = match node_runtime::UncheckedExtrinsic::decode(&mut &uxt.encode()[..]) {
+		Ok(uxt) => uxt,
+		Err(_) => return 0,
+	};
+	let delegated = uxt.signature.as_ref()
+		.map_or(false, |(_, _, (doughnut, ..))| doughnut.is_some());
+	delegated as u32 + call_origin_switches(&uxt.function)
+}
+
+/// Builds a new service for a development node sealing blocks on demand.
+///
+/// The node doesn't run BABE nor GRANDPA: blocks are sealed with `engine_createBlock` or as soon
+/// as a transaction is submitted, and finalized with `engine_finalizeBlock`.
+pub fn new_manual_seal(config: Configuration, sealing: Sealing)
+-> Result<impl AbstractService, ServiceError> {
+	type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
+	let inherent_data_providers = InherentDataProviders::new();
+
+	let (command_sink, commands_stream) = futures::channel::mpsc::channel(1024);
+	let mut backend = None;
+
+	let service = ServiceBuilder::new_full::<Block, RuntimeApi, node_executor::Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|builder| {
+			let pool_api = sc_transaction_pool::FullChainApi::new(
+				builder.client().clone()
+			)
+				.with_doughnut_issuer(Box::new(doughnut_issuer))
+				.with_origin_switches(Box::new(origin_switches))
+				.with_delegation_metrics(
+					builder.prometheus_registry(),
+					node_runtime::doughnut_error_code::VALIDATION_REVOKED,
+				);
+			Ok(sc_transaction_pool::BasicPool::new(
+				builder.config().transaction_pool.clone(),
+				Arc::new(pool_api),
+				builder.prometheus_registry(),
+			))
+		})?
+		.with_import_queue(|_config, client, _select_chain, _transaction_pool| {
+			Ok(sc_consensus_manual_seal::import_queue(Box::new(client)))
+		})?
+		.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
+			backend = Some(builder.backend().clone());
+			let deps = node_rpc::DevDeps {
+				client: builder.client().clone(),
+				pool: builder.pool(),
+				command_sink,
+			};
+			let mut io = node_rpc::create_dev(deps);
+			io.extend_with(sc_rpc::debug::DebugApi::to_delegate(
+				sc_rpc::debug::Debug::new(builder.client().clone())
+					.with_doughnut_issuer(Box::new(doughnut_issuer))
+			));
+			Ok(io)
+		})?
+		.build()?;
+
+	let backend = backend
+		.expect("Backend is set when the RPC extensions are built, or set up failed; qed.");
+
+	// the timestamps of the sealed blocks continue from the best block after a restart
+	let best_timestamp = {
+		use codec::Decode;
+		use frame_support::storage::StorageValue;
+		use sc_client_api::StorageProvider;
+
+		let client = service.client();
+		let best = sp_runtime::generic::BlockId::hash(client.chain_info().best_hash);
+		let now = sp_core::storage::StorageKey(
+			<pallet_timestamp::Now<node_runtime::Runtime>>::hashed_key().to_vec()
+		);
+		client.storage(&best, &now)?
+			.and_then(|timestamp| node_primitives::Moment::decode(&mut &timestamp.0[..]).ok())
+			.unwrap_or_default()
+	};
+	inherent_data_providers
+		.register_provider(sc_consensus_manual_seal::TimestampInherentDataProvider::new(
+			node_runtime::MinimumPeriod::get(),
+			best_timestamp,
+		))
+		.map_err(Into::into)
+		.map_err(sp_consensus::Error::InherentData)?;
+
+	let select_chain = service.select_chain()
+		.ok_or(ServiceError::SelectChainRequired)?;
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		service.client(),
+		service.transaction_pool()
+	).with_doughnut_batching(true);
+	let pool = service.transaction_pool().pool().clone();
+
+	grandpa::setup_disabled_grandpa(
+		service.client(),
+		&inherent_data_providers,
+		service.network(),
+	)?;
+
+	match sealing {
+		Sealing::Manual => service.spawn_essential_task(
+			"manual-seal",
+			sc_consensus_manual_seal::run_manual_seal(
+				Box::new(service.client()),
+				proposer,
+				backend,
+				pool,
+				commands_stream,
+				select_chain,
+				inherent_data_providers,
+			),
+		),
+		Sealing::Instant => service.spawn_essential_task(
+			"instant-seal",
+			sc_consensus_manual_seal::run_instant_seal(
+				Box::new(service.client()),
+				proposer,
+				backend,
+				pool,
+				select_chain,
+				inherent_data_providers,
+			),
+		),
+	}
+
+	Ok(service)
+}
+
+/// Builds a new service for a full node of a private network authoring blocks with the
+/// proof-of-authority engine instead of BABE.
+///
+/// The block authors are admitted on chain by the network privacy module and sign their blocks
+/// with their `poa_` key. Blocks are finalized with GRANDPA.
+pub fn new_poa(config: Configuration)
+-> Result<impl AbstractService, ServiceError> {
+	type RpcExtension = jsonrpc_core::IoHandler<sc_rpc::Metadata>;
+	let inherent_data_providers = InherentDataProviders::new();
+
+	let (is_authority, force_authoring, name, disable_grandpa, grandpa_restart_on_stall) = (
+		config.roles.is_authority(),
+		config.force_authoring,
+		config.name.clone(),
+		config.disable_grandpa,
+		config.grandpa_restart_on_stall,
+	);
+	let participates_in_consensus = is_authority && !config.sentry_mode;
+	let mut import_setup = None;
+
+	let service = ServiceBuilder::new_full::<Block, RuntimeApi, node_executor::Executor>(config)?
+		.with_select_chain(|_config, backend| {
+			Ok(LongestChain::new(backend.clone()))
+		})?
+		.with_transaction_pool(|builder| {
+			let pool_api = sc_transaction_pool::FullChainApi::new(
+				builder.client().clone()
+			)
+				.with_doughnut_issuer(Box::new(doughnut_issuer))
+				.with_origin_switches(Box::new(origin_switches))
+				.with_delegation_metrics(
+					builder.prometheus_registry(),
+					node_runtime::doughnut_error_code::VALIDATION_REVOKED,
+				);
+			Ok(sc_transaction_pool::BasicPool::new(
+				builder.config().transaction_pool.clone(),
+				Arc::new(pool_api),
+				builder.prometheus_registry(),
+			))
+		})?
+		.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
+			let select_chain = select_chain.take()
+				.ok_or_else(|| sc_service::Error::SelectChainRequired)?;
+			let (grandpa_block_import, grandpa_link) = grandpa::block_import(
+				client.clone(),
+				&(client.clone() as Arc<_>),
+				select_chain,
+			)?;
+			let slot_duration = sc_consensus_poa::slot_duration(
+				&*client,
+				node_runtime::constants::time::SLOT_DURATION,
+			)?;
+
+			// the BABE module of the runtime checks the timestamp of the blocks against their slot.
+			let import_queue = sc_consensus_poa::import_queue(
+				slot_duration.clone(),
+				grandpa_block_import.clone(),
+				Some(Box::new(grandpa_block_import.clone())),
+				None,
+				client,
+				inherent_data_providers.clone(),
+				vec![sp_consensus_babe::inherents::INHERENT_IDENTIFIER],
+			)?;
+
+			let shared_voter_state = grandpa::SharedVoterState::empty();
+
+			import_setup = Some((grandpa_block_import, grandpa_link, slot_duration, shared_voter_state));
+			Ok(import_queue)
+		})?
+		.with_warp_sync_provider(|builder| {
+			let grandpa_block_import = import_setup.as_ref().map(|s| &s.0)
+				.expect("GRANDPA block import is present for full services or set up failed; qed.");
+			Ok(Arc::new(grandpa_block_import.warp_sync_provider(builder.backend().clone())) as _)
+		})?
+		.with_rpc_extensions(|builder| -> Result<RpcExtension, _> {
+			let shared_voter_state = import_setup.as_ref().map(|s| &s.3)
+				.expect("SharedVoterState is present for full services or set up failed; qed.");
+			let deps = node_rpc::PoaDeps {
+				client: builder.client().clone(),
+				pool: builder.pool(),
+				grandpa: node_rpc::GrandpaDeps {
+					shared_voter_state: shared_voter_state.clone(),
+					backend: builder.backend().clone(),
+				},
+			};
+			let mut io = node_rpc::create_poa(deps);
+			io.extend_with(sc_rpc::debug::DebugApi::to_delegate(
+				sc_rpc::debug::Debug::new(builder.client().clone())
+					.with_doughnut_issuer(Box::new(doughnut_issuer))
+			));
+			Ok(io)
+		})?
+		.with_finality_proof_provider(|client, backend| {
+			// GenesisAuthoritySetProvider is implemented for StorageAndProofProvider
+			let provider = client as Arc<dyn grandpa::StorageAndProofProvider<_, _>>;
+			Ok(Arc::new(grandpa::FinalityProofProvider::new(backend, provider)) as _)
+		})?
+		.build()?;
+
+	let (block_import, grandpa_link, slot_duration, shared_voter_state) = import_setup.take()
+		.expect("Link Half and Block Import are present for Full Services or setup failed before. qed");
+
+	if participates_in_consensus {
+		use sc_client_api::ExecutorProvider;
+
+		let proposer = sc_basic_authorship::ProposerFactory::new(
+			service.client(),
+			service.transaction_pool()
+		).with_doughnut_batching(true);
+
+		let client = service.client();
+		let select_chain = service.select_chain()
+			.ok_or(ServiceError::SelectChainRequired)?;
+		let can_author_with =
+			sp_consensus::CanAuthorWithNativeVersion::new(client.executor().clone());
+
+		let poa = sc_consensus_poa::start_poa(
+			slot_duration,
+			client,
+			select_chain,
+			block_import,
+			proposer,
+			service.network(),
+			inherent_data_providers.clone(),
+			force_authoring,
+			service.keystore(),
+			can_author_with,
+		)?;
+		service.spawn_essential_task("poa-proposer", poa);
+	}
+
+	// if the node isn't actively participating in consensus then it doesn't
+	// need a keystore.
+	let keystore = if participates_in_consensus {
+		Some(service.keystore())
+	} else {
+		None
+	};
+
+	let config = grandpa::Config {
+		gossip_duration: std::time::Duration::from_millis(333),
+		justification_period: 512,
+		name: Some(name),
+		observer_enabled: false,
+		keystore,
+		is_authority,
+	};
+
+	if !disable_grandpa {
+		let grandpa_config = grandpa::GrandpaParams {
+			config,
+			link: grandpa_link,
+			network: service.network(),
+			inherent_data_providers: inherent_data_providers.clone(),
+			telemetry_on_connect: Some(service.telemetry_on_connect_stream()),
+			voting_rule: grandpa::VotingRulesBuilder::default().build(),
+			prometheus_registry: service.prometheus_registry(),
+			shared_voter_state,
+			stall_detection: Some(grandpa::StallDetection {
+				restart_voter: grandpa_restart_on_stall,
+				..Default::default()
+			}),
+		};
+
+		service.spawn_essential_task(
+			"grandpa-voter",
+			grandpa::run_grandpa_voter(grandpa_config)?
+		);
+	} else {
+		grandpa::setup_disabled_grandpa(
+			service.client(),
+			&inherent_data_providers,
+			service.network(),
+		)?;
+	}
+
+	Ok(service)
+}
+
 /// Builds a new service for a light client.
 pub fn new_light(config: Configuration)
 -> Result<impl AbstractService, ServiceError> {
@@ -392,7 +799,7 @@ mod tests {
 		RecordProof,
 	};
 	use node_primitives::{Block, DigestItem, Signature};
-	use node_runtime::{BalancesCall, Call, UncheckedExtrinsic, Address};
+	use node_runtime::{BalancesCall, Call, UncheckedExtrinsic, Address, SignedExtraAdditionalSigned};
 	use node_runtime::constants::{currency::CENTS, time::SLOT_DURATION};
 	use codec::{Encode, Decode};
 	use sp_core::{crypto::Pair as CryptoPair, H256};
@@ -563,7 +970,15 @@ mod tests {
 				let raw_payload = SignedPayload::from_raw(
 					function,
 					extra,
-					((), version, genesis_hash, genesis_hash, (), (), (), ())
+					SignedExtraAdditionalSigned {
+						check_version: version,
+						check_genesis: genesis_hash,
+						check_era: genesis_hash,
+						check_nonce: (),
+						check_weight: (),
+						charge_transaction_payment: (),
+						check_block_gas_limit: (),
+					}.into_additional_signed(),
 				);
 				let signature = raw_payload.using_encoded(|payload|	{
 					signer.sign(payload)
@@ -0,0 +1,97 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Structs to easily compose doughnut sub-command for CLI.
+
+use std::path::PathBuf;
+use sc_cli::SharedParams;
+use structopt::StructOpt;
+
+/// The `doughnut` command used to generate, inspect and verify doughnuts.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DoughnutCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub command: DoughnutSubCmd,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A possible doughnut sub-commands.
+#[derive(Debug, StructOpt, Clone)]
+pub enum DoughnutSubCmd {
+	/// Generate a doughnut signed by an sr25519 key of the keystore and print it out as
+	/// 0x-prefixed hex.
+	Generate {
+		/// Public key of the issuer, as SS58 address or 0x-prefixed hex.
+		///
+		/// The secret key must be in the keystore.
+		#[structopt(long = "issuer", value_name = "PUBLIC")]
+		issuer: String,
+
+		/// Public key of the holder, as SS58 address or 0x-prefixed hex.
+		#[structopt(long = "holder", value_name = "PUBLIC")]
+		holder: String,
+
+		/// Unix timestamp in seconds from which the doughnut can't be used anymore.
+		#[structopt(long = "expiry", value_name = "SECONDS")]
+		expiry: u32,
+
+		/// Unix timestamp in seconds before which the doughnut can't be used.
+		#[structopt(long = "not-before", value_name = "SECONDS", default_value = "0")]
+		not_before: u32,
+
+		/// A permission domain and its 0x-prefixed hex payload, can be repeated.
+		#[structopt(
+			long = "domain",
+			value_name = "NAME=BYTES",
+			required = true,
+			parse(try_from_str = crate::parse_domain)
+		)]
+		domains: Vec<(String, Vec<u8>)>,
+
+		/// Key type of the issuer key in the keystore.
+		#[structopt(long = "key-type", value_name = "KEY_TYPE", default_value = "acco")]
+		key_type: String,
+
+		/// Specify custom keystore path.
+		#[structopt(long = "keystore-path", value_name = "PATH", parse(from_os_str))]
+		keystore_path: Option<PathBuf>,
+
+		/// Password used by the keystore.
+		#[structopt(long = "password")]
+		password: Option<String>,
+	},
+	/// Decode a doughnut and print out the details.
+	Inspect {
+		/// 0x-prefixed hex string, representing the SCALE encoding of a doughnut.
+		#[structopt(value_name = "BYTES")]
+		input: String,
+	},
+	/// Verify the signature and the validity period of a doughnut, and that it is not in the
+	/// revocation registry of the chain.
+	Verify {
+		/// 0x-prefixed hex string, representing the SCALE encoding of a doughnut.
+		#[structopt(value_name = "BYTES")]
+		input: String,
+
+		/// Http RPC endpoint of a node of the chain.
+		#[structopt(long = "uri", value_name = "URL", default_value = "http://localhost:9933")]
+		uri: String,
+	},
+}
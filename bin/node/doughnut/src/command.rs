@@ -0,0 +1,125 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Command ran by the CLI
+
+use std::convert::TryFrom;
+use std::sync::mpsc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use codec::Encode;
+use futures::Future;
+use hyper::rt;
+use jsonrpc_core_client::transports::http;
+use sc_rpc::state::StateClient;
+use sp_core::{crypto::{KeyTypeId, Ss58Codec}, hexdisplay::HexDisplay, sr25519, storage::StorageKey, H256};
+
+use crate::cli::{DoughnutCmd, DoughnutSubCmd};
+use crate::{PrettyDoughnut, decode, generate, revoked_key, verify};
+
+/// Default sub directory of the keystore.
+const DEFAULT_KEYSTORE_CONFIG_PATH: &str = "keystore";
+
+impl DoughnutCmd {
+	/// Initialize
+	pub fn init(&self, version: &sc_cli::VersionInfo) -> sc_cli::Result<()> {
+		self.shared_params.init(version)
+	}
+
+	/// Parse CLI arguments and initialize given config.
+	pub fn update_config(
+		&self,
+		config: &mut sc_service::config::Configuration,
+		spec_factory: impl FnOnce(&str) -> Result<Box<dyn sc_service::ChainSpec>, String>,
+		version: &sc_cli::VersionInfo,
+	) -> sc_cli::Result<()> {
+		self.shared_params.update_config(config, spec_factory, version)
+	}
+
+	/// Run the doughnut command.
+	pub fn run(self, config: &sc_service::config::Configuration) -> sc_cli::Result<()> {
+		match self.command {
+			DoughnutSubCmd::Generate {
+				issuer,
+				holder,
+				expiry,
+				not_before,
+				domains,
+				key_type,
+				keystore_path,
+				password,
+			} => {
+				let issuer = parse_public(&issuer)?;
+				let holder = parse_public(&holder)?;
+				let key_type = KeyTypeId::try_from(key_type.as_str())
+					.map_err(|_| format!("Invalid key type {}", key_type))?;
+
+				let path = keystore_path.or_else(|| config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH))
+					.ok_or_else(|| "No `base_path` provided to open the keystore".to_string())?;
+				let keystore = sc_keystore::Store::open(path, password.map(Into::into))
+					.map_err(|e| format!("Error opening the keystore: {}", e))?;
+				let pair = keystore.read().key_pair_by_type::<sr25519::Pair>(&issuer, key_type)
+					.map_err(|e| format!("Issuer key not found in the keystore: {}", e))?;
+
+				let doughnut = generate(&pair, &holder, expiry, not_before, domains)?;
+				println!("0x{}", HexDisplay::from(&doughnut.encode()));
+				Ok(())
+			},
+			DoughnutSubCmd::Inspect { input } => {
+				let doughnut = decode(&input)?;
+				println!("{}", PrettyDoughnut(&doughnut));
+				Ok(())
+			},
+			DoughnutSubCmd::Verify { input, uri } => {
+				let doughnut = decode(&input)?;
+				let now = SystemTime::now().duration_since(UNIX_EPOCH)
+					.map_err(|e| format!("Invalid system time: {}", e))?
+					.as_secs();
+				verify(&doughnut, now as u32)?;
+				println!("Signature and validity period: ok");
+
+				if is_revoked(&uri, StorageKey(revoked_key(&doughnut)))? {
+					return Err("The doughnut is revoked".to_string().into());
+				}
+				println!("Revocation registry: not revoked");
+				Ok(())
+			},
+		}
+	}
+}
+
+/// Parse an sr25519 public key from an SS58 address or 0x-prefixed hex.
+fn parse_public(input: &str) -> Result<sr25519::Public, String> {
+	sr25519::Public::from_string(input).map_err(|e| format!("Invalid public key {}: {:?}", input, e))
+}
+
+/// Query the revocation registry entry `key` at the best block of the node at `uri`.
+fn is_revoked(uri: &str, key: StorageKey) -> Result<bool, String> {
+	let (sender, receiver) = mpsc::channel();
+	rt::run(
+		http::connect(uri)
+			.and_then(|client: StateClient<H256>| client.storage(key, None))
+			.then(move |result| {
+				let _ = sender.send(result);
+				Ok(())
+			})
+	);
+
+	receiver.recv()
+		.map_err(|_| "The RPC request was not completed".to_string())?
+		.map(|value| value.is_some())
+		.map_err(|e| format!("Error querying the revocation registry: {:?}", e))
+}
@@ -0,0 +1,211 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A CLI extension for the node, adding sub-commands to generate, inspect and verify doughnuts.
+//!
+//! Doughnuts are generated with an sr25519 key of the node keystore and printed out as the
+//! 0x-prefixed hex of their SCALE encoding, the format extrinsics carry them in. Verification
+//! checks the signature and the validity period locally, and queries the revocation registry of
+//! the chain over RPC.
+
+#![warn(missing_docs)]
+
+pub mod cli;
+pub mod command;
+
+use std::fmt;
+
+use chrono::{TimeZone, Utc};
+use codec::{Decode, Encode};
+use sp_core::{
+	crypto::Ss58Codec,
+	hashing::{blake2_256, twox_128},
+	hexdisplay::HexDisplay,
+	sr25519,
+};
+use sp_runtime::{
+	Doughnut, DoughnutV0,
+	traits::{DoughnutApi, DoughnutSigning, DoughnutVerify, ValidationError, VerifyError},
+};
+
+/// Signature version of sr25519 signed doughnuts.
+const SR25519_SIGNATURE_VERSION: u8 = 0;
+
+/// Generate a doughnut for `holder` signed by `issuer`.
+pub fn generate(
+	issuer: &sr25519::Pair,
+	holder: &sr25519::Public,
+	expiry: u32,
+	not_before: u32,
+	domains: Vec<(String, Vec<u8>)>,
+) -> Result<Doughnut, String> {
+	use sp_core::Pair;
+
+	let mut doughnut = DoughnutV0 {
+		issuer: issuer.public().0,
+		holder: holder.0,
+		expiry,
+		not_before,
+		payload_version: 0,
+		signature_version: SR25519_SIGNATURE_VERSION,
+		signature: [0u8; 64].into(),
+		domains,
+	};
+	doughnut.sign_sr25519(&issuer.to_ed25519_bytes())
+		.map_err(|e| format!("Error signing the doughnut: {:?}", e))?;
+
+	Ok(Doughnut::V0(doughnut))
+}
+
+/// Decode a doughnut from a 0x-prefixed hex string of its SCALE encoding.
+pub fn decode(input: &str) -> Result<Doughnut, String> {
+	if !input.starts_with("0x") {
+		return Err("The doughnut must be a 0x-prefixed hex string".into());
+	}
+	let bytes = hex::decode(&input[2..]).map_err(|e| format!("Invalid hex: {}", e))?;
+	Doughnut::decode(&mut &bytes[..]).map_err(|e| format!("Error decoding the doughnut: {}", e.what()))
+}
+
+/// Parse a permission domain formatted as `NAME=BYTES`, with 0x-prefixed hex bytes.
+pub fn parse_domain(input: &str) -> Result<(String, Vec<u8>), String> {
+	let mut parts = input.splitn(2, '=');
+	let name = parts.next().filter(|name| !name.is_empty())
+		.ok_or_else(|| format!("Missing domain name in {}", input))?;
+	let payload = parts.next()
+		.filter(|payload| payload.starts_with("0x"))
+		.ok_or_else(|| format!("Domain {} must be formatted as NAME=0xBYTES", input))?;
+	let payload = hex::decode(&payload[2..]).map_err(|e| format!("Invalid hex in {}: {}", input, e))?;
+
+	Ok((name.to_string(), payload))
+}
+
+/// Storage key of the entry of `doughnut` in the revocation registry of the chain, the
+/// `Revoked` map of the `prml-doughnut-revocation` module.
+pub fn revoked_key(doughnut: &Doughnut) -> Vec<u8> {
+	let mut key = twox_128(b"DoughnutRevocation").to_vec();
+	key.extend_from_slice(&twox_128(b"Revoked"));
+	key.extend_from_slice(&blake2_256(&doughnut.encode()));
+	key
+}
+
+/// Check the signature of `doughnut` and that it is usable by its holder at `now`, in seconds.
+pub fn verify(doughnut: &Doughnut, now: u32) -> Result<(), String> {
+	match doughnut {
+		Doughnut::V0(v0) => {
+			DoughnutVerify::verify(v0).map_err(|e| match e {
+				VerifyError::Invalid => "The signature is invalid",
+				VerifyError::UnsupportedVersion => "The signature version is not supported",
+				VerifyError::BadSignatureFormat => "The signature format is invalid",
+				VerifyError::BadPublicKeyFormat => "The issuer public key format is invalid",
+			})?;
+			DoughnutApi::validate(v0, v0.holder, now).map_err(|e| match e {
+				ValidationError::Expired => "The doughnut has expired",
+				ValidationError::Premature => "The doughnut is not valid yet",
+				ValidationError::HolderIdentityMismatched => "The holder doesn't match",
+				ValidationError::Conversion => "The timestamp can't be converted",
+			})?;
+		},
+	}
+
+	Ok(())
+}
+
+/// Pretty print the details of a doughnut.
+pub struct PrettyDoughnut<'a>(pub &'a Doughnut);
+
+impl<'a> fmt::Display for PrettyDoughnut<'a> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let timestamp = |secs: u32| Utc.timestamp(secs as i64, 0).to_rfc3339();
+		let account = |public: [u8; 32]| {
+			format!("{} (0x{})", sr25519::Public(public).to_ss58check(), HexDisplay::from(&public))
+		};
+
+		match self.0 {
+			Doughnut::V0(v0) => {
+				let signature: [u8; 64] = DoughnutApi::signature(v0).into();
+				let signature_version = match v0.signature_version {
+					SR25519_SIGNATURE_VERSION => "sr25519",
+					1 => "ed25519",
+					_ => "unknown",
+				};
+
+				writeln!(f, "Version:           0")?;
+				writeln!(f, "Issuer:            {}", account(v0.issuer))?;
+				writeln!(f, "Holder:            {}", account(v0.holder))?;
+				writeln!(f, "Not before:        {} ({})", v0.not_before, timestamp(v0.not_before))?;
+				writeln!(f, "Expiry:            {} ({})", v0.expiry, timestamp(v0.expiry))?;
+				writeln!(f, "Payload version:   {}", v0.payload_version)?;
+				writeln!(f, "Signature version: {} ({})", v0.signature_version, signature_version)?;
+				writeln!(f, "Signature:         0x{}", HexDisplay::from(&signature))?;
+				writeln!(f, "Domains:")?;
+				for (name, payload) in &v0.domains {
+					writeln!(f, "  {}: 0x{}", name, HexDisplay::from(payload))?;
+				}
+				write!(f, "Revocation key:    0x{}", HexDisplay::from(&revoked_key(self.0)))
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::Pair;
+
+	fn doughnut(expiry: u32, not_before: u32) -> Doughnut {
+		let issuer = sr25519::Pair::from_seed(&[1; 32]);
+		let holder = sr25519::Pair::from_seed(&[2; 32]).public();
+
+		generate(&issuer, &holder, expiry, not_before, vec![("plug".into(), vec![1, 2, 3])]).unwrap()
+	}
+
+	#[test]
+	fn generated_doughnut_verifies() {
+		let doughnut = doughnut(2_000, 1_000);
+
+		assert_eq!(verify(&doughnut, 1_500), Ok(()));
+		assert_eq!(verify(&doughnut, 500), Err("The doughnut is not valid yet".into()));
+		assert_eq!(verify(&doughnut, 3_000), Err("The doughnut has expired".into()));
+	}
+
+	#[test]
+	fn tampered_doughnut_does_not_verify() {
+		let mut doughnut = doughnut(2_000, 1_000);
+		let Doughnut::V0(ref mut v0) = doughnut;
+		v0.expiry = 5_000;
+
+		assert_eq!(verify(&doughnut, 1_500), Err("The signature is invalid".into()));
+	}
+
+	#[test]
+	fn doughnut_round_trips_through_hex() {
+		let doughnut = doughnut(2_000, 1_000);
+		let input = format!("0x{}", HexDisplay::from(&doughnut.encode()));
+
+		assert_eq!(decode(&input).map(|decoded| decoded.encode()), Ok(doughnut.encode()));
+		assert!(decode(&input[2..]).is_err());
+		assert!(PrettyDoughnut(&doughnut).to_string().contains("  plug: 0x010203"));
+	}
+
+	#[test]
+	fn domains_are_parsed() {
+		assert_eq!(parse_domain("plug=0x0102"), Ok(("plug".into(), vec![1, 2])));
+		assert_eq!(parse_domain("plug=0x"), Ok(("plug".into(), vec![])));
+		assert!(parse_domain("plug").is_err());
+		assert!(parse_domain("plug=0102").is_err());
+		assert!(parse_domain("=0x0102").is_err());
+	}
+}
@@ -59,4 +59,13 @@ pub enum InspectSubCmd {
 		#[structopt(value_name = "BLOCK:INDEX or BYTES")]
 		input: String,
 	},
+	/// Decode a SCALE encoded value of a type, by type name, and print it out as JSON.
+	Value {
+		/// Name of the type, as declared by the pallets, e.g. `Vec<(AccountId, Balance)>`.
+		#[structopt(value_name = "TYPE")]
+		ty: String,
+		/// A 0x-prefixed bytes hex string, representing SCALE encoding of the value.
+		#[structopt(value_name = "BYTES")]
+		input: String,
+	},
 }
@@ -76,6 +76,20 @@ impl InspectCmd {
 				println!("{}", res);
 				Ok(())
 			},
+			InspectSubCmd::Value { ty, input } => {
+				let bytes = sp_core::bytes::from_hex(&input)
+					.map_err(|e| format!("Invalid bytes: {}", e))?;
+				let input = &mut &bytes[..];
+				let value = sp_scale_value::TypeRegistry::default().decode(&ty, input)
+					.map_err(|e| format!("{}", e))?;
+				if !input.is_empty() {
+					return Err(format!("{} bytes left after decoding `{}`", input.len(), ty).into());
+				}
+				let json = serde_json::to_string_pretty(&serde_json::Value::from(value))
+					.map_err(|e| format!("{}", e))?;
+				println!("{}", json);
+				Ok(())
+			},
 		}
 	}
 }
@@ -31,7 +31,7 @@
 
 use std::{sync::Arc, fmt};
 
-use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, AssetId};
+use node_primitives::{Block, BlockNumber, AccountId, Index, Balance, AssetId, Hash};
 use node_runtime::UncheckedExtrinsic;
 use sp_api::ProvideRuntimeApi;
 use sp_transaction_pool::TransactionPool;
@@ -42,6 +42,10 @@ use sp_consensus_babe::BabeApi;
 use sc_consensus_epochs::SharedEpochChanges;
 use sc_consensus_babe::{Config, Epoch};
 use sc_consensus_babe_rpc::BabeRPCHandler;
+use sc_finality_grandpa::SharedVoterState;
+use sc_finality_grandpa_rpc::{GrandpaApi, GrandpaRpcHandler};
+use sc_consensus_manual_seal::{EngineCommand, rpc::{ManualSeal, ManualSealApi}};
+use futures::channel::mpsc;
 
 /// Light client extra dependencies.
 pub struct LightDeps<C, F, P> {
@@ -65,8 +69,16 @@ pub struct BabeDeps {
 	pub keystore: KeyStorePtr,
 }
 
+/// Extra dependencies for GRANDPA.
+pub struct GrandpaDeps<B> {
+	/// The state of the voter, recorded by the finality stall watchdog.
+	pub shared_voter_state: SharedVoterState<Block>,
+	/// The backend finality proofs are generated from.
+	pub backend: Arc<B>,
+}
+
 /// Full client dependencies.
-pub struct FullDeps<C, P, SC> {
+pub struct FullDeps<C, P, SC, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
@@ -75,11 +87,33 @@ pub struct FullDeps<C, P, SC> {
 	pub select_chain: SC,
 	/// BABE specific dependencies.
 	pub babe: BabeDeps,
+	/// GRANDPA specific dependencies.
+	pub grandpa: GrandpaDeps<B>,
+}
+
+/// Development node dependencies, for nodes sealing blocks on demand.
+pub struct DevDeps<C, P> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// Channel of the commands of the manual seal authorship task.
+	pub command_sink: mpsc::Sender<EngineCommand<Hash>>,
+}
+
+/// Full client dependencies of a node authoring blocks with the proof-of-authority engine.
+pub struct PoaDeps<C, P, B> {
+	/// The client instance to use.
+	pub client: Arc<C>,
+	/// Transaction pool instance.
+	pub pool: Arc<P>,
+	/// GRANDPA specific dependencies.
+	pub grandpa: GrandpaDeps<B>,
 }
 
 /// Instantiate all Full RPC extensions.
-pub fn create_full<C, P, M, SC>(
-	deps: FullDeps<C, P, SC>,
+pub fn create_full<C, P, M, SC, B>(
+	deps: FullDeps<C, P, SC, B>,
 ) -> jsonrpc_core::IoHandler<M> where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
@@ -87,24 +121,23 @@ pub fn create_full<C, P, M, SC>(
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_transaction_payment_rpc::DelegatedTransactionPaymentRuntimeApi<Block, Balance, AccountId, UncheckedExtrinsic>,
 	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
+	C::Api: pallet_evm_rpc::EvmRuntimeApi<Block>,
 	C::Api: BabeApi<Block>,
 	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
-	P: TransactionPool + 'static,
+	P: TransactionPool<Block = Block> + 'static,
 	M: jsonrpc_core::Metadata + Default,
 	SC: SelectChain<Block> +'static,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 {
-	use substrate_frame_rpc_system::{FullSystem, SystemApi};
-	use pallet_contracts_rpc::{Contracts, ContractsApi};
-	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
-	use pallet_generic_asset_rpc::{GenericAsset, GenericAssetApi};
-
 	let mut io = jsonrpc_core::IoHandler::default();
 	let FullDeps {
 		client,
 		pool,
 		select_chain,
-		babe
+		babe,
+		grandpa,
 	} = deps;
 	let BabeDeps {
 		keystore,
@@ -112,8 +145,113 @@ pub fn create_full<C, P, M, SC>(
 		shared_epoch_changes,
 	} = babe;
 
+	extend_with_runtime_rpcs(&mut io, client.clone(), pool);
+	io.extend_with(
+		sc_consensus_babe_rpc::BabeApi::to_delegate(
+			BabeRPCHandler::new(client.clone(), shared_epoch_changes, keystore, babe_config, select_chain)
+		)
+	);
 	io.extend_with(
-		SystemApi::to_delegate(FullSystem::new(client.clone(), pool))
+		GrandpaApi::to_delegate(GrandpaRpcHandler::new(grandpa.shared_voter_state, grandpa.backend))
+	);
+
+	io
+}
+
+/// Instantiate the RPC extensions of a development node sealing blocks on demand: the runtime
+/// RPCs of a full node and `engine_createBlock`/`engine_finalizeBlock`.
+pub fn create_dev<C, P, M>(
+	deps: DevDeps<C, P>,
+) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: Send + Sync + 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_transaction_payment_rpc::DelegatedTransactionPaymentRuntimeApi<Block, Balance, AccountId, UncheckedExtrinsic>,
+	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
+	C::Api: pallet_evm_rpc::EvmRuntimeApi<Block>,
+	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
+	P: TransactionPool<Block = Block> + 'static,
+	M: jsonrpc_core::Metadata + Default,
+{
+	let DevDeps {
+		client,
+		pool,
+		command_sink,
+	} = deps;
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	extend_with_runtime_rpcs(&mut io, client, pool);
+	io.extend_with(ManualSealApi::to_delegate(ManualSeal::new(command_sink)));
+
+	io
+}
+
+/// Instantiate the RPC extensions of a node authoring blocks with the proof-of-authority engine:
+/// the runtime RPCs of a full node and the GRANDPA RPCs.
+pub fn create_poa<C, P, M, B>(
+	deps: PoaDeps<C, P, B>,
+) -> jsonrpc_core::IoHandler<M> where
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: Send + Sync + 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_transaction_payment_rpc::DelegatedTransactionPaymentRuntimeApi<Block, Balance, AccountId, UncheckedExtrinsic>,
+	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
+	C::Api: pallet_evm_rpc::EvmRuntimeApi<Block>,
+	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
+	P: TransactionPool<Block = Block> + 'static,
+	M: jsonrpc_core::Metadata + Default,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
+{
+	let PoaDeps {
+		client,
+		pool,
+		grandpa,
+	} = deps;
+
+	let mut io = jsonrpc_core::IoHandler::default();
+	extend_with_runtime_rpcs(&mut io, client, pool);
+	io.extend_with(
+		GrandpaApi::to_delegate(GrandpaRpcHandler::new(grandpa.shared_voter_state, grandpa.backend))
+	);
+
+	io
+}
+
+/// Add the RPCs of the runtime pallets of a full node to `io`.
+fn extend_with_runtime_rpcs<C, P, M>(
+	io: &mut jsonrpc_core::IoHandler<M>,
+	client: Arc<C>,
+	pool: Arc<P>,
+) where
+	C: ProvideRuntimeApi<Block>,
+	C: HeaderBackend<Block> + HeaderMetadata<Block, Error=BlockChainError> + 'static,
+	C: Send + Sync + 'static,
+	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
+	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber>,
+	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance, UncheckedExtrinsic>,
+	C::Api: pallet_transaction_payment_rpc::DelegatedTransactionPaymentRuntimeApi<Block, Balance, AccountId, UncheckedExtrinsic>,
+	C::Api: pallet_generic_asset_rpc::AssetMetaApi<Block, AssetId>,
+	C::Api: pallet_evm_rpc::EvmRuntimeApi<Block>,
+	<C::Api as sp_api::ApiErrorExt>::Error: fmt::Debug,
+	P: TransactionPool<Block = Block> + 'static,
+	M: jsonrpc_core::Metadata + Default,
+{
+	use substrate_frame_rpc_system::{FullSystem, SystemApi};
+	use pallet_contracts_rpc::{Contracts, ContractsApi};
+	use pallet_transaction_payment_rpc::{
+		TransactionPayment, TransactionPaymentApi, DelegatedTransactionPaymentApi,
+	};
+	use pallet_generic_asset_rpc::{GenericAsset, GenericAssetApi};
+	use pallet_evm_rpc::{Eth, EthApi};
+
+	io.extend_with(
+		SystemApi::to_delegate(FullSystem::new(client.clone(), pool.clone()))
 	);
 	// Making synchronous calls in light client freezes the browser currently,
 	// more context: https://github.com/paritytech/substrate/pull/3480
@@ -125,13 +263,10 @@ pub fn create_full<C, P, M, SC>(
 		TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 	io.extend_with(
-		sc_consensus_babe_rpc::BabeApi::to_delegate(
-			BabeRPCHandler::new(client.clone(), shared_epoch_changes, keystore, babe_config, select_chain)
-		)
+		DelegatedTransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone()))
 	);
 	io.extend_with(GenericAssetApi::to_delegate(GenericAsset::new(client.clone())));
-
-	io
+	io.extend_with(EthApi::to_delegate(Eth::new(client, pool)));
 }
 
 /// Instantiate all Light RPC extensions.
@@ -154,7 +289,7 @@ pub fn create_light<C, P, M, F>(
 	} = deps;
 	let mut io = jsonrpc_core::IoHandler::default();
 	io.extend_with(
-		SystemApi::<AccountId, Index>::to_delegate(LightSystem::new(client, remote_blockchain, fetcher, pool))
+		SystemApi::<_, AccountId, Index>::to_delegate(LightSystem::new(client, remote_blockchain, fetcher, pool))
 	);
 
 	io
@@ -19,7 +19,7 @@
 use node_primitives::Balance;
 use sp_runtime::traits::{Convert, Saturating};
 use sp_runtime::{Fixed64, Perbill};
-use frame_support::{traits::{OnUnbalanced, Currency, Get}, weights::Weight};
+use frame_support::{traits::{OnUnbalanced, Currency, Get}, weights::{Weight, DispatchClass}};
 use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
 
 pub struct Author;
@@ -64,13 +64,17 @@ impl<C: Get<Balance>> Convert<Weight, Balance> for LinearWeightToFee<C> {
 ///   v = 0.00004
 ///   next_weight = weight * (1 + (v . diff) + (v . diff)^2 / 2)
 ///
-/// Where `target_weight` must be given as the `Get` implementation of the `T` generic type.
+/// Where `target_weight` must be given as the `Get` implementation of the `T` generic type, and
+/// `previous_block_weight` only counts the extrinsics of the dispatch class given by `C`.
 /// https://research.web3.foundation/en/latest/polkadot/Token%20Economics/#relay-chain-transaction-fees
-pub struct TargetedFeeAdjustment<T>(sp_std::marker::PhantomData<T>);
+pub struct TargetedFeeAdjustment<T, C>(sp_std::marker::PhantomData<(T, C)>);
 
-impl<T: Get<Perbill>> Convert<Fixed64, Fixed64> for TargetedFeeAdjustment<T> {
+impl<T, C> Convert<Fixed64, Fixed64> for TargetedFeeAdjustment<T, C> where
+	T: Get<Perbill>,
+	C: Get<DispatchClass>,
+{
 	fn convert(multiplier: Fixed64) -> Fixed64 {
-		let block_weight = System::all_extrinsics_weight();
+		let block_weight = System::extrinsics_weight(C::get());
 		let max_weight = MaximumBlockWeight::get();
 		let target_weight = (T::get() * max_weight) as u128;
 		let block_weight = block_weight as u128;
@@ -117,9 +121,11 @@ mod tests {
 	use super::*;
 	use sp_runtime::assert_eq_error_rate;
 	use crate::{MaximumBlockWeight, AvailableBlockRatio, Runtime};
-	use crate::{constants::currency::*, TransactionPayment, TargetBlockFullness};
+	use crate::{constants::currency::*, TransactionPayment, TargetBlockFullness, NormalDispatchClass};
 	use frame_support::weights::Weight;
 
+	type NormalFeeAdjustment = TargetedFeeAdjustment<TargetBlockFullness, NormalDispatchClass>;
+
 	fn max() -> Weight {
 		MaximumBlockWeight::get()
 	}
@@ -172,7 +178,7 @@ mod tests {
 			run_with_system_weight(w, || {
 				assert_eq_error_rate!(
 					fee_multiplier_update(w, fm).into_inner(),
-					TargetedFeeAdjustment::<TargetBlockFullness>::convert(fm).into_inner(),
+					NormalFeeAdjustment::convert(fm).into_inner(),
 					5,
 				);
 			})
@@ -187,7 +193,7 @@ mod tests {
 			let mut fm = Fixed64::default();
 			let mut iterations: u64 = 0;
 			loop {
-				let next = TargetedFeeAdjustment::<TargetBlockFullness>::convert(fm);
+				let next = NormalFeeAdjustment::convert(fm);
 				fm = next;
 				if fm == Fixed64::from_rational(-1, 1) { break; }
 				iterations += 1;
@@ -222,7 +228,7 @@ mod tests {
 
 			let mut iterations: u64 = 0;
 			loop {
-				let next = TargetedFeeAdjustment::<TargetBlockFullness>::convert(fm);
+				let next = NormalFeeAdjustment::convert(fm);
 				// if no change, panic. This should never happen in this case.
 				if fm == next { panic!("The fee should ever increase"); }
 				fm = next;
@@ -248,14 +254,14 @@ mod tests {
 		run_with_system_weight(target() / 4, || {
 			// Light block. Fee is reduced a little.
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(-7500),
 			);
 		});
 		run_with_system_weight(target() / 2, || {
 			// a bit more. Fee is decreased less, meaning that the fee increases as the block grows.
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(-5000),
 			);
 
@@ -263,14 +269,14 @@ mod tests {
 		run_with_system_weight(target(), || {
 			// ideal. Original fee. No changes.
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(0),
 			);
 		});
 		run_with_system_weight(target() * 2, || {
 			// // More than ideal. Fee is increased.
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(10000),
 			);
 		});
@@ -280,20 +286,20 @@ mod tests {
 	fn stateful_weight_mul_grow_to_infinity() {
 		run_with_system_weight(target() * 2, || {
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(10000)
 			);
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(10000)),
+				NormalFeeAdjustment::convert(feemul(10000)),
 				feemul(20000)
 			);
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(20000)),
+				NormalFeeAdjustment::convert(feemul(20000)),
 				feemul(30000)
 			);
 			// ...
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(1_000_000_000)),
+				NormalFeeAdjustment::convert(feemul(1_000_000_000)),
 				feemul(1_000_000_000 + 10000)
 			);
 		});
@@ -303,20 +309,20 @@ mod tests {
 	fn stateful_weight_mil_collapse_to_minus_one() {
 		run_with_system_weight(0, || {
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default()),
+				NormalFeeAdjustment::convert(Fixed64::default()),
 				feemul(-10000)
 			);
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(-10000)),
+				NormalFeeAdjustment::convert(feemul(-10000)),
 				feemul(-20000)
 			);
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(-20000)),
+				NormalFeeAdjustment::convert(feemul(-20000)),
 				feemul(-30000)
 			);
 			// ...
 			assert_eq!(
-				TargetedFeeAdjustment::<TargetBlockFullness>::convert(feemul(1_000_000_000 * -1)),
+				NormalFeeAdjustment::convert(feemul(1_000_000_000 * -1)),
 				feemul(-1_000_000_000)
 			);
 		})
@@ -343,7 +349,7 @@ mod tests {
 			Weight::max_value(),
 		].into_iter().for_each(|i| {
 			run_with_system_weight(i, || {
-				let next = TargetedFeeAdjustment::<TargetBlockFullness>::convert(Fixed64::default());
+				let next = NormalFeeAdjustment::convert(Fixed64::default());
 				let truth = fee_multiplier_update(i, Fixed64::default());
 				assert_eq_error_rate!(truth.into_inner(), next.into_inner(), 5);
 			});
@@ -355,7 +361,7 @@ mod tests {
 			.into_iter()
 			.for_each(|i| {
 				run_with_system_weight(i, || {
-					let fm = TargetedFeeAdjustment::<TargetBlockFullness>::convert(max_fm);
+					let fm = NormalFeeAdjustment::convert(max_fm);
 					// won't grow. The convert saturates everything.
 					assert_eq!(fm, max_fm);
 				})
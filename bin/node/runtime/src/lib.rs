@@ -24,7 +24,7 @@ use sp_std::prelude::*;
 use frame_support::{
 	construct_runtime, debug, parameter_types,
 	traits::{Currency, Imbalance, OnUnbalanced, Randomness},
-	weights::Weight,
+	weights::{Weight, DispatchClass},
 };
 use sp_core::u32_trait::{_1, _2, _3, _4};
 pub use node_primitives::{AccountId, AssetId, Signature};
@@ -39,7 +39,7 @@ use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::transaction_validity::{TransactionValidity, TransactionSource};
 use sp_runtime::traits::{
 	self, BlakeTwo256, Block as BlockT, IdentityLookup, SaturatedConversion,
-	ConvertInto, OpaqueKeys,
+	ConvertInto, OpaqueKeys, NumberFor,
 };
 
 use sp_version::RuntimeVersion;
@@ -50,8 +50,10 @@ use pallet_grandpa::AuthorityList as GrandpaAuthorityList;
 use pallet_grandpa::fg_primitives;
 use pallet_im_online::sr25519::{AuthorityId as ImOnlineId};
 use sp_authority_discovery::AuthorityId as AuthorityDiscoveryId;
-use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
+use sp_consensus_poa::AuthorityId as PoaId;
+use pallet_transaction_payment_rpc_runtime_api::{RuntimeDispatchInfo, RuntimeDelegatedDispatchInfo};
 use pallet_contracts_rpc_runtime_api::ContractExecResult;
+use pallet_evm_rpc_runtime_api::{BlockLog, EvmCallResult};
 use frame_system::offchain::TransactionSubmitter;
 use sp_inherents::{InherentData, CheckInherentsResult};
 
@@ -59,6 +61,8 @@ use sp_inherents::{InherentData, CheckInherentsResult};
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
+pub use pallet_utility::Call as UtilityCall;
+pub use prml_doughnut::error_code as doughnut_error_code;
 pub use pallet_contracts::Gas;
 pub use frame_support::StorageValue;
 pub use pallet_staking::StakerStatus;
@@ -84,8 +88,8 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 240,
-	impl_version: 1,
+	spec_version: 261,
+	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 };
 
@@ -153,26 +157,48 @@ parameter_types! {
 	pub const MaxSignatories: u16 = 100;
 }
 
+parameter_types! {
+	// A unit of weight is a nanosecond on the reference hardware of `benchmark calibrate`, which
+	// verifies the sr25519 signature of a 1 KiB message in 48µs, hashes it in 1.2µs and reads a
+	// value of the state in 25µs. A doughnut costs a signature verification and the read of its
+	// revocation, and its payload is hashed twice, by the signature and by the revocation check,
+	// i.e. 2.4ns per byte rounded up. `benchmark doughnut` checks these against the runtime.
+	pub const DoughnutVerificationWeight: Weight = 48_000 + 25_000;
+	pub const DoughnutVerificationWeightPerByte: Weight = 3;
+}
+
 impl prml_doughnut::DoughnutRuntime for Runtime {
 	type AccountId = <Self as frame_system::Trait>::AccountId;
+	type PublicKeyAccount = ConvertInto;
 	type Call = Call;
 	type Doughnut = <Self as frame_system::Trait>::Doughnut;
 	type TimestampProvider = pallet_timestamp::Module<Runtime>;
+	type VerificationWeight = DoughnutVerificationWeight;
+	type VerificationWeightPerByte = DoughnutVerificationWeightPerByte;
+	type RevocationRegistry = DoughnutRevocation;
 }
 
 impl pallet_generic_asset::Trait for Runtime {
 	type Balance = Balance;
 	type AssetId = AssetId;
 	type Event = Event;
+	type TransferRestriction = ();
 }
 
 impl pallet_utility::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type DoughnutValidator = prml_doughnut::PlugDoughnutValidator<Runtime>;
+}
+
+impl pallet_multisig::Trait for Runtime {
 	type Event = Event;
 	type Call = Call;
 	type Currency = Balances;
-	type MultisigDepositBase = MultisigDepositBase;
-	type MultisigDepositFactor = MultisigDepositFactor;
+	type DepositBase = MultisigDepositBase;
+	type DepositFactor = MultisigDepositFactor;
 	type MaxSignatories = MaxSignatories;
+	type WeightInfo = ();
 }
 
 parameter_types! {
@@ -184,6 +210,7 @@ impl pallet_babe::Trait for Runtime {
 	type EpochDuration = EpochDuration;
 	type ExpectedBlockTime = ExpectedBlockTime;
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+	type HandleEquivocation = pallet_babe::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 parameter_types! {
@@ -209,15 +236,21 @@ parameter_types! {
 	pub const WeightFeeCoefficient: Balance = 1_000;
 	// for a sane configuration, this should always be less than `AvailableBlockRatio`.
 	pub const TargetBlockFullness: Perbill = Perbill::from_percent(25);
+	// operational extrinsics are rare, so the operational multiplier only rises when they take
+	// up a noticeable share of the block.
+	pub const OperationalTargetBlockFullness: Perbill = Perbill::from_percent(5);
+	pub const NormalDispatchClass: DispatchClass = DispatchClass::Normal;
+	pub const OperationalDispatchClass: DispatchClass = DispatchClass::Operational;
 }
 
 impl pallet_transaction_payment::Trait for Runtime {
-	type Currency = Balances;
-	type OnTransactionPayment = DealWithFees;
+	type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, DealWithFees>;
 	type TransactionBaseFee = TransactionBaseFee;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = LinearWeightToFee<WeightFeeCoefficient>;
-	type FeeMultiplierUpdate = TargetedFeeAdjustment<TargetBlockFullness>;
+	type FeeMultiplierUpdate = TargetedFeeAdjustment<TargetBlockFullness, NormalDispatchClass>;
+	type OperationalFeeMultiplierUpdate =
+		TargetedFeeAdjustment<OperationalTargetBlockFullness, OperationalDispatchClass>;
 }
 
 parameter_types! {
@@ -246,6 +279,7 @@ impl_opaque_keys! {
 		pub babe: Babe,
 		pub im_online: ImOnline,
 		pub authority_discovery: AuthorityDiscovery,
+		pub randomness_beacon: RandomnessBeacon,
 	}
 }
 
@@ -449,7 +483,7 @@ parameter_types! {
 impl pallet_contracts::Trait for Runtime {
 	type Currency = Balances;
 	type Time = Timestamp;
-	type Randomness = RandomnessCollectiveFlip;
+	type Randomness = RandomnessBeacon;
 	type Call = Call;
 	type Event = Event;
 	type DetermineContractAddress = pallet_contracts::SimpleAddressDeterminer<Runtime>;
@@ -496,6 +530,19 @@ impl pallet_im_online::Trait for Runtime {
 	type SessionDuration = SessionDuration;
 }
 
+parameter_types! {
+	pub const RandomnessRoundLength: BlockNumber = 1 * MINUTES;
+	pub const RandomnessThreshold: Perbill = Perbill::from_percent(67);
+}
+
+impl pallet_randomness_beacon::Trait for Runtime {
+	type Event = Event;
+	type Call = Call;
+	type SubmitTransaction = SubmitTransaction;
+	type RoundLength = RandomnessRoundLength;
+	type Threshold = RandomnessThreshold;
+}
+
 impl pallet_offences::Trait for Runtime {
 	type Event = Event;
 	type IdentificationTuple = pallet_session::historical::IdentificationTuple<Self>;
@@ -506,6 +553,7 @@ impl pallet_authority_discovery::Trait for Runtime {}
 
 impl pallet_grandpa::Trait for Runtime {
 	type Event = Event;
+	type HandleEquivocation = pallet_grandpa::EquivocationHandler<Offences, SubmitTransaction>;
 }
 
 parameter_types! {
@@ -565,7 +613,7 @@ impl frame_system::offchain::CreateTransaction<Runtime, UncheckedExtrinsic> for
 			None,
 			frame_system::CheckVersion::<Runtime>::new(),
 			frame_system::CheckGenesis::<Runtime>::new(),
-			frame_system::CheckEra::<Runtime>::from(generic::Era::mortal(period, current_block)),
+			frame_system::CheckEra::<Runtime, ExpectedBlockTime>::from(generic::Era::mortal(period, current_block)),
 			frame_system::CheckNonce::<Runtime>::from(index),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(tip),
@@ -610,7 +658,7 @@ parameter_types! {
 impl pallet_society::Trait for Runtime {
 	type Event = Event;
 	type Currency = Balances;
-	type Randomness = RandomnessCollectiveFlip;
+	type Randomness = RandomnessBeacon;
 	type CandidateDeposit = CandidateDeposit;
 	type WrongSideDeduction = WrongSideDeduction;
 	type MaxStrikes = MaxStrikes;
@@ -634,6 +682,65 @@ impl pallet_vesting::Trait for Runtime {
 	type MinVestedTransfer = MinVestedTransfer;
 }
 
+parameter_types! {
+	pub const VerificationMethodDepositPerByte: Balance = 1 * CENTS;
+	pub const VerificationMethodDepositId: frame_system::StorageDepositId = *b"verifier";
+	pub const MaxCustomParamsLen: u32 = 256;
+}
+
+impl prml_doughnut_revocation::Trait for Runtime {
+	type Event = Event;
+	type IssuerAccount = ConvertInto;
+}
+
+impl prml_account_verifier::Trait for Runtime {
+	type Event = Event;
+	type CustomVerifier = ();
+	type StorageDeposit = frame_system::StorageDeposit<
+		Runtime,
+		Balances,
+		VerificationMethodDepositPerByte,
+		VerificationMethodDepositId,
+	>;
+	type MaxCustomParamsLen = MaxCustomParamsLen;
+}
+
+parameter_types! {
+	pub const MaxReservedNodes: u32 = 64;
+	pub const MaxLightClients: u32 = 256;
+	pub const MaxAddressLength: u32 = 256;
+	pub const MaxAuthors: u32 = 64;
+}
+
+impl prml_network_privacy::Trait for Runtime {
+	type Event = Event;
+	type AdminOrigin = pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, Self::Doughnut, CouncilCollective>;
+	type MaxReservedNodes = MaxReservedNodes;
+	type MaxLightClients = MaxLightClients;
+	type MaxAddressLength = MaxAddressLength;
+	type AuthorityId = PoaId;
+	type MaxAuthors = MaxAuthors;
+}
+
+parameter_types! {
+	pub const EvmChainId: u64 = 42;
+	pub const EvmMinGasPrice: u64 = MILLICENTS as u64;
+	pub const EvmBlockGasLimit: u64 = 10_000_000;
+	// half of the normal weight of a block at the block gas limit
+	pub const EvmWeightPerGas: Weight = 37;
+}
+
+impl pallet_evm::Trait for Runtime {
+	type FeeCalculator = pallet_evm::FixedGasPrice<EvmMinGasPrice>;
+	type ConvertAccountId = pallet_evm::HashTruncateConvertAccountId<BlakeTwo256>;
+	type Currency = Balances;
+	type Event = Event;
+	type Precompiles = ();
+	type ChainId = EvmChainId;
+	type BlockGasLimit = EvmBlockGasLimit;
+	type WeightPerGas = EvmWeightPerGas;
+}
+
 construct_runtime!(
 	pub enum Runtime where
 		Block = Block,
@@ -641,8 +748,9 @@ construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic
 	{
 		System: frame_system::{Module, Call, Storage, Config, Event},
-		Utility: pallet_utility::{Module, Call, Storage, Event<T>},
-		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp)},
+		Utility: pallet_utility::{Module, Call, Event<T>},
+		Multisig: pallet_multisig::{Module, Call, Storage, Event<T>},
+		Babe: pallet_babe::{Module, Call, Storage, Config, Inherent(Timestamp), ValidateUnsigned},
 		Timestamp: pallet_timestamp::{Module, Call, Storage, Inherent},
 		Authorship: pallet_authorship::{Module, Call, Storage, Inherent},
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
@@ -656,18 +764,22 @@ construct_runtime!(
 		Elections: pallet_elections_phragmen::{Module, Call, Storage, Event<T>},
 		TechnicalMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
 		FinalityTracker: pallet_finality_tracker::{Module, Call, Inherent},
-		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event},
+		Grandpa: pallet_grandpa::{Module, Call, Storage, Config, Event, ValidateUnsigned},
 		Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
 		Contracts: pallet_contracts::{Module, Call, Config<T>, Storage, Event<T>},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
 		ImOnline: pallet_im_online::{Module, Call, Storage, Event<T>, ValidateUnsigned, Config<T>},
 		AuthorityDiscovery: pallet_authority_discovery::{Module, Call, Config},
 		Offences: pallet_offences::{Module, Call, Storage, Event},
-		RandomnessCollectiveFlip: pallet_randomness_collective_flip::{Module, Call, Storage},
+		RandomnessBeacon: pallet_randomness_beacon::{Module, Call, Storage, Event<T>, ValidateUnsigned, Config},
 		Identity: pallet_identity::{Module, Call, Storage, Event<T>},
 		Society: pallet_society::{Module, Call, Storage, Event<T>, Config<T>},
 		Recovery: pallet_recovery::{Module, Call, Storage, Event<T>},
 		Vesting: pallet_vesting::{Module, Call, Storage, Event<T>, Config<T>},
+		AccountVerifier: prml_account_verifier::{Module, Call, Storage, Event<T>},
+		DoughnutRevocation: prml_doughnut_revocation::{Module, Call, Storage, Event<T>},
+		EVM: pallet_evm::{Module, Call, Storage, Event<T>, ValidateUnsigned},
+		NetworkPrivacy: prml_network_privacy::{Module, Call, Storage, Event<T>, Config<T>},
 	}
 );
 
@@ -681,18 +793,19 @@ pub type Block = generic::Block<Header, UncheckedExtrinsic>;
 pub type SignedBlock = generic::SignedBlock<Block>;
 /// BlockId type as expected by this runtime.
 pub type BlockId = generic::BlockId<Block>;
-/// The `SignedExtension` payload for transactions in the plug runtime.
-/// It can contain a doughnut delegation proof as it's first value.
-pub type SignedExtra = (
-	Option<<Runtime as frame_system::Trait>::Doughnut>,
-	frame_system::CheckVersion<Runtime>,
-	frame_system::CheckGenesis<Runtime>,
-	frame_system::CheckEra<Runtime>,
-	frame_system::CheckNonce<Runtime>,
-	frame_system::CheckWeight<Runtime>,
-	pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
-	pallet_contracts::CheckBlockGasLimit<Runtime>,
-);
+frame_support::compose_signed_extra! {
+	/// The `SignedExtension` payload for transactions in the plug runtime.
+	/// It can contain a doughnut delegation proof as it's first value.
+	pub type SignedExtra where Doughnut = <Runtime as frame_system::Trait>::Doughnut {
+		check_version: frame_system::CheckVersion<Runtime>,
+		check_genesis: frame_system::CheckGenesis<Runtime>,
+		check_era: frame_system::CheckEra<Runtime, ExpectedBlockTime>,
+		check_nonce: frame_system::CheckNonce<Runtime>,
+		check_weight: frame_system::CheckWeight<Runtime>,
+		charge_transaction_payment: pallet_transaction_payment::ChargeTransactionPayment<Runtime>,
+		check_block_gas_limit: pallet_contracts::CheckBlockGasLimit<Runtime>,
+	}
+}
 /// Unchecked extrinsic type as expected by this runtime.
 pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
 /// The payload being signed in transactions.
@@ -700,7 +813,13 @@ pub type SignedPayload = generic::SignedPayload<Call, SignedExtra>;
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
-pub type Executive = frame_executive::Executive<Runtime, Block, frame_system::ChainContext<Runtime>, Runtime, AllModules>;
+pub type Executive = frame_executive::Executive<
+	Runtime,
+	Block,
+	prml_account_verifier::VerifierContext<Runtime>,
+	Runtime,
+	AllModules,
+>;
 
 impl_runtime_apis! {
 	impl sp_api::Core<Block> for Runtime {
@@ -741,7 +860,7 @@ impl_runtime_apis! {
 		}
 
 		fn random_seed() -> <Block as BlockT>::Hash {
-			RandomnessCollectiveFlip::random_seed()
+			RandomnessBeacon::random_seed()
 		}
 	}
 
@@ -758,12 +877,40 @@ impl_runtime_apis! {
 		fn offchain_worker(header: &<Block as BlockT>::Header) {
 			Executive::offchain_worker(header)
 		}
+
+		fn offchain_workers() -> Vec<Vec<u8>> {
+			Executive::offchain_workers::<AllOffchainWorkers>()
+		}
+
+		fn offchain_worker_of(header: &<Block as BlockT>::Header, worker: Vec<u8>) {
+			Executive::offchain_worker_of::<AllOffchainWorkers>(header, &worker)
+		}
 	}
 
 	impl fg_primitives::GrandpaApi<Block> for Runtime {
 		fn grandpa_authorities() -> GrandpaAuthorityList {
 			Grandpa::grandpa_authorities()
 		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: fg_primitives::EquivocationProof<
+				<Block as BlockT>::Hash,
+				NumberFor<Block>,
+			>,
+			key_owner_proof: fg_primitives::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Grandpa::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+		}
+
+		fn generate_key_ownership_proof(
+			set_id: fg_primitives::SetId,
+			authority_id: fg_primitives::AuthorityId,
+		) -> Option<fg_primitives::OpaqueKeyOwnershipProof> {
+			Grandpa::prove_key_ownership(set_id, authority_id)
+				.map(|proof| fg_primitives::OpaqueKeyOwnershipProof::new(codec::Encode::encode(&proof)))
+		}
 	}
 
 	impl sp_consensus_babe::BabeApi<Block> for Runtime {
@@ -786,6 +933,23 @@ impl_runtime_apis! {
 		fn current_epoch_start() -> sp_consensus_babe::SlotNumber {
 			Babe::current_epoch_start()
 		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+			key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			let key_owner_proof = key_owner_proof.decode()?;
+
+			Babe::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+		}
+
+		fn generate_key_ownership_proof(
+			slot_number: sp_consensus_babe::SlotNumber,
+			authority_id: sp_consensus_babe::AuthorityId,
+		) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+			Babe::prove_key_ownership(slot_number, authority_id)
+				.map(|proof| sp_consensus_babe::OpaqueKeyOwnershipProof::new(codec::Encode::encode(&proof)))
+		}
 	}
 
 	impl sp_authority_discovery::AuthorityDiscoveryApi<Block> for Runtime {
@@ -794,6 +958,37 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl sp_consensus_poa::PoaApi<Block> for Runtime {
+		fn authorities() -> Vec<PoaId> {
+			NetworkPrivacy::authorities()
+		}
+	}
+
+	impl sp_network_privacy::NetworkPrivacyApi<Block> for Runtime {
+		fn reserved_peers() -> Vec<Vec<u8>> {
+			NetworkPrivacy::reserved_peers()
+		}
+
+		fn reserved_nodes_with_expiry() -> Vec<(Vec<u8>, Option<u64>)> {
+			NetworkPrivacy::reserved_nodes_with_expiry()
+		}
+
+		fn reserved_node_operators() -> Vec<(Vec<u8>, [u8; 32])> {
+			NetworkPrivacy::reserved_node_operators()
+				.into_iter()
+				.map(|(address, operator)| (address, operator.into()))
+				.collect()
+		}
+
+		fn authorized_light_clients() -> Vec<sp_network_privacy::PeerIdBytes> {
+			NetworkPrivacy::authorized_light_clients()
+		}
+
+		fn transactions_key() -> Option<(u64, [u8; 32])> {
+			NetworkPrivacy::current_transactions_key()
+		}
+	}
+
 	impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
 		fn account_nonce(account: AccountId) -> Index {
 			System::account_nonce(account)
@@ -841,6 +1036,53 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_evm_rpc_runtime_api::EvmApi<Block> for Runtime {
+		fn chain_id() -> u64 {
+			EvmChainId::get()
+		}
+
+		fn call(
+			from: sp_core::H160,
+			to: sp_core::H160,
+			data: Vec<u8>,
+			value: sp_core::U256,
+			gas_limit: u32,
+		) -> EvmCallResult {
+			match EVM::call_readonly(from, to, data, value, gas_limit) {
+				(pallet_evm::ExitReason::Succeed(_), output) => EvmCallResult::Succeed(output),
+				(pallet_evm::ExitReason::Revert(_), output) => EvmCallResult::Revert(output),
+				_ => EvmCallResult::Error,
+			}
+		}
+
+		fn transact_extrinsic(transaction: Vec<u8>) -> Option<UncheckedExtrinsic> {
+			EVM::check_transaction(&transaction).ok()?;
+			Some(UncheckedExtrinsic::new_unsigned(pallet_evm::Call::transact(transaction).into()))
+		}
+
+		fn account_nonce(address: sp_core::H160) -> sp_core::U256 {
+			EVM::accounts(address).nonce
+		}
+
+		fn receipts() -> Vec<pallet_evm::Receipt> {
+			EVM::receipts()
+		}
+
+		fn block_logs() -> Vec<BlockLog> {
+			let events = System::events();
+			let transaction_hash = |index| events.iter().find_map(|record| match (&record.phase, &record.event) {
+				(frame_system::Phase::ApplyExtrinsic(i), Event::pallet_evm(pallet_evm::RawEvent::Transacted(_, hash)))
+					if *i == index => Some(*hash),
+				_ => None,
+			});
+			events.iter().filter_map(|record| match (&record.phase, &record.event) {
+				(frame_system::Phase::ApplyExtrinsic(i), Event::pallet_evm(pallet_evm::RawEvent::Log(log))) =>
+					Some(BlockLog { extrinsic_index: *i, transaction_hash: transaction_hash(*i), log: log.clone() }),
+				_ => None,
+			}).collect()
+		}
+	}
+
 	impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<
 		Block,
 		Balance,
@@ -851,6 +1093,21 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_transaction_payment_rpc_runtime_api::DelegatedTransactionPaymentApi<
+		Block,
+		Balance,
+		AccountId,
+		UncheckedExtrinsic,
+	> for Runtime {
+		fn query_delegated_info(
+			uxt: UncheckedExtrinsic,
+			doughnut: Vec<u8>,
+			len: u32,
+		) -> Option<RuntimeDelegatedDispatchInfo<Balance, AccountId>> {
+			TransactionPayment::query_delegated_info(uxt, doughnut, len)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
@@ -861,6 +1118,91 @@ impl_runtime_apis! {
 		) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 			SessionKeys::decode_into_raw_public_keys(&encoded)
 		}
+
+		fn generate_session_keys_with_proof(
+			owner: Vec<u8>,
+			seed: Option<Vec<u8>>,
+		) -> Option<(Vec<u8>, Vec<u8>)> {
+			let owner = <AccountId as codec::Decode>::decode(&mut &owner[..]).ok()?;
+			let message = Session::ownership_message(&owner, Session::current_index() + 1);
+			SessionKeys::generate_with_proof(seed, &message)
+		}
+	}
+
+	impl sp_session::SessionMembershipApi<Block> for Runtime {
+		fn prove_session_membership(
+			session: sp_staking::SessionIndex,
+			key_type: sp_core::crypto::KeyTypeId,
+			key_data: Vec<u8>,
+		) -> Option<sp_session::OpaqueMembershipProof> {
+			pallet_session::historical::Module::<Runtime>::prove_session_membership(
+				session,
+				(key_type, &key_data[..]),
+			).map(|proof| sp_session::OpaqueMembershipProof::new(codec::Encode::encode(&proof)))
+		}
+	}
+
+	impl sp_runtime_upgrade::RuntimeUpgradeApi<Block> for Runtime {
+		fn pending_upgrade() -> Option<sp_runtime_upgrade::PendingUpgrade<BlockNumber>> {
+			use codec::Decode;
+			use frame_support::StorageMap;
+
+			// runtime upgrades pass a referendum and wait in the dispatch queue of democracy,
+			// ordered by block number, until the end of their enactment period.
+			Democracy::dispatch_queue().into_iter().find_map(|(at, proposal_hash, _)| {
+				let (proposal, ..) = pallet_democracy::Preimages::<Runtime>::get(proposal_hash)?;
+				match Call::decode(&mut &proposal[..]).ok()? {
+					Call::System(frame_system::Call::set_code(code)) |
+					Call::System(frame_system::Call::set_code_without_checks(code)) =>
+						Some(sp_runtime_upgrade::PendingUpgrade { at, code }),
+					_ => None,
+				}
+			})
+		}
+	}
+
+	#[cfg(feature = "try-runtime")]
+	impl sp_runtime_upgrade::MigrationApi<Block> for Runtime {
+		fn on_runtime_upgrade() -> u64 {
+			Executive::try_runtime_upgrade()
+		}
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	impl frame_benchmarking::Benchmark<Block> for Runtime {
+		fn dispatch_benchmark(
+			module: Vec<u8>,
+			extrinsic: Vec<u8>,
+			lowest_range_values: Vec<u32>,
+			highest_range_values: Vec<u32>,
+			steps: Vec<u32>,
+			repeat: u32,
+		) -> Result<Vec<frame_benchmarking::BenchmarkResults>, sp_runtime::RuntimeString> {
+			use frame_benchmarking::Benchmarking;
+
+			let result = match module.as_slice() {
+				b"pallet-multisig" | b"multisig" => Multisig::run_benchmark(
+					extrinsic,
+					lowest_range_values,
+					highest_range_values,
+					steps,
+					repeat,
+				),
+				_ => Err("Benchmark not found for this pallet."),
+			};
+
+			result.map_err(|e| e.into())
+		}
+
+		fn dispatch_doughnut_benchmark(
+			doughnuts: Vec<frame_benchmarking::DoughnutBenchmarkInput>,
+			repeat: u32,
+		) -> Result<Vec<frame_benchmarking::BenchmarkResults>, sp_runtime::RuntimeString> {
+			frame_benchmarking::run_doughnut_benchmark::<prml_doughnut::PlugDoughnutValidator<Runtime>>(
+				doughnuts,
+				repeat,
+			).map_err(Into::into)
+		}
 	}
 }
 
@@ -891,4 +1233,26 @@ mod tests {
 		is_submit_signed_transaction::<SubmitTransaction>();
 		is_sign_and_submit_transaction::<SubmitTransaction>();
 	}
+
+	#[test]
+	fn event_types_are_decodable_by_name() {
+		use codec::Encode;
+		use sp_scale_value::{RuntimeTypes, TypeRegistry};
+
+		let types = RuntimeTypes::from_metadata(&Runtime::metadata().encode()).unwrap();
+		let registry = TypeRegistry::default();
+		// Zeroes decode as a value of every supported type: empty sequences, `None`s and first
+		// variants.
+		let zeroes = [0u8; 1024];
+		for (module, events) in &types.events {
+			for (event, arguments) in events {
+				for ty in arguments.iter().filter(|ty| ty.as_str() != "Doughnut") {
+					assert!(
+						registry.decode(ty, &mut &zeroes[..]).is_ok(),
+						"`{}` of {}.{} can't be decoded", ty, module, event,
+					);
+				}
+			}
+		}
+	}
 }
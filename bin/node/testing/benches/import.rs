@@ -53,9 +53,9 @@ criterion_group!(
 criterion_main!(benches, profile);
 
 fn bench_block_import(c: &mut Criterion) {
-	sc_cli::init_logger("");
+	sc_cli::init_logger("", sc_cli::LogFormat::Human);
 	// for future uses, uncomment if something wrong.
-	// sc_cli::init_logger("sc_client=debug");
+	// sc_cli::init_logger("sc_client=debug", sc_cli::LogFormat::Human);
 
 	let mut bench_db = BenchDb::new(128);
 	let block = bench_db.generate_block(100);
@@ -115,7 +115,7 @@ fn bench_block_import(c: &mut Criterion) {
 //   It just produces special pattern of cpu load that allows easy picking
 //   the part of block import for the profiling in the tool of choice.
 fn profile_block_import(c: &mut Criterion) {
-	sc_cli::init_logger("");
+	sc_cli::init_logger("", sc_cli::LogFormat::Human);
 
 	let mut bench_db = BenchDb::new(128);
 	let block = bench_db.generate_block(100);
@@ -186,7 +186,7 @@ impl fmt::Debug for Setup {
 }
 
 fn bench_wasm_size_import(c: &mut Criterion) {
-	sc_cli::init_logger("");
+	sc_cli::init_logger("", sc_cli::LogFormat::Human);
 
 	c.bench_function_over_inputs("wasm_size_import",
 		move |bencher, setup| {
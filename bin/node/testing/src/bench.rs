@@ -142,7 +142,10 @@ impl BenchDb {
 		let db_config = sc_client_db::DatabaseSettings {
 			state_cache_size: 16*1024*1024,
 			state_cache_child_ratio: Some((0, 100)),
+			trie_cache_size: 16*1024*1024,
+			blocks_pruning: Default::default(),
 			pruning: PruningMode::ArchiveAll,
+			state_retention: Default::default(),
 			source: sc_client_db::DatabaseSettingsSrc::Path {
 				path: dir.into(),
 				cache_size: None,
@@ -116,6 +116,7 @@ pub fn config_endowed(
 		}),
 		pallet_im_online: Some(Default::default()),
 		pallet_authority_discovery: Some(Default::default()),
+		pallet_randomness_beacon: Some(Default::default()),
 		pallet_democracy: Some(Default::default()),
 		pallet_collective_Instance1: Some(Default::default()),
 		pallet_collective_Instance2: Some(Default::default()),
@@ -127,6 +128,7 @@ pub fn config_endowed(
 			pot: 0,
 			max_members: 999,
 		}),
+		prml_network_privacy: Some(Default::default()),
 		pallet_vesting: Some(Default::default()),
 	}
 }
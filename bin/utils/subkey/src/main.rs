@@ -25,13 +25,16 @@ use hex_literal::hex;
 use itertools::Itertools;
 use libp2p::identity::{ed25519 as libp2p_ed25519, PublicKey};
 use node_primitives::{Balance, Hash, Index, AccountId, Signature};
-use node_runtime::{BalancesCall, Call, Runtime, SignedPayload, UncheckedExtrinsic, VERSION};
+use node_runtime::{
+	BalancesCall, Call, Runtime, SignedExtraAdditionalSigned, SignedPayload, UncheckedExtrinsic,
+	VERSION,
+};
 use serde_json::json;
 use sp_core::{
-	crypto::{set_default_ss58_version, Ss58AddressFormat, Ss58Codec},
+	crypto::{set_default_ss58_version, AccountId20, Ss58AddressFormat, Ss58Codec},
 	ed25519, sr25519, ecdsa, Pair, Public, H256, hexdisplay::HexDisplay,
 };
-use sp_runtime::{traits::{IdentifyAccount, Verify}, generic::Era};
+use sp_runtime::{traits::{IdentifyAccount, Verify}, generic::Era, EthereumSignature};
 use sp_runtime::traits::Extrinsic;
 use std::{
 	convert::{TryInto, TryFrom}, io::{stdin, Read}, str::FromStr, path::PathBuf, fs, fmt,
@@ -72,6 +75,14 @@ trait Crypto: Sized {
 	fn public_from_pair(pair: &Self::Pair) -> Self::Public {
 		pair.public()
 	}
+	/// Whether the keys are accounts of the node runtime, signing its transactions.
+	const RUNTIME_ACCOUNTS: bool = true;
+	fn sign(pair: &Self::Pair, message: &[u8]) -> SignatureOf<Self> {
+		pair.sign(message)
+	}
+	fn verify(signature: &SignatureOf<Self>, message: &[u8], public: &Self::Public) -> bool {
+		<Self::Pair as Pair>::verify(signature, message, public)
+	}
 	fn print_from_uri(
 		uri: &str,
 		password: Option<&str>,
@@ -197,6 +208,77 @@ impl Crypto for Ecdsa {
 	type Public = ecdsa::Public;
 }
 
+/// Ethereum compatible accounts: secp256k1 keys identified by the `AccountId20` of their
+/// address, signing the messages as `personal_sign` does.
+struct Ethereum;
+
+impl Crypto for Ethereum {
+	type Pair = ecdsa::Pair;
+	type Public = ecdsa::Public;
+
+	const RUNTIME_ACCOUNTS: bool = false;
+
+	fn ss58_from_pair(pair: &Self::Pair) -> String {
+		AccountId20::try_from(pair.public()).map(|address| address.to_string()).unwrap_or_default()
+	}
+
+	fn sign(pair: &Self::Pair, message: &[u8]) -> SignatureOf<Self> {
+		pair.sign_prehashed(&EthereumSignature::message_hash(message))
+	}
+
+	fn verify(signature: &SignatureOf<Self>, message: &[u8], public: &Self::Public) -> bool {
+		AccountId20::try_from(public.clone())
+			.map_or(false, |address| EthereumSignature(signature.clone()).verify(message, &address))
+	}
+
+	fn print_from_uri(
+		uri: &str,
+		password: Option<&str>,
+		_network_override: Option<Ss58AddressFormat>,
+		output: OutputType,
+	) {
+		let (kind, key, seed, public_key) = if let Ok((pair, seed)) = ecdsa::Pair::from_phrase(uri, password) {
+			("Secret phrase", "secretPhrase", Some(format_seed::<Self>(seed)), pair.public())
+		} else if let Ok((pair, seed)) = ecdsa::Pair::from_string_with_seed(uri, password) {
+			let seed = seed.map_or_else(|| "n/a".into(), format_seed::<Self>);
+			("Secret Key URI", "secretKeyUri", Some(seed), pair.public())
+		} else if let Ok(public_key) = <ecdsa::Public as Ss58Codec>::from_string(uri) {
+			("Public Key URI", "publicKeyUri", None, public_key)
+		} else {
+			eprintln!("Invalid phrase/URI given");
+			return
+		};
+		let address = match AccountId20::try_from(public_key.clone()) {
+			Ok(address) => address,
+			Err(()) => {
+				eprintln!("Invalid secp256k1 public key given");
+				return
+			},
+		};
+
+		match output {
+			OutputType::Json => {
+				let mut json = json!({
+					key: uri,
+					"publicKey": format_public_key::<Self>(public_key),
+					"address": address.to_string(),
+				});
+				if let Some(seed) = seed {
+					json["secretSeed"] = seed.into();
+				}
+				println!("{}", serde_json::to_string_pretty(&json).expect("Json pretty print failed"));
+			},
+			OutputType::Text => {
+				println!("{} `{}` is Ethereum account:", kind, uri);
+				if let Some(seed) = seed {
+					println!("  Secret seed:      {}", seed);
+				}
+				println!("  Public key (hex): {}\n  Address:          {}", format_public_key::<Self>(public_key), address);
+			},
+		}
+	}
+}
+
 type SignatureOf<C> = <<C as Crypto>::Pair as Pair>::Signature;
 type PublicOf<C> = <<C as Crypto>::Pair as Pair>::Public;
 type SeedOf<C> = <<C as Crypto>::Pair as Pair>::Seed;
@@ -228,6 +310,8 @@ fn get_usage() -> String {
 	format!("
 		-e, --ed25519 'Use Ed25519/BIP39 cryptography'
 		-k, --secp256k1 'Use SECP256k1/ECDSA/BIP39 cryptography'
+		--ethereum 'Use Ethereum accounts: SECP256k1/ECDSA/BIP39 keys identified by their \
+			20-byte address and signing like `personal_sign`'
 		-s, --sr25519 'Use Schnorr/Ristretto x25519/BIP39 cryptography'
 		[network] -n, --network <network> 'Specify a network. One of {}. Default is {}'
 		[password] -p, --password <password> 'The password for the key'
@@ -325,6 +409,9 @@ fn main() -> Result<(), Error> {
 	if matches.is_present("secp256k1") {
 		return execute::<Ecdsa>(matches)
 	}
+	if matches.is_present("ethereum") {
+		return execute::<Ethereum>(matches)
+	}
 	return execute::<Sr25519>(matches)
 }
 
@@ -455,6 +542,9 @@ where
 			let formated_seed = format_seed::<C>(result.seed);
 			C::print_from_uri(&formated_seed, None, maybe_network, output);
 		}
+		("transfer", Some(_)) | ("sign-transaction", Some(_)) if !C::RUNTIME_ACCOUNTS => {
+			return static_err("The node runtime doesn't have Ethereum accounts");
+		}
 		("transfer", Some(matches)) => {
 			let signer = read_pair::<C>(matches.value_of("from"), password)?;
 			let index = read_required_parameter::<Index>(matches, "index")?;
@@ -526,7 +616,7 @@ where
 	PublicOf<C>: PublicT,
 {
 	let pair = read_pair::<C>(Some(suri), password)?;
-	let signature = pair.sign(&message);
+	let signature = C::sign(&pair, &message);
 	Ok(format_signature::<C>(&signature))
 }
 
@@ -538,7 +628,7 @@ where
 
 	let signature = read_signature::<C>(matches)?;
 	let pubkey = read_public_key::<C>(Some(uri));
-	Ok(<<C as Crypto>::Pair as Pair>::verify(&signature, &message, &pubkey))
+	Ok(C::verify(&signature, &message, &pubkey))
 }
 
 fn decode_hex<T: AsRef<[u8]>>(message: T) -> Result<Vec<u8>, Error> {
@@ -680,7 +770,7 @@ fn create_extrinsic<C: Crypto>(
 			None,
 			frame_system::CheckVersion::<Runtime>::new(),
 			frame_system::CheckGenesis::<Runtime>::new(),
-			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+			frame_system::CheckEra::<Runtime, node_runtime::ExpectedBlockTime>::from(Era::Immortal),
 			frame_system::CheckNonce::<Runtime>::from(i),
 			frame_system::CheckWeight::<Runtime>::new(),
 			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(f),
@@ -690,16 +780,15 @@ fn create_extrinsic<C: Crypto>(
 	let raw_payload = SignedPayload::from_raw(
 		function,
 		extra(index, 0),
-		(
-			(),
-			VERSION.spec_version as u32,
-			genesis_hash,
-			genesis_hash,
-			(),
-			(),
-			(),
-			(),
-		),
+		SignedExtraAdditionalSigned {
+			check_version: VERSION.spec_version as u32,
+			check_genesis: genesis_hash,
+			check_era: genesis_hash,
+			check_nonce: (),
+			check_weight: (),
+			charge_transaction_payment: (),
+			check_block_gas_limit: (),
+		}.into_additional_signed(),
 	);
 	let signature = raw_payload.using_encoded(|payload| signer.sign(payload)).into_runtime();
 	let signer = signer.public().into_runtime();
@@ -768,6 +857,23 @@ mod tests {
 		test_generate_sign_verify::<Sr25519>();
 	}
 
+	#[test]
+	fn generate_sign_verify_should_work_for_ethereum() {
+		test_generate_sign_verify::<Ethereum>();
+	}
+
+	#[test]
+	fn ethereum_signatures_are_personal_sign_signatures() {
+		let pair = Ethereum::pair_from_suri("//Alice", None);
+		let message = "Blah Blah\n".as_bytes();
+		let signature = Ethereum::sign(&pair, message);
+
+		let address = AccountId20::try_from(pair.public()).unwrap();
+		assert!(EthereumSignature(signature.clone()).verify(message, &address));
+		assert!(!Ecdsa::verify(&signature, message, &pair.public()));
+		assert!(!Ethereum::verify(&signature, b"other", &pair.public()));
+	}
+
 	#[test]
 	fn should_work() {
 		let s = "0123456789012345678901234567890123456789012345678901234567890123";
@@ -17,7 +17,7 @@
 //! Substrate Client data backend
 
 use std::sync::Arc;
-use std::collections::HashMap;
+use std::collections::{HashMap, BTreeSet};
 use sp_core::ChangesTrieConfigurationRange;
 use sp_core::offchain::OffchainStorage;
 use sp_runtime::{generic::BlockId, Justification, Storage};
@@ -40,6 +40,7 @@ use parking_lot::RwLock;
 
 pub use sp_state_machine::Backend as StateBackend;
 use std::marker::PhantomData;
+use codec::{Encode, Decode};
 
 /// Extracts the state backend type for the given backend.
 pub type StateBackendFor<B, Block> = <B as Backend<Block>>::State;
@@ -50,6 +51,15 @@ pub type TransactionForSB<B, Block> = <B as StateBackend<HashFor<Block>>>::Trans
 /// Extracts the transaction for the given backend.
 pub type TransactionFor<B, Block> = TransactionForSB<StateBackendFor<B, Block>, Block>;
 
+/// A key-value pair of the state of a block, as given to [`Backend::import_state`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum StateEntry {
+	/// A top-level key and value.
+	Top(Vec<u8>, Vec<u8>),
+	/// The storage key of a child trie, and a key and value in that child trie.
+	Child(Vec<u8>, Vec<u8>, Vec<u8>),
+}
+
 /// Import operation summary.
 ///
 /// Contains information about the block that just got imported,
@@ -150,6 +160,17 @@ pub trait BlockImportOperation<Block: BlockT> {
 		child_update: ChildStorageCollection,
 	) -> sp_blockchain::Result<()>;
 
+	/// Set the changes to the offchain index, written to the offchain storage on commit if the
+	/// block is imported as the new best block.
+	///
+	/// Backends without offchain storage ignore them.
+	fn update_offchain_storage(
+		&mut self,
+		_offchain_update: StorageCollection,
+	) -> sp_blockchain::Result<()> {
+		Ok(())
+	}
+
 	/// Inject changes trie data into the database.
 	fn update_changes_trie(
 		&mut self,
@@ -414,6 +435,12 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Returns a handle to offchain storage.
 	fn offchain_storage(&self) -> Option<Self::OffchainStorage>;
 
+	/// Returns a handle to the storage prefixes whose state history survives pruning, if the
+	/// backend supports it.
+	fn state_retention(&self) -> Option<StateRetention> {
+		None
+	}
+
 	/// Returns true if state for given block is available.
 	fn have_state_at(&self, hash: &Block::Hash, _number: NumberFor<Block>) -> bool {
 		self.state_at(BlockId::Hash(hash.clone())).is_ok()
@@ -422,6 +449,34 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	/// Returns state backend with post-state of given block.
 	fn state_at(&self, block: BlockId<Block>) -> sp_blockchain::Result<Self::State>;
 
+	/// Import a finalized block together with its whole state, but without its ancestors, and
+	/// make it the best and finalized block, e.g. at the end of a warp sync.
+	///
+	/// The `state` yields all top-level key-value pairs in key order, followed by the pairs of
+	/// each child trie, grouped by child trie and in key order. The state roots are checked
+	/// against the header and the child roots in the top-level state as the state is written.
+	/// `aux` is written together with the block.
+	///
+	/// Only possible while no block but genesis was imported. Fails if the backend doesn't
+	/// support it.
+	fn import_state(
+		&self,
+		_header: Block::Header,
+		_justification: Option<Justification>,
+		_aux: Vec<(Vec<u8>, Vec<u8>)>,
+		_state: &mut dyn Iterator<Item = sp_blockchain::Result<StateEntry>>,
+	) -> sp_blockchain::Result<()> {
+		Err(sp_blockchain::Error::Backend("Importing state is not supported by the backend".into()))
+	}
+
+	/// Prune the bodies and justifications of the finalized blocks according to the given
+	/// policy, e.g. after it was made stricter.
+	///
+	/// Returns the number of blocks whose body or justification was pruned.
+	fn prune_blocks(&self, _pruning: &BlocksPruning) -> sp_blockchain::Result<u64> {
+		Err(sp_blockchain::Error::Backend("Pruning blocks is not supported by the backend".into()))
+	}
+
 	/// Attempts to revert the chain by `n` blocks. If `revert_finalized` is set
 	/// it will attempt to revert past any finalized block, this is unsafe and
 	/// can potentially leave the node in an inconsistent state.
@@ -458,6 +513,134 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 	fn get_import_lock(&self) -> &RwLock<()>;
 }
 
+/// Maximum number of key prefixes whose state history can be retained at once.
+pub const MAX_RETAINED_PREFIXES: usize = 16;
+
+/// Storage key prefixes whose state history is retained when the state is pruned.
+///
+/// Clones share the same set of prefixes, so changes are seen by the backend immediately.
+#[derive(Debug, Clone, Default)]
+pub struct StateRetention(Arc<RwLock<RetainedPrefixes>>);
+
+#[derive(Debug, Default)]
+struct RetainedPrefixes {
+	/// The retained prefixes.
+	retained: BTreeSet<Vec<u8>>,
+	/// Retained prefixes whose whole state is yet to be copied.
+	unsynced: BTreeSet<Vec<u8>>,
+	/// Released prefixes whose retained state is yet to be deleted.
+	released: BTreeSet<Vec<u8>>,
+}
+
+impl StateRetention {
+	/// Create a new set of retained prefixes.
+	pub fn new(prefixes: impl IntoIterator<Item=Vec<u8>>) -> Self {
+		let retained: BTreeSet<_> = prefixes.into_iter().collect();
+		StateRetention(Arc::new(RwLock::new(RetainedPrefixes {
+			unsynced: retained.clone(),
+			retained,
+			released: BTreeSet::new(),
+		})))
+	}
+
+	/// The currently retained prefixes.
+	pub fn prefixes(&self) -> Vec<Vec<u8>> {
+		self.0.read().retained.iter().cloned().collect()
+	}
+
+	/// Start retaining the history of keys under `prefix`. Returns false if it was already retained.
+	///
+	/// Fails if [`MAX_RETAINED_PREFIXES`] prefixes are already retained.
+	pub fn retain(&self, prefix: Vec<u8>) -> sp_blockchain::Result<bool> {
+		let mut prefixes = self.0.write();
+		if prefixes.retained.contains(&prefix) {
+			return Ok(false);
+		}
+		if prefixes.retained.len() >= MAX_RETAINED_PREFIXES {
+			return Err(sp_blockchain::Error::Msg(
+				format!("At most {} prefixes can be retained", MAX_RETAINED_PREFIXES),
+			));
+		}
+		prefixes.released.remove(&prefix);
+		prefixes.unsynced.insert(prefix.clone());
+		prefixes.retained.insert(prefix);
+		Ok(true)
+	}
+
+	/// Stop retaining the history of keys under `prefix`. Returns false if it wasn't retained.
+	///
+	/// The history retained so far is deleted with the next finalized block.
+	pub fn release(&self, prefix: &[u8]) -> bool {
+		let mut prefixes = self.0.write();
+		if !prefixes.retained.remove(prefix) {
+			return false;
+		}
+		prefixes.unsynced.remove(prefix);
+		prefixes.released.insert(prefix.to_vec());
+		true
+	}
+
+	/// Take the retained prefixes whose whole state must be copied, rather than only the changes.
+	///
+	/// Called by the backend when it retains the state of a block.
+	pub fn take_unsynced(&self) -> Vec<Vec<u8>> {
+		std::mem::replace(&mut self.0.write().unsynced, BTreeSet::new()).into_iter().collect()
+	}
+
+	/// Take the released prefixes whose retained state must be deleted.
+	///
+	/// Called by the backend when it retains the state of a block.
+	pub fn take_released(&self) -> Vec<Vec<u8>> {
+		std::mem::replace(&mut self.0.write().released, BTreeSet::new()).into_iter().collect()
+	}
+}
+
+/// Prefix of the auxiliary data keys marking the blocks which enact an authority set change of
+/// the finality gadget, followed by the SCALE encoded number of the block.
+pub const AUTHORITY_SET_CHANGE_AUX_PREFIX: &[u8] = b"enacted_authority_set_change";
+
+/// The auxiliary data key marking the block with the given number as enacting an authority set
+/// change of the finality gadget.
+pub fn authority_set_change_aux_key<N: codec::Encode>(number: N) -> Vec<u8> {
+	let mut key = AUTHORITY_SET_CHANGE_AUX_PREFIX.to_vec();
+	number.using_encoded(|number| key.extend(number));
+	key
+}
+
+/// Pruning of the bodies and justifications of finalized blocks, separate from state pruning.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlocksPruning {
+	/// Number of the latest finalized blocks whose bodies are kept, all of them if `None`.
+	///
+	/// At least the body of the last finalized block is kept, `Some(0)` is invalid.
+	pub keep_bodies: Option<u32>,
+	/// The justifications of finalized blocks that are kept.
+	pub justifications: JustificationsPruning,
+}
+
+impl BlocksPruning {
+	/// Returns true if the policy prunes anything.
+	pub fn is_pruning(&self) -> bool {
+		self.keep_bodies.is_some() || self.justifications != JustificationsPruning::KeepAll
+	}
+}
+
+/// The justifications of finalized blocks that are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JustificationsPruning {
+	/// Keep all justifications.
+	KeepAll,
+	/// Keep the justifications of the blocks enacting an authority set change of the finality
+	/// gadget, as marked by the [`authority_set_change_aux_key`] auxiliary data.
+	KeepAuthoritySetChanges,
+}
+
+impl Default for JustificationsPruning {
+	fn default() -> Self {
+		JustificationsPruning::KeepAll
+	}
+}
+
 /// Changes trie storage that supports pruning.
 pub trait PrunableStateChangesTrieStorage<Block: BlockT>:
 	StateChangesTrieStorage<HashFor<Block>, NumberFor<Block>>
@@ -19,7 +19,7 @@
 use std::{fmt, collections::HashSet, sync::Arc};
 use sp_core::storage::StorageKey;
 use sp_runtime::{
-	traits::{Block as BlockT, NumberFor},
+	traits::{Block as BlockT, NumberFor, HashFor, Extrinsic as ExtrinsicT, Hash as HashT},
 	generic::{BlockId, SignedBlock},
 	Justification,
 };
@@ -36,6 +36,9 @@ pub type ImportNotifications<Block> = TracingUnboundedReceiver<BlockImportNotifi
 /// A stream of block finality notifications.
 pub type FinalityNotifications<Block> = TracingUnboundedReceiver<FinalityNotification<Block>>;
 
+/// A stream of re-org notifications.
+pub type ReorgNotifications<Block> = TracingUnboundedReceiver<ReorgNotification<Block>>;
+
 /// Expected hashes of blocks at given heights.
 ///
 /// This may be used as chain spec extension to set trusted checkpoints, i.e.
@@ -64,6 +67,10 @@ pub trait BlockchainEvents<Block: BlockT> {
 	/// finalized block.
 	fn finality_notification_stream(&self) -> FinalityNotifications<Block>;
 
+	/// Get a stream of re-org notifications, fired when a block that isn't a descendant of the
+	/// previous best block becomes the new best block.
+	fn reorg_notification_stream(&self) -> ReorgNotifications<Block>;
+
 	/// Get storage changes event stream.
 	///
 	/// Passing `None` as `filter_keys` subscribes to all storage changes.
@@ -154,6 +161,8 @@ pub struct StateDbMemoryInfo {
 pub struct MemoryInfo {
 	/// Size of state cache.
 	pub state_cache: MemorySize,
+	/// Size of the trie node cache.
+	pub trie_cache: MemorySize,
 	/// Size of backend database cache.
 	pub database_cache: MemorySize,
 	/// Size of the state db.
@@ -181,6 +190,12 @@ pub struct IoInfo {
 	pub state_reads_cache: u64,
 	/// State reads (keys) from cache.
 	pub state_writes: u64,
+	/// Entries evicted from the state cache to stay within its memory budget, since the start.
+	pub state_cache_evictions: u64,
+	/// Trie node reads served from the trie node cache, since the start.
+	pub trie_cache_hits: u64,
+	/// Trie node reads missing the trie node cache, since the start.
+	pub trie_cache_misses: u64,
 }
 
 /// Usage statistics for running client instance.
@@ -245,6 +260,57 @@ pub struct FinalityNotification<Block: BlockT> {
 	pub header: Block::Header,
 }
 
+/// Summary of a re-org of the best chain.
+///
+/// The transaction pool re-submits the retracted extrinsics on its own, this lets e.g. exchanges
+/// track the transactions that left the best chain. The notification is sent on block import, so
+/// it only lists the blocks of the re-org: see `retracted_extrinsics` for their extrinsics.
+#[derive(Clone, Debug)]
+pub struct ReorgNotification<Block: BlockT> {
+	/// Hash of the new best block.
+	pub hash: Block::Hash,
+	/// Hash of the common ancestor of the previous and the new best blocks.
+	pub common_ancestor: Block::Hash,
+	/// Hashes of the blocks retracted from the best chain, from the previous best block down.
+	pub retracted: Vec<Block::Hash>,
+	/// Hashes of the blocks enacted on the best chain, up to the new best block.
+	pub enacted: Vec<Block::Hash>,
+}
+
+impl<Block: BlockT> ReorgNotification<Block> {
+	/// Hashes of the signed extrinsics of the retracted blocks that aren't in the enacted blocks.
+	///
+	/// Reads the bodies of all the blocks of the re-org from `blocks`, blocks without a body are
+	/// skipped.
+	pub fn retracted_extrinsics<B: BlockBackend<Block> + ?Sized>(
+		&self,
+		blocks: &B,
+	) -> sp_blockchain::Result<Vec<Block::Hash>> {
+		let signed_extrinsics = |block: &Block::Hash| -> sp_blockchain::Result<Vec<Block::Hash>> {
+			Ok(blocks.block_body(&BlockId::Hash(*block))?
+				.unwrap_or_default()
+				.iter()
+				.filter(|xt| xt.is_signed().unwrap_or(true))
+				.map(|xt| HashFor::<Block>::hash_of(xt))
+				.collect())
+		};
+
+		let mut enacted_extrinsics = HashSet::new();
+		for block in &self.enacted {
+			enacted_extrinsics.extend(signed_extrinsics(block)?);
+		}
+
+		let mut retracted_extrinsics = Vec::new();
+		for block in &self.retracted {
+			retracted_extrinsics.extend(
+				signed_extrinsics(block)?.into_iter().filter(|xt| !enacted_extrinsics.contains(xt)),
+			);
+		}
+
+		Ok(retracted_extrinsics)
+	}
+}
+
 impl<B: BlockT> From<BlockImportNotification<B>> for sp_transaction_pool::ChainEvent<B> {
 	fn from(n: BlockImportNotification<B>) -> Self {
 		Self::NewBlock {
@@ -24,7 +24,10 @@ use std::sync::{Weak, Arc};
 use codec::Decode;
 use sp_core::{
 	ExecutionContext,
-	offchain::{self, OffchainExt, TransactionPoolExt},
+	offchain::{
+		self, HttpError, HttpRequestId, HttpRequestStatus, OffchainExt, OpaqueNetworkState,
+		StorageKind, Timestamp, TransactionPoolExt,
+	},
 	traits::{BareCryptoStorePtr, KeystoreExt},
 };
 use sp_runtime::{
@@ -85,6 +88,7 @@ pub struct ExecutionExtensions<Block: traits::Block> {
 	// FIXME: these two are only RwLock because of https://github.com/paritytech/substrate/issues/4587
 	//        remove when fixed.
 	transaction_pool: RwLock<Option<Weak<dyn sp_transaction_pool::OffchainSubmitTransaction<Block>>>>,
+	offchain_db: RwLock<Option<OffchainDbReader>>,
 	extensions_factory: RwLock<Box<dyn ExtensionsFactory>>,
 }
 
@@ -94,6 +98,7 @@ impl<Block: traits::Block> Default for ExecutionExtensions<Block> {
 			strategies: Default::default(),
 			keystore: None,
 			transaction_pool: RwLock::new(None),
+			offchain_db: RwLock::new(None),
 			extensions_factory: RwLock::new(Box::new(())),
 		}
 	}
@@ -106,8 +111,15 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		keystore: Option<BareCryptoStorePtr>,
 	) -> Self {
 		let transaction_pool = RwLock::new(None);
+		let offchain_db = RwLock::new(None);
 		let extensions_factory = Box::new(());
-		Self { strategies, keystore, extensions_factory: RwLock::new(extensions_factory), transaction_pool }
+		Self {
+			strategies,
+			keystore,
+			extensions_factory: RwLock::new(extensions_factory),
+			transaction_pool,
+			offchain_db,
+		}
 	}
 
 	/// Get a reference to the execution strategies.
@@ -130,6 +142,14 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 		*self.transaction_pool.write() = Some(pool);
 	}
 
+	/// Register the offchain storage, giving offchain calls without their own offchain
+	/// externalities read access to its persistent storage, e.g. to the offchain index.
+	pub fn register_offchain_db<S: offchain::OffchainStorage + 'static>(&self, db: S) {
+		*self.offchain_db.write() = Some(Arc::new(move |key: &[u8]| {
+			db.get(sp_offchain::STORAGE_PREFIX, key)
+		}));
+	}
+
 	/// Create `ExecutionManager` and `Extensions` for given offchain call.
 	///
 	/// Based on the execution context and capabilities it produces
@@ -178,12 +198,117 @@ impl<Block: traits::Block> ExecutionExtensions<Block> {
 			extensions.register(
 				OffchainExt::new(offchain::LimitedExternalities::new(capabilities, ext.0))
 			)
+		} else if capabilities.has(offchain::Capability::OffchainWorkerDbRead) {
+			// without a registered offchain storage the reads find nothing, rather than trap.
+			extensions.register(OffchainExt::new(offchain::LimitedExternalities::new(
+				capabilities,
+				OffchainDbAdapter(self.offchain_db.read().clone()),
+			)))
 		}
 
 		(manager, extensions)
 	}
 }
 
+/// Reads a key of the persistent offchain storage.
+type OffchainDbReader = Arc<dyn Fn(&[u8]) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Offchain externalities of the offchain calls without their own externalities, only reading
+/// the persistent offchain storage. The other APIs are forbidden by the capabilities of these
+/// calls, so they are never reached.
+struct OffchainDbAdapter(Option<OffchainDbReader>);
+
+impl OffchainDbAdapter {
+	fn unavailable(name: &str) -> ! {
+		unreachable!("`{}` isn't available to offchain calls without offchain externalities", name)
+	}
+}
+
+impl offchain::Externalities for OffchainDbAdapter {
+	fn is_validator(&self) -> bool {
+		false
+	}
+
+	fn network_state(&self) -> Result<OpaqueNetworkState, ()> {
+		Self::unavailable("network_state")
+	}
+
+	fn timestamp(&mut self) -> Timestamp {
+		Self::unavailable("timestamp")
+	}
+
+	fn sleep_until(&mut self, _deadline: Timestamp) {
+		Self::unavailable("sleep_until")
+	}
+
+	fn random_seed(&mut self) -> [u8; 32] {
+		Self::unavailable("random_seed")
+	}
+
+	fn local_storage_set(&mut self, _kind: StorageKind, _key: &[u8], _value: &[u8]) {
+		Self::unavailable("local_storage_set")
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		_kind: StorageKind,
+		_key: &[u8],
+		_old_value: Option<&[u8]>,
+		_new_value: &[u8],
+	) -> bool {
+		Self::unavailable("local_storage_compare_and_set")
+	}
+
+	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
+		match kind {
+			StorageKind::PERSISTENT => self.0.as_ref().and_then(|db| db(key)),
+			StorageKind::LOCAL => None,
+		}
+	}
+
+	fn http_request_start(&mut self, _method: &str, _uri: &str, _meta: &[u8]) -> Result<HttpRequestId, ()> {
+		Self::unavailable("http_request_start")
+	}
+
+	fn http_request_add_header(&mut self, _request_id: HttpRequestId, _name: &str, _value: &str) -> Result<(), ()> {
+		Self::unavailable("http_request_add_header")
+	}
+
+	fn http_request_write_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_chunk: &[u8],
+		_deadline: Option<Timestamp>,
+	) -> Result<(), HttpError> {
+		Self::unavailable("http_request_write_body")
+	}
+
+	fn http_response_wait(&mut self, _ids: &[HttpRequestId], _deadline: Option<Timestamp>) -> Vec<HttpRequestStatus> {
+		Self::unavailable("http_response_wait")
+	}
+
+	fn http_response_headers(&mut self, _request_id: HttpRequestId) -> Vec<(Vec<u8>, Vec<u8>)> {
+		Self::unavailable("http_response_headers")
+	}
+
+	fn http_response_read_body(
+		&mut self,
+		_request_id: HttpRequestId,
+		_buffer: &mut [u8],
+		_deadline: Option<Timestamp>,
+	) -> Result<usize, HttpError> {
+		Self::unavailable("http_response_read_body")
+	}
+
+	fn ipfs_get(&mut self, _cid: &str, _deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		Self::unavailable("ipfs_get")
+	}
+
+	fn ipfs_pin(&mut self, _cid: &str, _deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		Self::unavailable("ipfs_pin")
+	}
+}
+
 /// A wrapper type to pass `BlockId` to the actual transaction pool.
 struct TransactionPoolAdapter<Block: traits::Block> {
 	at: BlockId<Block>,
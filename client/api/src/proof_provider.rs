@@ -40,6 +40,20 @@ pub trait ProofProvider<Block: BlockT> {
 		keys: &mut dyn Iterator<Item=&[u8]>,
 	) -> sp_blockchain::Result<StorageProof>;
 
+	/// Read the key values of the state of a block in key order, starting after `start` (or at
+	/// the first key if `None`), until `size_limit` bytes of keys and values were read. Reads
+	/// the child trie at `storage_key` if given, and the top-level trie otherwise.
+	///
+	/// Returns the key values and whether the end of the trie was reached. Unless the end was
+	/// reached, at least one key value is returned.
+	fn read_state_chunk(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: Option<&[u8]>,
+		start: Option<&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<(Vec<(Vec<u8>, Vec<u8>)>, bool)>;
+
 	/// Execute a call to a contract on top of state in a block of given hash
 	/// AND returning execution proof.
 	///
@@ -18,7 +18,7 @@
 
 // FIXME #1021 move this into sp-consensus
 
-use std::{time, sync::Arc};
+use std::{time, sync::Arc, collections::{HashMap, HashSet, VecDeque}};
 use sc_client_api::backend;
 use codec::Decode;
 use sp_consensus::{evaluation, Proposal, RecordProof};
@@ -28,6 +28,7 @@ use sp_core::ExecutionContext;
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Hash as HashT, Header as HeaderT, DigestFor, BlakeTwo256},
+	Percent,
 };
 use sp_transaction_pool::{TransactionPool, InPoolTransaction};
 use sc_telemetry::{telemetry, CONSENSUS_INFO};
@@ -43,6 +44,10 @@ pub struct ProposerFactory<A, B, C> {
 	client: Arc<C>,
 	/// The transaction pool.
 	transaction_pool: Arc<A>,
+	/// Whether delegated transactions are batched by doughnut issuer.
+	doughnut_batching: bool,
+	/// Share of the proposing time after which no more transactions are packed.
+	soft_deadline: Option<Percent>,
 	/// phantom member to pin the `Backend` type.
 	_phantom: PhantomData<B>,
 }
@@ -52,9 +57,30 @@ impl<A, B, C> ProposerFactory<A, B, C> {
 		ProposerFactory {
 			client,
 			transaction_pool,
+			doughnut_batching: false,
+			soft_deadline: None,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Push delegated transactions sharing a doughnut issuer to the block back to back.
+	///
+	/// The runtime caches doughnut signature verification for the duration of a block, so
+	/// batching lets each doughnut be verified once rather than once per transaction.
+	pub fn with_doughnut_batching(mut self, enabled: bool) -> Self {
+		self.doughnut_batching = enabled;
+		self
+	}
+
+	/// Stop packing transactions once the given share of the proposing time has elapsed.
+	///
+	/// The execution time of every pushed transaction is tracked, and a transaction is deferred to a
+	/// later block when the slowest one seen so far would not finish before the soft deadline.
+	/// This leaves time to seal a partial block instead of missing the slot.
+	pub fn with_soft_deadline(mut self, soft_deadline: Percent) -> Self {
+		self.soft_deadline = Some(soft_deadline);
+		self
+	}
 }
 
 impl<B, Block, C, A> ProposerFactory<A, B, C>
@@ -85,6 +111,8 @@ impl<B, Block, C, A> ProposerFactory<A, B, C>
 				parent_id: id,
 				parent_number: *parent_header.number(),
 				transaction_pool: self.transaction_pool.clone(),
+				doughnut_batching: self.doughnut_batching,
+				soft_deadline: self.soft_deadline,
 				now,
 				_phantom: PhantomData,
 			}),
@@ -129,6 +157,8 @@ struct ProposerInner<B, Block: BlockT, C, A: TransactionPool> {
 	parent_id: BlockId<Block>,
 	parent_number: <<Block as BlockT>::Header as HeaderT>::Number,
 	transaction_pool: Arc<A>,
+	doughnut_batching: bool,
+	soft_deadline: Option<Percent>,
 	now: Box<dyn Fn() -> time::Instant + Send + Sync>,
 	_phantom: PhantomData<B>,
 }
@@ -160,8 +190,12 @@ impl<A, B, Block, C> sp_consensus::Proposer<Block> for
 		let inner = self.inner.clone();
 		tokio_executor::blocking::run(move || {
 			// leave some time for evaluation and block finalization (33%)
-			let deadline = (inner.now)() + max_duration - max_duration / 3;
-			inner.propose_with(inherent_data, inherent_digests, deadline, record_proof)
+			let now = (inner.now)();
+			let deadline = now + max_duration - max_duration / 3;
+			let soft_deadline = inner.soft_deadline
+				.map(|share| now + max_duration * share.deconstruct() as u32 / 100)
+				.map(|soft_deadline| soft_deadline.min(deadline));
+			inner.propose_with(inherent_data, inherent_digests, deadline, soft_deadline, record_proof)
 		})
 	}
 }
@@ -181,12 +215,18 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 		inherent_data: InherentData,
 		inherent_digests: DigestFor<Block>,
 		deadline: time::Instant,
+		soft_deadline: Option<time::Instant>,
 		record_proof: RecordProof,
 	) -> Result<Proposal<Block, backend::TransactionFor<B, Block>>, sp_blockchain::Error> {
 		/// If the block is full we will attempt to push at most
 		/// this number of transactions before quitting for real.
 		/// It allows us to increase block utilization.
 		const MAX_SKIPPED_TRANSACTIONS: usize = 8;
+		/// Number of deferred transactions whose hashes are reported to telemetry.
+		const MAX_REPORTED_DEFERRED: usize = 16;
+
+		// the block being built has no hash yet, its records are attached to its parent.
+		let _context = sp_tracing::log_context::enter_block(self.parent_number, self.parent_hash);
 
 		let mut block_builder = self.client.new_block_at(
 			&self.parent_id,
@@ -222,11 +262,20 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 				self.transaction_pool.ready()
 			}
 		};
+		let mut pending_iterator: Box<dyn Iterator<Item=_>> = if self.doughnut_batching {
+			let pool = &self.transaction_pool;
+			Box::new(group_by_doughnut_issuer(pending_iterator, |xt| pool.doughnut_issuer(xt)).into_iter())
+		} else {
+			pending_iterator
+		};
 
 		debug!("Attempting to push transactions from the pool.");
 		debug!("Pool status: {:?}", self.transaction_pool.status());
-		for pending_tx in pending_iterator {
-			if (self.now)() > deadline {
+		let mut slowest = time::Duration::default();
+		let mut deferred = Vec::new();
+		for pending_tx in &mut pending_iterator {
+			let now = (self.now)();
+			if now > deadline {
 				debug!(
 					"Consensus deadline reached when pushing block transactions, \
 					proceeding with proposing."
@@ -234,10 +283,25 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 				break;
 			}
 
-			let pending_tx_data = pending_tx.data().clone();
 			let pending_tx_hash = pending_tx.hash().clone();
+			if soft_deadline.map_or(false, |soft_deadline| now + slowest > soft_deadline) {
+				debug!(
+					"Soft deadline reached when pushing block transactions, \
+					proceeding with a partial block."
+				);
+				deferred.push(pending_tx_hash);
+				break;
+			}
+
+			let pending_tx_data = pending_tx.data().clone();
 			trace!("[{:?}] Pushing to the block.", pending_tx_hash);
-			match sc_block_builder::BlockBuilder::push(&mut block_builder, pending_tx_data) {
+			let result = sc_block_builder::BlockBuilder::push(&mut block_builder, pending_tx_data);
+			if soft_deadline.is_some() {
+				let elapsed = (self.now)().saturating_duration_since(now);
+				trace!("[{:?}] Executed in {:?}.", pending_tx_hash, elapsed);
+				slowest = slowest.max(elapsed);
+			}
+			match result {
 				Ok(()) => {
 					debug!("[{:?}] Pushed to the block.", pending_tx_hash);
 				}
@@ -275,6 +339,24 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 
 		self.transaction_pool.remove_invalid(&unqueue_invalid);
 
+		if !deferred.is_empty() {
+			// the rest of the pool is deferred too, only the first hashes are reported.
+			let mut remaining = pending_iterator.map(|pending_tx| pending_tx.hash().clone());
+			deferred.extend(remaining.by_ref().take(MAX_REPORTED_DEFERRED - 1));
+			let count = deferred.len() + remaining.count();
+			info!(
+				"⏳ Deferred {} transactions to a later block, slowest transaction took {:?}",
+				count,
+				slowest,
+			);
+			telemetry!(CONSENSUS_INFO; "deferred_block_transactions";
+				"parent_hash" => ?self.parent_hash,
+				"slowest_ms" => slowest.as_millis() as u64,
+				"count" => count,
+				"transactions" => ?deferred,
+			);
+		}
+
 		let (block, storage_changes, proof) = block_builder.build()?.into_inner();
 
 		info!("🎁 Prepared block for proposing at {} [hash: {:?}; parent_hash: {}; extrinsics ({}): [{}]]",
@@ -305,6 +387,62 @@ impl<A, B, Block, C> ProposerInner<B, Block, C, A>
 	}
 }
 
+/// Reorder ready transactions so that delegated transactions sharing a doughnut issuer are
+/// consecutive.
+///
+/// A transaction is only pulled forward into its issuer's batch once every tag it requires from
+/// another pending transaction is provided by one already placed, so the order stays valid.
+fn group_by_doughnut_issuer<T: InPoolTransaction>(
+	pending: impl Iterator<Item=Arc<T>>,
+	issuer_of: impl Fn(&T::Transaction) -> Option<Vec<u8>>,
+) -> Vec<Arc<T>> {
+	let pending = pending.collect::<Vec<_>>();
+	let issuers = pending.iter().map(|tx| issuer_of(tx.data())).collect::<Vec<_>>();
+	let pending_provides = pending.iter()
+		.flat_map(|tx| tx.provides().iter().cloned())
+		.collect::<HashSet<_>>();
+
+	let mut batches = HashMap::<&[u8], VecDeque<usize>>::new();
+	for (idx, issuer) in issuers.iter().enumerate() {
+		if let Some(issuer) = issuer {
+			batches.entry(&issuer[..]).or_default().push_back(idx);
+		}
+	}
+
+	let mut placed = vec![false; pending.len()];
+	let mut provided = HashSet::new();
+	let mut ordered = Vec::with_capacity(pending.len());
+	for idx in 0..pending.len() {
+		if placed[idx] {
+			continue
+		}
+		// every transaction before `idx` has been placed, so its requirements are met.
+		placed[idx] = true;
+		provided.extend(pending[idx].provides().iter().cloned());
+		ordered.push(pending[idx].clone());
+
+		let batch = match issuers[idx].as_ref().and_then(|issuer| batches.get_mut(&issuer[..])) {
+			Some(batch) => batch,
+			None => continue,
+		};
+		batch.retain(|&other| {
+			if placed[other] {
+				return false
+			}
+			let ready = pending[other].requires().iter()
+				.all(|tag| !pending_provides.contains(tag) || provided.contains(tag));
+			if ready {
+				placed[other] = true;
+				provided.extend(pending[other].provides().iter().cloned());
+				ordered.push(pending[other].clone());
+			}
+			!ready
+		});
+	}
+
+	ordered
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -396,6 +534,55 @@ mod tests {
 		assert_eq!(txpool.ready().count(), 2);
 	}
 
+	#[test]
+	fn should_defer_transactions_when_soft_deadline_is_reached() {
+		// given
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let txpool = Arc::new(
+			BasicPool::new(
+				Default::default(),
+				Arc::new(FullChainApi::new(client.clone())),
+				None,
+			).0
+		);
+
+		futures::executor::block_on(
+			txpool.submit_at(&BlockId::number(0), SOURCE, (0..4).map(extrinsic).collect())
+		).unwrap();
+
+		futures::executor::block_on(
+			txpool.maintain(chain_event(
+				client.header(&BlockId::Number(0u64)).expect("header get error").expect("there should be header")
+			))
+		);
+
+		let mut proposer_factory = ProposerFactory::new(client.clone(), txpool.clone())
+			.with_soft_deadline(Percent::from_percent(50));
+
+		// every transaction takes a second to execute
+		let cell = Mutex::new(time::Instant::now());
+		let mut proposer = proposer_factory.init_with_now(
+			&client.header(&BlockId::number(0)).unwrap().unwrap(),
+			Box::new(move || {
+				let mut value = cell.lock();
+				let old = *value;
+				*value = old + time::Duration::from_secs(1);
+				old
+			})
+		);
+
+		// when
+		let deadline = time::Duration::from_secs(10);
+		let block = futures::executor::block_on(
+			proposer.propose(Default::default(), Default::default(), deadline, RecordProof::No)
+		).map(|r| r.block).unwrap();
+
+		// then
+		// the hard deadline would allow a third transaction, but it would not finish before the soft one.
+		assert_eq!(block.extrinsics().len(), 2);
+		assert_eq!(txpool.ready().count(), 4);
+	}
+
 	#[test]
 	fn should_not_panic_when_deadline_is_reached() {
 		let client = Arc::new(substrate_test_runtime_client::new());
@@ -567,4 +754,47 @@ mod tests {
 		let block = propose_block(&client, 1, 2, 5);
 		client.import(BlockOrigin::Own, block).unwrap();
 	}
+
+	struct TestTransaction {
+		id: u64,
+		issuer: Option<Vec<u8>>,
+		requires: Vec<Vec<u8>>,
+		provides: Vec<Vec<u8>>,
+	}
+
+	impl InPoolTransaction for TestTransaction {
+		type Transaction = Option<Vec<u8>>;
+		type Hash = u64;
+
+		fn data(&self) -> &Self::Transaction { &self.issuer }
+		fn hash(&self) -> &Self::Hash { &self.id }
+		fn priority(&self) -> &u64 { &0 }
+		fn longevity(&self) -> &u64 { &64 }
+		fn requires(&self) -> &[Vec<u8>] { &self.requires }
+		fn provides(&self) -> &[Vec<u8>] { &self.provides }
+		fn is_propagable(&self) -> bool { true }
+	}
+
+	#[test]
+	fn should_batch_transactions_by_doughnut_issuer_without_breaking_dependencies() {
+		let tx = |id, issuer: Option<&str>, requires: &[&str], provides: &[&str]| Arc::new(TestTransaction {
+			id,
+			issuer: issuer.map(|issuer| issuer.as_bytes().to_vec()),
+			requires: requires.iter().map(|tag| tag.as_bytes().to_vec()).collect(),
+			provides: provides.iter().map(|tag| tag.as_bytes().to_vec()).collect(),
+		});
+		let pending = vec![
+			tx(0, Some("x"), &[], &["a0"]),
+			tx(1, None, &[], &["b0"]),
+			tx(2, Some("y"), &[], &["c0"]),
+			// depends on a transaction of another issuer, so it can't be pulled forward
+			tx(3, Some("x"), &["c0"], &["c1"]),
+			// depends on a tag provided by the chain state
+			tx(4, Some("x"), &["d0"], &["d1"]),
+		];
+
+		let ordered = group_by_doughnut_issuer(pending.into_iter(), |issuer| issuer.clone());
+
+		assert_eq!(ordered.iter().map(|tx| tx.id).collect::<Vec<_>>(), vec![0, 4, 1, 2, 3]);
+	}
 }
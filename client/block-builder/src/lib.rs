@@ -27,7 +27,7 @@
 use codec::Encode;
 
 use sp_runtime::{
-	generic::BlockId,
+	generic::{BlockId, Digest},
 	traits::{Header as HeaderT, Hash, Block as BlockT, HashFor, DigestFor, NumberFor, One},
 };
 use sp_blockchain::{ApplyExtrinsicFailed, Error};
@@ -61,6 +61,26 @@ impl<Block: BlockT, StateBackend: backend::StateBackend<HashFor<Block>>> BuiltBl
 	}
 }
 
+/// Return the header a block builder initialized the block described by `header` with, i.e.
+/// without the digest items the runtime and the block author added to it, to re-execute the
+/// block one runtime call at a time.
+pub fn initial_header<Block: BlockT>(header: &Block::Header) -> Block::Header {
+	let digest = Digest {
+		logs: header.digest().logs().iter()
+			.filter(|log| log.as_pre_runtime().is_some())
+			.cloned()
+			.collect(),
+	};
+
+	Block::Header::new(
+		*header.number(),
+		Default::default(),
+		Default::default(),
+		*header.parent_hash(),
+		digest,
+	)
+}
+
 /// Block builder provider
 pub trait BlockBuilderProvider<B, Block, RA>
 	where
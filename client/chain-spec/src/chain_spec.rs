@@ -26,7 +26,8 @@ use sp_core::storage::{StorageKey, StorageData, ChildInfo, Storage, StorageChild
 use sp_runtime::BuildStorage;
 use serde_json as json;
 use crate::RuntimeGenesis;
-use crate::extension::GetExtension;
+use crate::extension::{GetExtension, get_extension};
+use crate::network_privacy::NetworkPrivacyExtension;
 use sc_network::Multiaddr;
 use sc_telemetry::TelemetryEndpoints;
 
@@ -71,10 +72,10 @@ impl<G: RuntimeGenesis> GenesisSource<G> {
 	}
 }
 
-impl<G: RuntimeGenesis, E> BuildStorage for ChainSpec<G, E> {
+impl<G: RuntimeGenesis, E: GetExtension> BuildStorage for ChainSpec<G, E> {
 	fn build_storage(&self) -> Result<Storage, String> {
 		match self.genesis.resolve()? {
-			Genesis::Runtime(gc) => gc.build_storage(),
+			Genesis::Runtime(gc) => self.build_runtime_storage(gc),
 			Genesis::Raw(RawGenesis { top: map, children: children_map }) => Ok(Storage {
 				top: map.into_iter().map(|(k, v)| (k.0, v.0)).collect(),
 				children: children_map.into_iter().map(|(sk, child_content)| {
@@ -270,7 +271,20 @@ impl<G, E: serde::de::DeserializeOwned> ChainSpec<G, E> {
 	}
 }
 
-impl<G: RuntimeGenesis, E: serde::Serialize + Clone> ChainSpec<G, E> {
+impl<G: RuntimeGenesis, E: GetExtension> ChainSpec<G, E> {
+	/// Build the genesis storage of the runtime genesis config `genesis`, together with the
+	/// storage of the extensions written at genesis.
+	fn build_runtime_storage(&self, genesis: G) -> Result<Storage, String> {
+		let mut storage = genesis.build_storage()?;
+		let extensions: &dyn GetExtension = &self.client_spec.extensions;
+		if let Some(Some(network_privacy)) = get_extension::<NetworkPrivacyExtension>(extensions) {
+			network_privacy.assimilate_storage(&mut storage)?;
+		}
+		Ok(storage)
+	}
+}
+
+impl<G: RuntimeGenesis, E: GetExtension + serde::Serialize + Clone> ChainSpec<G, E> {
 	/// Dump to json string.
 	pub fn as_json(&self, raw: bool) -> Result<String, String> {
 		#[derive(Serialize, Deserialize)]
@@ -282,7 +296,7 @@ impl<G: RuntimeGenesis, E: serde::Serialize + Clone> ChainSpec<G, E> {
 		};
 		let genesis = match (raw, self.genesis.resolve()?) {
 			(true, Genesis::Runtime(g)) => {
-				let storage = g.build_storage()?;
+				let storage = self.build_runtime_storage(g)?;
 				let top = storage.top.into_iter()
 					.map(|(k, v)| (StorageKey(k), StorageData(v)))
 					.collect();
@@ -410,4 +424,44 @@ mod tests {
 
 		assert_eq!(spec.extensions().my_property, "Test Extension");
 	}
+
+	// Make the proc macro work for tests.
+	use crate as sc_chain_spec;
+
+	#[derive(Debug, Clone, Serialize, Deserialize, sc_chain_spec_derive::ChainSpecExtension)]
+	#[serde(rename_all = "camelCase")]
+	struct Extension2 {
+		network_privacy: NetworkPrivacyExtension,
+	}
+
+	#[test]
+	fn should_write_network_privacy_extension_to_genesis_storage() {
+		use crate::NetworkPrivacy;
+		use sp_network_privacy::well_known_keys;
+
+		let reserved_node = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+		let spec = ChainSpec::from_genesis(
+			"Test",
+			"test",
+			|| Genesis(HashMap::new()),
+			Vec::new(),
+			None,
+			None,
+			None,
+			Extension2 {
+				network_privacy: Some(NetworkPrivacy {
+					reserved_nodes: vec![reserved_node.into()],
+					..Default::default()
+				}),
+			},
+		);
+
+		let storage = spec.build_storage().unwrap();
+		assert!(storage.top.contains_key(well_known_keys::RESERVED_NODES));
+
+		let raw = ChainSpec::<Genesis, Extension2>::from_json_bytes(
+			spec.as_json(true).unwrap().into_bytes()
+		).unwrap();
+		assert_eq!(raw.build_storage().unwrap().top, storage.top);
+	}
 }
@@ -106,13 +106,39 @@
 //!
 //! pub type MyChainSpec<G> = GenericChainSpec<G, Extension>;
 //! ```
+//!
+//! Private networks can declare the [`NetworkPrivacyExtension`](./type.NetworkPrivacyExtension.html)
+//! in their extension. Its reserved nodes are added to the reserved nodes of every node and
+//! written to the genesis storage, and the boot nodes must be signed by the operator key if one
+//! is set.
+//!
+//! ```rust
+//! use serde::{Serialize, Deserialize};
+//! use sc_chain_spec::{ChainSpecExtension, GenericChainSpec, NetworkPrivacyExtension};
+//!
+//! #[derive(Clone, Debug, Serialize, Deserialize, ChainSpecExtension)]
+//! #[serde(rename_all = "camelCase")]
+//! pub struct Extension {
+//!		pub network_privacy: NetworkPrivacyExtension,
+//! }
+//!
+//! pub type MyChainSpec<G> = GenericChainSpec<G, Extension>;
+//! ```
+//!
+//! Chains can also declare the [`WasmFuelExtension`](./type.WasmFuelExtension.html) to limit the
+//! number of wasm instructions executed by a block import, whatever the weight annotations of the
+//! runtime.
 
 
 mod chain_spec;
 mod extension;
+mod network_privacy;
+mod wasm_fuel;
 
 pub use chain_spec::{ChainSpec as GenericChainSpec, Properties, NoExtension};
 pub use extension::{Group, Fork, Forks, Extension, GetExtension, get_extension};
+pub use network_privacy::{NetworkPrivacy, NetworkPrivacyExtension};
+pub use wasm_fuel::{WasmFuel, WasmFuelExtension};
 pub use sc_chain_spec_derive::{ChainSpecExtension, ChainSpecGroup};
 
 use serde::{Serialize, de::DeserializeOwned};
@@ -0,0 +1,175 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The `networkPrivacy` chain spec extension.
+
+use codec::Encode;
+use serde::{Serialize, Deserialize};
+use sp_core::{sr25519, Pair};
+use sp_core::storage::Storage;
+use sp_network_privacy::well_known_keys;
+use sp_runtime::BuildStorage;
+
+/// The `networkPrivacy` extension, `None` if the chain spec doesn't define it.
+pub type NetworkPrivacyExtension = Option<NetworkPrivacy>;
+
+/// Network privacy parameters of a private network.
+///
+/// The reserved nodes and the operator key are written to the genesis storage, from which nodes
+/// read the reserved nodes to connect to and the key of the operator. If the chain has an
+/// operator key, nodes refuse to start unless the boot nodes and the reserved nodes of the chain
+/// spec are signed by that key: the key of the chain spec itself is not trusted.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct NetworkPrivacy {
+	/// The reserved nodes at genesis, as `p2p` multiaddresses.
+	#[serde(default)]
+	pub reserved_nodes: Vec<String>,
+	/// The public key of the network operator.
+	#[serde(default)]
+	pub operator_key: Option<sr25519::Public>,
+	/// The signature by the operator key of the boot nodes and the reserved nodes.
+	#[serde(default)]
+	pub signature: Option<sr25519::Signature>,
+}
+
+impl NetworkPrivacy {
+	/// The message signed by the operator, the SCALE encoded boot nodes and reserved nodes.
+	fn signed_payload(&self, boot_nodes: &[String]) -> Vec<u8> {
+		(boot_nodes, &self.reserved_nodes).encode()
+	}
+
+	/// Sign `boot_nodes` and the reserved nodes with the operator key `pair`.
+	pub fn sign(&mut self, boot_nodes: &[String], pair: &sr25519::Pair) {
+		self.operator_key = Some(pair.public());
+		self.signature = Some(pair.sign(&self.signed_payload(boot_nodes)));
+	}
+
+	/// Check that `boot_nodes` and the reserved nodes are signed by `operator_key`, the operator
+	/// key stored on chain, if the chain has an operator.
+	pub fn verify(
+		&self,
+		boot_nodes: &[String],
+		operator_key: Option<&sr25519::Public>,
+	) -> Result<(), String> {
+		let operator_key = match operator_key {
+			Some(operator_key) => operator_key,
+			None => return Ok(()),
+		};
+		let signature = self.signature.as_ref()
+			.ok_or_else(|| "The boot nodes of the chain spec are not signed by the operator".to_string())?;
+
+		if sr25519::Pair::verify(signature, &self.signed_payload(boot_nodes), operator_key) {
+			Ok(())
+		} else {
+			Err("Invalid operator signature of the boot nodes of the chain spec".into())
+		}
+	}
+}
+
+impl BuildStorage for NetworkPrivacy {
+	fn assimilate_storage(&self, storage: &mut Storage) -> Result<(), String> {
+		let reserved_nodes = self.reserved_nodes.iter()
+			.map(|node| node.as_bytes().to_vec())
+			.collect::<Vec<_>>();
+		storage.top.insert(well_known_keys::RESERVED_NODES.to_vec(), reserved_nodes.encode());
+
+		if let Some(operator_key) = &self.operator_key {
+			storage.top.insert(well_known_keys::OPERATOR_KEY.to_vec(), operator_key.0.encode());
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BOOT_NODE: &str =
+		"/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	const RESERVED_NODE: &str =
+		"/ip4/198.51.100.20/tcp/30333/p2p/QmWv9Ww7znzgLFyCzf21SR6tUKXrmHCZH9KhebeH4gyE9f";
+
+	fn operator() -> sr25519::Public {
+		sr25519::Pair::from_seed(&[1; 32]).public()
+	}
+
+	fn signed() -> NetworkPrivacy {
+		let mut network_privacy = NetworkPrivacy {
+			reserved_nodes: vec![RESERVED_NODE.into()],
+			..Default::default()
+		};
+		network_privacy.sign(&[BOOT_NODE.into()], &sr25519::Pair::from_seed(&[1; 32]));
+		network_privacy
+	}
+
+	#[test]
+	fn without_operator_key_is_not_verified() {
+		let network_privacy = NetworkPrivacy {
+			reserved_nodes: vec![RESERVED_NODE.into()],
+			..Default::default()
+		};
+
+		assert_eq!(network_privacy.verify(&[BOOT_NODE.into()], None), Ok(()));
+	}
+
+	#[test]
+	fn signed_boot_nodes_are_verified() {
+		assert_eq!(signed().verify(&[BOOT_NODE.into()], Some(&operator())), Ok(()));
+	}
+
+	#[test]
+	fn tampered_nodes_are_rejected() {
+		let network_privacy = signed();
+		assert!(network_privacy.verify(&[], Some(&operator())).is_err());
+
+		let mut tampered = network_privacy.clone();
+		tampered.reserved_nodes.push(BOOT_NODE.into());
+		assert!(tampered.verify(&[BOOT_NODE.into()], Some(&operator())).is_err());
+
+		let mut unsigned = network_privacy;
+		unsigned.signature = None;
+		assert!(unsigned.verify(&[BOOT_NODE.into()], Some(&operator())).is_err());
+	}
+
+	#[test]
+	fn nodes_signed_by_another_key_are_rejected() {
+		let mut network_privacy = signed();
+		network_privacy.sign(&[BOOT_NODE.into()], &sr25519::Pair::from_seed(&[2; 32]));
+
+		// the chain spec carries the key it is signed with, but the operator key is on chain.
+		let spec_key = network_privacy.operator_key;
+		assert_eq!(network_privacy.verify(&[BOOT_NODE.into()], spec_key.as_ref()), Ok(()));
+		assert!(network_privacy.verify(&[BOOT_NODE.into()], Some(&operator())).is_err());
+	}
+
+	#[test]
+	fn genesis_storage_contains_reserved_nodes_and_operator_key() {
+		let network_privacy = signed();
+		let storage = network_privacy.build_storage().unwrap();
+
+		assert_eq!(
+			storage.top.get(well_known_keys::RESERVED_NODES),
+			Some(&vec![RESERVED_NODE.as_bytes().to_vec()].encode()),
+		);
+		assert_eq!(
+			storage.top.get(well_known_keys::OPERATOR_KEY),
+			Some(&network_privacy.operator_key.unwrap().0.encode()),
+		);
+	}
+}
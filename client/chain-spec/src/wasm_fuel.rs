@@ -0,0 +1,36 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The `wasmFuel` chain spec extension.
+
+use serde::{Serialize, Deserialize};
+
+/// The `wasmFuel` extension, `None` if the chain spec doesn't define it.
+pub type WasmFuelExtension = Option<WasmFuel>;
+
+/// Fuel metering of the wasm execution.
+///
+/// The execution of a block fails once it has executed `limit` wasm instructions. Unlike the
+/// block weight, the limit doesn't depend on the weight annotations of the runtime, so it bounds
+/// the execution of under-weighted calls. Other runtime calls are not metered. Native execution
+/// is not metered either, so the nodes of the chain always import blocks in wasm.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+pub struct WasmFuel {
+	/// The fuel of a block execution, roughly its number of executed wasm instructions.
+	pub limit: u64,
+}
@@ -74,6 +74,143 @@ impl Into<sc_tracing::TracingReceiver> for TracingReceiver {
 	}
 }
 
+arg_enum! {
+	/// The format of the log output.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum LogFormat {
+		// Human readable lines.
+		Human,
+		// One JSON object per line.
+		Json,
+	}
+}
+
+arg_enum! {
+	/// The database backend of the client.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum Database {
+		// RocksDB.
+		RocksDb,
+		// ParityDB, see the `db migrate` command to migrate a RocksDB database.
+		ParityDb,
+	}
+}
+
+arg_enum! {
+	/// What to do with the notifications of an RPC subscription when its buffer is full.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum RpcSubscriptionOverflow {
+		// Drop the oldest buffered notification.
+		DropOldest,
+		// Drop the new notification.
+		DropNewest,
+		// Close the subscription.
+		Unsubscribe,
+	}
+}
+
+impl Into<sc_service::config::RpcSubscriptionOverflow> for RpcSubscriptionOverflow {
+	fn into(self) -> sc_service::config::RpcSubscriptionOverflow {
+		match self {
+			RpcSubscriptionOverflow::DropOldest => sc_service::config::RpcSubscriptionOverflow::DropOldest,
+			RpcSubscriptionOverflow::DropNewest => sc_service::config::RpcSubscriptionOverflow::DropNewest,
+			RpcSubscriptionOverflow::Unsubscribe => sc_service::config::RpcSubscriptionOverflow::Unsubscribe,
+		}
+	}
+}
+
+arg_enum! {
+	/// The RPC methods exposed by the RPC servers.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum RpcMethods {
+		// Expose the unsafe methods only on the servers listening on a loopback address.
+		Auto,
+		// Only expose the safe methods.
+		Safe,
+		// Expose every method.
+		Unsafe,
+	}
+}
+
+impl Into<sc_service::config::RpcMethods> for RpcMethods {
+	fn into(self) -> sc_service::config::RpcMethods {
+		match self {
+			RpcMethods::Auto => sc_service::config::RpcMethods::Auto,
+			RpcMethods::Safe => sc_service::config::RpcMethods::Safe,
+			RpcMethods::Unsafe => sc_service::config::RpcMethods::Unsafe,
+		}
+	}
+}
+
+arg_enum! {
+	/// How to sync a node that only has the genesis block.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum SyncMode {
+		// Download and import every block.
+		Full,
+		// Prove the finality of a recent block, download its state and sync on from it.
+		Warp,
+	}
+}
+
+impl Into<sc_network::config::SyncMode> for SyncMode {
+	fn into(self) -> sc_network::config::SyncMode {
+		match self {
+			SyncMode::Full => sc_network::config::SyncMode::Full,
+			SyncMode::Warp => sc_network::config::SyncMode::Warp,
+		}
+	}
+}
+
+arg_enum! {
+	/// The blocks the transaction pool is revalidated against.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum RevalidationStrategy {
+		// The new best blocks.
+		Best,
+		// The newly finalized blocks.
+		Finalized,
+	}
+}
+
+arg_enum! {
+	/// The justifications of finalized blocks that are kept.
+	#[allow(missing_docs)]
+	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+	pub enum KeepJustifications {
+		// All of them.
+		All,
+		// The justifications of the blocks enacting a GRANDPA authority set change.
+		AuthoritySetChanges,
+	}
+}
+
+impl Into<sc_service::config::JustificationsPruning> for KeepJustifications {
+	fn into(self) -> sc_service::config::JustificationsPruning {
+		use sc_service::config::JustificationsPruning;
+
+		match self {
+			KeepJustifications::All => JustificationsPruning::KeepAll,
+			KeepJustifications::AuthoritySetChanges => JustificationsPruning::KeepAuthoritySetChanges,
+		}
+	}
+}
+
+impl Into<sc_service::config::RevalidateAt> for RevalidationStrategy {
+	fn into(self) -> sc_service::config::RevalidateAt {
+		match self {
+			RevalidationStrategy::Best => sc_service::config::RevalidateAt::Best,
+			RevalidationStrategy::Finalized => sc_service::config::RevalidateAt::Finalized,
+		}
+	}
+}
+
 arg_enum! {
 	#[allow(missing_docs)]
 	#[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -0,0 +1,119 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use std::fmt::Debug;
+use structopt::StructOpt;
+use sc_service::{
+	Configuration, ServiceBuilderCommand, ChainSpec, Roles,
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+use crate::error;
+use crate::VersionInfo;
+use crate::runtime::run_until_exit;
+use crate::params::{SharedParams, PruningParams};
+
+/// The `check-block-determinism` command used to compare the native and wasm executions of
+/// blocks.
+#[derive(Debug, StructOpt, Clone)]
+pub struct CheckBlockDeterminismCmd {
+	/// Range of block numbers, `FROM..TO` with `TO` included, or `FROM..` up to the best block.
+	#[structopt(value_name = "FROM..TO")]
+	pub range: String,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl CheckBlockDeterminismCmd {
+	/// Run the check-block-determinism command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		let (from, to) = parse_range(&self.range)?;
+
+		let start = std::time::Instant::now();
+		run_until_exit(config, |config| {
+			Ok(builder(config)?.check_block_determinism(from.into(), to.map(Into::into)))
+		})?;
+		println!("Completed in {} ms.", start.elapsed().as_millis());
+
+		Ok(())
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
+
+/// Parse a `FROM..TO` or `FROM..` range of block numbers.
+fn parse_range(range: &str) -> error::Result<(u32, Option<u32>)> {
+	let invalid = || error::Error::Input(format!("Invalid block range {}, expected FROM..TO", range));
+
+	let separator = range.find("..").ok_or_else(invalid)?;
+	let from = range[..separator].trim().parse::<u32>().map_err(|_| invalid())?;
+	let to = match range[separator + 2..].trim() {
+		"" => None,
+		to => Some(to.parse::<u32>().map_err(|_| invalid())?),
+	};
+
+	match to {
+		Some(to) if to < from => Err(invalid()),
+		_ => Ok((from, to)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn ranges_are_parsed() {
+		assert_eq!(parse_range("1..10").unwrap(), (1, Some(10)));
+		assert_eq!(parse_range("5..").unwrap(), (5, None));
+		assert_eq!(parse_range("7..7").unwrap(), (7, Some(7)));
+		assert!(parse_range("10..1").is_err());
+		assert!(parse_range("10").is_err());
+		assert!(parse_range("a..b").is_err());
+	}
+}
@@ -0,0 +1,193 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt::Debug;
+use std::io::{self, Write};
+use structopt::StructOpt;
+use sc_service::{
+	Configuration, ServiceBuilderCommand, ChainSpec, Roles, config::DatabaseConfig,
+	db_migration::{self, MigrationPhase, MigrationProgress},
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+
+use crate::error;
+use crate::VersionInfo;
+use crate::params::{SharedParams, PruningParams};
+
+/// The `db` command used to manage the database of the client.
+#[derive(Debug, StructOpt, Clone)]
+pub enum DbCmd {
+	/// Migrate the RocksDB database to ParityDB, in place.
+	Migrate(DbMigrateCmd),
+
+	/// Prune the bodies and justifications of the finalized blocks according to the
+	/// `--keep-bodies` and `--justifications-pruning` options.
+	PruneBodies(DbPruneBodiesCmd),
+}
+
+/// The `db migrate` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DbMigrateCmd {
+	/// Skip interactive prompt by answering yes automatically.
+	#[structopt(short = "y")]
+	pub yes: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// The `db prune-bodies` command.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DbPruneBodiesCmd {
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl DbCmd {
+	/// Get the shared parameters of the command.
+	pub fn shared_params(&self) -> &SharedParams {
+		match self {
+			DbCmd::Migrate(cmd) => &cmd.shared_params,
+			DbCmd::PruneBodies(cmd) => &cmd.shared_params,
+		}
+	}
+
+	/// Run the db command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		match self {
+			DbCmd::Migrate(cmd) => cmd.run(config),
+			DbCmd::PruneBodies(cmd) => cmd.run(config, builder),
+		}
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params().update_config(&mut config, spec_factory, version)?;
+		if let DbCmd::PruneBodies(cmd) = self {
+			cmd.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		}
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
+
+impl DbMigrateCmd {
+	/// Run the migrate command
+	pub fn run(self, config: Configuration) -> error::Result<()> {
+		let db_path = match config.expect_database() {
+			DatabaseConfig::Path { path, .. } => path,
+			DatabaseConfig::ParityDb { .. } => {
+				return Err("The database is already a ParityDB database, remove `--database paritydb`".into());
+			},
+			DatabaseConfig::Custom(_) => {
+				return Err("Cannot migrate custom database implementation".into());
+			},
+		};
+
+		if !self.yes {
+			print!(
+				"Migrate {:?} to ParityDB? The node must be stopped, and the disk needs room for a copy. [y/N]: ",
+				&db_path,
+			);
+			io::stdout().flush().expect("failed to flush stdout");
+
+			let mut input = String::new();
+			io::stdin().read_line(&mut input)?;
+			let input = input.trim();
+
+			match input.chars().nth(0) {
+				Some('y') | Some('Y') => {},
+				_ => {
+					println!("Aborted");
+					return Ok(());
+				},
+			}
+		}
+
+		let backup_path = db_migration::migrate_to_parity_db(db_path, print_progress)?;
+
+		println!("{:?} migrated to ParityDB.", &db_path);
+		println!("Start the node with `--database paritydb` to use it.");
+		println!(
+			"The RocksDB database was moved to {:?}, it can be removed once the node runs fine, or moved back to roll back.",
+			&backup_path,
+		);
+
+		Ok(())
+	}
+}
+
+impl DbPruneBodiesCmd {
+	/// Run the prune-bodies command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+	{
+		let pruning = config.blocks_pruning.clone();
+		if !pruning.is_pruning() {
+			return Err("Nothing to prune, pass `--keep-bodies` or `--justifications-pruning`".into());
+		}
+
+		builder(config)?.prune_blocks(&pruning)?;
+
+		Ok(())
+	}
+}
+
+fn print_progress(progress: MigrationProgress) {
+	let phase = match progress.phase {
+		MigrationPhase::Copy => "Copying",
+		MigrationPhase::Verify => "Verifying",
+	};
+	println!(
+		"{} column {}/{}: {} entries",
+		phase,
+		progress.column + 1,
+		progress.columns,
+		progress.entries,
+	);
+}
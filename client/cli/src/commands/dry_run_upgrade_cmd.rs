@@ -0,0 +1,205 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::fs;
+use std::path::PathBuf;
+use std::fmt::Debug;
+use std::str::FromStr;
+use log::info;
+use serde_json::{json, Value};
+use structopt::StructOpt;
+use sc_service::{
+	Configuration, ServiceBuilderCommand, ChainSpec, DryRunState, Roles,
+};
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::generic::BlockId;
+
+use crate::error;
+use crate::VersionInfo;
+use crate::runtime::run_until_exit;
+use crate::params::{SharedParams, PruningParams};
+
+/// Number of keys requested at once from a remote node.
+const PAGE_SIZE: u32 = 512;
+
+/// The `dry-run-upgrade` command used to execute the migrations of a new runtime against the
+/// state of a live chain.
+#[derive(Debug, StructOpt, Clone)]
+pub struct DryRunUpgradeCmd {
+	/// Wasm blob of the runtime whose migrations are executed, built with the `MigrationApi`
+	/// (the `try-runtime` feature of the node runtime).
+	#[structopt(long = "runtime", value_name = "PATH", parse(from_os_str))]
+	pub runtime: PathBuf,
+
+	/// Read the state from a snapshot made by `export-state` instead of the local database.
+	#[structopt(long = "snapshot", value_name = "PATH", parse(from_os_str), conflicts_with = "uri")]
+	pub snapshot: Option<PathBuf>,
+
+	/// Download the state over the http RPC of a node instead of reading the local database.
+	#[structopt(long = "uri", value_name = "URL")]
+	pub uri: Option<String>,
+
+	/// Block hash or number of the state, only a hash is accepted with `--uri`.
+	///
+	/// Default is the last finalized block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER", conflicts_with = "snapshot")]
+	pub at: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl DryRunUpgradeCmd {
+	/// Run the dry-run-upgrade command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		let code = fs::read(&self.runtime)?;
+
+		let state = match (&self.snapshot, &self.uri) {
+			(Some(snapshot), _) => DryRunState::Snapshot(
+				Box::new(io::BufReader::new(fs::File::open(snapshot)?))
+			),
+			(None, Some(uri)) => {
+				let at = match &self.at {
+					Some(at) if at.starts_with("0x") => Some(at.clone()),
+					Some(_) => return Err(error::Error::Input(
+						"Only a block hash can be specified with --uri".into()
+					)),
+					None => None,
+				};
+				let pairs = tokio::runtime::Runtime::new()?.block_on(download_state(uri, at))?;
+				DryRunState::Pairs(pairs)
+			},
+			(None, None) => DryRunState::Local(match &self.at {
+				Some(at) => {
+					let input = if at.starts_with("0x") { &at[2..] } else { &at[..] };
+					Some(match FromStr::from_str(input) {
+						Ok(hash) => BlockId::hash(hash),
+						Err(_) => match at.parse::<u32>() {
+							Ok(n) => BlockId::number(n.into()),
+							Err(_) => return Err(error::Error::Input("Invalid hash or number specified".into())),
+						}
+					})
+				},
+				None => None,
+			}),
+		};
+
+		run_until_exit(config, |config| {
+			Ok(builder(config)?.dry_run_upgrade(code, state))
+		})
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
+
+/// Download the top-level key-value pairs of the state of block `at`, the last finalized block
+/// by default, from the node at `uri`.
+async fn download_state(uri: &str, at: Option<String>) -> error::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+	let client = hyper::Client::new();
+	let at = match at {
+		Some(at) => at,
+		None => rpc_call(&client, uri, "chain_getFinalizedHead", json!([])).await?,
+	};
+	info!("Downloading the state of block {} from {}", at, uri);
+
+	let mut pairs = Vec::new();
+	let mut start_key: Option<Bytes> = None;
+	loop {
+		let keys: Vec<Bytes> = rpc_call(
+			&client,
+			uri,
+			"state_getKeysPaged",
+			json!(["0x", PAGE_SIZE, start_key, at]),
+		).await?;
+		if keys.is_empty() {
+			break;
+		}
+
+		let mut batch: Value = rpc_call(
+			&client,
+			uri,
+			"state_queryStorageBatchAt",
+			json!([keys, at, false]),
+		).await?;
+		let values: Vec<(Bytes, Option<Bytes>)> = serde_json::from_value(batch["values"].take())
+			.map_err(|e| error::Error::Other(format!("Invalid storage batch: {}", e)))?;
+		pairs.extend(values.into_iter().filter_map(|(key, value)| value.map(|value| (key.0, value.0))));
+
+		if keys.len() < PAGE_SIZE as usize {
+			break;
+		}
+		start_key = keys.last().cloned();
+	}
+
+	info!("Downloaded {} key-value pairs", pairs.len());
+	Ok(pairs)
+}
+
+/// Call `method` over the http RPC of the node at `uri` and decode the result.
+async fn rpc_call<T: serde::de::DeserializeOwned>(
+	client: &hyper::Client<hyper::client::HttpConnector>,
+	uri: &str,
+	method: &str,
+	params: Value,
+) -> error::Result<T> {
+	let rpc_err = |e: String| error::Error::Other(format!("RPC call {} failed: {}", method, e));
+
+	let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+	let request = hyper::Request::post(uri)
+		.header(hyper::header::CONTENT_TYPE, "application/json")
+		.body(hyper::Body::from(body.to_string()))
+		.map_err(|e| rpc_err(e.to_string()))?;
+	let response = client.request(request).await.map_err(|e| rpc_err(e.to_string()))?;
+	let body = hyper::body::to_bytes(response.into_body()).await.map_err(|e| rpc_err(e.to_string()))?;
+
+	let mut response: Value = serde_json::from_slice(&body).map_err(|e| rpc_err(e.to_string()))?;
+	if let Some(error) = response.get("error") {
+		return Err(rpc_err(error.to_string()));
+	}
+	serde_json::from_value(response["result"].take()).map_err(|e| rpc_err(e.to_string()))
+}
@@ -77,8 +77,10 @@ impl ExportBlocksCmd {
 		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
 		<BB as BlockT>::Hash: std::str::FromStr,
 	{
-		if let DatabaseConfig::Path { ref path, .. } = config.expect_database() {
-			info!("DB path: {}", path.display());
+		match config.expect_database() {
+			DatabaseConfig::Path { ref path, .. } | DatabaseConfig::ParityDb { ref path } =>
+				info!("DB path: {}", path.display()),
+			DatabaseConfig::Custom(_) => (),
 		}
 		let from = self.from.as_ref().and_then(|f| f.parse().ok()).unwrap_or(1);
 		let to = self.to.as_ref().and_then(|t| t.parse().ok());
@@ -0,0 +1,116 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::fs;
+use std::path::PathBuf;
+use std::fmt::Debug;
+use std::str::FromStr;
+use log::info;
+use structopt::StructOpt;
+use sc_service::{
+	Configuration, ServiceBuilderCommand, ChainSpec,
+	config::DatabaseConfig, Roles,
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_runtime::generic::BlockId;
+
+use crate::error;
+use crate::VersionInfo;
+use crate::runtime::run_until_exit;
+use crate::params::{SharedParams, PruningParams};
+
+/// The `export-state` command used to export the state of a block to a snapshot.
+#[derive(Debug, StructOpt, Clone)]
+pub struct ExportStateCmd {
+	/// Output file name or stdout if unspecified.
+	#[structopt(parse(from_os_str))]
+	pub output: Option<PathBuf>,
+
+	/// Block hash or number of the exported state.
+	///
+	/// Default is the last finalized block.
+	#[structopt(long = "at", value_name = "HASH or NUMBER")]
+	pub at: Option<String>,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl ExportStateCmd {
+	/// Run the export-state command
+	pub fn run<B, BC, BB>(
+		self,
+		config: Configuration,
+		builder: B,
+	) -> error::Result<()>
+	where
+		B: FnOnce(Configuration) -> Result<BC, sc_service::error::Error>,
+		BC: ServiceBuilderCommand<Block = BB> + Unpin,
+		BB: sp_runtime::traits::Block + Debug,
+		<<<BB as BlockT>::Header as HeaderT>::Number as std::str::FromStr>::Err: std::fmt::Debug,
+		<BB as BlockT>::Hash: std::str::FromStr,
+	{
+		match config.expect_database() {
+			DatabaseConfig::Path { ref path, .. } | DatabaseConfig::ParityDb { ref path } =>
+				info!("DB path: {}", path.display()),
+			DatabaseConfig::Custom(_) => (),
+		}
+		let block_id = match &self.at {
+			Some(at) => {
+				let input = if at.starts_with("0x") { &at[2..] } else { &at[..] };
+				Some(match FromStr::from_str(input) {
+					Ok(hash) => BlockId::hash(hash),
+					Err(_) => match at.parse::<u32>() {
+						Ok(n) => BlockId::number(n.into()),
+						Err(_) => return Err(error::Error::Input("Invalid hash or number specified".into())),
+					}
+				})
+			},
+			None => None,
+		};
+
+		let file: Box<dyn io::Write> = match &self.output {
+			Some(filename) => Box::new(fs::File::create(filename)?),
+			None => Box::new(io::stdout()),
+		};
+
+		run_until_exit(config, |config| {
+			Ok(builder(config)?.export_state(file, block_id))
+		})
+	}
+
+	/// Update and prepare a `Configuration` with command line parameters
+	pub fn update_config<F>(
+		&self,
+		mut config: &mut Configuration,
+		spec_factory: F,
+		version: &VersionInfo,
+	) -> error::Result<()> where
+		F: FnOnce(&str) -> Result<Box<dyn ChainSpec>, String>,
+	{
+		self.shared_params.update_config(&mut config, spec_factory, version)?;
+		self.pruning_params.update_config(&mut config, Roles::FULL, true)?;
+		config.use_in_memory_keystore()?;
+
+		Ok(())
+	}
+}
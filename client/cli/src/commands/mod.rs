@@ -21,6 +21,11 @@ mod import_blocks_cmd;
 mod check_block_cmd;
 mod revert_cmd;
 mod purge_chain_cmd;
+mod export_state_cmd;
+mod import_state_cmd;
+mod dry_run_upgrade_cmd;
+mod check_block_determinism_cmd;
+mod db_cmd;
 
 use std::fmt::Debug;
 use structopt::StructOpt;
@@ -39,6 +44,11 @@ pub use crate::commands::import_blocks_cmd::ImportBlocksCmd;
 pub use crate::commands::check_block_cmd::CheckBlockCmd;
 pub use crate::commands::revert_cmd::RevertCmd;
 pub use crate::commands::purge_chain_cmd::PurgeChainCmd;
+pub use crate::commands::export_state_cmd::ExportStateCmd;
+pub use crate::commands::import_state_cmd::ImportStateCmd;
+pub use crate::commands::dry_run_upgrade_cmd::DryRunUpgradeCmd;
+pub use crate::commands::check_block_determinism_cmd::CheckBlockDeterminismCmd;
+pub use crate::commands::db_cmd::{DbCmd, DbMigrateCmd, DbPruneBodiesCmd};
 
 /// default sub directory to store network config
 const DEFAULT_NETWORK_CONFIG_PATH : &'static str = "network";
@@ -67,6 +77,21 @@ pub enum Subcommand {
 
 	/// Remove the whole chain data.
 	PurgeChain(purge_chain_cmd::PurgeChainCmd),
+
+	/// Export the state of a block to a snapshot file.
+	ExportState(export_state_cmd::ExportStateCmd),
+
+	/// Bootstrap an empty database from a state snapshot file.
+	ImportState(import_state_cmd::ImportStateCmd),
+
+	/// Execute the migrations of a new runtime against an existing state without committing them.
+	DryRunUpgrade(dry_run_upgrade_cmd::DryRunUpgradeCmd),
+
+	/// Execute blocks natively and in wasm and report the blocks whose executions diverge.
+	CheckBlockDeterminism(check_block_determinism_cmd::CheckBlockDeterminismCmd),
+
+	/// Manage the database of the client.
+	Db(db_cmd::DbCmd),
 }
 
 impl Subcommand {
@@ -81,6 +106,11 @@ impl Subcommand {
 			CheckBlock(params) => &params.shared_params,
 			Revert(params) => &params.shared_params,
 			PurgeChain(params) => &params.shared_params,
+			ExportState(params) => &params.shared_params,
+			ImportState(params) => &params.shared_params,
+			DryRunUpgrade(params) => &params.shared_params,
+			CheckBlockDeterminism(params) => &params.shared_params,
+			Db(cmd) => cmd.shared_params(),
 		}
 	}
 
@@ -104,6 +134,11 @@ impl Subcommand {
 			Subcommand::CheckBlock(cmd) => cmd.run(config, builder),
 			Subcommand::PurgeChain(cmd) => cmd.run(config),
 			Subcommand::Revert(cmd) => cmd.run(config, builder),
+			Subcommand::ExportState(cmd) => cmd.run(config, builder),
+			Subcommand::ImportState(cmd) => cmd.run(config, builder),
+			Subcommand::DryRunUpgrade(cmd) => cmd.run(config, builder),
+			Subcommand::CheckBlockDeterminism(cmd) => cmd.run(config, builder),
+			Subcommand::Db(cmd) => cmd.run(config, builder),
 		}
 	}
 
@@ -123,6 +158,11 @@ impl Subcommand {
 			Subcommand::CheckBlock(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::PurgeChain(cmd) => cmd.update_config(&mut config, spec_factory, version),
 			Subcommand::Revert(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ExportState(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::ImportState(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::DryRunUpgrade(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::CheckBlockDeterminism(cmd) => cmd.update_config(&mut config, spec_factory, version),
+			Subcommand::Db(cmd) => cmd.update_config(&mut config, spec_factory, version),
 		}
 	}
 
@@ -14,9 +14,12 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::net::SocketAddr;
 use std::fs;
+use std::convert::TryFrom;
+use std::time::Duration;
 use log::info;
 use structopt::{StructOpt, clap::arg_enum};
 use names::{Generator, Name};
@@ -24,11 +27,16 @@ use regex::Regex;
 use chrono::prelude::*;
 use sc_service::{
 	AbstractService, Configuration, ChainSpec, Roles,
-	config::{KeystoreConfig, PrometheusConfig},
+	config::{
+		GrpcTlsConfig, KeystoreConfig, OffchainWorkerLimits, PrometheusConfig, RpcBatchLimits,
+		RpcSubscriptionLimits, SignerEndpoint, TelemetryAuth, KEY_PAIR_TYPES, TelemetryEndpointAuth,
+		TelemetrySigningKey,
+	},
 };
+use sp_core::crypto::KeyTypeId;
 use sc_telemetry::TelemetryEndpoints;
 
-use crate::VersionInfo;
+use crate::{RpcMethods, RpcSubscriptionOverflow, VersionInfo};
 use crate::error;
 use crate::params::ImportParams;
 use crate::params::SharedParams;
@@ -42,6 +50,9 @@ const NODE_NAME_MAX_LENGTH: usize = 32;
 /// default sub directory for the key store
 const DEFAULT_KEYSTORE_CONFIG_PATH : &'static str = "keystore";
 
+/// The default file name of the offchain HTTP client key, in the keystore directory.
+const DEFAULT_OFFCHAIN_HTTP_CLIENT_KEY: &str = "offchain-http-client.key";
+
 arg_enum! {
 	/// Whether off-chain workers are enabled.
 	#[allow(missing_docs)]
@@ -86,6 +97,11 @@ pub struct RunCmd {
 	#[structopt(long = "no-grandpa")]
 	pub no_grandpa: bool,
 
+	/// Restart the GRANDPA voter, with a fresh catch-up request to the peer furthest ahead, when
+	/// no block is finalized for 32 rounds while the best block is ahead of the finalized one.
+	#[structopt(long = "grandpa-restart-on-stall", conflicts_with = "no-grandpa")]
+	pub grandpa_restart_on_stall: bool,
+
 	/// Experimental: Run in light client mode.
 	#[structopt(long = "light", conflicts_with = "sentry")]
 	pub light: bool,
@@ -118,6 +134,19 @@ pub struct RunCmd {
 	#[structopt(long = "unsafe-ws-external")]
 	pub unsafe_ws_external: bool,
 
+	/// Listen to all gRPC interfaces.
+	///
+	/// Default is local. Use `--unsafe-grpc-external` to suppress the warning if you understand the
+	/// risks.
+	#[structopt(long = "grpc-external")]
+	pub grpc_external: bool,
+
+	/// Listen to all gRPC interfaces.
+	///
+	/// Same as `--grpc-external` but doesn't warn you about it.
+	#[structopt(long = "unsafe-grpc-external")]
+	pub unsafe_grpc_external: bool,
+
 	/// Listen to all Prometheus data source interfaces.
 	///
 	/// Default is local.
@@ -132,6 +161,44 @@ pub struct RunCmd {
 	#[structopt(long = "ws-port", value_name = "PORT")]
 	pub ws_port: Option<u16>,
 
+	/// Enable the gRPC gateway on the given TCP port.
+	///
+	/// Serves the chain, state, author and network privacy APIs over gRPC, subject to the RPC
+	/// access policy.
+	#[structopt(long = "grpc-port", value_name = "PORT")]
+	pub grpc_port: Option<u16>,
+
+	/// PEM file of the certificate chain of the gRPC gateway, which then only accepts TLS
+	/// connections.
+	#[structopt(
+		long = "grpc-tls-cert",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "grpc-tls-key"
+	)]
+	pub grpc_tls_cert: Option<PathBuf>,
+
+	/// PEM file of the private key of the gRPC gateway certificate.
+	#[structopt(
+		long = "grpc-tls-key",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "grpc-tls-cert"
+	)]
+	pub grpc_tls_key: Option<PathBuf>,
+
+	/// PEM file of the CA the client certificates of the gRPC gateway must be signed with.
+	///
+	/// Client certificates are then required, and their SHA-256 fingerprints are matched by the
+	/// certificate scopes of the RPC access policy.
+	#[structopt(
+		long = "grpc-tls-client-ca",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "grpc-tls-cert"
+	)]
+	pub grpc_tls_client_ca: Option<PathBuf>,
+
 	/// Maximum number of WS RPC server connections.
 	#[structopt(long = "ws-max-connections", value_name = "COUNT")]
 	pub ws_max_connections: Option<usize>,
@@ -145,6 +212,64 @@ pub struct RunCmd {
 	#[structopt(long = "rpc-cors", value_name = "ORIGINS", parse(try_from_str = parse_cors))]
 	pub rpc_cors: Option<Cors>,
 
+	/// Restrict the RPC methods to the ones allowed by the access policy in the given TOML file.
+	///
+	/// The policy lists the methods allowed to every client, and the methods allowed to API keys
+	/// and to the TLS client certificates authenticated by the gRPC gateway. It is reloaded when
+	/// the file is modified.
+	#[structopt(long = "rpc-access-policy", value_name = "PATH", parse(from_os_str))]
+	pub rpc_access_policy: Option<PathBuf>,
+
+	/// RPC methods to expose.
+	///
+	/// `Unsafe` exposes every method, `Safe` only the methods that are safe to expose publicly,
+	/// and `Auto` exposes the unsafe methods only on the servers listening on localhost, e.g.
+	/// not with `--rpc-external`.
+	#[structopt(
+		long = "rpc-methods",
+		value_name = "METHOD SET",
+		possible_values = &RpcMethods::variants(),
+		case_insensitive = true,
+		default_value = "Auto"
+	)]
+	pub rpc_methods: RpcMethods,
+
+	/// Number of notifications buffered per RPC subscription while its connection is busy.
+	#[structopt(long = "rpc-subscription-buffer", value_name = "COUNT", default_value = "256")]
+	pub rpc_subscription_buffer: usize,
+
+	/// What to do with the notifications of an RPC subscription when its buffer is full.
+	#[structopt(
+		long = "rpc-subscription-overflow",
+		value_name = "POLICY",
+		possible_values = &RpcSubscriptionOverflow::variants(),
+		case_insensitive = true,
+		default_value = "DropOldest"
+	)]
+	pub rpc_subscription_overflow: RpcSubscriptionOverflow,
+
+	/// Maximum number of RPC subscriptions per WebSocket connection.
+	#[structopt(long = "rpc-max-subscriptions-per-connection", value_name = "COUNT", default_value = "128")]
+	pub rpc_max_subscriptions_per_connection: usize,
+
+	/// Maximum number of calls of a JSON-RPC batch. The calls beyond the limit fail.
+	#[structopt(long = "rpc-max-batch-size", value_name = "COUNT", default_value = "256")]
+	pub rpc_max_batch_size: usize,
+
+	/// Maximum total weight of the calls of a JSON-RPC batch, where each method weighs 1 unless
+	/// configured with `--rpc-method-weight`. The calls beyond the limit fail.
+	#[structopt(long = "rpc-max-batch-weight", value_name = "WEIGHT", default_value = "1024")]
+	pub rpc_max_batch_weight: u64,
+
+	/// Weight of the RPC methods matching a pattern in JSON-RPC batches, e.g.
+	/// `state_queryStorage=64` or `state_*=2`.
+	#[structopt(
+		long = "rpc-method-weight",
+		value_name = "METHOD=WEIGHT",
+		parse(try_from_str = parse_rpc_method_weight)
+	)]
+	pub rpc_method_weights: Vec<(String, u64)>,
+
 	/// Specify Prometheus data source server TCP Port.
 	#[structopt(long = "prometheus-port", value_name = "PORT")]
 	pub prometheus_port: Option<u16>,
@@ -176,6 +301,32 @@ pub struct RunCmd {
 	#[structopt(long = "telemetry-url", value_name = "URL VERBOSITY", parse(try_from_str = parse_telemetry_endpoints))]
 	pub telemetry_endpoints: Vec<(String, u8)>,
 
+	/// File that contains the bearer token authenticating the node to a telemetry endpoint, can
+	/// be repeated.
+	///
+	/// The token is sent in the first message after connecting to the endpoint, which must be a
+	/// `wss://` endpoint. The URL must be the URL of the endpoint in the chain spec or
+	/// `--telemetry-url`.
+	#[structopt(
+		long = "telemetry-token-file",
+		value_name = "URL PATH",
+		parse(try_from_str = parse_telemetry_token_file)
+	)]
+	pub telemetry_token_files: Vec<(String, PathBuf)>,
+
+	/// Sign the telemetry payloads sent to the endpoint, can be repeated.
+	#[structopt(long = "telemetry-sign", value_name = "URL")]
+	pub telemetry_sign: Vec<String>,
+
+	/// The key telemetry payloads are signed with: `node` for the node key (the default), or a
+	/// key type, e.g. `babe`, for the first sr25519 key of that type in the keystore.
+	#[structopt(
+		long = "telemetry-signing-key",
+		value_name = "KEY",
+		parse(try_from_str = parse_telemetry_signing_key)
+	)]
+	pub telemetry_signing_key: Option<TelemetrySigningKey>,
+
 	/// Should execute offchain workers on every block.
 	///
 	/// By default it's only enabled for nodes that are authoring new blocks.
@@ -188,6 +339,60 @@ pub struct RunCmd {
 	)]
 	pub offchain_worker: OffchainWorkerEnabled,
 
+	/// PEM file of the certificate chain offchain workers present to HTTP servers requesting a
+	/// client certificate (mutual TLS).
+	#[structopt(long = "offchain-http-client-cert", value_name = "PATH", parse(from_os_str))]
+	pub offchain_http_client_cert: Option<PathBuf>,
+
+	/// PEM file of the private key of the offchain HTTP client certificate.
+	///
+	/// Defaults to `offchain-http-client.key` in the keystore directory.
+	#[structopt(
+		long = "offchain-http-client-key",
+		value_name = "PATH",
+		parse(from_os_str),
+		requires = "offchain-http-client-cert"
+	)]
+	pub offchain_http_client_key: Option<PathBuf>,
+
+	/// PEM file of a CA certificate the HTTP servers called by offchain workers must be signed
+	/// with, can be repeated. The CAs trusted by the system are used if none is given.
+	#[structopt(long = "offchain-http-ca", value_name = "PATH", parse(from_os_str))]
+	pub offchain_http_ca: Vec<PathBuf>,
+
+	/// The number of runs of an offchain worker, for different blocks, that can be in progress
+	/// at once.
+	///
+	/// Only applies to runtimes having their offchain workers started separately.
+	#[structopt(long = "offchain-worker-concurrency", value_name = "COUNT", default_value = "1")]
+	pub offchain_worker_concurrency: usize,
+
+	/// Seconds after which an offchain worker run is aborted, at its next call to the node.
+	///
+	/// Only applies to runtimes having their offchain workers started separately.
+	#[structopt(long = "offchain-worker-deadline", value_name = "SECONDS")]
+	pub offchain_worker_deadline: Option<u64>,
+
+	/// The concurrency and deadline of the given offchain worker, overriding
+	/// `--offchain-worker-concurrency` and `--offchain-worker-deadline`, e.g. `ImOnline=2:30`.
+	/// Can be repeated.
+	#[structopt(
+		long = "offchain-worker-limit",
+		value_name = "WORKER=COUNT[:SECONDS]",
+		parse(try_from_str = parse_offchain_worker_limit)
+	)]
+	pub offchain_worker_limits: Vec<(String, OffchainWorkerLimits)>,
+
+	/// URL of the HTTP gateway of the IPFS node offchain workers fetch content from, e.g.
+	/// `http://127.0.0.1:8080`.
+	#[structopt(long = "offchain-ipfs-gateway", value_name = "URL")]
+	pub offchain_ipfs_gateway: Option<String>,
+
+	/// URL of the HTTP API of the IPFS node offchain workers pin content on, e.g.
+	/// `http://127.0.0.1:5001`.
+	#[structopt(long = "offchain-ipfs-api", value_name = "URL")]
+	pub offchain_ipfs_api: Option<String>,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub shared_params: SharedParams,
@@ -267,11 +472,32 @@ pub struct RunCmd {
 	)]
 	pub password_filename: Option<PathBuf>,
 
+	/// Route the keys of a key type to a remote signer, e.g. a HSM or a signer daemon, instead of
+	/// the keystore, can be repeated.
+	///
+	/// The endpoint is a local socket, `tcp:HOST:PORT` or `unix:PATH`. The keys of a routed key
+	/// type never live on the node host: they are listed and the messages signed by the runtime
+	/// are signed by the signer. The key types of consensus engines signing with key pairs
+	/// (`babe`, `gran`, `aura` and `audi`) can't be routed.
+	#[structopt(
+		long = "remote-signer",
+		value_name = "KEY_TYPE=ENDPOINT",
+		parse(try_from_str = parse_remote_signer)
+	)]
+	pub remote_signers: Vec<(KeyTypeId, SignerEndpoint)>,
+
 	/// The size of the instances cache for each runtime.
 	///
 	/// The default value is 8 and the values higher than 256 are ignored.
 	#[structopt(long = "max-runtime-instances", default_value = "8")]
 	pub max_runtime_instances: usize,
+
+	/// The number of runtime versions kept compiled in the runtime cache.
+	///
+	/// A larger cache speeds up calls switching between runtime versions, e.g. `state_call` at
+	/// historical blocks. The default value is 2 and the values higher than 64 are ignored.
+	#[structopt(long = "runtime-cache-size", default_value = "2")]
+	pub runtime_cache_size: usize,
 }
 
 impl RunCmd {
@@ -321,10 +547,21 @@ impl RunCmd {
 			config.in_chain_config_dir(DEFAULT_KEYSTORE_CONFIG_PATH)
 		);
 
+		let path = path.ok_or_else(|| "No `base_path` provided to create keystore path!".to_string())?;
+
+		config.offchain_http_tls.client_certificate = self.offchain_http_client_cert.clone()
+			.map(|certificate| {
+				let key = self.offchain_http_client_key.clone()
+					.unwrap_or_else(|| path.join(DEFAULT_OFFCHAIN_HTTP_CLIENT_KEY));
+				(certificate, key)
+			});
+		config.offchain_http_tls.ca_certificates = self.offchain_http_ca.clone();
+
 		config.keystore = KeystoreConfig::Path {
-			path: path.ok_or_else(|| "No `base_path` provided to create keystore path!".to_string())?,
+			path,
 			password,
 		};
+		config.remote_signers = self.remote_signers.clone();
 
 		let keyring = self.get_keyring();
 		let is_dev = self.shared_params.dev;
@@ -365,9 +602,17 @@ impl RunCmd {
 			(OffchainWorkerEnabled::Never, _) => false,
 			(OffchainWorkerEnabled::WhenValidating, _) => false,
 		};
+		config.offchain_scheduling.default_limits = OffchainWorkerLimits {
+			concurrency: self.offchain_worker_concurrency,
+			deadline: self.offchain_worker_deadline.map(Duration::from_secs),
+		};
+		config.offchain_scheduling.worker_limits = self.offchain_worker_limits.iter().cloned().collect();
+		config.offchain_ipfs.gateway = self.offchain_ipfs_gateway.clone();
+		config.offchain_ipfs.api = self.offchain_ipfs_api.clone();
 
 		config.roles = role;
 		config.disable_grandpa = self.no_grandpa;
+		config.grandpa_restart_on_stall = self.grandpa_restart_on_stall;
 
 		let client_id = config.client_id();
 		let network_path = config
@@ -399,6 +644,18 @@ impl RunCmd {
 			let ws_interface: &str = interface_str(self.ws_external, self.unsafe_ws_external, self.validator)?;
 			config.rpc_ws = Some(parse_address(&format!("{}:{}", ws_interface, 9944), self.ws_port)?);
 		}
+		if let Some(grpc_port) = self.grpc_port {
+			let grpc_interface: &str = interface_str(self.grpc_external, self.unsafe_grpc_external, self.validator)?;
+			config.grpc = Some(parse_address(&format!("{}:{}", grpc_interface, grpc_port), None)?);
+		}
+		config.grpc_tls = match (&self.grpc_tls_cert, &self.grpc_tls_key) {
+			(Some(certificate), Some(key)) => Some(GrpcTlsConfig {
+				certificate: certificate.clone(),
+				key: key.clone(),
+				client_ca: self.grpc_tls_client_ca.clone(),
+			}),
+			_ => None,
+		};
 
 		config.rpc_ws_max_connections = self.ws_max_connections;
 		config.rpc_cors = self.rpc_cors.clone().unwrap_or_else(|| if is_dev {
@@ -413,6 +670,18 @@ impl RunCmd {
 				"https://polkadot.js.org".into(),
 			])
 		}).into();
+		config.rpc_access_policy = self.rpc_access_policy.clone();
+		config.rpc_methods = self.rpc_methods.into();
+		config.rpc_subscription_limits = RpcSubscriptionLimits {
+			buffer: self.rpc_subscription_buffer,
+			overflow: self.rpc_subscription_overflow.into(),
+			max_per_connection: self.rpc_max_subscriptions_per_connection,
+		};
+		config.rpc_batch_limits = RpcBatchLimits {
+			max_size: self.rpc_max_batch_size,
+			max_weight: self.rpc_max_batch_weight,
+			weights: self.rpc_method_weights.clone(),
+		};
 
 		// Override telemetry
 		if self.no_telemetry {
@@ -423,6 +692,19 @@ impl RunCmd {
 			);
 		}
 
+		let mut telemetry_auth = HashMap::<_, TelemetryEndpointAuth>::new();
+		for (url, file) in &self.telemetry_token_files {
+			let token = fs::read_to_string(file)
+				.map_err(|e| format!("Failed to read the telemetry token of {}: {}", url, e))?;
+			telemetry_auth.entry(url.clone()).or_default().bearer_token = Some(token.trim().to_owned());
+		}
+		for url in &self.telemetry_sign {
+			telemetry_auth.entry(url.clone()).or_default().sign = true;
+		}
+		config.telemetry_auth = TelemetryAuth::new(telemetry_auth.into_iter().collect())
+			.map_err(|e| e.to_string())?;
+		config.telemetry_signing_key = self.telemetry_signing_key.unwrap_or_default();
+
 		// Override prometheus
 		if self.no_prometheus {
 			config.prometheus_config = None;
@@ -440,6 +722,7 @@ impl RunCmd {
 		config.force_authoring = self.shared_params.dev || self.force_authoring;
 
 		config.max_runtime_instances = self.max_runtime_instances.min(256);
+		config.runtime_cache_size = self.runtime_cache_size.min(64);
 
 		Ok(())
 	}
@@ -580,6 +863,72 @@ fn parse_telemetry_endpoints(s: &str) -> Result<(String, u8), Box<dyn std::error
 	}
 }
 
+/// Parse the file of the bearer token of a telemetry endpoint, formatted as `URL PATH`.
+fn parse_telemetry_token_file(s: &str) -> Result<(String, PathBuf), String> {
+	let mut parts = s.splitn(2, ' ');
+	match (parts.next(), parts.next()) {
+		(Some(url), Some(path)) if !url.is_empty() && !path.is_empty() =>
+			Ok((url.to_owned(), path.into())),
+		_ => Err(format!("Telemetry token file {} must be formatted as URL PATH", s)),
+	}
+}
+
+/// Parse the key telemetry payloads are signed with, `node` or a key type.
+fn parse_telemetry_signing_key(s: &str) -> Result<TelemetrySigningKey, String> {
+	if s == "node" {
+		return Ok(TelemetrySigningKey::NodeKey);
+	}
+
+	KeyTypeId::try_from(s)
+		.map(TelemetrySigningKey::SessionKey)
+		.map_err(|_| format!("Invalid signing key {}, expected node or a key type of 4 characters", s))
+}
+
+/// Parse the weight of the RPC methods matching a pattern, formatted as `METHOD=WEIGHT`.
+fn parse_rpc_method_weight(s: &str) -> Result<(String, u64), String> {
+	let mut parts = s.splitn(2, '=');
+	match (parts.next(), parts.next().map(str::parse)) {
+		(Some(method), Some(Ok(weight))) if !method.is_empty() => Ok((method.to_owned(), weight)),
+		_ => Err(format!("RPC method weight {} must be formatted as METHOD=WEIGHT", s)),
+	}
+}
+
+/// Parse a key type routed to a remote signer, formatted as `KEY_TYPE=ENDPOINT`.
+fn parse_remote_signer(s: &str) -> Result<(KeyTypeId, SignerEndpoint), String> {
+	let mut parts = s.splitn(2, '=');
+	let name = parts.next().unwrap_or_default();
+	let key_type = KeyTypeId::try_from(name)
+		.map_err(|_| format!("Invalid key type {}, expected 4 characters", name))?;
+	if KEY_PAIR_TYPES.contains(&key_type) {
+		return Err(format!(
+			"Keys of type {} are used as key pairs by a consensus engine and can't be held by a remote signer",
+			name,
+		));
+	}
+	let endpoint = parts.next()
+		.ok_or_else(|| format!("Remote signer {} must be formatted as KEY_TYPE=ENDPOINT", s))?
+		.parse()?;
+
+	Ok((key_type, endpoint))
+}
+
+/// Parse the limits of an offchain worker, formatted as `WORKER=COUNT[:SECONDS]`.
+fn parse_offchain_worker_limit(s: &str) -> Result<(String, OffchainWorkerLimits), String> {
+	let mut parts = s.splitn(2, '=');
+	let worker = parts.next().unwrap_or_default().to_owned();
+	let limits = parts.next()
+		.ok_or_else(|| format!("Offchain worker limit {} must be formatted as WORKER=COUNT[:SECONDS]", s))?;
+	let mut limits = limits.splitn(2, ':');
+	let concurrency = limits.next().unwrap_or_default().parse()
+		.map_err(|e| format!("Invalid offchain worker concurrency in {}: {}", s, e))?;
+	let deadline = limits.next()
+		.map(|deadline| deadline.parse().map(Duration::from_secs))
+		.transpose()
+		.map_err(|e| format!("Invalid offchain worker deadline in {}: {}", s, e))?;
+
+	Ok((worker, OffchainWorkerLimits { concurrency, deadline }))
+}
+
 /// CORS setting
 ///
 /// The type is introduced to overcome `Option<Option<T>>`
@@ -649,6 +998,42 @@ mod tests {
 		assert!(is_node_name_valid("email@domain").is_err());
 	}
 
+	#[test]
+	fn remote_signers_are_parsed() {
+		assert_eq!(
+			parse_remote_signer("imon=tcp:127.0.0.1:9955"),
+			Ok((KeyTypeId(*b"imon"), SignerEndpoint::Tcp("127.0.0.1:9955".into()))),
+		);
+		assert!(parse_remote_signer("imon").is_err());
+		assert!(parse_remote_signer("imonline=tcp:127.0.0.1:9955").is_err());
+		assert!(parse_remote_signer("imon=127.0.0.1:9955").is_err());
+		assert!(parse_remote_signer("gran=tcp:127.0.0.1:9955").is_err());
+		assert!(parse_remote_signer("babe=unix:/run/signer.sock").is_err());
+	}
+
+	#[test]
+	fn telemetry_auth_is_parsed() {
+		assert_eq!(
+			parse_telemetry_token_file("wss://telemetry.example.com/submit/ /etc/telemetry-token"),
+			Ok(("wss://telemetry.example.com/submit/".into(), "/etc/telemetry-token".into())),
+		);
+		assert!(parse_telemetry_token_file("wss://telemetry.example.com/submit/").is_err());
+		assert_eq!(parse_telemetry_signing_key("node"), Ok(TelemetrySigningKey::NodeKey));
+		assert_eq!(
+			parse_telemetry_signing_key("babe"),
+			Ok(TelemetrySigningKey::SessionKey(KeyTypeId(*b"babe"))),
+		);
+		assert!(parse_telemetry_signing_key("session").is_err());
+	}
+
+	#[test]
+	fn rpc_method_weights_are_parsed() {
+		assert_eq!(parse_rpc_method_weight("state_*=2"), Ok(("state_*".into(), 2)));
+		assert!(parse_rpc_method_weight("state_*").is_err());
+		assert!(parse_rpc_method_weight("=2").is_err());
+		assert!(parse_rpc_method_weight("state_*=heavy").is_err());
+	}
+
 	#[test]
 	fn keystore_path_is_generated_correctly() {
 		let chain_spec = GenericChainSpec::from_genesis(
@@ -682,6 +1067,54 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn offchain_http_client_key_defaults_to_keystore() {
+		let chain_spec = GenericChainSpec::from_genesis(
+			"test",
+			"test-id",
+			|| (),
+			Vec::new(),
+			None,
+			None,
+			None,
+			None::<()>,
+		);
+
+		let cli = RunCmd::from_iter(vec![
+			"node", "--offchain-http-client-cert", "/certs/client.pem", "--offchain-http-ca", "/certs/ca.pem",
+		]);
+
+		let mut config = Configuration::default();
+		config.config_dir = Some(PathBuf::from("/test/path"));
+		cli.update_config(&mut config, move |_| Ok(Box::new(chain_spec)), TEST_VERSION_INFO).unwrap();
+
+		assert_eq!(
+			config.offchain_http_tls.client_certificate,
+			Some((
+				PathBuf::from("/certs/client.pem"),
+				PathBuf::from("/test/path/chains/test-id/keystore/offchain-http-client.key"),
+			)),
+		);
+		assert_eq!(config.offchain_http_tls.ca_certificates, vec![PathBuf::from("/certs/ca.pem")]);
+	}
+
+	#[test]
+	fn offchain_worker_limits_are_parsed() {
+		assert_eq!(
+			parse_offchain_worker_limit("ImOnline=2:30"),
+			Ok(("ImOnline".into(), OffchainWorkerLimits {
+				concurrency: 2,
+				deadline: Some(Duration::from_secs(30)),
+			})),
+		);
+		assert_eq!(
+			parse_offchain_worker_limit("ImOnline=3"),
+			Ok(("ImOnline".into(), OffchainWorkerLimits { concurrency: 3, deadline: None })),
+		);
+		assert!(parse_offchain_worker_limit("ImOnline").is_err());
+		assert!(parse_offchain_worker_limit("ImOnline=2:soon").is_err());
+	}
+
 	#[test]
 	fn ensure_load_spec_provide_defaults() {
 		let chain_spec = GenericChainSpec::from_genesis(
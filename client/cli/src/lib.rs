@@ -139,7 +139,7 @@ where
 /// 1. Set the panic handler
 /// 2. Raise the FD limit
 /// 3. Initialize the logger
-pub fn init(logger_pattern: &str, version: &VersionInfo) -> error::Result<()> {
+pub fn init(logger_pattern: &str, log_format: LogFormat, version: &VersionInfo) -> error::Result<()> {
 	let full_version = sc_service::config::full_version_from_strs(
 		version.version,
 		version.commit
@@ -147,13 +147,13 @@ pub fn init(logger_pattern: &str, version: &VersionInfo) -> error::Result<()> {
 	sp_panic_handler::set(version.support_url, &full_version);
 
 	fdlimit::raise_fd_limit();
-	init_logger(logger_pattern);
+	init_logger(logger_pattern, log_format);
 
 	Ok(())
 }
 
 /// Initialize the logger
-pub fn init_logger(pattern: &str) {
+pub fn init_logger(pattern: &str, format: LogFormat) {
 	use ansi_term::Colour;
 
 	let mut builder = env_logger::Builder::new();
@@ -171,6 +171,19 @@ pub fn init_logger(pattern: &str) {
 	}
 
 	builder.parse_filters(pattern);
+
+	if format == LogFormat::Json {
+		builder.format(|buf, record| {
+			let timestamp = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+			writeln!(buf, "{}", json_record(record, &timestamp, sp_tracing::log_context::current()))
+		});
+
+		if builder.try_init().is_err() {
+			info!("💬 Not registering Substrate logger, as there is already a global logger registered!");
+		}
+		return;
+	}
+
 	let isatty = atty::is(atty::Stream::Stderr);
 	let enable_color = isatty;
 
@@ -215,9 +228,66 @@ pub fn init_logger(pattern: &str) {
 	}
 }
 
+/// Formats the record as a JSON object, with the block and peer of the given context.
+fn json_record(
+	record: &log::Record,
+	timestamp: &str,
+	context: sp_tracing::log_context::LogContext,
+) -> String {
+	let mut json = serde_json::Map::new();
+	json.insert("timestamp".into(), timestamp.into());
+	json.insert("level".into(), record.level().to_string().into());
+	json.insert("target".into(), record.target().into());
+	if let Some(name) = std::thread::current().name() {
+		json.insert("thread".into(), name.into());
+	}
+	json.insert("message".into(), kill_color(&record.args().to_string()).into());
+	if let Some((number, hash)) = context.block {
+		json.insert("block_number".into(), number.into());
+		json.insert("block_hash".into(), hash.into());
+	}
+	if let Some(peer_id) = context.peer_id {
+		json.insert("peer_id".into(), peer_id.into());
+	}
+
+	serde_json::Value::Object(json).to_string()
+}
+
 fn kill_color(s: &str) -> String {
 	lazy_static! {
 		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").expect("Error initializing color regex");
 	}
 	RE.replace_all(s, "").to_string()
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_tracing::log_context::LogContext;
+
+	#[test]
+	fn json_records_carry_the_context() {
+		let context = LogContext {
+			block: Some(("5".into(), "0x01".into())),
+			peer_id: Some("QmPeer".into()),
+		};
+		let json = json_record(
+			&log::Record::builder()
+				.level(log::Level::Warn)
+				.target("sync")
+				.args(format_args!("\x1b[1mBad\x1b[0m block"))
+				.build(),
+			"2020-05-01T00:00:00.000Z",
+			context,
+		);
+
+		let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+		assert_eq!(json["timestamp"], "2020-05-01T00:00:00.000Z");
+		assert_eq!(json["level"], "WARN");
+		assert_eq!(json["target"], "sync");
+		assert_eq!(json["message"], "Bad block");
+		assert_eq!(json["block_number"], "5");
+		assert_eq!(json["block_hash"], "0x01");
+		assert_eq!(json["peer_id"], "QmPeer");
+	}
+}
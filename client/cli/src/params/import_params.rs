@@ -58,10 +58,20 @@ pub struct ImportParams {
 	#[structopt(long = "db-cache", value_name = "MiB", default_value = "128")]
 	pub database_cache_size: u32,
 
-	/// Specify the state cache size.
+	/// Limit the memory the state cache can use.
+	///
+	/// The cached keys and values are accounted for with the memory the allocator actually
+	/// reserves for them, and the least recently used ones are evicted past the limit.
 	#[structopt(long = "state-cache-size", value_name = "Bytes", default_value = "67108864")]
 	pub state_cache_size: usize,
 
+	/// Limit the memory the trie node cache can use.
+	///
+	/// The cache is shared by all the states, so that the trie nodes of hot keys are read from
+	/// the database only once across consecutive blocks. `0` disables the cache.
+	#[structopt(long = "trie-cache-size", value_name = "Bytes", default_value = "33554432")]
+	pub trie_cache_size: usize,
+
 	/// Comma separated list of targets for tracing.
 	#[structopt(long = "tracing-targets", value_name = "TARGETS")]
 	pub tracing_targets: Option<String>,
@@ -92,6 +102,7 @@ impl ImportParams {
 		}
 
 		config.state_cache_size = self.state_cache_size;
+		config.trie_cache_size = self.trie_cache_size;
 
 		self.pruning_params.update_config(&mut config, role, self.unsafe_pruning)?;
 
@@ -17,12 +17,14 @@
 use std::path::PathBuf;
 use std::iter;
 use std::net::Ipv4Addr;
+use std::str::FromStr;
 use structopt::StructOpt;
 use sc_network::{
-	config::{NonReservedPeerMode, TransportConfig}, multiaddr::Protocol,
+	config::{IpNet, NonReservedPeerMode, ProtocolSlots, TransportConfig}, multiaddr::Protocol,
 };
 use sc_service::Configuration;
 
+use crate::arg_enums::SyncMode;
 use crate::error;
 use crate::params::node_key_params::NodeKeyParams;
 
@@ -90,6 +92,38 @@ pub struct NetworkConfigurationParams {
 	#[structopt(long = "max-parallel-downloads", value_name = "COUNT", default_value = "5")]
 	pub max_parallel_downloads: u32,
 
+	/// Give a protocol its own budget of incoming and outgoing peers.
+	///
+	/// Formatted as `PROTOCOL=IN:OUT`, where `PROTOCOL` is `block-announces`, `transactions` or
+	/// the name of a notifications protocol, e.g. `--protocol-slots transactions=8:8`.
+	#[structopt(long = "protocol-slots", value_name = "PROTOCOL=IN:OUT")]
+	pub protocol_slots: Vec<ProtocolSlotsParam>,
+
+	/// Only accept connections from and dial IP addresses in these ranges.
+	///
+	/// Ranges use the CIDR notation, e.g. `10.0.0.0/8`. By default all addresses are allowed.
+	#[structopt(long = "ip-allow", value_name = "CIDR")]
+	pub ip_allow: Vec<IpNet>,
+
+	/// Never accept connections from nor dial IP addresses in these ranges, even if they are
+	/// allowed by `--ip-allow`.
+	#[structopt(long = "ip-deny", value_name = "CIDR")]
+	pub ip_deny: Vec<IpNet>,
+
+	/// Specify how to sync a node that only has the genesis block.
+	///
+	/// `Warp` proves the finality of a recent block from the genesis authorities, downloads the
+	/// state of that block and syncs on from it, without the history before it. The nodes
+	/// serving it need the state of that block, i.e. `--pruning archive` or a large window.
+	#[structopt(
+		long = "sync",
+		value_name = "MODE",
+		possible_values = &SyncMode::variants(),
+		case_insensitive = true,
+		default_value = "Full"
+	)]
+	pub sync: SyncMode,
+
 	#[allow(missing_docs)]
 	#[structopt(flatten)]
 	pub node_key_params: NodeKeyParams,
@@ -151,7 +185,60 @@ impl NetworkConfigurationParams {
 		};
 
 		config.network.max_parallel_downloads = self.max_parallel_downloads;
+		config.network.sync_mode = self.sync.into();
+
+		config.network.protocol_slots.extend(
+			self.protocol_slots.iter().map(|param| (param.protocol.clone(), param.slots))
+		);
+
+		config.network.ip_policy.allow.extend(self.ip_allow.iter().cloned());
+		config.network.ip_policy.deny.extend(self.ip_deny.iter().cloned());
 
 		Ok(())
 	}
 }
+
+/// Slot budget of a protocol, parsed from `PROTOCOL=IN:OUT`.
+#[derive(Debug, Clone)]
+pub struct ProtocolSlotsParam {
+	/// Name of the protocol.
+	pub protocol: String,
+	/// Budget of the protocol.
+	pub slots: ProtocolSlots,
+}
+
+impl FromStr for ProtocolSlotsParam {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("Invalid protocol slots: {}, expected PROTOCOL=IN:OUT", s);
+
+		let mut parts = s.splitn(2, '=');
+		let protocol = parts.next().filter(|p| !p.is_empty()).ok_or_else(invalid)?;
+		let mut slots = parts.next().ok_or_else(invalid)?.splitn(2, ':');
+		let in_peers = slots.next().and_then(|n| n.parse().ok()).ok_or_else(invalid)?;
+		let out_peers = slots.next().and_then(|n| n.parse().ok()).ok_or_else(invalid)?;
+
+		Ok(ProtocolSlotsParam {
+			protocol: protocol.to_owned(),
+			slots: ProtocolSlots { in_peers, out_peers },
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_protocol_slots() {
+		let param: ProtocolSlotsParam = "/paritytech/grandpa/1=4:8".parse().unwrap();
+		assert_eq!(param.protocol, "/paritytech/grandpa/1");
+		assert_eq!(param.slots, ProtocolSlots { in_peers: 4, out_peers: 8 });
+
+		assert!("transactions".parse::<ProtocolSlotsParam>().is_err());
+		assert!("transactions=4".parse::<ProtocolSlotsParam>().is_err());
+		assert!("=4:8".parse::<ProtocolSlotsParam>().is_err());
+		assert!("transactions=a:8".parse::<ProtocolSlotsParam>().is_err());
+	}
+}
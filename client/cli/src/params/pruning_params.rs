@@ -15,9 +15,11 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use structopt::StructOpt;
-use sc_service::{Configuration, PruningMode};
+use sp_core::Bytes;
+use sc_service::{Configuration, PruningMode, config::BlocksPruning};
 
 use crate::error;
+use crate::arg_enums::KeepJustifications;
 
 /// Parameters to define the pruning mode
 #[derive(Debug, StructOpt, Clone)]
@@ -29,6 +31,30 @@ pub struct PruningParams {
 	/// 256 blocks.
 	#[structopt(long = "pruning", value_name = "PRUNING_MODE")]
 	pub pruning: Option<String>,
+
+	/// Keep the state history of keys starting with the given hex-encoded prefix, even when
+	/// the rest of the state is pruned.
+	///
+	/// May be passed up to 16 times. Has no effect in archive mode.
+	#[structopt(long = "pruning-retain-prefix", value_name = "HEX")]
+	pub pruning_retain_prefix: Vec<Bytes>,
+
+	/// Only keep the bodies of the given number of latest finalized blocks.
+	///
+	/// Older bodies are removed as blocks get finalized, independently of the state pruning.
+	/// Must be at least 1. All bodies are kept by default.
+	#[structopt(long = "keep-bodies", value_name = "COUNT")]
+	pub keep_bodies: Option<u32>,
+
+	/// Specify which justifications of finalized blocks are kept.
+	#[structopt(
+		long = "justifications-pruning",
+		value_name = "POLICY",
+		possible_values = &KeepJustifications::variants(),
+		case_insensitive = true,
+		default_value = "All"
+	)]
+	pub justifications_pruning: KeepJustifications,
 }
 
 impl PruningParams {
@@ -60,6 +86,25 @@ impl PruningParams {
 				)
 			},
 		};
+		if self.pruning_retain_prefix.len() > sc_client_api::MAX_RETAINED_PREFIXES {
+			return Err(error::Error::Input(format!(
+				"At most {} prefixes can be retained with `--pruning-retain-prefix`",
+				sc_client_api::MAX_RETAINED_PREFIXES,
+			)));
+		}
+		config.pruning_retained_prefixes = self.pruning_retain_prefix.iter()
+			.map(|prefix| prefix.0.clone())
+			.collect();
+		if self.keep_bodies == Some(0) {
+			return Err(error::Error::Input(
+				"At least the body of the last finalized block must be kept, `--keep-bodies` must be \
+				at least 1".to_string()
+			));
+		}
+		config.blocks_pruning = BlocksPruning {
+			keep_bodies: self.keep_bodies,
+			justifications: self.justifications_pruning.into(),
+		};
 
 		Ok(())
 	}
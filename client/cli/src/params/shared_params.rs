@@ -23,6 +23,7 @@ use sc_service::{
 
 use crate::VersionInfo;
 use crate::error;
+use crate::arg_enums::{Database, LogFormat};
 
 /// default sub directory to store database
 const DEFAULT_DB_CONFIG_PATH : &'static str = "db";
@@ -48,6 +49,29 @@ pub struct SharedParams {
 	/// By default, all targets log `info`. The global log level can be set with -l<level>.
 	#[structopt(short = "l", long = "log", value_name = "LOG_PATTERN")]
 	pub log: Option<String>,
+
+	/// The format of the log output.
+	///
+	/// `json` outputs one JSON object per record, with the timestamp, level, target and message
+	/// of the record and the block or peer being processed, if any.
+	#[structopt(
+		long = "log-format",
+		value_name = "FORMAT",
+		possible_values = &LogFormat::variants(),
+		case_insensitive = true,
+		default_value = "Human"
+	)]
+	pub log_format: LogFormat,
+
+	/// The database backend of the client.
+	#[structopt(
+		long = "database",
+		value_name = "DB",
+		possible_values = &Database::variants(),
+		case_insensitive = true,
+		default_value = "RocksDb"
+	)]
+	pub database: Database,
 }
 
 impl SharedParams {
@@ -75,11 +99,12 @@ impl SharedParams {
 		}
 
 		if config.database.is_none() {
-			config.database = Some(DatabaseConfig::Path {
-				path: config
-					.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH)
-					.expect("We provided a base_path/config_dir."),
-				cache_size: None,
+			let path = config
+				.in_chain_config_dir(DEFAULT_DB_CONFIG_PATH)
+				.expect("We provided a base_path/config_dir.");
+			config.database = Some(match self.database {
+				Database::RocksDb => DatabaseConfig::Path { path, cache_size: None },
+				Database::ParityDb => DatabaseConfig::ParityDb { path },
 			});
 		}
 
@@ -94,7 +119,7 @@ impl SharedParams {
 	/// 2. Raise the FD limit
 	/// 3. Initialize the logger
 	pub fn init(&self, version: &VersionInfo) -> error::Result<()> {
-		crate::init(self.log.as_ref().map(|v| v.as_ref()).unwrap_or(""), version)
+		crate::init(self.log.as_ref().map(|v| v.as_ref()).unwrap_or(""), self.log_format, version)
 	}
 }
 
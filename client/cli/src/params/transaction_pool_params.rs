@@ -17,6 +17,10 @@
 use structopt::StructOpt;
 use sc_service::Configuration;
 use crate::error;
+use crate::arg_enums::RevalidationStrategy;
+
+/// Name of the file the transaction pool is persisted to.
+const DEFAULT_POOL_PERSISTENCE_PATH : &'static str = "transactions.bin";
 
 /// Parameters used to create the pool configuration.
 #[derive(Debug, StructOpt, Clone)]
@@ -27,6 +31,50 @@ pub struct TransactionPoolParams {
 	/// Maximum number of kilobytes of all transactions stored in the pool.
 	#[structopt(long = "pool-kbytes", value_name = "COUNT", default_value = "20480")]
 	pub pool_kbytes: usize,
+	/// Percentage by which the priority (e.g. the fee) of a transaction must exceed the priority of
+	/// a pending transaction with the same sender and nonce to replace it.
+	#[structopt(long = "pool-replacement-bump", value_name = "PERCENT", default_value = "10")]
+	pub pool_replacement_bump: u32,
+	/// Save the transaction pool on shutdown and restore it at startup.
+	#[structopt(long = "pool-persist")]
+	pub pool_persist: bool,
+	/// Maximum number of operational transactions in the reserved priority lane of the pool.
+	/// `0` disables the lane.
+	#[structopt(long = "pool-priority-limit", value_name = "COUNT", default_value = "1024")]
+	pub pool_priority_limit: usize,
+	/// Maximum number of kilobytes of all transactions stored in the priority lane.
+	#[structopt(long = "pool-priority-kbytes", value_name = "COUNT", default_value = "2048")]
+	pub pool_priority_kbytes: usize,
+	/// Number of invalid delegated transactions after which the submitting peer and the doughnut
+	/// issuer are temporarily banned. `0` disables banning.
+	#[structopt(long = "pool-invalid-delegation-threshold", value_name = "COUNT", default_value = "16")]
+	pub pool_invalid_delegation_threshold: u32,
+	/// Number of seconds a repeat-invalid doughnut issuer or peer stays banned.
+	#[structopt(long = "pool-invalid-delegation-ban-secs", value_name = "SECONDS", default_value = "1800")]
+	pub pool_invalid_delegation_ban_secs: u64,
+	/// Maximum number of unsigned transactions dispatching the same call (e.g. heartbeats)
+	/// admitted to the pool over `--pool-unsigned-window` blocks. Unsigned transactions aren't
+	/// rate limited without it.
+	#[structopt(long = "pool-unsigned-per-window", value_name = "COUNT")]
+	pub pool_unsigned_per_window: Option<u32>,
+	/// Maximum number of unsigned transactions dispatching the same call admitted to the pool
+	/// over the window from the node itself, and from the network. Defaults to
+	/// `--pool-unsigned-per-window`.
+	#[structopt(long = "pool-unsigned-per-source", value_name = "COUNT")]
+	pub pool_unsigned_per_source: Option<u32>,
+	/// Number of blocks the unsigned transaction quotas span.
+	#[structopt(long = "pool-unsigned-window", value_name = "BLOCKS", default_value = "10")]
+	pub pool_unsigned_window: u64,
+	/// The blocks the transactions are periodically revalidated against. Revalidating against the
+	/// finalized blocks saves the work wasted on short lived forks, e.g. during frequent reorgs.
+	#[structopt(
+		long = "revalidation-strategy",
+		value_name = "STRATEGY",
+		possible_values = &RevalidationStrategy::variants(),
+		case_insensitive = true,
+		default_value = "Best"
+	)]
+	pub revalidation_strategy: RevalidationStrategy,
 }
 
 impl TransactionPoolParams {
@@ -44,6 +92,37 @@ impl TransactionPoolParams {
 		config.transaction_pool.future.count = self.pool_limit / factor;
 		config.transaction_pool.future.total_bytes = self.pool_kbytes * 1024 / factor;
 
+		config.transaction_pool.replacement_priority_bump = self.pool_replacement_bump;
+
+		if self.pool_persist {
+			config.transaction_pool_path = config.in_chain_config_dir(DEFAULT_POOL_PERSISTENCE_PATH);
+		}
+
+		// priority lane
+		if self.pool_priority_limit == 0 {
+			config.transaction_pool.priority_lane = None;
+		} else if let Some(lane) = config.transaction_pool.priority_lane.as_mut() {
+			lane.ready.count = self.pool_priority_limit;
+			lane.ready.total_bytes = self.pool_priority_kbytes * 1024;
+			lane.future.count = self.pool_priority_limit / factor;
+			lane.future.total_bytes = self.pool_priority_kbytes * 1024 / factor;
+		}
+
+		// delegated transactions
+		config.transaction_pool.invalid_delegation_threshold = self.pool_invalid_delegation_threshold;
+		config.transaction_pool.invalid_delegation_ban_time =
+			std::time::Duration::from_secs(self.pool_invalid_delegation_ban_secs);
+
+		// unsigned transactions
+		config.transaction_pool.unsigned_limits = self.pool_unsigned_per_window
+			.map(|per_window| sc_service::config::UnsignedLimits {
+				window: self.pool_unsigned_window.max(1),
+				per_window,
+				per_source: self.pool_unsigned_per_source.unwrap_or(per_window),
+			});
+
+		config.transaction_pool.revalidate_at = self.revalidation_strategy.into();
+
 		Ok(())
 	}
 }
@@ -787,6 +787,29 @@ impl<Block: BlockT, C, I, P> BlockImport<Block> for AuraBlockImport<Block, C, I,
 	}
 }
 
+/// Create a verifier for Aura blocks, e.g. for the blocks authored with Aura
+/// before a chain switched to another consensus engine.
+pub fn verifier<B, C, P>(
+	slot_duration: SlotDuration,
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+) -> Result<AuraVerifier<C, P>, sp_consensus::Error> where
+	B: BlockT,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	C: ProvideRuntimeApi<B> + BlockOf + ProvideCache<B>,
+	P: Pair + Send + Sync + 'static,
+	P::Public: Encode + Decode,
+{
+	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
+	initialize_authorities_cache(&*client)?;
+
+	Ok(AuraVerifier {
+		client,
+		inherent_data_providers,
+		phantom: PhantomData,
+	})
+}
+
 /// Start an import queue for the Aura consensus algorithm.
 pub fn import_queue<B, I, C, P>(
 	slot_duration: SlotDuration,
@@ -805,14 +828,7 @@ pub fn import_queue<B, I, C, P>(
 	P::Public: Clone + Eq + Send + Sync + Hash + Debug + Encode + Decode,
 	P::Signature: Encode + Decode,
 {
-	register_aura_inherent_data_provider(&inherent_data_providers, slot_duration.get())?;
-	initialize_authorities_cache(&*client)?;
-
-	let verifier = AuraVerifier {
-		client: client.clone(),
-		inherent_data_providers,
-		phantom: PhantomData,
-	};
+	let verifier = verifier(slot_duration, client, inherent_data_providers)?;
 	Ok(BasicQueue::new(
 		verifier,
 		Box::new(block_import),
@@ -59,7 +59,7 @@
 #![forbid(unsafe_code)]
 #![warn(missing_docs)]
 pub use sp_consensus_babe::{
-	BabeApi, ConsensusLog, BABE_ENGINE_ID, SlotNumber, BabeConfiguration,
+	BabeApi, ConsensusLog, BABE_ENGINE_ID, SlotNumber, BabeConfiguration, EquivocationProof,
 	AuthorityId, AuthorityPair, AuthoritySignature,
 	BabeAuthorityWeight, VRF_OUTPUT_LENGTH,
 	digests::{
@@ -78,7 +78,7 @@ use sp_consensus::import_queue::{
 };
 use sp_runtime::{
 	generic::{BlockId, OpaqueDigestItemId}, Justification,
-	traits::{Block as BlockT, Header, DigestItemFor, Zero},
+	traits::{Block as BlockT, Header, DigestItemFor, Zero, One, SaturatedConversion, UniqueSaturatedInto},
 };
 use sp_api::{ProvideRuntimeApi, NumberFor};
 use sc_keystore::KeyStorePtr;
@@ -119,6 +119,7 @@ use sp_api::ApiExt;
 mod aux_schema;
 mod verification;
 pub mod authorship;
+pub mod migration;
 #[cfg(test)]
 mod tests;
 
@@ -246,7 +247,10 @@ pub static INTERMEDIATE_KEY: &[u8] = b"babe1";
 // and `super::babe::Config` can be eliminated.
 // https://github.com/paritytech/substrate/issues/2434
 #[derive(Clone)]
-pub struct Config(sc_consensus_slots::SlotDuration<BabeConfiguration>);
+pub struct Config {
+	configuration: sc_consensus_slots::SlotDuration<BabeConfiguration>,
+	bootstrap_block: Option<u64>,
+}
 
 impl Config {
 	/// Either fetch the slot duration from disk or compute it from the genesis
@@ -255,8 +259,37 @@ impl Config {
 		C: AuxStore + ProvideRuntimeApi<B>, C::Api: BabeApi<B, Error = sp_blockchain::Error>,
 	{
 		trace!(target: "babe", "Getting slot duration");
-		match sc_consensus_slots::SlotDuration::get_or_compute(client, |a, b| a.configuration(b)).map(Self) {
-			Ok(s) => Ok(s),
+		match sc_consensus_slots::SlotDuration::get_or_compute(client, |a, b| a.configuration(b)) {
+			Ok(configuration) => Ok(Config { configuration, bootstrap_block: None }),
+			Err(s) => {
+				warn!(target: "babe", "Failed to get slot duration");
+				Err(s)
+			}
+		}
+	}
+
+	/// Either fetch the configuration of a chain where BABE took over block
+	/// production at `bootstrap_block` from disk, or compute it from the state
+	/// of the parent of that block.
+	///
+	/// See [`migration`](migration/index.html) for switching a running chain to BABE.
+	pub fn get_or_compute_bootstrapped<B: BlockT, C>(
+		client: &C,
+		bootstrap_block: NumberFor<B>,
+	) -> ClientResult<Self> where
+		C: AuxStore + ProvideRuntimeApi<B>, C::Api: BabeApi<B, Error = sp_blockchain::Error>,
+	{
+		trace!(target: "babe", "Getting slot duration of chain bootstrapped at {:?}", bootstrap_block);
+		if bootstrap_block.is_zero() {
+			return Err(ClientError::Backend("BABE can't take over block production at genesis".into()));
+		}
+
+		let at = BlockId::Number(bootstrap_block - One::one());
+		match sc_consensus_slots::SlotDuration::get_or_compute_at(client, at, |a, b| a.configuration(b)) {
+			Ok(configuration) => Ok(Config {
+				configuration,
+				bootstrap_block: Some(bootstrap_block.saturated_into()),
+			}),
 			Err(s) => {
 				warn!(target: "babe", "Failed to get slot duration");
 				Err(s)
@@ -264,6 +297,25 @@ impl Config {
 		}
 	}
 
+	/// The first block authored with BABE.
+	pub fn bootstrap_block(&self) -> u64 {
+		self.bootstrap_block.unwrap_or(1)
+	}
+
+	/// Whether the given block was authored by the consensus engine BABE took
+	/// over from, or is the genesis block.
+	pub fn is_before_bootstrap<N: UniqueSaturatedInto<u64>>(&self, number: N) -> bool {
+		let number: u64 = number.unique_saturated_into();
+		number < self.bootstrap_block()
+	}
+
+	/// Whether the given block is the parent of the first BABE block, i.e.
+	/// plays the part of the genesis block for BABE.
+	pub fn is_genesis_parent<N: UniqueSaturatedInto<u64>>(&self, number: N) -> bool {
+		let number: u64 = number.unique_saturated_into();
+		number + 1 == self.bootstrap_block()
+	}
+
 	/// Create the genesis epoch (epoch #0). This is defined to start at the slot of
 	/// the first block, so that has to be provided.
 	pub fn genesis_epoch(&self, slot_number: SlotNumber) -> Epoch {
@@ -281,10 +333,44 @@ impl std::ops::Deref for Config {
 	type Target = BabeConfiguration;
 
 	fn deref(&self) -> &BabeConfiguration {
-		&*self.0
+		&*self.configuration
 	}
 }
 
+/// Finds the epoch for a child of the given block, like
+/// `EpochChanges::epoch_descriptor_for_child_of`, treating the parent of the
+/// first BABE block like the genesis block.
+fn epoch_descriptor_for_child_of<Block: BlockT, Client>(
+	epoch_changes: &EpochChangesFor<Block, Epoch>,
+	client: &Client,
+	config: &Config,
+	parent_hash: &Block::Hash,
+	parent_number: NumberFor<Block>,
+	slot_number: SlotNumber,
+) -> Result<
+	Option<ViableEpochDescriptor<Block::Hash, NumberFor<Block>, Epoch>>,
+	fork_tree::Error<ClientError>,
+> where
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError>,
+{
+	if config.is_genesis_parent(parent_number) {
+		// need to insert the genesis epoch.
+		return Ok(Some(ViableEpochDescriptor::UnimportedGenesis(slot_number)))
+	}
+
+	if config.is_before_bootstrap(parent_number) {
+		// blocks of the previous consensus engine don't start any BABE epoch.
+		return Ok(None)
+	}
+
+	epoch_changes.epoch_descriptor_for_child_of(
+		descendent_query(client),
+		parent_hash,
+		parent_number,
+		slot_number,
+	)
+}
+
 /// Parameters for BABE.
 pub struct BabeParams<B: BlockT, C, E, I, SO, SC, CAW> {
 	/// The keystore that manages the keys of the node.
@@ -370,7 +456,7 @@ pub fn start_babe<B, C, SC, E, I, SO, CAW, Error>(BabeParams {
 
 	babe_info!("👶 Starting BABE Authorship worker");
 	Ok(sc_consensus_slots::start_slot_worker(
-		config.0,
+		config.configuration,
 		select_chain,
 		worker,
 		sync_oracle,
@@ -426,8 +512,10 @@ impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for BabeWork
 		parent: &B::Header,
 		slot_number: u64,
 	) -> Result<Self::EpochData, ConsensusError> {
-		self.epoch_changes.lock().epoch_descriptor_for_child_of(
-			descendent_query(&*self.client),
+		epoch_descriptor_for_child_of(
+			&*self.epoch_changes.lock(),
+			&*self.client,
+			&self.config,
 			&parent.hash(),
 			parent.number().clone(),
 			slot_number,
@@ -697,6 +785,48 @@ impl<Block, Client> BabeVerifier<Block, Client>
 	}
 }
 
+impl<Block, Client> BabeVerifier<Block, Client> where
+	Block: BlockT,
+	Client: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+	Client::Api: BabeApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Report the given equivocation through the runtime, which submits an unsigned extrinsic
+	/// with the equivocation proof and the key ownership proof of the offender to the
+	/// transaction pool.
+	fn report_equivocation(
+		&self,
+		equivocation_proof: EquivocationProof<Block::Header>,
+	) -> Result<(), Error<Block>> {
+		let best_id = BlockId::Hash(self.client.info().best_hash);
+		let offender = equivocation_proof.offender.clone();
+
+		let key_owner_proof = match self.client.runtime_api()
+			.generate_key_ownership_proof(
+				&best_id,
+				equivocation_proof.slot_number,
+				offender.clone(),
+			)
+			.map_err(Error::Client)?
+		{
+			Some(proof) => proof,
+			None => {
+				debug!(target: "babe", "Equivocation offender {:?} isn't part of the epoch of the slot", offender);
+				return Ok(());
+			},
+		};
+
+		match self.client.runtime_api()
+			.submit_report_equivocation_unsigned_extrinsic(&best_id, equivocation_proof, key_owner_proof)
+			.map_err(Error::Client)?
+		{
+			Some(()) => info!(target: "babe", "Submitted report of equivocation by {:?}", offender),
+			None => warn!(target: "babe", "Failed to submit report of equivocation by {:?}", offender),
+		}
+
+		Ok(())
+	}
+}
+
 impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 	Block: BlockT,
 	Client: HeaderMetadata<Block, Error = sp_blockchain::Error> + HeaderBackend<Block> + ProvideRuntimeApi<Block>
@@ -736,8 +866,10 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 
 		let pre_digest = find_pre_digest::<Block>(&header)?;
 		let epoch_changes = self.epoch_changes.lock();
-		let epoch_descriptor = epoch_changes.epoch_descriptor_for_child_of(
-			descendent_query(&*self.client),
+		let epoch_descriptor = epoch_descriptor_for_child_of(
+			&*epoch_changes,
+			&*self.client,
+			&self.config,
 			&parent_hash,
 			parent_header_metadata.number,
 			pre_digest.slot_number(),
@@ -784,6 +916,16 @@ impl<Block, Client> Verifier<Block> for BabeVerifier<Block, Client> where
 						equivocation_proof.fst_header().hash(),
 						equivocation_proof.snd_header().hash(),
 					);
+
+					let equivocation_proof = EquivocationProof {
+						offender: author.clone(),
+						slot_number: equivocation_proof.slot(),
+						first_header: equivocation_proof.fst_header().clone(),
+						second_header: equivocation_proof.snd_header().clone(),
+					};
+					if let Err(err) = self.report_equivocation(equivocation_proof) {
+						warn!(target: "babe", "Error reporting equivocation: {}", err);
+					}
 				}
 
 				// if the body is passed through, we need to use the runtime
@@ -920,6 +1062,12 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 			Err(e) => return Err(ConsensusError::ClientImport(e.to_string())),
 		}
 
+		// blocks of the consensus engine BABE took over from don't carry any
+		// BABE data and have been verified by that engine.
+		if self.config.is_before_bootstrap(number) {
+			return self.inner.import_block(block, new_cache).map_err(Into::into);
+		}
+
 		let pre_digest = find_pre_digest::<Block>(&block.header)
 			.expect("valid babe headers must contain a predigest; \
 					 header has been already verified; qed");
@@ -932,10 +1080,14 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 				Error::<Block>::ParentUnavailable(parent_hash, hash)
 			).into()))?;
 
-		let parent_slot = find_pre_digest::<Block>(&parent_header)
-			.map(|d| d.slot_number())
-			.expect("parent is non-genesis; valid BABE headers contain a pre-digest; \
-					header has already been verified; qed");
+		let parent_slot = if self.config.is_genesis_parent(*parent_header.number()) {
+			0
+		} else {
+			find_pre_digest::<Block>(&parent_header)
+				.map(|d| d.slot_number())
+				.expect("parent is non-genesis; valid BABE headers contain a pre-digest; \
+						header has already been verified; qed")
+		};
 
 		// make sure that slot number is strictly increasing
 		if slot_number <= parent_slot {
@@ -954,7 +1106,7 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 		//
 		// also provides the total weight of the chain, including the imported block.
 		let (epoch_descriptor, first_in_epoch, parent_weight) = {
-			let parent_weight = if *parent_header.number() == Zero::zero() {
+			let parent_weight = if self.config.is_genesis_parent(*parent_header.number()) {
 				0
 			} else {
 				aux_schema::load_block_weight(&*self.client, parent_hash)
@@ -1030,6 +1182,7 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 			let prune_and_import = || {
 				prune_finalized(
 					self.client.clone(),
+					&self.config,
 					&mut epoch_changes,
 				)?;
 
@@ -1076,6 +1229,9 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 				// the parent=genesis case is already covered for loading parent weight,
 				// so we don't need to cover again here.
 				parent_weight
+			} else if self.config.is_before_bootstrap(last_best_number) {
+				// blocks authored before BABE took over have no weight.
+				0
 			} else {
 				aux_schema::load_block_weight(&*self.client, last_best)
 					.map_err(|e| ConsensusError::ChainLookup(format!("{:?}", e)))?
@@ -1117,6 +1273,7 @@ impl<Block, Client, Inner> BlockImport<Block> for BabeBlockImport<Block, Client,
 /// Gets the best finalized block and its slot, and prunes the given epoch tree.
 fn prune_finalized<Block, Client>(
 	client: Arc<Client>,
+	config: &Config,
 	epoch_changes: &mut EpochChangesFor<Block, Epoch>,
 ) -> Result<(), ConsensusError> where
 	Block: BlockT,
@@ -1124,6 +1281,12 @@ fn prune_finalized<Block, Client>(
 {
 	let info = client.info();
 
+	// blocks authored before BABE took over block production don't carry a
+	// slot, and there's nothing to prune before the first BABE block.
+	if config.is_before_bootstrap(info.finalized_number) {
+		return Ok(());
+	}
+
 	let finalized_slot = {
 		let finalized_header = client.header(BlockId::Hash(info.finalized_hash))
 			.map_err(|e| ConsensusError::ClientImport(format!("{:?}", e)))?
@@ -1170,6 +1333,7 @@ pub fn block_import<Client, Block: BlockT, I>(
 	// startup rather than waiting until importing the next epoch change block.
 	prune_finalized(
 		client.clone(),
+		&config,
 		&mut epoch_changes.lock(),
 	)?;
 
@@ -0,0 +1,206 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Switching a running chain to BABE.
+//!
+//! A chain launched with another consensus engine (e.g. Aura) can hand over
+//! block production to BABE at a block chosen on chain, see `pallet_aura` and
+//! `pallet_babe`. The runtime then reports the first BABE block through
+//! `BabeApi::bootstrap_block`, and the parent of that block plays the part of
+//! the genesis block for BABE: its state holds the BABE configuration and the
+//! first BABE block starts epoch #0.
+//!
+//! A node of such a chain:
+//!
+//! 1. runs the previous consensus engine until `await_bootstrap` resolves,
+//!    which happens once the hand over is enacted in an imported block, and
+//!    restarts;
+//! 2. on start, finds the first BABE block with `bootstrap_block`, loads the
+//!    configuration with `Config::get_or_compute_bootstrapped` and runs BABE
+//!    with the `import_queue` of this module, which verifies the blocks
+//!    authored before the hand over with the verifier of the previous engine.
+//!
+//! Operators can override the first BABE block reported by the runtime, e.g.
+//! for a chain whose runtime switched to BABE without `pallet_babe` recording
+//! it. The override only applies once its parent is imported, since the state
+//! of that block holds the configuration of BABE.
+
+use super::*;
+use sp_runtime::traits::{AtLeast32Bit, Saturating};
+
+/// Returns the first block authored with BABE, if BABE took over block
+/// production from another consensus engine before the best block.
+///
+/// Returns `None` for chains authored with BABE since genesis, and for chains
+/// that haven't switched to BABE (yet). `bootstrap_override` takes precedence
+/// over the first BABE block reported by the runtime.
+pub fn bootstrap_block<B, C>(
+	client: &C,
+	bootstrap_override: Option<NumberFor<B>>,
+) -> ClientResult<Option<NumberFor<B>>> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+	C::Api: BabeApi<B, Error = ClientError> + ApiExt<B, Error = ClientError>,
+{
+	let info = client.info();
+	let reported = bootstrap_block_at(client, &BlockId::Hash(info.best_hash))?;
+
+	let bootstrap_override = match bootstrap_override {
+		Some(bootstrap_override) => bootstrap_override,
+		None => return Ok(reported),
+	};
+
+	if reported.map_or(false, |reported| reported != bootstrap_override) {
+		warn!(
+			target: "babe",
+			"Overriding the first BABE block {:?} reported by the runtime with {:?}",
+			reported,
+			bootstrap_override,
+		);
+	}
+
+	Ok(Some(bootstrap_override).filter(|bootstrap| is_bootstrap_parent_imported(info.best_number, *bootstrap)))
+}
+
+/// Whether the parent of the first BABE block, which holds the configuration of
+/// BABE, is imported at or before the given block.
+fn is_bootstrap_parent_imported<N: AtLeast32Bit>(number: N, bootstrap: N) -> bool {
+	number.saturating_add(One::one()) >= bootstrap
+}
+
+fn bootstrap_block_at<B, C>(client: &C, at: &BlockId<B>) -> ClientResult<Option<NumberFor<B>>> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: BabeApi<B, Error = ClientError> + ApiExt<B, Error = ClientError>,
+{
+	let runtime_api = client.runtime_api();
+	if !runtime_api.has_api::<dyn BabeApi<B, Error = ClientError>>(at)? {
+		return Ok(None);
+	}
+
+	runtime_api.bootstrap_block(at)
+}
+
+/// Returns a future resolving with the first BABE block once an imported
+/// block enacts the hand over of block production to BABE, or is the parent
+/// of `bootstrap_override`.
+///
+/// Nodes running the previous consensus engine should restart once it
+/// resolves, to author and verify the following blocks with BABE.
+pub fn await_bootstrap<B, C>(
+	client: Arc<C>,
+	bootstrap_override: Option<NumberFor<B>>,
+) -> impl Future<Output = Option<NumberFor<B>>> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + BlockchainEvents<B>,
+	C::Api: BabeApi<B, Error = ClientError> + ApiExt<B, Error = ClientError>,
+{
+	client.import_notification_stream()
+		.filter_map(move |notification| {
+			let bootstrap = match bootstrap_override {
+				Some(bootstrap) => Some(bootstrap)
+					.filter(|bootstrap| is_bootstrap_parent_imported(*notification.header.number(), *bootstrap)),
+				None => bootstrap_block_at(&*client, &BlockId::Hash(notification.hash))
+					.unwrap_or_else(|e| {
+						warn!(target: "babe", "Failed to check for the BABE bootstrap block: {:?}", e);
+						None
+					}),
+			};
+
+			future::ready(bootstrap)
+		})
+		.into_future()
+		.map(|(bootstrap, _)| {
+			if let Some(bootstrap) = bootstrap {
+				info!(target: "babe", "👶 BABE takes over block production at block {:?}", bootstrap);
+			}
+
+			bootstrap
+		})
+}
+
+/// A verifier for chains where BABE took over block production, verifying
+/// the blocks authored before the hand over with `fallback`.
+pub struct MigrationVerifier<Block: BlockT, Client, F> {
+	pub(crate) babe: BabeVerifier<Block, Client>,
+	pub(crate) fallback: F,
+}
+
+impl<Block, Client, F> Verifier<Block> for MigrationVerifier<Block, Client, F> where
+	Block: BlockT,
+	Client: HeaderMetadata<Block, Error = sp_blockchain::Error> + HeaderBackend<Block> + ProvideRuntimeApi<Block>
+	+ Send + Sync + AuxStore + ProvideCache<Block>,
+	Client::Api: BlockBuilderApi<Block, Error = sp_blockchain::Error> + BabeApi<Block, Error = sp_blockchain::Error>,
+	F: Verifier<Block>,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: Block::Header,
+		justification: Option<Justification>,
+		body: Option<Vec<Block::Extrinsic>>,
+	) -> Result<(BlockImportParams<Block, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		if self.babe.config.is_before_bootstrap(*header.number()) {
+			self.fallback.verify(origin, header, justification, body)
+		} else {
+			self.babe.verify(origin, header, justification, body)
+		}
+	}
+}
+
+/// Start an import queue for a chain where BABE took over block production,
+/// verifying the blocks authored before with `fallback`.
+///
+/// The `babe_link` must be created with a `Config` from
+/// `Config::get_or_compute_bootstrapped`. The block import object provided
+/// must be the `BabeBlockImport` or a wrapper of it, which imports the blocks
+/// authored before the hand over without any BABE checks.
+pub fn import_queue<Block: BlockT, Client, Inner, F>(
+	babe_link: BabeLink<Block>,
+	block_import: Inner,
+	fallback: F,
+	justification_import: Option<BoxJustificationImport<Block>>,
+	finality_proof_import: Option<BoxFinalityProofImport<Block>>,
+	client: Arc<Client>,
+	inherent_data_providers: InherentDataProviders,
+) -> ClientResult<BabeImportQueue<Block, sp_api::TransactionFor<Client, Block>>> where
+	Inner: BlockImport<Block, Error = ConsensusError, Transaction = sp_api::TransactionFor<Client, Block>>
+		+ Send + Sync + 'static,
+	Client: ProvideRuntimeApi<Block> + ProvideCache<Block> + Send + Sync + AuxStore + 'static,
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = sp_blockchain::Error>,
+	Client::Api: BlockBuilderApi<Block> + BabeApi<Block> + ApiExt<Block, Error = sp_blockchain::Error>,
+	F: Verifier<Block> + 'static,
+{
+	register_babe_inherent_data_provider(&inherent_data_providers, babe_link.config.slot_duration)?;
+
+	let verifier = MigrationVerifier {
+		babe: BabeVerifier {
+			client: client.clone(),
+			inherent_data_providers,
+			config: babe_link.config,
+			epoch_changes: babe_link.epoch_changes,
+			time_source: babe_link.time_source,
+		},
+		fallback,
+	};
+
+	Ok(BasicQueue::new(
+		verifier,
+		Box::new(block_import),
+		justification_import,
+		finality_proof_import,
+	))
+}
@@ -33,6 +33,7 @@ use sc_network_test::{Block as TestBlock, PeersClient};
 use sc_network::config::{BoxFinalityProofRequestBuilder, ProtocolConfig};
 use sp_runtime::{generic::DigestItem, traits::{Block as BlockT, DigestFor}};
 use sc_client_api::{BlockchainEvents, backend::TransactionFor};
+use substrate_test_runtime_client::ClientBlockImportExt;
 use log::debug;
 use std::{time::Duration, cell::RefCell, task::Poll};
 
@@ -79,9 +80,13 @@ impl Environment<TestBlock> for DummyFactory {
 		-> Self::CreateProposer
 	{
 
-		let parent_slot = crate::find_pre_digest::<TestBlock>(parent_header)
-			.expect("parent header has a pre-digest")
-			.slot_number();
+		let parent_slot = if self.config.is_genesis_parent(*parent_header.number()) {
+			0
+		} else {
+			crate::find_pre_digest::<TestBlock>(parent_header)
+				.expect("parent header has a pre-digest")
+				.slot_number()
+		};
 
 		future::ready(Ok(DummyProposer {
 			factory: self.clone(),
@@ -122,15 +127,20 @@ impl DummyProposer {
 		// figure out if we should add a consensus digest, since the test runtime
 		// doesn't.
 		let epoch_changes = self.factory.epoch_changes.lock();
-		let epoch = epoch_changes.epoch_data_for_child_of(
-			descendent_query(&*self.factory.client),
+		let epoch_descriptor = crate::epoch_descriptor_for_child_of(
+			&*epoch_changes,
+			&*self.factory.client,
+			&self.factory.config,
 			&self.parent_hash,
 			self.parent_number,
 			this_slot,
-			|slot| self.factory.config.genesis_epoch(slot),
 		)
 			.expect("client has data to find epoch")
 			.expect("can compute epoch for baked block");
+		let epoch = epoch_changes.epoch_data(
+			&epoch_descriptor,
+			|slot| self.factory.config.genesis_epoch(slot),
+		).expect("can compute epoch for baked block");
 
 		let first_in_epoch = self.parent_slot < epoch.start_slot;
 		if first_in_epoch {
@@ -171,6 +181,7 @@ impl Proposer<TestBlock> for DummyProposer {
 
 thread_local! {
 	static MUTATOR: RefCell<Mutator> = RefCell::new(Arc::new(|_, _|()));
+	static BOOTSTRAP: RefCell<Option<(u64, Option<Config>)>> = RefCell::new(None);
 }
 
 #[derive(Clone)]
@@ -204,10 +215,34 @@ type TestHeader = <TestBlock as BlockT>::Header;
 type TestExtrinsic = <TestBlock as BlockT>::Extrinsic;
 
 pub struct TestVerifier {
-	inner: BabeVerifier<TestBlock, PeersFullClient>,
+	inner: migration::MigrationVerifier<TestBlock, PeersFullClient, PlainVerifier>,
 	mutator: Mutator,
 }
 
+/// Verifies the blocks authored before BABE took over block production, which
+/// carry no BABE digests, in place of the previous consensus engine.
+pub struct PlainVerifier;
+
+impl Verifier<TestBlock> for PlainVerifier {
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: TestHeader,
+		justification: Option<Justification>,
+		body: Option<Vec<TestExtrinsic>>,
+	) -> Result<(BlockImportParams<TestBlock, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		if header.digest().logs().iter().any(|log| log.as_babe_pre_digest().is_some()) {
+			return Err(format!("BABE block {:?} verified as a block authored before BABE", header.number()));
+		}
+
+		let mut import_block = BlockImportParams::new(origin, header);
+		import_block.body = body;
+		import_block.justification = justification;
+		import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		Ok((import_block, None))
+	}
+}
+
 impl Verifier<TestBlock> for TestVerifier {
 	/// Verify the given data and return the BlockImportParams and an optional
 	/// new set of validators to import. If not, err with an Error-Message
@@ -257,7 +292,24 @@ impl TestNetFactory for BabeTestNet {
 		let client = client.as_full().expect("only full clients are tested");
 		let inherent_data_providers = InherentDataProviders::new();
 
-		let config = Config::get_or_compute(&*client).expect("config available");
+		let config = match BOOTSTRAP.with(|b| b.borrow().clone()) {
+			None => Config::get_or_compute(&*client).expect("config available"),
+			// the first peer authored the blocks before BABE took over, the
+			// others sync them and share its configuration.
+			Some((_, Some(config))) => config,
+			Some((bootstrap, None)) => {
+				let mut importer = client.clone();
+				for _ in 1..bootstrap {
+					let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+					importer.import(BlockOrigin::Own, block).expect("imports block before bootstrap");
+				}
+
+				let config = Config::get_or_compute_bootstrapped(&*client, bootstrap)
+					.expect("config available at the parent of the first BABE block");
+				BOOTSTRAP.with(|b| *b.borrow_mut() = Some((bootstrap, Some(config.clone()))));
+				config
+			},
+		};
 		let (block_import, link) = crate::block_import(
 			config,
 			client.clone(),
@@ -293,12 +345,15 @@ impl TestNetFactory for BabeTestNet {
 		let data = maybe_link.as_ref().expect("babe link always provided to verifier instantiation");
 
 		TestVerifier {
-			inner: BabeVerifier {
-				client: client.clone(),
-				inherent_data_providers: data.inherent_data_providers.clone(),
-				config: data.link.config.clone(),
-				epoch_changes: data.link.epoch_changes.clone(),
-				time_source: data.link.time_source.clone(),
+			inner: migration::MigrationVerifier {
+				babe: BabeVerifier {
+					client: client.clone(),
+					inherent_data_providers: data.inherent_data_providers.clone(),
+					config: data.link.config.clone(),
+					epoch_changes: data.link.epoch_changes.clone(),
+					time_source: data.link.time_source.clone(),
+				},
+				fallback: PlainVerifier,
 			},
 			mutator: MUTATOR.with(|m| m.borrow().clone()),
 		}
@@ -337,12 +392,21 @@ fn rejects_empty_block() {
 
 fn run_one_test(
 	mutator: impl Fn(&mut TestHeader, Stage) + Send + Sync + 'static,
+) {
+	run_one_test_bootstrapped_at(None, mutator)
+}
+
+fn run_one_test_bootstrapped_at(
+	bootstrap: Option<u64>,
+	mutator: impl Fn(&mut TestHeader, Stage) + Send + Sync + 'static,
 ) {
 	let _ = env_logger::try_init();
 	let mutator = Arc::new(mutator) as Mutator;
 
 	MUTATOR.with(|m| *m.borrow_mut() = mutator.clone());
+	BOOTSTRAP.with(|b| *b.borrow_mut() = bootstrap.map(|bootstrap| (bootstrap, None)));
 	let net = BabeTestNet::new(3);
+	let first_babe_block = bootstrap.unwrap_or(1);
 
 	let peers = &[
 		(0, "//Alice"),
@@ -382,7 +446,7 @@ fn run_one_test(
 			// run each future until we get one of our own blocks with number higher than 5
 			// that was produced locally.
 			client.import_notification_stream()
-				.take_while(move |n| future::ready(n.header.number() < &5 || {
+				.take_while(move |n| future::ready(n.header.number() < &(first_babe_block + 4) || {
 					if n.origin == BlockOrigin::Own {
 						got_own = true;
 					} else {
@@ -432,6 +496,28 @@ fn authoring_blocks() {
 	run_one_test(|_, _| ())
 }
 
+#[test]
+fn authoring_blocks_after_bootstrap() {
+	// peers 1 and 2 sync blocks #1 to #4 from peer 0 and verify them with the
+	// fallback verifier, then all of them author blocks with BABE from #5 on.
+	run_one_test_bootstrapped_at(Some(5), |_, _| ())
+}
+
+#[test]
+#[should_panic]
+fn rejects_babe_blocks_before_bootstrap() {
+	// BABE blocks are only authored after the bootstrap block, a peer keeps
+	// verifying blocks with the fallback before.
+	run_one_test_bootstrapped_at(Some(5), |header: &mut TestHeader, stage| {
+		if stage == Stage::PostSeal && *header.number() < 5 {
+			header.digest_mut().push(Item::babe_pre_digest(PreDigest::Secondary(SecondaryPreDigest {
+				authority_index: 0,
+				slot_number: 1,
+			})));
+		}
+	})
+}
+
 #[test]
 #[should_panic]
 fn rejects_missing_inherent_digest() {
@@ -792,3 +878,21 @@ fn verify_slots_are_strictly_increasing() {
 		&mut block_import,
 	);
 }
+
+#[test]
+fn bootstrapped_config_treats_parent_of_first_babe_block_as_genesis() {
+	let mut net = BabeTestNet::new(1);
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let config = data.link.config.clone();
+	assert!(config.is_genesis_parent(0u64));
+	assert!(!config.is_before_bootstrap(1u64));
+
+	let bootstrapped = Config { bootstrap_block: Some(100), ..config };
+	assert!(bootstrapped.is_before_bootstrap(0u64));
+	assert!(bootstrapped.is_before_bootstrap(99u64));
+	assert!(!bootstrapped.is_before_bootstrap(100u64));
+	assert!(!bootstrapped.is_genesis_parent(0u64));
+	assert!(bootstrapped.is_genesis_parent(99u64));
+}
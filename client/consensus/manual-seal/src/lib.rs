@@ -40,10 +40,12 @@ pub mod rpc;
 mod error;
 mod finalize_block;
 mod seal_new_block;
+mod timestamp;
 use finalize_block::{finalize_block, FinalizeBlockParams};
 use seal_new_block::{seal_new_block, SealBlockParams};
 pub use error::Error;
 pub use rpc::{EngineCommand, CreatedBlock};
+pub use timestamp::TimestampInherentDataProvider;
 
 /// The synchronous block-import worker of the engine.
 pub struct ManualSealBlockImport<I> {
@@ -100,7 +102,12 @@ impl<B: BlockT> Verifier<B> for ManualSealVerifier {
 }
 
 /// Instantiate the import queue for the manual seal consensus engine.
-pub fn import_queue<B: BlockT>(block_import: BoxBlockImport<B, ()>) -> BasicQueue<B, ()>
+pub fn import_queue<B, Transaction>(
+	block_import: BoxBlockImport<B, Transaction>,
+) -> BasicQueue<B, Transaction>
+	where
+		B: BlockT,
+		Transaction: Send + 'static,
 {
 	BasicQueue::new(
 		ManualSealVerifier,
@@ -0,0 +1,111 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Timestamp inherent data for blocks sealed on demand.
+//!
+//! `pallet_timestamp` rejects blocks less than its minimum period apart, while blocks sealed on
+//! demand can follow each other immediately. The provider of this module moves the timestamp
+//! forward by the minimum period when the wall clock hasn't, starting from the timestamp of the
+//! best block so that the blocks sealed after a restart still follow it.
+
+use parking_lot::Mutex;
+use sp_inherents::{InherentData, InherentIdentifier, ProvideInherentData};
+use sp_timestamp::{INHERENT_IDENTIFIER, InherentError, InherentType};
+
+/// Provides the wall clock time as timestamp, but at least the minimum period after the
+/// timestamp of the previous block.
+pub struct TimestampInherentDataProvider {
+	minimum_period: InherentType,
+	last: Mutex<InherentType>,
+}
+
+impl TimestampInherentDataProvider {
+	/// Create a new provider for a runtime with the given timestamp minimum period, sealing on
+	/// top of a best block with the given timestamp, or 0 at genesis.
+	pub fn new(minimum_period: InherentType, best_timestamp: InherentType) -> Self {
+		TimestampInherentDataProvider {
+			minimum_period,
+			last: Mutex::new(best_timestamp),
+		}
+	}
+}
+
+impl ProvideInherentData for TimestampInherentDataProvider {
+	fn inherent_identifier(&self) -> &'static InherentIdentifier {
+		&INHERENT_IDENTIFIER
+	}
+
+	fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		use std::time::SystemTime;
+
+		let now = SystemTime::now()
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.map_err(|_| sp_inherents::Error::from("Current time is before unix epoch"))?
+			.as_millis() as InherentType;
+
+		let mut last = self.last.lock();
+		let timestamp = now.max(*last + self.minimum_period);
+		*last = timestamp;
+
+		inherent_data.put_data(INHERENT_IDENTIFIER, &timestamp)
+	}
+
+	fn error_to_string(&self, error: &[u8]) -> Option<String> {
+		InherentError::try_from(&INHERENT_IDENTIFIER, error).map(|e| format!("{:?}", e))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_timestamp::TimestampInherentData;
+
+	#[test]
+	fn timestamps_are_at_least_minimum_period_apart() {
+		let provider = TimestampInherentDataProvider::new(60_000, 0);
+		let mut timestamps = Vec::new();
+
+		for _ in 0..3 {
+			let mut inherent_data = InherentData::new();
+			provider.provide_inherent_data(&mut inherent_data).unwrap();
+			timestamps.push(inherent_data.timestamp_inherent_data().unwrap());
+		}
+
+		assert_eq!(timestamps[1], timestamps[0] + 60_000);
+		assert_eq!(timestamps[2], timestamps[1] + 60_000);
+	}
+
+	#[test]
+	fn timestamps_follow_the_best_block_after_a_restart() {
+		let seal = |provider: &TimestampInherentDataProvider| {
+			let mut inherent_data = InherentData::new();
+			provider.provide_inherent_data(&mut inherent_data).unwrap();
+			inherent_data.timestamp_inherent_data().unwrap()
+		};
+
+		// sealing quickly moves the timestamps ahead of the wall clock
+		let provider = TimestampInherentDataProvider::new(3_600_000, 0);
+		seal(&provider);
+		let best_timestamp = seal(&provider);
+
+		// restarted on top of the best block
+		let provider = TimestampInherentDataProvider::new(3_600_000, best_timestamp);
+		assert_eq!(seal(&provider), best_timestamp + 3_600_000);
+	}
+}
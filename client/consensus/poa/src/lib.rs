@@ -0,0 +1,588 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proof-of-authority consensus for private plug networks.
+//!
+//! The block authors are admitted on chain, e.g. by governance, and returned by `PoaApi::authorities`
+//! at the parent block.
+//!
+//! Time is divided up into slots of a fixed duration and the author of slot `s` is `A[s % |A|]`,
+//! where `A` are the authors in the order returned by the runtime. Authors sign the blocks with
+//! their proof-of-authority key, stored in the keystore under the `poa_` key type, and not with the
+//! key of an account. Blocks are chosen with the longest chain rule.
+
+#![forbid(missing_docs, unsafe_code)]
+
+use std::{sync::Arc, pin::Pin, time::Duration};
+
+use codec::{Encode, Decode};
+use futures::prelude::*;
+use log::{debug, info, trace};
+use parking_lot::Mutex;
+
+use sc_client_api::backend::AuxStore;
+use sc_consensus_slots::{
+	CheckedHeader, SlotWorker, SlotInfo, SlotCompatible, StorageChanges, check_equivocation,
+};
+use sc_keystore::KeyStorePtr;
+use sc_telemetry::{telemetry, CONSENSUS_TRACE, CONSENSUS_DEBUG};
+use sp_api::ProvideRuntimeApi;
+use sp_block_builder::BlockBuilder as BlockBuilderApi;
+use sp_blockchain::{HeaderBackend, Result as CResult, well_known_cache_keys::Id as CacheKeyId};
+use sp_consensus::{
+	self, BlockImport, Environment, Proposer, CanAuthorWith, ForkChoiceStrategy, BlockImportParams,
+	BlockOrigin, Error as ConsensusError, SelectChain, SlotData, SyncOracle,
+};
+use sp_consensus::import_queue::{
+	Verifier, BasicQueue, BoxJustificationImport, BoxFinalityProofImport,
+};
+use sp_core::crypto::Pair;
+use sp_inherents::{InherentDataProviders, InherentData, InherentIdentifier};
+use sp_runtime::{
+	Justification,
+	generic::{BlockId, DigestItem},
+	traits::{Block as BlockT, Header, DigestItemFor, Zero},
+};
+use sp_timestamp::{TimestampInherentData, InherentType as TimestampInherent};
+
+#[cfg(test)]
+mod tests;
+
+pub use sp_consensus_poa::{AuthorityId, AuthorityPair, AuthoritySignature, PoaApi, POA_ENGINE_ID};
+
+/// The slot duration of the proof-of-authority engine, in milliseconds.
+#[derive(Clone, Copy, Debug, Encode, Decode, PartialEq, Eq)]
+pub struct PoaSlotDuration(pub u64);
+
+impl SlotData for PoaSlotDuration {
+	fn slot_duration(&self) -> u64 {
+		self.0
+	}
+
+	const SLOT_KEY: &'static [u8] = b"poa_slot_duration";
+}
+
+/// Slot duration type for the proof-of-authority engine.
+pub type SlotDuration = sc_consensus_slots::SlotDuration<PoaSlotDuration>;
+
+/// Either fetch the slot duration from disk or store the given one on first launch.
+///
+/// The slot duration must be the same on every node of the network.
+pub fn slot_duration<B, C>(client: &C, slot_duration: u64) -> CResult<SlotDuration> where
+	B: BlockT,
+	C: AuxStore + ProvideRuntimeApi<B>,
+{
+	SlotDuration::get_or_compute(client, |_, _| Ok(PoaSlotDuration(slot_duration)))
+}
+
+/// Retrieve the block authors admitted at the given block.
+pub fn authorities<B, C>(client: &C, at: &BlockId<B>) -> Result<Vec<AuthorityId>, ConsensusError> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: PoaApi<B, Error = sp_blockchain::Error>,
+{
+	client.runtime_api()
+		.authorities(at)
+		.map_err(|e| ConsensusError::ChainLookup(e.to_string()))
+}
+
+/// Get slot author for given slot number and authorities.
+fn slot_author(slot_number: u64, authorities: &[AuthorityId]) -> Option<&AuthorityId> {
+	if authorities.is_empty() { return None }
+
+	let idx = slot_number % (authorities.len() as u64);
+	authorities.get(idx as usize)
+}
+
+/// The pre-runtime digest announcing the slot of a block.
+fn pre_digest<B: BlockT>(slot_number: u64) -> DigestItemFor<B> {
+	DigestItem::PreRuntime(POA_ENGINE_ID, slot_number.encode())
+}
+
+/// The seal digest carrying the signature of the author over the pre-sealed header hash.
+fn seal<B: BlockT>(signature: AuthoritySignature) -> DigestItemFor<B> {
+	DigestItem::Seal(POA_ENGINE_ID, signature.encode())
+}
+
+/// Extract the slot number from the pre-runtime digest of the given header.
+fn find_pre_digest<B: BlockT>(header: &B::Header) -> Result<u64, Error<B>> {
+	if header.number().is_zero() {
+		return Ok(0);
+	}
+
+	let mut pre_digest: Option<u64> = None;
+	for log in header.digest().logs() {
+		trace!(target: "poa", "Checking log {:?}", log);
+		let slot = match log.as_pre_runtime() {
+			Some((id, mut data)) if id == POA_ENGINE_ID => u64::decode(&mut data).ok(),
+			_ => None,
+		};
+
+		match (slot, pre_digest.is_some()) {
+			(Some(_), true) => return Err(poa_err(Error::MultipleHeaders)),
+			(None, _) => trace!(target: "poa", "Ignoring digest not meant for us"),
+			(s, false) => pre_digest = s,
+		}
+	}
+	pre_digest.ok_or_else(|| poa_err(Error::NoDigestFound))
+}
+
+/// Computes the slot from the timestamp inherent, there is no slot inherent.
+#[derive(Clone, Copy, Debug)]
+struct PoaSlotCompatible {
+	slot_duration: u64,
+}
+
+impl SlotCompatible for PoaSlotCompatible {
+	fn extract_timestamp_and_slot(
+		&self,
+		data: &InherentData,
+	) -> Result<(TimestampInherent, u64, Duration), sp_consensus::Error> {
+		data.timestamp_inherent_data()
+			.map_err(Into::into)
+			.map_err(sp_consensus::Error::InherentData)
+			.map(|timestamp| (timestamp, timestamp / self.slot_duration, Default::default()))
+	}
+}
+
+/// Register the timestamp inherent data provider, if not registered already.
+fn register_poa_inherent_data_provider(
+	inherent_data_providers: &InherentDataProviders,
+) -> Result<(), sp_consensus::Error> {
+	if !inherent_data_providers.has_provider(&sp_timestamp::INHERENT_IDENTIFIER) {
+		inherent_data_providers
+			.register_provider(sp_timestamp::InherentDataProvider)
+			.map_err(Into::into)
+			.map_err(sp_consensus::Error::InherentData)
+	} else {
+		Ok(())
+	}
+}
+
+/// Start the proof-of-authority worker. The returned future should be run in a futures executor.
+pub fn start_poa<B, C, SC, E, I, SO, CAW, Error>(
+	slot_duration: SlotDuration,
+	client: Arc<C>,
+	select_chain: SC,
+	block_import: I,
+	env: E,
+	sync_oracle: SO,
+	inherent_data_providers: InherentDataProviders,
+	force_authoring: bool,
+	keystore: KeyStorePtr,
+	can_author_with: CAW,
+) -> Result<impl Future<Output = ()>, sp_consensus::Error> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + Send + Sync,
+	C::Api: PoaApi<B, Error = sp_blockchain::Error>,
+	SC: SelectChain<B>,
+	E: Environment<B, Error = Error> + Send + Sync + 'static,
+	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
+	SO: SyncOracle + Send + Sync + Clone,
+	CAW: CanAuthorWith<B> + Send,
+{
+	let worker = PoaWorker {
+		client,
+		block_import: Arc::new(Mutex::new(block_import)),
+		env,
+		keystore,
+		sync_oracle: sync_oracle.clone(),
+		force_authoring,
+	};
+	register_poa_inherent_data_provider(&inherent_data_providers)?;
+
+	let slot_compatible = PoaSlotCompatible { slot_duration: slot_duration.slot_duration() };
+	Ok(sc_consensus_slots::start_slot_worker(
+		slot_duration,
+		select_chain,
+		worker,
+		sync_oracle,
+		inherent_data_providers,
+		slot_compatible,
+		can_author_with,
+	))
+}
+
+struct PoaWorker<C, E, I, SO> {
+	client: Arc<C>,
+	block_import: Arc<Mutex<I>>,
+	env: E,
+	keystore: KeyStorePtr,
+	sync_oracle: SO,
+	force_authoring: bool,
+}
+
+impl<B, C, E, I, Error, SO> sc_consensus_slots::SimpleSlotWorker<B> for PoaWorker<C, E, I, SO> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + Sync,
+	C::Api: PoaApi<B, Error = sp_blockchain::Error>,
+	E: Environment<B, Error = Error>,
+	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Clone,
+	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
+{
+	type BlockImport = I;
+	type SyncOracle = SO;
+	type CreateProposer = Pin<Box<
+		dyn Future<Output = Result<E::Proposer, sp_consensus::Error>> + Send + 'static
+	>>;
+	type Proposer = E::Proposer;
+	type Claim = AuthorityPair;
+	type EpochData = Vec<AuthorityId>;
+
+	fn logging_target(&self) -> &'static str {
+		"poa"
+	}
+
+	fn block_import(&self) -> Arc<Mutex<Self::BlockImport>> {
+		self.block_import.clone()
+	}
+
+	fn epoch_data(
+		&self,
+		header: &B::Header,
+		_slot_number: u64,
+	) -> Result<Self::EpochData, sp_consensus::Error> {
+		authorities(self.client.as_ref(), &BlockId::Hash(header.hash()))
+	}
+
+	fn authorities_len(&self, epoch_data: &Self::EpochData) -> Option<usize> {
+		Some(epoch_data.len())
+	}
+
+	fn claim_slot(
+		&self,
+		_header: &B::Header,
+		slot_number: u64,
+		epoch_data: &Self::EpochData,
+	) -> Option<Self::Claim> {
+		slot_author(slot_number, epoch_data).and_then(|author| {
+			self.keystore.read().key_pair::<AuthorityPair>(author).ok()
+		})
+	}
+
+	fn pre_digest_data(
+		&self,
+		slot_number: u64,
+		_claim: &Self::Claim,
+	) -> Vec<sp_runtime::DigestItem<B::Hash>> {
+		vec![pre_digest::<B>(slot_number)]
+	}
+
+	fn block_import_params(&self) -> Box<dyn Fn(
+		B::Header,
+		&B::Hash,
+		Vec<B::Extrinsic>,
+		StorageChanges<sp_api::TransactionFor<C, B>, B>,
+		Self::Claim,
+		Self::EpochData,
+	) -> sp_consensus::BlockImportParams<B, sp_api::TransactionFor<C, B>> + Send> {
+		Box::new(|header, header_hash, body, storage_changes, pair, _authorities| {
+			// sign the pre-sealed hash of the block and then
+			// add it to a digest item.
+			let signature = pair.sign(header_hash.as_ref());
+
+			let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+			import_block.post_digests.push(seal::<B>(signature));
+			import_block.body = Some(body);
+			import_block.storage_changes = Some(storage_changes);
+			import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+
+			import_block
+		})
+	}
+
+	fn force_authoring(&self) -> bool {
+		self.force_authoring
+	}
+
+	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
+		&mut self.sync_oracle
+	}
+
+	fn proposer(&mut self, block: &B::Header) -> Self::CreateProposer {
+		Box::pin(self.env.init(block).map_err(|e| {
+			sp_consensus::Error::ClientImport(format!("{:?}", e)).into()
+		}))
+	}
+}
+
+impl<B, C, E, I, Error, SO> SlotWorker<B> for PoaWorker<C, E, I, SO> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + Sync + Send,
+	C::Api: PoaApi<B, Error = sp_blockchain::Error>,
+	E: Environment<B, Error = Error> + Send + Sync,
+	E::Proposer: Proposer<B, Error = Error, Transaction = sp_api::TransactionFor<C, B>>,
+	I: BlockImport<B, Transaction = sp_api::TransactionFor<C, B>> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Sync + Clone,
+	Error: std::error::Error + Send + From<sp_consensus::Error> + 'static,
+{
+	type OnSlot = Pin<Box<dyn Future<Output = Result<(), sp_consensus::Error>> + Send>>;
+
+	fn on_slot(&mut self, chain_head: B::Header, slot_info: SlotInfo) -> Self::OnSlot {
+		<Self as sc_consensus_slots::SimpleSlotWorker<B>>::on_slot(self, chain_head, slot_info)
+	}
+}
+
+fn poa_err<B: BlockT>(error: Error<B>) -> Error<B> {
+	debug!(target: "poa", "{}", error);
+	error
+}
+
+#[derive(derive_more::Display, Debug)]
+enum Error<B: BlockT> {
+	#[display(fmt = "Multiple PoA pre-runtime headers")]
+	MultipleHeaders,
+	#[display(fmt = "No PoA pre-runtime digest found")]
+	NoDigestFound,
+	#[display(fmt = "Header {:?} is unsealed", _0)]
+	HeaderUnsealed(B::Hash),
+	#[display(fmt = "Header {:?} has a bad seal", _0)]
+	HeaderBadSeal(B::Hash),
+	#[display(fmt = "No admitted author for slot {}", _0)]
+	SlotAuthorNotFound(u64),
+	#[display(fmt = "Bad signature on {:?}", _0)]
+	BadSignature(B::Hash),
+	#[display(fmt = "Slot number must increase: parent slot: {}, this slot: {}", _0, _1)]
+	SlotNumberMustIncrease(u64, u64),
+	#[display(fmt = "Parent ({}) of {} unavailable. Cannot import", _0, _1)]
+	ParentUnavailable(B::Hash, B::Hash),
+	#[display(fmt = "Could not fetch authorities at {:?}: {:?}", _0, _1)]
+	FetchAuthorities(B::Hash, ConsensusError),
+	#[display(fmt = "Checking inherents failed: {}", _0)]
+	CheckInherents(String),
+	Client(sp_blockchain::Error),
+	DataProvider(String),
+}
+
+impl<B: BlockT> std::convert::From<Error<B>> for String {
+	fn from(error: Error<B>) -> String {
+		error.to_string()
+	}
+}
+
+/// Check a header has been signed by the author of its slot. If the slot is in the future, the
+/// header is deferred. If it's successful, returns the pre-header, the slot number and the author.
+fn check_header<B: BlockT>(
+	slot_now: u64,
+	mut header: B::Header,
+	hash: B::Hash,
+	authorities: &[AuthorityId],
+) -> Result<CheckedHeader<B::Header, (u64, AuthorityId, DigestItemFor<B>)>, Error<B>> {
+	let seal = match header.digest_mut().pop() {
+		Some(x) => x,
+		None => return Err(Error::HeaderUnsealed(hash)),
+	};
+
+	let signature = match seal.as_seal() {
+		Some((id, mut data)) if id == POA_ENGINE_ID => AuthoritySignature::decode(&mut data).ok(),
+		_ => None,
+	}.ok_or_else(|| poa_err(Error::HeaderBadSeal(hash)))?;
+
+	let slot_number = find_pre_digest::<B>(&header)?;
+
+	if slot_number > slot_now {
+		header.digest_mut().push(seal);
+		return Ok(CheckedHeader::Deferred(header, slot_number));
+	}
+
+	let expected_author = slot_author(slot_number, authorities)
+		.ok_or_else(|| poa_err(Error::SlotAuthorNotFound(slot_number)))?;
+
+	let pre_hash = header.hash();
+	if AuthorityPair::verify(&signature, pre_hash.as_ref(), expected_author) {
+		Ok(CheckedHeader::Checked(header, (slot_number, expected_author.clone(), seal)))
+	} else {
+		Err(poa_err(Error::BadSignature(hash)))
+	}
+}
+
+/// A verifier for proof-of-authority blocks.
+pub struct PoaVerifier<C> {
+	client: Arc<C>,
+	slot_compatible: PoaSlotCompatible,
+	inherent_data_providers: InherentDataProviders,
+	slot_inherents: Vec<InherentIdentifier>,
+}
+
+impl<C> PoaVerifier<C> {
+	fn check_inherents<B: BlockT>(
+		&self,
+		block: B,
+		block_id: BlockId<B>,
+		inherent_data: InherentData,
+	) -> Result<(), Error<B>> where
+		C: ProvideRuntimeApi<B>,
+		C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>,
+	{
+		let inherent_res = self.client.runtime_api().check_inherents(
+			&block_id,
+			block,
+			inherent_data,
+		).map_err(Error::Client)?;
+
+		if !inherent_res.ok() {
+			inherent_res
+				.into_errors()
+				.try_for_each(|(i, e)| {
+					Err(Error::CheckInherents(self.inherent_data_providers.error_to_string(&i, &e)))
+				})
+		} else {
+			Ok(())
+		}
+	}
+}
+
+impl<B, C> Verifier<B> for PoaVerifier<C> where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + AuxStore + Send + Sync,
+	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
+		+ PoaApi<B, Error = sp_blockchain::Error>,
+{
+	fn verify(
+		&mut self,
+		origin: BlockOrigin,
+		header: B::Header,
+		justification: Option<Justification>,
+		mut body: Option<Vec<B::Extrinsic>>,
+	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String> {
+		let mut inherent_data = self.inherent_data_providers
+			.create_inherent_data()
+			.map_err(|e| Error::<B>::DataProvider(e.into_string()))?;
+		let (_, slot_now, _) = self.slot_compatible.extract_timestamp_and_slot(&inherent_data)
+			.map_err(|e| format!("Could not extract timestamp and slot: {:?}", e))?;
+
+		let hash = header.hash();
+		let parent_hash = *header.parent_hash();
+		let parent_header = self.client.header(BlockId::Hash(parent_hash))
+			.map_err(Error::<B>::Client)?
+			.ok_or_else(|| poa_err(Error::<B>::ParentUnavailable(parent_hash, hash)))?;
+		let parent_slot = find_pre_digest::<B>(&parent_header)?;
+
+		let authorities = authorities(self.client.as_ref(), &BlockId::Hash(parent_hash))
+			.map_err(|e| Error::<B>::FetchAuthorities(parent_hash, e))?;
+
+		// we add one to allow for some small drift.
+		let checked_header = check_header::<B>(slot_now + 1, header, hash, &authorities)?;
+		match checked_header {
+			CheckedHeader::Checked(pre_header, (slot_number, author, seal)) => {
+				// make sure that slot number is strictly increasing
+				if slot_number <= parent_slot {
+					return Err(poa_err(Error::<B>::SlotNumberMustIncrease(parent_slot, slot_number)).into());
+				}
+
+				if let Some(equivocation_proof) = check_equivocation(
+					&*self.client,
+					slot_now,
+					slot_number,
+					&pre_header,
+					&author,
+				).map_err(Error::<B>::Client)? {
+					info!(
+						target: "poa",
+						"Slot author {:?} is equivocating at slot {} with headers {:?} and {:?}",
+						author,
+						slot_number,
+						equivocation_proof.fst_header().hash(),
+						equivocation_proof.snd_header().hash(),
+					);
+				}
+
+				if let Some(inner_body) = body.take() {
+					let block = B::new(pre_header.clone(), inner_body);
+
+					// the inherents are checked against the slot of the block, not the current one.
+					for identifier in &self.slot_inherents {
+						inherent_data.replace_data(*identifier, &slot_number);
+					}
+
+					self.check_inherents(
+						block.clone(),
+						BlockId::Hash(parent_hash),
+						inherent_data,
+					)?;
+
+					let (_, inner_body) = block.deconstruct();
+					body = Some(inner_body);
+				}
+
+				trace!(target: "poa", "Checked {:?}; importing.", pre_header);
+				telemetry!(CONSENSUS_TRACE; "poa.checked_and_importing"; "pre_header" => ?pre_header);
+
+				let mut import_block = BlockImportParams::new(origin, pre_header);
+				import_block.post_digests.push(seal);
+				import_block.body = body;
+				import_block.justification = justification;
+				import_block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+				import_block.post_hash = Some(hash);
+
+				Ok((import_block, None))
+			}
+			CheckedHeader::Deferred(a, b) => {
+				debug!(target: "poa", "Checking {:?} failed; {:?}, {:?}.", hash, a, b);
+				telemetry!(CONSENSUS_DEBUG; "poa.header_too_far_in_future";
+					"hash" => ?hash, "a" => ?a, "b" => ?b
+				);
+				Err(format!("Header {:?} rejected: too far in the future", hash))
+			}
+		}
+	}
+}
+
+/// The proof-of-authority import queue type.
+pub type PoaImportQueue<B, Transaction> = BasicQueue<B, Transaction>;
+
+/// Start an import queue for the proof-of-authority engine.
+///
+/// The inherents of the runtime checking the slot of a block against its timestamp, e.g. the
+/// inherent of the BABE module of a runtime that also runs BABE, read the slot number from the
+/// inherent data with one of the `slot_inherents` identifiers. The verifier sets it to the slot
+/// of the block it checks.
+pub fn import_queue<B, I, C>(
+	slot_duration: SlotDuration,
+	block_import: I,
+	justification_import: Option<BoxJustificationImport<B>>,
+	finality_proof_import: Option<BoxFinalityProofImport<B>>,
+	client: Arc<C>,
+	inherent_data_providers: InherentDataProviders,
+	slot_inherents: Vec<InherentIdentifier>,
+) -> Result<PoaImportQueue<B, sp_api::TransactionFor<C, B>>, sp_consensus::Error> where
+	B: BlockT,
+	C: 'static + ProvideRuntimeApi<B> + HeaderBackend<B> + AuxStore + Send + Sync,
+	C::Api: BlockBuilderApi<B, Error = sp_blockchain::Error>
+		+ PoaApi<B, Error = sp_blockchain::Error>,
+	I: BlockImport<B, Error = ConsensusError, Transaction = sp_api::TransactionFor<C, B>>
+		+ Send + Sync + 'static,
+{
+	register_poa_inherent_data_provider(&inherent_data_providers)?;
+
+	let verifier = PoaVerifier {
+		client,
+		slot_compatible: PoaSlotCompatible { slot_duration: slot_duration.slot_duration() },
+		inherent_data_providers,
+		slot_inherents,
+	};
+
+	Ok(BasicQueue::new(
+		verifier,
+		Box::new(block_import),
+		justification_import,
+		finality_proof_import,
+	))
+}
@@ -0,0 +1,87 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! PoA testsuite
+
+use super::*;
+use sp_keyring::sr25519::Keyring;
+use sp_runtime::traits::Header as _;
+use substrate_test_runtime_client::runtime::{Block as TestBlock, Header as TestHeader};
+
+fn authorities() -> Vec<AuthorityId> {
+	vec![Keyring::Alice.public().into(), Keyring::Bob.public().into(), Keyring::Charlie.public().into()]
+}
+
+fn sealed_header(slot_number: u64, author: Keyring) -> TestHeader {
+	let mut header = TestHeader::new(
+		1,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Default::default(),
+	);
+	header.digest_mut().push(pre_digest::<TestBlock>(slot_number));
+	let signature = AuthorityPair::from(author.pair()).sign(header.hash().as_ref());
+	header.digest_mut().push(seal::<TestBlock>(signature));
+	header
+}
+
+#[test]
+fn slot_author_rotates_through_authorities() {
+	let authorities = authorities();
+
+	assert_eq!(slot_author(0, &authorities), Some(&authorities[0]));
+	assert_eq!(slot_author(4, &authorities), Some(&authorities[1]));
+	assert_eq!(slot_author(8, &authorities), Some(&authorities[2]));
+	assert_eq!(slot_author(8, &[]), None);
+}
+
+#[test]
+fn check_header_accepts_slot_author_signature() {
+	let header = sealed_header(4, Keyring::Bob);
+	let hash = header.hash();
+
+	match check_header::<TestBlock>(10, header, hash, &authorities()) {
+		Ok(CheckedHeader::Checked(pre_header, (slot_number, author, _))) => {
+			assert_eq!(slot_number, 4);
+			assert_eq!(author, AuthorityId::from(Keyring::Bob.public()));
+			assert_eq!(find_pre_digest::<TestBlock>(&pre_header).unwrap(), 4);
+		},
+		_ => panic!("header should be checked"),
+	}
+}
+
+#[test]
+fn check_header_rejects_other_authors() {
+	let header = sealed_header(4, Keyring::Alice);
+	let hash = header.hash();
+
+	match check_header::<TestBlock>(10, header, hash, &authorities()) {
+		Err(Error::BadSignature(_)) => {},
+		_ => panic!("header should be rejected"),
+	}
+}
+
+#[test]
+fn check_header_defers_future_slots() {
+	let header = sealed_header(11, Keyring::Charlie);
+	let hash = header.hash();
+
+	match check_header::<TestBlock>(10, header, hash, &authorities()) {
+		Ok(CheckedHeader::Deferred(_, 11)) => {},
+		_ => panic!("header should be deferred"),
+	}
+}
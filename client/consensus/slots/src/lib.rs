@@ -450,6 +450,23 @@ impl<T: Clone> SlotDuration<T> {
 		C: ProvideRuntimeApi<B>,
 		CB: FnOnce(ApiRef<C::Api>, &BlockId<B>) -> sp_blockchain::Result<T>,
 		T: SlotData + Encode + Decode + Debug,
+	{
+		use sp_runtime::traits::Zero;
+		Self::get_or_compute_at(client, BlockId::number(Zero::zero()), cb)
+	}
+
+	/// Either fetch the slot duration from disk or compute it from the state
+	/// at the given block, for consensus engines that didn't author the chain
+	/// since genesis.
+	pub fn get_or_compute_at<B: BlockT, C, CB>(
+		client: &C,
+		at: BlockId<B>,
+		cb: CB,
+	) -> sp_blockchain::Result<Self> where
+		C: sc_client_api::backend::AuxStore,
+		C: ProvideRuntimeApi<B>,
+		CB: FnOnce(ApiRef<C::Api>, &BlockId<B>) -> sp_blockchain::Result<T>,
+		T: SlotData + Encode + Decode + Debug,
 	{
 		match client.get_aux(T::SLOT_KEY)? {
 			Some(v) => <T as codec::Decode>::decode(&mut &v[..])
@@ -461,19 +478,18 @@ impl<T: Clone> SlotDuration<T> {
 					})
 				}),
 			None => {
-				use sp_runtime::traits::Zero;
-				let genesis_slot_duration =
-					cb(client.runtime_api(), &BlockId::number(Zero::zero()))?;
+				let slot_duration = cb(client.runtime_api(), &at)?;
 
 				info!(
-					"Loaded block-time = {:?} milliseconds from genesis on first-launch",
-					genesis_slot_duration
+					"Loaded block-time = {:?} milliseconds from {} on first-launch",
+					slot_duration,
+					at,
 				);
 
-				genesis_slot_duration
+				slot_duration
 					.using_encoded(|s| client.insert_aux(&[(T::SLOT_KEY, &s[..])], &[]))?;
 
-				Ok(SlotDuration(genesis_slot_duration))
+				Ok(SlotDuration(slot_duration))
 			}
 		}
 	}
@@ -0,0 +1,103 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads of hot keys through a RocksDB backed trie, with and without the trie node cache.
+
+use std::sync::Arc;
+use criterion::{criterion_group, criterion_main, Criterion};
+use kvdb::{DBTransaction, KeyValueDB};
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use sc_client_db::TrieNodeCache;
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::{Backend, DBValue, Storage, TrieBackend};
+use sp_trie::{PrefixedMemoryDB, TrieMut, trie_types::TrieDBMut};
+use hash_db::Prefix;
+
+/// Number of keys of the state.
+const STATE_KEYS: u32 = 100_000;
+/// Number of hot keys read per iteration.
+const HOT_KEYS: u32 = 16;
+
+struct DbStorage {
+	db: Database,
+	cache: Option<TrieNodeCache>,
+}
+
+impl Storage<BlakeTwo256> for DbStorage {
+	fn get(&self, key: &H256, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let key = sp_trie::prefixed_key::<BlakeTwo256>(key, prefix);
+		let fetch = || self.db.get(0, &key).map_err(|e| format!("Database backend error: {:?}", e));
+		match self.cache {
+			Some(ref cache) => cache.get_or_fetch(&key, fetch),
+			None => fetch(),
+		}
+	}
+}
+
+fn key(i: u32) -> Vec<u8> {
+	sp_core::blake2_256(&i.to_le_bytes()).to_vec()
+}
+
+/// Write a state of `STATE_KEYS` keys to a new database, returning it with the state root.
+fn create_state(path: &std::path::Path) -> (Database, H256) {
+	let mut nodes = PrefixedMemoryDB::<BlakeTwo256>::default();
+	let mut root = H256::default();
+	{
+		let mut trie = TrieDBMut::<BlakeTwo256>::new(&mut nodes, &mut root);
+		for i in 0..STATE_KEYS {
+			trie.insert(&key(i), &[i as u8; 32][..]).expect("in memory trie; qed");
+		}
+	}
+
+	let db = Database::open(&DatabaseConfig::with_columns(1), path.to_str().unwrap()).unwrap();
+	let mut transaction = DBTransaction::new();
+	for (key, (value, rc)) in nodes.drain() {
+		if rc > 0 {
+			transaction.put(0, &key, &value);
+		}
+	}
+	db.write(transaction).unwrap();
+
+	(db, root)
+}
+
+fn bench_hot_keys(c: &mut Criterion) {
+	let dir = tempfile::tempdir().unwrap();
+	let (db, root) = create_state(dir.path());
+	let storage = Arc::new(DbStorage { db, cache: None });
+	let uncached = TrieBackend::new(storage.clone() as Arc<dyn Storage<BlakeTwo256>>, root);
+
+	c.bench_function("hot keys without trie node cache", |b| b.iter(|| {
+		for i in 0..HOT_KEYS {
+			uncached.storage(&key(i)).unwrap().unwrap();
+		}
+	}));
+
+	drop(uncached);
+	let db = Arc::try_unwrap(storage).ok().expect("no other reference to the storage; qed").db;
+	let storage = Arc::new(DbStorage { db, cache: Some(TrieNodeCache::new(32 * 1024 * 1024)) });
+	let cached = TrieBackend::new(storage as Arc<dyn Storage<BlakeTwo256>>, root);
+
+	c.bench_function("hot keys with trie node cache", |b| b.iter(|| {
+		for i in 0..HOT_KEYS {
+			cached.storage(&key(i)).unwrap().unwrap();
+		}
+	}));
+}
+
+criterion_group!(benches, bench_hot_keys);
+criterion_main!(benches);
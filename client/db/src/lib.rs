@@ -28,6 +28,7 @@
 
 pub mod light;
 pub mod offchain;
+pub mod migration;
 
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 pub mod bench;
@@ -38,18 +39,24 @@ mod changes_tries_storage;
 mod storage_cache;
 #[cfg(any(feature = "kvdb-rocksdb", test))]
 mod upgrade;
+#[cfg(feature = "parity-db")]
+mod paritydb;
 mod utils;
 mod stats;
+mod trie_cache;
 
 use std::sync::Arc;
 use std::path::PathBuf;
 use std::io;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use sc_client_api::{
 	ForkBlocks, UsageInfo, MemoryInfo, BadBlocks, IoInfo, MemorySize, CloneableSpawn,
 	execution_extensions::ExecutionExtensions,
-	backend::{NewBlockState, PrunableStateChangesTrieStorage},
+	backend::{
+		NewBlockState, PrunableStateChangesTrieStorage, StateRetention, BlocksPruning, StateEntry,
+		JustificationsPruning, AUTHORITY_SET_CHANGE_AUX_PREFIX, authority_set_change_aux_key,
+	},
 };
 use sp_blockchain::{
 	Result as ClientResult, Error as ClientError,
@@ -59,8 +66,8 @@ use codec::{Decode, Encode};
 use hash_db::Prefix;
 use kvdb::{KeyValueDB, DBTransaction};
 use sp_trie::{MemoryDB, PrefixedMemoryDB, prefixed_key};
-use parking_lot::RwLock;
-use sp_core::{ChangesTrieConfiguration, traits::CodeExecutor};
+use parking_lot::{Mutex, RwLock};
+use sp_core::{ChangesTrieConfiguration, hexdisplay::HexDisplay, traits::CodeExecutor};
 use sp_core::storage::{well_known_keys, ChildInfo};
 use sp_runtime::{
 	generic::BlockId, Justification, Storage,
@@ -84,6 +91,7 @@ use crate::storage_cache::{CachingState, SyncingCachingState, SharedCache, new_s
 use crate::stats::StateUsageStats;
 use log::{trace, debug, warn};
 pub use sc_state_db::PruningMode;
+pub use trie_cache::TrieNodeCache;
 use prometheus_endpoint::Registry;
 
 #[cfg(any(feature = "kvdb-rocksdb", test))]
@@ -264,8 +272,14 @@ pub struct DatabaseSettings {
 	pub state_cache_size: usize,
 	/// Ratio of cache size dedicated to child tries.
 	pub state_cache_child_ratio: Option<(usize, usize)>,
+	/// Size of the trie node cache in bytes. `0` disables the cache.
+	pub trie_cache_size: usize,
+	/// Pruning of block bodies and justifications.
+	pub blocks_pruning: BlocksPruning,
 	/// Pruning mode.
 	pub pruning: PruningMode,
+	/// Key prefixes whose state is retained past pruning.
+	pub state_retention: StateRetention,
 	/// Where to find the database.
 	pub source: DatabaseSettingsSrc,
 }
@@ -280,6 +294,12 @@ pub enum DatabaseSettingsSrc {
 		cache_size: Option<usize>,
 	},
 
+	/// Load a ParityDB database from a given path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
+	},
+
 	/// Use a custom already-open database.
 	Custom(Arc<dyn KeyValueDB>),
 }
@@ -339,6 +359,8 @@ pub(crate) mod columns {
 	/// Offchain workers local storage
 	pub const OFFCHAIN: u32 = 9;
 	pub const CACHE: u32 = 10;
+	/// Trie nodes of retained key prefixes, kept past pruning.
+	pub const RETAINED_STATE: u32 = 11;
 }
 
 struct PendingBlock<Block: BlockT> {
@@ -527,6 +549,7 @@ pub struct BlockImportOperation<Block: BlockT> {
 	db_updates: PrefixedMemoryDB<HashFor<Block>>,
 	storage_updates: StorageCollection,
 	child_storage_updates: ChildStorageCollection,
+	offchain_storage_updates: StorageCollection,
 	changes_trie_updates: MemoryDB<HashFor<Block>>,
 	changes_trie_build_cache_update: Option<ChangesTrieCacheAction<Block::Hash, NumberFor<Block>>>,
 	changes_trie_config_update: Option<Option<ChangesTrieConfiguration>>,
@@ -546,6 +569,16 @@ impl<Block: BlockT> BlockImportOperation<Block> {
 			}
 		}
 	}
+
+	fn apply_offchain(&mut self, transaction: &mut DBTransaction) {
+		for (key, maybe_val) in self.offchain_storage_updates.drain(..) {
+			let key = offchain::concatenate_prefix_and_key(sp_offchain::STORAGE_PREFIX, &key);
+			match maybe_val {
+				Some(val) => transaction.put_vec(columns::OFFCHAIN, &key, val),
+				None => transaction.delete(columns::OFFCHAIN, &key),
+			}
+		}
+	}
 }
 
 impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for BlockImportOperation<Block> {
@@ -650,6 +683,14 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 		Ok(())
 	}
 
+	fn update_offchain_storage(
+		&mut self,
+		offchain_update: StorageCollection,
+	) -> ClientResult<()> {
+		self.offchain_storage_updates = offchain_update;
+		Ok(())
+	}
+
 	fn mark_finalized(
 		&mut self,
 		block: BlockId<Block>,
@@ -669,13 +710,35 @@ impl<Block: BlockT> sc_client_api::backend::BlockImportOperation<Block> for Bloc
 struct StorageDb<Block: BlockT> {
 	pub db: Arc<dyn KeyValueDB>,
 	pub state_db: StateDb<Block::Hash, Vec<u8>>,
+	pub node_cache: TrieNodeCache,
 }
 
 impl<Block: BlockT> sp_state_machine::Storage<HashFor<Block>> for StorageDb<Block> {
 	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
 		let key = prefixed_key::<HashFor<Block>>(key, prefix);
-		self.state_db.get(&key, self)
-			.map_err(|e| format!("Database backend error: {:?}", e))
+		self.node_cache.get_or_fetch(&key, || match self.state_db.get(&key, self) {
+			Ok(None) => self.db.get(columns::RETAINED_STATE, &key)
+				.map_err(|e| format!("Database backend error: {:?}", e)),
+			result => result.map_err(|e| format!("Database backend error: {:?}", e)),
+		})
+	}
+}
+
+/// Storage that records every trie node read through it.
+///
+/// Used to collect the nodes of retained key prefixes before they get pruned.
+struct RecordingStorage<Block: BlockT> {
+	storage: Arc<StorageDb<Block>>,
+	recorded: Mutex<HashMap<Vec<u8>, DBValue>>,
+}
+
+impl<Block: BlockT> sp_state_machine::Storage<HashFor<Block>> for RecordingStorage<Block> {
+	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		let value = sp_state_machine::Storage::get(self.storage.as_ref(), key, prefix)?;
+		if let Some(ref value) = value {
+			self.recorded.lock().insert(prefixed_key::<HashFor<Block>>(key, prefix), value.clone());
+		}
+		Ok(value)
 	}
 }
 
@@ -759,6 +822,13 @@ pub struct Backend<Block: BlockT> {
 	is_archive: bool,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
+	state_retention: StateRetention,
+	/// Trie nodes under retained key prefixes inserted by non-canonical blocks, with the number
+	/// of the block.
+	retained_changes: Mutex<HashMap<Block::Hash, (u64, Vec<(Vec<u8>, Vec<u8>)>)>>,
+	/// Whether the database supports iteration, needed to delete the state of released prefixes.
+	is_iterable: bool,
+	blocks_pruning: BlocksPruning,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -777,7 +847,10 @@ impl<Block: BlockT> Backend<Block> {
 		let db_setting = DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: Default::default(),
 			pruning: PruningMode::keep_blocks(keep_blocks),
+			state_retention: Default::default(),
 			source: DatabaseSettingsSrc::Custom(db),
 		};
 
@@ -789,6 +862,12 @@ impl<Block: BlockT> Backend<Block> {
 		canonicalization_delay: u64,
 		config: &DatabaseSettings,
 	) -> ClientResult<Self> {
+		if config.blocks_pruning.keep_bodies == Some(0) {
+			return Err(sp_blockchain::Error::Backend(
+				"At least the body of the last finalized block must be kept".into(),
+			));
+		}
+
 		let is_archive_pruning = config.pruning.is_archive();
 		let blockchain = BlockchainDb::new(db.clone())?;
 		let meta = blockchain.meta.clone();
@@ -800,6 +879,7 @@ impl<Block: BlockT> Backend<Block> {
 		let storage_db = StorageDb {
 			db: db.clone(),
 			state_db,
+			node_cache: TrieNodeCache::new(config.trie_cache_size),
 		};
 		let offchain_storage = offchain::LocalStorage::new(db.clone());
 		let changes_tries_storage = DbChangesTrieStorage::new(
@@ -831,6 +911,13 @@ impl<Block: BlockT> Backend<Block> {
 			is_archive: is_archive_pruning,
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
+			state_retention: config.state_retention.clone(),
+			retained_changes: Mutex::new(HashMap::new()),
+			is_iterable: match config.source {
+				DatabaseSettingsSrc::ParityDb { .. } => false,
+				_ => true,
+			},
+			blocks_pruning: config.blocks_pruning.clone(),
 		})
 	}
 
@@ -980,6 +1067,7 @@ impl<Block: BlockT> Backend<Block> {
 		header: &Block::Header,
 		last_finalized: Option<Block::Hash>,
 		justification: Option<Justification>,
+		written_set_changes: &HashSet<Vec<u8>>,
 		changes_trie_cache_ops: &mut Option<DbChangesTrieStorageTransaction<Block>>,
 		finalization_displaced: &mut Option<FinalizationDisplaced<Block::Hash, NumberFor<Block>>>,
 	) -> ClientResult<(Block::Hash, <Block::Header as HeaderT>::Number, bool, bool)> {
@@ -996,11 +1084,13 @@ impl<Block: BlockT> Backend<Block> {
 		)?;
 
 		if let Some(justification) = justification {
-			transaction.put(
-				columns::JUSTIFICATION,
-				&utils::number_and_hash_to_lookup_key(number, hash)?,
-				&justification.encode(),
-			);
+			if self.keeps_justification(self.blocks_pruning.justifications, number, written_set_changes)? {
+				transaction.put(
+					columns::JUSTIFICATION,
+					&utils::number_and_hash_to_lookup_key(number, hash)?,
+					&justification.encode(),
+				);
+			}
 		}
 		Ok((*hash, number, false, true))
 	}
@@ -1009,12 +1099,12 @@ impl<Block: BlockT> Backend<Block> {
 	fn force_delayed_canonicalize(
 		&self,
 		transaction: &mut DBTransaction,
+		header: &Block::Header,
 		hash: Block::Hash,
-		number: NumberFor<Block>,
 	)
 		-> ClientResult<()>
 	{
-		let number_u64 = number.saturated_into::<u64>();
+		let number_u64 = header.number().saturated_into::<u64>();
 		if number_u64 > self.canonicalization_delay {
 			let new_canonical = number_u64 - self.canonicalization_delay;
 
@@ -1022,15 +1112,17 @@ impl<Block: BlockT> Backend<Block> {
 				return Ok(())
 			}
 
-			let hash = if new_canonical == number_u64 {
-				hash
+			let (hash, state_root) = if new_canonical == number_u64 {
+				(hash, *header.state_root())
 			} else {
-				::sc_client::blockchain::HeaderBackend::hash(&self.blockchain, new_canonical.saturated_into())?
+				let hash = ::sc_client::blockchain::HeaderBackend::hash(&self.blockchain, new_canonical.saturated_into())?
 					.expect("existence of block with number `new_canonical` \
-						implies existence of blocks with all numbers before it; qed")
+						implies existence of blocks with all numbers before it; qed");
+				(hash, *self.blockchain.expect_header(BlockId::Hash(hash))?.state_root())
 			};
 
 			trace!(target: "db", "Canonicalize block #{} ({:?})", new_canonical, hash);
+			self.retain_state(transaction, hash, new_canonical, state_root)?;
 			let commit = self.storage.state_db.canonicalize_block(&hash)
 				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
 			apply_state_commit(transaction, commit);
@@ -1045,7 +1137,17 @@ impl<Block: BlockT> Backend<Block> {
 		let mut transaction = DBTransaction::new();
 		let mut finalization_displaced_leaves = None;
 
+		// the authority set changes enacted by the blocks of the operation aren't in the
+		// database yet, but decide which justifications are kept.
+		let written_set_changes = operation.aux_ops.iter()
+			.filter(|(key, value)| value.is_some() && key.starts_with(AUTHORITY_SET_CHANGE_AUX_PREFIX))
+			.map(|(key, _)| key.clone())
+			.collect::<HashSet<_>>();
 		operation.apply_aux(&mut transaction);
+		// the offchain index of a fork only gets written if the fork becomes the best chain.
+		if operation.pending_block.as_ref().map_or(false, |block| block.leaf_state.is_best()) {
+			operation.apply_offchain(&mut transaction);
+		}
 
 		let mut meta_updates = Vec::with_capacity(operation.finalized_blocks.len());
 		let mut last_finalized_hash = self.blockchain.meta.read().finalized_hash;
@@ -1061,6 +1163,7 @@ impl<Block: BlockT> Backend<Block> {
 				&block_header,
 				Some(last_finalized_hash),
 				justification,
+				&written_set_changes,
 				&mut changes_trie_cache_ops,
 				&mut finalization_displaced_leaves,
 			)?);
@@ -1099,7 +1202,9 @@ impl<Block: BlockT> Backend<Block> {
 				transaction.put(columns::BODY, &lookup_key, &body.encode());
 			}
 			if let Some(justification) = pending_block.justification {
-				transaction.put(columns::JUSTIFICATION, &lookup_key, &justification.encode());
+				if self.keeps_justification(self.blocks_pruning.justifications, number, &written_set_changes)? {
+					transaction.put(columns::JUSTIFICATION, &lookup_key, &justification.encode());
+				}
 			}
 
 			if number.is_zero() {
@@ -1116,11 +1221,20 @@ impl<Block: BlockT> Backend<Block> {
 				let mut changeset: sc_state_db::ChangeSet<Vec<u8>> = sc_state_db::ChangeSet::default();
 				let mut ops: u64 = 0;
 				let mut bytes: u64 = 0;
+				let retained_prefixes = if self.is_archive {
+					Vec::new()
+				} else {
+					self.state_retention.prefixes()
+				};
+				let mut retained_changes = Vec::new();
 				for (key, (val, rc)) in operation.db_updates.drain() {
 					if rc > 0 {
 						ops += 1;
 						bytes += key.len() as u64 + val.len() as u64;
 
+						if is_retained_node::<Block>(&key, &retained_prefixes) {
+							retained_changes.push((key.clone(), val.to_vec()));
+						}
 						changeset.inserted.push((key, val.to_vec()));
 					} else if rc < 0 {
 						ops += 1;
@@ -1132,6 +1246,9 @@ impl<Block: BlockT> Backend<Block> {
 				self.state_usage.tally_writes(ops, bytes);
 
 				let number_u64 = number.saturated_into::<u64>();
+				if !retained_prefixes.is_empty() {
+					self.retained_changes.lock().insert(hash, (number_u64, retained_changes));
+				}
 				let commit = self.storage.state_db.insert_block(
 					&hash,
 					number_u64,
@@ -1183,7 +1300,7 @@ impl<Block: BlockT> Backend<Block> {
 				)?;
 			} else {
 				// canonicalize blocks which are old enough, regardless of finality.
-				self.force_delayed_canonicalize(&mut transaction, hash, *header.number())?
+				self.force_delayed_canonicalize(&mut transaction, header, hash)?
 			}
 
 			debug!(target: "db", "DB Commit {:?} ({}), best = {}", hash, number, is_best);
@@ -1307,6 +1424,8 @@ impl<Block: BlockT> Backend<Block> {
 			let lookup_key = utils::number_and_hash_to_lookup_key(f_num, f_hash.clone())?;
 			transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
 
+			self.retain_state(transaction, f_hash, f_num.saturated_into(), *f_header.state_root())?;
+
 			let commit = self.storage.state_db.canonicalize_block(&f_hash)
 				.map_err(|e: sc_state_db::Error<io::Error>| sp_blockchain::Error::from(format!("State database error: {:?}", e)))?;
 			apply_state_commit(transaction, commit);
@@ -1324,6 +1443,8 @@ impl<Block: BlockT> Backend<Block> {
 			}
 		}
 
+		self.prune_body(transaction, f_num)?;
+
 		let new_displaced = self.blockchain.leaves.write().finalize_height(f_num);
 		match displaced {
 			x @ &mut None => *x = Some(new_displaced),
@@ -1332,6 +1453,256 @@ impl<Block: BlockT> Backend<Block> {
 
 		Ok(())
 	}
+
+	/// Returns true if the justification of block `number` is kept by the given policy.
+	///
+	/// `written_set_changes` are the authority set change keys written along with the block,
+	/// which aren't in the database yet.
+	fn keeps_justification(
+		&self,
+		justifications: JustificationsPruning,
+		number: NumberFor<Block>,
+		written_set_changes: &HashSet<Vec<u8>>,
+	) -> ClientResult<bool> {
+		match justifications {
+			JustificationsPruning::KeepAll => Ok(true),
+			JustificationsPruning::KeepAuthoritySetChanges => {
+				let key = authority_set_change_aux_key(number);
+				Ok(written_set_changes.contains(&key) ||
+					self.storage.db.get(columns::AUX, &key).map_err(db_err)?.is_some())
+			},
+		}
+	}
+
+	/// Remove the body of the finalized block leaving the window of kept bodies, when
+	/// finalizing block `f_num`.
+	///
+	/// The window holds at least the block being finalized, whose hash may not be committed
+	/// yet.
+	fn prune_body(&self, transaction: &mut DBTransaction, f_num: NumberFor<Block>) -> ClientResult<()> {
+		let keep_bodies = match self.blocks_pruning.keep_bodies {
+			Some(keep_bodies) => NumberFor::<Block>::from(keep_bodies),
+			None => return Ok(()),
+		};
+		if f_num <= keep_bodies {
+			return Ok(());
+		}
+
+		let number = f_num - keep_bodies;
+		if let Some(hash) = self.blockchain.hash(number)? {
+			trace!(target: "db", "Prune body of block #{} ({:?})", number, hash);
+			transaction.delete(columns::BODY, &utils::number_and_hash_to_lookup_key(number, hash)?);
+		}
+		Ok(())
+	}
+
+	/// Copy the trie nodes of all retained key prefixes of the block being canonicalized into
+	/// the retained state column, so that they outlive pruning of the block's state.
+	///
+	/// Only the nodes inserted by the block are copied, unless a prefix was just retained or the
+	/// block was imported before the node started: then the whole state under the prefix is
+	/// walked. The state of released prefixes is deleted. Only the top-level trie is retained;
+	/// child tries are not.
+	fn retain_state(
+		&self,
+		transaction: &mut DBTransaction,
+		hash: Block::Hash,
+		number: u64,
+		root: Block::Hash,
+	) -> ClientResult<()> {
+		if self.is_archive {
+			return Ok(());
+		}
+		let prefixes = self.state_retention.prefixes();
+		let released = self.state_retention.take_released();
+		if !released.is_empty() {
+			self.release_state(transaction, &released, &prefixes);
+		}
+
+		let unsynced = self.state_retention.take_unsynced();
+		let changes = {
+			let mut retained_changes = self.retained_changes.lock();
+			let changes = retained_changes.remove(&hash);
+			if unsynced.is_empty() {
+				retained_changes.retain(|_, (n, _)| *n > number);
+			} else {
+				// changes of other blocks were collected before the prefixes were retained.
+				retained_changes.clear();
+			}
+			changes
+		};
+		if prefixes.is_empty() {
+			return Ok(());
+		}
+
+		let walked = match changes {
+			Some((_, changes)) => {
+				trace!(target: "db", "Retaining {} changed trie nodes of state {:?}", changes.len(), root);
+				for (key, value) in changes {
+					if is_retained_node::<Block>(&key, &prefixes) {
+						transaction.put(columns::RETAINED_STATE, &key, &value);
+					}
+				}
+				unsynced
+			},
+			None => prefixes,
+		};
+		if walked.is_empty() {
+			return Ok(());
+		}
+
+		let recorder = Arc::new(RecordingStorage {
+			storage: self.storage.clone(),
+			recorded: Mutex::new(HashMap::new()),
+		});
+		// The root node is needed to open the trie even if none of the prefixes have any keys.
+		sp_state_machine::Storage::get(&*recorder, &root, hash_db::EMPTY_PREFIX)?;
+		let state = DbState::<Block>::new(
+			recorder.clone() as Arc<dyn sp_state_machine::Storage<HashFor<Block>>>,
+			root,
+		);
+		for prefix in &walked {
+			state.for_keys_with_prefix(prefix, |_| ());
+		}
+
+		let recorded = std::mem::replace(&mut *recorder.recorded.lock(), HashMap::new());
+		trace!(target: "db", "Retaining {} trie nodes of state {:?}", recorded.len(), root);
+		for (key, value) in recorded {
+			transaction.put(columns::RETAINED_STATE, &key, &value);
+		}
+
+		Ok(())
+	}
+
+	/// Delete the retained trie nodes under the `released` key prefixes, except for those still
+	/// needed by the `retained` ones.
+	///
+	/// Nodes above a released prefix are shared with the other prefixes and are kept.
+	fn release_state(&self, transaction: &mut DBTransaction, released: &[Vec<u8>], retained: &[Vec<u8>]) {
+		if !self.is_iterable {
+			warn!(target: "db", "The database can't be iterated, the state of released prefixes is kept");
+			return;
+		}
+		let hash_len = Block::Hash::default().as_ref().len();
+		for prefix in released {
+			let mut deleted = 0;
+			for (key, _) in self.storage.db.iter_with_prefix(columns::RETAINED_STATE, prefix) {
+				let path = &key[..key.len().saturating_sub(hash_len)];
+				if path.len() >= prefix.len() && !retained.iter().any(|p| is_on_retained_path(path, p)) {
+					transaction.delete(columns::RETAINED_STATE, &key);
+					deleted += 1;
+				}
+			}
+			debug!(
+				target: "db",
+				"Deleting {} retained trie nodes of released prefix {}",
+				deleted,
+				sp_core::hexdisplay::HexDisplay::from(prefix),
+			);
+		}
+	}
+
+	/// Whether the state with the given root was retained past pruning.
+	fn is_state_retained(&self, root: &Block::Hash) -> bool {
+		let key = prefixed_key::<HashFor<Block>>(root, hash_db::EMPTY_PREFIX);
+		self.storage.db.get(columns::RETAINED_STATE, &key).unwrap_or(None).is_some()
+	}
+}
+
+/// Whether the trie node with the given prefixed database key is needed to read the keys under
+/// any of the retained prefixes.
+fn is_retained_node<Block: BlockT>(key: &[u8], prefixes: &[Vec<u8>]) -> bool {
+	let path = &key[..key.len().saturating_sub(Block::Hash::default().as_ref().len())];
+	prefixes.iter().any(|prefix| is_on_retained_path(path, prefix))
+}
+
+/// Whether the node at the packed nibble `path` lies above or under the key `prefix`.
+///
+/// A path of an odd number of nibbles ends with a byte holding only the high nibble.
+fn is_on_retained_path(path: &[u8], prefix: &[u8]) -> bool {
+	let common = path.len().min(prefix.len());
+	if path[..common] == prefix[..common] {
+		return true;
+	}
+	match path.split_last() {
+		Some((last, rest)) if last & 0x0f == 0 && rest.len() < prefix.len() && prefix.starts_with(rest) =>
+			prefix[rest.len()] & 0xf0 == *last,
+		_ => false,
+	}
+}
+
+/// Size of the state node batches written while importing a state.
+const STATE_IMPORT_BATCH_BYTES: usize = 16 * 1024 * 1024;
+
+/// Writes the state nodes of an imported state to the database in batches.
+struct StateNodeWriter<'a> {
+	db: &'a dyn KeyValueDB,
+	transaction: DBTransaction,
+	size: usize,
+	result: ClientResult<()>,
+}
+
+impl<'a> StateNodeWriter<'a> {
+	fn new(db: &'a dyn KeyValueDB) -> Self {
+		StateNodeWriter { db, transaction: DBTransaction::new(), size: 0, result: Ok(()) }
+	}
+
+	fn put(&mut self, key: Vec<u8>, node: Vec<u8>) {
+		self.size += key.len() + node.len();
+		self.transaction.put(columns::STATE, &key, &node);
+		if self.size >= STATE_IMPORT_BATCH_BYTES {
+			self.flush();
+		}
+	}
+
+	fn flush(&mut self) {
+		let transaction = std::mem::replace(&mut self.transaction, DBTransaction::new());
+		self.size = 0;
+		if self.result.is_ok() {
+			self.result = self.db.write(transaction).map_err(db_err);
+		}
+	}
+
+	/// Write the remaining nodes and return the first write error.
+	fn finish(mut self) -> ClientResult<()> {
+		self.flush();
+		self.result
+	}
+}
+
+/// Reads the entries of a state given to `import_state` one trie at a time.
+trait StateEntries {
+	/// Take the next entry if it is a top-level one.
+	fn next_if_top(&mut self) -> ClientResult<Option<(Vec<u8>, Vec<u8>)>>;
+	/// Take the next entry if it belongs to the child trie at `storage_key`.
+	fn next_if_child(&mut self, storage_key: &[u8]) -> ClientResult<Option<(Vec<u8>, Vec<u8>)>>;
+}
+
+impl<I: Iterator<Item = ClientResult<StateEntry>>> StateEntries for std::iter::Peekable<I> {
+	fn next_if_top(&mut self) -> ClientResult<Option<(Vec<u8>, Vec<u8>)>> {
+		match self.peek() {
+			Some(Ok(StateEntry::Top(..))) | Some(Err(_)) => match self.next() {
+				Some(Ok(StateEntry::Top(key, value))) => Ok(Some((key, value))),
+				Some(Err(e)) => Err(e),
+				_ => Ok(None),
+			},
+			_ => Ok(None),
+		}
+	}
+
+	fn next_if_child(&mut self, storage_key: &[u8]) -> ClientResult<Option<(Vec<u8>, Vec<u8>)>> {
+		match self.peek() {
+			Some(Ok(StateEntry::Child(key, ..))) if key.as_slice() == storage_key => match self.next() {
+				Some(Ok(StateEntry::Child(_, key, value))) => Ok(Some((key, value))),
+				_ => Ok(None),
+			},
+			Some(Err(_)) => match self.next() {
+				Some(Err(e)) => Err(e),
+				_ => Ok(None),
+			},
+			_ => Ok(None),
+		}
+	}
 }
 
 fn apply_state_commit(transaction: &mut DBTransaction, commit: sc_state_db::CommitSet<Vec<u8>>) {
@@ -1389,6 +1760,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			db_updates: PrefixedMemoryDB::default(),
 			storage_updates: Default::default(),
 			child_storage_updates: Default::default(),
+			offchain_storage_updates: Default::default(),
 			changes_trie_config_update: None,
 			changes_trie_updates: MemoryDB::default(),
 			changes_trie_build_cache_update: None,
@@ -1442,6 +1814,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				&header,
 				None,
 				justification,
+				&HashSet::new(),
 				&mut changes_trie_cache_ops,
 				displaced,
 			)?;
@@ -1471,6 +1844,69 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		Some(self.offchain_storage.clone())
 	}
 
+	fn state_retention(&self) -> Option<StateRetention> {
+		Some(self.state_retention.clone())
+	}
+
+	fn prune_blocks(&self, pruning: &BlocksPruning) -> ClientResult<u64> {
+		/// Number of pruned blocks written at once.
+		const BATCH_SIZE: u64 = 1024;
+
+		use sp_runtime::traits::Saturating;
+
+		if pruning.keep_bodies == Some(0) {
+			return Err(sp_blockchain::Error::Backend(
+				"At least the body of the last finalized block must be kept".into(),
+			));
+		}
+
+		let finalized = self.blockchain.info().finalized_number;
+		let last_body = pruning.keep_bodies
+			.map(|keep_bodies| finalized.saturating_sub(NumberFor::<Block>::from(keep_bodies)));
+
+		let mut pruned = 0;
+		let mut transaction = DBTransaction::new();
+		let mut number = NumberFor::<Block>::one();
+		while number <= finalized {
+			let hash = match self.blockchain.hash(number)? {
+				Some(hash) => hash,
+				None => {
+					number += One::one();
+					continue;
+				},
+			};
+			let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+			let mut pruned_block = false;
+
+			if last_body.map_or(false, |last_body| number <= last_body)
+				&& self.storage.db.get(columns::BODY, &lookup_key).map_err(db_err)?.is_some()
+			{
+				transaction.delete(columns::BODY, &lookup_key);
+				pruned_block = true;
+			}
+
+			if pruning.justifications != JustificationsPruning::KeepAll
+				&& self.storage.db.get(columns::JUSTIFICATION, &lookup_key).map_err(db_err)?.is_some()
+				&& !self.keeps_justification(pruning.justifications, number, &HashSet::new())?
+			{
+				transaction.delete(columns::JUSTIFICATION, &lookup_key);
+				pruned_block = true;
+			}
+
+			if pruned_block {
+				pruned += 1;
+				if pruned % BATCH_SIZE == 0 {
+					self.storage.db.write(std::mem::replace(&mut transaction, DBTransaction::new()))
+						.map_err(db_err)?;
+				}
+			}
+			number += One::one();
+		}
+
+		self.storage.db.write(transaction).map_err(db_err)?;
+		Ok(pruned)
+	}
+
 	fn usage_info(&self) -> Option<UsageInfo> {
 		let (io_stats, state_stats) = self.io_stats.take_or_else(||
 			(
@@ -1479,14 +1915,18 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			)
 		);
 		let database_cache = MemorySize::from_bytes(parity_util_mem::malloc_size(&*self.storage.db));
-		let state_cache = MemorySize::from_bytes(
-			(*&self.shared_cache).lock().used_storage_cache_size(),
-		);
+		let (state_cache, state_cache_evictions) = {
+			let shared_cache = self.shared_cache.lock();
+			(MemorySize::from_bytes(shared_cache.used_storage_cache_size()), shared_cache.evictions())
+		};
 		let state_db = self.storage.state_db.memory_info();
+		let trie_cache = MemorySize::from_bytes(self.storage.node_cache.used_size());
+		let (trie_cache_hits, trie_cache_misses) = self.storage.node_cache.hits_and_misses();
 
 		Some(UsageInfo {
 			memory: MemoryInfo {
 				state_cache,
+				trie_cache,
 				database_cache,
 				state_db,
 			},
@@ -1500,6 +1940,9 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				state_reads: state_stats.reads.ops,
 				state_reads_cache: state_stats.cache_reads.ops,
 				state_writes: state_stats.writes.ops,
+				state_cache_evictions,
+				trie_cache_hits,
+				trie_cache_misses,
 			},
 		})
 	}
@@ -1614,6 +2057,22 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			Ok(Some(ref hdr)) => {
 				let hash = hdr.hash();
 				if !self.have_state_at(&hash, *hdr.number()) {
+					if self.is_state_retained(hdr.state_root()) {
+						// Only the retained key prefixes can be read from this state.
+						let db_state = DbState::<Block>::new(self.storage.clone(), *hdr.state_root());
+						let state = RefTrackingState::new(db_state, self.storage.clone(), None);
+						let caching_state = CachingState::new(
+							state,
+							self.shared_cache.clone(),
+							None,
+						);
+						return Ok(SyncingCachingState::new(
+							caching_state,
+							self.state_usage.clone(),
+							self.blockchain.meta.clone(),
+							self.import_lock.clone(),
+						));
+					}
 					return Err(
 						sp_blockchain::Error::UnknownBlock(
 							format!("State already discarded for {:?}", block)
@@ -1656,6 +2115,182 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		}
 	}
 
+	fn import_state(
+		&self,
+		header: Block::Header,
+		justification: Option<Justification>,
+		aux: Vec<(Vec<u8>, Vec<u8>)>,
+		state: &mut dyn Iterator<Item = ClientResult<StateEntry>>,
+	) -> ClientResult<()> {
+		let hash = header.hash();
+		let number = *header.number();
+		if number.is_zero() {
+			return Err(ClientError::Backend("Cannot import the state of the genesis block".into()));
+		}
+		if !self.blockchain.info().best_number.is_zero() ||
+			self.blockchain.status(BlockId::Hash(hash))? == sp_blockchain::BlockStatus::InChain
+		{
+			return Err(ClientError::Backend(
+				format!("Cannot import the state of block {:?} once blocks were imported", hash),
+			));
+		}
+
+		// nodes are written as the tries are built, so that the state is never held in memory.
+		// The nodes of a state failing the checks below stay in the database, unreferenced.
+		let mut writer = StateNodeWriter::new(&*self.storage.db);
+		let mut entries = state.peekable();
+		let mut failure = None;
+		let mut child_roots = HashMap::new();
+
+		let root = {
+			let mut last_key = None;
+			let top = std::iter::from_fn(|| {
+				let (key, value) = match entries.next_if_top() {
+					Ok(Some(pair)) => pair,
+					Ok(None) => return None,
+					Err(e) => {
+						failure = Some(e);
+						return None
+					},
+				};
+				if last_key.as_ref().map_or(false, |last| &key <= last) {
+					failure = Some(ClientError::Backend("State entries aren't in key order".into()));
+					return None
+				}
+				if key.as_slice() == well_known_keys::CHANGES_TRIE_CONFIG {
+					failure = Some(ClientError::Backend("Cannot import state with changes tries".into()));
+					return None
+				}
+				if well_known_keys::is_child_storage_key(&key) {
+					child_roots.insert(key.clone(), value.clone());
+				}
+				last_key = Some(key.clone());
+				Some((key, value))
+			});
+			sp_trie::build_trie::<sp_trie::Layout<HashFor<Block>>, _, _, _, _>(
+				&[],
+				top,
+				|key, node| writer.put(key, node),
+			)
+		};
+		if let Some(e) = failure.take() {
+			return Err(e)
+		}
+		if root != *header.state_root() {
+			return Err(ClientError::Backend(format!(
+				"Imported state root {:?} doesn't match the state root {:?} of block {:?}",
+				root,
+				header.state_root(),
+				hash,
+			)));
+		}
+
+		while let Some(entry) = entries.next() {
+			let (storage_key, key, value) = match entry? {
+				StateEntry::Child(storage_key, key, value) => (storage_key, key, value),
+				StateEntry::Top(..) => return Err(ClientError::Backend(
+					"Top-level state entry after child trie entries".into(),
+				)),
+			};
+			// a child trie that isn't in the top-level state, or isn't grouped.
+			let expected_root = child_roots.remove(&storage_key).ok_or_else(|| ClientError::Backend(
+				format!("Unexpected entries of child trie {}", HexDisplay::from(&storage_key)),
+			))?;
+			let child_info = ChildInfo::default_for_storage_key(&storage_key).ok_or_else(||
+				ClientError::Backend(format!("Invalid child trie key {}", HexDisplay::from(&storage_key))),
+			)?;
+
+			let mut unordered = false;
+			let root = {
+				let mut last_key = None;
+				let first = std::iter::once((key, value));
+				let rest = std::iter::from_fn(|| match entries.next_if_child(&storage_key) {
+					Ok(pair) => pair,
+					Err(e) => {
+						failure = Some(e);
+						None
+					},
+				});
+				let child = first.chain(rest).take_while(|(key, _)| {
+					unordered = last_key.as_ref().map_or(false, |last| key <= last);
+					last_key = Some(key.clone());
+					!unordered
+				});
+				sp_trie::build_trie::<sp_trie::Layout<HashFor<Block>>, _, _, _, _>(
+					child_info.keyspace(),
+					child,
+					|key, node| writer.put(key, node),
+				)
+			};
+			if let Some(e) = failure.take() {
+				return Err(e)
+			}
+			if unordered {
+				return Err(ClientError::Backend("State entries aren't in key order".into()))
+			}
+			if root.as_ref() != &expected_root[..] {
+				return Err(ClientError::Backend(format!(
+					"Imported root of child trie {} doesn't match the state of block {:?}",
+					HexDisplay::from(&storage_key),
+					hash,
+				)));
+			}
+		}
+		if let Some(storage_key) = child_roots.keys().next() {
+			return Err(ClientError::Backend(
+				format!("Missing child trie {}", HexDisplay::from(storage_key)),
+			));
+		}
+		writer.finish()?;
+
+		let mut transaction = DBTransaction::new();
+		for (key, value) in aux {
+			transaction.put(columns::AUX, &key, &value);
+		}
+
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		transaction.put(columns::HEADER, &lookup_key, &header.encode());
+		if let Some(justification) = justification {
+			transaction.put(columns::JUSTIFICATION, &lookup_key, &justification.encode());
+		}
+		utils::insert_hash_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash)?;
+		utils::insert_number_to_key_mapping(&mut transaction, columns::KEY_LOOKUP, number, hash)?;
+		transaction.put(columns::META, meta_keys::BEST_BLOCK, &lookup_key);
+		transaction.put(columns::META, meta_keys::FINALIZED_BLOCK, &lookup_key);
+
+		let commit = self.storage.state_db.import_canonical_block(&hash, number.saturated_into::<u64>())
+			.map_err(|e: sc_state_db::Error<io::Error>|
+				sp_blockchain::Error::from(format!("State database error: {:?}", e))
+			)?;
+		apply_state_commit(&mut transaction, commit);
+
+		let (displaced_leaf, finalization_displaced) = {
+			let mut leaves = self.blockchain.leaves.write();
+			let displaced_leaf = leaves.import(hash, number, *header.parent_hash());
+			let finalization_displaced = leaves.finalize_height(number);
+			leaves.prepare_transaction(&mut transaction, columns::META, meta_keys::LEAF_PREFIX);
+			(displaced_leaf, finalization_displaced)
+		};
+
+		if let Err(e) = self.storage.db.write(transaction).map_err(db_err) {
+			self.storage.state_db.revert_pending();
+			let mut leaves = self.blockchain.leaves.write();
+			let mut undo = leaves.undo();
+			if let Some(displaced_leaf) = displaced_leaf {
+				undo.undo_import(displaced_leaf);
+			}
+			undo.undo_finalization(finalization_displaced);
+			return Err(e)
+		}
+		self.storage.state_db.apply_pending();
+
+		self.blockchain.insert_header_metadata(hash, CachedHeaderMetadata::from(&header));
+		self.blockchain.update_meta(hash, number, true, true);
+		debug!(target: "db", "Imported the state of block {:?} ({})", hash, number);
+
+		Ok(())
+	}
+
 	fn have_state_at(&self, hash: &Block::Hash, number: NumberFor<Block>) -> bool {
 		if self.is_archive {
 			match self.blockchain.header(BlockId::Hash(hash.clone())) {
@@ -1796,7 +2431,10 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(DatabaseSettings {
 			state_cache_size: 16777216,
 			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: Default::default(),
 			pruning: PruningMode::keep_blocks(1),
+			state_retention: Default::default(),
 			source: DatabaseSettingsSrc::Custom(backing),
 		}, 0).unwrap();
 		assert_eq!(backend.blockchain().info().best_number, 9);
@@ -1805,6 +2443,47 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn offchain_index_is_written_for_best_blocks_only() {
+		use sp_core::offchain::OffchainStorage;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+		let import = |number: u64, parent_hash: H256, extrinsics_root: H256, value: &[u8], state| {
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: BlakeTwo256::trie_root(Vec::new()),
+				digest: Default::default(),
+				extrinsics_root,
+			};
+			let hash = header.hash();
+			let block_id = if number == 0 {
+				BlockId::Hash(Default::default())
+			} else {
+				BlockId::Hash(parent_hash)
+			};
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, block_id).unwrap();
+			op.set_block_data(header, Some(Vec::new()), None, state).unwrap();
+			op.update_offchain_storage(vec![(b"key".to_vec(), Some(value.to_vec()))]).unwrap();
+			backend.commit_operation(op).unwrap();
+			hash
+		};
+		let index = || backend.offchain_storage().unwrap().get(sp_offchain::STORAGE_PREFIX, b"key");
+
+		let genesis = import(0, Default::default(), Default::default(), b"genesis", NewBlockState::Final);
+		assert_eq!(index(), Some(b"genesis".to_vec()));
+
+		let best = import(1, genesis, Default::default(), b"best", NewBlockState::Best);
+		assert_eq!(index(), Some(b"best".to_vec()));
+
+		import(1, genesis, H256::from_low_u64_be(1), b"fork", NewBlockState::Normal);
+		assert_eq!(index(), Some(b"best".to_vec()));
+
+		import(2, best, Default::default(), b"next", NewBlockState::Best);
+		assert_eq!(index(), Some(b"next".to_vec()));
+	}
+
 	#[test]
 	fn set_state_data() {
 		let db = Backend::<Block>::new_test(2, 0);
@@ -1891,6 +2570,198 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn retained_prefixes_survive_pruning() {
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: Default::default(),
+			pruning: PruningMode::keep_blocks(1),
+			state_retention: StateRetention::new(vec![vec![1]]),
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 0).unwrap();
+
+		commit_retained_blocks(&backend, 0..4, Default::default());
+
+		let genesis_hash = backend.blockchain().hash(0).unwrap().unwrap();
+		assert!(!backend.have_state_at(&genesis_hash, 0));
+		let state = backend.state_at(BlockId::Number(0)).unwrap();
+		assert_eq!(state.storage(&[1, 1]).unwrap(), Some(vec![0; 40]));
+		let state = backend.state_at(BlockId::Number(3)).unwrap();
+		assert_eq!(state.storage(&[2, 2]).unwrap(), Some(vec![3; 40]));
+	}
+
+	#[test]
+	fn released_prefixes_are_deleted() {
+		let retention = StateRetention::new(vec![vec![1], vec![2]]);
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: Default::default(),
+			pruning: PruningMode::keep_blocks(1),
+			state_retention: retention.clone(),
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 0).unwrap();
+		let retained_under = |prefix: u8| backend.storage.db.iter(columns::RETAINED_STATE)
+			.filter(|(key, _)| key.len() > 32 && key[0] == prefix)
+			.count();
+
+		let parent_hash = commit_retained_blocks(&backend, 0..3, Default::default());
+		assert!(retained_under(1) > 0);
+		assert!(retained_under(2) > 0);
+
+		assert!(retention.release(&[1]));
+		commit_retained_blocks(&backend, 3..4, parent_hash);
+		assert_eq!(retained_under(1), 0);
+		assert!(retained_under(2) > 0);
+		let state = backend.state_at(BlockId::Number(0)).unwrap();
+		assert_eq!(state.storage(&[2, 2]).unwrap(), Some(vec![0; 40]));
+	}
+
+	#[test]
+	fn retained_prefixes_are_capped() {
+		let max = sc_client_api::backend::MAX_RETAINED_PREFIXES as u8;
+		let retention = StateRetention::new((0..max).map(|prefix| vec![prefix]));
+		assert!(!retention.retain(vec![0]).unwrap());
+		assert!(retention.retain(vec![0xff]).is_err());
+		assert!(retention.release(&[0]));
+		assert!(retention.retain(vec![0xff]).unwrap());
+	}
+
+	#[test]
+	fn retained_paths() {
+		assert!(is_on_retained_path(&[], &[1, 2]));
+		assert!(is_on_retained_path(&[1], &[1, 2]));
+		assert!(is_on_retained_path(&[1, 2, 3], &[1, 2]));
+		assert!(is_on_retained_path(&[1, 0x20], &[1, 2]));
+		assert!(is_on_retained_path(&[0x10], &[0x12]));
+		assert!(!is_on_retained_path(&[2], &[1, 2]));
+		assert!(!is_on_retained_path(&[1, 3], &[1, 2]));
+		assert!(!is_on_retained_path(&[0x20], &[0x12]));
+	}
+
+	/// Commit final blocks with the given numbers, each setting keys `[1, 1]` and `[2, 2]` to the
+	/// block number, and return the hash of the last one.
+	fn commit_retained_blocks(
+		backend: &Backend<Block>,
+		numbers: std::ops::Range<u64>,
+		mut parent_hash: H256,
+	) -> H256 {
+		for number in numbers {
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(parent_hash)).unwrap();
+			// values are large enough not to be inlined into their parent node
+			let storage = vec![
+				(vec![1, 1], Some(vec![number as u8; 40])),
+				(vec![2, 2], Some(vec![number as u8; 40])),
+			];
+			let (root, overlay) = op.old_state.storage_root(storage.iter().cloned());
+			let header = Header {
+				number,
+				parent_hash,
+				state_root: root.into(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			if number == 0 {
+				op.reset_storage(Storage {
+					top: storage.into_iter().map(|(k, v)| (k, v.unwrap())).collect(),
+					children: Default::default(),
+				}).unwrap();
+			} else {
+				op.update_db_storage(overlay).unwrap();
+				op.update_storage(storage, Vec::new()).unwrap();
+			}
+			parent_hash = header.hash();
+			op.set_block_data(header, Some(vec![]), None, NewBlockState::Final).unwrap();
+			backend.commit_operation(op).unwrap();
+		}
+		parent_hash
+	}
+
+	#[test]
+	fn import_state_of_block_without_ancestors() {
+		let child_key = b":child_storage:default:child".to_vec();
+		let child_info = ChildInfo::new_default(b"child");
+
+		// the state of the imported block, read from another database.
+		let other = Backend::<Block>::new_test(2, 0);
+		let mut op = other.begin_operation().unwrap();
+		other.begin_state_operation(&mut op, BlockId::Hash(Default::default())).unwrap();
+		let state_root = op.reset_storage(Storage {
+			top: vec![(vec![1, 1], vec![1; 40]), (vec![2, 2], vec![2; 40])].into_iter().collect(),
+			children: vec![(child_key.clone(), sp_core::storage::StorageChild {
+				data: vec![(vec![3], vec![3; 40]), (vec![4], vec![4; 40])].into_iter().collect(),
+				child_info: child_info.to_owned(),
+			})].into_iter().collect(),
+		}).unwrap();
+		let genesis = Header {
+			number: 0,
+			parent_hash: Default::default(),
+			state_root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		op.set_block_data(genesis, Some(vec![]), None, NewBlockState::Final).unwrap();
+		other.commit_operation(op).unwrap();
+
+		let state = other.state_at(BlockId::Number(0)).unwrap();
+		let mut entries: Vec<_> = state.pairs().into_iter()
+			.map(|(key, value)| StateEntry::Top(key, value))
+			.collect();
+		for key in vec![vec![3], vec![4]] {
+			let value = state.child_storage(&child_key, child_info, &key).unwrap().unwrap();
+			entries.push(StateEntry::Child(child_key.clone(), key, value));
+		}
+		assert_eq!(entries.len(), 5);
+
+		let backend = Backend::<Block>::new_test(2, 0);
+		let header = Header {
+			number: 5,
+			parent_hash: [1; 32].into(),
+			state_root,
+			digest: Default::default(),
+			extrinsics_root: Default::default(),
+		};
+		let hash = header.hash();
+		let import = |entries: Vec<StateEntry>| backend.import_state(
+			header.clone(),
+			Some(vec![42]),
+			vec![(b"aux".to_vec(), b"value".to_vec())],
+			&mut entries.into_iter().map(Ok::<_, ClientError>),
+		);
+
+		// a wrong value, a missing child trie and unordered child entries are refused.
+		let mut wrong = entries.clone();
+		match &mut wrong[0] {
+			StateEntry::Top(_, value) => value.push(0),
+			_ => panic!("the top-level entries come first"),
+		}
+		assert!(import(wrong).is_err());
+		assert!(import(entries[..3].to_vec()).is_err());
+		let mut unordered = entries.clone();
+		unordered.swap(3, 4);
+		assert!(import(unordered).is_err());
+		assert_eq!(backend.blockchain().info().best_number, 0);
+		assert_eq!(backend.get_aux(b"aux").unwrap(), None);
+
+		import(entries.clone()).unwrap();
+
+		let info = backend.blockchain().info();
+		assert_eq!((info.best_hash, info.best_number), (hash, 5));
+		assert_eq!((info.finalized_hash, info.finalized_number), (hash, 5));
+		assert_eq!(backend.blockchain().justification(BlockId::Hash(hash)).unwrap(), Some(vec![42]));
+		assert_eq!(backend.get_aux(b"aux").unwrap(), Some(b"value".to_vec()));
+		let state = backend.state_at(BlockId::Hash(hash)).unwrap();
+		assert_eq!(state.storage(&[2, 2]).unwrap(), Some(vec![2; 40]));
+		assert_eq!(state.child_storage(&child_key, child_info, &[4]).unwrap(), Some(vec![4; 40]));
+
+		// the state of a block is only imported into an empty database.
+		assert!(import(entries).is_err());
+	}
+
 	#[test]
 	fn delete_only_when_negative_rc() {
 		let _ = ::env_logger::try_init();
@@ -2282,6 +3153,90 @@ pub(crate) mod tests {
 		);
 	}
 
+	#[test]
+	fn bodies_and_justifications_are_pruned_on_finalization() {
+		use sc_client::blockchain::Backend as BlockChainBackend;
+
+		let backend = Backend::<Block>::new(DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: BlocksPruning {
+				keep_bodies: Some(2),
+				justifications: JustificationsPruning::KeepAuthoritySetChanges,
+			},
+			pruning: PruningMode::keep_blocks(1),
+			state_retention: Default::default(),
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		}, 0).unwrap();
+
+		// block 3 enacts a set change recorded before its finalization, block 4 one recorded
+		// along with its finalization.
+		backend.insert_aux(&[(&authority_set_change_aux_key(3u64)[..], &[][..])], &[]).unwrap();
+		let mut hash = insert_header(&backend, 0, Default::default(), None, Default::default());
+		for number in 1..=4 {
+			hash = insert_header(&backend, number, hash, None, Default::default());
+			let mut op = backend.begin_operation().unwrap();
+			backend.begin_state_operation(&mut op, BlockId::Hash(hash)).unwrap();
+			if number == 4 {
+				op.insert_aux(vec![(authority_set_change_aux_key(4u64), Some(vec![]))]).unwrap();
+			}
+			op.mark_finalized(BlockId::Hash(hash), Some(vec![number as u8])).unwrap();
+			backend.commit_operation(op).unwrap();
+		}
+
+		let blockchain = backend.blockchain();
+		assert!(blockchain.body(BlockId::Number(1)).unwrap().is_none());
+		assert!(blockchain.body(BlockId::Number(2)).unwrap().is_none());
+		assert!(blockchain.body(BlockId::Number(3)).unwrap().is_some());
+		assert!(blockchain.body(BlockId::Number(4)).unwrap().is_some());
+		assert!(blockchain.justification(BlockId::Number(2)).unwrap().is_none());
+		assert!(blockchain.justification(BlockId::Number(3)).unwrap().is_some());
+		assert!(blockchain.justification(BlockId::Number(4)).unwrap().is_some());
+	}
+
+	#[test]
+	fn keeping_no_bodies_is_rejected() {
+		let settings = |keep_bodies| DatabaseSettings {
+			state_cache_size: 16777216,
+			state_cache_child_ratio: Some((50, 100)),
+			trie_cache_size: 16777216,
+			blocks_pruning: BlocksPruning { keep_bodies, justifications: JustificationsPruning::KeepAll },
+			pruning: PruningMode::keep_blocks(1),
+			state_retention: Default::default(),
+			source: DatabaseSettingsSrc::Custom(Arc::new(kvdb_memorydb::create(crate::utils::NUM_COLUMNS))),
+		};
+		assert!(Backend::<Block>::new(settings(Some(0)), 0).is_err());
+		assert!(Backend::<Block>::new(settings(Some(1)), 0).is_ok());
+	}
+
+	#[test]
+	fn bodies_and_justifications_are_pruned_retroactively() {
+		use sc_client::blockchain::Backend as BlockChainBackend;
+
+		let backend = Backend::<Block>::new_test(10, 10);
+
+		let mut hash = insert_header(&backend, 0, Default::default(), None, Default::default());
+		for number in 1..=4 {
+			hash = insert_header(&backend, number, hash, None, Default::default());
+			backend.finalize_block(BlockId::Number(number), Some(vec![number as u8])).unwrap();
+		}
+		backend.insert_aux(&[(&authority_set_change_aux_key(2u64)[..], &[][..])], &[]).unwrap();
+		assert!(backend.blockchain().justification(BlockId::Number(4)).unwrap().is_some());
+
+		let pruned = backend.prune_blocks(&BlocksPruning {
+			keep_bodies: Some(1),
+			justifications: JustificationsPruning::KeepAuthoritySetChanges,
+		}).unwrap();
+
+		assert_eq!(pruned, 4);
+		let blockchain = backend.blockchain();
+		assert!(blockchain.body(BlockId::Number(3)).unwrap().is_none());
+		assert!(blockchain.body(BlockId::Number(4)).unwrap().is_some());
+		assert!(blockchain.justification(BlockId::Number(2)).unwrap().is_some());
+		assert!(blockchain.justification(BlockId::Number(4)).unwrap().is_none());
+	}
+
 	#[test]
 	fn test_finalize_multiple_blocks_in_single_op() {
 		let backend = Backend::<Block>::new_test(10, 10);
@@ -581,6 +581,7 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 			memory: MemoryInfo {
 				database_cache,
 				state_cache: Default::default(),
+				trie_cache: Default::default(),
 				state_db: Default::default(),
 			},
 			io: IoInfo {
@@ -594,6 +595,9 @@ impl<Block> LightBlockchainStorage<Block> for LightStorage<Block>
 				state_reads: 0,
 				state_reads_cache: 0,
 				state_writes: 0,
+				state_cache_evictions: 0,
+				trie_cache_hits: 0,
+				trie_cache_misses: 0,
 			}
 		})
 	}
@@ -0,0 +1,214 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Migration of a RocksDB database to ParityDB.
+//!
+//! The migration copies every column of the RocksDB database to a new ParityDB database next to
+//! it, reads every entry back, and only then swaps the directories. The RocksDB database is kept
+//! as a backup, so that the node can be rolled back by moving it back in place.
+
+use std::path::{Path, PathBuf};
+
+/// Phase of a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationPhase {
+	/// The entries are copied to the new database.
+	Copy,
+	/// The entries of the new database are checked against the old database.
+	Verify,
+}
+
+/// Progress of a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationProgress {
+	/// Current phase.
+	pub phase: MigrationPhase,
+	/// Column being processed.
+	pub column: u32,
+	/// Number of columns of the database.
+	pub columns: u32,
+	/// Number of entries of the column processed so far.
+	pub entries: u64,
+}
+
+/// Path of the backup of the RocksDB database at the given path.
+pub fn backup_path(path: &Path) -> PathBuf {
+	sibling_path(path, "rocksdb-backup")
+}
+
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+	let mut name = path.file_name().map(|name| name.to_os_string()).unwrap_or_default();
+	name.push(".");
+	name.push(extension);
+	path.with_file_name(name)
+}
+
+/// Migrate the RocksDB database at the given path to ParityDB, in place.
+///
+/// The database must be at the current version. On success, the RocksDB database has been moved
+/// to the returned backup path. On failure, the database at the given path is left untouched.
+#[cfg(all(any(feature = "kvdb-rocksdb", test), feature = "parity-db"))]
+pub fn migrate_to_parity_db(
+	path: &Path,
+	mut progress: impl FnMut(MigrationProgress),
+) -> sp_blockchain::Result<PathBuf> {
+	use std::fs;
+	use crate::{upgrade, utils::db_err};
+
+	let version = upgrade::current_version(path)?;
+	if version != upgrade::CURRENT_VERSION {
+		return Err(sp_blockchain::Error::Backend(format!(
+			"Database version {} must be upgraded to {} before the migration, start the node once to upgrade it",
+			version,
+			upgrade::CURRENT_VERSION,
+		)));
+	}
+
+	let backup_path = backup_path(path);
+	if backup_path.exists() {
+		return Err(sp_blockchain::Error::Backend(format!(
+			"Backup of a previous migration found at {}, remove it first",
+			backup_path.display(),
+		)));
+	}
+
+	// leftover of an interrupted migration
+	let migration_path = sibling_path(path, "paritydb-migration");
+	if migration_path.exists() {
+		fs::remove_dir_all(&migration_path).map_err(db_err)?;
+	}
+
+	if let Err(e) = copy_database(path, &migration_path, &mut progress) {
+		let _ = fs::remove_dir_all(&migration_path);
+		return Err(e);
+	}
+
+	fs::rename(path, &backup_path).map_err(db_err)?;
+	if let Err(e) = fs::rename(&migration_path, path) {
+		fs::rename(&backup_path, path).map_err(db_err)?;
+		return Err(db_err(e));
+	}
+
+	Ok(backup_path)
+}
+
+/// Migrate the RocksDB database at the given path to ParityDB, in place.
+///
+/// Always fails: both the RocksDB and the ParityDB backends must be enabled.
+#[cfg(not(all(any(feature = "kvdb-rocksdb", test), feature = "parity-db")))]
+pub fn migrate_to_parity_db(
+	_path: &Path,
+	_progress: impl FnMut(MigrationProgress),
+) -> sp_blockchain::Result<PathBuf> {
+	Err(sp_blockchain::Error::Backend(
+		"Migrating to ParityDB requires both the RocksDB and the ParityDB backends".into()
+	))
+}
+
+/// Copy the RocksDB database at `from` to a new ParityDB database at `to` and verify the copy.
+#[cfg(all(any(feature = "kvdb-rocksdb", test), feature = "parity-db"))]
+fn copy_database(
+	from: &Path,
+	to: &Path,
+	progress: &mut impl FnMut(MigrationProgress),
+) -> sp_blockchain::Result<()> {
+	use std::{fs, mem};
+	use kvdb::{DBTransaction, KeyValueDB};
+	use kvdb_rocksdb::{Database, DatabaseConfig};
+	use crate::{paritydb::ParityDb, upgrade::VERSION_FILE_NAME, utils::{NUM_COLUMNS, db_err}};
+
+	/// Number of entries written at once, and between progress reports.
+	const BATCH_SIZE: u64 = 10_000;
+
+	let from_str = from.to_str()
+		.ok_or_else(|| sp_blockchain::Error::Backend("Invalid database path".into()))?;
+	let source = Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), from_str).map_err(db_err)?;
+	let target = ParityDb::open(to, NUM_COLUMNS).map_err(db_err)?;
+
+	for column in 0..NUM_COLUMNS {
+		let mut transaction = DBTransaction::new();
+		let mut entries = 0;
+		for (key, value) in source.iter(column) {
+			transaction.put(column, &key, &value);
+			entries += 1;
+			if entries % BATCH_SIZE == 0 {
+				target.write(mem::replace(&mut transaction, DBTransaction::new())).map_err(db_err)?;
+				progress(MigrationProgress { phase: MigrationPhase::Copy, column, columns: NUM_COLUMNS, entries });
+			}
+		}
+		target.write(transaction).map_err(db_err)?;
+		progress(MigrationProgress { phase: MigrationPhase::Copy, column, columns: NUM_COLUMNS, entries });
+	}
+
+	for column in 0..NUM_COLUMNS {
+		let mut entries = 0;
+		for (key, value) in source.iter(column) {
+			let copied = target.get(column, &key).map_err(db_err)?;
+			if copied.as_ref().map(|copied| &copied[..]) != Some(&value[..]) {
+				return Err(sp_blockchain::Error::Backend(format!(
+					"Entry {} of column {} differs after the migration",
+					sp_core::hexdisplay::HexDisplay::from(&&key[..]),
+					column,
+				)));
+			}
+			entries += 1;
+			if entries % BATCH_SIZE == 0 {
+				progress(MigrationProgress { phase: MigrationPhase::Verify, column, columns: NUM_COLUMNS, entries });
+			}
+		}
+		progress(MigrationProgress { phase: MigrationPhase::Verify, column, columns: NUM_COLUMNS, entries });
+	}
+
+	fs::copy(from.join(VERSION_FILE_NAME), to.join(VERSION_FILE_NAME)).map_err(db_err)?;
+
+	Ok(())
+}
+
+#[cfg(all(test, feature = "parity-db"))]
+mod tests {
+	use kvdb::{DBTransaction, KeyValueDB};
+	use kvdb_rocksdb::{Database, DatabaseConfig};
+	use crate::{paritydb::ParityDb, utils::{NUM_COLUMNS, COLUMN_META}};
+	use super::*;
+
+	#[test]
+	fn rocksdb_database_is_migrated_in_place() {
+		let dir = tempfile::TempDir::new().unwrap();
+		let path = dir.path().join("db");
+		{
+			let db = Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path.to_str().unwrap()).unwrap();
+			let mut transaction = DBTransaction::new();
+			transaction.put(COLUMN_META, b"key", b"value");
+			db.write(transaction).unwrap();
+		}
+		std::fs::write(path.join(crate::upgrade::VERSION_FILE_NAME), format!("{}", crate::upgrade::CURRENT_VERSION))
+			.unwrap();
+
+		let mut reports = Vec::new();
+		let backup = migrate_to_parity_db(&path, |progress| reports.push(progress)).unwrap();
+
+		assert_eq!(backup, backup_path(&path));
+		assert!(backup.exists());
+		assert_eq!(reports.len(), 2 * NUM_COLUMNS as usize);
+		assert_eq!(reports.last().unwrap().phase, MigrationPhase::Verify);
+
+		let db = ParityDb::open(&path, NUM_COLUMNS).unwrap();
+		assert_eq!(db.get(COLUMN_META, b"key").unwrap(), Some(b"value".to_vec()));
+
+		// the backup is never overwritten
+		assert!(migrate_to_parity_db(&path, |_| ()).is_err());
+	}
+}
@@ -58,7 +58,7 @@ impl LocalStorage {
 
 impl sp_core::offchain::OffchainStorage for LocalStorage {
 	fn set(&mut self, prefix: &[u8], key: &[u8], value: &[u8]) {
-		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		let key = concatenate_prefix_and_key(prefix, key);
 		let mut tx = self.db.transaction();
 		tx.put(columns::OFFCHAIN, &key, value);
 
@@ -68,7 +68,7 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 	}
 
 	fn get(&self, prefix: &[u8], key: &[u8]) -> Option<Vec<u8>> {
-		let key: Vec<u8> = prefix.iter().chain(key).cloned().collect();
+		let key = concatenate_prefix_and_key(prefix, key);
 		self.db.get(columns::OFFCHAIN, &key)
 			.ok()
 			.and_then(|x| x)
@@ -82,7 +82,7 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 		old_value: Option<&[u8]>,
 		new_value: &[u8],
 	) -> bool {
-		let key: Vec<u8> = prefix.iter().chain(item_key).cloned().collect();
+		let key = concatenate_prefix_and_key(prefix, item_key);
 		let key_lock = {
 			let mut locks = self.locks.lock();
 			locks.entry(key.clone()).or_default().clone()
@@ -114,6 +114,11 @@ impl sp_core::offchain::OffchainStorage for LocalStorage {
 	}
 }
 
+/// The database key of `key` in the offchain storage under `prefix`.
+pub(crate) fn concatenate_prefix_and_key(prefix: &[u8], key: &[u8]) -> Vec<u8> {
+	prefix.iter().chain(key).cloned().collect()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -0,0 +1,142 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! ParityDB backed key-value database.
+//!
+//! ParityDB doesn't support iteration over a column, which the client doesn't need outside of
+//! the test helpers: `iter`, `iter_with_prefix` and `get_by_prefix` panic rather than silently
+//! yield nothing.
+
+use std::{fs, io, path::Path};
+
+use kvdb::{DBOp, DBTransaction, DBValue, KeyValueDB};
+use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
+use crate::utils::PARITY_DB_MARKER_FILE_NAME;
+
+/// Version file written by the RocksDB backend.
+const VERSION_FILE_NAME: &str = "db_version";
+
+/// A ParityDB database exposed as a `KeyValueDB`.
+pub struct ParityDb(parity_db::Db);
+
+impl ParityDb {
+	/// Open or create the database at the given path, with the given number of columns.
+	///
+	/// The directory is marked as a ParityDB database, so that it isn't opened with RocksDB.
+	/// Opening a directory that holds a RocksDB database fails: `db migrate` converts it instead.
+	pub fn open(path: &Path, num_columns: u32) -> io::Result<Self> {
+		if holds_other_database(path)? {
+			return Err(io::Error::new(
+				io::ErrorKind::Other,
+				format!(
+					"{} holds a RocksDB database; run `db migrate` to convert it to ParityDB",
+					path.display(),
+				),
+			));
+		}
+		fs::create_dir_all(path)?;
+		let db = parity_db::Db::with_columns(path, num_columns as u8).map_err(other_err)?;
+		fs::write(path.join(PARITY_DB_MARKER_FILE_NAME), b"")?;
+		Ok(ParityDb(db))
+	}
+}
+
+/// Whether the directory at `path` holds a database that isn't marked as ParityDB.
+fn holds_other_database(path: &Path) -> io::Result<bool> {
+	if !path.exists() || path.join(PARITY_DB_MARKER_FILE_NAME).exists() {
+		return Ok(false);
+	}
+	for entry in fs::read_dir(path)? {
+		let name = entry?.file_name();
+		let name = name.to_string_lossy();
+		if name == VERSION_FILE_NAME
+			|| name == "CURRENT"
+			|| name == "IDENTITY"
+			|| name.starts_with("MANIFEST-")
+			|| name.ends_with(".sst")
+		{
+			return Ok(true);
+		}
+	}
+	Ok(false)
+}
+
+const ITERATION_UNSUPPORTED: &str = "ParityDB doesn't support iterating over a column";
+
+fn other_err(err: parity_db::Error) -> io::Error {
+	io::Error::new(io::ErrorKind::Other, format!("ParityDB error: {:?}", err))
+}
+
+impl KeyValueDB for ParityDb {
+	fn get(&self, col: u32, key: &[u8]) -> io::Result<Option<DBValue>> {
+		self.0.get(col as u8, key).map_err(other_err)
+	}
+
+	fn get_by_prefix(&self, _col: u32, _prefix: &[u8]) -> Option<Box<[u8]>> {
+		panic!("{}", ITERATION_UNSUPPORTED)
+	}
+
+	fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+		self.0.commit(transaction.ops.into_iter().map(|op| match op {
+			DBOp::Insert { col, key, value } => (col as u8, key.to_vec(), Some(value)),
+			DBOp::Delete { col, key } => (col as u8, key.to_vec(), None),
+		})).map_err(other_err)
+	}
+
+	fn iter<'a>(&'a self, _col: u32) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		panic!("{}", ITERATION_UNSUPPORTED)
+	}
+
+	fn iter_with_prefix<'a>(
+		&'a self,
+		_col: u32,
+		_prefix: &'a [u8],
+	) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+		panic!("{}", ITERATION_UNSUPPORTED)
+	}
+
+	fn restore(&self, _new_db: &str) -> io::Result<()> {
+		Err(io::Error::new(io::ErrorKind::Other, "Restoring a ParityDB database is not supported"))
+	}
+}
+
+impl MallocSizeOf for ParityDb {
+	fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+		// ParityDB doesn't report the memory it uses.
+		0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn refuses_unmarked_database_directory() {
+		let dir = tempfile::TempDir::new().unwrap();
+		fs::write(dir.path().join(VERSION_FILE_NAME), b"2").unwrap();
+		assert!(ParityDb::open(dir.path(), 1).is_err());
+		assert!(!dir.path().join(PARITY_DB_MARKER_FILE_NAME).exists());
+	}
+
+	#[test]
+	fn reopens_marked_database_directory() {
+		let dir = tempfile::TempDir::new().unwrap();
+		drop(ParityDb::open(dir.path(), 1).unwrap());
+		fs::write(dir.path().join(VERSION_FILE_NAME), b"2").unwrap();
+		assert!(ParityDb::open(dir.path(), 1).is_ok());
+	}
+}
@@ -17,11 +17,13 @@
 //! Global cache state.
 
 use std::collections::{VecDeque, HashSet, HashMap};
+use std::mem;
 use std::sync::Arc;
 use std::hash::Hash as StdHash;
 use parking_lot::{Mutex, RwLock, RwLockUpgradableReadGuard};
 use linked_hash_map::{LinkedHashMap, Entry};
 use hash_db::Hasher;
+use parity_util_mem::{MallocSizeOf, malloc_size};
 use sp_runtime::traits::{Block as BlockT, Header, HashFor, NumberFor};
 use sp_core::hexdisplay::HexDisplay;
 use sp_core::storage::ChildInfo;
@@ -41,7 +43,7 @@ pub struct Cache<B: BlockT> {
 	/// Storage cache. `None` indicates that key is known to be missing.
 	lru_storage: LRUMap<StorageKey, Option<StorageValue>>,
 	/// Storage hashes cache. `None` indicates that key is known to be missing.
-	lru_hashes: LRUMap<StorageKey, OptionHOut<B::Hash>>,
+	lru_hashes: LRUMap<StorageKey, Option<B::Hash>>,
 	/// Storage cache for child trie. `None` indicates that key is known to be missing.
 	lru_child_storage: LRUMap<ChildStorageKey, Option<StorageValue>>,
 	/// Information on the modifications in recently committed blocks; specifically which keys
@@ -49,81 +51,66 @@ pub struct Cache<B: BlockT> {
 	modifications: VecDeque<BlockChanges<B::Header>>,
 }
 
-struct LRUMap<K, V>(LinkedHashMap<K, V>, usize, usize);
-
-/// Internal trait similar to `heapsize` but using
-/// a simply estimation.
-///
-/// This should not be made public, it is implementation
-/// detail trait. If it need to become public please
-/// consider using `malloc_size_of`.
-trait EstimateSize {
-	/// Return a size estimation of additional size needed
-	/// to cache this struct (in bytes).
-	fn estimate_size(&self) -> usize;
-}
-
-impl EstimateSize for Vec<u8> {
-	fn estimate_size(&self) -> usize {
-		self.capacity()
-	}
-}
-
-impl EstimateSize for Option<Vec<u8>> {
-	fn estimate_size(&self) -> usize {
-		self.as_ref().map(|v|v.capacity()).unwrap_or(0)
-	}
+/// LRU map bounded by the memory used by its entries.
+pub(crate) struct LRUMap<K, V> {
+	/// The entries, with the memory they use.
+	map: LinkedHashMap<K, (V, usize)>,
+	/// Memory used by the entries, in bytes.
+	used_size: usize,
+	/// Memory budget of the entries, in bytes.
+	limit: usize,
+	/// Number of entries evicted to stay within the budget.
+	evictions: u64,
 }
 
-struct OptionHOut<T: AsRef<[u8]>>(Option<T>);
+/// Size of the links of an entry of the linked hash map: its hash table slot and list pointers.
+const ENTRY_LINKS_SIZE: usize = 3 * mem::size_of::<usize>();
 
-impl<T: AsRef<[u8]>> EstimateSize for OptionHOut<T> {
-	fn estimate_size(&self) -> usize {
-		// capacity would be better
-		self.0.as_ref().map(|v|v.as_ref().len()).unwrap_or(0)
-	}
+/// Memory used by an entry of an `LRUMap`, in bytes.
+///
+/// This is the heap memory of the key and value, as reported by the allocator, plus the node
+/// holding the entry in the linked hash map.
+fn entry_size<K: MallocSizeOf, V: MallocSizeOf>(k: &K, v: &V) -> usize {
+	malloc_size(k) + malloc_size(v) + mem::size_of::<(K, (V, usize))>() + ENTRY_LINKS_SIZE
 }
 
-impl<T: EstimateSize> EstimateSize for (T, T) {
-	fn estimate_size(&self) -> usize {
-		self.0.estimate_size() + self.1.estimate_size()
+impl<K: MallocSizeOf + Eq + StdHash, V: MallocSizeOf> LRUMap<K, V> {
+	pub(crate) fn new(limit: usize) -> Self {
+		LRUMap { map: LinkedHashMap::new(), used_size: 0, limit, evictions: 0 }
 	}
-}
 
-impl<K: EstimateSize + Eq + StdHash, V: EstimateSize> LRUMap<K, V> {
 	fn remove(&mut self, k: &K) {
-		let map = &mut self.0;
-		let storage_used_size = &mut self.1;
-		if let Some(v) = map.remove(k) {
-			*storage_used_size -= k.estimate_size();
-			*storage_used_size -= v.estimate_size();
+		if let Some((_, size)) = self.map.remove(k) {
+			self.used_size -= size;
 		}
 	}
 
-	fn add(&mut self, k: K, v: V) {
-		let lmap = &mut self.0;
-		let storage_used_size = &mut self.1;
-		let limit = self.2;
-		let klen = k.estimate_size();
-		*storage_used_size += v.estimate_size();
-		// TODO assert k v size fit into limit?? to avoid insert remove?
-		match lmap.entry(k) {
+	pub(crate) fn add(&mut self, k: K, v: V) {
+		if entry_size(&k, &v) > self.limit {
+			// caching it would only flush the whole map
+			self.remove(&k);
+			return;
+		}
+
+		match self.map.entry(k) {
 			Entry::Occupied(mut entry) => {
 				// note that in this case we are not running pure lru as
 				// it would require to remove first
-				*storage_used_size -= entry.get().estimate_size();
-				entry.insert(v);
+				let size = entry_size(entry.key(), &v);
+				let (_, old_size) = entry.insert((v, size));
+				self.used_size = self.used_size - old_size + size;
 			},
 			Entry::Vacant(entry) => {
-				*storage_used_size += klen;
-				entry.insert(v);
+				let size = entry_size(entry.key(), &v);
+				entry.insert((v, size));
+				self.used_size += size;
 			},
 		};
 
-		while *storage_used_size > limit {
-			if let Some((k,v)) = lmap.pop_front() {
-				*storage_used_size -= k.estimate_size();
-				*storage_used_size -= v.estimate_size();
+		while self.used_size > self.limit {
+			if let Some((_, (_, size))) = self.map.pop_front() {
+				self.used_size -= size;
+				self.evictions += 1;
 			} else {
 				// can happen fairly often as we get value from multiple lru
 				// and only remove from a single lru
@@ -132,20 +119,20 @@ impl<K: EstimateSize + Eq + StdHash, V: EstimateSize> LRUMap<K, V> {
 		}
 	}
 
-	fn get<Q:?Sized>(&mut self, k: &Q) -> Option<&mut V>
+	pub(crate) fn get<Q:?Sized>(&mut self, k: &Q) -> Option<&mut V>
 		where K: std::borrow::Borrow<Q>,
 			Q: StdHash + Eq {
-		self.0.get_refresh(k)
+		self.map.get_refresh(k).map(|entry| &mut entry.0)
 	}
 
-	fn used_size(&self) -> usize {
-		self.1
+	pub(crate) fn used_size(&self) -> usize {
+		self.used_size
 	}
+
 	fn clear(&mut self) {
-		self.0.clear();
-		self.1 = 0;
+		self.map.clear();
+		self.used_size = 0;
 	}
-
 }
 
 impl<B: BlockT> Cache<B> {
@@ -153,7 +140,15 @@ impl<B: BlockT> Cache<B> {
 	pub fn used_storage_cache_size(&self) -> usize {
 		self.lru_storage.used_size()
 			+ self.lru_child_storage.used_size()
-			//  ignore small hashes storage and self.lru_hashes.used_size()
+			+ self.lru_hashes.used_size()
+	}
+
+	/// Returns the number of entries evicted from the storage cache to stay within its memory
+	/// budget.
+	pub fn evictions(&self) -> u64 {
+		self.lru_storage.evictions
+			+ self.lru_child_storage.evictions
+			+ self.lru_hashes.evictions
 	}
 
 	/// Synchronize the shared cache with the best block state.
@@ -230,13 +225,9 @@ pub fn new_shared_cache<B: BlockT>(
 	Arc::new(
 		Mutex::new(
 			Cache {
-				lru_storage: LRUMap(
-					LinkedHashMap::new(), 0, shared_cache_size * top / child_ratio.1
-				),
-				lru_hashes: LRUMap(LinkedHashMap::new(), 0, FIX_LRU_HASH_SIZE),
-				lru_child_storage: LRUMap(
-					LinkedHashMap::new(), 0, shared_cache_size * child_ratio.0 / child_ratio.1
-				),
+				lru_storage: LRUMap::new(shared_cache_size * top / child_ratio.1),
+				lru_hashes: LRUMap::new(FIX_LRU_HASH_SIZE),
+				lru_child_storage: LRUMap::new(shared_cache_size * child_ratio.0 / child_ratio.1),
 				modifications: VecDeque::new(),
 			}
 		)
@@ -365,7 +356,7 @@ impl<B: BlockT> CacheChanges<B> {
 					cache.lru_child_storage.add(k, v);
 				}
 				for (k, v) in local_cache.hashes.drain() {
-					cache.lru_hashes.add(k, OptionHOut(v));
+					cache.lru_hashes.add(k, v);
 				}
 			}
 		}
@@ -526,7 +517,7 @@ impl<S: StateBackend<HashFor<B>>, B: BlockT> StateBackend<HashFor<B>> for Cachin
 		}
 		let mut cache = self.cache.shared_cache.lock();
 		if Self::is_allowed(Some(key), None, &self.cache.parent_hash, &cache.modifications) {
-			if let Some(entry) = cache.lru_hashes.get(key).map(|a| a.0.clone()) {
+			if let Some(entry) = cache.lru_hashes.get(key).map(|a| a.clone()) {
 				trace!("Found hash in shared cache: {:?}", HexDisplay::from(&key));
 				return Ok(entry)
 			}
@@ -1176,7 +1167,7 @@ mod tests {
 	#[test]
 	fn should_track_used_size_correctly() {
 		let root_parent = H256::random();
-		let shared = new_shared_cache::<Block>(109, ((109-36), 109));
+		let shared = new_shared_cache::<Block>(64 * 1024, (1, 2));
 		let h0 = H256::random();
 
 		let mut s = CachingState::new(
@@ -1194,8 +1185,8 @@ mod tests {
 			Some(0),
 			true,
 		);
-		// 32 key, 3 byte size
-		assert_eq!(shared.lock().used_storage_cache_size(), 35 /* bytes */);
+		let top_size = entry_size(&key, &Some(vec![1, 2, 3]));
+		assert_eq!(shared.lock().used_storage_cache_size(), top_size);
 
 		let key = H256::random()[..].to_vec();
 		s.cache.sync_cache(
@@ -1207,15 +1198,19 @@ mod tests {
 			Some(0),
 			true,
 		);
-		// 35 + (2 * 32) key, 2 byte size
-		assert_eq!(shared.lock().used_storage_cache_size(), 101 /* bytes */);
+		let child_size = entry_size(&(s_key, key), &Some(vec![1, 2]));
+		assert_eq!(shared.lock().used_storage_cache_size(), top_size + child_size);
 	}
 
 	#[test]
 	fn should_remove_lru_items_based_on_tracking_used_size() {
 		let root_parent = H256::random();
-		let shared = new_shared_cache::<Block>(36*3, (2,3));
 		let h0 = H256::random();
+		let key1 = H256::random()[..].to_vec();
+		let key2 = H256::random()[..].to_vec();
+		let size1 = entry_size(&key1, &Some(vec![1, 2, 3, 4]));
+		let size2 = entry_size(&key2, &Some(vec![1, 2]));
+		let shared = new_shared_cache::<Block>(size1 + size2 - 1, (0, 1));
 
 		let mut s = CachingState::new(
 			InMemoryBackend::<BlakeTwo256>::default(),
@@ -1223,31 +1218,41 @@ mod tests {
 			Some(root_parent),
 		);
 
-		let key = H256::random()[..].to_vec();
 		s.cache.sync_cache(
 			&[],
 			&[],
-			vec![(key.clone(), Some(vec![1, 2, 3, 4]))],
+			vec![(key1.clone(), Some(vec![1, 2, 3, 4]))],
 			vec![],
 			Some(h0),
 			Some(0),
 			true,
 		);
-		// 32 key, 4 byte size
-		assert_eq!(shared.lock().used_storage_cache_size(), 36 /* bytes */);
+		assert_eq!(shared.lock().used_storage_cache_size(), size1);
 
-		let key = H256::random()[..].to_vec();
 		s.cache.sync_cache(
 			&[],
 			&[],
-			vec![(key.clone(), Some(vec![1, 2]))],
+			vec![(key2.clone(), Some(vec![1, 2]))],
+			vec![],
+			Some(h0),
+			Some(0),
+			true,
+		);
+		assert_eq!(shared.lock().used_storage_cache_size(), size2);
+		assert_eq!(shared.lock().evictions(), 1);
+
+		// values larger than the whole budget are not cached
+		s.cache.sync_cache(
+			&[],
+			&[],
+			vec![(H256::random()[..].to_vec(), Some(vec![0; size1 + size2]))],
 			vec![],
 			Some(h0),
 			Some(0),
 			true,
 		);
-		// 32 key, 2 byte size
-		assert_eq!(shared.lock().used_storage_cache_size(), 34 /* bytes */);
+		assert_eq!(shared.lock().used_storage_cache_size(), size2);
+		assert_eq!(shared.lock().evictions(), 1);
 	}
 
 	#[test]
@@ -0,0 +1,108 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trie node cache shared across block executions.
+//!
+//! Trie nodes are stored by hash, so a cached node stays valid whatever the block being executed:
+//! hot keys read by consecutive blocks are found without reading their trie nodes from the
+//! database again. Missing nodes are never cached, as they may be inserted later.
+
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+use parking_lot::Mutex;
+use sp_trie::DBValue;
+use crate::storage_cache::LRUMap;
+
+/// Shared LRU cache of trie nodes, bounded by the memory used by the nodes.
+pub struct TrieNodeCache {
+	nodes: Mutex<LRUMap<Vec<u8>, DBValue>>,
+	hits: AtomicU64,
+	misses: AtomicU64,
+}
+
+impl TrieNodeCache {
+	/// Create a new cache using up to `size` bytes. A size of `0` disables the cache.
+	pub fn new(size: usize) -> Self {
+		TrieNodeCache {
+			nodes: Mutex::new(LRUMap::new(size)),
+			hits: 0.into(),
+			misses: 0.into(),
+		}
+	}
+
+	/// Get the node with the given prefixed key, fetching it with `fetch` if it isn't cached.
+	pub fn get_or_fetch<E>(
+		&self,
+		key: &[u8],
+		fetch: impl FnOnce() -> Result<Option<DBValue>, E>,
+	) -> Result<Option<DBValue>, E> {
+		if let Some(node) = self.nodes.lock().get(key) {
+			self.hits.fetch_add(1, AtomicOrdering::Relaxed);
+			return Ok(Some(node.clone()));
+		}
+
+		self.misses.fetch_add(1, AtomicOrdering::Relaxed);
+		let node = fetch()?;
+		if let Some(ref node) = node {
+			self.nodes.lock().add(key.to_vec(), node.clone());
+		}
+		Ok(node)
+	}
+
+	/// Returns the memory used by the cached nodes, in bytes.
+	pub fn used_size(&self) -> usize {
+		self.nodes.lock().used_size()
+	}
+
+	/// Returns the number of reads served from the cache and from the database since the start.
+	pub fn hits_and_misses(&self) -> (u64, u64) {
+		(self.hits.load(AtomicOrdering::Relaxed), self.misses.load(AtomicOrdering::Relaxed))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn nodes_are_fetched_once() {
+		let cache = TrieNodeCache::new(64 * 1024);
+		let fetch = || Ok::<_, ()>(Some(vec![1, 2, 3]));
+
+		assert_eq!(cache.get_or_fetch(b"node", fetch), Ok(Some(vec![1, 2, 3])));
+		assert_eq!(cache.get_or_fetch(b"node", || panic!("node is cached")), Ok(Some(vec![1, 2, 3])));
+		assert_eq!(cache.hits_and_misses(), (1, 1));
+		assert!(cache.used_size() > 0);
+	}
+
+	#[test]
+	fn missing_nodes_are_not_cached() {
+		let cache = TrieNodeCache::new(64 * 1024);
+
+		assert_eq!(cache.get_or_fetch(b"node", || Ok::<_, ()>(None)), Ok(None));
+		assert_eq!(cache.get_or_fetch(b"node", || Ok::<_, ()>(Some(vec![1]))), Ok(Some(vec![1])));
+		assert_eq!(cache.hits_and_misses(), (0, 2));
+	}
+
+	#[test]
+	fn disabled_cache_always_fetches() {
+		let cache = TrieNodeCache::new(0);
+
+		cache.get_or_fetch(b"node", || Ok::<_, ()>(Some(vec![1]))).unwrap();
+		cache.get_or_fetch(b"node", || Ok::<_, ()>(Some(vec![1]))).unwrap();
+		assert_eq!(cache.hits_and_misses(), (0, 2));
+		assert_eq!(cache.used_size(), 0);
+	}
+}
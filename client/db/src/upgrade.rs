@@ -33,20 +33,26 @@ use crate::{
 };
 
 /// Version file name.
-const VERSION_FILE_NAME: &'static str = "db_version";
+pub(crate) const VERSION_FILE_NAME: &'static str = "db_version";
 
 /// Current db version.
-const CURRENT_VERSION: u32 = 1;
+pub(crate) const CURRENT_VERSION: u32 = 2;
 
 /// Number of columns in v0.
 const V0_NUM_COLUMNS: u32 = 10;
+/// Number of columns in v1.
+const V1_NUM_COLUMNS: u32 = 11;
 
 /// Upgrade database to current version.
 pub fn upgrade_db<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_blockchain::Result<()> {
 	let db_version = current_version(db_path)?;
 	match db_version {
-		0 => migrate_0_to_1::<Block>(db_path, db_type)?,
-		1 => (),
+		0 => {
+			migrate_0_to_1::<Block>(db_path, db_type)?;
+			migrate_1_to_2::<Block>(db_path, db_type)?
+		},
+		1 => migrate_1_to_2::<Block>(db_path, db_type)?,
+		2 => (),
 		_ => Err(sp_blockchain::Error::Backend(format!("Future database version: {}", db_version)))?,
 	}
 
@@ -102,9 +108,19 @@ fn migrate_0_to_1<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_bl
 	Ok(())
 }
 
+/// Migration from version1 to version2:
+/// 1) the number of columns has changed from 11 to 12 (retained state column).
+fn migrate_1_to_2<Block: BlockT>(db_path: &Path, db_type: DatabaseType) -> sp_blockchain::Result<()> {
+	let db = open_database(db_path, db_type, V1_NUM_COLUMNS)?;
+	db.add_column().map_err(db_err)?;
+	db.flush().map_err(db_err)?;
+
+	Ok(())
+}
+
 /// Reads current database version from the file at given path.
 /// If the file does not exist returns 0.
-fn current_version(path: &Path) -> sp_blockchain::Result<u32> {
+pub(crate) fn current_version(path: &Path) -> sp_blockchain::Result<u32> {
 	let unknown_version_err = || sp_blockchain::Error::Backend("Unknown database version".into());
 
 	match fs::File::open(version_file_path(path)) {
@@ -165,7 +181,10 @@ mod tests {
 		crate::utils::open_database::<Block>(&DatabaseSettings {
 			state_cache_size: 0,
 			state_cache_child_ratio: None,
+			trie_cache_size: 0,
+			blocks_pruning: Default::default(),
 			pruning: PruningMode::ArchiveAll,
+			state_retention: Default::default(),
 			source: DatabaseSettingsSrc::Path { path: db_path.to_owned(), cache_size: None },
 		}, DatabaseType::Full).map(|_| ())
 	}
@@ -195,4 +214,18 @@ mod tests {
 			assert_eq!(current_version(db_path).unwrap(), CURRENT_VERSION);
 		}
 	}
+
+	#[test]
+	fn upgrade_from_1_to_2_works() {
+		let db_dir = tempfile::TempDir::new().unwrap();
+		let db_path = db_dir.path();
+		{
+			let db_cfg = DatabaseConfig::with_columns(V1_NUM_COLUMNS);
+			Database::open(&db_cfg, db_path.to_str().unwrap()).unwrap();
+			let mut file = fs::File::create(version_file_path(db_path)).unwrap();
+			file.write_all(b"1").unwrap();
+		}
+		open_database(db_path).unwrap();
+		assert_eq!(current_version(db_path).unwrap(), CURRENT_VERSION);
+	}
 }
@@ -36,10 +36,13 @@ use crate::{DatabaseSettings, DatabaseSettingsSrc};
 
 /// Number of columns in the db. Must be the same for both full && light dbs.
 /// Otherwise RocksDb will fail to open database && check its type.
-#[cfg(any(feature = "kvdb-rocksdb", feature = "test-helpers", test))]
-pub const NUM_COLUMNS: u32 = 11;
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db", feature = "test-helpers", test))]
+pub const NUM_COLUMNS: u32 = 12;
 /// Meta column. The set of keys in the column is shared by full && light storages.
 pub const COLUMN_META: u32 = 0;
+/// Name of the file marking a directory as a ParityDB database.
+#[cfg(any(feature = "kvdb-rocksdb", feature = "parity-db", test))]
+pub(crate) const PARITY_DB_MARKER_FILE_NAME: &str = "db_paritydb";
 
 /// Keys of entries in COLUMN_META.
 pub mod meta_keys {
@@ -214,7 +217,7 @@ pub fn db_err(err: io::Error) -> sp_blockchain::Error {
 	sp_blockchain::Error::Backend(format!("{}", err))
 }
 
-/// Open RocksDB database.
+/// Open the database of the client.
 pub fn open_database<Block: BlockT>(
 	config: &DatabaseSettings,
 	db_type: DatabaseType,
@@ -222,6 +225,13 @@ pub fn open_database<Block: BlockT>(
 	let db: Arc<dyn KeyValueDB> = match &config.source {
 		#[cfg(any(feature = "kvdb-rocksdb", test))]
 		DatabaseSettingsSrc::Path { path, cache_size } => {
+			if path.join(PARITY_DB_MARKER_FILE_NAME).exists() {
+				return Err(sp_blockchain::Error::Backend(format!(
+					"Database at {} is a ParityDB database, it can't be opened with RocksDB",
+					path.display(),
+				)));
+			}
+
 			// first upgrade database to required version
 			crate::upgrade::upgrade_db::<Block>(&path, db_type)?;
 
@@ -252,6 +262,14 @@ pub fn open_database<Block: BlockT>(
 			let msg = "Try to open RocksDB database with RocksDB disabled".into();
 			return Err(sp_blockchain::Error::Backend(msg));
 		},
+		#[cfg(feature = "parity-db")]
+		DatabaseSettingsSrc::ParityDb { path } =>
+			Arc::new(crate::paritydb::ParityDb::open(&path, NUM_COLUMNS).map_err(db_err)?),
+		#[cfg(not(feature = "parity-db"))]
+		DatabaseSettingsSrc::ParityDb { .. } => {
+			let msg = "Try to open ParityDB database with ParityDB disabled".into();
+			return Err(sp_blockchain::Error::Backend(msg));
+		},
 		DatabaseSettingsSrc::Custom(db) => db.clone(),
 	};
 
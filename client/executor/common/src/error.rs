@@ -121,6 +121,9 @@ pub enum WasmError {
 	InvalidModule,
 	/// Wasm code could not be deserialized.
 	CantDeserializeWasm,
+	/// Compressed wasm code could not be decompressed.
+	#[display(fmt="Failed to decompress the wasm code: {}", _0)]
+	CodeDecompressionFailed(String),
 	/// The module does not export a linear memory named `memory`.
 	InvalidMemory,
 	/// The number of heap pages requested is disallowed by the module.
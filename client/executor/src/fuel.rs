@@ -0,0 +1,158 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deterministic fuel metering of the wasm execution.
+//!
+//! The runtime code is instrumented to charge the fuel of each block of instructions, one unit
+//! per instruction, to the `env.gas` host function before executing it. A block execution that
+//! runs out of fuel traps, whatever the weight annotations of the runtime.
+//!
+//! Only the `METERED_METHOD` calls executed in wasm are metered: other runtime calls, e.g. the
+//! RPC queries, and native execution are not.
+
+use std::cell::Cell;
+use parity_wasm::elements::Module;
+use sc_executor_common::error::WasmError;
+use sp_wasm_interface::{Function, FunctionContext, Signature, Value, ValueType};
+
+/// The runtime method whose wasm execution is metered.
+pub const METERED_METHOD: &str = "Core_execute_block";
+
+thread_local! {
+	/// The fuel left to the runtime call executing on this thread, `None` if it isn't metered.
+	static FUEL_LEFT: Cell<Option<u64>> = Cell::new(None);
+}
+
+/// Instrument `code` to charge the fuel of its execution with the `env.gas` host function.
+pub fn instrument(code: &[u8]) -> Result<Vec<u8>, WasmError> {
+	let module: Module = parity_wasm::deserialize_buffer(code)
+		.map_err(|_| WasmError::CantDeserializeWasm)?;
+	let module = pwasm_utils::inject_gas_counter(module, &pwasm_utils::rules::Set::default())
+		.map_err(|_| WasmError::Other("Failed to inject the fuel counter".into()))?;
+
+	parity_wasm::serialize(module)
+		.map_err(|e| WasmError::Other(format!("Failed to serialize the metered module: {}", e)))
+}
+
+/// Run `f` with `limit` fuel for the wasm execution on this thread.
+///
+/// Runtime calls nested into `f`, e.g. with `CallInWasm`, share the fuel of `f`.
+pub fn with_fuel<R>(limit: u64, f: impl FnOnce() -> R) -> R {
+	/// Restores the fuel of the outer call, even if `f` panics.
+	struct Restore(Option<u64>);
+
+	impl Drop for Restore {
+		fn drop(&mut self) {
+			FUEL_LEFT.with(|fuel| fuel.set(self.0));
+		}
+	}
+
+	let outer = FUEL_LEFT.with(|fuel| fuel.get());
+	if outer.is_some() {
+		return f()
+	}
+
+	FUEL_LEFT.with(|fuel| fuel.set(Some(limit)));
+	let _restore = Restore(outer);
+	f()
+}
+
+/// The fuel left to the runtime call executing on this thread, `None` if it isn't metered.
+pub fn fuel_left() -> Option<u64> {
+	FUEL_LEFT.with(|fuel| fuel.get())
+}
+
+/// Charge `amount` fuel to the current runtime call.
+fn charge(amount: u64) -> Result<(), String> {
+	FUEL_LEFT.with(|fuel| match fuel.get() {
+		Some(left) if left < amount => {
+			fuel.set(Some(0));
+			Err("Runtime call ran out of fuel".into())
+		},
+		Some(left) => {
+			fuel.set(Some(left - amount));
+			Ok(())
+		},
+		None => Ok(()),
+	})
+}
+
+/// The `env.gas` host function called by the instrumented code.
+struct ChargeFuel;
+
+impl Function for ChargeFuel {
+	fn name(&self) -> &str {
+		"gas"
+	}
+
+	fn signature(&self) -> Signature {
+		Signature::new_with_args(&[ValueType::I32][..])
+	}
+
+	fn execute(
+		&self,
+		_: &mut dyn FunctionContext,
+		args: &mut dyn Iterator<Item = Value>,
+	) -> sp_wasm_interface::Result<Option<Value>> {
+		match args.next() {
+			Some(Value::I32(amount)) => charge(amount as u32 as u64).map(|_| None),
+			_ => Err("Invalid arguments to `gas`".into()),
+		}
+	}
+}
+
+/// The host function charging fuel, to register with the host functions of instrumented code.
+pub static CHARGE_FUEL: &'static dyn Function = &ChargeFuel;
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fuel_is_charged() {
+		assert_eq!(fuel_left(), None);
+		assert!(charge(u64::max_value()).is_ok());
+
+		with_fuel(10, || {
+			assert!(charge(4).is_ok());
+			assert_eq!(fuel_left(), Some(6));
+
+			// Nested calls share the fuel of the outer call.
+			with_fuel(100, || assert!(charge(6).is_ok()));
+			assert_eq!(fuel_left(), Some(0));
+			assert!(charge(1).is_err());
+		});
+
+		assert_eq!(fuel_left(), None);
+	}
+
+	#[test]
+	fn instrumented_code_imports_gas() {
+		let code = wabt::wat2wasm(r#"
+			(module
+				(func (export "main") (result i32)
+					i32.const 1
+					i32.const 2
+					i32.add
+				)
+			)
+		"#).unwrap();
+
+		let module: Module = parity_wasm::deserialize_buffer(&instrument(&code).unwrap()).unwrap();
+		let imports = module.import_section().expect("the gas function is imported");
+		assert!(imports.entries().iter().any(|entry| entry.module() == "env" && entry.field() == "gas"));
+	}
+}
@@ -523,6 +523,68 @@ fn should_trap_when_heap_exhausted(wasm_method: WasmExecutionMethod) {
 	).unwrap();
 }
 
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn compressed_code_should_work(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+	let code = sp_maybe_compressed_blob::compress(
+		&WASM_BINARY[..],
+		sp_maybe_compressed_blob::CODE_BLOB_BOMB_LIMIT,
+	).unwrap();
+
+	let executor = crate::WasmExecutor::new(
+		wasm_method,
+		Some(1024),
+		HostFunctions::host_functions(),
+		true,
+		8,
+	);
+
+	for code_hash in vec![None, Some(blake2_256(&code).to_vec())] {
+		assert_eq!(
+			executor.call_in_wasm(
+				&code,
+				code_hash,
+				"test_blake2_256",
+				&b"Hello world!".to_vec().encode(),
+				&mut ext.ext(),
+			).unwrap(),
+			blake2_256(&b"Hello world!"[..]).to_vec().encode(),
+		);
+	}
+}
+
+#[test_case(WasmExecutionMethod::Interpreted)]
+#[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
+fn should_fail_when_out_of_fuel(wasm_method: WasmExecutionMethod) {
+	let mut ext = TestExternalities::default();
+
+	let executor = crate::WasmExecutor::new(
+		wasm_method,
+		Some(1024),
+		HostFunctions::host_functions(),
+		true,
+		8,
+	).with_fuel_limit(Some(10));
+	let mut call = || executor.call_in_wasm(
+		&WASM_BINARY[..],
+		None,
+		"test_blake2_256",
+		&b"Hello world!".to_vec().encode(),
+		&mut ext.ext(),
+	);
+
+	// only the block execution is metered, but the code charges the fuel of any metered call.
+	assert_eq!(call().unwrap(), blake2_256(&b"Hello world!"[..]).to_vec().encode());
+	assert_eq!(
+		crate::fuel::with_fuel(1_000_000, &mut call).unwrap(),
+		blake2_256(&b"Hello world!"[..]).to_vec().encode(),
+	);
+
+	let err = crate::fuel::with_fuel(10, &mut call).unwrap_err();
+	assert!(err.contains("Runtime call ran out of fuel"), "unexpected error: {}", err);
+}
+
 #[test_case(WasmExecutionMethod::Interpreted)]
 #[cfg_attr(feature = "wasmtime", test_case(WasmExecutionMethod::Compiled))]
 fn returns_mutable_static(wasm_method: WasmExecutionMethod) {
@@ -532,6 +594,7 @@ fn returns_mutable_static(wasm_method: WasmExecutionMethod) {
 		&WASM_BINARY[..],
 		HostFunctions::host_functions(),
 		true,
+		false,
 	).expect("Creates runtime");
 
 	let instance = runtime.new_instance().unwrap();
@@ -566,6 +629,7 @@ fn restoration_of_globals(wasm_method: WasmExecutionMethod) {
 		&WASM_BINARY[..],
 		HostFunctions::host_functions(),
 		true,
+		false,
 	).expect("Creates runtime");
 	let instance = runtime.new_instance().unwrap();
 
@@ -586,6 +650,7 @@ fn heap_is_reset_between_calls(wasm_method: WasmExecutionMethod) {
 		&WASM_BINARY[..],
 		HostFunctions::host_functions(),
 		true,
+		false,
 	).expect("Creates runtime");
 	let instance = runtime.new_instance().unwrap();
 
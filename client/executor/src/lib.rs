@@ -32,6 +32,7 @@
 #[macro_use]
 mod native_executor;
 mod wasm_runtime;
+pub mod fuel;
 #[cfg(test)]
 mod integration_tests;
 
@@ -43,7 +44,7 @@ pub use codec::Codec;
 pub use sp_core::traits::{Externalities, CallInWasm};
 #[doc(hidden)]
 pub use sp_wasm_interface;
-pub use wasm_runtime::WasmExecutionMethod;
+pub use wasm_runtime::{WasmExecutionMethod, DEFAULT_RUNTIME_CACHE_SIZE};
 
 pub use sc_executor_common::{error, sandbox};
 
@@ -16,7 +16,7 @@
 
 use crate::{
 	RuntimeInfo, error::{Error, Result},
-	wasm_runtime::{RuntimeCache, WasmExecutionMethod},
+	wasm_runtime::{RuntimeCache, WasmExecutionMethod, DEFAULT_RUNTIME_CACHE_SIZE},
 };
 use sp_version::{NativeVersion, RuntimeVersion};
 use codec::{Decode, Encode};
@@ -87,6 +87,10 @@ pub struct WasmExecutor {
 	allow_missing_func_imports: bool,
 	/// The size of the instances cache.
 	max_runtime_instances: usize,
+	/// The number of runtimes kept in the runtime cache.
+	runtime_cache_size: usize,
+	/// The fuel of a runtime call, `None` if the execution isn't metered.
+	fuel_limit: Option<u64>,
 }
 
 impl WasmExecutor {
@@ -109,9 +113,53 @@ impl WasmExecutor {
 			method,
 			default_heap_pages: default_heap_pages.unwrap_or(DEFAULT_HEAP_PAGES),
 			host_functions: Arc::new(host_functions),
-			cache: Arc::new(RuntimeCache::new(max_runtime_instances)),
+			cache: Arc::new(RuntimeCache::new(max_runtime_instances, DEFAULT_RUNTIME_CACHE_SIZE)),
 			allow_missing_func_imports,
 			max_runtime_instances,
+			runtime_cache_size: DEFAULT_RUNTIME_CACHE_SIZE,
+			fuel_limit: None,
+		}
+	}
+
+	/// Keep up to `runtime_cache_size` runtimes in the runtime cache, instead of
+	/// `DEFAULT_RUNTIME_CACHE_SIZE`.
+	///
+	/// Runtimes are cached by code hash, a larger cache avoids compiling runtimes again when calls
+	/// switch between many runtime versions, e.g. `state_call` at historical blocks.
+	pub fn with_runtime_cache_size(mut self, runtime_cache_size: usize) -> Self {
+		self.runtime_cache_size = runtime_cache_size;
+		self.cache = Arc::new(RuntimeCache::new(self.max_runtime_instances, runtime_cache_size));
+		self
+	}
+
+	/// Limit the fuel of every block executed in wasm to `fuel_limit`, see `crate::fuel`.
+	///
+	/// The fuel is roughly the number of executed wasm instructions, so the limit is deterministic
+	/// unlike a time limit. A block running out of fuel fails to execute and is rejected. Native
+	/// execution isn't metered: blocks must be imported with the `AlwaysWasm` strategy.
+	pub fn with_fuel_limit(mut self, fuel_limit: Option<u64>) -> Self {
+		if self.fuel_limit.is_some() != fuel_limit.is_some() {
+			self.cache = Arc::new(
+				RuntimeCache::new(self.max_runtime_instances, self.runtime_cache_size),
+			);
+		}
+		self.fuel_limit = fuel_limit;
+		self
+	}
+
+	/// The fuel limit of a runtime call, `None` if the execution isn't metered.
+	pub fn fuel_limit(&self) -> Option<u64> {
+		self.fuel_limit
+	}
+
+	/// Run `f`, the wasm execution of `method`, with the fuel of a runtime call if the execution
+	/// of `method` is metered.
+	///
+	/// Calls nested into a metered call are always charged to it.
+	fn metered<R>(&self, method: &str, f: impl FnOnce() -> R) -> R {
+		match self.fuel_limit {
+			Some(limit) if method == crate::fuel::METERED_METHOD => crate::fuel::with_fuel(limit, f),
+			_ => f(),
 		}
 	}
 
@@ -147,6 +195,7 @@ impl WasmExecutor {
 			self.default_heap_pages,
 			&*self.host_functions,
 			self.allow_missing_func_imports,
+			self.fuel_limit.is_some(),
 			|instance, version, ext| {
 				let instance = AssertUnwindSafe(instance);
 				let ext = AssertUnwindSafe(ext);
@@ -176,10 +225,10 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 			};
 
 			self.with_instance(&code, ext, |instance, _, mut ext| {
-				with_externalities_safe(
+				self.metered(method, || with_externalities_safe(
 					&mut **ext,
 					move || instance.call(method, call_data),
-				)
+				))
 			}).map_err(|e| e.to_string())
 		} else {
 			let module = crate::wasm_runtime::create_wasm_runtime_with_code(
@@ -188,6 +237,7 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 				&wasm_code,
 				self.host_functions.to_vec(),
 				self.allow_missing_func_imports,
+				self.fuel_limit.is_some(),
 			)
 				.map_err(|e| format!("Failed to create module: {:?}", e))?;
 
@@ -197,10 +247,10 @@ impl sp_core::traits::CallInWasm for WasmExecutor {
 			let instance = AssertUnwindSafe(instance);
 			let mut ext = AssertUnwindSafe(ext);
 
-			with_externalities_safe(
+			self.metered(method, || with_externalities_safe(
 				&mut **ext,
 				move || instance.call(method, call_data),
-			)
+			))
 			.and_then(|r| r)
 			.map_err(|e| e.to_string())
 		}
@@ -250,6 +300,19 @@ impl<D: NativeExecutionDispatch> NativeExecutor<D> {
 			wasm: wasm_executor,
 		}
 	}
+
+	/// Keep up to `runtime_cache_size` runtimes in the runtime cache, see
+	/// `WasmExecutor::with_runtime_cache_size`.
+	pub fn with_runtime_cache_size(mut self, runtime_cache_size: usize) -> Self {
+		self.wasm = self.wasm.with_runtime_cache_size(runtime_cache_size);
+		self
+	}
+
+	/// Limit the fuel of every block executed in wasm, see `WasmExecutor::with_fuel_limit`.
+	pub fn with_fuel_limit(mut self, fuel_limit: Option<u64>) -> Self {
+		self.wasm = self.wasm.with_fuel_limit(fuel_limit);
+		self
+	}
 }
 
 impl<D: NativeExecutionDispatch> RuntimeInfo for NativeExecutor<D> {
@@ -307,16 +370,16 @@ impl<D: NativeExecutionDispatch + 'static> CodeExecutor for NativeExecutor<D> {
 							onchain_version,
 						);
 
-						with_externalities_safe(
+						self.wasm.metered(method, || with_externalities_safe(
 							&mut **ext,
 							move || instance.call(method, data).map(NativeOrEncoded::Encoded)
-						)
+						))
 					}
 					(false, _, _) => {
-						with_externalities_safe(
+						self.wasm.metered(method, || with_externalities_safe(
 							&mut **ext,
 							move || instance.call(method, data).map(NativeOrEncoded::Encoded)
-						)
+						))
 					},
 					(true, true, Some(call)) => {
 						trace!(
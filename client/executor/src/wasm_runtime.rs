@@ -50,6 +50,8 @@ struct VersionedRuntime {
 	module: Box<dyn WasmModule>,
 	/// The number of WebAssembly heap pages this instance was created with.
 	heap_pages: u64,
+	/// Whether the code is instrumented for fuel metering.
+	fuel_metering: bool,
 	/// Runtime version according to `Core_version` if any.
 	version: Option<RuntimeVersion>,
 	/// Cached instance pool.
@@ -123,7 +125,8 @@ impl VersionedRuntime {
 	}
 }
 
-const MAX_RUNTIMES: usize = 2;
+/// The default number of runtimes kept in the runtime cache.
+pub const DEFAULT_RUNTIME_CACHE_SIZE: usize = 2;
 
 /// Cache for the runtimes.
 ///
@@ -135,22 +138,31 @@ const MAX_RUNTIMES: usize = 2;
 /// the memory reset to the initial memory. So, one runtime instance is reused for every fetch
 /// request.
 ///
-/// The size of cache is equal to `MAX_RUNTIMES`.
+/// Runtimes are keyed by the hash of their code, so calls switching between runtime versions, e.g.
+/// `state_call` at historical blocks, only compile each version once as long as it stays in the
+/// cache. The least recently used runtime is evicted once the cache holds `runtime_cache_size`
+/// runtimes.
 pub struct RuntimeCache {
 	/// A cache of runtimes along with metadata.
 	///
 	/// Runtimes sorted by recent usage. The most recently used is at the front.
-	runtimes: Mutex<[Option<Arc<VersionedRuntime>>; MAX_RUNTIMES]>,
+	runtimes: Mutex<Vec<Arc<VersionedRuntime>>>,
 	/// The size of the instances cache for each runtime.
 	max_runtime_instances: usize,
+	/// The number of runtimes kept in the cache.
+	runtime_cache_size: usize,
 }
 
 impl RuntimeCache {
 	/// Creates a new instance of a runtimes cache.
-	pub fn new(max_runtime_instances: usize) -> RuntimeCache {
+	///
+	/// `runtime_cache_size` is the number of runtimes kept in the cache, at least one.
+	pub fn new(max_runtime_instances: usize, runtime_cache_size: usize) -> RuntimeCache {
+		let runtime_cache_size = runtime_cache_size.max(1);
 		RuntimeCache {
-			runtimes: Default::default(),
+			runtimes: Mutex::new(Vec::with_capacity(runtime_cache_size)),
 			max_runtime_instances,
+			runtime_cache_size,
 		}
 	}
 
@@ -171,6 +183,8 @@ impl RuntimeCache {
 	///
 	/// `allow_missing_func_imports` - Ignore missing function imports.
 	///
+	/// `fuel_metering` - Instrument the code for fuel metering, see `crate::fuel`.
+	///
 	/// `max_runtime_instances` - The size of the instances cache.
 	///
 	/// `f` - Function to execute.
@@ -190,6 +204,7 @@ impl RuntimeCache {
 		default_heap_pages: u64,
 		host_functions: &[&'static dyn Function],
 		allow_missing_func_imports: bool,
+		fuel_metering: bool,
 		f: F,
 	) -> Result<Result<R, Error>, Error>
 		where F: FnOnce(
@@ -202,17 +217,14 @@ impl RuntimeCache {
 		let heap_pages = runtime_code.heap_pages.unwrap_or(default_heap_pages);
 
 		let mut runtimes = self.runtimes.lock(); // this must be released prior to calling f
-		let pos = runtimes.iter().position(|r| r.as_ref().map_or(
-			false,
-			|r| r.wasm_method == wasm_method &&
-				r.code_hash == *code_hash &&
-				r.heap_pages == heap_pages
-		));
+		let pos = runtimes.iter().position(|r| r.wasm_method == wasm_method &&
+			r.code_hash == *code_hash &&
+			r.heap_pages == heap_pages &&
+			r.fuel_metering == fuel_metering
+		);
 
 		let runtime = match pos {
-			Some(n) => runtimes[n]
-				.clone()
-				.expect("`position` only returns `Some` for entries that are `Some`"),
+			Some(n) => runtimes.remove(n),
 			None =>  {
 				let code = runtime_code.fetch_runtime_code().ok_or(WasmError::CodeNotFound)?;
 
@@ -224,6 +236,7 @@ impl RuntimeCache {
 					heap_pages,
 					host_functions.into(),
 					allow_missing_func_imports,
+					fuel_metering,
 					self.max_runtime_instances,
 				);
 				if let Err(ref err) = result {
@@ -234,34 +247,40 @@ impl RuntimeCache {
 		};
 
 		// Rearrange runtimes by last recently used.
-		match pos {
-			Some(0) => {},
-			Some(n) => {
-				for i in (1 .. n + 1).rev() {
-					runtimes.swap(i, i - 1);
-				}
-			}
-			None => {
-				runtimes[MAX_RUNTIMES-1] = Some(runtime.clone());
-				for i in (1 .. MAX_RUNTIMES).rev() {
-					runtimes.swap(i, i - 1);
-				}
-			}
-		}
+		runtimes.insert(0, runtime.clone());
+		runtimes.truncate(self.runtime_cache_size);
 		drop(runtimes);
 
 		Ok(runtime.with_instance(ext, f))
 	}
 }
 
-/// Create a wasm runtime with the given `code`.
+/// Create a wasm runtime with the given `code`, which may be compressed.
+///
+/// With `fuel_metering`, the code is instrumented to charge the fuel of its execution, see
+/// `crate::fuel`.
 pub fn create_wasm_runtime_with_code(
 	wasm_method: WasmExecutionMethod,
 	heap_pages: u64,
 	code: &[u8],
-	host_functions: Vec<&'static dyn Function>,
+	mut host_functions: Vec<&'static dyn Function>,
 	allow_missing_func_imports: bool,
+	fuel_metering: bool,
 ) -> Result<Box<dyn WasmModule>, WasmError> {
+	let code = sp_maybe_compressed_blob::decompress(
+		code,
+		sp_maybe_compressed_blob::CODE_BLOB_BOMB_LIMIT,
+	).map_err(|e| WasmError::CodeDecompressionFailed(e.to_string()))?;
+
+	let metered_code;
+	let code = if fuel_metering {
+		metered_code = crate::fuel::instrument(&code)?;
+		host_functions.push(crate::fuel::CHARGE_FUEL);
+		&metered_code[..]
+	} else {
+		&code[..]
+	};
+
 	match wasm_method {
 		WasmExecutionMethod::Interpreted =>
 			sc_executor_wasmi::create_runtime(
@@ -289,6 +308,7 @@ fn create_versioned_wasm_runtime(
 	heap_pages: u64,
 	host_functions: Vec<&'static dyn Function>,
 	allow_missing_func_imports: bool,
+	fuel_metering: bool,
 	max_instances: usize,
 ) -> Result<VersionedRuntime, WasmError> {
 	#[cfg(not(target_os = "unknown"))]
@@ -299,6 +319,7 @@ fn create_versioned_wasm_runtime(
 		&code,
 		host_functions,
 		allow_missing_func_imports,
+		fuel_metering,
 	)?;
 
 	// Call to determine runtime version.
@@ -337,6 +358,7 @@ fn create_versioned_wasm_runtime(
 		module: runtime,
 		version,
 		heap_pages,
+		fuel_metering,
 		wasm_method,
 		instances,
 	})
@@ -0,0 +1,267 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC api for GRANDPA.
+
+#![warn(missing_docs)]
+
+use std::sync::Arc;
+
+use codec::Encode;
+use finality_grandpa::BlockNumberOps;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sc_client_api::backend::Backend;
+use sc_finality_grandpa::{
+	BlockFinalityProof, SharedVoterState, VoterStateReport, CurrentRoundReport,
+	CompletedRoundReport,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+
+/// Error code of a failure to generate a finality proof.
+const FINALITY_PROOF_ERROR: i64 = 1;
+
+/// Provides rpc methods for interacting with GRANDPA.
+#[rpc]
+pub trait GrandpaApi<Hash, Number> {
+	/// Returns the state of the voter as last recorded by the finality stall watchdog: the
+	/// rounds the voter is live in and its votes in them, whether finality is stalled and the
+	/// estimates and votes per authority of the last completed rounds. Returns `null` if the
+	/// voter isn't running.
+	#[rpc(name = "grandpa_voterState")]
+	fn voter_state(&self) -> Result<Option<VoterState<Hash, Number>>>;
+
+	/// Returns the SCALE encoded proof of finality of the given block, verifiable from the
+	/// authority set `authority_set_id` (the genesis set if omitted): the justified headers
+	/// enacting the authority set changes since that set, the headers from the block to the
+	/// first justified block of its set, and that block's justification. Returns `null` if
+	/// the block isn't provably final yet.
+	#[rpc(name = "grandpa_proveFinality")]
+	fn prove_finality(&self, block: Hash, authority_set_id: Option<u64>) -> Result<Option<Bytes>>;
+}
+
+/// A block hash and number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockRef<Hash, Number> {
+	/// The block hash.
+	pub hash: Hash,
+	/// The block number.
+	pub number: Number,
+}
+
+impl<Hash, Number> From<(Hash, Number)> for BlockRef<Hash, Number> {
+	fn from((hash, number): (Hash, Number)) -> Self {
+		BlockRef { hash, number }
+	}
+}
+
+/// The votes of an authority in a completed round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityVotes {
+	/// The authority, SS58 encoded.
+	pub authority: String,
+	/// Number of prevotes of the authority.
+	pub prevotes: u32,
+	/// Number of precommits of the authority.
+	pub precommits: u32,
+}
+
+/// The votes cast by the voter in a round it is live in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentRoundState<Hash, Number> {
+	/// The round number.
+	pub round: u64,
+	/// The block proposed by the voter as primary.
+	pub proposed: Option<BlockRef<Hash, Number>>,
+	/// The block prevoted for by the voter.
+	pub prevoted: Option<BlockRef<Hash, Number>>,
+	/// The block precommitted to by the voter.
+	pub precommitted: Option<BlockRef<Hash, Number>>,
+}
+
+/// The state of a completed round.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundState<Hash, Number> {
+	/// The round number.
+	pub round: u64,
+	/// The prevote-GHOST of the round.
+	pub prevote_ghost: Option<BlockRef<Hash, Number>>,
+	/// The estimate of the round.
+	pub estimate: Option<BlockRef<Hash, Number>>,
+	/// The block finalized in the round.
+	pub finalized: Option<BlockRef<Hash, Number>>,
+	/// Whether the round is completable.
+	pub completable: bool,
+	/// The votes observed in the round, per authority.
+	pub votes: Vec<AuthorityVotes>,
+}
+
+/// The state of the GRANDPA voter.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoterState<Hash, Number> {
+	/// The current authority set id.
+	pub set_id: u64,
+	/// The highest round the voter is live in, `null` if the voter is paused.
+	pub current_round: Option<u64>,
+	/// The rounds the voter is live in, oldest first.
+	pub current_rounds: Vec<CurrentRoundState<Hash, Number>>,
+	/// The last finalized block.
+	pub finalized: BlockRef<Hash, Number>,
+	/// Number of rounds since the last finalized block.
+	pub rounds_since_finalized: u64,
+	/// Whether finality is stalled.
+	pub stalled: bool,
+	/// Number of voter restarts because of stalls.
+	pub restarts: u32,
+	/// The last completed rounds, oldest first.
+	pub completed_rounds: Vec<RoundState<Hash, Number>>,
+}
+
+impl<Block: BlockT> From<CurrentRoundReport<Block>> for CurrentRoundState<Block::Hash, NumberFor<Block>> {
+	fn from(report: CurrentRoundReport<Block>) -> Self {
+		CurrentRoundState {
+			round: report.number,
+			proposed: report.proposed.map(Into::into),
+			prevoted: report.prevoted.map(Into::into),
+			precommitted: report.precommitted.map(Into::into),
+		}
+	}
+}
+
+impl<Block: BlockT> From<CompletedRoundReport<Block>> for RoundState<Block::Hash, NumberFor<Block>> {
+	fn from(report: CompletedRoundReport<Block>) -> Self {
+		RoundState {
+			round: report.number,
+			prevote_ghost: report.prevote_ghost.map(Into::into),
+			estimate: report.estimate.map(Into::into),
+			finalized: report.finalized.map(Into::into),
+			completable: report.completable,
+			votes: report.votes.into_iter().map(|votes| AuthorityVotes {
+				authority: votes.authority.to_string(),
+				prevotes: votes.prevotes,
+				precommits: votes.precommits,
+			}).collect(),
+		}
+	}
+}
+
+impl<Block: BlockT> From<VoterStateReport<Block>> for VoterState<Block::Hash, NumberFor<Block>> {
+	fn from(report: VoterStateReport<Block>) -> Self {
+		VoterState {
+			set_id: report.set_id,
+			current_round: report.current_round,
+			current_rounds: report.current_rounds.into_iter().map(Into::into).collect(),
+			finalized: report.finalized.into(),
+			rounds_since_finalized: report.rounds_since_finalized,
+			stalled: report.stalled,
+			restarts: report.restarts,
+			completed_rounds: report.completed_rounds.into_iter().map(Into::into).collect(),
+		}
+	}
+}
+
+/// Implements the GrandpaApi RPC trait for interacting with GRANDPA.
+pub struct GrandpaRpcHandler<Block: BlockT, BE> {
+	shared_voter_state: SharedVoterState<Block>,
+	backend: Arc<BE>,
+}
+
+impl<Block: BlockT, BE> GrandpaRpcHandler<Block, BE> {
+	/// Creates a new GRANDPA RPC handler reading the state recorded by the voter and proving
+	/// finality from the given backend.
+	pub fn new(shared_voter_state: SharedVoterState<Block>, backend: Arc<BE>) -> Self {
+		GrandpaRpcHandler { shared_voter_state, backend }
+	}
+}
+
+impl<Block, BE> GrandpaApi<Block::Hash, NumberFor<Block>> for GrandpaRpcHandler<Block, BE> where
+	Block: BlockT,
+	NumberFor<Block>: BlockNumberOps,
+	BE: Backend<Block> + Send + Sync + 'static,
+{
+	fn voter_state(&self) -> Result<Option<VoterState<Block::Hash, NumberFor<Block>>>> {
+		Ok(self.shared_voter_state.get().map(Into::into))
+	}
+
+	fn prove_finality(
+		&self,
+		block: Block::Hash,
+		authority_set_id: Option<u64>,
+	) -> Result<Option<Bytes>> {
+		BlockFinalityProof::<Block>::generate(&*self.backend, block, authority_set_id.unwrap_or(0))
+			.map(|proof| proof.map(|proof| proof.encode().into()))
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(FINALITY_PROOF_ERROR),
+				message: "Unable to prove finality.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::IoHandler;
+	use sp_core::H256;
+	use substrate_test_runtime_client::{Backend, runtime::Block};
+
+	#[test]
+	fn voter_state_is_null_until_recorded() {
+		let mut io = IoHandler::new();
+		io.extend_with(GrandpaApi::to_delegate(
+			GrandpaRpcHandler::<Block, _>::new(
+				SharedVoterState::empty(),
+				Arc::new(Backend::new_test(1, 0)),
+			),
+		));
+
+		let request = r#"{"jsonrpc":"2.0","method":"grandpa_voterState","params":[],"id":1}"#;
+		let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+		assert_eq!(Some(response.into()), io.handle_request_sync(request));
+	}
+
+	#[test]
+	fn voter_state_serializes_in_camel_case() {
+		let state = VoterState::<H256, u64> {
+			set_id: 1,
+			current_round: Some(12),
+			current_rounds: vec![CurrentRoundState {
+				round: 12,
+				proposed: None,
+				prevoted: Some((H256::zero(), 11).into()),
+				precommitted: None,
+			}],
+			finalized: (H256::zero(), 10).into(),
+			rounds_since_finalized: 2,
+			stalled: false,
+			restarts: 0,
+			completed_rounds: Vec::new(),
+		};
+
+		let json = serde_json::to_value(&state).unwrap();
+		assert_eq!(json["currentRound"], 12);
+		assert_eq!(json["currentRounds"][0]["prevoted"]["number"], 11);
+		assert_eq!(json["finalized"]["number"], 10);
+		assert_eq!(json["roundsSinceFinalized"], 2);
+	}
+}
@@ -19,7 +19,7 @@
 use std::fmt::Debug;
 use std::sync::Arc;
 use parity_scale_codec::{Encode, Decode};
-use sc_client_api::backend::AuxStore;
+use sc_client_api::backend::{AuxStore, authority_set_change_aux_key};
 use sp_blockchain::{Result as ClientResult, Error as ClientError};
 use fork_tree::ForkTree;
 use finality_grandpa::round::State as RoundState;
@@ -39,6 +39,7 @@ const SET_STATE_KEY: &[u8] = b"grandpa_completed_round";
 const CONCLUDED_ROUNDS: &[u8] = b"grandpa_concluded_rounds";
 const AUTHORITY_SET_KEY: &[u8] = b"grandpa_voters";
 const CONSENSUS_CHANGES_KEY: &[u8] = b"grandpa_consensus_changes";
+const AUTHORITY_SET_CHANGE_PREFIX: &[u8] = b"grandpa_set_change";
 
 const CURRENT_VERSION: u32 = 2;
 
@@ -360,17 +361,40 @@ pub(crate) fn load_persistent<Block: BlockT, B, G>(
 	})
 }
 
+fn authority_set_change_key(set_id: SetId) -> Vec<u8> {
+	let mut key = AUTHORITY_SET_CHANGE_PREFIX.to_vec();
+	set_id.using_encoded(|s| key.extend(s));
+	key
+}
+
+/// Load the number of the block that ended the authority set with the given id, if that set
+/// change was recorded.
+pub(crate) fn load_authority_set_change<Block: BlockT, B: AuxStore>(
+	backend: &B,
+	set_id: SetId,
+) -> ClientResult<Option<NumberFor<Block>>> {
+	load_decode(backend, &authority_set_change_key(set_id))
+}
+
+/// Load the id of the current authority set.
+pub(crate) fn load_current_set_id<B: AuxStore, H: Decode, N: Decode>(
+	backend: &B,
+) -> ClientResult<Option<SetId>> {
+	Ok(load_decode::<_, AuthoritySet<H, N>>(backend, AUTHORITY_SET_KEY)?.map(|set| set.set_id))
+}
+
 /// Update the authority set on disk after a change.
 ///
 /// If there has just been a handoff, pass a `new_set` parameter that describes the
 /// handoff. `set` in all cases should reflect the current authority set, with all
-/// changes and handoffs applied.
+/// changes and handoffs applied. Handoffs are also recorded in an index of the blocks
+/// ending each set, used to build warp sync proofs.
 pub(crate) fn update_authority_set<Block: BlockT, F, R>(
 	set: &AuthoritySet<Block::Hash, NumberFor<Block>>,
 	new_set: Option<&NewAuthoritySet<Block::Hash, NumberFor<Block>>>,
 	write_aux: F
 ) -> R where
-	F: FnOnce(&[(&'static [u8], &[u8])]) -> R,
+	F: FnOnce(&[(&[u8], &[u8])]) -> R,
 {
 	// write new authority set state to disk.
 	let encoded_set = set.encode();
@@ -385,16 +409,38 @@ pub(crate) fn update_authority_set<Block: BlockT, F, R>(
 			(new_set.canon_hash, new_set.canon_number),
 		);
 		let encoded = set_state.encode();
+		let set_change_key = authority_set_change_key(new_set.set_id.saturating_sub(1));
+		let encoded_set_change = new_set.canon_number.encode();
+		// marks the block enacting the change, whose justification is kept by justification
+		// pruning.
+		let enacted_key = authority_set_change_aux_key(new_set.canon_number);
+		let encoded_set_id = new_set.set_id.encode();
 
 		write_aux(&[
 			(AUTHORITY_SET_KEY, &encoded_set[..]),
 			(SET_STATE_KEY, &encoded[..]),
+			(&set_change_key[..], &encoded_set_change[..]),
+			(&enacted_key[..], &encoded_set_id[..]),
 		])
 	} else {
 		write_aux(&[(AUTHORITY_SET_KEY, &encoded_set[..])])
 	}
 }
 
+/// The aux entries of the authority set reached by a warp sync, and of a blank voter set state
+/// based on the warp synced block.
+pub(crate) fn warp_sync_aux<Block: BlockT>(
+	set: &AuthoritySet<Block::Hash, NumberFor<Block>>,
+	base: (Block::Hash, NumberFor<Block>),
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+	let set_state = VoterSetState::<Block>::live(set.set_id, set, base);
+
+	vec![
+		(AUTHORITY_SET_KEY.to_vec(), set.encode()),
+		(SET_STATE_KEY.to_vec(), set_state.encode()),
+	]
+}
+
 /// Write voter set state.
 pub(crate) fn write_voter_set_state<Block: BlockT, B: AuxStore>(
 	backend: &B,
@@ -646,4 +692,37 @@ mod test {
 			Some(completed_round),
 		);
 	}
+
+	#[test]
+	fn update_authority_set_records_set_changes() {
+		type Block = substrate_test_runtime_client::runtime::Block;
+
+		let client = substrate_test_runtime_client::new();
+		let authorities = vec![(AuthorityId::default(), 1)];
+		let new_set = NewAuthoritySet {
+			canon_hash: H256::random(),
+			canon_number: 42,
+			set_id: 3,
+			authorities: authorities.clone(),
+		};
+
+		update_authority_set::<Block, _, _>(
+			&AuthoritySet::genesis(authorities.clone()),
+			None,
+			|insert| client.insert_aux(insert, &[]),
+		).unwrap();
+		assert_eq!(load_authority_set_change::<Block, _>(&client, 2).unwrap(), None);
+
+		update_authority_set::<Block, _, _>(
+			&AuthoritySet::genesis(authorities),
+			Some(&new_set),
+			|insert| client.insert_aux(insert, &[]),
+		).unwrap();
+		assert_eq!(load_authority_set_change::<Block, _>(&client, 2).unwrap(), Some(42));
+		assert_eq!(load_authority_set_change::<Block, _>(&client, 3).unwrap(), None);
+		assert_eq!(
+			client.get_aux(&authority_set_change_aux_key(42u64)).unwrap(),
+			Some(3u64.encode()),
+		);
+	}
 }
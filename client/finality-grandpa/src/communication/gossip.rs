@@ -1026,6 +1026,38 @@ impl<Block: BlockT> Inner<Block> {
 		(catch_up, report)
 	}
 
+	/// Forget any pending catch-up request and ask the peer furthest ahead of us in the current
+	/// set for a catch-up, regardless of `CATCH_UP_THRESHOLD`.
+	fn fresh_catch_up(&mut self) -> Option<(PeerId, GossipMessage<Block>)> {
+		self.pending_catch_up = PendingCatchUp::None;
+
+		let (who, request) = {
+			let local_view = self.local_view.as_ref()?;
+			let catch_up_config = &self.catch_up_config;
+			let (who, peer) = self.peers.inner.iter()
+				.filter(|(_, peer)| catch_up_config.request_allowed(peer) &&
+					peer.view.set_id == local_view.set_id &&
+					peer.view.round > local_view.round
+				)
+				.max_by_key(|(_, peer)| peer.view.round)?;
+
+			let request = CatchUpRequestMessage {
+				set_id: peer.view.set_id,
+				round: Round(peer.view.round.0 - 1), // peer.view.round is > 0
+			};
+
+			(who.clone(), request)
+		};
+
+		trace!(target: "afg", "Sending fresh catch-up request for round {} to {}",
+			request.round.0,
+			who,
+		);
+
+		let _ = self.note_catch_up_request(&who, &request);
+		Some((who, GossipMessage::<Block>::CatchUpRequest(request)))
+	}
+
 	fn import_neighbor_message(&mut self, who: &PeerId, update: NeighborPacket<NumberFor<Block>>)
 		-> (Vec<Block::Hash>, Action<Block::Hash>, Option<GossipMessage<Block>>, Option<Report>)
 	{
@@ -1287,6 +1319,12 @@ impl<Block: BlockT> GossipValidator<Block> {
 		}
 	}
 
+	/// Drop any pending catch-up request and build a new one for the peer furthest ahead of us
+	/// in the current set, if any.
+	pub(super) fn fresh_catch_up(&self) -> Option<(PeerId, GossipMessage<Block>)> {
+		self.inner.write().fresh_catch_up()
+	}
+
 	/// Note that we've imported a commit finalizing a given block.
 	pub(super) fn note_commit_finalized<F>(
 		&self,
@@ -2093,6 +2131,53 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn fresh_catch_up_requested_from_peer_furthest_ahead() {
+		let (val, _) = GossipValidator::<Block>::new(
+			config(),
+			voter_set_state(),
+			None,
+		);
+
+		// the validator starts at set id 1 and we're at round 41.
+		val.note_set(SetId(1), Vec::new(), |_, _| {});
+		val.note_round(Round(41), |_, _| {});
+
+		// no peer is ahead of us.
+		assert!(val.fresh_catch_up().is_none());
+
+		let import_neighbor_message = |peer: &PeerId, round| {
+			val.inner.write().peers.new_peer(peer.clone(), Roles::AUTHORITY);
+			val.inner.write().import_neighbor_message(
+				peer,
+				NeighborPacket {
+					round: Round(round),
+					set_id: SetId(1),
+					commit_finalized_height: 42,
+				},
+			);
+		};
+
+		// both peers are within CATCH_UP_THRESHOLD.
+		let peer1 = PeerId::random();
+		let peer2 = PeerId::random();
+		import_neighbor_message(&peer1, 42);
+		import_neighbor_message(&peer2, 43);
+
+		// a fresh catch up is requested from the peer furthest ahead anyway,
+		// and again even though the previous request is pending.
+		for _ in 0..2 {
+			match val.fresh_catch_up() {
+				Some((who, GossipMessage::CatchUpRequest(request))) => {
+					assert_eq!(who, peer2);
+					assert_eq!(request.set_id, SetId(1));
+					assert_eq!(request.round, Round(42));
+				},
+				_ => panic!("expected catch up message"),
+			}
+		}
+	}
+
 	#[test]
 	fn doesnt_send_catch_up_requests_when_disabled() {
 		// we create a gossip validator with catch up requests disabled.
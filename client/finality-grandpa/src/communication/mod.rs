@@ -267,6 +267,18 @@ impl<B: BlockT, N: Network<B>> NetworkBridge<B, N> {
 		);
 	}
 
+	/// Ask the peer furthest ahead of us in the current set for a catch-up, dropping any pending
+	/// catch-up request. Used to recover from finality stalls.
+	pub(crate) fn request_catch_up(&self) {
+		match self.validator.fresh_catch_up() {
+			Some((who, message)) => {
+				debug!(target: "afg", "Requesting a fresh catch-up from {}", who);
+				self.gossip_engine.lock().send_message(vec![who], message.encode());
+			},
+			None => debug!(target: "afg", "No peer ahead of us to request a catch-up from"),
+		}
+	}
+
 	/// Get a stream of signature-checked round messages from the network as well as a sink for round messages to the
 	/// network all within the current set.
 	pub(crate) fn round_communication(
@@ -54,7 +54,10 @@ use crate::consensus_changes::SharedConsensusChanges;
 use crate::justification::GrandpaJustification;
 use crate::until_imported::UntilVoteTargetImported;
 use crate::voting_rule::VotingRule;
-use sp_finality_grandpa::{AuthorityId, AuthoritySignature, SetId, RoundNumber};
+use sp_api::ProvideRuntimeApi;
+use sp_finality_grandpa::{
+	AuthorityId, AuthoritySignature, EquivocationProof, GrandpaApi, SetId, RoundNumber,
+};
 use prometheus_endpoint::{Gauge, U64, register, PrometheusError};
 
 type HistoricalVotes<Block> = finality_grandpa::HistoricalVotes<
@@ -573,6 +576,48 @@ pub(crate) fn ancestry<Block: BlockT, Client>(
 	Ok(tree_route.retracted().iter().skip(1).map(|e| e.hash).collect())
 }
 
+impl<B, Block: BlockT, C, N, SC, VR> Environment<B, Block, C, N, SC, VR>
+where
+	B: Backend<Block>,
+	C: crate::ClientForGrandpa<Block, B>,
+	C::Api: GrandpaApi<Block, Error = ClientError>,
+	N: NetworkT<Block>,
+	SC: SelectChain<Block>,
+{
+	/// Report the given equivocation through the runtime, which submits an unsigned extrinsic
+	/// with the equivocation proof and the key ownership proof of the offender, in a session of
+	/// the set of the equivocation, to the transaction pool.
+	fn report_equivocation(
+		&self,
+		equivocation: sp_finality_grandpa::Equivocation<Block::Hash, NumberFor<Block>>,
+	) -> Result<(), Error> {
+		let best_header = self.select_chain.best_chain()
+			.map_err(|e| Error::Blockchain(e.to_string()))?;
+		let at = BlockId::Hash(best_header.hash());
+		let offender = equivocation.offender().clone();
+
+		let key_owner_proof = match self.client.runtime_api()
+			.generate_key_ownership_proof(&at, self.set_id, offender.clone())?
+		{
+			Some(proof) => proof,
+			None => {
+				debug!(target: "afg", "Equivocation offender {:?} isn't part of the authority set", offender);
+				return Ok(());
+			},
+		};
+
+		let equivocation_proof = EquivocationProof::new(self.set_id, equivocation);
+		match self.client.runtime_api()
+			.submit_report_equivocation_unsigned_extrinsic(&at, equivocation_proof, key_owner_proof)?
+		{
+			Some(()) => info!(target: "afg", "Submitted report of equivocation by {:?}", offender),
+			None => warn!(target: "afg", "Failed to submit report of equivocation by {:?}", offender),
+		}
+
+		Ok(())
+	}
+}
+
 impl<B, Block: BlockT, C, N, SC, VR>
 	voter::Environment<Block::Hash, NumberFor<Block>>
 for Environment<B, Block, C, N, SC, VR>
@@ -580,6 +625,7 @@ where
 	Block: 'static,
 	B: Backend<Block>,
 	C: crate::ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = ClientError>,
  	N: NetworkT<Block> + 'static + Send,
 	SC: SelectChain<Block> + 'static,
 	VR: VotingRule<Block, C>,
@@ -927,7 +973,9 @@ where
 		equivocation: ::finality_grandpa::Equivocation<Self::Id, Prevote<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected prevote equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet; this could craft misbehavior reports of some kind.
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting prevote equivocation: {:?}", err);
+		}
 	}
 
 	fn precommit_equivocation(
@@ -936,7 +984,9 @@ where
 		equivocation: Equivocation<Self::Id, Precommit<Block>, Self::Signature>
 	) {
 		warn!(target: "afg", "Detected precommit equivocation in the finality worker: {:?}", equivocation);
-		// nothing yet
+		if let Err(err) = self.report_equivocation(equivocation.into()) {
+			warn!(target: "afg", "Error reporting precommit equivocation: {:?}", err);
+		}
 	}
 }
 
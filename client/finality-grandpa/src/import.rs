@@ -42,6 +42,7 @@ use crate::authorities::{AuthoritySet, SharedAuthoritySet, DelayKind, PendingCha
 use crate::consensus_changes::SharedConsensusChanges;
 use crate::environment::finalize_block;
 use crate::justification::GrandpaJustification;
+use crate::warp_proof::GrandpaWarpSyncProvider;
 use std::marker::PhantomData;
 
 /// A block-import handler for GRANDPA.
@@ -175,7 +176,7 @@ impl<'a, Block: 'a + BlockT> Drop for PendingSetChanges<'a, Block> {
 	}
 }
 
-fn find_scheduled_change<B: BlockT>(header: &B::Header)
+pub(crate) fn find_scheduled_change<B: BlockT>(header: &B::Header)
 	-> Option<ScheduledChange<NumberFor<B>>>
 {
 	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
@@ -593,6 +594,11 @@ where
 	Client: crate::ClientForGrandpa<Block, BE>,
 	NumberFor<Block>: finality_grandpa::BlockNumberOps,
 {
+	/// A provider serving warp sync proofs of the chain finalized through this block import,
+	/// and warp syncing the chain while it only has the genesis block.
+	pub fn warp_sync_provider(&self, backend: Arc<BE>) -> GrandpaWarpSyncProvider<Block, BE> {
+		GrandpaWarpSyncProvider::new(backend, self.authority_set.clone(), self.send_voter_commands.clone())
+	}
 
 	/// Import a block justification and finalize the block.
 	///
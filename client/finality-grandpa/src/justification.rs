@@ -14,19 +14,17 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use sp_blockchain::{Error as ClientError, HeaderBackend};
 use parity_scale_codec::{Encode, Decode};
 use finality_grandpa::voter_set::VoterSet;
-use finality_grandpa::{Error as GrandpaError};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{NumberFor, Block as BlockT, Header as HeaderT};
 use sp_finality_grandpa::AuthorityId;
 
 use crate::{Commit, Error};
-use crate::communication;
 
 /// A GRANDPA justification for block finality, it includes a commit message and
 /// an ancestry proof including all headers routing all precommit target blocks
@@ -113,111 +111,14 @@ impl<Block: BlockT> GrandpaJustification<Block> {
 	where
 		NumberFor<Block>: finality_grandpa::BlockNumberOps,
 	{
-		use finality_grandpa::Chain;
-
-		let ancestry_chain = AncestryChain::<Block>::new(&self.votes_ancestries);
-
-		match finality_grandpa::validate_commit(
+		plug_light::verify_commit::<Block::Header>(
+			self.round,
 			&self.commit,
-			voters,
-			&ancestry_chain,
-		) {
-			Ok(ref result) if result.ghost().is_some() => {},
-			_ => {
-				let msg = "invalid commit in grandpa justification".to_string();
-				return Err(ClientError::BadJustification(msg));
-			}
-		}
-
-		let mut buf = Vec::new();
-		let mut visited_hashes = HashSet::new();
-		for signed in self.commit.precommits.iter() {
-			if let Err(_) = communication::check_message_sig_with_buffer::<Block>(
-				&finality_grandpa::Message::Precommit(signed.precommit.clone()),
-				&signed.id,
-				&signed.signature,
-				self.round,
-				set_id,
-				&mut buf,
-			) {
-				return Err(ClientError::BadJustification(
-					"invalid signature for precommit in grandpa justification".to_string()).into());
-			}
-
-			if self.commit.target_hash == signed.precommit.target_hash {
-				continue;
-			}
-
-			match ancestry_chain.ancestry(self.commit.target_hash, signed.precommit.target_hash) {
-				Ok(route) => {
-					// ancestry starts from parent hash but the precommit target hash has been visited
-					visited_hashes.insert(signed.precommit.target_hash);
-					for hash in route {
-						visited_hashes.insert(hash);
-					}
-				},
-				_ => {
-					return Err(ClientError::BadJustification(
-						"invalid precommit ancestry proof in grandpa justification".to_string()).into());
-				},
-			}
-		}
-
-		let ancestry_hashes = self.votes_ancestries
-			.iter()
-			.map(|h: &Block::Header| h.hash())
-			.collect();
-
-		if visited_hashes != ancestry_hashes {
-			return Err(ClientError::BadJustification(
-				"invalid precommit ancestries in grandpa justification with unused headers".to_string()).into());
-		}
-
-		Ok(())
-	}
-}
-
-/// A utility trait implementing `finality_grandpa::Chain` using a given set of headers.
-/// This is useful when validating commits, using the given set of headers to
-/// verify a valid ancestry route to the target commit block.
-struct AncestryChain<Block: BlockT> {
-	ancestry: HashMap<Block::Hash, Block::Header>,
-}
-
-impl<Block: BlockT> AncestryChain<Block> {
-	fn new(ancestry: &[Block::Header]) -> AncestryChain<Block> {
-		let ancestry: HashMap<_, _> = ancestry
-			.iter()
-			.cloned()
-			.map(|h: Block::Header| (h.hash(), h))
-			.collect();
-
-		AncestryChain { ancestry }
-	}
-}
-
-impl<Block: BlockT> finality_grandpa::Chain<Block::Hash, NumberFor<Block>> for AncestryChain<Block> where
-	NumberFor<Block>: finality_grandpa::BlockNumberOps
-{
-	fn ancestry(&self, base: Block::Hash, block: Block::Hash) -> Result<Vec<Block::Hash>, GrandpaError> {
-		let mut route = Vec::new();
-		let mut current_hash = block;
-		loop {
-			if current_hash == base { break; }
-			match self.ancestry.get(&current_hash) {
-				Some(current_header) => {
-					current_hash = *current_header.parent_hash();
-					route.push(current_hash);
-				},
-				_ => return Err(GrandpaError::NotDescendent),
-			}
-		}
-		route.pop(); // remove the base
-
-		Ok(route)
-	}
-
-	fn best_chain_containing(&self, _block: Block::Hash) -> Option<(Block::Hash, NumberFor<Block>)> {
-		None
+			&self.votes_ancestries,
+			set_id,
+			voters.voters(),
+		).map_err(|err| ClientError::BadJustification(
+			format!("invalid commit in grandpa justification: {:?}", err)
+		))
 	}
 }
@@ -93,12 +93,19 @@ mod import;
 mod justification;
 mod light_import;
 mod observer;
+mod stall;
 mod until_imported;
 mod voting_rule;
+mod warp_proof;
 
 pub use finality_proof::{FinalityProofProvider, StorageAndProofProvider};
 pub use justification::GrandpaJustification;
 pub use light_import::light_block_import;
+pub use stall::{
+	StallDetection, SharedVoterState, VoterStateReport, CurrentRoundReport, CompletedRoundReport,
+	AuthorityVotes,
+};
+pub use warp_proof::{BlockFinalityProof, GrandpaWarpSyncProvider, WarpSyncFragment, WarpSyncProof};
 pub use voting_rule::{
 	BeforeBestBlockBy, ThreeQuartersOfTheUnfinalizedChain, VotingRule, VotingRulesBuilder
 };
@@ -108,7 +115,7 @@ use environment::{Environment, VoterSetState, Metrics};
 use import::GrandpaBlockImport;
 use until_imported::UntilGlobalMessageBlocksImported;
 use communication::{NetworkBridge, Network as NetworkT};
-use sp_finality_grandpa::{AuthorityList, AuthorityPair, AuthoritySignature, SetId};
+use sp_finality_grandpa::{AuthorityList, AuthorityPair, AuthoritySignature, GrandpaApi, SetId};
 
 // Re-export these two because it's just so damn convenient.
 pub use sp_finality_grandpa::{AuthorityId, ScheduledChange};
@@ -600,6 +607,10 @@ pub struct GrandpaParams<Block: BlockT, C, N, SC, VR> {
 	pub voting_rule: VR,
 	/// The prometheus metrics registry.
 	pub prometheus_registry: Option<prometheus_endpoint::Registry>,
+	/// The state of the voter, recorded once per round for the RPC.
+	pub shared_voter_state: SharedVoterState<Block>,
+	/// Finality stall detection, `None` to only record the state of the voter.
+	pub stall_detection: Option<StallDetection>,
 }
 
 /// Run a GRANDPA voter as a task. Provide configuration and a link to a
@@ -615,6 +626,7 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 	NumberFor<Block>: BlockNumberOps,
 	DigestFor<Block>: Encode,
 	C: ClientForGrandpa<Block, BE> + 'static,
+	C::Api: GrandpaApi<Block, Error = ClientError>,
 {
 	let GrandpaParams {
 		mut config,
@@ -624,6 +636,8 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 		telemetry_on_connect,
 		voting_rule,
 		prometheus_registry,
+		shared_voter_state,
+		stall_detection,
 	} = grandpa_params;
 
 	// NOTE: we have recently removed `run_grandpa_observer` from the public
@@ -684,6 +698,8 @@ pub fn run_grandpa_voter<Block: BlockT, BE: 'static, C, N, SC, VR>(
 		persistent_data,
 		voter_commands_rx,
 		prometheus_registry,
+		shared_voter_state,
+		stall_detection,
 	);
 
 	let voter_work = voter_work
@@ -703,6 +719,7 @@ struct VoterWork<B, Block: BlockT, C, N: NetworkT<Block>, SC, VR> {
 	env: Arc<Environment<B, Block, C, N, SC, VR>>,
 	voter_commands_rx: TracingUnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
 	network: NetworkBridge<Block, N>,
+	stall_watchdog: stall::StallWatchdog<Block>,
 }
 
 impl<B, Block, C, N, SC, VR> VoterWork<B, Block, C, N, SC, VR>
@@ -710,6 +727,7 @@ where
 	Block: BlockT,
 	B: Backend<Block> + 'static,
 	C: ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = ClientError>,
 	N: NetworkT<Block> + Sync,
 	NumberFor<Block>: BlockNumberOps,
 	SC: SelectChain<Block> + 'static,
@@ -724,7 +742,15 @@ where
 		persistent_data: PersistentData<Block>,
 		voter_commands_rx: TracingUnboundedReceiver<VoterCommand<Block::Hash, NumberFor<Block>>>,
 		prometheus_registry: Option<prometheus_endpoint::Registry>,
+		shared_voter_state: SharedVoterState<Block>,
+		stall_detection: Option<StallDetection>,
 	) -> Self {
+		// a round lasts at least the prevote and precommit timers.
+		let stall_watchdog = stall::StallWatchdog::new(
+			stall_detection,
+			shared_voter_state,
+			config.gossip_duration * 4,
+		);
 
 		let voters = persistent_data.authority_set.current_authorities();
 		let env = Arc::new(Environment {
@@ -752,6 +778,7 @@ where
 			env,
 			voter_commands_rx,
 			network,
+			stall_watchdog,
 		};
 		work.rebuild_voter();
 		work
@@ -896,6 +923,7 @@ where
 	NumberFor<Block>: BlockNumberOps,
 	SC: SelectChain<Block> + 'static,
 	C: ClientForGrandpa<Block, B> + 'static,
+	C::Api: GrandpaApi<Block, Error = ClientError>,
 	VR: VotingRule<Block, C> + Clone + 'static,
 {
 	type Output = Result<(), Error>;
@@ -931,6 +959,21 @@ where
 			}
 		}
 
+		let this = &mut *self;
+		let chain_info = this.env.client.info();
+		let restart = this.stall_watchdog.poll_check(
+			cx,
+			this.env.set_id,
+			&*this.env.voter_set_state.read(),
+			(chain_info.finalized_hash, chain_info.finalized_number),
+			chain_info.best_number,
+		);
+		if restart.is_ready() {
+			this.rebuild_voter();
+			this.network.request_catch_up();
+			cx.waker().wake_by_ref();
+		}
+
 		Future::poll(Pin::new(&mut self.network), cx)
 	}
 }
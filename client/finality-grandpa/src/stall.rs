@@ -0,0 +1,420 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Detection of finality stalls.
+//!
+//! Small permissioned networks can stop finalizing after a connectivity blip, with the voters
+//! stuck in diverging rounds. The watchdog of this module periodically records the state of the
+//! voter in a `SharedVoterState`, exposed over RPC, and flags a stall when no new block has been
+//! finalized for a number of rounds while the best block is ahead of the finalized one. Rounds
+//! are counted with the rounds completed by the voter, or with the time elapsed when the voter
+//! doesn't complete rounds anymore. On a stall, the voter can be restarted with a fresh catch-up
+//! request to the peer furthest ahead.
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::prelude::*;
+use futures_timer::Delay;
+use log::warn;
+use parking_lot::RwLock;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use sp_finality_grandpa::{AuthorityId, RoundNumber, SetId};
+
+use crate::environment::{CompletedRound, HasVoted, Vote, VoterSetState};
+
+/// Configuration of the finality stall watchdog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StallDetection {
+	/// Number of rounds without a new finalized block after which finality is stalled.
+	pub rounds: u64,
+	/// Whether to restart the voter, with a fresh catch-up request, when finality is stalled.
+	pub restart_voter: bool,
+}
+
+impl Default for StallDetection {
+	fn default() -> Self {
+		StallDetection {
+			rounds: 32,
+			restart_voter: false,
+		}
+	}
+}
+
+/// The votes cast by this voter in a round it is live in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CurrentRoundReport<Block: BlockT> {
+	/// The round number.
+	pub number: RoundNumber,
+	/// The block proposed by this voter as primary, if any.
+	pub proposed: Option<(Block::Hash, NumberFor<Block>)>,
+	/// The block prevoted for by this voter, if any.
+	pub prevoted: Option<(Block::Hash, NumberFor<Block>)>,
+	/// The block precommitted to by this voter, if any.
+	pub precommitted: Option<(Block::Hash, NumberFor<Block>)>,
+}
+
+impl<Block: BlockT> CurrentRoundReport<Block> {
+	fn new(number: RoundNumber, has_voted: &HasVoted<Block>) -> Self {
+		let (proposed, prevoted, precommitted) = match has_voted {
+			HasVoted::No => (None, None, None),
+			HasVoted::Yes(_, Vote::Propose(propose)) => (Some(propose), None, None),
+			HasVoted::Yes(_, Vote::Prevote(propose, prevote)) =>
+				(propose.as_ref(), Some(prevote), None),
+			HasVoted::Yes(_, Vote::Precommit(propose, prevote, precommit)) =>
+				(propose.as_ref(), Some(prevote), Some(precommit)),
+		};
+
+		CurrentRoundReport {
+			number,
+			proposed: proposed.map(|p| (p.target_hash, p.target_number)),
+			prevoted: prevoted.map(|p| (p.target_hash, p.target_number)),
+			precommitted: precommitted.map(|p| (p.target_hash, p.target_number)),
+		}
+	}
+}
+
+/// The votes of an authority observed in a completed round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorityVotes {
+	/// The authority.
+	pub authority: AuthorityId,
+	/// Number of prevotes of the authority.
+	pub prevotes: u32,
+	/// Number of precommits of the authority.
+	pub precommits: u32,
+}
+
+/// The state of a completed round.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletedRoundReport<Block: BlockT> {
+	/// The round number.
+	pub number: RoundNumber,
+	/// The prevote-GHOST of the round.
+	pub prevote_ghost: Option<(Block::Hash, NumberFor<Block>)>,
+	/// The estimate of the round.
+	pub estimate: Option<(Block::Hash, NumberFor<Block>)>,
+	/// The block finalized in the round.
+	pub finalized: Option<(Block::Hash, NumberFor<Block>)>,
+	/// Whether the round is completable.
+	pub completable: bool,
+	/// The votes observed in the round, per authority.
+	pub votes: Vec<AuthorityVotes>,
+}
+
+impl<Block: BlockT> From<&CompletedRound<Block>> for CompletedRoundReport<Block> {
+	fn from(round: &CompletedRound<Block>) -> Self {
+		let mut votes: Vec<AuthorityVotes> = Vec::new();
+		for signed in round.votes.iter() {
+			let index = match votes.iter().position(|v| v.authority == signed.id) {
+				Some(index) => index,
+				None => {
+					votes.push(AuthorityVotes {
+						authority: signed.id.clone(),
+						prevotes: 0,
+						precommits: 0,
+					});
+					votes.len() - 1
+				},
+			};
+
+			match signed.message {
+				finality_grandpa::Message::Prevote(_) => votes[index].prevotes += 1,
+				finality_grandpa::Message::Precommit(_) => votes[index].precommits += 1,
+				finality_grandpa::Message::PrimaryPropose(_) => {},
+			}
+		}
+
+		CompletedRoundReport {
+			number: round.number,
+			prevote_ghost: round.state.prevote_ghost.clone(),
+			estimate: round.state.estimate.clone(),
+			finalized: round.state.finalized.clone(),
+			completable: round.state.completable,
+			votes,
+		}
+	}
+}
+
+/// The state of the voter, as last recorded by the stall watchdog.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoterStateReport<Block: BlockT> {
+	/// The current authority set id.
+	pub set_id: SetId,
+	/// The highest round the voter is live in, `None` if the voter is paused.
+	pub current_round: Option<RoundNumber>,
+	/// The rounds the voter is live in and its votes in them, oldest first.
+	pub current_rounds: Vec<CurrentRoundReport<Block>>,
+	/// The last finalized block.
+	pub finalized: (Block::Hash, NumberFor<Block>),
+	/// Number of rounds since the last finalized block.
+	pub rounds_since_finalized: u64,
+	/// Whether finality is stalled.
+	pub stalled: bool,
+	/// Number of voter restarts because of stalls.
+	pub restarts: u32,
+	/// The last completed rounds, oldest first.
+	pub completed_rounds: Vec<CompletedRoundReport<Block>>,
+}
+
+/// The state of the voter shared with the RPC, updated by the stall watchdog.
+pub struct SharedVoterState<Block: BlockT> {
+	inner: Arc<RwLock<Option<VoterStateReport<Block>>>>,
+}
+
+impl<Block: BlockT> Clone for SharedVoterState<Block> {
+	fn clone(&self) -> Self {
+		SharedVoterState { inner: self.inner.clone() }
+	}
+}
+
+impl<Block: BlockT> SharedVoterState<Block> {
+	/// Create a new empty shared voter state.
+	pub fn empty() -> Self {
+		SharedVoterState { inner: Arc::new(RwLock::new(None)) }
+	}
+
+	/// The last recorded state of the voter, `None` if the voter isn't running (yet).
+	pub fn get(&self) -> Option<VoterStateReport<Block>> {
+		self.inner.read().clone()
+	}
+
+	fn set(&self, report: VoterStateReport<Block>) {
+		*self.inner.write() = Some(report);
+	}
+}
+
+/// Watches the voter for finality stalls and records its state in a `SharedVoterState`.
+pub(crate) struct StallWatchdog<Block: BlockT> {
+	detection: Option<StallDetection>,
+	shared_voter_state: SharedVoterState<Block>,
+	round_duration: Duration,
+	delay: Delay,
+	/// The set id, finalized number and last completed round when finality last progressed.
+	last_progress: Option<(SetId, NumberFor<Block>, RoundNumber)>,
+	last_progress_instant: Instant,
+	restarts: u32,
+}
+
+impl<Block: BlockT> StallWatchdog<Block> {
+	/// Create a new watchdog, expecting rounds to last about `round_duration`.
+	pub(crate) fn new(
+		detection: Option<StallDetection>,
+		shared_voter_state: SharedVoterState<Block>,
+		round_duration: Duration,
+	) -> Self {
+		StallWatchdog {
+			detection,
+			shared_voter_state,
+			round_duration,
+			delay: Delay::new(round_duration),
+			last_progress: None,
+			last_progress_instant: Instant::now(),
+			restarts: 0,
+		}
+	}
+
+	/// Check the voter once per round. Returns `Poll::Ready(())` when the voter must be restarted.
+	pub(crate) fn poll_check(
+		&mut self,
+		cx: &mut Context,
+		set_id: SetId,
+		voter_set_state: &VoterSetState<Block>,
+		finalized: (Block::Hash, NumberFor<Block>),
+		best_number: NumberFor<Block>,
+	) -> Poll<()> {
+		if self.delay.poll_unpin(cx).is_pending() {
+			return Poll::Pending;
+		}
+		self.delay.reset(self.round_duration);
+		// make sure the task is woken up for the next check.
+		while self.delay.poll_unpin(cx).is_ready() {}
+
+		let restart = self.check(set_id, voter_set_state, finalized, best_number, Instant::now());
+		if restart { Poll::Ready(()) } else { Poll::Pending }
+	}
+
+	fn check(
+		&mut self,
+		set_id: SetId,
+		voter_set_state: &VoterSetState<Block>,
+		finalized: (Block::Hash, NumberFor<Block>),
+		best_number: NumberFor<Block>,
+		now: Instant,
+	) -> bool {
+		let completed_rounds = voter_set_state.completed_rounds();
+		let last_completed = completed_rounds.last().number;
+
+		// finality can't progress with nothing to finalize, e.g. when no blocks are authored.
+		let progressed = best_number <= finalized.1 || match self.last_progress {
+			Some((last_set_id, last_finalized, _)) =>
+				last_set_id != set_id || finalized.1 > last_finalized,
+			None => true,
+		};
+		if progressed {
+			self.last_progress = Some((set_id, finalized.1, last_completed));
+			self.last_progress_instant = now;
+		}
+
+		let completed_since = self.last_progress
+			.map(|(_, _, round)| last_completed.saturating_sub(round))
+			.unwrap_or(0);
+		let elapsed_since = (now.duration_since(self.last_progress_instant).as_millis()
+			/ self.round_duration.as_millis().max(1)) as u64;
+		let rounds_since_finalized = completed_since.max(elapsed_since);
+
+		let stalled = self.detection.as_ref()
+			.map_or(false, |detection| rounds_since_finalized >= detection.rounds);
+		let restart = stalled && self.detection.as_ref().map_or(false, |d| d.restart_voter);
+
+		if stalled {
+			warn!(target: "afg",
+				"Finality stalled: no block finalized after #{} for {} rounds (set {}, last completed round {}){}",
+				finalized.1,
+				rounds_since_finalized,
+				set_id,
+				last_completed,
+				if restart { ", restarting the voter" } else { "" },
+			);
+		}
+
+		if restart {
+			self.restarts += 1;
+			// give the restarted voter another `rounds` rounds before restarting it again.
+			self.last_progress = Some((set_id, finalized.1, last_completed));
+			self.last_progress_instant = now;
+		}
+
+		let current_rounds: Vec<_> = match voter_set_state {
+			VoterSetState::Live { current_rounds, .. } => current_rounds.iter()
+				.map(|(number, has_voted)| CurrentRoundReport::new(*number, has_voted))
+				.collect(),
+			VoterSetState::Paused { .. } => Vec::new(),
+		};
+		let current_round = current_rounds.last().map(|round| round.number);
+
+		self.shared_voter_state.set(VoterStateReport {
+			set_id,
+			current_round,
+			current_rounds,
+			finalized,
+			rounds_since_finalized,
+			stalled,
+			restarts: self.restarts,
+			completed_rounds: completed_rounds.iter().map(Into::into).collect(),
+		});
+
+		restart
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::authorities::AuthoritySet;
+	use substrate_test_runtime_client::runtime::Block;
+	use sp_core::H256;
+
+	fn voter_set_state() -> VoterSetState<Block> {
+		let voters = vec![(AuthorityId::default(), 1)];
+		let authority_set = AuthoritySet::genesis(voters);
+		VoterSetState::live(0, &authority_set, (H256::zero(), 0))
+	}
+
+	fn watchdog(restart_voter: bool) -> (StallWatchdog<Block>, SharedVoterState<Block>) {
+		let shared_voter_state = SharedVoterState::empty();
+		let watchdog = StallWatchdog::new(
+			Some(StallDetection { rounds: 4, restart_voter }),
+			shared_voter_state.clone(),
+			Duration::from_secs(1),
+		);
+
+		(watchdog, shared_voter_state)
+	}
+
+	#[test]
+	fn reports_stall_after_rounds_without_finality() {
+		let (mut watchdog, shared_voter_state) = watchdog(false);
+		let state = voter_set_state();
+		let start = Instant::now();
+
+		assert!(shared_voter_state.get().is_none());
+
+		assert!(!watchdog.check(0, &state, (H256::zero(), 0), 10, start));
+		let report = shared_voter_state.get().unwrap();
+		assert_eq!(report.current_round, Some(1));
+		assert_eq!(report.current_rounds, vec![CurrentRoundReport {
+			number: 1,
+			proposed: None,
+			prevoted: None,
+			precommitted: None,
+		}]);
+		assert_eq!(report.completed_rounds.len(), 1);
+		assert!(!report.stalled);
+
+		// finality progresses.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 1), 10, start + Duration::from_secs(3)));
+		assert_eq!(shared_voter_state.get().unwrap().rounds_since_finalized, 0);
+
+		// no new finalized block for 3 rounds.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 1), 10, start + Duration::from_secs(6)));
+		let report = shared_voter_state.get().unwrap();
+		assert_eq!(report.rounds_since_finalized, 3);
+		assert!(!report.stalled);
+
+		// no new finalized block for 4 rounds, but restarts are disabled.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 1), 10, start + Duration::from_secs(7)));
+		let report = shared_voter_state.get().unwrap();
+		assert!(report.stalled);
+		assert_eq!(report.restarts, 0);
+	}
+
+	#[test]
+	fn restarts_voter_on_stall() {
+		let (mut watchdog, shared_voter_state) = watchdog(true);
+		let state = voter_set_state();
+		let start = Instant::now();
+
+		assert!(!watchdog.check(0, &state, (H256::zero(), 0), 10, start));
+		assert!(watchdog.check(0, &state, (H256::zero(), 0), 10, start + Duration::from_secs(4)));
+		assert_eq!(shared_voter_state.get().unwrap().restarts, 1);
+
+		// the restarted voter gets another 4 rounds.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 0), 10, start + Duration::from_secs(5)));
+		assert!(watchdog.check(0, &state, (H256::zero(), 0), 10, start + Duration::from_secs(8)));
+		assert_eq!(shared_voter_state.get().unwrap().restarts, 2);
+	}
+
+	#[test]
+	fn no_stall_without_blocks_to_finalize() {
+		let (mut watchdog, shared_voter_state) = watchdog(true);
+		let state = voter_set_state();
+		let start = Instant::now();
+
+		// the best block is finalized, nothing was authored for 8 rounds.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 5), 5, start));
+		assert!(!watchdog.check(0, &state, (H256::zero(), 5), 5, start + Duration::from_secs(8)));
+		let report = shared_voter_state.get().unwrap();
+		assert_eq!(report.rounds_since_finalized, 0);
+		assert!(!report.stalled);
+		assert_eq!(report.restarts, 0);
+
+		// a new block isn't finalized for 4 rounds.
+		assert!(!watchdog.check(0, &state, (H256::zero(), 5), 6, start + Duration::from_secs(9)));
+		assert!(watchdog.check(0, &state, (H256::zero(), 5), 6, start + Duration::from_secs(13)));
+	}
+}
@@ -435,6 +435,8 @@ fn run_to_completion_with<F>(
 			telemetry_on_connect: None,
 			voting_rule: (),
 			prometheus_registry: None,
+			shared_voter_state: SharedVoterState::empty(),
+			stall_detection: None,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -566,6 +568,8 @@ fn finalize_3_voters_1_full_observer() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			prometheus_registry: None,
+			shared_voter_state: SharedVoterState::empty(),
+			stall_detection: None,
 		};
 
 		voters.push(run_grandpa_voter(grandpa_params).expect("all in order with client and network"));
@@ -729,6 +733,8 @@ fn transition_3_voters_twice_1_full_observer() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			prometheus_registry: None,
+			shared_voter_state: SharedVoterState::empty(),
+			stall_detection: None,
 		};
 		let voter = run_grandpa_voter(grandpa_params).expect("all in order with client and network");
 
@@ -1154,6 +1160,8 @@ fn voter_persists_its_votes() {
 							telemetry_on_connect: None,
 							voting_rule: VotingRulesBuilder::default().build(),
 							prometheus_registry: None,
+							shared_voter_state: SharedVoterState::empty(),
+							stall_detection: None,
 						};
 
 						let voter = run_grandpa_voter(grandpa_params)
@@ -1499,6 +1507,8 @@ fn voter_catches_up_to_latest_round_when_behind() {
 			telemetry_on_connect: None,
 			voting_rule: (),
 			prometheus_registry: None,
+			shared_voter_state: SharedVoterState::empty(),
+			stall_detection: None,
 		};
 
 		Box::pin(run_grandpa_voter(grandpa_params).expect("all in order with client and network"))
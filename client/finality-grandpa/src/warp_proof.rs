@@ -0,0 +1,672 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GRANDPA warp sync proofs.
+//!
+//! A warp sync proof is the chain of justifications for the last block of every authority
+//! set since a given set, followed by the latest justified block of the current set. Each
+//! set change must be a standard change enacted with no delay, so the justified header also
+//! carries the next authority set. Verifying the proof from a known set (e.g. the genesis
+//! set) yields the current authority set and a recent finalized header, without
+//! downloading the blocks in between.
+//!
+//! Forced changes are not justified and can't be warped over.
+//!
+//! The same set change proofs let a block finality proof be verified from a known set: the
+//! block is proven by the justification of a descendant within its authority set, together
+//! with the headers linking the two.
+//!
+//! [`GrandpaWarpSyncProvider`] serves warp sync proofs over the network, and warp syncs a node
+//! that only has the genesis block: it verifies proofs from the genesis set, then imports the
+//! state of the latest justified block together with the authority set it reached.
+
+use parity_scale_codec::{Encode, Decode};
+use finality_grandpa::BlockNumberOps;
+use log::{info, trace};
+use parking_lot::Mutex;
+use sc_client_api::{StateEntry, backend::Backend};
+use sc_network::config::{WarpSyncProgress, WarpSyncProvider};
+use sp_utils::mpsc::TracingUnboundedSender;
+use std::sync::Arc;
+use sp_blockchain::{
+	Backend as BlockchainBackend, Error as ClientError, HeaderBackend, Result as ClientResult,
+};
+use sp_finality_grandpa::{AuthorityList, SetId};
+use sp_runtime::{
+	Justification, generic::BlockId,
+	traits::{NumberFor, Block as BlockT, Header as HeaderT, One, Saturating, Zero},
+};
+
+use crate::{NewAuthoritySet, VoterCommand, aux_schema};
+use crate::authorities::{AuthoritySet, SharedAuthoritySet};
+use crate::import::find_scheduled_change;
+use crate::justification::GrandpaJustification;
+
+/// Maximum number of fragments returned in a single warp sync proof.
+const MAX_WARP_SYNC_PROOF_FRAGMENTS: usize = 128;
+
+/// Maximum number of blocks searched for a justification of the current authority set, and
+/// so of headers in a block finality proof.
+const MAX_JUSTIFICATION_SCAN: u32 = 4096;
+
+/// A justified header in a warp sync proof.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct WarpSyncFragment<Block: BlockT> {
+	/// The header of the justified block. Unless this is the last fragment of a finished
+	/// proof, it enacts the change to the next authority set.
+	pub header: Block::Header,
+	/// The justification of `header` by the authority set it ends.
+	pub justification: Justification,
+}
+
+/// Proof of the authority set changes since a given set.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct WarpSyncProof<Block: BlockT> {
+	/// The justified headers, in order.
+	pub fragments: Vec<WarpSyncFragment<Block>>,
+	/// Whether the proof reaches the current authority set. If not, a new proof should be
+	/// requested from the set following the last fragment.
+	pub is_finished: bool,
+}
+
+impl<Block: BlockT> WarpSyncProof<Block> where
+	NumberFor<Block>: BlockNumberOps,
+{
+	/// Generate the proof of all authority set changes since the set `begin`.
+	pub fn generate<BE: Backend<Block>>(backend: &BE, begin: SetId) -> ClientResult<Self> {
+		let current_set_id = aux_schema::load_current_set_id::<_, Block::Hash, NumberFor<Block>>(backend)?
+			.ok_or_else(|| ClientError::Backend("GRANDPA authority set isn't initialized".into()))?;
+
+		prove_warp_sync(
+			backend.blockchain(),
+			current_set_id,
+			begin,
+			|set_id| aux_schema::load_authority_set_change::<Block, _>(backend, set_id),
+		)
+	}
+
+	/// Verify the proof against the authority set `set_id`.
+	///
+	/// Returns the id and authorities of the last set reached by the proof, and the header
+	/// of the latest justified block.
+	pub fn verify(
+		&self,
+		set_id: SetId,
+		authorities: AuthorityList,
+	) -> ClientResult<(SetId, AuthorityList, Block::Header)> {
+		let last_index = self.fragments.len().checked_sub(1)
+			.ok_or_else(|| ClientError::BadJustification("empty warp sync proof".into()))?;
+
+		let mut current_set_id = set_id;
+		let mut current_authorities = authorities;
+		for (index, fragment) in self.fragments.iter().enumerate() {
+			GrandpaJustification::<Block>::decode_and_verify_finalizes(
+				&fragment.justification,
+				(fragment.header.hash(), *fragment.header.number()),
+				current_set_id,
+				&current_authorities.iter().cloned().collect(),
+			)?;
+
+			match find_scheduled_change::<Block>(&fragment.header) {
+				Some(change) if change.delay.is_zero() => {
+					current_authorities = change.next_authorities;
+					current_set_id += 1;
+				},
+				_ if self.is_finished && index == last_index => {},
+				_ => return Err(ClientError::BadJustification(
+					"warp sync fragment doesn't enact an authority set change".into(),
+				)),
+			}
+		}
+
+		let header = self.fragments[last_index].header.clone();
+		Ok((current_set_id, current_authorities, header))
+	}
+}
+
+/// Serves warp sync proofs of the finality of the local chain, and warp syncs the local chain.
+pub struct GrandpaWarpSyncProvider<Block: BlockT, BE> {
+	backend: Arc<BE>,
+	authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	send_voter_commands: TracingUnboundedSender<VoterCommand<Block::Hash, NumberFor<Block>>>,
+	/// The authority set the next proof is verified against.
+	verified_set: Mutex<(SetId, AuthorityList)>,
+}
+
+impl<Block: BlockT, BE> GrandpaWarpSyncProvider<Block, BE> {
+	pub(crate) fn new(
+		backend: Arc<BE>,
+		authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+		send_voter_commands: TracingUnboundedSender<VoterCommand<Block::Hash, NumberFor<Block>>>,
+	) -> Self {
+		let verified_set = {
+			let set = authority_set.inner().read();
+			(set.set_id, set.current_authorities.clone())
+		};
+		GrandpaWarpSyncProvider {
+			backend,
+			authority_set,
+			send_voter_commands,
+			verified_set: Mutex::new(verified_set),
+		}
+	}
+}
+
+impl<Block: BlockT, BE> WarpSyncProvider<Block> for GrandpaWarpSyncProvider<Block, BE> where
+	BE: Backend<Block> + 'static,
+	NumberFor<Block>: BlockNumberOps,
+{
+	fn generate(&self, begin: u64) -> ClientResult<Vec<u8>> {
+		WarpSyncProof::<Block>::generate(&*self.backend, begin).map(|proof| proof.encode())
+	}
+
+	fn current_set(&self) -> u64 {
+		self.verified_set.lock().0
+	}
+
+	fn verify(&self, proof: &[u8]) -> ClientResult<WarpSyncProgress<Block>> {
+		let proof = WarpSyncProof::<Block>::decode(&mut &proof[..])
+			.map_err(|e| ClientError::BadJustification(format!("invalid warp sync proof: {}", e.what())))?;
+
+		let mut verified_set = self.verified_set.lock();
+		let (set_id, authorities, header) = proof.verify(verified_set.0, verified_set.1.clone())?;
+		*verified_set = (set_id, authorities);
+
+		if !proof.is_finished {
+			return Ok(WarpSyncProgress::Partial(set_id))
+		}
+		// `verify` checked that the proof isn't empty.
+		let justification = proof.fragments.last()
+			.map(|fragment| fragment.justification.clone())
+			.unwrap_or_default();
+		Ok(WarpSyncProgress::Complete(header, justification))
+	}
+
+	fn import(
+		&self,
+		header: Block::Header,
+		justification: Justification,
+		state: &mut dyn Iterator<Item = ClientResult<StateEntry>>,
+	) -> ClientResult<()> {
+		let (set_id, authorities) = self.verified_set.lock().clone();
+		let (hash, number) = (header.hash(), *header.number());
+
+		let mut set = AuthoritySet::genesis(authorities.clone());
+		set.set_id = set_id;
+		let aux = aux_schema::warp_sync_aux::<Block>(&set, (hash, number));
+
+		self.backend.import_state(header, Some(justification), aux, state)?;
+
+		info!(target: "afg", "Warp synced to block #{} ({}) of authority set #{}", number, hash, set_id);
+		*self.authority_set.inner().write() = set;
+		let _ = self.send_voter_commands.unbounded_send(VoterCommand::ChangeAuthorities(NewAuthoritySet {
+			canon_hash: hash,
+			canon_number: number,
+			set_id,
+			authorities,
+		}));
+		Ok(())
+	}
+}
+
+/// Proof of the finality of a block, verifiable from a known authority set.
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct BlockFinalityProof<Block: BlockT> {
+	/// The justified headers enacting the authority set changes from the known set to the set
+	/// which finalized the block.
+	pub set_changes: Vec<WarpSyncFragment<Block>>,
+	/// The headers from the proven block to the justified block, both included.
+	pub headers: Vec<Block::Header>,
+	/// The justification of the last of `headers`.
+	pub justification: Justification,
+}
+
+impl<Block: BlockT> BlockFinalityProof<Block> where
+	NumberFor<Block>: BlockNumberOps,
+{
+	/// Generate the proof of finality of the given block, verifiable from the set `begin`.
+	///
+	/// Returns `None` if the block isn't final yet, or is final but no block of the current
+	/// set has been justified since.
+	pub fn generate<BE: Backend<Block>>(
+		backend: &BE,
+		block: Block::Hash,
+		begin: SetId,
+	) -> ClientResult<Option<Self>> {
+		let current_set_id = aux_schema::load_current_set_id::<_, Block::Hash, NumberFor<Block>>(backend)?
+			.ok_or_else(|| ClientError::Backend("GRANDPA authority set isn't initialized".into()))?;
+
+		prove_finality(
+			backend.blockchain(),
+			current_set_id,
+			begin,
+			block,
+			|set_id| aux_schema::load_authority_set_change::<Block, _>(backend, set_id),
+		)
+	}
+
+	/// Verify the proof against the authority set `set_id`.
+	///
+	/// Returns the header of the proven block.
+	pub fn verify(
+		&self,
+		set_id: SetId,
+		authorities: AuthorityList,
+	) -> ClientResult<Block::Header> {
+		let mut current_set_id = set_id;
+		let mut current_authorities = authorities;
+		for fragment in self.set_changes.iter() {
+			GrandpaJustification::<Block>::decode_and_verify_finalizes(
+				&fragment.justification,
+				(fragment.header.hash(), *fragment.header.number()),
+				current_set_id,
+				&current_authorities.iter().cloned().collect(),
+			)?;
+
+			match find_scheduled_change::<Block>(&fragment.header) {
+				Some(change) if change.delay.is_zero() => {
+					current_authorities = change.next_authorities;
+					current_set_id += 1;
+				},
+				_ => return Err(ClientError::BadJustification(
+					"finality proof fragment doesn't enact an authority set change".into(),
+				)),
+			}
+		}
+
+		let first = self.headers.first()
+			.ok_or_else(|| ClientError::BadJustification("finality proof without headers".into()))?;
+		for pair in self.headers.windows(2) {
+			if *pair[1].parent_hash() != pair[0].hash() {
+				return Err(ClientError::BadJustification(
+					"finality proof headers don't form a chain".into(),
+				));
+			}
+		}
+
+		let justified = self.headers.last().expect("headers are not empty; qed");
+		GrandpaJustification::<Block>::decode_and_verify_finalizes(
+			&self.justification,
+			(justified.hash(), *justified.number()),
+			current_set_id,
+			&current_authorities.iter().cloned().collect(),
+		)?;
+
+		Ok(first.clone())
+	}
+}
+
+/// The justified headers enacting the authority set changes from the set `begin` to the set
+/// `end`, given the number of the block ending each set.
+fn prove_set_changes<Block: BlockT, B: BlockchainBackend<Block>>(
+	blockchain: &B,
+	begin: SetId,
+	end: SetId,
+	set_change: &impl Fn(SetId) -> ClientResult<Option<NumberFor<Block>>>,
+) -> ClientResult<Vec<WarpSyncFragment<Block>>> {
+	let mut fragments = Vec::new();
+	for set_id in begin..end {
+		let number = set_change(set_id)?.ok_or_else(|| ClientError::Backend(
+			format!("The end of authority set #{} wasn't recorded", set_id),
+		))?;
+		let header = blockchain.expect_header(BlockId::Number(number))?;
+		match find_scheduled_change::<Block>(&header) {
+			Some(change) if change.delay.is_zero() => {},
+			_ => return Err(ClientError::Backend(format!(
+				"Authority set #{} ended at #{} without a standard change enacted with no delay",
+				set_id,
+				number,
+			))),
+		}
+		let justification = blockchain.justification(BlockId::Number(number))?
+			.ok_or_else(|| ClientError::Backend(
+				format!("Missing justification of authority set change at #{}", number),
+			))?;
+
+		fragments.push(WarpSyncFragment { header, justification });
+	}
+
+	Ok(fragments)
+}
+
+/// Prove the authority set changes since the set `begin`, given the number of the block
+/// ending each set.
+fn prove_warp_sync<Block: BlockT, B: BlockchainBackend<Block>>(
+	blockchain: &B,
+	current_set_id: SetId,
+	begin: SetId,
+	set_change: impl Fn(SetId) -> ClientResult<Option<NumberFor<Block>>>,
+) -> ClientResult<WarpSyncProof<Block>> {
+	if begin > current_set_id {
+		return Err(ClientError::Backend(
+			format!("Cannot prove warp sync from unknown authority set #{}", begin),
+		));
+	}
+
+	let end = current_set_id.min(begin.saturating_add(MAX_WARP_SYNC_PROOF_FRAGMENTS as SetId));
+	let mut fragments = prove_set_changes(blockchain, begin, end, &set_change)?;
+	if end < current_set_id {
+		return Ok(WarpSyncProof { fragments, is_finished: false });
+	}
+
+	let last_number = match end.checked_sub(1) {
+		Some(previous) => set_change(previous)?.unwrap_or_else(Zero::zero),
+		None => Zero::zero(),
+	};
+
+	// add the latest justified block of the current set, if one is recent enough.
+	let mut number = blockchain.info().finalized_number;
+	let lowest = last_number.max(number.saturating_sub(MAX_JUSTIFICATION_SCAN.into()));
+	while number > lowest {
+		if let Some(justification) = blockchain.justification(BlockId::Number(number))? {
+			let header = blockchain.expect_header(BlockId::Number(number))?;
+			fragments.push(WarpSyncFragment { header, justification });
+			break;
+		}
+		number = number - One::one();
+	}
+
+	trace!(
+		target: "afg",
+		"Built warp sync proof from set #{} of {} fragments.",
+		begin,
+		fragments.len(),
+	);
+
+	Ok(WarpSyncProof { fragments, is_finished: true })
+}
+
+/// Prove the finality of the given block from the set `begin`, given the number of the block
+/// ending each set.
+fn prove_finality<Block: BlockT, B: BlockchainBackend<Block>>(
+	blockchain: &B,
+	current_set_id: SetId,
+	begin: SetId,
+	block: Block::Hash,
+	set_change: impl Fn(SetId) -> ClientResult<Option<NumberFor<Block>>>,
+) -> ClientResult<Option<BlockFinalityProof<Block>>> {
+	if begin > current_set_id {
+		return Err(ClientError::Backend(
+			format!("Cannot prove finality from unknown authority set #{}", begin),
+		));
+	}
+
+	let number = *blockchain.expect_header(BlockId::Hash(block))?.number();
+	let finalized_number = blockchain.info().finalized_number;
+	if number > finalized_number {
+		return Ok(None);
+	}
+	if blockchain.hash(number)? != Some(block) {
+		return Err(ClientError::Backend(
+			format!("Block {:?} isn't in the finalized chain", block),
+		));
+	}
+
+	if let Some(previous) = begin.checked_sub(1) {
+		match set_change(previous)? {
+			Some(start) if number <= start => return Err(ClientError::Backend(format!(
+				"Block #{} was finalized before authority set #{}",
+				number,
+				begin,
+			))),
+			_ => {},
+		}
+	}
+
+	// the set which finalized the block, and the last block it finalized.
+	let mut set_id = begin;
+	let mut end = finalized_number;
+	while set_id < current_set_id {
+		let set_end = set_change(set_id)?.ok_or_else(|| ClientError::Backend(
+			format!("The end of authority set #{} wasn't recorded", set_id),
+		))?;
+		if number <= set_end {
+			end = set_end;
+			break;
+		}
+		set_id += 1;
+	}
+
+	let mut justified = number;
+	let justification = loop {
+		if let Some(justification) = blockchain.justification(BlockId::Number(justified))? {
+			break justification;
+		}
+		if justified - number >= MAX_JUSTIFICATION_SCAN.into() {
+			return Err(ClientError::Backend(format!(
+				"No justification within {} blocks of #{}",
+				MAX_JUSTIFICATION_SCAN,
+				number,
+			)));
+		}
+		if justified >= end {
+			if set_id < current_set_id {
+				return Err(ClientError::Backend(
+					format!("Missing justification of authority set change at #{}", end),
+				));
+			}
+			return Ok(None);
+		}
+		justified = justified + One::one();
+	};
+
+	let mut headers = Vec::new();
+	let mut header_number = number;
+	while header_number <= justified {
+		headers.push(blockchain.expect_header(BlockId::Number(header_number))?);
+		header_number = header_number + One::one();
+	}
+
+	let set_changes = prove_set_changes(blockchain, begin, set_id, &set_change)?;
+
+	trace!(
+		target: "afg",
+		"Built finality proof of #{} from set #{} justified at #{}.",
+		number,
+		begin,
+		justified,
+	);
+
+	Ok(Some(BlockFinalityProof { set_changes, headers, justification }))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::Arc;
+	use sc_client_api::NewBlockState;
+	use sp_core::H256;
+	use sp_finality_grandpa::{ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID};
+	use sp_keyring::Ed25519Keyring;
+	use sp_runtime::DigestItem;
+	use substrate_test_runtime_client::runtime::{Block, Header};
+	use substrate_test_runtime_client::sc_client::in_mem::Blockchain as InMemoryBlockchain;
+	use crate::communication;
+
+	fn header(number: u64, parent_hash: H256, next_authorities: Option<&[Ed25519Keyring]>) -> Header {
+		let mut header = Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			parent_hash,
+			Default::default(),
+		);
+		if let Some(next_authorities) = next_authorities {
+			let change = ConsensusLog::ScheduledChange(ScheduledChange {
+				next_authorities: next_authorities.iter().map(|k| (k.public().into(), 1)).collect(),
+				delay: 0,
+			});
+			header.digest_mut().push(DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode()));
+		}
+		header
+	}
+
+	fn justification(
+		blockchain: &Arc<InMemoryBlockchain<Block>>,
+		header: &Header,
+		set_id: SetId,
+		signer: Ed25519Keyring,
+	) -> Justification {
+		let precommit = finality_grandpa::Precommit {
+			target_hash: header.hash(),
+			target_number: *header.number(),
+		};
+		let msg = finality_grandpa::Message::Precommit(precommit.clone());
+		let encoded = communication::localized_payload(1, set_id, &msg);
+		let commit = finality_grandpa::Commit {
+			target_hash: header.hash(),
+			target_number: *header.number(),
+			precommits: vec![finality_grandpa::SignedPrecommit {
+				precommit,
+				signature: signer.sign(&encoded[..]).into(),
+				id: signer.public().into(),
+			}],
+		};
+
+		GrandpaJustification::from_commit(blockchain, 1, commit).unwrap().encode()
+	}
+
+	/// Blocks #1 and #3 enact changes to Bob and Charlie, block #4 is justified by Charlie.
+	fn test_blockchain() -> (Arc<InMemoryBlockchain<Block>>, Vec<Header>) {
+		let blockchain = Arc::new(InMemoryBlockchain::<Block>::new());
+		let blocks = [
+			(None, None),
+			(Some(&[Ed25519Keyring::Bob][..]), Some((0, Ed25519Keyring::Alice))),
+			(None, None),
+			(Some(&[Ed25519Keyring::Charlie][..]), Some((1, Ed25519Keyring::Bob))),
+			(None, Some((2, Ed25519Keyring::Charlie))),
+		];
+
+		let mut headers: Vec<Header> = Vec::new();
+		for (number, (change, signer)) in blocks.iter().enumerate() {
+			let parent_hash = headers.last().map(|h| h.hash()).unwrap_or_default();
+			let header = header(number as u64, parent_hash, *change);
+			let justification = signer.map(|(set_id, signer)|
+				justification(&blockchain, &header, set_id, signer)
+			);
+			blockchain.insert(header.hash(), header.clone(), justification, None, NewBlockState::Final)
+				.unwrap();
+			headers.push(header);
+		}
+
+		(blockchain, headers)
+	}
+
+	fn set_change(set_id: SetId) -> ClientResult<Option<u64>> {
+		Ok(match set_id {
+			0 => Some(1),
+			1 => Some(3),
+			_ => None,
+		})
+	}
+
+	#[test]
+	fn warp_sync_proof_reaches_current_set() {
+		let (blockchain, headers) = test_blockchain();
+
+		let proof = prove_warp_sync(&*blockchain, 2, 0, set_change).unwrap();
+		assert!(proof.is_finished);
+		assert_eq!(
+			proof.fragments.iter().map(|f| f.header.clone()).collect::<Vec<_>>(),
+			vec![headers[1].clone(), headers[3].clone(), headers[4].clone()],
+		);
+
+		let (set_id, authorities, header) = proof.verify(
+			0,
+			vec![(Ed25519Keyring::Alice.public().into(), 1)],
+		).unwrap();
+		assert_eq!(set_id, 2);
+		assert_eq!(authorities, vec![(Ed25519Keyring::Charlie.public().into(), 1)]);
+		assert_eq!(header, headers[4]);
+
+		// the proof from the second set starts at the second change
+		let proof = prove_warp_sync(&*blockchain, 2, 1, set_change).unwrap();
+		assert_eq!(
+			proof.fragments.iter().map(|f| f.header.clone()).collect::<Vec<_>>(),
+			vec![headers[3].clone(), headers[4].clone()],
+		);
+	}
+
+	#[test]
+	fn warp_sync_proof_fails_with_wrong_authorities() {
+		let (blockchain, _) = test_blockchain();
+
+		let proof = prove_warp_sync(&*blockchain, 2, 0, set_change).unwrap();
+		assert!(proof.verify(0, vec![(Ed25519Keyring::Bob.public().into(), 1)]).is_err());
+		assert!(proof.verify(1, vec![(Ed25519Keyring::Alice.public().into(), 1)]).is_err());
+	}
+
+	#[test]
+	fn finality_proof_verifies_from_known_set() {
+		let (blockchain, headers) = test_blockchain();
+		let alice = vec![(Ed25519Keyring::Alice.public().into(), 1)];
+		let bob = vec![(Ed25519Keyring::Bob.public().into(), 1)];
+
+		// block #2 is finalized by Bob with the justification of block #3.
+		let proof = prove_finality(&*blockchain, 2, 0, headers[2].hash(), set_change)
+			.unwrap()
+			.unwrap();
+		assert_eq!(
+			proof.set_changes.iter().map(|f| f.header.clone()).collect::<Vec<_>>(),
+			vec![headers[1].clone()],
+		);
+		assert_eq!(proof.headers, vec![headers[2].clone(), headers[3].clone()]);
+		assert_eq!(proof.verify(0, alice.clone()).unwrap(), headers[2]);
+		assert!(proof.verify(0, bob.clone()).is_err());
+
+		// from the second set, no set change is needed.
+		let proof = prove_finality(&*blockchain, 2, 1, headers[2].hash(), set_change)
+			.unwrap()
+			.unwrap();
+		assert!(proof.set_changes.is_empty());
+		assert_eq!(proof.verify(1, bob).unwrap(), headers[2]);
+
+		// block #4 of the current set is justified.
+		let proof = prove_finality(&*blockchain, 2, 0, headers[4].hash(), set_change)
+			.unwrap()
+			.unwrap();
+		assert_eq!(proof.headers, vec![headers[4].clone()]);
+		assert_eq!(proof.verify(0, alice).unwrap(), headers[4]);
+	}
+
+	#[test]
+	fn finality_proof_rejects_broken_header_chain() {
+		let (blockchain, headers) = test_blockchain();
+
+		let mut proof = prove_finality(&*blockchain, 2, 0, headers[2].hash(), set_change)
+			.unwrap()
+			.unwrap();
+		proof.headers[0] = headers[0].clone();
+		assert!(proof.verify(0, vec![(Ed25519Keyring::Alice.public().into(), 1)]).is_err());
+	}
+
+	#[test]
+	fn finality_proof_requires_block_after_known_set() {
+		let (blockchain, headers) = test_blockchain();
+
+		// block #1 was finalized by Alice, before the set of Bob.
+		assert!(prove_finality(&*blockchain, 2, 1, headers[1].hash(), set_change).is_err());
+		assert!(prove_finality(&*blockchain, 2, 3, headers[4].hash(), set_change).is_err());
+	}
+
+	#[test]
+	fn warp_sync_proof_requires_recorded_set_changes() {
+		let (blockchain, _) = test_blockchain();
+
+		assert!(prove_warp_sync(&*blockchain, 3, 0, set_change).is_err());
+		assert!(prove_warp_sync(&*blockchain, 2, 3, set_change).is_err());
+	}
+}
@@ -18,16 +18,33 @@
 
 #![warn(missing_docs)]
 
-use std::{collections::HashMap, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc};
+use std::{
+	collections::HashMap, convert::TryFrom, path::PathBuf, fs::{self, File}, io::{self, Write}, sync::Arc,
+};
 use sp_core::{
-	crypto::{KeyTypeId, Pair as PairT, Public, IsWrappedBy, Protected}, traits::BareCryptoStore,
+	crypto::{key_types, KeyTypeId, Pair as PairT, Public, IsWrappedBy, Protected}, traits::BareCryptoStore,
+	H256, H512,
 };
-use sp_application_crypto::{AppKey, AppPublic, AppPair, ed25519, sr25519};
+use sp_application_crypto::{AppKey, AppPublic, AppPair, bls12_381, ed25519, sr25519};
 use parking_lot::RwLock;
 
+mod remote;
+
+pub use remote::{RemoteSigner, SignerEndpoint};
+
 /// Keystore pointer
 pub type KeyStorePtr = Arc<RwLock<Store>>;
 
+/// The key types whose key pairs are used by the node, e.g. by the consensus engines to author
+/// blocks and vote, which can't be routed to a remote signer.
+pub const KEY_PAIR_TYPES: &[KeyTypeId] = &[
+	key_types::BABE,
+	key_types::GRANDPA,
+	key_types::AURA,
+	key_types::AUTHORITY_DISCOVERY,
+	key_types::POA,
+];
+
 /// Keystore error.
 #[derive(Debug, derive_more::Display, derive_more::From)]
 pub enum Error {
@@ -47,6 +64,10 @@ pub enum Error {
 	/// Keystore unavailable
 	#[display(fmt="Keystore unavailable")]
 	Unavailable,
+	/// Remote signer error, or the key is held by a remote signer.
+	#[display(fmt="Remote signer: {}", _0)]
+	#[from(ignore)]
+	Remote(String),
 }
 
 /// Keystore Result
@@ -67,11 +88,17 @@ impl std::error::Error for Error {
 /// Stores key pairs in a file system store + short lived key pairs in memory.
 ///
 /// Every pair that is being generated by a `seed`, will be placed in memory.
+///
+/// The keys of the key types routed to a remote signer never live in the store: their public keys
+/// are listed and their messages signed by the signer, and they can't be generated, inserted or
+/// extracted as key pairs. The key types in `KEY_PAIR_TYPES` can't be routed.
 pub struct Store {
 	path: Option<PathBuf>,
 	/// Map over `(KeyTypeId, Raw public key)` -> `Key phrase/seed`
 	additional: HashMap<(KeyTypeId, Vec<u8>), String>,
 	password: Option<Protected<String>>,
+	/// Map over `KeyTypeId` -> `Remote signer holding the keys`
+	remote: HashMap<KeyTypeId, RemoteSigner>,
 }
 
 impl Store {
@@ -82,7 +109,7 @@ impl Store {
 		let path = path.into();
 		fs::create_dir_all(&path)?;
 
-		let instance = Self { path: Some(path), additional: HashMap::new(), password };
+		let instance = Self { path: Some(path), additional: HashMap::new(), password, remote: HashMap::new() };
 		Ok(Arc::new(RwLock::new(instance)))
 	}
 
@@ -91,10 +118,35 @@ impl Store {
 		Arc::new(RwLock::new(Self {
 			path: None,
 			additional: HashMap::new(),
-			password: None
+			password: None,
+			remote: HashMap::new(),
 		}))
 	}
 
+	/// Route the keys of `key_type` to `signer`.
+	///
+	/// Keys of this type already in the store are ignored from now on. Fails for the key types in
+	/// `KEY_PAIR_TYPES`, whose key pairs would silently be missing.
+	pub fn set_remote_signer(&mut self, key_type: KeyTypeId, signer: RemoteSigner) -> Result<()> {
+		if KEY_PAIR_TYPES.contains(&key_type) {
+			return Err(Error::Remote(format!(
+				"keys of type {} are used as key pairs by the node and can't be held by {}",
+				String::from_utf8_lossy(&key_type.0),
+				signer.endpoint(),
+			)))
+		}
+		self.remote.insert(key_type, signer);
+		Ok(())
+	}
+
+	/// Fail if the keys of `key_type` are held by a remote signer.
+	fn ensure_local(&self, key_type: KeyTypeId) -> Result<()> {
+		match self.remote.get(&key_type) {
+			Some(signer) => Err(Error::Remote(format!("key is held by {}", signer.endpoint()))),
+			None => Ok(()),
+		}
+	}
+
 	/// Get the key phrase for the given public key and key type from the in-memory store.
 	fn get_additional_pair(
 		&self,
@@ -117,6 +169,7 @@ impl Store {
 	///
 	/// Places it into the file system store.
 	fn insert_unknown(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<()> {
+		self.ensure_local(key_type)?;
 		if let Some(path) = self.key_file_path(public, key_type) {
 			let mut file = File::create(path).map_err(Error::Io)?;
 			serde_json::to_writer(&file, &suri).map_err(Error::Json)?;
@@ -133,6 +186,7 @@ impl Store {
 			suri,
 			self.password.as_ref().map(|p| &***p)
 		).map_err(|_| Error::InvalidSeed)?;
+		self.ensure_local(key_type)?;
 		self.insert_unknown(key_type, suri, pair.public().as_slice())
 			.map_err(|_| Error::Unavailable)?;
 		Ok(pair)
@@ -149,6 +203,7 @@ impl Store {
 	///
 	/// Places it into the file system store.
 	pub fn generate_by_type<Pair: PairT>(&self, key_type: KeyTypeId) -> Result<Pair> {
+		self.ensure_local(key_type)?;
 		let (pair, phrase, _) = Pair::generate_with_phrase(self.password.as_ref().map(|p| &***p));
 		if let Some(path) = self.key_file_path(pair.public().as_slice(), key_type) {
 			let mut file = File::create(path)?;
@@ -173,6 +228,7 @@ impl Store {
 		seed: &str,
 		key_type: KeyTypeId,
 	) -> Result<Pair> {
+		self.ensure_local(key_type)?;
 		let pair = Pair::from_string(seed, None).map_err(|_| Error::InvalidSeed)?;
 		self.insert_ephemeral_pair(&pair, seed, key_type);
 		Ok(pair)
@@ -187,6 +243,7 @@ impl Store {
 
 	/// Get the key phrase for a given public key and key type.
 	fn key_phrase_by_type(&self, public: &[u8], key_type: KeyTypeId) -> Result<String> {
+		self.ensure_local(key_type)?;
 		if let Some(phrase) = self.get_additional_pair(public, key_type) {
 			return Ok(phrase.clone())
 		}
@@ -221,10 +278,24 @@ impl Store {
 	}
 
 	/// Get public keys of all stored keys that match the given key type.
+	///
+	/// Keys of the type but of another crypto, whose length differs, are skipped.
 	pub fn public_keys_by_type<TPublic: Public>(&self, key_type: KeyTypeId) -> Result<Vec<TPublic>> {
+		let len = TPublic::default().as_ref().len();
+		if let Some(signer) = self.remote.get(&key_type) {
+			return signer.public_keys(key_type)?
+				.iter()
+				.map(|public| if public.len() == len {
+					Ok(TPublic::from_slice(public))
+				} else {
+					Err(Error::Remote(format!("public key 0x{} is not {} bytes long", hex::encode(public), len)))
+				})
+				.collect()
+		}
+
 		let mut public_keys: Vec<TPublic> = self.additional.keys()
 			.filter_map(|(ty, public)| {
-				if *ty == key_type {
+				if *ty == key_type && public.len() == len {
 					Some(TPublic::from_slice(public))
 				} else {
 					None
@@ -240,7 +311,7 @@ impl Store {
 				// skip directories and non-unicode file names (hex is unicode)
 				if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
 					match hex::decode(name) {
-						Ok(ref hex) if hex.len() > 4 => {
+						Ok(ref hex) if hex.len() == 4 + len => {
 							if &hex[0..4] != &key_type.0 { continue }
 							let public = TPublic::from_slice(&hex[4..]);
 							public_keys.push(public);
@@ -297,6 +368,38 @@ impl BareCryptoStore for Store {
 		self.key_pair_by_type::<sr25519::Pair>(pub_key, id).ok()
 	}
 
+	fn sr25519_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &sr25519::Public,
+		msg: &[u8],
+	) -> Option<sr25519::Signature> {
+		match self.remote.get(&id) {
+			Some(signer) => signer.sign(id, "sr25519", pub_key.as_ref(), msg).ok()
+				.and_then(|signature| sr25519::Signature::try_from(&signature[..]).ok())
+				.filter(|signature| sr25519::Pair::verify(signature, msg, pub_key)),
+			None => self.sr25519_key_pair(id, pub_key).map(|pair| pair.sign(msg)),
+		}
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &sr25519::Public,
+		input: &[u8],
+	) -> Option<sr25519::VrfSignature> {
+		match self.remote.get(&id) {
+			Some(signer) => signer.vrf_sign(id, pub_key.as_ref(), input).ok()
+				.filter(|(output, proof)| output.len() == 32 && proof.len() == 64)
+				.map(|(output, proof)| sr25519::VrfSignature {
+					output: H256::from_slice(&output),
+					proof: H512::from_slice(&proof),
+				})
+				.filter(|signature| sr25519::Pair::vrf_verify(signature, input, pub_key)),
+			None => self.sr25519_key_pair(id, pub_key).map(|pair| pair.vrf_sign(input)),
+		}
+	}
+
 	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
 		self.public_keys_by_type::<ed25519::Public>(key_type).unwrap_or_default()
 	}
@@ -318,6 +421,55 @@ impl BareCryptoStore for Store {
 		self.key_pair_by_type::<ed25519::Pair>(pub_key, id).ok()
 	}
 
+	fn ed25519_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &ed25519::Public,
+		msg: &[u8],
+	) -> Option<ed25519::Signature> {
+		match self.remote.get(&id) {
+			Some(signer) => signer.sign(id, "ed25519", pub_key.as_ref(), msg).ok()
+				.and_then(|signature| ed25519::Signature::try_from(&signature[..]).ok())
+				.filter(|signature| ed25519::Pair::verify(signature, msg, pub_key)),
+			None => self.ed25519_key_pair(id, pub_key).map(|pair| pair.sign(msg)),
+		}
+	}
+
+	fn bls12_381_public_keys(&self, key_type: KeyTypeId) -> Vec<bls12_381::Public> {
+		self.public_keys_by_type::<bls12_381::Public>(key_type).unwrap_or_default()
+	}
+
+	fn bls12_381_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<bls12_381::Public, String> {
+		let pair = match seed {
+			Some(seed) => self.insert_ephemeral_from_seed_by_type::<bls12_381::Pair>(seed, id),
+			None => self.generate_by_type::<bls12_381::Pair>(id),
+		}.map_err(|e| e.to_string())?;
+
+		Ok(pair.public())
+	}
+
+	fn bls12_381_key_pair(&self, id: KeyTypeId, pub_key: &bls12_381::Public) -> Option<bls12_381::Pair> {
+		self.key_pair_by_type::<bls12_381::Pair>(pub_key, id).ok()
+	}
+
+	fn bls12_381_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &bls12_381::Public,
+		msg: &[u8],
+	) -> Option<bls12_381::Signature> {
+		match self.remote.get(&id) {
+			Some(signer) => signer.sign(id, "bls12_381", pub_key.as_ref(), msg).ok()
+				.and_then(|signature| bls12_381::Signature::try_from(&signature[..]).ok())
+				.filter(|signature| bls12_381::Pair::verify(signature, msg, pub_key)),
+			None => self.bls12_381_key_pair(id, pub_key).map(|pair| pair.sign(msg)),
+		}
+	}
+
 	fn insert_unknown(&mut self, key_type: KeyTypeId, suri: &str, public: &[u8])
 		-> std::result::Result<(), ()>
 	{
@@ -329,7 +481,10 @@ impl BareCryptoStore for Store {
 	}
 
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
-		public_keys.iter().all(|(p, t)| self.key_phrase_by_type(&p, *t).is_ok())
+		public_keys.iter().all(|(p, t)| match self.remote.get(t) {
+			Some(signer) => signer.public_keys(*t).map(|keys| keys.contains(p)).unwrap_or(false),
+			None => self.key_phrase_by_type(&p, *t).is_ok(),
+		})
 	}
 }
 
@@ -420,6 +575,23 @@ mod tests {
 		assert_eq!(public_keys, store_pubs);
 	}
 
+	#[test]
+	fn public_keys_of_other_cryptos_are_skipped() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+
+		let sr25519 = store.write().sr25519_generate_new(SR25519, None).unwrap();
+		let bls12_381 = store.write().bls12_381_generate_new(SR25519, None).unwrap();
+		let ephemeral = store.write().bls12_381_generate_new(SR25519, Some("//Alice")).unwrap();
+
+		assert_eq!(store.read().sr25519_public_keys(SR25519), vec![sr25519]);
+		let mut bls12_381_keys = store.read().bls12_381_public_keys(SR25519);
+		bls12_381_keys.sort();
+		let mut expected = vec![bls12_381, ephemeral];
+		expected.sort();
+		assert_eq!(bls12_381_keys, expected);
+	}
+
 	#[test]
 	fn store_unknown_and_extract_it() {
 		let temp_dir = TempDir::new().unwrap();
@@ -442,6 +614,152 @@ mod tests {
 		assert_eq!(key_pair.public(), store_key_pair.public());
 	}
 
+	/// Serve `signer_publicKeys`, `signer_sign` and `signer_vrfSign` requests for `pair` on a local
+	/// TCP socket, listing `public` as its public key.
+	fn fake_signer_listing(pair: sr25519::Pair, public: Vec<u8>) -> SignerEndpoint {
+		use std::io::{BufRead, BufReader};
+		use std::net::TcpListener;
+
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = SignerEndpoint::Tcp(listener.local_addr().unwrap().to_string());
+		std::thread::spawn(move || for stream in listener.incoming() {
+			let mut stream = stream.unwrap();
+			let mut line = String::new();
+			BufReader::new(&stream).read_line(&mut line).unwrap();
+			let request: serde_json::Value = serde_json::from_str(&line).unwrap();
+			let result = match request["method"].as_str().unwrap() {
+				"signer_publicKeys" => serde_json::json!([format!("0x{}", hex::encode(&public))]),
+				"signer_sign" => {
+					let msg = hex::decode(&request["params"][3].as_str().unwrap()[2..]).unwrap();
+					serde_json::json!(format!("0x{}", hex::encode(pair.sign(&msg))))
+				},
+				"signer_vrfSign" => {
+					let input = hex::decode(&request["params"][2].as_str().unwrap()[2..]).unwrap();
+					let signature = pair.vrf_sign(&input);
+					serde_json::json!({
+						"output": format!("0x{}", hex::encode(signature.output)),
+						"proof": format!("0x{}", hex::encode(signature.proof)),
+					})
+				},
+				_ => serde_json::Value::Null,
+			};
+			let response = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result });
+			stream.write_all(format!("{}\n", response).as_bytes()).unwrap();
+		});
+
+		endpoint
+	}
+
+	/// Serve the requests for `pair` on a local TCP socket.
+	fn fake_signer(pair: sr25519::Pair) -> SignerEndpoint {
+		let public = pair.public().to_raw_vec();
+		fake_signer_listing(pair, public)
+	}
+
+	#[test]
+	fn remote_keys_are_signed_by_the_signer() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let local = store.write().generate_by_type::<sr25519::Pair>(SR25519).unwrap();
+
+		store.write().set_remote_signer(SR25519, RemoteSigner::new(fake_signer(pair.clone()))).unwrap();
+
+		assert_eq!(store.read().sr25519_public_keys(SR25519), vec![pair.public()]);
+		assert!(store.read().has_keys(&[(pair.public().to_raw_vec(), SR25519)]));
+		assert!(!store.read().has_keys(&[(local.public().to_raw_vec(), SR25519)]));
+
+		let signature = store.read().sr25519_sign(SR25519, &pair.public(), b"plug").unwrap();
+		assert!(sr25519::Pair::verify(&signature, b"plug", &pair.public()));
+		assert!(store.read().sr25519_sign(SR25519, &local.public(), b"plug").is_none());
+	}
+
+	#[test]
+	fn remote_keys_never_live_in_the_store() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		store.write().set_remote_signer(SR25519, RemoteSigner::new(fake_signer(pair.clone()))).unwrap();
+
+		assert!(store.read().key_pair_by_type::<sr25519::Pair>(&pair.public(), SR25519).is_err());
+		assert!(store.write().sr25519_generate_new(SR25519, None).is_err());
+		assert!(store.write().sr25519_generate_new(SR25519, Some("//Bob")).is_err());
+		assert!(BareCryptoStore::insert_unknown(
+			&mut *store.write(),
+			SR25519,
+			"//Alice",
+			pair.public().as_ref(),
+		).is_err());
+		assert!(fs::read_dir(temp_dir.path()).unwrap().next().is_none());
+
+		// Other key types are still held locally.
+		assert!(store.write().generate::<ed25519::AppPair>().is_ok());
+	}
+
+	#[test]
+	fn remote_keys_evaluate_the_vrf() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		store.write().set_remote_signer(SR25519, RemoteSigner::new(fake_signer(pair.clone()))).unwrap();
+
+		let signature = store.read().sr25519_vrf_sign(SR25519, &pair.public(), b"round 1").unwrap();
+		assert_eq!(signature, pair.vrf_sign(b"round 1"));
+	}
+
+	#[test]
+	fn malformed_remote_keys_are_rejected() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let endpoint = fake_signer_listing(pair, vec![1; 48]);
+		store.write().set_remote_signer(SR25519, RemoteSigner::new(endpoint)).unwrap();
+
+		assert!(store.read().public_keys_by_type::<sr25519::Public>(SR25519).is_err());
+		assert!(store.read().sr25519_public_keys(SR25519).is_empty());
+	}
+
+	#[test]
+	fn key_pair_types_are_not_routed() {
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+
+		for key_type in KEY_PAIR_TYPES {
+			let signer = RemoteSigner::new(fake_signer(pair.clone()));
+			assert!(store.write().set_remote_signer(*key_type, signer).is_err());
+		}
+		assert!(store.write().generate_by_type::<sr25519::Pair>(key_types::BABE).is_ok());
+	}
+
+	#[test]
+	fn unreachable_signers_time_out() {
+		use std::{net::TcpListener, time::{Duration, Instant}};
+
+		let temp_dir = TempDir::new().unwrap();
+		let store = Store::open(temp_dir.path(), None).unwrap();
+		let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+		// Accept connections without ever answering.
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let endpoint = SignerEndpoint::Tcp(listener.local_addr().unwrap().to_string());
+		let signer = RemoteSigner::new(endpoint).with_timeout(Duration::from_millis(100));
+		store.write().set_remote_signer(SR25519, signer).unwrap();
+
+		let started = Instant::now();
+		assert!(store.read().sr25519_sign(SR25519, &pair.public(), b"plug").is_none());
+		assert!(started.elapsed() < Duration::from_secs(2));
+		drop(listener);
+	}
+
+	#[test]
+	fn signer_endpoints_are_parsed() {
+		assert_eq!("tcp:127.0.0.1:9955".parse(), Ok(SignerEndpoint::Tcp("127.0.0.1:9955".into())));
+		#[cfg(unix)]
+		assert_eq!("unix:/run/signer.sock".parse(), Ok(SignerEndpoint::Unix("/run/signer.sock".into())));
+		assert!("127.0.0.1:9955".parse::<SignerEndpoint>().is_err());
+		assert!("tcp:".parse::<SignerEndpoint>().is_err());
+	}
+
 	#[test]
 	fn store_ignores_files_with_invalid_name() {
 		let temp_dir = TempDir::new().unwrap();
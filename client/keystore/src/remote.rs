@@ -0,0 +1,225 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Client of an external signer holding the secret keys of some key types, e.g. a HSM or a signer
+//! daemon.
+//!
+//! Requests are JSON-RPC 2.0 calls sent as a single line over a local TCP or Unix socket, answered
+//! by a single line. Binary data is 0x-prefixed hex. The signer implements three methods:
+//!
+//! - `signer_publicKeys(keyType)`: the public keys held for a key type, e.g. `["0x..."]`.
+//! - `signer_sign(keyType, scheme, public, message)`: the signature of `message` by `public`,
+//!   `scheme` being `"sr25519"`, `"ed25519"` or `"bls12_381"`.
+//! - `signer_vrfSign(keyType, public, input)`: the VRF output of the sr25519 key `public` for
+//!   `input` and its proof, e.g. `{"output": "0x...", "proof": "0x..."}`.
+//!
+//! The requests are made from host functions called by the runtime, e.g. by offchain workers, so
+//! every step of a request is bounded by the timeout of the signer, and the public keys are cached.
+
+use std::{
+	collections::HashMap, fmt, io::{self, BufRead, BufReader, Read, Write}, net::{TcpStream, ToSocketAddrs},
+	str::FromStr, sync::Arc, time::{Duration, Instant},
+};
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::PathBuf};
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use sp_core::crypto::KeyTypeId;
+
+use crate::{Error, Result};
+
+/// Default timeout of each step of a request to a remote signer: connecting, writing the request
+/// and reading the response.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Maximum size of a response of a remote signer.
+const MAX_RESPONSE_SIZE: u64 = 1024 * 1024;
+
+/// How long the public keys listed by a remote signer are cached.
+const PUBLIC_KEYS_CACHE_DURATION: Duration = Duration::from_secs(60);
+
+/// Local endpoint of a remote signer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerEndpoint {
+	/// A TCP socket, formatted as `tcp:HOST:PORT`.
+	Tcp(String),
+	/// A Unix socket, formatted as `unix:PATH`.
+	#[cfg(unix)]
+	Unix(PathBuf),
+}
+
+impl FromStr for SignerEndpoint {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		if s.starts_with("tcp:") && s.len() > 4 {
+			return Ok(SignerEndpoint::Tcp(s[4..].into()))
+		}
+		#[cfg(unix)]
+		{
+			if s.starts_with("unix:") && s.len() > 5 {
+				return Ok(SignerEndpoint::Unix(s[5..].into()))
+			}
+		}
+		Err(format!("Invalid signer endpoint {}, expected tcp:HOST:PORT or unix:PATH", s))
+	}
+}
+
+impl fmt::Display for SignerEndpoint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			SignerEndpoint::Tcp(address) => write!(f, "tcp:{}", address),
+			#[cfg(unix)]
+			SignerEndpoint::Unix(path) => write!(f, "unix:{}", path.display()),
+		}
+	}
+}
+
+/// Client of a remote signer.
+///
+/// A connection is opened per request, so the node keeps working when the signer restarts.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+	endpoint: SignerEndpoint,
+	timeout: Duration,
+	/// The public keys listed by the signer, by key type, with the time they were listed.
+	public_keys: Arc<Mutex<HashMap<KeyTypeId, (Instant, Vec<Vec<u8>>)>>>,
+}
+
+impl RemoteSigner {
+	/// Create a client of the signer at `endpoint`.
+	pub fn new(endpoint: SignerEndpoint) -> Self {
+		RemoteSigner { endpoint, timeout: DEFAULT_TIMEOUT, public_keys: Default::default() }
+	}
+
+	/// Set the timeout of a request.
+	pub fn with_timeout(mut self, timeout: Duration) -> Self {
+		self.timeout = timeout;
+		self
+	}
+
+	/// The endpoint of the signer.
+	pub fn endpoint(&self) -> &SignerEndpoint {
+		&self.endpoint
+	}
+
+	/// Get the public keys of `key_type` held by the signer, listed at most
+	/// `PUBLIC_KEYS_CACHE_DURATION` ago.
+	pub fn public_keys(&self, key_type: KeyTypeId) -> Result<Vec<Vec<u8>>> {
+		if let Some((listed, keys)) = self.public_keys.lock().get(&key_type) {
+			if listed.elapsed() < PUBLIC_KEYS_CACHE_DURATION {
+				return Ok(keys.clone())
+			}
+		}
+
+		let keys: Vec<String> = serde_json::from_value(
+			self.call("signer_publicKeys", json!([key_type_str(key_type)?]))?
+		)?;
+		let keys = keys.iter().map(|key| from_hex(key)).collect::<Result<Vec<_>>>()?;
+		self.public_keys.lock().insert(key_type, (Instant::now(), keys.clone()));
+		Ok(keys)
+	}
+
+	/// Sign `msg` with the key `public` of `key_type` using `scheme`, see the module docs.
+	pub fn sign(&self, key_type: KeyTypeId, scheme: &str, public: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+		let signature: String = serde_json::from_value(self.call(
+			"signer_sign",
+			json!([key_type_str(key_type)?, scheme, to_hex(public), to_hex(msg)]),
+		)?)?;
+		from_hex(&signature)
+	}
+
+	/// Evaluate the VRF of the sr25519 key `public` of `key_type` on `input`, returning the output
+	/// and its proof.
+	pub fn vrf_sign(&self, key_type: KeyTypeId, public: &[u8], input: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+		let signature = self.call(
+			"signer_vrfSign",
+			json!([key_type_str(key_type)?, to_hex(public), to_hex(input)]),
+		)?;
+		let field = |name: &str| signature.get(name)
+			.and_then(Value::as_str)
+			.ok_or_else(|| Error::Remote(format!("signer_vrfSign returned no {}", name)));
+		Ok((from_hex(field("output")?)?, from_hex(field("proof")?)?))
+	}
+
+	/// Send a request to the signer and return the result of the response.
+	fn call(&self, method: &str, params: Value) -> Result<Value> {
+		let request = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+		let line = match &self.endpoint {
+			SignerEndpoint::Tcp(address) => {
+				let stream = connect_tcp(address, self.timeout)?;
+				stream.set_read_timeout(Some(self.timeout))?;
+				stream.set_write_timeout(Some(self.timeout))?;
+				exchange(stream, &request)?
+			},
+			#[cfg(unix)]
+			SignerEndpoint::Unix(path) => {
+				let stream = UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(self.timeout))?;
+				stream.set_write_timeout(Some(self.timeout))?;
+				exchange(stream, &request)?
+			},
+		};
+
+		let mut response: Value = serde_json::from_str(&line)?;
+		if let Some(error) = response.get("error") {
+			return Err(Error::Remote(format!("{} failed: {}", method, error)))
+		}
+		match response.get_mut("result") {
+			Some(result) => Ok(result.take()),
+			None => Err(Error::Remote(format!("{} returned no result", method))),
+		}
+	}
+}
+
+/// Connect to the first reachable address of `address`, waiting at most `timeout` for each.
+fn connect_tcp(address: &str, timeout: Duration) -> io::Result<TcpStream> {
+	let mut last_error = io::Error::new(io::ErrorKind::NotFound, format!("{} has no address", address));
+	for address in address.to_socket_addrs()? {
+		match TcpStream::connect_timeout(&address, timeout) {
+			Ok(stream) => return Ok(stream),
+			Err(e) => last_error = e,
+		}
+	}
+	Err(last_error)
+}
+
+/// Write `request` as a line to `stream` and read the response line, at most `MAX_RESPONSE_SIZE`
+/// long.
+fn exchange<S: Read + Write>(mut stream: S, request: &Value) -> Result<String> {
+	stream.write_all(format!("{}\n", request).as_bytes())?;
+	stream.flush()?;
+
+	let mut line = String::new();
+	BufReader::new(stream.take(MAX_RESPONSE_SIZE)).read_line(&mut line)?;
+	Ok(line)
+}
+
+fn key_type_str(key_type: KeyTypeId) -> Result<String> {
+	String::from_utf8(key_type.0.to_vec())
+		.map_err(|_| Error::Remote(format!("Key type {:?} is not valid utf8", key_type.0)))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	format!("0x{}", hex::encode(bytes))
+}
+
+fn from_hex(input: &str) -> Result<Vec<u8>> {
+	if !input.starts_with("0x") {
+		return Err(Error::Remote(format!("Expected 0x-prefixed hex, got {}", input)))
+	}
+	hex::decode(&input[2..]).map_err(|e| Error::Remote(format!("Invalid hex {}: {}", input, e)))
+}
@@ -0,0 +1,56 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Network privacy errors.
+
+/// NetworkPrivacy Result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error type for the network privacy module.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// The runtime does not implement the `NetworkPrivacyApi`.
+	ApiNotSupported,
+	/// The runtime implements a version of the api lower than required.
+	#[display(
+		fmt = "The runtime implements version {} of the `{}` api, but at least version {} is required",
+		found,
+		api,
+		required,
+	)]
+	#[from(ignore)]
+	ApiVersionTooLow {
+		/// The name of the api.
+		api: &'static str,
+		/// The version implemented by the runtime.
+		found: u32,
+		/// The minimal required version.
+		required: u32,
+	},
+	/// Failed calling into the Substrate runtime.
+	CallingRuntime(sp_blockchain::Error),
+}
+
+impl From<sp_api::ApiVersionError<sp_blockchain::Error>> for Error {
+	fn from(e: sp_api::ApiVersionError<sp_blockchain::Error>) -> Self {
+		match e {
+			sp_api::ApiVersionError::NotImplemented { .. } => Error::ApiNotSupported,
+			sp_api::ApiVersionError::VersionTooLow { api, found, required } =>
+				Error::ApiVersionTooLow { api, found, required },
+			sp_api::ApiVersionError::Runtime(e) => Error::CallingRuntime(e),
+		}
+	}
+}
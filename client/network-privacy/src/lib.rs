@@ -0,0 +1,262 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+#![warn(missing_docs)]
+
+//! Plug network privacy.
+//!
+//! This crate keeps the reserved peers of a node in sync with the reserved peers of a private
+//! network stored on chain. [`ReservedPeersSync`] listens for finalized blocks and on every
+//! finalized block:
+//!
+//! 1. Retrieves the reserved peers through the `NetworkPrivacyApi`.
+//!
+//! 2. Computes the difference to the reserved peers it added to the network before.
+//!
+//! 3. Adds new reserved peers and removes reserved peers that are not on chain anymore.
+//!
+//! If the runtime does not implement the `NetworkPrivacyApi` or calling it fails, the following
+//! finalized blocks are skipped with an exponential backoff.
+//!
+//! [`OnChainMembership`] can be passed to the network as a membership validator, so that peers
+//! which are not reserved peers on chain are disconnected during the handshake. Full nodes prove
+//! their membership with a [`MembershipStatement`] signed by the account operating them on chain.
+//! Light clients authorized on chain are admitted as well, but only served light client requests.
+//! The members are retrieved on every finalized block by the future returned by
+//! `OnChainMembership::run`, never from the network thread.
+//!
+//! [`OnChainTransactionsKey`] can be passed to the network as a transactions key provider, so
+//! that transactions gossiped between members are encrypted with the rotating key stored on
+//! chain. The keys are retrieved on every finalized block by the future returned by
+//! `OnChainTransactionsKey::run`, never from the network thread.
+//!
+//! [`PeerScoreSync`] applies the reputation changes returned by the `NetworkPolicyApi` of every
+//! finalized block, so that misbehaving peers are penalized by every node of the network.
+
+use std::collections::HashSet;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use futures::{Future, StreamExt};
+use log::{debug, warn};
+use sc_client_api::BlockchainEvents;
+use sc_network::{ExHashT, PeerId, ReputationChange, config::parse_str_addr};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_network_privacy::NetworkPrivacyApi;
+use sp_runtime::{traits::Block as BlockT, generic::BlockId};
+
+pub use error::{Error, Result};
+pub use membership::{MembershipStatement, OnChainMembership, statement_payload};
+pub use peer_scores::PeerScoreSync;
+pub use transactions_key::OnChainTransactionsKey;
+
+#[cfg(test)]
+mod tests;
+
+mod error;
+mod membership;
+mod peer_scores;
+mod transactions_key;
+
+/// The backoff after the first failed synchronization.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(6);
+
+/// The maximum backoff between failed synchronizations.
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+/// NetworkProvider provides ReservedPeersSync with all necessary hooks into the underlying
+/// Substrate networking. Using this trait abstraction instead of NetworkService directly is
+/// necessary to unit test ReservedPeersSync.
+pub trait NetworkProvider {
+	/// Adds a reserved peer. The string should encode the address and peer ID of the remote node.
+	fn add_reserved_peer(&self, peer: String) -> std::result::Result<(), String>;
+
+	/// Removes a reserved peer.
+	fn remove_reserved_peer(&self, peer: PeerId);
+
+	/// Changes the reputation of a peer.
+	fn report_peer(&self, who: PeerId, cost_benefit: ReputationChange);
+}
+
+impl<B, H> NetworkProvider for sc_network::NetworkService<B, H>
+where
+	B: BlockT + 'static,
+	H: ExHashT,
+{
+	fn add_reserved_peer(&self, peer: String) -> std::result::Result<(), String> {
+		self.add_reserved_peer(peer)
+	}
+
+	fn remove_reserved_peer(&self, peer: PeerId) {
+		self.remove_reserved_peer(peer)
+	}
+
+	fn report_peer(&self, who: PeerId, cost_benefit: ReputationChange) {
+		self.report_peer(who, cost_benefit)
+	}
+}
+
+/// Exponential backoff between failed synchronizations.
+#[derive(Debug, Default)]
+struct Backoff {
+	/// The current backoff, `None` if the last synchronization succeeded.
+	current: Option<Duration>,
+	/// No synchronization should be attempted before this instant.
+	next_attempt: Option<Instant>,
+}
+
+impl Backoff {
+	/// Returns if a synchronization should be attempted at `now`.
+	fn is_ready(&self, now: Instant) -> bool {
+		self.next_attempt.map_or(true, |next| now >= next)
+	}
+
+	/// Note a failed synchronization at `now`.
+	fn failed(&mut self, now: Instant) {
+		let backoff = self.current
+			.map_or(INITIAL_BACKOFF, |b| std::cmp::min(b * 2, MAX_BACKOFF));
+		self.current = Some(backoff);
+		self.next_attempt = Some(now + backoff);
+	}
+
+	/// Note a successful synchronization.
+	fn reset(&mut self) {
+		self.current = None;
+		self.next_attempt = None;
+	}
+}
+
+/// Returns the peers that need to be added and the peers that need to be removed to get from
+/// `current` to `on_chain`.
+fn diff(current: &HashSet<String>, on_chain: &HashSet<String>) -> (Vec<String>, Vec<String>) {
+	let to_add = on_chain.difference(current).cloned().collect();
+	let to_remove = current.difference(on_chain).cloned().collect();
+
+	(to_add, to_remove)
+}
+
+/// A `ReservedPeersSync` keeps the reserved peers of the node in sync with the chain state.
+pub struct ReservedPeersSync<Client, Network, Block> {
+	client: Arc<Client>,
+	network: Arc<Network>,
+	/// The reserved peers that were added to the network by us.
+	current: HashSet<String>,
+	backoff: Backoff,
+	phantom: PhantomData<Block>,
+}
+
+impl<Client, Network, Block> ReservedPeersSync<Client, Network, Block>
+where
+	Block: BlockT + 'static,
+	Network: NetworkProvider,
+	Client: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPrivacyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Return a new reserved peers synchronization.
+	pub fn new(client: Arc<Client>, network: Arc<Network>) -> Self {
+		ReservedPeersSync {
+			client,
+			network,
+			current: HashSet::new(),
+			backoff: Backoff::default(),
+			phantom: PhantomData,
+		}
+	}
+
+	/// Run the synchronization on every finalized block.
+	///
+	/// The returned future is a long running task with the same lifetime as the node itself.
+	pub fn run(mut self) -> impl Future<Output = ()> {
+		let mut finality_notifications = self.client.finality_notification_stream();
+
+		async move {
+			while let Some(notification) = finality_notifications.next().await {
+				self.on_finalized(&notification.hash, Instant::now());
+			}
+		}
+	}
+
+	/// Synchronize the reserved peers with the state of the finalized block `hash`, unless we are
+	/// backing off.
+	fn on_finalized(&mut self, hash: &Block::Hash, now: Instant) {
+		if !self.backoff.is_ready(now) {
+			return;
+		}
+
+		match self.sync(&BlockId::hash(*hash)) {
+			Ok(()) => self.backoff.reset(),
+			Err(e) => {
+				self.backoff.failed(now);
+				warn!(
+					target: "network-privacy",
+					"Failed to synchronize reserved peers at {:?}: {}. Retrying in {:?}.",
+					hash,
+					e,
+					self.backoff.current,
+				);
+			}
+		}
+	}
+
+	/// Synchronize the reserved peers with the state at the given block.
+	fn sync(&mut self, at: &BlockId<Block>) -> Result<()> {
+		let runtime_api = self.client.runtime_api();
+
+		let api = runtime_api
+			.require_api::<dyn NetworkPrivacyApi<Block, Error = sp_blockchain::Error>>(at, 1)?;
+		let on_chain = api.reserved_peers(at)?
+			.into_iter()
+			.filter_map(|peer| match String::from_utf8(peer) {
+				Ok(peer) => Some(peer),
+				Err(e) => {
+					warn!(target: "network-privacy", "Ignoring non utf8 reserved peer: {:?}", e);
+					None
+				}
+			})
+			.collect::<HashSet<_>>();
+
+		let (to_add, to_remove) = diff(&self.current, &on_chain);
+
+		for peer in to_remove {
+			match parse_str_addr(&peer) {
+				Ok((peer_id, _)) => {
+					debug!(target: "network-privacy", "Removing reserved peer {}", peer);
+					self.network.remove_reserved_peer(peer_id);
+				},
+				Err(e) => warn!(
+					target: "network-privacy",
+					"Failed to parse reserved peer {}: {:?}", peer, e,
+				),
+			}
+			self.current.remove(&peer);
+		}
+
+		for peer in to_add {
+			debug!(target: "network-privacy", "Adding reserved peer {}", peer);
+			match self.network.add_reserved_peer(peer.clone()) {
+				Ok(()) => { self.current.insert(peer); },
+				Err(e) => warn!(
+					target: "network-privacy",
+					"Failed to add reserved peer {}: {}", peer, e,
+				),
+			}
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,389 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Membership of the private network, checked against the chain state.
+
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
+
+use codec::{Decode, Encode};
+use futures::{Future, StreamExt};
+use log::{debug, warn};
+use parking_lot::RwLock;
+use sc_client_api::BlockchainEvents;
+use sc_network::{PeerId, config::{MembershipStatus, MembershipValidator, parse_str_addr}};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{Pair, crypto::key_types, sr25519, traits::BareCryptoStorePtr};
+use sp_network_privacy::{NetworkPrivacyApi, PeerIdBytes};
+use sp_runtime::{traits::{Block as BlockT, NumberFor, UniqueSaturatedInto}, generic::BlockId};
+
+use crate::{Error, Result};
+
+/// Context of the payload signed in membership statements.
+const STATEMENT_CONTEXT: &[u8] = b"plug:network-membership";
+
+/// The statement a member sends in its status message to prove its membership: the signature
+/// by the account operating the member, as stored on chain, of the `statement_payload` binding
+/// the `PeerId` of the member to the network.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct MembershipStatement {
+	/// The sr25519 public key of the operator of the member.
+	pub operator: sr25519::Public,
+	/// The signature of the `statement_payload` by the operator.
+	pub signature: sr25519::Signature,
+}
+
+/// The payload signed by the operator of the member `peer_id` of the network with the encoded
+/// genesis hash `genesis_hash`.
+pub fn statement_payload(genesis_hash: &[u8], peer_id: &PeerId) -> Vec<u8> {
+	(STATEMENT_CONTEXT, genesis_hash, peer_id.as_bytes()).encode()
+}
+
+/// Validates that peers are members of the private network stored on chain.
+///
+/// The members are the reserved peers returned by the `NetworkPrivacyApi` at the last finalized
+/// block, the light clients are the members and the authorized light clients returned by the
+/// `NetworkPrivacyApi`. They are retrieved by the future returned by `run` on every finalized
+/// block, the validator itself never calls into the runtime, so that the network isn't blocked
+/// by the runtime.
+///
+/// Full nodes prove their membership with a `MembershipStatement` signed by the operator of
+/// their reserved node, which the local node signs with the operator key of the keystore passed
+/// to `with_keystore`, under the `NETWORK_MEMBERSHIP` key type. Runtimes before version 3 of the
+/// `NetworkPrivacyApi` don't return the operators, their members are trusted by their `PeerId`.
+///
+/// If the runtime does not implement the `NetworkPrivacyApi`, every peer is a member. If calling
+/// the runtime fails, the members of the last successful call are used. Until the members are
+/// retrieved for the first time, no peer is a member.
+pub struct OnChainMembership<Client, Block: BlockT> {
+	client: Arc<Client>,
+	membership: Arc<RwLock<Membership>>,
+	keystore: Option<BareCryptoStorePtr>,
+	/// The encoded genesis hash, binding the membership statements to the network.
+	genesis_hash: Vec<u8>,
+	phantom: PhantomData<Block>,
+}
+
+impl<Client, Block: BlockT> Clone for OnChainMembership<Client, Block> {
+	fn clone(&self) -> Self {
+		OnChainMembership {
+			client: self.client.clone(),
+			membership: self.membership.clone(),
+			keystore: self.keystore.clone(),
+			genesis_hash: self.genesis_hash.clone(),
+			phantom: PhantomData,
+		}
+	}
+}
+
+/// The membership of the private network, as last retrieved from the chain.
+#[derive(Clone)]
+pub(crate) enum Membership {
+	/// The members were never retrieved.
+	Unknown,
+	/// The runtime does not restrict membership.
+	Unrestricted,
+	/// The members of the network.
+	Restricted(Members),
+}
+
+/// The members and the authorized light clients of the private network.
+#[derive(Clone)]
+pub(crate) struct Members {
+	pub(crate) members: HashSet<PeerId>,
+	/// The operators of the members, `None` if the runtime doesn't return them.
+	pub(crate) operators: Option<HashMap<PeerId, sr25519::Public>>,
+	pub(crate) light_clients: HashSet<PeerId>,
+	/// When and at which block number the members were retrieved.
+	pub(crate) synced: (Instant, u64),
+}
+
+impl Membership {
+	/// Returns `true` if `peer_id` is a member.
+	pub(crate) fn is_member(&self, peer_id: &PeerId) -> bool {
+		match self {
+			Membership::Unknown => false,
+			Membership::Unrestricted => true,
+			Membership::Restricted(members) => members.members.contains(peer_id),
+		}
+	}
+
+	/// Returns `true` if `statement` is signed by the operator of the member `peer_id` of the
+	/// network with the encoded genesis hash `genesis_hash`.
+	pub(crate) fn verify_statement(&self, genesis_hash: &[u8], peer_id: &PeerId, statement: &[u8]) -> bool {
+		let operators = match self {
+			Membership::Restricted(Members { operators: Some(operators), .. }) => operators,
+			_ => return self.is_member(peer_id),
+		};
+		let operator = match operators.get(peer_id) {
+			Some(operator) => operator,
+			None => return false,
+		};
+
+		match MembershipStatement::decode(&mut &statement[..]) {
+			Ok(statement) => statement.operator == *operator && sr25519::Pair::verify(
+				&statement.signature,
+				statement_payload(genesis_hash, peer_id),
+				operator,
+			),
+			Err(_) => false,
+		}
+	}
+
+	/// Returns `true` if `peer_id` is a member or an authorized light client.
+	pub(crate) fn is_light_client(&self, peer_id: &PeerId) -> bool {
+		match self {
+			Membership::Unknown => false,
+			Membership::Unrestricted => true,
+			Membership::Restricted(members) =>
+				members.members.contains(peer_id) || members.light_clients.contains(peer_id),
+		}
+	}
+}
+
+impl<Client, Block> OnChainMembership<Client, Block>
+where
+	Block: BlockT,
+	Client: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPrivacyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Return a new on chain membership validator, with no members until `run` retrieves them.
+	pub fn new(client: Arc<Client>) -> Self {
+		let genesis_hash = client.info().genesis_hash.as_ref().to_vec();
+		OnChainMembership {
+			client,
+			membership: Arc::new(RwLock::new(Membership::Unknown)),
+			keystore: None,
+			genesis_hash,
+			phantom: PhantomData,
+		}
+	}
+
+	/// Sign the membership statements of the local node with the key of its operator, if the
+	/// keystore holds it under the `NETWORK_MEMBERSHIP` key type.
+	pub fn with_keystore(mut self, keystore: BareCryptoStorePtr) -> Self {
+		self.keystore = Some(keystore);
+		self
+	}
+
+	/// Retrieve the members at the given block.
+	fn members(&self, at: &BlockId<Block>, number: NumberFor<Block>) -> Result<Members> {
+		let runtime_api = self.client.runtime_api();
+
+		let api = runtime_api
+			.require_api::<dyn NetworkPrivacyApi<Block, Error = sp_blockchain::Error>>(at, 1)?;
+		// runtimes before version 2 of the api do not authorize light clients.
+		let light_clients = if api.api_version() >= 2 {
+			api.authorized_light_clients(at)?
+		} else {
+			Vec::new()
+		};
+		// runtimes before version 3 of the api do not return the operators.
+		let operators = if api.api_version() >= 3 {
+			Some(parse_operators(api.reserved_node_operators(at)?))
+		} else {
+			None
+		};
+		Ok(Members {
+			members: parse_members(api.reserved_peers(at)?),
+			operators,
+			light_clients: parse_light_clients(light_clients),
+			synced: (Instant::now(), number.unique_saturated_into()),
+		})
+	}
+
+	/// Retrieve the members at the last finalized block, keeping the last retrieved members if
+	/// calling the runtime fails.
+	fn refresh(&self) {
+		let info = self.client.info();
+		let membership = match self.members(&BlockId::hash(info.finalized_hash), info.finalized_number) {
+			Ok(members) => Membership::Restricted(members),
+			Err(Error::ApiNotSupported) => Membership::Unrestricted,
+			Err(e) => {
+				warn!(
+					target: "network-privacy",
+					"Failed to retrieve the members of the private network at {:?}: {}",
+					info.finalized_hash,
+					e,
+				);
+				return;
+			}
+		};
+		*self.membership.write() = membership;
+	}
+}
+
+impl<Client, Block> OnChainMembership<Client, Block>
+where
+	Block: BlockT + 'static,
+	Client: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>
+		+ Send + Sync + 'static,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPrivacyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Retrieve the members now and on every finalized block.
+	///
+	/// The returned future is a long running task with the same lifetime as the node itself.
+	pub fn run(&self) -> impl Future<Output = ()> {
+		let membership = self.clone();
+		let mut finality_notifications = self.client.finality_notification_stream();
+
+		async move {
+			membership.refresh();
+			while finality_notifications.next().await.is_some() {
+				membership.refresh();
+			}
+		}
+	}
+}
+
+impl<Client, Block> MembershipValidator for OnChainMembership<Client, Block>
+where
+	Block: BlockT,
+	Client: Send + Sync,
+{
+	fn is_member(&self, peer_id: &PeerId) -> bool {
+		let is_member = self.membership.read().is_member(peer_id);
+		if !is_member {
+			debug!(target: "network-privacy", "Peer {} is not a member", peer_id);
+		}
+
+		is_member
+	}
+
+	fn local_statement(&self, local_peer_id: &PeerId) -> Vec<u8> {
+		let operator = match &*self.membership.read() {
+			Membership::Restricted(Members { operators: Some(operators), .. }) =>
+				operators.get(local_peer_id).cloned(),
+			_ => None,
+		};
+		let (operator, keystore) = match (operator, &self.keystore) {
+			(Some(operator), Some(keystore)) => (operator, keystore),
+			_ => return Vec::new(),
+		};
+
+		let payload = statement_payload(&self.genesis_hash, local_peer_id);
+		match keystore.read().sr25519_sign(key_types::NETWORK_MEMBERSHIP, &operator, &payload) {
+			Some(signature) => MembershipStatement { operator, signature }.encode(),
+			None => {
+				debug!(
+					target: "network-privacy",
+					"The key of the operator {} of the local node is not in the keystore",
+					operator,
+				);
+				Vec::new()
+			}
+		}
+	}
+
+	fn verify_statement(&self, peer_id: &PeerId, statement: &[u8]) -> bool {
+		let is_member = self.membership.read().verify_statement(&self.genesis_hash, peer_id, statement);
+		if !is_member {
+			debug!(target: "network-privacy", "Peer {} didn't prove it is a member", peer_id);
+		}
+
+		is_member
+	}
+
+	fn is_light_client(&self, peer_id: &PeerId) -> bool {
+		let is_light_client = self.membership.read().is_light_client(peer_id);
+		if !is_light_client {
+			debug!(target: "network-privacy", "Peer {} is not an authorized light client", peer_id);
+		}
+
+		is_light_client
+	}
+
+	fn status(&self) -> Option<MembershipStatus> {
+		let membership = self.membership.read();
+		let members = match &*membership {
+			Membership::Restricted(members) => members,
+			Membership::Unknown => return Some(MembershipStatus {
+				enforced: true,
+				members: 0,
+				light_clients: 0,
+				last_sync_block: None,
+				since_last_sync: None,
+			}),
+			Membership::Unrestricted => return Some(MembershipStatus {
+				enforced: false,
+				members: 0,
+				light_clients: 0,
+				last_sync_block: None,
+				since_last_sync: None,
+			}),
+		};
+
+		Some(MembershipStatus {
+			enforced: true,
+			members: members.members.len(),
+			light_clients: members.light_clients.difference(&members.members).count(),
+			last_sync_block: Some(members.synced.1),
+			since_last_sync: Some(members.synced.0.elapsed()),
+		})
+	}
+}
+
+/// Extract the peer ids from the encoded reserved peer addresses, ignoring invalid entries.
+pub(crate) fn parse_members(peers: Vec<Vec<u8>>) -> HashSet<PeerId> {
+	peers.into_iter()
+		.filter_map(|peer| {
+			let peer = String::from_utf8(peer).ok()?;
+			match parse_str_addr(&peer) {
+				Ok((peer_id, _)) => Some(peer_id),
+				Err(e) => {
+					warn!(target: "network-privacy", "Ignoring invalid member {}: {:?}", peer, e);
+					None
+				}
+			}
+		})
+		.collect()
+}
+
+/// Decode the peer ids of the authorized light clients, ignoring invalid entries.
+pub(crate) fn parse_light_clients(peers: Vec<PeerIdBytes>) -> HashSet<PeerId> {
+	peers.into_iter()
+		.filter_map(|peer_id| match PeerId::from_bytes(peer_id) {
+			Ok(peer_id) => Some(peer_id),
+			Err(bytes) => {
+				warn!(target: "network-privacy", "Ignoring invalid light client {:?}", bytes);
+				None
+			}
+		})
+		.collect()
+}
+
+/// Extract the peer ids and the operators from the encoded reserved peer addresses, ignoring
+/// invalid entries.
+pub(crate) fn parse_operators(peers: Vec<(Vec<u8>, [u8; 32])>) -> HashMap<PeerId, sr25519::Public> {
+	peers.into_iter()
+		.filter_map(|(peer, operator)| {
+			let peer = String::from_utf8(peer).ok()?;
+			match parse_str_addr(&peer) {
+				Ok((peer_id, _)) => Some((peer_id, sr25519::Public::from_raw(operator))),
+				Err(e) => {
+					warn!(target: "network-privacy", "Ignoring invalid member {}: {:?}", peer, e);
+					None
+				}
+			}
+		})
+		.collect()
+}
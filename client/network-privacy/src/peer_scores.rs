@@ -0,0 +1,157 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Reputation changes decided on chain, applied to the peers of the node.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use futures::{Future, StreamExt};
+use log::{debug, warn};
+use sc_client_api::BlockchainEvents;
+use sc_network::{PeerId, ReputationChange};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_network_privacy::{NetworkPolicyApi, PeerIdBytes};
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, NumberFor, Saturating, Zero},
+	generic::BlockId,
+};
+
+use crate::{Error, NetworkProvider, Result};
+
+/// The maximum number of finalized blocks whose adjustments are applied at once. Adjustments of
+/// older blocks are skipped, e.g. when the node catches up with the chain.
+const MAX_BLOCKS_PER_FINALIZATION: u32 = 256;
+
+/// Reason of the reputation changes reported to the peerset.
+const ADJUSTMENT_REASON: &str = "On chain peer score adjustment";
+
+/// A `PeerScoreSync` applies the reputation changes returned by the `NetworkPolicyApi` of every
+/// finalized block to the peers of the node.
+pub struct PeerScoreSync<Client, Network, Block: BlockT> {
+	client: Arc<Client>,
+	network: Arc<Network>,
+	/// The last finalized block whose adjustments were applied.
+	last_applied: Option<NumberFor<Block>>,
+	phantom: PhantomData<Block>,
+}
+
+impl<Client, Network, Block> PeerScoreSync<Client, Network, Block>
+where
+	Block: BlockT + 'static,
+	Network: NetworkProvider,
+	Client: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + HeaderBackend<Block>
+		+ Send + Sync + 'static,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPolicyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Return a new peer score synchronization.
+	pub fn new(client: Arc<Client>, network: Arc<Network>) -> Self {
+		PeerScoreSync {
+			client,
+			network,
+			last_applied: None,
+			phantom: PhantomData,
+		}
+	}
+
+	/// Apply the adjustments of every finalized block.
+	///
+	/// The returned future is a long running task with the same lifetime as the node itself.
+	pub fn run(mut self) -> impl Future<Output = ()> {
+		let mut finality_notifications = self.client.finality_notification_stream();
+
+		async move {
+			while let Some(notification) = finality_notifications.next().await {
+				self.on_finalized(notification.header);
+			}
+		}
+	}
+
+	/// Apply the adjustments of the finalized blocks up to and including `header` that were not
+	/// applied yet.
+	fn on_finalized(&mut self, header: Block::Header) {
+		let number = *header.number();
+		let first = match self.last_applied {
+			Some(last) => last + 1u32.into(),
+			None => number,
+		};
+		let first = std::cmp::max(first, number.saturating_sub(MAX_BLOCKS_PER_FINALIZATION.into()));
+		if first > number {
+			return;
+		}
+
+		let mut blocks = vec![header.hash()];
+		let mut parent = *header.parent_hash();
+		let mut current = number;
+		while current > first && !current.is_zero() {
+			match self.client.header(BlockId::hash(parent)) {
+				Ok(Some(header)) => {
+					blocks.push(header.hash());
+					parent = *header.parent_hash();
+					current = *header.number();
+				},
+				Ok(None) | Err(_) => {
+					warn!(
+						target: "network-privacy",
+						"Missing header {:?}, skipping older peer score adjustments", parent,
+					);
+					break;
+				},
+			}
+		}
+
+		for hash in blocks.into_iter().rev() {
+			match self.apply(&BlockId::hash(hash)) {
+				Ok(()) | Err(Error::ApiNotSupported) => {},
+				Err(e) => warn!(
+					target: "network-privacy",
+					"Failed to apply peer score adjustments at {:?}: {}", hash, e,
+				),
+			}
+		}
+		self.last_applied = Some(number);
+	}
+
+	/// Apply the adjustments of the block `at`.
+	fn apply(&self, at: &BlockId<Block>) -> Result<()> {
+		let runtime_api = self.client.runtime_api();
+
+		let api = runtime_api
+			.require_api::<dyn NetworkPolicyApi<Block, Error = sp_blockchain::Error>>(at, 1)?;
+		for (peer_id, value) in parse_adjustments(api.peer_score_adjustments(at)?) {
+			debug!(target: "network-privacy", "Adjusting reputation of {} by {}", peer_id, value);
+			self.network.report_peer(peer_id, ReputationChange::new(value, ADJUSTMENT_REASON));
+		}
+
+		Ok(())
+	}
+}
+
+/// Decode the peer ids of the adjustments, ignoring invalid entries.
+pub(crate) fn parse_adjustments(adjustments: Vec<(PeerIdBytes, i32)>) -> Vec<(PeerId, i32)> {
+	adjustments.into_iter()
+		.filter_map(|(peer_id, value)| match PeerId::from_bytes(peer_id) {
+			Ok(peer_id) => Some((peer_id, value)),
+			Err(bytes) => {
+				warn!(target: "network-privacy", "Ignoring adjustment of invalid peer id {:?}", bytes);
+				None
+			}
+		})
+		.collect()
+}
@@ -0,0 +1,169 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+fn peers(peers: &[&str]) -> HashSet<String> {
+	peers.iter().map(|p| p.to_string()).collect()
+}
+
+#[test]
+fn diff_works() {
+	let current = peers(&["/ip4/127.0.0.1/tcp/30333/p2p/A", "/ip4/127.0.0.1/tcp/30333/p2p/B"]);
+	let on_chain = peers(&["/ip4/127.0.0.1/tcp/30333/p2p/B", "/ip4/127.0.0.1/tcp/30333/p2p/C"]);
+
+	let (to_add, to_remove) = diff(&current, &on_chain);
+
+	assert_eq!(to_add, vec!["/ip4/127.0.0.1/tcp/30333/p2p/C".to_string()]);
+	assert_eq!(to_remove, vec!["/ip4/127.0.0.1/tcp/30333/p2p/A".to_string()]);
+}
+
+#[test]
+fn diff_of_equal_sets_is_empty() {
+	let current = peers(&["/ip4/127.0.0.1/tcp/30333/p2p/A"]);
+
+	let (to_add, to_remove) = diff(&current, &current.clone());
+
+	assert!(to_add.is_empty());
+	assert!(to_remove.is_empty());
+}
+
+#[test]
+fn backoff_doubles_until_max_and_resets() {
+	let now = Instant::now();
+	let mut backoff = Backoff::default();
+	assert!(backoff.is_ready(now));
+
+	backoff.failed(now);
+	assert_eq!(backoff.current, Some(INITIAL_BACKOFF));
+	assert!(!backoff.is_ready(now));
+	assert!(backoff.is_ready(now + INITIAL_BACKOFF));
+
+	backoff.failed(now);
+	assert_eq!(backoff.current, Some(INITIAL_BACKOFF * 2));
+
+	for _ in 0..20 {
+		backoff.failed(now);
+	}
+	assert_eq!(backoff.current, Some(MAX_BACKOFF));
+
+	backoff.reset();
+	assert!(backoff.is_ready(now));
+	assert_eq!(backoff.current, None);
+}
+
+#[test]
+fn parse_members_ignores_invalid_addresses() {
+	let peer_id = PeerId::random();
+	let members = membership::parse_members(vec![
+		format!("/ip4/127.0.0.1/tcp/30333/p2p/{}", peer_id).into_bytes(),
+		b"/ip4/127.0.0.1/tcp/30333".to_vec(),
+		vec![0xff, 0xfe],
+	]);
+
+	assert_eq!(members, vec![peer_id].into_iter().collect());
+}
+
+#[test]
+fn parse_light_clients_ignores_invalid_peer_ids() {
+	let peer_id = PeerId::random();
+	let light_clients = membership::parse_light_clients(vec![
+		peer_id.clone().into_bytes(),
+		vec![0xff, 0xfe],
+	]);
+
+	assert_eq!(light_clients, vec![peer_id].into_iter().collect());
+}
+
+#[test]
+fn key_ring_retains_rotated_out_keys() {
+	let mut keys = transactions_key::KeyRing::default();
+	assert_eq!(keys.current(), None);
+
+	keys.update(Some((1, [1; 32])));
+	keys.update(Some((1, [1; 32])));
+	keys.update(Some((2, [2; 32])));
+	keys.update(Some((3, [3; 32])));
+	assert_eq!(keys.current(), Some((3, [3; 32])));
+	assert_eq!(keys.get(2), Some([2; 32]));
+	assert_eq!(keys.get(1), Some([1; 32]));
+
+	keys.update(Some((4, [4; 32])));
+	assert_eq!(keys.get(1), None);
+	assert_eq!(keys.get(2), Some([2; 32]));
+
+	keys.update(None);
+	assert_eq!(keys.current(), None);
+	assert_eq!(keys.get(4), Some([4; 32]));
+}
+
+#[test]
+fn parse_adjustments_ignores_invalid_peer_ids() {
+	let peer_id = PeerId::random();
+	let adjustments = peer_scores::parse_adjustments(vec![
+		(peer_id.clone().into_bytes(), -100),
+		(vec![0xff, 0xfe], -100),
+	]);
+
+	assert_eq!(adjustments, vec![(peer_id, -100)]);
+}
+
+fn restricted_membership(member: &PeerId, operator: &sp_core::sr25519::Pair) -> membership::Membership {
+	use sp_core::Pair;
+
+	membership::Membership::Restricted(membership::Members {
+		members: vec![member.clone()].into_iter().collect(),
+		operators: Some(vec![(member.clone(), operator.public())].into_iter().collect()),
+		light_clients: HashSet::new(),
+		synced: (Instant::now(), 1),
+	})
+}
+
+#[test]
+fn unknown_membership_admits_no_peer() {
+	let peer_id = PeerId::random();
+	let membership = membership::Membership::Unknown;
+
+	assert!(!membership.is_member(&peer_id));
+	assert!(!membership.is_light_client(&peer_id));
+	assert!(!membership.verify_statement(&[0; 32], &peer_id, &[]));
+}
+
+#[test]
+fn members_prove_membership_with_statement_of_their_operator() {
+	use codec::Encode;
+	use sp_core::{Pair, sr25519};
+
+	let genesis_hash = [1; 32];
+	let member = PeerId::random();
+	let operator = sr25519::Pair::from_seed(&[1; 32]);
+	let other = sr25519::Pair::from_seed(&[2; 32]);
+	let membership = restricted_membership(&member, &operator);
+
+	let statement = |signer: &sr25519::Pair, genesis_hash: &[u8], peer_id: &PeerId| MembershipStatement {
+		operator: operator.public(),
+		signature: signer.sign(&statement_payload(genesis_hash, peer_id)),
+	}.encode();
+
+	assert!(membership.verify_statement(&genesis_hash, &member, &statement(&operator, &genesis_hash, &member)));
+	// members are not trusted by their `PeerId` alone.
+	assert!(!membership.verify_statement(&genesis_hash, &member, &[]));
+	// statements signed by another key, for another network or another peer are rejected.
+	assert!(!membership.verify_statement(&genesis_hash, &member, &statement(&other, &genesis_hash, &member)));
+	assert!(!membership.verify_statement(&genesis_hash, &member, &statement(&operator, &[2; 32], &member)));
+	let stranger = PeerId::random();
+	assert!(!membership.verify_statement(&genesis_hash, &stranger, &statement(&operator, &genesis_hash, &stranger)));
+}
@@ -0,0 +1,191 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Keys transactions are encrypted with, retrieved from the chain state.
+
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use futures::{Future, StreamExt};
+use log::{debug, warn};
+use parking_lot::RwLock;
+use sc_client_api::BlockchainEvents;
+use sc_network::config::{TransactionsKey, TransactionsKeyProvider};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_network_privacy::NetworkPrivacyApi;
+use sp_runtime::{traits::Block as BlockT, generic::BlockId};
+
+use crate::{Error, Result};
+
+/// Number of rotated out keys incoming transactions are still decrypted with.
+const RETAINED_KEYS: usize = 2;
+
+/// The current key and the last rotated out keys, most recent first.
+#[derive(Debug, Default)]
+pub(crate) struct KeyRing {
+	current: Option<(u64, TransactionsKey)>,
+	previous: VecDeque<(u64, TransactionsKey)>,
+}
+
+impl KeyRing {
+	/// Replace the current key, retaining the previous one if the key was rotated.
+	pub(crate) fn update(&mut self, key: Option<(u64, TransactionsKey)>) {
+		if key.map(|(id, _)| id) != self.current.map(|(id, _)| id) {
+			if let Some(previous) = self.current.take() {
+				self.previous.push_front(previous);
+				self.previous.truncate(RETAINED_KEYS);
+			}
+		}
+		self.current = key;
+	}
+
+	/// The key outgoing transactions are encrypted with.
+	pub(crate) fn current(&self) -> Option<(u64, TransactionsKey)> {
+		self.current
+	}
+
+	/// The current or a retained key with the given id.
+	pub(crate) fn get(&self, id: u64) -> Option<TransactionsKey> {
+		self.current.iter()
+			.chain(self.previous.iter())
+			.find(|(key_id, _)| *key_id == id)
+			.map(|(_, key)| *key)
+	}
+}
+
+/// Provides the transactions key returned by the `NetworkPrivacyApi` at the last finalized block.
+///
+/// The keys are retrieved when the provider is created, and by the future returned by `run` on
+/// every finalized block. The provider itself never calls into the runtime, so that the network
+/// isn't blocked by the runtime. After a rotation, the last `RETAINED_KEYS` keys are still
+/// accepted for incoming transactions, so that transactions sent by nodes that didn't see the
+/// rotation yet aren't dropped.
+///
+/// If the runtime does not implement the `NetworkPrivacyApi`, transactions are sent in clear. If
+/// calling the runtime fails, the last retrieved key is used.
+pub struct OnChainTransactionsKey<Client, Block: BlockT> {
+	client: Arc<Client>,
+	keys: Arc<RwLock<KeyRing>>,
+	phantom: PhantomData<Block>,
+}
+
+impl<Client, Block: BlockT> Clone for OnChainTransactionsKey<Client, Block> {
+	fn clone(&self) -> Self {
+		OnChainTransactionsKey {
+			client: self.client.clone(),
+			keys: self.keys.clone(),
+			phantom: PhantomData,
+		}
+	}
+}
+
+impl<Client, Block> OnChainTransactionsKey<Client, Block>
+where
+	Block: BlockT,
+	Client: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPrivacyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Return a new on chain transactions key provider, with the keys at the last finalized
+	/// block.
+	///
+	/// The keys are retrieved before the provider is handed to the network, so that no
+	/// transaction is sent in clear while `run` didn't retrieve them yet.
+	pub fn new(client: Arc<Client>) -> Self {
+		let provider = OnChainTransactionsKey {
+			client,
+			keys: Arc::new(RwLock::new(KeyRing::default())),
+			phantom: PhantomData,
+		};
+		provider.refresh();
+		provider
+	}
+
+	/// Retrieve the transactions key at the given block.
+	fn transactions_key(&self, at: &BlockId<Block>) -> Result<Option<(u64, TransactionsKey)>> {
+		let runtime_api = self.client.runtime_api();
+
+		let api = runtime_api
+			.require_api::<dyn NetworkPrivacyApi<Block, Error = sp_blockchain::Error>>(at, 1)?;
+		Ok(api.transactions_key(at)?)
+	}
+
+	/// Retrieve the keys at the last finalized block, keeping the last retrieved keys if calling
+	/// the runtime fails.
+	fn refresh(&self) {
+		let finalized = self.client.info().finalized_hash;
+		let key = match self.transactions_key(&BlockId::hash(finalized)) {
+			Ok(key) => key,
+			Err(Error::ApiNotSupported) => None,
+			Err(e) => {
+				warn!(
+					target: "network-privacy",
+					"Failed to retrieve the transactions key at {:?}: {}",
+					finalized,
+					e,
+				);
+				return;
+			}
+		};
+		debug!(
+			target: "network-privacy",
+			"Transactions key at {:?}: {:?}",
+			finalized,
+			key.map(|(id, _)| id),
+		);
+		self.keys.write().update(key);
+	}
+}
+
+impl<Client, Block> OnChainTransactionsKey<Client, Block>
+where
+	Block: BlockT + 'static,
+	Client: ProvideRuntimeApi<Block> + HeaderBackend<Block> + BlockchainEvents<Block>
+		+ Send + Sync + 'static,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		NetworkPrivacyApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Retrieve the keys on every finalized block.
+	///
+	/// The returned future is a long running task with the same lifetime as the node itself.
+	pub fn run(&self) -> impl Future<Output = ()> {
+		let provider = self.clone();
+		let mut finality_notifications = self.client.finality_notification_stream();
+
+		async move {
+			while finality_notifications.next().await.is_some() {
+				provider.refresh();
+			}
+		}
+	}
+}
+
+impl<Client, Block> TransactionsKeyProvider for OnChainTransactionsKey<Client, Block>
+where
+	Block: BlockT,
+	Client: Send + Sync,
+{
+	fn current_key(&self) -> Option<(u64, TransactionsKey)> {
+		self.keys.read().current()
+	}
+
+	fn key(&self, id: u64) -> Option<TransactionsKey> {
+		self.keys.read().get(id)
+	}
+}
@@ -16,16 +16,16 @@
 
 use crate::{
 	debug_info, discovery::DiscoveryBehaviour, discovery::DiscoveryOut, DiscoveryNetBehaviour,
-	Event, protocol::event::DhtEvent, ExHashT,
+	Event, protocol::event::{AccessEvent, DhtEvent}, ExHashT,
 };
-use crate::protocol::{self, light_client_handler, CustomMessageOutcome, Protocol};
+use crate::protocol::{self, light_client_handler, warp_sync::WarpSyncOut, CustomMessageOutcome, Protocol};
 use libp2p::NetworkBehaviour;
 use libp2p::core::{Multiaddr, PeerId, PublicKey};
 use libp2p::kad::record;
 use libp2p::swarm::{NetworkBehaviourAction, NetworkBehaviourEventProcess, PollParameters};
-use log::debug;
+use log::{debug, info};
 use sp_consensus::{BlockOrigin, import_queue::{IncomingBlock, Origin}};
-use sp_runtime::{traits::{Block as BlockT, NumberFor}, Justification};
+use sp_runtime::{traits::{Block as BlockT, Header as HeaderT, NumberFor}, Justification};
 use std::{iter, task::Context, task::Poll};
 use void;
 
@@ -44,6 +44,8 @@ pub struct Behaviour<B: BlockT, H: ExHashT> {
 	block_requests: protocol::BlockRequests<B>,
 	/// Light client request handling.
 	light_client_handler: protocol::LightClientHandler<B>,
+	/// Warp sync handling.
+	warp_sync: protocol::WarpSync<B>,
 	/// Queue of events to produce for the outside.
 	#[behaviour(ignore)]
 	events: Vec<BehaviourOut<B>>,
@@ -57,6 +59,8 @@ pub enum BehaviourOut<B: BlockT> {
 	/// Started a random Kademlia discovery query.
 	RandomKademliaStarted,
 	Event(Event),
+	/// A node was accepted, refused or disconnected, see `NetworkService::access_event_stream`.
+	Access(AccessEvent),
 }
 
 impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
@@ -71,6 +75,7 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 		discovery_only_if_under_num: u64,
 		block_requests: protocol::BlockRequests<B>,
 		light_client_handler: protocol::LightClientHandler<B>,
+		warp_sync: protocol::WarpSync<B>,
 	) -> Self {
 		Behaviour {
 			substrate,
@@ -84,6 +89,7 @@ impl<B: BlockT, H: ExHashT> Behaviour<B, H> {
 			).await,
 			block_requests,
 			light_client_handler,
+			warp_sync,
 			events: Vec::new()
 		}
 	}
@@ -175,11 +181,26 @@ Behaviour<B, H> {
 				let ev = Event::NotificationsReceived { remote, messages };
 				self.events.push(BehaviourOut::Event(ev));
 			},
+			CustomMessageOutcome::Access(ev) =>
+				self.events.push(BehaviourOut::Access(ev)),
 			CustomMessageOutcome::None => {}
 		}
 	}
 }
 
+impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<WarpSyncOut<B>> for
+Behaviour<B, H> {
+	fn inject_event(&mut self, event: WarpSyncOut<B>) {
+		match event {
+			WarpSyncOut::Imported(header) =>
+				info!("⏩ Warp sync complete, syncing on from block #{}", header.number()),
+			WarpSyncOut::Failed =>
+				info!("⏩ Warp sync failed, syncing from genesis"),
+		}
+		self.substrate.on_warp_sync_complete();
+	}
+}
+
 impl<B: BlockT, H: ExHashT> NetworkBehaviourEventProcess<debug_info::DebugInfoEvent>
 	for Behaviour<B, H> {
 	fn inject_event(&mut self, event: debug_info::DebugInfoEvent) {
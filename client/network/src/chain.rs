@@ -17,8 +17,8 @@
 //! Blockchain access trait
 
 use sp_blockchain::{Error, HeaderBackend, HeaderMetadata};
-use sc_client_api::{BlockBackend, ProofProvider};
-use sp_runtime::traits::{Block as BlockT, BlockIdTo};
+use sc_client_api::{BlockBackend, ProofProvider, StateEntry};
+use sp_runtime::{Justification, traits::{Block as BlockT, BlockIdTo}};
 
 /// Local client abstraction for the network.
 pub trait Client<Block: BlockT>: HeaderBackend<Block> + ProofProvider<Block> + BlockIdTo<Block, Error = Error>
@@ -42,3 +42,33 @@ impl<Block: BlockT> FinalityProofProvider<Block> for () {
 		Ok(None)
 	}
 }
+
+/// Progress of a warp sync after verifying a proof.
+#[derive(Debug)]
+pub enum WarpSyncProgress<Block: BlockT> {
+	/// The proof is valid, but the next proof should be requested from the given authority set.
+	Partial(u64),
+	/// The proof reaches a finalized header, whose state can be downloaded.
+	Complete(Block::Header, Justification),
+}
+
+/// Proves the finality of recent blocks to warp syncing nodes, and verifies these proofs.
+pub trait WarpSyncProvider<Block: BlockT>: Send + Sync {
+	/// Generate a proof of finality of a recent block, starting from the authority set `begin`.
+	fn generate(&self, begin: u64) -> Result<Vec<u8>, Error>;
+
+	/// The authority set the next proof should be requested from.
+	fn current_set(&self) -> u64;
+
+	/// Verify a proof requested from `current_set`. Valid proofs move `current_set` forward.
+	fn verify(&self, proof: &[u8]) -> Result<WarpSyncProgress<Block>, Error>;
+
+	/// Import the state of a header returned by `verify`, in the order of
+	/// `sc_client_api::backend::Backend::import_state`. Blocks until the state is imported.
+	fn import(
+		&self,
+		header: Block::Header,
+		justification: Justification,
+		state: &mut dyn Iterator<Item = Result<StateEntry, Error>>,
+	) -> Result<(), Error>;
+}
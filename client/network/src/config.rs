@@ -19,10 +19,13 @@
 //! The [`Params`] struct is the struct that must be passed in order to initialize the networking.
 //! See the documentation of [`Params`].
 
-pub use crate::chain::{Client, FinalityProofProvider};
+pub use crate::chain::{Client, FinalityProofProvider, WarpSyncProgress, WarpSyncProvider};
 pub use crate::on_demand_layer::OnDemand;
 pub use crate::service::{TransactionPool, EmptyTransactionPool};
+pub use crate::protocol::{BLOCK_ANNOUNCES_PROTOCOL, TRANSACTIONS_PROTOCOL};
 pub use libp2p::{identity, core::PublicKey, wasm_ext::ExtTransport, build_multiaddr};
+pub use sc_peerset::ProtocolSlots;
+pub use crate::ip_policy::{IpNet, IpPolicy};
 
 // Note: this re-export shouldn't be part of the public API of the crate and will be removed in
 // the future.
@@ -40,6 +43,8 @@ use libp2p::{PeerId, Multiaddr, multiaddr};
 use core::{fmt, iter};
 use std::{future::Future, pin::Pin};
 use std::{error::Error, fs, io::{self, Write}, net::Ipv4Addr, path::{Path, PathBuf}, sync::Arc};
+use std::collections::HashMap;
+use std::time::Duration;
 use zeroize::Zeroize;
 use prometheus_endpoint::Registry;
 
@@ -95,6 +100,88 @@ pub struct Params<B: BlockT, H: ExHashT> {
 
 	/// Registry for recording prometheus metrics to.
 	pub metrics_registry: Option<Registry>,
+
+	/// Type to check whether a peer belongs to a private network.
+	///
+	/// If `Some`, peers that are neither members nor authorized light clients are disconnected
+	/// right after the connection is established, before any status message is exchanged with
+	/// them. Light client requests are only served to authorized light clients.
+	pub membership_validator: Option<Arc<dyn MembershipValidator>>,
+
+	/// Provides the keys transactions are encrypted with.
+	///
+	/// If `Some` and it returns a current key, transactions are encrypted before being sent to
+	/// other nodes. Encrypted transactions are only accepted if they can be decrypted.
+	pub transactions_key_provider: Option<Arc<dyn TransactionsKeyProvider>>,
+
+	/// Warp sync proof provider.
+	///
+	/// This object, if `Some`, is used to serve warp sync proofs to other nodes, and to warp
+	/// sync if [`NetworkConfiguration::sync_mode`] is [`SyncMode::Warp`].
+	pub warp_sync_provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+}
+
+/// Checks whether a peer is allowed to take part in a private network.
+///
+/// The `PeerId` passed to the validator has already been authenticated by the transport
+/// handshake, which proves that the remote holds the private key matching its identity. Full
+/// nodes additionally prove their membership with the statement they send in their status
+/// message, e.g. signed by the account operating them.
+pub trait MembershipValidator: Send + Sync {
+	/// Returns `true` if `peer_id` is a member of the network.
+	fn is_member(&self, peer_id: &PeerId) -> bool;
+
+	/// Returns the statement proving that the local node, `local_peer_id`, is a member of the
+	/// network, sent to the other nodes in the status message. Empty by default.
+	fn local_statement(&self, _local_peer_id: &PeerId) -> Vec<u8> {
+		Vec::new()
+	}
+
+	/// Returns `true` if `statement`, sent by `peer_id` in its status message, proves that it is
+	/// a member of the network. By default, members are trusted without a statement.
+	fn verify_statement(&self, peer_id: &PeerId, _statement: &[u8]) -> bool {
+		self.is_member(peer_id)
+	}
+
+	/// Returns `true` if `peer_id` may connect as a light client and request headers, read proofs
+	/// and call proofs, without being a member. By default only members are served.
+	fn is_light_client(&self, peer_id: &PeerId) -> bool {
+		self.is_member(peer_id)
+	}
+
+	/// Returns the state of the membership enforced by the validator, or `None` if unknown.
+	fn status(&self) -> Option<MembershipStatus> {
+		None
+	}
+}
+
+/// The state of the membership of a private network enforced by a `MembershipValidator`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MembershipStatus {
+	/// Whether membership is restricted, `false` if every peer is a member.
+	pub enforced: bool,
+	/// Number of members.
+	pub members: usize,
+	/// Number of authorized light clients that are not members.
+	pub light_clients: usize,
+	/// Number of the last block the members were successfully retrieved at.
+	pub last_sync_block: Option<u64>,
+	/// Time elapsed since the members were last successfully retrieved.
+	pub since_last_sync: Option<Duration>,
+}
+
+/// Symmetric key transactions are encrypted with, shared by all the members of a private network.
+pub type TransactionsKey = [u8; 32];
+
+/// Provides the rotating network-wide keys transactions are encrypted with.
+pub trait TransactionsKeyProvider: Send + Sync {
+	/// Returns the id and the key outgoing transactions must be encrypted with, or `None` if
+	/// transactions are sent in clear.
+	fn current_key(&self) -> Option<(u64, TransactionsKey)>;
+
+	/// Returns the key with the given id, if incoming transactions encrypted with it are still
+	/// accepted.
+	fn key(&self, id: u64) -> Option<TransactionsKey>;
 }
 
 bitflags! {
@@ -284,6 +371,17 @@ pub struct NetworkConfiguration {
 	pub transport: TransportConfig,
 	/// Maximum number of peers to ask the same blocks in parallel.
 	pub max_parallel_downloads: u32,
+	/// Separate incoming and outgoing slot budgets for protocols.
+	///
+	/// The keys are [`BLOCK_ANNOUNCES_PROTOCOL`], [`TRANSACTIONS_PROTOCOL`] or the name of a
+	/// notifications protocol (e.g. `/paritytech/grandpa/1`). Peers without a slot of a protocol
+	/// are neither sent nor accepted messages of that protocol.
+	pub protocol_slots: HashMap<String, ProtocolSlots>,
+	/// IP addresses we accept connections from and dial. Can be changed at runtime with
+	/// `NetworkService::set_ip_policy`.
+	pub ip_policy: IpPolicy,
+	/// How to sync a node that only has the genesis block.
+	pub sync_mode: SyncMode,
 }
 
 impl Default for NetworkConfiguration {
@@ -309,6 +407,9 @@ impl Default for NetworkConfiguration {
 				use_yamux_flow_control: false,
 			},
 			max_parallel_downloads: 5,
+			protocol_slots: HashMap::new(),
+			ip_policy: IpPolicy::default(),
+			sync_mode: SyncMode::Full,
 		}
 	}
 }
@@ -393,6 +494,33 @@ impl NonReservedPeerMode {
 	}
 }
 
+/// How to sync a node that only has the genesis block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncMode {
+	/// Download and import every block. This is the default.
+	Full,
+	/// Prove the finality of a recent block from the genesis authorities, download its state and
+	/// sync on from it. Requires a [`WarpSyncProvider`].
+	Warp,
+}
+
+impl SyncMode {
+	/// Attempt to parse the sync mode from a string.
+	pub fn parse(s: &str) -> Option<Self> {
+		match s {
+			"full" => Some(SyncMode::Full),
+			"warp" => Some(SyncMode::Warp),
+			_ => None,
+		}
+	}
+}
+
+impl Default for SyncMode {
+	fn default() -> Self {
+		SyncMode::Full
+	}
+}
+
 /// The configuration of a node's secret key, describing the type of key
 /// and how it is obtained. A node's identity keypair is the result of
 /// the evaluation of the node key configuration.
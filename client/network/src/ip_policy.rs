@@ -0,0 +1,169 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! IP address allow and deny lists.
+//!
+//! The [`IpPolicy`] is checked by the transport for every connection, right after the TCP
+//! connection has been established and before any byte is exchanged with the remote. DNS
+//! addresses are resolved before, so the policy applies to the resolved IP addresses. It
+//! complements the `PeerId`-based reserved nodes: a node must pass both.
+
+use libp2p::{Multiaddr, multiaddr::Protocol};
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// A range of IP addresses in CIDR notation, e.g. `10.0.0.0/8` or `2001:db8::/32`.
+///
+/// An address without prefix length (e.g. `10.0.0.1`) is a range containing only that address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+	addr: IpAddr,
+	prefix_len: u8,
+}
+
+impl IpNet {
+	/// Returns `true` if `ip` is within this range.
+	///
+	/// IPv4 addresses are never within an IPv6 range and vice versa.
+	pub fn contains(&self, ip: &IpAddr) -> bool {
+		match (self.addr, ip) {
+			(IpAddr::V4(net), IpAddr::V4(ip)) => {
+				let mask = u32::max_value().checked_shl(32 - u32::from(self.prefix_len)).unwrap_or(0);
+				u32::from(net) & mask == u32::from(*ip) & mask
+			},
+			(IpAddr::V6(net), IpAddr::V6(ip)) => {
+				let mask = u128::max_value().checked_shl(128 - u32::from(self.prefix_len)).unwrap_or(0);
+				u128::from(net) & mask == u128::from(*ip) & mask
+			},
+			_ => false,
+		}
+	}
+}
+
+impl FromStr for IpNet {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let (addr, prefix_len) = match s.find('/') {
+			Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+			None => (s, None),
+		};
+		let addr = addr.parse::<IpAddr>()
+			.map_err(|e| format!("Invalid IP address `{}`: {}", addr, e))?;
+		let max_len = if addr.is_ipv4() { 32 } else { 128 };
+		let prefix_len = match prefix_len {
+			Some(len) => len.parse::<u8>()
+				.ok()
+				.filter(|len| *len <= max_len)
+				.ok_or_else(|| format!("Invalid prefix length `{}` in `{}`", len, s))?,
+			None => max_len,
+		};
+		Ok(IpNet { addr, prefix_len })
+	}
+}
+
+impl fmt::Display for IpNet {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}/{}", self.addr, self.prefix_len)
+	}
+}
+
+/// IP addresses allowed to connect to the node, and that the node is allowed to dial.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IpPolicy {
+	/// If not empty, only addresses within one of these ranges are allowed.
+	pub allow: Vec<IpNet>,
+	/// Addresses within one of these ranges are refused, even if they are in `allow`.
+	pub deny: Vec<IpNet>,
+}
+
+impl IpPolicy {
+	/// Returns `true` if the policy allows connections with `ip`.
+	pub fn is_allowed(&self, ip: &IpAddr) -> bool {
+		if self.deny.iter().any(|net| net.contains(ip)) {
+			return false;
+		}
+		self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip))
+	}
+
+	/// Returns `true` if the policy allows connections with `addr`.
+	///
+	/// DNS addresses must be resolved first: they are refused unless the policy allows every
+	/// address. Addresses that contain neither an IP address nor a domain name, such as memory
+	/// addresses, are always allowed.
+	pub fn is_address_allowed(&self, addr: &Multiaddr) -> bool {
+		for protocol in addr.iter() {
+			match protocol {
+				Protocol::Ip4(ip) => return self.is_allowed(&IpAddr::V4(ip)),
+				Protocol::Ip6(ip) => return self.is_allowed(&IpAddr::V6(ip)),
+				Protocol::Dns4(_) | Protocol::Dns6(_) => return self.allow.is_empty() && self.deny.is_empty(),
+				_ => {},
+			}
+		}
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ip(s: &str) -> IpAddr {
+		s.parse().unwrap()
+	}
+
+	#[test]
+	fn parses_ip_nets() {
+		assert_eq!("10.0.0.0/8".parse::<IpNet>().unwrap().to_string(), "10.0.0.0/8");
+		assert_eq!("10.0.0.1".parse::<IpNet>().unwrap().to_string(), "10.0.0.1/32");
+		assert_eq!("2001:db8::/32".parse::<IpNet>().unwrap().to_string(), "2001:db8::/32");
+		assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+		assert!("10.0.0/8".parse::<IpNet>().is_err());
+	}
+
+	#[test]
+	fn ip_net_contains() {
+		let net = "192.168.0.0/16".parse::<IpNet>().unwrap();
+		assert!(net.contains(&ip("192.168.4.2")));
+		assert!(!net.contains(&ip("192.169.0.1")));
+		assert!(!net.contains(&ip("::1")));
+		assert!("0.0.0.0/0".parse::<IpNet>().unwrap().contains(&ip("8.8.8.8")));
+		assert!("2001:db8::/32".parse::<IpNet>().unwrap().contains(&ip("2001:db8:1::1")));
+	}
+
+	#[test]
+	fn deny_takes_precedence_over_allow() {
+		let policy = IpPolicy {
+			allow: vec!["10.0.0.0/8".parse().unwrap()],
+			deny: vec!["10.1.0.0/16".parse().unwrap()],
+		};
+		assert!(policy.is_allowed(&ip("10.2.0.1")));
+		assert!(!policy.is_allowed(&ip("10.1.0.1")));
+		assert!(!policy.is_allowed(&ip("11.0.0.1")));
+		assert!(IpPolicy::default().is_allowed(&ip("11.0.0.1")));
+	}
+
+	#[test]
+	fn unresolved_dns_addresses_are_refused() {
+		let policy = IpPolicy { allow: vec!["10.0.0.0/8".parse().unwrap()], deny: Vec::new() };
+		assert!(policy.is_address_allowed(&"/ip4/10.0.0.1/tcp/30333".parse().unwrap()));
+		assert!(!policy.is_address_allowed(&"/ip4/11.0.0.1/tcp/30333".parse().unwrap()));
+		assert!(!policy.is_address_allowed(&"/dns4/example.com/tcp/30333".parse().unwrap()));
+		assert!(IpPolicy::default().is_address_allowed(&"/dns4/example.com/tcp/30333".parse().unwrap()));
+		assert!(policy.is_address_allowed(&"/memory/5".parse().unwrap()));
+	}
+}
@@ -78,6 +78,11 @@
 //! - DNS for addresses of the form `/dns4/example.com/tcp/5` or `/dns4/example.com/tcp/5/ws`. A
 //! node's address can contain a domain name.
 //!
+//! QUIC (addresses of the form `/ip4/1.2.3.4/udp/5/quic`) and NAT hole punching are not
+//! supported: the version of libp2p used by this crate provides neither a QUIC transport nor a
+//! hole punching protocol. Dialing a `/quic` address fails with an unsupported multiaddress error.
+//! Nodes behind restrictive firewalls can be reached through WebSockets on an open TCP port.
+//!
 //! On top of the base-layer protocol, the [Noise](https://noiseprotocol.org/) protocol is
 //! negotiated and applied. The exact handshake protocol is experimental and is subject to change.
 //!
@@ -228,6 +233,7 @@ mod behaviour;
 mod chain;
 mod debug_info;
 mod discovery;
+mod ip_policy;
 mod on_demand_layer;
 mod protocol;
 mod service;
@@ -240,7 +246,7 @@ pub mod network_state;
 
 pub use service::{NetworkService, NetworkStateInfo, NetworkWorker, ExHashT, ReportHandle};
 pub use protocol::PeerInfo;
-pub use protocol::event::{Event, DhtEvent};
+pub use protocol::event::{Event, DhtEvent, AccessEvent, RefusalReason};
 pub use protocol::sync::SyncState;
 pub use libp2p::{Multiaddr, PeerId};
 #[doc(inline)]
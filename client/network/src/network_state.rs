@@ -22,6 +22,7 @@ use libp2p::{core::ConnectedPoint, Multiaddr};
 use serde::{Deserialize, Serialize};
 use slog_derive::SerdeValue;
 use std::{collections::{HashMap, HashSet}, time::Duration};
+use crate::config::MembershipStatus;
 
 /// Returns general information about the networking.
 ///
@@ -109,3 +110,15 @@ impl From<ConnectedPoint> for PeerEndpoint {
 		}
 	}
 }
+
+/// State of the enforcement of the membership of a private network. Unstable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrivacyStatus {
+	/// Number of reserved peers.
+	pub reserved_peers: usize,
+	/// Number of peers disconnected because they are not members of the private network.
+	pub refused_connections: u64,
+	/// The state of the membership enforced by the membership validator, `None` if there is no
+	/// validator and `Some(None)` if its state is unknown.
+	pub membership: Option<Option<MembershipStatus>>,
+}
@@ -19,6 +19,7 @@ use crate::utils::interval;
 use bytes::{Bytes, BytesMut};
 use futures::prelude::*;
 use generic_proto::{GenericProto, GenericProtoOut};
+use event::{AccessEvent, RefusalReason};
 use libp2p::{Multiaddr, PeerId};
 use libp2p::core::{ConnectedPoint, nodes::listeners::ListenerId};
 use libp2p::swarm::{ProtocolsHandler, IntoProtocolsHandler};
@@ -44,9 +45,11 @@ use light_dispatch::{LightDispatch, LightDispatchNetwork, RequestData};
 use prometheus_endpoint::{Registry, Gauge, GaugeVec, PrometheusError, Opts, register, U64};
 use sync::{ChainSync, SyncState};
 use crate::service::{TransactionPool, ExHashT};
-use crate::config::{BoxFinalityProofRequestBuilder, Roles};
+use crate::config::{BoxFinalityProofRequestBuilder, MembershipValidator, Roles, TransactionsKeyProvider};
+use crate::network_state::PrivacyStatus;
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{io, path::Path};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::sync::Arc;
 use std::fmt::Write;
 use std::{cmp, num::NonZeroUsize, pin::Pin, task::Poll, time};
@@ -67,6 +70,7 @@ pub mod api {
 	}
 }
 
+mod encryption;
 mod generic_proto;
 mod util;
 
@@ -76,9 +80,11 @@ pub mod event;
 pub mod light_client_handler;
 pub mod light_dispatch;
 pub mod sync;
+pub mod warp_sync;
 
 pub use block_requests::BlockRequests;
 pub use light_client_handler::LightClientHandler;
+pub use warp_sync::WarpSync;
 
 const REQUEST_TIMEOUT_SEC: u64 = 40;
 /// Interval at which we perform time based maintenance
@@ -103,6 +109,11 @@ const MAX_BLOCK_DATA_RESPONSE: u32 = 128;
 /// and disconnect to free connection slot.
 const LIGHT_MAXIMAL_BLOCKS_DIFFERENCE: u64 = 8192;
 
+/// Name of the block announces protocol in the protocol slot budgets.
+pub const BLOCK_ANNOUNCES_PROTOCOL: &str = "block-announces";
+/// Name of the transactions protocol in the protocol slot budgets.
+pub const TRANSACTIONS_PROTOCOL: &str = "transactions";
+
 mod rep {
 	use sc_peerset::ReputationChange as Rep;
 	/// Reputation change when a peer is "clogged", meaning that it's not fast enough to process our
@@ -136,6 +147,10 @@ mod rep {
 	pub const BAD_ROLE: Rep = Rep::new_fatal("Unsupported role");
 	/// Peer response data does not have requested bits.
 	pub const BAD_RESPONSE: Rep = Rep::new(-(1 << 12), "Incomplete response");
+	/// Peer is not a member of the private network.
+	pub const NOT_A_MEMBER: Rep = Rep::new_fatal("Not a member of the private network");
+	/// Reputation change when a peer sends us transactions we can't decrypt.
+	pub const UNDECRYPTABLE_EXTRINSICS: Rep = Rep::new(-(1 << 10), "Undecryptable extrinsics");
 }
 
 struct Metrics {
@@ -227,6 +242,18 @@ pub struct Protocol<B: BlockT, H: ExHashT> {
 	metrics: Option<Metrics>,
 	/// The `PeerId`'s of all boot nodes.
 	boot_node_ids: Arc<HashSet<PeerId>>,
+	/// If `Some`, only peers accepted by the validator are allowed to stay connected.
+	membership_validator: Option<Arc<dyn MembershipValidator>>,
+	/// Our own `PeerId`, the membership statement we send in our status message is about.
+	local_peer_id: PeerId,
+	/// The membership statements of the full nodes we completed the handshake with.
+	membership_statements: HashMap<PeerId, Vec<u8>>,
+	/// Number of peers disconnected because they are not members of the private network.
+	refused_connections: u64,
+	/// If `Some`, provides the keys transactions are encrypted with.
+	transactions_key_provider: Option<Arc<dyn TransactionsKeyProvider>>,
+	/// Access events that couldn't be returned yet, because `poll` returns one outcome at a time.
+	pending_access_events: VecDeque<AccessEvent>,
 }
 
 #[derive(Default)]
@@ -257,6 +284,12 @@ struct Peer<B: BlockT, H: ExHashT> {
 	known_blocks: LruHashSet<B::Hash>,
 	/// Request counter,
 	next_request_id: message::RequestId,
+	/// Whether the peer was allocated a slot of the block announces protocol.
+	block_announces: bool,
+	/// Whether the peer was allocated a slot of the transactions protocol.
+	transactions: bool,
+	/// Notifications protocols the peer was allocated a slot of.
+	notifications_protocols: HashSet<ConsensusEngineId>,
 }
 
 /// Info about a peer's known state.
@@ -438,6 +471,9 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		metrics_registry: Option<&Registry>,
 		boot_node_ids: Arc<HashSet<PeerId>>,
+		local_peer_id: PeerId,
+		membership_validator: Option<Arc<dyn MembershipValidator>>,
+		transactions_key_provider: Option<Arc<dyn TransactionsKeyProvider>>,
 	) -> error::Result<(Protocol<B, H>, sc_peerset::PeersetHandle)> {
 		let info = chain.info();
 		let sync = ChainSync::new(
@@ -488,6 +524,12 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				None
 			},
 			boot_node_ids,
+			membership_validator,
+			local_peer_id,
+			membership_statements: HashMap::new(),
+			refused_connections: 0,
+			transactions_key_provider,
+			pending_access_events: VecDeque::new(),
 		};
 
 		Ok((protocol, peerset_handle))
@@ -513,6 +555,15 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		self.behaviour.num_discovered_peers()
 	}
 
+	/// Returns the state of the enforcement of the membership of the private network.
+	pub fn privacy_status(&self) -> PrivacyStatus {
+		PrivacyStatus {
+			reserved_peers: self.behaviour.num_reserved_peers(),
+			refused_connections: self.refused_connections,
+			membership: self.membership_validator.as_ref().map(|v| v.status()),
+		}
+	}
+
 	/// Disconnects the given peer if we are connected to it.
 	pub fn disconnect_peer(&mut self, peer_id: &PeerId) {
 		self.behaviour.disconnect_peer(peer_id)
@@ -528,6 +579,21 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns the reputation of the given peer, or `None` if the peerset doesn't know about it.
+	pub fn peer_reputation(&mut self, peer_id: &PeerId) -> Option<i32> {
+		self.behaviour.peer_reputation(peer_id)
+	}
+
+	/// Writes a snapshot of the peerset reputations to `path`.
+	pub fn export_reputations(&mut self, path: &Path) -> io::Result<()> {
+		self.behaviour.export_reputations(path)
+	}
+
+	/// Restores the peerset reputations previously written to `path`.
+	pub fn import_reputations(&mut self, path: &Path) -> io::Result<()> {
+		self.behaviour.import_reputations(path)
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.context_data.peers.values().count()
@@ -627,6 +693,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		who: PeerId,
 		data: BytesMut,
 	) -> CustomMessageOutcome<B> {
+		let _context = sp_tracing::log_context::enter_peer(&who);
 
 		let message = match <Message<B> as Decode>::decode(&mut &data[..]) {
 			Ok(message) => message,
@@ -657,12 +724,18 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				}
 			},
 			GenericMessage::BlockAnnounce(announce) => {
+				if !self.context_data.peers.get(&who).map_or(true, |p| p.block_announces) {
+					trace!(target: "sync", "Ignoring block announce from {} without a protocol slot", who);
+					return CustomMessageOutcome::None;
+				}
 				let outcome = self.on_block_announce(who.clone(), announce);
 				self.update_peer_info(&who);
 				return outcome;
 			},
 			GenericMessage::Transactions(m) =>
 				self.on_extrinsics(who, m),
+			GenericMessage::EncryptedTransactions(m) =>
+				self.on_encrypted_extrinsics(who, m),
 			GenericMessage::RemoteCallRequest(request) => self.on_remote_call_request(who, request),
 			GenericMessage::RemoteCallResponse(response) =>
 				self.on_remote_call_response(who, response),
@@ -686,9 +759,14 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				self.on_remote_read_child_request(who, request),
 			GenericMessage::Consensus(msg) =>
 				return if self.protocol_name_by_engine.contains_key(&msg.engine_id) {
-					CustomMessageOutcome::NotificationsReceived {
-						remote: who.clone(),
-						messages: vec![(msg.engine_id, From::from(msg.data))],
+					if self.has_notifications_slot(&who, &msg.engine_id) {
+						CustomMessageOutcome::NotificationsReceived {
+							remote: who.clone(),
+							messages: vec![(msg.engine_id, From::from(msg.data))],
+						}
+					} else {
+						trace!(target: "sync", "Ignoring {:?} message from {} without a protocol slot", msg.engine_id, who);
+						CustomMessageOutcome::None
 					}
 				} else {
 					warn!(target: "sync", "Received message on non-registered protocol: {:?}", msg.engine_id);
@@ -705,6 +783,7 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 							None
 						}
 					})
+					.filter(|(engine_id, _)| self.has_notifications_slot(&who, engine_id))
 					.collect::<Vec<_>>();
 
 				return if !messages.is_empty() {
@@ -741,14 +820,51 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 	}
 
 	/// Called when a new peer is connected
-	pub fn on_peer_connected(&mut self, who: PeerId) {
+	///
+	/// Returns `false` if the peer is not a member of the private network and was disconnected.
+	pub fn on_peer_connected(&mut self, who: PeerId) -> bool {
+		let _context = sp_tracing::log_context::enter_peer(&who);
 		trace!(target: "sync", "Connecting {}", who);
+		if !self.is_admitted(&who) {
+			debug!(target: "sync", "Peer {} is not a member of the private network", who);
+			self.refuse_non_member(&who);
+			return false;
+		}
 		self.handshaking_peers.insert(who.clone(), HandshakingPeer { timestamp: Instant::now() });
 		self.send_status(who);
+		true
+	}
+
+	/// Disconnects a peer that is not a member of the private network.
+	fn refuse_non_member(&mut self, who: &PeerId) {
+		self.refused_connections = self.refused_connections.saturating_add(1);
+		self.peerset_handle.report_peer(who.clone(), rep::NOT_A_MEMBER);
+		self.behaviour.disconnect_peer(who);
+	}
+
+	/// Returns `true` if the peer is allowed to be connected to us, checking the membership
+	/// statement it sent in its status message if it is a full node.
+	fn is_member(&self, who: &PeerId) -> bool {
+		self.membership_validator.as_ref().map_or(true, |v| match self.membership_statements.get(who) {
+			Some(statement) => v.verify_statement(who, statement),
+			None => v.is_member(who),
+		})
+	}
+
+	/// Returns `true` if the peer is allowed to stay connected to us: members, and authorized
+	/// light clients unless they told us they are full nodes.
+	fn is_admitted(&self, who: &PeerId) -> bool {
+		if self.is_member(who) {
+			return true;
+		}
+
+		let is_light = self.context_data.peers.get(who).map_or(true, |p| p.info.roles.is_light());
+		is_light && self.membership_validator.as_ref().map_or(true, |v| v.is_light_client(who))
 	}
 
 	/// Called by peer when it is disconnecting
 	pub fn on_peer_disconnected(&mut self, peer: PeerId) -> CustomMessageOutcome<B> {
+		let _context = sp_tracing::log_context::enter_peer(&peer);
 		if self.important_peers.contains(&peer) {
 			warn!(target: "sync", "Reserved peer {} disconnected", peer);
 		} else {
@@ -758,19 +874,21 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		// lock all the the peer lists so that add/remove peer events are in order
 		let removed = {
 			self.handshaking_peers.remove(&peer);
+			self.membership_statements.remove(&peer);
 			self.context_data.peers.remove(&peer)
 		};
-		if let Some(_peer_data) = removed {
+		if let Some(peer_data) = removed {
+			self.behaviour.release_protocol_slots(&peer);
 			self.sync.peer_disconnected(peer.clone());
 			self.light_dispatch.on_disconnect(LightDispatchIn {
 				behaviour: &mut self.behaviour,
 				peerset: self.peerset_handle.clone(),
 			}, &peer);
 
-			// Notify all the notification protocols as closed.
+			// Notify the notification protocols the peer was using as closed.
 			CustomMessageOutcome::NotificationStreamClosed {
 				remote: peer,
-				protocols: self.protocol_name_by_engine.keys().cloned().collect(),
+				protocols: peer_data.notifications_protocols.into_iter().collect(),
 			}
 		} else {
 			CustomMessageOutcome::None
@@ -994,6 +1112,17 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			self.behaviour.disconnect_peer(&p);
 			self.peerset_handle.report_peer(p, rep::TIMEOUT);
 		}
+
+		// Membership may have been revoked since the peer connected.
+		let revoked = self.context_data.peers.keys()
+			.chain(self.handshaking_peers.keys())
+			.filter(|who| !self.is_admitted(who))
+			.cloned()
+			.collect::<Vec<_>>();
+		for p in revoked {
+			debug!(target: "sync", "Peer {} is no longer a member of the private network", p);
+			self.refuse_non_member(&p);
+		}
 	}
 
 	/// Called by peer to report status
@@ -1042,6 +1171,19 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 				return CustomMessageOutcome::None;
 			}
 
+			if !status.roles.is_light() {
+				let is_member = self.membership_validator.as_ref()
+					.map_or(true, |v| v.verify_statement(&who, &status.chain_status));
+				if !is_member {
+					debug!(target: "sync", "Peer {} didn't prove it is a member of the private network", who);
+					self.refuse_non_member(&who);
+					return CustomMessageOutcome::None;
+				}
+				if self.membership_validator.is_some() {
+					self.membership_statements.insert(who.clone(), status.chain_status.clone());
+				}
+			}
+
 			if self.config.roles.is_light() {
 				// we're not interested in light peers
 				if status.roles.is_light() {
@@ -1093,6 +1235,9 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 					.expect("Constant is nonzero")),
 				next_request_id: 0,
 				obsolete_requests: HashMap::new(),
+				block_announces: self.behaviour.allocate_protocol_slot(BLOCK_ANNOUNCES_PROTOCOL, &who),
+				transactions: self.behaviour.allocate_protocol_slot(TRANSACTIONS_PROTOCOL, &who),
+				notifications_protocols: self.allocate_notifications_slots(&who),
 			};
 			self.context_data.peers.insert(who.clone(), peer);
 
@@ -1100,7 +1245,10 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			status.version
 		};
 
-		let info = self.context_data.peers.get(&who).expect("We just inserted above; QED").info.clone();
+		let (info, notifications_protocols) = {
+			let peer = self.context_data.peers.get(&who).expect("We just inserted above; QED");
+			(peer.info.clone(), peer.notifications_protocols.iter().cloned().collect())
+		};
 		self.light_dispatch.on_connect(LightDispatchIn {
 			behaviour: &mut self.behaviour,
 			peerset: self.peerset_handle.clone(),
@@ -1116,14 +1264,30 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			}
 		}
 
-		// Notify all the notification protocols as open.
+		// Notify the notification protocols the peer was allocated a slot of as open.
 		CustomMessageOutcome::NotificationStreamOpened {
 			remote: who,
-			protocols: self.protocol_name_by_engine.keys().cloned().collect(),
+			protocols: notifications_protocols,
 			roles: info.roles,
 		}
 	}
 
+	/// Allocates to the peer a slot of each notifications protocol that has a free one, and
+	/// returns these protocols.
+	fn allocate_notifications_slots(&mut self, who: &PeerId) -> HashSet<ConsensusEngineId> {
+		let behaviour = &mut self.behaviour;
+		self.protocol_name_by_engine.iter()
+			.filter(|(_, name)| behaviour.allocate_protocol_slot(&String::from_utf8_lossy(name), who))
+			.map(|(engine_id, _)| *engine_id)
+			.collect()
+	}
+
+	/// Returns `true` if the peer was allocated a slot of the notifications protocol.
+	fn has_notifications_slot(&self, who: &PeerId, engine_id: &ConsensusEngineId) -> bool {
+		self.context_data.peers.get(who)
+			.map_or(false, |peer| peer.notifications_protocols.contains(engine_id))
+	}
+
 	/// Send a notification to the given peer we're connected to.
 	///
 	/// Doesn't do anything if we don't have a notifications substream for that protocol with that
@@ -1134,6 +1298,11 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		engine_id: ConsensusEngineId,
 		message: impl Into<Vec<u8>>
 	) {
+		if !self.has_notifications_slot(&target, &engine_id) {
+			trace!(target: "sub-libp2p", "Not sending {:?} notification to {} without a protocol slot", engine_id, target);
+			return;
+		}
+
 		if let Some(protocol_name) = self.protocol_name_by_engine.get(&engine_id) {
 			self.behaviour.write_notification(&target, engine_id, protocol_name.clone(), message);
 		} else {
@@ -1163,14 +1332,19 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		}
 
 		// Registering a protocol while we already have open connections isn't great, but for now
-		// we handle it by notifying that we opened channels with everyone.
-		self.context_data.peers.iter()
-			.map(|(peer_id, peer)|
+		// we handle it by notifying that we opened channels with everyone that gets a slot.
+		let protocol_name = String::from_utf8_lossy(&protocol_name).into_owned();
+		let behaviour = &mut self.behaviour;
+		self.context_data.peers.iter_mut()
+			.filter(|(peer_id, _)| behaviour.allocate_protocol_slot(&protocol_name, peer_id))
+			.map(|(peer_id, peer)| {
+				peer.notifications_protocols.insert(engine_id);
 				event::Event::NotificationStreamOpened {
 					remote: peer_id.clone(),
 					engine_id,
 					roles: peer.info.roles,
-				})
+				}
+			})
 			.collect()
 	}
 
@@ -1195,6 +1369,11 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		}
 		trace!(target: "sync", "Received {} extrinsics from {}", extrinsics.len(), who);
 		if let Some(ref mut peer) = self.context_data.peers.get_mut(&who) {
+			if !peer.transactions {
+				trace!(target: "sync", "Ignoring extrinsics from {} without a protocol slot", who);
+				return;
+			}
+
 			for t in extrinsics {
 				let hash = self.transaction_pool.hash_of(&t);
 				peer.known_extrinsics.insert(hash);
@@ -1210,6 +1389,31 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		}
 	}
 
+	/// Called when peer sends us new encrypted extrinsics
+	fn on_encrypted_extrinsics(
+		&mut self,
+		who: PeerId,
+		message: message::generic::EncryptedTransactions,
+	) {
+		let extrinsics = self.transactions_key_provider.as_ref()
+			.and_then(|provider| provider.key(message.key_id))
+			.and_then(|key| encryption::decrypt(&key, &message))
+			.and_then(|extrinsics| message::Transactions::<B::Extrinsic>::decode(&mut &extrinsics[..]).ok());
+
+		match extrinsics {
+			Some(extrinsics) => self.on_extrinsics(who, extrinsics),
+			None => {
+				debug!(
+					target: "sync",
+					"Failed to decrypt extrinsics from {} with key {}",
+					who,
+					message.key_id,
+				);
+				self.peerset_handle.report_peer(who, rep::UNDECRYPTABLE_EXTRINSICS);
+			}
+		}
+	}
+
 	/// Propagate one extrinsic.
 	pub fn propagate_extrinsic(
 		&mut self,
@@ -1231,12 +1435,17 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		extrinsics: &[(H, B::Extrinsic)],
 	) -> HashMap<H,  Vec<String>> {
 		let mut propagated_to = HashMap::new();
+		let key = self.transactions_key_provider.as_ref().and_then(|provider| provider.current_key());
 		for (who, peer) in self.context_data.peers.iter_mut() {
 			// never send extrinsics to the light node
 			if !peer.info.roles.is_full() {
 				continue;
 			}
 
+			if !peer.transactions {
+				continue;
+			}
+
 			let (hashes, to_send): (Vec<_>, Vec<_>) = extrinsics
 				.iter()
 				.filter(|&(ref hash, _)| peer.known_extrinsics.insert(hash.clone()))
@@ -1251,11 +1460,17 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 						.push(who.to_base58());
 				}
 				trace!(target: "sync", "Sending {} transactions to {}", to_send.len(), who);
+				let message = match key {
+					Some((key_id, ref key)) => GenericMessage::EncryptedTransactions(
+						encryption::encrypt(key_id, key, &to_send.encode())
+					),
+					None => GenericMessage::Transactions(to_send),
+				};
 				send_message::<B> (
 					&mut self.behaviour,
 					&mut self.context_data.stats,
 					&who,
-					GenericMessage::Transactions(to_send)
+					message,
 				)
 			}
 		}
@@ -1306,6 +1521,10 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		let hash = header.hash();
 
 		for (who, ref mut peer) in self.context_data.peers.iter_mut() {
+			if !peer.block_announces {
+				continue;
+			}
+
 			trace!(target: "sync", "Announcing block {:?} to {}", hash, who);
 			let inserted = peer.known_blocks.insert(hash);
 			if inserted || force {
@@ -1347,7 +1566,9 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 			roles: self.config.roles.into(),
 			best_number: info.best_number,
 			best_hash: info.best_hash,
-			chain_status: Vec::new(), // TODO: find a way to make this backwards-compatible
+			// the statement proving our membership of a private network, if any.
+			chain_status: self.membership_validator.as_ref()
+				.map_or_else(Vec::new, |v| v.local_statement(&self.local_peer_id)),
 		};
 
 		self.send_message(&who, GenericMessage::Status(status))
@@ -1524,6 +1745,34 @@ impl<B: BlockT, H: ExHashT> Protocol<B, H> {
 		}
 	}
 
+	/// Hold back block sync while the state of a recent block is warp synced.
+	pub fn pause_for_warp_sync(&mut self) {
+		self.sync.pause_for_warp_sync()
+	}
+
+	/// Call this when a warp sync is over, with or without errors, to sync on from the best block.
+	pub fn on_warp_sync_complete(&mut self) {
+		let results = self.sync.on_warp_sync_complete();
+		for result in results {
+			match result {
+				Ok((id, req)) => {
+					let msg = GenericMessage::BlockRequest(req);
+					send_request(
+						&mut self.behaviour,
+						&mut self.context_data.stats,
+						&mut self.context_data.peers,
+						&id,
+						msg
+					)
+				}
+				Err(sync::BadPeer(id, repu)) => {
+					self.behaviour.disconnect_peer(&id);
+					self.peerset_handle.report_peer(id, repu)
+				}
+			}
+		}
+	}
+
 	/// Call this when a justification has been processed by the import queue, with or without
 	/// errors.
 	pub fn justification_import_result(&mut self, hash: B::Hash, number: NumberFor<B>, success: bool) {
@@ -1930,6 +2179,8 @@ pub enum CustomMessageOutcome<B: BlockT> {
 	NotificationStreamClosed { remote: PeerId, protocols: Vec<ConsensusEngineId> },
 	/// Messages have been received on one or more notifications protocols.
 	NotificationsReceived { remote: PeerId, messages: Vec<(ConsensusEngineId, Bytes)> },
+	/// A node was accepted, refused or disconnected.
+	Access(AccessEvent),
 	None,
 }
 
@@ -2005,6 +2256,10 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 			Self::OutEvent
 		>
 	> {
+		if let Some(event) = self.pending_access_events.pop_front() {
+			return Poll::Ready(NetworkBehaviourAction::GenerateEvent(CustomMessageOutcome::Access(event)));
+		}
+
 		while let Poll::Ready(Some(())) = self.tick_timeout.poll_next_unpin(cx) {
 			self.tick();
 		}
@@ -2054,12 +2309,22 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 		};
 
 		let outcome = match event {
-			GenericProtoOut::CustomProtocolOpen { peer_id, .. } => {
-				self.on_peer_connected(peer_id.clone());
-				CustomMessageOutcome::None
+			GenericProtoOut::CustomProtocolOpen { peer_id, endpoint } => {
+				let event = if self.on_peer_connected(peer_id.clone()) {
+					AccessEvent::Accepted { remote: peer_id, incoming: endpoint.is_listener() }
+				} else {
+					AccessEvent::Refused { remote: peer_id, reason: RefusalReason::NotMember }
+				};
+				CustomMessageOutcome::Access(event)
 			}
-			GenericProtoOut::CustomProtocolClosed { peer_id, .. } => {
-				self.on_peer_disconnected(peer_id.clone())
+			GenericProtoOut::Refused { peer_id, reason } =>
+				CustomMessageOutcome::Access(AccessEvent::Refused { remote: peer_id, reason: reason.into() }),
+			GenericProtoOut::CustomProtocolClosed { peer_id, reason } => {
+				self.pending_access_events.push_back(AccessEvent::Disconnected {
+					remote: peer_id.clone(),
+					reason: reason.into_owned(),
+				});
+				self.on_peer_disconnected(peer_id)
 			},
 			GenericProtoOut::CustomMessage { peer_id, message } =>
 				self.on_custom_message(peer_id, message),
@@ -2075,7 +2340,11 @@ impl<B: BlockT, H: ExHashT> NetworkBehaviour for Protocol<B, H> {
 		};
 
 		if let CustomMessageOutcome::None = outcome {
-			Poll::Pending
+			match self.pending_access_events.pop_front() {
+				Some(event) =>
+					Poll::Ready(NetworkBehaviourAction::GenerateEvent(CustomMessageOutcome::Access(event))),
+				None => Poll::Pending,
+			}
 		} else {
 			Poll::Ready(NetworkBehaviourAction::GenerateEvent(outcome))
 		}
@@ -2135,8 +2404,8 @@ impl<B: BlockT, H: ExHashT> Drop for Protocol<B, H> {
 mod tests {
 	use crate::PeerId;
 	use crate::protocol::light_dispatch::AlwaysBadChecker;
-	use crate::config::{EmptyTransactionPool, Roles};
-	use super::{CustomMessageOutcome, Protocol, ProtocolConfig};
+	use crate::config::{EmptyTransactionPool, MembershipValidator, Roles};
+	use super::{CustomMessageOutcome, Protocol, ProtocolConfig, CURRENT_VERSION, MIN_VERSION, message};
 
 	use sp_consensus::block_validation::DefaultBlockAnnounceValidator;
 	use std::sync::Arc;
@@ -2164,10 +2433,14 @@ mod tests {
 				bootnodes: Vec::new(),
 				reserved_only: false,
 				reserved_nodes: Vec::new(),
+				protocol_slots: Default::default(),
 			},
 			Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
 			None,
 			Default::default(),
+			PeerId::random(),
+			None,
+			None,
 		).unwrap();
 
 		let dummy_peer_id = PeerId::random();
@@ -2177,4 +2450,176 @@ mod tests {
 			_ => panic!()
 		};
 	}
+
+	#[test]
+	fn non_member_is_not_handshaked() {
+		struct OnlyMember(PeerId);
+		impl MembershipValidator for OnlyMember {
+			fn is_member(&self, peer_id: &PeerId) -> bool {
+				*peer_id == self.0
+			}
+		}
+
+		let client = Arc::new(TestClientBuilder::with_default_backend().build_with_longest_chain().0);
+		let member = PeerId::random();
+
+		let (mut protocol, _) = Protocol::<Block, Hash>::new(
+			ProtocolConfig {
+				roles: Roles::FULL,
+				max_parallel_downloads: 10,
+			},
+			client.clone(),
+			Arc::new(AlwaysBadChecker),
+			Arc::new(EmptyTransactionPool),
+			None,
+			None,
+			From::from(&b"test"[..]),
+			sc_peerset::PeersetConfig {
+				in_peers: 10,
+				out_peers: 10,
+				bootnodes: Vec::new(),
+				reserved_only: false,
+				reserved_nodes: Vec::new(),
+				protocol_slots: Default::default(),
+			},
+			Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
+			None,
+			Default::default(),
+			PeerId::random(),
+			Some(Arc::new(OnlyMember(member.clone()))),
+			None,
+		).unwrap();
+
+		let stranger = PeerId::random();
+		protocol.on_peer_connected(stranger.clone());
+		assert!(!protocol.handshaking_peers.contains_key(&stranger));
+
+		protocol.on_peer_connected(member.clone());
+		assert!(protocol.handshaking_peers.contains_key(&member));
+
+		let status = protocol.privacy_status();
+		assert_eq!(status.refused_connections, 1);
+		assert_eq!(status.membership, Some(None));
+	}
+
+	#[test]
+	fn authorized_light_client_is_handshaked() {
+		struct OnlyLightClient(PeerId);
+		impl MembershipValidator for OnlyLightClient {
+			fn is_member(&self, _: &PeerId) -> bool {
+				false
+			}
+
+			fn is_light_client(&self, peer_id: &PeerId) -> bool {
+				*peer_id == self.0
+			}
+		}
+
+		let client = Arc::new(TestClientBuilder::with_default_backend().build_with_longest_chain().0);
+		let light_client = PeerId::random();
+
+		let (mut protocol, _) = Protocol::<Block, Hash>::new(
+			ProtocolConfig {
+				roles: Roles::FULL,
+				max_parallel_downloads: 10,
+			},
+			client.clone(),
+			Arc::new(AlwaysBadChecker),
+			Arc::new(EmptyTransactionPool),
+			None,
+			None,
+			From::from(&b"test"[..]),
+			sc_peerset::PeersetConfig {
+				in_peers: 10,
+				out_peers: 10,
+				bootnodes: Vec::new(),
+				reserved_only: false,
+				reserved_nodes: Vec::new(),
+				protocol_slots: Default::default(),
+			},
+			Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
+			None,
+			Default::default(),
+			PeerId::random(),
+			Some(Arc::new(OnlyLightClient(light_client.clone()))),
+			None,
+		).unwrap();
+
+		let stranger = PeerId::random();
+		protocol.on_peer_connected(stranger.clone());
+		assert!(!protocol.handshaking_peers.contains_key(&stranger));
+
+		protocol.on_peer_connected(light_client.clone());
+		assert!(protocol.handshaking_peers.contains_key(&light_client));
+	}
+
+	#[test]
+	fn full_node_proves_membership_with_its_statement() {
+		struct SignedMember(PeerId);
+		impl MembershipValidator for SignedMember {
+			fn is_member(&self, peer_id: &PeerId) -> bool {
+				*peer_id == self.0
+			}
+
+			fn local_statement(&self, _: &PeerId) -> Vec<u8> {
+				b"member".to_vec()
+			}
+
+			fn verify_statement(&self, peer_id: &PeerId, statement: &[u8]) -> bool {
+				self.is_member(peer_id) && statement == b"member"
+			}
+		}
+
+		let client = Arc::new(TestClientBuilder::with_default_backend().build_with_longest_chain().0);
+		let member = PeerId::random();
+
+		let (mut protocol, _) = Protocol::<Block, Hash>::new(
+			ProtocolConfig {
+				roles: Roles::FULL,
+				max_parallel_downloads: 10,
+			},
+			client.clone(),
+			Arc::new(AlwaysBadChecker),
+			Arc::new(EmptyTransactionPool),
+			None,
+			None,
+			From::from(&b"test"[..]),
+			sc_peerset::PeersetConfig {
+				in_peers: 10,
+				out_peers: 10,
+				bootnodes: Vec::new(),
+				reserved_only: false,
+				reserved_nodes: Vec::new(),
+				protocol_slots: Default::default(),
+			},
+			Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
+			None,
+			Default::default(),
+			PeerId::random(),
+			Some(Arc::new(SignedMember(member.clone()))),
+			None,
+		).unwrap();
+
+		let info = client.chain_info();
+		let status = |statement: &[u8]| message::generic::Status {
+			version: CURRENT_VERSION,
+			min_supported_version: MIN_VERSION,
+			roles: Roles::FULL,
+			best_number: info.best_number,
+			best_hash: info.best_hash,
+			genesis_hash: info.genesis_hash,
+			chain_status: statement.to_vec(),
+		};
+
+		protocol.on_peer_connected(member.clone());
+		protocol.on_status_message(member.clone(), status(b"forged"));
+		assert!(!protocol.context_data.peers.contains_key(&member));
+		assert_eq!(protocol.privacy_status().refused_connections, 1);
+
+		protocol.on_peer_disconnected(member.clone());
+		protocol.on_peer_connected(member.clone());
+		protocol.on_status_message(member.clone(), status(b"member"));
+		assert!(protocol.context_data.peers.contains_key(&member));
+		assert_eq!(protocol.membership_statements.get(&member), Some(&b"member".to_vec()));
+	}
 }
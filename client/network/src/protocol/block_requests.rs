@@ -271,6 +271,7 @@ where
 	}
 
 	fn inject_node_event(&mut self, peer: PeerId, Request(request, mut stream): Request<NegotiatedSubstream>) {
+		let _context = sp_tracing::log_context::enter_peer(&peer);
 		match self.on_block_request(&peer, &request) {
 			Ok(res) => {
 				log::trace!("enqueueing block response for peer {} with {} blocks", peer, res.blocks.len());
@@ -0,0 +1,81 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Encryption of the transactions exchanged between the members of a private network.
+//!
+//! Transactions are encrypted with ChaCha20-Poly1305, using the network-wide key identified by
+//! `key_id` and a random nonce. The key id is authenticated as additional data.
+
+use codec::Encode;
+use crate::config::TransactionsKey;
+use super::message::generic::EncryptedTransactions;
+
+/// Encrypts the SCALE encoded transactions with the key `key_id`.
+pub fn encrypt(key_id: u64, key: &TransactionsKey, transactions: &[u8]) -> EncryptedTransactions {
+	let nonce = rand::random::<[u8; 12]>();
+	let mut ciphertext = Vec::with_capacity(transactions.len());
+	let tag = chacha20_poly1305_aead::encrypt(key, &nonce, &key_id.encode(), transactions, &mut ciphertext)
+		.expect("writing to a Vec never fails; qed");
+
+	EncryptedTransactions { key_id, nonce, tag, ciphertext }
+}
+
+/// Decrypts the transactions. Returns `None` if the message was not encrypted with `key` or was
+/// tampered with.
+pub fn decrypt(key: &TransactionsKey, message: &EncryptedTransactions) -> Option<Vec<u8>> {
+	let mut transactions = Vec::with_capacity(message.ciphertext.len());
+	chacha20_poly1305_aead::decrypt(
+		key,
+		&message.nonce,
+		&message.key_id.encode(),
+		&message.ciphertext,
+		&message.tag,
+		&mut transactions,
+	).ok()?;
+
+	Some(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decrypts_encrypted_transactions() {
+		let key = [7; 32];
+		let message = encrypt(1, &key, b"transactions");
+
+		assert_ne!(&message.ciphertext[..], &b"transactions"[..]);
+		assert_eq!(decrypt(&key, &message), Some(b"transactions".to_vec()));
+	}
+
+	#[test]
+	fn rejects_wrong_key_and_tampered_messages() {
+		let key = [7; 32];
+		let message = encrypt(1, &key, b"transactions");
+
+		assert_eq!(decrypt(&[8; 32], &message), None);
+
+		let mut tampered = message.clone();
+		tampered.ciphertext[0] ^= 1;
+		assert_eq!(decrypt(&key, &tampered), None);
+
+		let mut tampered = message;
+		tampered.key_id = 2;
+		assert_eq!(decrypt(&key, &tampered), None);
+	}
+}
@@ -40,6 +40,57 @@ pub enum DhtEvent {
 	ValuePutFailed(Key),
 }
 
+/// Reason why a connection with a node was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefusalReason {
+	/// We only accept reserved nodes and the node isn't one.
+	NotReserved,
+	/// The node is banned because of its low reputation.
+	Banned,
+	/// All the slots are in use.
+	SlotsFull,
+	/// The node is not a member of the private network.
+	NotMember,
+}
+
+impl From<sc_peerset::RejectReason> for RefusalReason {
+	fn from(reason: sc_peerset::RejectReason) -> Self {
+		match reason {
+			sc_peerset::RejectReason::NotReserved => RefusalReason::NotReserved,
+			sc_peerset::RejectReason::Banned => RefusalReason::Banned,
+			sc_peerset::RejectReason::SlotsFull => RefusalReason::SlotsFull,
+		}
+	}
+}
+
+/// Events about nodes accessing the local node, for auditing purposes.
+#[derive(Debug, Clone)]
+pub enum AccessEvent {
+	/// A connection with the node was accepted.
+	Accepted {
+		/// Node we are connected to.
+		remote: PeerId,
+		/// `true` if the node connected to us, `false` if we connected to it.
+		incoming: bool,
+	},
+
+	/// A connection with the node was refused.
+	Refused {
+		/// Node that was refused.
+		remote: PeerId,
+		/// Why the connection was refused.
+		reason: RefusalReason,
+	},
+
+	/// We were disconnected from the node.
+	Disconnected {
+		/// Node we were connected to.
+		remote: PeerId,
+		/// Why the connection was closed, for debugging purposes.
+		reason: String,
+	},
+}
+
 /// Type for events generated by networking layer.
 #[derive(Debug, Clone)]
 #[must_use]
@@ -30,7 +30,7 @@ use rand::distributions::{Distribution as _, Uniform};
 use smallvec::SmallVec;
 use sp_runtime::ConsensusEngineId;
 use std::{borrow::Cow, collections::hash_map::Entry, cmp};
-use std::{error, mem, pin::Pin, str, time::Duration};
+use std::{error, io, mem, path::Path, pin::Pin, str, time::Duration};
 use std::task::{Context, Poll};
 use wasm_timer::Instant;
 
@@ -230,6 +230,14 @@ pub enum GenericProtoOut {
 		endpoint: ConnectedPoint,
 	},
 
+	/// The peerset manager refused an incoming connection.
+	Refused {
+		/// Id of the node that tried to connect.
+		peer_id: PeerId,
+		/// Why the connection was refused.
+		reason: sc_peerset::RejectReason,
+	},
+
 	/// Closed a custom protocol with the remote.
 	CustomProtocolClosed {
 		/// Id of the peer we were connected to.
@@ -296,6 +304,11 @@ impl GenericProto {
 		self.peerset.num_discovered_peers()
 	}
 
+	/// Returns the number of reserved peers.
+	pub fn num_reserved_peers(&self) -> usize {
+		self.peerset.num_reserved_peers()
+	}
+
 	/// Returns the list of all the peers we have an open channel to.
 	pub fn open_peers<'a>(&'a self) -> impl Iterator<Item = &'a PeerId> + 'a {
 		self.peers.iter().filter(|(_, state)| state.is_open()).map(|(id, _)| id)
@@ -463,6 +476,32 @@ impl GenericProto {
 		self.peerset.debug_info()
 	}
 
+	/// Tries to allocate a slot of `protocol` to the peer. See
+	/// [`sc_peerset::Peerset::allocate_protocol_slot`].
+	pub fn allocate_protocol_slot(&mut self, protocol: &str, peer_id: &PeerId) -> bool {
+		self.peerset.allocate_protocol_slot(protocol, peer_id)
+	}
+
+	/// Releases the protocol slots allocated to the peer.
+	pub fn release_protocol_slots(&mut self, peer_id: &PeerId) {
+		self.peerset.release_protocol_slots(peer_id)
+	}
+
+	/// Returns the reputation of the given peer, or `None` if the peerset doesn't know about it.
+	pub fn peer_reputation(&mut self, peer_id: &PeerId) -> Option<i32> {
+		self.peerset.peer_reputation(peer_id)
+	}
+
+	/// Writes a snapshot of the peerset reputations to `path`.
+	pub fn export_reputations(&mut self, path: &Path) -> io::Result<()> {
+		self.peerset.export_reputations(path)
+	}
+
+	/// Restores the peerset reputations previously written to `path`.
+	pub fn import_reputations(&mut self, path: &Path) -> io::Result<()> {
+		self.peerset.import_reputations(path)
+	}
+
 	/// Function that is called when the peerset wants us to connect to a node.
 	fn peerset_report_connect(&mut self, peer_id: PeerId) {
 		let mut occ_entry = match self.peers.entry(peer_id) {
@@ -661,6 +700,7 @@ impl GenericProto {
 
 	/// Function that is called when the peerset wants us to reject an incoming node.
 	fn peerset_report_reject(&mut self, index: sc_peerset::IncomingIndex) {
+		let reason = self.peerset.take_reject_reason(index);
 		let incoming = if let Some(pos) = self.incoming.iter().position(|i| i.incoming_id == index) {
 			self.incoming.remove(pos)
 		} else {
@@ -694,10 +734,15 @@ impl GenericProto {
 			{:?}", index, incoming.peer_id, connected_point);
 		debug!(target: "sub-libp2p", "Handler({:?}) <= Disable", incoming.peer_id);
 		self.events.push(NetworkBehaviourAction::SendEvent {
-			peer_id: incoming.peer_id,
+			peer_id: incoming.peer_id.clone(),
 			event: NotifsHandlerIn::Disable,
 		});
 		*state = PeerState::Disabled { open: false, connected_point, banned_until: None };
+
+		if let Some(reason) = reason {
+			let event = GenericProtoOut::Refused { peer_id: incoming.peer_id, reason };
+			self.events.push(NetworkBehaviourAction::GenerateEvent(event));
+		}
 	}
 }
 
@@ -28,7 +28,7 @@ use bytes::Bytes;
 use codec::{self, Encode, Decode};
 use crate::{
 	chain::Client,
-	config::ProtocolId,
+	config::{MembershipValidator, ProtocolId},
 	protocol::{api, light_dispatch::TIMEOUT_REPUTATION_CHANGE}
 };
 use futures::{channel::oneshot, future::BoxFuture, prelude::*, stream::FuturesUnordered};
@@ -252,6 +252,8 @@ pub struct LightClientHandler<B: Block> {
 	next_request_id: u64,
 	/// Handle to use for reporting misbehaviour of peers.
 	peerset: sc_peerset::PeersetHandle,
+	/// Checks that remote peers are authorized light clients before serving their requests.
+	membership_validator: Option<Arc<dyn MembershipValidator>>,
 }
 
 impl<B> LightClientHandler<B>
@@ -275,9 +277,17 @@ where
 			outstanding: IntMap::default(),
 			next_request_id: 1,
 			peerset,
+			membership_validator: None,
 		}
 	}
 
+	/// Only serve the requests of peers the given validator considers light clients of the
+	/// private network.
+	pub fn with_membership_validator(mut self, validator: Option<Arc<dyn MembershipValidator>>) -> Self {
+		self.membership_validator = validator;
+		self
+	}
+
 	/// We rely on external information about peers best blocks as we lack the
 	/// means to determine it ourselves.
 	pub fn update_best_block(&mut self, peer: &PeerId, num: NumberFor<B>) {
@@ -689,10 +699,17 @@ where
 	}
 
 	fn inject_node_event(&mut self, peer: PeerId, event: Event<NegotiatedSubstream>) {
+		let _context = sp_tracing::log_context::enter_peer(&peer);
 		match event {
 			// An incoming request from remote has been received.
 			Event::Request(request, mut stream) => {
 				log::trace!("incoming request from {}", peer);
+				if !self.membership_validator.as_ref().map_or(true, |v| v.is_light_client(&peer)) {
+					log::debug!("ignoring request from unauthorized light client {}", peer);
+					self.remove_peer(&peer);
+					self.peerset.report_peer(peer, ReputationChange::new_fatal("unauthorized light client"));
+					return
+				}
 				let result = match &request.request {
 					Some(api::v1::light::request::Request::RemoteCallRequest(r)) =>
 						self.on_remote_call_request(&peer, r),
@@ -1202,6 +1219,7 @@ mod tests {
 			bootnodes: Vec::new(),
 			reserved_only: false,
 			reserved_nodes: Vec::new(),
+			protocol_slots: Default::default(),
 		};
 		sc_peerset::Peerset::from_config(cfg)
 	}
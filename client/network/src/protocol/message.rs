@@ -154,6 +154,19 @@ pub mod generic {
 		pub data: Vec<u8>,
 	}
 
+	/// SCALE encoded `Transactions`, encrypted with ChaCha20-Poly1305.
+	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
+	pub struct EncryptedTransactions {
+		/// Id of the key the transactions are encrypted with.
+		pub key_id: u64,
+		/// Nonce of the encryption, unique per message.
+		pub nonce: [u8; 12],
+		/// Authentication tag.
+		pub tag: [u8; 16],
+		/// Encrypted transactions.
+		pub ciphertext: Vec<u8>,
+	}
+
 	/// Block data sent in the response.
 	#[derive(Debug, PartialEq, Eq, Clone, Encode, Decode)]
 	pub struct BlockData<Header, Hash, Extrinsic> {
@@ -219,6 +232,8 @@ pub mod generic {
 		FinalityProofResponse(FinalityProofResponse<Hash>),
 		/// Batch of consensus protocol messages.
 		ConsensusBatch(Vec<ConsensusMessage>),
+		/// Transactions encrypted with the key of the private network.
+		EncryptedTransactions(EncryptedTransactions),
 	}
 
 	impl<Header, Hash, Number, Extrinsic> Message<Header, Hash, Number, Extrinsic> {
@@ -243,6 +258,7 @@ pub mod generic {
 				Message::FinalityProofRequest(_) => "FinalityProofRequest",
 				Message::FinalityProofResponse(_) => "FinalityProofResponse",
 				Message::ConsensusBatch(_) => "ConsensusBatch",
+				Message::EncryptedTransactions(_) => "EncryptedTransactions",
 			}
 		}
 	}
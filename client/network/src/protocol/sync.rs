@@ -140,6 +140,8 @@ pub struct ChainSync<B: BlockT> {
 	max_parallel_downloads: u32,
 	/// Total number of processed blocks (imported or failed).
 	processed_blocks: usize,
+	/// Whether blocks are held back while the state of a recent block is warp synced.
+	warp_pending: bool,
 }
 
 /// All the data we have about a Peer that we are trying to sync with
@@ -321,6 +323,7 @@ impl<B: BlockT> ChainSync<B> {
 			block_announce_validator,
 			max_parallel_downloads,
 			processed_blocks: 0,
+			warp_pending: false,
 		}
 	}
 
@@ -371,6 +374,19 @@ impl<B: BlockT> ChainSync<B> {
 	pub fn new_peer(&mut self, who: PeerId, best_hash: B::Hash, best_number: NumberFor<B>)
 		-> Result<Option<BlockRequest<B>>, BadPeer>
 	{
+		// peers are only tracked during a warp sync, they are checked on `restart`.
+		if self.warp_pending {
+			debug!(target:"sync", "New peer with best hash {} ({}) during warp sync.", best_hash, best_number);
+			self.peers.insert(who, PeerSync {
+				common_number: Zero::zero(),
+				best_hash,
+				best_number,
+				state: PeerSyncState::Available,
+				recently_announced: Default::default(),
+			});
+			return Ok(None)
+		}
+
 		// There is nothing sync can get from the node that has no blockchain data.
 		match self.block_status(&best_hash) {
 			Err(e) => {
@@ -579,7 +595,7 @@ impl<B: BlockT> ChainSync<B> {
 
 	/// Get an iterator over all block requests of all peers.
 	pub fn block_requests(&mut self) -> impl Iterator<Item = (PeerId, BlockRequest<B>)> + '_ {
-		if self.is_idle {
+		if self.is_idle || self.warp_pending {
 			return Either::Left(std::iter::empty())
 		}
 		if self.queue_blocks.len() > MAX_IMPORTING_BLOCKS {
@@ -1106,6 +1122,9 @@ impl<B: BlockT> ChainSync<B> {
 		if let PeerSyncState::AncestorSearch(_, _) = peer.state {
 			return OnBlockAnnounce::Nothing
 		}
+		if self.warp_pending {
+			return OnBlockAnnounce::Nothing
+		}
 		// If the announced block is the best they have and is not ahead of us, our common number
 		// is either one further ahead or it's the one they just announced, if we know about it.
 		if is_best {
@@ -1180,6 +1199,20 @@ impl<B: BlockT> ChainSync<B> {
 		self.is_idle = false;
 	}
 
+	/// Hold back block requests and announced blocks while the state of a recent block is
+	/// downloaded, until `on_warp_sync_complete` is called.
+	pub fn pause_for_warp_sync(&mut self) {
+		self.warp_pending = true;
+	}
+
+	/// Sync on from the best block once a warp sync is over, whether it succeeded or not.
+	pub fn on_warp_sync_complete<'a>(&'a mut self)
+		-> impl Iterator<Item = Result<(PeerId, BlockRequest<B>), BadPeer>> + 'a
+	{
+		self.warp_pending = false;
+		self.restart()
+	}
+
 	/// Restart the sync process.
 	fn restart<'a>(&'a mut self) -> impl Iterator<Item = Result<(PeerId, BlockRequest<B>), BadPeer>> + 'a {
 		self.processed_blocks = 0;
@@ -1505,4 +1538,31 @@ mod test {
 			})
 		);
 	}
+
+	#[test]
+	fn holds_back_block_requests_during_warp_sync() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let info = client.info();
+		let block_announce_validator = Box::new(DefaultBlockAnnounceValidator::new(client.clone()));
+		let peer_id = PeerId::random();
+
+		let mut sync = ChainSync::new(
+			Roles::FULL,
+			client.clone(),
+			&info,
+			None,
+			block_announce_validator,
+			1,
+		);
+		sync.pause_for_warp_sync();
+
+		// a peer ahead of us is tracked, but no block is requested from it.
+		assert_eq!(sync.new_peer(peer_id.clone(), [42u8; 32].into(), 10), Ok(None));
+		assert!(sync.peer_info(&peer_id).is_some());
+		assert_eq!(sync.block_requests().count(), 0);
+
+		// once the warp sync is over, blocks are requested from the best block.
+		assert_eq!(sync.on_warp_sync_complete().count(), 0);
+		assert!(sync.block_requests().any(|(who, _)| who == peer_id));
+	}
 }
@@ -0,0 +1,639 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! `NetworkBehaviour` implementation of warp sync.
+//!
+//! A node that only has the genesis block can warp sync: it requests proofs of finality of a
+//! recent block from the known authorities (cf. [`WarpSyncProvider`]), then downloads the
+//! state of that block in chunks, which are imported as they arrive. Regular sync carries on
+//! from that block afterwards.
+//!
+//! Every request is sent on a separate substream, which gets closed after the response has
+//! been sent back. Requests and responses are SCALE encoded.
+
+use bytes::Bytes;
+use codec::{Encode, Decode};
+use crate::{
+	chain::{Client, WarpSyncProgress, WarpSyncProvider},
+	config::{MembershipValidator, ProtocolId},
+	protocol::light_dispatch::TIMEOUT_REPUTATION_CHANGE,
+};
+use futures::{
+	channel::{mpsc, oneshot},
+	future::BoxFuture,
+	prelude::*,
+	stream::FuturesUnordered,
+};
+use libp2p::{
+	core::{
+		ConnectedPoint,
+		Multiaddr,
+		PeerId,
+		upgrade::{InboundUpgrade, OutboundUpgrade, ReadOneError, UpgradeInfo},
+		upgrade::{read_one, write_one},
+	},
+	swarm::{
+		NegotiatedSubstream,
+		NetworkBehaviour,
+		NetworkBehaviourAction,
+		OneShotHandler,
+		PollParameters,
+		SubstreamProtocol,
+	},
+};
+use log::{debug, error, info};
+use sc_client_api::StateEntry;
+use sc_peerset::ReputationChange;
+use sp_core::storage::well_known_keys;
+use sp_runtime::{
+	Justification,
+	generic::BlockId,
+	traits::{Block, Header},
+};
+use std::{
+	collections::{HashSet, VecDeque},
+	io,
+	iter,
+	sync::Arc,
+	task::{Context, Poll},
+	time::Duration,
+};
+use wasm_timer::Instant;
+
+/// Number of downloaded state chunks waiting to be imported before we stop downloading.
+const STATE_CHUNKS_BUFFER: usize = 4;
+
+/// Configuration options for the `WarpSync` behaviour.
+#[derive(Debug, Clone)]
+pub struct Config {
+	max_request_len: usize,
+	max_response_len: usize,
+	max_state_chunk: usize,
+	inactivity_timeout: Duration,
+	request_timeout: Duration,
+	protocol: Bytes,
+}
+
+impl Config {
+	/// Create a fresh configuration with the following options:
+	///
+	/// - max. request size = 64 KiB
+	/// - max. response size = 16 MiB
+	/// - max. size of the keys and values of a state chunk = 4 MiB
+	/// - inactivity timeout = 15s
+	/// - request timeout = 40s
+	pub fn new(id: &ProtocolId) -> Self {
+		let mut c = Config {
+			max_request_len: 64 * 1024,
+			max_response_len: 16 * 1024 * 1024,
+			max_state_chunk: 4 * 1024 * 1024,
+			inactivity_timeout: Duration::from_secs(15),
+			request_timeout: Duration::from_secs(40),
+			protocol: Bytes::new(),
+		};
+		c.set_protocol(id);
+		c
+	}
+
+	/// Set protocol to use for upgrade negotiation.
+	pub fn set_protocol(&mut self, id: &ProtocolId) -> &mut Self {
+		let mut v = Vec::new();
+		v.extend_from_slice(b"/");
+		v.extend_from_slice(id.as_bytes());
+		v.extend_from_slice(b"/warp/1");
+		self.protocol = v.into();
+		self
+	}
+}
+
+/// A warp sync request.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum WarpSyncRequest {
+	/// A proof of finality of a recent block, from the given authority set.
+	Proof(u64),
+	/// A chunk of the state of a block.
+	State {
+		/// The hash of the block.
+		block: Vec<u8>,
+		/// The storage key of a child trie, or `None` for the top-level trie.
+		storage_key: Option<Vec<u8>>,
+		/// The key after which the chunk starts, or `None` for the first key.
+		start: Option<Vec<u8>>,
+	},
+}
+
+/// A response to a [`WarpSyncRequest`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum WarpSyncResponse {
+	/// The requested proof.
+	Proof(Vec<u8>),
+	/// The requested key values in key order, and whether they end the trie.
+	State(Vec<(Vec<u8>, Vec<u8>)>, bool),
+	/// The request can't be served, e.g. because the state of the block was pruned.
+	Refused,
+}
+
+/// Event generated by the `WarpSync` behaviour.
+#[derive(Debug)]
+pub enum WarpSyncOut<B: Block> {
+	/// The state of the given block was imported, regular sync should carry on from it.
+	Imported(B::Header),
+	/// Warp sync failed, regular sync should start from genesis.
+	Failed,
+}
+
+/// Progress of our own warp sync.
+enum Phase<B: Block> {
+	/// Requesting proofs until one reaches a recent finalized block.
+	Proof,
+	/// Downloading the state of the proven block, which is imported as it arrives.
+	State {
+		/// The proven block.
+		block: B::Hash,
+		/// The storage key of the child trie being downloaded, `None` for the top-level trie.
+		storage_key: Option<Vec<u8>>,
+		/// The last key received of the trie being downloaded.
+		start: Option<Vec<u8>>,
+		/// The storage keys of the child tries to download after the top-level trie.
+		child_tries: VecDeque<Vec<u8>>,
+		/// Feeds the downloaded state to the import. Dropped at the end of the state.
+		sender: mpsc::Sender<Vec<StateEntry>>,
+	},
+	/// Waiting for the import of the downloaded state.
+	Importing,
+}
+
+/// The warp sync behaviour.
+pub struct WarpSync<B: Block> {
+	/// This behaviour's configuration.
+	config: Config,
+	/// Blockchain client.
+	chain: Arc<dyn Client<B>>,
+	/// Generates the proofs we serve and verifies the proofs we receive.
+	provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+	/// Checks that remote peers are members before serving their requests.
+	membership_validator: Option<Arc<dyn MembershipValidator>>,
+	/// Handle to use for reporting misbehaviour of peers.
+	peerset: sc_peerset::PeersetHandle,
+	/// Connected peers we may send requests to.
+	peers: HashSet<PeerId>,
+	/// Futures sending back responses to remote peers.
+	responses: FuturesUnordered<BoxFuture<'static, ()>>,
+	/// Our own warp sync, if in progress.
+	phase: Option<Phase<B>>,
+	/// Our request in flight: its ID, the peer it was sent to and when.
+	outstanding: Option<(u64, PeerId, Instant)>,
+	/// (Local) Request ID counter
+	next_request_id: u64,
+	/// The header whose state is imported, and the result of the import.
+	import: Option<(B::Header, oneshot::Receiver<Result<(), String>>)>,
+}
+
+impl<B> WarpSync<B>
+where
+	B: Block,
+{
+	/// Construct a new warp sync behaviour, serving the requests of other nodes.
+	pub fn new(
+		cfg: Config,
+		chain: Arc<dyn Client<B>>,
+		provider: Option<Arc<dyn WarpSyncProvider<B>>>,
+		peerset: sc_peerset::PeersetHandle,
+	) -> Self {
+		WarpSync {
+			config: cfg,
+			chain,
+			provider,
+			membership_validator: None,
+			peerset,
+			peers: HashSet::new(),
+			responses: FuturesUnordered::new(),
+			phase: None,
+			outstanding: None,
+			next_request_id: 1,
+			import: None,
+		}
+	}
+
+	/// Only serve the requests of peers the given validator considers members of the private
+	/// network.
+	pub fn with_membership_validator(mut self, validator: Option<Arc<dyn MembershipValidator>>) -> Self {
+		self.membership_validator = validator;
+		self
+	}
+
+	/// Start warp syncing. Does nothing without a provider to verify proofs.
+	pub fn start(&mut self) {
+		if self.provider.is_some() && self.phase.is_none() && self.import.is_none() {
+			info!("⏩ Warp syncing from authority set #{}", self.provider.as_ref().map_or(0, |p| p.current_set()));
+			self.phase = Some(Phase::Proof);
+		}
+	}
+
+	fn next_request_id(&mut self) -> u64 {
+		let id = self.next_request_id;
+		self.next_request_id += 1;
+		id
+	}
+
+	/// Stop sending requests to the given peer, reporting it if it misbehaved.
+	fn remove_peer(&mut self, peer: &PeerId, reputation: Option<ReputationChange>) {
+		self.peers.remove(peer);
+		if self.outstanding.as_ref().map_or(false, |(_, p, _)| p == peer) {
+			self.outstanding = None;
+		}
+		if let Some(reputation) = reputation {
+			self.peerset.report_peer(peer.clone(), reputation);
+		}
+	}
+
+	/// Callback, invoked when a request has been received from remote.
+	fn on_request(&self, peer: &PeerId, request: WarpSyncRequest) -> WarpSyncResponse {
+		match request {
+			WarpSyncRequest::Proof(begin) => {
+				let provider = match &self.provider {
+					Some(provider) => provider,
+					None => return WarpSyncResponse::Refused,
+				};
+				match provider.generate(begin) {
+					Ok(proof) => WarpSyncResponse::Proof(proof),
+					Err(e) => {
+						debug!(target: "sync", "Failed to prove warp sync from set #{} to {}: {}", begin, peer, e);
+						WarpSyncResponse::Refused
+					},
+				}
+			},
+			WarpSyncRequest::State { block, storage_key, start } => {
+				let block = match B::Hash::decode(&mut &block[..]) {
+					Ok(block) => block,
+					Err(_) => return WarpSyncResponse::Refused,
+				};
+				match self.chain.read_state_chunk(
+					&BlockId::Hash(block),
+					storage_key.as_ref().map(|key| &key[..]),
+					start.as_ref().map(|key| &key[..]),
+					self.config.max_state_chunk,
+				) {
+					Ok((pairs, complete)) => WarpSyncResponse::State(pairs, complete),
+					Err(e) => {
+						debug!(target: "sync", "Failed to read state of {} for {}: {}", block, peer, e);
+						WarpSyncResponse::Refused
+					},
+				}
+			},
+		}
+	}
+
+	/// Callback, invoked when a response to our request has been received from remote.
+	fn on_response(&mut self, peer: PeerId, response: WarpSyncResponse) {
+		let provider = match &self.provider {
+			Some(provider) => provider.clone(),
+			None => return,
+		};
+
+		let phase = self.phase.take();
+		self.phase = match (phase, response) {
+			(phase, WarpSyncResponse::Refused) => {
+				debug!(target: "sync", "Warp sync request refused by {}", peer);
+				self.remove_peer(&peer, None);
+				phase
+			},
+			(Some(Phase::Proof), WarpSyncResponse::Proof(proof)) => match provider.verify(&proof) {
+				Ok(WarpSyncProgress::Partial(next)) => {
+					debug!(target: "sync", "Verified warp sync proof of {}, continuing from set #{}", peer, next);
+					Some(Phase::Proof)
+				},
+				Ok(WarpSyncProgress::Complete(header, justification)) => {
+					info!(
+						"⏩ Warp sync proved block #{} ({}), downloading its state",
+						header.number(),
+						header.hash(),
+					);
+					Some(self.start_import(provider, header, justification))
+				},
+				Err(e) => {
+					debug!(target: "sync", "Invalid warp sync proof from {}: {}", peer, e);
+					self.remove_peer(&peer, Some(ReputationChange::new_fatal("invalid warp sync proof")));
+					Some(Phase::Proof)
+				},
+			},
+			(
+				Some(Phase::State { block, storage_key, start, mut child_tries, mut sender }),
+				WarpSyncResponse::State(pairs, complete),
+			) => {
+				// the keys must follow `start` in order. The import checks the values.
+				let first_follows = match (&start, pairs.first()) {
+					(Some(start), Some((first, _))) => first > start,
+					_ => true,
+				};
+				if !first_follows || !pairs.windows(2).all(|w| w[0].0 < w[1].0) || (pairs.is_empty() && !complete) {
+					debug!(target: "sync", "Invalid state chunk from {}", peer);
+					self.remove_peer(&peer, Some(ReputationChange::new_fatal("invalid state chunk")));
+					self.phase = Some(Phase::State { block, storage_key, start, child_tries, sender });
+					return
+				}
+
+				if storage_key.is_none() {
+					child_tries.extend(pairs.iter()
+						.filter(|(key, _)| well_known_keys::is_child_storage_key(key))
+						.map(|(key, _)| key.clone())
+					);
+				}
+				let start = pairs.last().map(|(key, _)| key.clone()).or(start);
+				let entries = pairs.into_iter()
+					.map(|(key, value)| match &storage_key {
+						Some(storage_key) => StateEntry::Child(storage_key.clone(), key, value),
+						None => StateEntry::Top(key, value),
+					})
+					.collect();
+				if let Err(e) = sender.try_send(entries) {
+					// the import stopped, and its result tells why.
+					debug!(target: "sync", "Failed to pass state chunk to the import: {}", e);
+					self.phase = Some(Phase::Importing);
+					return
+				}
+
+				if !complete {
+					Some(Phase::State { block, storage_key, start, child_tries, sender })
+				} else if let Some(next) = child_tries.pop_front() {
+					Some(Phase::State { block, storage_key: Some(next), start: None, child_tries, sender })
+				} else {
+					// dropping the sender ends the state given to the import.
+					info!("⏩ Warp sync downloaded the state of block {}, importing", block);
+					Some(Phase::Importing)
+				}
+			},
+			(phase, _) => {
+				debug!(target: "sync", "Unexpected warp sync response from {}", peer);
+				self.remove_peer(&peer, Some(ReputationChange::new_fatal("unexpected warp sync response")));
+				phase
+			},
+		};
+	}
+
+	/// Import the state of a proven header on a separate thread as it is downloaded.
+	fn start_import(
+		&mut self,
+		provider: Arc<dyn WarpSyncProvider<B>>,
+		header: B::Header,
+		justification: Justification,
+	) -> Phase<B> {
+		let (sender, receiver) = mpsc::channel(STATE_CHUNKS_BUFFER);
+		let (result_sender, result) = oneshot::channel();
+		let block = header.hash();
+		let import_header = header.clone();
+		let spawned = std::thread::Builder::new()
+			.name("warp-sync-import".into())
+			.spawn(move || {
+				let mut state = futures::executor::block_on_stream(receiver)
+					.flat_map(|entries: Vec<StateEntry>| entries.into_iter().map(Ok));
+				let result = provider.import(import_header, justification, &mut state);
+				let _ = result_sender.send(result.map_err(|e| e.to_string()));
+			});
+		if let Err(e) = spawned {
+			// the result is cancelled, which ends the warp sync.
+			error!("Failed to spawn the warp sync import: {}", e);
+		}
+
+		self.import = Some((header, result));
+		Phase::State { block, storage_key: None, start: None, child_tries: VecDeque::new(), sender }
+	}
+
+	/// Our next request, if any can be sent.
+	fn next_request(&mut self, cx: &mut Context) -> Option<WarpSyncRequest> {
+		match &mut self.phase {
+			Some(Phase::Proof) =>
+				self.provider.as_ref().map(|provider| WarpSyncRequest::Proof(provider.current_set())),
+			Some(Phase::State { block, storage_key, start, sender, .. }) => match sender.poll_ready(cx) {
+				Poll::Ready(Ok(())) => Some(WarpSyncRequest::State {
+					block: block.as_ref().to_vec(),
+					storage_key: storage_key.clone(),
+					start: start.clone(),
+				}),
+				// the import is busy with earlier chunks, or it stopped.
+				_ => None,
+			},
+			Some(Phase::Importing) | None => None,
+		}
+	}
+}
+
+impl<B> NetworkBehaviour for WarpSync<B>
+where
+	B: Block
+{
+	type ProtocolsHandler = OneShotHandler<InboundProtocol, OutboundProtocol, Event<NegotiatedSubstream>>;
+	type OutEvent = WarpSyncOut<B>;
+
+	fn new_handler(&mut self) -> Self::ProtocolsHandler {
+		let p = InboundProtocol {
+			max_request_len: self.config.max_request_len,
+			protocol: self.config.protocol.clone(),
+		};
+		OneShotHandler::new(SubstreamProtocol::new(p), self.config.inactivity_timeout)
+	}
+
+	fn addresses_of_peer(&mut self, _: &PeerId) -> Vec<Multiaddr> {
+		Vec::new()
+	}
+
+	fn inject_connected(&mut self, peer: PeerId, _: ConnectedPoint) {
+		self.peers.insert(peer);
+	}
+
+	fn inject_disconnected(&mut self, peer: &PeerId, _: ConnectedPoint) {
+		self.remove_peer(peer, None)
+	}
+
+	fn inject_node_event(&mut self, peer: PeerId, event: Event<NegotiatedSubstream>) {
+		let _context = sp_tracing::log_context::enter_peer(&peer);
+		match event {
+			// An incoming request from remote has been received.
+			Event::Request(request, mut stream) => {
+				if !self.membership_validator.as_ref().map_or(true, |v| v.is_member(&peer)) {
+					debug!(target: "sync", "Ignoring warp sync request from non-member {}", peer);
+					self.remove_peer(&peer, Some(ReputationChange::new_fatal("warp sync request from non-member")));
+					return
+				}
+				let response = self.on_request(&peer, request).encode();
+				let future = async move {
+					if let Err(e) = write_one(&mut stream, response).await {
+						debug!(target: "sync", "Error writing warp sync response: {}", e)
+					}
+				};
+				self.responses.push(future.boxed())
+			},
+			// A response to our request has been received.
+			Event::Response(id, response) => {
+				match &self.outstanding {
+					Some((expected, from, _)) if *expected == id && *from == peer => {
+						self.outstanding = None;
+						self.on_response(peer, response)
+					},
+					// e.g. the request timed out.
+					_ => debug!(target: "sync", "Ignoring stale warp sync response {} from {}", id, peer),
+				}
+			},
+		}
+	}
+
+	fn poll(&mut self, cx: &mut Context, _: &mut impl PollParameters) -> Poll<NetworkBehaviourAction<OutboundProtocol, WarpSyncOut<B>>> {
+		// Process response sending futures.
+		while let Poll::Ready(Some(_)) = self.responses.poll_next_unpin(cx) {}
+
+		if let Some((header, result)) = &mut self.import {
+			if let Poll::Ready(result) = result.poll_unpin(cx) {
+				let event = match result {
+					Ok(Ok(())) => {
+						info!("⏩ Warp sync imported the state of block #{} ({})", header.number(), header.hash());
+						WarpSyncOut::Imported(header.clone())
+					},
+					Ok(Err(e)) => {
+						error!("❌ Warp sync failed to import the state of block #{}: {}", header.number(), e);
+						WarpSyncOut::Failed
+					},
+					Err(oneshot::Canceled) => {
+						error!("❌ Warp sync failed to import the state of block #{}", header.number());
+						WarpSyncOut::Failed
+					},
+				};
+				self.import = None;
+				self.phase = None;
+				self.outstanding = None;
+				return Poll::Ready(NetworkBehaviourAction::GenerateEvent(event))
+			}
+		}
+
+		if let Some((id, peer, sent)) = self.outstanding.clone() {
+			if Instant::now() <= sent + self.config.request_timeout {
+				return Poll::Pending
+			}
+			debug!(target: "sync", "Warp sync request {} to {} timed out", id, peer);
+			self.remove_peer(&peer, Some(ReputationChange::new(TIMEOUT_REPUTATION_CHANGE, "warp sync request timeout")));
+		}
+
+		let peer = match self.peers.iter().next() {
+			Some(peer) => peer.clone(),
+			None => return Poll::Pending,
+		};
+		if let Some(request) = self.next_request(cx) {
+			let id = self.next_request_id();
+			self.outstanding = Some((id, peer.clone(), Instant::now()));
+			let protocol = OutboundProtocol {
+				request: request.encode(),
+				request_id: id,
+				max_response_len: self.config.max_response_len,
+				protocol: self.config.protocol.clone(),
+			};
+			return Poll::Ready(NetworkBehaviourAction::SendEvent { peer_id: peer, event: protocol })
+		}
+
+		Poll::Pending
+	}
+}
+
+/// Output type of inbound and outbound substream upgrades.
+#[derive(Debug)]
+pub enum Event<T> {
+	/// Incoming request from remote and substream to use for the response.
+	Request(WarpSyncRequest, T),
+	/// Incoming response from remote.
+	Response(u64, WarpSyncResponse),
+}
+
+/// Substream upgrade protocol.
+///
+/// Reads incoming requests from remote.
+#[derive(Debug, Clone)]
+pub struct InboundProtocol {
+	/// The max. request length in bytes.
+	max_request_len: usize,
+	/// The protocol to use for upgrade negotiation.
+	protocol: Bytes,
+}
+
+impl UpgradeInfo for InboundProtocol {
+	type Info = Bytes;
+	type InfoIter = iter::Once<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(self.protocol.clone())
+	}
+}
+
+impl<T> InboundUpgrade<T> for InboundProtocol
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = Event<T>;
+	type Error = ReadOneError;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_inbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		let future = async move {
+			let vec = read_one(&mut s, self.max_request_len).await?;
+			match WarpSyncRequest::decode(&mut &vec[..]) {
+				Ok(r) => Ok(Event::Request(r, s)),
+				Err(e) => Err(ReadOneError::Io(io::Error::new(io::ErrorKind::Other, e.what())))
+			}
+		};
+		future.boxed()
+	}
+}
+
+/// Substream upgrade protocol.
+///
+/// Sends a request to remote and awaits the response.
+#[derive(Debug, Clone)]
+pub struct OutboundProtocol {
+	/// The SCALE encoded request.
+	request: Vec<u8>,
+	/// Local identifier for the request. Used to associate it with a response.
+	request_id: u64,
+	/// The max. response length in bytes.
+	max_response_len: usize,
+	/// The protocol to use for upgrade negotiation.
+	protocol: Bytes,
+}
+
+impl UpgradeInfo for OutboundProtocol {
+	type Info = Bytes;
+	type InfoIter = iter::Once<Self::Info>;
+
+	fn protocol_info(&self) -> Self::InfoIter {
+		iter::once(self.protocol.clone())
+	}
+}
+
+impl<T> OutboundUpgrade<T> for OutboundProtocol
+where
+	T: AsyncRead + AsyncWrite + Unpin + Send + 'static
+{
+	type Output = Event<T>;
+	type Error = ReadOneError;
+	type Future = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+	fn upgrade_outbound(self, mut s: T, _: Self::Info) -> Self::Future {
+		let future = async move {
+			write_one(&mut s, &self.request).await?;
+			let vec = read_one(&mut s, self.max_response_len).await?;
+			WarpSyncResponse::decode(&mut &vec[..])
+				.map(|r| Event::Response(self.request_id, r))
+				.map_err(|e| ReadOneError::Io(io::Error::new(io::ErrorKind::Other, e.what())))
+		};
+		future.boxed()
+	}
+}
@@ -25,32 +25,40 @@
 //! The methods of the [`NetworkService`] are implemented by sending a message over a channel,
 //! which is then processed by [`NetworkWorker::poll`].
 
-use std::{borrow::Cow, collections::{HashMap, HashSet}, fs, marker::PhantomData, io, path::Path, str};
+use std::{borrow::Cow, collections::{HashMap, HashSet}, fs, marker::PhantomData, io, path::{Path, PathBuf}, str};
 use std::sync::{Arc, atomic::{AtomicBool, AtomicUsize, Ordering}};
 use std::pin::Pin;
 use std::task::Poll;
+use wasm_timer::Instant;
 
 use sp_consensus::import_queue::{ImportQueue, Link};
 use sp_consensus::import_queue::{BlockImportResult, BlockImportError};
 use futures::{prelude::*, channel::mpsc};
-use log::{warn, error, info, trace};
+use log::{warn, error, info, debug, trace};
 use libp2p::{PeerId, Multiaddr, kad::record};
 use libp2p::swarm::{NetworkBehaviour, SwarmBuilder, SwarmEvent};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 use sc_peerset::PeersetHandle;
-use sp_runtime::{traits::{Block as BlockT, NumberFor}, ConsensusEngineId};
+use sp_runtime::{traits::{Block as BlockT, NumberFor, Zero}, ConsensusEngineId};
 use prometheus_endpoint::{Registry, Counter, CounterVec, Gauge, GaugeVec, Opts, U64, register, PrometheusError};
 
 use crate::{behaviour::{Behaviour, BehaviourOut}, config::{parse_str_addr, parse_addr}};
 use crate::{transport, config::NonReservedPeerMode, ReputationChange};
-use crate::config::{Params, TransportConfig};
+use crate::config::{IpPolicy, Params, SyncMode, TransportConfig};
 use crate::error::Error;
-use crate::network_state::{NetworkState, NotConnectedPeer as NetworkStateNotConnectedPeer, Peer as NetworkStatePeer};
+use crate::network_state::{
+	NetworkState, NotConnectedPeer as NetworkStateNotConnectedPeer, Peer as NetworkStatePeer,
+	PrivacyStatus,
+};
 use crate::protocol::{self, Protocol, PeerInfo};
-use crate::protocol::{event::Event, light_dispatch::{AlwaysBadChecker, RequestData}};
+use crate::protocol::{event::{AccessEvent, Event}, light_dispatch::{AlwaysBadChecker, RequestData}};
 use crate::protocol::sync::SyncState;
 
 
+/// Name of the file in the network configuration directory that the peer reputations are
+/// persisted to.
+const REPUTATIONS_FILE: &str = "reputations.json";
+
 /// Minimum Requirements for a Hash within Networking
 pub trait ExHashT: std::hash::Hash + Eq + std::fmt::Debug + Clone + Send + Sync + 'static {}
 
@@ -147,6 +155,8 @@ pub struct NetworkService<B: BlockT + 'static, H: ExHashT> {
 	/// Peerset manager (PSM); manages the reputation of nodes and indicates the network which
 	/// nodes it should be connected to or not.
 	peerset: PeersetHandle,
+	/// IP addresses allowed by the transport. Shared with the transport.
+	ip_policy: Arc<RwLock<IpPolicy>>,
 	/// Channel that sends messages to the actual worker.
 	to_worker: mpsc::UnboundedSender<ServiceToWorkerMsg<B, H>>,
 	/// Marker to pin the `H` generic. Serves no purpose except to not break backwards
@@ -166,6 +176,8 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 		if let Some(ref path) = params.network_config.net_config_path {
 			fs::create_dir_all(Path::new(path))?;
 		}
+		let reputations_path = params.network_config.net_config_path.as_ref()
+			.map(|path| path.join(REPUTATIONS_FILE));
 
 		// List of multiaddresses that we know in the network.
 		let mut known_addresses = Vec::new();
@@ -220,6 +232,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			bootnodes,
 			reserved_only: params.network_config.non_reserved_mode == NonReservedPeerMode::Deny,
 			reserved_nodes,
+			protocol_slots: params.network_config.protocol_slots.clone(),
 		};
 
 		// Private and public keys configuration.
@@ -233,8 +246,17 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			.unwrap_or(Arc::new(AlwaysBadChecker));
 
 		let num_connected = Arc::new(AtomicUsize::new(0));
+		let ip_policy = Arc::new(RwLock::new(params.network_config.ip_policy.clone()));
 		let is_major_syncing = Arc::new(AtomicBool::new(false));
-		let (protocol, peerset_handle) = Protocol::new(
+		let warp_sync = params.network_config.sync_mode == SyncMode::Warp
+			&& !params.roles.is_light()
+			&& params.chain.info().best_number.is_zero();
+		if warp_sync && params.warp_sync_provider.is_none() {
+			return Err(Error::Client(sp_blockchain::Error::Msg(
+				"Warp sync requires a warp sync provider".into()
+			)));
+		}
+		let (mut protocol, peerset_handle) = Protocol::new(
 			protocol::ProtocolConfig {
 				roles: params.roles,
 				max_parallel_downloads: params.network_config.max_parallel_downloads,
@@ -249,7 +271,13 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			params.block_announce_validator,
 			params.metrics_registry.as_ref(),
 			boot_node_ids.clone(),
+			local_peer_id.clone(),
+			params.membership_validator.clone(),
+			params.transactions_key_provider,
 		)?;
+		if warp_sync {
+			protocol.pause_for_warp_sync();
+		}
 
 		// Build the swarm.
 		let (mut swarm, bandwidth): (Swarm::<B, H>, _) = {
@@ -262,9 +290,23 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 				let config = protocol::block_requests::Config::new(&params.protocol_id);
 				protocol::BlockRequests::new(config, params.chain.clone())
 			};
+			let warp_sync = {
+				let config = protocol::warp_sync::Config::new(&params.protocol_id);
+				let mut handler = protocol::WarpSync::new(
+					config,
+					params.chain.clone(),
+					params.warp_sync_provider,
+					peerset_handle.clone(),
+				).with_membership_validator(params.membership_validator.clone());
+				if warp_sync {
+					handler.start();
+				}
+				handler
+			};
 			let light_client_handler = {
 				let config = protocol::light_client_handler::Config::new(&params.protocol_id);
 				protocol::LightClientHandler::new(config, params.chain, checker, peerset_handle.clone())
+					.with_membership_validator(params.membership_validator)
 			};
 			let behaviour = futures::executor::block_on(Behaviour::new(
 				protocol,
@@ -281,15 +323,17 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 				},
 				u64::from(params.network_config.out_peers) + 15,
 				block_requests,
-				light_client_handler
+				light_client_handler,
+				warp_sync,
 			));
 			let (transport, bandwidth) = {
+				let ip_policy = ip_policy.clone();
 				let (config_mem, config_wasm, flowctrl) = match params.network_config.transport {
 					TransportConfig::MemoryOnly => (true, None, false),
 					TransportConfig::Normal { wasm_external_transport, use_yamux_flow_control, .. } =>
 						(false, wasm_external_transport, use_yamux_flow_control)
 				};
-				transport::build_transport(local_identity, config_mem, config_wasm, flowctrl)
+				transport::build_transport(local_identity, config_mem, config_wasm, flowctrl, ip_policy)
 			};
 			let mut builder = SwarmBuilder::new(transport, behaviour, local_peer_id.clone());
 			if let Some(spawner) = params.executor {
@@ -298,6 +342,15 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			(builder.build(), bandwidth)
 		};
 
+		// Restore the peer reputations of the previous run.
+		if let Some(ref path) = reputations_path {
+			if path.exists() {
+				if let Err(err) = swarm.user_protocol_mut().import_reputations(path) {
+					warn!(target: "sub-libp2p", "Failed to restore peer reputations from {:?}: {}", path, err)
+				}
+			}
+		}
+
 		// Listen on multiaddresses.
 		for addr in &params.network_config.listen_addresses {
 			if let Err(err) = Swarm::<B, H>::listen_on(&mut swarm, addr.clone()) {
@@ -318,6 +371,7 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			num_connected: num_connected.clone(),
 			is_major_syncing: is_major_syncing.clone(),
 			peerset: peerset_handle,
+			ip_policy,
 			local_peer_id,
 			to_worker: to_worker.clone(),
 			_marker: PhantomData,
@@ -333,11 +387,13 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			from_worker,
 			light_client_rqs: params.on_demand.and_then(|od| od.extract_receiver()),
 			event_streams: Vec::new(),
+			access_event_streams: Vec::new(),
 			metrics: match params.metrics_registry {
 				Some(registry) => Some(Metrics::register(&registry)?),
 				None => None
 			},
 			boot_node_ids,
+			reputations_path,
 		})
 	}
 
@@ -391,6 +447,11 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 		self.network_service.user_protocol().num_sync_requests()
 	}
 
+	/// Returns the state of the enforcement of the membership of the private network.
+	pub fn privacy_status(&self) -> PrivacyStatus {
+		self.network_service.user_protocol().privacy_status()
+	}
+
 	/// Adds an address for a node.
 	pub fn add_known_address(&mut self, peer_id: PeerId, addr: Multiaddr) {
 		self.network_service.add_known_address(peer_id, addr);
@@ -481,6 +542,11 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 			.collect()
 	}
 
+	/// Returns the reputation of the given peer, or `None` if the peer is not known.
+	pub fn peer_reputation(&mut self, peer_id: &PeerId) -> Option<i32> {
+		self.network_service.user_protocol_mut().peer_reputation(peer_id)
+	}
+
 	/// Removes a `PeerId` from the list of reserved peers.
 	pub fn remove_reserved_peer(&self, peer: PeerId) {
 		self.service.remove_reserved_peer(peer);
@@ -491,6 +557,11 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkWorker<B, H> {
 	pub fn add_reserved_peer(&self, peer: String) -> Result<(), String> {
 		self.service.add_reserved_peer(peer)
 	}
+
+	/// Replaces the IP addresses allowed to connect to us and to be dialed.
+	pub fn set_ip_policy(&self, policy: IpPolicy) {
+		self.service.set_ip_policy(policy);
+	}
 }
 
 impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
@@ -525,6 +596,16 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		rx
 	}
 
+	/// Returns a stream of the nodes accepted, refused or disconnected, for auditing purposes.
+	///
+	/// Up to `buffer` events are kept while the stream isn't polled, the events after that are
+	/// dropped until it is.
+	pub fn access_event_stream(&self, buffer: usize) -> impl Stream<Item = AccessEvent> {
+		let (tx, rx) = mpsc::channel(buffer);
+		let _ = self.to_worker.unbounded_send(ServiceToWorkerMsg::AccessEventStream(tx));
+		rx
+	}
+
 	/// Registers a new notifications protocol.
 	///
 	/// After that, you can call `write_notifications`.
@@ -632,6 +713,18 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		self.peerset.remove_reserved_peer(peer);
 	}
 
+	/// Replaces the IP addresses allowed to connect to us and to be dialed.
+	///
+	/// The new policy only applies to new connections. Existing connections are kept open.
+	pub fn set_ip_policy(&self, policy: IpPolicy) {
+		*self.ip_policy.write() = policy;
+	}
+
+	/// Returns the IP addresses allowed to connect to us and to be dialed.
+	pub fn ip_policy(&self) -> IpPolicy {
+		self.ip_policy.read().clone()
+	}
+
 	/// Adds a `PeerId` and its address as reserved. The string should encode the address
 	/// and peer ID of the remote node.
 	pub fn add_reserved_peer(&self, peer: String) -> Result<(), String> {
@@ -745,6 +838,7 @@ enum ServiceToWorkerMsg<B: BlockT, H: ExHashT> {
 	AddKnownAddress(PeerId, Multiaddr),
 	SyncFork(Vec<PeerId>, B::Hash, NumberFor<B>),
 	EventStream(mpsc::UnboundedSender<Event>),
+	AccessEventStream(mpsc::Sender<AccessEvent>),
 	WriteNotification {
 		message: Vec<u8>,
 		engine_id: ConsensusEngineId,
@@ -780,10 +874,14 @@ pub struct NetworkWorker<B: BlockT + 'static, H: ExHashT> {
 	light_client_rqs: Option<mpsc::UnboundedReceiver<RequestData<B>>>,
 	/// Senders for events that happen on the network.
 	event_streams: Vec<mpsc::UnboundedSender<Event>>,
+	/// Bounded senders for the access events of the network.
+	access_event_streams: Vec<mpsc::Sender<AccessEvent>>,
 	/// Prometheus network metrics.
 	metrics: Option<Metrics>,
 	/// The `PeerId`'s of all boot nodes.
 	boot_node_ids: Arc<HashSet<PeerId>>,
+	/// File that the peer reputations are persisted to on shutdown.
+	reputations_path: Option<PathBuf>,
 }
 
 struct Metrics {
@@ -801,7 +899,12 @@ struct Metrics {
 	peers_count: Gauge<U64>,
 	peerset_num_discovered: Gauge<U64>,
 	peerset_num_requested: Gauge<U64>,
+	privacy_membership_staleness: Gauge<U64>,
+	privacy_refused_connections_total: Counter<U64>,
+	privacy_reserved_peers: Gauge<U64>,
 	random_kademalia_queries_total: Counter<U64>,
+	/// When the metrics were registered. Members that were never retrieved are stale since then.
+	registered: Instant,
 }
 
 impl Metrics {
@@ -863,9 +966,22 @@ impl Metrics {
 			peerset_num_requested: register(Gauge::new(
 				"sub_libp2p_peerset_num_requested", "Number of nodes that the peerset manager wants us to be connected to",
 			)?, registry)?,
+			privacy_membership_staleness: register(Gauge::new(
+				"sub_libp2p_privacy_membership_staleness_seconds",
+				"Number of seconds since the members of the private network were last retrieved, \
+				or since the node started if they never were",
+			)?, registry)?,
+			privacy_refused_connections_total: register(Counter::new(
+				"sub_libp2p_privacy_refused_connections_total",
+				"Number of peers disconnected because they are not members of the private network",
+			)?, registry)?,
+			privacy_reserved_peers: register(Gauge::new(
+				"sub_libp2p_privacy_reserved_peers", "Number of reserved peers",
+			)?, registry)?,
 			random_kademalia_queries_total: register(Counter::new(
 				"sub_libp2p_random_kademalia_queries_total", "Number of random Kademlia queries started",
 			)?, registry)?,
+			registered: Instant::now(),
 		})
 	}
 
@@ -932,6 +1048,8 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 					this.network_service.user_protocol_mut().set_sync_fork_request(peer_ids, &hash, number),
 				ServiceToWorkerMsg::EventStream(sender) =>
 					this.event_streams.push(sender),
+				ServiceToWorkerMsg::AccessEventStream(sender) =>
+					this.access_event_streams.push(sender),
 				ServiceToWorkerMsg::WriteNotification { message, engine_id, target } => {
 					if let Some(metrics) = this.metrics.as_ref() {
 						metrics.notifications_total.with_label_values(&["out", &engine_id_to_string(&engine_id)]).inc();
@@ -981,6 +1099,16 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 						metrics.random_kademalia_queries_total.inc();
 					}
 				},
+				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::Access(ev))) => {
+					for sender in this.access_event_streams.iter_mut() {
+						if let Err(err) = sender.try_send(ev.clone()) {
+							if err.is_full() {
+								debug!(target: "sub-libp2p", "Access event stream is full, dropping {:?}", ev);
+							}
+						}
+					}
+					this.access_event_streams.retain(|sender| !sender.is_closed());
+				},
 				Poll::Ready(SwarmEvent::Behaviour(BehaviourOut::Event(ev))) => {
 					this.event_streams.retain(|sender| sender.unbounded_send(ev.clone()).is_ok());
 					if let Some(metrics) = this.metrics.as_ref() {
@@ -1056,6 +1184,19 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 			metrics.peers_count.set(num_connected_peers as u64);
 			metrics.peerset_num_discovered.set(this.network_service.user_protocol().num_discovered_peers() as u64);
 			metrics.peerset_num_requested.set(this.network_service.user_protocol().requested_peers().count() as u64);
+
+			let privacy = this.network_service.user_protocol().privacy_status();
+			let staleness = match privacy.membership {
+				Some(Some(status)) if status.enforced => Some(status.since_last_sync
+					.unwrap_or_else(|| metrics.registered.elapsed())),
+				_ => None,
+			};
+			metrics.privacy_membership_staleness.set(staleness.map_or(0, |since| since.as_secs()));
+			// The protocol counts the refused connections, the counter catches up with it.
+			let refused = privacy.refused_connections
+				.saturating_sub(metrics.privacy_refused_connections_total.get());
+			metrics.privacy_refused_connections_total.inc_by(refused);
+			metrics.privacy_reserved_peers.set(privacy.reserved_peers as u64);
 		}
 
 		Poll::Pending
@@ -1065,6 +1206,16 @@ impl<B: BlockT + 'static, H: ExHashT> Future for NetworkWorker<B, H> {
 impl<B: BlockT + 'static, H: ExHashT> Unpin for NetworkWorker<B, H> {
 }
 
+impl<B: BlockT + 'static, H: ExHashT> Drop for NetworkWorker<B, H> {
+	fn drop(&mut self) {
+		if let Some(ref path) = self.reputations_path {
+			if let Err(err) = self.network_service.user_protocol_mut().export_reputations(path) {
+				warn!(target: "sub-libp2p", "Failed to persist peer reputations to {:?}: {}", path, err)
+			}
+		}
+	}
+}
+
 /// Turns a `ConsensusEngineId` into a representable string.
 fn engine_id_to_string(id: &ConsensusEngineId) -> Cow<str> {
 	if let Ok(s) = std::str::from_utf8(&id[..]) {
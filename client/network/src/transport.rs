@@ -22,6 +22,8 @@ use libp2p::{
 #[cfg(not(target_os = "unknown"))]
 use libp2p::{tcp, dns, websocket};
 use libp2p::core::{self, upgrade, transport::boxed::Boxed, transport::OptionalTransport, muxing::StreamMuxerBox};
+use crate::ip_policy::IpPolicy;
+use parking_lot::RwLock;
 use std::{io, sync::Arc, time::Duration, usize};
 
 pub use self::bandwidth::BandwidthSinks;
@@ -31,13 +33,17 @@ pub use self::bandwidth::BandwidthSinks;
 /// If `memory_only` is true, then only communication within the same process are allowed. Only
 /// addresses with the format `/memory/...` are allowed.
 ///
+/// TCP connections with addresses refused by `ip_policy` are closed right after being
+/// established. DNS addresses are resolved before, so the resolved addresses are checked.
+///
 /// Returns a `BandwidthSinks` object that allows querying the average bandwidth produced by all
 /// the connections spawned with this transport.
 pub fn build_transport(
 	keypair: identity::Keypair,
 	memory_only: bool,
 	wasm_external_transport: Option<wasm_ext::ExtTransport>,
-	use_yamux_flow_control: bool
+	use_yamux_flow_control: bool,
+	ip_policy: Arc<RwLock<IpPolicy>>,
 ) -> (Boxed<(PeerId, StreamMuxerBox), io::Error>, Arc<bandwidth::BandwidthSinks>) {
 	// Build configuration objects for encryption mechanisms.
 	let noise_config = {
@@ -73,7 +79,18 @@ pub fn build_transport(
 	};
 	#[cfg(not(target_os = "unknown"))]
 	let transport = transport.or_transport(if !memory_only {
-		let desktop_trans = tcp::TcpConfig::new();
+		// IP filtering, before anything is sent to or read from the remote. It wraps the TCP
+		// transport, below DNS and WebSocket, so it checks the resolved addresses.
+		let desktop_trans = tcp::TcpConfig::new().and_then(move |stream, endpoint| {
+			let allowed = ip_policy.read().is_address_allowed(endpoint.get_remote_address());
+			async move {
+				if allowed {
+					Ok(stream)
+				} else {
+					Err(io::Error::new(io::ErrorKind::PermissionDenied, "Address refused by the IP policy"))
+				}
+			}
+		});
 		let desktop_trans = websocket::WsConfig::new(desktop_trans.clone())
 			.or_transport(desktop_trans);
 		OptionalTransport::some(if let Ok(dns) = dns::DnsConfig::new(desktop_trans.clone()) {
@@ -85,6 +102,9 @@ pub fn build_transport(
 		OptionalTransport::none()
 	});
 
+	#[cfg(target_os = "unknown")]
+	let _ = ip_policy;
+
 	let transport = transport.or_transport(if memory_only {
 		OptionalTransport::some(libp2p::core::transport::MemoryTransport::default())
 	} else {
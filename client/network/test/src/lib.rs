@@ -618,6 +618,9 @@ pub trait TestNetFactory: Sized {
 			import_queue,
 			block_announce_validator: Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
 			metrics_registry: None,
+			membership_validator: None,
+			transactions_key_provider: None,
+			warp_sync_provider: None,
 		}).unwrap();
 
 		self.mut_peers(|peers| {
@@ -692,6 +695,9 @@ pub trait TestNetFactory: Sized {
 			import_queue,
 			block_announce_validator: Box::new(DefaultBlockAnnounceValidator::new(client.clone())),
 			metrics_registry: None,
+			membership_validator: None,
+			transactions_key_provider: None,
+			warp_sync_provider: None,
 		}).unwrap();
 
 		self.mut_peers(|peers| {
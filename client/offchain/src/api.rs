@@ -17,8 +17,10 @@
 use std::{
 	str::FromStr,
 	sync::Arc,
-	convert::TryFrom,
+	convert::{TryFrom, TryInto},
+	path::PathBuf,
 	thread::sleep,
+	time::Duration,
 };
 
 use sp_core::offchain::OffchainStorage;
@@ -27,7 +29,7 @@ use log::error;
 use sc_network::{PeerId, Multiaddr, NetworkStateInfo};
 use codec::{Encode, Decode};
 use sp_core::offchain::{
-	Externalities as OffchainExt, HttpRequestId, Timestamp, HttpRequestStatus, HttpError,
+	self, Externalities as OffchainExt, HttpRequestId, Timestamp, HttpRequestStatus, HttpError,
 	OpaqueNetworkState, OpaquePeerId, OpaqueMultiaddr, StorageKind,
 };
 pub use sp_offchain::STORAGE_PREFIX;
@@ -40,8 +42,24 @@ use http_dummy as http;
 #[cfg(target_os = "unknown")]
 mod http_dummy;
 
+mod ipfs;
 mod timestamp;
 
+pub(crate) use http::{client_tls, ClientTls};
+pub use ipfs::IpfsConfig;
+
+/// TLS settings of the HTTP requests of offchain workers, e.g. to call APIs requiring mutual
+/// TLS authentication.
+#[derive(Debug, Clone, Default)]
+pub struct HttpTlsConfig {
+	/// PEM files of the certificate chain presented to servers requesting a client certificate
+	/// and of its PKCS#8 or RSA private key.
+	pub client_certificate: Option<(PathBuf, PathBuf)>,
+	/// PEM files of the CA certificates servers must be signed with. If empty, servers signed by
+	/// any CA trusted by the system are accepted.
+	pub ca_certificates: Vec<PathBuf>,
+}
+
 /// Asynchronous offchain API.
 ///
 /// NOTE this is done to prevent recursive calls into the runtime (which are not supported currently).
@@ -54,6 +72,40 @@ pub(crate) struct Api<Storage> {
 	is_validator: bool,
 	/// Everything HTTP-related is handled by a different struct.
 	http: http::HttpApi,
+	/// The IPFS node the content is fetched and pinned with, over HTTP.
+	ipfs: IpfsConfig,
+	/// The time after which the run of the worker is aborted.
+	deadline: Option<Timestamp>,
+}
+
+impl<Storage> Api<Storage> {
+	/// Abort the run of the worker at its first host call once the given time passed, and cut
+	/// the waits of its calls short at that deadline.
+	pub fn with_deadline(mut self, deadline: Option<Duration>) -> Self {
+		self.deadline = deadline.map(|deadline| timestamp::now().add(
+			offchain::Duration::from_millis(deadline.as_millis().try_into().unwrap_or(u64::max_value()))
+		));
+		self
+	}
+
+	/// Aborts the run of the worker if its deadline is reached.
+	///
+	/// The panic unwinds out of the runtime call, which fails with `RuntimePanicked`.
+	fn check_deadline(&self) {
+		if let Some(deadline) = self.deadline {
+			if timestamp::now() >= deadline {
+				panic!("Offchain worker missed its deadline of {:?}", deadline);
+			}
+		}
+	}
+
+	/// The earlier of the given deadline of a call and the deadline of the worker.
+	fn call_deadline(&self, deadline: Option<Timestamp>) -> Option<Timestamp> {
+		match (deadline, self.deadline) {
+			(Some(call), Some(worker)) => Some(call.min(worker)),
+			(call, worker) => call.or(worker),
+		}
+	}
 }
 
 fn unavailable_yet<R: Default>(name: &str) -> R {
@@ -68,10 +120,12 @@ const LOCAL_DB: &str = "LOCAL (fork-aware) DB";
 
 impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	fn is_validator(&self) -> bool {
+		self.check_deadline();
 		self.is_validator
 	}
 
 	fn network_state(&self) -> Result<OpaqueNetworkState, ()> {
+		self.check_deadline();
 		let external_addresses = self.network_state.external_addresses();
 
 		let state = NetworkState::new(
@@ -82,18 +136,24 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	}
 
 	fn timestamp(&mut self) -> Timestamp {
+		self.check_deadline();
 		timestamp::now()
 	}
 
 	fn sleep_until(&mut self, deadline: Timestamp) {
+		self.check_deadline();
+		let deadline = self.call_deadline(Some(deadline)).unwrap_or(deadline);
 		sleep(timestamp::timestamp_from_now(deadline));
+		self.check_deadline();
 	}
 
 	fn random_seed(&mut self) -> [u8; 32] {
+		self.check_deadline();
 		rand::random()
 	}
 
 	fn local_storage_set(&mut self, kind: StorageKind, key: &[u8], value: &[u8]) {
+		self.check_deadline();
 		match kind {
 			StorageKind::PERSISTENT => self.db.set(STORAGE_PREFIX, key, value),
 			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
@@ -107,6 +167,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		old_value: Option<&[u8]>,
 		new_value: &[u8],
 	) -> bool {
+		self.check_deadline();
 		match kind {
 			StorageKind::PERSISTENT => {
 				self.db.compare_and_set(STORAGE_PREFIX, key, old_value, new_value)
@@ -116,6 +177,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 	}
 
 	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
+		self.check_deadline();
 		match kind {
 			StorageKind::PERSISTENT => self.db.get(STORAGE_PREFIX, key),
 			StorageKind::LOCAL => unavailable_yet(LOCAL_DB),
@@ -128,6 +190,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		uri: &str,
 		_meta: &[u8]
 	) -> Result<HttpRequestId, ()> {
+		self.check_deadline();
 		self.http.request_start(method, uri)
 	}
 
@@ -137,6 +200,7 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		name: &str,
 		value: &str
 	) -> Result<(), ()> {
+		self.check_deadline();
 		self.http.request_add_header(request_id, name, value)
 	}
 
@@ -146,7 +210,8 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		chunk: &[u8],
 		deadline: Option<Timestamp>
 	) -> Result<(), HttpError> {
-		self.http.request_write_body(request_id, chunk, deadline)
+		self.check_deadline();
+		self.http.request_write_body(request_id, chunk, self.call_deadline(deadline))
 	}
 
 	fn http_response_wait(
@@ -154,13 +219,15 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		ids: &[HttpRequestId],
 		deadline: Option<Timestamp>
 	) -> Vec<HttpRequestStatus> {
-		self.http.response_wait(ids, deadline)
+		self.check_deadline();
+		self.http.response_wait(ids, self.call_deadline(deadline))
 	}
 
 	fn http_response_headers(
 		&mut self,
 		request_id: HttpRequestId
 	) -> Vec<(Vec<u8>, Vec<u8>)> {
+		self.check_deadline();
 		self.http.response_headers(request_id)
 	}
 
@@ -170,7 +237,20 @@ impl<Storage: OffchainStorage> OffchainExt for Api<Storage> {
 		buffer: &mut [u8],
 		deadline: Option<Timestamp>
 	) -> Result<usize, HttpError> {
-		self.http.response_read_body(request_id, buffer, deadline)
+		self.check_deadline();
+		self.http.response_read_body(request_id, buffer, self.call_deadline(deadline))
+	}
+
+	fn ipfs_get(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		self.check_deadline();
+		let deadline = self.call_deadline(deadline);
+		ipfs::get(&mut self.http, &self.ipfs, cid, deadline)
+	}
+
+	fn ipfs_pin(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		self.check_deadline();
+		let deadline = self.call_deadline(deadline);
+		ipfs::pin(&mut self.http, &self.ipfs, cid, deadline)
 	}
 }
 
@@ -253,14 +333,18 @@ impl AsyncApi {
 		db: S,
 		network_state: Arc<dyn NetworkStateInfo + Send + Sync>,
 		is_validator: bool,
+		http_tls: ClientTls,
+		ipfs: IpfsConfig,
 	) -> (Api<S>, AsyncApi) {
-		let (http_api, http_worker) = http::http();
+		let (http_api, http_worker) = http::http(http_tls);
 
 		let api = Api {
 			db,
 			network_state,
 			is_validator,
 			http: http_api,
+			ipfs,
+			deadline: None,
 		};
 
 		let async_api = AsyncApi {
@@ -306,6 +390,8 @@ mod tests {
 			db,
 			mock,
 			false,
+			Default::default(),
+			Default::default(),
 		)
 	}
 
@@ -343,6 +429,21 @@ mod tests {
 		assert!(new_now.unix_millis() - 100 >= now.unix_millis());
 	}
 
+	#[test]
+	fn should_abort_worker_after_its_deadline() {
+		let mut api = offchain_api().0;
+		let now = api.timestamp();
+		let mut api = api.with_deadline(Some(Duration::from_millis(100)));
+
+		// the sleep is cut short at the deadline of the worker, which is aborted.
+		let aborted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+			api.sleep_until(now.add(sp_core::offchain::Duration::from_millis(60_000)));
+		}));
+		assert!(aborted.is_err());
+		assert!(timestamp::now() < now.add(sp_core::offchain::Duration::from_millis(60_000)));
+		assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| api.timestamp())).is_err());
+	}
+
 	#[test]
 	fn should_set_and_get_local_storage() {
 		// given
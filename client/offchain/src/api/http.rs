@@ -25,16 +25,71 @@
 //! (i.e.: the socket should continue being processed) in the background even if the runtime isn't
 //! actively calling any function.
 
-use crate::api::timestamp;
+use crate::api::{timestamp, HttpTlsConfig};
 use bytes::buf::ext::{Reader, BufExt};
 use fnv::FnvHashMap;
 use futures::{prelude::*, future, channel::mpsc};
 use log::error;
+use rustls::internal::pemfile;
 use sp_core::offchain::{HttpRequestId, Timestamp, HttpRequestStatus, HttpError};
-use std::{fmt, io::Read as _, mem, pin::Pin, task::Context, task::Poll};
+use std::{
+	fmt, fs::File, io::{BufReader, Read as _}, mem, path::Path, pin::Pin, sync::Arc,
+	task::Context, task::Poll,
+};
 
-/// Creates a pair of [`HttpApi`] and [`HttpWorker`].
-pub fn http() -> (HttpApi, HttpWorker) {
+/// The TLS configuration of the HTTP clients, `None` to use the default configuration.
+pub type ClientTls = Option<Arc<rustls::ClientConfig>>;
+
+/// Load the client certificate and the pinned CA certificates of the given configuration.
+///
+/// Returns `None` if the configuration is empty.
+pub fn client_tls(config: &HttpTlsConfig) -> Result<ClientTls, String> {
+	fn open(path: &Path) -> Result<BufReader<File>, String> {
+		File::open(path)
+			.map(BufReader::new)
+			.map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+	}
+
+	if config.client_certificate.is_none() && config.ca_certificates.is_empty() {
+		return Ok(None);
+	}
+
+	let mut tls = rustls::ClientConfig::new();
+	tls.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+
+	if config.ca_certificates.is_empty() {
+		tls.root_store = rustls_native_certs::load_native_certs()
+			.map_err(|(_, e)| format!("Failed to load the system CA certificates: {}", e))?;
+	} else {
+		// only servers signed by the pinned CAs are trusted.
+		for path in &config.ca_certificates {
+			match tls.root_store.add_pem_file(&mut open(path)?) {
+				Ok((valid, _)) if valid > 0 => {},
+				_ => return Err(format!("No valid CA certificate in {}", path.display())),
+			}
+		}
+	}
+
+	if let Some((certificate, key)) = &config.client_certificate {
+		let chain = pemfile::certs(&mut open(certificate)?)
+			.ok()
+			.filter(|chain| !chain.is_empty())
+			.ok_or_else(|| format!("No valid certificate in {}", certificate.display()))?;
+		let pkcs8_keys = pemfile::pkcs8_private_keys(&mut open(key)?).unwrap_or_default();
+		let rsa_keys = pemfile::rsa_private_keys(&mut open(key)?).unwrap_or_default();
+		let key = pkcs8_keys.into_iter().chain(rsa_keys).next()
+			.ok_or_else(|| format!("No valid PKCS#8 or RSA private key in {}", key.display()))?;
+
+		tls.set_single_client_cert(chain, key)
+			.map_err(|e| format!("Invalid client certificate {}: {}", certificate.display(), e))?;
+	}
+
+	Ok(Some(Arc::new(tls)))
+}
+
+/// Creates a pair of [`HttpApi`] and [`HttpWorker`], the worker using the given TLS
+/// configuration for HTTPS requests.
+pub fn http(tls: ClientTls) -> (HttpApi, HttpWorker) {
 	let (to_worker, from_api) = mpsc::unbounded();
 	let (to_api, from_worker) = mpsc::unbounded();
 
@@ -50,13 +105,25 @@ pub fn http() -> (HttpApi, HttpWorker) {
 	let engine = HttpWorker {
 		to_api,
 		from_api,
-		http_client: hyper::Client::builder().build(hyper_rustls::HttpsConnector::new()),
+		http_client: hyper::Client::builder().build(https_connector(tls)),
 		requests: Vec::new(),
 	};
 
 	(api, engine)
 }
 
+/// Build the HTTPS connector of a worker, the default one if no TLS configuration is given.
+fn https_connector(tls: ClientTls) -> hyper_rustls::HttpsConnector<hyper::client::HttpConnector> {
+	match tls {
+		Some(tls) => {
+			let mut http = hyper::client::HttpConnector::new();
+			http.enforce_http(false);
+			(http, tls).into()
+		},
+		None => hyper_rustls::HttpsConnector::new(),
+	}
+}
+
 /// Provides HTTP capabilities.
 ///
 /// Since this struct is a helper for offchain workers, its API is mimicking the API provided
@@ -687,8 +754,8 @@ impl fmt::Debug for HttpWorkerRequest {
 #[cfg(test)]
 mod tests {
 	use core::convert::Infallible;
-	use crate::api::timestamp;
-	use super::http;
+	use crate::api::{timestamp, HttpTlsConfig};
+	use super::{client_tls, http};
 	use sp_core::offchain::{HttpError, HttpRequestId, HttpRequestStatus, Duration};
 
 	// Returns an `HttpApi` whose worker is ran in the background, and a `SocketAddr` to an HTTP
@@ -705,7 +772,7 @@ mod tests {
 			// not be enough).
 			fdlimit::raise_fd_limit();
 
-			let (api, worker) = http();
+			let (api, worker) = http(None);
 			std::thread::spawn(move || tokio_run(worker));
 
 			let (addr_tx, addr_rx) = std::sync::mpsc::channel();
@@ -978,4 +1045,15 @@ mod tests {
 			}
 		}
 	}
+
+	#[test]
+	fn client_tls_is_loaded_only_when_configured() {
+		assert!(client_tls(&HttpTlsConfig::default()).unwrap().is_none());
+
+		let config = HttpTlsConfig {
+			client_certificate: None,
+			ca_certificates: vec!["/nonexistent/ca.pem".into()],
+		};
+		assert!(client_tls(&config).is_err());
+	}
 }
@@ -16,11 +16,20 @@
 
 //! Contains the same API as the `http` module, except that everything returns an error.
 
+use crate::api::HttpTlsConfig;
 use sp_core::offchain::{HttpRequestId, Timestamp, HttpRequestStatus, HttpError};
 use std::{future::Future, pin::Pin, task::Context, task::Poll};
 
+/// Dummy TLS configuration of the HTTP clients.
+pub type ClientTls = ();
+
+/// Ignores the TLS configuration, as there are no HTTP requests.
+pub fn client_tls(_: &HttpTlsConfig) -> Result<ClientTls, String> {
+	Ok(())
+}
+
 /// Creates a pair of [`HttpApi`] and [`HttpWorker`].
-pub fn http() -> (HttpApi, HttpWorker) {
+pub fn http(_: ClientTls) -> (HttpApi, HttpWorker) {
 	(HttpApi, HttpWorker)
 }
 
@@ -0,0 +1,154 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fetching and pinning of IPFS content by offchain workers, through the HTTP gateway and API of
+//! an IPFS node.
+
+use sp_core::offchain::{HttpError, HttpRequestStatus, Timestamp};
+
+use super::http::HttpApi;
+
+/// The maximum size of the content fetched from IPFS.
+const MAX_CONTENT_SIZE: usize = 16 * 1024 * 1024;
+
+/// The IPFS node offchain workers fetch and pin content with.
+#[derive(Debug, Clone, Default)]
+pub struct IpfsConfig {
+	/// URL of the HTTP gateway serving the content under `/ipfs/<CID>`, e.g.
+	/// `http://127.0.0.1:8080`.
+	pub gateway: Option<String>,
+	/// URL of the HTTP API of the IPFS node pinning the content, e.g. `http://127.0.0.1:5001`.
+	pub api: Option<String>,
+}
+
+/// Fetch the content of the given CID from the gateway.
+pub fn get(
+	http: &mut HttpApi,
+	config: &IpfsConfig,
+	cid: &str,
+	deadline: Option<Timestamp>,
+) -> Result<Vec<u8>, HttpError> {
+	let gateway = config.gateway.as_ref().ok_or(HttpError::IoError)?;
+	let uri = format!("{}/ipfs/{}", gateway.trim_end_matches('/'), checked_cid(cid)?);
+	request(http, "GET", &uri, deadline)
+}
+
+/// Pin the content of the given CID on the IPFS node.
+pub fn pin(
+	http: &mut HttpApi,
+	config: &IpfsConfig,
+	cid: &str,
+	deadline: Option<Timestamp>,
+) -> Result<(), HttpError> {
+	let api = config.api.as_ref().ok_or(HttpError::IoError)?;
+	let uri = format!("{}/api/v0/pin/add?arg={}", api.trim_end_matches('/'), checked_cid(cid)?);
+	request(http, "POST", &uri, deadline).map(|_| ())
+}
+
+/// CIDs are base encoded, rejecting anything else keeps them from altering the URLs.
+fn checked_cid(cid: &str) -> Result<&str, HttpError> {
+	if !cid.is_empty() && cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+		Ok(cid)
+	} else {
+		Err(HttpError::Invalid)
+	}
+}
+
+/// Send a request with an empty body, returning the body of its successful response.
+fn request(
+	http: &mut HttpApi,
+	method: &str,
+	uri: &str,
+	deadline: Option<Timestamp>,
+) -> Result<Vec<u8>, HttpError> {
+	let id = http.request_start(method, uri).map_err(|_| HttpError::Invalid)?;
+	let status = match http.response_wait(&[id], deadline)[0] {
+		HttpRequestStatus::Finished(status) => status,
+		HttpRequestStatus::DeadlineReached => return Err(HttpError::DeadlineReached),
+		HttpRequestStatus::IoError | HttpRequestStatus::Invalid => return Err(HttpError::IoError),
+	};
+
+	// the body is read even if the request failed, releasing the request
+	let mut body = Vec::new();
+	let mut buffer = [0; 4096];
+	loop {
+		let read = http.response_read_body(id, &mut buffer, deadline)?;
+		if read == 0 {
+			break;
+		}
+		if body.len() + read > MAX_CONTENT_SIZE {
+			return Err(HttpError::IoError);
+		}
+		body.extend_from_slice(&buffer[..read]);
+	}
+
+	if status == 200 {
+		Ok(body)
+	} else {
+		Err(HttpError::IoError)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use core::convert::Infallible;
+	use sp_core::offchain::Duration;
+	use crate::api::{http::http, timestamp};
+	use super::*;
+
+	/// A gateway and API serving the method and path of the requests, and failing for `missing`.
+	fn ipfs_server() -> IpfsConfig {
+		fn tokio_run<T>(future: impl std::future::Future<Output = T>) {
+			let _ = tokio::runtime::Runtime::new().unwrap().block_on(future);
+		}
+
+		let (addr_tx, addr_rx) = std::sync::mpsc::channel();
+		std::thread::spawn(move || {
+			tokio_run(async move {
+				let server = hyper::Server::bind(&"127.0.0.1:0".parse().unwrap())
+					.serve(hyper::service::make_service_fn(|_| { async move {
+						Ok::<_, Infallible>(hyper::service::service_fn(move |req: hyper::Request<hyper::Body>| async move {
+							let served = format!("{} {}", req.method(), req.uri());
+							let mut response = hyper::Response::new(hyper::Body::from(served));
+							if req.uri().path().ends_with("missing") {
+								*response.status_mut() = hyper::StatusCode::NOT_FOUND;
+							}
+							Ok::<_, Infallible>(response)
+						}))
+					}}));
+				let _ = addr_tx.send(server.local_addr());
+				server.await
+			});
+		});
+
+		let url = format!("http://{}/", addr_rx.recv().unwrap());
+		IpfsConfig { gateway: Some(url.clone()), api: Some(url) }
+	}
+
+	#[test]
+	fn content_is_fetched_and_pinned() {
+		let deadline = timestamp::now().add(Duration::from_millis(10_000));
+		let (mut api, worker) = http(None);
+		std::thread::spawn(move || tokio::runtime::Runtime::new().unwrap().block_on(worker));
+		let config = ipfs_server();
+
+		assert_eq!(get(&mut api, &config, "QmHash", Some(deadline)), Ok(b"GET /ipfs/QmHash".to_vec()));
+		assert_eq!(get(&mut api, &config, "missing", Some(deadline)), Err(HttpError::IoError));
+		assert_eq!(get(&mut api, &config, "../api", Some(deadline)), Err(HttpError::Invalid));
+		assert_eq!(pin(&mut api, &config, "QmHash", Some(deadline)), Ok(()));
+		assert_eq!(pin(&mut api, &IpfsConfig::default(), "QmHash", Some(deadline)), Err(HttpError::IoError));
+	}
+}
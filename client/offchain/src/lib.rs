@@ -30,22 +30,29 @@
 //! 1. Challenge period for incorrect computations
 //! 2. Majority voting for results
 //! 3. etc
+//!
+//! Runtimes can list their workers, e.g. one per module, to have them started separately and
+//! in parallel, each with its own concurrency limit and deadline, see the [`scheduler`].
+//! `construct_runtime!` lists the modules having an offchain worker as `AllOffchainWorkers`.
 
 #![warn(missing_docs)]
 
-use std::{fmt, marker::PhantomData, sync::Arc};
+use std::{fmt, marker::PhantomData, sync::Arc, time::Instant};
 
 use parking_lot::Mutex;
 use threadpool::ThreadPool;
 use sp_api::{ApiExt, ProvideRuntimeApi};
-use futures::future::Future;
+use futures::{future::{self, Future}, FutureExt};
 use log::{debug, warn};
 use sc_network::NetworkStateInfo;
 use sp_core::{offchain::{self, OffchainStorage}, ExecutionContext};
 use sp_runtime::{generic::BlockId, traits::{self, Header}};
 
 mod api;
+pub mod scheduler;
 
+pub use api::{HttpTlsConfig, IpfsConfig};
+pub use scheduler::{SchedulingConfig, WorkerLimits};
 pub use sp_offchain::{OffchainWorkerApi, STORAGE_PREFIX};
 
 /// An offchain workers manager.
@@ -54,6 +61,9 @@ pub struct OffchainWorkers<Client, Storage, Block: traits::Block> {
 	db: Storage,
 	_block: PhantomData<Block>,
 	thread_pool: Mutex<ThreadPool>,
+	http_tls: api::ClientTls,
+	ipfs: IpfsConfig,
+	scheduler: scheduler::Scheduler,
 }
 
 impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Block> {
@@ -64,8 +74,31 @@ impl<Client, Storage, Block: traits::Block> OffchainWorkers<Client, Storage, Blo
 			db,
 			_block: PhantomData,
 			thread_pool: Mutex::new(ThreadPool::new(num_cpus::get())),
+			http_tls: Default::default(),
+			ipfs: Default::default(),
+			scheduler: Default::default(),
 		}
 	}
+
+	/// Schedule the workers of runtimes starting them separately with the given config.
+	pub fn with_scheduling(mut self, config: SchedulingConfig) -> Self {
+		self.scheduler = scheduler::Scheduler::new(config);
+		self
+	}
+
+	/// Use the given TLS settings for the HTTP requests of the offchain workers.
+	///
+	/// Fails if the certificates or the key of the settings can't be loaded.
+	pub fn with_http_tls(mut self, config: &HttpTlsConfig) -> Result<Self, String> {
+		self.http_tls = api::client_tls(config)?;
+		Ok(self)
+	}
+
+	/// Fetch and pin the IPFS content requested by the offchain workers with the given IPFS node.
+	pub fn with_ipfs(mut self, config: IpfsConfig) -> Self {
+		self.ipfs = config;
+		self
+	}
 }
 
 impl<Client, Storage, Block: traits::Block> fmt::Debug for OffchainWorkers<
@@ -96,17 +129,22 @@ impl<Client, Storage, Block> OffchainWorkers<
 		network_state: Arc<dyn NetworkStateInfo + Send + Sync>,
 		is_validator: bool,
 	) -> impl Future<Output = ()> {
+		let _context = sp_tracing::log_context::enter_block(header.number(), header.hash());
 		let runtime = self.client.runtime_api();
 		let at = BlockId::hash(header.hash());
 		let has_api_v1 = runtime.has_api_with::<dyn OffchainWorkerApi<Block, Error = ()>, _>(
 			&at, |v| v == 1
 		);
 		let has_api_v2 = runtime.has_api_with::<dyn OffchainWorkerApi<Block, Error = ()>, _>(
-			&at, |v| v == 2
+			&at, |v| v >= 2
 		);
-		let version = match (has_api_v1, has_api_v2) {
-			(_, Ok(true)) => 2,
-			(Ok(true), _) => 1,
+		let has_api_v3 = runtime.has_api_with::<dyn OffchainWorkerApi<Block, Error = ()>, _>(
+			&at, |v| v >= 3
+		);
+		let version = match (has_api_v1, has_api_v2, has_api_v3) {
+			(_, _, Ok(true)) => 3,
+			(_, Ok(true), _) => 2,
+			(Ok(true), _, _) => 1,
 			err => {
 				let help = "Consider turning off offchain workers if they are not part of your runtime.";
 				log::error!("Unsupported Offchain Worker API version: {:?}. {}.", err, help);
@@ -114,23 +152,35 @@ impl<Client, Storage, Block> OffchainWorkers<
 			}
 		};
 		debug!("Checking offchain workers at {:?}: version:{}", at, version);
+		if version == 3 {
+			let workers = runtime.offchain_workers(&at).unwrap_or_else(|e| {
+				debug!("Unable to list the offchain workers at {:?}, starting them at once: {:?}", at, e);
+				Vec::new()
+			});
+			if !workers.is_empty() {
+				return self.spawn_scheduled_workers(header, workers, network_state, is_validator);
+			}
+		}
 		if version > 0 {
 			let (api, runner) = api::AsyncApi::new(
 				self.db.clone(),
 				network_state.clone(),
 				is_validator,
+				self.http_tls.clone(),
+				self.ipfs.clone(),
 			);
 			debug!("Spawning offchain workers at {:?}", at);
 			let header = header.clone();
 			let client = self.client.clone();
 			self.spawn_worker(move || {
+				let _context = sp_tracing::log_context::enter_block(header.number(), header.hash());
 				let runtime = client.runtime_api();
 				let api = Box::new(api);
 				debug!("Running offchain workers at {:?}", at);
 				let context = ExecutionContext::OffchainCall(Some(
 					(api, offchain::Capabilities::all())
 				));
-				let run = if version == 2 {
+				let run = if version >= 2 {
 					runtime.offchain_worker_with_context(&at, context, &header)
 				} else {
 					#[allow(deprecated)]
@@ -142,10 +192,76 @@ impl<Client, Storage, Block> OffchainWorkers<
 					log::error!("Error running offchain workers at {:?}: {:?}", at, e);
 				}
 			});
-			futures::future::Either::Left(runner.process())
+			runner.process().boxed()
 		} else {
-			futures::future::Either::Right(futures::future::ready(()))
+			future::ready(()).boxed()
+		}
+	}
+
+	/// Spawns each of the given workers of the runtime separately, unless the scheduler holds
+	/// them back.
+	fn spawn_scheduled_workers(
+		&self,
+		header: &Block::Header,
+		workers: Vec<Vec<u8>>,
+		network_state: Arc<dyn NetworkStateInfo + Send + Sync>,
+		is_validator: bool,
+	) -> future::BoxFuture<'static, ()> {
+		let mut runners = Vec::with_capacity(workers.len());
+		for worker in workers {
+			let at = BlockId::hash(header.hash());
+			let name = String::from_utf8_lossy(&worker).into_owned();
+			let run = match self.scheduler.try_start(&worker) {
+				Some(run) => run,
+				None => {
+					debug!("Skipping offchain worker {} at {:?}: previous runs in progress", name, at);
+					continue;
+				},
+			};
+			if !scheduler::try_lock_block(&mut self.db.clone(), &worker, *header.number()) {
+				debug!("Skipping offchain worker {} at {:?}: already ran for this block number", name, at);
+				continue;
+			}
+
+			// the deadline of the run starts when it's scheduled, it may wait for a thread.
+			let deadline = self.scheduler.limits(&worker).deadline;
+			let scheduled = Instant::now();
+			let (api, runner) = api::AsyncApi::new(
+				self.db.clone(),
+				network_state.clone(),
+				is_validator,
+				self.http_tls.clone(),
+				self.ipfs.clone(),
+			);
+			let api = api.with_deadline(deadline);
+			debug!("Spawning offchain worker {} at {:?}", name, at);
+			let header = header.clone();
+			let client = self.client.clone();
+			self.spawn_worker(move || {
+				let _context = sp_tracing::log_context::enter_block(header.number(), header.hash());
+				// the run is in progress until the worker returns.
+				let _run = run;
+				let runtime = client.runtime_api();
+				let api = Box::new(api);
+				debug!("Running offchain worker {} at {:?}", name, at);
+				let context = ExecutionContext::OffchainCall(Some(
+					(api, offchain::Capabilities::all())
+				));
+				let run = runtime.offchain_worker_of_with_context(&at, context, &header, worker);
+				match (run, deadline) {
+					(Err(_), Some(deadline)) if scheduled.elapsed() >= deadline => warn!(
+						"Offchain worker {} at {:?} missed its deadline of {:?} and was aborted",
+						name, at, deadline,
+					),
+					(Err(e), _) => log::error!("Error running offchain worker {} at {:?}: {:?}", name, at, e),
+					(Ok(()), _) => {},
+				}
+			});
+
+			runners.push(runner.process());
 		}
+
+		future::join_all(runners).map(|_| ()).boxed()
 	}
 
 	/// Spawns a new offchain worker.
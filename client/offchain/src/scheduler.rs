@@ -0,0 +1,184 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scheduling of the workers of runtimes starting them separately.
+//!
+//! Runtimes listing their workers in `OffchainWorkerApi::offchain_workers` have each of them
+//! started on its own thread, so a slow worker doesn't hold back the others. A worker isn't
+//! started while its concurrency limit of runs is still in progress, and at most once per block
+//! number, tracked with a lock persisted in the offchain storage so that re-imports and restarts
+//! don't run it again. A run missing its deadline is aborted at its next host call, and the
+//! waits of its calls, e.g. for HTTP responses, are cut short at the deadline.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use codec::{Decode, Encode};
+use parking_lot::Mutex;
+use sp_core::offchain::OffchainStorage;
+
+/// Offchain storage prefix of the block number locks of the workers.
+pub const WORKER_LOCK_PREFIX: &[u8] = b"offchain_worker_lock";
+
+/// The limits of the runs of a worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerLimits {
+	/// The number of runs of the worker, for different blocks, that can be in progress at once.
+	pub concurrency: usize,
+	/// The time after which a run is aborted, `None` for no deadline.
+	pub deadline: Option<Duration>,
+}
+
+impl Default for WorkerLimits {
+	fn default() -> Self {
+		WorkerLimits {
+			concurrency: 1,
+			deadline: None,
+		}
+	}
+}
+
+/// The scheduling of the offchain workers.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchedulingConfig {
+	/// The limits of the workers without limits of their own.
+	pub default_limits: WorkerLimits,
+	/// The limits of the workers, by name.
+	pub worker_limits: HashMap<String, WorkerLimits>,
+}
+
+impl SchedulingConfig {
+	/// The limits of the given worker.
+	pub fn limits(&self, worker: &[u8]) -> &WorkerLimits {
+		std::str::from_utf8(worker).ok()
+			.and_then(|worker| self.worker_limits.get(worker))
+			.unwrap_or(&self.default_limits)
+	}
+}
+
+/// Tracks the runs of the workers in progress.
+#[derive(Default)]
+pub(crate) struct Scheduler {
+	config: SchedulingConfig,
+	running: Arc<Mutex<HashMap<Vec<u8>, usize>>>,
+}
+
+impl Scheduler {
+	/// Creates a new scheduler with the given config.
+	pub fn new(config: SchedulingConfig) -> Self {
+		Scheduler {
+			config,
+			running: Default::default(),
+		}
+	}
+
+	/// The limits of the given worker.
+	pub fn limits(&self, worker: &[u8]) -> &WorkerLimits {
+		self.config.limits(worker)
+	}
+
+	/// Start a run of the given worker, unless its concurrency limit of runs is in progress.
+	///
+	/// The run is in progress until the returned guard is dropped.
+	pub fn try_start(&self, worker: &[u8]) -> Option<RunGuard> {
+		let mut running = self.running.lock();
+		let runs = running.entry(worker.to_vec()).or_default();
+		if *runs >= self.limits(worker).concurrency {
+			return None;
+		}
+
+		*runs += 1;
+		Some(RunGuard {
+			running: self.running.clone(),
+			worker: worker.to_vec(),
+		})
+	}
+}
+
+/// A run of a worker in progress.
+pub(crate) struct RunGuard {
+	running: Arc<Mutex<HashMap<Vec<u8>, usize>>>,
+	worker: Vec<u8>,
+}
+
+impl Drop for RunGuard {
+	fn drop(&mut self) {
+		let mut running = self.running.lock();
+		if let Some(runs) = running.get_mut(&self.worker) {
+			*runs -= 1;
+			if *runs == 0 {
+				running.remove(&self.worker);
+			}
+		}
+	}
+}
+
+/// Take the lock of the given worker for the given block number.
+///
+/// Returns `false` if the worker already ran for this or a higher block number.
+pub(crate) fn try_lock_block<S, N>(db: &mut S, worker: &[u8], number: N) -> bool where
+	S: OffchainStorage,
+	N: Encode + Decode + Ord,
+{
+	loop {
+		let locked = db.get(WORKER_LOCK_PREFIX, worker);
+		if let Some(locked) = locked.as_ref().and_then(|locked| N::decode(&mut &locked[..]).ok()) {
+			if locked >= number {
+				return false;
+			}
+		}
+
+		if db.compare_and_set(WORKER_LOCK_PREFIX, worker, locked.as_deref(), &number.encode()) {
+			return true;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::offchain::storage::InMemOffchainStorage;
+
+	#[test]
+	fn runs_are_limited_per_worker() {
+		let mut config = SchedulingConfig::default();
+		config.worker_limits.insert("slow".into(), WorkerLimits { concurrency: 2, deadline: None });
+		let scheduler = Scheduler::new(config);
+
+		let first = scheduler.try_start(b"slow").unwrap();
+		let _second = scheduler.try_start(b"slow").unwrap();
+		assert!(scheduler.try_start(b"slow").is_none());
+
+		// other workers aren't held back by the slow one.
+		let fast = scheduler.try_start(b"fast").unwrap();
+		assert!(scheduler.try_start(b"fast").is_none());
+
+		drop(first);
+		drop(fast);
+		assert!(scheduler.try_start(b"slow").is_some());
+		assert!(scheduler.try_start(b"fast").is_some());
+	}
+
+	#[test]
+	fn workers_run_once_per_block_number() {
+		let mut db = InMemOffchainStorage::default();
+
+		assert!(try_lock_block(&mut db, b"worker", 5u64));
+		assert!(!try_lock_block(&mut db, b"worker", 5u64));
+		assert!(!try_lock_block(&mut db, b"worker", 4u64));
+		assert!(try_lock_block(&mut db, b"other", 4u64));
+		assert!(try_lock_block(&mut db, b"worker", 6u64));
+	}
+}
@@ -18,15 +18,17 @@
 //! connected to.
 
 mod peersstate;
+mod protocol_slots;
 
 use std::{collections::{HashSet, HashMap}, collections::VecDeque};
 use futures::{prelude::*, channel::mpsc};
 use log::{debug, error, trace};
 use serde_json::json;
-use std::{pin::Pin, task::{Context, Poll}, time::Duration};
+use std::{fs, io, path::Path, pin::Pin, task::{Context, Poll}, time::Duration};
 use wasm_timer::Instant;
 
 pub use libp2p::PeerId;
+pub use protocol_slots::ProtocolSlots;
 
 /// We don't accept nodes whose reputation is under this value.
 const BANNED_THRESHOLD: i32 = 82 * (i32::min_value() / 100);
@@ -145,6 +147,17 @@ pub enum Message {
 	Reject(IncomingIndex),
 }
 
+/// Reason why the peer set manager rejected an incoming connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+	/// We only accept reserved nodes and the node isn't one.
+	NotReserved,
+	/// The reputation of the node is below the ban threshold.
+	Banned,
+	/// All the incoming slots are in use.
+	SlotsFull,
+}
+
 /// Opaque identifier for an incoming connection. Allocated by the network.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct IncomingIndex(pub u64);
@@ -178,6 +191,12 @@ pub struct PeersetConfig {
 	/// > **Note**: Keep in mind that the networking has to know an address for these nodes,
 	/// >			otherwise it will not be able to connect to them.
 	pub reserved_nodes: Vec<PeerId>,
+
+	/// Separate ingoing and outgoing slot budgets for protocols, by protocol name.
+	///
+	/// Protocols without a budget may use every connected peer. Nodes in a priority group (e.g.
+	/// reserved nodes) don't count towards the budgets.
+	pub protocol_slots: HashMap<String, ProtocolSlots>,
 }
 
 /// Side of the peer set manager owned by the network. In other words, the "receiving" side.
@@ -199,6 +218,10 @@ pub struct Peerset {
 	created: Instant,
 	/// Last time when we updated the reputations of connected nodes.
 	latest_time_update: Instant,
+	/// Slots allocated to connected nodes for the protocols that have a budget.
+	protocol_slots: protocol_slots::ProtocolSlotsState,
+	/// Reasons of the `Reject` messages, until retrieved with `take_reject_reason`.
+	reject_reasons: HashMap<IncomingIndex, RejectReason>,
 }
 
 impl Peerset {
@@ -220,6 +243,8 @@ impl Peerset {
 			message_queue: VecDeque::new(),
 			created: now,
 			latest_time_update: now,
+			protocol_slots: protocol_slots::ProtocolSlotsState::new(config.protocol_slots),
+			reject_reasons: HashMap::new(),
 		};
 
 		peerset.data.set_priority_group(RESERVED_NODES, config.reserved_nodes.into_iter().collect());
@@ -452,16 +477,33 @@ impl Peerset {
 		};
 
 		if not_connected.reputation() < BANNED_THRESHOLD {
+			self.reject_reasons.insert(index, RejectReason::Banned);
 			self.message_queue.push_back(Message::Reject(index));
 			return
 		}
 
+		let is_priority = not_connected.is_priority();
 		match not_connected.try_accept_incoming() {
 			Ok(_) => self.message_queue.push_back(Message::Accept(index)),
-			Err(_) => self.message_queue.push_back(Message::Reject(index)),
+			Err(_) => {
+				let reason = if self.reserved_only && !is_priority {
+					RejectReason::NotReserved
+				} else {
+					RejectReason::SlotsFull
+				};
+				self.reject_reasons.insert(index, reason);
+				self.message_queue.push_back(Message::Reject(index));
+			},
 		}
 	}
 
+	/// Returns the reason why the incoming connection with the given index was rejected.
+	///
+	/// The reason can only be retrieved once, after the corresponding `Reject` message.
+	pub fn take_reject_reason(&mut self, index: IncomingIndex) -> Option<RejectReason> {
+		self.reject_reasons.remove(&index)
+	}
+
 	/// Indicate that we dropped an active connection with a peer, or that we failed to connect.
 	///
 	/// Must only be called after the PSM has either generated a `Connect` message with this
@@ -477,6 +519,7 @@ impl Peerset {
 				// Decrease the node's reputation so that we don't try it again and again and again.
 				entry.add_reputation(DISCONNECT_REPUTATION_CHANGE);
 				entry.disconnect();
+				self.protocol_slots.release(&peer_id);
 			}
 			peersstate::Peer::NotConnected(_) | peersstate::Peer::Unknown(_) =>
 				error!(target: "peerset", "Received dropped() for non-connected node"),
@@ -535,6 +578,15 @@ impl Peerset {
 			}).collect::<HashMap<_, _>>(),
 			"reserved_only": self.reserved_only,
 			"message_queue": self.message_queue.len(),
+			"protocol_slots": self.protocol_slots.protocols().map(|(protocol, slots)| {
+				let (num_in, num_out) = self.protocol_slots.num_allocated(protocol);
+				(protocol.clone(), json!({
+					"in": num_in,
+					"out": num_out,
+					"max_in": slots.in_peers,
+					"max_out": slots.out_peers,
+				}))
+			}).collect::<HashMap<_, _>>(),
 		})
 	}
 
@@ -543,10 +595,105 @@ impl Peerset {
 		self.data.peers().len()
 	}
 
+	/// Returns the number of reserved peers.
+	pub fn num_reserved_peers(&self) -> usize {
+		self.data.get_priority_group(RESERVED_NODES).map_or(0, |reserved| reserved.len())
+	}
+
 	/// Returns priority group by id.
 	pub fn get_priority_group(&self, group_id: &str) -> Option<HashSet<PeerId>> {
 		self.data.get_priority_group(group_id)
 	}
+
+	/// Tries to allocate a slot of `protocol` to a peer we are connected to.
+	///
+	/// Returns `true` if the peer may be used for the protocol, which is always the case for
+	/// protocols without a budget and for nodes in a priority group. Returns `false` if we are not
+	/// connected to the peer or if all the slots of its connection direction are used.
+	pub fn allocate_protocol_slot(&mut self, protocol: &str, peer_id: &PeerId) -> bool {
+		match self.data.peer(peer_id) {
+			peersstate::Peer::Connected(entry) => {
+				if entry.is_priority() {
+					return true;
+				}
+				let ingoing = entry.is_ingoing();
+				self.protocol_slots.allocate(protocol, peer_id, ingoing)
+			},
+			peersstate::Peer::NotConnected(_) | peersstate::Peer::Unknown(_) => false,
+		}
+	}
+
+	/// Releases the protocol slots allocated to the peer.
+	pub fn release_protocol_slots(&mut self, peer_id: &PeerId) {
+		self.protocol_slots.release(peer_id);
+	}
+
+	/// Returns the reputation of the given peer, or `None` if we don't know about this peer.
+	///
+	/// The reputation restored from a previous run is returned for peers that have not been
+	/// discovered yet.
+	pub fn peer_reputation(&mut self, peer_id: &PeerId) -> Option<i32> {
+		self.update_time();
+
+		match self.data.peer(peer_id) {
+			peersstate::Peer::Connected(entry) => Some(entry.reputation()),
+			peersstate::Peer::NotConnected(entry) => Some(entry.reputation()),
+			peersstate::Peer::Unknown(_) => self.data.restored_reputations().get(peer_id).cloned(),
+		}
+	}
+
+	/// Returns a snapshot of all the non-zero reputations, including the restored reputations of
+	/// peers that have not been discovered yet.
+	pub fn reputations(&mut self) -> HashMap<PeerId, i32> {
+		self.update_time();
+
+		let mut reputations = self.data.restored_reputations().clone();
+		for peer_id in self.data.peers().cloned().collect::<Vec<_>>() {
+			let reputation = match self.data.peer(&peer_id) {
+				peersstate::Peer::Connected(entry) => entry.reputation(),
+				peersstate::Peer::NotConnected(entry) => entry.reputation(),
+				peersstate::Peer::Unknown(_) =>
+					unreachable!("We iterate over the known peers; QED")
+			};
+			reputations.insert(peer_id, reputation);
+		}
+
+		reputations.retain(|_, reputation| *reputation != 0);
+		reputations
+	}
+
+	/// Writes a snapshot of the reputations to `path` as a JSON object mapping base58 encoded
+	/// peer IDs to reputations.
+	pub fn export_reputations(&mut self, path: &Path) -> io::Result<()> {
+		let reputations = self.reputations()
+			.into_iter()
+			.map(|(peer_id, reputation)| (peer_id.to_base58(), reputation))
+			.collect::<HashMap<_, _>>();
+
+		let file = fs::File::create(path)?;
+		serde_json::to_writer(file, &reputations)
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	/// Restores the reputations written by `export_reputations`.
+	///
+	/// Entries with an invalid peer ID are ignored.
+	pub fn import_reputations(&mut self, path: &Path) -> io::Result<()> {
+		let file = fs::File::open(path)?;
+		let reputations: HashMap<String, i32> = serde_json::from_reader(io::BufReader::new(file))
+			.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+		for (peer_id, reputation) in reputations {
+			match peer_id.parse::<PeerId>() {
+				Ok(peer_id) => self.data.restore_reputation(peer_id, reputation),
+				Err(_) => debug!(target: "peerset", "Ignoring invalid peer id {} in {:?}", peer_id, path),
+			}
+		}
+
+		// Restored reputations may ban peers or make others more attractive.
+		self.alloc_slots();
+		Ok(())
+	}
 }
 
 impl Stream for Peerset {
@@ -590,7 +737,10 @@ impl Stream for Peerset {
 mod tests {
 	use libp2p::PeerId;
 	use futures::prelude::*;
-	use super::{PeersetConfig, Peerset, Message, IncomingIndex, ReputationChange, BANNED_THRESHOLD};
+	use super::{
+		PeersetConfig, Peerset, Message, IncomingIndex, ReputationChange, RejectReason,
+		BANNED_THRESHOLD,
+	};
 	use std::{collections::HashSet, pin::Pin, task::Poll, thread, time::Duration};
 
 	fn assert_messages(mut peerset: Peerset, messages: Vec<Message>) -> Peerset {
@@ -620,6 +770,7 @@ mod tests {
 			bootnodes: vec![bootnode],
 			reserved_only: true,
 			reserved_nodes: Vec::new(),
+			protocol_slots: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -645,6 +796,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: true,
 			reserved_nodes: vec![],
+			protocol_slots: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -682,6 +834,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: true,
 			reserved_nodes: vec![],
+			protocol_slots: Default::default(),
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -719,6 +872,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: false,
 			reserved_nodes: Vec::new(),
+			protocol_slots: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -726,6 +880,7 @@ mod tests {
 		peerset.incoming(incoming.clone(), ii4);
 		peerset.incoming(incoming2.clone(), ii2);
 		peerset.incoming(incoming3.clone(), ii3);
+		assert_eq!(peerset.take_reject_reason(ii3), Some(RejectReason::SlotsFull));
 
 		assert_messages(peerset, vec![
 			Message::Connect(bootnode.clone()),
@@ -735,6 +890,22 @@ mod tests {
 		]);
 	}
 
+	#[test]
+	fn test_peerset_incoming_not_reserved() {
+		let (mut peerset, _handle) = Peerset::from_config(PeersetConfig {
+			in_peers: 25,
+			out_peers: 25,
+			bootnodes: vec![],
+			reserved_only: true,
+			reserved_nodes: vec![],
+			protocol_slots: Default::default(),
+		});
+
+		peerset.incoming(PeerId::random(), IncomingIndex(1));
+		assert_eq!(peerset.take_reject_reason(IncomingIndex(1)), Some(RejectReason::NotReserved));
+		assert_messages(peerset, vec![Message::Reject(IncomingIndex(1))]);
+	}
+
 	#[test]
 	fn test_peerset_discovered() {
 		let bootnode = PeerId::random();
@@ -746,6 +917,7 @@ mod tests {
 			bootnodes: vec![bootnode.clone()],
 			reserved_only: false,
 			reserved_nodes: vec![],
+			protocol_slots: Default::default(),
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -767,6 +939,7 @@ mod tests {
 			bootnodes: vec![],
 			reserved_only: false,
 			reserved_nodes: vec![],
+			protocol_slots: Default::default(),
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -784,6 +957,8 @@ mod tests {
 			} else {
 				panic!()
 			}
+			assert_eq!(peerset.take_reject_reason(IncomingIndex(1)), Some(RejectReason::Banned));
+			assert_eq!(peerset.take_reject_reason(IncomingIndex(1)), None);
 
 			// Wait a bit for the node's reputation to go above the threshold.
 			thread::sleep(Duration::from_millis(1500));
@@ -799,5 +974,37 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
-}
 
+	#[test]
+	fn test_peerset_reputations_export_import() {
+		let bootnode = PeerId::random();
+		let unknown = PeerId::random();
+		let config = || PeersetConfig {
+			in_peers: 0,
+			out_peers: 0,
+			bootnodes: vec![bootnode.clone()],
+			reserved_only: false,
+			reserved_nodes: Vec::new(),
+			protocol_slots: Default::default(),
+		};
+
+		let dir = std::env::temp_dir().join(format!("peerset-reputations-{}", bootnode.to_base58()));
+		std::fs::create_dir_all(&dir).unwrap();
+		let path = dir.join("reputations.json");
+
+		let (mut peerset, _handle) = Peerset::from_config(config());
+		peerset.on_report_peer(bootnode.clone(), ReputationChange::new_fatal(""));
+		let reputation = peerset.peer_reputation(&bootnode).unwrap();
+		assert!(reputation < BANNED_THRESHOLD);
+		assert_eq!(peerset.peer_reputation(&unknown), None);
+		peerset.export_reputations(&path).unwrap();
+
+		let (mut peerset, _handle) = Peerset::from_config(config());
+		assert_eq!(peerset.peer_reputation(&bootnode), Some(0));
+		peerset.import_reputations(&path).unwrap();
+		assert!(peerset.peer_reputation(&bootnode).unwrap() < BANNED_THRESHOLD);
+		assert_eq!(peerset.peer_reputation(&unknown), None);
+
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
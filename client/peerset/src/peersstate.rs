@@ -54,6 +54,10 @@ pub struct PeersState {
 
 	/// Only allow connections to/from peers in a priority group.
 	priority_only: bool,
+
+	/// Reputations restored from a previous run for nodes that we don't know about yet. They are
+	/// applied when the node is discovered.
+	restored_reputations: HashMap<PeerId, i32>,
 }
 
 /// State of a single node that we know about.
@@ -115,9 +119,26 @@ impl PeersState {
 			max_out: out_peers,
 			priority_nodes: HashMap::new(),
 			priority_only,
+			restored_reputations: HashMap::new(),
+		}
+	}
+
+	/// Restores the reputation of a node from a previous run.
+	///
+	/// If the node is already known, its reputation is overwritten. Otherwise the reputation is
+	/// remembered and applied when the node is discovered.
+	pub fn restore_reputation(&mut self, peer_id: PeerId, reputation: i32) {
+		match self.nodes.get_mut(&peer_id) {
+			Some(node) => node.reputation = reputation,
+			None => { self.restored_reputations.insert(peer_id, reputation); },
 		}
 	}
 
+	/// Returns the reputations restored for nodes that have not been discovered yet.
+	pub fn restored_reputations(&self) -> &HashMap<PeerId, i32> {
+		&self.restored_reputations
+	}
+
 	/// Returns an object that grants access to the state of a peer.
 	pub fn peer<'a>(&'a mut self, peer_id: &'a PeerId) -> Peer<'a> {
 		match self.nodes.get_mut(peer_id) {
@@ -435,6 +456,17 @@ impl<'a> ConnectedPeer<'a> {
 		self.peer_id.into_owned()
 	}
 
+	/// Returns `true` if we are connected to the peer through an ingoing connection.
+	pub fn is_ingoing(&self) -> bool {
+		self.state.nodes.get(&*self.peer_id)
+			.map_or(false, |node| node.connection_state == ConnectionState::In)
+	}
+
+	/// Returns `true` if the peer is in a priority group.
+	pub fn is_priority(&self) -> bool {
+		self.state.is_priority(&self.peer_id)
+	}
+
 	/// Switches the peer to "not connected".
 	pub fn disconnect(self) -> NotConnectedPeer<'a> {
 		self.state.disconnect(&self.peer_id);
@@ -476,6 +508,11 @@ impl<'a> NotConnectedPeer<'a> {
 		self.peer_id.into_owned()
 	}
 
+	/// Returns `true` if the peer is in a priority group.
+	pub fn is_priority(&self) -> bool {
+		self.state.is_priority(&self.peer_id)
+	}
+
 	/// Bumps the value that `last_connected_or_discovered` would return to now, even if we
 	/// didn't connect or disconnect.
 	pub fn bump_last_connected_or_discovered(&mut self) {
@@ -593,14 +630,15 @@ pub struct UnknownPeer<'a> {
 impl<'a> UnknownPeer<'a> {
 	/// Inserts the peer identity in our list.
 	///
-	/// The node starts with a reputation of 0. You can adjust these default
-	/// values using the `NotConnectedPeer` that this method returns.
+	/// The node starts with a reputation of 0, or with its restored reputation if any. You can
+	/// adjust these default values using the `NotConnectedPeer` that this method returns.
 	pub fn discover(self) -> NotConnectedPeer<'a> {
+		let reputation = self.parent.restored_reputations.remove(&self.peer_id).unwrap_or(0);
 		self.parent.nodes.insert(self.peer_id.clone().into_owned(), Node {
 			connection_state: ConnectionState::NotConnected {
 				last_connected: Instant::now(),
 			},
-			reputation: 0,
+			reputation,
 		});
 
 		let state = self.parent;
@@ -783,4 +821,23 @@ mod tests {
 		peers_state.remove_from_priority_group("TEST_GROUP", &id);
 		assert!(!test_connection(&mut peers_state, &id));
 	}
+
+	#[test]
+	fn restored_reputation_applied_on_discover() {
+		let mut peers_state = PeersState::new(1, 1, false);
+		let id = PeerId::random();
+
+		peers_state.restore_reputation(id.clone(), -1000);
+		assert_eq!(peers_state.restored_reputations().get(&id), Some(&-1000));
+
+		if let Peer::Unknown(e) = peers_state.peer(&id) {
+			assert_eq!(e.discover().reputation(), -1000);
+		} else {
+			panic!("peer should be unknown");
+		}
+		assert!(peers_state.restored_reputations().is_empty());
+
+		peers_state.restore_reputation(id.clone(), 50);
+		assert_eq!(peers_state.peer(&id).into_not_connected().unwrap().reputation(), 50);
+	}
 }
@@ -0,0 +1,141 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-protocol slot budgets.
+//!
+//! All the protocols share the connections allocated by the peerset. On top of that, a protocol
+//! can be given its own budget of ingoing and outgoing peers, so that a busy protocol can't use
+//! every connected peer and starve the other protocols.
+
+use libp2p::PeerId;
+use std::collections::HashMap;
+
+/// Slot budget of a single protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolSlots {
+	/// Maximum number of peers connected through an ingoing connection that use the protocol.
+	pub in_peers: u32,
+
+	/// Maximum number of peers connected through an outgoing connection that use the protocol.
+	pub out_peers: u32,
+}
+
+/// Keeps track of the protocol slots allocated to connected peers.
+#[derive(Debug, Default)]
+pub(crate) struct ProtocolSlotsState {
+	/// Budget of each protocol. Protocols without a budget may use every connected peer.
+	budgets: HashMap<String, ProtocolSlots>,
+	/// For each protocol, the peers that were allocated a slot and whether they are connected
+	/// through an ingoing connection.
+	allocated: HashMap<String, HashMap<PeerId, bool>>,
+}
+
+impl ProtocolSlotsState {
+	/// Builds a new state with the given budgets and no allocated slot.
+	pub fn new(budgets: HashMap<String, ProtocolSlots>) -> Self {
+		ProtocolSlotsState {
+			budgets,
+			allocated: HashMap::new(),
+		}
+	}
+
+	/// Tries to allocate a slot of `protocol` to the peer.
+	///
+	/// Returns `true` if the protocol has no budget, if the peer already had a slot, or if a
+	/// slot was free.
+	pub fn allocate(&mut self, protocol: &str, peer_id: &PeerId, incoming: bool) -> bool {
+		let budget = match self.budgets.get(protocol) {
+			Some(budget) => budget,
+			None => return true,
+		};
+
+		let allocated = self.allocated.entry(protocol.to_owned()).or_default();
+		if allocated.contains_key(peer_id) {
+			return true;
+		}
+
+		let max = if incoming { budget.in_peers } else { budget.out_peers };
+		let num = allocated.values().filter(|is_in| **is_in == incoming).count();
+		if num >= max as usize {
+			return false;
+		}
+
+		allocated.insert(peer_id.clone(), incoming);
+		true
+	}
+
+	/// Releases every slot allocated to the peer.
+	pub fn release(&mut self, peer_id: &PeerId) {
+		for allocated in self.allocated.values_mut() {
+			allocated.remove(peer_id);
+		}
+	}
+
+	/// Returns the number of ingoing and outgoing slots of `protocol` currently allocated.
+	pub fn num_allocated(&self, protocol: &str) -> (usize, usize) {
+		self.allocated.get(protocol).map_or((0, 0), |allocated| {
+			let num_in = allocated.values().filter(|is_in| **is_in).count();
+			(num_in, allocated.len() - num_in)
+		})
+	}
+
+	/// Returns the protocols that have a budget.
+	pub fn protocols(&self) -> impl Iterator<Item = (&String, &ProtocolSlots)> {
+		self.budgets.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{ProtocolSlots, ProtocolSlotsState};
+	use libp2p::PeerId;
+
+	fn state() -> ProtocolSlotsState {
+		ProtocolSlotsState::new(
+			vec![("transactions".to_owned(), ProtocolSlots { in_peers: 1, out_peers: 2 })]
+				.into_iter()
+				.collect()
+		)
+	}
+
+	#[test]
+	fn protocol_without_budget_is_unlimited() {
+		let mut state = state();
+		for _ in 0..10 {
+			assert!(state.allocate("block-announces", &PeerId::random(), true));
+		}
+		assert_eq!(state.num_allocated("block-announces"), (0, 0));
+	}
+
+	#[test]
+	fn budget_is_per_direction() {
+		let mut state = state();
+		let (in1, in2) = (PeerId::random(), PeerId::random());
+
+		assert!(state.allocate("transactions", &in1, true));
+		assert!(!state.allocate("transactions", &in2, true));
+		assert!(state.allocate("transactions", &PeerId::random(), false));
+		assert!(state.allocate("transactions", &PeerId::random(), false));
+		assert!(!state.allocate("transactions", &PeerId::random(), false));
+		assert_eq!(state.num_allocated("transactions"), (1, 2));
+
+		// Allocating twice to the same peer doesn't use another slot.
+		assert!(state.allocate("transactions", &in1, true));
+
+		state.release(&in1);
+		assert!(state.allocate("transactions", &in2, true));
+	}
+}
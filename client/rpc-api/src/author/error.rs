@@ -57,6 +57,12 @@ pub enum Error {
 	/// Invalid session keys encoding.
 	#[display(fmt="Session keys are not encoded correctly")]
 	InvalidSessionKeys,
+	/// The possession of the session keys could not be proven for the owner.
+	#[display(fmt="Session keys ownership could not be proven, the owner is invalid or a key could not sign")]
+	KeyOwnershipProof,
+	/// The runtime can't prove the possession of the session keys.
+	#[display(fmt="Session keys ownership proofs are not supported by the runtime, `SessionKeys` version 2 is required")]
+	KeyOwnershipProofUnsupported,
 }
 
 impl std::error::Error for Error {
@@ -76,6 +82,8 @@ const BASE_ERROR: i64 = 1000;
 const BAD_FORMAT: i64 = BASE_ERROR + 1;
 /// Error during transaction verification in runtime.
 const VERIFICATION_ERROR: i64 = BASE_ERROR + 2;
+/// The runtime doesn't support the session keys ownership proofs.
+const UNSUPPORTED_KEY_OWNERSHIP_PROOF: i64 = BASE_ERROR + 3;
 
 /// Pool rejected the transaction as invalid
 const POOL_INVALID_TX: i64 = BASE_ERROR + 10;
@@ -93,6 +101,8 @@ const POOL_CYCLE_DETECTED: i64 = POOL_INVALID_TX + 5;
 const POOL_IMMEDIATELY_DROPPED: i64 = POOL_INVALID_TX + 6;
 /// The key type crypto is not known.
 const UNSUPPORTED_KEY_TYPE: i64 = POOL_INVALID_TX + 7;
+/// Too many unsigned transactions like this one were submitted at the current block.
+const POOL_RATE_LIMITED: i64 = POOL_INVALID_TX + 8;
 
 impl From<Error> for rpc::Error {
 	fn from(e: Error) -> Self {
@@ -129,9 +139,9 @@ impl From<Error> for rpc::Error {
 				message: "Transaction Already Imported".into(),
 				data: Some(format!("{:?}", hash).into()),
 			},
-			Error::Pool(PoolError::TooLowPriority { old, new }) => rpc::Error {
+			Error::Pool(PoolError::TooLowPriority { old, required, new }) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(POOL_TOO_LOW_PRIORITY),
-				message: format!("Priority is too low: ({} vs {})", old, new),
+				message: format!("Priority is too low: ({} vs {}, above {} required)", old, new, required),
 				data: Some("The transaction has too low priority to replace another transaction already in the pool.".into()),
 			},
 			Error::Pool(PoolError::CycleDetected) => rpc::Error {
@@ -144,6 +154,11 @@ impl From<Error> for rpc::Error {
 				message: "Immediately Dropped".into(),
 				data: Some("The transaction couldn't enter the pool because of the limit".into()),
 			},
+			Error::Pool(PoolError::RateLimited) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_RATE_LIMITED),
+				message: "Rate Limited".into(),
+				data: Some("Too many unsigned transactions dispatching the same call were submitted in the last blocks".into()),
+			},
 			Error::UnsupportedKeyType => rpc::Error {
 				code: rpc::ErrorCode::ServerError(UNSUPPORTED_KEY_TYPE),
 				message: "Unknown key type crypto" .into(),
@@ -152,6 +167,11 @@ impl From<Error> for rpc::Error {
 					request to insert the key successfully.".into()
 				),
 			},
+			Error::KeyOwnershipProofUnsupported => rpc::Error {
+				code: rpc::ErrorCode::ServerError(UNSUPPORTED_KEY_OWNERSHIP_PROOF),
+				message: "Session keys ownership proofs are not supported by the runtime".into(),
+				data: Some("The `SessionKeys` runtime api of version 2 or above is required to rotate the keys with a proof".into()),
+			},
 			e => errors::internal(e),
 		}
 	}
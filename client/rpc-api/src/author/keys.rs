@@ -0,0 +1,54 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Session keys helpers for author RPC module.
+
+use sp_core::Bytes;
+use serde::{Serialize, Deserialize};
+
+/// Session keys generated by `author_rotateKeys`.
+///
+/// Serialized as the bare public keys when no owner is given, as before proofs were introduced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RotatedKeys {
+	/// The SCALE encoded public keys.
+	Keys(Bytes),
+	/// The SCALE encoded public keys and the proof of their possession for the owner.
+	WithProof {
+		/// The SCALE encoded public keys.
+		keys: Bytes,
+		/// The signatures by every key, to pass to `set_keys` with the keys.
+		proof: Bytes,
+	},
+}
+
+impl RotatedKeys {
+	/// The SCALE encoded public keys.
+	pub fn keys(&self) -> &Bytes {
+		match self {
+			RotatedKeys::Keys(keys) | RotatedKeys::WithProof { keys, .. } => keys,
+		}
+	}
+
+	/// The proof of possession of the keys, if an owner was given.
+	pub fn proof(&self) -> Option<&Bytes> {
+		match self {
+			RotatedKeys::Keys(_) => None,
+			RotatedKeys::WithProof { proof, .. } => Some(proof),
+		}
+	}
+}
@@ -18,12 +18,14 @@
 
 pub mod error;
 pub mod hash;
+pub mod keys;
 
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use sp_core::Bytes;
 use sp_transaction_pool::TransactionStatus;
 use self::error::{FutureResult, Result};
+use self::keys::RotatedKeys;
 
 pub use self::gen_client::Client as AuthorClient;
 
@@ -47,8 +49,11 @@ pub trait AuthorApi<Hash, BlockHash> {
 	) -> Result<()>;
 
 	/// Generate new session keys and returns the corresponding public keys.
+	///
+	/// If `owner`, the SCALE encoded account that will set the keys, is given, the keys are
+	/// returned with the proof of their possession by `owner` expected by the runtime.
 	#[rpc(name = "author_rotateKeys")]
-	fn rotate_keys(&self) -> Result<Bytes>;
+	fn rotate_keys(&self, owner: Option<Bytes>) -> Result<RotatedKeys>;
 
 	/// Checks if the keystore has private keys for the given session public keys.
 	///
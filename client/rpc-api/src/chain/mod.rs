@@ -22,11 +22,30 @@ use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use serde::{Deserialize, Serialize};
 use sp_rpc::{number::NumberOrHex, list::ListOrValue};
 use self::error::{FutureResult, Result};
 
 pub use self::gen_client::Client as ChainClient;
 
+/// A re-org of the best chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reorg<Hash> {
+	/// Hash of the new best block.
+	pub new_best: Hash,
+	/// Hash of the common ancestor of the previous and the new best blocks.
+	pub common_ancestor: Hash,
+	/// Hashes of the blocks retracted from the best chain, from the previous best block down.
+	pub retracted: Vec<Hash>,
+	/// Hashes of the blocks enacted on the best chain, up to the new best block.
+	pub enacted: Vec<Hash>,
+	/// Hashes of the signed extrinsics of the retracted blocks that aren't in the enacted blocks.
+	///
+	/// The node re-submits them to its transaction pool.
+	pub retracted_extrinsics: Vec<Hash>,
+}
+
 /// Substrate blockchain API
 #[rpc]
 pub trait ChainApi<Number, Hash, Header, SignedBlock> {
@@ -109,4 +128,16 @@ pub trait ChainApi<Number, Hash, Header, SignedBlock> {
 		metadata: Option<Self::Metadata>,
 		id: SubscriptionId,
 	) -> RpcResult<bool>;
+
+	/// Re-org subscription.
+	#[pubsub(subscription = "chain_reorg", subscribe, name = "chain_subscribeReorgs")]
+	fn subscribe_reorgs(&self, metadata: Self::Metadata, subscriber: Subscriber<Reorg<Hash>>);
+
+	/// Unsubscribe from re-org subscription.
+	#[pubsub(subscription = "chain_reorg", unsubscribe, name = "chain_unsubscribeReorgs")]
+	fn unsubscribe_reorgs(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool>;
 }
@@ -0,0 +1,90 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Debug RPC errors.
+
+use crate::errors;
+use jsonrpc_core as rpc;
+
+/// Debug RPC Result type.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Debug RPC future Result type.
+pub type FutureResult<T> = Box<dyn rpc::futures::Future<Item = T, Error = Error> + Send>;
+
+/// Debug RPC errors.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Client error.
+	#[display(fmt="Client error: {}", _0)]
+	Client(Box<dyn std::error::Error + Send>),
+	/// The header or the body of the block is not in the backend.
+	#[display(fmt = "Unknown block {}", _0)]
+	#[from(ignore)]
+	UnknownBlock(String),
+	/// Failed to execute the block.
+	#[display(fmt = "Execution failed: {}", _0)]
+	#[from(ignore)]
+	Execution(String),
+	/// The events of the block couldn't be decoded with the runtime metadata.
+	#[display(fmt = "Failed to decode the events: {}", _0)]
+	#[from(ignore)]
+	InvalidEvents(String),
+	/// Another block is being traced.
+	#[display(fmt = "Another block is being traced")]
+	Busy,
+}
+
+impl std::error::Error for Error {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Error::Client(ref err) => Some(&**err),
+			_ => None,
+		}
+	}
+}
+
+/// Base code for all debug errors.
+const BASE_ERROR: i64 = 6000;
+
+impl From<Error> for rpc::Error {
+	fn from(e: Error) -> Self {
+		match e {
+			Error::UnknownBlock(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 1),
+				message: format!("{}", e),
+				data: None,
+			},
+			Error::Execution(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 2),
+				message: format!("{}", e),
+				data: None,
+			},
+			Error::InvalidEvents(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 3),
+				message: format!("{}", e),
+				data: None,
+			},
+			Error::Busy => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 4),
+				message: format!("{}", e),
+				data: None,
+			},
+			e => errors::internal(e),
+		}
+	}
+}
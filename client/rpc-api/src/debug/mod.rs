@@ -0,0 +1,131 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Plug debug API.
+
+pub mod error;
+
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_core::{Bytes, storage::{StorageData, StorageKey}};
+
+use crate::state::{DecodedEvent, UndecodedEvents};
+use self::error::FutureResult;
+
+pub use self::gen_client::Client as DebugClient;
+
+/// Options of a block trace.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TraceOptions {
+	/// Only trace the storage keys starting with one of these prefixes, every key if empty.
+	pub key_prefixes: Vec<StorageKey>,
+	/// Don't trace the storage reads and writes.
+	pub disable_storage: bool,
+	/// Don't trace the events.
+	pub disable_events: bool,
+}
+
+/// A storage value read or written.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageAccess {
+	/// The storage key.
+	pub key: StorageKey,
+	/// The value, `None` if the key has no value or was deleted.
+	pub value: Option<StorageData>,
+}
+
+/// The storage accesses and the events of a step of the execution of a block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StepTrace<Hash> {
+	/// The values read from the state of the parent block, in the order they were read. Values
+	/// written earlier in the block are read from the changes of the block and aren't listed.
+	pub reads: Vec<StorageAccess>,
+	/// The values written, by key.
+	pub writes: Vec<StorageAccess>,
+	/// The events deposited.
+	pub events: Vec<DecodedEvent<Hash>>,
+}
+
+/// A dispatch with the origin switched to the issuer of a doughnut.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginSwitch {
+	/// Index of the call in the delegated batch of the extrinsic, `None` for the extrinsic itself.
+	pub call: Option<u32>,
+	/// The issuer of the doughnut, the origin of the dispatch.
+	pub issuer: Bytes,
+}
+
+/// The trace of an extrinsic of a block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtrinsicTrace<Hash> {
+	/// Index of the extrinsic in the block.
+	pub index: u32,
+	/// Hash of the extrinsic.
+	pub hash: Hash,
+	/// Whether the extrinsic was dispatched successfully.
+	pub success: bool,
+	/// The weight of the extrinsic, as reported by its `System` event.
+	pub weight: Option<u64>,
+	/// The issuer of the doughnut the extrinsic is delegated with, if any.
+	pub doughnut_issuer: Option<Bytes>,
+	/// The dispatches of the extrinsic with the origin switched to a doughnut issuer, in order:
+	/// the extrinsic itself if it's delegated, then the delegated calls of its batches, as
+	/// reported by their `Utility` events.
+	pub origin_switches: Vec<OriginSwitch>,
+	/// The storage accesses and the events of the extrinsic.
+	#[serde(flatten)]
+	pub trace: StepTrace<Hash>,
+}
+
+/// The trace of the execution of a block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockTrace<Hash> {
+	/// Hash of the block.
+	pub hash: Hash,
+	/// Hash of the parent block, the block is executed on its state.
+	pub parent_hash: Hash,
+	/// Version of the runtime the block is executed with.
+	pub spec_version: u32,
+	/// The initialization of the block.
+	pub initialization: StepTrace<Hash>,
+	/// The extrinsics of the block, in order.
+	pub extrinsics: Vec<ExtrinsicTrace<Hash>>,
+	/// The finalization of the block.
+	pub finalization: StepTrace<Hash>,
+	/// Whether the execution computed the state root of the block header.
+	pub state_root_matches: bool,
+	/// The events from the first event which can't be decoded on, missing from the steps.
+	pub undecoded_events: Option<UndecodedEvents>,
+}
+
+/// Plug debug RPC API
+#[rpc]
+pub trait DebugApi<Hash> {
+	/// Re-execute a block on the state of its parent and trace the storage reads and writes, the
+	/// events, the weight and the doughnut origin switches of every extrinsic.
+	///
+	/// The block is executed in wasm with the runtime of its parent on a dedicated thread, one
+	/// block at a time, nothing is committed. The method is unsafe: a trace is expensive.
+	#[rpc(name = "debug_traceBlock")]
+	fn trace_block(&self, hash: Hash, options: Option<TraceOptions>) -> FutureResult<BlockTrace<Hash>>;
+}
@@ -25,11 +25,17 @@ mod helpers;
 mod subscriptions;
 
 pub use jsonrpc_core::IoHandlerExtension as RpcExtension;
-pub use subscriptions::{Subscriptions, TaskExecutor};
+pub use subscriptions::{
+	BoundedSink, OverflowPolicy, SinkError, SubscriptionLimits, SubscriptionMetrics, Subscriptions,
+	TaskExecutor, TOO_MANY_SUBSCRIPTIONS_ERROR,
+};
 pub use helpers::Receiver;
 
 pub mod author;
 pub mod chain;
+pub mod debug;
+pub mod network;
 pub mod offchain;
+pub mod pruning;
 pub mod state;
 pub mod system;
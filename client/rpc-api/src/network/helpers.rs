@@ -0,0 +1,92 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Substrate network API helpers.
+
+use serde::{Serialize, Deserialize};
+
+/// An access event returned by the RPC.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessEvent {
+	/// Base58-encoded id of the remote node.
+	pub peer_id: String,
+	/// Milliseconds since the UNIX epoch at which the event happened.
+	pub timestamp: u64,
+	/// What happened.
+	pub kind: AccessEventKind,
+}
+
+/// Kind of an access event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum AccessEventKind {
+	/// The connection was accepted.
+	Accepted {
+		/// `true` if the remote node dialed us.
+		incoming: bool,
+	},
+	/// The connection was refused.
+	Refused {
+		/// Why the connection was refused.
+		reason: RefusalReason,
+	},
+	/// The connection was closed.
+	Disconnected {
+		/// Why the connection was closed, for debugging purposes.
+		reason: String,
+	},
+}
+
+/// Reason why a connection was refused.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RefusalReason {
+	/// The node only accepts reserved nodes.
+	NotReserved,
+	/// The remote node is banned.
+	Banned,
+	/// All the slots are in use.
+	SlotsFull,
+	/// The remote node is not a member of the private network.
+	NotMember,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_serialize_access_events() {
+		assert_eq!(
+			::serde_json::to_string(&AccessEvent {
+				peer_id: "2".into(),
+				timestamp: 5,
+				kind: AccessEventKind::Refused { reason: RefusalReason::SlotsFull },
+			}).unwrap(),
+			r#"{"peerId":"2","timestamp":5,"kind":{"type":"refused","reason":"slotsFull"}}"#,
+		);
+		assert_eq!(
+			::serde_json::to_string(&AccessEvent {
+				peer_id: "2".into(),
+				timestamp: 5,
+				kind: AccessEventKind::Accepted { incoming: true },
+			}).unwrap(),
+			r#"{"peerId":"2","timestamp":5,"kind":{"type":"accepted","incoming":true}}"#,
+		);
+	}
+}
@@ -0,0 +1,55 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Substrate network API.
+
+pub mod helpers;
+
+use jsonrpc_core::Result as RpcResult;
+use jsonrpc_derive::rpc;
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+
+pub use self::helpers::{AccessEvent, AccessEventKind, RefusalReason};
+pub use self::gen_client::Client as NetworkClient;
+
+/// Substrate network API
+#[rpc]
+pub trait NetworkApi {
+	/// RPC metadata
+	type Metadata;
+
+	/// Access events subscription. Streams an event whenever a connection with a node is
+	/// accepted, refused or closed.
+	#[pubsub(
+		subscription = "network_accessEvents",
+		subscribe,
+		name = "network_subscribeAccessEvents"
+	)]
+	fn subscribe_access_events(&self, metadata: Self::Metadata, subscriber: Subscriber<AccessEvent>);
+
+	/// Unsubscribe from access events subscription.
+	#[pubsub(
+		subscription = "network_accessEvents",
+		unsubscribe,
+		name = "network_unsubscribeAccessEvents"
+	)]
+	fn unsubscribe_access_events(
+		&self,
+		metadata: Option<Self::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool>;
+}
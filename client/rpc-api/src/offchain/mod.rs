@@ -32,6 +32,9 @@ pub trait OffchainApi {
 	fn set_local_storage(&self, kind: StorageKind, key: Bytes, value: Bytes) -> Result<()>;
 
 	/// Get offchain local storage under given key and prefix.
+	///
+	/// The `PERSISTENT` storage also holds the offchain index, i.e. the values written by the
+	/// runtime with `offchain_index::set` while importing blocks.
 	#[rpc(name = "offchain_localStorageGet")]
 	fn get_local_storage(&self, kind: StorageKind, key: Bytes) -> Result<Option<Bytes>>;
 }
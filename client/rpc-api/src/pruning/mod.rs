@@ -0,0 +1,52 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate state pruning maintenance API.
+
+pub mod error;
+
+use jsonrpc_core::Result as RpcResult;
+use jsonrpc_derive::rpc;
+use sp_core::Bytes;
+use self::error::Result;
+
+pub use self::gen_client::Client as PruningClient;
+
+/// Substrate state pruning maintenance API.
+///
+/// Changes made through this API last until the node is restarted; use
+/// `--pruning-retain-prefix` to make them permanent. `pruning_retainPrefix` and
+/// `pruning_releasePrefix` are unsafe: they are denied on RPC servers exposed to the network.
+#[rpc]
+pub trait PruningApi {
+	/// Storage key prefixes whose state history is retained when pruning.
+	#[rpc(name = "pruning_retainedPrefixes")]
+	fn retained_prefixes(&self) -> RpcResult<Vec<Bytes>>;
+
+	/// Start retaining the state history of keys under `prefix` from the next finalized block.
+	///
+	/// Returns false if the prefix was already retained. Fails if the maximum number of prefixes
+	/// is already retained.
+	#[rpc(name = "pruning_retainPrefix")]
+	fn retain_prefix(&self, prefix: Bytes) -> Result<bool>;
+
+	/// Stop retaining the state history of keys under `prefix`. History retained so far is
+	/// deleted with the next finalized block.
+	///
+	/// Returns false if the prefix wasn't retained.
+	#[rpc(name = "pruning_releasePrefix")]
+	fn release_prefix(&self, prefix: Bytes) -> RpcResult<bool>;
+}
@@ -49,6 +49,10 @@ pub enum Error {
 		/// Maximum allowed value
 		max: u32,
 	},
+	/// The events of the block couldn't be decoded with the runtime metadata.
+	#[display(fmt = "Failed to decode the events: {}", _0)]
+	#[from(ignore)]
+	InvalidEvents(String),
 }
 
 impl std::error::Error for Error {
@@ -76,6 +80,11 @@ impl From<Error> for rpc::Error {
 				message: format!("{}", e),
 				data: None,
 			},
+			Error::InvalidEvents(_) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(BASE_ERROR + 3),
+				message: format!("{}", e),
+				data: None,
+			},
 			e => errors::internal(e),
 		}
 	}
@@ -22,6 +22,7 @@ use jsonrpc_core::Result as RpcResult;
 use jsonrpc_core::futures::Future;
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use serde::{Serialize, Deserialize};
 use sp_core::Bytes;
 use sp_core::storage::{StorageKey, StorageData, StorageChangeSet};
 use sp_version::RuntimeVersion;
@@ -29,6 +30,86 @@ use self::error::FutureResult;
 
 pub use self::gen_client::Client as StateClient;
 
+/// Values of a batch of storage entries read at a single block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageBatch<Hash> {
+	/// Block hash the values were read at.
+	pub at: Hash,
+	/// The value of every requested key, in request order.
+	pub values: Vec<(StorageKey, Option<StorageData>)>,
+	/// Trie nodes proving all the values against the block state root, if requested.
+	pub proof: Option<Vec<Bytes>>,
+}
+
+/// A page of the events of a block, decoded with the runtime metadata.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedEvents<Hash> {
+	/// Number of events in the block.
+	pub total: u32,
+	/// The events of the page.
+	pub events: Vec<DecodedEvent<Hash>>,
+	/// The events from the first event of the page whose fields can't be decoded on, if any.
+	pub undecoded: Option<UndecodedEvents>,
+}
+
+/// Events of a block whose fields can't be decoded with the runtime metadata.
+///
+/// Events are not length prefixed, so the events following an event which can't be decoded can't
+/// be split either.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UndecodedEvents {
+	/// Index of the first event which can't be decoded.
+	pub index: u32,
+	/// Why the event can't be decoded.
+	pub error: String,
+	/// The encoded `EventRecord`s from this event to the last event of the block.
+	pub raw: Bytes,
+}
+
+/// An event decoded with the runtime metadata.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedEvent<Hash> {
+	/// Index of the event in the block.
+	pub index: u32,
+	/// Phase of the block the event was deposited in.
+	pub phase: EventPhase,
+	/// Name of the pallet of the event.
+	pub pallet: String,
+	/// Name of the event.
+	pub variant: String,
+	/// The fields of the event, in order.
+	pub fields: Vec<EventField>,
+	/// The topics of the event.
+	pub topics: Vec<Hash>,
+}
+
+/// Phase of a block.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EventPhase {
+	/// Applying the extrinsic with the given index.
+	ApplyExtrinsic(u32),
+	/// Finalizing the block.
+	Finalization,
+	/// Initializing the block.
+	Initialization,
+}
+
+/// A field of a decoded event.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct EventField {
+	/// The type of the field, as declared by the pallet.
+	#[serde(rename = "type")]
+	pub ty: String,
+	/// The value of the field. Byte arrays and hashes are hex encoded and 128-bit or larger
+	/// integers are decimal strings.
+	pub value: serde_json::Value,
+}
+
 /// Substrate state API
 #[rpc]
 pub trait StateApi<Hash> {
@@ -36,6 +117,9 @@ pub trait StateApi<Hash> {
 	type Metadata;
 
 	/// Call a contract at a block's state.
+	///
+	/// The call is executed by the runtime code of the block, so calls at blocks before a
+	/// runtime upgrade use the runtime of that time.
 	#[rpc(name = "state_call", alias("state_callAt"))]
 	fn call(&self, name: String, bytes: Bytes, hash: Option<Hash>) -> FutureResult<Bytes>;
 
@@ -120,8 +204,12 @@ pub trait StateApi<Hash> {
 	#[rpc(name = "state_getMetadata")]
 	fn metadata(&self, hash: Option<Hash>) -> FutureResult<Bytes>;
 
-	/// Get the runtime version.
-	#[rpc(name = "state_getRuntimeVersion", alias("chain_getRuntimeVersion"))]
+	/// Get the runtime version at a block, which tells the runtime api versions a `state_call` at
+	/// this block can rely on.
+	#[rpc(
+		name = "state_getRuntimeVersion",
+		alias("chain_getRuntimeVersion", "state_runtimeVersionAt")
+	)]
 	fn runtime_version(&self, hash: Option<Hash>) -> FutureResult<RuntimeVersion>;
 
 	/// Query historical storage entries (by key) starting from a block given as the second parameter.
@@ -144,6 +232,29 @@ pub trait StateApi<Hash> {
 		at: Option<Hash>,
 	) -> FutureResult<Vec<StorageChangeSet<Hash>>>;
 
+	/// Query storage entries (by key) at a block in one round trip, optionally with a single
+	/// read proof covering all of them. Duplicate keys are queried once, and at most 1000
+	/// distinct keys are queried per call.
+	#[rpc(name = "state_queryStorageBatchAt")]
+	fn query_storage_batch_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Hash>,
+		with_proof: Option<bool>,
+	) -> FutureResult<StorageBatch<Hash>>;
+
+	/// Returns the events of a block decoded with the runtime metadata of the block.
+	///
+	/// Up to `count` events are returned, starting with the event at index `start`. The events
+	/// from the first event of the page which can't be decoded on are returned encoded.
+	#[rpc(name = "state_decodeEvents")]
+	fn decode_events(
+		&self,
+		hash: Option<Hash>,
+		start: Option<u32>,
+		count: Option<u32>,
+	) -> FutureResult<DecodedEvents<Hash>>;
+
 	/// New runtime version subscription
 	#[pubsub(
 		subscription = "state_runtimeVersion",
@@ -163,9 +274,18 @@ pub trait StateApi<Hash> {
 	fn unsubscribe_runtime_version(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool>;
 
 	/// New storage subscription
+	///
+	/// Changes are delivered for the given `keys` and for keys starting with any of `prefixes`,
+	/// along with all changes in the child tries identified by `child_tries`. Without any filter
+	/// all top level changes are delivered.
 	#[pubsub(subscription = "state_storage", subscribe, name = "state_subscribeStorage")]
 	fn subscribe_storage(
-		&self, metadata: Self::Metadata, subscriber: Subscriber<StorageChangeSet<Hash>>, keys: Option<Vec<StorageKey>>
+		&self,
+		metadata: Self::Metadata,
+		subscriber: Subscriber<StorageChangeSet<Hash>>,
+		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		child_tries: Option<Vec<StorageKey>>,
 	);
 
 	/// Unsubscribe from storage subscription
@@ -14,17 +14,84 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque, hash_map::Entry};
 use std::sync::{Arc, atomic::{self, AtomicUsize}};
 
-use log::{error, warn};
-use jsonrpc_pubsub::{SubscriptionId, typed::{Sink, Subscriber}};
+use log::{debug, error, warn};
+use jsonrpc_pubsub::{PubSubMetadata, Session, SubscriptionId, TransportError, typed::{Sink, Subscriber}};
 use parking_lot::Mutex;
+use jsonrpc_core::{Error, ErrorCode};
 use jsonrpc_core::futures::sync::oneshot;
-use jsonrpc_core::futures::{Future, future};
+use jsonrpc_core::futures::{Async, AsyncSink, Future, Poll, Sink as _, StartSend, future};
+use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use serde::Serialize;
 
 type Id = u64;
 
+/// Error code of the subscriptions rejected because their connection reached its limit.
+pub const TOO_MANY_SUBSCRIPTIONS_ERROR: i64 = -32011;
+
+/// What to do with a notification when the buffer of its subscription is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+	/// Drop the oldest buffered notification, i.e. the subscriber lags but gets the latest ones.
+	DropOldest,
+	/// Drop the new notification.
+	DropNewest,
+	/// Close the subscription.
+	Unsubscribe,
+}
+
+/// The limits of the subscriptions, so that slow subscribers don't make the node buffer an
+/// unbounded number of notifications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionLimits {
+	/// Number of notifications buffered per subscription while its connection is busy.
+	pub buffer: usize,
+	/// What to do with notifications when the buffer is full.
+	pub overflow: OverflowPolicy,
+	/// Maximum number of subscriptions per connection.
+	pub max_per_connection: usize,
+}
+
+impl Default for SubscriptionLimits {
+	fn default() -> Self {
+		SubscriptionLimits {
+			buffer: 256,
+			overflow: OverflowPolicy::DropOldest,
+			max_per_connection: 128,
+		}
+	}
+}
+
+/// Prometheus metrics of the subscriptions.
+#[derive(Clone)]
+pub struct SubscriptionMetrics {
+	dropped_notifications: Counter<U64>,
+	closed_subscriptions: Counter<U64>,
+	rejected_subscriptions: Counter<U64>,
+}
+
+impl SubscriptionMetrics {
+	/// Register the metrics in the given registry.
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(SubscriptionMetrics {
+			dropped_notifications: register(Counter::new(
+				"rpc_subscription_notifications_dropped",
+				"Number of notifications dropped because the buffer of their subscription was full",
+			)?, registry)?,
+			closed_subscriptions: register(Counter::new(
+				"rpc_subscriptions_closed_on_overflow",
+				"Number of subscriptions closed because their buffer was full",
+			)?, registry)?,
+			rejected_subscriptions: register(Counter::new(
+				"rpc_subscriptions_rejected",
+				"Number of subscriptions rejected because their connection reached its limit",
+			)?, registry)?,
+		})
+	}
+}
+
 /// Alias for a an implementation of `futures::future::Executor`.
 pub type TaskExecutor = Arc<dyn future::Executor<Box<dyn Future<Item = (), Error = ()> + Send>> + Send + Sync>;
 
@@ -56,15 +123,31 @@ impl IdProvider {
 pub struct Subscriptions {
 	next_id: IdProvider,
 	active_subscriptions: Arc<Mutex<HashMap<Id, oneshot::Sender<()>>>>,
+	/// The subscriptions of each open connection, by address of its session.
+	connections: Arc<Mutex<HashMap<usize, HashSet<Id>>>>,
+	limits: SubscriptionLimits,
+	metrics: Option<SubscriptionMetrics>,
 	executor: TaskExecutor,
 }
 
 impl Subscriptions {
 	/// Creates new `Subscriptions` object.
 	pub fn new(executor: TaskExecutor) -> Self {
+		Self::with_limits(executor, Default::default(), None)
+	}
+
+	/// Creates new `Subscriptions` object enforcing the given limits.
+	pub fn with_limits(
+		executor: TaskExecutor,
+		limits: SubscriptionLimits,
+		metrics: Option<SubscriptionMetrics>,
+	) -> Self {
 		Subscriptions {
 			next_id: Default::default(),
 			active_subscriptions: Default::default(),
+			connections: Default::default(),
+			limits,
+			metrics,
 			executor,
 		}
 	}
@@ -76,34 +159,109 @@ impl Subscriptions {
 		&self.executor
 	}
 
-	/// Creates new subscription for given subscriber.
+	/// Creates new subscription for given subscriber, on the connection of the given metadata.
 	///
-	/// Second parameter is a function that converts Subscriber sink into a future.
+	/// The subscriber is rejected if its connection reached its limit of subscriptions.
+	///
+	/// Third parameter is a function that converts Subscriber sink into a future.
 	/// This future will be driven to completion by the underlying event loop
 	/// or will be cancelled in case #cancel is invoked.
-	pub fn add<T, E, G, R, F>(&self, subscriber: Subscriber<T, E>, into_future: G) -> SubscriptionId where
-		G: FnOnce(Sink<T, E>) -> R,
+	pub fn add<M, T, E, G, R, F>(
+		&self,
+		meta: &M,
+		subscriber: Subscriber<T, E>,
+		into_future: G,
+	) -> SubscriptionId where
+		M: PubSubMetadata,
+		G: FnOnce(BoundedSink<T, E>) -> R,
 		R: future::IntoFuture<Future=F, Item=(), Error=()>,
 		F: future::Future<Item=(), Error=()> + Send + 'static,
 	{
 		let id = self.next_id.next_id();
 		let subscription_id: SubscriptionId = id.into();
-		if let Ok(sink) = subscriber.assign_id(subscription_id.clone()) {
-			let (tx, rx) = oneshot::channel();
-			let future = into_future(sink)
-				.into_future()
-				.select(rx.map_err(|e| warn!("Error timeing out: {:?}", e)))
-				.then(|_| Ok(()));
-
-			self.active_subscriptions.lock().insert(id, tx);
-			if self.executor.execute(Box::new(future)).is_err() {
-				error!("Failed to spawn RPC subscription task");
-			}
+
+		let connection = match self.add_to_connection(meta.session(), id) {
+			Ok(connection) => connection,
+			Err(()) => {
+				debug!(target: "rpc", "Rejected subscription, too many subscriptions on the connection");
+				if let Some(ref metrics) = self.metrics {
+					metrics.rejected_subscriptions.inc();
+				}
+				let _ = subscriber.reject(Error {
+					code: ErrorCode::ServerError(TOO_MANY_SUBSCRIPTIONS_ERROR),
+					message: "Too many subscriptions on the connection".into(),
+					data: None,
+				});
+				return subscription_id;
+			},
+		};
+
+		match subscriber.assign_id(subscription_id.clone()) {
+			Ok(sink) => {
+				let sink = BoundedSink::new(sink, &self.limits, self.metrics.clone());
+				let (tx, rx) = oneshot::channel();
+				let subscriptions = self.clone();
+				let future = into_future(sink)
+					.into_future()
+					.select(rx.map_err(|e| warn!("Error timeing out: {:?}", e)))
+					.then(move |_| {
+						subscriptions.remove_from_connection(connection, id);
+						Ok(())
+					});
+
+				self.active_subscriptions.lock().insert(id, tx);
+				if self.executor.execute(Box::new(future)).is_err() {
+					error!("Failed to spawn RPC subscription task");
+				}
+			},
+			Err(_) => self.remove_from_connection(connection, id),
 		}
 
 		subscription_id
 	}
 
+	/// Count the subscription in the subscriptions of its connection, if any.
+	///
+	/// Returns the connection, or an error if it reached its limit of subscriptions.
+	fn add_to_connection(&self, session: Option<Arc<Session>>, id: Id) -> Result<Option<usize>, ()> {
+		let session = match session {
+			Some(session) => session,
+			None => return Ok(None),
+		};
+		let connection = &*session as *const Session as usize;
+
+		let mut connections = self.connections.lock();
+		let subscriptions = match connections.entry(connection) {
+			Entry::Occupied(entry) => entry.into_mut(),
+			Entry::Vacant(entry) => {
+				// cancel the subscriptions of the connection once it's closed. The connection is
+				// known until then, so that this is only registered once per session.
+				let this = self.clone();
+				session.on_drop(move || {
+					let ids = this.connections.lock().remove(&connection).unwrap_or_default();
+					for id in ids {
+						this.cancel(SubscriptionId::Number(id));
+					}
+				});
+				entry.insert(HashSet::new())
+			},
+		};
+		if subscriptions.len() >= self.limits.max_per_connection {
+			return Err(());
+		}
+
+		subscriptions.insert(id);
+		Ok(Some(connection))
+	}
+
+	fn remove_from_connection(&self, connection: Option<usize>, id: Id) {
+		if let Some(connection) = connection {
+			if let Some(subscriptions) = self.connections.lock().get_mut(&connection) {
+				subscriptions.remove(&id);
+			}
+		}
+	}
+
 	/// Cancel subscription.
 	///
 	/// Returns true if subscription existed or false otherwise.
@@ -117,3 +275,214 @@ impl Subscriptions {
 		false
 	}
 }
+
+/// Error of a `BoundedSink`.
+#[derive(Debug)]
+pub enum SinkError {
+	/// The connection of the subscription is closed.
+	Transport(TransportError),
+	/// The buffer of the subscription is full and the subscription is closed.
+	Overflow,
+}
+
+/// A sink of notifications that never blocks the stream of notifications: they are buffered
+/// while the connection is busy, and handled according to the `OverflowPolicy` once the buffer
+/// is full.
+pub struct BoundedSink<T, E> {
+	inner: Sink<T, E>,
+	buffer: VecDeque<Result<T, E>>,
+	capacity: usize,
+	overflow: OverflowPolicy,
+	metrics: Option<SubscriptionMetrics>,
+}
+
+impl<T, E> BoundedSink<T, E> {
+	fn new(inner: Sink<T, E>, limits: &SubscriptionLimits, metrics: Option<SubscriptionMetrics>) -> Self {
+		BoundedSink {
+			inner,
+			buffer: VecDeque::new(),
+			capacity: limits.buffer.max(1),
+			overflow: limits.overflow,
+			metrics,
+		}
+	}
+}
+
+impl<T: Serialize, E: Serialize> BoundedSink<T, E> {
+	/// Send the buffered notifications until the connection is busy.
+	fn send_buffered(&mut self) -> Result<(), SinkError> {
+		while let Some(item) = self.buffer.pop_front() {
+			if let AsyncSink::NotReady(item) = self.inner.start_send(item).map_err(SinkError::Transport)? {
+				self.buffer.push_front(item);
+				break;
+			}
+		}
+		Ok(())
+	}
+}
+
+impl<T: Serialize, E: Serialize> jsonrpc_core::futures::Sink for BoundedSink<T, E> {
+	type SinkItem = Result<T, E>;
+	type SinkError = SinkError;
+
+	fn start_send(&mut self, item: Self::SinkItem) -> StartSend<Self::SinkItem, Self::SinkError> {
+		self.send_buffered()?;
+
+		if self.buffer.len() >= self.capacity {
+			if let Some(ref metrics) = self.metrics {
+				match self.overflow {
+					OverflowPolicy::Unsubscribe => metrics.closed_subscriptions.inc(),
+					_ => metrics.dropped_notifications.inc(),
+				}
+			}
+			match self.overflow {
+				OverflowPolicy::DropOldest => {
+					self.buffer.pop_front();
+				},
+				OverflowPolicy::DropNewest => return Ok(AsyncSink::Ready),
+				OverflowPolicy::Unsubscribe => return Err(SinkError::Overflow),
+			}
+		}
+
+		self.buffer.push_back(item);
+		Ok(AsyncSink::Ready)
+	}
+
+	fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
+		self.send_buffered()?;
+		if !self.buffer.is_empty() {
+			return Ok(Async::NotReady);
+		}
+		self.inner.poll_complete().map_err(SinkError::Transport)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::futures::{Stream, sync::mpsc};
+
+	#[derive(Clone, Default)]
+	struct TestMetadata(Option<Arc<Session>>);
+
+	impl jsonrpc_core::Metadata for TestMetadata {}
+
+	impl PubSubMetadata for TestMetadata {
+		fn session(&self) -> Option<Arc<Session>> {
+			self.0.clone()
+		}
+	}
+
+	struct NoopExecutor;
+
+	impl future::Executor<Box<dyn Future<Item = (), Error = ()> + Send>> for NoopExecutor {
+		fn execute(
+			&self,
+			_: Box<dyn Future<Item = (), Error = ()> + Send>,
+		) -> Result<(), future::ExecuteError<Box<dyn Future<Item = (), Error = ()> + Send>>> {
+			Ok(())
+		}
+	}
+
+	fn bounded_sink(overflow: OverflowPolicy) -> (BoundedSink<u64, ()>, mpsc::Receiver<String>) {
+		let (subscriber, _, transport) = Subscriber::new_test("test_notification");
+		let sink = subscriber.assign_id(SubscriptionId::Number(1)).unwrap();
+		let limits = SubscriptionLimits { buffer: 2, overflow, ..Default::default() };
+		(BoundedSink::new(sink, &limits, None), transport)
+	}
+
+	fn result(notification: &str) -> u64 {
+		let notification: serde_json::Value = serde_json::from_str(notification).unwrap();
+		notification["params"]["result"].as_u64().unwrap()
+	}
+
+	#[test]
+	fn oldest_notifications_are_dropped_when_the_buffer_is_full() {
+		let (mut sink, mut transport) = bounded_sink(OverflowPolicy::DropOldest);
+
+		let received = future::lazy(move || {
+			for i in 0..10 {
+				assert!(sink.start_send(Ok(i)).unwrap().is_ready());
+			}
+
+			let mut received = Vec::new();
+			loop {
+				let _ = sink.poll_complete();
+				match transport.poll() {
+					Ok(Async::Ready(Some(notification))) => received.push(result(&notification)),
+					_ => break,
+				}
+			}
+			assert!(sink.buffer.is_empty());
+			Ok::<_, ()>(received)
+		}).wait().unwrap();
+
+		// the notifications sent before the connection was busy, then the latest ones.
+		let sent = received.len() - 2;
+		assert!(sent < 8);
+		assert_eq!(received, (0..sent as u64).chain(8..10).collect::<Vec<_>>());
+	}
+
+	#[test]
+	fn subscription_is_closed_when_the_buffer_is_full() {
+		let (mut sink, _transport) = bounded_sink(OverflowPolicy::Unsubscribe);
+
+		future::lazy(move || {
+			let mut sent = 0;
+			while sink.start_send(Ok(sent)).is_ok() {
+				sent += 1;
+				assert!(sent < 10);
+			}
+			Ok::<_, ()>(())
+		}).wait().unwrap();
+	}
+
+	#[test]
+	fn subscriptions_are_limited_per_connection() {
+		let subscriptions = Subscriptions::with_limits(
+			Arc::new(NoopExecutor),
+			SubscriptionLimits { max_per_connection: 2, ..Default::default() },
+			None,
+		);
+		let (transport, _) = mpsc::channel(1);
+		let connection = TestMetadata(Some(Arc::new(Session::new(transport))));
+
+		let subscribe = |meta: &TestMetadata| {
+			let (subscriber, id, _) = Subscriber::<u64>::new_test("test_notification");
+			subscriptions.add(meta, subscriber, |_| Ok(()));
+			id.wait().unwrap()
+		};
+
+		assert!(subscribe(&connection).is_ok());
+		assert!(subscribe(&connection).is_ok());
+		assert_eq!(
+			subscribe(&connection).unwrap_err().code,
+			ErrorCode::ServerError(TOO_MANY_SUBSCRIPTIONS_ERROR),
+		);
+		// other connections have their own limit.
+		assert!(subscribe(&TestMetadata::default()).is_ok());
+
+		drop(connection);
+		assert!(subscriptions.connections.lock().is_empty());
+	}
+
+	#[test]
+	fn connections_are_watched_once() {
+		let subscriptions = Subscriptions::new(Arc::new(NoopExecutor));
+		let (transport, _) = mpsc::channel(1);
+		let session = Arc::new(Session::new(transport));
+		let handles = Arc::strong_count(&subscriptions.connections);
+
+		for id in 0..3 {
+			let connection = subscriptions.add_to_connection(Some(session.clone()), id).unwrap();
+			subscriptions.remove_from_connection(connection, id);
+		}
+		// a single `on_drop` callback of the session holds the subscriptions.
+		assert_eq!(Arc::strong_count(&subscriptions.connections), handles + 1);
+		assert_eq!(subscriptions.connections.lock().len(), 1);
+
+		drop(session);
+		assert!(subscriptions.connections.lock().is_empty());
+		assert_eq!(Arc::strong_count(&subscriptions.connections), handles);
+	}
+}
@@ -32,6 +32,8 @@ pub enum Error {
 	MalformattedPeerArg(String),
 	/// Reserved Peer Commands Disabled
 	ReservedPeerCommandsDisabled,
+	/// IP range argument is malformatted.
+	MalformattedIpRange(String),
 }
 
 impl std::error::Error for Error {}
@@ -56,7 +58,12 @@ impl From<Error> for rpc::Error {
 				code :rpc::ErrorCode::ServerError(BASE_ERROR + 3),
 				message: "Reserved Peer Commands Disabled".to_string(),
 				data: None,
-			}
+			},
+			Error::MalformattedIpRange(ref e) => rpc::Error {
+				code :rpc::ErrorCode::ServerError(BASE_ERROR + 4),
+				message: e.clone(),
+				data: None,
+			},
 		}
 	}
 }
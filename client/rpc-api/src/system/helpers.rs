@@ -34,6 +34,8 @@ pub struct SystemInfo {
 	pub chain_name: String,
 	/// A custom set of properties defined in the chain spec.
 	pub properties: Properties,
+	/// The host functions provided by the node.
+	pub host_functions: Vec<HostFunction>,
 }
 
 /// Health struct returned by the RPC
@@ -87,6 +89,51 @@ pub enum NodeRole {
 	UnknownRole(u8)
 }
 
+/// How the membership of the private network is enforced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum PrivacyMode {
+	/// The node has no membership validator, every peer may connect.
+	Disabled,
+	/// The node has a membership validator, but the runtime doesn't restrict membership.
+	Open,
+	/// Only the members of the private network may connect.
+	Enforced,
+}
+
+/// The state of the enforcement of the membership of the private network.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivacyStatus {
+	/// How membership is enforced.
+	pub mode: PrivacyMode,
+	/// Number of reserved peers.
+	pub reserved_peers: usize,
+	/// Number of peers disconnected because they are not members.
+	pub refused_connections: u64,
+	/// Number of members.
+	pub members: usize,
+	/// Number of authorized light clients that are not members.
+	pub light_clients: usize,
+	/// Number of the last block the members were successfully retrieved at.
+	pub last_sync_block: Option<u64>,
+	/// Number of seconds since the members were last successfully retrieved.
+	pub seconds_since_last_sync: Option<u64>,
+}
+
+/// A host function provided by the node to the wasm runtime.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HostFunction {
+	/// The name of the host function, as imported by the wasm runtime.
+	pub name: String,
+	/// The version of the host function.
+	pub version: u32,
+	/// The FFI types of the arguments.
+	pub args: Vec<String>,
+	/// The FFI type of the return value.
+	pub return_value: Option<String>,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -116,4 +163,33 @@ mod tests {
 			r#"{"peerId":"2","roles":"a","protocolVersion":2,"bestHash":5,"bestNumber":6}"#,
 		);
 	}
+
+	#[test]
+	fn should_serialize_privacy_status() {
+		assert_eq!(
+			::serde_json::to_string(&PrivacyStatus {
+				mode: PrivacyMode::Enforced,
+				reserved_peers: 3,
+				refused_connections: 7,
+				members: 4,
+				light_clients: 1,
+				last_sync_block: Some(10),
+				seconds_since_last_sync: None,
+			}).unwrap(),
+			r#"{"mode":"Enforced","reservedPeers":3,"refusedConnections":7,"members":4,"lightClients":1,"lastSyncBlock":10,"secondsSinceLastSync":null}"#,
+		);
+	}
+
+	#[test]
+	fn should_serialize_host_function() {
+		assert_eq!(
+			::serde_json::to_string(&HostFunction {
+				name: "ext_storage_get_version_1".into(),
+				version: 1,
+				args: vec!["I64".into()],
+				return_value: Some("I64".into()),
+			}).unwrap(),
+			r#"{"name":"ext_storage_get_version_1","version":1,"args":["I64"],"returnValue":"I64"}"#,
+		);
+	}
 }
@@ -25,7 +25,9 @@ use futures::{future::BoxFuture, compat::Compat};
 
 use self::error::Result as SystemResult;
 
-pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole};
+pub use self::helpers::{
+	Properties, SystemInfo, Health, PeerInfo, NodeRole, HostFunction, PrivacyMode, PrivacyStatus,
+};
 pub use self::gen_client::Client as SystemClient;
 
 /// Substrate system RPC API
@@ -47,6 +49,13 @@ pub trait SystemApi<Hash, Number> {
 	#[rpc(name = "system_properties")]
 	fn system_properties(&self) -> SystemResult<Properties>;
 
+	/// Get the host functions provided by the node.
+	///
+	/// Can be used to check if the host functions imported by a runtime are provided by this
+	/// node before upgrading to that runtime.
+	#[rpc(name = "system_hostFunctions")]
+	fn system_host_functions(&self) -> SystemResult<Vec<HostFunction>>;
+
 	/// Return health status of the node.
 	///
 	/// Node is considered healthy if it is:
@@ -81,7 +90,29 @@ pub trait SystemApi<Hash, Number> {
 	fn system_remove_reserved_peer(&self, peer_id: String)
 		-> Compat<BoxFuture<'static, Result<(), jsonrpc_core::Error>>>;
 
+	/// Replaces the IP address ranges the node accepts connections from and dials. Ranges use the
+	/// CIDR notation, e.g. `10.0.0.0/8`. An empty `allow` list allows every address that isn't
+	/// in `deny`. Only applies to new connections. Domain names of addresses are resolved
+	/// before the ranges are checked.
+	///
+	/// This method is unsafe: it is denied by the servers that don't expose the unsafe methods.
+	#[rpc(name = "system_setIpPolicy", returns = "()")]
+	fn system_set_ip_policy(&self, allow: Vec<String>, deny: Vec<String>)
+		-> Compat<BoxFuture<'static, Result<(), jsonrpc_core::Error>>>;
+
+	/// Returns the reputation of a peer, or `null` if the peer is not known. The string
+	/// should encode only the PeerId e.g. `QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV`.
+	#[rpc(name = "system_peerReputation", returns = "Option<i32>")]
+	fn system_peer_reputation(&self, peer_id: String)
+		-> Compat<BoxFuture<'static, Result<Option<i32>, jsonrpc_core::Error>>>;
+
 	/// Returns the roles the node is running as.
 	#[rpc(name = "system_nodeRoles", returns = "Vec<NodeRole>")]
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>>;
+
+	/// Returns the state of the enforcement of the membership of the private network: the
+	/// enforcement mode, the number of reserved peers, members and authorized light clients,
+	/// the number of refused connections and the last block the members were retrieved at.
+	#[rpc(name = "system_privacyStatus", returns = "PrivacyStatus")]
+	fn system_privacy_status(&self) -> Receiver<PrivacyStatus>;
 }
@@ -0,0 +1,367 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Execution of the gRPC calls as JSON-RPC calls.
+
+use std::{pin::Pin, sync::{Arc, atomic::{AtomicU64, Ordering}}};
+
+use futures::{Stream, StreamExt, compat::{Future01CompatExt, Stream01CompatExt}};
+use jsonrpc_core::{Error, ErrorCode, Output};
+use sc_rpc::Metadata;
+use sc_rpc_server::{AccessMetadata, Credentials, RpcHandler, METHOD_NOT_ALLOWED_ERROR};
+use serde_json::{json, Value};
+use sp_core::{Bytes, hashing::sha2_256, hexdisplay::HexDisplay};
+use tonic::{Code, Request, Response, Status};
+
+use crate::proto::{
+	self, author_server::Author, chain_server::Chain, network_privacy_server::NetworkPrivacy,
+	state_server::State,
+};
+use crate::runtime_proto;
+
+/// Number of notifications of a subscription buffered for its gRPC stream.
+const SUBSCRIPTION_BUFFER: usize = 64;
+
+/// A stream of the notifications of a subscription.
+pub type JsonStream = Pin<Box<dyn Stream<Item = Result<proto::Json, Status>> + Send + Sync>>;
+
+/// Executes the gRPC calls as JSON-RPC calls with the RPC handler of the node.
+#[derive(Clone)]
+pub struct Gateway {
+	handler: Arc<RpcHandler<Metadata>>,
+	next_id: Arc<AtomicU64>,
+	/// Whether the handler enforces an RPC access policy.
+	access_policy: bool,
+}
+
+impl Gateway {
+	/// Creates a new gateway to the given RPC handler, enforcing an RPC access policy or not.
+	pub fn new(handler: RpcHandler<Metadata>, access_policy: bool) -> Self {
+		Gateway {
+			handler: Arc::new(handler),
+			next_id: Default::default(),
+			access_policy,
+		}
+	}
+
+	/// Fails unless the calls are authenticated by an RPC access policy, as required by the
+	/// network privacy administration.
+	fn ensure_access_policy(&self) -> Result<(), Status> {
+		if self.access_policy {
+			Ok(())
+		} else {
+			Err(Status::permission_denied(
+				"The network privacy administration requires an RPC access policy (--rpc-access-policy)"
+			))
+		}
+	}
+
+	/// Execute a JSON-RPC call, returning its result.
+	async fn call(&self, meta: Metadata, method: &str, params: Value) -> Result<Value, Status> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string();
+		let response = self.handler.handle_request(&request, meta).compat().await
+			.map_err(|_| Status::internal("The RPC handler failed"))?
+			.ok_or_else(|| Status::internal("The RPC handler returned no response"))?;
+
+		match serde_json::from_str(&response) {
+			Ok(Output::Success(success)) => Ok(success.result),
+			Ok(Output::Failure(failure)) => Err(status(failure.error)),
+			Err(e) => Err(Status::internal(format!("Invalid RPC response: {}", e))),
+		}
+	}
+
+	/// Subscribe with a JSON-RPC call, returning the stream of the results of the notifications.
+	///
+	/// The subscription is cancelled once the stream is dropped.
+	async fn subscribe(&self, credentials: Credentials, method: &str, params: Value) -> Result<JsonStream, Status> {
+		let (sender, notifications) = futures01::sync::mpsc::channel(SUBSCRIPTION_BUFFER);
+		let mut meta = Metadata::new(sender);
+		meta.set_credentials(credentials);
+		self.call(meta.clone(), method, params).await?;
+
+		let (mut tx, rx) = tokio::sync::mpsc::channel(SUBSCRIPTION_BUFFER);
+		tokio::spawn(async move {
+			// the session of the subscription, which unsubscribes when dropped.
+			let _session = meta;
+			let mut notifications = notifications.compat();
+			while let Some(Ok(notification)) = notifications.next().await {
+				if tx.send(notification_result(&notification)).await.is_err() {
+					break;
+				}
+			}
+		});
+
+		Ok(Box::pin(rx))
+	}
+}
+
+/// The credentials of the client, from the metadata of the request and the TLS certificate it was
+/// authenticated with.
+fn credentials<T>(request: &Request<T>) -> Credentials {
+	let mut credentials = Credentials::from_headers(|name| {
+		request.metadata().get(name).and_then(|value| value.to_str().ok())
+	});
+	credentials.cert_fingerprint = request.peer_certs()
+		.and_then(|certs| certs.first().map(|cert| cert_fingerprint(cert.get_ref())));
+	credentials
+}
+
+/// The SHA-256 fingerprint of a DER encoded certificate, lower case hex.
+fn cert_fingerprint(der: &[u8]) -> String {
+	HexDisplay::from(&sha2_256(der)).to_string()
+}
+
+fn metadata<T>(request: &Request<T>) -> Metadata {
+	let mut meta = Metadata::default();
+	meta.set_credentials(credentials(request));
+	meta
+}
+
+fn status(error: Error) -> Status {
+	let code = match error.code {
+		ErrorCode::ServerError(METHOD_NOT_ALLOWED_ERROR) => Code::PermissionDenied,
+		ErrorCode::ParseError | ErrorCode::InvalidRequest | ErrorCode::InvalidParams => Code::InvalidArgument,
+		ErrorCode::MethodNotFound => Code::Unimplemented,
+		_ => Code::Unknown,
+	};
+	Status::new(code, error.message)
+}
+
+fn notification_result(notification: &str) -> Result<proto::Json, Status> {
+	let notification: Value = serde_json::from_str(notification)
+		.map_err(|e| Status::internal(format!("Invalid RPC notification: {}", e)))?;
+	Ok(proto::Json { json: notification["params"]["result"].to_string() })
+}
+
+/// The block parameter of a JSON-RPC call, `null` for the best block.
+fn at(hash: Vec<u8>) -> Value {
+	if hash.is_empty() {
+		Value::Null
+	} else {
+		json!(Bytes(hash))
+	}
+}
+
+fn found(value: Value, what: &str) -> Result<Value, Status> {
+	if value.is_null() {
+		Err(Status::not_found(format!("{} not found", what)))
+	} else {
+		Ok(value)
+	}
+}
+
+fn bytes(value: Value) -> Result<Vec<u8>, Status> {
+	serde_json::from_value::<Bytes>(value)
+		.map(|bytes| bytes.0)
+		.map_err(|e| Status::internal(format!("Invalid RPC result: {}", e)))
+}
+
+fn json(value: Value) -> Response<proto::Json> {
+	Response::new(proto::Json { json: value.to_string() })
+}
+
+#[tonic::async_trait]
+impl Chain for Gateway {
+	type SubscribeNewHeadsStream = JsonStream;
+	type SubscribeFinalizedHeadsStream = JsonStream;
+
+	async fn get_header(&self, request: Request<proto::BlockRequest>) -> Result<Response<proto::Json>, Status> {
+		let meta = metadata(&request);
+		let header = self.call(meta, "chain_getHeader", json!([at(request.into_inner().hash)])).await?;
+		found(header, "Block").map(json)
+	}
+
+	async fn get_block(&self, request: Request<proto::BlockRequest>) -> Result<Response<proto::Json>, Status> {
+		let meta = metadata(&request);
+		let block = self.call(meta, "chain_getBlock", json!([at(request.into_inner().hash)])).await?;
+		found(block, "Block").map(json)
+	}
+
+	async fn get_block_hash(&self, request: Request<proto::BlockNumber>) -> Result<Response<proto::Hash>, Status> {
+		let meta = metadata(&request);
+		let hash = self.call(meta, "chain_getBlockHash", json!([request.into_inner().number])).await?;
+		Ok(Response::new(proto::Hash { hash: bytes(found(hash, "Block")?)? }))
+	}
+
+	async fn get_finalized_head(&self, request: Request<proto::Empty>) -> Result<Response<proto::Hash>, Status> {
+		let hash = self.call(metadata(&request), "chain_getFinalizedHead", json!([])).await?;
+		Ok(Response::new(proto::Hash { hash: bytes(hash)? }))
+	}
+
+	async fn subscribe_new_heads(&self, request: Request<proto::Empty>) -> Result<Response<JsonStream>, Status> {
+		self.subscribe(credentials(&request), "chain_subscribeNewHeads", json!([])).await.map(Response::new)
+	}
+
+	async fn subscribe_finalized_heads(&self, request: Request<proto::Empty>) -> Result<Response<JsonStream>, Status> {
+		self.subscribe(credentials(&request), "chain_subscribeFinalizedHeads", json!([])).await.map(Response::new)
+	}
+}
+
+#[tonic::async_trait]
+impl State for Gateway {
+	async fn get_storage(&self, request: Request<proto::StorageRequest>) -> Result<Response<proto::StorageValue>, Status> {
+		let meta = metadata(&request);
+		let request = request.into_inner();
+		let value = self.call(meta, "state_getStorage", json!([Bytes(request.key), at(request.at)])).await?;
+		Ok(Response::new(match value {
+			Value::Null => proto::StorageValue { exists: false, value: Vec::new() },
+			value => proto::StorageValue { exists: true, value: bytes(value)? },
+		}))
+	}
+
+	async fn call(&self, request: Request<proto::CallRequest>) -> Result<Response<proto::Bytes>, Status> {
+		let meta = metadata(&request);
+		let request = request.into_inner();
+		let params = json!([request.method, Bytes(request.data), at(request.at)]);
+		let result = Gateway::call(self, meta, "state_call", params).await?;
+		Ok(Response::new(proto::Bytes { data: bytes(result)? }))
+	}
+
+	async fn get_metadata(&self, request: Request<proto::BlockRequest>) -> Result<Response<proto::Bytes>, Status> {
+		let meta = metadata(&request);
+		let metadata = Gateway::call(self, meta, "state_getMetadata", json!([at(request.into_inner().hash)])).await?;
+		Ok(Response::new(proto::Bytes { data: bytes(metadata)? }))
+	}
+
+	async fn get_runtime_version(&self, request: Request<proto::BlockRequest>) -> Result<Response<proto::Json>, Status> {
+		let meta = metadata(&request);
+		let version = Gateway::call(self, meta, "state_getRuntimeVersion", json!([at(request.into_inner().hash)])).await?;
+		Ok(json(version))
+	}
+
+	async fn get_runtime_proto(&self, request: Request<proto::BlockRequest>) -> Result<Response<proto::RuntimeProto>, Status> {
+		let meta = metadata(&request);
+		let metadata = Gateway::call(self, meta, "state_getMetadata", json!([at(request.into_inner().hash)])).await?;
+		let proto = runtime_proto(&bytes(metadata)?).map_err(Status::internal)?;
+		Ok(Response::new(proto::RuntimeProto { proto }))
+	}
+
+	async fn decode_events(&self, request: Request<proto::EventsRequest>) -> Result<Response<proto::Json>, Status> {
+		let meta = metadata(&request);
+		let request = request.into_inner();
+		let count = if request.count == 0 { Value::Null } else { json!(request.count) };
+		let params = json!([at(request.hash), request.start, count]);
+		let events = Gateway::call(self, meta, "state_decodeEvents", params).await?;
+		Ok(json(events))
+	}
+}
+
+#[tonic::async_trait]
+impl Author for Gateway {
+	type SubmitAndWatchExtrinsicStream = JsonStream;
+
+	async fn submit_extrinsic(&self, request: Request<proto::Extrinsic>) -> Result<Response<proto::Hash>, Status> {
+		let meta = metadata(&request);
+		let hash = self.call(meta, "author_submitExtrinsic", json!([Bytes(request.into_inner().extrinsic)])).await?;
+		Ok(Response::new(proto::Hash { hash: bytes(hash)? }))
+	}
+
+	async fn pending_extrinsics(&self, request: Request<proto::Empty>) -> Result<Response<proto::Extrinsics>, Status> {
+		let extrinsics = self.call(metadata(&request), "author_pendingExtrinsics", json!([])).await?;
+		let extrinsics = serde_json::from_value::<Vec<Bytes>>(extrinsics)
+			.map_err(|e| Status::internal(format!("Invalid RPC result: {}", e)))?;
+		Ok(Response::new(proto::Extrinsics {
+			extrinsics: extrinsics.into_iter().map(|extrinsic| extrinsic.0).collect(),
+		}))
+	}
+
+	async fn submit_and_watch_extrinsic(&self, request: Request<proto::Extrinsic>) -> Result<Response<JsonStream>, Status> {
+		let credentials = credentials(&request);
+		let params = json!([Bytes(request.into_inner().extrinsic)]);
+		self.subscribe(credentials, "author_submitAndWatchExtrinsic", params).await.map(Response::new)
+	}
+}
+
+#[tonic::async_trait]
+impl NetworkPrivacy for Gateway {
+	type SubscribeAccessEventsStream = JsonStream;
+
+	async fn status(&self, request: Request<proto::Empty>) -> Result<Response<proto::Json>, Status> {
+		self.ensure_access_policy()?;
+		self.call(metadata(&request), "system_privacyStatus", json!([])).await.map(json)
+	}
+
+	async fn add_reserved_peer(&self, request: Request<proto::PeerAddress>) -> Result<Response<proto::Empty>, Status> {
+		self.ensure_access_policy()?;
+		let meta = metadata(&request);
+		self.call(meta, "system_addReservedPeer", json!([request.into_inner().address])).await?;
+		Ok(Response::new(proto::Empty {}))
+	}
+
+	async fn remove_reserved_peer(&self, request: Request<proto::PeerId>) -> Result<Response<proto::Empty>, Status> {
+		self.ensure_access_policy()?;
+		let meta = metadata(&request);
+		self.call(meta, "system_removeReservedPeer", json!([request.into_inner().peer_id])).await?;
+		Ok(Response::new(proto::Empty {}))
+	}
+
+	async fn set_ip_policy(&self, request: Request<proto::IpPolicy>) -> Result<Response<proto::Empty>, Status> {
+		self.ensure_access_policy()?;
+		let meta = metadata(&request);
+		let policy = request.into_inner();
+		self.call(meta, "system_setIpPolicy", json!([policy.allow, policy.deny])).await?;
+		Ok(Response::new(proto::Empty {}))
+	}
+
+	async fn peer_reputation(&self, request: Request<proto::PeerId>) -> Result<Response<proto::Reputation>, Status> {
+		self.ensure_access_policy()?;
+		let meta = metadata(&request);
+		let reputation = self.call(meta, "system_peerReputation", json!([request.into_inner().peer_id])).await?;
+		Ok(Response::new(match reputation.as_i64() {
+			Some(reputation) => proto::Reputation { known: true, reputation: reputation as i32 },
+			None => proto::Reputation { known: false, reputation: 0 },
+		}))
+	}
+
+	async fn subscribe_access_events(&self, request: Request<proto::Empty>) -> Result<Response<JsonStream>, Status> {
+		self.ensure_access_policy()?;
+		self.subscribe(credentials(&request), "network_subscribeAccessEvents", json!([])).await.map(Response::new)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn rpc_errors_map_to_grpc_codes() {
+		let denied = Error {
+			code: ErrorCode::ServerError(METHOD_NOT_ALLOWED_ERROR),
+			message: "Method author_submitExtrinsic is not allowed".into(),
+			data: None,
+		};
+		assert_eq!(status(denied).code(), Code::PermissionDenied);
+		assert_eq!(status(Error::invalid_params("bad hash")).code(), Code::InvalidArgument);
+		assert_eq!(status(Error::method_not_found()).code(), Code::Unimplemented);
+	}
+
+	#[test]
+	fn certificate_fingerprints_are_lower_case_hex() {
+		assert_eq!(
+			cert_fingerprint(b"abc"),
+			"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+		);
+	}
+
+	#[test]
+	fn notification_results_are_forwarded() {
+		let notification = r#"{"jsonrpc":"2.0","method":"chain_newHead","params":{"result":{"number":"0x1"},"subscription":1}}"#;
+		assert_eq!(notification_result(notification).unwrap().json, r#"{"number":"0x1"}"#);
+		assert_eq!(at(Vec::new()), Value::Null);
+		assert_eq!(at(vec![1, 2]), json!("0x0102"));
+	}
+}
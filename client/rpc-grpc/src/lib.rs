@@ -0,0 +1,143 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! gRPC gateway to the node RPC.
+//!
+//! Serves the chain, state, author and network privacy APIs over gRPC, as defined in
+//! `proto/node.proto`. The calls are executed by the JSON-RPC handler of the node, with the same
+//! middleware, so the RPC access policy applies: API keys are presented in the `authorization`
+//! (`Bearer <key>`) or the `x-api-key` metadata of the requests. With TLS, the server authenticates
+//! the client certificates signed by the client CA, whose SHA-256 fingerprints are matched by the
+//! certificate scopes of the policy. The network privacy administration
+//! is only served if the node has an RPC access policy, so that it's never open to every client.
+
+#![warn(missing_docs)]
+
+mod gateway;
+mod runtime_proto;
+
+use std::{fs, io, net::SocketAddr, path::PathBuf};
+
+use futures::Future;
+use sc_rpc::Metadata;
+use sc_rpc_server::RpcHandler;
+use tonic::transport::{Certificate, Identity, ServerTlsConfig};
+
+pub use runtime_proto::runtime_proto;
+
+/// The generated gRPC messages, clients and servers.
+#[allow(missing_docs)]
+pub mod proto {
+	tonic::include_proto!("plug.node.v1");
+}
+
+/// TLS configuration of the gRPC server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsConfig {
+	/// PEM file of the certificate chain of the server.
+	pub certificate: PathBuf,
+	/// PEM file of the private key of the server.
+	pub key: PathBuf,
+	/// PEM file of the CA the client certificates must be signed with. Client certificates are
+	/// required if set, and not requested otherwise.
+	pub client_ca: Option<PathBuf>,
+}
+
+/// An error of the gRPC server.
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// Start the gRPC server on the given address, serving the calls with the given RPC handler.
+///
+/// `access_policy` tells whether the handler enforces an RPC access policy, which is required by
+/// the network privacy administration. Fails if the TLS files can't be read or the address can't
+/// be bound, otherwise returns the bound address and the future running the server.
+pub fn start_grpc(
+	addr: &SocketAddr,
+	handler: RpcHandler<Metadata>,
+	access_policy: bool,
+	tls: Option<&TlsConfig>,
+) -> io::Result<(SocketAddr, impl Future<Output = Result<(), Error>>)> {
+	let mut builder = tonic::transport::Server::builder();
+	if let Some(tls) = tls {
+		let identity = Identity::from_pem(fs::read(&tls.certificate)?, fs::read(&tls.key)?);
+		let mut tls_config = ServerTlsConfig::new().identity(identity);
+		if let Some(ref client_ca) = tls.client_ca {
+			tls_config = tls_config.client_ca_root(Certificate::from_pem(fs::read(client_ca)?));
+		}
+		builder = builder.tls_config(tls_config);
+	}
+
+	let listener = std::net::TcpListener::bind(addr)?;
+	listener.set_nonblocking(true)?;
+	let local_addr = listener.local_addr()?;
+
+	let gateway = gateway::Gateway::new(handler, access_policy);
+	let server = builder
+		.add_service(proto::chain_server::ChainServer::new(gateway.clone()))
+		.add_service(proto::state_server::StateServer::new(gateway.clone()))
+		.add_service(proto::author_server::AuthorServer::new(gateway.clone()))
+		.add_service(proto::network_privacy_server::NetworkPrivacyServer::new(gateway));
+
+	Ok((local_addr, async move {
+		let mut listener = tokio::net::TcpListener::from_std(listener)?;
+		server.serve_with_incoming(listener.incoming()).await?;
+		Ok(())
+	}))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{MetaIoHandler, Value};
+	use sc_rpc_server::RpcMiddleware;
+
+	fn handler() -> RpcHandler<Metadata> {
+		let mut io = RpcHandler::new(MetaIoHandler::with_middleware(RpcMiddleware::default()));
+		io.add_method("chain_getFinalizedHead", |_| Ok(Value::String("0x0102".into())));
+		io.add_method("system_privacyStatus", |_| Ok(Value::Bool(true)));
+		io
+	}
+
+	#[tokio::test]
+	async fn serves_the_calls_over_grpc() {
+		let (addr, server) = start_grpc(&"127.0.0.1:0".parse().unwrap(), handler(), false, None).unwrap();
+		tokio::spawn(server);
+		// the address is bound until the server is dropped.
+		assert!(start_grpc(&addr, handler(), false, None).is_err());
+
+		let mut chain = proto::chain_client::ChainClient::connect(format!("http://{}", addr)).await.unwrap();
+		let head = chain.get_finalized_head(proto::Empty {}).await.unwrap();
+		assert_eq!(head.into_inner().hash, vec![1, 2]);
+
+		let mut privacy = proto::network_privacy_client::NetworkPrivacyClient::connect(format!("http://{}", addr))
+			.await
+			.unwrap();
+		let denied = privacy.status(proto::Empty {}).await.unwrap_err();
+		assert_eq!(denied.code(), tonic::Code::PermissionDenied);
+	}
+
+	#[tokio::test]
+	async fn serves_the_network_privacy_administration_with_an_access_policy() {
+		let (addr, server) = start_grpc(&"127.0.0.1:0".parse().unwrap(), handler(), true, None).unwrap();
+		tokio::spawn(server);
+
+		let mut privacy = proto::network_privacy_client::NetworkPrivacyClient::connect(format!("http://{}", addr))
+			.await
+			.unwrap();
+		let status = privacy.status(proto::Empty {}).await.unwrap();
+		assert_eq!(status.into_inner().json, "true");
+	}
+}
@@ -0,0 +1,191 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Protobuf definitions of the calls and events of a runtime, generated from its metadata.
+//!
+//! Each module gets a `<Module>Call` message with a `oneof` of its calls and a `<Module>Event`
+//! message with a `oneof` of its events, and the runtime gets `Call` and `Event` messages with a
+//! `oneof` of the modules. Field numbers are the SCALE indices plus one, so that a message maps to
+//! the index of its module and call. Arguments are `bytes` holding their SCALE encoding, commented
+//! with their Rust type. Modules without calls or events, e.g. `RandomnessBeacon`, get no message
+//! since a `oneof` can't be empty, but still take their index.
+//!
+//! The calls and events are read from the metadata by `sp_scale_value::RuntimeTypes`, as decoded
+//! by the `state_decodeEvents` RPC.
+
+use std::fmt::Write;
+
+use sp_scale_value::RuntimeTypes;
+
+/// Generate the protobuf definitions of the calls and events of the runtime, from its encoded
+/// metadata.
+pub fn runtime_proto(metadata: &[u8]) -> Result<String, String> {
+	let types = RuntimeTypes::from_metadata(metadata).map_err(|e| e.0)?;
+	Ok(types_proto(&types))
+}
+
+fn types_proto(types: &RuntimeTypes) -> String {
+	let mut proto = String::from(
+		"// Generated from the runtime metadata.\n\nsyntax = \"proto3\";\n\npackage plug.runtime;\n"
+	);
+
+	for (module, calls) in &types.calls {
+		let calls = calls.iter().map(|(name, arguments)| (name.as_str(), arguments.clone())).collect();
+		write_module(&mut proto, module, "Call", "call", calls);
+	}
+	for (module, events) in &types.events {
+		let events = events.iter()
+			.map(|(name, arguments)| (name.as_str(), arguments.iter().map(|ty| (String::new(), ty.clone())).collect()))
+			.collect();
+		write_module(&mut proto, module, "Event", "event", events);
+	}
+
+	let calls = types.calls.iter().enumerate()
+		.filter(|(_, (_, calls))| !calls.is_empty())
+		.map(|(i, (module, _))| (format!("{}Call", module), snake_case(module), i + 1));
+	write_oneof(&mut proto, "Call", "module", calls);
+	let events = types.events.iter().enumerate()
+		.filter(|(_, (_, events))| !events.is_empty())
+		.map(|(i, (module, _))| (format!("{}Event", module), snake_case(module), i + 1));
+	write_oneof(&mut proto, "Event", "module", events);
+	proto
+}
+
+/// Write the messages of the calls or events of a module, with the names and types of their
+/// arguments, and the message of the module if it has any.
+fn write_module(
+	proto: &mut String,
+	module: &str,
+	kind: &str,
+	oneof: &str,
+	items: Vec<(&str, Vec<(String, String)>)>,
+) {
+	if items.is_empty() {
+		return;
+	}
+
+	let mut variants = Vec::new();
+	for (i, (name, arguments)) in items.into_iter().enumerate() {
+		let message = format!("{}{}{}", module, camel_case(name), kind);
+
+		let _ = writeln!(proto, "\nmessage {} {{", message);
+		for (i, (field, ty)) in arguments.into_iter().enumerate() {
+			let field = if field.is_empty() { format!("arg_{}", i) } else { field };
+			let _ = writeln!(proto, "\t// {}, SCALE encoded.", ty);
+			let _ = writeln!(proto, "\tbytes {} = {};", field, i + 1);
+		}
+		let _ = writeln!(proto, "}}");
+
+		variants.push((message, snake_case(name), i + 1));
+	}
+
+	write_oneof(proto, &format!("{}{}", module, kind), oneof, variants.into_iter());
+}
+
+/// Write a message with a `oneof` of the given messages and their field numbers, an empty message
+/// if there are none.
+fn write_oneof(
+	proto: &mut String,
+	message: &str,
+	oneof: &str,
+	variants: impl Iterator<Item = (String, String, usize)>,
+) {
+	let variants = variants.collect::<Vec<_>>();
+	if variants.is_empty() {
+		let _ = writeln!(proto, "\nmessage {} {{\n}}", message);
+		return;
+	}
+
+	let _ = writeln!(proto, "\nmessage {} {{\n\toneof {} {{", message, oneof);
+	for (variant, field, number) in variants {
+		let _ = writeln!(proto, "\t\t{} {} = {};", variant, field, number);
+	}
+	let _ = writeln!(proto, "\t}}\n}}");
+}
+
+fn camel_case(name: &str) -> String {
+	name.split('_')
+		.map(|word| {
+			let mut chars = word.chars();
+			chars.next()
+				.map(|first| first.to_uppercase().chain(chars).collect::<String>())
+				.unwrap_or_default()
+		})
+		.collect()
+}
+
+fn snake_case(name: &str) -> String {
+	let mut snake = String::new();
+	for (i, c) in name.char_indices() {
+		if c.is_uppercase() {
+			if i > 0 && !name[..i].ends_with('_') {
+				snake.push('_');
+			}
+			snake.extend(c.to_lowercase());
+		} else {
+			snake.push(c);
+		}
+	}
+	snake
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn calls_and_events_are_numbered_by_index() {
+		let types = RuntimeTypes {
+			calls: vec![
+				("Balances".into(), vec![
+					("transfer".into(), vec![
+						("dest".into(), "<T::Lookup as StaticLookup>::Source".into()),
+						("value".into(), "Compact<T::Balance>".into()),
+					]),
+					("set_balance".into(), vec![]),
+				]),
+				("RandomnessBeacon".into(), vec![]),
+				("Sudo".into(), vec![("sudo".into(), vec![("call".into(), "Box<T::Call>".into())])]),
+			],
+			events: vec![("System".into(), vec![("ExtrinsicSuccess".into(), vec!["DispatchInfo".into()])])],
+		};
+		let proto = types_proto(&types);
+
+		assert!(proto.contains(concat!(
+			"message BalancesTransferCall {\n",
+			"\t// <T::Lookup as StaticLookup>::Source, SCALE encoded.\n",
+			"\tbytes dest = 1;\n",
+			"\t// Compact<T::Balance>, SCALE encoded.\n",
+			"\tbytes value = 2;\n",
+			"}\n",
+		)));
+		assert!(proto.contains(concat!(
+			"message BalancesCall {\n\toneof call {\n",
+			"\t\tBalancesTransferCall transfer = 1;\n",
+			"\t\tBalancesSetBalanceCall set_balance = 2;\n",
+			"\t}\n}\n",
+		)));
+		assert!(proto.contains("\tbytes arg_0 = 1;\n"));
+		assert!(proto.contains("\t\tSystemExtrinsicSuccessEvent extrinsic_success = 1;\n"));
+		// modules without calls don't take a call index.
+		assert!(proto.contains("message Call {\n\toneof module {\n\t\tBalancesCall balances = 1;\n"));
+		assert!(proto.contains("message Event {\n\toneof module {\n\t\tSystemEvent system = 1;\n"));
+		// modules with a call index but no calls get no message, and no empty `oneof`.
+		assert!(!proto.contains("RandomnessBeaconCall"));
+		assert!(!proto.contains("{\n\t}"));
+		assert!(proto.contains("\t\tBalancesCall balances = 1;\n\t\tSudoCall sudo = 3;\n\t}\n}\n"));
+	}
+}
@@ -0,0 +1,374 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Access control of the RPC methods.
+//!
+//! An access policy, read from a TOML file, lists the methods allowed to every client and the
+//! methods allowed to the clients presenting an API key or a TLS client certificate:
+//!
+//! ```toml
+//! [default]
+//! methods = ["chain_*", "state_getStorage", "system_health"]
+//!
+//! [[keys]]
+//! key = "operator-secret"
+//! methods = ["author_*", "system_*"]
+//!
+//! # SHA-256 fingerprint of the DER encoding of the certificate.
+//! [[certs]]
+//! fingerprint = "3f:a2:09:..."
+//! methods = ["*"]
+//! ```
+//!
+//! A method is allowed if it matches a pattern of the default scope or of a scope of the
+//! credentials of the client, where patterns ending with `*` match the methods starting with the
+//! rest of the pattern. Calls to other methods fail with `METHOD_NOT_ALLOWED_ERROR`.
+//!
+//! API keys are presented in the `Authorization: Bearer <key>` or the `X-Api-Key` header over
+//! HTTP, and as a `api-key.<key>` protocol over WebSocket, and are compared in constant time.
+//! Client certificates are only authenticated by the servers terminating TLS themselves, which
+//! set the fingerprint of the verified peer certificate in the credentials: the HTTP and
+//! WebSocket servers don't, and never match the certificate scopes.
+//!
+//! The policy file is reloaded when it's modified.
+
+use std::{fs, io, path::PathBuf, time::{Duration, Instant, SystemTime}};
+
+use log::{info, warn};
+use parking_lot::{Mutex, RwLock};
+use pubsub::PubSubMetadata;
+use serde::Deserialize;
+
+/// Error code of the calls to methods that aren't allowed.
+pub const METHOD_NOT_ALLOWED_ERROR: i64 = -32010;
+
+/// Prefix of the WebSocket protocols presenting an API key.
+const API_KEY_PROTOCOL_PREFIX: &str = "api-key.";
+
+/// Interval between the checks for modifications of the policy file.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The credentials presented by a client.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Credentials {
+	/// The API key of the client.
+	pub api_key: Option<String>,
+	/// The SHA-256 fingerprint of the TLS client certificate verified by the server, lower case
+	/// hex without separators.
+	pub cert_fingerprint: Option<String>,
+}
+
+impl Credentials {
+	/// Read the API key from the given HTTP headers, by lower case name.
+	pub fn from_headers<'a>(header: impl Fn(&str) -> Option<&'a str>) -> Self {
+		let bearer = header("authorization")
+			.filter(|value| value.starts_with("Bearer "))
+			.map(|value| &value["Bearer ".len()..]);
+		Credentials {
+			api_key: bearer.or_else(|| header("x-api-key")).map(|key| key.trim().to_owned()),
+			cert_fingerprint: None,
+		}
+	}
+
+	/// Read the credentials from the given WebSocket protocols.
+	pub fn from_protocols(protocols: &[String]) -> Self {
+		Credentials {
+			api_key: protocols.iter()
+				.find(|protocol| protocol.starts_with(API_KEY_PROTOCOL_PREFIX))
+				.map(|protocol| protocol[API_KEY_PROTOCOL_PREFIX.len()..].to_owned()),
+			cert_fingerprint: None,
+		}
+	}
+}
+
+/// RPC metadata carrying the credentials of the client.
+pub trait AccessMetadata: PubSubMetadata {
+	/// The credentials presented by the client.
+	fn credentials(&self) -> &Credentials;
+
+	/// Set the credentials presented by the client.
+	fn set_credentials(&mut self, credentials: Credentials);
+}
+
+/// The methods allowed to a scope, as patterns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Scope {
+	/// Patterns of the allowed methods, e.g. `author_submitExtrinsic` or `author_*`.
+	#[serde(default)]
+	pub methods: Vec<String>,
+}
+
+/// The methods allowed to an API key.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct KeyScope {
+	/// The API key.
+	pub key: String,
+	/// Patterns of the allowed methods.
+	#[serde(default)]
+	pub methods: Vec<String>,
+}
+
+/// The methods allowed to a TLS client certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CertScope {
+	/// The fingerprint of the certificate, hex with or without separators.
+	pub fingerprint: String,
+	/// Patterns of the allowed methods.
+	#[serde(default)]
+	pub methods: Vec<String>,
+}
+
+/// The rules of an access policy.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct AccessRules {
+	/// The methods allowed to every client.
+	pub default: Scope,
+	/// The methods allowed to API keys.
+	pub keys: Vec<KeyScope>,
+	/// The methods allowed to TLS client certificates.
+	pub certs: Vec<CertScope>,
+}
+
+impl AccessRules {
+	/// Returns `true` if the method is allowed to a client with the given credentials.
+	pub fn allows(&self, credentials: &Credentials, method: &str) -> bool {
+		let matches = |methods: &[String]| methods.iter().any(|pattern| matches_pattern(pattern, method));
+
+		if matches(&self.default.methods) {
+			return true;
+		}
+
+		if let Some(ref api_key) = credentials.api_key {
+			let allowed = self.keys.iter()
+				.filter(|scope| constant_time_eq(scope.key.as_bytes(), api_key.as_bytes()))
+				.any(|scope| matches(&scope.methods));
+			if allowed {
+				return true;
+			}
+		}
+
+		match credentials.cert_fingerprint {
+			Some(ref fingerprint) => self.certs.iter().any(|scope| {
+				normalize_fingerprint(&scope.fingerprint) == *fingerprint && matches(&scope.methods)
+			}),
+			None => false,
+		}
+	}
+}
+
+/// An access policy, reloaded when its file is modified.
+pub struct AccessPolicy {
+	/// The policy file, `None` if the rules aren't read from a file.
+	path: Option<PathBuf>,
+	rules: RwLock<AccessRules>,
+	/// When the policy file was last checked for modifications, and its modification time.
+	checked: Mutex<(Instant, Option<SystemTime>)>,
+}
+
+impl AccessPolicy {
+	/// Creates a new policy with the given rules.
+	pub fn new(rules: AccessRules) -> Self {
+		AccessPolicy {
+			path: None,
+			rules: RwLock::new(rules),
+			checked: Mutex::new((Instant::now(), None)),
+		}
+	}
+
+	/// Loads the policy from the given TOML file.
+	pub fn load(path: PathBuf) -> io::Result<Self> {
+		let modified = modified(&path);
+		let rules = read_rules(&path)?;
+		info!(target: "rpc", "🔐 RPC access policy loaded from {}", path.display());
+
+		Ok(AccessPolicy {
+			path: Some(path),
+			rules: RwLock::new(rules),
+			checked: Mutex::new((Instant::now(), modified)),
+		})
+	}
+
+	/// Returns `true` if the method is allowed to a client with the given credentials.
+	pub fn allows(&self, credentials: &Credentials, method: &str) -> bool {
+		self.reload_if_modified();
+		self.rules.read().allows(credentials, method)
+	}
+
+	/// Reload the rules if the policy file was modified, keeping the current rules if the file
+	/// is invalid.
+	fn reload_if_modified(&self) {
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return,
+		};
+
+		let mut checked = self.checked.lock();
+		if checked.0.elapsed() < RELOAD_INTERVAL {
+			return;
+		}
+		checked.0 = Instant::now();
+
+		let modified = modified(path);
+		if modified == checked.1 {
+			return;
+		}
+
+		match read_rules(path) {
+			Ok(rules) => {
+				info!(target: "rpc", "🔐 RPC access policy reloaded from {}", path.display());
+				*self.rules.write() = rules;
+				checked.1 = modified;
+			},
+			Err(e) => warn!(
+				target: "rpc",
+				"Keeping the current RPC access policy, failed to reload {}: {}",
+				path.display(),
+				e,
+			),
+		}
+	}
+}
+
+pub(crate) fn matches_pattern(pattern: &str, method: &str) -> bool {
+	if pattern.ends_with('*') {
+		method.starts_with(&pattern[..pattern.len() - 1])
+	} else {
+		pattern == method
+	}
+}
+
+/// Compare the bytes in a time independent of their content, so that API keys can't be guessed
+/// byte per byte from the response times.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+	a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn normalize_fingerprint(fingerprint: &str) -> String {
+	fingerprint.chars()
+		.filter(char::is_ascii_hexdigit)
+		.map(|c| c.to_ascii_lowercase())
+		.collect()
+}
+
+fn modified(path: &PathBuf) -> Option<SystemTime> {
+	fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+fn read_rules(path: &PathBuf) -> io::Result<AccessRules> {
+	toml::from_str(&fs::read_to_string(path)?)
+		.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const POLICY: &str = r#"
+		[default]
+		methods = ["chain_*", "system_health"]
+
+		[[keys]]
+		key = "operator"
+		methods = ["author_*"]
+
+		[[certs]]
+		fingerprint = "AB:CD:01"
+		methods = ["*"]
+	"#;
+
+	fn rules() -> AccessRules {
+		toml::from_str(POLICY).unwrap()
+	}
+
+	fn api_key(key: &str) -> Credentials {
+		Credentials { api_key: Some(key.into()), cert_fingerprint: None }
+	}
+
+	#[test]
+	fn methods_are_allowed_per_scope() {
+		let rules = rules();
+		let anonymous = Credentials::default();
+
+		assert!(rules.allows(&anonymous, "chain_getBlock"));
+		assert!(rules.allows(&anonymous, "system_health"));
+		assert!(!rules.allows(&anonymous, "system_peers"));
+		assert!(!rules.allows(&anonymous, "author_submitExtrinsic"));
+
+		assert!(rules.allows(&api_key("operator"), "author_submitExtrinsic"));
+		assert!(!rules.allows(&api_key("operator"), "system_peers"));
+		assert!(!rules.allows(&api_key("guest"), "author_submitExtrinsic"));
+
+		let cert = Credentials { api_key: None, cert_fingerprint: Some("abcd01".into()) };
+		assert!(rules.allows(&cert, "system_peers"));
+		let other_cert = Credentials { api_key: None, cert_fingerprint: Some("abcd02".into()) };
+		assert!(!rules.allows(&other_cert, "system_peers"));
+
+		// the fingerprints aren't read from the headers.
+		let header = Credentials::from_headers(|name| match name {
+			"x-client-cert-fingerprint" => Some("ab:cd:01"),
+			_ => None,
+		});
+		assert!(!rules.allows(&header, "system_peers"));
+	}
+
+	#[test]
+	fn api_keys_are_compared_in_full() {
+		assert!(constant_time_eq(b"operator", b"operator"));
+		assert!(!constant_time_eq(b"operator", b"operatos"));
+		assert!(!constant_time_eq(b"operator", b"operato"));
+		assert!(!constant_time_eq(b"", b"operator"));
+	}
+
+	#[test]
+	fn credentials_are_read_from_headers_and_protocols() {
+		let bearer = Credentials::from_headers(|name| match name {
+			"authorization" => Some("Bearer operator"),
+			_ => None,
+		});
+		assert_eq!(bearer, api_key("operator"));
+
+		let header = Credentials::from_headers(|name| match name {
+			"x-api-key" => Some("operator"),
+			_ => None,
+		});
+		assert_eq!(header, api_key("operator"));
+
+		let protocols = Credentials::from_protocols(&["json".into(), "api-key.operator".into()]);
+		assert_eq!(protocols, api_key("operator"));
+	}
+
+	#[test]
+	fn policy_is_reloaded_when_modified() {
+		let path = std::env::temp_dir().join(format!("rpc-access-policy-{}.toml", std::process::id()));
+		fs::write(&path, POLICY).unwrap();
+		let policy = AccessPolicy::load(path.clone()).unwrap();
+		assert!(!policy.allows(&Credentials::default(), "system_peers"));
+
+		fs::write(&path, "[default]\nmethods = [\"*\"]\n").unwrap();
+		{
+			let mut checked = policy.checked.lock();
+			checked.0 -= RELOAD_INTERVAL;
+			checked.1 = None;
+		}
+		assert!(policy.allows(&Credentials::default(), "system_peers"));
+
+		fs::remove_file(path).unwrap();
+	}
+}
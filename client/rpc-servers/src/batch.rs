@@ -0,0 +1,154 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Limits of the JSON-RPC batches.
+//!
+//! The calls of a batch are executed in order while they fit in the maximum number of calls and
+//! the maximum total weight of a batch, where each method weighs 1 unless configured otherwise.
+//! The remaining calls fail with `BATCH_LIMIT_ERROR`, so the response of an oversized batch
+//! holds the results of the first calls and an error for each of the others.
+
+use jsonrpc_core::{Call, Error, ErrorCode, Output, Version};
+
+use crate::access::matches_pattern;
+
+/// Error code of the calls exceeding the limits of their batch.
+pub const BATCH_LIMIT_ERROR: i64 = -32012;
+
+/// The limits of the JSON-RPC batches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchLimits {
+	/// Maximum number of calls of a batch.
+	pub max_size: usize,
+	/// Maximum total weight of the calls of a batch.
+	pub max_weight: u64,
+	/// The weights of the methods, by pattern as in access policies, e.g. `state_queryStorage`
+	/// or `state_*`. The first matching pattern applies.
+	pub weights: Vec<(String, u64)>,
+}
+
+impl Default for BatchLimits {
+	fn default() -> Self {
+		BatchLimits {
+			max_size: 256,
+			max_weight: 1024,
+			weights: Vec::new(),
+		}
+	}
+}
+
+impl BatchLimits {
+	/// The weight of the given method.
+	pub fn weight(&self, method: &str) -> u64 {
+		self.weights.iter()
+			.find(|(pattern, _)| matches_pattern(pattern, method))
+			.map_or(1, |(_, weight)| *weight)
+	}
+
+	/// Split the calls of a batch into the calls within the limits and the outputs of the
+	/// others, or return the calls if they are all within the limits.
+	pub(crate) fn split(&self, calls: Vec<Call>) -> Result<Vec<Call>, (Vec<Call>, Vec<Output>)> {
+		let mut weight = 0u64;
+		let within = calls.iter().take_while(|call| {
+			weight = weight.saturating_add(match call {
+				Call::MethodCall(call) => self.weight(&call.method),
+				Call::Notification(notification) => self.weight(&notification.method),
+				Call::Invalid { .. } => 1,
+			});
+			weight <= self.max_weight
+		}).count().min(self.max_size);
+
+		if within == calls.len() {
+			return Ok(calls);
+		}
+
+		let mut calls = calls;
+		let exceeding = calls.split_off(within);
+		let message = if within == self.max_size {
+			format!("Batch size limit of {} calls exceeded", self.max_size)
+		} else {
+			format!("Batch weight limit of {} exceeded", self.max_weight)
+		};
+		let error = || Error {
+			code: ErrorCode::ServerError(BATCH_LIMIT_ERROR),
+			message: message.clone(),
+			data: None,
+		};
+		let outputs = exceeding.into_iter().filter_map(|call| match call {
+			Call::MethodCall(call) => Some(Output::from(Err(error()), call.id, call.jsonrpc)),
+			Call::Notification(_) => None,
+			Call::Invalid { id } => Some(Output::from(Err(error()), id, Some(Version::V2))),
+		}).collect();
+
+		Err((calls, outputs))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{Id, MethodCall, Params};
+
+	fn call(method: &str, id: u64) -> Call {
+		Call::MethodCall(MethodCall {
+			jsonrpc: Some(Version::V2),
+			method: method.into(),
+			params: Params::None,
+			id: Id::Num(id),
+		})
+	}
+
+	fn ids(outputs: &[Output]) -> Vec<Id> {
+		outputs.iter().map(|output| output.id().clone()).collect()
+	}
+
+	#[test]
+	fn calls_beyond_the_limits_fail() {
+		let limits = BatchLimits {
+			max_size: 3,
+			max_weight: 10,
+			weights: vec![("state_queryStorage".into(), 8), ("state_*".into(), 2)],
+		};
+		assert_eq!(limits.weight("state_queryStorage"), 8);
+		assert_eq!(limits.weight("state_getStorage"), 2);
+		assert_eq!(limits.weight("chain_getBlock"), 1);
+
+		let within = vec![call("chain_getBlock", 1), call("state_getStorage", 2)];
+		assert_eq!(limits.split(within.clone()).unwrap(), within);
+
+		// the size limit is exceeded.
+		let (calls, outputs) = limits.split((1..6).map(|id| call("chain_getBlock", id)).collect())
+			.unwrap_err();
+		assert_eq!(calls.len(), 3);
+		assert_eq!(ids(&outputs), vec![Id::Num(4), Id::Num(5)]);
+
+		// the weight limit is exceeded.
+		let (calls, outputs) = limits.split(vec![
+			call("state_getStorage", 1),
+			call("state_queryStorage", 2),
+			call("chain_getBlock", 3),
+		]).unwrap_err();
+		assert_eq!(calls.len(), 2);
+		assert_eq!(ids(&outputs), vec![Id::Num(3)]);
+		match outputs[0] {
+			Output::Failure(ref failure) => {
+				assert_eq!(failure.error.code, ErrorCode::ServerError(BATCH_LIMIT_ERROR));
+				assert_eq!(failure.error.message, "Batch weight limit of 10 exceeded");
+			},
+			_ => panic!("calls beyond the limits fail"),
+		}
+	}
+}
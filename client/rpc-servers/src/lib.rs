@@ -18,10 +18,20 @@
 
 #![warn(missing_docs)]
 
+mod access;
+mod batch;
+mod middleware;
+
 use std::io;
-use jsonrpc_core::IoHandlerExtension;
+use jsonrpc_core::{IoHandlerExtension, MetaIoHandler};
 use log::error;
-use pubsub::PubSubMetadata;
+
+pub use access::{
+	AccessMetadata, AccessPolicy, AccessRules, CertScope, Credentials, KeyScope, Scope,
+	METHOD_NOT_ALLOWED_ERROR,
+};
+pub use batch::{BatchLimits, BATCH_LIMIT_ERROR};
+pub use middleware::{RpcMethods, RpcMiddleware, UNSAFE_METHODS};
 
 /// Maximal payload accepted by RPC servers.
 const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
@@ -30,15 +40,17 @@ const MAX_PAYLOAD: usize = 15 * 1024 * 1024;
 const WS_MAX_CONNECTIONS: usize = 100;
 
 /// The RPC IoHandler containing all requested APIs.
-pub type RpcHandler<T> = pubsub::PubSubHandler<T>;
+pub type RpcHandler<T> = pubsub::PubSubHandler<T, RpcMiddleware>;
 
 pub use self::inner::*;
 
-/// Construct rpc `IoHandler`
-pub fn rpc_handler<M: PubSubMetadata>(
-	extension: impl IoHandlerExtension<M>
+/// Construct rpc `IoHandler`, restricting the methods called to the ones allowed by the access
+/// policy of the middleware.
+pub fn rpc_handler<M: AccessMetadata>(
+	extension: impl IoHandlerExtension<M>,
+	middleware: RpcMiddleware,
 ) -> RpcHandler<M> {
-	let mut io = pubsub::PubSubHandler::default();
+	let mut io = pubsub::PubSubHandler::new(MetaIoHandler::with_middleware(middleware));
 	extension.augment(&mut io);
 
 	// add an endpoint to list all available methods.
@@ -67,13 +79,22 @@ mod inner {
 
 	/// Start HTTP server listening on given address.
 	///
+	/// The credentials of the clients are read from the `Authorization`, `X-Api-Key` and
+	/// `X-Client-Cert-Fingerprint` headers.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_http<M: pubsub::PubSubMetadata + Default>(
+	pub fn start_http<M: AccessMetadata + Default>(
 		addr: &std::net::SocketAddr,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
 	) -> io::Result<http::Server> {
-		http::ServerBuilder::new(io)
+		http::ServerBuilder::with_meta_extractor(io, |request: &http::hyper::Request<http::hyper::Body>| {
+			let mut meta = M::default();
+			meta.set_credentials(Credentials::from_headers(|name| {
+				request.headers().get(name).and_then(|value| value.to_str().ok())
+			}));
+			meta
+		})
 			.threads(4)
 			.health_api(("/health", "system_health"))
 			.allowed_hosts(hosts_filtering(cors.is_some()))
@@ -89,14 +110,20 @@ mod inner {
 
 	/// Start WS server listening on given address.
 	///
+	/// The API keys of the clients are read from their `api-key.<key>` protocols.
+	///
 	/// **Note**: Only available if `not(target_os = "unknown")`.
-	pub fn start_ws<M: pubsub::PubSubMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
+	pub fn start_ws<M: AccessMetadata + From<jsonrpc_core::futures::sync::mpsc::Sender<String>>> (
 		addr: &std::net::SocketAddr,
 		max_connections: Option<usize>,
 		cors: Option<&Vec<String>>,
 		io: RpcHandler<M>,
 	) -> io::Result<ws::Server> {
-		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| context.sender().into())
+		ws::ServerBuilder::with_meta_extractor(io, |context: &ws::RequestContext| {
+			let mut meta = M::from(context.sender());
+			meta.set_credentials(Credentials::from_protocols(&context.protocols));
+			meta
+		})
 			.max_payload(MAX_PAYLOAD)
 			.max_connections(max_connections.unwrap_or(WS_MAX_CONNECTIONS))
 			.allowed_origins(map_cors(cors))
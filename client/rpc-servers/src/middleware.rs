@@ -0,0 +1,303 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The middleware of the RPC servers, enforcing the access policy, the exposure of the unsafe
+//! methods and the batch limits.
+
+use std::{net::SocketAddr, sync::Arc};
+
+use jsonrpc_core::{
+	Call, Error, ErrorCode, FutureOutput, FutureResponse, Middleware, Output, Request, Response,
+	futures::{Future, future::{self, Either}},
+};
+use log::debug;
+
+use crate::access::{AccessMetadata, AccessPolicy, Credentials, METHOD_NOT_ALLOWED_ERROR, matches_pattern};
+use crate::batch::BatchLimits;
+
+/// The methods that are unsafe to expose publicly, because they change the configuration of
+/// the node, expose its private data or are expensive to serve. Patterns ending with `*` match
+/// the methods starting with the rest of the pattern.
+pub const UNSAFE_METHODS: &[&str] = &[
+	"author_insertKey",
+	"author_rotateKeys",
+	"author_hasKey",
+	"author_hasSessionKeys",
+	"author_removeExtrinsic",
+	"debug_traceBlock",
+	"network_subscribeAccessEvents",
+	"offchain_*",
+	"pruning_retainPrefix",
+	"pruning_releasePrefix",
+	"system_addReservedPeer",
+	"system_removeReservedPeer",
+	"system_setIpPolicy",
+	"system_networkState",
+	"system_peers",
+	"system_peerReputation",
+	"system_privacyStatus",
+];
+
+/// The methods exposed by an RPC server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcMethods {
+	/// Expose the unsafe methods only on the servers listening on a loopback address.
+	Auto,
+	/// Only expose the safe methods.
+	Safe,
+	/// Expose every method.
+	Unsafe,
+}
+
+impl Default for RpcMethods {
+	fn default() -> Self {
+		RpcMethods::Auto
+	}
+}
+
+impl RpcMethods {
+	/// Returns `true` if the unsafe methods are denied by a server listening on `address`.
+	pub fn denies_unsafe(&self, address: &SocketAddr) -> bool {
+		match self {
+			RpcMethods::Auto => !address.ip().is_loopback(),
+			RpcMethods::Safe => true,
+			RpcMethods::Unsafe => false,
+		}
+	}
+}
+
+/// Restricts the methods called by the clients to the safe ones if the unsafe ones are denied
+/// and to the ones allowed by the access policy, and the batches to their limits, if any.
+#[derive(Clone, Default)]
+pub struct RpcMiddleware {
+	policy: Option<Arc<AccessPolicy>>,
+	batch_limits: Option<BatchLimits>,
+	deny_unsafe: bool,
+}
+
+impl RpcMiddleware {
+	/// Creates a new middleware enforcing the given policy and batch limits, allowing every
+	/// method and batch if `None`.
+	pub fn new(policy: Option<Arc<AccessPolicy>>, batch_limits: Option<BatchLimits>) -> Self {
+		RpcMiddleware { policy, batch_limits, deny_unsafe: false }
+	}
+
+	/// Denies the calls to the `UNSAFE_METHODS` if `deny` is set, whatever the access policy.
+	pub fn deny_unsafe(mut self, deny: bool) -> Self {
+		self.deny_unsafe = deny;
+		self
+	}
+
+	/// Returns `true` if the method is allowed to a client with the given credentials.
+	fn allows(&self, credentials: &Credentials, method: &str) -> bool {
+		if self.deny_unsafe && UNSAFE_METHODS.iter().any(|pattern| matches_pattern(pattern, method)) {
+			return false;
+		}
+
+		self.policy.as_ref().map_or(true, |policy| policy.allows(credentials, method))
+	}
+}
+
+impl<M: AccessMetadata> Middleware<M> for RpcMiddleware {
+	type Future = FutureResponse;
+	type CallFuture = FutureOutput;
+
+	fn on_request<F, X>(&self, request: Request, meta: M, next: F) -> Either<Self::Future, X> where
+		F: Fn(Request, M) -> X + Send + Sync,
+		X: Future<Item = Option<Response>, Error = ()> + Send + 'static,
+	{
+		let (calls, exceeding) = match (request, self.batch_limits.as_ref()) {
+			(Request::Batch(calls), Some(limits)) => match limits.split(calls) {
+				Ok(calls) => return Either::B(next(Request::Batch(calls), meta)),
+				Err(split) => split,
+			},
+			(request, _) => return Either::B(next(request, meta)),
+		};
+
+		debug!(target: "rpc", "Batch limits exceeded by {} calls", exceeding.len());
+		if calls.is_empty() {
+			return Either::A(Box::new(future::ok(Some(Response::Batch(exceeding)))));
+		}
+
+		Either::A(Box::new(next(Request::Batch(calls), meta).map(move |response| {
+			let mut outputs = match response {
+				Some(Response::Batch(outputs)) => outputs,
+				Some(Response::Single(output)) => vec![output],
+				None => Vec::new(),
+			};
+			outputs.extend(exceeding);
+			if outputs.is_empty() {
+				None
+			} else {
+				Some(Response::Batch(outputs))
+			}
+		})))
+	}
+
+	fn on_call<F, X>(&self, call: Call, meta: M, next: F) -> Either<Self::CallFuture, X> where
+		F: FnOnce(Call, M) -> X + Send,
+		X: Future<Item = Option<Output>, Error = ()> + Send + 'static,
+	{
+		match call {
+			Call::MethodCall(ref call) if !self.allows(meta.credentials(), &call.method) => {
+				debug!(target: "rpc", "Denied call to {}", call.method);
+				let error = Error {
+					code: ErrorCode::ServerError(METHOD_NOT_ALLOWED_ERROR),
+					message: format!("Method {} is not allowed", call.method),
+					data: None,
+				};
+				let output = Output::from(Err(error), call.id.clone(), call.jsonrpc);
+				Either::A(Box::new(future::ok(Some(output))))
+			},
+			Call::Notification(ref notification)
+				if !self.allows(meta.credentials(), &notification.method) =>
+			{
+				debug!(target: "rpc", "Denied notification {}", notification.method);
+				Either::A(Box::new(future::ok(None)))
+			},
+			_ => Either::B(next(call, meta)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use jsonrpc_core::{MetaIoHandler, Value};
+	use pubsub::{PubSubMetadata, Session};
+	use crate::access::{AccessRules, Credentials};
+
+	#[derive(Default, Clone)]
+	struct TestMetadata(Credentials);
+
+	impl jsonrpc_core::Metadata for TestMetadata {}
+
+	impl PubSubMetadata for TestMetadata {
+		fn session(&self) -> Option<Arc<Session>> {
+			None
+		}
+	}
+
+	impl AccessMetadata for TestMetadata {
+		fn credentials(&self) -> &Credentials {
+			&self.0
+		}
+
+		fn set_credentials(&mut self, credentials: Credentials) {
+			self.0 = credentials;
+		}
+	}
+
+	fn io(middleware: RpcMiddleware) -> MetaIoHandler<TestMetadata, RpcMiddleware> {
+		let mut io = MetaIoHandler::with_middleware(middleware);
+		io.add_method("author_submitExtrinsic", |_| Ok(Value::Bool(true)));
+		io.add_method("chain_getBlock", |_| Ok(Value::Bool(true)));
+		io.add_method("system_setIpPolicy", |_| Ok(Value::Bool(true)));
+		io
+	}
+
+	#[test]
+	fn denied_calls_fail() {
+		let rules: AccessRules = toml::from_str(r#"
+			[[keys]]
+			key = "operator"
+			methods = ["author_*"]
+		"#).unwrap();
+		let io = io(RpcMiddleware::new(Some(Arc::new(AccessPolicy::new(rules))), None));
+		let operator = Credentials { api_key: Some("operator".into()), cert_fingerprint: None };
+
+		let request = r#"{"jsonrpc":"2.0","method":"author_submitExtrinsic","params":[],"id":1}"#;
+		assert_eq!(
+			io.handle_request_sync(request, TestMetadata(operator)),
+			Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.into()),
+		);
+		assert_eq!(
+			io.handle_request_sync(request, TestMetadata::default()),
+			Some(r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Method author_submitExtrinsic is not allowed"},"id":1}"#.into()),
+		);
+	}
+
+	#[test]
+	fn unsafe_calls_fail_when_denied() {
+		let request = r#"{"jsonrpc":"2.0","method":"system_setIpPolicy","params":[],"id":1}"#;
+		assert_eq!(
+			io(RpcMiddleware::default()).handle_request_sync(request, TestMetadata::default()),
+			Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.into()),
+		);
+		assert_eq!(
+			io(RpcMiddleware::default().deny_unsafe(true)).handle_request_sync(request, TestMetadata::default()),
+			Some(r#"{"jsonrpc":"2.0","error":{"code":-32010,"message":"Method system_setIpPolicy is not allowed"},"id":1}"#.into()),
+		);
+
+		let safe = r#"{"jsonrpc":"2.0","method":"chain_getBlock","params":[],"id":1}"#;
+		assert_eq!(
+			io(RpcMiddleware::default().deny_unsafe(true)).handle_request_sync(safe, TestMetadata::default()),
+			Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.into()),
+		);
+	}
+
+	#[test]
+	fn network_privacy_methods_are_denied_on_public_interfaces() {
+		let public = "0.0.0.0:9933".parse().unwrap();
+		let middleware = RpcMiddleware::default().deny_unsafe(RpcMethods::Auto.denies_unsafe(&public));
+		let mut io = MetaIoHandler::with_middleware(middleware);
+		for method in &["network_subscribeAccessEvents", "system_peerReputation", "system_privacyStatus"] {
+			io.add_method(method, |_| Ok(Value::Bool(true)));
+		}
+
+		for method in &["network_subscribeAccessEvents", "system_peerReputation", "system_privacyStatus"] {
+			let request = format!(r#"{{"jsonrpc":"2.0","method":"{}","params":[],"id":1}}"#, method);
+			assert_eq!(
+				io.handle_request_sync(&request, TestMetadata::default()),
+				Some(format!(
+					r#"{{"jsonrpc":"2.0","error":{{"code":-32010,"message":"Method {} is not allowed"}},"id":1}}"#,
+					method,
+				)),
+				"{} is exposed publicly",
+				method,
+			);
+		}
+	}
+
+	#[test]
+	fn unsafe_methods_are_exposed_on_loopback_by_default() {
+		let local = "127.0.0.1:9933".parse().unwrap();
+		let public = "0.0.0.0:9933".parse().unwrap();
+		assert!(!RpcMethods::Auto.denies_unsafe(&local));
+		assert!(RpcMethods::Auto.denies_unsafe(&public));
+		assert!(RpcMethods::Safe.denies_unsafe(&local));
+		assert!(!RpcMethods::Unsafe.denies_unsafe(&public));
+	}
+
+	#[test]
+	fn batches_beyond_the_limits_get_partial_results() {
+		let limits = BatchLimits { max_size: 2, ..Default::default() };
+		let io = io(RpcMiddleware::new(None, Some(limits)));
+
+		let request = r#"[
+			{"jsonrpc":"2.0","method":"chain_getBlock","params":[],"id":1},
+			{"jsonrpc":"2.0","method":"chain_getBlock","params":[],"id":2},
+			{"jsonrpc":"2.0","method":"chain_getBlock","params":[],"id":3}
+		]"#;
+		assert_eq!(
+			io.handle_request_sync(request, TestMetadata::default()),
+			Some(concat!(
+				r#"[{"jsonrpc":"2.0","result":true,"id":1},{"jsonrpc":"2.0","result":true,"id":2},"#,
+				r#"{"jsonrpc":"2.0","error":{"code":-32012,"message":"Batch size limit of 2 calls exceeded"},"id":3}]"#,
+			).into()),
+		);
+	}
+}
@@ -34,7 +34,7 @@ use sc_rpc_api::Subscriptions;
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use codec::{Encode, Decode};
 use sp_core::{Bytes, traits::BareCryptoStorePtr};
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ApiExt, ProvideRuntimeApi};
 use sp_runtime::generic;
 use sp_transaction_pool::{
 	TransactionPool, InPoolTransaction, TransactionStatus, TransactionSource,
@@ -44,6 +44,7 @@ use sp_session::SessionKeys;
 
 /// Re-export the API for backward compatibility.
 pub use sc_rpc_api::author::*;
+use sc_rpc_api::author::keys::RotatedKeys;
 use self::error::{Error, FutureResult, Result};
 
 /// Authoring API
@@ -104,12 +105,26 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 		Ok(())
 	}
 
-	fn rotate_keys(&self) -> Result<Bytes> {
+	fn rotate_keys(&self, owner: Option<Bytes>) -> Result<RotatedKeys> {
 		let best_block_hash = self.client.info().best_hash;
-		self.client.runtime_api().generate_session_keys(
-			&generic::BlockId::Hash(best_block_hash),
-			None,
-		).map(Into::into).map_err(|e| Error::Client(Box::new(e)))
+		let at = generic::BlockId::Hash(best_block_hash);
+		match owner {
+			None => self.client.runtime_api().generate_session_keys(&at, None)
+				.map(|keys| RotatedKeys::Keys(keys.into()))
+				.map_err(|e| Error::Client(Box::new(e))),
+			Some(owner) => {
+				let has_v2 = self.client.runtime_api()
+					.has_api_with::<dyn SessionKeys<P::Block, Error = ()>, _>(&at, |v| v >= 2)
+					.map_err(|e| Error::Client(Box::new(e)))?;
+				if !has_v2 {
+					return Err(Error::KeyOwnershipProofUnsupported);
+				}
+				self.client.runtime_api().generate_session_keys_with_proof(&at, owner.0, None)
+					.map_err(|e| Error::Client(Box::new(e)))?
+					.map(|(keys, proof)| RotatedKeys::WithProof { keys: keys.into(), proof: proof.into() })
+					.ok_or(Error::KeyOwnershipProof)
+			},
+		}
 	}
 
 	fn has_session_keys(&self, session_keys: Bytes) -> Result<bool> {
@@ -171,7 +186,7 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 	}
 
 	fn watch_extrinsic(&self,
-		_metadata: Self::Metadata,
+		metadata: Self::Metadata,
 		subscriber: Subscriber<TransactionStatus<TxHash<P>, BlockHash<P>>>,
 		xt: Bytes,
 	) {
@@ -198,9 +213,9 @@ impl<P, Client> AuthorApi<TxHash<P>, BlockHash<P>> for Author<P, Client>
 			// start a new subscrition
 			.map(move |result| match result {
 				Ok(watcher) => {
-					subscriptions.add(subscriber, move |sink| {
+					subscriptions.add(&metadata, subscriber, move |sink| {
 						sink
-							.sink_map_err(|_| unimplemented!())
+							.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
 							.send_all(Compat::new(watcher))
 							.map(|_| ())
 					});
@@ -180,6 +180,33 @@ fn should_return_pending_extrinsics() {
 	);
 }
 
+#[test]
+fn should_watch_extrinsic_removal_reason() {
+	//given
+	let mut setup = TestSetup::default();
+	let p = setup.author();
+	let xt = uxt(AccountKeyring::Alice, 0).encode();
+	let h: H256 = blake2_256(&xt).into();
+
+	let (subscriber, id_rx, data) = jsonrpc_pubsub::typed::Subscriber::new_test("test");
+	p.watch_extrinsic(Default::default(), subscriber, xt.into());
+	assert_eq!(setup.runtime.block_on(id_rx), Ok(Ok(SubscriptionId::Number(1).into())));
+
+	// when
+	p.remove_extrinsic(vec![hash::ExtrinsicOrHash::Hash(h)]).unwrap();
+
+	// then
+	let (res, data) = setup.runtime.block_on(data.into_future()).unwrap();
+	assert_eq!(
+		res,
+		Some(r#"{"jsonrpc":"2.0","method":"test","params":{"result":"ready","subscription":1}}"#.into())
+	);
+	assert_eq!(
+		setup.runtime.block_on(data.into_future()).unwrap().0,
+		Some(r#"{"jsonrpc":"2.0","method":"test","params":{"result":{"invalid":"removed"},"subscription":1}}"#.into())
+	);
+}
+
 #[test]
 fn should_remove_extrinsics() {
 	let setup = TestSetup::default();
@@ -227,7 +254,9 @@ fn should_rotate_keys() {
 	let setup = TestSetup::default();
 	let p = setup.author();
 
-	let new_public_keys = p.rotate_keys().expect("Rotates the keys");
+	let new_public_keys = p.rotate_keys(None).expect("Rotates the keys");
+	assert_eq!(new_public_keys.proof(), None);
+	let new_public_keys = new_public_keys.keys();
 
 	let session_keys = SessionKeys::decode(&mut &new_public_keys[..])
 		.expect("SessionKeys decode successfully");
@@ -246,6 +275,24 @@ fn should_rotate_keys() {
 	assert_eq!(session_keys.sr25519, sr25519_key_pair.public().into());
 }
 
+#[test]
+fn should_rotate_keys_with_ownership_proof() {
+	use sp_runtime::traits::OpaqueKeys;
+
+	let setup = TestSetup::default();
+	let p = setup.author();
+	let owner = vec![1u8; 32];
+
+	let rotated = p.rotate_keys(Some(owner.clone().into())).expect("Rotates the keys");
+	let proof = rotated.proof().expect("Ownership proof is returned");
+
+	let session_keys = SessionKeys::decode(&mut &rotated.keys()[..])
+		.expect("SessionKeys decode successfully");
+	assert!(session_keys.ownership_proof_is_valid(&owner, proof));
+	assert!(!session_keys.ownership_proof_is_valid(&[2u8; 32], proof));
+	assert!(setup.keystore.read().has_keys(&session_keys.into_raw_public_keys()));
+}
+
 #[test]
 fn test_has_session_keys() {
 	let setup = TestSetup::default();
@@ -253,10 +300,12 @@ fn test_has_session_keys() {
 
 	let non_existent_public_keys = TestSetup::default()
 		.author()
-		.rotate_keys()
-		.expect("Rotates the keys");
+		.rotate_keys(None)
+		.expect("Rotates the keys")
+		.keys()
+		.clone();
 
-	let public_keys = p.rotate_keys().expect("Rotates the keys");
+	let public_keys = p.rotate_keys(None).expect("Rotates the keys").keys().clone();
 	let test_vectors = vec![
 		(public_keys, Ok(true)),
 		(vec![1, 2, 3].into(), Err(Error::InvalidSessionKeys)),
@@ -18,12 +18,13 @@
 
 use std::sync::Arc;
 use rpc::futures::future::result;
+use jsonrpc_pubsub::typed::Subscriber;
 
 use sc_rpc_api::Subscriptions;
 use sc_client_api::{BlockchainEvents, BlockBackend};
 use sp_runtime::{generic::{BlockId, SignedBlock}, traits::{Block as BlockT}};
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{ChainBackend, Reorg, client_err, error::FutureResult, subscribe_reorgs};
 use std::marker::PhantomData;
 use sp_blockchain::HeaderBackend;
 
@@ -75,4 +76,19 @@ impl<Block, Client> ChainBackend<Client, Block> for FullChain<Block, Client> whe
 			.map_err(client_err)
 		))
 	}
+
+	fn subscribe_reorgs(
+		&self,
+		metadata: crate::metadata::Metadata,
+		subscriber: Subscriber<Reorg<Block::Hash>>,
+	) {
+		let client = self.client.clone();
+		subscribe_reorgs(
+			&self.client,
+			&self.subscriptions,
+			&metadata,
+			subscriber,
+			move |reorg| reorg.retracted_extrinsics(&*client),
+		)
+	}
 }
@@ -19,6 +19,7 @@
 use std::sync::Arc;
 use futures::{future::ready, FutureExt, TryFutureExt};
 use rpc::futures::future::{result, Future, Either};
+use jsonrpc_pubsub::typed::Subscriber;
 
 use sc_rpc_api::Subscriptions;
 use sc_client::{
@@ -29,7 +30,7 @@ use sp_runtime::{
 	traits::{Block as BlockT},
 };
 
-use super::{ChainBackend, client_err, error::FutureResult};
+use super::{ChainBackend, Reorg, client_err, error::FutureResult, subscribe_reorgs};
 use sp_blockchain::HeaderBackend;
 use sc_client_api::BlockchainEvents;
 
@@ -115,4 +116,19 @@ impl<Block, Client, F> ChainBackend<Client, Block> for LightChain<Block, Client,
 
 		Box::new(block)
 	}
+
+	fn subscribe_reorgs(
+		&self,
+		metadata: crate::metadata::Metadata,
+		subscriber: Subscriber<Reorg<Block::Hash>>,
+	) {
+		// Light clients don't store the bodies of the blocks, so no extrinsics are listed.
+		subscribe_reorgs(
+			&self.client,
+			&self.subscriptions,
+			&metadata,
+			subscriber,
+			|_| Ok(Vec::new()),
+		)
+	}
 }
@@ -32,7 +32,7 @@ use rpc::{
 
 use sc_rpc_api::Subscriptions;
 use sc_client::{
-	self, BlockchainEvents,
+	self, BlockchainEvents, ReorgNotification,
 	light::{fetcher::Fetcher, blockchain::RemoteBlockchain},
 };
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
@@ -98,12 +98,13 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	/// All new head subscription
 	fn subscribe_all_heads(
 		&self,
-		_metadata: crate::metadata::Metadata,
+		metadata: crate::metadata::Metadata,
 		subscriber: Subscriber<Block::Header>,
 	) {
 		subscribe_headers(
 			self.client(),
 			self.subscriptions(),
+			&metadata,
 			subscriber,
 			|| self.client().info().best_hash,
 			|| self.client().import_notification_stream()
@@ -124,12 +125,13 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	/// New best head subscription
 	fn subscribe_new_heads(
 		&self,
-		_metadata: crate::metadata::Metadata,
+		metadata: crate::metadata::Metadata,
 		subscriber: Subscriber<Block::Header>,
 	) {
 		subscribe_headers(
 			self.client(),
 			self.subscriptions(),
+			&metadata,
 			subscriber,
 			|| self.client().info().best_hash,
 			|| self.client().import_notification_stream()
@@ -151,12 +153,13 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	/// Finalized head subscription
 	fn subscribe_finalized_heads(
 		&self,
-		_metadata: crate::metadata::Metadata,
+		metadata: crate::metadata::Metadata,
 		subscriber: Subscriber<Block::Header>,
 	) {
 		subscribe_headers(
 			self.client(),
 			self.subscriptions(),
+			&metadata,
 			subscriber,
 			|| self.client().info().finalized_hash,
 			|| self.client().finality_notification_stream()
@@ -173,6 +176,22 @@ trait ChainBackend<Client, Block: BlockT>: Send + Sync + 'static
 	) -> RpcResult<bool> {
 		Ok(self.subscriptions().cancel(id))
 	}
+
+	/// Re-org subscription
+	fn subscribe_reorgs(
+		&self,
+		metadata: crate::metadata::Metadata,
+		subscriber: Subscriber<Reorg<Block::Hash>>,
+	);
+
+	/// Unsubscribe from re-org subscription.
+	fn unsubscribe_reorgs(
+		&self,
+		_metadata: Option<crate::metadata::Metadata>,
+		id: SubscriptionId,
+	) -> RpcResult<bool> {
+		Ok(self.subscriptions().cancel(id))
+	}
 }
 
 /// Create new state API that works on full node.
@@ -275,12 +294,21 @@ impl<Block, Client> ChainApi<NumberFor<Block>, Block::Hash, Block::Header, Signe
 	fn unsubscribe_finalized_heads(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
 		self.backend.unsubscribe_finalized_heads(metadata, id)
 	}
+
+	fn subscribe_reorgs(&self, metadata: Self::Metadata, subscriber: Subscriber<Reorg<Block::Hash>>) {
+		self.backend.subscribe_reorgs(metadata, subscriber)
+	}
+
+	fn unsubscribe_reorgs(&self, metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		self.backend.unsubscribe_reorgs(metadata, id)
+	}
 }
 
 /// Subscribe to new headers.
 fn subscribe_headers<Block, Client, F, G, S, ERR>(
 	client: &Arc<Client>,
 	subscriptions: &Subscriptions,
+	metadata: &crate::metadata::Metadata,
 	subscriber: Subscriber<Block::Header>,
 	best_block_hash: G,
 	stream: F,
@@ -292,7 +320,7 @@ fn subscribe_headers<Block, Client, F, G, S, ERR>(
 	ERR: ::std::fmt::Debug,
 	S: Stream<Item=Block::Header, Error=ERR> + Send + 'static,
 {
-	subscriptions.add(subscriber, |sink| {
+	subscriptions.add(metadata, subscriber, |sink| {
 		// send current head right at the start.
 		let header = client.header(BlockId::Hash(best_block_hash()))
 			.map_err(client_err)
@@ -317,6 +345,45 @@ fn subscribe_headers<Block, Client, F, G, S, ERR>(
 	});
 }
 
+/// Subscribe to re-orgs, listing the extrinsics they retract with `retracted_extrinsics`.
+///
+/// The extrinsics are listed as the notifications are sent to the subscriber, not on block import.
+fn subscribe_reorgs<Block, Client, F>(
+	client: &Arc<Client>,
+	subscriptions: &Subscriptions,
+	metadata: &crate::metadata::Metadata,
+	subscriber: Subscriber<Reorg<Block::Hash>>,
+	retracted_extrinsics: F,
+) where
+	Block: BlockT + 'static,
+	Client: BlockchainEvents<Block> + 'static,
+	F: Fn(&ReorgNotification<Block>) -> sp_blockchain::Result<Vec<Block::Hash>> + Send + 'static,
+{
+	let stream = client.reorg_notification_stream()
+		.filter_map(move |notification| future::ready(match retracted_extrinsics(&notification) {
+			Ok(retracted_extrinsics) => Some(Ok::<_, ()>(Ok(Reorg {
+				new_best: notification.hash,
+				common_ancestor: notification.common_ancestor,
+				retracted: notification.retracted,
+				enacted: notification.enacted,
+				retracted_extrinsics,
+			}))),
+			Err(e) => {
+				warn!("Failed to list the extrinsics retracted by the re-org to {}: {:?}", notification.hash, e);
+				None
+			},
+		}))
+		.compat();
+
+	subscriptions.add(metadata, subscriber, |sink| {
+		sink
+			.sink_map_err(|e| warn!("Error sending notifications: {:?}", e))
+			.send_all(stream)
+			// we ignore the resulting Stream (if the first stream is over we are unsubscribed)
+			.map(|_| ())
+	});
+}
+
 fn client_err(err: sp_blockchain::Error) -> Error {
 	Error::Client(Box::new(err))
 }
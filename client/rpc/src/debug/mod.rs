@@ -0,0 +1,296 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Plug debug API.
+//!
+//! `debug_traceBlock` re-executes a block on the state of its parent, one runtime call at a
+//! time, committing the changes of every call before the next one. The storage reads of a call
+//! are recorded by the state backend, its writes are the uncommitted changes it leaves and its
+//! events are the events of the block deposited in its phase. Blocks are traced on a dedicated
+//! thread, one at a time.
+
+#[cfg(test)]
+mod tests;
+
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+
+use codec::{Decode, Encode};
+use sc_client::{Client, LocalCallExecutor};
+use sc_client_api::{backend, ExecutorProvider};
+use sc_executor::RuntimeInfo;
+use sp_core::{
+	twox_128, Bytes,
+	storage::{StorageData, StorageKey},
+	traits::CodeExecutor,
+};
+use sp_runtime::{
+	ApplyExtrinsicResult,
+	generic::BlockId,
+	traits::{Block as BlockT, Hash as HashT, HashFor, Header as HeaderT},
+};
+use sp_state_machine::OverlayedChanges;
+use rpc::futures::{Future, future, sync::oneshot};
+
+use crate::state::{events, DecodedEvent, EventPhase};
+use self::error::{Error, FutureResult, Result};
+
+pub use sc_rpc_api::debug::*;
+
+/// Extracts the encoded doughnut issuer from a delegated extrinsic.
+pub type DoughnutIssuerFn<Block> =
+	Box<dyn Fn(&<Block as BlockT>::Extrinsic) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Debug API
+pub struct Debug<BE, E, Block: BlockT, RA> {
+	client: Arc<Client<BE, LocalCallExecutor<BE, E>, Block, RA>>,
+	doughnut_issuer: Option<Arc<DoughnutIssuerFn<Block>>>,
+	/// Whether a block is being traced.
+	tracing: Arc<AtomicBool>,
+}
+
+impl<BE, E, Block: BlockT, RA> Clone for Debug<BE, E, Block, RA> {
+	fn clone(&self) -> Self {
+		Debug {
+			client: self.client.clone(),
+			doughnut_issuer: self.doughnut_issuer.clone(),
+			tracing: self.tracing.clone(),
+		}
+	}
+}
+
+impl<BE, E, Block: BlockT, RA> Debug<BE, E, Block, RA> {
+	/// Create new instance of Debug API.
+	pub fn new(client: Arc<Client<BE, LocalCallExecutor<BE, E>, Block, RA>>) -> Self {
+		Debug {
+			client,
+			doughnut_issuer: None,
+			tracing: Default::default(),
+		}
+	}
+
+	/// Report the doughnut issuer of the delegated extrinsics, extracted with `doughnut_issuer`.
+	pub fn with_doughnut_issuer(mut self, doughnut_issuer: DoughnutIssuerFn<Block>) -> Self {
+		self.doughnut_issuer = Some(Arc::new(doughnut_issuer));
+		self
+	}
+}
+
+/// Marks a block as being traced until dropped.
+struct TracingGuard(Arc<AtomicBool>);
+
+impl TracingGuard {
+	/// Mark a block as being traced, unless one already is.
+	fn new(tracing: &Arc<AtomicBool>) -> Option<Self> {
+		tracing.compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst).ok()?;
+		Some(TracingGuard(tracing.clone()))
+	}
+}
+
+impl Drop for TracingGuard {
+	fn drop(&mut self) {
+		self.0.store(false, Ordering::SeqCst);
+	}
+}
+
+impl<BE, E, Block, RA> Debug<BE, E, Block, RA> where
+	BE: backend::Backend<Block>,
+	E: CodeExecutor + RuntimeInfo + Clone + 'static,
+	Block: BlockT,
+{
+	fn trace(&self, hash: Block::Hash, options: TraceOptions) -> Result<BlockTrace<Block::Hash>> {
+		let id = BlockId::Hash(hash);
+		let header = self.client.header(&id).map_err(client_err)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", hash)))?;
+		let extrinsics = self.client.body(&id).map_err(client_err)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", hash)))?;
+		let parent_hash = *header.parent_hash();
+		let parent = BlockId::Hash(parent_hash);
+		let spec_version = self.client.runtime_version_at(&parent).map_err(client_err)?.spec_version;
+
+		let executor = self.client.executor();
+		let traced = |key: &[u8]| !options.disable_storage && (
+			options.key_prefixes.is_empty() ||
+			options.key_prefixes.iter().any(|prefix| key.starts_with(&prefix.0))
+		);
+		let mut changes = OverlayedChanges::default();
+		let mut call = |method: &str, data: &[u8]| -> Result<(Vec<u8>, StepTrace<Block::Hash>)> {
+			let (result, reads) = executor
+				.call_recording_reads(&parent, method, data, &mut changes, None)
+				.map_err(|e| Error::Execution(e.to_string()))?;
+			let writes = changes.prospective_changes()
+				.filter(|(key, _)| traced(key))
+				.map(|(key, value)| StorageAccess {
+					key: StorageKey(key.to_vec()),
+					value: value.map(|value| StorageData(value.to_vec())),
+				})
+				.collect();
+			changes.commit_prospective();
+
+			let reads = reads.into_iter()
+				.filter(|(key, _)| traced(key))
+				.map(|(key, value)| StorageAccess {
+					key: StorageKey(key),
+					value: value.map(StorageData),
+				})
+				.collect();
+			Ok((result, StepTrace { reads, writes, events: Vec::new() }))
+		};
+
+		let (_, mut initialization) = call(
+			"Core_initialize_block",
+			&sc_block_builder::initial_header::<Block>(&header).encode(),
+		)?;
+		let mut traces = Vec::with_capacity(extrinsics.len());
+		for (index, extrinsic) in extrinsics.iter().enumerate() {
+			let (result, trace) = call("BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+			let result = ApplyExtrinsicResult::decode(&mut &result[..])
+				.map_err(|e| Error::Execution(e.what().into()))?;
+
+			let doughnut_issuer: Option<Bytes> = self.doughnut_issuer.as_ref()
+				.and_then(|issuer| issuer(extrinsic))
+				.map(Into::into);
+			traces.push(ExtrinsicTrace {
+				index: index as u32,
+				hash: <HashFor<Block> as HashT>::hash_of(extrinsic),
+				success: result.map_or(false, |outcome| outcome.is_ok()),
+				weight: None,
+				origin_switches: doughnut_issuer.iter()
+					.map(|issuer| OriginSwitch { call: None, issuer: issuer.clone() })
+					.collect(),
+				doughnut_issuer,
+				trace,
+			});
+		}
+		let (finalized, mut finalization) = call("BlockBuilder_finalize_block", &[])?;
+		let finalized = Block::Header::decode(&mut &finalized[..])
+			.map_err(|e| Error::Execution(e.what().into()))?;
+
+		let events_key = [twox_128(b"System"), twox_128(b"Events")].concat();
+		let encoded_events = changes.storage(&events_key).and_then(|events| events).unwrap_or_default();
+		let decoded = if encoded_events.is_empty() {
+			Default::default()
+		} else {
+			let (metadata, _) = executor.call_recording_reads(
+				&parent,
+				"Metadata_metadata",
+				&[],
+				&mut OverlayedChanges::default(),
+				None,
+			).map_err(|e| Error::Execution(e.to_string()))?;
+			let metadata = Vec::<u8>::decode(&mut &metadata[..])
+				.map_err(|e| Error::Execution(e.what().into()))?;
+			events::decode_events::<Block::Hash>(&metadata, encoded_events, 0, u32::max_value())
+				.map_err(Error::InvalidEvents)?
+		};
+
+		for event in decoded.events {
+			let events = match event.phase {
+				EventPhase::Initialization => &mut initialization.events,
+				EventPhase::Finalization => &mut finalization.events,
+				EventPhase::ApplyExtrinsic(index) => match traces.get_mut(index as usize) {
+					Some(trace) => {
+						trace.weight = trace.weight.or_else(|| extrinsic_weight(&event));
+						trace.origin_switches.extend(origin_switch(&event));
+						&mut trace.trace.events
+					},
+					None => return Err(Error::InvalidEvents(
+						format!("Event {} of the unknown extrinsic {}", event.index, index)
+					)),
+				},
+			};
+			if !options.disable_events {
+				events.push(event);
+			}
+		}
+
+		Ok(BlockTrace {
+			hash,
+			parent_hash,
+			spec_version,
+			initialization,
+			extrinsics: traces,
+			finalization,
+			state_root_matches: finalized.state_root() == header.state_root(),
+			undecoded_events: decoded.undecoded,
+		})
+	}
+}
+
+impl<BE, E, Block, RA> DebugApi<Block::Hash> for Debug<BE, E, Block, RA> where
+	BE: backend::Backend<Block> + Send + Sync + 'static,
+	E: CodeExecutor + RuntimeInfo + Clone + Send + Sync + 'static,
+	Block: BlockT + 'static,
+	RA: Send + Sync + 'static,
+{
+	fn trace_block(
+		&self,
+		hash: Block::Hash,
+		options: Option<TraceOptions>,
+	) -> FutureResult<BlockTrace<Block::Hash>> {
+		let guard = match TracingGuard::new(&self.tracing) {
+			Some(guard) => guard,
+			None => return Box::new(future::err(Error::Busy)),
+		};
+		let debug = self.clone();
+		let (sender, receiver) = oneshot::channel();
+		let spawned = std::thread::Builder::new()
+			.name("debug-trace-block".into())
+			.spawn(move || {
+				let trace = debug.trace(hash, options.unwrap_or_default());
+				drop(guard);
+				let _ = sender.send(trace);
+			});
+		if let Err(e) = spawned {
+			return Box::new(future::err(Error::Execution(e.to_string())));
+		}
+
+		Box::new(receiver.then(|trace| {
+			trace.unwrap_or_else(|_| Err(Error::Execution("The trace was interrupted".into())))
+		}))
+	}
+}
+
+/// The weight of an extrinsic, reported by the `System` event of its dispatch.
+fn extrinsic_weight<Hash>(event: &DecodedEvent<Hash>) -> Option<u64> {
+	if event.pallet != "System" ||
+		(event.variant != "ExtrinsicSuccess" && event.variant != "ExtrinsicFailed")
+	{
+		return None;
+	}
+
+	event.fields.iter()
+		.find(|field| field.ty == "DispatchInfo")
+		.and_then(|field| field.value["weight"].as_u64())
+}
+
+/// The origin switch reported by a `Utility` event of a delegated batch call.
+fn origin_switch<Hash>(event: &DecodedEvent<Hash>) -> Option<OriginSwitch> {
+	if event.pallet != "Utility" || event.variant != "DelegatedDispatch" {
+		return None;
+	}
+
+	let call = event.fields.get(0)?.value.as_u64()?;
+	let issuer = event.fields.get(1)?.value.as_str()?;
+	Some(OriginSwitch {
+		call: Some(call as u32),
+		issuer: Bytes(sp_core::bytes::from_hex(issuer).ok()?),
+	})
+}
+
+fn client_err(err: sp_blockchain::Error) -> Error {
+	Error::Client(Box::new(err))
+}
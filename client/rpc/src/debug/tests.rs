@@ -0,0 +1,108 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use super::*;
+
+use codec::Encode;
+use sc_block_builder::BlockBuilderProvider;
+use sp_io::hashing::blake2_256;
+use substrate_test_runtime_client::{
+	prelude::*,
+	sp_consensus::BlockOrigin,
+	runtime,
+};
+
+#[test]
+fn should_trace_block() {
+	let mut client = Arc::new(substrate_test_runtime_client::new());
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_transfer(runtime::Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Ferdie.into(),
+		amount: 42,
+		nonce: 0,
+	}).unwrap();
+	let block = builder.build().unwrap().block;
+	let hash = block.header.hash();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let api = Debug::new(client.clone());
+	let ferdie_balance = StorageKey(
+		blake2_256(&runtime::system::balance_of_key(AccountKeyring::Ferdie.into())).to_vec()
+	);
+	let trace = api.trace_block(hash, Some(TraceOptions {
+		key_prefixes: vec![ferdie_balance.clone()],
+		..Default::default()
+	})).wait().unwrap();
+
+	assert!(trace.state_root_matches);
+	assert_eq!(trace.extrinsics.len(), 1);
+	let transfer = &trace.extrinsics[0];
+	assert!(transfer.success);
+	assert_eq!(transfer.doughnut_issuer, None);
+	assert!(transfer.origin_switches.is_empty());
+	assert_eq!(transfer.trace.reads, vec![StorageAccess { key: ferdie_balance.clone(), value: None }]);
+	assert_eq!(
+		transfer.trace.writes,
+		vec![StorageAccess { key: ferdie_balance, value: Some(StorageData(42u64.encode())) }],
+	);
+
+	let trace = api.trace_block(hash, Some(TraceOptions {
+		disable_storage: true,
+		..Default::default()
+	})).wait().unwrap();
+	assert!(trace.extrinsics[0].trace.reads.is_empty());
+	assert!(trace.extrinsics[0].trace.writes.is_empty());
+
+	assert_matches::assert_matches!(
+		api.trace_block(Default::default(), None).wait(),
+		Err(Error::UnknownBlock(_))
+	);
+}
+
+#[test]
+fn should_trace_one_block_at_a_time() {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let api = Debug::new(client.clone());
+	let genesis = client.chain_info().genesis_hash;
+
+	let guard = TracingGuard::new(&api.tracing).unwrap();
+	assert_matches::assert_matches!(api.trace_block(genesis, None).wait(), Err(Error::Busy));
+	drop(guard);
+	assert_matches::assert_matches!(api.trace_block(Default::default(), None).wait(), Err(Error::UnknownBlock(_)));
+}
+
+#[test]
+fn should_trace_origin_switches_of_delegated_calls() {
+	let event = |pallet: &str, variant: &str| DecodedEvent::<runtime::Hash> {
+		index: 0,
+		phase: EventPhase::ApplyExtrinsic(0),
+		pallet: pallet.into(),
+		variant: variant.into(),
+		fields: vec![
+			crate::state::EventField { ty: "u32".into(), value: serde_json::json!(2) },
+			crate::state::EventField { ty: "AccountId".into(), value: serde_json::json!("0x0102") },
+		],
+		topics: vec![],
+	};
+
+	assert_eq!(
+		origin_switch(&event("Utility", "DelegatedDispatch")),
+		Some(OriginSwitch { call: Some(2), issuer: Bytes(vec![1, 2]) }),
+	);
+	assert_eq!(origin_switch(&event("Utility", "BatchCompleted")), None);
+}
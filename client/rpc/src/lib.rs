@@ -22,12 +22,15 @@
 
 mod metadata;
 
-pub use sc_rpc_api::Subscriptions;
+pub use sc_rpc_api::{OverflowPolicy, SubscriptionLimits, SubscriptionMetrics, Subscriptions};
 pub use self::metadata::Metadata;
 pub use rpc::IoHandlerExtension as RpcExtension;
 
 pub mod author;
 pub mod chain;
+pub mod debug;
+pub mod network;
 pub mod offchain;
+pub mod pruning;
 pub mod state;
 pub mod system;
@@ -19,6 +19,7 @@ use std::sync::Arc;
 
 use jsonrpc_pubsub::{Session, PubSubMetadata};
 use rpc::futures::sync::mpsc;
+use sc_rpc_server::{AccessMetadata, Credentials};
 
 /// RPC Metadata.
 ///
@@ -28,6 +29,7 @@ use rpc::futures::sync::mpsc;
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	credentials: Credentials,
 }
 
 impl rpc::Metadata for Metadata {}
@@ -37,11 +39,22 @@ impl PubSubMetadata for Metadata {
 	}
 }
 
+impl AccessMetadata for Metadata {
+	fn credentials(&self) -> &Credentials {
+		&self.credentials
+	}
+
+	fn set_credentials(&mut self, credentials: Credentials) {
+		self.credentials = credentials;
+	}
+}
+
 impl Metadata {
 	/// Create new `Metadata` with session (Pub/Sub) support.
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			credentials: Default::default(),
 		}
 	}
 
@@ -0,0 +1,69 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Substrate network API.
+
+#[cfg(test)]
+mod tests;
+
+use log::warn;
+use rpc::{Result as RpcResult, futures::{Future, Sink}};
+use futures::{StreamExt as _, stream::BoxStream, compat::Compat};
+use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
+use sc_rpc_api::Subscriptions;
+
+pub use sc_rpc_api::network::*;
+
+/// Network API implementation
+pub struct Network {
+	subscriptions: Subscriptions,
+	access_events: Box<dyn Fn() -> BoxStream<'static, AccessEvent> + Send + Sync>,
+}
+
+impl Network {
+	/// Creates new `Network`.
+	///
+	/// `access_events` is called once per subscription and must return a stream of all the
+	/// access events happening from that point on.
+	pub fn new(
+		subscriptions: Subscriptions,
+		access_events: impl Fn() -> BoxStream<'static, AccessEvent> + Send + Sync + 'static,
+	) -> Self {
+		Network {
+			subscriptions,
+			access_events: Box::new(access_events),
+		}
+	}
+}
+
+impl NetworkApi for Network {
+	type Metadata = crate::metadata::Metadata;
+
+	fn subscribe_access_events(&self, metadata: Self::Metadata, subscriber: Subscriber<AccessEvent>) {
+		let stream = (self.access_events)().map(|event| Ok::<_, ()>(Ok(event)));
+		self.subscriptions.add(&metadata, subscriber, |sink| {
+			sink
+				.sink_map_err(|e| warn!("Error sending access events: {:?}", e))
+				.send_all(Compat::new(stream))
+				.map(|_| ())
+		});
+	}
+
+	fn unsubscribe_access_events(&self, _metadata: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
+		Ok(self.subscriptions.cancel(id))
+	}
+}
@@ -0,0 +1,56 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+use super::*;
+use std::sync::Arc;
+use futures01::Stream as _;
+
+#[test]
+fn should_notify_about_access_events() {
+	let mut core = ::tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let api = Network::new(Subscriptions::new(Arc::new(remote)), || {
+			futures::stream::iter(vec![
+				AccessEvent {
+					peer_id: "1".into(),
+					timestamp: 1,
+					kind: AccessEventKind::Accepted { incoming: true },
+				},
+				AccessEvent {
+					peer_id: "2".into(),
+					timestamp: 2,
+					kind: AccessEventKind::Refused { reason: RefusalReason::Banned },
+				},
+			]).boxed()
+		});
+
+		api.subscribe_access_events(Default::default(), subscriber);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+	}
+
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""peerId":"1""#));
+	let (notification, next) = core.block_on(next.into_future()).unwrap();
+	assert!(notification.unwrap().contains(r#""reason":"banned""#));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
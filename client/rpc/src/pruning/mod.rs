@@ -0,0 +1,54 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Substrate state pruning maintenance API.
+
+#[cfg(test)]
+mod tests;
+
+use rpc::Result as RpcResult;
+use sc_client_api::StateRetention;
+use sp_core::Bytes;
+
+pub use sc_rpc_api::pruning::*;
+use self::error::{Error, Result};
+
+/// State pruning maintenance API implementation.
+#[derive(Debug)]
+pub struct Pruning {
+	retention: StateRetention,
+}
+
+impl Pruning {
+	/// Creates new `Pruning` managing the given retained prefixes.
+	pub fn new(retention: StateRetention) -> Self {
+		Pruning { retention }
+	}
+}
+
+impl PruningApi for Pruning {
+	fn retained_prefixes(&self) -> RpcResult<Vec<Bytes>> {
+		Ok(self.retention.prefixes().into_iter().map(Into::into).collect())
+	}
+
+	fn retain_prefix(&self, prefix: Bytes) -> Result<bool> {
+		self.retention.retain(prefix.0).map_err(|e| Error::Retention(e.to_string()))
+	}
+
+	fn release_prefix(&self, prefix: Bytes) -> RpcResult<bool> {
+		Ok(self.retention.release(&prefix))
+	}
+}
@@ -0,0 +1,40 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+
+#[test]
+fn retained_prefixes_can_be_changed() {
+	let retention = StateRetention::new(vec![b"balances".to_vec()]);
+	let pruning = Pruning::new(retention.clone());
+
+	assert!(pruning.retain_prefix(Bytes(b"staking".to_vec())).unwrap());
+	assert!(!pruning.retain_prefix(Bytes(b"staking".to_vec())).unwrap());
+	assert_eq!(pruning.release_prefix(Bytes(b"balances".to_vec())), Ok(true));
+	assert_eq!(pruning.release_prefix(Bytes(b"balances".to_vec())), Ok(false));
+	assert_eq!(pruning.retained_prefixes(), Ok(vec![Bytes(b"staking".to_vec())]));
+	assert_eq!(retention.prefixes(), vec![b"staking".to_vec()]);
+}
+
+#[test]
+fn retained_prefixes_are_capped() {
+	let max = sc_client_api::MAX_RETAINED_PREFIXES as u8;
+	let pruning = Pruning::new(StateRetention::new((0..max).map(|prefix| vec![prefix])));
+
+	assert!(pruning.retain_prefix(Bytes(b"staking".to_vec())).is_err());
+	assert_eq!(pruning.release_prefix(Bytes(vec![0])), Ok(true));
+	assert!(pruning.retain_prefix(Bytes(b"staking".to_vec())).unwrap());
+}
@@ -0,0 +1,201 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decoding of the events of a block with the runtime metadata.
+//!
+//! The arguments of the events are decoded by the type names declared by the pallets with the
+//! default `TypeRegistry` of `sp-scale-value`, with the concrete types of the Plug runtime for
+//! its generic types, e.g. `u128` for `Balance`. The events from the first event which can't be
+//! decoded on, e.g. with a type unknown to the registry, are returned encoded.
+
+use codec::{Compact, Decode};
+use sp_scale_value::{RuntimeTypes, TypeRegistry};
+
+use sp_core::Bytes;
+
+use super::{DecodedEvent, DecodedEvents, EventField, EventPhase, UndecodedEvents};
+
+/// Decode the page of `count` events starting at `start` of the encoded `Vec<EventRecord>` of
+/// a block.
+pub(crate) fn decode_events<Hash: Decode>(
+	metadata: &[u8],
+	events: &[u8],
+	start: u32,
+	count: u32,
+) -> Result<DecodedEvents<Hash>, String> {
+	let types = RuntimeTypes::from_metadata(metadata).map_err(|e| e.0)?;
+	let registry = TypeRegistry::default();
+	let input = &mut &events[..];
+	let total = if events.is_empty() { 0 } else { decode::<Compact<u32>>(input)?.0 };
+
+	let mut decoded = Vec::new();
+	for index in 0..start.saturating_add(count).min(total) {
+		let raw = *input;
+		match decode_event(&types, &registry, input) {
+			Ok(event) => if index >= start {
+				decoded.push(DecodedEvent { index, ..event });
+			},
+			Err(error) => return Ok(DecodedEvents {
+				total,
+				events: decoded,
+				undecoded: Some(UndecodedEvents { index, error, raw: Bytes(raw.to_vec()) }),
+			}),
+		}
+	}
+
+	Ok(DecodedEvents { total, events: decoded, undecoded: None })
+}
+
+/// Decode an `EventRecord`, with a placeholder index.
+fn decode_event<Hash: Decode>(
+	types: &RuntimeTypes,
+	registry: &TypeRegistry,
+	input: &mut &[u8],
+) -> Result<DecodedEvent<Hash>, String> {
+	let phase = match decode::<u8>(input)? {
+		0 => EventPhase::ApplyExtrinsic(decode(input)?),
+		1 => EventPhase::Finalization,
+		2 => EventPhase::Initialization,
+		phase => return Err(format!("Invalid phase {}", phase)),
+	};
+	let event = types.decode_event(registry, input).map_err(|e| e.0)?;
+	let topics = decode::<Vec<Hash>>(input)?;
+
+	Ok(DecodedEvent {
+		index: 0,
+		phase,
+		pallet: event.module,
+		variant: event.variant,
+		fields: event.fields.into_iter()
+			.map(|field| EventField { ty: field.ty, value: field.value.into() })
+			.collect(),
+		topics,
+	})
+}
+
+fn decode<T: Decode>(input: &mut &[u8]) -> Result<T, String> {
+	T::decode(input).map_err(|e| e.what().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::{
+		DecodeDifferent, EventMetadata, ExtrinsicMetadata, FnEncode, ModuleMetadata,
+		RuntimeMetadataLastVersion, RuntimeMetadataPrefixed,
+	};
+	use serde_json::json;
+	use sp_core::H256;
+
+	fn metadata() -> Vec<u8> {
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataLastVersion {
+			modules: DecodeDifferent::Encode(&[
+				ModuleMetadata {
+					name: DecodeDifferent::Encode("System"),
+					storage: None,
+					calls: None,
+					event: Some(DecodeDifferent::Encode(FnEncode(|| &[
+						EventMetadata {
+							name: DecodeDifferent::Encode("ExtrinsicSuccess"),
+							arguments: DecodeDifferent::Encode(&["DispatchInfo"]),
+							documentation: DecodeDifferent::Encode(&[]),
+						},
+					]))),
+					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
+				},
+				ModuleMetadata {
+					name: DecodeDifferent::Encode("Timestamp"),
+					storage: None,
+					calls: None,
+					event: None,
+					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
+				},
+				ModuleMetadata {
+					name: DecodeDifferent::Encode("Balances"),
+					storage: None,
+					calls: None,
+					event: Some(DecodeDifferent::Encode(FnEncode(|| &[
+						EventMetadata {
+							name: DecodeDifferent::Encode("Transfer"),
+							arguments: DecodeDifferent::Encode(&["AccountId", "AccountId", "Balance"]),
+							documentation: DecodeDifferent::Encode(&[]),
+						},
+					]))),
+					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
+				},
+			]),
+			extrinsic: ExtrinsicMetadata { version: 4, signed_extensions: vec![] },
+		}.into();
+		metadata.encode()
+	}
+
+	#[test]
+	fn events_are_decoded_by_page() {
+		let no_topics = Vec::<H256>::new();
+		let mut events = Compact(3u32).encode();
+		for extrinsic in 0..3u32 {
+			events.extend((0u8, extrinsic, 1u8, 0u8, [1u8; 32], [2u8; 32], 100u128, &no_topics).encode());
+		}
+
+		let page = decode_events::<H256>(&metadata(), &events, 1, 1).unwrap();
+		assert_eq!(page.total, 3);
+		assert_eq!(page.events, vec![DecodedEvent {
+			index: 1,
+			phase: EventPhase::ApplyExtrinsic(1),
+			pallet: "Balances".into(),
+			variant: "Transfer".into(),
+			fields: vec![
+				EventField { ty: "AccountId".into(), value: json!(format!("0x{}", "01".repeat(32))) },
+				EventField { ty: "AccountId".into(), value: json!(format!("0x{}", "02".repeat(32))) },
+				EventField { ty: "Balance".into(), value: json!("100") },
+			],
+			topics: vec![],
+		}]);
+
+		let empty = decode_events::<H256>(&metadata(), &[], 0, 10).unwrap();
+		assert_eq!(empty, DecodedEvents { total: 0, events: vec![], undecoded: None });
+	}
+
+	#[test]
+	fn undecodable_events_are_returned_encoded() {
+		let no_topics = Vec::<H256>::new();
+		let mut events = Compact(3u32).encode();
+		events.extend((0u8, 0u32, 1u8, 0u8, [1u8; 32], [2u8; 32], 100u128, &no_topics).encode());
+		// An event of an unknown module, and the following event.
+		let rest = (0u8, 1u32, 7u8, 0u8, &no_topics, 0u8, 2u32, 1u8, 0u8, [1u8; 32], [2u8; 32], 5u128, &no_topics)
+			.encode();
+		events.extend(&rest);
+
+		let page = decode_events::<H256>(&metadata(), &events, 0, 10).unwrap();
+		assert_eq!(page.total, 3);
+		assert_eq!(page.events.len(), 1);
+		assert_eq!(page.undecoded, Some(UndecodedEvents {
+			index: 1,
+			error: "Unknown module 7 of the event".into(),
+			raw: Bytes(rest),
+		}));
+
+		let page = decode_events::<H256>(&metadata(), &events, 0, 1).unwrap();
+		assert_eq!((page.events.len(), page.undecoded), (1, None));
+	}
+}
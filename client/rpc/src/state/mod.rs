@@ -16,19 +16,20 @@
 
 //! Substrate state API.
 
+pub(crate) mod events;
 mod state_full;
 mod state_light;
 
 #[cfg(test)]
 mod tests;
 
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 use jsonrpc_pubsub::{typed::Subscriber, SubscriptionId};
 use rpc::{Result as RpcResult, futures::{Future, future::result}};
 
 use sc_rpc_api::Subscriptions;
 use sc_client::{light::{blockchain::RemoteBlockchain, fetcher::Fetcher}};
-use sp_core::{Bytes, storage::{StorageKey, StorageData, StorageChangeSet}};
+use sp_core::{Bytes, twox_128, storage::{StorageKey, StorageData, StorageChangeSet}};
 use sp_version::RuntimeVersion;
 use sp_runtime::traits::Block as BlockT;
 
@@ -37,11 +38,17 @@ use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt};
 use self::error::{Error, FutureResult};
 
 pub use sc_rpc_api::state::*;
-use sc_client_api::{ExecutorProvider, StorageProvider, BlockchainEvents, Backend};
+use sc_client_api::{ExecutorProvider, StorageProvider, BlockchainEvents, Backend, ProofProvider};
 use sp_blockchain::{HeaderMetadata, HeaderBackend};
 
 const STORAGE_KEYS_PAGED_MAX_COUNT: u32 = 1000;
 
+/// Maximum number of events returned by `state_decodeEvents`.
+const DECODE_EVENTS_MAX_COUNT: u32 = 1000;
+
+/// Maximum number of distinct keys queried by `state_queryStorageBatchAt`.
+const QUERY_STORAGE_BATCH_MAX_KEYS: u32 = 1000;
+
 /// State backend API.
 pub trait StateBackend<Block: BlockT, Client>: Send + Sync + 'static
 	where
@@ -170,6 +177,15 @@ pub trait StateBackend<Block: BlockT, Client>: Send + Sync + 'static
 		at: Option<Block::Hash>
 	) -> FutureResult<Vec<StorageChangeSet<Block::Hash>>>;
 
+	/// Query storage entries (by distinct keys) at a block in one round trip, optionally with a
+	/// read proof.
+	fn query_storage_batch_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+		with_proof: bool,
+	) -> FutureResult<StorageBatch<Block::Hash>>;
+
 	/// New runtime version subscription
 	fn subscribe_runtime_version(
 		&self,
@@ -190,6 +206,8 @@ pub trait StateBackend<Block: BlockT, Client>: Send + Sync + 'static
 		_meta: crate::metadata::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
 		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		child_tries: Option<Vec<StorageKey>>,
 	);
 
 	/// Unsubscribe from storage subscription
@@ -210,7 +228,7 @@ pub fn new_full<BE, Block: BlockT, Client>(
 		BE: Backend<Block> + 'static,
 		Client: ExecutorProvider<Block> + StorageProvider<Block, BE> + HeaderBackend<Block>
 			+ HeaderMetadata<Block, Error = sp_blockchain::Error> + BlockchainEvents<Block>
-			+ CallApiAt<Block, Error = sp_blockchain::Error>
+			+ CallApiAt<Block, Error = sp_blockchain::Error> + ProofProvider<Block>
 			+ ProvideRuntimeApi<Block> + Send + Sync + 'static,
 		Client::Api: Metadata<Block, Error = sp_blockchain::Error>,
 {
@@ -372,13 +390,62 @@ impl<Block, Client> StateApi<Block::Hash> for State<Block, Client>
 		self.backend.query_storage_at(keys, at)
 	}
 
+	fn query_storage_batch_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+		with_proof: Option<bool>,
+	) -> FutureResult<StorageBatch<Block::Hash>> {
+		let mut keys = keys;
+		let mut seen = HashSet::new();
+		keys.retain(|key| seen.insert(key.clone()));
+
+		if keys.len() > QUERY_STORAGE_BATCH_MAX_KEYS as usize {
+			return Box::new(result(Err(
+				Error::InvalidCount {
+					value: keys.len() as u32,
+					max: QUERY_STORAGE_BATCH_MAX_KEYS,
+				}
+			)));
+		}
+		self.backend.query_storage_batch_at(keys, at, with_proof.unwrap_or(false))
+	}
+
+	fn decode_events(
+		&self,
+		block: Option<Block::Hash>,
+		start: Option<u32>,
+		count: Option<u32>,
+	) -> FutureResult<DecodedEvents<Block::Hash>> {
+		let count = count.unwrap_or(DECODE_EVENTS_MAX_COUNT);
+		if count > DECODE_EVENTS_MAX_COUNT {
+			return Box::new(result(Err(
+				Error::InvalidCount {
+					value: count,
+					max: DECODE_EVENTS_MAX_COUNT,
+				}
+			)));
+		}
+
+		let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+		Box::new(self.backend.metadata(block)
+			.join(self.backend.storage(block, key))
+			.and_then(move |(metadata, events)| {
+				let events = events.map(|events| events.0).unwrap_or_default();
+				events::decode_events(&metadata, &events, start.unwrap_or(0), count)
+					.map_err(Error::InvalidEvents)
+			}))
+	}
+
 	fn subscribe_storage(
 		&self,
 		meta: Self::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
-		keys: Option<Vec<StorageKey>>
+		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		child_tries: Option<Vec<StorageKey>>,
 	) {
-		self.backend.subscribe_storage(meta, subscriber, keys);
+		self.backend.subscribe_storage(meta, subscriber, keys, prefixes, child_tries);
 	}
 
 	fn unsubscribe_storage(&self, meta: Option<Self::Metadata>, id: SubscriptionId) -> RpcResult<bool> {
@@ -38,9 +38,11 @@ use sp_runtime::{
 
 use sp_api::{Metadata, ProvideRuntimeApi, CallApiAt};
 
-use super::{StateBackend, error::{FutureResult, Error, Result}, client_err, child_resolution_error};
+use super::{
+	StateBackend, StorageBatch, error::{FutureResult, Error, Result}, client_err, child_resolution_error,
+};
 use std::marker::PhantomData;
-use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider};
+use sc_client_api::{CallExecutor, StorageProvider, ExecutorProvider, ProofProvider};
 
 /// Ranges to query in state_queryStorage.
 struct QueryStorageRange<Block: BlockT> {
@@ -146,7 +148,11 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 	) -> Result<()> {
 		for block in range.unfiltered_range.start..range.unfiltered_range.end {
 			let block_hash = range.hashes[block].clone();
-			let mut block_changes = StorageChangeSet { block: block_hash.clone(), changes: Vec::new() };
+			let mut block_changes = StorageChangeSet {
+				block: block_hash.clone(),
+				changes: Vec::new(),
+				child_changes: Vec::new(),
+			};
 			let id = BlockId::hash(block_hash);
 			for key in keys {
 				let (has_changed, data) = {
@@ -201,7 +207,11 @@ impl<BE, Block: BlockT, Client> FullState<BE, Block, Client>
 				}
 
 				changes_map.entry(block)
-					.or_insert_with(|| StorageChangeSet { block: block_hash, changes: Vec::new() })
+					.or_insert_with(|| StorageChangeSet {
+						block: block_hash,
+						changes: Vec::new(),
+						child_changes: Vec::new(),
+					})
 					.changes.push((key.clone(), value_at_block.clone()));
 				last_block = Some(block);
 				last_value = value_at_block;
@@ -221,7 +231,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 	Client: ExecutorProvider<Block> + StorageProvider<Block, BE> + HeaderBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error> + BlockchainEvents<Block>
 		+ CallApiAt<Block, Error = sp_blockchain::Error> + ProvideRuntimeApi<Block>
-		+ Send + Sync + 'static,
+		+ ProofProvider<Block> + Send + Sync + 'static,
 	Client::Api: Metadata<Block, Error = sp_blockchain::Error>,
 {
 	fn call(
@@ -410,9 +420,32 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		self.query_storage(at, Some(at), keys)
 	}
 
+	fn query_storage_batch_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+		with_proof: bool,
+	) -> FutureResult<StorageBatch<Block::Hash>> {
+		let query = || -> ClientResult<StorageBatch<Block::Hash>> {
+			let at = self.block_or_best(at)?;
+			let id = BlockId::Hash(at);
+			let values = keys.iter()
+				.map(|key| self.client.storage(&id, key).map(|value| (key.clone(), value)))
+				.collect::<ClientResult<Vec<_>>>()?;
+			let proof = if with_proof {
+				let proof = self.client.read_proof(&id, &mut keys.iter().map(|key| &key.0[..]))?;
+				Some(proof.iter_nodes().map(Into::into).collect())
+			} else {
+				None
+			};
+			Ok(StorageBatch { at, values, proof })
+		};
+		Box::new(result(query().map_err(client_err)))
+	}
+
 	fn subscribe_runtime_version(
 		&self,
-		_meta: crate::metadata::Metadata,
+		meta: crate::metadata::Metadata,
 		subscriber: Subscriber<RuntimeVersion>,
 	) {
 		let stream = match self.client.storage_changes_notification_stream(
@@ -426,7 +459,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 			}
 		};
 
-		self.subscriptions.add(subscriber, |sink| {
+		self.subscriptions.add(&meta, subscriber, |sink| {
 			let version = self.runtime_version(None.into())
 				.map_err(Into::into)
 				.wait();
@@ -471,14 +504,30 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 
 	fn subscribe_storage(
 		&self,
-		_meta: crate::metadata::Metadata,
+		meta: crate::metadata::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
 		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		child_tries: Option<Vec<StorageKey>>,
 	) {
 		let keys = Into::<Option<Vec<_>>>::into(keys);
+		// prefixes can't be matched by the notifications, so listen to every top level key and
+		// filter the changes before sending them.
+		let filtered = prefixes.is_some() || child_tries.is_some();
+		let listen_keys = match (&keys, &prefixes) {
+			(_, Some(_)) => None,
+			(Some(keys), None) => Some(keys.clone()),
+			(None, None) if filtered => Some(Vec::new()),
+			(None, None) => None,
+		};
+		let child_filter = child_tries.map(|child_tries| child_tries
+			.into_iter()
+			.map(|child_trie| (child_trie, None))
+			.collect::<Vec<_>>()
+		);
 		let stream = match self.client.storage_changes_notification_stream(
-			keys.as_ref().map(|x| &**x),
-			None
+			listen_keys.as_ref().map(|x| &**x),
+			child_filter.as_ref().map(|x| &**x),
 		) {
 			Ok(stream) => stream,
 			Err(err) => {
@@ -488,7 +537,7 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 		};
 
 		// initial values
-		let initial = stream::iter_result(keys
+		let initial = stream::iter_result(keys.clone()
 			.map(|keys| {
 				let block = self.client.info().best_hash;
 				let changes = keys
@@ -499,18 +548,39 @@ impl<BE, Block, Client> StateBackend<Block, Client> for FullState<BE, Block, Cli
 						.unwrap_or_else(|_| (key, None))
 					)
 					.collect();
-				vec![Ok(Ok(StorageChangeSet { block, changes }))]
+				vec![Ok(Ok(StorageChangeSet { block, changes, child_changes: Vec::new() }))]
 			}).unwrap_or_default());
 
-		self.subscriptions.add(subscriber, |sink| {
+		let wanted = move |key: &StorageKey| match &prefixes {
+			Some(prefixes) => keys.as_ref().map_or(false, |keys| keys.contains(key))
+				|| prefixes.iter().any(|prefix| key.0.starts_with(&prefix.0)),
+			None => true,
+		};
+		self.subscriptions.add(&meta, subscriber, |sink| {
 			let stream = stream
-				.map(|(block, changes)| Ok::<_, ()>(Ok(StorageChangeSet {
-					block,
-					changes: changes.iter()
-						.filter_map(|(o_sk, k, v)| if o_sk.is_none() {
-							Some((k.clone(),v.cloned()))
-						} else { None }).collect(),
-				})))
+				.map(move |(block, changes)| {
+					let mut change_set = StorageChangeSet {
+						block,
+						changes: Vec::new(),
+						child_changes: Vec::new(),
+					};
+					for (o_sk, k, v) in changes.iter() {
+						match o_sk {
+							None if wanted(k) => change_set.changes.push((k.clone(), v.cloned())),
+							None => (),
+							Some(sk) => match change_set.child_changes.last_mut() {
+								Some((last, child_changes)) if last == sk =>
+									child_changes.push((k.clone(), v.cloned())),
+								_ => change_set.child_changes.push((sk.clone(), vec![(k.clone(), v.cloned())])),
+							},
+						}
+					}
+					change_set
+				})
+				.filter(move |change_set| future::ready(
+					!filtered || !change_set.changes.is_empty() || !change_set.child_changes.is_empty()
+				))
+				.map(|change_set| Ok::<_, ()>(Ok(change_set)))
 				.compat();
 
 			sink
@@ -53,7 +53,7 @@ use sp_core::{
 use sp_version::RuntimeVersion;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, HashFor}};
 
-use super::{StateBackend, error::{FutureResult, Error}, client_err};
+use super::{StateBackend, StorageBatch, error::{FutureResult, Error}, client_err};
 
 /// Storage data map of storage keys => (optional) storage value.
 type StorageMap = HashMap<StorageKey, Option<StorageData>>;
@@ -339,12 +339,49 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 		Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))))
 	}
 
+	fn query_storage_batch_at(
+		&self,
+		keys: Vec<StorageKey>,
+		at: Option<Block::Hash>,
+		with_proof: bool,
+	) -> FutureResult<StorageBatch<Block::Hash>> {
+		// remote reads are checked against the proof, but the proof itself is not kept
+		if with_proof {
+			return Box::new(result(Err(client_err(ClientError::NotAvailableOnLightClient))));
+		}
+
+		let at = self.block_or_best(at);
+		Box::new(storage(
+			&*self.remote_blockchain,
+			self.fetcher.clone(),
+			at,
+			keys.iter().map(|key| key.0.clone()).collect(),
+		).boxed().compat().map(move |mut values| StorageBatch {
+			at,
+			values: keys.into_iter()
+				.map(|key| {
+					let value = values.remove(&key)
+						.expect("successful request has entries for all requested keys; qed");
+					(key, value)
+				})
+				.collect(),
+			proof: None,
+		}))
+	}
+
 	fn subscribe_storage(
 		&self,
-		_meta: crate::metadata::Metadata,
+		meta: crate::metadata::Metadata,
 		subscriber: Subscriber<StorageChangeSet<Block::Hash>>,
-		keys: Option<Vec<StorageKey>>
+		keys: Option<Vec<StorageKey>>,
+		prefixes: Option<Vec<StorageKey>>,
+		child_tries: Option<Vec<StorageKey>>,
 	) {
+		if prefixes.is_some() || child_tries.is_some() {
+			warn!("Cannot subscribe to key prefixes or child tries on light client. Subscription rejected.");
+			return;
+		}
+
 		let keys = match keys {
 			Some(keys) if !keys.is_empty() => keys,
 			_ => {
@@ -355,7 +392,7 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 
 		let keys = keys.iter().cloned().collect::<HashSet<_>>();
 		let keys_to_check = keys.iter().map(|k| k.0.clone()).collect::<HashSet<_>>();
-		let subscription_id = self.subscriptions.add(subscriber, move |sink| {
+		let subscription_id = self.subscriptions.add(&meta, subscriber, move |sink| {
 			let fetcher = self.fetcher.clone();
 			let remote_blockchain = self.remote_blockchain.clone();
 			let storage_subscriptions = self.storage_subscriptions.clone();
@@ -409,6 +446,7 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 								.iter()
 								.map(|(k, v)| (k.clone(), v.clone()))
 								.collect(),
+							child_changes: Vec::new(),
 						}),
 						false => None,
 					}
@@ -464,10 +502,10 @@ impl<Block, F, Client> StateBackend<Block, Client> for LightState<Block, F, Clie
 
 	fn subscribe_runtime_version(
 		&self,
-		_meta: crate::metadata::Metadata,
+		meta: crate::metadata::Metadata,
 		subscriber: Subscriber<RuntimeVersion>,
 	) {
-		self.subscriptions.add(subscriber, move |sink| {
+		self.subscriptions.add(&meta, subscriber, move |sink| {
 			let fetcher = self.fetcher.clone();
 			let remote_blockchain = self.remote_blockchain.clone();
 			let version_subscriptions = self.version_subscriptions.clone();
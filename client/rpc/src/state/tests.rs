@@ -30,7 +30,7 @@ use substrate_test_runtime_client::{
 	sp_consensus::BlockOrigin,
 	runtime,
 };
-use sp_runtime::generic::BlockId;
+use sp_runtime::{generic::BlockId, traits::{BlakeTwo256, Header as HeaderT}};
 
 const CHILD_INFO: ChildInfo<'static> = ChildInfo::new_default(b"unique_id");
 
@@ -77,6 +77,68 @@ fn should_return_storage() {
 
 }
 
+#[test]
+fn should_return_storage_batch_with_proof() {
+	const KEY: &[u8] = b":mock";
+	const VALUE: &[u8] = b"hello world";
+	const MISSING_KEY: &[u8] = b":missing";
+
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(TestClientBuilder::new()
+		.add_extra_storage(KEY.to_vec(), VALUE.to_vec())
+		.build());
+	let genesis_hash = client.genesis_hash();
+	let state_root = *client.header(&BlockId::Hash(genesis_hash)).unwrap().unwrap().state_root();
+	let api = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+	let keys = vec![StorageKey(KEY.to_vec()), StorageKey(MISSING_KEY.to_vec())];
+
+	let batch = api.query_storage_batch_at(keys.clone(), None, None).wait().unwrap();
+	assert_eq!(batch, StorageBatch {
+		at: genesis_hash,
+		values: vec![
+			(keys[0].clone(), Some(StorageData(VALUE.to_vec()))),
+			(keys[1].clone(), None),
+		],
+		proof: None,
+	});
+
+	let batch = api.query_storage_batch_at(keys.clone(), Some(genesis_hash), Some(true)).wait().unwrap();
+	let proof = sp_state_machine::StorageProof::new(
+		batch.proof.expect("proof was requested").into_iter().map(|node| node.0).collect(),
+	);
+	let proved = sp_state_machine::read_proof_check::<BlakeTwo256, _>(
+		state_root,
+		proof,
+		keys.iter().map(|key| &key.0),
+	).unwrap();
+	assert_eq!(proved.get(KEY), Some(&Some(VALUE.to_vec())));
+	assert_eq!(proved.get(MISSING_KEY), Some(&None));
+}
+
+#[test]
+fn should_query_storage_batch_of_distinct_keys() {
+	const KEY: &[u8] = b":mock";
+	const VALUE: &[u8] = b"hello world";
+
+	let core = tokio::runtime::Runtime::new().unwrap();
+	let client = Arc::new(TestClientBuilder::new()
+		.add_extra_storage(KEY.to_vec(), VALUE.to_vec())
+		.build());
+	let api = new_full(client, Subscriptions::new(Arc::new(core.executor())));
+	let key = StorageKey(KEY.to_vec());
+
+	let batch = api.query_storage_batch_at(vec![key.clone(), key.clone()], None, Some(true))
+		.wait()
+		.unwrap();
+	assert_eq!(batch.values, vec![(key, Some(StorageData(VALUE.to_vec())))]);
+
+	let keys = (0..1001u32).map(|i| StorageKey(i.to_le_bytes().to_vec())).collect();
+	assert_matches!(
+		api.query_storage_batch_at(keys, None, None).wait(),
+		Err(Error::InvalidCount { value: 1001, max: 1000 })
+	);
+}
+
 #[test]
 fn should_return_child_storage() {
 	let (child_info, child_type) = CHILD_INFO.info();
@@ -148,7 +210,7 @@ fn should_notify_about_storage_changes() {
 		let mut client = Arc::new(substrate_test_runtime_client::new());
 		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
 
-		api.subscribe_storage(Default::default(), subscriber, None.into());
+		api.subscribe_storage(Default::default(), subscriber, None.into(), None, None);
 
 		// assert id assigned
 		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
@@ -185,7 +247,7 @@ fn should_send_initial_storage_changes_and_notifications() {
 
 		api.subscribe_storage(Default::default(), subscriber, Some(vec![
 			StorageKey(alice_balance_key.to_vec()),
-		]).into());
+		]).into(), None, None);
 
 		// assert id assigned
 		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
@@ -211,6 +273,52 @@ fn should_send_initial_storage_changes_and_notifications() {
 	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
 }
 
+#[test]
+fn should_notify_about_storage_changes_by_prefix() {
+	const WATCHED_KEY: &[u8] = b":plug:watched";
+	const OTHER_KEY: &[u8] = b":other";
+
+	let mut core = tokio::runtime::Runtime::new().unwrap();
+	let remote = core.executor();
+	let (subscriber, id, transport) = Subscriber::new_test("test");
+
+	{
+		let mut client = Arc::new(substrate_test_runtime_client::new());
+		let api = new_full(client.clone(), Subscriptions::new(Arc::new(remote)));
+
+		api.subscribe_storage(
+			Default::default(),
+			subscriber,
+			None,
+			Some(vec![StorageKey(b":plug:".to_vec())]),
+			None,
+		);
+
+		// assert id assigned
+		assert_eq!(core.block_on(id), Ok(Ok(SubscriptionId::Number(1))));
+
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_storage_change(WATCHED_KEY.to_vec(), Some(vec![1])).unwrap();
+		builder.push_storage_change(OTHER_KEY.to_vec(), Some(vec![1])).unwrap();
+		let block = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+
+		// no key matches the prefix
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_storage_change(OTHER_KEY.to_vec(), Some(vec![2])).unwrap();
+		let block = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, block).unwrap();
+	}
+
+	// assert only the matching change is sent to transport
+	let (notification, next) = core.block_on(transport.into_future()).unwrap();
+	let notification = notification.expect("a key matching the prefix changed");
+	assert!(notification.contains(&sp_core::bytes::to_hex(WATCHED_KEY, false)));
+	assert!(!notification.contains(&sp_core::bytes::to_hex(OTHER_KEY, false)));
+	// no more notifications on this channel
+	assert_eq!(core.block_on(next.into_future()).unwrap().0, None);
+}
+
 #[test]
 fn should_query_storage() {
 	fn run_tests(mut client: Arc<TestClient>, has_changes_trie_config: bool) {
@@ -255,6 +363,7 @@ fn should_query_storage() {
 					(StorageKey(vec![4]), None),
 					(StorageKey(vec![5]), None),
 				],
+				child_changes: Vec::new(),
 			},
 			StorageChangeSet {
 				block: block1_hash,
@@ -263,6 +372,7 @@ fn should_query_storage() {
 					(StorageKey(vec![3]), Some(StorageData(vec![3]))),
 					(StorageKey(vec![5]), Some(StorageData(vec![0]))),
 				],
+				child_changes: Vec::new(),
 			},
 		];
 
@@ -290,6 +400,7 @@ fn should_query_storage() {
 				(StorageKey(vec![4]), Some(StorageData(vec![4]))),
 				(StorageKey(vec![5]), Some(StorageData(vec![1]))),
 			],
+			child_changes: Vec::new(),
 		});
 		assert_eq!(result.wait().unwrap(), expected);
 
@@ -402,7 +513,8 @@ fn should_query_storage() {
 						(StorageKey(vec![3_u8]), Some(StorageData(vec![3_u8]))),
 						(StorageKey(vec![4_u8]), None),
 						(StorageKey(vec![5_u8]), Some(StorageData(vec![0_u8]))),
-					]
+					],
+					child_changes: Vec::new(),
 				}
 			]
 		);
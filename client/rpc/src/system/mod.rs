@@ -28,7 +28,9 @@ use sp_utils::mpsc::TracingUnboundedSender;
 use self::error::Result;
 
 pub use sc_rpc_api::system::*;
-pub use self::helpers::{Properties, SystemInfo, Health, PeerInfo, NodeRole};
+pub use self::helpers::{
+	Properties, SystemInfo, Health, PeerInfo, NodeRole, HostFunction, PrivacyMode, PrivacyStatus,
+};
 pub use self::gen_client::Client as SystemClient;
 
 /// System API implementation
@@ -50,7 +52,13 @@ pub enum Request<B: traits::Block> {
 	/// Must return any potential parse error.
 	NetworkRemoveReservedPeer(String, oneshot::Sender<Result<()>>),
 	/// Must return the node role.
-	NodeRoles(oneshot::Sender<Vec<NodeRole>>)
+	NodeRoles(oneshot::Sender<Vec<NodeRole>>),
+	/// Must replace the IP policy of the network, or return any potential parse error.
+	SetIpPolicy(Vec<String>, Vec<String>, oneshot::Sender<Result<()>>),
+	/// Must return the reputation of the peer or any potential parse error.
+	PeerReputation(String, oneshot::Sender<Result<Option<i32>>>),
+	/// Must return the state of the enforcement of the membership of the private network.
+	PrivacyStatus(oneshot::Sender<PrivacyStatus>),
 }
 
 impl<B: traits::Block> System<B> {
@@ -86,6 +94,10 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		Ok(self.info.properties.clone())
 	}
 
+	fn system_host_functions(&self) -> Result<Vec<HostFunction>> {
+		Ok(self.info.host_functions.clone())
+	}
+
 	fn system_health(&self) -> Receiver<Health> {
 		let (tx, rx) = oneshot::channel();
 		let _ = self.send_back.unbounded_send(Request::Health(tx));
@@ -150,9 +162,43 @@ impl<B: traits::Block> SystemApi<B::Hash, <B::Header as HeaderT>::Number> for Sy
 		}
 	}
 
+	fn system_set_ip_policy(&self, allow: Vec<String>, deny: Vec<String>)
+		-> Compat<BoxFuture<'static, std::result::Result<(), rpc::Error>>>
+	{
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::SetIpPolicy(allow, deny, tx));
+		async move {
+			match rx.await {
+				Ok(Ok(())) => Ok(()),
+				Ok(Err(e)) => Err(rpc::Error::from(e)),
+				Err(_) => Err(rpc::Error::internal_error()),
+			}
+		}.boxed().compat()
+	}
+
+	fn system_peer_reputation(&self, peer_id: String)
+		-> Compat<BoxFuture<'static, std::result::Result<Option<i32>, rpc::Error>>>
+	{
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::PeerReputation(peer_id, tx));
+		async move {
+			match rx.await {
+				Ok(Ok(reputation)) => Ok(reputation),
+				Ok(Err(e)) => Err(rpc::Error::from(e)),
+				Err(_) => Err(rpc::Error::internal_error()),
+			}
+		}.boxed().compat()
+	}
+
 	fn system_node_roles(&self) -> Receiver<Vec<NodeRole>> {
 		let (tx, rx) = oneshot::channel();
 		let _ = self.send_back.unbounded_send(Request::NodeRoles(tx));
 		Receiver(Compat::new(rx))
 	}
+
+	fn system_privacy_status(&self) -> Receiver<PrivacyStatus> {
+		let (tx, rx) = oneshot::channel();
+		let _ = self.send_back.unbounded_send(Request::PrivacyStatus(tx));
+		Receiver(Compat::new(rx))
+	}
 }
@@ -96,6 +96,29 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 				Request::NodeRoles(sender) => {
 					let _ = sender.send(vec![NodeRole::Authority]);
 				}
+				Request::PrivacyStatus(sender) => {
+					let _ = sender.send(PrivacyStatus {
+						mode: PrivacyMode::Enforced,
+						reserved_peers: 2,
+						refused_connections: 1,
+						members: 3,
+						light_clients: 0,
+						last_sync_block: Some(5),
+						seconds_since_last_sync: Some(6),
+					});
+				}
+				Request::PeerReputation(peer, sender) => {
+					let _ = match peer.parse::<PeerId>() {
+						Ok(_) => sender.send(Ok(Some(-256))),
+						Err(s) => sender.send(Err(error::Error::MalformattedPeerArg(s.to_string()))),
+					};
+				}
+				Request::SetIpPolicy(allow, deny, sender) => {
+					let x = allow.iter().chain(deny.iter())
+						.try_for_each(|range| range.parse::<sc_network::config::IpNet>().map(|_| ()))
+						.map_err(error::Error::MalformattedIpRange);
+					let _ = sender.send(x);
+				}
 			};
 
 			future::ready(())
@@ -106,6 +129,12 @@ fn api<T: Into<Option<Status>>>(sync: T) -> System<Block> {
 		impl_version: "0.2.0".into(),
 		chain_name: "testchain".into(),
 		properties: Default::default(),
+		host_functions: vec![HostFunction {
+			name: "ext_storage_get_version_1".into(),
+			version: 1,
+			args: vec!["I64".into()],
+			return_value: Some("I64".into()),
+		}],
 	}, tx)
 }
 
@@ -146,6 +175,13 @@ fn system_properties_works() {
 	);
 }
 
+#[test]
+fn system_host_functions_works() {
+	let host_functions = api(None).system_host_functions().unwrap();
+	assert_eq!(host_functions.len(), 1);
+	assert_eq!(host_functions[0].name, "ext_storage_get_version_1");
+}
+
 #[test]
 fn system_health() {
 	assert_matches!(
@@ -246,6 +282,14 @@ fn system_node_roles() {
 	);
 }
 
+#[test]
+fn system_privacy_status() {
+	let status = wait_receiver(api(None).system_privacy_status());
+	assert_eq!(status.mode, PrivacyMode::Enforced);
+	assert_eq!(status.refused_connections, 1);
+	assert_eq!(status.last_sync_block, Some(5));
+}
+
 #[test]
 fn system_network_add_reserved() {
 	let good_peer_id = "/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
@@ -269,3 +313,25 @@ fn system_network_remove_reserved() {
 	assert_eq!(runtime.block_on(good_fut), Ok(()));
 	assert!(runtime.block_on(bad_fut).is_err());
 }
+
+#[test]
+fn system_peer_reputation() {
+	let good_peer_id = "QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV";
+	let bad_peer_id = "/ip4/198.51.100.19/tcp/30333";
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let good_fut = api(None).system_peer_reputation(good_peer_id.into());
+	let bad_fut = api(None).system_peer_reputation(bad_peer_id.into());
+	assert_eq!(runtime.block_on(good_fut), Ok(Some(-256)));
+	assert!(runtime.block_on(bad_fut).is_err());
+}
+
+#[test]
+fn system_set_ip_policy() {
+	let mut runtime = tokio::runtime::current_thread::Runtime::new().unwrap();
+
+	let good_fut = api(None).system_set_ip_policy(vec!["10.0.0.0/8".into()], vec!["10.1.0.0/16".into()]);
+	let bad_fut = api(None).system_set_ip_policy(vec![], vec!["10.0.0.0/33".into()]);
+	assert_eq!(runtime.block_on(good_fut), Ok(()));
+	assert!(runtime.block_on(bad_fut).is_err());
+}
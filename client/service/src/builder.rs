@@ -17,7 +17,10 @@
 use crate::{Service, NetworkStatus, NetworkState, error::Error, DEFAULT_PROTOCOL_ID, MallocSizeOfWasm};
 use crate::{TaskManagerBuilder, start_rpc_servers, build_network_future, TransactionPoolAdapter};
 use crate::status_sinks;
-use crate::config::{Configuration, DatabaseConfig, KeystoreConfig, PrometheusConfig};
+use crate::upgrade_dry_run::DryRunState;
+use crate::config::{
+	Configuration, DatabaseConfig, KeystoreConfig, PrometheusConfig, TelemetrySigningKey,
+};
 use sc_client_api::{
 	self,
 	BlockchainEvents,
@@ -27,7 +30,7 @@ use sc_client_api::{
 };
 use sc_client::Client;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
-use sc_chain_spec::get_extension;
+use sc_chain_spec::{get_extension, NetworkPrivacyExtension, WasmFuelExtension};
 use sp_consensus::import_queue::ImportQueue;
 use futures::{
 	Future, FutureExt, StreamExt,
@@ -35,7 +38,10 @@ use futures::{
 };
 use sc_keystore::{Store as Keystore};
 use log::{info, warn, error};
-use sc_network::config::{FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
+use sc_network::config::{
+	FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder, MembershipValidator,
+	NodeKeyConfig, Secret, TransactionsKeyProvider, WarpSyncProvider,
+};
 use sc_network::{NetworkService, NetworkStateInfo};
 use parking_lot::{Mutex, RwLock};
 use sp_runtime::generic::BlockId;
@@ -50,10 +56,13 @@ use std::{
 };
 use wasm_timer::SystemTime;
 use sysinfo::{get_current_pid, ProcessExt, System, SystemExt};
-use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+use sc_telemetry::{telemetry, SUBSTRATE_INFO, TelemetrySigner};
+use sp_core::traits::BareCryptoStore;
 use sp_transaction_pool::MaintainedTransactionPool;
 use sp_blockchain;
-use prometheus_endpoint::{register, Gauge, U64, F64, Registry, PrometheusError, Opts, GaugeVec};
+use prometheus_endpoint::{
+	register, Counter, CounterVec, Gauge, U64, F64, Registry, PrometheusError, Opts, GaugeVec,
+};
 
 struct ServiceMetrics {
 	block_height_number: GaugeVec<U64>,
@@ -63,7 +72,11 @@ struct ServiceMetrics {
 	network_per_sec_bytes: GaugeVec<U64>,
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
+	state_cache_evictions: Counter<U64>,
+	trie_cache: Gauge<U64>,
+	trie_cache_reads: CounterVec<U64>,
 	state_db: GaugeVec<U64>,
+	deprecated_host_function_calls: CounterVec<U64>,
 }
 
 impl ServiceMetrics {
@@ -92,10 +105,27 @@ impl ServiceMetrics {
 			state_cache: register(Gauge::new(
 				"state_cache_bytes", "State cache size in bytes",
 			)?, registry)?,
+			state_cache_evictions: register(Counter::new(
+				"state_cache_evictions", "Number of entries evicted from the state cache",
+			)?, registry)?,
+			trie_cache: register(Gauge::new(
+				"trie_cache_bytes", "Trie node cache size in bytes",
+			)?, registry)?,
+			trie_cache_reads: register(CounterVec::new(
+				Opts::new("trie_cache_reads", "Number of trie node reads, by cache result"),
+				&["result"]
+			)?, registry)?,
 			state_db: register(GaugeVec::new(
 				Opts::new("state_db_cache_bytes", "State DB cache in bytes"),
 				&["subtype"]
 			)?, registry)?,
+			deprecated_host_function_calls: register(CounterVec::new(
+				Opts::new(
+					"deprecated_host_function_calls_total",
+					"Number of calls of deprecated host functions",
+				),
+				&["function"]
+			)?, registry)?,
 		})
 	}
 }
@@ -122,9 +152,9 @@ pub type BackgroundTask = Pin<Box<dyn Future<Output=()> + Send>>;
 pub struct ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 	TExPool, TRpc, Backend>
 {
-	config: Configuration,
+	pub (crate) config: Configuration,
 	pub (crate) client: Arc<TCl>,
-	backend: Arc<Backend>,
+	pub (crate) backend: Arc<Backend>,
 	tasks_builder: TaskManagerBuilder,
 	keystore: Arc<RwLock<Keystore>>,
 	fetcher: Option<TFchr>,
@@ -137,6 +167,9 @@ pub struct ServiceBuilder<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp,
 	remote_backend: Option<Arc<dyn RemoteBlockchain<TBl>>>,
 	marker: PhantomData<(TBl, TRtApi)>,
 	background_tasks: Vec<(&'static str, BackgroundTask)>,
+	membership_validator: Option<Arc<dyn MembershipValidator>>,
+	transactions_key_provider: Option<Arc<dyn TransactionsKeyProvider>>,
+	warp_sync_provider: Option<Arc<dyn WarpSyncProvider<TBl>>>,
 }
 
 /// Full client type.
@@ -192,6 +225,109 @@ type TFullParts<TBl, TRtApi, TExecDisp> = (
 	TaskManagerBuilder,
 );
 
+/// The fuel limit of the wasm execution set by the `wasmFuel` extension of the chain spec.
+fn wasm_fuel_limit(config: &Configuration) -> Option<u64> {
+	let limit = get_extension::<WasmFuelExtension>(config.expect_chain_spec().extensions())
+		.and_then(|wasm_fuel| wasm_fuel.as_ref())
+		.map(|wasm_fuel| wasm_fuel.limit);
+	if let Some(limit) = limit {
+		info!("⛽ Blocks are imported in wasm and limited to {} fuel", limit);
+	}
+	limit
+}
+
+/// Read the operator key and the reserved nodes of the network from the storage keys written by
+/// the `networkPrivacy` chain spec extension.
+///
+/// They are read at the best block, or at genesis if the best state isn't available, e.g. on
+/// light clients.
+fn on_chain_network_privacy<TBl, TBackend, TExec, TRtApi>(
+	client: &Client<TBackend, TExec, TBl, TRtApi>,
+) -> Result<(Option<sp_core::sr25519::Public>, Option<Vec<String>>), Error> where
+	TBl: BlockT,
+	TBackend: sc_client_api::backend::Backend<TBl>,
+	TExec: sc_client::CallExecutor<TBl>,
+{
+	use codec::Decode;
+	use sc_client_api::StorageProvider;
+	use sp_network_privacy::well_known_keys;
+
+	let read = |key: &[u8]| -> Result<Option<Vec<u8>>, Error> {
+		let key = sp_core::storage::StorageKey(key.to_vec());
+		client.storage(&BlockId::Hash(client.chain_info().best_hash), &key)
+			.or_else(|_| client.storage(&BlockId::Number(0u32.into()), &key))
+			.map(|value| value.map(|value| value.0))
+			.map_err(|e| Error::Other(format!("Failed to read the network privacy storage: {}", e)))
+	};
+	let decode_err = |e: codec::Error| Error::Other(format!("Invalid network privacy storage: {}", e));
+
+	let operator_key = read(well_known_keys::OPERATOR_KEY)?
+		.map(|value| <[u8; 32]>::decode(&mut &value[..]).map(sp_core::sr25519::Public))
+		.transpose()
+		.map_err(decode_err)?;
+	let reserved_nodes = read(well_known_keys::RESERVED_NODES)?
+		.map(|value| Vec::<Vec<u8>>::decode(&mut &value[..]))
+		.transpose()
+		.map_err(decode_err)?
+		.map(|nodes| nodes.into_iter()
+			.map(|node| String::from_utf8(node)
+				.map_err(|_| Error::Other("Invalid reserved node in the network privacy storage".into())))
+			.collect::<Result<Vec<_>, _>>())
+		.transpose()?;
+
+	Ok((operator_key, reserved_nodes))
+}
+
+/// Route the key types of `config.remote_signers` to their signer.
+fn set_remote_signers(keystore: &Arc<RwLock<Keystore>>, config: &Configuration) -> Result<(), Error> {
+	for (key_type, endpoint) in &config.remote_signers {
+		keystore.write().set_remote_signer(*key_type, sc_keystore::RemoteSigner::new(endpoint.clone()))?;
+		info!("🔑 Keys of type {:?} are held by the remote signer at {}", key_type, endpoint);
+	}
+	Ok(())
+}
+
+/// Signs telemetry payloads with the first sr25519 key of a type in the keystore.
+struct SessionKeySigner {
+	keystore: Arc<RwLock<Keystore>>,
+	key_type: sp_core::crypto::KeyTypeId,
+}
+
+impl TelemetrySigner for SessionKeySigner {
+	fn sign(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+		let keystore = self.keystore.read();
+		let public = keystore.sr25519_public_keys(self.key_type).into_iter().next()?;
+		let signature = keystore.sr25519_sign(self.key_type, &public, payload)?;
+		Some((public.as_ref().to_vec(), signature.as_ref().to_vec()))
+	}
+}
+
+/// The signer of the telemetry payloads configured with `config.telemetry_signing_key`.
+fn telemetry_signer(
+	config: &Configuration,
+	keystore: &Arc<RwLock<Keystore>>,
+) -> Option<Arc<dyn TelemetrySigner>> {
+	match config.telemetry_signing_key {
+		TelemetrySigningKey::NodeKey => match config.network.node_key.clone() {
+			NodeKeyConfig::Ed25519(Secret::New) => {
+				warn!("Telemetry payloads can't be signed with a new node key, persist it in a file");
+				None
+			},
+			node_key => match node_key.into_keypair() {
+				Ok(keypair) => Some(Arc::new(keypair)),
+				Err(e) => {
+					warn!("Failed to load the node key to sign telemetry payloads: {}", e);
+					None
+				},
+			},
+		},
+		TelemetrySigningKey::SessionKey(key_type) => Some(Arc::new(SessionKeySigner {
+			keystore: keystore.clone(),
+			key_type,
+		})),
+	}
+}
+
 /// Creates a new full client for the given config.
 pub fn new_full_client<TBl, TRtApi, TExecDisp>(
 	config: &Configuration,
@@ -216,14 +352,18 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 		KeystoreConfig::InMemory => Keystore::new_in_memory(),
 		KeystoreConfig::None => return Err("No keystore config provided!".into()),
 	};
+	set_remote_signers(&keystore, config)?;
 
 	let tasks_builder = TaskManagerBuilder::new();
 
+	let fuel_limit = wasm_fuel_limit(config);
 	let executor = NativeExecutor::<TExecDisp>::new(
 		config.wasm_method,
 		config.default_heap_pages,
 		config.max_runtime_instances,
-	);
+	)
+		.with_runtime_cache_size(config.runtime_cache_size)
+		.with_fuel_limit(fuel_limit);
 
 	let chain_spec = config.expect_chain_spec();
 	let fork_blocks = get_extension::<sc_client::ForkBlocks<TBl>>(chain_spec.extensions())
@@ -239,20 +379,33 @@ fn new_full_parts<TBl, TRtApi, TExecDisp>(
 			state_cache_size: config.state_cache_size,
 			state_cache_child_ratio:
 			config.state_cache_child_ratio.map(|v| (v, 100)),
+			trie_cache_size: config.trie_cache_size,
+			blocks_pruning: config.blocks_pruning.clone(),
 			pruning: config.pruning.clone(),
+			state_retention: sc_client_api::StateRetention::new(
+				config.pruning_retained_prefixes.iter().cloned(),
+			),
 			source: match config.expect_database() {
 				DatabaseConfig::Path { path, cache_size } =>
 					sc_client_db::DatabaseSettingsSrc::Path {
 						path: path.clone(),
 						cache_size: cache_size.clone().map(|u| u as usize),
 					},
+				DatabaseConfig::ParityDb { path } =>
+					sc_client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
 				DatabaseConfig::Custom(db) =>
 					sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
 			},
 		};
 
+		let mut execution_strategies = config.execution_strategies.clone();
+		if fuel_limit.is_some() {
+			// native execution isn't metered, it would import the blocks running out of fuel.
+			execution_strategies.syncing = sp_state_machine::ExecutionStrategy::AlwaysWasm;
+			execution_strategies.importing = sp_state_machine::ExecutionStrategy::AlwaysWasm;
+		}
 		let extensions = sc_client_api::execution_extensions::ExecutionExtensions::new(
-			config.execution_strategies.clone(),
+			execution_strategies,
 			Some(keystore.clone()),
 		);
 
@@ -307,6 +460,9 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			rpc_extensions: Default::default(),
 			remote_backend: None,
 			background_tasks: Default::default(),
+			membership_validator: None,
+			transactions_key_provider: None,
+			warp_sync_provider: None,
 			marker: PhantomData,
 		})
 	}
@@ -337,25 +493,35 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			KeystoreConfig::InMemory => Keystore::new_in_memory(),
 			KeystoreConfig::None => return Err("No keystore config provided!".into()),
 		};
+		set_remote_signers(&keystore, &config)?;
 
 		let executor = NativeExecutor::<TExecDisp>::new(
 			config.wasm_method,
 			config.default_heap_pages,
 			config.max_runtime_instances,
-		);
+		)
+			.with_runtime_cache_size(config.runtime_cache_size)
+			.with_fuel_limit(wasm_fuel_limit(&config));
 
 		let db_storage = {
 			let db_settings = sc_client_db::DatabaseSettings {
 				state_cache_size: config.state_cache_size,
 				state_cache_child_ratio:
 					config.state_cache_child_ratio.map(|v| (v, 100)),
+				trie_cache_size: config.trie_cache_size,
+				blocks_pruning: config.blocks_pruning.clone(),
 				pruning: config.pruning.clone(),
+				state_retention: sc_client_api::StateRetention::new(
+					config.pruning_retained_prefixes.iter().cloned(),
+				),
 				source: match config.expect_database() {
 					DatabaseConfig::Path { path, cache_size } =>
 						sc_client_db::DatabaseSettingsSrc::Path {
 							path: path.clone(),
 							cache_size: cache_size.clone().map(|u| u as usize),
 						},
+					DatabaseConfig::ParityDb { path } =>
+						sc_client_db::DatabaseSettingsSrc::ParityDb { path: path.clone() },
 					DatabaseConfig::Custom(db) =>
 						sc_client_db::DatabaseSettingsSrc::Custom(db.clone()),
 				},
@@ -396,6 +562,9 @@ impl ServiceBuilder<(), (), (), (), (), (), (), (), (), (), ()> {
 			rpc_extensions: Default::default(),
 			remote_backend: Some(remote_blockchain),
 			background_tasks: Default::default(),
+			membership_validator: None,
+			transactions_key_provider: None,
+			warp_sync_provider: None,
 			marker: PhantomData,
 		})
 	}
@@ -479,6 +648,9 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions: self.rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
@@ -522,6 +694,9 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions: self.rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
@@ -560,6 +735,9 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions: self.rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
@@ -622,6 +800,9 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions: self.rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
@@ -676,6 +857,9 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions: self.rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
@@ -704,9 +888,39 @@ impl<TBl, TRtApi, TCl, TFchr, TSc, TImpQu, TFprb, TFpp, TExPool, TRpc, Backend>
 			rpc_extensions,
 			remote_backend: self.remote_backend,
 			background_tasks: self.background_tasks,
+			membership_validator: self.membership_validator,
+			transactions_key_provider: self.transactions_key_provider,
+			warp_sync_provider: self.warp_sync_provider,
 			marker: self.marker,
 		})
 	}
+
+	/// Defines which peers are allowed to be connected to in a private network.
+	pub fn with_membership_validator(
+		mut self,
+		builder: impl FnOnce(&Self) -> Result<Arc<dyn MembershipValidator>, Error>,
+	) -> Result<Self, Error> {
+		self.membership_validator = Some(builder(&self)?);
+		Ok(self)
+	}
+
+	/// Defines the keys transactions are encrypted with in a private network.
+	pub fn with_transactions_key_provider(
+		mut self,
+		builder: impl FnOnce(&Self) -> Result<Arc<dyn TransactionsKeyProvider>, Error>,
+	) -> Result<Self, Error> {
+		self.transactions_key_provider = Some(builder(&self)?);
+		Ok(self)
+	}
+
+	/// Defines how to prove finality to warp syncing nodes, and how to warp sync ourselves.
+	pub fn with_warp_sync_provider(
+		mut self,
+		builder: impl FnOnce(&Self) -> Result<Arc<dyn WarpSyncProvider<TBl>>, Error>,
+	) -> Result<Self, Error> {
+		self.warp_sync_provider = Some(builder(&self)?);
+		Ok(self)
+	}
 }
 
 /// Implemented on `ServiceBuilder`. Allows running block commands, such as import/export/validate
@@ -743,6 +957,41 @@ pub trait ServiceBuilderCommand {
 		self,
 		block: BlockId<Self::Block>
 	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+	/// Performs the export of the state of a block, the last finalized block by default.
+	fn export_state(
+		self,
+		output: impl Write + 'static,
+		block: Option<BlockId<Self::Block>>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>;
+
+	/// Imports the block and state of a snapshot made by `export_state` into an empty database.
+	fn import_state(
+		self,
+		input: impl Read + Send + 'static,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>>;
+
+	/// Prunes the bodies and justifications of the finalized blocks according to `pruning`.
+	fn prune_blocks(
+		&self,
+		pruning: &sc_client_api::BlocksPruning,
+	) -> Result<(), Error>;
+
+	/// Executes the migrations of the runtime `code` against `state` and reports the weight
+	/// they consume and the storage entries they change, without committing anything.
+	fn dry_run_upgrade(
+		self,
+		code: Vec<u8>,
+		state: DryRunState<Self::Block>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>;
+
+	/// Executes the blocks `from..=to`, up to the best block by default, natively and in wasm
+	/// and reports the blocks whose executions diverge. Fails if any block diverges.
+	fn check_block_determinism(
+		self,
+		from: NumberFor<Self::Block>,
+		to: Option<NumberFor<Self::Block>>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>>;
 }
 
 impl<TBl, TRtApi, TBackend, TExec, TSc, TImpQu, TExPool, TRpc>
@@ -817,6 +1066,9 @@ ServiceBuilder<
 			rpc_extensions,
 			remote_backend,
 			background_tasks,
+			membership_validator,
+			transactions_key_provider,
+			warp_sync_provider,
 		} = self;
 
 		sp_session::generate_initial_session_keys(
@@ -845,6 +1097,12 @@ ServiceBuilder<
 		client.execution_extensions()
 			.register_transaction_pool(Arc::downgrade(&transaction_pool) as _);
 
+		// make the offchain index readable to off-chain runtime calls, e.g. to prove key
+		// ownership in past sessions.
+		if let Some(offchain_storage) = backend.offchain_storage() {
+			client.execution_extensions().register_offchain_db(offchain_storage);
+		}
+
 		let transaction_pool_adapter = Arc::new(TransactionPoolAdapter {
 			imports_external_transactions: !config.roles.is_light(),
 			pool: transaction_pool.clone(),
@@ -868,6 +1126,20 @@ ServiceBuilder<
 		let block_announce_validator =
 			Box::new(sp_consensus::block_validation::DefaultBlockAnnounceValidator::new(client.clone()));
 
+		let mut network_config = config.network.clone();
+		if let Some(Some(network_privacy)) =
+			get_extension::<NetworkPrivacyExtension>(chain_spec.extensions())
+		{
+			let (operator_key, reserved_nodes) = on_chain_network_privacy(&*client)?;
+			network_privacy.verify(chain_spec.boot_nodes(), operator_key.as_ref()).map_err(Error::Other)?;
+			let reserved_nodes = reserved_nodes.unwrap_or_else(|| network_privacy.reserved_nodes.clone());
+			for node in reserved_nodes {
+				if !network_config.reserved_nodes.contains(&node) {
+					network_config.reserved_nodes.push(node);
+				}
+			}
+		}
+
 		let network_params = sc_network::config::Params {
 			roles: config.roles,
 			executor: {
@@ -876,7 +1148,7 @@ ServiceBuilder<
 					spawn_handle.spawn("libp2p-node", fut);
 				}))
 			},
-			network_config: config.network.clone(),
+			network_config,
 			chain: client.clone(),
 			finality_proof_provider,
 			finality_proof_request_builder,
@@ -885,7 +1157,10 @@ ServiceBuilder<
 			import_queue,
 			protocol_id,
 			block_announce_validator,
-			metrics_registry: config.prometheus_config.as_ref().map(|config| config.registry.clone())
+			metrics_registry: config.prometheus_config.as_ref().map(|config| config.registry.clone()),
+			membership_validator,
+			transactions_key_provider,
+			warp_sync_provider,
 		};
 
 		let has_bootnodes = !network_params.network_config.boot_nodes.is_empty();
@@ -894,9 +1169,15 @@ ServiceBuilder<
 		let network_status_sinks = Arc::new(Mutex::new(status_sinks::StatusSinks::new()));
 
 		let offchain_storage = backend.offchain_storage();
+		let state_retention = backend.state_retention();
 		let offchain_workers = match (config.offchain_worker, offchain_storage.clone()) {
 			(true, Some(db)) => {
-				Some(Arc::new(sc_offchain::OffchainWorkers::new(client.clone(), db)))
+				let offchain_workers = sc_offchain::OffchainWorkers::new(client.clone(), db)
+					.with_http_tls(&config.offchain_http_tls)
+					.map_err(|e| Error::Other(format!("Invalid offchain HTTP TLS settings: {}", e)))?
+					.with_scheduling(config.offchain_scheduling.clone())
+					.with_ipfs(config.offchain_ipfs.clone());
+				Some(Arc::new(offchain_workers))
 			},
 			(true, None) => {
 				warn!("Offchain workers disabled, due to lack of offchain storage support in backend.");
@@ -919,6 +1200,31 @@ ServiceBuilder<
 			sc_transaction_pool::notification_future(client.clone(), transaction_pool.clone()),
 		);
 
+		// Resubmit the transactions saved on the last shutdown and save them again on the next one.
+		let pool_persistence = config.transaction_pool_path.clone().map(|path| {
+			match crate::pool_persistence::load::<TBl::Extrinsic>(&path) {
+				Ok(transactions) if !transactions.is_empty() => {
+					info!("💾 Restoring {} pool transactions from {}", transactions.len(), path.display());
+					let best_block_id = BlockId::hash(client.chain_info().best_hash);
+					let source = sp_transaction_pool::TransactionSource::Local;
+					let restore = transaction_pool.submit_at(&best_block_id, source, transactions)
+						.map(|result| match result {
+							Ok(results) => info!(
+								"💾 Restored {} pool transactions",
+								results.iter().filter(|r| r.is_ok()).count(),
+							),
+							Err(err) => warn!("Failed to restore pool transactions: {:?}", err),
+						});
+					spawn_handle.spawn("txpool-restore", restore);
+				},
+				Ok(_) => {},
+				Err(err) => warn!("Failed to read pool transactions from {}: {}", path.display(), err),
+			}
+
+			Box::new(crate::pool_persistence::PoolPersistence::new(path, transaction_pool.clone()))
+				as Box<dyn std::any::Any + Send + Sync>
+		});
+
 		// Inform the offchain worker about new imported blocks
 		{
 			let offchain = offchain_workers.as_ref().map(Arc::downgrade);
@@ -1061,6 +1367,15 @@ ServiceBuilder<
 				if let Some(info) = info.usage.as_ref() {
 					metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
 					metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+					// the evictions are counted since the start of the node.
+					let evictions = info.io.state_cache_evictions
+						.saturating_sub(metrics.state_cache_evictions.get());
+					metrics.state_cache_evictions.inc_by(evictions);
+					metrics.trie_cache.set(info.memory.trie_cache.as_bytes() as u64);
+					for &(result, reads) in &[("hit", info.io.trie_cache_hits), ("miss", info.io.trie_cache_misses)] {
+						let counter = metrics.trie_cache_reads.with_label_values(&[result]);
+						counter.inc_by(reads.saturating_sub(counter.get()));
+					}
 
 					metrics.state_db.with_label_values(&["non_canonical"]).set(info.memory.state_db.non_canonical.as_bytes() as u64);
 					if let Some(pruning) = info.memory.state_db.pruning {
@@ -1068,6 +1383,12 @@ ServiceBuilder<
 					}
 					metrics.state_db.with_label_values(&["pinned"]).set(info.memory.state_db.pinned.as_bytes() as u64);
 				}
+
+				// the calls are counted since the start of the node.
+				for (function, calls) in sp_runtime_interface::deprecation::deprecated_calls() {
+					let counter = metrics.deprecated_host_function_calls.with_label_values(&[function]);
+					counter.inc_by(calls.saturating_sub(counter.get()));
+				}
 			}
 
 			ready(())
@@ -1096,17 +1417,33 @@ ServiceBuilder<
 
 		// RPC
 		let (system_rpc_tx, system_rpc_rx) = tracing_unbounded("mpsc_system_rpc");
-		let gen_handler = || {
-			use sc_rpc::{chain, state, author, system, offchain};
+		let subscription_metrics = config.prometheus_config.as_ref()
+			.map(|config| sc_rpc::SubscriptionMetrics::register(&config.registry))
+			.transpose()?;
+		let gen_handler = |middleware: sc_rpc_server::RpcMiddleware| {
+			use sc_rpc::{chain, state, author, system, offchain, pruning, network as network_rpc};
 
 			let system_info = sc_rpc::system::SystemInfo {
 				chain_name: chain_spec.name().into(),
 				impl_name: config.impl_name.into(),
 				impl_version: config.impl_version.into(),
 				properties: chain_spec.properties().clone(),
+				host_functions: sp_io::substrate_host_functions_metadata()
+					.into_iter()
+					.map(|f| sc_rpc::system::HostFunction {
+						name: f.name.into(),
+						version: f.version,
+						args: f.args.iter().map(|a| format!("{:?}", a)).collect(),
+						return_value: f.return_value.map(|r| format!("{:?}", r)),
+					})
+					.collect(),
 			};
 
-			let subscriptions = sc_rpc::Subscriptions::new(Arc::new(tasks_builder.spawn_handle()));
+			let subscriptions = sc_rpc::Subscriptions::with_limits(
+				Arc::new(tasks_builder.spawn_handle()),
+				config.rpc_subscription_limits.clone(),
+				subscription_metrics.clone(),
+			);
 
 			let (chain, state) = if let (Some(remote_backend), Some(on_demand)) =
 				(remote_backend.as_ref(), on_demand.as_ref()) {
@@ -1132,6 +1469,13 @@ ServiceBuilder<
 				(chain, state)
 			};
 
+			let access_events_network = network.clone();
+			let access_events_buffer = config.rpc_subscription_limits.buffer;
+			let network_api = network_rpc::Network::new(subscriptions.clone(), move || {
+				access_events_network.access_event_stream(access_events_buffer)
+					.map(access_event_to_rpc)
+					.boxed()
+			});
 			let author = sc_rpc::author::Author::new(
 				client.clone(),
 				transaction_pool.clone(),
@@ -1139,6 +1483,8 @@ ServiceBuilder<
 				keystore.clone(),
 			);
 			let system = system::System::new(system_info, system_rpc_tx.clone());
+			let pruning_api = state_retention.clone()
+				.map(|retention| pruning::PruningApi::to_delegate(pruning::Pruning::new(retention)));
 
 			match offchain_storage.clone() {
 				Some(storage) => {
@@ -1149,20 +1495,25 @@ ServiceBuilder<
 						offchain::OffchainApi::to_delegate(offchain),
 						author::AuthorApi::to_delegate(author),
 						system::SystemApi::to_delegate(system),
+						network_rpc::NetworkApi::to_delegate(network_api),
+						pruning_api,
 						rpc_extensions.clone(),
-					))
+					), middleware)
 				},
 				None => sc_rpc_server::rpc_handler((
 					state::StateApi::to_delegate(state),
 					chain::ChainApi::to_delegate(chain),
 					author::AuthorApi::to_delegate(author),
 					system::SystemApi::to_delegate(system),
+					network_rpc::NetworkApi::to_delegate(network_api),
+					pruning_api,
 					rpc_extensions.clone(),
-				))
+				), middleware)
 			}
 		};
-		let rpc_handlers = gen_handler();
-		let rpc = start_rpc_servers(&config, gen_handler)?;
+		// In-process queries aren't restricted by the access policy.
+		let rpc_handlers = gen_handler(Default::default());
+		let rpc = start_rpc_servers(&config, &spawn_handle, gen_handler)?;
 
 		spawn_handle.spawn(
 			"network-worker",
@@ -1190,6 +1541,8 @@ ServiceBuilder<
 			let telemetry = sc_telemetry::init_telemetry(sc_telemetry::TelemetryConfig {
 				endpoints,
 				wasm_external_transport: config.telemetry_external_transport.take(),
+				auth: config.telemetry_auth.clone(),
+				signer: telemetry_signer(&config, &keystore),
 			});
 			let startup_time = SystemTime::UNIX_EPOCH.elapsed()
 				.map(|dur| dur.as_millis())
@@ -1246,6 +1599,7 @@ ServiceBuilder<
 			essential_failed_rx,
 			rpc_handlers,
 			_rpc: rpc,
+			_pool_persistence: pool_persistence,
 			_telemetry: telemetry,
 			_offchain_workers: offchain_workers,
 			_telemetry_on_connect_sinks: telemetry_connection_sinks.clone(),
@@ -1276,4 +1630,34 @@ async fn extrinsic_notifications<TBl, TExPool>(
 			ready(())
 		})
 		.await;
-}
\ No newline at end of file
+}
+/// Converts an access event of the network into its RPC representation, timestamped with the
+/// current time.
+fn access_event_to_rpc(event: sc_network::AccessEvent) -> sc_rpc::network::AccessEvent {
+	use sc_rpc::network::{AccessEventKind, RefusalReason};
+
+	let timestamp = SystemTime::UNIX_EPOCH.elapsed()
+		.map(|dur| dur.as_millis() as u64)
+		.unwrap_or(0);
+	let (remote, kind) = match event {
+		sc_network::AccessEvent::Accepted { remote, incoming } =>
+			(remote, AccessEventKind::Accepted { incoming }),
+		sc_network::AccessEvent::Refused { remote, reason } => {
+			let reason = match reason {
+				sc_network::RefusalReason::NotReserved => RefusalReason::NotReserved,
+				sc_network::RefusalReason::Banned => RefusalReason::Banned,
+				sc_network::RefusalReason::SlotsFull => RefusalReason::SlotsFull,
+				sc_network::RefusalReason::NotMember => RefusalReason::NotMember,
+			};
+			(remote, AccessEventKind::Refused { reason })
+		},
+		sc_network::AccessEvent::Disconnected { remote, reason } =>
+			(remote, AccessEventKind::Disconnected { reason }),
+	};
+
+	sc_rpc::network::AccessEvent {
+		peer_id: remote.to_base58(),
+		timestamp,
+		kind,
+	}
+}
@@ -19,11 +19,14 @@
 use crate::error;
 use crate::builder::{ServiceBuilderCommand, ServiceBuilder};
 use crate::error::Error;
+use crate::state_snapshot::{SnapshotReader, SnapshotWriter};
+use crate::upgrade_dry_run::{DryRunState, StorageDiff, dry_run};
+use crate::determinism::{Determinism, check_block};
 use sc_chain_spec::ChainSpec;
 use log::{warn, info};
 use futures::{future, prelude::*};
 use sp_runtime::traits::{
-	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion
+	Block as BlockT, NumberFor, One, Zero, Header, SaturatedConversion, HashFor,
 };
 use sp_runtime::generic::{BlockId, SignedBlock};
 use codec::{Decode, Encode, IoReader};
@@ -35,7 +38,11 @@ use sp_consensus::{
 use sc_executor::{NativeExecutor, NativeExecutionDispatch};
 
 use std::{io::{Read, Write, Seek}, pin::Pin};
-use sc_client_api::BlockBackend;
+use sc_client_api::{BlockBackend, ProofProvider, StateBackend, StateEntry, backend::Backend as _};
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys};
+
+/// Size of the keys and values read at once when exporting a state.
+const EXPORT_STATE_CHUNK_BYTES: usize = 4 * 1024 * 1024;
 
 /// Build a chain spec json
 pub fn build_spec(spec: &dyn ChainSpec, raw: bool) -> error::Result<String> {
@@ -56,6 +63,7 @@ impl<
 	TExecDisp: 'static + NativeExecutionDispatch,
 	TImpQu: 'static + ImportQueue<TBl>,
 	TRtApi: 'static + Send + Sync,
+	Backend: sc_client_api::backend::Backend<TBl>,
 {
 	type Block = TBl;
 	type NativeDispatch = TExecDisp;
@@ -280,6 +288,15 @@ impl<
 		Ok(())
 	}
 
+	fn prune_blocks(
+		&self,
+		pruning: &sc_client_api::BlocksPruning,
+	) -> Result<(), Error> {
+		let pruned = self.backend.prune_blocks(pruning)?;
+		info!("Pruned the bodies or justifications of {} blocks.", pruned);
+		Ok(())
+	}
+
 	fn check_block(
 		self,
 		block_id: BlockId<TBl>
@@ -296,4 +313,215 @@ impl<
 			Err(e) => Box::pin(future::err(format!("Error reading block: {:?}", e).into())),
 		}
 	}
+
+	fn export_state(
+		self,
+		output: impl Write + 'static,
+		block: Option<BlockId<TBl>>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+		let client = self.client;
+		let export = move || -> Result<(), Error> {
+			let block = block.unwrap_or_else(|| BlockId::Hash(client.chain_info().finalized_hash));
+			let header = client.header(&block)?
+				.ok_or_else(|| Error::Other(format!("Unknown block {:?}", block)))?;
+			// importing the snapshot finalizes its block.
+			let info = client.chain_info();
+			if *header.number() > info.finalized_number
+				|| client.block_hash(*header.number())? != Some(header.hash())
+			{
+				return Err(Error::Other(format!("Block {:?} isn't finalized", block)));
+			}
+			let justification = client.justification(&block)?;
+			info!("Exporting state of block #{} ({})", header.number(), header.hash());
+
+			// the top-level trie first, then the child tries in the order of their keys.
+			let mut writer = SnapshotWriter::new(output, &header, &justification)?;
+			let mut tries = std::collections::VecDeque::new();
+			tries.push_back(None);
+			while let Some(storage_key) = tries.pop_front() {
+				let mut start: Option<Vec<u8>> = None;
+				loop {
+					let (pairs, complete) = client.read_state_chunk(
+						&block,
+						storage_key.as_ref().map(|key: &Vec<u8>| &key[..]),
+						start.as_ref().map(|key| &key[..]),
+						EXPORT_STATE_CHUNK_BYTES,
+					)?;
+					start = pairs.last().map(|(key, _)| key.clone()).or(start);
+					for (key, value) in pairs {
+						writer.push(match &storage_key {
+							Some(storage_key) => StateEntry::Child(storage_key.clone(), key, value),
+							None => {
+								if well_known_keys::is_child_storage_key(&key) {
+									tries.push_back(Some(key.clone()));
+								}
+								StateEntry::Top(key, value)
+							},
+						})?;
+					}
+					if complete {
+						break
+					}
+				}
+			}
+			let entries = writer.finish()?;
+
+			info!("Exported {} state entries", entries);
+			Ok(())
+		};
+
+		Box::pin(future::ready(export()))
+	}
+
+	fn import_state(
+		self,
+		input: impl Read + Send + 'static,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send>> {
+		let client = self.client;
+		let backend = self.backend;
+		let import = move || -> Result<(), Error> {
+			let (header, justification, mut reader) = SnapshotReader::open::<TBl::Header>(input)?;
+			let (hash, number) = (header.hash(), *header.number());
+			if !client.chain_info().best_number.is_zero() {
+				return Err(Error::Other(format!(
+					"Cannot import the state of block #{} ({}) once blocks were imported, the database must be purged first",
+					number,
+					hash,
+				)));
+			}
+
+			info!("Importing state of block #{} ({})", number, hash);
+			backend.import_state(header, justification, Vec::new(), &mut reader)?;
+			info!("Imported {} state entries, syncing on from block #{}", reader.entries(), number);
+			Ok(())
+		};
+
+		Box::pin(future::ready(import()))
+	}
+
+	fn dry_run_upgrade(
+		self,
+		code: Vec<u8>,
+		state: DryRunState<TBl>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+		let client = self.client;
+		let executor = NativeExecutor::<TExecDisp>::new(
+			self.config.wasm_method,
+			self.config.default_heap_pages,
+			self.config.max_runtime_instances,
+		);
+		let in_memory = |pairs: Vec<(Vec<u8>, Vec<u8>)>| {
+			sp_state_machine::InMemoryBackend::<HashFor<TBl>>::from(
+				pairs.into_iter().collect::<std::collections::BTreeMap<_, _>>()
+			)
+		};
+		let run = move || -> Result<(), Error> {
+			let report = match state {
+				DryRunState::Local(block) => {
+					let block = block.unwrap_or_else(|| BlockId::Hash(client.chain_info().finalized_hash));
+					let state = client.state_at(&block)?;
+					info!("Executing migrations against the state of block {:?}", block);
+					dry_run::<TBl, _, _>(&state, &executor, &code)?
+				},
+				DryRunState::Snapshot(input) => {
+					let (header, pairs) = read_snapshot::<TBl::Header>(input)?;
+					info!("Executing migrations against the snapshot of block #{} ({})", header.number(), header.hash());
+					dry_run::<TBl, _, _>(&in_memory(pairs), &executor, &code)?
+				},
+				DryRunState::Pairs(pairs) => {
+					info!("Executing migrations against {} key-value pairs", pairs.len());
+					dry_run::<TBl, _, _>(&in_memory(pairs), &executor, &code)?
+				},
+			};
+
+			for diff in &report.diffs {
+				match diff {
+					StorageDiff::Inserted(key, len) =>
+						info!("  + 0x{} ({} bytes)", HexDisplay::from(key), len),
+					StorageDiff::Removed(key) =>
+						info!("  - 0x{}", HexDisplay::from(key)),
+					StorageDiff::Changed(key, old, new) =>
+						info!("  ~ 0x{} ({} -> {} bytes)", HexDisplay::from(key), old, new),
+				}
+			}
+			info!(
+				"Migrations consumed {} weight and changed {} storage entries, storage root {} -> {}",
+				report.weight,
+				report.diffs.len(),
+				report.root_before,
+				report.root_after,
+			);
+			Ok(())
+		};
+
+		Box::pin(future::ready(run()))
+	}
+
+	fn check_block_determinism(
+		self,
+		from: NumberFor<TBl>,
+		to: Option<NumberFor<TBl>>,
+	) -> Pin<Box<dyn Future<Output = Result<(), Error>>>> {
+		let client = self.client;
+		let executor = NativeExecutor::<TExecDisp>::new(
+			self.config.wasm_method,
+			self.config.default_heap_pages,
+			self.config.max_runtime_instances,
+		);
+		let run = move || -> Result<(), Error> {
+			// the genesis block isn't executed.
+			let mut number = std::cmp::max(from, One::one());
+			let to = to.unwrap_or_else(|| client.chain_info().best_number);
+			let (mut checked, mut skipped, mut divergent) = (0u64, 0u64, 0u64);
+
+			while number <= to {
+				let block = client.block(&BlockId::Number(number))?
+					.ok_or_else(|| Error::Other(format!("Unknown block #{}", number)))?
+					.block;
+				let hash = block.hash();
+				let (header, extrinsics) = block.deconstruct();
+				let parent = BlockId::Hash(*header.parent_hash());
+				let version = client.runtime_version_at(&parent)?;
+				let state = client.state_at(&parent)?;
+
+				match check_block::<TBl, _, _>(&state, &executor, &header, &extrinsics, &version)? {
+					Determinism::NativeUnavailable => {
+						skipped += 1;
+						warn!(
+							"Skipping #{} ({}): the native runtime can't execute spec version {}",
+							number, hash, version.spec_version,
+						);
+					},
+					Determinism::Deterministic(root) => {
+						checked += 1;
+						info!("#{} ({}) is deterministic, storage root {}", number, hash, root);
+					},
+					Determinism::Divergent(divergence) => {
+						checked += 1;
+						divergent += 1;
+						warn!(
+							"#{} ({}) diverges: native {:?}, wasm {:?}{}",
+							number,
+							hash,
+							divergence.native,
+							divergence.wasm,
+							if divergence.events { ", events differ" } else { "" },
+						);
+						for key in &divergence.keys {
+							warn!("  ~ 0x{}", HexDisplay::from(key));
+						}
+					},
+				}
+				number += One::one();
+			}
+
+			info!("Checked {} blocks, {} diverged, {} skipped", checked, divergent, skipped);
+			if divergent > 0 {
+				return Err(Error::Other(format!("{} blocks diverged between native and wasm", divergent)));
+			}
+			Ok(())
+		};
+
+		Box::pin(future::ready(run()))
+	}
 }
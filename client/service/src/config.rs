@@ -18,17 +18,48 @@
 
 pub use sc_client::ExecutionStrategies;
 pub use sc_client_db::{kvdb::KeyValueDB, PruningMode};
+pub use sc_client_api::{BlocksPruning, JustificationsPruning};
 pub use sc_network::config::{ExtTransport, NetworkConfiguration, Roles};
 pub use sc_executor::WasmExecutionMethod;
+pub use sc_offchain::{
+	HttpTlsConfig as OffchainHttpTlsConfig,
+	IpfsConfig as OffchainIpfsConfig,
+	SchedulingConfig as OffchainSchedulingConfig,
+	WorkerLimits as OffchainWorkerLimits,
+};
 
 use std::{future::Future, path::{PathBuf, Path}, pin::Pin, net::SocketAddr, sync::Arc};
-pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::txpool::{Options as TransactionPoolOptions, RevalidateAt, UnsignedLimits};
 use sc_chain_spec::ChainSpec;
-use sp_core::crypto::Protected;
+use sp_core::crypto::{KeyTypeId, Protected};
+pub use sc_keystore::{SignerEndpoint, KEY_PAIR_TYPES};
+pub use sc_rpc::{
+	OverflowPolicy as RpcSubscriptionOverflow,
+	SubscriptionLimits as RpcSubscriptionLimits,
+};
+pub use sc_rpc_server::{BatchLimits as RpcBatchLimits, RpcMethods};
+pub use sc_rpc_grpc::TlsConfig as GrpcTlsConfig;
 use target_info::Target;
 use sc_telemetry::TelemetryEndpoints;
+pub use sc_telemetry::{EndpointAuth as TelemetryEndpointAuth, TelemetryAuth};
 use prometheus_endpoint::Registry;
 
+/// The key telemetry payloads are signed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TelemetrySigningKey {
+	/// The node key, i.e. the key of the `PeerId` of the node. It must be persisted, e.g. in a
+	/// file, as a new node key isn't known outside of the network.
+	NodeKey,
+	/// The first sr25519 key of the given type in the keystore, e.g. a session key.
+	SessionKey(KeyTypeId),
+}
+
+impl Default for TelemetrySigningKey {
+	fn default() -> Self {
+		TelemetrySigningKey::NodeKey
+	}
+}
+
 /// Executable version. Used to pass version information from the root crate.
 #[derive(Clone)]
 pub struct VersionInfo {
@@ -64,20 +95,31 @@ pub struct Configuration {
 	pub task_executor: Option<Arc<dyn Fn(Pin<Box<dyn Future<Output = ()> + Send>>) + Send + Sync>>,
 	/// Extrinsic pool configuration.
 	pub transaction_pool: TransactionPoolOptions,
+	/// Path of the file the transaction pool is saved to on shutdown and restored from at
+	/// startup. `None` if the pool isn't persisted.
+	pub transaction_pool_path: Option<PathBuf>,
 	/// Network configuration.
 	pub network: NetworkConfiguration,
 	/// Path to the base configuration directory.
 	pub config_dir: Option<PathBuf>,
 	/// Configuration for the keystore.
 	pub keystore: KeystoreConfig,
+	/// Key types whose keys are held by a remote signer instead of the keystore.
+	pub remote_signers: Vec<(KeyTypeId, SignerEndpoint)>,
 	/// Configuration for the database.
 	pub database: Option<DatabaseConfig>,
 	/// Size of internal state cache in Bytes
 	pub state_cache_size: usize,
 	/// Size in percent of cache size dedicated to child tries
 	pub state_cache_child_ratio: Option<usize>,
+	/// Size of the trie node cache in Bytes
+	pub trie_cache_size: usize,
 	/// Pruning settings.
 	pub pruning: PruningMode,
+	/// Storage key prefixes whose state history is kept when pruning.
+	pub pruning_retained_prefixes: Vec<Vec<u8>>,
+	/// Pruning of block bodies and justifications.
+	pub blocks_pruning: BlocksPruning,
 	/// Chain configuration.
 	pub chain_spec: Option<Box<dyn ChainSpec>>,
 	/// Node name.
@@ -94,6 +136,18 @@ pub struct Configuration {
 	pub rpc_ws_max_connections: Option<usize>,
 	/// CORS settings for HTTP & WS servers. `None` if all origins are allowed.
 	pub rpc_cors: Option<Vec<String>>,
+	/// TOML file of the access policy of the RPC methods. `None` if all methods are allowed.
+	pub rpc_access_policy: Option<PathBuf>,
+	/// Limits of the RPC subscriptions of each connection.
+	pub rpc_subscription_limits: RpcSubscriptionLimits,
+	/// Limits of the JSON-RPC batches of the HTTP & WS servers.
+	pub rpc_batch_limits: RpcBatchLimits,
+	/// The RPC methods exposed by the HTTP, WS and gRPC servers.
+	pub rpc_methods: RpcMethods,
+	/// gRPC gateway binding address. `None` if disabled.
+	pub grpc: Option<SocketAddr>,
+	/// TLS configuration of the gRPC gateway. `None` if it doesn't use TLS.
+	pub grpc_tls: Option<GrpcTlsConfig>,
 	/// Prometheus endpoint configuration. `None` if disabled.
 	pub prometheus_config: Option<PrometheusConfig>,
 	/// Telemetry service URL. `None` if disabled.
@@ -101,10 +155,21 @@ pub struct Configuration {
 	/// External WASM transport for the telemetry. If `Some`, when connection to a telemetry
 	/// endpoint, this transport will be tried in priority before all others.
 	pub telemetry_external_transport: Option<ExtTransport>,
+	/// Bearer tokens of the telemetry endpoints, and the endpoints telemetry payloads are signed
+	/// for.
+	pub telemetry_auth: TelemetryAuth,
+	/// The key telemetry payloads are signed with.
+	pub telemetry_signing_key: TelemetrySigningKey,
 	/// The default number of 64KB pages to allocate for Wasm execution
 	pub default_heap_pages: Option<u64>,
 	/// Should offchain workers be executed.
 	pub offchain_worker: bool,
+	/// TLS settings of the HTTP requests of offchain workers.
+	pub offchain_http_tls: OffchainHttpTlsConfig,
+	/// Scheduling of the offchain workers of runtimes starting them separately.
+	pub offchain_scheduling: OffchainSchedulingConfig,
+	/// The IPFS node offchain workers fetch and pin content with.
+	pub offchain_ipfs: OffchainIpfsConfig,
 	/// Sentry mode is enabled, the node's role is AUTHORITY but it should not
 	/// actively participate in consensus (i.e. no keystores should be passed to
 	/// consensus modules).
@@ -113,6 +178,8 @@ pub struct Configuration {
 	pub force_authoring: bool,
 	/// Disable GRANDPA when running in validator mode
 	pub disable_grandpa: bool,
+	/// Restart the GRANDPA voter, with a fresh catch-up request, when finality is stalled.
+	pub grandpa_restart_on_stall: bool,
 	/// Development key seed.
 	///
 	/// When running in development mode, the seed will be used to generate authority keys by the keystore.
@@ -127,6 +194,10 @@ pub struct Configuration {
 	///
 	/// The default value is 8.
 	pub max_runtime_instances: usize,
+	/// The number of runtimes, i.e. runtime code versions, kept compiled in the runtime cache.
+	///
+	/// The default value is 2.
+	pub runtime_cache_size: usize,
 }
 
 /// Configuration of the client keystore.
@@ -166,6 +237,12 @@ pub enum DatabaseConfig {
 		cache_size: Option<u32>,
 	},
 
+	/// ParityDB database at a specific path.
+	ParityDb {
+		/// Path to the database.
+		path: PathBuf,
+	},
+
 	/// A custom implementation of an already-open database.
 	Custom(Arc<dyn KeyValueDB>),
 }
@@ -205,30 +282,48 @@ impl Default for Configuration {
 			roles: Roles::FULL,
 			task_executor: None,
 			transaction_pool: Default::default(),
+			transaction_pool_path: None,
 			network: Default::default(),
 			keystore: KeystoreConfig::None,
+			remote_signers: Vec::new(),
 			database: None,
 			state_cache_size: Default::default(),
 			state_cache_child_ratio: Default::default(),
+			trie_cache_size: Default::default(),
 			pruning: PruningMode::default(),
+			pruning_retained_prefixes: Default::default(),
+			blocks_pruning: Default::default(),
 			wasm_method: WasmExecutionMethod::Interpreted,
 			execution_strategies: Default::default(),
 			rpc_http: None,
 			rpc_ws: None,
 			rpc_ws_max_connections: None,
 			rpc_cors: Some(vec![]),
+			rpc_access_policy: None,
+			rpc_subscription_limits: Default::default(),
+			rpc_batch_limits: Default::default(),
+			rpc_methods: Default::default(),
+			grpc: None,
+			grpc_tls: None,
 			prometheus_config: None,
 			telemetry_endpoints: None,
 			telemetry_external_transport: None,
+			telemetry_auth: Default::default(),
+			telemetry_signing_key: Default::default(),
 			default_heap_pages: None,
 			offchain_worker: Default::default(),
+			offchain_http_tls: Default::default(),
+			offchain_scheduling: Default::default(),
+			offchain_ipfs: Default::default(),
 			sentry_mode: false,
 			force_authoring: false,
 			disable_grandpa: false,
+			grandpa_restart_on_stall: false,
 			dev_key_seed: None,
 			tracing_targets: Default::default(),
 			tracing_receiver: Default::default(),
 			max_runtime_instances: 8,
+			runtime_cache_size: sc_executor::DEFAULT_RUNTIME_CACHE_SIZE,
 		}
 	}
 }
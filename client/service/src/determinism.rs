@@ -0,0 +1,227 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Determinism check of the native runtime against the wasm runtime.
+//!
+//! A block is executed on the state of its parent twice, natively and in wasm, the way the block
+//! builder does, and the storage changes of both executions are compared. A divergence points at
+//! nondeterminism in the runtime or at host functions behaving differently when called from the
+//! native runtime and from wasm.
+
+use std::collections::BTreeMap;
+use codec::{Decode, Encode};
+use sc_client_api::StateBackend;
+use sc_executor::{RuntimeInfo, RuntimeVersion};
+use sp_core::{traits::{CodeExecutor, RuntimeCode}, twox_128};
+use sp_runtime::traits::{Block as BlockT, HashFor, Header as HeaderT, NumberFor};
+use sp_state_machine::{
+	backend::BackendRuntimeCode, ChangesTrieState, ExecutionStrategy, OverlayedChanges,
+	StateMachine,
+};
+
+use crate::error::Error;
+
+/// The top-level storage changes of an execution, by key.
+type Changes = BTreeMap<Vec<u8>, Option<Vec<u8>>>;
+
+/// The outcome of executing a block natively and in wasm.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Determinism<Hash> {
+	/// The native runtime can't execute blocks of the runtime of the block.
+	NativeUnavailable,
+	/// Both executions computed the given storage root.
+	Deterministic(Hash),
+	/// The executions diverged.
+	Divergent(Divergence<Hash>),
+}
+
+/// How the native and the wasm executions of a block diverged.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Divergence<Hash> {
+	/// The storage root computed natively, or the error of the native execution.
+	pub native: Result<Hash, String>,
+	/// The storage root computed in wasm, or the error of the wasm execution.
+	pub wasm: Result<Hash, String>,
+	/// The keys changed differently by the executions, ordered by key.
+	pub keys: Vec<Vec<u8>>,
+	/// Whether the executions deposited different events.
+	pub events: bool,
+}
+
+/// Execute the block described by `header` and `extrinsics` natively and in wasm on `state`,
+/// the state of its parent, and compare the executions.
+///
+/// `version` is the version of the runtime of the parent state, the block isn't executed if the
+/// native runtime can't execute it.
+pub(crate) fn check_block<Block, S, E>(
+	state: &S,
+	executor: &E,
+	header: &Block::Header,
+	extrinsics: &[Block::Extrinsic],
+	version: &RuntimeVersion,
+) -> Result<Determinism<Block::Hash>, Error> where
+	Block: BlockT,
+	S: StateBackend<HashFor<Block>>,
+	E: CodeExecutor + RuntimeInfo,
+{
+	if !executor.native_version().runtime_version.can_call_with(version) {
+		return Ok(Determinism::NativeUnavailable);
+	}
+
+	let state_runtime_code = BackendRuntimeCode::new(state);
+	let runtime_code = state_runtime_code.runtime_code().map_err(|e| Error::Other(e.into()))?;
+	let run = |strategy| execute::<Block, _, _>(
+		state,
+		executor,
+		&runtime_code,
+		header,
+		extrinsics,
+		strategy,
+	);
+
+	let native = run(ExecutionStrategy::NativeWhenPossible);
+	let wasm = run(ExecutionStrategy::AlwaysWasm);
+	let (native, wasm, keys) = match (native, wasm) {
+		(Ok((native_root, native_changes)), Ok((wasm_root, wasm_changes))) => {
+			let keys = divergent_keys(&native_changes, &wasm_changes);
+			if native_root == wasm_root && keys.is_empty() {
+				return Ok(Determinism::Deterministic(native_root));
+			}
+			(Ok(native_root), Ok(wasm_root), keys)
+		},
+		(native, wasm) => (
+			native.map(|(root, _)| root),
+			wasm.map(|(root, _)| root),
+			Vec::new(),
+		),
+	};
+
+	let events_key = [twox_128(b"System"), twox_128(b"Events")].concat();
+	Ok(Determinism::Divergent(Divergence {
+		native,
+		wasm,
+		events: keys.contains(&events_key),
+		keys,
+	}))
+}
+
+/// Build the block described by `header` and `extrinsics` on `state` with `strategy`, and
+/// return the storage root it ends up with and its changes.
+fn execute<Block, S, E>(
+	state: &S,
+	executor: &E,
+	runtime_code: &RuntimeCode,
+	header: &Block::Header,
+	extrinsics: &[Block::Extrinsic],
+	strategy: ExecutionStrategy,
+) -> Result<(Block::Hash, Changes), String> where
+	Block: BlockT,
+	S: StateBackend<HashFor<Block>>,
+	E: CodeExecutor,
+{
+	let mut overlay = OverlayedChanges::default();
+	let mut call = |method: &str, data: &[u8]| {
+		StateMachine::new(
+			state,
+			None::<ChangesTrieState<HashFor<Block>, NumberFor<Block>>>,
+			&mut overlay,
+			executor,
+			method,
+			data,
+			Default::default(),
+			runtime_code,
+			sp_core::tasks::executor(),
+		)
+			.execute(strategy)
+			.map_err(|e| format!("{} failed: {}", method, e))
+	};
+
+	call("Core_initialize_block", &sc_block_builder::initial_header::<Block>(header).encode())?;
+	for extrinsic in extrinsics {
+		call("BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+	}
+	let finalized = call("BlockBuilder_finalize_block", &[])?;
+	let finalized = Block::Header::decode(&mut &finalized[..]).map_err(|e| e.what().to_string())?;
+
+	let changes = overlay.prospective_changes()
+		.map(|(key, value)| (key.to_vec(), value.map(|value| value.to_vec())))
+		.collect();
+	Ok((*finalized.state_root(), changes))
+}
+
+/// The keys changed by only one of the executions or to different values, ordered by key.
+fn divergent_keys(native: &Changes, wasm: &Changes) -> Vec<Vec<u8>> {
+	let mut keys = native.iter()
+		.filter(|(key, value)| wasm.get(*key) != Some(value))
+		.map(|(key, _)| key.clone())
+		.chain(wasm.keys().filter(|key| !native.contains_key(*key)).cloned())
+		.collect::<Vec<_>>();
+	keys.sort();
+	keys
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_block_builder::BlockBuilderProvider;
+	use sp_runtime::generic::BlockId;
+	use substrate_test_runtime_client::{
+		prelude::*,
+		runtime::{self, Block},
+	};
+
+	#[test]
+	fn divergent_keys_are_listed_in_order() {
+		let native: Changes = vec![
+			(vec![1], Some(vec![1])),
+			(vec![3], None),
+			(vec![4], Some(vec![4])),
+		].into_iter().collect();
+		let wasm: Changes = vec![
+			(vec![1], Some(vec![1])),
+			(vec![2], Some(vec![2])),
+			(vec![3], Some(vec![3])),
+		].into_iter().collect();
+
+		assert_eq!(divergent_keys(&native, &wasm), vec![vec![2], vec![3], vec![4]]);
+		assert!(divergent_keys(&native, &native).is_empty());
+	}
+
+	#[test]
+	fn native_and_wasm_execution_match() {
+		let client = substrate_test_runtime_client::new();
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		}).unwrap();
+		let block = builder.build().unwrap().block;
+		let (header, extrinsics) = (block.header, block.extrinsics);
+
+		let parent = BlockId::Hash(header.parent_hash);
+		let state = client.state_at(&parent).unwrap();
+		let version = client.runtime_version_at(&parent).unwrap();
+		let executor = substrate_test_runtime_client::new_native_executor();
+
+		match check_block::<Block, _, _>(&state, &executor, &header, &extrinsics, &version) {
+			Ok(Determinism::Deterministic(root)) => assert_eq!(root, header.state_root),
+			result => panic!("Unexpected result: {:?}", result),
+		}
+	}
+}
@@ -25,8 +25,12 @@ pub mod chain_ops;
 pub mod error;
 
 mod builder;
+mod determinism;
+mod pool_persistence;
+mod state_snapshot;
 mod status_sinks;
 mod task_manager;
+mod upgrade_dry_run;
 
 use std::{borrow::Cow, io, pin::Pin};
 use std::marker::PhantomData;
@@ -45,7 +49,7 @@ use futures::{
 	task::{Spawn, FutureObj, SpawnError},
 };
 use sc_network::{NetworkService, network_state::NetworkState, PeerId, ReportHandle};
-use log::{log, warn, debug, error, Level};
+use log::{log, info, warn, debug, error, Level};
 use codec::{Encode, Decode};
 use sp_runtime::generic::BlockId;
 use sp_runtime::traits::{NumberFor, Block as BlockT};
@@ -67,11 +71,13 @@ pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
 pub use sc_client::FinalityNotifications;
 pub use sc_rpc::Metadata as RpcMetadata;
 pub use sc_executor::NativeExecutionDispatch;
+pub use sc_client_db::migration as db_migration;
 #[doc(hidden)]
 pub use std::{ops::Deref, result::Result, sync::Arc};
 #[doc(hidden)]
 pub use sc_network::config::{FinalityProofProvider, OnDemand, BoxFinalityProofRequestBuilder};
 pub use task_manager::{TaskManagerBuilder, SpawnTaskHandle};
+pub use upgrade_dry_run::DryRunState;
 use task_manager::TaskManager;
 
 const DEFAULT_PROTOCOL_ID: &str = "sup";
@@ -103,6 +109,7 @@ pub struct Service<TBl, TCl, TSc, TNetStatus, TNet, TTxPool, TOc> {
 	essential_failed_rx: TracingUnboundedReceiver<()>,
 	rpc_handlers: sc_rpc_server::RpcHandler<sc_rpc::Metadata>,
 	_rpc: Box<dyn std::any::Any + Send + Sync>,
+	_pool_persistence: Option<Box<dyn std::any::Any + Send + Sync>>,
 	_telemetry: Option<sc_telemetry::Telemetry>,
 	_telemetry_on_connect_sinks: Arc<Mutex<Vec<TracingUnboundedSender<()>>>>,
 	_offchain_workers: Option<Arc<TOc>>,
@@ -391,6 +398,24 @@ fn build_network_future<
 						))),
 					};
 				}
+				sc_rpc::system::Request::SetIpPolicy(allow, deny, sender) => {
+					let parse = |ranges: Vec<String>| ranges.iter()
+						.map(|range| range.parse::<sc_network::config::IpNet>())
+						.collect::<Result<Vec<_>, _>>();
+					let x = parse(allow)
+						.and_then(|allow| Ok(sc_network::config::IpPolicy { allow, deny: parse(deny)? }))
+						.map(|policy| network.set_ip_policy(policy))
+						.map_err(sc_rpc::system::error::Error::MalformattedIpRange);
+					let _ = sender.send(x);
+				}
+				sc_rpc::system::Request::PeerReputation(peer_id, sender) => {
+					let _ = match peer_id.parse::<PeerId>() {
+						Ok(peer_id) => sender.send(Ok(network.peer_reputation(&peer_id))),
+						Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+							e.to_string(),
+						))),
+					};
+				}
 				sc_rpc::system::Request::NodeRoles(sender) => {
 					use sc_rpc::system::NodeRole;
 
@@ -406,6 +431,29 @@ fn build_network_future<
 
 					let _ = sender.send(node_roles);
 				}
+				sc_rpc::system::Request::PrivacyStatus(sender) => {
+					use sc_rpc::system::{PrivacyMode, PrivacyStatus};
+
+					let status = network.privacy_status();
+					let membership = status.membership.clone().and_then(|membership| membership);
+					let mode = match status.membership {
+						None => PrivacyMode::Disabled,
+						Some(Some(ref membership)) if membership.enforced => PrivacyMode::Enforced,
+						Some(_) => PrivacyMode::Open,
+					};
+
+					let _ = sender.send(PrivacyStatus {
+						mode,
+						reserved_peers: status.reserved_peers,
+						refused_connections: status.refused_connections,
+						members: membership.as_ref().map_or(0, |m| m.members),
+						light_clients: membership.as_ref().map_or(0, |m| m.light_clients),
+						last_sync_block: membership.as_ref().and_then(|m| m.last_sync_block),
+						seconds_since_last_sync: membership.as_ref()
+							.and_then(|m| m.since_last_sync)
+							.map(|since| since.as_secs()),
+					});
+				}
 			};
 		}
 
@@ -489,10 +537,13 @@ mod waiting {
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(not(target_os = "unknown"))]
-fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
+fn start_rpc_servers<H>(
 	config: &Configuration,
+	spawn_handle: &SpawnTaskHandle,
 	mut gen_handler: H
-) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
+) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> where
+	H: FnMut(sc_rpc_server::RpcMiddleware) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>,
+{
 	fn maybe_start_server<T, F>(address: Option<SocketAddr>, mut start: F) -> Result<Option<T>, io::Error>
 		where F: FnMut(&SocketAddr) -> Result<T, io::Error>,
 	{
@@ -511,10 +562,40 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 		})
 	}
 
+	let policy = match config.rpc_access_policy {
+		Some(ref path) => Some(Arc::new(sc_rpc_server::AccessPolicy::load(path.clone()).map_err(|e| {
+			error::Error::Other(format!("Failed to load the RPC access policy {}: {}", path.display(), e))
+		})?)),
+		None => None,
+	};
+	let middleware = sc_rpc_server::RpcMiddleware::new(policy, Some(config.rpc_batch_limits.clone()));
+
+	let grpc = maybe_start_server(
+		config.grpc,
+		|address| sc_rpc_grpc::start_grpc(
+			address,
+			gen_handler(middleware.clone().deny_unsafe(config.rpc_methods.denies_unsafe(address))),
+			config.rpc_access_policy.is_some(),
+			config.grpc_tls.as_ref(),
+		),
+	)?;
+	if let Some((address, server)) = grpc {
+		info!("gRPC server listening on {}", address);
+		spawn_handle.spawn("grpc-server", async move {
+			if let Err(e) = server.await {
+				error!("gRPC server on {} failed: {}", address, e);
+			}
+		});
+	}
+
 	Ok(Box::new((
 		maybe_start_server(
 			config.rpc_http,
-			|address| sc_rpc_server::start_http(address, config.rpc_cors.as_ref(), gen_handler()),
+			|address| sc_rpc_server::start_http(
+				address,
+				config.rpc_cors.as_ref(),
+				gen_handler(middleware.clone().deny_unsafe(config.rpc_methods.denies_unsafe(address))),
+			),
 		)?.map(|s| waiting::HttpServer(Some(s))),
 		maybe_start_server(
 			config.rpc_ws,
@@ -522,7 +603,7 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 				address,
 				config.rpc_ws_max_connections,
 				config.rpc_cors.as_ref(),
-				gen_handler(),
+				gen_handler(middleware.clone().deny_unsafe(config.rpc_methods.denies_unsafe(address))),
 			),
 		)?.map(|s| waiting::WsServer(Some(s))).map(Mutex::new),
 	)))
@@ -530,10 +611,13 @@ fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
 
 /// Starts RPC servers that run in their own thread, and returns an opaque object that keeps them alive.
 #[cfg(target_os = "unknown")]
-fn start_rpc_servers<H: FnMut() -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>>(
+fn start_rpc_servers<H>(
 	_: &Configuration,
+	_: &SpawnTaskHandle,
 	_: H
-) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> {
+) -> Result<Box<dyn std::any::Any + Send + Sync>, error::Error> where
+	H: FnMut(sc_rpc_server::RpcMiddleware) -> sc_rpc_server::RpcHandler<sc_rpc::Metadata>,
+{
 	Ok(Box::new(()))
 }
 
@@ -617,9 +701,17 @@ where
 			return;
 		}
 
+		let peer = who.to_base58();
+		if self.pool.is_peer_banned(&peer) {
+			debug!("Transaction from temporarily banned peer {} rejected", peer);
+			report_handle.report_peer(who, reputation_change_bad);
+			return;
+		}
+
 		let encoded = transaction.encode();
 		match Decode::decode(&mut &encoded[..]) {
 			Ok(uxt) => {
+				self.pool.on_received(self.pool.hash_of(&uxt), peer);
 				let best_block_id = BlockId::hash(self.client.info().best_hash);
 				let source = sp_transaction_pool::TransactionSource::External;
 				let import_future = self.pool.submit_one(&best_block_id, source, uxt);
@@ -0,0 +1,109 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Persistence of the transaction pool across restarts.
+//!
+//! On shutdown the ready and future transactions of the pool are written to a flat file, which is
+//! read back (and removed) at startup. The restored transactions are resubmitted to the pool as
+//! local transactions, so they are revalidated against the new best block.
+
+use std::{fs, io, path::{Path, PathBuf}, sync::Arc};
+use codec::{Decode, Encode};
+use log::{info, warn};
+use sp_transaction_pool::{InPoolTransaction, TransactionPool};
+
+/// Saves the ready and future transactions of the pool to a file when dropped.
+pub struct PoolPersistence<Pool> {
+	path: PathBuf,
+	pool: Arc<Pool>,
+}
+
+impl<Pool: TransactionPool> PoolPersistence<Pool> {
+	/// Creates a new `PoolPersistence` saving the transactions of `pool` to `path`.
+	pub fn new(path: PathBuf, pool: Arc<Pool>) -> Self {
+		PoolPersistence { path, pool }
+	}
+
+	fn save(&self) -> io::Result<usize> {
+		let transactions = self.pool.ready()
+			.map(|tx| tx.data().clone())
+			.chain(self.pool.futures().into_iter().map(|tx| tx.data().clone()))
+			.collect::<Vec<_>>();
+		// a crash while writing leaves the previous file intact.
+		let tmp_path = self.path.with_extension("tmp");
+		fs::write(&tmp_path, transactions.encode())?;
+		fs::rename(&tmp_path, &self.path)?;
+		Ok(transactions.len())
+	}
+}
+
+impl<Pool> Drop for PoolPersistence<Pool> where Pool: TransactionPool {
+	fn drop(&mut self) {
+		match self.save() {
+			Ok(count) => info!("💾 Saved {} pool transactions to {}", count, self.path.display()),
+			Err(err) => warn!("Failed to save pool transactions to {}: {}", self.path.display(), err),
+		}
+	}
+}
+
+/// Reads the transactions saved by `PoolPersistence` from `path` and removes the file.
+///
+/// Returns no transactions if the file doesn't exist. A file that can't be decoded is moved
+/// aside to `<path>.corrupt` instead, so it isn't overwritten on the next shutdown.
+pub fn load<Ex: Decode>(path: &Path) -> io::Result<Vec<Ex>> {
+	let encoded = match fs::read(path) {
+		Ok(encoded) => encoded,
+		Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(err) => return Err(err),
+	};
+
+	match Decode::decode(&mut &encoded[..]) {
+		Ok(transactions) => {
+			fs::remove_file(path)?;
+			Ok(transactions)
+		},
+		Err(err) => {
+			fs::rename(path, path.with_extension("corrupt"))?;
+			Err(io::Error::new(io::ErrorKind::InvalidData, err.what()))
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn load_reads_and_removes_saved_transactions() {
+		let path = std::env::temp_dir().join(format!("txpool-persistence-{}", std::process::id()));
+		assert_eq!(load::<Vec<u8>>(&path).unwrap(), Vec::<Vec<u8>>::new());
+
+		fs::write(&path, vec![vec![1u8, 2], vec![3]].encode()).unwrap();
+		assert_eq!(load::<Vec<u8>>(&path).unwrap(), vec![vec![1u8, 2], vec![3]]);
+		assert!(!path.exists());
+	}
+
+	#[test]
+	fn load_keeps_undecodable_file() {
+		let path = std::env::temp_dir().join(format!("txpool-persistence-corrupt-{}", std::process::id()));
+		fs::write(&path, vec![0xffu8, 0xff, 0xff]).unwrap();
+
+		assert_eq!(load::<Vec<u8>>(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+		assert!(!path.exists());
+		assert_eq!(fs::read(path.with_extension("corrupt")).unwrap(), vec![0xffu8, 0xff, 0xff]);
+		fs::remove_file(path.with_extension("corrupt")).unwrap();
+	}
+}
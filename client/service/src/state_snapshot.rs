@@ -0,0 +1,287 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Binary format of state snapshots.
+//!
+//! A snapshot starts with the `MAGIC` bytes, the format version, the header of the block whose
+//! state it contains and the justification of that block, if any. The state entries follow in
+//! chunks, the top-level entries first and then the entries of each child trie, each chunk
+//! followed by the blake2-256 hash of its encoding. An empty chunk ends the entries and is
+//! followed by a trailer holding the number of entries and the hash chaining all chunk hashes,
+//! so truncated, reordered or extended snapshots are detected.
+//!
+//! Snapshots are read as they are imported, without holding the state in memory.
+
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use codec::{Decode, Encode, IoReader};
+use sc_client_api::StateEntry;
+use sp_core::hashing::blake2_256;
+use sp_runtime::Justification;
+
+use crate::error::Error;
+
+/// Bytes every snapshot starts with.
+const MAGIC: [u8; 8] = *b"plugsnap";
+
+/// Current snapshot format version.
+const VERSION: u32 = 2;
+
+/// Number of state entries in a chunk.
+const CHUNK_SIZE: usize = 1024;
+
+/// Chain the hash of a chunk to the hash of the chunks before it.
+fn chain_hash(chained: &[u8; 32], chunk_hash: &[u8; 32]) -> [u8; 32] {
+	let mut input = chained.to_vec();
+	input.extend_from_slice(chunk_hash);
+	blake2_256(&input)
+}
+
+/// Streams the state of a block to a snapshot.
+pub(crate) struct SnapshotWriter<W> {
+	output: W,
+	chunk: Vec<StateEntry>,
+	chained_hash: [u8; 32],
+	entries: u64,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+	/// Start a snapshot of the state of the block with the given header and justification.
+	pub(crate) fn new<H: Encode>(
+		mut output: W,
+		header: &H,
+		justification: &Option<Justification>,
+	) -> Result<Self, Error> {
+		output.write_all(&MAGIC)?;
+		output.write_all(&VERSION.encode())?;
+		output.write_all(&header.encode())?;
+		output.write_all(&justification.encode())?;
+
+		Ok(SnapshotWriter {
+			output,
+			chunk: Vec::with_capacity(CHUNK_SIZE),
+			chained_hash: [0; 32],
+			entries: 0,
+		})
+	}
+
+	/// Add a state entry to the snapshot, in the order expected by `Backend::import_state`.
+	pub(crate) fn push(&mut self, entry: StateEntry) -> Result<(), Error> {
+		self.chunk.push(entry);
+		self.entries += 1;
+		if self.chunk.len() == CHUNK_SIZE {
+			self.write_chunk()?;
+		}
+
+		Ok(())
+	}
+
+	/// End the snapshot, returning the number of state entries written.
+	pub(crate) fn finish(mut self) -> Result<u64, Error> {
+		if !self.chunk.is_empty() {
+			self.write_chunk()?;
+		}
+		self.write_chunk()?;
+		self.output.write_all(&self.entries.encode())?;
+		self.output.write_all(&self.chained_hash)?;
+		self.output.flush()?;
+
+		Ok(self.entries)
+	}
+
+	fn write_chunk(&mut self) -> Result<(), Error> {
+		let encoded = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE)).encode();
+		let hash = blake2_256(&encoded);
+		self.output.write_all(&encoded)?;
+		self.output.write_all(&hash)?;
+		self.chained_hash = chain_hash(&self.chained_hash, &hash);
+
+		Ok(())
+	}
+}
+
+/// Reads the state entries of a snapshot, checking them chunk by chunk.
+///
+/// Yields an error, and then nothing, as soon as the snapshot turns out corrupted, truncated or
+/// extended. The entries are only complete once the iterator ended without an error.
+pub(crate) struct SnapshotReader<R> {
+	input: IoReader<R>,
+	chunk: VecDeque<StateEntry>,
+	chained_hash: [u8; 32],
+	entries: u64,
+	finished: bool,
+}
+
+impl<R: Read> SnapshotReader<R> {
+	/// Open a snapshot, returning the header and justification of its block and a reader of its
+	/// state entries.
+	pub(crate) fn open<H: Decode>(input: R) -> Result<(H, Option<Justification>, Self), Error> {
+		let mut input = IoReader(input);
+
+		let magic = <[u8; 8]>::decode(&mut input).map_err(|e| decode_err("magic", e))?;
+		if magic != MAGIC {
+			return Err("Not a state snapshot".into());
+		}
+		let version = u32::decode(&mut input).map_err(|e| decode_err("version", e))?;
+		if version != VERSION {
+			return Err(Error::Other(format!("Unsupported state snapshot version {}", version)));
+		}
+		let header = H::decode(&mut input).map_err(|e| decode_err("header", e))?;
+		let justification = Option::<Justification>::decode(&mut input)
+			.map_err(|e| decode_err("justification", e))?;
+
+		let reader = SnapshotReader {
+			input,
+			chunk: VecDeque::new(),
+			chained_hash: [0; 32],
+			entries: 0,
+			finished: false,
+		};
+		Ok((header, justification, reader))
+	}
+
+	fn read_chunk(&mut self) -> Result<(), Error> {
+		let chunk = Vec::<StateEntry>::decode(&mut self.input).map_err(|e| decode_err("chunk", e))?;
+		let hash = <[u8; 32]>::decode(&mut self.input).map_err(|e| decode_err("chunk hash", e))?;
+		if blake2_256(&chunk.encode()) != hash {
+			return Err(Error::Other(format!("Corrupted state snapshot chunk after {} entries", self.entries)));
+		}
+		self.chained_hash = chain_hash(&self.chained_hash, &hash);
+		self.entries += chunk.len() as u64;
+
+		if chunk.is_empty() {
+			self.finished = true;
+			let entries = u64::decode(&mut self.input).map_err(|e| decode_err("entry count", e))?;
+			let chained_hash = <[u8; 32]>::decode(&mut self.input).map_err(|e| decode_err("hash", e))?;
+			if entries != self.entries || chained_hash != self.chained_hash {
+				return Err("State snapshot chunks don't match the snapshot trailer".into());
+			}
+			if self.input.0.read(&mut [0u8])? != 0 {
+				return Err("Unexpected data after the state snapshot".into());
+			}
+		}
+		self.chunk = chunk.into();
+
+		Ok(())
+	}
+
+	/// Number of state entries read so far.
+	pub(crate) fn entries(&self) -> u64 {
+		self.entries
+	}
+}
+
+impl<R: Read> Iterator for SnapshotReader<R> {
+	type Item = sp_blockchain::Result<StateEntry>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(entry) = self.chunk.pop_front() {
+				return Some(Ok(entry))
+			}
+			if self.finished {
+				return None
+			}
+			if let Err(e) = self.read_chunk() {
+				self.finished = true;
+				self.chunk.clear();
+				return Some(Err(sp_blockchain::Error::Msg(e.to_string())))
+			}
+		}
+	}
+}
+
+fn decode_err(what: &str, e: codec::Error) -> Error {
+	Error::Other(format!("Error decoding snapshot {}: {}", what, e.what()))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn entries() -> Vec<StateEntry> {
+		let top = (0..CHUNK_SIZE as u32 * 2 + 1)
+			.map(|i| StateEntry::Top(i.to_be_bytes().to_vec(), vec![i as u8; 3]));
+		let child = (0..10u8)
+			.map(|i| StateEntry::Child(b":child_storage:default:child".to_vec(), vec![i], vec![i]));
+		top.chain(child).collect()
+	}
+
+	fn snapshot(entries: &[StateEntry]) -> Vec<u8> {
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output, &42u64, &Some(vec![1, 2, 3])).unwrap();
+		for entry in entries {
+			writer.push(entry.clone()).unwrap();
+		}
+		assert_eq!(writer.finish().unwrap(), entries.len() as u64);
+		output
+	}
+
+	fn read(snapshot: &[u8]) -> Result<Vec<StateEntry>, Error> {
+		let (_, _, reader) = SnapshotReader::open::<u64>(snapshot)?;
+		reader.collect::<Result<Vec<_>, _>>().map_err(|e| Error::Other(e.to_string()))
+	}
+
+	#[test]
+	fn snapshot_roundtrip() {
+		let entries = entries();
+		let encoded = snapshot(&entries);
+
+		let (header, justification, reader) = SnapshotReader::open::<u64>(&encoded[..]).unwrap();
+		assert_eq!(header, 42);
+		assert_eq!(justification, Some(vec![1, 2, 3]));
+		assert_eq!(reader.collect::<Result<Vec<_>, _>>().unwrap(), entries);
+
+		assert!(read(&snapshot(&[])).unwrap().is_empty());
+	}
+
+	#[test]
+	fn corrupted_snapshot_is_rejected() {
+		let entries = entries();
+		let encoded = snapshot(&entries);
+
+		// flip a byte of the last child entry, before its chunk hash, the empty chunk and the
+		// trailer.
+		let mut corrupted = encoded.clone();
+		let last_value = corrupted.len() - 32 - 1 - 32 - 8 - 32 - 1;
+		corrupted[last_value] ^= 1;
+		assert!(read(&corrupted).is_err());
+
+		// truncated anywhere, e.g. in the trailer or between chunks
+		assert!(read(&encoded[..encoded.len() - 1]).is_err());
+		assert!(read(&encoded[..encoded.len() - 32 - 8 - 32 - 1]).is_err());
+
+		// extended
+		let mut extended = encoded.clone();
+		extended.push(0);
+		assert!(read(&extended).is_err());
+
+		assert!(read(&b"notasnap"[..]).is_err());
+	}
+
+	#[test]
+	fn entries_are_read_before_the_end_is_checked() {
+		let entries = entries();
+		let encoded = snapshot(&entries);
+
+		let (_, _, mut reader) = SnapshotReader::open::<u64>(&encoded[..encoded.len() - 1]).unwrap();
+		for entry in &entries {
+			assert_eq!(reader.next().unwrap().unwrap(), *entry);
+		}
+		assert!(reader.next().unwrap().is_err());
+		assert!(reader.next().is_none());
+	}
+}
@@ -0,0 +1,171 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dry run of the migrations of a runtime upgrade.
+//!
+//! The `on_runtime_upgrade` hooks of the new runtime are executed through the `MigrationApi` on
+//! top of an existing state. The changes are collected but never committed to the backend.
+
+use std::io::Read;
+use codec::{Decode, Encode};
+use sc_client_api::StateBackend;
+use sp_core::{
+	storage::well_known_keys,
+	traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode},
+};
+use sp_runtime::generic::BlockId;
+use sp_runtime::traits::{Block as BlockT, Hash as HashT, HashFor, NumberFor};
+use sp_state_machine::{
+	ChangesTrieState, ExecutionStrategy, OverlayedChanges, StateMachine, StorageTransactionCache,
+};
+
+use crate::error::Error;
+
+/// Name of the runtime function executing the migrations.
+const ON_RUNTIME_UPGRADE: &str = "MigrationApi_on_runtime_upgrade";
+
+/// The state the migrations of a runtime upgrade are executed against.
+pub enum DryRunState<Block: BlockT> {
+	/// The state of a block in the local database, the last finalized block by default.
+	Local(Option<BlockId<Block>>),
+	/// A snapshot made by the `export-state` command.
+	Snapshot(Box<dyn Read + Send>),
+	/// Top-level key-value pairs, e.g. downloaded from a remote node.
+	Pairs(Vec<(Vec<u8>, Vec<u8>)>),
+}
+
+/// A storage entry changed by the migrations.
+#[derive(Debug, PartialEq)]
+pub(crate) enum StorageDiff {
+	/// The key didn't exist before.
+	Inserted(Vec<u8>, usize),
+	/// The key was removed.
+	Removed(Vec<u8>),
+	/// The value of the key changed size, from the first to the second length.
+	Changed(Vec<u8>, usize, usize),
+}
+
+/// The outcome of a dry run.
+#[derive(Debug)]
+pub(crate) struct DryRunReport<Hash> {
+	/// The weight the migrations reported to consume.
+	pub weight: u64,
+	/// The storage root before the migrations.
+	pub root_before: Hash,
+	/// The storage root after the migrations.
+	pub root_after: Hash,
+	/// The changed storage entries, ordered by key.
+	pub diffs: Vec<StorageDiff>,
+}
+
+/// Execute the migrations of the runtime `code` on top of `state`.
+pub(crate) fn dry_run<Block, S, E>(
+	state: &S,
+	executor: &E,
+	code: &[u8],
+) -> Result<DryRunReport<Block::Hash>, Error> where
+	Block: BlockT,
+	S: StateBackend<HashFor<Block>>,
+	E: CodeExecutor,
+{
+	let heap_pages = state.storage(well_known_keys::HEAP_PAGES)
+		.map_err(|e| Error::Other(format!("Error reading heap pages: {}", e)))?
+		.and_then(|d| Decode::decode(&mut &d[..]).ok());
+	let code_fetcher = WrappedRuntimeCode(code.into());
+	let runtime_code = RuntimeCode {
+		code_fetcher: &code_fetcher,
+		heap_pages,
+		hash: <HashFor<Block> as HashT>::hash(code).encode(),
+	};
+
+	let mut overlay = OverlayedChanges::default();
+	let output = StateMachine::new(
+		state,
+		None::<ChangesTrieState<HashFor<Block>, NumberFor<Block>>>,
+		&mut overlay,
+		executor,
+		ON_RUNTIME_UPGRADE,
+		&[],
+		Default::default(),
+		&runtime_code,
+		sp_core::tasks::executor(),
+	)
+		.execute(ExecutionStrategy::AlwaysWasm)
+		.map_err(|e| Error::Other(format!("Error executing the migrations: {}", e)))?;
+	let weight = u64::decode(&mut &output[..])
+		.map_err(|e| Error::Other(format!("Error decoding the migrations weight: {}", e.what())))?;
+
+	let root_before = state.storage_root(std::iter::empty()).0;
+	let changes = overlay.drain_storage_changes(
+		state,
+		None::<&ChangesTrieState<HashFor<Block>, NumberFor<Block>>>,
+		Default::default(),
+		&mut StorageTransactionCache::default(),
+	).map_err(Error::Other)?;
+
+	let mut diffs = Vec::new();
+	for (key, value) in changes.main_storage_changes {
+		let old = state.storage(&key)
+			.map_err(|e| Error::Other(format!("Error reading storage: {}", e)))?;
+		match (old, value) {
+			(None, Some(new)) => diffs.push(StorageDiff::Inserted(key, new.len())),
+			(Some(_), None) => diffs.push(StorageDiff::Removed(key)),
+			(Some(old), Some(new)) if old != new =>
+				diffs.push(StorageDiff::Changed(key, old.len(), new.len())),
+			_ => {},
+		}
+	}
+
+	Ok(DryRunReport {
+		weight,
+		root_before,
+		root_after: changes.transaction_storage_root,
+		diffs,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use substrate_test_runtime_client::runtime::{Block, WASM_BINARY};
+
+	#[test]
+	fn migrations_are_reported() {
+		let state = sp_state_machine::InMemoryBackend::<HashFor<Block>>::default();
+		let executor = substrate_test_runtime_client::new_native_executor();
+
+		let report = dry_run::<Block, _, _>(&state, &executor, WASM_BINARY).unwrap();
+		assert_eq!(report.weight, 100);
+		assert_eq!(report.diffs, vec![StorageDiff::Inserted(b":migrated".to_vec(), 1)]);
+		assert_eq!(report.root_before, state.storage_root(std::iter::empty()).0);
+		assert_ne!(report.root_after, report.root_before);
+		// the changes are never committed.
+		assert_eq!(state.storage(b":migrated").unwrap(), None);
+	}
+
+	#[test]
+	fn failing_migrations_fail() {
+		let state = sp_state_machine::InMemoryBackend::<HashFor<Block>>::from(
+			std::iter::once((b":migrated".to_vec(), vec![1])).collect::<std::collections::BTreeMap<_, _>>(),
+		);
+		let executor = substrate_test_runtime_client::new_native_executor();
+
+		match dry_run::<Block, _, _>(&state, &executor, WASM_BINARY) {
+			Err(Error::Other(e)) => assert!(e.starts_with("Error executing the migrations")),
+			result => panic!("Unexpected result: {:?}", result),
+		}
+	}
+}
@@ -171,6 +171,9 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 			use_yamux_flow_control: true,
 		},
 		max_parallel_downloads: NetworkConfiguration::default().max_parallel_downloads,
+		protocol_slots: Default::default(),
+		ip_policy: Default::default(),
+		sync_mode: Default::default(),
 	};
 
 	Configuration {
@@ -180,11 +183,13 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		roles: role,
 		task_executor: Some(task_executor),
 		transaction_pool: Default::default(),
+		transaction_pool_path: None,
 		network: network_config,
 		keystore: KeystoreConfig::Path {
 			path: root.join("key"),
 			password: None
 		},
+		remote_signers: Vec::new(),
 		config_dir: Some(root.clone()),
 		database: Some(DatabaseConfig::Path {
 			path: root.join("db"),
@@ -192,7 +197,10 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		}),
 		state_cache_size: 16777216,
 		state_cache_child_ratio: None,
+		trie_cache_size: 16777216,
+		blocks_pruning: Default::default(),
 		pruning: Default::default(),
+		pruning_retained_prefixes: Default::default(),
 		chain_spec: Some(Box::new((*spec).clone())),
 		name: format!("Node {}", index),
 		wasm_method: sc_service::config::WasmExecutionMethod::Interpreted,
@@ -201,18 +209,31 @@ fn node_config<G: RuntimeGenesis + 'static, E: ChainSpecExtension + Clone + 'sta
 		rpc_ws: None,
 		rpc_ws_max_connections: None,
 		rpc_cors: None,
+		rpc_access_policy: None,
+		rpc_subscription_limits: Default::default(),
+		rpc_batch_limits: Default::default(),
+		rpc_methods: Default::default(),
+		grpc: None,
+		grpc_tls: None,
 		prometheus_config: None,
 		telemetry_endpoints: None,
 		telemetry_external_transport: None,
+		telemetry_auth: Default::default(),
+		telemetry_signing_key: Default::default(),
 		default_heap_pages: None,
 		offchain_worker: false,
+		offchain_http_tls: Default::default(),
+		offchain_scheduling: Default::default(),
+		offchain_ipfs: Default::default(),
 		sentry_mode: false,
 		force_authoring: false,
 		disable_grandpa: false,
+		grandpa_restart_on_stall: false,
 		dev_key_seed: key_seed,
 		tracing_targets: None,
 		tracing_receiver: Default::default(),
 		max_runtime_instances: 8,
+		runtime_cache_size: 2,
 	}
 }
 
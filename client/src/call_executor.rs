@@ -21,7 +21,7 @@ use sp_runtime::{
 };
 use sp_state_machine::{
 	self, OverlayedChanges, Ext, ExecutionManager, StateMachine, ExecutionStrategy,
-	backend::Backend as _, StorageProof,
+	backend::Backend as _, StorageProof, ReadRecordingBackend, StorageKey, StorageValue,
 };
 use sc_executor::{RuntimeVersion, RuntimeInfo, NativeVersion};
 use sp_externalities::Extensions;
@@ -52,6 +52,47 @@ impl<B, E> LocalCallExecutor<B, E> {
 	}
 }
 
+impl<B, E> LocalCallExecutor<B, E> where E: CodeExecutor + Clone + 'static {
+	/// Execute a call in wasm on top of the state of the given block and the given changes,
+	/// keeping the changes made by the call in `changes`.
+	///
+	/// Returns the result of the call and the values it read from the state of the block, see
+	/// `ReadRecordingBackend`. The calls of a block executed one after the other on the same
+	/// `changes` re-execute that block.
+	pub fn call_recording_reads<Block: BlockT>(
+		&self,
+		at: &BlockId<Block>,
+		method: &str,
+		call_data: &[u8],
+		changes: &mut OverlayedChanges,
+		extensions: Option<Extensions>,
+	) -> sp_blockchain::Result<(Vec<u8>, Vec<(StorageKey, Option<StorageValue>)>)>
+	where
+		B: backend::Backend<Block>,
+	{
+		let changes_trie = backend::changes_tries_state_at_block(
+			at, self.backend.changes_trie_storage()
+		)?;
+		let state = self.backend.state_at(*at)?;
+		let state_runtime_code = sp_state_machine::backend::BackendRuntimeCode::new(&state);
+		let runtime_code = state_runtime_code.runtime_code()?;
+		let recording_state = ReadRecordingBackend::new(&state);
+		let return_data = StateMachine::new(
+			&recording_state,
+			changes_trie,
+			changes,
+			&self.executor,
+			method,
+			call_data,
+			extensions.unwrap_or_default(),
+			&runtime_code,
+			self.spawn_handle.clone(),
+		).execute(ExecutionStrategy::AlwaysWasm)?;
+
+		Ok((return_data, recording_state.take_reads()))
+	}
+}
+
 impl<B, E> Clone for LocalCallExecutor<B, E> where E: Clone {
 	fn clone(&self) -> Self {
 		LocalCallExecutor {
@@ -25,7 +25,7 @@ use parking_lot::{Mutex, RwLock};
 use codec::{Encode, Decode};
 use hash_db::Prefix;
 use sp_core::{
-	ChangesTrieConfiguration, convert_hash, traits::CodeExecutor,
+	ChangesTrieConfiguration, convert_hash, hexdisplay::HexDisplay, traits::CodeExecutor,
 	NativeOrEncoded, storage::{StorageKey, StorageData, well_known_keys, ChildInfo},
 };
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
@@ -70,7 +70,7 @@ pub use sc_client_api::{
 	},
 	client::{
 		ImportNotifications, FinalityNotification, FinalityNotifications, BlockImportNotification,
-		ClientInfo, BlockchainEvents, BlockBackend, ProvideUncles, BadBlocks, ForkBlocks,
+		ReorgNotification, ReorgNotifications, ClientInfo, BlockchainEvents, BlockBackend, ProvideUncles, BadBlocks, ForkBlocks,
 		BlockOf,
 	},
 	execution_extensions::{ExecutionExtensions, ExecutionStrategies},
@@ -97,6 +97,7 @@ pub struct Client<B, E, Block, RA> where Block: BlockT {
 	storage_notifications: Mutex<StorageNotifications<Block>>,
 	import_notification_sinks: NotificationSinks<BlockImportNotification<Block>>,
 	finality_notification_sinks: NotificationSinks<FinalityNotification<Block>>,
+	reorg_notification_sinks: NotificationSinks<ReorgNotification<Block>>,
 	// holds the block hash currently being imported. TODO: replace this with block queue
 	importing_block: RwLock<Option<Block::Hash>>,
 	block_rules: BlockRules<Block>,
@@ -281,6 +282,7 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 			storage_notifications: Default::default(),
 			import_notification_sinks: Default::default(),
 			finality_notification_sinks: Default::default(),
+			reorg_notification_sinks: Default::default(),
 			importing_block: Default::default(),
 			block_rules: BlockRules::new(fork_blocks, bad_blocks),
 			execution_extensions,
@@ -689,10 +691,12 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 
 				operation.op.update_cache(new_cache);
 
-				let (main_sc, child_sc, tx, _, changes_trie_tx) = storage_changes.into_inner();
+				let (main_sc, child_sc, offchain_sc, tx, _, changes_trie_tx) =
+					storage_changes.into_inner();
 
 				operation.op.update_db_storage(tx)?;
 				operation.op.update_storage(main_sc.clone(), child_sc.clone())?;
+				operation.op.update_offchain_storage(offchain_sc)?;
 
 				if let Some(changes_trie_transaction) = changes_trie_tx {
 					operation.op.update_changes_trie(changes_trie_transaction)?;
@@ -970,6 +974,25 @@ impl<B, E, Block, RA> Client<B, E, Block, RA> where
 		self.import_notification_sinks.lock()
 			.retain(|sink| sink.unbounded_send(notification.clone()).is_ok());
 
+		// The notification only lists the blocks of the tree route, the subscribers read the bodies
+		// of the blocks they are interested in, off the import path.
+		let tree_route = notification.tree_route.as_ref().filter(|route| !route.retracted().is_empty());
+		if let Some(tree_route) = tree_route {
+			let reorg = ReorgNotification {
+				hash: notification.hash,
+				common_ancestor: tree_route.common_block().hash,
+				retracted: tree_route.retracted().iter().map(|block| block.hash).collect(),
+				enacted: tree_route.enacted().iter()
+					.map(|block| block.hash)
+					.chain(std::iter::once(notification.hash))
+					.collect(),
+			};
+			self.reorg_notification_sinks.lock()
+				.retain(|sink| sink.unbounded_send(reorg.clone()).is_ok());
+		} else {
+			self.reorg_notification_sinks.lock().retain(|sink| !sink.is_closed());
+		}
+
 		Ok(())
 	}
 
@@ -1111,6 +1134,56 @@ impl<B, E, Block, RA> ProofProvider<Block> for Client<B, E, Block, RA> where
 				.map_err(Into::into))
 	}
 
+	fn read_state_chunk(
+		&self,
+		id: &BlockId<Block>,
+		storage_key: Option<&[u8]>,
+		start: Option<&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<(Vec<(Vec<u8>, Vec<u8>)>, bool)> {
+		let state = self.state_at(id)?;
+		let child = match storage_key {
+			Some(storage_key) => Some((
+				storage_key,
+				ChildInfo::default_for_storage_key(storage_key).ok_or_else(|| sp_blockchain::Error::Msg(
+					format!("Invalid child trie key {}", HexDisplay::from(storage_key)),
+				))?,
+			)),
+			None => None,
+		};
+		let value = |key: &[u8]| match child {
+			Some((storage_key, child_info)) => state.child_storage(storage_key, child_info, key),
+			None => state.storage(key),
+		}.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)));
+		let next_key = |key: &[u8]| match child {
+			Some((storage_key, child_info)) => state.next_child_storage_key(storage_key, child_info, key),
+			None => state.next_storage_key(key),
+		}.map_err(|e| sp_blockchain::Error::from_state(Box::new(e)));
+
+		let mut pairs = Vec::new();
+		let mut size = 0;
+		let mut key = match start {
+			Some(start) => next_key(start)?,
+			// the empty key is the first key, but `next_key` never returns it.
+			None => match value(&[])? {
+				Some(_) => Some(Vec::new()),
+				None => next_key(&[])?,
+			},
+		};
+		while let Some(current) = key {
+			if size >= size_limit {
+				return Ok((pairs, false))
+			}
+			let current_value = value(&current)?.ok_or_else(|| sp_blockchain::Error::Msg(
+				format!("Missing value of key {}", HexDisplay::from(&current)),
+			))?;
+			size += current.len() + current_value.len();
+			key = next_key(&current)?;
+			pairs.push((current, current_value));
+		}
+		Ok((pairs, true))
+	}
+
 	fn execution_proof(
 		&self,
 		id: &BlockId<Block>,
@@ -1596,8 +1669,17 @@ impl<B, E, Block, RA> sp_consensus::BlockImport<Block> for &Client<B, E, Block,
 		mut import_block: BlockImportParams<Block, backend::TransactionFor<B, Block>>,
 		new_cache: HashMap<CacheKeyId, Vec<u8>>,
 	) -> Result<ImportResult, Self::Error> {
-		let span = tracing::span!(tracing::Level::DEBUG, "import_block");
+		let span = tracing::span!(
+			tracing::Level::DEBUG,
+			"import_block",
+			number = ?import_block.header.number(),
+			hash = ?import_block.post_hash(),
+		);
 		let _enter = span.enter();
+		let _context = sp_tracing::log_context::enter_block(
+			import_block.header.number(),
+			import_block.post_hash(),
+		);
 
 		if let Some(res) = self.prepare_block_storage_changes(&mut import_block).map_err(|e| {
 			warn!("Block prepare storage changes error:\n{:?}", e);
@@ -1712,6 +1794,8 @@ impl<B, E, Block, RA> Finalizer<Block, B> for Client<B, E, Block, RA> where
 	) -> sp_blockchain::Result<()> {
 		let last_best = self.backend.blockchain().info().best_hash;
 		let to_finalize_hash = self.backend.blockchain().expect_block_hash_from_id(&id)?;
+		let to_finalize_number = self.backend.blockchain().expect_block_number_from_id(&id)?;
+		let _context = sp_tracing::log_context::enter_block(to_finalize_number, to_finalize_hash);
 		self.apply_finality_with_block_hash(
 			operation,
 			to_finalize_hash,
@@ -1777,6 +1861,12 @@ where
 		stream
 	}
 
+	fn reorg_notification_stream(&self) -> ReorgNotifications<Block> {
+		let (sink, stream) = tracing_unbounded("mpsc_reorg_notification_stream");
+		self.reorg_notification_sinks.lock().push(sink);
+		stream
+	}
+
 	/// Get storage changes event stream.
 	fn storage_changes_notification_stream(
 		&self,
@@ -3023,7 +3113,10 @@ pub(crate) mod tests {
 			DatabaseSettings {
 				state_cache_size: 1 << 20,
 				state_cache_child_ratio: None,
+				trie_cache_size: 1 << 20,
+				blocks_pruning: Default::default(),
 				pruning: PruningMode::ArchiveAll,
+				state_retention: Default::default(),
 				source: DatabaseSettingsSrc::Path {
 					path: tmp.path().into(),
 					cache_size: None,
@@ -3225,7 +3318,10 @@ pub(crate) mod tests {
 				DatabaseSettings {
 					state_cache_size: 1 << 20,
 					state_cache_child_ratio: None,
+					trie_cache_size: 1 << 20,
+					blocks_pruning: Default::default(),
 					pruning: PruningMode::keep_blocks(1),
+					state_retention: Default::default(),
 					source: DatabaseSettingsSrc::Path {
 						path: tmp.path().into(),
 						cache_size: None,
@@ -3474,6 +3570,59 @@ pub(crate) mod tests {
 		assert_eq!(res, [hex!("cf722c0832b5231d35e29f319ff27389f5032bfc7bfc3ba5ed7839f2042fb99f").to_vec()]);
 	}
 
+	#[test]
+	fn reorg_notifications_list_retracted_extrinsics() {
+		// block tree:
+		// G -> A1
+		// G -> B1 -> B2
+		let mut client = substrate_test_runtime_client::new();
+		let mut reorgs = client.reorg_notification_stream();
+
+		let transfer = |to: AccountKeyring, amount| Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: to.into(),
+			amount,
+			nonce: 0,
+		};
+
+		// G -> A1
+		let mut builder = client.new_block(Default::default()).unwrap();
+		builder.push_transfer(transfer(AccountKeyring::Bob, 10)).unwrap();
+		let a1 = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, a1.clone()).unwrap();
+
+		// G -> B1
+		let mut builder = client.new_block_at(
+			&BlockId::Hash(client.genesis_hash()),
+			Default::default(),
+			false,
+		).unwrap();
+		builder.push_transfer(transfer(AccountKeyring::Ferdie, 1)).unwrap();
+		let b1 = builder.build().unwrap().block;
+		client.import(BlockOrigin::Own, b1.clone()).unwrap();
+
+		// B1 -> B2, the new best block.
+		let b2 = client.new_block_at(
+			&BlockId::Hash(b1.hash()),
+			Default::default(),
+			false,
+		).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, b2.clone()).unwrap();
+		assert_eq!(client.chain_info().best_hash, b2.hash());
+
+		let reorg = futures::executor::block_on(futures::StreamExt::next(&mut reorgs)).unwrap();
+		assert_eq!(reorg.hash, b2.hash());
+		assert_eq!(reorg.common_ancestor, client.genesis_hash());
+		assert_eq!(reorg.retracted, vec![a1.hash()]);
+		assert_eq!(reorg.enacted, vec![b1.hash(), b2.hash()]);
+		assert_eq!(
+			reorg.retracted_extrinsics(&client).unwrap(),
+			a1.extrinsics().iter()
+				.map(|xt| <HashFor<Block> as sp_runtime::traits::Hash>::hash_of(xt))
+				.collect::<Vec<_>>(),
+		);
+	}
+
 	#[test]
 	fn cleans_up_closed_notification_sinks_on_block_import() {
 		use substrate_test_runtime_client::GenesisInit;
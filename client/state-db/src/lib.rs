@@ -294,6 +294,23 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDbSync<Block
 		Ok(commit)
 	}
 
+	fn import_canonical_block<E: fmt::Debug>(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+	) -> Result<CommitSet<Key>, Error<E>> {
+		let mut commit = CommitSet::default();
+		if self.mode == PruningMode::ArchiveAll {
+			return Ok(commit)
+		}
+		self.non_canonical.import_canonical(hash, number, &mut commit)?;
+		if let Some(ref mut pruning) = self.pruning {
+			pruning.note_canonical(&hash, &mut commit);
+		}
+		self.prune(&mut commit);
+		Ok(commit)
+	}
+
 	fn best_canonical(&self) -> Option<u64> {
 		return self.non_canonical.last_canonicalized_block_number()
 	}
@@ -464,6 +481,16 @@ impl<BlockHash: Hash + MallocSizeOf, Key: Hash + MallocSizeOf> StateDb<BlockHash
 		self.db.write().canonicalize_block(hash)
 	}
 
+	/// Finalize a block whose state was written to the database directly, without its
+	/// ancestors. Only possible while no blocks wait for canonicalization.
+	pub fn import_canonical_block<E: fmt::Debug>(
+		&self,
+		hash: &BlockHash,
+		number: u64,
+	) -> Result<CommitSet<Key>, Error<E>> {
+		self.db.write().import_canonical_block(hash, number)
+	}
+
 	/// Prevents pruning of specified block and its descendants.
 	pub fn pin(&self, hash: &BlockHash) -> Result<(), PinError> {
 		self.db.write().pin(hash)
@@ -635,6 +662,40 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn imported_block_is_canonical() {
+		let (mut db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			max_mem: None,
+		}));
+		db.commit(&sdb.insert_block::<io::Error>(
+			&H256::from_low_u64_be(5),
+			5,
+			&H256::from_low_u64_be(4),
+			make_changeset(&[5], &[]),
+		).unwrap());
+		sdb.apply_pending();
+		// a block waits for canonicalization.
+		assert!(sdb.import_canonical_block::<io::Error>(&H256::from_low_u64_be(100), 100).is_err());
+		db.commit(&sdb.canonicalize_block::<io::Error>(&H256::from_low_u64_be(4)).unwrap());
+		sdb.apply_pending();
+		db.commit(&sdb.canonicalize_block::<io::Error>(&H256::from_low_u64_be(5)).unwrap());
+		sdb.apply_pending();
+
+		db.commit(&sdb.import_canonical_block::<io::Error>(&H256::from_low_u64_be(100), 100).unwrap());
+		sdb.apply_pending();
+		assert_eq!(sdb.best_canonical(), Some(100));
+		assert!(!sdb.is_pruned(&H256::from_low_u64_be(100), 100));
+		db.commit(&sdb.insert_block::<io::Error>(
+			&H256::from_low_u64_be(101),
+			101,
+			&H256::from_low_u64_be(100),
+			make_changeset(&[101], &[]),
+		).unwrap());
+		sdb.apply_pending();
+		assert!(!sdb.is_pruned(&H256::from_low_u64_be(101), 101));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
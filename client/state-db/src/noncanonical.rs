@@ -37,6 +37,7 @@ pub struct NonCanonicalOverlay<BlockHash: Hash, Key: Hash> {
 	parents: HashMap<BlockHash, BlockHash>,
 	pending_canonicalizations: Vec<BlockHash>,
 	pending_insertions: Vec<BlockHash>,
+	pending_import: Option<(BlockHash, u64)>,
 	values: HashMap<Key, (u32, DBValue)>, //ref counted
 	//would be deleted but kept around because block is pinned, ref counted.
 	pinned: HashMap<BlockHash, u32>,
@@ -181,6 +182,7 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 			parents,
 			pending_canonicalizations: Default::default(),
 			pending_insertions: Default::default(),
+			pending_import: None,
 			pinned: Default::default(),
 			pinned_insertions: Default::default(),
 			values: values,
@@ -290,6 +292,24 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 			.unwrap_or_default()
 	}
 
+	/// Make a block whose state was written to the database directly the last canonicalized
+	/// block. The block does not need a known parent, but no other block may be in the overlay.
+	pub fn import_canonical<E: fmt::Debug>(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		commit: &mut CommitSet<Key>,
+	) -> Result<(), Error<E>> {
+		if !self.levels.is_empty() || !self.pending_canonicalizations.is_empty() || self.pending_import.is_some() {
+			return Err(Error::InvalidBlock);
+		}
+		trace!(target: "state-db", "Importing canonical {:?} ({})", hash, number);
+		let canonicalized = (hash.clone(), number);
+		commit.meta.inserted.push((to_meta_key(LAST_CANONICAL, &()), canonicalized.encode()));
+		self.pending_import = Some(canonicalized);
+		Ok(())
+	}
+
 	/// Select a top-level root and canonicalized it. Discards all sibling subtrees and the root.
 	/// Returns a set of changes that need to be added to the DB.
 	pub fn canonicalize<E: fmt::Debug>(
@@ -419,12 +439,16 @@ impl<BlockHash: Hash, Key: Hash> NonCanonicalOverlay<BlockHash, Key> {
 
 	/// Apply all pending changes
 	pub fn apply_pending(&mut self) {
+		if let Some(imported) = self.pending_import.take() {
+			self.last_canonicalized = Some(imported);
+		}
 		self.apply_canonicalizations();
 		self.pending_insertions.clear();
 	}
 
 	/// Revert all pending changes
 	pub fn revert_pending(&mut self) {
+		self.pending_import = None;
 		self.pending_canonicalizations.clear();
 		self.revert_insertions();
 	}
@@ -492,6 +516,31 @@ mod tests {
 		assert!(overlay.parents.is_empty());
 	}
 
+	#[test]
+	fn import_canonical_without_parent() {
+		let h1 = H256::random();
+		let h2 = H256::random();
+		let mut db = make_db(&[1]);
+		let mut overlay = NonCanonicalOverlay::<H256, H256>::new(&db).unwrap();
+		let mut commit = CommitSet::default();
+		overlay.import_canonical::<io::Error>(&h1, 100, &mut commit).unwrap();
+		db.commit(&commit);
+		assert_eq!(overlay.last_canonicalized, None);
+		overlay.apply_pending();
+		assert_eq!(overlay.last_canonicalized, Some((h1, 100)));
+
+		// the next block goes on top of the imported one.
+		assert!(overlay.insert::<io::Error>(&h2, 102, &h1, make_changeset(&[2], &[])).is_err());
+		db.commit(&overlay.insert::<io::Error>(&h2, 101, &h1, make_changeset(&[2], &[])).unwrap());
+		let overlay2 = NonCanonicalOverlay::<H256, H256>::new(&db).unwrap();
+		assert_eq!(overlay2.last_canonicalized, Some((h1, 100)));
+		assert_eq!(overlay.levels, overlay2.levels);
+
+		// but nothing may be imported while blocks wait for canonicalization.
+		let mut commit = CommitSet::default();
+		assert!(overlay.import_canonical::<io::Error>(&h2, 101, &mut commit).is_err());
+	}
+
 	#[test]
 	#[should_panic]
 	fn canonicalize_empty_panics() {
@@ -0,0 +1,201 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Authentication to telemetry endpoints and signing of telemetry payloads.
+//!
+//! The WebSocket transport doesn't let us set headers on the handshake, so the bearer token of
+//! an endpoint is sent in the first message after connecting:
+//! `{"msg":"auth","authorization":"Bearer <token>"}`. Tokens are only sent to `wss://` endpoints.
+//!
+//! Payloads sent to endpoints requiring signatures are wrapped as
+//! `{"payload":<payload>,"signer":"0x<public key>","signature":"0x<signature>"}`, where the
+//! signature is over the bytes of the payload.
+
+use std::{collections::HashMap, fmt};
+use libp2p::{Multiaddr, identity::Keypair, multiaddr::Protocol};
+use crate::url_to_multiaddr;
+
+/// Authentication options of a telemetry endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EndpointAuth {
+	/// The bearer token sent to the endpoint after connecting.
+	pub bearer_token: Option<String>,
+	/// Whether the payloads sent to the endpoint are signed.
+	pub sign: bool,
+}
+
+/// Error in the authentication options of the telemetry endpoints.
+#[derive(Debug)]
+pub enum AuthError {
+	/// The URL of an endpoint is invalid.
+	InvalidUrl(libp2p::multiaddr::Error),
+	/// The endpoint has a bearer token but isn't a secure WebSocket endpoint, the token would be
+	/// sent in cleartext.
+	Insecure(String),
+}
+
+impl fmt::Display for AuthError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			AuthError::InvalidUrl(e) => write!(f, "Invalid telemetry endpoint: {}", e),
+			AuthError::Insecure(url) =>
+				write!(f, "Telemetry endpoint {} must use wss:// to be sent a bearer token", url),
+		}
+	}
+}
+
+impl std::error::Error for AuthError {}
+
+/// Authentication options of the telemetry endpoints, by endpoint.
+///
+/// The URL strings can be either URLs or multiaddresses, as in `TelemetryEndpoints`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TelemetryAuth(HashMap<Multiaddr, EndpointAuth>);
+
+impl TelemetryAuth {
+	/// Fails if an endpoint with a bearer token isn't a secure WebSocket endpoint.
+	pub fn new(endpoints: Vec<(String, EndpointAuth)>) -> Result<Self, AuthError> {
+		endpoints.into_iter()
+			.map(|(url, auth)| {
+				let addr = url_to_multiaddr(&url).map_err(AuthError::InvalidUrl)?;
+				let secure = addr.iter().any(|protocol| match protocol {
+					Protocol::Wss(_) => true,
+					_ => false,
+				});
+				if auth.bearer_token.is_some() && !secure {
+					return Err(AuthError::Insecure(url))
+				}
+				Ok((addr, auth))
+			})
+			.collect::<Result<_, _>>()
+			.map(Self)
+	}
+
+	/// The authentication options of the given endpoint.
+	pub fn get(&self, addr: &Multiaddr) -> Option<&EndpointAuth> {
+		self.0.get(addr)
+	}
+}
+
+/// Signs telemetry payloads, e.g. with the node key or a session key.
+pub trait TelemetrySigner: Send + Sync {
+	/// Sign the payload, returning the public key and the signature, or `None` if no key is
+	/// available.
+	fn sign(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<u8>)>;
+}
+
+/// Signs with the node key. The public key is protobuf encoded, as in the `PeerId` of the node.
+impl TelemetrySigner for Keypair {
+	fn sign(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+		let signature = Keypair::sign(self, payload).ok()?;
+		Some((self.public().into_protobuf_encoding(), signature))
+	}
+}
+
+/// The message authenticating the node with the given bearer token.
+pub(crate) fn auth_message(bearer_token: &str) -> Vec<u8> {
+	serde_json::json!({
+		"msg": "auth",
+		"authorization": format!("Bearer {}", bearer_token),
+	}).to_string().into_bytes()
+}
+
+/// Wrap the JSON payload with its signature, or `None` if the signer has no key.
+pub(crate) fn signed_payload(signer: &dyn TelemetrySigner, payload: &[u8]) -> Option<Vec<u8>> {
+	let payload = trim_end(payload);
+	let (public, signature) = signer.sign(payload)?;
+
+	let mut signed = b"{\"payload\":".to_vec();
+	signed.extend_from_slice(payload);
+	signed.extend_from_slice(format!(
+		",\"signer\":\"0x{}\",\"signature\":\"0x{}\"}}",
+		to_hex(&public),
+		to_hex(&signature),
+	).as_bytes());
+	Some(signed)
+}
+
+fn trim_end(payload: &[u8]) -> &[u8] {
+	let len = payload.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |i| i + 1);
+	&payload[..len]
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+	bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	struct TestSigner;
+
+	impl TelemetrySigner for TestSigner {
+		fn sign(&self, payload: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+			Some((vec![1, 2], payload.iter().rev().cloned().collect()))
+		}
+	}
+
+	#[test]
+	fn payloads_are_wrapped_with_their_signature() {
+		let signed = signed_payload(&TestSigner, b"{\"a\":1}\n").unwrap();
+		assert_eq!(
+			String::from_utf8(signed).unwrap(),
+			r#"{"payload":{"a":1},"signer":"0x0102","signature":"0x7d313a2261227b"}"#,
+		);
+	}
+
+	#[test]
+	fn node_key_signatures_verify() {
+		let keypair = Keypair::generate_ed25519();
+		let signed = signed_payload(&keypair, b"{}").unwrap();
+		let signed: serde_json::Value = serde_json::from_slice(&signed).unwrap();
+		let signature = signed["signature"].as_str().unwrap();
+
+		let (_, expected) = TelemetrySigner::sign(&keypair, b"{}").unwrap();
+		assert_eq!(signature, format!("0x{}", to_hex(&expected)));
+		assert!(keypair.public().verify(b"{}", &expected));
+	}
+
+	#[test]
+	fn auth_is_keyed_by_multiaddr() {
+		let auth = TelemetryAuth::new(vec![(
+			"wss://telemetry.example.com/submit/".into(),
+			EndpointAuth { bearer_token: Some("secret".into()), sign: true },
+		)]).unwrap();
+
+		let addr = url_to_multiaddr("wss://telemetry.example.com/submit/").unwrap();
+		assert_eq!(auth.get(&addr).and_then(|a| a.bearer_token.as_deref()), Some("secret"));
+		let message: serde_json::Value = serde_json::from_slice(&auth_message("secret")).unwrap();
+		assert_eq!(message["msg"], "auth");
+		assert_eq!(message["authorization"], "Bearer secret");
+	}
+
+	#[test]
+	fn tokens_are_only_sent_over_wss() {
+		let auth = |url: &str, bearer_token: Option<&str>| TelemetryAuth::new(vec![(
+			url.into(),
+			EndpointAuth { bearer_token: bearer_token.map(Into::into), sign: false },
+		)]);
+
+		match auth("ws://telemetry.example.com/submit/", Some("secret")) {
+			Err(AuthError::Insecure(url)) => assert_eq!(url, "ws://telemetry.example.com/submit/"),
+			other => panic!("Unexpected {:?}", other),
+		}
+		assert!(auth("ws://telemetry.example.com/submit/", None).is_ok());
+		assert!(auth("wss://telemetry.example.com/submit/", Some("secret")).is_ok());
+	}
+}
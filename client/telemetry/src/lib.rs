@@ -44,6 +44,9 @@
 //! 	]).expect("Invalid URL or multiaddr provided"),
 //! 	// Can be used to pass an external implementation of WebSockets.
 //! 	wasm_external_transport: None,
+//! 	// Bearer tokens and signing of the payloads, per endpoint.
+//! 	auth: Default::default(),
+//! 	signer: None,
 //! });
 //!
 //! // The `telemetry` object implements `Stream` and must be processed.
@@ -69,8 +72,10 @@ use wasm_timer::Instant;
 pub use libp2p::wasm_ext::ExtTransport;
 pub use slog_scope::with_logger;
 pub use slog;
+pub use auth::{AuthError, EndpointAuth, TelemetryAuth, TelemetrySigner};
 
 mod async_record;
+mod auth;
 mod worker;
 
 /// Configuration for telemetry.
@@ -89,6 +94,13 @@ pub struct TelemetryConfig {
 	/// >                internal buffering going on. In the context of WebSockets, each `write`
 	/// >                must be one individual WebSockets frame.
 	pub wasm_external_transport: Option<wasm_ext::ExtTransport>,
+
+	/// Bearer tokens of the endpoints, and the endpoints the payloads sent to are signed.
+	pub auth: TelemetryAuth,
+
+	/// Signs the payloads sent to the endpoints requiring signatures. Payloads are sent unsigned
+	/// if `None`.
+	pub signer: Option<Arc<dyn TelemetrySigner>>,
 }
 
 /// List of telemetry servers we want to talk to. Contains the URL of the server, and the
@@ -185,6 +197,7 @@ struct TelemetryDrain {
 pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 	// Build the list of telemetry endpoints.
 	let (endpoints, wasm_external_transport) = (config.endpoints.0, config.wasm_external_transport);
+	let (auth, signer) = (config.auth, config.signer);
 
 	let (sender, receiver) = mpsc::channel(16);
 	let guard = {
@@ -193,7 +206,7 @@ pub fn init_telemetry(config: TelemetryConfig) -> Telemetry {
 		slog_scope::set_global_logger(root)
 	};
 
-	let worker = match worker::TelemetryWorker::new(endpoints, wasm_external_transport) {
+	let worker = match worker::TelemetryWorker::new(endpoints, wasm_external_transport, &auth, signer) {
 		Ok(w) => Some(w),
 		Err(err) => {
 			error!(target: "telemetry", "Failed to initialize telemetry worker: {:?}", err);
@@ -31,7 +31,9 @@ use futures::{prelude::*, ready};
 use libp2p::{core::transport::OptionalTransport, Multiaddr, Transport, wasm_ext};
 use log::{trace, warn, error};
 use slog::Drain;
-use std::{io, pin::Pin, task::Context, task::Poll, time};
+use std::{fmt, io, pin::Pin, sync::Arc, task::Context, task::Poll, time};
+
+use crate::auth::{self, TelemetryAuth, TelemetrySigner};
 
 mod node;
 
@@ -48,10 +50,23 @@ pub enum TelemetryWorkerEvent {
 }
 
 /// Telemetry processing machine.
-#[derive(Debug)]
 pub struct TelemetryWorker {
-	/// List of nodes with their maximum verbosity level.
-	nodes: Vec<(node::Node<WsTrans>, u8)>,
+	/// List of nodes with their maximum verbosity level and whether their payloads are signed.
+	nodes: Vec<(node::Node<WsTrans>, u8, bool)>,
+	/// Signs the payloads sent to the nodes requiring signatures.
+	signer: Option<Arc<dyn TelemetrySigner>>,
+	/// Whether we warned that the payloads couldn't be signed, since the last signed payload.
+	unsigned_warned: bool,
+}
+
+impl fmt::Debug for TelemetryWorker {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("TelemetryWorker")
+			.field("nodes", &self.nodes)
+			.field("signer", &self.signer.is_some())
+			.field("unsigned_warned", &self.unsigned_warned)
+			.finish()
+	}
 }
 
 trait StreamAndSink<I>: Stream + Sink<I> {}
@@ -72,9 +87,14 @@ impl TelemetryWorker {
 	/// The endpoints must be a list of targets, plus a verbosity level. When you send a message
 	/// to the telemetry, only the targets whose verbosity is higher than the verbosity of the
 	/// message will receive it.
+	///
+	/// The nodes authenticate with their bearer token in `auth`, if any, and the messages sent to
+	/// the nodes requiring signatures are signed with `signer`.
 	pub fn new(
 		endpoints: impl IntoIterator<Item = (Multiaddr, u8)>,
-		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>
+		wasm_external_transport: impl Into<Option<wasm_ext::ExtTransport>>,
+		auth: &TelemetryAuth,
+		signer: Option<Arc<dyn TelemetrySigner>>,
 	) -> Result<Self, io::Error> {
 		let transport = match wasm_external_transport.into() {
 			Some(t) => OptionalTransport::some(t),
@@ -113,15 +133,20 @@ impl TelemetryWorker {
 
 		Ok(TelemetryWorker {
 			nodes: endpoints.into_iter().map(|(addr, verbosity)| {
-				let node = node::Node::new(transport.clone(), addr);
-				(node, verbosity)
-			}).collect()
+				let endpoint_auth = auth.get(&addr).cloned().unwrap_or_default();
+				let auth_message = endpoint_auth.bearer_token.as_ref()
+					.map(|token| auth::auth_message(token));
+				let node = node::Node::new(transport.clone(), addr, auth_message);
+				(node, verbosity, endpoint_auth.sign)
+			}).collect(),
+			signer,
+			unsigned_warned: false,
 		})
 	}
 
 	/// Polls the worker for events that happened.
 	pub fn poll(&mut self, cx: &mut Context) -> Poll<TelemetryWorkerEvent> {
-		for (node, _) in &mut self.nodes {
+		for (node, _, _) in &mut self.nodes {
 			loop {
 				match node::Node::poll(Pin::new(node), cx) {
 					Poll::Ready(node::NodeEvent::Connected) =>
@@ -150,7 +175,7 @@ impl TelemetryWorker {
 		};
 
 		// None of the nodes want that verbosity, so just return without doing any serialization.
-		if self.nodes.iter().all(|(_, node_max_verbosity)| msg_verbosity > *node_max_verbosity) {
+		if self.nodes.iter().all(|(_, node_max_verbosity, _)| msg_verbosity > *node_max_verbosity) {
 			trace!(
 				target: "telemetry",
 				"Skipping log entry because verbosity {:?} is too high for all endpoints",
@@ -166,15 +191,43 @@ impl TelemetryWorker {
 			out
 		};
 
-		for (node, node_max_verbosity) in &mut self.nodes {
+		// Signed once, for all the nodes requiring signatures.
+		let mut signed = None;
+
+		for (node, node_max_verbosity, sign) in &mut self.nodes {
 			if msg_verbosity > *node_max_verbosity {
 				trace!(target: "telemetry", "Skipping {:?} for log entry with verbosity {:?}",
 					node.addr(), msg_verbosity);
 				continue;
 			}
 
+			let payload = if *sign {
+				let (signer, unsigned_warned) = (&self.signer, &mut self.unsigned_warned);
+				let signed = signed.get_or_insert_with(|| {
+					let signed = signer.as_ref()
+						.and_then(|signer| auth::signed_payload(&**signer, &serialized));
+					// Warn once until payloads can be signed again, not for every log entry.
+					if signed.is_none() && !*unsigned_warned {
+						warn!(target: "telemetry", "Telemetry payloads can't be signed, skipping the \
+							endpoints requiring signatures");
+					}
+					*unsigned_warned = signed.is_none();
+					signed
+				});
+				match signed {
+					Some(signed) => &signed[..],
+					None => {
+						trace!(target: "telemetry", "Skipping {:?} because the log entry couldn't be signed",
+							node.addr());
+						continue;
+					}
+				}
+			} else {
+				&serialized[..]
+			};
+
 			// `send_message` returns an error if we're not connected, which we silently ignore.
-			let _ = node.send_message(&serialized.clone()[..]);
+			let _ = node.send_message(payload);
 		}
 
 		Ok(())
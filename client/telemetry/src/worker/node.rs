@@ -36,6 +36,8 @@ pub struct Node<TTrans: Transport> {
 	socket: NodeSocket<TTrans>,
 	/// Transport used to establish new connections.
 	transport: TTrans,
+	/// Message sent first after connecting, authenticating us to the node.
+	auth_message: Option<BytesMut>,
 }
 
 enum NodeSocket<TTrans: Transport> {
@@ -84,12 +86,14 @@ pub enum ConnectionError<TSinkErr> {
 }
 
 impl<TTrans: Transport> Node<TTrans> {
-	/// Builds a new node handler.
-	pub fn new(transport: TTrans, addr: Multiaddr) -> Self {
+	/// Builds a new node handler. The authentication message, if any, is sent first after
+	/// each connection.
+	pub fn new(transport: TTrans, addr: Multiaddr, auth_message: Option<Vec<u8>>) -> Self {
 		Node {
 			addr,
 			socket: NodeSocket::ReconnectNow,
 			transport,
+			auth_message: auth_message.map(|message| message[..].into()),
 		}
 	}
 
@@ -149,7 +153,7 @@ where TTrans: Clone + Unpin, TTrans::Dial: Unpin,
 						debug!(target: "telemetry", "✅ Connected to {}", self.addr);
 						let conn = NodeSocketConnected {
 							sink,
-							pending: VecDeque::new(),
+							pending: self.auth_message.iter().cloned().collect(),
 							need_flush: false,
 							timeout: None,
 						};
@@ -34,9 +34,21 @@
 //! let span = tracing::span!(tracing::Level::INFO, "my_span_name", my_number = 10, a_key = "a value");
 //! let _guard = span.enter();
 //! ```
+//! Values that are recorded after the span was created, e.g. declared as `tracing::field::Empty`,
+//! are reported as well.
+//!
 //! Currently we provide `Log` (default), `Telemetry` variants for `Receiver`
+//!
+//! Host function calls are traced with the target `host_function` and can be profiled by
+//! enabling this target.
+//!
+//! Spans entered by the wasm runtime, e.g. with `frame_support::enter_span!`, are reported with
+//! their actual target and name, e.g. the pallet and the dispatched call, and the index of the
+//! extrinsic being applied. Spans are reported with the id of their parent span, so the spans of
+//! the runtime can be attributed to the block being imported and folded into a flamegraph.
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, Instant};
@@ -54,6 +66,12 @@ use tracing_core::{
 };
 
 use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+use sp_tracing::proxy::{WASM_TRACE_IDENTIFIER, WASM_TARGET_KEY, WASM_NAME_KEY};
+
+thread_local! {
+	/// The ids of the recorded spans entered on this thread, innermost last.
+	static CURRENT_SPANS: RefCell<Vec<u64>> = RefCell::new(Vec::new());
+}
 
 /// Used to configure how to receive the metrics
 #[derive(Debug, Clone)]
@@ -73,8 +91,9 @@ impl Default for TracingReceiver {
 #[derive(Debug)]
 struct SpanDatum {
 	id: u64,
-	name: &'static str,
-	target: &'static str,
+	parent_id: Option<u64>,
+	name: String,
+	target: String,
 	level: Level,
 	line: u32,
 	start_time: Instant,
@@ -98,11 +117,23 @@ impl Visit for Visitor {
 		self.record_debug(field, &value)
 	}
 
+	fn record_str(&mut self, field: &Field, value: &str) {
+		self.0.push((field.name().to_string(), value.to_string()));
+	}
+
 	fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
 		self.0.push((field.name().to_string(), format!("{:?}",value)));
 	}
 }
 
+impl Visitor {
+	/// Remove the value of the given field.
+	fn take(&mut self, key: &str) -> Option<String> {
+		let pos = self.0.iter().position(|(k, _)| k == key)?;
+		Some(self.0.remove(pos).1)
+	}
+}
+
 impl Serialize for Visitor {
 	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
 		where S: Serializer,
@@ -149,6 +180,8 @@ pub struct ProfilingSubscriber {
 	targets: Vec<(String, Level)>,
 	receiver: TracingReceiver,
 	span_data: Mutex<HashMap<u64, SpanDatum>>,
+	/// Spans of the runtime whose target isn't enabled.
+	filtered_spans: Mutex<HashSet<u64>>,
 }
 
 impl ProfilingSubscriber {
@@ -162,8 +195,21 @@ impl ProfilingSubscriber {
 			targets,
 			receiver,
 			span_data: Mutex::new(HashMap::new()),
+			filtered_spans: Mutex::new(HashSet::new()),
 		}
 	}
+
+	fn check_target(&self, target: &str, level: &Level) -> bool {
+		for t in &self.targets {
+			if target.starts_with(t.0.as_str()) && level <= &t.1 {
+				log::debug!("Enabled target: {}, level: {}", target, level);
+				return true;
+			} else {
+				log::debug!("Disabled target: {}, level: {}", target, level);
+			}
+		}
+		false
+	}
 }
 
 // Default to TRACE if no level given or unable to parse Level
@@ -185,25 +231,39 @@ fn parse_target(s: &str) -> (String, Level) {
 
 impl Subscriber for ProfilingSubscriber {
 	fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-		for t in &self.targets {
-			if metadata.target().starts_with(t.0.as_str()) && metadata.level() <= &t.1 {
-				log::debug!("Enabled target: {}, level: {}", metadata.target(), metadata.level());
-				return true;
-			} else {
-				log::debug!("Disabled target: {}, level: {}", metadata.target(), metadata.level());
-			}
-		}
-		false
+		// the spans of the runtime are filtered by their actual target once created.
+		metadata.target() == WASM_TRACE_IDENTIFIER || self.check_target(metadata.target(), metadata.level())
 	}
 
 	fn new_span(&self, attrs: &Attributes<'_>) -> Id {
 		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
 		let mut values = Visitor(Vec::new());
 		attrs.record(&mut values);
+		let mut name = attrs.metadata().name().to_string();
+		let mut target = attrs.metadata().target().to_string();
+		if target == WASM_TRACE_IDENTIFIER {
+			if let Some(wasm_name) = values.take(WASM_NAME_KEY) {
+				name = wasm_name;
+			}
+			if let Some(wasm_target) = values.take(WASM_TARGET_KEY) {
+				target = wasm_target;
+			}
+			if !self.check_target(&target, attrs.metadata().level()) {
+				self.filtered_spans.lock().insert(id);
+				return Id::from_u64(id);
+			}
+		}
+		let parent_id = attrs.parent().map(|parent| parent.into_u64())
+			.or_else(|| if attrs.is_contextual() {
+				CURRENT_SPANS.with(|spans| spans.borrow().last().cloned())
+			} else {
+				None
+			});
 		let span_datum = SpanDatum {
 			id,
-			name: attrs.metadata().name(),
-			target: attrs.metadata().target(),
+			parent_id,
+			name,
+			target,
 			level: attrs.metadata().level().clone(),
 			line: attrs.metadata().line().unwrap_or(0),
 			start_time: Instant::now(),
@@ -214,7 +274,12 @@ impl Subscriber for ProfilingSubscriber {
 		Id::from_u64(id)
 	}
 
-	fn record(&self, _span: &Id, _values: &Record<'_>) {}
+	fn record(&self, span: &Id, values: &Record<'_>) {
+		let mut span_data = self.span_data.lock();
+		if let Some(s) = span_data.get_mut(&span.into_u64()) {
+			values.record(&mut s.values);
+		}
+	}
 
 	fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
 
@@ -225,7 +290,8 @@ impl Subscriber for ProfilingSubscriber {
 		let start_time = Instant::now();
 		if let Some(mut s) = span_data.get_mut(&span.into_u64()) {
 			s.start_time = start_time;
-		} else {
+			CURRENT_SPANS.with(|spans| spans.borrow_mut().push(span.into_u64()));
+		} else if !self.filtered_spans.lock().contains(&span.into_u64()) {
 			log::warn!("Tried to enter span {:?} that has already been closed!", span);
 		}
 	}
@@ -235,10 +301,19 @@ impl Subscriber for ProfilingSubscriber {
 		let end_time = Instant::now();
 		if let Some(mut s) = span_data.get_mut(&span.into_u64()) {
 			s.overall_time = end_time - s.start_time + s.overall_time;
+			CURRENT_SPANS.with(|spans| {
+				let mut spans = spans.borrow_mut();
+				if let Some(pos) = spans.iter().rposition(|id| *id == span.into_u64()) {
+					spans.remove(pos);
+				}
+			});
 		}
 	}
 
 	fn try_close(&self, span: Id) -> bool {
+		if self.filtered_spans.lock().remove(&span.into_u64()) {
+			return true;
+		}
 		let mut span_data = self.span_data.lock();
 		if let Some(data) = span_data.remove(&span.into_u64()) {
 			self.send_span(data);
@@ -258,20 +333,24 @@ impl ProfilingSubscriber {
 
 fn print_log(span_datum: SpanDatum) {
 	if span_datum.values.0.is_empty() {
-		log::info!("TRACING: {} {}: {}, line: {}, time: {}",
+		log::info!("TRACING: {} {}: {}, line: {}, time: {}, id: {}, parent_id: {:?}",
 			span_datum.level,
 			span_datum.target,
 			span_datum.name,
 			span_datum.line,
 			span_datum.overall_time.as_nanos(),
+			span_datum.id,
+			span_datum.parent_id,
 		);
 	} else {
-		log::info!("TRACING: {} {}: {}, line: {}, time: {}, {}",
+		log::info!("TRACING: {} {}: {}, line: {}, time: {}, id: {}, parent_id: {:?}, {}",
 			span_datum.level,
 			span_datum.target,
 			span_datum.name,
 			span_datum.line,
 			span_datum.overall_time.as_nanos(),
+			span_datum.id,
+			span_datum.parent_id,
 			span_datum.values
 		);
 	}
@@ -283,7 +362,43 @@ fn send_telemetry(span_datum: SpanDatum) {
 		"target" => span_datum.target,
 		"line" => span_datum.line,
 		"time" => span_datum.overall_time.as_nanos(),
+		"id" => span_datum.id,
+		"parent_id" => span_datum.parent_id,
 		"values" => span_datum.values
 	);
 }
 
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn spans_of_the_runtime_are_recorded_with_their_target_and_extrinsic_index() {
+		let dispatch = tracing::Dispatch::new(
+			ProfilingSubscriber::new(TracingReceiver::Log, "import_block,pallet_balances"),
+		);
+		tracing::dispatcher::with_default(&dispatch, || {
+			let block = tracing::span!(target: "import_block", tracing::Level::DEBUG, "import_block");
+			let _enter = block.enter();
+			let transfer = sp_tracing::proxy::enter_span("pallet_balances", "transfer", Some(2));
+			let set = sp_tracing::proxy::enter_span("pallet_timestamp", "set", None);
+			assert!(transfer != 0 && set != 0);
+
+			let subscriber = dispatch.downcast_ref::<ProfilingSubscriber>().unwrap();
+			{
+				let span_data = subscriber.span_data.lock();
+				let block = span_data.values().find(|s| s.name == "import_block").unwrap();
+				let transfer = span_data.values().find(|s| s.name == "transfer").unwrap();
+				assert_eq!(transfer.target, "pallet_balances");
+				assert_eq!(transfer.parent_id, Some(block.id));
+				assert!(transfer.values.0.contains(&("extrinsic_index".to_string(), "2".to_string())));
+				assert!(span_data.values().all(|s| s.name != "set"));
+			}
+			assert_eq!(subscriber.filtered_spans.lock().len(), 1);
+
+			sp_tracing::proxy::exit_span(transfer);
+			CURRENT_SPANS.with(|spans| assert_eq!(spans.borrow().len(), 1));
+		});
+	}
+}
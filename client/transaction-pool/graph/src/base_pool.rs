@@ -248,6 +248,12 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		}
 	}
 
+	/// Sets the percentage by which the priority of a transaction must exceed the priority of the
+	/// ready transactions with the same `provides` tags to replace them.
+	pub fn set_replacement_priority_bump(&mut self, percent: u32) {
+		self.ready.set_replacement_priority_bump(percent);
+	}
+
 	/// Temporary enables future transactions, runs closure and then restores
 	/// `reject_future_transactions` flag back to previous value.
 	///
@@ -403,47 +409,71 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
 	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
-	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
+	///
+	/// If a `lane` is given, transactions in the priority lane are only counted against the lane
+	/// limits and regular transactions only against `ready` and `future`.
+	pub fn enforce_limits(
+		&mut self,
+		ready: &Limit,
+		future: &Limit,
+		lane: Option<&PriorityLane>,
+	) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
+		let in_lane = |tx: &Transaction<Hash, Ex>| lane.map_or(false, |lane| lane.contains(tx));
 
-		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
-			// find the worst transaction
-			let minimal = self.ready
-				.fold(|minimal, current| {
-					let transaction = &current.transaction;
-					match minimal {
-						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
-							Some(transaction.clone())
-						},
-						other => other,
-					}
-				});
-
-			if let Some(minimal) = minimal {
-				removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
-			} else {
-				break;
-			}
+		let mut limits = vec![(ready, future, false)];
+		if let Some(lane) = lane {
+			limits.push((&lane.ready, &lane.future, true));
 		}
 
-		while future.is_exceeded(self.future.len(), self.future.bytes()) {
-			// find the worst transaction
-			let minimal = self.future
-				.fold(|minimal, current| {
-					match minimal {
-						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
-							Some(current.clone())
-						},
-						other => other,
-					}
-				});
+		for (ready, future, prioritized) in limits {
+			loop {
+				// find the worst transaction of the lane
+				let (count, bytes, minimal) = self.ready
+					.fold(|acc, current| {
+						let transaction = &current.transaction;
+						if in_lane(&transaction.transaction) != prioritized {
+							return acc;
+						}
+						let (count, bytes, minimal) = acc.unwrap_or((0, 0, None));
+						let minimal = match minimal {
+							Some(ref tx) if tx.insertion_id < transaction.insertion_id => minimal,
+							_ => Some(transaction.clone()),
+						};
+						Some((count + 1, bytes + transaction.transaction.bytes, minimal))
+					})
+					.unwrap_or((0, 0, None));
+
+				match minimal {
+					Some(minimal) if ready.is_exceeded(count, bytes) => {
+						removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
+					},
+					_ => break,
+				}
+			}
 
-			if let Some(minimal) = minimal {
-				removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
-			} else {
-				break;
+			loop {
+				// find the worst transaction of the lane
+				let (count, bytes, minimal) = self.future
+					.fold(|acc, current| {
+						if in_lane(&current.transaction) != prioritized {
+							return acc;
+						}
+						let (count, bytes, minimal) = acc.unwrap_or((0, 0, None));
+						let minimal = match minimal {
+							Some(ref tx) if tx.imported_at < current.imported_at => minimal,
+							_ => Some(current.clone()),
+						};
+						Some((count + 1, bytes + current.transaction.bytes, minimal))
+					})
+					.unwrap_or((0, 0, None));
+
+				match minimal {
+					Some(minimal) if future.is_exceeded(count, bytes) => {
+						removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
+					},
+					_ => break,
+				}
 			}
 		}
 
@@ -538,6 +568,27 @@ impl Limit {
 	}
 }
 
+/// Reserved lane for high priority transactions.
+///
+/// Transactions with a priority of at least `min_priority` (e.g. operational extrinsics) have their
+/// own queue limits, so that a flood of regular transactions can't evict them from the pool.
+#[derive(Debug, Clone)]
+pub struct PriorityLane {
+	/// Minimal priority of transactions in the lane.
+	pub min_priority: Priority,
+	/// Ready queue limits of the lane.
+	pub ready: Limit,
+	/// Future queue limits of the lane.
+	pub future: Limit,
+}
+
+impl PriorityLane {
+	/// Returns true if the transaction belongs to the lane.
+	pub fn contains<Hash, Ex>(&self, tx: &Transaction<Hash, Ex>) -> bool {
+		tx.priority >= self.min_priority
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1196,4 +1247,40 @@ source: TransactionSource::External, requires: [03,02], provides: [04], data: [4
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_not_evict_priority_lane_transactions_when_enforcing_limits() {
+		// given
+		let mut pool = pool();
+		let limit = |count| Limit { count, total_bytes: 1024 };
+		let lane = PriorityLane {
+			min_priority: Priority::max_value(),
+			ready: limit(1),
+			future: limit(1),
+		};
+		for (hash, priority) in vec![(1, Priority::max_value()), (2, 5), (3, 5), (4, 5)] {
+			pool.import(Transaction {
+				data: vec![hash as u8],
+				bytes: 1,
+				hash,
+				priority,
+				valid_till: 64u64,
+				requires: vec![],
+				provides: vec![vec![hash as u8]],
+				propagate: true,
+				source: Source::External,
+			}).unwrap();
+		}
+
+		// when
+		let removed = pool.enforce_limits(&limit(2), &limit(2), Some(&lane));
+
+		// then
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<HashSet<_>>(), vec![1, 3, 4].into_iter().collect());
+
+		// and then when the lane is disabled the oldest transaction is evicted
+		let removed = pool.enforce_limits(&limit(2), &limit(2), None);
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
 }
@@ -0,0 +1,242 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Track invalid delegated transactions.
+//!
+//! A delegated transaction carries a doughnut, whose validity may only be checked when the
+//! transaction is dispatched. The same bad doughnut can thus be wrapped in new transactions and
+//! re-gossiped endlessly. Invalid delegated transactions are counted per doughnut issuer and per
+//! submitting peer, and sources reaching the threshold are temporarily banned.
+
+use std::{
+	borrow::Borrow,
+	collections::HashMap,
+	hash,
+	time::Duration,
+};
+use parking_lot::RwLock;
+use wasm_timer::Instant;
+
+/// Expected number of tracked submitters.
+const EXPECTED_SIZE: usize = 2048;
+
+/// Invalid transaction counts and bans of one kind of source.
+#[derive(Debug)]
+struct Sources<Key> {
+	/// Number of invalid transactions and the end of the counting window.
+	invalid: HashMap<Key, (u32, Instant)>,
+	/// Currently banned sources.
+	banned_until: HashMap<Key, Instant>,
+}
+
+impl<Key> Default for Sources<Key> {
+	fn default() -> Self {
+		Sources {
+			invalid: HashMap::new(),
+			banned_until: HashMap::new(),
+		}
+	}
+}
+
+impl<Key: hash::Hash + Eq + Clone> Sources<Key> {
+	/// Counts an invalid transaction, banning the source when it reaches `threshold` invalid
+	/// transactions within `ban_time`.
+	fn note_invalid(&mut self, now: &Instant, key: Key, threshold: u32, ban_time: Duration) {
+		let count = {
+			let entry = self.invalid.entry(key.clone()).or_insert((0, *now + ban_time));
+			if entry.1 < *now {
+				*entry = (0, *now + ban_time);
+			}
+			entry.0 += 1;
+			entry.0
+		};
+
+		if count >= threshold {
+			self.invalid.remove(&key);
+			self.banned_until.insert(key, *now + ban_time);
+		}
+	}
+
+	fn is_banned<Q: ?Sized + hash::Hash + Eq>(&self, key: &Q) -> bool where Key: Borrow<Q> {
+		self.banned_until.contains_key(key)
+	}
+
+	fn clear_timeouts(&mut self, now: &Instant) {
+		self.banned_until.retain(|_, until| *until >= *now);
+		self.invalid.retain(|_, (_, until)| *until >= *now);
+	}
+}
+
+/// Counts invalid delegated transactions per doughnut issuer and per submitting peer, and
+/// temporarily bans the sources reaching the threshold.
+pub struct DelegationTracker<Hash> {
+	/// Number of invalid delegated transactions after which a source is banned. `0` disables
+	/// the tracking.
+	threshold: u32,
+	/// How long a source is banned for. Also the window invalid transactions are counted in.
+	ban_time: Duration,
+	/// Sources keyed by the encoded doughnut issuer.
+	issuers: RwLock<Sources<Vec<u8>>>,
+	/// Sources keyed by the submitting peer.
+	peers: RwLock<Sources<String>>,
+	/// Peers that submitted the transactions.
+	submitters: RwLock<HashMap<Hash, String>>,
+}
+
+impl<Hash: hash::Hash + Eq + Clone> DelegationTracker<Hash> {
+	/// Creates a new tracker.
+	pub fn new(threshold: u32, ban_time: Duration) -> Self {
+		DelegationTracker {
+			threshold,
+			ban_time,
+			issuers: Default::default(),
+			peers: Default::default(),
+			submitters: Default::default(),
+		}
+	}
+
+	/// Notes that the transaction was received from `peer`.
+	pub fn note_submitter(&self, hash: Hash, peer: String) {
+		if self.threshold == 0 {
+			return;
+		}
+
+		let mut submitters = self.submitters.write();
+		submitters.insert(hash, peer);
+
+		if submitters.len() > 2 * EXPECTED_SIZE {
+			while submitters.len() > EXPECTED_SIZE {
+				if let Some(key) = submitters.keys().next().cloned() {
+					submitters.remove(&key);
+				}
+			}
+		}
+	}
+
+	/// Notes that the transaction was found invalid.
+	///
+	/// `issuer` is the encoded doughnut issuer, `None` if the transaction isn't delegated. Only
+	/// delegated transactions count towards bans.
+	pub fn note_invalid(&self, now: &Instant, hash: &Hash, issuer: Option<Vec<u8>>) {
+		let submitter = self.submitters.write().remove(hash);
+		let issuer = match issuer {
+			Some(issuer) if self.threshold > 0 => issuer,
+			_ => return,
+		};
+
+		self.issuers.write().note_invalid(now, issuer, self.threshold, self.ban_time);
+		if let Some(peer) = submitter {
+			self.peers.write().note_invalid(now, peer, self.threshold, self.ban_time);
+		}
+	}
+
+	/// Forgets the submitter of a transaction that left the pool without being invalid.
+	pub fn forget(&self, hash: &Hash) {
+		self.submitters.write().remove(hash);
+	}
+
+	/// Returns `true` if transactions delegated by `issuer` are currently banned.
+	pub fn is_issuer_banned(&self, issuer: &[u8]) -> bool {
+		self.issuers.read().is_banned(issuer)
+	}
+
+	/// Returns `true` if transactions submitted by `peer` are currently banned.
+	pub fn is_peer_banned(&self, peer: &str) -> bool {
+		self.peers.read().is_banned(peer)
+	}
+
+	/// Removes timed out bans and counts.
+	pub fn clear_timeouts(&self, now: &Instant) {
+		self.issuers.write().clear_timeouts(now);
+		self.peers.write().clear_timeouts(now);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	type Hash = u64;
+
+	fn tracker() -> DelegationTracker<Hash> {
+		DelegationTracker::new(2, Duration::from_millis(10))
+	}
+
+	#[test]
+	fn should_ban_issuer_and_submitter_reaching_threshold() {
+		// given
+		let tracker = tracker();
+		let now = Instant::now();
+		tracker.note_submitter(1, "peer".into());
+		tracker.note_submitter(2, "peer".into());
+
+		// when
+		tracker.note_invalid(&now, &1, Some(vec![7]));
+		assert!(!tracker.is_issuer_banned(&[7]));
+		assert!(!tracker.is_peer_banned("peer"));
+		tracker.note_invalid(&now, &2, Some(vec![7]));
+
+		// then
+		assert!(tracker.is_issuer_banned(&[7]));
+		assert!(tracker.is_peer_banned("peer"));
+		assert!(!tracker.is_issuer_banned(&[8]));
+	}
+
+	#[test]
+	fn should_not_count_transactions_without_doughnut() {
+		// given
+		let tracker = tracker();
+		let now = Instant::now();
+
+		// when
+		for hash in 0..5 {
+			tracker.note_submitter(hash, "peer".into());
+			tracker.note_invalid(&now, &hash, None);
+		}
+
+		// then
+		assert!(!tracker.is_peer_banned("peer"));
+	}
+
+	#[test]
+	fn should_clear_bans() {
+		// given
+		let tracker = tracker();
+		let now = Instant::now();
+		tracker.note_invalid(&now, &1, Some(vec![7]));
+		tracker.note_invalid(&now, &2, Some(vec![7]));
+		assert!(tracker.is_issuer_banned(&[7]));
+
+		// when
+		tracker.clear_timeouts(&(now + Duration::from_millis(20)));
+
+		// then
+		assert!(!tracker.is_issuer_banned(&[7]));
+	}
+
+	#[test]
+	fn should_be_disabled_by_zero_threshold() {
+		// given
+		let tracker = DelegationTracker::<Hash>::new(0, Duration::from_millis(10));
+
+		// when
+		tracker.note_invalid(&Instant::now(), &1, Some(vec![7]));
+
+		// then
+		assert!(!tracker.is_issuer_banned(&[7]));
+	}
+}
@@ -26,11 +26,13 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+mod delegations;
 mod future;
 mod listener;
 mod pool;
 mod ready;
 mod rotator;
+mod unsigned;
 mod validated_pool;
 mod tracked_map;
 
@@ -38,7 +40,9 @@ pub mod base_pool;
 pub mod watcher;
 
 pub use self::base_pool::Transaction;
+pub use self::delegations::DelegationTracker;
+pub use self::unsigned::{UnsignedLimits, UnsignedRateLimiter};
 pub use self::pool::{
 	Pool, Options, ChainApi, EventStream, ExtrinsicFor, ExtrinsicHash,
-	BlockHash, NumberFor, TransactionFor, ValidatedTransaction,
+	BlockHash, NumberFor, TransactionFor, ValidatedTransaction, RevalidateAt,
 };
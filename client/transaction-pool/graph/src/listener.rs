@@ -25,6 +25,7 @@ use serde::Serialize;
 use crate::{watcher, ChainApi, ExtrinsicHash, BlockHash};
 use log::{debug, trace, warn};
 use sp_runtime::traits;
+use sp_transaction_pool::{DropReason, InvalidReason};
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
@@ -87,23 +88,26 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		self.fire(tx, |watcher| watcher.future());
 	}
 
-	/// Transaction was dropped from the pool because of the limit.
-	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
+	/// Transaction was replaced by another transaction providing the same tags.
+	pub fn usurped(&mut self, tx: &H, by: &H) {
 		trace!(target: "txpool", "[{:?}] Dropped (replaced with {:?})", tx, by);
-		self.fire(tx, |watcher| match by {
-			Some(t) => watcher.usurped(t.clone()),
-			None => watcher.dropped(),
-		})
+		self.fire(tx, |watcher| watcher.usurped(by.clone()))
+	}
+
+	/// Transaction was dropped from the pool.
+	pub fn dropped(&mut self, tx: &H, reason: DropReason) {
+		trace!(target: "txpool", "[{:?}] Dropped ({:?})", tx, reason);
+		self.fire(tx, |watcher| watcher.dropped(reason))
 	}
 
 	/// Transaction was removed as invalid.
-	pub fn invalid(&mut self, tx: &H, warn: bool) {
+	pub fn invalid(&mut self, tx: &H, reason: InvalidReason, warn: bool) {
 		if warn {
-			warn!(target: "txpool", "[{:?}] Extrinsic invalid", tx);
+			warn!(target: "txpool", "[{:?}] Extrinsic invalid ({:?})", tx, reason);
 		} else {
-			debug!(target: "txpool", "[{:?}] Extrinsic invalid", tx);
+			debug!(target: "txpool", "[{:?}] Extrinsic invalid ({:?})", tx, reason);
 		}
-		self.fire(tx, |watcher| watcher.invalid());
+		self.fire(tx, |watcher| watcher.invalid(reason));
 	}
 
 	/// Transaction was pruned from the pool.
@@ -19,9 +19,10 @@
 use std::{
 	collections::HashMap,
 	sync::Arc,
+	time::Duration,
 };
 
-use crate::{base_pool as base, watcher::Watcher};
+use crate::{base_pool as base, unsigned::UnsignedLimits, watcher::Watcher};
 
 use futures::{Future, FutureExt};
 use sp_runtime::{
@@ -29,6 +30,7 @@ use sp_runtime::{
 	traits::{self, SaturatedConversion, Block as BlockT},
 	transaction_validity::{
 		TransactionValidity, TransactionTag as Tag, TransactionValidityError, TransactionSource,
+		TransactionPriority,
 	},
 };
 use sp_transaction_pool::error;
@@ -96,6 +98,17 @@ pub trait ChainApi: Send + Sync {
 
 	/// Returns a block body given the block id.
 	fn block_body(&self, at: &BlockId<Self::Block>) -> Self::BodyFuture;
+
+	/// Returns the encoded issuer of the doughnut the extrinsic is delegated with, if any.
+	fn doughnut_issuer(&self, _uxt: &ExtrinsicFor<Self>) -> Option<Vec<u8>> {
+		None
+	}
+
+	/// Returns the number of calls of the extrinsic dispatched with the origin switched to a
+	/// doughnut issuer.
+	fn origin_switches(&self, _uxt: &ExtrinsicFor<Self>) -> u32 {
+		0
+	}
 }
 
 /// Pool configuration options.
@@ -105,8 +118,41 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Reserved lane for high priority (e.g. operational) transactions, `None` to disable.
+	pub priority_lane: Option<base::PriorityLane>,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// Percentage by which the priority of a transaction must exceed the priority of the
+	/// transactions with the same `provides` tags (e.g. same sender and nonce) to replace them.
+	pub replacement_priority_bump: u32,
+	/// Number of invalid delegated transactions after which their doughnut issuer and submitting
+	/// peer are temporarily banned. `0` disables the bans.
+	pub invalid_delegation_threshold: u32,
+	/// How long doughnut issuers and peers are banned for.
+	pub invalid_delegation_ban_time: Duration,
+	/// Quotas of unsigned transactions per call, `None` to disable them.
+	pub unsigned_limits: Option<UnsignedLimits>,
+	/// The block the transactions are periodically revalidated against.
+	pub revalidate_at: RevalidateAt,
+}
+
+/// The block the transactions of the pool are periodically revalidated against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevalidateAt {
+	/// The new best blocks.
+	Best,
+	/// The newly finalized blocks.
+	///
+	/// The transactions invalid at a finalized block are invalid in all the forks built on it,
+	/// so no work is wasted on revalidating against short lived forks, but the invalid
+	/// transactions are only dropped once their inclusion is finalized.
+	Finalized,
+}
+
+impl Default for RevalidateAt {
+	fn default() -> Self {
+		RevalidateAt::Best
+	}
 }
 
 impl Default for Options {
@@ -120,7 +166,23 @@ impl Default for Options {
 				count: 512,
 				total_bytes: 1 * 1024 * 1024,
 			},
+			priority_lane: Some(base::PriorityLane {
+				min_priority: TransactionPriority::max_value(),
+				ready: base::Limit {
+					count: 1024,
+					total_bytes: 2 * 1024 * 1024,
+				},
+				future: base::Limit {
+					count: 64,
+					total_bytes: 128 * 1024,
+				},
+			}),
 			reject_future_transactions: false,
+			replacement_priority_bump: 10,
+			invalid_delegation_threshold: 16,
+			invalid_delegation_ban_time: Duration::from_secs(60 * 30),
+			unsigned_limits: None,
+			revalidate_at: RevalidateAt::Best,
 		}
 	}
 }
@@ -387,12 +449,28 @@ impl<B: ChainApi> Pool<B> {
 		force: bool,
 	) -> (ExtrinsicHash<B>, ValidatedTransactionFor<B>) {
 		let (hash, bytes) = self.validated_pool.api().hash_and_length(&xt);
+		let issuer = self.validated_pool.api().doughnut_issuer(&xt);
 		if !force && self.validated_pool.is_banned(&hash) {
+			self.validated_pool.delegations().forget(&hash);
+			return (
+				hash.clone(),
+				ValidatedTransaction::Invalid(hash, error::Error::TemporarilyBanned.into()),
+			)
+		}
+		if !force && issuer.as_ref().map_or(false, |issuer| self.validated_pool.is_issuer_banned(issuer)) {
+			self.validated_pool.delegations().forget(&hash);
 			return (
 				hash.clone(),
 				ValidatedTransaction::Invalid(hash, error::Error::TemporarilyBanned.into()),
 			)
 		}
+		if !force && !self.validated_pool.admit_unsigned(block_number.saturated_into::<u64>(), source, &xt) {
+			log::debug!(target: "txpool", "[{:?}] Unsigned transaction rate limited", hash);
+			return (
+				hash.clone(),
+				ValidatedTransaction::Unknown(hash, error::Error::RateLimited.into()),
+			)
+		}
 
 		let validation_result = self.validated_pool.api().validate_transaction(
 			block_id,
@@ -420,8 +498,10 @@ impl<B: ChainApi> Pool<B> {
 					)
 				}
 			},
-			Err(TransactionValidityError::Invalid(e)) =>
-				ValidatedTransaction::Invalid(hash.clone(), error::Error::InvalidTransaction(e).into()),
+			Err(TransactionValidityError::Invalid(e)) => {
+				self.validated_pool.delegations().note_invalid(&Instant::now(), &hash, issuer);
+				ValidatedTransaction::Invalid(hash.clone(), error::Error::InvalidTransaction(e).into())
+			},
 			Err(TransactionValidityError::Unknown(e)) =>
 				ValidatedTransaction::Unknown(hash.clone(), error::Error::UnknownTransaction(e).into()),
 		};
@@ -449,7 +529,7 @@ mod tests {
 	use parking_lot::Mutex;
 	use futures::executor::block_on;
 	use super::*;
-	use sp_transaction_pool::TransactionStatus;
+	use sp_transaction_pool::{TransactionStatus, DropReason, InvalidReason};
 	use sp_runtime::{
 		transaction_validity::{ValidTransaction, InvalidTransaction, TransactionSource}, traits::Hash,
 	};
@@ -890,7 +970,7 @@ mod tests {
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
-			assert_eq!(stream.next(), Some(TransactionStatus::Invalid));
+			assert_eq!(stream.next(), Some(TransactionStatus::Invalid(InvalidReason::Removed)));
 			assert_eq!(stream.next(), None);
 		}
 
@@ -955,7 +1035,7 @@ mod tests {
 			// then
 			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
-			assert_eq!(stream.next(), Some(TransactionStatus::Dropped));
+			assert_eq!(stream.next(), Some(TransactionStatus::Dropped(DropReason::PoolFull)));
 		}
 
 		#[test]
@@ -118,6 +118,8 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	ready: TrackedMap<Hash, ReadyTx<Hash, Ex>>,
 	/// Best transactions that are ready to be included to the block without any other previous transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Percentage by which the priority of a replacement must exceed the replaced transactions.
+	replacement_priority_bump: u32,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -133,11 +135,18 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			replacement_priority_bump: 0,
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Sets the percentage by which the priority of a transaction must exceed the collective
+	/// priority of the transactions providing the same tags to replace them.
+	pub fn set_replacement_priority_bump(&mut self, percent: u32) {
+		self.replacement_priority_bump = percent;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -407,7 +416,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	///
 	/// In case that's true it determines if the priority of transactions that
 	/// we are about to replace is lower than the priority of the replacement transaction.
-	/// We remove/replace old transactions in case they have lower priority.
+	/// We remove/replace old transactions in case they have lower priority, bumped by
+	/// `replacement_priority_bump` percent, rounded up.
 	///
 	/// In case replacement is successful returns a list of removed transactions
 	/// and a list of hashes that are still in pool and gets unlocked by the new transaction.
@@ -440,9 +450,19 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 					)
 			};
 
+			// the priority the replacement has to exceed, rounding the bump up so that it isn't
+			// lost on small priorities
+			let required_priority = old_priority.saturating_add(
+				old_priority.saturating_mul(self.replacement_priority_bump.into()).saturating_add(99) / 100
+			);
+
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
-				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
+			if required_priority >= tx.priority {
+				return Err(error::Error::TooLowPriority {
+					old: old_priority,
+					required: required_priority,
+					new: tx.priority,
+				})
 			}
 
 			// construct a list of unlocked transactions
@@ -554,6 +574,7 @@ fn remove_item<T: PartialEq>(vec: &mut Vec<T>, item: &T) {
 mod tests {
 	use super::*;
 	use sp_runtime::transaction_validity::TransactionSource as Source;
+	use assert_matches::assert_matches;
 
 	fn tx(id: u8) -> Transaction<u64, Vec<u8>> {
 		Transaction {
@@ -605,6 +626,57 @@ mod tests {
 		assert_eq!(ready.get().count(), 1);
 	}
 
+	#[test]
+	fn should_replace_transaction_only_if_priority_is_bumped_enough() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_replacement_priority_bump(10);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 110;
+		let mut tx3 = tx(3);
+		tx3.requires.clear();
+		tx3.priority = 111;
+		import(&mut ready, tx1).unwrap();
+
+		// when
+		let err = import(&mut ready, tx2).unwrap_err();
+		let replaced = import(&mut ready, tx3).unwrap();
+
+		// then
+		assert_matches!(err, error::Error::TooLowPriority { old: 100, required: 110, new: 110 });
+		assert_eq!(replaced.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(ready.get().count(), 1);
+	}
+
+	#[test]
+	fn should_round_the_priority_bump_up() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_replacement_priority_bump(10);
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 5;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 6;
+		let mut tx3 = tx(3);
+		tx3.requires.clear();
+		tx3.priority = 7;
+		import(&mut ready, tx1).unwrap();
+
+		// when
+		let err = import(&mut ready, tx2).unwrap_err();
+		let replaced = import(&mut ready, tx3).unwrap();
+
+		// then
+		assert_matches!(err, error::Error::TooLowPriority { old: 5, required: 6, new: 6 });
+		assert_eq!(replaced.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
 	#[test]
 	fn should_replace_multiple_transactions_correctly() {
 		// given
@@ -0,0 +1,216 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rate limiting of unsigned transactions.
+//!
+//! Unsigned transactions, e.g. heartbeats or price feeds submitted by offchain workers, pay no
+//! fees, so a peer can amplify a valid one into a flood of valid variants. Unsigned
+//! transactions are bucketed by the module and call they dispatch, and each bucket admits a
+//! quota of transactions over a window of the last blocks, in total and per source. The quotas
+//! are checked before the transactions are validated by the runtime, so the rejected ones cost
+//! no runtime call. Transactions from retracted blocks are always admitted.
+
+use std::collections::{BTreeMap, HashMap};
+use parking_lot::Mutex;
+use sp_runtime::transaction_validity::TransactionSource;
+
+/// The quotas of unsigned transactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsignedLimits {
+	/// Number of blocks the quotas span.
+	pub window: u64,
+	/// Number of transactions of a bucket admitted per window.
+	pub per_window: u32,
+	/// Number of transactions of a bucket admitted per window from each source, i.e. from the
+	/// node itself (e.g. its offchain workers) and from the network.
+	pub per_source: u32,
+}
+
+/// A bucket of unsigned transactions, the module and call indices of their call. `None` for
+/// the transactions too short to have one.
+type Bucket = Option<[u8; 2]>;
+
+/// Admitted transactions of a bucket at a block.
+#[derive(Debug, Default)]
+struct Quota {
+	total: u32,
+	/// Admitted transactions per source, local and external.
+	sources: [u32; 2],
+}
+
+/// Admitted transactions per bucket over the last blocks.
+#[derive(Debug, Default)]
+struct Window {
+	/// The highest block number transactions were admitted at.
+	latest: u64,
+	buckets: HashMap<Bucket, BTreeMap<u64, Quota>>,
+}
+
+/// Admits unsigned transactions up to the quotas of their bucket.
+#[derive(Debug, Default)]
+pub struct UnsignedRateLimiter {
+	/// The quotas, `None` to admit all transactions.
+	limits: Option<UnsignedLimits>,
+	window: Mutex<Window>,
+}
+
+impl UnsignedRateLimiter {
+	/// Creates a new limiter.
+	pub fn new(limits: Option<UnsignedLimits>) -> Self {
+		UnsignedRateLimiter {
+			limits,
+			window: Default::default(),
+		}
+	}
+
+	/// Admits the encoded unsigned transaction `encoded` from `source`, to be validated at block
+	/// number `block`.
+	///
+	/// Returns `false` if its bucket exhausted one of its quotas over the window ending at the
+	/// highest block seen. Blocks older than the window count towards its oldest block.
+	pub fn admit(&self, block: u64, source: TransactionSource, encoded: &[u8]) -> bool {
+		let limits = match self.limits {
+			Some(ref limits) => limits,
+			None => return true,
+		};
+		let source = match source {
+			TransactionSource::InBlock => return true,
+			TransactionSource::Local => 0,
+			TransactionSource::External => 1,
+		};
+
+		let window = &mut *self.window.lock();
+		let oldest = |latest: u64| (latest + 1).saturating_sub(limits.window.max(1));
+		if block > window.latest {
+			window.latest = block;
+			let oldest = oldest(block);
+			window.buckets.retain(|_, blocks| {
+				*blocks = blocks.split_off(&oldest);
+				!blocks.is_empty()
+			});
+		}
+		let block = block.max(oldest(window.latest));
+
+		let blocks = window.buckets.entry(call_index(encoded)).or_default();
+		let (total, from_source) = blocks.values()
+			.fold((0, 0), |(total, from_source), quota| {
+				(total + quota.total, from_source + quota.sources[source])
+			});
+		if total >= limits.per_window || from_source >= limits.per_source {
+			return false;
+		}
+
+		let quota = blocks.entry(block).or_default();
+		quota.total += 1;
+		quota.sources[source] += 1;
+		true
+	}
+}
+
+/// The module and call indices of an encoded unsigned extrinsic, i.e. the first two bytes of
+/// its call, after its compact length prefix and its version byte.
+fn call_index(encoded: &[u8]) -> Bucket {
+	let prefix = match encoded.first()? & 0b11 {
+		0b00 => 1,
+		0b01 => 2,
+		0b10 => 4,
+		_ => 1 + (encoded[0] >> 2) as usize + 4,
+	};
+	let call = encoded.get(prefix + 1..prefix + 3)?;
+	Some([call[0], call[1]])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+
+	fn limiter() -> UnsignedRateLimiter {
+		UnsignedRateLimiter::new(Some(UnsignedLimits {
+			window: 4,
+			per_window: 3,
+			per_source: 2,
+		}))
+	}
+
+	/// An encoded unsigned extrinsic dispatching call `call` of module `module`.
+	fn unsigned(module: u8, call: u8, nonce: u8) -> Vec<u8> {
+		(4u8, module, call, nonce).encode().encode()
+	}
+
+	#[test]
+	fn call_index_skips_length_prefix_and_version() {
+		assert_eq!(call_index(&unsigned(5, 1, 0)), Some([5, 1]));
+		assert_eq!(call_index(&(4u8, 7u8, 2u8, vec![0u8; 1000]).encode().encode()), Some([7, 2]));
+		assert_eq!(call_index(&[4, 4, 5]), None);
+		assert_eq!(call_index(&[]), None);
+	}
+
+	#[test]
+	fn quotas_are_enforced_per_call_and_source() {
+		let limiter = limiter();
+		let heartbeat = |n: u8| unsigned(5, 1, n);
+
+		assert!(limiter.admit(1, TransactionSource::External, &heartbeat(1)));
+		assert!(limiter.admit(1, TransactionSource::External, &heartbeat(2)));
+		// the external quota of the bucket is exhausted.
+		assert!(!limiter.admit(1, TransactionSource::External, &heartbeat(3)));
+		// other sources and calls have their own quotas.
+		assert!(limiter.admit(1, TransactionSource::Local, &heartbeat(3)));
+		assert!(limiter.admit(1, TransactionSource::External, &unsigned(5, 2, 1)));
+		assert!(limiter.admit(1, TransactionSource::External, &unsigned(6, 1, 1)));
+		// the window quota of the bucket is exhausted.
+		assert!(!limiter.admit(1, TransactionSource::Local, &heartbeat(4)));
+		// transactions from retracted blocks are always admitted.
+		assert!(limiter.admit(1, TransactionSource::InBlock, &heartbeat(5)));
+	}
+
+	#[test]
+	fn quotas_span_the_window() {
+		let limiter = limiter();
+		let heartbeat = unsigned(5, 1, 0);
+
+		assert!(limiter.admit(1, TransactionSource::External, &heartbeat));
+		assert!(limiter.admit(2, TransactionSource::External, &heartbeat));
+		assert!(!limiter.admit(3, TransactionSource::External, &heartbeat));
+		assert!(!limiter.admit(4, TransactionSource::External, &heartbeat));
+		// the transaction of block 1 left the window.
+		assert!(limiter.admit(5, TransactionSource::External, &heartbeat));
+		assert!(!limiter.admit(5, TransactionSource::External, &heartbeat));
+	}
+
+	#[test]
+	fn older_blocks_do_not_reset_quotas() {
+		let limiter = limiter();
+		let heartbeat = unsigned(5, 1, 0);
+
+		assert!(limiter.admit(10, TransactionSource::External, &heartbeat));
+		assert!(limiter.admit(10, TransactionSource::External, &heartbeat));
+		// alternating between forks, or validating at an older block, keeps the quotas.
+		assert!(!limiter.admit(9, TransactionSource::External, &heartbeat));
+		assert!(!limiter.admit(1, TransactionSource::External, &heartbeat));
+		assert!(!limiter.admit(10, TransactionSource::External, &heartbeat));
+	}
+
+	#[test]
+	fn all_transactions_are_admitted_without_limits() {
+		let limiter = UnsignedRateLimiter::new(None);
+
+		for _ in 0..1000 {
+			assert!(limiter.admit(1, TransactionSource::External, &unsigned(5, 1, 0)));
+		}
+	}
+}
@@ -25,16 +25,19 @@ use std::{
 use crate::base_pool as base;
 use crate::listener::Listener;
 use crate::rotator::PoolRotator;
+use crate::delegations::DelegationTracker;
+use crate::unsigned::UnsignedRateLimiter;
 use crate::watcher::Watcher;
 use serde::Serialize;
 
 use parking_lot::{Mutex, RwLock};
 use sp_runtime::{
+	codec::Encode,
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
 	transaction_validity::{TransactionTag as Tag, ValidTransaction, TransactionSource},
 };
-use sp_transaction_pool::{error, PoolStatus};
+use sp_transaction_pool::{error::{self, IntoPoolError}, PoolStatus, DropReason, InvalidReason};
 use wasm_timer::Instant;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
 
@@ -100,6 +103,8 @@ pub struct ValidatedPool<B: ChainApi> {
 	>>,
 	import_notification_sinks: Mutex<Vec<TracingUnboundedSender<ExtrinsicHash<B>>>>,
 	rotator: PoolRotator<ExtrinsicHash<B>>,
+	delegations: DelegationTracker<ExtrinsicHash<B>>,
+	unsigned: UnsignedRateLimiter,
 }
 
 #[cfg(not(target_os = "unknown"))]
@@ -116,7 +121,13 @@ where
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let mut base_pool = base::BasePool::new(options.reject_future_transactions);
+		base_pool.set_replacement_priority_bump(options.replacement_priority_bump);
+		let delegations = DelegationTracker::new(
+			options.invalid_delegation_threshold,
+			options.invalid_delegation_ban_time,
+		);
+		let unsigned = UnsignedRateLimiter::new(options.unsigned_limits.clone());
 		ValidatedPool {
 			options,
 			listener: Default::default(),
@@ -124,6 +135,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
 			rotator: Default::default(),
+			delegations,
+			unsigned,
 		}
 	}
 
@@ -137,6 +150,29 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.rotator.is_banned(hash)
 	}
 
+	/// Returns true if transactions delegated by the given doughnut issuer are currently banned
+	/// from the pool.
+	pub fn is_issuer_banned(&self, issuer: &[u8]) -> bool {
+		self.delegations.is_issuer_banned(issuer)
+	}
+
+	/// Returns the tracker of invalid delegated transactions.
+	pub fn delegations(&self) -> &DelegationTracker<ExtrinsicHash<B>> {
+		&self.delegations
+	}
+
+	/// Returns false if the transaction is unsigned and the quotas of unsigned transactions
+	/// dispatching the same call are exhausted at the given block number.
+	pub fn admit_unsigned(
+		&self,
+		block_number: u64,
+		source: TransactionSource,
+		xt: &ExtrinsicFor<B>,
+	) -> bool {
+		traits::Extrinsic::is_signed(xt) != Some(false)
+			|| xt.using_encoded(|encoded| self.unsigned.admit(block_number, source, encoded))
+	}
+
 	/// Imports a bunch of pre-validated transactions to the pool.
 	pub fn submit<T>(&self, txs: T) -> Vec<Result<ExtrinsicHash<B>, B::Error>> where
 		T: IntoIterator<Item=ValidatedTransactionFor<B>>
@@ -178,7 +214,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				Err(err.into())
 			},
 			ValidatedTransaction::Unknown(hash, err) => {
-				self.listener.write().invalid(&hash, false);
+				self.listener.write().invalid(&hash, InvalidReason::Unknown, false);
 				Err(err.into())
 			},
 		}
@@ -188,10 +224,15 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
 		let future_limit = &self.options.future;
+		let lane = self.options.priority_lane.as_ref();
 
 		log::debug!(target: "txpool", "Pool Status: {:?}", status);
 		if ready_limit.is_exceeded(status.ready, status.ready_bytes)
 			|| future_limit.is_exceeded(status.future, status.future_bytes)
+			|| lane.map_or(false, |lane| {
+				lane.ready.is_exceeded(status.ready, status.ready_bytes)
+					|| lane.future.is_exceeded(status.future, status.future_bytes)
+			})
 		{
 			log::debug!(
 				target: "txpool",
@@ -203,7 +244,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			// clean up the pool
 			let removed = {
 				let mut pool = self.pool.write();
-				let removed = pool.enforce_limits(ready_limit, future_limit)
+				let removed = pool.enforce_limits(ready_limit, future_limit, lane)
 					.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
 				// ban all removed transactions
 				self.rotator.ban(&Instant::now(), removed.iter().map(|x| x.clone()));
@@ -216,7 +257,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 			// run notifications
 			let mut listener = self.listener.write();
 			for h in &removed {
-				listener.dropped(h, None);
+				listener.dropped(h, DropReason::PoolFull);
 			}
 
 			removed
@@ -252,8 +293,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Removes and then submits passed transactions and all dependent transactions.
 	/// Transactions that are missing from the pool are not submitted.
 	pub fn resubmit(&self, mut updated_transactions: HashMap<ExtrinsicHash<B>, ValidatedTransactionFor<B>>) {
-		#[derive(Debug, Clone, Copy, PartialEq)]
-		enum Status { Future, Ready, Failed, Dropped };
+		#[derive(Debug, Clone, PartialEq)]
+		enum Status<H> { Future, Ready, Failed(InvalidReason), Dropped(DropReason), Usurped(H) };
 
 		let (mut initial_statuses, final_statuses) = {
 			let mut pool = self.pool.write();
@@ -312,11 +353,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 									for hash in promoted {
 										final_statuses.insert(hash, Status::Ready);
 									}
-									for hash in failed {
-										final_statuses.insert(hash, Status::Failed);
+									for failed in failed {
+										final_statuses.insert(failed, Status::Failed(InvalidReason::ImportFailed));
 									}
 									for tx in removed {
-										final_statuses.insert(tx.hash.clone(), Status::Dropped);
+										final_statuses.insert(tx.hash.clone(), Status::Usurped(hash.clone()));
 									}
 								},
 								base::Imported::Future { .. } => {
@@ -333,11 +374,18 @@ impl<B: ChainApi> ValidatedPool<B> {
 									hash,
 									err,
 								);
-								final_statuses.insert(hash, Status::Failed);
+								final_statuses.insert(hash, Status::Failed(InvalidReason::ImportFailed));
 							},
 						},
-						ValidatedTransaction::Invalid(_, _) | ValidatedTransaction::Unknown(_, _) => {
-							final_statuses.insert(hash, Status::Failed);
+						ValidatedTransaction::Invalid(_, err) => {
+							let reason = match err.into_pool_error() {
+								Ok(error::Error::InvalidTransaction(ref invalid)) => invalid.into(),
+								_ => InvalidReason::Revalidation,
+							};
+							final_statuses.insert(hash, Status::Failed(reason));
+						},
+						ValidatedTransaction::Unknown(_, _) => {
+							final_statuses.insert(hash, Status::Failed(InvalidReason::Unknown));
 						},
 					}
 				}
@@ -346,7 +394,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				// queue, updating final statuses as required
 				if reject_future_transactions {
 					for future_tx in pool.clear_future() {
-						final_statuses.insert(future_tx.hash.clone(), Status::Dropped);
+						final_statuses.insert(future_tx.hash.clone(), Status::Dropped(DropReason::FutureRejected));
 					}
 				}
 
@@ -358,12 +406,13 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let mut listener = self.listener.write();
 		for (hash, final_status) in final_statuses {
 			let initial_status = initial_statuses.remove(&hash);
-			if initial_status.is_none() || Some(final_status) != initial_status {
+			if initial_status.is_none() || Some(&final_status) != initial_status.as_ref() {
 				match final_status {
 					Status::Future => listener.future(&hash),
 					Status::Ready => listener.ready(&hash, None),
-					Status::Dropped => listener.dropped(&hash, None),
-					Status::Failed => listener.invalid(&hash, initial_status.is_some()),
+					Status::Dropped(reason) => listener.dropped(&hash, reason),
+					Status::Usurped(by) => listener.usurped(&hash, &by),
+					Status::Failed(reason) => listener.invalid(&hash, reason, initial_status.is_some()),
 				}
 			}
 		}
@@ -390,6 +439,9 @@ impl<B: ChainApi> ValidatedPool<B> {
 	) -> Result<PruneStatus<ExtrinsicHash<B>, ExtrinsicFor<B>>, B::Error> {
 		// Perform tag-based pruning in the base pool
 		let status = self.pool.write().prune_tags(tags);
+		for pruned in &status.pruned {
+			self.delegations.forget(&pruned.hash);
+		}
 		// Notify event listeners of all transactions
 		// that were promoted to `Ready` or were dropped.
 		{
@@ -398,7 +450,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 				fire_events(&mut *listener, promoted);
 			}
 			for f in &status.failed {
-				listener.dropped(f, None);
+				listener.dropped(f, DropReason::PromotionFailed);
 			}
 		}
 
@@ -485,10 +537,17 @@ impl<B: ChainApi> ValidatedPool<B> {
 			hashes
 		};
 		// removing old transactions
-		self.remove_invalid(&to_remove);
-		self.remove_invalid(&futures_to_remove);
+		let stale = to_remove.iter().chain(&futures_to_remove).cloned().collect::<HashSet<_>>();
+		let reason = |hash: &ExtrinsicHash<B>| if stale.contains(hash) {
+			Some(InvalidReason::Stale)
+		} else {
+			None
+		};
+		self.remove_and_ban(&to_remove, reason);
+		self.remove_and_ban(&futures_to_remove, reason);
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
+		self.delegations.clear_timeouts(&now);
 
 		Ok(())
 	}
@@ -529,6 +588,38 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// Note this is not the case for the dependent transactions - those may
 	/// still be valid so we want to be able to re-import them.
 	pub fn remove_invalid(&self, hashes: &[ExtrinsicHash<B>]) -> Vec<TransactionFor<B>> {
+		self.remove_invalid_with_reasons(
+			hashes.iter().map(|hash| (hash.clone(), InvalidReason::Removed)).collect()
+		)
+	}
+
+	/// Same as `remove_invalid`, but the watchers of every transaction are notified
+	/// with the given reason.
+	pub fn remove_invalid_with_reasons(
+		&self,
+		reasons: HashMap<ExtrinsicHash<B>, InvalidReason>,
+	) -> Vec<TransactionFor<B>> {
+		let hashes = reasons.keys().cloned().collect::<Vec<_>>();
+		let invalid = self.remove_and_ban(&hashes, |hash| reasons.get(hash).cloned());
+
+		let now = Instant::now();
+		for tx in invalid.iter().filter(|tx| reasons.contains_key(&tx.hash)) {
+			self.delegations.note_invalid(&now, &tx.hash, self.api.doughnut_issuer(&tx.data));
+		}
+
+		invalid
+	}
+
+	/// Remove a subtree of transactions from the pool and ban the transactions passed as an
+	/// argument.
+	///
+	/// Watchers are notified with the reason returned by `reason`, or `DependencyInvalid`
+	/// for the dependent transactions it returns `None` for.
+	fn remove_and_ban(
+		&self,
+		hashes: &[ExtrinsicHash<B>],
+		reason: impl Fn(&ExtrinsicHash<B>) -> Option<InvalidReason>,
+	) -> Vec<TransactionFor<B>> {
 		// early exit in case there is no invalid transactions.
 		if hashes.is_empty() {
 			return vec![];
@@ -545,7 +636,8 @@ impl<B: ChainApi> ValidatedPool<B> {
 
 		let mut listener = self.listener.write();
 		for tx in &invalid {
-			listener.invalid(&tx.hash, true);
+			let reason = reason(&tx.hash).unwrap_or(InvalidReason::DependencyInvalid);
+			listener.invalid(&tx.hash, reason, true);
 		}
 
 		invalid
@@ -556,6 +648,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().ready()
 	}
 
+	/// Returns all transactions in the future queue.
+	pub fn futures(&self) -> Vec<TransactionFor<B>> {
+		self.pool.read().futures().map(|tx| Arc::new(tx.duplicate())).collect()
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
@@ -585,10 +682,10 @@ fn fire_events<H, B, Ex>(
 		base::Imported::Ready { ref promoted, ref failed, ref removed, ref hash } => {
 			listener.ready(hash, None);
 			for f in failed {
-				listener.invalid(f, true);
+				listener.invalid(f, InvalidReason::ImportFailed, true);
 			}
 			for r in removed {
-				listener.dropped(&r.hash, Some(hash));
+				listener.usurped(&r.hash, hash);
 			}
 			for p in promoted {
 				listener.ready(p, None);
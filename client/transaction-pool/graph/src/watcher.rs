@@ -19,7 +19,7 @@
 //! Extrinsics status updates.
 
 use futures::Stream;
-use sp_transaction_pool::TransactionStatus;
+use sp_transaction_pool::{TransactionStatus, DropReason, InvalidReason};
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 /// Extrinsic watcher.
@@ -110,16 +110,16 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.send(TransactionStatus::Retracted(hash));
 	}
 
-	/// Extrinsic has been marked as invalid by the block builder.
-	pub fn invalid(&mut self) {
-		self.send(TransactionStatus::Invalid);
+	/// Extrinsic is no longer valid.
+	pub fn invalid(&mut self, reason: InvalidReason) {
+		self.send(TransactionStatus::Invalid(reason));
 		// we mark as finalized as there are no more notifications
 		self.is_finalized = true;
 	}
 
-	/// Transaction has been dropped from the pool because of the limit.
-	pub fn dropped(&mut self) {
-		self.send(TransactionStatus::Dropped);
+	/// Transaction has been dropped from the pool.
+	pub fn dropped(&mut self, reason: DropReason) {
+		self.send(TransactionStatus::Dropped(reason));
 		self.is_finalized = true;
 	}
 
@@ -18,7 +18,7 @@
 
 //! Chain api required for the transaction pool.
 
-use std::{marker::PhantomData, pin::Pin, sync::Arc};
+use std::{marker::PhantomData, pin::Pin, sync::Arc, time::Instant};
 use codec::{Decode, Encode};
 use futures::{
 	channel::oneshot, executor::{ThreadPool, ThreadPoolBuilder}, future::{Future, FutureExt, ready, Ready},
@@ -29,21 +29,37 @@ use sc_client_api::{
 };
 use sp_runtime::{
 	generic::BlockId, traits::{self, Block as BlockT, BlockIdTo, Header as HeaderT, Hash as HashT},
-	transaction_validity::{TransactionValidity, TransactionSource},
+	transaction_validity::{
+		TransactionValidity, TransactionSource, TransactionValidityError, InvalidTransaction,
+	},
 };
 use sp_transaction_pool::runtime_api::TaggedTransactionQueue;
 use sp_api::{ProvideRuntimeApi, ApiExt};
+use prometheus_endpoint::Registry as PrometheusRegistry;
 
-use crate::error::{self, Error};
+use crate::{error::{self, Error}, metrics::ApiMetricsLink as ApiMetrics};
+
+/// Extracts the encoded doughnut issuer from a delegated extrinsic.
+pub type DoughnutIssuerFn<Block> =
+	Box<dyn Fn(&<Block as BlockT>::Extrinsic) -> Option<Vec<u8>> + Send + Sync>;
+
+/// Counts the calls of an extrinsic dispatched with the origin switched to a doughnut issuer.
+pub type OriginSwitchesFn<Block> =
+	Box<dyn Fn(&<Block as BlockT>::Extrinsic) -> u32 + Send + Sync>;
 
 /// The transaction pool logic for full client.
-pub struct FullChainApi<Client, Block> {
+pub struct FullChainApi<Client, Block: BlockT> {
 	client: Arc<Client>,
 	pool: ThreadPool,
+	doughnut_issuer: Option<DoughnutIssuerFn<Block>>,
+	origin_switches: Option<OriginSwitchesFn<Block>>,
+	/// The custom invalid transaction error of revoked doughnuts.
+	revoked_error: Option<u8>,
+	metrics: ApiMetrics,
 	_marker: PhantomData<Block>,
 }
 
-impl<Client, Block> FullChainApi<Client, Block> {
+impl<Client, Block: BlockT> FullChainApi<Client, Block> {
 	/// Create new transaction pool logic.
 	pub fn new(client: Arc<Client>) -> Self {
 		FullChainApi {
@@ -53,9 +69,42 @@ impl<Client, Block> FullChainApi<Client, Block> {
 				.name_prefix("txpool-verifier")
 				.create()
 				.expect("Failed to spawn verifier threads, that are critical for node operation."),
+			doughnut_issuer: None,
+			origin_switches: None,
+			revoked_error: None,
+			metrics: Default::default(),
 			_marker: Default::default(),
 		}
 	}
+
+	/// Use the given function to find the doughnut issuer of delegated extrinsics, so that
+	/// issuers of repeatedly invalid delegated transactions can be temporarily banned.
+	pub fn with_doughnut_issuer(mut self, doughnut_issuer: DoughnutIssuerFn<Block>) -> Self {
+		self.doughnut_issuer = Some(doughnut_issuer);
+		self
+	}
+
+	/// Use the given function to count the calls of extrinsics dispatched with the origin
+	/// switched to a doughnut issuer, reported for the blocks of the best chain.
+	pub fn with_origin_switches(mut self, origin_switches: OriginSwitchesFn<Block>) -> Self {
+		self.origin_switches = Some(origin_switches);
+		self
+	}
+
+	/// Report the validation time of delegated transactions, and how many of them are rejected
+	/// because their doughnut is revoked, to the given registry.
+	///
+	/// `revoked_error` is the custom invalid transaction error the runtime rejects revoked
+	/// doughnuts with.
+	pub fn with_delegation_metrics(
+		mut self,
+		prometheus: Option<&PrometheusRegistry>,
+		revoked_error: u8,
+	) -> Self {
+		self.metrics = ApiMetrics::new(prometheus);
+		self.revoked_error = Some(revoked_error);
+		self
+	}
 }
 
 impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Block>
@@ -86,11 +135,25 @@ where
 		let (tx, rx) = oneshot::channel();
 		let client = self.client.clone();
 		let at = at.clone();
+		let delegated = self.doughnut_issuer(&uxt).is_some();
+		let revoked_error = self.revoked_error;
+		let metrics = self.metrics.clone();
 
 		self.pool.spawn_ok(futures_diagnose::diagnose(
 			"validate-transaction",
 			async move {
+				let start = Instant::now();
 				let res = validate_transaction_blocking(&*client, &at, source, uxt);
+				if delegated {
+					metrics.report(|metrics| {
+						metrics.delegated_validation_time.observe(start.elapsed().as_secs_f64());
+						if let Ok(Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(code)))) = &res {
+							if Some(*code) == revoked_error {
+								metrics.doughnut_revocation_failures.inc();
+							}
+						}
+					});
+				}
 				if let Err(e) = tx.send(res) {
 					log::warn!("Unable to send a validate transaction result: {:?}", e);
 				}
@@ -112,6 +175,14 @@ where
 		self.client.to_number(at).map_err(|e| Error::BlockIdConversion(format!("{:?}", e)))
 	}
 
+	fn doughnut_issuer(&self, uxt: &sc_transaction_graph::ExtrinsicFor<Self>) -> Option<Vec<u8>> {
+		self.doughnut_issuer.as_ref().and_then(|issuer| issuer(uxt))
+	}
+
+	fn origin_switches(&self, uxt: &sc_transaction_graph::ExtrinsicFor<Self>) -> u32 {
+		self.origin_switches.as_ref().map_or(0, |origin_switches| origin_switches(uxt))
+	}
+
 	fn block_id_to_hash(
 		&self,
 		at: &BlockId<Self::Block>,
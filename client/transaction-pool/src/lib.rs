@@ -47,7 +47,7 @@ use sp_transaction_pool::{
 	TransactionStatusStreamFor, MaintainedTransactionPool, PoolFuture, ChainEvent,
 	TransactionSource,
 };
-use sc_transaction_graph::{ChainApi, ExtrinsicHash};
+use sc_transaction_graph::{ChainApi, ExtrinsicHash, RevalidateAt};
 use wasm_timer::Instant;
 
 use prometheus_endpoint::Registry as PrometheusRegistry;
@@ -73,6 +73,7 @@ pub struct BasicPool<PoolApi, Block>
 	api: Arc<PoolApi>,
 	revalidation_strategy: Arc<Mutex<RevalidationStrategy<NumberFor<Block>>>>,
 	revalidation_queue: Arc<revalidation::RevalidationQueue<PoolApi>>,
+	revalidate_at: RevalidateAt,
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 }
@@ -178,6 +179,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 				pool,
 				revalidation_queue: Arc::new(revalidation_queue),
 				revalidation_strategy: Arc::new(Mutex::new(RevalidationStrategy::Always)),
+				revalidate_at: RevalidateAt::Best,
 				ready_poll: Default::default(),
 				metrics: Default::default(),
 			},
@@ -194,6 +196,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 		prometheus: Option<&PrometheusRegistry>,
 		revalidation_type: RevalidationType,
 	) -> (Self, Option<Pin<Box<dyn Future<Output=()> + Send>>>) {
+		let revalidate_at = options.revalidate_at;
 		let pool = Arc::new(sc_transaction_graph::Pool::new(options, pool_api.clone()));
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light => (revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
@@ -214,6 +217,7 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 						RevalidationType::Full => RevalidationStrategy::Always,
 					}
 				)),
+				revalidate_at,
 				ready_poll: Default::default(),
 				metrics: PrometheusMetrics::new(prometheus),
 			},
@@ -225,6 +229,17 @@ impl<PoolApi, Block> BasicPool<PoolApi, Block>
 	pub fn pool(&self) -> &Arc<sc_transaction_graph::Pool<PoolApi>> {
 		&self.pool
 	}
+
+	/// The maintenance action at the given block, revalidating the transactions only at the
+	/// blocks of the configured kind.
+	fn next_action(&self, block: NumberFor<Block>, finalized: bool) -> RevalidationAction {
+		let mut strategy = self.revalidation_strategy.lock();
+		if finalized == (self.revalidate_at == RevalidateAt::Finalized) {
+			strategy.next(block, Some(std::time::Duration::from_secs(60)), Some(20.into()))
+		} else {
+			strategy.without_revalidation()
+		}
+	}
 }
 
 impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
@@ -329,6 +344,14 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		self.pool.validated_pool().ready_by_hash(hash)
 	}
 
+	fn on_received(&self, hash: TxHash<Self>, peer: String) {
+		self.pool.validated_pool().delegations().note_submitter(hash, peer)
+	}
+
+	fn is_peer_banned(&self, peer: &str) -> bool {
+		self.pool.validated_pool().delegations().is_peer_banned(peer)
+	}
+
 	fn ready_at(&self, at: NumberFor<Self::Block>) -> PolledIterator<PoolApi> {
 		if self.ready_poll.lock().updated_at() >= at {
 			log::trace!(target: "txpool", "Transaction pool already processed block  #{}", at);
@@ -350,6 +373,14 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 	fn ready(&self) -> ReadyIteratorFor<PoolApi> {
 		Box::new(self.pool.validated_pool().ready())
 	}
+
+	fn futures(&self) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.validated_pool().futures()
+	}
+
+	fn doughnut_issuer(&self, xt: &TransactionFor<Self>) -> Option<Vec<u8>> {
+		self.api.doughnut_issuer(xt)
+	}
 }
 
 #[cfg_attr(test, derive(Debug))]
@@ -379,6 +410,17 @@ impl<N: Clone + Copy + AtLeast32Bit> RevalidationStrategy<N> {
 		}
 	}
 
+	/// The action at a block the transactions aren't revalidated at.
+	pub fn without_revalidation(&self) -> RevalidationAction {
+		RevalidationAction {
+			revalidate: false,
+			resubmit: match self {
+				Self::Light(_) => false,
+				Self::Always => true,
+			},
+		}
+	}
+
 	pub fn next(
 		&mut self,
 		block: N,
@@ -436,19 +478,31 @@ impl<N: Clone + Copy + AtLeast32Bit> RevalidationStatus<N> {
 	}
 }
 
-/// Prune the known txs for the given block.
+/// Prunes the transactions of the given block from the pool, reporting its delegated
+/// transactions to `metrics` if given.
 async fn prune_known_txs_for_block<Block: BlockT, Api: ChainApi<Block = Block>>(
 	block_id: BlockId<Block>,
 	api: &Api,
 	pool: &sc_transaction_graph::Pool<Api>,
+	metrics: Option<&PrometheusMetrics>,
 ) -> Vec<ExtrinsicHash<Api>> {
-	let hashes = api.block_body(&block_id).await
+	let extrinsics = api.block_body(&block_id).await
 		.unwrap_or_else(|e| {
 			log::warn!("Prune known transactions: error request {:?}!", e);
 			None
 		})
-		.unwrap_or_default()
-		.into_iter()
+		.unwrap_or_default();
+
+	if let Some(metrics) = metrics {
+		metrics.report(|metrics| {
+			let delegated = extrinsics.iter().filter(|tx| api.doughnut_issuer(tx).is_some()).count();
+			let origin_switches = extrinsics.iter().map(|tx| api.origin_switches(tx) as u64).sum();
+			metrics.block_delegated_transactions.observe(delegated as f64);
+			metrics.doughnut_origin_switches.inc_by(origin_switches);
+		});
+	}
+
+	let hashes = extrinsics.into_iter()
 		.map(|tx| pool.hash_of(&tx))
 		.collect::<Vec<_>>();
 
@@ -485,15 +539,12 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 					}
 				};
 
-				let next_action = self.revalidation_strategy.lock().next(
-					block_number,
-					Some(std::time::Duration::from_secs(60)),
-					Some(20.into()),
-				);
+				let next_action = self.next_action(block_number, false);
 				let revalidation_strategy = self.revalidation_strategy.clone();
 				let revalidation_queue = self.revalidation_queue.clone();
 				let ready_poll = self.ready_poll.clone();
 				let metrics = self.metrics.clone();
+				let revalidate_at = self.revalidate_at;
 
 				async move {
 					// We keep track of everything we prune so that later we won't add
@@ -520,6 +571,7 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 										BlockId::Hash(h.hash.clone()),
 										&*api,
 										&*pool,
+										Some(&metrics),
 									),
 								),
 						).await.into_iter().for_each(|enacted_log|{
@@ -529,7 +581,11 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 
 					// If this is a new best block, we need to prune its transactions from the pool.
 					if is_new_best {
-						pruned_log.extend(prune_known_txs_for_block(id.clone(), &*api, &*pool).await);
+						// the block is reported once, as enacted if it is on a tree route.
+						let enacted = tree_route.as_ref()
+							.map_or(false, |tree_route| tree_route.enacted().iter().any(|h| h.hash == hash));
+						let metrics = if enacted { None } else { Some(&metrics) };
+						pruned_log.extend(prune_known_txs_for_block(id.clone(), &*api, &*pool, metrics).await);
 					}
 
 					metrics.report(
@@ -612,11 +668,30 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 						revalidation_queue.revalidate_later(block_number, hashes).await;
 					}
 
-					revalidation_strategy.lock().clear();
+					if revalidate_at == RevalidateAt::Best {
+						revalidation_strategy.lock().clear();
+					}
 				}.boxed()
 			}
 			ChainEvent::Finalized { hash } => {
 				let pool = self.pool.clone();
+				let revalidation = match self.revalidate_at {
+					RevalidateAt::Best => None,
+					RevalidateAt::Finalized => match self.api.block_id_to_number(&BlockId::hash(hash)) {
+						Ok(Some(number)) => Some((number, self.next_action(number, true))),
+						_ => {
+							log::trace!(
+								target: "txpool",
+								"Skipping revalidation - no number for the finalized block {:?}",
+								hash,
+							);
+							None
+						}
+					},
+				};
+				let revalidation_strategy = self.revalidation_strategy.clone();
+				let revalidation_queue = self.revalidation_queue.clone();
+
 				async move {
 					if let Err(e) = pool.validated_pool().on_block_finalized(hash).await {
 						log::warn!(
@@ -625,6 +700,18 @@ impl<PoolApi, Block> MaintainedTransactionPool for BasicPool<PoolApi, Block>
 							e, hash
 						)
 					}
+
+					// Transactions invalid at the finalized block are invalid in all the forks.
+					if let Some((block_number, next_action)) = revalidation {
+						if next_action.revalidate {
+							let hashes = pool.validated_pool()
+								.ready()
+								.map(|tx| tx.hash.clone())
+								.collect();
+							revalidation_queue.revalidate_later(block_number, hashes).await;
+							revalidation_strategy.lock().clear();
+						}
+					}
 				}.boxed()
 			}
 		}
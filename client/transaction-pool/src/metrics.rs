@@ -20,7 +20,9 @@
 
 use std::sync::Arc;
 
-use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
+use prometheus_endpoint::{
+	register, exponential_buckets, Counter, Histogram, HistogramOpts, PrometheusError, Registry, U64,
+};
 
 #[derive(Clone, Default)]
 pub struct MetricsLink(Arc<Option<Metrics>>);
@@ -50,6 +52,8 @@ pub struct Metrics {
 	pub validations_invalid: Counter<U64>,
 	pub block_transactions_pruned: Counter<U64>,
 	pub block_transactions_resubmitted: Counter<U64>,
+	pub block_delegated_transactions: Histogram,
+	pub doughnut_origin_switches: Counter<U64>,
 }
 
 impl Metrics {
@@ -90,6 +94,73 @@ impl Metrics {
 				)?,
 				registry,
 			)?,
+			block_delegated_transactions: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"sub_txpool_block_delegated_transactions",
+						"Number of delegated transactions in the blocks of the best chain",
+					).buckets(exponential_buckets(1.0, 4.0, 8)?),
+				)?,
+				registry,
+			)?,
+			doughnut_origin_switches: register(
+				Counter::new(
+					"sub_txpool_doughnut_origin_switches",
+					"Total number of calls of the blocks of the best chain dispatched with the origin \
+					switched to a doughnut issuer",
+				)?,
+				registry,
+			)?,
+		})
+	}
+}
+
+#[derive(Clone, Default)]
+pub struct ApiMetricsLink(Arc<Option<ApiMetrics>>);
+
+impl ApiMetricsLink {
+	pub fn new(registry: Option<&Registry>) -> Self {
+		Self(Arc::new(
+			registry.and_then(|registry|
+				ApiMetrics::register(registry)
+					.map_err(|err| { log::warn!("Failed to register prometheus metrics: {}", err); })
+					.ok()
+			)
+		))
+	}
+
+	pub fn report(&self, do_this: impl FnOnce(&ApiMetrics)) {
+		if let Some(metrics) = self.0.as_ref() {
+			do_this(metrics);
+		}
+	}
+}
+
+/// Prometheus metrics of the validation of delegated transactions.
+pub struct ApiMetrics {
+	pub delegated_validation_time: Histogram,
+	pub doughnut_revocation_failures: Counter<U64>,
+}
+
+impl ApiMetrics {
+	pub fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			delegated_validation_time: register(
+				Histogram::with_opts(
+					HistogramOpts::new(
+						"sub_txpool_delegated_validation_time",
+						"Time in seconds to validate delegated transactions, their doughnut included",
+					).buckets(exponential_buckets(0.0001, 2.0, 12)?),
+				)?,
+				registry,
+			)?,
+			doughnut_revocation_failures: register(
+				Counter::new(
+					"sub_txpool_doughnut_revocation_failures",
+					"Total number of delegated transactions rejected because their doughnut is revoked",
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -24,6 +24,7 @@ use sc_transaction_graph::{ChainApi, Pool, ExtrinsicHash, NumberFor, ValidatedTr
 use sp_runtime::traits::{Zero, SaturatedConversion};
 use sp_runtime::generic::BlockId;
 use sp_runtime::transaction_validity::TransactionValidityError;
+use sp_transaction_pool::InvalidReason;
 use sp_utils::mpsc::{tracing_unbounded, TracingUnboundedSender, TracingUnboundedReceiver};
 
 use futures::prelude::*;
@@ -65,7 +66,7 @@ async fn batch_revalidate<Api: ChainApi>(
 	at: NumberFor<Api>,
 	batch: impl IntoIterator<Item=ExtrinsicHash<Api>>,
 ) {
-	let mut invalid_hashes = Vec::new();
+	let mut invalid_hashes = HashMap::new();
 	let mut revalidated = HashMap::new();
 
 	let validation_results = futures::future::join_all(
@@ -81,7 +82,7 @@ async fn batch_revalidate<Api: ChainApi>(
 		match validation_result {
 			Ok(Err(TransactionValidityError::Invalid(err))) => {
 				log::debug!(target: "txpool", "[{:?}]: Revalidation: invalid {:?}", ext_hash, err);
-				invalid_hashes.push(ext_hash);
+				invalid_hashes.insert(ext_hash, InvalidReason::from(&err));
 			},
 			Ok(Err(TransactionValidityError::Unknown(err))) => {
 				// skipping unknown, they might be pushed by valid or invalid transaction
@@ -108,12 +109,12 @@ async fn batch_revalidate<Api: ChainApi>(
 					ext_hash,
 					validation_err
 				);
-				invalid_hashes.push(ext_hash);
+				invalid_hashes.insert(ext_hash, InvalidReason::Unknown);
 			}
 		}
 	}
 
-	pool.validated_pool().remove_invalid(&invalid_hashes);
+	pool.validated_pool().remove_invalid_with_reasons(invalid_hashes);
 	if revalidated.len() > 0 {
 		pool.resubmit(revalidated);
 	}
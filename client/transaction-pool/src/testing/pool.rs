@@ -17,7 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use crate::*;
-use sp_transaction_pool::TransactionStatus;
+use sp_transaction_pool::{TransactionStatus, InvalidReason};
 use futures::executor::{block_on, block_on_stream};
 use txpool::{self, Pool};
 use sp_runtime::{
@@ -374,6 +374,34 @@ fn should_revalidate_across_many_blocks() {
 	assert_eq!(pool.api.validation_requests().len(), 7);
 }
 
+#[test]
+fn should_revalidate_at_finalized_blocks_if_configured() {
+	let xt = uxt(Alice, 209);
+
+	let (mut pool, background_task, mut notifier) = BasicPool::new_test(
+		Arc::new(TestApi::with_alice_nonce(209)),
+	);
+	pool.revalidate_at = RevalidateAt::Finalized;
+	let thread_pool = futures::executor::ThreadPool::new().unwrap();
+	thread_pool.spawn_ok(background_task);
+
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	let header = pool.api.push_block(1, vec![]);
+	pool.api.add_invalid(&xt);
+
+	// the transaction isn't revalidated at the new best block.
+	block_on(pool.maintain(block_event(header.clone())));
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.api.validation_requests().len(), 1);
+
+	block_on(pool.maintain(ChainEvent::Finalized { hash: header.hash() }));
+	block_on(notifier.next());
+
+	assert_eq!(pool.status().ready, 0);
+}
+
 
 #[test]
 fn should_push_watchers_during_maintaince() {
@@ -421,11 +449,11 @@ fn should_push_watchers_during_maintaince() {
 	assert_eq!(pool.status().ready, 3);
 	assert_eq!(
 		futures::executor::block_on_stream(watcher3).collect::<Vec<_>>(),
-		vec![TransactionStatus::Ready, TransactionStatus::Invalid],
+		vec![TransactionStatus::Ready, TransactionStatus::Invalid(InvalidReason::Revalidation)],
 	);
 	assert_eq!(
 		futures::executor::block_on_stream(watcher4).collect::<Vec<_>>(),
-		vec![TransactionStatus::Ready, TransactionStatus::Invalid],
+		vec![TransactionStatus::Ready, TransactionStatus::Invalid(InvalidReason::Revalidation)],
 	);
 
 	// when
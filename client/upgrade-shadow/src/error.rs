@@ -0,0 +1,35 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Upgrade shadow errors.
+
+/// UpgradeShadow Result.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Error type for the upgrade shadow module.
+#[derive(Debug, derive_more::Display, derive_more::From)]
+pub enum Error {
+	/// Failed to read from the backend or calling into the Substrate runtime.
+	Blockchain(sp_blockchain::Error),
+	/// The header or the body of the block is not in the backend.
+	#[display(fmt = "Unknown block {}", _0)]
+	#[from(ignore)]
+	UnknownBlock(String),
+	/// Failed to execute the block.
+	#[display(fmt = "Execution failed: {}", _0)]
+	#[from(ignore)]
+	Execution(String),
+}
@@ -0,0 +1,307 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+#![warn(missing_docs)]
+
+//! Plug runtime upgrade staging.
+//!
+//! While a runtime upgrade is scheduled, e.g. a `set_code` call waiting in the scheduler,
+//! [`ShadowExecution`] executes every imported block a second time against the pending runtime.
+//! On every imported block it:
+//!
+//! 1. Retrieves the pending upgrade at the parent block through the `RuntimeUpgradeApi`.
+//!
+//! 2. Executes the block on the parent state with the current and with the pending runtime,
+//! both in wasm, without committing any changes.
+//!
+//! 3. Reports the block through telemetry and the log if the storage roots differ, or if the
+//! pending runtime fails to execute it.
+//!
+//! An upgrade relying on host functions the node doesn't provide, or decoding the doughnuts of
+//! existing transactions differently, is noticed before it stops the chain. Only the first
+//! `max_blocks` blocks are shadowed for every pending runtime, so that a long upgrade delay
+//! doesn't double the import time of every block until it is enacted.
+//!
+//! If the runtime does not implement the `RuntimeUpgradeApi`, no block is shadowed.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::{Future, StreamExt};
+use log::{debug, warn};
+use sc_client_api::{backend, BlockchainEvents, CloneableSpawn};
+use sc_telemetry::{telemetry, SUBSTRATE_INFO};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::{Backend as _, HeaderBackend as _};
+use sp_core::traits::{CodeExecutor, RuntimeCode, WrappedRuntimeCode};
+use sp_externalities::Extensions;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, Hash as HashT, HashFor, Header as HeaderT},
+};
+use sp_runtime_upgrade::RuntimeUpgradeApi;
+use sp_state_machine::{
+	backend::BackendRuntimeCode, ExecutionStrategy, OverlayedChanges, StateMachine,
+};
+
+pub use error::{Error, Result};
+
+#[cfg(test)]
+mod tests;
+
+mod error;
+
+/// The default number of blocks shadowed for every pending runtime.
+pub const DEFAULT_MAX_BLOCKS: u32 = 10;
+
+/// The outcome of executing a block against the current and the pending runtime.
+#[derive(Debug, PartialEq)]
+pub enum ShadowOutcome<Hash> {
+	/// Both runtimes computed the same storage root.
+	Matching(Hash),
+	/// The runtimes computed different storage roots.
+	Divergent {
+		/// The storage root computed by the current runtime.
+		current: Hash,
+		/// The storage root computed by the pending runtime.
+		pending: Hash,
+	},
+	/// The pending runtime failed to execute the block.
+	PendingFailed(String),
+}
+
+/// A `ShadowExecution` executes imported blocks against a pending runtime upgrade.
+pub struct ShadowExecution<Block: BlockT, Client, Backend, Exec> {
+	client: Arc<Client>,
+	backend: Arc<Backend>,
+	executor: Exec,
+	spawn_handle: Box<dyn CloneableSpawn>,
+	max_blocks: u32,
+	/// The number of blocks shadowed for every pending runtime, by code hash.
+	shadowed: HashMap<Block::Hash, u32>,
+}
+
+impl<Block, Client, Backend, Exec> ShadowExecution<Block, Client, Backend, Exec>
+where
+	Block: BlockT,
+	Backend: backend::Backend<Block>,
+	Exec: CodeExecutor,
+{
+	/// Return a new shadow execution, shadowing up to `max_blocks` blocks for every pending
+	/// runtime.
+	pub fn new(
+		client: Arc<Client>,
+		backend: Arc<Backend>,
+		executor: Exec,
+		spawn_handle: Box<dyn CloneableSpawn>,
+		max_blocks: u32,
+	) -> Self {
+		ShadowExecution {
+			client,
+			backend,
+			executor,
+			spawn_handle,
+			max_blocks,
+			shadowed: HashMap::new(),
+		}
+	}
+
+	/// Execute the block `hash` on the state of its parent with the current runtime and with
+	/// `pending_code`, and compare the storage roots. Nothing is committed to the backend.
+	pub fn shadow_block(
+		&self,
+		hash: &Block::Hash,
+		pending_code: &[u8],
+	) -> Result<ShadowOutcome<Block::Hash>> {
+		let id = BlockId::Hash(*hash);
+		let blockchain = self.backend.blockchain();
+		let header = blockchain.header(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", hash)))?;
+		let extrinsics = blockchain.body(id)?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", hash)))?;
+
+		let parent = BlockId::Hash(*header.parent_hash());
+		let state = self.backend.state_at(parent)?;
+		let state_runtime_code = BackendRuntimeCode::new(&state);
+		let current_code = state_runtime_code.runtime_code()
+			.map_err(|e| Error::Execution(e.into()))?;
+
+		let pending_fetcher = WrappedRuntimeCode(pending_code.into());
+		let pending_runtime_code = RuntimeCode {
+			code_fetcher: &pending_fetcher,
+			heap_pages: current_code.heap_pages,
+			hash: <HashFor<Block> as HashT>::hash(pending_code).encode(),
+		};
+
+		let current = self.execute(&parent, &state, &header, &extrinsics, &current_code)?;
+		match self.execute(&parent, &state, &header, &extrinsics, &pending_runtime_code) {
+			Ok(pending) if pending == current => Ok(ShadowOutcome::Matching(current)),
+			Ok(pending) => Ok(ShadowOutcome::Divergent { current, pending }),
+			Err(e) => Ok(ShadowOutcome::PendingFailed(e.to_string())),
+		}
+	}
+
+	/// Build the block described by `header` and `extrinsics` on `state` with `runtime_code`,
+	/// the way the block builder does, and return the storage root it ends up with.
+	fn execute(
+		&self,
+		parent: &BlockId<Block>,
+		state: &Backend::State,
+		header: &Block::Header,
+		extrinsics: &[Block::Extrinsic],
+		runtime_code: &RuntimeCode,
+	) -> Result<Block::Hash> {
+		let mut overlay = OverlayedChanges::default();
+		let mut call = |method: &str, data: &[u8]| -> Result<Vec<u8>> {
+			let changes_trie_state = backend::changes_tries_state_at_block(
+				parent,
+				self.backend.changes_trie_storage(),
+			)?;
+			StateMachine::new(
+				state,
+				changes_trie_state,
+				&mut overlay,
+				&self.executor,
+				method,
+				data,
+				Extensions::default(),
+				runtime_code,
+				self.spawn_handle.clone(),
+			)
+				.execute(ExecutionStrategy::AlwaysWasm)
+				.map_err(|e| Error::Execution(e.to_string()))
+		};
+
+		call("Core_initialize_block", &sc_block_builder::initial_header::<Block>(header).encode())?;
+		for extrinsic in extrinsics {
+			call("BlockBuilder_apply_extrinsic", &extrinsic.encode())?;
+		}
+		let finalized = call("BlockBuilder_finalize_block", &[])?;
+		let finalized = Block::Header::decode(&mut &finalized[..])
+			.map_err(|e| Error::Execution(e.what().into()))?;
+
+		Ok(*finalized.state_root())
+	}
+}
+
+impl<Block, Client, Backend, Exec> ShadowExecution<Block, Client, Backend, Exec>
+where
+	Block: BlockT + 'static,
+	Backend: backend::Backend<Block>,
+	Exec: CodeExecutor,
+	Client: ProvideRuntimeApi<Block> + BlockchainEvents<Block> + Send + Sync + 'static,
+	<Client as ProvideRuntimeApi<Block>>::Api:
+		RuntimeUpgradeApi<Block, Error = sp_blockchain::Error>,
+{
+	/// Run the shadow execution on every imported block.
+	///
+	/// The returned future is a long running task with the same lifetime as the node itself.
+	/// Blocks are executed synchronously, which only holds the task up while an upgrade is
+	/// pending, for at most `max_blocks` blocks of every pending runtime.
+	pub fn run(mut self) -> impl Future<Output = ()> {
+		let mut import_notifications = self.client.import_notification_stream();
+
+		async move {
+			while let Some(notification) = import_notifications.next().await {
+				if let Err(e) = self.on_imported(&notification.hash) {
+					warn!(
+						target: "upgrade-shadow",
+						"Failed to shadow execute block {:?}: {}",
+						notification.hash,
+						e,
+					);
+				}
+			}
+		}
+	}
+
+	/// Shadow execute the imported block `hash` if an upgrade is pending at its parent.
+	fn on_imported(&mut self, hash: &Block::Hash) -> Result<()> {
+		let header = self.backend.blockchain().header(BlockId::Hash(*hash))?
+			.ok_or_else(|| Error::UnknownBlock(format!("{:?}", hash)))?;
+		let parent = BlockId::Hash(*header.parent_hash());
+
+		let runtime_api = self.client.runtime_api();
+		let upgrade = match runtime_api
+			.require_api::<dyn RuntimeUpgradeApi<Block, Error = sp_blockchain::Error>>(&parent, 1)
+		{
+			Ok(api) => api.pending_upgrade(&parent)?,
+			Err(sp_api::ApiVersionError::Runtime(e)) => return Err(e.into()),
+			Err(_) => return Ok(()),
+		};
+
+		let upgrade = match upgrade {
+			Some(upgrade) => upgrade,
+			None => {
+				self.shadowed.clear();
+				return Ok(());
+			},
+		};
+
+		let code_hash = <HashFor<Block> as HashT>::hash(&upgrade.code);
+		let shadowed = self.shadowed.entry(code_hash).or_insert(0);
+		if *shadowed >= self.max_blocks {
+			return Ok(());
+		}
+		*shadowed += 1;
+
+		let number = *header.number();
+		match self.shadow_block(hash, &upgrade.code)? {
+			ShadowOutcome::Matching(root) => debug!(
+				target: "upgrade-shadow",
+				"Pending runtime {:?} matches storage root {:?} at #{} ({:?})",
+				code_hash, root, number, hash,
+			),
+			ShadowOutcome::Divergent { current, pending } => {
+				warn!(
+					target: "upgrade-shadow",
+					"Pending runtime {:?} diverges at #{} ({:?}): storage root {:?} instead of {:?}",
+					code_hash, number, hash, pending, current,
+				);
+				telemetry!(
+					SUBSTRATE_INFO;
+					"upgrade.shadow_divergence";
+					"height" => ?number,
+					"block" => ?hash,
+					"code_hash" => ?code_hash,
+					"enacted_at" => ?upgrade.at,
+					"current_root" => ?current,
+					"pending_root" => ?pending,
+				);
+			},
+			ShadowOutcome::PendingFailed(e) => {
+				warn!(
+					target: "upgrade-shadow",
+					"Pending runtime {:?} failed to execute #{} ({:?}): {}",
+					code_hash, number, hash, e,
+				);
+				telemetry!(
+					SUBSTRATE_INFO;
+					"upgrade.shadow_failure";
+					"height" => ?number,
+					"block" => ?hash,
+					"code_hash" => ?code_hash,
+					"enacted_at" => ?upgrade.at,
+					"error" => e,
+				);
+			},
+		}
+
+		Ok(())
+	}
+}
+
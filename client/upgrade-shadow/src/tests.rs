@@ -0,0 +1,87 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::*;
+use sc_block_builder::BlockBuilderProvider;
+use sp_consensus::BlockOrigin;
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block, Transfer, WASM_BINARY},
+	Client,
+};
+
+type TestShadowExecution = ShadowExecution<
+	Block,
+	Client<Backend>,
+	Backend,
+	NativeExecutor<LocalExecutor>,
+>;
+
+/// Import a block with a transfer and return a shadow execution on top of it, with the header.
+fn import_block() -> (TestShadowExecution, <Block as BlockT>::Header) {
+	let (mut client, backend) = TestClientBuilder::new().build_with_backend();
+
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_transfer(Transfer {
+		from: AccountKeyring::Alice.into(),
+		to: AccountKeyring::Bob.into(),
+		amount: 42,
+		nonce: 0,
+	}).unwrap();
+	let block = builder.build().unwrap().block;
+	let header = block.header.clone();
+	client.import(BlockOrigin::Own, block).unwrap();
+
+	let shadow = ShadowExecution::new(
+		Arc::new(client),
+		backend,
+		substrate_test_runtime_client::new_native_executor(),
+		sp_core::tasks::executor(),
+		DEFAULT_MAX_BLOCKS,
+	);
+
+	(shadow, header)
+}
+
+#[test]
+fn same_runtime_matches_imported_state_root() {
+	let (shadow, header) = import_block();
+
+	assert_eq!(
+		shadow.shadow_block(&header.hash(), WASM_BINARY).unwrap(),
+		ShadowOutcome::Matching(header.state_root),
+	);
+}
+
+#[test]
+fn invalid_pending_runtime_is_reported() {
+	let (shadow, header) = import_block();
+
+	match shadow.shadow_block(&header.hash(), &[0, 1, 2, 3]).unwrap() {
+		ShadowOutcome::PendingFailed(_) => {},
+		outcome => panic!("Unexpected outcome: {:?}", outcome),
+	}
+}
+
+#[test]
+fn unknown_block_fails() {
+	let (shadow, _) = import_block();
+
+	match shadow.shadow_block(&Default::default(), WASM_BINARY) {
+		Err(Error::UnknownBlock(_)) => {},
+		result => panic!("Unexpected result: {:?}", result),
+	}
+}
@@ -29,6 +29,25 @@
 //!
 //! - `slot_duration` - Determine the Aura slot-duration based on the Timestamp module configuration.
 //!
+//! ### Dispatchable Functions
+//!
+//! - `schedule_hand_over` - Schedule the hand over of block production to another consensus engine
+//! (e.g. BABE) at the end of a given block.
+//!
+//! ## Switching to BABE
+//!
+//! A chain launched with Aura can switch to BABE without a relaunch: upgrade the runtime to include
+//! `pallet_babe`, set `HandOver = Babe` and schedule the hand over with a root origin. At the end
+//! of the chosen block the Aura authorities are cleared, which stops the Aura authoring workers, and
+//! handed over to BABE, which authors every following block. The validators need their Aura
+//! sr25519 keys in their keystore under the BABE key type as well. The hand over is refused
+//! while `HandOver` can't take over from the current authorities, and skipped if it can't
+//! anymore when due.
+//!
+//! The node template runtime and service are set up this way: the node runs Aura until the hand
+//! over, shuts down once it is enacted and runs BABE when restarted. `--babe-bootstrap-block`
+//! overrides the first BABE block reported by the runtime.
+//!
 //! ## Related Modules
 //!
 //! - [Timestamp](../pallet_timestamp/index.html): The Timestamp module is used in Aura to track
@@ -48,9 +67,12 @@ use pallet_timestamp;
 use sp_std::{result, prelude::*};
 use codec::{Encode, Decode};
 use frame_support::{
-	decl_storage, decl_module, Parameter, traits::{Get, FindAuthor},
+	decl_storage, decl_module, decl_error, ensure, Parameter,
+	traits::{Get, FindAuthor, ConsensusHandOver},
+	weights::SimpleDispatchInfo,
 	ConsensusEngineId,
 };
+use frame_system::ensure_root;
 use sp_runtime::{
 	RuntimeAppPublic,
 	traits::{SaturatedConversion, Saturating, Zero, Member, IsMember}, generic::DigestItem,
@@ -68,6 +90,11 @@ mod tests;
 pub trait Trait: pallet_timestamp::Trait {
 	/// The identifier type for an authority.
 	type AuthorityId: Member + Parameter + RuntimeAppPublic + Default;
+
+	/// The consensus engine taking over block production when Aura is retired with
+	/// `schedule_hand_over`. Use `()` if the chain never leaves Aura, which refuses any
+	/// hand over.
+	type HandOver: ConsensusHandOver<Self::AuthorityId>;
 }
 
 decl_storage! {
@@ -77,6 +104,13 @@ decl_storage! {
 
 		/// The current authorities
 		pub Authorities get(fn authorities): Vec<T::AuthorityId>;
+
+		/// The last block authored with Aura, if the hand over to another consensus engine
+		/// is scheduled.
+		pub HandOverAt get(fn hand_over_at): Option<T::BlockNumber>;
+
+		/// Whether Aura handed over block production to another consensus engine.
+		pub Retired get(fn retired): bool;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<T::AuthorityId>;
@@ -84,8 +118,42 @@ decl_storage! {
 	}
 }
 
+decl_error! {
+	/// Error for the aura module.
+	pub enum Error for Module<T: Trait> {
+		/// Aura already handed over block production.
+		AlreadyRetired,
+		/// The hand over must happen at a future block.
+		HandOverInPast,
+		/// No consensus engine can take over block production from the current authorities.
+		NoSuccessor,
+	}
+}
+
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// Schedule the hand over of block production to `T::HandOver` at the end of block `at`,
+		/// which is the last block authored with Aura. Replaces any previously scheduled hand over.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn schedule_hand_over(origin, at: T::BlockNumber) {
+			ensure_root(origin)?;
+			ensure!(!Self::retired(), Error::<T>::AlreadyRetired);
+			ensure!(at >= <frame_system::Module<T>>::block_number(), Error::<T>::HandOverInPast);
+			ensure!(T::HandOver::can_take_over(&Self::authorities()), Error::<T>::NoSuccessor);
+
+			<HandOverAt<T>>::put(at);
+		}
+
+		fn on_finalize(now: T::BlockNumber) {
+			if Self::hand_over_at() == Some(now) {
+				Self::hand_over();
+			}
+		}
+	}
 }
 
 impl<T: Trait> Module<T> {
@@ -99,6 +167,24 @@ impl<T: Trait> Module<T> {
 		<frame_system::Module<T>>::deposit_log(log.into());
 	}
 
+	fn hand_over() {
+		<HandOverAt<T>>::kill();
+
+		// the authorities may have changed since the hand over was scheduled: keep authoring
+		// with Aura rather than retiring without a successor.
+		let authorities = <Authorities<T>>::get();
+		if !T::HandOver::can_take_over(&authorities) {
+			return;
+		}
+
+		Retired::put(true);
+
+		// announcing an empty authority set stops the Aura authoring workers.
+		Self::change_authorities(Vec::new());
+
+		T::HandOver::take_over(authorities);
+	}
+
 	fn initialize_authorities(authorities: &[T::AuthorityId]) {
 		if !authorities.is_empty() {
 			assert!(<Authorities<T>>::get().is_empty(), "Authorities are already initialized!");
@@ -125,7 +211,7 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		where I: Iterator<Item=(&'a T::AccountId, T::AuthorityId)>
 	{
 		// instant changes
-		if changed {
+		if changed && !Self::retired() {
 			let next_authorities = validators.map(|(_, k)| k).collect::<Vec<_>>();
 			let last_authorities = <Module<T>>::authorities();
 			if next_authorities != last_authorities {
@@ -135,6 +221,10 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 	}
 
 	fn on_disabled(i: usize) {
+		if Self::retired() {
+			return;
+		}
+
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(
 			AURA_ENGINE_ID,
 			ConsensusLog::<T::AuthorityId>::OnDisabled(i as AuthorityIndex).encode(),
@@ -178,6 +268,10 @@ impl<T: Trait> Module<T> {
 	}
 
 	fn on_timestamp_set(now: T::Moment, slot_duration: T::Moment) {
+		if Self::retired() {
+			return;
+		}
+
 		let last = Self::last();
 		<Self as Store>::LastTimestamp::put(now);
 
@@ -213,6 +307,11 @@ impl<T: Trait> ProvideInherent for Module<T> {
 
 	/// Verify the validity of the inherent using the timestamp.
 	fn check_inherent(call: &Self::Call, data: &InherentData) -> result::Result<(), Self::Error> {
+		// blocks authored after the hand over don't carry an Aura slot.
+		if Self::retired() {
+			return Ok(());
+		}
+
 		let timestamp = match call {
 			pallet_timestamp::Call::set(ref timestamp) => timestamp.clone(),
 			_ => return Ok(()),
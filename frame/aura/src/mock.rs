@@ -24,8 +24,9 @@ use sp_runtime::{
 	traits::IdentityLookup, Perbill,
 	testing::{Header, UintAuthorityId},
 };
-use frame_support::{impl_outer_origin, parameter_types, weights::Weight};
+use frame_support::{impl_outer_origin, parameter_types, weights::Weight, traits::ConsensusHandOver};
 use sp_io;
+use std::cell::RefCell;
 use sp_core::H256;
 
 impl_outer_origin!{
@@ -71,8 +72,26 @@ impl pallet_timestamp::Trait for Test {
 	type MinimumPeriod = MinimumPeriod;
 }
 
+thread_local! {
+	pub static HANDED_OVER: RefCell<Option<Vec<AuthorityId>>> = RefCell::new(None);
+	pub static CAN_TAKE_OVER: RefCell<bool> = RefCell::new(true);
+}
+
+pub struct TestHandOver;
+
+impl ConsensusHandOver<AuthorityId> for TestHandOver {
+	fn can_take_over(_: &[AuthorityId]) -> bool {
+		CAN_TAKE_OVER.with(|c| *c.borrow())
+	}
+
+	fn take_over(authorities: Vec<AuthorityId>) {
+		HANDED_OVER.with(|h| *h.borrow_mut() = Some(authorities));
+	}
+}
+
 impl Trait for Test {
 	type AuthorityId = AuthorityId;
+	type HandOver = TestHandOver;
 }
 
 pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
@@ -84,3 +103,4 @@ pub fn new_test_ext(authorities: Vec<u64>) -> sp_io::TestExternalities {
 }
 
 pub type Aura = Module<Test>;
+pub type System = frame_system::Module<Test>;
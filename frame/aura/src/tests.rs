@@ -18,7 +18,9 @@
 
 #![cfg(test)]
 
-use crate::mock::{Aura, new_test_ext};
+use crate::{Error, mock::{Aura, System, Test, Origin, HANDED_OVER, CAN_TAKE_OVER, new_test_ext}};
+use frame_support::{assert_ok, assert_noop, traits::OnFinalize};
+use sp_runtime::DispatchError;
 
 #[test]
 fn initial_values() {
@@ -27,3 +29,50 @@ fn initial_values() {
 		assert_eq!(Aura::authorities().len(), 4);
 	});
 }
+
+#[test]
+fn hand_over_happens_at_scheduled_block() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		System::set_block_number(5);
+		let authorities = Aura::authorities();
+
+		assert_noop!(Aura::schedule_hand_over(Origin::signed(1), 10), DispatchError::BadOrigin);
+		assert_noop!(Aura::schedule_hand_over(Origin::ROOT, 4), Error::<Test>::HandOverInPast);
+		assert_ok!(Aura::schedule_hand_over(Origin::ROOT, 10));
+		assert_eq!(Aura::hand_over_at(), Some(10));
+
+		Aura::on_finalize(9);
+		assert!(!Aura::retired());
+		assert!(HANDED_OVER.with(|h| h.borrow().is_none()));
+
+		Aura::on_finalize(10);
+		assert!(Aura::retired());
+		assert_eq!(Aura::hand_over_at(), None);
+		assert!(Aura::authorities().is_empty());
+		assert_eq!(HANDED_OVER.with(|h| h.borrow().clone()), Some(authorities));
+
+		assert_noop!(Aura::schedule_hand_over(Origin::ROOT, 20), Error::<Test>::AlreadyRetired);
+	});
+}
+
+#[test]
+fn hand_over_needs_a_successor() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		System::set_block_number(5);
+		let authorities = Aura::authorities();
+
+		CAN_TAKE_OVER.with(|c| *c.borrow_mut() = false);
+		assert_noop!(Aura::schedule_hand_over(Origin::ROOT, 10), Error::<Test>::NoSuccessor);
+
+		CAN_TAKE_OVER.with(|c| *c.borrow_mut() = true);
+		assert_ok!(Aura::schedule_hand_over(Origin::ROOT, 10));
+
+		// the successor can't take over anymore when the hand over is due.
+		CAN_TAKE_OVER.with(|c| *c.borrow_mut() = false);
+		Aura::on_finalize(10);
+		assert!(!Aura::retired());
+		assert_eq!(Aura::hand_over_at(), None);
+		assert_eq!(Aura::authorities(), authorities);
+		assert!(HANDED_OVER.with(|h| h.borrow().is_none()));
+	});
+}
@@ -0,0 +1,225 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reporting of BABE equivocations.
+//!
+//! An equivocation report carries the two headers the offender authored in the same slot and a
+//! proof that the offender's BABE key was owned by a validator of the session of the slot.
+//! Nodes importing an equivocating block submit the report as an unsigned extrinsic through
+//! the `BabeApi`, the module checks it and reports the offence, e.g. to `pallet-offences`.
+
+use sp_std::{convert::TryFrom, marker::PhantomData, prelude::*};
+use codec::Encode;
+use frame_support::{Parameter, traits::KeyOwnerProofSystem};
+use frame_system::offchain::SubmitUnsignedTransaction;
+use pallet_session::historical::{self, IdentificationTuple};
+use sp_runtime::Perbill;
+use sp_staking::{
+	SessionIndex,
+	offence::{Offence, Kind, OffenceError, ReportOffence},
+};
+use sp_consensus_babe::{EquivocationProof, SlotNumber, KEY_TYPE};
+
+use crate::{AuthorityId, Call, Module, Trait};
+
+/// The equivocation proof of a BABE equivocation report.
+pub type EquivocationProofOf<T> = EquivocationProof<<T as frame_system::Trait>::Header>;
+
+/// The key ownership proof of a BABE equivocation report.
+pub type KeyOwnerProofOf<T> = <<T as Trait>::HandleEquivocation as HandleEquivocation<T>>::KeyOwnerProof;
+
+/// Why an equivocation report was rejected by the `HandleEquivocation`.
+#[derive(Clone, Copy, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+pub enum ReportError {
+	/// The key ownership proof doesn't prove the offender's key was owned by a validator in
+	/// the session of the slot.
+	InvalidKeyOwnershipProof,
+	/// The offence was already reported.
+	DuplicateOffenceReport,
+}
+
+/// Handles the equivocation reports of the module: proves and checks the key ownership of
+/// offenders, reports their offences and submits reports as unsigned extrinsics.
+pub trait HandleEquivocation<T: Trait> {
+	/// The proof that the key of an offender was owned by a validator of a session.
+	type KeyOwnerProof: Parameter;
+
+	/// Prove that the given authority key is owned by a validator of the session of the given
+	/// slot. Only called off-chain, returns `None` if the key isn't owned by a validator of that
+	/// session or its key ownership can't be proven.
+	fn prove_key_ownership(slot: SlotNumber, authority: AuthorityId) -> Option<Self::KeyOwnerProof>;
+
+	/// Check the key ownership proof of the offender for the session of the slot and report
+	/// the equivocation offence of its owner.
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offender: AuthorityId,
+		key_owner_proof: Self::KeyOwnerProof,
+		slot: SlotNumber,
+	) -> Result<(), ReportError>;
+
+	/// Submit an unsigned extrinsic reporting the equivocation to the transaction pool. Only
+	/// called off-chain.
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: Self::KeyOwnerProof,
+	) -> Result<(), ()>;
+}
+
+/// Rejects all equivocation reports, i.e. disables equivocation reporting.
+impl<T: Trait> HandleEquivocation<T> for () {
+	type KeyOwnerProof = ();
+
+	fn prove_key_ownership(_slot: SlotNumber, _authority: AuthorityId) -> Option<()> {
+		None
+	}
+
+	fn report_offence(
+		_reporters: Vec<T::AccountId>,
+		_offender: AuthorityId,
+		_key_owner_proof: (),
+		_slot: SlotNumber,
+	) -> Result<(), ReportError> {
+		Err(ReportError::InvalidKeyOwnershipProof)
+	}
+
+	fn submit_unsigned_equivocation_report(
+		_equivocation_proof: EquivocationProofOf<T>,
+		_key_owner_proof: (),
+	) -> Result<(), ()> {
+		Err(())
+	}
+}
+
+/// Proves key ownership with the historical session module, reports offences to `R` (e.g.
+/// `pallet-offences`) and submits unsigned reports with `S`.
+///
+/// Sessions are expected to be coupled to epochs, i.e. BABE is the `ShouldEndSession` of the
+/// session module.
+pub struct EquivocationHandler<R, S>(PhantomData<(R, S)>);
+
+/// The session of the given slot, `None` if the slot is before the first epoch.
+///
+/// Sessions and epochs advance together, so the session of the slot is as far from the current
+/// session as the epoch of the slot is from the current epoch.
+fn slot_session<T: Trait + pallet_session::Trait>(slot: SlotNumber) -> Option<SessionIndex> {
+	let current_session = pallet_session::Module::<T>::current_index();
+	Module::<T>::epoch_for_slot(slot)
+		.and_then(|epoch| (epoch + current_session as u64).checked_sub(Module::<T>::epoch_index()))
+		.and_then(|session| SessionIndex::try_from(session).ok())
+}
+
+impl<T, R, S> HandleEquivocation<T> for EquivocationHandler<R, S> where
+	T: Trait + historical::Trait,
+	R: ReportOffence<
+		T::AccountId,
+		IdentificationTuple<T>,
+		BabeEquivocationOffence<IdentificationTuple<T>>,
+	>,
+	S: SubmitUnsignedTransaction<T, <T as frame_system::Trait>::Call>,
+	Call<T>: Into<<T as frame_system::Trait>::Call>,
+{
+	type KeyOwnerProof = historical::Proof;
+
+	fn prove_key_ownership(slot: SlotNumber, authority: AuthorityId) -> Option<historical::Proof> {
+		historical::Module::<T>::prove_session_membership(
+			slot_session::<T>(slot)?,
+			(KEY_TYPE, &authority.encode()[..]),
+		)
+	}
+
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offender: AuthorityId,
+		key_owner_proof: historical::Proof,
+		slot: SlotNumber,
+	) -> Result<(), ReportError> {
+		let session_index = key_owner_proof.session();
+		if slot_session::<T>(slot) != Some(session_index) {
+			return Err(ReportError::InvalidKeyOwnershipProof);
+		}
+
+		let validator_set_count = historical::Module::<T>::historical_root(session_index)
+			.map(|(_, count)| count)
+			.ok_or(ReportError::InvalidKeyOwnershipProof)?;
+		let offender = historical::Module::<T>::check_proof((KEY_TYPE, offender), key_owner_proof)
+			.ok_or(ReportError::InvalidKeyOwnershipProof)?;
+
+		let offence = BabeEquivocationOffence {
+			slot,
+			session_index,
+			validator_set_count,
+			offender,
+		};
+
+		R::report_offence(reporters, offence).map_err(|e| match e {
+			OffenceError::DuplicateReport => ReportError::DuplicateOffenceReport,
+			_ => ReportError::InvalidKeyOwnershipProof,
+		})
+	}
+
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: historical::Proof,
+	) -> Result<(), ()> {
+		S::submit_unsigned(Call::<T>::report_equivocation_unsigned(equivocation_proof, key_owner_proof))
+	}
+}
+
+/// A BABE equivocation offence report.
+///
+/// When a validator released two or more blocks at the same slot.
+pub struct BabeEquivocationOffence<FullIdentification> {
+	/// A babe slot number in which this incident happened.
+	pub slot: SlotNumber,
+	/// The session index in which the incident happened.
+	pub session_index: SessionIndex,
+	/// The size of the validator set at the time of the offence.
+	pub validator_set_count: u32,
+	/// The authority that produced the equivocation.
+	pub offender: FullIdentification,
+}
+
+impl<FullIdentification: Clone> Offence<FullIdentification> for BabeEquivocationOffence<FullIdentification> {
+	const ID: Kind = *b"babe:equivocatio";
+	type TimeSlot = SlotNumber;
+
+	fn offenders(&self) -> Vec<FullIdentification> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.slot
+	}
+
+	fn slash_fraction(
+		offenders_count: u32,
+		validator_set_count: u32,
+	) -> Perbill {
+		// the formula is min((3k / n)^2, 1)
+		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
+		// _ ^ 2
+		x.square()
+	}
+}
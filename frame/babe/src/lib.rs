@@ -16,6 +16,15 @@
 
 //! Consensus extension module for BABE consensus. Collects on-chain randomness
 //! from VRF outputs and manages epoch transitions.
+//!
+//! BABE can also take over block production on a running chain through its
+//! `ConsensusHandOver` implementation, e.g. from `pallet_aura`. The first BABE
+//! block is then treated like block #1 of a chain authored with BABE since
+//! genesis, with the randomness of the first two epochs derived from the hash
+//! of the last block authored by the previous consensus engine.
+//!
+//! Equivocations of block authors are reported to the module as extrinsics, see
+//! the `equivocation` module.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unused_must_use, unsafe_code, unused_variables, unused_must_use)]
@@ -24,16 +33,20 @@ pub use pallet_timestamp;
 
 use sp_std::{result, prelude::*};
 use frame_support::{
-	decl_storage, decl_module, traits::{FindAuthor, Get, Randomness as RandomnessT},
+	decl_storage, decl_module, decl_error,
+	traits::{FindAuthor, Get, Randomness as RandomnessT, ConsensusHandOver},
 	weights::{Weight, SimpleDispatchInfo, WeighData},
 };
+use frame_system::{ensure_none, ensure_signed};
 use sp_timestamp::OnTimestampSet;
-use sp_runtime::{generic::DigestItem, ConsensusEngineId, Perbill};
-use sp_runtime::traits::{IsMember, SaturatedConversion, Saturating, Hash};
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+use sp_runtime::{
+	generic::DigestItem, ConsensusEngineId, DispatchResult, RuntimeAppPublic,
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
+	},
 };
+use sp_runtime::traits::{IsMember, SaturatedConversion, Saturating, Hash};
 
 use codec::{Encode, Decode};
 use sp_inherents::{InherentIdentifier, InherentData, ProvideInherent, MakeFatalError};
@@ -45,12 +58,19 @@ use sp_consensus_babe::{
 use sp_consensus_vrf::schnorrkel;
 pub use sp_consensus_babe::{AuthorityId, VRF_OUTPUT_LENGTH, RANDOMNESS_LENGTH, PUBLIC_KEY_LENGTH};
 
+mod equivocation;
+
 #[cfg(all(feature = "std", test))]
 mod tests;
 
 #[cfg(all(feature = "std", test))]
 mod mock;
 
+pub use equivocation::{
+	BabeEquivocationOffence, EquivocationHandler, EquivocationProofOf, HandleEquivocation,
+	KeyOwnerProofOf, ReportError,
+};
+
 pub trait Trait: pallet_timestamp::Trait {
 	/// The amount of time, in slots, that each epoch should last.
 	type EpochDuration: Get<SlotNumber>;
@@ -68,6 +88,10 @@ pub trait Trait: pallet_timestamp::Trait {
 	/// Typically, the `ExternalTrigger` type should be used. An internal trigger should only be used
 	/// when no other module is responsible for changing authority set.
 	type EpochChangeTrigger: EpochChangeTrigger;
+
+	/// The equivocation handling subsystem, proving the key ownership of offenders and
+	/// reporting their offences. `()` rejects all equivocation reports.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// Trigger an epoch change, if any should take place.
@@ -102,6 +126,10 @@ impl EpochChangeTrigger for SameAuthoritiesForever {
 
 const UNDER_CONSTRUCTION_SEGMENT_LENGTH: usize = 256;
 
+/// Context mixed into the hash of the last block before BABE took over block
+/// production, to derive the randomness of the first epochs.
+const BOOTSTRAP_RANDOMNESS_CONTEXT: &[u8] = b"babe-bootstrap-randomness";
+
 type MaybeVrf = Option<schnorrkel::RawVRFOutput>;
 
 decl_storage! {
@@ -152,6 +180,10 @@ decl_storage! {
 		/// Temporary value (cleared at block finalization) which is `Some`
 		/// if per-block initialization has already been called for current block.
 		Initialized get(fn initialized): Option<MaybeVrf>;
+
+		/// The first block authored with BABE, if BABE took over block production
+		/// from another consensus engine instead of running since genesis.
+		pub BootstrapBlock get(fn bootstrap_block): Option<T::BlockNumber>;
 	}
 	add_extra_genesis {
 		config(authorities): Vec<(AuthorityId, BabeAuthorityWeight)>;
@@ -159,9 +191,22 @@ decl_storage! {
 	}
 }
 
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
+	}
+}
+
 decl_module! {
 	/// The BABE Pallet
 	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
 		/// The number of **slots** that an epoch takes. We couple sessions to
 		/// epochs, i.e. we start a new session once the new epoch begins.
 		const EpochDuration: u64 = T::EpochDuration::get();
@@ -191,6 +236,35 @@ decl_module! {
 				Self::deposit_vrf_output(&vrf_output);
 			}
 		}
+
+		/// Report authority equivocation/misbehavior. This method will verify the equivocation
+		/// proof and validate the given key ownership proof against the extracted offender.
+		/// If both are valid, the offence will be reported, with the sender as reporter.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000_000)]
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProofOf<T>,
+			key_owner_proof: KeyOwnerProofOf<T>,
+		) {
+			let reporter = ensure_signed(origin)?;
+			Self::do_report_equivocation(vec![reporter], equivocation_proof, key_owner_proof)?;
+		}
+
+		/// Report authority equivocation/misbehavior. This method will verify the equivocation
+		/// proof and validate the given key ownership proof against the extracted offender.
+		/// If both are valid, the offence will be reported.
+		///
+		/// This extrinsic must be called unsigned and it is expected that only block authors
+		/// will call it (validated in `ValidateUnsigned`).
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000_000)]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProofOf<T>,
+			key_owner_proof: KeyOwnerProofOf<T>,
+		) {
+			ensure_none(origin)?;
+			Self::do_report_equivocation(vec![], equivocation_proof, key_owner_proof)?;
+		}
 	}
 }
 
@@ -242,52 +316,6 @@ impl<T: Trait> pallet_session::ShouldEndSession<T::BlockNumber> for Module<T> {
 	}
 }
 
-// TODO [slashing]: @marcio use this, remove the dead_code annotation.
-/// A BABE equivocation offence report.
-///
-/// When a validator released two or more blocks at the same slot.
-struct BabeEquivocationOffence<FullIdentification> {
-	/// A babe slot number in which this incident happened.
-	slot: u64,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority that produced the equivocation.
-	offender: FullIdentification,
-}
-
-impl<FullIdentification: Clone> Offence<FullIdentification> for BabeEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"babe:equivocatio";
-	type TimeSlot = u64;
-
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
-
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
-
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
-
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.slot
-	}
-
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
-	}
-}
-
 impl<T: Trait> Module<T> {
 	/// Determine the BABE slot duration based on the Timestamp module configuration.
 	pub fn slot_duration() -> T::Moment {
@@ -366,6 +394,49 @@ impl<T: Trait> Module<T> {
 		(EpochIndex::get() * T::EpochDuration::get()) + GenesisSlot::get()
 	}
 
+	/// The index of the epoch of the given slot, `None` if the slot is before the first epoch.
+	pub fn epoch_for_slot(slot: SlotNumber) -> Option<u64> {
+		slot.checked_sub(GenesisSlot::get()).map(|slots| slots / T::EpochDuration::get())
+	}
+
+	/// Submit an unsigned extrinsic reporting the given equivocation, returning `None` if the
+	/// submission failed. Only called off-chain, from the `BabeApi`.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: KeyOwnerProofOf<T>,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+			.ok()
+	}
+
+	/// Prove that the given authority key is owned by a validator of the session of the given
+	/// slot, for equivocation reports. Only called off-chain, from the `BabeApi`.
+	pub fn prove_key_ownership(slot: SlotNumber, authority: AuthorityId) -> Option<KeyOwnerProofOf<T>> {
+		T::HandleEquivocation::prove_key_ownership(slot, authority)
+	}
+
+	fn do_report_equivocation(
+		reporters: Vec<T::AccountId>,
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: KeyOwnerProofOf<T>,
+	) -> DispatchResult {
+		let offender = equivocation_proof.offender.clone();
+		let slot = equivocation_proof.slot_number;
+
+		// validate equivocation proof (check headers are different and seals are valid).
+		if !sp_consensus_babe::check_equivocation_proof(equivocation_proof) {
+			Err(Error::<T>::InvalidEquivocationProof)?
+		}
+
+		T::HandleEquivocation::report_offence(reporters, offender, key_owner_proof, slot)
+			.map_err(|e| match e {
+				ReportError::InvalidKeyOwnershipProof => Error::<T>::InvalidKeyOwnershipProof,
+				ReportError::DuplicateOffenceReport => Error::<T>::DuplicateOffenceReport,
+			})?;
+
+		Ok(())
+	}
+
 	fn deposit_consensus<U: Encode>(new: U) {
 		let log: DigestItem<T::Hash> = DigestItem::Consensus(BABE_ENGINE_ID, new.encode());
 		<frame_system::Module<T>>::deposit_log(log.into())
@@ -467,6 +538,64 @@ impl<T: Trait> Module<T> {
 			Authorities::put(authorities);
 		}
 	}
+
+	/// Start BABE on a running chain with the given authorities, from the next block on.
+	///
+	/// Does nothing if BABE is already running. The next block is treated like the
+	/// first block of the chain: it starts epoch #0 and announces the same
+	/// authorities and randomness for epoch #1.
+	pub fn bootstrap(authorities: Vec<(AuthorityId, BabeAuthorityWeight)>) {
+		if authorities.is_empty() || !Authorities::get().is_empty() {
+			return;
+		}
+
+		let parent_hash = <frame_system::Module<T>>::parent_hash();
+		let randomness = sp_io::hashing::blake2_256(
+			&(BOOTSTRAP_RANDOMNESS_CONTEXT, parent_hash).encode(),
+		);
+
+		EpochIndex::put(0);
+		GenesisSlot::put(0);
+		Authorities::put(authorities);
+		Randomness::put(randomness);
+		NextRandomness::put(randomness);
+
+		let next_block = <frame_system::Module<T>>::block_number() + sp_runtime::traits::One::one();
+		<BootstrapBlock<T>>::put(next_block);
+	}
+}
+
+/// The BABE authorities with the keys of the authorities of the previous consensus engine,
+/// if all of them are sr25519 keys.
+fn handed_over_authorities<A: RuntimeAppPublic>(
+	authorities: &[A],
+) -> Option<Vec<(AuthorityId, BabeAuthorityWeight)>> {
+	authorities.iter()
+		.map(|a| {
+			let raw = a.to_raw_vec();
+			if raw.len() != 32 {
+				return None;
+			}
+
+			AuthorityId::decode(&mut &raw[..]).ok().map(|id| (id, 1))
+		})
+		.collect()
+}
+
+impl<T: Trait, A: RuntimeAppPublic> ConsensusHandOver<A> for Module<T> {
+	fn can_take_over(authorities: &[A]) -> bool {
+		// the authorities keep their keys, so this only works for sr25519 keys of the
+		// previous consensus engine.
+		!authorities.is_empty()
+			&& Authorities::get().is_empty()
+			&& handed_over_authorities(authorities).is_some()
+	}
+
+	fn take_over(authorities: Vec<A>) {
+		if let Some(authorities) = handed_over_authorities(&authorities) {
+			Self::bootstrap(authorities)
+		}
+	}
 }
 
 impl<T: Trait> OnTimestampSet<T::Moment> for Module<T> {
@@ -537,6 +666,11 @@ impl<T: Trait> ProvideInherent for Module<T> {
 	}
 
 	fn check_inherent(call: &Self::Call, data: &InherentData) -> result::Result<(), Self::Error> {
+		// blocks authored before BABE took over block production don't carry a BABE slot.
+		if Self::authorities().is_empty() {
+			return Ok(());
+		}
+
 		let timestamp = match call {
 			pallet_timestamp::Call::set(ref timestamp) => timestamp.clone(),
 			_ => return Ok(()),
@@ -552,3 +686,36 @@ impl<T: Trait> ProvideInherent for Module<T> {
 		}
 	}
 }
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, _) = call {
+			// discard equivocation reports not coming from the local node
+			match source {
+				TransactionSource::Local | TransactionSource::InBlock => {},
+				_ => return InvalidTransaction::Call.into(),
+			}
+
+			if !sp_consensus_babe::check_equivocation_proof(equivocation_proof.clone()) {
+				return InvalidTransaction::BadProof.into();
+			}
+
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				provides: vec![(
+					b"BabeEquivocation",
+					&equivocation_proof.offender,
+					equivocation_proof.slot_number,
+				).encode()],
+				// the key ownership proof is only valid in the session of the slot.
+				longevity: T::EpochDuration::get(),
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+}
@@ -19,7 +19,7 @@
 use super::*;
 use frame_support::traits::OnFinalize;
 use mock::{new_test_ext, Babe, System};
-use sp_runtime::testing::{Digest, DigestItem};
+use sp_runtime::testing::{Digest, DigestItem, UintAuthorityId};
 use sp_consensus_vrf::schnorrkel::{RawVRFOutput, RawVRFProof};
 use pallet_session::ShouldEndSession;
 
@@ -132,3 +132,97 @@ fn authority_index() {
 			"Trivially invalid authorities are ignored")
 	})
 }
+
+#[test]
+fn bootstrap_starts_epoch_zero_at_next_block() {
+	new_test_ext(vec![]).execute_with(|| {
+		let authorities: Vec<(AuthorityId, BabeAuthorityWeight)> = vec![0u64, 1, 2]
+			.into_iter()
+			.map(|a| (UintAuthorityId(a).to_public_key(), 1))
+			.collect();
+
+		System::initialize(
+			&10,
+			&[7; 32].into(),
+			&Default::default(),
+			&Default::default(),
+			Default::default(),
+		);
+
+		// nothing to check without a BABE slot before the take over.
+		assert!(Babe::authorities().is_empty());
+		let keys = authorities.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+		assert!(Babe::can_take_over(&keys[..]));
+		assert!(!Babe::can_take_over(&Vec::<AuthorityId>::new()[..]));
+		// only sr25519 keys can be handed over.
+		assert!(!Babe::can_take_over(&[UintAuthorityId(1)][..]));
+
+		Babe::on_finalize(10);
+		Babe::take_over(keys.clone());
+		System::finalize();
+
+		assert_eq!(Babe::bootstrap_block(), Some(11));
+		assert_eq!(Babe::authorities(), authorities);
+		assert_eq!(Babe::epoch_index(), 0);
+		assert_eq!(Babe::genesis_slot(), 0);
+		assert_ne!(Babe::randomness(), [0; 32]);
+		assert_eq!(NextRandomness::get(), Babe::randomness());
+
+		// a second take over is refused and ignored.
+		assert!(!Babe::can_take_over(&keys[..]));
+		Babe::bootstrap(vec![(UintAuthorityId(5).to_public_key(), 1)]);
+		assert_eq!(Babe::authorities(), authorities);
+
+		let genesis_slot = 500;
+		let pre_digest = make_pre_digest(0, genesis_slot, RawVRFOutput([1; 32]), RawVRFProof([0xff; 64]));
+		System::initialize(
+			&11,
+			&Default::default(),
+			&Default::default(),
+			&pre_digest,
+			Default::default(),
+		);
+
+		assert!(!Babe::should_end_session(11));
+		assert_eq!(Babe::genesis_slot(), genesis_slot);
+
+		Babe::on_finalize(11);
+		let header = System::finalize();
+
+		let consensus_log = sp_consensus_babe::ConsensusLog::NextEpochData(
+			sp_consensus_babe::digests::NextEpochDescriptor {
+				authorities,
+				randomness: Babe::randomness(),
+			}
+		);
+		let consensus_digest = DigestItem::Consensus(BABE_ENGINE_ID, consensus_log.encode());
+		assert_eq!(header.digest.logs[1], consensus_digest);
+	})
+}
+
+#[test]
+fn equivocation_report_with_the_same_header_is_rejected() {
+	new_test_ext(vec![0, 1, 2, 3]).execute_with(|| {
+		let pre_digest = make_pre_digest(0, 10, RawVRFOutput([1; 32]), RawVRFProof([0xff; 64]));
+		System::initialize(
+			&1,
+			&Default::default(),
+			&Default::default(),
+			&pre_digest,
+			Default::default(),
+		);
+		let header = System::finalize();
+
+		let equivocation_proof = sp_consensus_babe::EquivocationProof {
+			offender: UintAuthorityId(0).to_public_key(),
+			slot_number: 10,
+			first_header: header.clone(),
+			second_header: header,
+		};
+
+		assert_eq!(
+			Babe::report_equivocation(mock::Origin::signed(1), equivocation_proof, ()),
+			Err(Error::<mock::Test>::InvalidEquivocationProof.into()),
+		);
+	})
+}
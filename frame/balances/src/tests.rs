@@ -25,7 +25,7 @@ macro_rules! decl_tests {
 		use frame_support::{
 			assert_noop, assert_ok, assert_err,
 			traits::{LockableCurrency, LockIdentifier, WithdrawReason, WithdrawReasons,
-				Currency, ReservableCurrency, ExistenceRequirement::AllowDeath}
+				Currency, ReservableCurrency, StorageDepositCollector, ExistenceRequirement::AllowDeath}
 		};
 		use pallet_transaction_payment::ChargeTransactionPayment;
 		use frame_system::RawOrigin;
@@ -457,6 +457,60 @@ macro_rules! decl_tests {
 			});
 		}
 
+		#[test]
+		fn storage_deposits_are_reserved_per_byte() {
+			<$ext_builder>::default().monied(true).build().execute_with(|| {
+				frame_support::parameter_types! {
+					pub const DepositPerByte: u64 = 2;
+					pub const DepositId: frame_system::StorageDepositId = *b"deposit ";
+				}
+				type Deposit = frame_system::StorageDeposit<$test, Balances, DepositPerByte, DepositId>;
+
+				assert_ok!(Deposit::charge(&1, 1, 3));
+				assert_eq!(Balances::reserved_balance(1), 6);
+				assert_eq!(System::account_storage(1), frame_system::StorageStats { items: 1, bytes: 3 });
+				assert_noop!(Deposit::charge(&1, 1, 3), Error::<$test, _>::InsufficientBalance);
+
+				// refunds are capped at the held deposit
+				Deposit::refund(&1, 1, 10);
+				assert_eq!(Balances::reserved_balance(1), 0);
+				assert_eq!(System::account_storage(1), Default::default());
+			});
+		}
+
+		#[test]
+		fn storage_deposits_are_refunded_per_collector() {
+			<$ext_builder>::default().monied(true).build().execute_with(|| {
+				frame_support::parameter_types! {
+					pub const CheapPerByte: u64 = 1;
+					pub const CheapId: frame_system::StorageDepositId = *b"cheap   ";
+					pub const DearPerByte: u64 = 10;
+					pub const DearId: frame_system::StorageDepositId = *b"dear    ";
+				}
+				type Cheap = frame_system::StorageDeposit<$test, Balances, CheapPerByte, CheapId>;
+				type Dear = frame_system::StorageDeposit<$test, Balances, DearPerByte, DearId>;
+
+				assert_ok!(Balances::reserve(&1, 3));
+				assert_ok!(Cheap::charge(&1, 1, 5));
+				assert_ok!(Dear::charge(&1, 1, 2));
+				assert_eq!(Balances::reserved_balance(1), 28);
+				assert_eq!(System::storage_deposit(1, *b"dear    "), 20);
+
+				// the dear collector refunds the bytes of the cheap one at most its own deposit
+				Dear::refund(&1, 1, 5);
+				assert_eq!(Balances::reserved_balance(1), 8);
+				assert_eq!(System::storage_deposit(1, *b"dear    "), 0);
+				assert_eq!(System::storage_deposit(1, *b"cheap   "), 5);
+
+				Dear::refund(&1, 1, 5);
+				assert_eq!(Balances::reserved_balance(1), 8);
+
+				Cheap::refund(&1, 1, 5);
+				assert_eq!(Balances::reserved_balance(1), 3);
+				assert_eq!(System::storage_deposit(1, *b"cheap   "), 0);
+			});
+		}
+
 		#[test]
 		fn transferring_reserved_balance_should_work() {
 			<$ext_builder>::default().build().execute_with(|| {
@@ -0,0 +1,170 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Benchmarking of the verification of the doughnuts carried by delegated transactions.
+//!
+//! The node builds and signs worst-case doughnuts, with the most permission domains and the
+//! longest domain payloads, and the runtime times their decoding and validation as done before a
+//! delegated call is dispatched. The doughnuts are validated in chains, as the delegated calls of
+//! a `batch_delegated` are, each doughnut of a chain being issued by another account to the same
+//! holder. The results weigh the doughnuts in `WeighDoughnut`.
+
+use codec::Decode;
+use sp_std::prelude::*;
+use sp_runtime::traits::PlugDoughnutApi;
+use frame_support::additional_traits::DoughnutValidator;
+
+use crate::{benchmarking, BenchmarkParameter, BenchmarkResults};
+
+/// The most permission domains a doughnut can carry.
+pub const MAX_DOUGHNUT_DOMAINS: u32 = 128;
+
+/// The longest permission domain name, in bytes.
+pub const MAX_DOMAIN_NAME_LEN: usize = 16;
+
+/// The longest domain payload benchmarked by default, in bytes.
+pub const DEFAULT_MAX_DOMAIN_PAYLOAD_LEN: u32 = 1024;
+
+/// The longest chain of doughnuts benchmarked by default.
+pub const DEFAULT_MAX_CHAIN_LEN: u32 = 16;
+
+/// A chain of encoded doughnuts to benchmark, along with the values of its components.
+pub type DoughnutBenchmarkInput = (Vec<(BenchmarkParameter, u32)>, Vec<Vec<u8>>);
+
+/// The components of the doughnut benchmark with their default ranges: the number of permission
+/// domains `d`, the length of each domain payload `p` and the number of doughnuts of a chain `c`.
+pub fn doughnut_components() -> Vec<(BenchmarkParameter, u32, u32)> {
+	sp_std::vec![
+		(BenchmarkParameter::d, 1, MAX_DOUGHNUT_DOMAINS),
+		(BenchmarkParameter::p, 0, DEFAULT_MAX_DOMAIN_PAYLOAD_LEN),
+		(BenchmarkParameter::c, 1, DEFAULT_MAX_CHAIN_LEN),
+	]
+}
+
+/// Decode and validate every chain of doughnuts of `inputs` with `V`, `repeat` times each, timing
+/// every run.
+pub fn run_doughnut_benchmark<V>(
+	inputs: Vec<DoughnutBenchmarkInput>,
+	repeat: u32,
+) -> Result<Vec<BenchmarkResults>, &'static str>
+where
+	V: DoughnutValidator,
+	V::Doughnut: Decode + PlugDoughnutApi<PublicKey = V::AccountId>,
+{
+	let mut results = Vec::new();
+	for (components, chain) in inputs {
+		for _ in 0..repeat {
+			let start = benchmarking::current_time();
+			for encoded in &chain {
+				let doughnut = V::Doughnut::decode(&mut &encoded[..]).map_err(|_| "Invalid doughnut")?;
+				V::validate_doughnut(&doughnut, &doughnut.holder())?;
+			}
+			let elapsed = benchmarking::current_time() - start;
+			results.push((components.clone(), elapsed, 0));
+		}
+	}
+	Ok(results)
+}
+
+/// Build a doughnut valid forever, signed with sr25519 by the `issuer`th issuer, with `domains`
+/// permission domains of the longest name and a `payload_len` bytes payload each. Every doughnut
+/// has the same holder.
+#[cfg(feature = "std")]
+pub fn worst_case_doughnut(
+	issuer: u32,
+	domains: u32,
+	payload_len: u32,
+) -> Result<sp_runtime::Doughnut, &'static str> {
+	use sp_core::{sr25519, Pair};
+	use sp_runtime::{DoughnutV0, traits::DoughnutSigning};
+
+	if domains > MAX_DOUGHNUT_DOMAINS {
+		return Err("Too many doughnut domains");
+	}
+
+	let mut issuer_seed = [1u8; 32];
+	issuer_seed[..4].copy_from_slice(&issuer.to_le_bytes());
+	let issuer = sr25519::Pair::from_seed(&issuer_seed);
+	let holder = sr25519::Pair::from_seed(&[2u8; 32]);
+	let mut doughnut = DoughnutV0 {
+		issuer: issuer.public().0,
+		holder: holder.public().0,
+		expiry: u32::max_value(),
+		not_before: 0,
+		payload_version: 0,
+		signature_version: 0,
+		signature: [0u8; 64].into(),
+		domains: (0..domains)
+			.map(|i| (
+				format!("{:0>width$}", i, width = MAX_DOMAIN_NAME_LEN),
+				vec![0xff; payload_len as usize],
+			))
+			.collect(),
+	};
+	doughnut.sign_sr25519(&issuer.to_ed25519_bytes()).map_err(|_| "Failed to sign the doughnut")?;
+
+	Ok(sp_runtime::Doughnut::V0(doughnut))
+}
+
+/// Build the worst-case doughnuts to benchmark, stepping each component through its range while
+/// the others take their highest value, as `benchmarks!` does for dispatchables.
+#[cfg(feature = "std")]
+pub fn doughnut_benchmark_inputs(
+	lowest_range_values: &[u32],
+	highest_range_values: &[u32],
+	steps: &[u32],
+) -> Result<Vec<DoughnutBenchmarkInput>, &'static str> {
+	use codec::Encode;
+
+	let components = doughnut_components();
+	let mut inputs = Vec::new();
+	// Default number of steps for a component.
+	let mut prev_steps = 10;
+
+	for (idx, (name, low, high)) in components.iter().enumerate() {
+		let steps = steps.get(idx).cloned().unwrap_or(prev_steps);
+		prev_steps = steps;
+
+		let lowest = lowest_range_values.get(idx).cloned().unwrap_or(*low);
+		let highest = highest_range_values.get(idx).cloned().unwrap_or(*high);
+		let diff = highest.checked_sub(lowest).ok_or("The lowest value is above the highest value")?;
+
+		let step_size = (diff / steps.max(1)).max(1);
+		let num_of_steps = diff / step_size + 1;
+
+		for s in 0..num_of_steps {
+			let component_value = lowest + step_size * s;
+			let c: Vec<(BenchmarkParameter, u32)> = components.iter()
+				.enumerate()
+				.map(|(idx, (n, _, h))|
+					if n == name {
+						(*n, component_value)
+					} else {
+						(*n, *highest_range_values.get(idx).unwrap_or(h))
+					}
+				)
+				.collect();
+
+			let chain = (0..c[2].1)
+				.map(|issuer| worst_case_doughnut(issuer, c[0].1, c[1].1).map(|doughnut| doughnut.encode()))
+				.collect::<Result<_, _>>()?;
+			inputs.push((c, chain));
+		}
+	}
+
+	Ok(inputs)
+}
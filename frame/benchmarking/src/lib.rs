@@ -19,10 +19,12 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod utils;
+mod doughnut;
 #[cfg(feature = "std")]
 mod analysis;
 
 pub use utils::*;
+pub use doughnut::*;
 #[cfg(feature = "std")]
 pub use analysis::Analysis;
 #[doc(hidden)]
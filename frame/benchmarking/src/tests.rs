@@ -164,3 +164,44 @@ fn benchmarks_macro_works_for_non_dispatchable() {
 
 	assert_eq!(closure(), Ok(()));
 }
+
+#[test]
+fn worst_case_doughnuts_are_built_for_every_step() {
+	use sp_runtime::{Doughnut, traits::{DoughnutApi, DoughnutVerify}};
+
+	use BenchmarkParameter::{c, d, p};
+
+	let inputs = doughnut_benchmark_inputs(&[1, 0, 1], &[4, 64, 3], &[3, 2, 2]).unwrap();
+	let components: Vec<_> = inputs.iter().map(|(components, _)| components.clone()).collect();
+	assert_eq!(components, vec![
+		vec![(d, 1), (p, 64), (c, 3)],
+		vec![(d, 2), (p, 64), (c, 3)],
+		vec![(d, 3), (p, 64), (c, 3)],
+		vec![(d, 4), (p, 64), (c, 3)],
+		vec![(d, 4), (p, 0), (c, 3)],
+		vec![(d, 4), (p, 32), (c, 3)],
+		vec![(d, 4), (p, 64), (c, 3)],
+		vec![(d, 4), (p, 64), (c, 1)],
+		vec![(d, 4), (p, 64), (c, 2)],
+		vec![(d, 4), (p, 64), (c, 3)],
+	]);
+	let chain_lens: Vec<_> = inputs.iter().map(|(_, chain)| chain.len()).collect();
+	assert_eq!(chain_lens, vec![3, 3, 3, 3, 3, 3, 3, 1, 2, 3]);
+
+	let decode = |encoded: &Vec<u8>| match Doughnut::decode(&mut &encoded[..]).unwrap() {
+		Doughnut::V0(v0) => v0,
+	};
+	let doughnut = decode(&inputs[0].1[0]);
+	assert!(DoughnutVerify::verify(&doughnut).is_ok());
+	assert_eq!(doughnut.domains.len(), 1);
+	assert_eq!(doughnut.domains[0].0.len(), MAX_DOMAIN_NAME_LEN);
+	assert!(doughnut.get_domain(&doughnut.domains[0].0).is_some());
+
+	// the doughnuts of a chain are issued by distinct accounts to the same holder.
+	let chain: Vec<_> = inputs[9].1.iter().map(decode).collect();
+	assert!(chain.iter().all(|doughnut| DoughnutVerify::verify(doughnut).is_ok()));
+	assert!(chain.iter().all(|doughnut| doughnut.holder == chain[0].holder));
+	assert!(chain[0].issuer != chain[1].issuer && chain[1].issuer != chain[2].issuer);
+
+	assert!(worst_case_doughnut(0, MAX_DOUGHNUT_DOMAINS + 1, 0).is_err());
+}
@@ -21,6 +21,8 @@ use sp_std::vec::Vec;
 use sp_io::hashing::blake2_256;
 use sp_runtime::RuntimeString;
 
+use crate::DoughnutBenchmarkInput;
+
 /// An alphabet of possible parameters to use for benchmarking.
 #[derive(codec::Encode, codec::Decode, Clone, Copy, PartialEq, Debug)]
 #[allow(missing_docs)]
@@ -36,6 +38,7 @@ pub type BenchmarkResults = (Vec<(BenchmarkParameter, u32)>, u128, u128);
 
 sp_api::decl_runtime_apis! {
 	/// Runtime api for benchmarking a FRAME runtime.
+	#[api_version(2)]
 	pub trait Benchmark {
 		/// Dispatch the given benchmark.
 		fn dispatch_benchmark(
@@ -46,6 +49,13 @@ sp_api::decl_runtime_apis! {
 			steps: Vec<u32>,
 			repeat: u32,
 		) -> Result<Vec<BenchmarkResults>, RuntimeString>;
+
+		/// Decode and validate the given doughnuts `repeat` times each, as done before the
+		/// dispatch of a delegated call.
+		fn dispatch_doughnut_benchmark(
+			doughnuts: Vec<DoughnutBenchmarkInput>,
+			repeat: u32,
+		) -> Result<Vec<BenchmarkResults>, RuntimeString>;
 	}
 }
 
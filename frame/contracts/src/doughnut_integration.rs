@@ -89,6 +89,10 @@ pub struct MockDoughnut {
 	/// A mock branching point for verify_contract_to_contract_call, as a doughnut is verified at different level.
 	/// A doughnut is then verified at contract execution via ext_call().
 	contract_verifiable: bool,
+	/// The public key of the issuer.
+	issuer: [u8; 32],
+	/// The public key of the holder.
+	holder: [u8; 32],
 }
 impl MockDoughnut {
 	pub fn set_runtime_verifiable(mut self, verifiable: bool) -> Self {
@@ -99,14 +103,22 @@ impl MockDoughnut {
 		self.contract_verifiable = verifiable;
 		self
 	}
+	pub fn set_issuer(mut self, issuer: [u8; 32]) -> Self {
+		self.issuer = issuer;
+		self
+	}
+	pub fn set_holder(mut self, holder: [u8; 32]) -> Self {
+		self.holder = holder;
+		self
+	}
 }
 
 impl PlugDoughnutApi for MockDoughnut {
 	type PublicKey = [u8; 32];
 	type Timestamp = u32;
 	type Signature = ();
-	fn holder(&self) -> Self::PublicKey { Default::default() }
-	fn issuer(&self) -> Self::PublicKey { Default::default() }
+	fn holder(&self) -> Self::PublicKey { self.holder }
+	fn issuer(&self) -> Self::PublicKey { self.issuer }
 	fn expiry(&self) -> Self::Timestamp { 0 }
 	fn not_before(&self) -> Self::Timestamp { 0 }
 	fn payload(&self) -> Vec<u8> { Vec::default() }
@@ -1423,6 +1423,120 @@ mod tests {
 		);
 	}
 
+	/// Returns the status of `ext_doughnut_issuer` followed by the scratch buffer.
+	const CODE_DOUGHNUT_ISSUER: &str = r#"
+(module
+	(import "env" "ext_doughnut_issuer" (func $ext_doughnut_issuer (result i32)))
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(i32.store8
+			(i32.const 0)
+			(call $ext_doughnut_issuer)
+		)
+		(call $ext_scratch_read
+			(i32.const 1)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(call $ext_scratch_size)	;; Count of bytes to copy.
+		)
+		(call $ext_return
+			(i32.const 0)
+			(i32.add
+				(i32.const 1)
+				(call $ext_scratch_size)
+			)
+		)
+	)
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn doughnut_issuer() {
+		let doughnut = MockDoughnut::default().set_issuer([7; 32]).set_holder([8; 32]);
+		let mut mock_ext = MockExt::default_with_doughnut(Some(doughnut));
+		let output = execute(
+			CODE_DOUGHNUT_ISSUER,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		let mut issuer = vec![0];
+		issuer.extend_from_slice(&[7; 32]);
+		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: issuer });
+
+		let mut mock_ext = MockExt::default_with_doughnut(None);
+		let output = execute(
+			CODE_DOUGHNUT_ISSUER,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: vec![1] });
+	}
+
+	/// Returns the status of `ext_doughnut_holder` followed by the scratch buffer.
+	const CODE_DOUGHNUT_HOLDER: &str = r#"
+(module
+	(import "env" "ext_doughnut_holder" (func $ext_doughnut_holder (result i32)))
+	(import "env" "ext_scratch_size" (func $ext_scratch_size (result i32)))
+	(import "env" "ext_scratch_read" (func $ext_scratch_read (param i32 i32 i32)))
+	(import "env" "ext_return" (func $ext_return (param i32 i32)))
+	(import "env" "memory" (memory 1 1))
+
+	(func (export "call")
+		(i32.store8
+			(i32.const 0)
+			(call $ext_doughnut_holder)
+		)
+		(call $ext_scratch_read
+			(i32.const 1)		;; Pointer in memory to the place where to copy.
+			(i32.const 0)		;; Offset from the start of the scratch buffer.
+			(call $ext_scratch_size)	;; Count of bytes to copy.
+		)
+		(call $ext_return
+			(i32.const 0)
+			(i32.add
+				(i32.const 1)
+				(call $ext_scratch_size)
+			)
+		)
+	)
+	(func (export "deploy"))
+)
+"#;
+
+	#[test]
+	fn doughnut_holder() {
+		let doughnut = MockDoughnut::default().set_issuer([7; 32]).set_holder([8; 32]);
+		let mut mock_ext = MockExt::default_with_doughnut(Some(doughnut));
+		let output = execute(
+			CODE_DOUGHNUT_HOLDER,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		let mut holder = vec![0];
+		holder.extend_from_slice(&[8; 32]);
+		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: holder });
+
+		let mut mock_ext = MockExt::default_with_doughnut(None);
+		let output = execute(
+			CODE_DOUGHNUT_HOLDER,
+			vec![],
+			&mut mock_ext,
+			&mut GasMeter::with_limit(50_000, 1),
+		).unwrap();
+
+		assert_eq!(output, ExecReturnValue { status: STATUS_SUCCESS, data: vec![1] });
+	}
+
 	const CODE_RETURN_FROM_START_FN: &str = r#"
 (module
 	(import "env" "ext_return" (func $ext_return (param i32 i32)))
@@ -26,7 +26,7 @@ use frame_support::additional_traits::DelegatedDispatchVerifier;
 use frame_system;
 use sp_std::{prelude::*, mem, convert::TryInto};
 use codec::{Decode, Encode};
-use sp_runtime::traits::{Bounded, SaturatedConversion};
+use sp_runtime::traits::{Bounded, PlugDoughnutApi, SaturatedConversion};
 use sp_io::hashing::{
 	keccak_256,
 	blake2_256,
@@ -698,6 +698,34 @@ define_env!(Env, <E: Ext>,
 		Ok(())
 	},
 
+	// Stores the public key of the issuer of the doughnut the contract is called with into the
+	// scratch buffer and returns 0, i.e. the account delegating the call, e.g. sponsoring it.
+	// If the contract isn't called with a doughnut, clears the scratch buffer and returns 1.
+	ext_doughnut_issuer(ctx) -> u32 => {
+		ctx.scratch_buf.clear();
+		match ctx.ext.doughnut() {
+			Some(doughnut) => {
+				ctx.scratch_buf.extend_from_slice(doughnut.issuer().as_ref());
+				Ok(0)
+			},
+			None => Ok(1),
+		}
+	},
+
+	// Stores the public key of the holder of the doughnut the contract is called with into the
+	// scratch buffer and returns 0, i.e. the account the call is delegated to.
+	// If the contract isn't called with a doughnut, clears the scratch buffer and returns 1.
+	ext_doughnut_holder(ctx) -> u32 => {
+		ctx.scratch_buf.clear();
+		match ctx.ext.doughnut() {
+			Some(doughnut) => {
+				ctx.scratch_buf.extend_from_slice(doughnut.holder().as_ref());
+				Ok(0)
+			},
+			None => Ok(1),
+		}
+	},
+
 	// Stores the address of the current contract into the scratch buffer.
 	ext_address(ctx) => {
 		ctx.scratch_buf.clear();
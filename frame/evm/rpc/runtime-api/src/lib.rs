@@ -0,0 +1,83 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition required by the Ethereum RPC extensions of the EVM pallet.
+//!
+//! This API should be imported and implemented by the runtime of a node that wants to serve
+//! `eth_call`, `eth_sendRawTransaction`, `eth_getTransactionCount`, `eth_getTransactionReceipt`
+//! and `eth_getLogs`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use pallet_evm::{Log, Receipt};
+use sp_core::{H160, H256, U256};
+use sp_runtime::{traits::Block as BlockT, RuntimeDebug};
+use sp_std::vec::Vec;
+
+/// A result of a read only EVM call.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum EvmCallResult {
+	/// The call succeeded, with the given output.
+	Succeed(Vec<u8>),
+	/// The call reverted, with the given output.
+	Revert(Vec<u8>),
+	/// The call failed, e.g. it ran out of gas or the source balance is too low.
+	Error,
+}
+
+/// A log of the EVM, as deposited in a block.
+#[derive(Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct BlockLog {
+	/// The index of the extrinsic in the block.
+	pub extrinsic_index: u32,
+	/// The hash of the Ethereum transaction of the extrinsic, if any.
+	pub transaction_hash: Option<H256>,
+	/// The log.
+	pub log: Log,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The API to serve the Ethereum RPC methods from the EVM pallet.
+	pub trait EvmApi {
+		/// The chain id of the Ethereum transactions (EIP-155).
+		fn chain_id() -> u64;
+
+		/// Perform a call from a given address to a given contract, without applying its changes.
+		fn call(
+			from: H160,
+			to: H160,
+			data: Vec<u8>,
+			value: U256,
+			gas_limit: u32,
+		) -> EvmCallResult;
+
+		/// The unsigned extrinsic applying an RLP encoded signed Ethereum transaction, or `None`
+		/// if the transaction is invalid.
+		fn transact_extrinsic(transaction: Vec<u8>) -> Option<<Block as BlockT>::Extrinsic>;
+
+		/// The logs of the EVM deposited in the block.
+		#[skip_initialize_block]
+		fn block_logs() -> Vec<BlockLog>;
+
+		/// The nonce of the given address.
+		fn account_nonce(address: H160) -> U256;
+
+		/// The receipts of the Ethereum transactions applied in the block.
+		#[skip_initialize_block]
+		fn receipts() -> Vec<Receipt>;
+	}
+}
@@ -0,0 +1,542 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethereum RPC methods of the EVM pallet.
+//!
+//! Serves `eth_chainId`, `eth_blockNumber`, `eth_call`, `eth_getTransactionCount`,
+//! `eth_sendRawTransaction`, `eth_getTransactionReceipt` and `eth_getLogs` from the EVM pallet, so
+//! that Ethereum tooling can target the chain. Ethereum block numbers are the numbers of the plug
+//! blocks, and the logs of a block are the `Log` events of the EVM pallet.
+//!
+//! No index of the transactions is kept, receipts are searched in the recent blocks only.
+
+use std::sync::Arc;
+
+use futures::{FutureExt, TryFutureExt};
+use jsonrpc_core::{futures::future as future01, BoxFuture, Error, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::keccak_256, hexdisplay::HexDisplay, Bytes, H160, H256, U256};
+use sp_rpc::number::NumberOrHex;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, NumberFor, UniqueSaturatedFrom, UniqueSaturatedInto},
+};
+use sp_transaction_pool::{TransactionPool, TransactionSource};
+
+pub use self::gen_client::Client as EthClient;
+pub use pallet_evm_rpc_runtime_api::{self as runtime_api, BlockLog, EvmApi as EvmRuntimeApi, EvmCallResult};
+
+const RUNTIME_ERROR: i64 = 1;
+const INVALID_TRANSACTION: i64 = 2;
+const POOL_ERROR: i64 = 4;
+/// The error code of reverted calls, as used by Ethereum clients.
+const EXECUTION_REVERTED: i64 = 3;
+
+/// The gas limit of `eth_call` if none is given, and the maximum gas limit of `eth_call`.
+const MAX_CALL_GAS: u32 = 25_000_000;
+
+/// The maximum number of blocks searched by `eth_getLogs`.
+const MAX_LOG_BLOCKS: u64 = 1_000;
+
+/// The number of recent blocks searched by `eth_getTransactionReceipt`.
+const MAX_RECEIPT_BLOCKS: u64 = 256;
+
+/// The size of the bloom filter of the logs of a receipt.
+const BLOOM_SIZE: usize = 256;
+
+/// A block tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockTag {
+	/// The genesis block.
+	Earliest,
+	/// The best block.
+	Latest,
+	/// The best block, pending transactions aren't executed.
+	Pending,
+}
+
+/// A block, by number or tag.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BlockNumber {
+	/// A block tag.
+	Tag(BlockTag),
+	/// A block number.
+	Number(NumberOrHex<u64>),
+}
+
+/// One value or several values.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ValueOrArray<T> {
+	/// One value.
+	Value(T),
+	/// Several values.
+	Array(Vec<T>),
+}
+
+impl<T: PartialEq> ValueOrArray<T> {
+	fn contains(&self, value: &T) -> bool {
+		match self {
+			ValueOrArray::Value(v) => v == value,
+			ValueOrArray::Array(values) => values.contains(value),
+		}
+	}
+}
+
+/// The parameters of `eth_call`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+	/// The caller, the zero address if none.
+	pub from: Option<H160>,
+	/// The called contract.
+	pub to: H160,
+	/// The gas limit.
+	pub gas: Option<U256>,
+	/// The gas price, ignored.
+	pub gas_price: Option<U256>,
+	/// The value transferred to the contract.
+	pub value: Option<U256>,
+	/// The input of the call.
+	pub data: Option<Bytes>,
+}
+
+/// The parameters of `eth_getLogs`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+	/// The first block searched, the best block if none.
+	pub from_block: Option<BlockNumber>,
+	/// The last block searched, the best block if none.
+	pub to_block: Option<BlockNumber>,
+	/// The addresses of the logs, any address if none.
+	pub address: Option<ValueOrArray<H160>>,
+	/// The topics of the logs, by position. A `null` position matches any topic.
+	pub topics: Option<Vec<Option<ValueOrArray<H256>>>>,
+}
+
+impl Filter {
+	/// Returns `true` if the log matches the address and topics of the filter.
+	pub fn matches(&self, log: &pallet_evm::Log) -> bool {
+		if let Some(ref address) = self.address {
+			if !address.contains(&log.address) {
+				return false;
+			}
+		}
+
+		let topics = self.topics.as_ref().map(|topics| &topics[..]).unwrap_or(&[]);
+		topics.iter().enumerate().all(|(i, topic)| match topic {
+			Some(topic) => log.topics.get(i).map_or(false, |t| topic.contains(t)),
+			None => true,
+		})
+	}
+}
+
+/// A log, as returned by `eth_getLogs`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcLog {
+	/// The contract which deposited the log.
+	pub address: H160,
+	/// The topics of the log.
+	pub topics: Vec<H256>,
+	/// The data of the log.
+	pub data: Bytes,
+	/// The hash of the block.
+	pub block_hash: H256,
+	/// The number of the block.
+	pub block_number: U256,
+	/// The hash of the Ethereum transaction, if the log was deposited by one.
+	pub transaction_hash: Option<H256>,
+	/// The index of the extrinsic in the block.
+	pub transaction_index: U256,
+	/// The index of the log in the block.
+	pub log_index: U256,
+	/// Whether the log was removed by a reorganisation, always `false`.
+	pub removed: bool,
+}
+
+/// The receipt of a transaction, as returned by `eth_getTransactionReceipt`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The index of the extrinsic in the block.
+	pub transaction_index: U256,
+	/// The hash of the block.
+	pub block_hash: H256,
+	/// The number of the block.
+	pub block_number: U256,
+	/// The sender of the transaction.
+	pub from: H160,
+	/// The called address, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// The gas used by the Ethereum transactions of the block, up to this one.
+	pub cumulative_gas_used: U256,
+	/// The gas used by the transaction.
+	pub gas_used: U256,
+	/// The address of the created contract, if any.
+	pub contract_address: Option<H160>,
+	/// The logs deposited by the transaction.
+	pub logs: Vec<RpcLog>,
+	/// The bloom filter of the addresses and topics of the logs.
+	pub logs_bloom: Bytes,
+	/// `1` if the transaction succeeded, `0` otherwise.
+	pub status: U256,
+}
+
+/// Ethereum RPC methods.
+#[rpc]
+pub trait EthApi {
+	/// Returns the chain id of the Ethereum transactions (EIP-155).
+	#[rpc(name = "eth_chainId")]
+	fn chain_id(&self) -> Result<U256>;
+
+	/// Returns the number of the best block.
+	#[rpc(name = "eth_blockNumber")]
+	fn block_number(&self) -> Result<U256>;
+
+	/// Executes a call to a contract, without submitting any transaction.
+	#[rpc(name = "eth_call")]
+	fn call(&self, request: CallRequest, block: Option<BlockNumber>) -> Result<Bytes>;
+
+	/// Returns the nonce of the given address, i.e. the number of transactions it sent.
+	#[rpc(name = "eth_getTransactionCount")]
+	fn transaction_count(&self, address: H160, block: Option<BlockNumber>) -> Result<U256>;
+
+	/// Submits an RLP encoded signed Ethereum transaction, returning its hash.
+	#[rpc(name = "eth_sendRawTransaction")]
+	fn send_raw_transaction(&self, transaction: Bytes) -> BoxFuture<H256>;
+
+	/// Returns the receipt of the transaction with the given hash, `None` if the transaction
+	/// isn't in a recent block.
+	#[rpc(name = "eth_getTransactionReceipt")]
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<RpcReceipt>>;
+
+	/// Returns the logs of the EVM matching the given filter.
+	#[rpc(name = "eth_getLogs")]
+	fn logs(&self, filter: Filter) -> Result<Vec<RpcLog>>;
+}
+
+/// An implementation of the Ethereum RPC methods.
+pub struct Eth<C, P> {
+	client: Arc<C>,
+	pool: Arc<P>,
+}
+
+impl<C, P> Eth<C, P> {
+	/// Create new `Eth` with the given reference to the client and the transaction pool.
+	pub fn new(client: Arc<C>, pool: Arc<P>) -> Self {
+		Eth { client, pool }
+	}
+}
+
+impl<C, P, Block> Eth<C, P> where
+	Block: BlockT<Hash = H256>,
+	C: HeaderBackend<Block>,
+{
+	/// The number of the best block.
+	fn best_number(&self) -> u64 {
+		UniqueSaturatedInto::<u64>::unique_saturated_into(self.client.info().best_number)
+	}
+
+	/// The number of the given block, the best block if none.
+	fn number_of(&self, block: Option<BlockNumber>) -> Result<u64> {
+		let best = self.best_number();
+		match block {
+			None | Some(BlockNumber::Tag(BlockTag::Latest)) | Some(BlockNumber::Tag(BlockTag::Pending)) =>
+				Ok(best),
+			Some(BlockNumber::Tag(BlockTag::Earliest)) => Ok(0),
+			Some(BlockNumber::Number(number)) => number.to_number().map_err(|e| Error {
+				code: ErrorCode::InvalidParams,
+				message: e,
+				data: None,
+			}),
+		}
+	}
+
+	/// The hash of the block with the given number.
+	fn block_hash(&self, number: u64) -> Result<H256> {
+		self.client.hash(NumberFor::<Block>::unique_saturated_from(number))
+			.map_err(runtime_error_into_rpc_err)?
+			.ok_or_else(|| Error {
+				code: ErrorCode::InvalidParams,
+				message: format!("Unknown block: {}", number),
+				data: None,
+			})
+	}
+}
+
+impl<C, P, Block> EthApi for Eth<C, P> where
+	Block: BlockT<Hash = H256>,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EvmRuntimeApi<Block>,
+	P: TransactionPool<Block = Block> + 'static,
+{
+	fn chain_id(&self) -> Result<U256> {
+		let at = BlockId::hash(self.client.info().best_hash);
+		let chain_id = self.client.runtime_api().chain_id(&at).map_err(runtime_error_into_rpc_err)?;
+		Ok(chain_id.into())
+	}
+
+	fn call(&self, request: CallRequest, block: Option<BlockNumber>) -> Result<Bytes> {
+		let at = BlockId::hash(self.block_hash(self.number_of(block)?)?);
+
+		let gas_limit = request.gas.unwrap_or_else(|| U256::from(MAX_CALL_GAS));
+		if gas_limit > U256::from(MAX_CALL_GAS) {
+			return Err(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!(
+					"Requested gas limit is greater than maximum allowed: {} > {}",
+					gas_limit, MAX_CALL_GAS,
+				),
+				data: None,
+			});
+		}
+
+		let result = self.client.runtime_api()
+			.call(
+				&at,
+				request.from.unwrap_or_default(),
+				request.to,
+				request.data.map(|data| data.0).unwrap_or_default(),
+				request.value.unwrap_or_default(),
+				gas_limit.low_u32(),
+			)
+			.map_err(runtime_error_into_rpc_err)?;
+
+		match result {
+			EvmCallResult::Succeed(output) => Ok(output.into()),
+			EvmCallResult::Revert(output) => Err(Error {
+				code: ErrorCode::ServerError(EXECUTION_REVERTED),
+				message: "execution reverted".into(),
+				data: Some(format!("0x{}", HexDisplay::from(&output)).into()),
+			}),
+			EvmCallResult::Error => Err(Error {
+				code: ErrorCode::ServerError(RUNTIME_ERROR),
+				message: "execution failed".into(),
+				data: None,
+			}),
+		}
+	}
+
+	fn send_raw_transaction(&self, transaction: Bytes) -> BoxFuture<H256> {
+		let at = BlockId::hash(self.client.info().best_hash);
+		let hash = pallet_evm::transaction_hash(&transaction);
+		let extrinsic = match self.client.runtime_api().transact_extrinsic(&at, transaction.0) {
+			Ok(Some(extrinsic)) => extrinsic,
+			Ok(None) => return Box::new(future01::err(Error {
+				code: ErrorCode::ServerError(INVALID_TRANSACTION),
+				message: "Invalid transaction".into(),
+				data: None,
+			})),
+			Err(e) => return Box::new(future01::err(runtime_error_into_rpc_err(e))),
+		};
+
+		Box::new(self.pool
+			.submit_one(&at, TransactionSource::External, extrinsic)
+			.map_ok(move |_| hash)
+			.map_err(|e| Error {
+				code: ErrorCode::ServerError(POOL_ERROR),
+				message: "Transaction rejected by the pool".into(),
+				data: Some(format!("{:?}", e).into()),
+			})
+			.boxed()
+			.compat())
+	}
+
+	fn logs(&self, mut filter: Filter) -> Result<Vec<RpcLog>> {
+		let from = self.number_of(filter.from_block.take())?;
+		let to = self.number_of(filter.to_block.take())?;
+		if to.saturating_sub(from) >= MAX_LOG_BLOCKS {
+			return Err(Error {
+				code: ErrorCode::InvalidParams,
+				message: format!("Requested block range is greater than maximum allowed: {}", MAX_LOG_BLOCKS),
+				data: None,
+			});
+		}
+
+		let mut logs = Vec::new();
+		for number in from..=to {
+			let hash = self.block_hash(number)?;
+			logs.extend(self.block_logs(number, hash)?
+				.into_iter()
+				.filter(|(log, _)| filter.matches(log))
+				.map(|(_, log)| log));
+		}
+
+		Ok(logs)
+	}
+
+	fn block_number(&self) -> Result<U256> {
+		Ok(self.best_number().into())
+	}
+
+	fn transaction_count(&self, address: H160, block: Option<BlockNumber>) -> Result<U256> {
+		let at = BlockId::hash(self.block_hash(self.number_of(block)?)?);
+		self.client.runtime_api().account_nonce(&at, address).map_err(runtime_error_into_rpc_err)
+	}
+
+	fn transaction_receipt(&self, hash: H256) -> Result<Option<RpcReceipt>> {
+		let best = self.best_number();
+		for number in (best.saturating_sub(MAX_RECEIPT_BLOCKS - 1)..=best).rev() {
+			let block_hash = self.block_hash(number)?;
+			let receipts = self.client.runtime_api()
+				.receipts(&BlockId::hash(block_hash))
+				.map_err(runtime_error_into_rpc_err)?;
+			let position = match receipts.iter().position(|receipt| receipt.transaction_hash == hash) {
+				Some(position) => position,
+				None => continue,
+			};
+
+			let cumulative_gas_used = receipts[..=position].iter()
+				.fold(U256::zero(), |gas, receipt| gas.saturating_add(receipt.used_gas));
+			let receipt = &receipts[position];
+			let logs: Vec<_> = self.block_logs(number, block_hash)?
+				.into_iter()
+				.map(|(_, log)| log)
+				.filter(|log| log.transaction_index == receipt.extrinsic_index.into())
+				.collect();
+
+			return Ok(Some(RpcReceipt {
+				transaction_hash: hash,
+				transaction_index: receipt.extrinsic_index.into(),
+				block_hash,
+				block_number: number.into(),
+				from: receipt.from,
+				to: receipt.to,
+				cumulative_gas_used,
+				gas_used: receipt.used_gas,
+				contract_address: receipt.contract_address,
+				logs_bloom: logs_bloom(&logs).into(),
+				logs,
+				status: if receipt.succeeded { U256::one() } else { U256::zero() },
+			}));
+		}
+
+		Ok(None)
+	}
+}
+
+impl<C, P, Block> Eth<C, P> where
+	Block: BlockT<Hash = H256>,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: EvmRuntimeApi<Block>,
+{
+	/// The logs of the given block, with the logs of the EVM pallet they were made from.
+	fn block_logs(&self, number: u64, hash: H256) -> Result<Vec<(pallet_evm::Log, RpcLog)>> {
+		let block_logs = self.client.runtime_api()
+			.block_logs(&BlockId::hash(hash))
+			.map_err(runtime_error_into_rpc_err)?;
+		Ok(block_logs.into_iter().enumerate().map(|(i, BlockLog { extrinsic_index, transaction_hash, log })| {
+			let rpc_log = RpcLog {
+				address: log.address,
+				topics: log.topics.clone(),
+				data: log.data.clone().into(),
+				block_hash: hash,
+				block_number: number.into(),
+				transaction_hash,
+				transaction_index: extrinsic_index.into(),
+				log_index: i.into(),
+				removed: false,
+			};
+			(log, rpc_log)
+		}).collect())
+	}
+}
+
+/// The bloom filter of the addresses and topics of the given logs.
+fn logs_bloom(logs: &[RpcLog]) -> Vec<u8> {
+	let mut bloom = vec![0u8; BLOOM_SIZE];
+	let mut accrue = |input: &[u8]| {
+		let hash = keccak_256(input);
+		for i in 0..3 {
+			let bit = (((hash[2 * i] as usize) << 8) | hash[2 * i + 1] as usize) % (BLOOM_SIZE * 8);
+			bloom[BLOOM_SIZE - 1 - bit / 8] |= 1 << (bit % 8);
+		}
+	};
+	for log in logs {
+		accrue(log.address.as_bytes());
+		for topic in &log.topics {
+			accrue(topic.as_bytes());
+		}
+	}
+	bloom
+}
+
+/// Converts a runtime trap into an RPC error.
+fn runtime_error_into_rpc_err(err: impl std::fmt::Debug) -> Error {
+	Error {
+		code: ErrorCode::ServerError(RUNTIME_ERROR),
+		message: "Runtime trapped".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn logs_bloom_sets_three_bits_per_address_and_topic() {
+		let log = RpcLog {
+			address: H160::repeat_byte(0x11),
+			topics: vec![H256::repeat_byte(0x22)],
+			data: Bytes(vec![]),
+			block_hash: H256::zero(),
+			block_number: U256::zero(),
+			transaction_hash: None,
+			transaction_index: U256::zero(),
+			log_index: U256::zero(),
+			removed: false,
+		};
+		let bloom = logs_bloom(&[log]);
+		assert_eq!(bloom.len(), BLOOM_SIZE);
+		let bits: u32 = bloom.iter().map(|byte| byte.count_ones()).sum();
+		assert!(bits > 0 && bits <= 6);
+		assert!(logs_bloom(&[]).iter().all(|byte| *byte == 0));
+	}
+
+	#[test]
+	fn filter_is_deserialized_and_matches_logs() {
+		let filter: Filter = serde_json::from_str(r#"{
+			"fromBlock": "0x10",
+			"toBlock": "latest",
+			"address": "0x1111111111111111111111111111111111111111",
+			"topics": [null, ["0x0000000000000000000000000000000000000000000000000000000000000002"]]
+		}"#).unwrap();
+		assert_eq!(filter.from_block, Some(BlockNumber::Number(NumberOrHex::Hex(16.into()))));
+		assert_eq!(filter.to_block, Some(BlockNumber::Tag(BlockTag::Latest)));
+
+		let log = |address: u8, topics: Vec<u64>| pallet_evm::Log {
+			address: H160::repeat_byte(address),
+			topics: topics.into_iter().map(H256::from_low_u64_be).collect(),
+			data: vec![],
+		};
+		assert!(filter.matches(&log(0x11, vec![1, 2])));
+		assert!(filter.matches(&log(0x11, vec![3, 2, 1])));
+		assert!(!filter.matches(&log(0x22, vec![1, 2])));
+		assert!(!filter.matches(&log(0x11, vec![1, 3])));
+		assert!(!filter.matches(&log(0x11, vec![1])));
+		assert!(Filter::default().matches(&log(0x22, vec![])));
+	}
+}
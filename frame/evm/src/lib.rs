@@ -20,27 +20,40 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 mod backend;
+mod transaction;
+#[cfg(test)]
+mod tests;
 
 pub use crate::backend::{Account, Log, Vicinity, Backend};
+pub use crate::transaction::{Transaction, TransactionAction, transaction_hash};
+pub use evm::ExitReason;
 
-use sp_std::{vec::Vec, marker::PhantomData};
+use sp_std::{vec, vec::Vec, marker::PhantomData};
+use codec::{Encode, Decode};
 use frame_support::{ensure, decl_module, decl_storage, decl_event, decl_error};
 use frame_support::weights::{Weight, DispatchClass, FunctionOf};
-use frame_support::traits::{Currency, WithdrawReason, ExistenceRequirement};
-use frame_system::{self as system, ensure_signed};
+use frame_support::traits::{Currency, Get, WithdrawReason, ExistenceRequirement};
+use frame_system::{self as system, ensure_none, ensure_signed};
 use sp_runtime::ModuleId;
 use frame_support::weights::SimpleDispatchInfo;
 use sp_core::{U256, H256, H160, Hasher};
 use sp_runtime::{
 	DispatchResult, traits::{UniqueSaturatedInto, AccountIdConversion, SaturatedConversion},
+	transaction_validity::{
+		InvalidTransaction, TransactionLongevity, TransactionSource, TransactionValidity,
+		TransactionValidityError, ValidTransaction,
+	},
 };
 use sha3::{Digest, Keccak256};
-use evm::{ExitReason, ExitSucceed, ExitError, Config};
+use evm::{Capture, Context, ExitSucceed, ExitError, Config, Handler, Transfer};
 use evm::executor::StackExecutor;
 use evm::backend::ApplyBackend;
 
 const MODULE_ID: ModuleId = ModuleId(*b"py/ethvm");
 
+/// The weight of `transact`, in addition to the weight of the gas limit of the transaction.
+const TRANSACT_BASE_WEIGHT: Weight = 100_000;
+
 /// Type alias for currency balance.
 pub type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
 
@@ -54,6 +67,13 @@ impl FeeCalculator for () {
 	fn min_gas_price() -> U256 { U256::zero() }
 }
 
+/// A fixed minimal gas price.
+pub struct FixedGasPrice<P>(PhantomData<P>);
+
+impl<P: Get<u64>> FeeCalculator for FixedGasPrice<P> {
+	fn min_gas_price() -> U256 { P::get().into() }
+}
+
 /// Trait for converting account ids of `balances` module into
 /// `H160` for EVM module.
 ///
@@ -130,6 +150,12 @@ pub trait Trait: frame_system::Trait + pallet_timestamp::Trait {
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 	/// Precompiles associated with this EVM engine.
 	type Precompiles: Precompiles;
+	/// The chain id of the Ethereum transactions of the chain (EIP-155).
+	type ChainId: Get<u64>;
+	/// The maximum gas of the EVM operations of a block, by their gas limits.
+	type BlockGasLimit: Get<u64>;
+	/// The weight of a unit of gas of the Ethereum transactions applied with `transact`.
+	type WeightPerGas: Get<Weight>;
 
 	/// EVM config used in the module.
 	fn config() -> &'static Config {
@@ -142,9 +168,33 @@ decl_storage! {
 		Accounts get(fn accounts) config(): map hasher(blake2_128_concat) H160 => Account;
 		AccountCodes: map hasher(blake2_128_concat) H160 => Vec<u8>;
 		AccountStorages: double_map hasher(blake2_128_concat) H160, hasher(blake2_128_concat) H256 => H256;
+		/// The gas of the EVM operations of the current block, by their gas limits.
+		BlockGasUsed get(fn block_gas_used): u64;
+		/// The receipts of the Ethereum transactions applied in the current block.
+		Receipts get(fn receipts): Vec<Receipt>;
 	}
 }
 
+/// The receipt of an Ethereum transaction applied with `transact`.
+#[derive(Clone, Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Receipt {
+	/// The index of the extrinsic in the block.
+	pub extrinsic_index: u32,
+	/// The hash of the transaction.
+	pub transaction_hash: H256,
+	/// The sender of the transaction.
+	pub from: H160,
+	/// The called address, `None` for a contract creation.
+	pub to: Option<H160>,
+	/// The address of the created contract, if any.
+	pub contract_address: Option<H160>,
+	/// Whether the transaction succeeded.
+	pub succeeded: bool,
+	/// The gas used by the transaction.
+	pub used_gas: U256,
+}
+
 decl_event! {
 	/// EVM events
 	pub enum Event<T> where
@@ -158,6 +208,9 @@ decl_event! {
 		BalanceDeposit(AccountId, H160, U256),
 		/// A withdrawal has been made from a given address.
 		BalanceWithdraw(AccountId, H160, U256),
+		/// A signed Ethereum transaction, by hash, has been applied from a given address, whether it
+		/// succeeded or not.
+		Transacted(H160, H256),
 	}
 }
 
@@ -181,6 +234,14 @@ decl_error! {
 		ExitReasonFatal,
 		/// Nonce is invalid
 		InvalidNonce,
+		/// Ethereum transaction can't be decoded, or its gas limit is too high
+		InvalidTransaction,
+		/// Ethereum transaction isn't signed for this chain
+		InvalidChainId,
+		/// Ethereum transaction signature is invalid
+		InvalidSignature,
+		/// The gas limit exceeds the gas left in the block
+		BlockGasLimitExceeded,
 	}
 }
 
@@ -190,6 +251,12 @@ decl_module! {
 
 		fn deposit_event() = default;
 
+		fn on_initialize(_n: T::BlockNumber) -> Weight {
+			BlockGasUsed::kill();
+			Receipts::kill();
+			0
+		}
+
 		/// Deposit balance from currency/balances module into EVM.
 		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
 		fn deposit_balance(origin, value: BalanceOf<T>) {
@@ -249,7 +316,7 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			Self::execute_evm(
+			let (result, _) = Self::execute_evm(
 				source,
 				value,
 				gas_limit,
@@ -262,7 +329,8 @@ decl_module! {
 					input,
 					gas_limit as usize,
 				)),
-			).map_err(Into::into)
+			)?;
+			result.map_err(Into::into)
 		}
 
 		/// Issue an EVM create operation. This is similar to a contract creation transaction in
@@ -279,7 +347,7 @@ decl_module! {
 			let sender = ensure_signed(origin)?;
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
-			let create_address = Self::execute_evm(
+			let (create_address, _) = Self::execute_evm(
 				source,
 				value,
 				gas_limit,
@@ -296,6 +364,7 @@ decl_module! {
 					))
 				},
 			)?;
+			let create_address = create_address?;
 
 			Module::<T>::deposit_event(Event::<T>::Created(create_address));
 			Ok(())
@@ -316,7 +385,7 @@ decl_module! {
 			let source = T::ConvertAccountId::convert_account_id(&sender);
 
 			let code_hash = H256::from_slice(Keccak256::digest(&init).as_slice());
-			let create_address = Self::execute_evm(
+			let (create_address, _) = Self::execute_evm(
 				source,
 				value,
 				gas_limit,
@@ -334,10 +403,126 @@ decl_module! {
 					))
 				},
 			)?;
+			let create_address = create_address?;
 
 			Module::<T>::deposit_event(Event::<T>::Created(create_address));
 			Ok(())
 		}
+
+		/// Apply an RLP encoded signed Ethereum transaction, e.g. from `eth_sendRawTransaction`,
+		/// from the address of its signer.
+		///
+		/// The dispatch origin for this call must be _None_, the transaction is validated by its
+		/// signature. The call is weighed by the gas limit of the transaction, and its receipt is
+		/// kept until the end of the block, whether it succeeds or not.
+		#[weight = FunctionOf(
+			|(transaction,): (&Vec<u8>,)| Module::<T>::transact_weight(transaction),
+			DispatchClass::Normal,
+			true,
+		)]
+		fn transact(origin, transaction: Vec<u8>) -> DispatchResult {
+			ensure_none(origin)?;
+			let (source, decoded) = Self::check_transaction(&transaction)?;
+			let gas_limit = decoded.gas_limit.low_u32();
+			let transaction_hash = transaction_hash(&transaction);
+			let Transaction { nonce, gas_price, value, input, action, .. } = decoded;
+
+			let (to, (result, used_gas)) = match action {
+				TransactionAction::Call(target) => (Some(target), Self::execute_evm(
+					source,
+					value,
+					gas_limit,
+					gas_price,
+					Some(nonce),
+					|executor| (None, executor.transact_call(
+						source,
+						target,
+						value,
+						input,
+						gas_limit as usize,
+					)),
+				)?),
+				TransactionAction::Create => (None, Self::execute_evm(
+					source,
+					value,
+					gas_limit,
+					gas_price,
+					Some(nonce),
+					|executor| {
+						(Some(executor.create_address(
+							evm::CreateScheme::Legacy { caller: source },
+						)), executor.transact_create(
+							source,
+							value,
+							input,
+							gas_limit as usize,
+						))
+					},
+				)?),
+			};
+
+			let contract_address = result.as_ref().ok().and_then(|address| *address);
+			if let Some(address) = contract_address {
+				Module::<T>::deposit_event(Event::<T>::Created(address));
+			}
+			Receipts::mutate(|receipts| receipts.push(Receipt {
+				extrinsic_index: <frame_system::Module<T>>::extrinsic_index().unwrap_or_default(),
+				transaction_hash,
+				from: source,
+				to,
+				contract_address,
+				succeeded: result.is_ok(),
+				used_gas,
+			}));
+			Module::<T>::deposit_event(Event::<T>::Transacted(source, transaction_hash));
+
+			result.map(|_| ()).map_err(Into::into)
+		}
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(
+		_source: TransactionSource,
+		call: &Self::Call,
+	) -> TransactionValidity {
+		if let Call::transact(transaction) = call {
+			let (source, transaction, account) = Self::validate_transaction(transaction)?;
+
+			// transactions of the same sender are applied in the order of their nonces
+			let requires = if transaction.nonce > account.nonce {
+				vec![(source, transaction.nonce - 1).encode()]
+			} else {
+				vec![]
+			};
+			Ok(ValidTransaction {
+				priority: transaction.gas_price.low_u64(),
+				requires,
+				provides: vec![(source, transaction.nonce).encode()],
+				longevity: TransactionLongevity::max_value(),
+				propagate: true,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
+	}
+
+	fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+		if let Call::transact(transaction) = call {
+			let (_, transaction, account) = Self::validate_transaction(transaction)?;
+			if transaction.nonce > account.nonce {
+				return Err(InvalidTransaction::Future.into());
+			}
+			// the gas limit is at most the block gas limit, checked by `validate_transaction`
+			if BlockGasUsed::get().saturating_add(transaction.gas_limit.low_u64()) > T::BlockGasLimit::get() {
+				return Err(InvalidTransaction::ExhaustsResources.into());
+			}
+			Ok(())
+		} else {
+			Err(InvalidTransaction::Call.into())
+		}
 	}
 }
 
@@ -374,7 +559,90 @@ impl<T: Trait> Module<T> {
 		AccountStorages::remove_prefix(address);
 	}
 
-	/// Execute an EVM operation.
+	/// Decode an RLP encoded signed Ethereum transaction of this chain, and recover its signer.
+	pub fn check_transaction(transaction: &[u8]) -> Result<(H160, Transaction), Error<T>> {
+		let transaction = Transaction::decode(transaction).map_err(|_| Error::<T>::InvalidTransaction)?;
+		ensure!(transaction.gas_limit <= U256::from(u32::max_value()), Error::<T>::InvalidTransaction);
+		ensure!(transaction.chain_id() == Some(T::ChainId::get()), Error::<T>::InvalidChainId);
+		let source = transaction.sender().ok_or(Error::<T>::InvalidSignature)?;
+		Ok((source, transaction))
+	}
+
+	/// The weight of applying an RLP encoded Ethereum transaction with `transact`, by its gas
+	/// limit.
+	pub fn transact_weight(transaction: &[u8]) -> Weight {
+		let gas_limit = Transaction::decode(transaction)
+			.map(|transaction| transaction.gas_limit.saturated_into::<Weight>())
+			.unwrap_or(0);
+		TRANSACT_BASE_WEIGHT.saturating_add(gas_limit.saturating_mul(T::WeightPerGas::get()))
+	}
+
+	/// Check that an RLP encoded Ethereum transaction can be applied with `transact`, returning
+	/// its signer, the transaction and the account of the signer.
+	///
+	/// The transaction must pay a nonzero gas price, at least the minimal gas price, and its gas
+	/// limit must not exceed the block gas limit.
+	fn validate_transaction(
+		transaction: &[u8],
+	) -> Result<(H160, Transaction, Account), TransactionValidityError> {
+		let (source, transaction) = Self::check_transaction(transaction)
+			.map_err(|_| InvalidTransaction::BadProof)?;
+		let min_gas_price = T::FeeCalculator::min_gas_price().max(U256::one());
+		if transaction.gas_price < min_gas_price {
+			return Err(InvalidTransaction::Payment.into());
+		}
+		if transaction.gas_limit > U256::from(T::BlockGasLimit::get()) {
+			return Err(InvalidTransaction::ExhaustsResources.into());
+		}
+
+		let account = Accounts::get(&source);
+		if transaction.nonce < account.nonce {
+			return Err(InvalidTransaction::Stale.into());
+		}
+		let total_payment = transaction.gas_price.checked_mul(transaction.gas_limit)
+			.and_then(|fee| fee.checked_add(transaction.value));
+		match total_payment {
+			Some(total_payment) if total_payment <= account.balance => {},
+			_ => return Err(InvalidTransaction::Payment.into()),
+		}
+
+		Ok((source, transaction, account))
+	}
+
+	/// Execute an EVM call without applying its changes, e.g. for `eth_call`, returning the exit
+	/// reason and the output of the call.
+	pub fn call_readonly(
+		source: H160,
+		target: H160,
+		input: Vec<u8>,
+		value: U256,
+		gas_limit: u32,
+	) -> (ExitReason, Vec<u8>) {
+		let vicinity = Vicinity {
+			gas_price: U256::zero(),
+			origin: source,
+		};
+
+		let backend = Backend::<T>::new(&vicinity);
+		let mut executor = StackExecutor::new_with_precompile(
+			&backend,
+			gas_limit as usize,
+			T::config(),
+			T::Precompiles::execute,
+		);
+
+		let transfer = Transfer { source, target, value };
+		let context = Context { caller: source, address: target, apparent_value: value };
+		match executor.call(target, Some(transfer), input, Some(gas_limit as usize), false, context) {
+			Capture::Exit(result) => result,
+			Capture::Trap(infallible) => match infallible {},
+		}
+	}
+
+	/// Execute an EVM operation, returning its result and the gas it used.
+	///
+	/// Fails without executing anything if the operation can't be paid for, or if its gas limit
+	/// exceeds the gas left in the block.
 	fn execute_evm<F, R>(
 		source: H160,
 		value: U256,
@@ -382,7 +650,7 @@ impl<T: Trait> Module<T> {
 		gas_price: U256,
 		nonce: Option<U256>,
 		f: F,
-	) -> Result<R, Error<T>> where
+	) -> Result<(Result<R, Error<T>>, U256), Error<T>> where
 		F: FnOnce(&mut StackExecutor<Backend<T>>) -> (R, ExitReason),
 	{
 		ensure!(gas_price >= T::FeeCalculator::min_gas_price(), Error::<T>::GasPriceTooLow);
@@ -411,6 +679,10 @@ impl<T: Trait> Module<T> {
 			ensure!(source_account.nonce == nonce, Error::<T>::InvalidNonce);
 		}
 
+		let block_gas_used = BlockGasUsed::get().saturating_add(gas_limit.into());
+		ensure!(block_gas_used <= T::BlockGasLimit::get(), Error::<T>::BlockGasLimitExceeded);
+		BlockGasUsed::put(block_gas_used);
+
 		let (retv, reason) = f(&mut executor);
 
 		let ret = match reason {
@@ -420,12 +692,13 @@ impl<T: Trait> Module<T> {
 			ExitReason::Fatal(_) => Err(Error::<T>::ExitReasonFatal),
 		};
 
+		let used_gas = U256::from(executor.used_gas());
 		let actual_fee = executor.fee(gas_price);
 		executor.deposit(source, total_fee.saturating_sub(actual_fee));
 
 		let (values, logs) = executor.deconstruct();
 		backend.apply(values, logs, true);
 
-		ret
+		Ok((ret, used_gas))
 	}
 }
@@ -0,0 +1,262 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tests of the Ethereum transactions applied with `transact`.
+
+use super::*;
+
+use std::cell::RefCell;
+use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types};
+use frame_support::traits::OnInitialize;
+use frame_support::unsigned::ValidateUnsigned;
+use frame_support::weights::GetDispatchInfo;
+use sp_core::{ecdsa, Pair};
+use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Test;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Call = ();
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = ();
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type Version = ();
+	type ModuleToIndex = ();
+	type Doughnut = ();
+	type DelegatedDispatchVerifier = ();
+}
+parameter_types! {
+	pub const ExistentialDeposit: u64 = 1;
+	pub const CreationFee: u64 = 0;
+}
+impl pallet_balances::Trait for Test {
+	type Balance = u64;
+	type DustRemoval = ();
+	type Event = ();
+	type ExistentialDeposit = ExistentialDeposit;
+	type OnReapAccount = frame_system::Module<Test>;
+	type OnNewAccount = ();
+	type TransferPayment = ();
+	type CreationFee = CreationFee;
+}
+parameter_types! {
+	pub const MinimumPeriod: u64 = 1;
+}
+impl pallet_timestamp::Trait for Test {
+	type Moment = u64;
+	type OnTimestampSet = ();
+	type MinimumPeriod = MinimumPeriod;
+}
+
+thread_local! {
+	static MIN_GAS_PRICE: RefCell<u64> = RefCell::new(0);
+}
+
+pub struct TestFeeCalculator;
+impl FeeCalculator for TestFeeCalculator {
+	fn min_gas_price() -> U256 {
+		MIN_GAS_PRICE.with(|price| *price.borrow()).into()
+	}
+}
+
+pub struct TestConvertAccountId;
+impl ConvertAccountId<u64> for TestConvertAccountId {
+	fn convert_account_id(account_id: &u64) -> H160 {
+		H160::from_low_u64_be(*account_id)
+	}
+}
+
+parameter_types! {
+	pub const ChainId: u64 = 42;
+	pub const BlockGasLimit: u64 = 50_000;
+	pub const WeightPerGas: Weight = 2;
+}
+impl Trait for Test {
+	type FeeCalculator = TestFeeCalculator;
+	type ConvertAccountId = TestConvertAccountId;
+	type Currency = pallet_balances::Module<Test>;
+	type Event = ();
+	type Precompiles = ();
+	type ChainId = ChainId;
+	type BlockGasLimit = BlockGasLimit;
+	type WeightPerGas = WeightPerGas;
+}
+type EVM = Module<Test>;
+
+const TARGET: H160 = H160([0x35; 20]);
+
+fn alice() -> ecdsa::Pair {
+	ecdsa::Pair::from_seed(&[1u8; 32])
+}
+
+fn address(pair: &ecdsa::Pair) -> H160 {
+	let public = pair.public().to_uncompressed().unwrap();
+	H160::from_slice(&Keccak256::digest(&public)[12..])
+}
+
+/// An RLP encoded transaction transferring 1 to `TARGET`, signed by `pair` for the chain.
+fn transfer(pair: &ecdsa::Pair, nonce: u64, gas_price: u64, gas_limit: u64) -> Vec<u8> {
+	let mut transaction = Transaction {
+		nonce: nonce.into(),
+		gas_price: gas_price.into(),
+		gas_limit: gas_limit.into(),
+		action: TransactionAction::Call(TARGET),
+		value: 1.into(),
+		input: vec![],
+		v: ChainId::get() * 2 + 35,
+		r: H256::zero(),
+		s: H256::zero(),
+	};
+	let signature = pair.sign_prehashed(transaction.signing_hash().as_fixed_bytes());
+	let signature: &[u8] = signature.as_ref();
+	transaction.r = H256::from_slice(&signature[..32]);
+	transaction.s = H256::from_slice(&signature[32..64]);
+	transaction.v += signature[64] as u64;
+	transaction.encode()
+}
+
+fn new_test_ext() -> sp_io::TestExternalities {
+	MIN_GAS_PRICE.with(|price| *price.borrow_mut() = 1);
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	GenesisConfig {
+		accounts: vec![(address(&alice()), Account { nonce: 0.into(), balance: 1_000_000.into() })],
+	}.assimilate_storage::<Test>(&mut t).unwrap();
+	t.into()
+}
+
+fn validate(transaction: Vec<u8>) -> TransactionValidity {
+	EVM::validate_unsigned(TransactionSource::External, &Call::transact(transaction))
+}
+
+#[test]
+fn transact_is_weighed_by_its_gas_limit() {
+	new_test_ext().execute_with(|| {
+		let info = Call::<Test>::transact(transfer(&alice(), 0, 1, 21_000)).get_dispatch_info();
+		assert_eq!(info.weight, TRANSACT_BASE_WEIGHT + 21_000 * 2);
+		assert_eq!(Call::<Test>::transact(vec![0xc0]).get_dispatch_info().weight, TRANSACT_BASE_WEIGHT);
+	});
+}
+
+#[test]
+fn transactions_below_the_minimum_gas_price_are_invalid() {
+	new_test_ext().execute_with(|| {
+		MIN_GAS_PRICE.with(|price| *price.borrow_mut() = 0);
+		assert_eq!(validate(transfer(&alice(), 0, 0, 21_000)), InvalidTransaction::Payment.into());
+		assert_ok!(validate(transfer(&alice(), 0, 1, 21_000)));
+
+		MIN_GAS_PRICE.with(|price| *price.borrow_mut() = 10);
+		assert_eq!(validate(transfer(&alice(), 0, 9, 21_000)), InvalidTransaction::Payment.into());
+		assert_ok!(validate(transfer(&alice(), 0, 10, 21_000)));
+	});
+}
+
+#[test]
+fn transactions_are_validated_by_signature_nonce_balance_and_gas_limit() {
+	new_test_ext().execute_with(|| {
+		let source = address(&alice());
+
+		let valid = validate(transfer(&alice(), 0, 1, 21_000)).unwrap();
+		assert_eq!(valid.provides, vec![(source, U256::zero()).encode()]);
+		assert!(valid.requires.is_empty());
+		let future = validate(transfer(&alice(), 2, 1, 21_000)).unwrap();
+		assert_eq!(future.requires, vec![(source, U256::one()).encode()]);
+
+		// the example transaction of EIP-155, signed for the chain 1
+		let other_chain = hex_literal::hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+		assert_eq!(validate(other_chain.to_vec()), InvalidTransaction::BadProof.into());
+		assert_eq!(validate(vec![0xc0]), InvalidTransaction::BadProof.into());
+
+		assert_eq!(validate(transfer(&alice(), 0, 100, 21_000)), InvalidTransaction::Payment.into());
+		assert_eq!(validate(transfer(&alice(), 0, 1, 50_001)), InvalidTransaction::ExhaustsResources.into());
+
+		Accounts::mutate(&source, |account| account.nonce = 1.into());
+		assert_eq!(validate(transfer(&alice(), 0, 1, 21_000)), InvalidTransaction::Stale.into());
+	});
+}
+
+#[test]
+fn transact_applies_the_transaction_and_keeps_its_receipt() {
+	new_test_ext().execute_with(|| {
+		let source = address(&alice());
+		let transaction = transfer(&alice(), 0, 1, 21_000);
+		assert_noop!(
+			EVM::transact(Origin::signed(1), transaction.clone()),
+			sp_runtime::traits::BadOrigin,
+		);
+
+		assert_ok!(EVM::pre_dispatch(&Call::transact(transaction.clone())));
+		assert_ok!(EVM::transact(Origin::NONE, transaction.clone()));
+
+		assert_eq!(Accounts::get(&TARGET).balance, 1.into());
+		assert_eq!(Accounts::get(&source), Account { nonce: 1.into(), balance: (1_000_000 - 21_000 - 1).into() });
+		assert_eq!(EVM::block_gas_used(), 21_000);
+		assert_eq!(EVM::receipts(), vec![Receipt {
+			extrinsic_index: 0,
+			transaction_hash: transaction_hash(&transaction),
+			from: source,
+			to: Some(TARGET),
+			contract_address: None,
+			succeeded: true,
+			used_gas: 21_000.into(),
+		}]);
+
+		EVM::on_initialize(2);
+		assert_eq!(EVM::block_gas_used(), 0);
+		assert!(EVM::receipts().is_empty());
+	});
+}
+
+#[test]
+fn transactions_exceeding_the_gas_left_in_the_block_are_not_applied() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(EVM::transact(Origin::NONE, transfer(&alice(), 0, 1, 30_000)));
+
+		let second = transfer(&alice(), 1, 1, 30_000);
+		assert_ok!(validate(second.clone()));
+		assert_eq!(
+			EVM::pre_dispatch(&Call::transact(second.clone())),
+			Err(InvalidTransaction::ExhaustsResources.into()),
+		);
+		assert_noop!(EVM::transact(Origin::NONE, second.clone()), Error::<Test>::BlockGasLimitExceeded);
+		assert_eq!(
+			EVM::pre_dispatch(&Call::transact(transfer(&alice(), 2, 1, 21_000))),
+			Err(InvalidTransaction::Future.into()),
+		);
+
+		EVM::on_initialize(2);
+		assert_ok!(EVM::pre_dispatch(&Call::transact(second)));
+	});
+}
@@ -0,0 +1,197 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signed Ethereum transactions, as submitted to `eth_sendRawTransaction`.
+
+use sp_std::vec::Vec;
+use sp_core::{H160, H256, U256};
+use sha3::{Digest, Keccak256};
+use rlp::{DecoderError, Rlp, RlpStream};
+
+/// The action of a transaction.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum TransactionAction {
+	/// Call the given address.
+	Call(H160),
+	/// Create a contract.
+	Create,
+}
+
+/// A signed legacy Ethereum transaction, replay protected by the chain id (EIP-155) or not.
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Transaction {
+	/// The nonce of the sender.
+	pub nonce: U256,
+	/// The gas price.
+	pub gas_price: U256,
+	/// The gas limit.
+	pub gas_limit: U256,
+	/// The action.
+	pub action: TransactionAction,
+	/// The value transferred.
+	pub value: U256,
+	/// The input of the call, or the init code of the contract.
+	pub input: Vec<u8>,
+	/// The `v` of the signature, encoding the recovery id and the chain id.
+	pub v: u64,
+	/// The `r` of the signature.
+	pub r: H256,
+	/// The `s` of the signature.
+	pub s: H256,
+}
+
+impl Transaction {
+	/// Decode an RLP encoded transaction.
+	pub fn decode(bytes: &[u8]) -> Result<Self, DecoderError> {
+		let rlp = Rlp::new(bytes);
+		if rlp.item_count()? != 9 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let to = rlp.at(3)?;
+		Ok(Transaction {
+			nonce: rlp.val_at(0)?,
+			gas_price: rlp.val_at(1)?,
+			gas_limit: rlp.val_at(2)?,
+			action: if to.is_empty() { TransactionAction::Create } else { TransactionAction::Call(to.as_val()?) },
+			value: rlp.val_at(4)?,
+			input: rlp.val_at(5)?,
+			v: rlp.val_at(6)?,
+			r: h256(rlp.val_at(7)?),
+			s: h256(rlp.val_at(8)?),
+		})
+	}
+
+	/// The chain id the transaction is signed for, `None` if it isn't replay protected.
+	pub fn chain_id(&self) -> Option<u64> {
+		if self.v >= 35 {
+			Some((self.v - 35) / 2)
+		} else {
+			None
+		}
+	}
+
+	/// RLP encode the transaction.
+	pub fn encode(&self) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(9);
+		self.append_unsigned(&mut stream);
+		stream.append(&self.v);
+		stream.append(&U256::from(self.r.as_bytes()));
+		stream.append(&U256::from(self.s.as_bytes()));
+		stream.out()
+	}
+
+	/// The hash signed by the sender.
+	pub fn signing_hash(&self) -> H256 {
+		let chain_id = self.chain_id();
+		let mut stream = RlpStream::new_list(if chain_id.is_some() { 9 } else { 6 });
+		self.append_unsigned(&mut stream);
+		if let Some(chain_id) = chain_id {
+			stream.append(&chain_id);
+			stream.append(&0u8);
+			stream.append(&0u8);
+		}
+		keccak_256(&stream.out())
+	}
+
+	fn append_unsigned(&self, stream: &mut RlpStream) {
+		stream.append(&self.nonce);
+		stream.append(&self.gas_price);
+		stream.append(&self.gas_limit);
+		match self.action {
+			TransactionAction::Call(ref to) => stream.append(to),
+			TransactionAction::Create => stream.append_empty_data(),
+		};
+		stream.append(&self.value);
+		stream.append(&self.input);
+	}
+
+	/// Recover the address of the sender from the signature.
+	pub fn sender(&self) -> Option<H160> {
+		let recovery_id = match self.chain_id() {
+			Some(chain_id) => self.v - 35 - chain_id * 2,
+			None => self.v.checked_sub(27)?,
+		};
+		if recovery_id > 1 {
+			return None;
+		}
+
+		let mut signature = [0u8; 65];
+		signature[..32].copy_from_slice(self.r.as_bytes());
+		signature[32..64].copy_from_slice(self.s.as_bytes());
+		signature[64] = recovery_id as u8;
+		let public = sp_io::crypto::secp256k1_ecdsa_recover(&signature, self.signing_hash().as_fixed_bytes())
+			.ok()?;
+		Some(H160::from_slice(&keccak_256(&public)[12..]))
+	}
+}
+
+/// The hash of an RLP encoded transaction.
+pub fn transaction_hash(bytes: &[u8]) -> H256 {
+	keccak_256(bytes)
+}
+
+fn keccak_256(data: &[u8]) -> H256 {
+	H256::from_slice(Keccak256::digest(data).as_slice())
+}
+
+fn h256(value: U256) -> H256 {
+	let mut bytes = [0u8; 32];
+	value.to_big_endian(&mut bytes);
+	H256::from(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hex_literal::hex;
+
+	#[test]
+	fn eip155_transaction_is_decoded_and_its_sender_recovered() {
+		// the example transaction of EIP-155, signed with the private key `0x4646...46`.
+		let bytes = hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+		let transaction = Transaction::decode(&bytes).unwrap();
+
+		assert_eq!(transaction.nonce, U256::from(9));
+		assert_eq!(transaction.gas_price, U256::from(20_000_000_000u64));
+		assert_eq!(transaction.gas_limit, U256::from(21_000));
+		assert_eq!(transaction.action, TransactionAction::Call(H160::repeat_byte(0x35)));
+		assert_eq!(transaction.value, U256::from(1_000_000_000_000_000_000u64));
+		assert_eq!(transaction.chain_id(), Some(1));
+		assert_eq!(
+			transaction.signing_hash(),
+			H256::from(hex!("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")),
+		);
+		assert_eq!(
+			transaction.sender(),
+			Some(H160::from(hex!("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f"))),
+		);
+	}
+
+	#[test]
+	fn transaction_is_encoded_as_decoded() {
+		let bytes = hex!("f86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83");
+		assert_eq!(Transaction::decode(&bytes).unwrap().encode(), bytes.to_vec());
+	}
+
+	#[test]
+	fn malformed_transactions_are_rejected() {
+		assert!(Transaction::decode(&hex!("c0")).is_err());
+		assert!(Transaction::decode(&[]).is_err());
+	}
+}
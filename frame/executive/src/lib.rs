@@ -78,8 +78,8 @@
 
 use sp_std::{prelude::*, marker::PhantomData};
 use frame_support::{
-	storage::StorageValue, weights::{GetDispatchInfo, DispatchInfo},
-	traits::{OnInitialize, OnFinalize, OnRuntimeUpgrade, OffchainWorker},
+	storage::StorageValue, weights::{GetDispatchInfo, DispatchInfo, Weight},
+	traits::{OnInitialize, OnFinalize, OnRuntimeUpgrade, OffchainWorker, OffchainWorkers},
 };
 use sp_runtime::{
 	generic::Digest, ApplyExtrinsicResult,
@@ -153,6 +153,7 @@ where
 {
 	/// Start the execution of a particular block.
 	pub fn initialize_block(header: &System::Header) {
+		frame_support::enter_span!("initialize_block");
 		let digests = Self::extract_pre_digest(&header);
 		Self::initialize_block_impl(
 			header.number(),
@@ -198,6 +199,16 @@ where
 		frame_system::Module::<System>::note_finished_initialize();
 	}
 
+	/// Execute the `on_runtime_upgrade` hooks of all modules like the first block after a runtime
+	/// upgrade does, even if the runtime version didn't change, and return the weight they
+	/// consumed. This is meant to dry run the migrations of a new runtime against an existing
+	/// state, off chain.
+	pub fn try_runtime_upgrade() -> Weight {
+		let _ = Self::runtime_upgraded();
+		let weight = <frame_system::Module::<System> as OnRuntimeUpgrade>::on_runtime_upgrade();
+		weight.saturating_add(<AllModules as OnRuntimeUpgrade>::on_runtime_upgrade())
+	}
+
 	/// Returns if the runtime was upgraded since the last time this function was called.
 	fn runtime_upgraded() -> bool {
 		let last = frame_system::LastRuntimeUpgrade::get();
@@ -232,15 +243,23 @@ where
 
 	/// Actually execute all transitions for `block`.
 	pub fn execute_block(block: Block) {
+		frame_support::enter_span!("execute_block");
 		Self::initialize_block(block.header());
 
 		// any initial checks
 		Self::initial_checks(&block);
 
+		// the signatures checked while executing the extrinsics are verified together at the end
+		let signature_batching = sp_runtime::SignatureBatching::start();
+
 		// execute extrinsics
 		let (header, extrinsics) = block.deconstruct();
 		Self::execute_extrinsics_with_book_keeping(extrinsics, *header.number());
 
+		if !signature_batching.verify() {
+			panic!("Signature verification failed.");
+		}
+
 		// any final checks
 		Self::final_checks(&header);
 	}
@@ -250,6 +269,7 @@ where
 		extrinsics.into_iter().for_each(Self::apply_extrinsic_no_note);
 
 		// post-extrinsics book-keeping
+		frame_support::enter_span!("finalize_block");
 		<frame_system::Module<System>>::note_finished_extrinsics();
 		<frame_system::Module<System> as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
 		<AllModules as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
@@ -258,6 +278,7 @@ where
 	/// Finalize the block - it is up the caller to ensure that all header fields are valid
 	/// except state-root.
 	pub fn finalize_block() -> System::Header {
+		frame_support::enter_span!("finalize_block");
 		<frame_system::Module<System>>::note_finished_extrinsics();
 		let block_number = <frame_system::Module<System>>::block_number();
 		<frame_system::Module<System> as OnFinalize<System::BlockNumber>>::on_finalize(block_number);
@@ -293,6 +314,10 @@ where
 		encoded_len: usize,
 		to_note: Option<Vec<u8>>,
 	) -> ApplyExtrinsicResult {
+		frame_support::enter_span!(
+			"apply_extrinsic",
+			extrinsic_index = <frame_system::Module<System>>::extrinsic_index()
+		);
 		// Verify that the signature is good.
 		let xt = uxt.check(&Default::default())?;
 
@@ -353,6 +378,30 @@ where
 
 	/// Start an offchain worker and generate extrinsics.
 	pub fn offchain_worker(header: &System::Header) {
+		Self::run_offchain_worker(
+			header,
+			<AllModules as OffchainWorker<System::BlockNumber>>::offchain_worker,
+		)
+	}
+
+	/// The names of the modules with an offchain worker, to let the node schedule the workers
+	/// of the modules separately.
+	pub fn offchain_workers<W: OffchainWorkers<System::BlockNumber>>() -> Vec<Vec<u8>> {
+		W::offchain_workers().into_iter().map(|worker| worker.to_vec()).collect()
+	}
+
+	/// Start the offchain worker of the module with the given name only, one of
+	/// `offchain_workers`. Does nothing for other names.
+	pub fn offchain_worker_of<W: OffchainWorkers<System::BlockNumber>>(
+		header: &System::Header,
+		worker: &[u8],
+	) {
+		if W::offchain_workers().iter().any(|name| *name == worker) {
+			Self::run_offchain_worker(header, |n| { W::offchain_worker_of(worker, n); })
+		}
+	}
+
+	fn run_offchain_worker(header: &System::Header, worker: impl FnOnce(System::BlockNumber)) {
 		// We need to keep events available for offchain workers,
 		// hence we initialize the block manually.
 		// OffchainWorker RuntimeApi should skip initialization.
@@ -370,7 +419,7 @@ where
 		// also when running WASM.
 		frame_support::debug::RuntimeLogger::init();
 
-		<AllModules as OffchainWorker<System::BlockNumber>>::offchain_worker(
+		worker(
 			// to maintain backward compatibility we call module offchain workers
 			// with parent block number.
 			header.number().saturating_sub(1.into())
@@ -431,6 +480,10 @@ mod tests {
 				fn on_finalize() {
 					println!("on_finalize(?)");
 				}
+
+				fn on_runtime_upgrade() -> Weight {
+					200
+				}
 			}
 		}
 	}
@@ -508,9 +561,13 @@ mod tests {
 	}
 	impl DoughnutRuntime for Runtime {
 		type AccountId = <Self as system::Trait>::AccountId;
+		type PublicKeyAccount = ConvertInto;
 		type Call = <Self as system::Trait>::Call;
 		type Doughnut = <Self as system::Trait>::Doughnut;
 		type TimestampProvider = TimestampProvider;
+		type VerificationWeight = ();
+		type VerificationWeightPerByte = ();
+		type RevocationRegistry = ();
 	}
 	parameter_types! {
 		pub const ExistentialDeposit: u64 = 1;
@@ -532,12 +589,12 @@ mod tests {
 		pub const TransactionByteFee: u64 = 0;
 	}
 	impl pallet_transaction_payment::Trait for Runtime {
-		type Currency = Balances;
-		type OnTransactionPayment = ();
+		type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, ()>;
 		type TransactionBaseFee = TransactionBaseFee;
 		type TransactionByteFee = TransactionByteFee;
 		type WeightToFee = ConvertInto;
 		type FeeMultiplierUpdate = ();
+		type OperationalFeeMultiplierUpdate = ();
 	}
 	impl custom::Trait for Runtime {}
 
@@ -876,6 +933,25 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn try_runtime_upgrade_should_work() {
+		new_test_ext(1).execute_with(|| {
+			RUNTIME_VERSION.with(|v| *v.borrow_mut() = Default::default());
+			// The hooks are executed even if the version didn't change
+			assert_eq!(Executive::try_runtime_upgrade(), 200);
+
+			RUNTIME_VERSION.with(|v| *v.borrow_mut() = sp_version::RuntimeVersion {
+				spec_version: 1,
+				..Default::default()
+			});
+			assert_eq!(Executive::try_runtime_upgrade(), 200);
+			assert_eq!(
+				Some(LastRuntimeUpgradeInfo { spec_version: 1.into(), spec_name: "".into() }),
+				frame_system::LastRuntimeUpgrade::get(),
+			);
+		})
+	}
+
 	#[test]
 	fn last_runtime_upgrade_was_upgraded_works() {
 		let test_data = vec![
@@ -135,9 +135,13 @@ impl Time for TimestampProvider {
 }
 impl DoughnutRuntime for Runtime {
 	type AccountId = <Self as frame_system::Trait>::AccountId;
+	type PublicKeyAccount = ConvertInto;
 	type Call = <Self as frame_system::Trait>::Call;
 	type Doughnut = <Self as frame_system::Trait>::Doughnut;
 	type TimestampProvider = TimestampProvider;
+	type VerificationWeight = ();
+	type VerificationWeightPerByte = ();
+	type RevocationRegistry = ();
 }
 parameter_types! {
 	pub const ExistentialDeposit: u64 = 1;
@@ -160,12 +164,12 @@ parameter_types! {
 	pub const TransactionByteFee: u64 = 0;
 }
 impl pallet_transaction_payment::Trait for Runtime {
-	type Currency = Balances;
-	type OnTransactionPayment = ();
+	type OnChargeTransaction = pallet_transaction_payment::CurrencyAdapter<Balances, ()>;
 	type TransactionBaseFee = TransactionBaseFee;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = ConvertInto;
 	type FeeMultiplierUpdate = ();
+	type OperationalFeeMultiplierUpdate = ();
 }
 
 #[allow(deprecated)] // Allow ValidateUnsigned
@@ -0,0 +1,271 @@
+// Copyright 2020 Plug New Zealand Limited
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//!
+//! Ethereum accounts + Extrinsic + Executive integration tests
+//!
+#![cfg(test)]
+use codec::Encode;
+use pallet_generic_asset::Call as GenericAssetCall;
+use prml_doughnut::{DoughnutRuntime, PlugDoughnut, TruncatedAccount};
+use sp_core::{ecdsa, Pair, H256};
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	AccountId20, Doughnut, DoughnutV0, EthereumSignature,
+	generic::{self, Era}, Perbill, testing::{Block, Digest, Header},
+	traits::{BlakeTwo256, Convert, DoughnutSigning, Header as HeaderT, IdentityLookup},
+	transaction_validity::{InvalidTransaction, TransactionSource, TransactionValidity, UnknownTransaction},
+};
+#[allow(deprecated)]
+use sp_runtime::traits::ValidateUnsigned;
+use frame_support::{
+	impl_outer_event, impl_outer_origin, parameter_types, impl_outer_dispatch,
+	additional_traits::DummyDispatchVerifier,
+	traits::Time,
+};
+use frame_system as system;
+use std::convert::TryFrom;
+
+type AccountId = AccountId20;
+type Address = AccountId;
+type Index = u32;
+type Signature = EthereumSignature;
+type System = frame_system::Module<Runtime>;
+type GenericAsset = pallet_generic_asset::Module<Runtime>;
+
+const ASSET_ID: u32 = 16000;
+
+impl_outer_origin! {
+	pub enum Origin for Runtime {}
+}
+
+impl_outer_event!{
+	pub enum MetaEvent for Runtime {
+		system, pallet_generic_asset<T>,
+	}
+}
+impl_outer_dispatch! {
+	pub enum Call for Runtime where origin: Origin {
+		frame_system::System,
+		pallet_generic_asset::GenericAsset,
+	}
+}
+
+// A minimal runtime with Ethereum compatible accounts, signing their transactions with MetaMask's
+// `personal_sign`, and doughnuts naming them by their address.
+#[derive(Clone, Eq, PartialEq)]
+pub struct Runtime;
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: u32 = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+}
+impl frame_system::Trait for Runtime {
+	type Origin = Origin;
+	type Index = Index;
+	type Call = Call;
+	type BlockNumber = u64;
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = AccountId;
+	type Lookup = IdentityLookup<Self::AccountId>;
+	type Header = Header;
+	type Event = MetaEvent;
+	type BlockHashCount = BlockHashCount;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type MaximumBlockLength = MaximumBlockLength;
+	type Version = ();
+	type ModuleToIndex = ();
+	type Doughnut = PlugDoughnut<Runtime>;
+	type DelegatedDispatchVerifier = DummyDispatchVerifier<Self::Doughnut, Self::AccountId>;
+}
+pub struct TimestampProvider;
+impl Time for TimestampProvider {
+	type Moment = u64;
+	fn now() -> Self::Moment {
+		10_000
+	}
+}
+impl DoughnutRuntime for Runtime {
+	type AccountId = <Self as frame_system::Trait>::AccountId;
+	type PublicKeyAccount = TruncatedAccount;
+	type Call = <Self as frame_system::Trait>::Call;
+	type Doughnut = <Self as frame_system::Trait>::Doughnut;
+	type TimestampProvider = TimestampProvider;
+	type VerificationWeight = ();
+	type VerificationWeightPerByte = ();
+	type RevocationRegistry = ();
+}
+impl pallet_generic_asset::Trait for Runtime {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = MetaEvent;
+	type TransferRestriction = ();
+}
+
+#[allow(deprecated)] // Allow ValidateUnsigned
+impl ValidateUnsigned for Runtime {
+	type Call = Call;
+
+	fn validate_unsigned(_source: TransactionSource, _call: &Self::Call) -> TransactionValidity {
+		UnknownTransaction::NoUnsignedValidator.into()
+	}
+}
+type SignedExtra = (
+	Option<PlugDoughnut<Runtime>>,
+	frame_system::CheckVersion<Runtime>,
+	frame_system::CheckGenesis<Runtime>,
+	frame_system::CheckEra<Runtime>,
+	frame_system::CheckNonce<Runtime>,
+	frame_system::CheckWeight<Runtime>,
+);
+
+type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signature, SignedExtra>;
+type Executive = frame_executive::Executive<Runtime, Block<UncheckedExtrinsic>, frame_system::ChainContext<Runtime>, Runtime, ()>;
+
+fn signed_extra(nonce: Index, doughnut: Option<PlugDoughnut<Runtime>>) -> SignedExtra {
+	(
+		doughnut,
+		frame_system::CheckVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(Era::mortal(256, 0)),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+	)
+}
+
+fn ethereum_pair(suri: &str) -> ecdsa::Pair {
+	ecdsa::Pair::from_string(suri, None).unwrap()
+}
+
+fn ethereum_account(pair: &ecdsa::Pair) -> AccountId {
+	AccountId20::try_from(pair.public()).unwrap()
+}
+
+/// Sign `function` as `signer` with the key of `pair`, as MetaMask's `personal_sign` does
+fn sign_extrinsic(function: Call, signer: AccountId, pair: &ecdsa::Pair, extra: SignedExtra) -> UncheckedExtrinsic {
+	let raw_payload = generic::SignedPayload::new(function, extra).expect("signed payload is valid");
+	let signature = raw_payload.using_encoded(|payload| {
+		pair.sign_prehashed(&EthereumSignature::message_hash(payload))
+	});
+	let (function, extra, _) = raw_payload.deconstruct();
+	UncheckedExtrinsic::new_signed(function, signer, signature.into(), extra)
+}
+
+fn new_test_ext(endowed_accounts: Vec<AccountId>) -> sp_io::TestExternalities {
+	let mut t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
+	pallet_generic_asset::GenesisConfig::<Runtime> {
+		assets: vec![ASSET_ID],
+		endowed_accounts,
+		initial_balance: 1_000,
+		next_asset_id: 17000,
+		staking_asset_id: ASSET_ID,
+		spending_asset_id: ASSET_ID,
+		permissions: vec![],
+		asset_meta: vec![],
+	}.assimilate_storage(&mut t).unwrap();
+	t.into()
+}
+
+fn initialize_block() {
+	Executive::initialize_block(&Header::new(
+		1,
+		H256::default(),
+		H256::default(),
+		[69u8; 32].into(),
+		Digest::default(),
+	));
+}
+
+#[test]
+fn ethereum_account_transfers_asset() {
+	let alice = ethereum_pair("//Alice");
+	let bob = ethereum_account(&ethereum_pair("//Bob"));
+
+	new_test_ext(vec![ethereum_account(&alice)]).execute_with(|| {
+		initialize_block();
+
+		let uxt = sign_extrinsic(
+			Call::GenericAsset(GenericAssetCall::transfer(ASSET_ID, bob, 69)),
+			ethereum_account(&alice),
+			&alice,
+			signed_extra(0, None),
+		);
+		assert_eq!(Executive::apply_extrinsic(uxt), Ok(Ok(())));
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &ethereum_account(&alice)), 1_000 - 69);
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &bob), 69);
+		assert_eq!(System::account_nonce(ethereum_account(&alice)), 1);
+	});
+}
+
+#[test]
+fn ethereum_signature_of_another_account_is_rejected() {
+	let alice = ethereum_pair("//Alice");
+	let bob = ethereum_pair("//Bob");
+
+	new_test_ext(vec![ethereum_account(&alice)]).execute_with(|| {
+		initialize_block();
+
+		let uxt = sign_extrinsic(
+			Call::GenericAsset(GenericAssetCall::transfer(ASSET_ID, ethereum_account(&bob), 69)),
+			ethereum_account(&alice),
+			&bob,
+			signed_extra(0, None),
+		);
+		assert_eq!(Executive::apply_extrinsic(uxt), Err(InvalidTransaction::BadProof.into()));
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &ethereum_account(&alice)), 1_000);
+	});
+}
+
+#[test]
+fn ethereum_account_dispatches_under_doughnut() {
+	// Alice issues a doughnut with her sr25519 key to Bob's Ethereum account, named by its
+	// address left padded with zeros. Bob transfers Alice's assets to Charlie under it.
+	let issuer_alice = TruncatedAccount::convert(AccountKeyring::Alice.to_raw_public());
+	let bob = ethereum_pair("//Bob");
+	let charlie = ethereum_account(&ethereum_pair("//Charlie"));
+
+	let address: [u8; 20] = ethereum_account(&bob).into();
+	let mut holder = [0u8; 32];
+	holder[12..].copy_from_slice(&address);
+	let mut doughnut = DoughnutV0 {
+		issuer: AccountKeyring::Alice.to_raw_public(),
+		holder,
+		expiry: u32::max_value(),
+		not_before: 0,
+		payload_version: 0,
+		signature_version: 0, // sr25519
+		signature: [0u8; 64].into(),
+		domains: vec![("test".to_string(), vec![1u8])],
+	};
+	assert!(doughnut.sign_sr25519(&AccountKeyring::Alice.pair().to_ed25519_bytes()).is_ok());
+	let doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+
+	new_test_ext(vec![issuer_alice, ethereum_account(&bob)]).execute_with(|| {
+		initialize_block();
+
+		let uxt = sign_extrinsic(
+			Call::GenericAsset(GenericAssetCall::transfer(ASSET_ID, charlie, 69)),
+			ethereum_account(&bob),
+			&bob,
+			signed_extra(0, Some(doughnut)),
+		);
+		assert_eq!(Executive::apply_extrinsic(uxt), Ok(Ok(())));
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &issuer_alice), 1_000 - 69);
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &ethereum_account(&bob)), 1_000);
+		assert_eq!(GenericAsset::free_balance(&ASSET_ID, &charlie), 69);
+	});
+}
@@ -55,6 +55,9 @@
 //! - **Lock:** A freeze on a specified amount of an account's free balance until a specified block number. Multiple
 //! locks always operate over the same funds, so they "overlay" rather than "stack".
 //!
+//! - **Transfer Restriction:** A runtime hook which is consulted before every transfer. A transfer made with a
+//! delegated origin passes the doughnut along, so the hook can check the doughnut's permission domains.
+//!
 //! ### Implementations
 //!
 //! The Generic Asset module provides `AssetCurrency`, which implements the following traits. If these traits provide
@@ -169,7 +172,7 @@ use frame_support::{
 		Currency, ExistenceRequirement, Imbalance, LockIdentifier, LockableCurrency, ReservableCurrency,
 		SignedImbalance, UpdateBalanceOutcome, WithdrawReason, WithdrawReasons, TryDrop,
 	},
-	additional_traits::{AssetIdAuthority, DummyDispatchVerifier},
+	additional_traits::{AssetIdAuthority, DummyDispatchVerifier, MaybeDoughnutRef, TransferRestriction},
 	weights::SimpleDispatchInfo,
 	Parameter, StorageMap, IterableStorageMap,
 };
@@ -191,6 +194,9 @@ pub trait Trait: frame_system::Trait {
 		+ Debug;
 	type AssetId: Parameter + Member + AtLeast32Bit + Default + Copy;
 	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	/// Checks every transfer of an asset, e.g. against the permission domains of the doughnut
+	/// it is made under.
+	type TransferRestriction: TransferRestriction<Self::AssetId, Self::AccountId, Self::Balance, Self::Doughnut>;
 }
 
 pub trait Subtrait: frame_system::Trait {
@@ -426,7 +432,8 @@ decl_module! {
 		/// If the sender's account is below the existential deposit as a result
 		/// of the transfer, the account will be reaped.
 		///
-		/// The dispatch origin for this call must be `Signed` by the transactor.
+		/// The dispatch origin for this call must be `Signed` by the transactor. If it is delegated,
+		/// the doughnut is passed on to the runtime's `TransferRestriction`.
 		///
 		/// # <weight>
 		/// - Dependent on arguments but not critical, given proper implementations for
@@ -436,9 +443,13 @@ decl_module! {
 		/// # </weight>
 		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
 		pub fn transfer(origin, #[compact] asset_id: T::AssetId, to: T::AccountId, #[compact] amount: T::Balance) {
+			let doughnut = origin.doughnut().cloned();
 			let origin = ensure_signed(origin)?;
 			ensure!(!amount.is_zero(), Error::<T>::ZeroAmount);
-			Self::make_transfer_with_event(&asset_id, &origin, &to, amount)?;
+			Self::do_transfer(&asset_id, &origin, &to, amount, doughnut.as_ref())?;
+			if origin != to {
+				Self::deposit_event(RawEvent::Transferred(asset_id, origin, to, amount));
+			}
 		}
 
 		/// Updates permissions(mint/burn/change permission) for a given `asset_id` and an account.
@@ -738,6 +749,20 @@ impl<T: Trait> Module<T> {
 		to: &T::AccountId,
 		amount: T::Balance
 	) -> DispatchResult {
+		Self::do_transfer(asset_id, from, to, amount, None)
+	}
+
+	/// Transfer some liquid free balance from one account to another, once the transfer has been
+	/// allowed by `T::TransferRestriction` under `doughnut`.
+	fn do_transfer(
+		asset_id: &T::AssetId,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+		doughnut: Option<&T::Doughnut>,
+	) -> DispatchResult {
+		T::TransferRestriction::check_transfer(asset_id, from, to, amount, doughnut)?;
+
 		let new_from_balance = Self::free_balance(asset_id, from)
 			.checked_sub(&amount)
 			.ok_or(Error::<T>::InsufficientBalance)?;
@@ -44,6 +44,8 @@ pub const SPENDING_ASSET_ID: u32 = 16001;
 pub const TEST1_ASSET_ID: u32 = 16003;
 // pre-existing asset 2
 pub const TEST2_ASSET_ID: u32 = 16004;
+// asset which may only be transferred under a doughnut
+pub const RESTRICTED_ASSET_ID: u32 = 16005;
 // default next asset id
 pub const ASSET_ID: u32 = 1000;
 
@@ -95,6 +97,7 @@ impl Trait for Test {
 	type Balance = u64;
 	type AssetId = u32;
 	type Event = TestEvent;
+	type TransferRestriction = TestTransferRestriction;
 }
 
 mod generic_asset {
@@ -111,6 +114,18 @@ impl_outer_event! {
 
 pub type GenericAsset = Module<Test>;
 
+/// Transfers of `RESTRICTED_ASSET_ID` must be made under a doughnut.
+pub struct TestTransferRestriction;
+impl TransferRestriction<u32, u64, u64, ()> for TestTransferRestriction {
+	fn check_transfer(asset_id: &u32, _: &u64, _: &u64, _: u64, doughnut: Option<&()>) -> DispatchResult {
+		if *asset_id == RESTRICTED_ASSET_ID && doughnut.is_none() {
+			Err(DispatchError::Other("transfer requires a doughnut"))
+		} else {
+			Ok(())
+		}
+	}
+}
+
 pub type System = frame_system::Module<Test>;
 
 pub struct ExtBuilder {
@@ -24,9 +24,10 @@ use super::*;
 use crate::imbalances::ImbalanceWithAssetId;
 use crate::mock::{
 	new_test_ext, ExtBuilder, GenericAsset, NegativeImbalanceOf, Origin, PositiveImbalanceOf, System, Test, TestEvent,
-	ALICE, ASSET_ID, BOB, CHARLIE, INITIAL_BALANCE, INITIAL_ISSUANCE, SPENDING_ASSET_ID, STAKING_ASSET_ID,
-	TEST1_ASSET_ID, TEST2_ASSET_ID,
+	ALICE, ASSET_ID, BOB, CHARLIE, INITIAL_BALANCE, INITIAL_ISSUANCE, RESTRICTED_ASSET_ID, SPENDING_ASSET_ID,
+	STAKING_ASSET_ID, TEST1_ASSET_ID, TEST2_ASSET_ID,
 };
+use frame_system::RawOrigin;
 use frame_support::{assert_noop, assert_ok, traits::Imbalance};
 
 fn asset_options(permissions: PermissionLatest<u64>) -> AssetOptions<u64, u64> {
@@ -1583,3 +1584,31 @@ fn non_owner_permitted_update_asset_info() {
 			assert_eq!(<AssetMeta<Test>>::get(ASSET_ID), web3_asset_info);
 		});
 }
+
+#[test]
+fn transfer_restriction_is_checked_with_doughnut() {
+	ExtBuilder::default()
+		.free_balance((RESTRICTED_ASSET_ID, ALICE, INITIAL_BALANCE))
+		.build()
+		.execute_with(|| {
+			// Should fail as the restricted asset may only be transferred under a doughnut
+			assert_noop!(
+				GenericAsset::transfer(Origin::signed(ALICE), RESTRICTED_ASSET_ID, BOB, 10),
+				DispatchError::Other("transfer requires a doughnut")
+			);
+			assert_noop!(
+				GenericAsset::make_transfer(&RESTRICTED_ASSET_ID, &ALICE, &BOB, 10),
+				DispatchError::Other("transfer requires a doughnut")
+			);
+
+			// Should succeed as the doughnut is passed to the restriction
+			assert_ok!(GenericAsset::transfer(
+				RawOrigin::Delegated(ALICE, ()).into(),
+				RESTRICTED_ASSET_ID,
+				BOB,
+				10
+			));
+			assert_eq!(GenericAsset::free_balance(&RESTRICTED_ASSET_ID, &ALICE), INITIAL_BALANCE - 10);
+			assert_eq!(GenericAsset::free_balance(&RESTRICTED_ASSET_ID, &BOB), 10);
+		});
+}
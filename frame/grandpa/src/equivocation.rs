@@ -0,0 +1,243 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reporting of GRANDPA equivocations.
+//!
+//! An equivocation report carries the two conflicting votes of the offender and a proof that
+//! the offender's GRANDPA key was owned by a validator of the session. Nodes detecting an
+//! equivocation submit the report as an unsigned extrinsic through the `GrandpaApi`, the
+//! module checks it and reports the offence, e.g. to `pallet-offences`.
+//!
+//! The `HandleEquivocation` of the module decides how key ownership is proven and where
+//! offences are reported: `()` rejects all reports, `EquivocationHandler` proves key
+//! ownership with the historical session module.
+
+use sp_std::{marker::PhantomData, prelude::*};
+use codec::{Encode, Decode};
+use frame_support::{Parameter, traits::KeyOwnerProofSystem};
+use frame_system::offchain::SubmitUnsignedTransaction;
+use pallet_session::historical::{self, IdentificationTuple};
+use sp_runtime::Perbill;
+use sp_staking::{
+	SessionIndex,
+	offence::{Offence, Kind, OffenceError, ReportOffence},
+};
+use fg_primitives::{EquivocationProof, RoundNumber, SetId, KEY_TYPE};
+
+use crate::{AuthorityId, Call, Module, Trait};
+
+/// The equivocation proof of a GRANDPA equivocation report.
+pub type EquivocationProofOf<T> = EquivocationProof<
+	<T as frame_system::Trait>::Hash,
+	<T as frame_system::Trait>::BlockNumber,
+>;
+
+/// The key ownership proof of a GRANDPA equivocation report.
+pub type KeyOwnerProofOf<T> = <<T as Trait>::HandleEquivocation as HandleEquivocation<T>>::KeyOwnerProof;
+
+/// Why an equivocation report was rejected by the `HandleEquivocation`.
+#[derive(Clone, Copy, PartialEq, Eq, sp_runtime::RuntimeDebug)]
+pub enum ReportError {
+	/// The key ownership proof doesn't prove the offender's key was owned by a validator.
+	InvalidKeyOwnershipProof,
+	/// The offence was already reported.
+	DuplicateOffenceReport,
+}
+
+/// Handles the equivocation reports of the module: proves and checks the key ownership of
+/// offenders, reports their offences and submits reports as unsigned extrinsics.
+pub trait HandleEquivocation<T: Trait> {
+	/// The proof that the key of an offender was owned by a validator of a session.
+	type KeyOwnerProof: Parameter;
+
+	/// Prove that the given authority key is owned by a validator of a session of the given
+	/// set. Only called off-chain, returns `None` if the key isn't owned by a validator of that
+	/// session or its key ownership can't be proven.
+	fn prove_key_ownership(set_id: SetId, authority: AuthorityId) -> Option<Self::KeyOwnerProof>;
+
+	/// The session the given key ownership proof was generated in.
+	fn session_index(key_owner_proof: &Self::KeyOwnerProof) -> SessionIndex;
+
+	/// Check the key ownership proof of the offender and report the equivocation offence of
+	/// its owner, committed in the given round of the given set.
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offender: AuthorityId,
+		key_owner_proof: Self::KeyOwnerProof,
+		time_slot: GrandpaTimeSlot,
+	) -> Result<(), ReportError>;
+
+	/// Submit an unsigned extrinsic reporting the equivocation to the transaction pool. Only
+	/// called off-chain.
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: Self::KeyOwnerProof,
+	) -> Result<(), ()>;
+}
+
+/// Rejects all equivocation reports, i.e. disables equivocation reporting.
+impl<T: Trait> HandleEquivocation<T> for () {
+	type KeyOwnerProof = ();
+
+	fn prove_key_ownership(_set_id: SetId, _authority: AuthorityId) -> Option<()> {
+		None
+	}
+
+	fn session_index(_key_owner_proof: &()) -> SessionIndex {
+		0
+	}
+
+	fn report_offence(
+		_reporters: Vec<T::AccountId>,
+		_offender: AuthorityId,
+		_key_owner_proof: (),
+		_time_slot: GrandpaTimeSlot,
+	) -> Result<(), ReportError> {
+		Err(ReportError::InvalidKeyOwnershipProof)
+	}
+
+	fn submit_unsigned_equivocation_report(
+		_equivocation_proof: EquivocationProofOf<T>,
+		_key_owner_proof: (),
+	) -> Result<(), ()> {
+		Err(())
+	}
+}
+
+/// Proves key ownership with the historical session module, reports offences to `R` (e.g.
+/// `pallet-offences`) and submits unsigned reports with `S`.
+pub struct EquivocationHandler<R, S>(PhantomData<(R, S)>);
+
+/// The last session of the given set, i.e. the current session for the current set. `None` if
+/// the set is unknown.
+fn set_session<T: Trait + pallet_session::Trait>(set_id: SetId) -> Option<SessionIndex> {
+	if set_id == Module::<T>::current_set_id() {
+		Some(pallet_session::Module::<T>::current_index())
+	} else {
+		Module::<T>::session_for_set(set_id)
+	}
+}
+
+impl<T, R, S> HandleEquivocation<T> for EquivocationHandler<R, S> where
+	T: Trait + historical::Trait,
+	R: ReportOffence<
+		T::AccountId,
+		IdentificationTuple<T>,
+		GrandpaEquivocationOffence<IdentificationTuple<T>>,
+	>,
+	S: SubmitUnsignedTransaction<T, <T as frame_system::Trait>::Call>,
+	Call<T>: Into<<T as frame_system::Trait>::Call>,
+{
+	type KeyOwnerProof = historical::Proof;
+
+	fn prove_key_ownership(set_id: SetId, authority: AuthorityId) -> Option<historical::Proof> {
+		// the key is proven in the last session of the set.
+		historical::Module::<T>::prove_session_membership(
+			set_session::<T>(set_id)?,
+			(KEY_TYPE, &authority.encode()[..]),
+		)
+	}
+
+	fn session_index(key_owner_proof: &historical::Proof) -> SessionIndex {
+		key_owner_proof.session()
+	}
+
+	fn report_offence(
+		reporters: Vec<T::AccountId>,
+		offender: AuthorityId,
+		key_owner_proof: historical::Proof,
+		time_slot: GrandpaTimeSlot,
+	) -> Result<(), ReportError> {
+		let session_index = key_owner_proof.session();
+		let validator_set_count = historical::Module::<T>::historical_root(session_index)
+			.map(|(_, count)| count)
+			.ok_or(ReportError::InvalidKeyOwnershipProof)?;
+		let offender = historical::Module::<T>::check_proof((KEY_TYPE, offender), key_owner_proof)
+			.ok_or(ReportError::InvalidKeyOwnershipProof)?;
+
+		let offence = GrandpaEquivocationOffence {
+			time_slot,
+			session_index,
+			validator_set_count,
+			offender,
+		};
+
+		R::report_offence(reporters, offence).map_err(|e| match e {
+			OffenceError::DuplicateReport => ReportError::DuplicateOffenceReport,
+			_ => ReportError::InvalidKeyOwnershipProof,
+		})
+	}
+
+	fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: historical::Proof,
+	) -> Result<(), ()> {
+		S::submit_unsigned(Call::<T>::report_equivocation_unsigned(equivocation_proof, key_owner_proof))
+	}
+}
+
+/// A round number and set id which point on the time of an offence.
+#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode, sp_runtime::RuntimeDebug)]
+pub struct GrandpaTimeSlot {
+	// The order of these matters for `derive(Ord)`.
+	/// Grandpa Set ID.
+	pub set_id: SetId,
+	/// Round number.
+	pub round: RoundNumber,
+}
+
+/// A grandpa equivocation offence report.
+pub struct GrandpaEquivocationOffence<FullIdentification> {
+	/// Time slot at which this incident happened.
+	pub time_slot: GrandpaTimeSlot,
+	/// The session index in which the incident happened.
+	pub session_index: SessionIndex,
+	/// The size of the validator set at the time of the offence.
+	pub validator_set_count: u32,
+	/// The authority which produced this equivocation.
+	pub offender: FullIdentification,
+}
+
+impl<FullIdentification: Clone> Offence<FullIdentification> for GrandpaEquivocationOffence<FullIdentification> {
+	const ID: Kind = *b"grandpa:equivoca";
+	type TimeSlot = GrandpaTimeSlot;
+
+	fn offenders(&self) -> Vec<FullIdentification> {
+		vec![self.offender.clone()]
+	}
+
+	fn session_index(&self) -> SessionIndex {
+		self.session_index
+	}
+
+	fn validator_set_count(&self) -> u32 {
+		self.validator_set_count
+	}
+
+	fn time_slot(&self) -> Self::TimeSlot {
+		self.time_slot
+	}
+
+	fn slash_fraction(
+		offenders_count: u32,
+		validator_set_count: u32,
+	) -> Perbill {
+		// the formula is min((3k / n)^2, 1)
+		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
+		// _ ^ 2
+		x.square()
+	}
+}
@@ -19,8 +19,8 @@
 //! This manages the GRANDPA authority set ready for the native code.
 //! These authorities are only for GRANDPA finality, not for consensus overall.
 //!
-//! In the future, it will also handle misbehavior reports, and on-chain
-//! finality notifications.
+//! It also handles equivocation reports, see the `equivocation` module. In the future, it
+//! will also handle on-chain finality notifications.
 //!
 //! For full integration with GRANDPA, the `GrandpaApi` should be implemented.
 //! The necessary items are re-exported via the `fg_primitives` crate.
@@ -32,26 +32,40 @@ pub use sp_finality_grandpa as fg_primitives;
 
 use sp_std::prelude::*;
 use codec::{self as codec, Encode, Decode};
-use frame_support::{decl_event, decl_storage, decl_module, decl_error, storage};
-use sp_runtime::{
-	DispatchResult, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero, Perbill,
+use frame_support::{
+	decl_event, decl_storage, decl_module, decl_error, storage,
+	weights::SimpleDispatchInfo,
 };
-use sp_staking::{
-	SessionIndex,
-	offence::{Offence, Kind},
+use sp_runtime::{
+	DispatchResult, generic::{DigestItem, OpaqueDigestItemId}, traits::Zero,
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity,
+		ValidTransaction,
+	},
 };
+use sp_staking::SessionIndex;
 use fg_primitives::{
-	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId, RoundNumber,
+	GRANDPA_AUTHORITIES_KEY, GRANDPA_ENGINE_ID, ScheduledChange, ConsensusLog, SetId,
 };
 pub use fg_primitives::{AuthorityId, AuthorityList, AuthorityWeight, VersionedAuthorityList};
-use frame_system::{self as system, ensure_signed, DigestOf};
+use frame_system::{self as system, ensure_none, ensure_signed, DigestOf};
 
+mod equivocation;
 mod mock;
 mod tests;
 
+pub use equivocation::{
+	EquivocationHandler, EquivocationProofOf, GrandpaEquivocationOffence, GrandpaTimeSlot,
+	HandleEquivocation, KeyOwnerProofOf, ReportError,
+};
+
 pub trait Trait: frame_system::Trait {
 	/// The event type of this module.
 	type Event: From<Event> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// The equivocation handling subsystem, proving the key ownership of offenders and
+	/// reporting their offences. `()` rejects all equivocation reports.
+	type HandleEquivocation: HandleEquivocation<Self>;
 }
 
 /// A stored pending change, old format.
@@ -146,6 +160,12 @@ decl_error! {
 		ChangePending,
 		/// Cannot signal forced change so soon after last.
 		TooSoon,
+		/// A key ownership proof provided as part of an equivocation report is invalid.
+		InvalidKeyOwnershipProof,
+		/// An equivocation proof provided as part of an equivocation report is invalid.
+		InvalidEquivocationProof,
+		/// A given equivocation report is valid but already previously reported.
+		DuplicateOffenceReport,
 	}
 }
 
@@ -183,10 +203,34 @@ decl_module! {
 
 		fn deposit_event() = default;
 
-		/// Report some misbehavior.
-		fn report_misbehavior(origin, _report: Vec<u8>) {
-			ensure_signed(origin)?;
-			// FIXME: https://github.com/paritytech/substrate/issues/1112
+		/// Report voter equivocation/misbehavior. This method will verify the equivocation
+		/// proof and validate the given key ownership proof against the extracted offender.
+		/// If both are valid, the offence will be reported, with the sender as reporter.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000_000)]
+		fn report_equivocation(
+			origin,
+			equivocation_proof: EquivocationProofOf<T>,
+			key_owner_proof: KeyOwnerProofOf<T>,
+		) {
+			let reporter = ensure_signed(origin)?;
+			Self::do_report_equivocation(vec![reporter], equivocation_proof, key_owner_proof)?;
+		}
+
+		/// Report voter equivocation/misbehavior. This method will verify the equivocation
+		/// proof and validate the given key ownership proof against the extracted offender.
+		/// If both are valid, the offence will be reported.
+		///
+		/// This extrinsic must be called unsigned and it is expected that only block authors
+		/// will call it (validated in `ValidateUnsigned`), as such if the block author is
+		/// defined it will be defined as the equivocation reporter.
+		#[weight = SimpleDispatchInfo::FixedOperational(100_000_000)]
+		fn report_equivocation_unsigned(
+			origin,
+			equivocation_proof: EquivocationProofOf<T>,
+			key_owner_proof: KeyOwnerProofOf<T>,
+		) {
+			ensure_none(origin)?;
+			Self::do_report_equivocation(vec![], equivocation_proof, key_owner_proof)?;
 		}
 
 		fn on_finalize(block_number: T::BlockNumber) {
@@ -350,6 +394,80 @@ impl<T: Trait> Module<T> {
 		<frame_system::Module<T>>::deposit_log(log.into());
 	}
 
+	/// Submit an unsigned extrinsic reporting the given equivocation, returning `None` if the
+	/// submission failed. Only called off-chain, from the `GrandpaApi`.
+	pub fn submit_unsigned_equivocation_report(
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: KeyOwnerProofOf<T>,
+	) -> Option<()> {
+		T::HandleEquivocation::submit_unsigned_equivocation_report(equivocation_proof, key_owner_proof)
+			.ok()
+	}
+
+	/// Prove that the given authority key is owned by a validator of a session of the given
+	/// set, for equivocation reports. Only called off-chain, from the `GrandpaApi`.
+	pub fn prove_key_ownership(set_id: SetId, authority: AuthorityId) -> Option<KeyOwnerProofOf<T>> {
+		T::HandleEquivocation::prove_key_ownership(set_id, authority)
+	}
+
+	fn do_report_equivocation(
+		reporters: Vec<T::AccountId>,
+		equivocation_proof: EquivocationProofOf<T>,
+		key_owner_proof: KeyOwnerProofOf<T>,
+	) -> DispatchResult {
+		let session_index = T::HandleEquivocation::session_index(&key_owner_proof);
+		let offender = equivocation_proof.offender().clone();
+		let time_slot = GrandpaTimeSlot {
+			set_id: equivocation_proof.set_id(),
+			round: equivocation_proof.round(),
+		};
+
+		// the key ownership proof must be generated in one of the sessions of the set.
+		Self::check_set_session(time_slot.set_id, session_index)?;
+
+		// validate equivocation proof (check votes are different and signatures are valid).
+		if !fg_primitives::check_equivocation_proof(equivocation_proof) {
+			Err(Error::<T>::InvalidEquivocationProof)?
+		}
+
+		T::HandleEquivocation::report_offence(reporters, offender, key_owner_proof, time_slot)
+			.map_err(|e| match e {
+				ReportError::InvalidKeyOwnershipProof => Error::<T>::InvalidKeyOwnershipProof,
+				ReportError::DuplicateOffenceReport => Error::<T>::DuplicateOffenceReport,
+			})?;
+
+		Ok(())
+	}
+
+	/// Check that the given session is one of the sessions the given set was responsible for,
+	/// i.e. it comes after the last session of the previous set and isn't after the last
+	/// session of the set. The current set is responsible for all sessions since the previous.
+	fn check_set_session(set_id: SetId, session_index: SessionIndex) -> DispatchResult {
+		let current_set_id = Self::current_set_id();
+		let set_session = if set_id > current_set_id {
+			Err(Error::<T>::InvalidEquivocationProof)?
+		} else if set_id == current_set_id {
+			None
+		} else {
+			Some(Self::session_for_set(set_id).ok_or(Error::<T>::InvalidEquivocationProof)?)
+		};
+		let previous_set_session = match set_id.checked_sub(1) {
+			Some(previous_set_id) => Some(
+				Self::session_for_set(previous_set_id)
+					.ok_or(Error::<T>::InvalidEquivocationProof)?
+			),
+			None => None,
+		};
+
+		if set_session.map_or(false, |last| session_index > last) ||
+			previous_set_session.map_or(false, |previous| session_index <= previous)
+		{
+			Err(Error::<T>::InvalidKeyOwnershipProof)?
+		}
+
+		Ok(())
+	}
+
 	fn initialize_authorities(authorities: &AuthorityList) {
 		if !authorities.is_empty() {
 			assert!(
@@ -453,54 +571,43 @@ impl<T: Trait> pallet_finality_tracker::OnFinalizationStalled<T::BlockNumber> fo
 	}
 }
 
-/// A round number and set id which point on the time of an offence.
-#[derive(Copy, Clone, PartialOrd, Ord, Eq, PartialEq, Encode, Decode)]
-struct GrandpaTimeSlot {
-	// The order of these matters for `derive(Ord)`.
-	set_id: SetId,
-	round: RoundNumber,
-}
-
-// TODO [slashing]: Integrate this.
-/// A grandpa equivocation offence report.
-struct GrandpaEquivocationOffence<FullIdentification> {
-	/// Time slot at which this incident happened.
-	time_slot: GrandpaTimeSlot,
-	/// The session index in which the incident happened.
-	session_index: SessionIndex,
-	/// The size of the validator set at the time of the offence.
-	validator_set_count: u32,
-	/// The authority which produced this equivocation.
-	offender: FullIdentification,
-}
-
-impl<FullIdentification: Clone> Offence<FullIdentification> for GrandpaEquivocationOffence<FullIdentification> {
-	const ID: Kind = *b"grandpa:equivoca";
-	type TimeSlot = GrandpaTimeSlot;
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
 
-	fn offenders(&self) -> Vec<FullIdentification> {
-		vec![self.offender.clone()]
-	}
-
-	fn session_index(&self) -> SessionIndex {
-		self.session_index
-	}
+	fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+		if let Call::report_equivocation_unsigned(equivocation_proof, key_owner_proof) = call {
+			// discard equivocation reports not coming from the local node
+			match source {
+				TransactionSource::Local | TransactionSource::InBlock => {},
+				_ => return InvalidTransaction::Call.into(),
+			}
 
-	fn validator_set_count(&self) -> u32 {
-		self.validator_set_count
-	}
+			let session_index = T::HandleEquivocation::session_index(key_owner_proof);
+			if Self::check_set_session(equivocation_proof.set_id(), session_index).is_err() {
+				return InvalidTransaction::Stale.into();
+			}
 
-	fn time_slot(&self) -> Self::TimeSlot {
-		self.time_slot
-	}
+			// check the signatures last, as this is the most expensive check.
+			if !fg_primitives::check_equivocation_proof(equivocation_proof.clone()) {
+				return InvalidTransaction::BadProof.into();
+			}
 
-	fn slash_fraction(
-		offenders_count: u32,
-		validator_set_count: u32,
-	) -> Perbill {
-		// the formula is min((3k / n)^2, 1)
-		let x = Perbill::from_rational_approximation(3 * offenders_count, validator_set_count);
-		// _ ^ 2
-		x.square()
+			Ok(ValidTransaction {
+				priority: TransactionPriority::max_value(),
+				requires: vec![],
+				provides: vec![(
+					b"GrandpaEquivocation",
+					equivocation_proof.offender(),
+					equivocation_proof.set_id(),
+					equivocation_proof.round(),
+				).encode()],
+				// an equivocation report is only valid in the sessions of its set, which
+				// outlive the transaction.
+				longevity: 64,
+				propagate: false,
+			})
+		} else {
+			InvalidTransaction::Call.into()
+		}
 	}
 }
@@ -41,6 +41,7 @@ pub struct Test;
 
 impl Trait for Test {
 	type Event = TestEvent;
+	type HandleEquivocation = ();
 }
 parameter_types! {
 	pub const BlockHashCount: u64 = 250;
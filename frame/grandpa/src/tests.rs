@@ -320,6 +320,29 @@ fn time_slot_have_sane_ord() {
 	assert!(FIXTURE.windows(2).all(|f| f[0] < f[1]));
 }
 
+#[test]
+fn equivocation_report_with_the_same_votes_is_rejected() {
+	new_test_ext(vec![(1, 1), (2, 1), (3, 1)]).execute_with(|| {
+		let offender = to_authorities(vec![(1, 1)])[0].0.clone();
+		let prevote = grandpa::Prevote { target_hash: H256::repeat_byte(1), target_number: 1 };
+		let signature = fg_primitives::AuthoritySignature::default();
+		let equivocation_proof = fg_primitives::EquivocationProof::new(
+			0,
+			fg_primitives::Equivocation::Prevote(grandpa::Equivocation {
+				round_number: 1,
+				identity: offender,
+				first: (prevote.clone(), signature.clone()),
+				second: (prevote, signature),
+			}),
+		);
+
+		assert_eq!(
+			Grandpa::report_equivocation(Origin::signed(1), equivocation_proof, ()),
+			Err(Error::<Test>::InvalidEquivocationProof.into()),
+		);
+	});
+}
+
 #[test]
 #[cfg(feature = "migrate-authorities")]
 fn authorities_migration() {
@@ -60,6 +60,13 @@
 //! * `add_registrar` - Add a new registrar to the system.
 //! * `kill_identity` - Forcibly remove the associated identity; the deposit is lost.
 //!
+//! ### Attestation
+//!
+//! An identity which a registrar has judged `Reasonable` or `KnownGood` is attested to. The module
+//! implements `IdentityAttestation`, so other modules can require an attestation before an
+//! account is admitted, e.g. before a node operator can add a reserved node entry to a
+//! permissioned network.
+//!
 //! [`Call`]: ./enum.Call.html
 //! [`Trait`]: ./trait.Trait.html
 
@@ -75,6 +82,7 @@ use frame_support::{
 	decl_module, decl_event, decl_storage, ensure, decl_error,
 	traits::{Currency, ReservableCurrency, OnUnbalanced, Get},
 	weights::SimpleDispatchInfo,
+	additional_traits::IdentityAttestation,
 };
 use frame_system::{self as system, ensure_signed, ensure_root};
 
@@ -237,6 +245,14 @@ impl<
 			_ => false,
 		}
 	}
+
+	/// Returns `true` if this judgement attests to the accuracy of the identity.
+	fn is_attestation(&self) -> bool {
+		match self {
+			Judgement::Reasonable | Judgement::KnownGood => true,
+			_ => false,
+		}
+	}
 }
 
 /// The fields that we use to identify the owner of an account with. Each corresponds to a field
@@ -881,6 +897,21 @@ impl<T: Trait> Module<T> {
 			.filter_map(|a| SuperOf::<T>::get(&a).map(|x| (a, x.1)))
 			.collect()
 	}
+
+	/// Whether `who` has an identity which a current registrar has judged `Reasonable` or
+	/// `KnownGood`.
+	pub fn has_attestation(who: &T::AccountId) -> bool {
+		let registrars = <Registrars<T>>::get();
+		<IdentityOf<T>>::get(who).map_or(false, |id| id.judgements.iter().any(|(index, j)|
+			j.is_attestation() && registrars.get(*index as usize).map_or(false, Option::is_some)
+		))
+	}
+}
+
+impl<T: Trait> IdentityAttestation<T::AccountId> for Module<T> {
+	fn is_attested(who: &T::AccountId) -> bool {
+		Self::has_attestation(who)
+	}
 }
 
 #[cfg(test)]
@@ -1076,6 +1107,29 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn attestation_requires_good_judgement() {
+		new_test_ext().execute_with(|| {
+			let is_attested = |who| <Identity as IdentityAttestation<u64>>::is_attested(&who);
+			assert_ok!(Identity::add_registrar(Origin::signed(1), 3));
+			assert!(!is_attested(10));
+
+			assert_ok!(Identity::set_identity(Origin::signed(10), ten()));
+			assert_ok!(Identity::request_judgement(Origin::signed(10), 0, 10));
+			assert!(!is_attested(10));
+
+			assert_ok!(Identity::provide_judgement(Origin::signed(3), 0, 10, Judgement::Reasonable));
+			assert!(is_attested(10));
+			assert_ok!(Identity::provide_judgement(Origin::signed(3), 0, 10, Judgement::OutOfDate));
+			assert!(!is_attested(10));
+			assert_ok!(Identity::provide_judgement(Origin::signed(3), 0, 10, Judgement::KnownGood));
+			assert!(is_attested(10));
+
+			assert_ok!(Identity::clear_identity(Origin::signed(10)));
+			assert!(!is_attested(10));
+		});
+	}
+
 	#[test]
 	fn clearing_judgement_should_work() {
 		new_test_ext().execute_with(|| {
@@ -0,0 +1,295 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! # Merkle Mountain Range Module
+//!
+//! The MMR module appends a leaf committing to the parent block to a Merkle Mountain Range at the
+//! start of every block, and keeps the root of the MMR in storage. A proof of a leaf against this
+//! root shows that the block is part of the chain history, which allows light clients and bridges
+//! to verify historical inclusion while only tracking recent MMR roots.
+//!
+//! Each leaf holds the number and hash of the parent block, and the data returned by the
+//! configured [`LeafDataProvider`](./trait.LeafDataProvider.html), which can be used to commit to
+//! e.g. the doughnuts used in the block.
+//!
+//! ## Public Functions
+//!
+//! - `generate_proof` - Generate a proof of a leaf against the current root.
+//! - `verify_proof` - Verify a proof of a leaf against the current root.
+//!
+//! Runtimes use these to implement the `sp_merkle_mountain_range::MmrApi` runtime api.
+//!
+//! All nodes of the MMR are kept in storage, so its storage grows with every block.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode, FullCodec};
+use frame_support::{
+	decl_module, decl_storage,
+	weights::{Weight, SimpleDispatchInfo, WeighData},
+};
+use sp_runtime::{RuntimeDebug, traits::{Hash, One}};
+use sp_merkle_mountain_range::{self as mmr, Error, LeafIndex, NodeIndex, Proof};
+
+/// Provides the additional data committed to by every leaf.
+pub trait LeafDataProvider {
+	/// The data type.
+	type LeafData: FullCodec;
+
+	/// The data to commit to in the leaf of the parent of the block being initialized.
+	fn leaf_data() -> Self::LeafData;
+}
+
+impl LeafDataProvider for () {
+	type LeafData = ();
+
+	fn leaf_data() -> Self::LeafData {}
+}
+
+/// A leaf of the MMR.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq)]
+pub struct Leaf<BlockNumber, Hash, Data> {
+	/// The number of the block.
+	pub number: BlockNumber,
+	/// The hash of the block.
+	pub hash: Hash,
+	/// Additional data provided by the `LeafDataProvider`.
+	pub data: Data,
+}
+
+/// The leaf type of the MMR of the given runtime.
+pub type LeafOf<T> = Leaf<
+	<T as frame_system::Trait>::BlockNumber,
+	<T as frame_system::Trait>::Hash,
+	<<T as Trait>::LeafData as LeafDataProvider>::LeafData,
+>;
+
+/// The module's configuration trait.
+pub trait Trait: frame_system::Trait {
+	/// Provides the additional data of each leaf. Use `()` to only commit to block hashes.
+	type LeafData: LeafDataProvider;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Mmr {
+		/// The root of the MMR.
+		pub RootHash get(fn mmr_root_hash): T::Hash;
+
+		/// The number of leaves of the MMR.
+		pub NumberOfLeaves get(fn mmr_leaves): u64;
+
+		/// The hashes of the nodes of the MMR, by position.
+		Nodes get(fn mmr_node): map hasher(identity) NodeIndex => Option<T::Hash>;
+
+		/// The SCALE encoded leaves of the MMR, by index.
+		Leaves get(fn mmr_leaf): map hasher(identity) LeafIndex => Option<Vec<u8>>;
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		fn on_initialize(block_number: T::BlockNumber) -> Weight {
+			let leaf: LeafOf<T> = Leaf {
+				number: block_number - One::one(),
+				hash: <frame_system::Module<T>>::parent_hash(),
+				data: T::LeafData::leaf_data(),
+			};
+			Self::append(leaf);
+
+			SimpleDispatchInfo::default().weigh_data(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Generate a proof of the leaf `leaf_index` against the current root.
+	///
+	/// Returns the SCALE encoded leaf together with the proof.
+	pub fn generate_proof(leaf_index: LeafIndex) -> Result<(Vec<u8>, Proof<T::Hash>), Error> {
+		let leaf = Leaves::get(leaf_index).ok_or(Error::InvalidLeafIndex)?;
+		let proof = mmr::generate_proof::<T::Hashing>(
+			leaf_index,
+			Self::mmr_leaves(),
+			|pos| <Nodes<T>>::get(pos),
+		)?;
+		Ok((leaf, proof))
+	}
+
+	/// Verify a proof of the SCALE encoded `leaf` against the current root.
+	pub fn verify_proof(leaf: Vec<u8>, proof: Proof<T::Hash>) -> Result<(), Error> {
+		if proof.leaf_count != Self::mmr_leaves() {
+			return Err(Error::InvalidProof);
+		}
+		mmr::verify_proof::<T::Hashing>(&Self::mmr_root_hash(), T::Hashing::hash(&leaf), &proof)
+	}
+
+	fn append(leaf: LeafOf<T>) {
+		let leaf_index = Self::mmr_leaves();
+		let leaf = leaf.encode();
+		let nodes = mmr::append::<T::Hashing>(
+			leaf_index,
+			T::Hashing::hash(&leaf),
+			|pos| <Nodes<T>>::get(pos),
+		).expect("All nodes of the MMR are kept in storage; qed");
+		for (pos, node) in nodes {
+			<Nodes<T>>::insert(pos, node);
+		}
+		Leaves::insert(leaf_index, leaf);
+
+		let leaf_count = leaf_index + 1;
+		let root = mmr::root::<T::Hashing>(leaf_count, |pos| <Nodes<T>>::get(pos))
+			.expect("All nodes of the MMR are kept in storage; qed");
+		NumberOfLeaves::put(leaf_count);
+		<RootHash<T>>::put(root);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill,
+		testing::Header,
+		traits::{BlakeTwo256, Header as _, IdentityLookup},
+	};
+	use frame_support::{impl_outer_origin, parameter_types, weights::Weight, traits::OnInitialize};
+
+	#[derive(Clone, PartialEq, Eq)]
+	pub struct Test;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Call = ();
+		type Hash = H256;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type MaximumBlockLength = MaximumBlockLength;
+		type Version = ();
+		type ModuleToIndex = ();
+		type DelegatedDispatchVerifier = ();
+		type Doughnut = ();
+	}
+
+	pub struct BlockNumberData;
+
+	impl LeafDataProvider for BlockNumberData {
+		type LeafData = u64;
+
+		fn leaf_data() -> u64 {
+			System::block_number()
+		}
+	}
+
+	impl Trait for Test {
+		type LeafData = BlockNumberData;
+	}
+
+	type System = frame_system::Module<Test>;
+	type Mmr = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		t.into()
+	}
+
+	fn setup_blocks(blocks: u64) -> Vec<H256> {
+		let mut parent_hash = System::parent_hash();
+		let mut hashes = vec![parent_hash];
+
+		for i in 1 .. (blocks + 1) {
+			System::initialize(
+				&i,
+				&parent_hash,
+				&Default::default(),
+				&Default::default(),
+				frame_system::InitKind::Full,
+			);
+			Mmr::on_initialize(i);
+
+			let header = System::finalize();
+			parent_hash = header.hash();
+			hashes.push(parent_hash);
+			System::set_block_number(*header.number());
+		}
+
+		hashes
+	}
+
+	#[test]
+	fn appends_a_leaf_per_block() {
+		new_test_ext().execute_with(|| {
+			let hashes = setup_blocks(7);
+
+			assert_eq!(Mmr::mmr_leaves(), 7);
+			assert_eq!(Mmr::mmr_node(mmr::mmr_size(7)), None);
+			assert!(Mmr::mmr_node(mmr::mmr_size(7) - 1).is_some());
+
+			let leaf: LeafOf<Test> = Decode::decode(&mut &Mmr::mmr_leaf(3).unwrap()[..]).unwrap();
+			assert_eq!(leaf, Leaf { number: 3, hash: hashes[3], data: 4 });
+		});
+	}
+
+	#[test]
+	fn proofs_verify_against_current_root() {
+		new_test_ext().execute_with(|| {
+			setup_blocks(10);
+
+			for leaf_index in 0..10 {
+				let (leaf, proof) = Mmr::generate_proof(leaf_index).unwrap();
+				assert_eq!(Mmr::verify_proof(leaf.clone(), proof.clone()), Ok(()));
+				assert_eq!(
+					mmr::verify_proof::<BlakeTwo256>(
+						&Mmr::mmr_root_hash(),
+						BlakeTwo256::hash(&leaf),
+						&proof,
+					),
+					Ok(()),
+				);
+			}
+
+			let (_, proof) = Mmr::generate_proof(2).unwrap();
+			let (other_leaf, _) = Mmr::generate_proof(3).unwrap();
+			assert_eq!(Mmr::verify_proof(other_leaf, proof.clone()), Err(Error::Verify));
+			assert_eq!(Mmr::generate_proof(10), Err(Error::InvalidLeafIndex));
+
+			Mmr::on_initialize(11);
+			let (leaf, _) = Mmr::generate_proof(2).unwrap();
+			assert_eq!(Mmr::verify_proof(leaf, proof), Err(Error::InvalidProof));
+		});
+	}
+}
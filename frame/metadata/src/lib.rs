@@ -323,6 +323,24 @@ pub struct StorageMetadata {
 #[cfg_attr(feature = "std", derive(Decode, Serialize))]
 pub struct RuntimeMetadataPrefixed(pub u32, pub RuntimeMetadata);
 
+/// Metadata of a signed extension of the extrinsic.
+///
+/// The doughnut extension, `PlugDoughnutSignedExtension`, is an optional `Doughnut` in its
+/// versioned binary encoding, with no additional signed data.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct SignedExtensionMetadata {
+	/// The unique identifier of the extension.
+	pub identifier: DecodeDifferentStr,
+	/// The type of the data of the extension in the extrinsic.
+	pub ty: DecodeDifferentStr,
+	/// The type of the additional signed data of the extension, which is signed but not part of
+	/// the extrinsic.
+	pub additional_signed: DecodeDifferentStr,
+	/// Whether the data of the extension is optional, encoded as an `Option` of `ty`.
+	pub optional: bool,
+}
+
 /// Metadata of the extrinsic used by the runtime.
 #[derive(Eq, Encode, PartialEq, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Decode, Serialize))]
@@ -330,7 +348,7 @@ pub struct ExtrinsicMetadata {
 	/// Extrinsic version.
 	pub version: u8,
 	/// The signed extensions in the order they appear in the extrinsic.
-	pub signed_extensions: Vec<DecodeDifferentStr>,
+	pub signed_extensions: Vec<SignedExtensionMetadata>,
 }
 
 /// The metadata of a runtime.
@@ -361,8 +379,13 @@ pub enum RuntimeMetadata {
 	V9(RuntimeMetadataDeprecated),
 	/// Version 10 for runtime metadata. No longer used.
 	V10(RuntimeMetadataDeprecated),
-	/// Version 11 for runtime metadata.
+	/// Version 11 for runtime metadata. No longer produced, but still decoded.
 	V11(RuntimeMetadataV11),
+	/// Version 12 for runtime metadata. Never used, the upstream Substrate layout of version 12
+	/// differs from ours.
+	V12(RuntimeMetadataDeprecated),
+	/// Version 13 for runtime metadata.
+	V13(RuntimeMetadataV13),
 }
 
 /// Enum that should fail.
@@ -383,10 +406,42 @@ impl Decode for RuntimeMetadataDeprecated {
 	}
 }
 
-/// The metadata of a runtime.
+/// The metadata of a runtime, version 11.
 #[derive(Eq, Encode, PartialEq, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Decode, Serialize))]
 pub struct RuntimeMetadataV11 {
+	/// Metadata of all the modules.
+	pub modules: DecodeDifferentArray<ModuleMetadataV11>,
+	/// Metadata of the extrinsic.
+	pub extrinsic: ExtrinsicMetadataV11,
+}
+
+/// Metadata of the extrinsic used by the runtime, version 11.
+#[derive(Eq, Encode, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct ExtrinsicMetadataV11 {
+	/// Extrinsic version.
+	pub version: u8,
+	/// The identifiers of the signed extensions in the order they appear in the extrinsic.
+	pub signed_extensions: Vec<DecodeDifferentStr>,
+}
+
+/// All metadata about an runtime module, version 11.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct ModuleMetadataV11 {
+	pub name: DecodeDifferentStr,
+	pub storage: Option<DecodeDifferent<FnEncode<StorageMetadata>, StorageMetadata>>,
+	pub calls: ODFnA<FunctionMetadata>,
+	pub event: ODFnA<EventMetadata>,
+	pub constants: DFnA<ModuleConstantMetadata>,
+	pub errors: DFnA<ErrorMetadata>,
+}
+
+/// The metadata of a runtime.
+#[derive(Eq, Encode, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct RuntimeMetadataV13 {
 	/// Metadata of all the modules.
 	pub modules: DecodeDifferentArray<ModuleMetadata>,
 	/// Metadata of the extrinsic.
@@ -394,7 +449,7 @@ pub struct RuntimeMetadataV11 {
 }
 
 /// The latest version of the metadata.
-pub type RuntimeMetadataLastVersion = RuntimeMetadataV11;
+pub type RuntimeMetadataLastVersion = RuntimeMetadataV13;
 
 /// All metadata about an runtime module.
 #[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
@@ -406,6 +461,23 @@ pub struct ModuleMetadata {
 	pub event: ODFnA<EventMetadata>,
 	pub constants: DFnA<ModuleConstantMetadata>,
 	pub errors: DFnA<ErrorMetadata>,
+	/// The doughnut permissions the delegated calls of the module are verified against, `None`
+	/// if the runtime doesn't verify the doughnuts of delegated calls or the module has no calls.
+	pub permissions: DecodeDifferent<FnEncode<Option<PermissionMetadata>>, Option<PermissionMetadata>>,
+}
+
+/// The doughnut permissions the delegated calls of a module are verified against.
+///
+/// A doughnut authorizes a call of the module if the permissions of its `domain` grant the call,
+/// named as in the module's calls metadata, of `module`.
+#[derive(Clone, PartialEq, Eq, Encode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Decode, Serialize))]
+pub struct PermissionMetadata {
+	/// The permission domain of the doughnut, shared by the modules of a runtime.
+	pub domain: DecodeDifferentStr,
+	/// The name of the module in the permissions, the name of its crate. Instances of a module
+	/// share it.
+	pub module: DecodeDifferentStr,
 }
 
 type ODFnA<T> = Option<DFnA<T>>;
@@ -419,6 +491,6 @@ impl Into<sp_core::OpaqueMetadata> for RuntimeMetadataPrefixed {
 
 impl Into<RuntimeMetadataPrefixed> for RuntimeMetadataLastVersion {
 	fn into(self) -> RuntimeMetadataPrefixed {
-		RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V11(self))
+		RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V13(self))
 	}
 }
@@ -0,0 +1,162 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Multisig pallet benchmarking.
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account};
+use sp_runtime::traits::Bounded;
+
+use crate::Module as Multisig;
+
+const SEED: u32 = 0;
+
+// The maximum length of the call we will test.
+const MAX_CALL_LEN: u32 = 10_000;
+
+// Create `s + 1` funded signatories, sorted, and a call of length about `z`.
+fn setup_multi<T: Trait>(s: u32, z: u32) -> (Vec<T::AccountId>, Box<<T as Trait>::Call>) {
+	let mut signatories: Vec<T::AccountId> = Vec::new();
+	for i in 0 .. s + 1 {
+		let signatory = account("signatory", i, SEED);
+		let _ = T::Currency::make_free_balance_be(&signatory, BalanceOf::<T>::max_value());
+		signatories.push(signatory);
+	}
+	signatories.sort();
+	let call: <T as Trait>::Call = system::Call::<T>::remark(vec![0; z as usize]).into();
+	(signatories, Box::new(call))
+}
+
+// Split `signatories` into the one at `index` and the other signatories.
+fn split<T: Trait>(signatories: &[T::AccountId], index: u32) -> (T::AccountId, Vec<T::AccountId>) {
+	let mut others = signatories.to_vec();
+	let who = others.remove(index as usize);
+	(who, others)
+}
+
+// Approve the hash of `call` by the first `n` of `signatories`, the first of which stores `call`
+// if `store_call` is set.
+fn approve<T: Trait>(
+	signatories: &[T::AccountId],
+	threshold: u16,
+	n: u32,
+	call: &Box<<T as Trait>::Call>,
+	store_call: bool,
+) -> Result<(), &'static str> {
+	let call_hash = call.using_encoded(blake2_256);
+	let (first, others) = split::<T>(signatories, 0);
+	Multisig::<T>::as_multi(
+		RawOrigin::Signed(first).into(), threshold, others, None, call.clone(), store_call,
+	)?;
+	for i in 1 .. n {
+		let (who, others) = split::<T>(signatories, i);
+		Multisig::<T>::approve_as_multi(
+			RawOrigin::Signed(who).into(),
+			threshold,
+			others,
+			Some(Multisig::<T>::timepoint()),
+			call_hash,
+			0,
+		)?;
+	}
+	Ok(())
+}
+
+benchmarks! {
+	_ { }
+
+	as_multi_threshold_1 {
+		let z in 0 .. MAX_CALL_LEN;
+		let (signatories, call) = setup_multi::<T>(1, z);
+		let (caller, others) = split::<T>(&signatories, 0);
+	}: _(RawOrigin::Signed(caller), others, call)
+
+	as_multi_create {
+		let s in 1 .. T::MaxSignatories::get() as u32 - 1;
+		let z in 0 .. MAX_CALL_LEN;
+		let (signatories, call) = setup_multi::<T>(s, z);
+		let (caller, others) = split::<T>(&signatories, 0);
+	}: as_multi(RawOrigin::Signed(caller), 2, others, None, call, false)
+
+	as_multi_create_store {
+		let s in 1 .. T::MaxSignatories::get() as u32 - 1;
+		let z in 0 .. MAX_CALL_LEN;
+		let (signatories, call) = setup_multi::<T>(s, z);
+		let (caller, others) = split::<T>(&signatories, 0);
+	}: as_multi(RawOrigin::Signed(caller), 2, others, None, call, true)
+
+	as_multi_approve {
+		let s in 2 .. T::MaxSignatories::get() as u32 - 1;
+		let z in 0 .. MAX_CALL_LEN;
+		let (signatories, call) = setup_multi::<T>(s, z);
+		approve::<T>(&signatories, 3, 1, &call, false)?;
+		let (caller, others) = split::<T>(&signatories, 1);
+		let timepoint = Multisig::<T>::timepoint();
+	}: as_multi(RawOrigin::Signed(caller), 3, others, Some(timepoint), call, false)
+
+	as_multi_complete {
+		let s in 2 .. T::MaxSignatories::get() as u32 - 1;
+		let z in 0 .. MAX_CALL_LEN;
+		let (signatories, call) = setup_multi::<T>(s, z);
+		let threshold = s as u16 + 1;
+		approve::<T>(&signatories, threshold, s, &call, false)?;
+		let (caller, others) = split::<T>(&signatories, s);
+		let timepoint = Multisig::<T>::timepoint();
+	}: as_multi(RawOrigin::Signed(caller), threshold, others, Some(timepoint), call, false)
+
+	approve_as_multi_create {
+		let s in 1 .. T::MaxSignatories::get() as u32 - 1;
+		let (signatories, call) = setup_multi::<T>(s, 0);
+		let call_hash = call.using_encoded(blake2_256);
+		let (caller, others) = split::<T>(&signatories, 0);
+	}: approve_as_multi(RawOrigin::Signed(caller), 2, others, None, call_hash, 0)
+
+	approve_as_multi_approve {
+		let s in 2 .. T::MaxSignatories::get() as u32 - 1;
+		let (signatories, call) = setup_multi::<T>(s, 0);
+		let call_hash = call.using_encoded(blake2_256);
+		approve::<T>(&signatories, 3, 1, &call, false)?;
+		let (caller, others) = split::<T>(&signatories, 1);
+		let timepoint = Multisig::<T>::timepoint();
+	}: approve_as_multi(RawOrigin::Signed(caller), 3, others, Some(timepoint), call_hash, 0)
+
+	approve_as_multi_complete {
+		let s in 2 .. T::MaxSignatories::get() as u32 - 1;
+		let (signatories, call) = setup_multi::<T>(s, MAX_CALL_LEN);
+		let call_hash = call.using_encoded(blake2_256);
+		let max_weight = call.get_dispatch_info().weight;
+		let threshold = s as u16 + 1;
+		approve::<T>(&signatories, threshold, s, &call, true)?;
+		let (caller, others) = split::<T>(&signatories, s);
+		let timepoint = Multisig::<T>::timepoint();
+	}: approve_as_multi(
+		RawOrigin::Signed(caller), threshold, others, Some(timepoint), call_hash, max_weight
+	)
+
+	cancel_as_multi {
+		let s in 1 .. T::MaxSignatories::get() as u32 - 1;
+		let (signatories, call) = setup_multi::<T>(s, MAX_CALL_LEN);
+		let call_hash = call.using_encoded(blake2_256);
+		approve::<T>(&signatories, 2, 1, &call, true)?;
+		let (caller, others) = split::<T>(&signatories, 0);
+		let timepoint = Multisig::<T>::timepoint();
+	}: _(RawOrigin::Signed(caller), 2, others, timepoint, call_hash)
+}
@@ -0,0 +1,95 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Default weights of the multisig module, counted from the storage accesses and the hashing of
+//! each dispatchable with the reference timings of `benchmark calibrate`, where a unit of weight
+//! is a nanosecond. A runtime may replace them with the results of running the module's
+//! benchmarks on its own hardware.
+//!
+//! Reserving or unreserving a deposit reads and writes the account of the depositor, and every
+//! dispatchable but `as_multi_threshold_1` writes an event.
+
+use frame_support::weights::Weight;
+
+/// Reading a value of the state.
+const READ: Weight = 25_000;
+
+/// Writing a value of the state.
+const WRITE: Weight = 100_000;
+
+/// Hashing a byte with blake2-256, 1.2µs per KiB rounded up. A stored call is hashed once more by
+/// the trie when it is written.
+const HASH_BYTE: Weight = 2;
+
+/// Hashing a signatory into the multisig account and keeping it in the approvals, rounded up.
+const SIGNATORY: Weight = 100;
+
+/// The execution of a dispatchable besides its storage accesses and hashing.
+const BASE: Weight = 10_000;
+
+impl crate::WeightInfo for () {
+	// The call is neither hashed nor stored.
+	fn as_multi_threshold_1(_z: u32) -> Weight {
+		BASE
+	}
+	// Reads the operation and the depositor, writes both and the event.
+	fn as_multi_create(s: u32, z: u32) -> Weight {
+		(BASE + 2 * READ + 3 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+			.saturating_add((z as Weight).saturating_mul(HASH_BYTE))
+	}
+	// As `as_multi_create`, and reads and writes the stored call.
+	fn as_multi_create_store(s: u32, z: u32) -> Weight {
+		(BASE + 3 * READ + 4 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+			.saturating_add((z as Weight).saturating_mul(2 * HASH_BYTE))
+	}
+	// Reads the operation, the stored call and the sender, writes them and the event.
+	fn as_multi_approve(s: u32, z: u32) -> Weight {
+		(BASE + 3 * READ + 4 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+			.saturating_add((z as Weight).saturating_mul(2 * HASH_BYTE))
+	}
+	// Reads the operation, the stored call and both depositors, removes the operation and the
+	// stored call, writes the depositors and the event.
+	fn as_multi_complete(s: u32, z: u32) -> Weight {
+		(BASE + 4 * READ + 5 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+			.saturating_add((z as Weight).saturating_mul(HASH_BYTE))
+	}
+	// Reads the operation and the depositor, writes both and the event.
+	fn approve_as_multi_create(s: u32) -> Weight {
+		(BASE + 2 * READ + 3 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+	}
+	// Reads the operation, writes it and the event.
+	fn approve_as_multi_approve(s: u32) -> Weight {
+		(BASE + READ + 2 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+	}
+	// As `as_multi_complete`, but the call is decoded from storage instead of hashed.
+	fn approve_as_multi_complete(s: u32) -> Weight {
+		(BASE + 4 * READ + 5 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+	}
+	// Reads the operation, the stored call and both depositors, removes the operation and the
+	// stored call, writes the depositors and the event.
+	fn cancel_as_multi(s: u32) -> Weight {
+		(BASE + 4 * READ + 5 * WRITE)
+			.saturating_add((s as Weight).saturating_mul(SIGNATORY))
+	}
+}
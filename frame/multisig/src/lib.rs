@@ -0,0 +1,995 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! # Multisig Module
+//!
+//! A module for dispatching calls from an account controlled by a threshold of signatories.
+//!
+//! ## Overview
+//!
+//! A multisig account is derived deterministically from a set of signatories and the number of
+//! them, the threshold, which must approve a call before it is dispatched from that account. No
+//! single key can then move the funds of, e.g., a treasury or an admin account.
+//!
+//! The first approval of a call opens a multisig operation and reserves a deposit from its
+//! depositor, which is returned when the call is dispatched or the operation is cancelled. The
+//! call itself may be stored on-chain with the approval, so the other signatories only need to
+//! approve its hash; the final approval then dispatches the stored call. Storing a call reserves
+//! an additional deposit, proportional to its length, from the account which stored it.
+//!
+//! This module replaces the multisig dispatch of the utility module. It derives the same multisig
+//! accounts, and moves the operations left open in the utility module into its own storage on the
+//! runtime upgrade which introduces it.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `as_multi_threshold_1` - Dispatch a call from a multisig account with a threshold of one.
+//! * `as_multi` - Approve, and if possible dispatch, a call from a multisig account, optionally
+//!   storing the call.
+//! * `approve_as_multi` - Approve a call by its hash, dispatching it if it is stored and this is
+//!   the final approval.
+//! * `cancel_as_multi` - Cancel a multisig operation, returning its deposits.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+mod benchmarking;
+mod default_weights;
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_io::hashing::blake2_256;
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, Parameter, ensure, RuntimeDebug,
+};
+use frame_support::{
+	traits::{Get, ReservableCurrency, Currency},
+	weights::{GetDispatchInfo, DispatchClass, FunctionOf, Weight},
+};
+use frame_system::{self as system, ensure_signed, RawOrigin};
+use sp_runtime::{DispatchError, DispatchResult, traits::{Dispatchable, Saturating, Zero}};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// An encoded call. It should decode to the runtime's `Call`.
+pub type OpaqueCall = Vec<u8>;
+
+/// The hash of a call.
+pub type CallHash = [u8; 32];
+
+/// The weights of the dispatchable functions of this module.
+///
+/// `s` is the number of other signatories and `z` the length of the encoded call. The weight of
+/// a dispatched call is accounted for separately.
+pub trait WeightInfo {
+	fn as_multi_threshold_1(z: u32) -> Weight;
+	fn as_multi_create(s: u32, z: u32) -> Weight;
+	fn as_multi_create_store(s: u32, z: u32) -> Weight;
+	fn as_multi_approve(s: u32, z: u32) -> Weight;
+	fn as_multi_complete(s: u32, z: u32) -> Weight;
+	fn approve_as_multi_create(s: u32) -> Weight;
+	fn approve_as_multi_approve(s: u32) -> Weight;
+	fn approve_as_multi_complete(s: u32) -> Weight;
+	fn cancel_as_multi(s: u32) -> Weight;
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The overarching call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo
+		+ From<system::Call<Self>>;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The base amount of currency reserved for opening a multisig operation, or for storing a
+	/// call.
+	type DepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency reserved per unit threshold when opening a multisig operation, and
+	/// per 32 bytes of a stored call.
+	type DepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum number of signatories allowed in a multisig.
+	type MaxSignatories: Get<u16>;
+
+	/// The weights of the dispatchable functions of this module.
+	type WeightInfo: WeightInfo;
+}
+
+/// A global extrinsic index, formed as the extrinsic index within a block, together with that
+/// block's height. This allows the transaction which opened a multisig operation to be uniquely
+/// identified.
+#[derive(Copy, Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Timepoint<BlockNumber> {
+	/// The height of the chain at the point in time.
+	pub height: BlockNumber,
+	/// The index of the extrinsic at the point in time.
+	pub index: u32,
+}
+
+/// An open multisig operation.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, Default, RuntimeDebug)]
+pub struct Multisig<BlockNumber, Balance, AccountId> {
+	/// The extrinsic when the multisig operation was opened.
+	when: Timepoint<BlockNumber>,
+	/// The amount held in reserve of the `depositor`, to be returned once the operation ends.
+	deposit: Balance,
+	/// The account who opened it (i.e. the first to approve it).
+	depositor: AccountId,
+	/// The approvals achieved so far, including the depositor. Always sorted.
+	approvals: Vec<AccountId>,
+}
+
+/// A call given in full, or only by its hash.
+enum CallOrHash<Call> {
+	/// The call, and whether it should be stored if it is not dispatched.
+	Call(Box<Call>, bool),
+	/// The hash of the call, and the maximum weight of the call if it is stored and dispatched.
+	Hash(CallHash, Weight),
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Multisig {
+		/// The set of open multisig operations.
+		pub Multisigs get(fn multisigs): double_map
+			hasher(twox_64_concat) T::AccountId, hasher(blake2_128_concat) CallHash
+			=> Option<Multisig<T::BlockNumber, BalanceOf<T>, T::AccountId>>;
+
+		/// The calls stored for open multisig operations, with the account which stored each and
+		/// the amount reserved from it.
+		pub Calls get(fn calls):
+			map hasher(identity) CallHash => Option<(OpaqueCall, T::AccountId, BalanceOf<T>)>;
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// Threshold must be 2 or greater.
+		MinimumThreshold,
+		/// Call is already approved by this signatory.
+		AlreadyApproved,
+		/// There are too few signatories in the list.
+		TooFewSignatories,
+		/// There are too many signatories in the list.
+		TooManySignatories,
+		/// The signatories were provided out of order; they should be ordered.
+		SignatoriesOutOfOrder,
+		/// The sender was contained in the other signatories; it shouldn't be.
+		SenderInSignatories,
+		/// Multisig operation not found when attempting to cancel.
+		NotFound,
+		/// Only the account that originally created the multisig is able to cancel it.
+		NotOwner,
+		/// No timepoint was given, yet the multisig operation is already underway.
+		NoTimepoint,
+		/// A different timepoint was given to the multisig operation that is underway.
+		WrongTimepoint,
+		/// A timepoint was given, yet no multisig operation is underway.
+		UnexpectedTimepoint,
+		/// The stored call is heavier than the maximum weight given.
+		WeightTooLow,
+	}
+}
+
+decl_event! {
+	/// Events type.
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		BlockNumber = <T as system::Trait>::BlockNumber,
+		CallHash = [u8; 32]
+	{
+		/// A new multisig operation has begun (approving account, multisig account, call hash).
+		NewMultisig(AccountId, AccountId, CallHash),
+		/// A multisig operation has been approved by someone (approving account, timepoint,
+		/// multisig account, call hash).
+		MultisigApproval(AccountId, Timepoint<BlockNumber>, AccountId, CallHash),
+		/// A multisig operation has been executed (approving account, timepoint, multisig
+		/// account, call hash, result).
+		MultisigExecuted(AccountId, Timepoint<BlockNumber>, AccountId, CallHash, DispatchResult),
+		/// A multisig operation has been cancelled (cancelling account, timepoint, multisig
+		/// account, call hash).
+		MultisigCancelled(AccountId, Timepoint<BlockNumber>, AccountId, CallHash),
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The base amount of currency reserved for opening a multisig operation, or for storing
+		/// a call.
+		const DepositBase: BalanceOf<T> = T::DepositBase::get();
+
+		/// The amount of currency reserved per unit threshold when opening a multisig operation,
+		/// and per 32 bytes of a stored call.
+		const DepositFactor: BalanceOf<T> = T::DepositFactor::get();
+
+		/// The maximum number of signatories allowed in a multisig.
+		const MaxSignatories: u16 = T::MaxSignatories::get();
+
+		/// Deposit one of this module's events by using the default implementation.
+		fn deposit_event() = default;
+
+		fn on_runtime_upgrade() -> Weight {
+			migration::migrate::<T>()
+		}
+
+		/// Immediately dispatch a call from a multisig account with a threshold of one.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `other_signatories`: The accounts (other than the sender) who are part of the
+		/// multisig. May not be empty.
+		/// - `call`: The call to be executed.
+		///
+		/// Result is equivalent to the dispatched result.
+		///
+		/// # <weight>
+		/// - `T::WeightInfo::as_multi_threshold_1` + the weight of the `call`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<T::AccountId>, &Box<<T as Trait>::Call>)| {
+				let z = args.1.using_encoded(|call| call.len() as u32);
+				T::WeightInfo::as_multi_threshold_1(z)
+					.saturating_add(args.1.get_dispatch_info().weight)
+			},
+			|args: (&Vec<T::AccountId>, &Box<<T as Trait>::Call>)| {
+				args.1.get_dispatch_info().class
+			},
+			true
+		)]
+		fn as_multi_threshold_1(origin,
+			other_signatories: Vec<T::AccountId>,
+			call: Box<<T as Trait>::Call>,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let signatories = Self::ensure_signatories(other_signatories, who)?;
+			let id = Self::multi_account_id(&signatories, 1);
+			call.dispatch(RawOrigin::Signed(id).into())
+		}
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold - 1` of `other_signatories`.
+		///
+		/// If there are enough, then dispatch the call.
+		///
+		/// Payment: `DepositBase` will be reserved if this is the first approval, plus `threshold`
+		/// times `DepositFactor`. It is returned once this dispatch happens or is cancelled. If
+		/// the call is stored, `DepositBase` plus `DepositFactor` per 32 bytes of the call is
+		/// reserved too, and returned on the same terms.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `threshold`: The total number of approvals for this dispatch before it is executed.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		/// dispatch. May not be empty.
+		/// - `maybe_timepoint`: If this is the first approval, then this must be `None`. If it is
+		/// not the first approval, then it must be `Some`, with the timepoint (block number and
+		/// transaction index) of the first approval transaction.
+		/// - `call`: The call to be executed.
+		/// - `store_call`: Whether to store the call if it is not dispatched, so the remaining
+		/// approvals can be given with `approve_as_multi`.
+		///
+		/// Result is `Ok` on success, and the result from the interior call, if it was executed,
+		/// may be found in the deposited `MultisigExecuted` event.
+		///
+		/// # <weight>
+		/// - `T::WeightInfo::as_multi_create` or `as_multi_create_store` for the first approval,
+		///   otherwise the greater of `as_multi_approve` and `as_multi_complete`.
+		/// - The weight of the `call`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (
+				&u16,
+				&Vec<T::AccountId>,
+				&Option<Timepoint<T::BlockNumber>>,
+				&Box<<T as Trait>::Call>,
+				&bool,
+			)| {
+				let s = args.1.len() as u32;
+				let z = args.3.using_encoded(|call| call.len() as u32);
+				let operate = match (args.2, args.4) {
+					(None, false) => T::WeightInfo::as_multi_create(s, z),
+					(None, true) => T::WeightInfo::as_multi_create_store(s, z),
+					(Some(_), _) => T::WeightInfo::as_multi_approve(s, z)
+						.max(T::WeightInfo::as_multi_complete(s, z)),
+				};
+				operate.saturating_add(args.3.get_dispatch_info().weight)
+			},
+			|args: (
+				&u16,
+				&Vec<T::AccountId>,
+				&Option<Timepoint<T::BlockNumber>>,
+				&Box<<T as Trait>::Call>,
+				&bool,
+			)| {
+				args.3.get_dispatch_info().class
+			},
+			true
+		)]
+		fn as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call: Box<<T as Trait>::Call>,
+			store_call: bool,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let call_or_hash = CallOrHash::Call(call, store_call);
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, call_or_hash)
+		}
+
+		/// Register approval for a dispatch to be made from a deterministic composite account if
+		/// approved by a total of `threshold - 1` of `other_signatories`.
+		///
+		/// If this is the final approval and the call was stored, then dispatch it.
+		///
+		/// Payment: `DepositBase` will be reserved if this is the first approval, plus `threshold`
+		/// times `DepositFactor`. It is returned once this dispatch happens or is cancelled.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// - `threshold`: The total number of approvals for this dispatch before it is executed.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		/// dispatch. May not be empty.
+		/// - `maybe_timepoint`: If this is the first approval, then this must be `None`. If it is
+		/// not the first approval, then it must be `Some`, with the timepoint (block number and
+		/// transaction index) of the first approval transaction.
+		/// - `call_hash`: The hash of the call to be executed.
+		/// - `max_weight`: The maximum weight of the stored call, which is paid for upfront.
+		///
+		/// NOTE: If this is the final approval and the call was not stored, you will want to use
+		/// `as_multi` instead.
+		///
+		/// # <weight>
+		/// - `T::WeightInfo::approve_as_multi_create` for the first approval, otherwise the greater
+		///   of `approve_as_multi_approve` and `approve_as_multi_complete`, plus `max_weight`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (
+				&u16,
+				&Vec<T::AccountId>,
+				&Option<Timepoint<T::BlockNumber>>,
+				&CallHash,
+				&Weight,
+			)| {
+				let s = args.1.len() as u32;
+				match args.2 {
+					None => T::WeightInfo::approve_as_multi_create(s),
+					Some(_) => T::WeightInfo::approve_as_multi_approve(s)
+						.max(T::WeightInfo::approve_as_multi_complete(s))
+						.saturating_add(*args.4),
+				}
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn approve_as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+			call_hash: CallHash,
+			max_weight: Weight,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let call_or_hash = CallOrHash::Hash(call_hash, max_weight);
+			Self::operate(who, threshold, other_signatories, maybe_timepoint, call_or_hash)
+		}
+
+		/// Cancel a pre-existing, on-going multisig transaction. Any deposit reserved previously
+		/// for this operation, or for storing its call, will be unreserved on success.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the account which opened the
+		/// operation.
+		///
+		/// - `threshold`: The total number of approvals for this dispatch before it is executed.
+		/// - `other_signatories`: The accounts (other than the sender) who can approve this
+		/// dispatch. May not be empty.
+		/// - `timepoint`: The timepoint (block number and transaction index) of the first approval
+		/// transaction for this dispatch.
+		/// - `call_hash`: The hash of the call to be executed.
+		///
+		/// # <weight>
+		/// - `T::WeightInfo::cancel_as_multi`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&u16, &Vec<T::AccountId>, &Timepoint<T::BlockNumber>, &CallHash)| {
+				T::WeightInfo::cancel_as_multi(args.1.len() as u32)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn cancel_as_multi(origin,
+			threshold: u16,
+			other_signatories: Vec<T::AccountId>,
+			timepoint: Timepoint<T::BlockNumber>,
+			call_hash: CallHash,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(threshold >= 2, Error::<T>::MinimumThreshold);
+			let signatories = Self::ensure_signatories(other_signatories, who.clone())?;
+			let id = Self::multi_account_id(&signatories, threshold);
+
+			let m = <Multisigs<T>>::get(&id, call_hash).ok_or(Error::<T>::NotFound)?;
+			ensure!(m.when == timepoint, Error::<T>::WrongTimepoint);
+			ensure!(m.depositor == who, Error::<T>::NotOwner);
+
+			let _ = T::Currency::unreserve(&m.depositor, m.deposit);
+			<Multisigs<T>>::remove(&id, call_hash);
+			Self::clear_call(&call_hash);
+
+			Self::deposit_event(RawEvent::MultisigCancelled(who, timepoint, id, call_hash));
+			Ok(())
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Derive a multi-account ID from the sorted list of accounts and the threshold that are
+	/// required.
+	///
+	/// NOTE: `who` must be sorted. If it is not, then you'll get the wrong answer.
+	///
+	/// The derivation is the one of the utility module, which provided multisig dispatch before
+	/// this module, so that the multisig accounts it derived keep their funds.
+	pub fn multi_account_id(who: &[T::AccountId], threshold: u16) -> T::AccountId {
+		let entropy = (b"modlpy/utilisuba", who, threshold).using_encoded(blake2_256);
+		T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+	}
+
+	/// The current `Timepoint`.
+	pub fn timepoint() -> Timepoint<T::BlockNumber> {
+		Timepoint {
+			height: <system::Module<T>>::block_number(),
+			index: <system::Module<T>>::extrinsic_index().unwrap_or_default(),
+		}
+	}
+
+	/// Approve the call given by `call_or_hash` as `who`, and dispatch it if this was the final
+	/// approval and the call is available.
+	fn operate(
+		who: T::AccountId,
+		threshold: u16,
+		other_signatories: Vec<T::AccountId>,
+		maybe_timepoint: Option<Timepoint<T::BlockNumber>>,
+		call_or_hash: CallOrHash<<T as Trait>::Call>,
+	) -> DispatchResult {
+		ensure!(threshold >= 2, Error::<T>::MinimumThreshold);
+		let signatories = Self::ensure_signatories(other_signatories, who.clone())?;
+		let id = Self::multi_account_id(&signatories, threshold);
+
+		let (call_hash, maybe_call, store_call, max_weight) = match call_or_hash {
+			CallOrHash::Call(call, store_call) => {
+				let call_hash = call.using_encoded(blake2_256);
+				let weight = call.get_dispatch_info().weight;
+				(call_hash, Some(call), store_call, weight)
+			},
+			CallOrHash::Hash(call_hash, max_weight) => (call_hash, None, false, max_weight),
+		};
+
+		if let Some(mut m) = <Multisigs<T>>::get(&id, call_hash) {
+			let timepoint = maybe_timepoint.ok_or(Error::<T>::NoTimepoint)?;
+			ensure!(m.when == timepoint, Error::<T>::WrongTimepoint);
+
+			let maybe_pos = m.approvals.binary_search(&who).err();
+			let approvals = m.approvals.len() + maybe_pos.is_some() as usize;
+			let maybe_call = if approvals >= threshold as usize {
+				match maybe_call {
+					Some(call) => Some(call),
+					None => Self::stored_call(&call_hash, max_weight)?,
+				}
+			} else {
+				maybe_call
+			};
+
+			match maybe_call {
+				Some(call) if approvals >= threshold as usize => {
+					let result = call.dispatch(RawOrigin::Signed(id.clone()).into());
+					let _ = T::Currency::unreserve(&m.depositor, m.deposit);
+					<Multisigs<T>>::remove(&id, call_hash);
+					Self::clear_call(&call_hash);
+					Self::deposit_event(
+						RawEvent::MultisigExecuted(who, timepoint, id, call_hash, result)
+					);
+				},
+				maybe_call => {
+					let maybe_store = match maybe_call {
+						Some(call) if store_call => Self::call_to_store(&call_hash, &call),
+						_ => None,
+					};
+					// Storing the call is the only thing left to do for an existing approval.
+					ensure!(
+						maybe_pos.is_some() || maybe_store.is_some(),
+						Error::<T>::AlreadyApproved,
+					);
+					if let Some((data, call_deposit)) = maybe_store {
+						T::Currency::reserve(&who, call_deposit)?;
+						<Calls<T>>::insert(call_hash, (data, who.clone(), call_deposit));
+					}
+					if let Some(pos) = maybe_pos {
+						m.approvals.insert(pos, who.clone());
+						<Multisigs<T>>::insert(&id, call_hash, m);
+						Self::deposit_event(
+							RawEvent::MultisigApproval(who, timepoint, id, call_hash)
+						);
+					}
+				},
+			}
+		} else {
+			ensure!(maybe_timepoint.is_none(), Error::<T>::UnexpectedTimepoint);
+			let deposit = T::DepositBase::get() + T::DepositFactor::get() * threshold.into();
+			let maybe_store = match maybe_call {
+				Some(call) if store_call => Self::call_to_store(&call_hash, &call),
+				_ => None,
+			};
+			let call_deposit = maybe_store.as_ref().map_or_else(Zero::zero, |(_, d)| *d);
+			T::Currency::reserve(&who, deposit.saturating_add(call_deposit))?;
+			if let Some((data, call_deposit)) = maybe_store {
+				<Calls<T>>::insert(call_hash, (data, who.clone(), call_deposit));
+			}
+			<Multisigs<T>>::insert(&id, call_hash, Multisig {
+				when: Self::timepoint(),
+				deposit,
+				depositor: who.clone(),
+				approvals: vec![who.clone()],
+			});
+			Self::deposit_event(RawEvent::NewMultisig(who, id, call_hash));
+		}
+		Ok(())
+	}
+
+	/// The encoded `call` and the deposit for storing it, unless it is already stored.
+	fn call_to_store(
+		call_hash: &CallHash,
+		call: &<T as Trait>::Call,
+	) -> Option<(OpaqueCall, BalanceOf<T>)> {
+		if <Calls<T>>::contains_key(call_hash) {
+			return None;
+		}
+		let data = call.encode();
+		let deposit = T::DepositBase::get()
+			+ T::DepositFactor::get() * (((data.len() + 31) / 32) as u32).into();
+		Some((data, deposit))
+	}
+
+	/// The stored call with `call_hash`, if any. It is an error for it to be heavier than
+	/// `max_weight`.
+	fn stored_call(
+		call_hash: &CallHash,
+		max_weight: Weight,
+	) -> Result<Option<Box<<T as Trait>::Call>>, DispatchError> {
+		let maybe_call = <Calls<T>>::get(call_hash)
+			.and_then(|(data, _, _)| <T as Trait>::Call::decode(&mut &data[..]).ok());
+		if let Some(ref call) = maybe_call {
+			ensure!(call.get_dispatch_info().weight <= max_weight, Error::<T>::WeightTooLow);
+		}
+		Ok(maybe_call.map(Box::new))
+	}
+
+	/// Remove the stored call with `call_hash`, if any, returning its deposit.
+	fn clear_call(call_hash: &CallHash) {
+		if let Some((_, depositor, deposit)) = <Calls<T>>::take(call_hash) {
+			let _ = T::Currency::unreserve(&depositor, deposit);
+		}
+	}
+
+	/// Check that `other_signatories` is not empty, is sorted and doesn't contain `who`, and
+	/// that the signatories are not too many. Return the signatories with `who` inserted.
+	fn ensure_signatories(other_signatories: Vec<T::AccountId>, who: T::AccountId)
+		-> Result<Vec<T::AccountId>, DispatchError>
+	{
+		let max_sigs = T::MaxSignatories::get() as usize;
+		ensure!(!other_signatories.is_empty(), Error::<T>::TooFewSignatories);
+		ensure!(other_signatories.len() < max_sigs, Error::<T>::TooManySignatories);
+
+		let mut signatories = other_signatories;
+		let mut maybe_last = None;
+		let mut index = 0;
+		for item in signatories.iter() {
+			if let Some(last) = maybe_last {
+				ensure!(last < item, Error::<T>::SignatoriesOutOfOrder);
+			}
+			if item <= &who {
+				ensure!(item != &who, Error::<T>::SenderInSignatories);
+				index += 1;
+			}
+			maybe_last = Some(item);
+		}
+		signatories.insert(index, who);
+		Ok(signatories)
+	}
+}
+
+mod migration {
+	use super::*;
+	use frame_support::migration::{StorageIterator, put_storage_value};
+
+	/// Move the multisig operations left open in the utility module into `Multisigs`. Both key
+	/// them with the same hashers, so the keys are kept.
+	pub fn migrate<T: Trait>() -> Weight {
+		let mut moved: Weight = 0;
+		for (key, multisig) in StorageIterator
+			::<Multisig<T::BlockNumber, BalanceOf<T>, T::AccountId>>
+			::new(b"Utility", b"Multisigs")
+			.drain()
+		{
+			put_storage_value(b"Multisig", b"Multisigs", &key, multisig);
+			moved += 1;
+		}
+		// Each operation is read, removed and written again.
+		moved.saturating_mul(25_000 + 2 * 100_000)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch,
+		impl_outer_event, traits::OnRuntimeUpgrade,
+	};
+	use sp_core::H256;
+	use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use crate as multisig;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			pallet_balances<T>,
+			multisig<T>,
+		}
+	}
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			frame_system::System,
+			pallet_balances::Balances,
+			multisig::Multisig,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = TestEvent;
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const DepositBase: u64 = 1;
+		pub const DepositFactor: u64 = 1;
+		pub const MaxSignatories: u16 = 3;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Call = Call;
+		type Currency = Balances;
+		type DepositBase = DepositBase;
+		type DepositFactor = DepositFactor;
+		type MaxSignatories = MaxSignatories;
+		type WeightInfo = ();
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Multisig = Module<Test>;
+
+	use pallet_balances::Call as BalancesCall;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10), (5, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn last_event() -> TestEvent {
+		system::Module::<Test>::events().pop().map(|e| e.event).expect("Event expected")
+	}
+
+	fn expect_event<E: Into<TestEvent>>(e: E) {
+		assert_eq!(last_event(), e.into());
+	}
+
+	fn now() -> Timepoint<u64> {
+		Multisig::timepoint()
+	}
+
+	/// A call from the 2 of 3 multisig of accounts 1, 2 and 3, which holds 15.
+	fn funded_transfer() -> (u64, Box<Call>, CallHash) {
+		let multi = Multisig::multi_account_id(&[1, 2, 3][..], 2);
+		assert_ok!(Balances::transfer(Origin::signed(4), multi, 10));
+		assert_ok!(Balances::transfer(Origin::signed(5), multi, 5));
+		let call = Box::new(Call::Balances(BalancesCall::transfer(6, 15)));
+		let hash = call.using_encoded(blake2_256);
+		(multi, call, hash)
+	}
+
+	#[test]
+	fn multisig_deposit_is_taken_and_returned() {
+		new_test_ext().execute_with(|| {
+			let (_, call, _) = funded_transfer();
+			assert_ok!(Multisig::as_multi(
+				Origin::signed(1), 2, vec![2, 3], None, call.clone(), false,
+			));
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(Balances::reserved_balance(1), 3);
+
+			assert_ok!(Multisig::as_multi(
+				Origin::signed(2), 2, vec![1, 3], Some(now()), call, false,
+			));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::free_balance(6), 15);
+		});
+	}
+
+	#[test]
+	fn stored_call_is_dispatched_by_final_approval() {
+		new_test_ext().execute_with(|| {
+			let (multi, call, hash) = funded_transfer();
+			let weight = call.get_dispatch_info().weight;
+			assert_ok!(Multisig::as_multi(
+				Origin::signed(1), 2, vec![2, 3], None, call.clone(), true,
+			));
+			// the call is 11 bytes long, so its deposit is the base plus one factor.
+			assert_eq!(Multisig::calls(hash), Some((call.encode(), 1, 2)));
+			assert_eq!(Balances::free_balance(1), 5);
+			assert_eq!(Balances::reserved_balance(1), 5);
+
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(2), 2, vec![1, 3], Some(now()), hash, 0),
+				Error::<Test>::WeightTooLow,
+			);
+			assert_ok!(
+				Multisig::approve_as_multi(
+					Origin::signed(2), 2, vec![1, 3], Some(now()), hash, weight,
+				)
+			);
+			expect_event(RawEvent::MultisigExecuted(2, now(), multi, hash, Ok(())));
+			assert_eq!(Balances::free_balance(6), 15);
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Multisig::calls(hash), None);
+			assert_eq!(Multisig::multisigs(multi, hash), None);
+		});
+	}
+
+	#[test]
+	fn call_can_be_stored_by_a_later_approval() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 3);
+			assert_ok!(Balances::transfer(Origin::signed(4), multi, 10));
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 5)));
+			let hash = call.using_encoded(blake2_256);
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash, 0));
+			assert_ok!(
+				Multisig::as_multi(
+					Origin::signed(2), 3, vec![1, 3], Some(now()), call.clone(), true,
+				)
+			);
+			assert_eq!(Balances::reserved_balance(2), 2);
+			// storing it again is neither an approval nor a new call.
+			assert_noop!(
+				Multisig::as_multi(
+					Origin::signed(2), 3, vec![1, 3], Some(now()), call.clone(), true,
+				),
+				Error::<Test>::AlreadyApproved,
+			);
+
+			let weight = call.get_dispatch_info().weight;
+			assert_ok!(
+				Multisig::approve_as_multi(
+					Origin::signed(3), 3, vec![1, 2], Some(now()), hash, weight,
+				)
+			);
+			assert_eq!(Balances::free_balance(6), 5);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Balances::reserved_balance(2), 0);
+		});
+	}
+
+	#[test]
+	fn cancel_multisig_returns_deposits() {
+		new_test_ext().execute_with(|| {
+			let (multi, call, hash) = funded_transfer();
+			assert_ok!(Multisig::as_multi(Origin::signed(1), 2, vec![2, 3], None, call, true));
+			assert_eq!(Balances::reserved_balance(1), 5);
+
+			assert_noop!(
+				Multisig::cancel_as_multi(Origin::signed(2), 2, vec![1, 3], now(), hash),
+				Error::<Test>::NotOwner,
+			);
+			assert_ok!(Multisig::cancel_as_multi(Origin::signed(1), 2, vec![2, 3], now(), hash));
+			expect_event(RawEvent::MultisigCancelled(1, now(), multi, hash));
+			assert_eq!(Balances::free_balance(1), 10);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(Multisig::calls(hash), None);
+			assert_noop!(
+				Multisig::cancel_as_multi(Origin::signed(1), 2, vec![2, 3], now(), hash),
+				Error::<Test>::NotFound,
+			);
+		});
+	}
+
+	#[test]
+	fn timepoint_checking_works() {
+		new_test_ext().execute_with(|| {
+			let (_, call, hash) = funded_transfer();
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(2), 2, vec![1, 3], Some(now()), hash, 0),
+				Error::<Test>::UnexpectedTimepoint,
+			);
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 2, vec![2, 3], None, hash, 0));
+
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], None, call.clone(), false),
+				Error::<Test>::NoTimepoint,
+			);
+			let later = Timepoint { index: 1, .. now() };
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(2), 2, vec![1, 3], Some(later), call, false),
+				Error::<Test>::WrongTimepoint,
+			);
+		});
+	}
+
+	#[test]
+	fn multisig_3_of_3_works() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 3);
+			assert_ok!(Balances::transfer(Origin::signed(4), multi, 10));
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 5)));
+			let hash = call.using_encoded(blake2_256);
+
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash, 0));
+			assert_ok!(
+				Multisig::approve_as_multi(Origin::signed(2), 3, vec![1, 3], Some(now()), hash, 0)
+			);
+			// the call was not stored, so the final approval can not dispatch it by hash.
+			assert_ok!(
+				Multisig::approve_as_multi(Origin::signed(3), 3, vec![1, 2], Some(now()), hash, 0)
+			);
+			assert_eq!(Balances::free_balance(6), 0);
+
+			assert_ok!(Multisig::as_multi(
+				Origin::signed(3), 3, vec![1, 2], Some(now()), call, false,
+			));
+			assert_eq!(Balances::free_balance(6), 5);
+		});
+	}
+
+	#[test]
+	fn multisig_1_of_3_works() {
+		new_test_ext().execute_with(|| {
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 1);
+			assert_ok!(Balances::transfer(Origin::signed(4), multi, 10));
+			let call = Box::new(Call::Balances(BalancesCall::transfer(6, 5)));
+			assert_ok!(Multisig::as_multi_threshold_1(Origin::signed(1), vec![2, 3], call));
+			assert_eq!(Balances::free_balance(6), 5);
+		});
+	}
+
+	#[test]
+	fn signatories_and_threshold_are_checked() {
+		new_test_ext().execute_with(|| {
+			let (_, call, _) = funded_transfer();
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 1, vec![2, 3], None, call.clone(), false),
+				Error::<Test>::MinimumThreshold,
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![], None, call.clone(), false),
+				Error::<Test>::TooFewSignatories,
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![2, 3, 4], None, call.clone(), false),
+				Error::<Test>::TooManySignatories,
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![3, 2], None, call.clone(), false),
+				Error::<Test>::SignatoriesOutOfOrder,
+			);
+			assert_noop!(
+				Multisig::as_multi(Origin::signed(1), 2, vec![1, 3], None, call, false),
+				Error::<Test>::SenderInSignatories,
+			);
+		});
+	}
+
+	#[test]
+	fn duplicate_approvals_are_rejected() {
+		new_test_ext().execute_with(|| {
+			let (_, _, hash) = funded_transfer();
+			let multi = Multisig::multi_account_id(&[1, 2, 3][..], 3);
+			assert_ok!(Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], None, hash, 0));
+			assert_noop!(
+				Multisig::approve_as_multi(Origin::signed(1), 3, vec![2, 3], Some(now()), hash, 0),
+				Error::<Test>::AlreadyApproved,
+			);
+			assert_eq!(Multisig::multisigs(multi, hash).map(|m| m.approvals), Some(vec![1]));
+		});
+	}
+
+	#[test]
+	fn operations_of_the_utility_module_are_migrated() {
+		new_test_ext().execute_with(|| {
+			let (multi, call, hash) = funded_transfer();
+			assert_ok!(Balances::reserve(&1, 3));
+			let key = <Multisigs<Test>>::hashed_key_for(&multi, &hash);
+			let operation = super::Multisig { when: now(), deposit: 3, depositor: 1, approvals: vec![1] };
+			frame_support::migration::put_storage_value(b"Utility", b"Multisigs", &key[32..], operation);
+
+			assert_eq!(<Module<Test> as OnRuntimeUpgrade>::on_runtime_upgrade(), 225_000);
+			assert!(Multisig::multisigs(multi, hash).is_some());
+			assert!(!frame_support::migration::have_storage_value(b"Utility", b"Multisigs", &key[32..]));
+
+			assert_ok!(Multisig::as_multi(
+				Origin::signed(2), 2, vec![1, 3], Some(now()), call, false,
+			));
+			assert_eq!(Balances::free_balance(6), 15);
+			assert_eq!(Balances::reserved_balance(1), 0);
+			assert_eq!(<Module<Test> as OnRuntimeUpgrade>::on_runtime_upgrade(), 0);
+		});
+	}
+}
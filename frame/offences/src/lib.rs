@@ -16,7 +16,9 @@
 
 //! # Offences Module
 //!
-//! Tracks reported offences
+//! Tracks reported offences, and passes them on to the `OnOffenceHandler` (e.g. staking, to
+//! slash the offenders) while slashing is enabled. Root can disable slashing, e.g. for
+//! permissioned networks only keeping the offence records.
 
 // Ensure we're `no_std` when compiling for Wasm.
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -29,6 +31,7 @@ use frame_support::{
 	decl_module, decl_event, decl_storage, Parameter,
 	weights::{Weight, SimpleDispatchInfo, WeighData},
 };
+use frame_system::ensure_root;
 use sp_runtime::traits::Hash;
 use sp_staking::{
 	offence::{Offence, ReportOffence, Kind, OnOffenceHandler, OffenceDetails, OffenceError},
@@ -71,6 +74,10 @@ decl_storage! {
 		/// Note that the actual type of this mapping is `Vec<u8>`, this is because values of
 		/// different types are not supported at the moment so we are doing the manual serialization.
 		ReportsByKindIndex: map hasher(twox_64_concat) Kind => Vec<u8>; // (O::TimeSlot, ReportIdOf<T>)
+
+		/// Whether reported offences are passed on to the `OnOffenceHandler`. Offences are
+		/// recorded either way.
+		SlashingEnabled get(fn slashing_enabled): bool = true;
 	}
 }
 
@@ -79,6 +86,8 @@ decl_event!(
 		/// There is an offence reported of the given `kind` happened at the `session_index` and
 		/// (kind-specific) time slot. This event is not deposited for duplicate slashes.
 		Offence(Kind, OpaqueTimeSlot),
+		/// Passing offences on to the `OnOffenceHandler` was enabled or disabled.
+		SlashingEnabled(bool),
 	}
 );
 
@@ -94,6 +103,17 @@ decl_module! {
 
 			SimpleDispatchInfo::default().weigh_data(())
 		}
+
+		/// Enable or disable passing reported offences on to the `OnOffenceHandler`. Offences
+		/// reported while slashing is disabled are recorded, but never handled.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn set_slashing_enabled(origin, enabled: bool) {
+			ensure_root(origin)?;
+			SlashingEnabled::put(enabled);
+			Self::deposit_event(Event::SlashingEnabled(enabled));
+		}
 	}
 }
 
@@ -122,6 +142,10 @@ where
 		// Deposit the event.
 		Self::deposit_event(Event::Offence(O::ID, time_slot.encode()));
 
+		if !Self::slashing_enabled() {
+			return Ok(());
+		}
+
 		let offenders_count = concurrent_offenders.len() as u32;
 
 		// The amount new offenders are slashed
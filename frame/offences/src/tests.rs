@@ -49,6 +49,32 @@ fn should_report_an_authority_and_trigger_on_offence() {
 	});
 }
 
+#[test]
+fn should_record_but_not_handle_offences_while_slashing_is_disabled() {
+	new_test_ext().execute_with(|| {
+		// given
+		let time_slot = 42;
+		assert!(Offences::set_slashing_enabled(frame_system::RawOrigin::Signed(1).into(), false).is_err());
+		Offences::set_slashing_enabled(frame_system::RawOrigin::Root.into(), false).unwrap();
+
+		let offence = Offence {
+			validator_set_count: 5,
+			time_slot,
+			offenders: vec![5],
+		};
+
+		// when
+		Offences::report_offence(vec![], offence.clone()).unwrap();
+
+		// then
+		assert_eq!(offence_reports(KIND, time_slot).len(), 1);
+		with_on_offence_fractions(|f| {
+			assert_eq!(f.clone(), vec![]);
+		});
+		assert_eq!(Offences::report_offence(vec![], offence), Err(OffenceError::DuplicateReport));
+	});
+}
+
 #[test]
 fn should_not_report_the_same_authority_twice_in_the_same_slot() {
 	new_test_ext().execute_with(|| {
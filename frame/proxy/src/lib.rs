@@ -0,0 +1,485 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! # Proxy Module
+//!
+//! A module for dispatching calls on behalf of another account.
+//!
+//! ## Overview
+//!
+//! An account may register other accounts as its proxies on-chain, reserving a deposit for each.
+//! A proxy can then dispatch calls with the authority of the account through `proxy`.
+//!
+//! The same call also accepts off-chain delegation: the holder of a doughnut issued by an account
+//! can dispatch calls on its behalf by attaching the doughnut to a `proxy` extrinsic. The call is
+//! dispatched with the delegated origin, so the doughnut's permissions are verified by the
+//! dispatched module as usual.
+//!
+//! Both mechanisms resolve to a [`DelegatedOrigin`], and every call is checked against the
+//! runtime's `CallFilter` before it is dispatched. A runtime restricts what proxies and doughnut
+//! holders may do with a single `DelegationFilter`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `proxy` - Dispatch a call on behalf of an account, by proxy or doughnut.
+//! * `add_proxy` - Register an account as a proxy of the sender.
+//! * `remove_proxy` - Unregister a proxy of the sender.
+//! * `remove_proxies` - Unregister all proxies of the sender.
+//!
+//! [`DelegatedOrigin`]: ../frame_support/additional_traits/enum.DelegatedOrigin.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use sp_runtime::{DispatchResult, traits::{BadOrigin, Dispatchable, Saturating, Zero}};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, ensure, Parameter,
+	additional_traits::{DelegatedOrigin, DelegationFilter},
+	traits::{Currency, Get, ReservableCurrency},
+	weights::{GetDispatchInfo, FunctionOf, SimpleDispatchInfo},
+};
+use frame_system::{self as system, ensure_signed, RawOrigin};
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event> + Into<<Self as system::Trait>::Event>;
+
+	/// The aggregated call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+
+	/// The currency mechanism.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The base amount of currency reserved for having any proxies.
+	type ProxyDepositBase: Get<BalanceOf<Self>>;
+
+	/// The amount of currency reserved for each proxy.
+	type ProxyDepositFactor: Get<BalanceOf<Self>>;
+
+	/// The maximum number of proxies an account may have.
+	type MaxProxies: Get<u32>;
+
+	/// The calls which may be dispatched by a proxy or a doughnut holder.
+	type CallFilter: DelegationFilter<
+		<Self as Trait>::Call,
+		Self::AccountId,
+		<Self as system::Trait>::Doughnut,
+	>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Proxy {
+		/// The proxies of each account, sorted, and the amount reserved for them.
+		pub Proxies get(fn proxies):
+			map hasher(blake2_128_concat) T::AccountId => (Vec<T::AccountId>, BalanceOf<T>);
+	}
+}
+
+decl_event! {
+	pub enum Event {
+		/// A call was dispatched on behalf of an account (result).
+		ProxyExecuted(DispatchResult),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The account already has the maximum number of proxies.
+		TooMany,
+		/// The account is already a proxy.
+		Duplicate,
+		/// An account can not be its own proxy.
+		NoSelfProxy,
+		/// The account is not a proxy.
+		NotFound,
+		/// The sender may not act on behalf of the account.
+		NotProxy,
+		/// The call may not be dispatched with delegated authority.
+		Unproxyable,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The base amount of currency reserved for having any proxies.
+		const ProxyDepositBase: BalanceOf<T> = T::ProxyDepositBase::get();
+
+		/// The amount of currency reserved for each proxy.
+		const ProxyDepositFactor: BalanceOf<T> = T::ProxyDepositFactor::get();
+
+		/// The maximum number of proxies an account may have.
+		const MaxProxies: u32 = T::MaxProxies::get();
+
+		fn deposit_event() = default;
+
+		/// Dispatch `call` on behalf of `real`.
+		///
+		/// The dispatch origin for this call must be either _Signed_ by a proxy of `real`, in
+		/// which case `call` is dispatched as signed by `real`, or _Delegated_ by a doughnut
+		/// issued by `real`, in which case `call` is dispatched with the same delegated origin.
+		/// Either way `call` must pass the `CallFilter`.
+		///
+		/// # <weight>
+		/// - The weight of the `call` + 10,000.
+		/// - One storage read.
+		/// - One event.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| {
+				args.1.get_dispatch_info().weight + 10_000
+			},
+			|args: (&T::AccountId, &Box<<T as Trait>::Call>)| args.1.get_dispatch_info().class,
+			true
+		)]
+		fn proxy(origin, real: T::AccountId, call: Box<<T as Trait>::Call>) {
+			let delegated = match origin.into() {
+				Ok(RawOrigin::Signed(delegate)) => {
+					ensure!(Proxies::<T>::get(&real).0.contains(&delegate), Error::<T>::NotProxy);
+					DelegatedOrigin::Proxy { real, delegate }
+				},
+				Ok(RawOrigin::Delegated(issuer, doughnut)) => {
+					ensure!(issuer == real, Error::<T>::NotProxy);
+					DelegatedOrigin::Doughnut { issuer, doughnut }
+				},
+				_ => Err(BadOrigin)?,
+			};
+			ensure!(T::CallFilter::filter(&delegated, &call), Error::<T>::Unproxyable);
+
+			let result = call.dispatch(Self::dispatch_origin(delegated).into());
+			Self::deposit_event(Event::ProxyExecuted(result));
+		}
+
+		/// Register `proxy` as a proxy of the sender.
+		///
+		/// The dispatch origin for this call must be _Signed_. `ProxyDepositFactor` is reserved
+		/// from the sender, plus `ProxyDepositBase` for its first proxy.
+		///
+		/// # <weight>
+		/// - O(P) where P is the number of proxies of the sender.
+		/// - One storage read and write.
+		/// - One reserve.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn add_proxy(origin, proxy: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			ensure!(proxy != who, Error::<T>::NoSelfProxy);
+			let (mut proxies, deposit) = Proxies::<T>::get(&who);
+			ensure!(proxies.len() < T::MaxProxies::get() as usize, Error::<T>::TooMany);
+			let index = proxies.binary_search(&proxy).err().ok_or(Error::<T>::Duplicate)?;
+			proxies.insert(index, proxy);
+
+			let new_deposit = Self::deposit(proxies.len() as u32);
+			if new_deposit > deposit {
+				T::Currency::reserve(&who, new_deposit - deposit)?;
+			}
+			Proxies::<T>::insert(&who, (proxies, new_deposit));
+		}
+
+		/// Unregister `proxy` as a proxy of the sender, unreserving its deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(P) where P is the number of proxies of the sender.
+		/// - One storage read and write.
+		/// - One unreserve.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxy(origin, proxy: T::AccountId) {
+			let who = ensure_signed(origin)?;
+			let (mut proxies, deposit) = Proxies::<T>::get(&who);
+			let index = proxies.binary_search(&proxy).ok().ok_or(Error::<T>::NotFound)?;
+			proxies.remove(index);
+
+			if proxies.is_empty() {
+				let _ = T::Currency::unreserve(&who, deposit);
+				Proxies::<T>::remove(&who);
+			} else {
+				let new_deposit = Self::deposit(proxies.len() as u32);
+				let _ = T::Currency::unreserve(&who, deposit.saturating_sub(new_deposit));
+				Proxies::<T>::insert(&who, (proxies, new_deposit));
+			}
+		}
+
+		/// Unregister all proxies of the sender, unreserving their deposit.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - One storage read and removal.
+		/// - One unreserve.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_proxies(origin) {
+			let who = ensure_signed(origin)?;
+			let (_, deposit) = Proxies::<T>::take(&who);
+			let _ = T::Currency::unreserve(&who, deposit);
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The amount reserved for `count` proxies.
+	fn deposit(count: u32) -> BalanceOf<T> {
+		if count == 0 {
+			return Zero::zero();
+		}
+		T::ProxyDepositBase::get() + T::ProxyDepositFactor::get() * count.into()
+	}
+
+	/// The origin a call is dispatched with on behalf of another account. A doughnut is kept, so
+	/// its permissions are verified by the dispatched module.
+	fn dispatch_origin(
+		delegated: DelegatedOrigin<T::AccountId, T::Doughnut>,
+	) -> system::Origin<T> {
+		match delegated {
+			DelegatedOrigin::Proxy { real, .. } => RawOrigin::Signed(real),
+			DelegatedOrigin::Doughnut { issuer, doughnut } =>
+				RawOrigin::Delegated(issuer, doughnut),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch,
+		impl_outer_event, weights::Weight,
+	};
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill, DispatchError, traits::{BlakeTwo256, IdentityLookup}, testing::Header,
+	};
+	use crate as proxy;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			pallet_balances<T>,
+			proxy,
+		}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			frame_system::System,
+			pallet_balances::Balances,
+			proxy::Proxy,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = TestEvent;
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const ProxyDepositBase: u64 = 1;
+		pub const ProxyDepositFactor: u64 = 1;
+		pub const MaxProxies: u32 = 2;
+	}
+	/// Proxies may not manage proxies, doughnut holders may not make remarks.
+	pub struct TestFilter;
+	impl DelegationFilter<Call, u64, ()> for TestFilter {
+		fn filter(origin: &DelegatedOrigin<u64, ()>, call: &Call) -> bool {
+			match (origin, call) {
+				(DelegatedOrigin::Proxy { .. }, Call::Proxy(..)) => false,
+				(DelegatedOrigin::Doughnut { .. }, Call::System(..)) => false,
+				_ => true,
+			}
+		}
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Call = Call;
+		type Currency = Balances;
+		type ProxyDepositBase = ProxyDepositBase;
+		type ProxyDepositFactor = ProxyDepositFactor;
+		type MaxProxies = MaxProxies;
+		type CallFilter = TestFilter;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Proxy = Module<Test>;
+
+	use pallet_balances::Call as BalancesCall;
+	use pallet_balances::Error as BalancesError;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(1, 10), (2, 10), (3, 10), (4, 10)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	fn last_event() -> TestEvent {
+		system::Module::<Test>::events().pop().map(|e| e.event).expect("Event expected")
+	}
+
+	fn expect_event<E: Into<TestEvent>>(e: E) {
+		assert_eq!(last_event(), e.into());
+	}
+
+	fn transfer(dest: u64, value: u64) -> Box<Call> {
+		Box::new(Call::Balances(BalancesCall::transfer(dest, value)))
+	}
+
+	#[test]
+	fn adding_and_removing_proxies_reserves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2));
+			assert_eq!(Proxy::proxies(1), (vec![2, 3], 3));
+			assert_eq!(Balances::reserved_balance(1), 3);
+
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 2), Error::<Test>::TooMany);
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 3));
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 2), Error::<Test>::Duplicate);
+			assert_noop!(Proxy::add_proxy(Origin::signed(1), 1), Error::<Test>::NoSelfProxy);
+			assert_noop!(Proxy::remove_proxy(Origin::signed(1), 3), Error::<Test>::NotFound);
+			assert_eq!(Proxy::proxies(1), (vec![2], 2));
+			assert_eq!(Balances::reserved_balance(1), 2);
+
+			assert_ok!(Proxy::remove_proxy(Origin::signed(1), 2));
+			assert_eq!(Proxy::proxies(1), (vec![], 0));
+			assert_eq!(Balances::reserved_balance(1), 0);
+		});
+	}
+
+	#[test]
+	fn remove_proxies_unreserves_deposit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 3));
+			assert_ok!(Proxy::remove_proxies(Origin::signed(1)));
+			assert_eq!(Proxy::proxies(1), (vec![], 0));
+			assert_eq!(Balances::reserved_balance(1), 0);
+		});
+	}
+
+	#[test]
+	fn proxy_dispatches_as_real_account() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(3), 1, transfer(4, 1)),
+				Error::<Test>::NotProxy,
+			);
+
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, transfer(4, 1)));
+			expect_event(Event::ProxyExecuted(Ok(())));
+			assert_eq!(Balances::free_balance(1), 7);
+			assert_eq!(Balances::free_balance(2), 10);
+			assert_eq!(Balances::free_balance(4), 11);
+
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, transfer(4, 10)));
+			expect_event(Event::ProxyExecuted(
+				Err(DispatchError::from(BalancesError::<Test, _>::InsufficientBalance).stripped())
+			));
+		});
+	}
+
+	#[test]
+	fn proxy_dispatches_with_doughnut() {
+		new_test_ext().execute_with(|| {
+			// the doughnut issuer need not register any proxy
+			assert_ok!(Proxy::proxy(RawOrigin::Delegated(1, ()).into(), 1, transfer(4, 1)));
+			expect_event(Event::ProxyExecuted(Ok(())));
+			assert_eq!(Balances::free_balance(1), 9);
+			assert_eq!(Balances::free_balance(4), 11);
+
+			assert_noop!(
+				Proxy::proxy(RawOrigin::Delegated(1, ()).into(), 2, transfer(4, 1)),
+				Error::<Test>::NotProxy,
+			);
+			assert_noop!(Proxy::proxy(Origin::NONE, 1, transfer(4, 1)), BadOrigin);
+		});
+	}
+
+	#[test]
+	fn call_filter_applies_to_proxies_and_doughnuts() {
+		new_test_ext().execute_with(|| {
+			let remark = Box::new(Call::System(system::Call::remark(vec![])));
+			let remove_proxies = Box::new(Call::Proxy(proxy::Call::remove_proxies()));
+			assert_ok!(Proxy::add_proxy(Origin::signed(1), 2));
+
+			assert_ok!(Proxy::proxy(Origin::signed(2), 1, remark.clone()));
+			assert_noop!(
+				Proxy::proxy(Origin::signed(2), 1, remove_proxies.clone()),
+				Error::<Test>::Unproxyable,
+			);
+
+			assert_noop!(
+				Proxy::proxy(RawOrigin::Delegated(1, ()).into(), 1, remark),
+				Error::<Test>::Unproxyable,
+			);
+			assert_ok!(Proxy::proxy(RawOrigin::Delegated(1, ()).into(), 1, remove_proxies));
+			assert_eq!(Proxy::proxies(1), (vec![], 0));
+		});
+	}
+}
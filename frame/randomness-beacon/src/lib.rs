@@ -0,0 +1,848 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Randomness Beacon Module
+//!
+//! A randomness beacon, combining the VRF outputs of the authorities into an output at the end of
+//! every round of `RoundLength` blocks.
+//!
+//! The VRF input of a round is the round index and the output of the previous round. A round has
+//! two phases:
+//!
+//! - the commit phase, the first half of the round, in which the offchain worker of every
+//!   authority evaluates the VRF of its key on the input and submits a hash of the output in an
+//!   unsigned `commit` transaction, signed with the authority key.
+//! - the reveal phase, the second half of the round, in which the authorities which committed
+//!   reveal their VRF output with its proof in an unsigned `reveal` transaction. The proof is
+//!   verified by the `sr25519_vrf_verify` host function and the output must match the commitment.
+//!
+//! At the end of the round, the output is the hash of the revealed VRF outputs, if at least a
+//! `Threshold` share of the authorities revealed. Otherwise the round is skipped and the previous
+//! output is kept.
+//!
+//! The module implements `Randomness`, as a replacement of the collective flip randomness for
+//! application pallets.
+//!
+//! ## Security
+//!
+//! A VRF output is unique for the key and the input, so authorities can't grind their
+//! contributions. The commitments fix the contributing authorities before any output is revealed,
+//! so an authority can't decide whether to contribute once it knows the outputs of the others.
+//!
+//! The commitments don't prevent withholding a reveal though: the outputs are public as soon as
+//! they are revealed, and an authority revealing late knows the output of the round both with and
+//! without its own contribution. `k` colluding authorities which committed can choose between up to
+//! `2^k` outputs of a round by withholding their reveals, and skip the round if the other reveals
+//! don't meet the threshold. Withheld reveals are reported with a `RevealsWithheld` event, it's up
+//! to the runtime or governance to punish them.
+//!
+//! The output of a round can be computed by anyone once the committed authorities revealed, before
+//! the round ends. It must only be used to decide outcomes committed to before the reveal phase of
+//! the round which produces it.
+//!
+//! ## Dependencies
+//!
+//! The authorities are set at genesis and follow the validators of the
+//! [Session module](../pallet_session/index.html) through `OneSessionHandler`. A new set of
+//! validators takes over at the end of the current round, so `RoundLength` should be well below the
+//! session length.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use sp_core::{crypto::KeyTypeId, sr25519, H256};
+use sp_std::prelude::*;
+use sp_runtime::{
+	offchain::storage::StorageValueRef,
+	traits::{Hash, One, SaturatedConversion, Zero},
+	transaction_validity::{
+		InvalidTransaction, TransactionPriority, TransactionSource, TransactionValidity, ValidTransaction,
+	},
+	Perbill, RuntimeAppPublic, RuntimeDebug,
+};
+use frame_support::{
+	debug, decl_error, decl_event, decl_module, decl_storage,
+	traits::{Get, Randomness},
+	weights::{SimpleDispatchInfo, WeighData, Weight},
+};
+use frame_system::{self as system, ensure_none, offchain::SubmitUnsignedTransaction};
+
+/// The key type of the authority keys of the beacon.
+pub const KEY_TYPE: KeyTypeId = KeyTypeId(*b"rbcn");
+
+/// The prefix of the offchain storage keys of the last round and phase submitted by a local
+/// authority.
+const DB_PREFIX: &[u8] = b"randomness-beacon::submitted::";
+
+/// The authority keys of the beacon, sr25519 keys since the VRF is evaluated with schnorrkel.
+pub mod crypto {
+	use super::KEY_TYPE;
+	use sp_application_crypto::{app_crypto, sr25519};
+	app_crypto!(sr25519, KEY_TYPE);
+}
+
+/// An authority of the beacon.
+pub type AuthorityId = crypto::Public;
+
+/// The signature of an authority on its commitment.
+pub type AuthoritySignature = crypto::Signature;
+
+/// The index of an authority in the authorities.
+pub type AuthIndex = u32;
+
+/// The index of a round.
+pub type RoundIndex = u64;
+
+/// A phase of a round.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Phase {
+	/// The authorities commit to the hash of their VRF output.
+	Commit,
+	/// The authorities which committed reveal their VRF output.
+	Reveal,
+}
+
+/// The commitment to a VRF output.
+pub fn commitment_of(signature: &sr25519::VrfSignature) -> H256 {
+	sp_io::hashing::blake2_256(signature.output.as_bytes()).into()
+}
+
+/// The payload signed by an authority committing to a round.
+pub fn commit_payload(round: RoundIndex, authority_index: AuthIndex, commitment: &H256) -> Vec<u8> {
+	(b"randomness-beacon-commit", round, authority_index, commitment).encode()
+}
+
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+
+	/// A dispatchable call type.
+	type Call: From<Call<Self>>;
+
+	/// A transaction submitter.
+	type SubmitTransaction: SubmitUnsignedTransaction<Self, <Self as Trait>::Call>;
+
+	/// The number of blocks of a round, at least two: the first half of the blocks is the commit
+	/// phase and the second half the reveal phase.
+	type RoundLength: Get<Self::BlockNumber>;
+
+	/// The minimum share of the authorities revealing in a round for it to produce an output.
+	type Threshold: Get<Perbill>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as RandomnessBeacon {
+		/// The authorities contributing to the current round.
+		Authorities get(fn authorities): Vec<AuthorityId>;
+
+		/// The authorities of the new session, taking over at the end of the current round.
+		NextAuthorities get(fn next_authorities): Option<Vec<AuthorityId>>;
+
+		/// The current round.
+		Round get(fn round): RoundIndex;
+
+		/// The commitments to the VRF outputs of the current round, by authority index.
+		Commitments get(fn commitments): Vec<(AuthIndex, H256)>;
+
+		/// The revealed VRF outputs of the current round, by authority index.
+		Contributions get(fn contributions): Vec<(AuthIndex, H256)>;
+
+		/// The output of the last round which produced one, the VRF input of the next rounds.
+		Output get(fn output): T::Hash;
+
+		/// The round of `Output`, zero before the first output.
+		OutputRound get(fn output_round): RoundIndex;
+	}
+	add_extra_genesis {
+		config(authorities): Vec<AuthorityId>;
+		build(|config| Module::<T>::initialize_authorities(&config.authorities))
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where <T as frame_system::Trait>::Hash {
+		/// An authority committed to its VRF output for the round.
+		Committed(RoundIndex, AuthorityId),
+		/// An authority revealed its VRF output for the round.
+		Contributed(RoundIndex, AuthorityId),
+		/// Authorities committed to the round but didn't reveal their VRF output.
+		RevealsWithheld(RoundIndex, Vec<AuthorityId>),
+		/// A round produced an output, from the given number of contributions.
+		RoundCompleted(RoundIndex, Hash, u32),
+		/// A round didn't meet the threshold and was skipped, with the given number of contributions.
+		RoundSkipped(RoundIndex, u32),
+	}
+);
+
+decl_error! {
+	/// Error for the randomness beacon module.
+	pub enum Error for Module<T: Trait> {
+		/// The contribution isn't for the current round.
+		StaleContribution,
+		/// The authority index is unknown.
+		InvalidAuthority,
+		/// The commitment or reveal isn't for the current phase of the round.
+		WrongPhase,
+		/// The authority already committed to the round.
+		DuplicateCommitment,
+		/// The authority didn't commit to the round.
+		NoCommitment,
+		/// The revealed VRF output doesn't match the commitment of the authority.
+		CommitmentMismatch,
+		/// The authority already contributed to the round.
+		DuplicateContribution,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Commit to the VRF output of an authority for the current round.
+		///
+		/// The dispatch origin for this call must be _None_, the signature of the authority is
+		/// verified in `validate_unsigned`.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn commit(
+			origin,
+			round: RoundIndex,
+			authority_index: AuthIndex,
+			commitment: H256,
+			_signature: AuthoritySignature,
+		) {
+			ensure_none(origin)?;
+			let authority = Self::ensure_current(round, authority_index, Phase::Commit)?;
+
+			let mut commitments = Commitments::get();
+			match commitments.binary_search_by_key(&authority_index, |c| c.0) {
+				Ok(_) => return Err(Error::<T>::DuplicateCommitment.into()),
+				Err(position) => commitments.insert(position, (authority_index, commitment)),
+			}
+			Commitments::put(commitments);
+
+			Self::deposit_event(RawEvent::Committed(round, authority));
+		}
+
+		/// Reveal the VRF output of an authority which committed to the current round.
+		///
+		/// The dispatch origin for this call must be _None_, the VRF proof is verified in
+		/// `validate_unsigned`.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn reveal(origin, round: RoundIndex, authority_index: AuthIndex, signature: sr25519::VrfSignature) {
+			ensure_none(origin)?;
+			let authority = Self::ensure_current(round, authority_index, Phase::Reveal)?;
+
+			let commitments = Commitments::get();
+			let commitment = commitments
+				.binary_search_by_key(&authority_index, |c| c.0)
+				.map(|position| commitments[position].1)
+				.map_err(|_| Error::<T>::NoCommitment)?;
+			frame_support::ensure!(commitment == commitment_of(&signature), Error::<T>::CommitmentMismatch);
+
+			let mut contributions = Contributions::get();
+			match contributions.binary_search_by_key(&authority_index, |c| c.0) {
+				Ok(_) => return Err(Error::<T>::DuplicateContribution.into()),
+				Err(position) => contributions.insert(position, (authority_index, signature.output)),
+			}
+			Contributions::put(contributions);
+
+			Self::deposit_event(RawEvent::Contributed(round, authority));
+		}
+
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			if Self::round_ends(now) {
+				Self::end_round();
+			}
+
+			SimpleDispatchInfo::default().weigh_data(())
+		}
+
+		fn offchain_worker(now: T::BlockNumber) {
+			if sp_io::offchain::is_validator() {
+				Self::submit_local_authorities(now);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The VRF input of the given round.
+	pub fn round_input(round: RoundIndex) -> Vec<u8> {
+		(b"randomness-beacon", round, Output::<T>::get()).encode()
+	}
+
+	/// The phase of its round the given block is in, with the number of blocks left in the phase.
+	pub fn phase_at(block: T::BlockNumber) -> (Phase, T::BlockNumber) {
+		let round_length = T::RoundLength::get();
+		let reveal_start = round_length / T::BlockNumber::from(2u32);
+		let offset = if round_length.is_zero() { Zero::zero() } else { block % round_length };
+
+		if offset < reveal_start {
+			(Phase::Commit, reveal_start - offset)
+		} else {
+			(Phase::Reveal, round_length - offset)
+		}
+	}
+
+	/// Whether the round ends at the initialization of the given block.
+	fn round_ends(block: T::BlockNumber) -> bool {
+		let round_length = T::RoundLength::get();
+		!block.is_zero() && !round_length.is_zero() && (block % round_length).is_zero()
+	}
+
+	/// Check a commitment or reveal is for the current round and phase, returning the authority.
+	fn ensure_current(
+		round: RoundIndex,
+		authority_index: AuthIndex,
+		phase: Phase,
+	) -> Result<AuthorityId, Error<T>> {
+		frame_support::ensure!(round == Round::get(), Error::<T>::StaleContribution);
+		let now = <frame_system::Module<T>>::block_number();
+		frame_support::ensure!(Self::phase_at(now).0 == phase, Error::<T>::WrongPhase);
+		Authorities::get()
+			.get(authority_index as usize)
+			.cloned()
+			.ok_or(Error::<T>::InvalidAuthority)
+	}
+
+	/// Produce the output of the current round, or skip it, and start the next round.
+	fn end_round() {
+		let round = Round::get();
+		let commitments = Commitments::take();
+		let contributions = Contributions::take();
+		let count = contributions.len() as u32;
+		let authorities = Authorities::get();
+		let required = (T::Threshold::get() * authorities.len() as u32).max(1);
+
+		let withheld = commitments
+			.iter()
+			.filter(|c| contributions.binary_search_by_key(&c.0, |c| c.0).is_err())
+			.filter_map(|c| authorities.get(c.0 as usize).cloned())
+			.collect::<Vec<_>>();
+		if !withheld.is_empty() {
+			Self::deposit_event(RawEvent::RevealsWithheld(round, withheld));
+		}
+
+		if count >= required {
+			let output = T::Hashing::hash_of(&(round, Output::<T>::get(), contributions));
+			Output::<T>::put(output);
+			OutputRound::put(round);
+			Self::deposit_event(RawEvent::RoundCompleted(round, output, count));
+		} else {
+			Self::deposit_event(RawEvent::RoundSkipped(round, count));
+		}
+
+		if let Some(next_authorities) = NextAuthorities::take() {
+			Authorities::put(next_authorities);
+		}
+		Round::put(round + 1);
+	}
+
+	/// Submit the commitments or reveals of the local authorities for the phase of the next block.
+	fn submit_local_authorities(now: T::BlockNumber) {
+		let next = now + One::one();
+		if Self::round_ends(next) {
+			// the round ends before the transactions could be included
+			return;
+		}
+
+		let (phase, _) = Self::phase_at(next);
+		let round = Round::get();
+		let commitments = Commitments::get();
+		let contributions = Contributions::get();
+		let mut local_keys = AuthorityId::all();
+		local_keys.sort();
+
+		for (index, authority) in Authorities::get().into_iter().enumerate() {
+			let index = index as AuthIndex;
+			if local_keys.binary_search(&authority).is_err() {
+				continue;
+			}
+
+			let committed = commitments.binary_search_by_key(&index, |c| c.0).is_ok();
+			let pending = match phase {
+				Phase::Commit => !committed,
+				Phase::Reveal => committed && contributions.binary_search_by_key(&index, |c| c.0).is_err(),
+			};
+			if !pending {
+				continue;
+			}
+
+			// only submit once per phase, the transaction stays in the pool until the phase ends
+			let key = [DB_PREFIX, &authority.encode()[..]].concat();
+			let storage = StorageValueRef::persistent(&key);
+			if storage.get::<(RoundIndex, Phase)>() == Some(Some((round, phase))) {
+				continue;
+			}
+
+			let input = Self::round_input(round);
+			let signature = match sp_io::crypto::sr25519_vrf_sign(KEY_TYPE, &authority.clone().into(), &input) {
+				Some(signature) => signature,
+				None => {
+					debug::warn!(target: "randomness-beacon", "Failed to evaluate the VRF of {:?}", authority);
+					continue;
+				},
+			};
+
+			let call = match phase {
+				Phase::Commit => {
+					let commitment = commitment_of(&signature);
+					match authority.sign(&commit_payload(round, index, &commitment)) {
+						Some(proof) => Call::commit(round, index, commitment, proof),
+						None => {
+							debug::warn!(
+								target: "randomness-beacon",
+								"Failed to sign the commitment of {:?}",
+								authority,
+							);
+							continue;
+						},
+					}
+				},
+				Phase::Reveal => Call::reveal(round, index, signature),
+			};
+
+			match T::SubmitTransaction::submit_unsigned(call) {
+				Ok(()) => storage.set(&(round, phase)),
+				Err(()) => debug::warn!(
+					target: "randomness-beacon",
+					"Failed to submit the {:?} of {:?} to round {}",
+					phase,
+					authority,
+					round,
+				),
+			}
+		}
+	}
+
+	fn initialize_authorities(authorities: &[AuthorityId]) {
+		if !authorities.is_empty() {
+			assert!(Authorities::get().is_empty(), "Authorities are already initialized!");
+			Authorities::put(authorities);
+		}
+	}
+
+	/// Check the round and phase of an unsigned transaction, for inclusion in the next block.
+	fn validate_current(
+		round: RoundIndex,
+		authority_index: AuthIndex,
+		phase: Phase,
+	) -> Result<(AuthorityId, T::BlockNumber), InvalidTransaction> {
+		let next = <frame_system::Module<T>>::block_number() + One::one();
+		if round != Round::get() || Self::round_ends(next) {
+			return Err(InvalidTransaction::Stale);
+		}
+
+		let (next_phase, left) = Self::phase_at(next);
+		match (phase, next_phase) {
+			(Phase::Commit, Phase::Reveal) => return Err(InvalidTransaction::Stale),
+			(Phase::Reveal, Phase::Commit) => return Err(InvalidTransaction::Future),
+			_ => {},
+		}
+
+		Authorities::get()
+			.get(authority_index as usize)
+			.cloned()
+			.map(|authority| (authority, left))
+			.ok_or(InvalidTransaction::BadProof)
+	}
+}
+
+impl<T: Trait> Randomness<T::Hash> for Module<T> {
+	/// The output of the last round mixed with `subject`, computable by anyone once the committed
+	/// authorities revealed, see the module documentation.
+	fn random(subject: &[u8]) -> T::Hash {
+		T::Hashing::hash_of(&(Output::<T>::get(), subject))
+	}
+
+	fn random_seed() -> T::Hash {
+		Output::<T>::get()
+	}
+}
+
+impl<T: Trait> sp_runtime::BoundToRuntimeAppPublic for Module<T> {
+	type Public = AuthorityId;
+}
+
+impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
+	type Key = AuthorityId;
+
+	fn on_genesis_session<'a, I: 'a>(validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		let authorities = validators.map(|x| x.1).collect::<Vec<_>>();
+		Self::initialize_authorities(&authorities);
+	}
+
+	fn on_new_session<'a, I: 'a>(changed: bool, validators: I, _queued_validators: I)
+		where I: Iterator<Item=(&'a T::AccountId, AuthorityId)>
+	{
+		if changed {
+			// the commitments and contributions are by authority index, the round goes on with its
+			// authorities and the new ones take over at its end
+			NextAuthorities::put(validators.map(|x| x.1).collect::<Vec<_>>());
+		}
+	}
+
+	fn on_disabled(_i: usize) {
+		// ignore
+	}
+}
+
+impl<T: Trait> frame_support::unsigned::ValidateUnsigned for Module<T> {
+	type Call = Call<T>;
+
+	fn validate_unsigned(
+		_source: TransactionSource,
+		call: &Self::Call,
+	) -> TransactionValidity {
+		let (round, authority_index, phase, left) = match call {
+			Call::commit(round, authority_index, commitment, signature) => {
+				let (authority, left) = Self::validate_current(*round, *authority_index, Phase::Commit)?;
+				if Commitments::get().binary_search_by_key(authority_index, |c| c.0).is_ok() {
+					return InvalidTransaction::Stale.into();
+				}
+				if !authority.verify(&commit_payload(*round, *authority_index, commitment), signature) {
+					return InvalidTransaction::BadProof.into();
+				}
+				(round, authority_index, Phase::Commit, left)
+			},
+			Call::reveal(round, authority_index, signature) => {
+				let (authority, left) = Self::validate_current(*round, *authority_index, Phase::Reveal)?;
+				if Contributions::get().binary_search_by_key(authority_index, |c| c.0).is_ok() {
+					return InvalidTransaction::Stale.into();
+				}
+				let commitments = Commitments::get();
+				match commitments.binary_search_by_key(authority_index, |c| c.0) {
+					Ok(position) => if commitments[position].1 != commitment_of(signature) {
+						return InvalidTransaction::BadProof.into();
+					},
+					// the authority can't contribute to the round without a commitment
+					Err(_) => return InvalidTransaction::Stale.into(),
+				}
+
+				// check the VRF proof (this is expensive so we do it last).
+				let input = Self::round_input(*round);
+				if !sp_io::crypto::sr25519_vrf_verify(signature, &input, &authority.into()) {
+					return InvalidTransaction::BadProof.into();
+				}
+				(round, authority_index, Phase::Reveal, left)
+			},
+			_ => return InvalidTransaction::Call.into(),
+		};
+
+		Ok(ValidTransaction {
+			priority: TransactionPriority::max_value(),
+			requires: vec![],
+			provides: vec![(round, authority_index, phase).encode()],
+			longevity: left.saturated_into::<u64>(),
+			propagate: true,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use codec::Decode;
+	use frame_support::{
+		assert_noop, assert_ok, impl_outer_event, impl_outer_origin, parameter_types,
+		traits::{OffchainWorker, OnInitialize},
+		unsigned::ValidateUnsigned,
+	};
+	use pallet_session::OneSessionHandler;
+	use sp_core::{
+		offchain::{testing::{TestOffchainExt, TestTransactionPoolExt}, OffchainExt, TransactionPoolExt},
+		testing::KeyStore,
+		traits::{BareCryptoStore, KeystoreExt},
+		Pair,
+	};
+	use sp_runtime::{
+		testing::{Header, TestXt},
+		traits::{BlakeTwo256, Dispatchable, IdentityLookup},
+		DispatchResult,
+	};
+	use crate as randomness_beacon;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			randomness_beacon<T>,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	type Extrinsic = TestXt<u64, Call<Test>, ()>;
+	parameter_types! {
+		pub const RoundLength: u64 = 10;
+		pub const Threshold: Perbill = Perbill::from_percent(50);
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Call = Call<Test>;
+		type SubmitTransaction = frame_system::offchain::TransactionSubmitter<(), Call<Test>, Extrinsic>;
+		type RoundLength = RoundLength;
+		type Threshold = Threshold;
+	}
+	type System = frame_system::Module<Test>;
+	type RandomnessBeacon = Module<Test>;
+
+	fn pairs() -> Vec<sr25519::Pair> {
+		(1..=4).map(|i| sr25519::Pair::from_seed(&[i; 32])).collect()
+	}
+
+	fn new_test_ext(authorities: Vec<AuthorityId>) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig { authorities }.assimilate_storage::<Test>(&mut t).unwrap();
+		t.into()
+	}
+
+	fn commitment(pair: &sr25519::Pair, index: AuthIndex) -> Call<Test> {
+		let round = RandomnessBeacon::round();
+		let commitment = commitment_of(&pair.vrf_sign(&RandomnessBeacon::round_input(round)));
+		Call::commit(round, index, commitment, pair.sign(&commit_payload(round, index, &commitment)).into())
+	}
+
+	fn reveal(pair: &sr25519::Pair, index: AuthIndex) -> Call<Test> {
+		let round = RandomnessBeacon::round();
+		Call::reveal(round, index, pair.vrf_sign(&RandomnessBeacon::round_input(round)))
+	}
+
+	fn dispatch(call: Call<Test>) -> DispatchResult {
+		call.dispatch(Origin::NONE)
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			System::set_block_number(System::block_number() + 1);
+			RandomnessBeacon::on_initialize(System::block_number());
+		}
+	}
+
+	fn last_event() -> TestEvent {
+		System::events().pop().expect("event expected").event
+	}
+
+	#[test]
+	fn rounds_meeting_the_threshold_produce_an_output() {
+		let pairs = pairs();
+		new_test_ext(pairs.iter().map(|pair| pair.public().into()).collect()).execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(dispatch(commitment(&pairs[2], 2)));
+			run_to_block(5);
+			assert_ok!(dispatch(reveal(&pairs[2], 2)));
+			run_to_block(10);
+			assert_eq!(last_event(), TestEvent::randomness_beacon(RawEvent::RoundSkipped(0, 1)));
+			assert_eq!(RandomnessBeacon::random_seed(), H256::zero());
+
+			// the second round has another input, the first contribution is stale
+			assert_eq!(RandomnessBeacon::round(), 1);
+			let stale = pairs[0].vrf_sign(&RandomnessBeacon::round_input(0));
+			assert_noop!(
+				RandomnessBeacon::reveal(Origin::NONE, 0, 0, stale),
+				Error::<Test>::StaleContribution,
+			);
+			assert_ok!(dispatch(commitment(&pairs[3], 3)));
+			assert_ok!(dispatch(commitment(&pairs[0], 0)));
+			assert_noop!(dispatch(commitment(&pairs[0], 0)), Error::<Test>::DuplicateCommitment);
+			assert_noop!(dispatch(reveal(&pairs[0], 0)), Error::<Test>::WrongPhase);
+
+			run_to_block(15);
+			assert_noop!(dispatch(commitment(&pairs[1], 1)), Error::<Test>::WrongPhase);
+			assert_noop!(dispatch(reveal(&pairs[1], 1)), Error::<Test>::NoCommitment);
+			assert_noop!(dispatch(reveal(&pairs[3], 0)), Error::<Test>::CommitmentMismatch);
+			assert_ok!(dispatch(reveal(&pairs[3], 3)));
+			assert_ok!(dispatch(reveal(&pairs[0], 0)));
+			assert_noop!(dispatch(reveal(&pairs[0], 0)), Error::<Test>::DuplicateContribution);
+			let contributions = RandomnessBeacon::contributions();
+			assert_eq!(contributions.iter().map(|c| c.0).collect::<Vec<_>>(), vec![0, 3]);
+
+			run_to_block(20);
+			let output = BlakeTwo256::hash_of(&(1u64, H256::zero(), contributions));
+			assert_eq!(last_event(), TestEvent::randomness_beacon(RawEvent::RoundCompleted(1, output, 2)));
+			assert_eq!(RandomnessBeacon::output_round(), 1);
+			assert_eq!(RandomnessBeacon::random_seed(), output);
+			assert_eq!(RandomnessBeacon::random(b"subject"), BlakeTwo256::hash_of(&(output, &b"subject"[..])));
+			assert!(RandomnessBeacon::commitments().is_empty());
+			assert!(RandomnessBeacon::contributions().is_empty());
+		});
+	}
+
+	#[test]
+	fn withheld_reveals_are_reported() {
+		let pairs = pairs();
+		new_test_ext(pairs.iter().map(|pair| pair.public().into()).collect()).execute_with(|| {
+			System::set_block_number(1);
+			for index in 0..3 {
+				assert_ok!(dispatch(commitment(&pairs[index], index as AuthIndex)));
+			}
+			run_to_block(5);
+			assert_ok!(dispatch(reveal(&pairs[0], 0)));
+			assert_ok!(dispatch(reveal(&pairs[1], 1)));
+			run_to_block(10);
+
+			let withheld = RawEvent::RevealsWithheld(0, vec![pairs[2].public().into()]);
+			let withheld = TestEvent::randomness_beacon(withheld);
+			assert!(System::events().iter().any(|record| record.event == withheld));
+			match last_event() {
+				TestEvent::randomness_beacon(RawEvent::RoundCompleted(0, _, 2)) => {},
+				event => panic!("Unexpected event: {:?}", event),
+			}
+		});
+	}
+
+	#[test]
+	fn new_authorities_take_over_at_the_end_of_the_round() {
+		let pairs = pairs();
+		new_test_ext(pairs.iter().map(|pair| pair.public().into()).collect()).execute_with(|| {
+			System::set_block_number(1);
+			assert_ok!(dispatch(commitment(&pairs[3], 3)));
+
+			let accounts = [1u64, 2];
+			let new_authorities = pairs[2..].iter().map(|pair| pair.public().into()).collect::<Vec<AuthorityId>>();
+			let validators = || accounts.iter().zip(new_authorities.iter().cloned());
+			RandomnessBeacon::on_new_session(true, validators(), validators());
+
+			// the round goes on with its authorities and commitments
+			assert_eq!(RandomnessBeacon::authorities().len(), 4);
+			assert_eq!(RandomnessBeacon::next_authorities(), Some(new_authorities.clone()));
+			run_to_block(5);
+			assert_ok!(dispatch(reveal(&pairs[3], 3)));
+			assert_eq!(RandomnessBeacon::contributions().len(), 1);
+
+			run_to_block(10);
+			assert_eq!(RandomnessBeacon::authorities(), new_authorities);
+			assert_eq!(RandomnessBeacon::next_authorities(), None);
+		});
+	}
+
+	#[test]
+	fn commitments_and_reveals_are_validated() {
+		let pairs = pairs();
+		new_test_ext(pairs.iter().map(|pair| pair.public().into()).collect()).execute_with(|| {
+			let validate = |call: &Call<Test>| RandomnessBeacon::validate_unsigned(TransactionSource::External, call);
+
+			let valid = validate(&commitment(&pairs[1], 1)).unwrap();
+			assert_eq!(valid.provides, vec![(0u64, 1u32, Phase::Commit).encode()]);
+			assert_eq!(valid.longevity, 4);
+
+			// the signature of another authority
+			assert_eq!(validate(&commitment(&pairs[0], 1)), InvalidTransaction::BadProof.into());
+			assert_eq!(validate(&commitment(&pairs[1], 4)), InvalidTransaction::BadProof.into());
+			// reveals wait for the reveal phase
+			assert_eq!(validate(&reveal(&pairs[1], 1)), InvalidTransaction::Future.into());
+
+			assert_ok!(dispatch(commitment(&pairs[1], 1)));
+			assert_eq!(validate(&commitment(&pairs[1], 1)), InvalidTransaction::Stale.into());
+
+			// the next block is in the reveal phase
+			System::set_block_number(4);
+			assert_eq!(validate(&commitment(&pairs[2], 2)), InvalidTransaction::Stale.into());
+			let valid = validate(&reveal(&pairs[1], 1)).unwrap();
+			assert_eq!(valid.provides, vec![(0u64, 1u32, Phase::Reveal).encode()]);
+			assert_eq!(valid.longevity, 5);
+
+			// the output of another authority
+			assert_eq!(validate(&reveal(&pairs[0], 1)), InvalidTransaction::BadProof.into());
+			// the committed output with an invalid proof
+			let mut signature = pairs[1].vrf_sign(&RandomnessBeacon::round_input(0));
+			signature.proof = Default::default();
+			assert_eq!(validate(&Call::reveal(0, 1, signature)), InvalidTransaction::BadProof.into());
+			// no commitment
+			assert_eq!(validate(&reveal(&pairs[2], 2)), InvalidTransaction::Stale.into());
+
+			// the round ends before the next block
+			System::set_block_number(9);
+			assert_eq!(validate(&reveal(&pairs[1], 1)), InvalidTransaction::Stale.into());
+		});
+	}
+
+	#[test]
+	fn offchain_worker_commits_and_reveals_once_per_phase() {
+		let keystore = KeyStore::new();
+		let local = keystore.write().sr25519_generate_new(KEY_TYPE, Some("//Alice")).unwrap();
+		let mut ext = new_test_ext(vec![pairs()[0].public().into(), local.into()]);
+		let (offchain, _state) = TestOffchainExt::new();
+		let (pool, pool_state) = TestTransactionPoolExt::new();
+		ext.register_extension(OffchainExt::new(offchain));
+		ext.register_extension(TransactionPoolExt::new(pool));
+		ext.register_extension(KeystoreExt(keystore));
+
+		ext.execute_with(|| {
+			let submitted = || {
+				let transaction = pool_state.write().transactions.pop().unwrap();
+				Extrinsic::decode(&mut &*transaction).unwrap().call
+			};
+
+			RandomnessBeacon::offchain_worker(1);
+			RandomnessBeacon::offchain_worker(2);
+			assert_eq!(pool_state.read().transactions.len(), 1);
+			let call = submitted();
+			match call {
+				Call::commit(0, 1, _, _) =>
+					assert!(RandomnessBeacon::validate_unsigned(TransactionSource::Local, &call).is_ok()),
+				call => panic!("Unexpected call: {:?}", call),
+			}
+			assert_ok!(dispatch(call));
+
+			// the next block is in the reveal phase
+			System::set_block_number(4);
+			RandomnessBeacon::offchain_worker(4);
+			RandomnessBeacon::offchain_worker(4);
+			assert_eq!(pool_state.read().transactions.len(), 1);
+			let call = submitted();
+			match call {
+				Call::reveal(0, 1, _) =>
+					assert!(RandomnessBeacon::validate_unsigned(TransactionSource::Local, &call).is_ok()),
+				call => panic!("Unexpected call: {:?}", call),
+			}
+		});
+	}
+}
@@ -21,6 +21,10 @@
 //! `81` blocks. Low-influence randomness can be useful when defending against relatively weak
 //! adversaries.
 //!
+//! Block authors can bias these values by withholding blocks, pallets needing randomness against
+//! stronger adversaries should use the VRF based
+//! [Randomness Beacon module](../pallet_randomness_beacon/index.html) instead.
+//!
 //! ## Public Functions
 //!
 //! See the [`Module`](./struct.Module.html) struct for details of publicly available functions.
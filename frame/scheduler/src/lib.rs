@@ -0,0 +1,814 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Scheduler Module
+//!
+//! A module for scheduling dispatches to be made in a future block.
+//!
+//! ## Overview
+//!
+//! Calls are queued in the agenda of a future block and dispatched at the start of that block, in
+//! order of their priority. A task may be given a name, so that it can be cancelled without
+//! knowing its address in the agenda. This allows governance actions, e.g. rotating the
+//! reserved-node list, to be staged in advance. Names are scoped to the account a task is
+//! dispatched on behalf of, root tasks sharing a single scope.
+//!
+//! A scheduled call is dispatched either as root, in which case it must be scheduled by the
+//! `ScheduleOrigin`, or with the origin which scheduled it. A preserved origin may be root, signed
+//! or delegated by a doughnut. A delegated call keeps the doughnut it was scheduled with, and the
+//! doughnut's permissions are verified again when the call is dispatched.
+//!
+//! The weight of the scheduled call is charged when it is scheduled, as it is dispatched without
+//! fees. The calls scheduled for a block are dispatched while their total weight is within
+//! `MaximumWeight`. Calls with a priority at or below [`HARD_DEADLINE`] are always dispatched, so
+//! only root may schedule them; the other calls which do not fit are postponed to the next block
+//! whose agenda isn't full.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `schedule` - Schedule a call for a future block.
+//! * `schedule_named` - Schedule a call for a future block under a name.
+//! * `cancel` - Cancel a task by its address in the agenda.
+//! * `cancel_named` - Cancel a task by its name.
+//!
+//! [`HARD_DEADLINE`]: ./constant.HARD_DEADLINE.html
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	DispatchError, DispatchResult, RuntimeDebug,
+	traits::{BadOrigin, Dispatchable, EnsureOrigin, One, Saturating},
+};
+use frame_support::{
+	decl_module, decl_storage, decl_event, decl_error, ensure, Parameter,
+	traits::Get,
+	weights::{DispatchClass, FunctionOf, GetDispatchInfo, SimpleDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_signed, RawOrigin};
+
+/// The priority of a task. Tasks with lower values are dispatched first.
+pub type Priority = u8;
+
+/// Tasks with a priority at or below this are dispatched regardless of the weight limit.
+pub const HARD_DEADLINE: Priority = 63;
+
+/// The lowest priority a task can have.
+pub const LOWEST_PRIORITY: Priority = 255;
+
+/// The address of a task: the block it is scheduled for and its index in that block's agenda.
+pub type TaskAddress<BlockNumber> = (BlockNumber, u32);
+
+/// The origin a scheduled call is dispatched with.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum ScheduledOrigin<AccountId, Doughnut> {
+	/// Dispatched as root.
+	Root,
+	/// Dispatched as signed by the account.
+	Signed(AccountId),
+	/// Dispatched with the authority delegated to the account by the doughnut.
+	Delegated(AccountId, Doughnut),
+}
+
+impl<AccountId, Doughnut> ScheduledOrigin<AccountId, Doughnut> {
+	/// The account the call is dispatched on behalf of, if any.
+	pub fn account(&self) -> Option<&AccountId> {
+		match self {
+			ScheduledOrigin::Root => None,
+			ScheduledOrigin::Signed(who) | ScheduledOrigin::Delegated(who, _) => Some(who),
+		}
+	}
+}
+
+impl<AccountId, Doughnut> From<ScheduledOrigin<AccountId, Doughnut>>
+	for RawOrigin<AccountId, Doughnut>
+{
+	fn from(origin: ScheduledOrigin<AccountId, Doughnut>) -> Self {
+		match origin {
+			ScheduledOrigin::Root => RawOrigin::Root,
+			ScheduledOrigin::Signed(who) => RawOrigin::Signed(who),
+			ScheduledOrigin::Delegated(who, doughnut) => RawOrigin::Delegated(who, doughnut),
+		}
+	}
+}
+
+/// A task in the agenda.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub struct Scheduled<Call, AccountId, Doughnut> {
+	/// The name of the task, if any.
+	maybe_id: Option<Vec<u8>>,
+	/// The priority of the task.
+	priority: Priority,
+	/// The call to dispatch.
+	call: Call,
+	/// The origin to dispatch the call with.
+	origin: ScheduledOrigin<AccountId, Doughnut>,
+}
+
+type ScheduledOriginOf<T> =
+	ScheduledOrigin<<T as system::Trait>::AccountId, <T as system::Trait>::Doughnut>;
+type ScheduledOf<T> =
+	Scheduled<<T as Trait>::Call, <T as system::Trait>::AccountId, <T as system::Trait>::Doughnut>;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The aggregated call type.
+	type Call: Parameter + Dispatchable<Origin=Self::Origin> + GetDispatchInfo;
+
+	/// Required origin to schedule calls dispatched as root, and to cancel any task.
+	type ScheduleOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The maximum weight of the calls dispatched in a block, excluding those with a hard deadline.
+	type MaximumWeight: Get<Weight>;
+
+	/// The maximum number of tasks which may be scheduled for a single block.
+	type MaxScheduledPerBlock: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Scheduler {
+		/// The tasks scheduled for each block. A cancelled task leaves a `None` behind, so the
+		/// addresses of the other tasks do not change.
+		pub Agenda get(fn agenda):
+			map hasher(twox_64_concat) T::BlockNumber => Vec<Option<ScheduledOf<T>>>;
+
+		/// The address of each named task, by the account the task is dispatched on behalf of
+		/// (`None` for root) and name.
+		pub Lookup get(fn lookup):
+			double_map hasher(blake2_128_concat) Option<T::AccountId>, hasher(blake2_128_concat) Vec<u8>
+			=> Option<TaskAddress<T::BlockNumber>>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where BlockNumber = <T as system::Trait>::BlockNumber {
+		/// A task was scheduled (address).
+		Scheduled(TaskAddress<BlockNumber>),
+		/// A task was cancelled (address).
+		Canceled(TaskAddress<BlockNumber>),
+		/// A task was dispatched (address, name, result).
+		Dispatched(TaskAddress<BlockNumber>, Option<Vec<u8>>, DispatchResult),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The task must be scheduled for a future block.
+		TargetBlockNumberInPast,
+		/// The agenda of the block already holds the maximum number of tasks.
+		AgendaFull,
+		/// A task with the same name is already scheduled.
+		NameInUse,
+		/// No task is scheduled at the address or under the name.
+		NotFound,
+		/// The task can only be cancelled by the `ScheduleOrigin` or the account it is dispatched
+		/// on behalf of.
+		NotOwner,
+		/// Only tasks dispatched as root may have a hard deadline.
+		HardDeadlineNotAllowed,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+		type Error = Error<T>;
+
+		/// The maximum weight of the calls dispatched in a block, excluding those with a hard
+		/// deadline.
+		const MaximumWeight: Weight = T::MaximumWeight::get();
+
+		/// The maximum number of tasks which may be scheduled for a single block.
+		const MaxScheduledPerBlock: u32 = T::MaxScheduledPerBlock::get();
+
+		fn deposit_event() = default;
+
+		/// Schedule `call` to be dispatched at the start of block `when`.
+		///
+		/// If `preserve_origin` is set, the call is dispatched with the origin of this call, which
+		/// must be root, signed or delegated. Otherwise it is dispatched as root, and the origin of
+		/// this call must be the `ScheduleOrigin`. Only calls dispatched as root may be given a
+		/// priority at or below `HARD_DEADLINE`.
+		#[weight = FunctionOf(
+			|args: (&T::BlockNumber, &Priority, &bool, &Box<<T as Trait>::Call>)| {
+				args.3.get_dispatch_info().weight.saturating_add(100_000)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn schedule(origin,
+			when: T::BlockNumber,
+			priority: Priority,
+			preserve_origin: bool,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let origin = Self::scheduled_origin(origin, preserve_origin)?;
+			Self::do_schedule(None, when, priority, origin, *call)?;
+		}
+
+		/// Schedule `call` to be dispatched at the start of block `when`, under the name `id`.
+		///
+		/// See `schedule` for the meaning of `preserve_origin`. The name must be unique among the
+		/// tasks dispatched on behalf of the same account.
+		#[weight = FunctionOf(
+			|args: (&Vec<u8>, &T::BlockNumber, &Priority, &bool, &Box<<T as Trait>::Call>)| {
+				args.4.get_dispatch_info().weight.saturating_add(100_000)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn schedule_named(origin,
+			id: Vec<u8>,
+			when: T::BlockNumber,
+			priority: Priority,
+			preserve_origin: bool,
+			call: Box<<T as Trait>::Call>,
+		) {
+			let origin = Self::scheduled_origin(origin, preserve_origin)?;
+			Self::do_schedule(Some(id), when, priority, origin, *call)?;
+		}
+
+		/// Cancel the task at `index` of the agenda of block `when`.
+		///
+		/// The origin must be the `ScheduleOrigin`, or the account the task is dispatched on
+		/// behalf of.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn cancel(origin, when: T::BlockNumber, index: u32) {
+			Self::do_cancel(origin, (when, index))?;
+		}
+
+		/// Cancel the task named `id`.
+		///
+		/// The `ScheduleOrigin` cancels the root task named `id`, a signed origin the task named
+		/// `id` dispatched on its behalf.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn cancel_named(origin, id: Vec<u8>) {
+			let (origin, namespace) = match T::ScheduleOrigin::try_origin(origin) {
+				Ok(_) => (RawOrigin::Root, None),
+				Err(origin) => {
+					let who = ensure_signed(origin)?;
+					(RawOrigin::Signed(who.clone()), Some(who))
+				},
+			};
+			let address = Lookup::<T>::get(&namespace, &id).ok_or(Error::<T>::NotFound)?;
+			Self::do_cancel_as(origin, address)?;
+		}
+
+		fn on_initialize(now: T::BlockNumber) -> Weight {
+			Self::service_agenda(now)
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Return the origin a call scheduled by `origin` should be dispatched with.
+	fn scheduled_origin(
+		origin: T::Origin,
+		preserve_origin: bool,
+	) -> Result<ScheduledOriginOf<T>, DispatchError> {
+		if !preserve_origin {
+			T::ScheduleOrigin::ensure_origin(origin)?;
+			return Ok(ScheduledOrigin::Root);
+		}
+		match origin.into() {
+			Ok(RawOrigin::Root) => Ok(ScheduledOrigin::Root),
+			Ok(RawOrigin::Signed(who)) => Ok(ScheduledOrigin::Signed(who)),
+			Ok(RawOrigin::Delegated(who, doughnut)) =>
+				Ok(ScheduledOrigin::Delegated(who, doughnut)),
+			_ => Err(BadOrigin.into()),
+		}
+	}
+
+	fn do_schedule(
+		maybe_id: Option<Vec<u8>>,
+		when: T::BlockNumber,
+		priority: Priority,
+		origin: ScheduledOriginOf<T>,
+		call: <T as Trait>::Call,
+	) -> DispatchResult {
+		ensure!(when > <system::Module<T>>::block_number(), Error::<T>::TargetBlockNumberInPast);
+		if priority <= HARD_DEADLINE {
+			let is_root = match origin {
+				ScheduledOrigin::Root => true,
+				_ => false,
+			};
+			ensure!(is_root, Error::<T>::HardDeadlineNotAllowed);
+		}
+		let namespace = origin.account().cloned();
+		if let Some(ref id) = maybe_id {
+			ensure!(!Lookup::<T>::contains_key(&namespace, id), Error::<T>::NameInUse);
+		}
+		let mut agenda = Agenda::<T>::get(when);
+		let scheduled = agenda.iter().filter(|task| task.is_some()).count() as u32;
+		ensure!(scheduled < T::MaxScheduledPerBlock::get(), Error::<T>::AgendaFull);
+
+		let address = (when, agenda.len() as u32);
+		if let Some(ref id) = maybe_id {
+			Lookup::<T>::insert(&namespace, id, address);
+		}
+		agenda.push(Some(Scheduled { maybe_id, priority, call, origin }));
+		Agenda::<T>::insert(when, agenda);
+		Self::deposit_event(RawEvent::Scheduled(address));
+		Ok(())
+	}
+
+	fn do_cancel(origin: T::Origin, address: TaskAddress<T::BlockNumber>) -> DispatchResult {
+		let origin = match T::ScheduleOrigin::try_origin(origin) {
+			Ok(_) => RawOrigin::Root,
+			Err(origin) => RawOrigin::Signed(ensure_signed(origin)?),
+		};
+		Self::do_cancel_as(origin, address)
+	}
+
+	/// Cancel the task at `address` on behalf of root, i.e. the `ScheduleOrigin`, or of a signed
+	/// account.
+	fn do_cancel_as(
+		origin: RawOrigin<T::AccountId, T::Doughnut>,
+		address: TaskAddress<T::BlockNumber>,
+	) -> DispatchResult {
+		let (when, index) = address;
+		let task = Agenda::<T>::get(when)
+			.into_iter()
+			.nth(index as usize)
+			.and_then(|task| task)
+			.ok_or(Error::<T>::NotFound)?;
+		if let RawOrigin::Signed(who) = origin {
+			ensure!(task.origin.account() == Some(&who), Error::<T>::NotOwner);
+		}
+
+		Agenda::<T>::mutate(when, |agenda| agenda[index as usize] = None);
+		if let Some(id) = task.maybe_id {
+			Lookup::<T>::remove(task.origin.account().cloned(), id);
+		}
+		Self::deposit_event(RawEvent::Canceled(address));
+		Ok(())
+	}
+
+	/// Dispatch the tasks scheduled for `now`, returning the weight of the dispatched calls.
+	fn service_agenda(now: T::BlockNumber) -> Weight {
+		let limit = T::MaximumWeight::get();
+		let mut queued = Agenda::<T>::take(now)
+			.into_iter()
+			.enumerate()
+			.filter_map(|(index, task)| task.map(|task| (index as u32, task)))
+			.collect::<Vec<_>>();
+		// The sort is stable, tasks of equal priority are dispatched in the order they were
+		// scheduled.
+		queued.sort_by_key(|(_, task)| task.priority);
+
+		let mut postponed = Vec::new();
+		let mut total_weight: Weight = 0;
+		for (order, (index, task)) in queued.into_iter().enumerate() {
+			let weight = task.call.get_dispatch_info().weight;
+			let cumulative_weight = total_weight.saturating_add(weight);
+			// The first task is always dispatched, so a task heavier than the limit can not block
+			// the agenda forever.
+			if task.priority <= HARD_DEADLINE || cumulative_weight <= limit || order == 0 {
+				total_weight = cumulative_weight;
+				let Scheduled { maybe_id, call, origin, .. } = task;
+				if let Some(ref id) = maybe_id {
+					Lookup::<T>::remove(origin.account().cloned(), id);
+				}
+				let result = call.dispatch(RawOrigin::from(origin).into());
+				Self::deposit_event(RawEvent::Dispatched((now, index), maybe_id, result));
+			} else {
+				postponed.push(task);
+			}
+		}
+		Self::postpone(now, postponed);
+		total_weight
+	}
+
+	/// Move the `tasks` which didn't fit in the agenda of `now` to the next blocks, filling each
+	/// agenda up to `MaxScheduledPerBlock` tasks.
+	fn postpone(now: T::BlockNumber, tasks: Vec<ScheduledOf<T>>) {
+		if tasks.is_empty() {
+			return;
+		}
+		let max = T::MaxScheduledPerBlock::get().max(1) as usize;
+		let mut when = now.saturating_add(One::one());
+		let mut agenda = Agenda::<T>::get(when);
+		let mut changed = false;
+		for task in tasks {
+			while agenda.iter().filter(|scheduled| scheduled.is_some()).count() >= max {
+				if changed {
+					Agenda::<T>::insert(when, &agenda);
+				}
+				when = when.saturating_add(One::one());
+				agenda = Agenda::<T>::get(when);
+				changed = false;
+			}
+			if let Some(ref id) = task.maybe_id {
+				Lookup::<T>::insert(task.origin.account().cloned(), id, (when, agenda.len() as u32));
+			}
+			agenda.push(Some(task));
+			changed = true;
+		}
+		Agenda::<T>::insert(when, agenda);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, parameter_types, impl_outer_dispatch,
+		impl_outer_event, traits::OnInitialize, weights::{DispatchClass, FunctionOf},
+	};
+	use sp_core::H256;
+	use sp_runtime::{Perbill, traits::{BlakeTwo256, IdentityLookup}, testing::Header};
+	use crate as scheduler;
+
+	mod logger {
+		use super::*;
+		use std::cell::RefCell;
+
+		/// The origin a call was logged with.
+		#[derive(Clone, Debug, PartialEq)]
+		pub enum LoggedOrigin {
+			Root,
+			Signed(u64),
+			Delegated(u64),
+		}
+
+		thread_local! {
+			static LOG: RefCell<Vec<(LoggedOrigin, u32)>> = RefCell::new(Vec::new());
+		}
+
+		pub fn log() -> Vec<(LoggedOrigin, u32)> {
+			LOG.with(|log| log.borrow().clone())
+		}
+
+		pub trait Trait: system::Trait<AccountId = u64> {}
+
+		decl_module! {
+			pub struct Module<T: Trait> for enum Call where origin: <T as system::Trait>::Origin {
+				#[weight = FunctionOf(|args: (&u32, &Weight)| *args.1, DispatchClass::Normal, true)]
+				fn log(origin, i: u32, weight: Weight) {
+					let origin = match origin.into() {
+						Ok(RawOrigin::Root) => LoggedOrigin::Root,
+						Ok(RawOrigin::Signed(who)) => LoggedOrigin::Signed(who),
+						Ok(RawOrigin::Delegated(who, _)) => LoggedOrigin::Delegated(who),
+						_ => Err(BadOrigin)?,
+					};
+					let _ = weight;
+					LOG.with(|log| log.borrow_mut().push((origin, i)));
+				}
+			}
+		}
+	}
+	use logger::LoggedOrigin;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			scheduler<T>,
+		}
+	}
+
+	impl_outer_dispatch! {
+		pub enum Call for Test where origin: Origin {
+			system::System,
+			logger::Logger,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 2_000;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = Call;
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	impl logger::Trait for Test {}
+	parameter_types! {
+		pub const MaximumWeight: Weight = 1_000;
+		pub const MaxScheduledPerBlock: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Call = Call;
+		type ScheduleOrigin = system::EnsureRoot<u64, ()>;
+		type MaximumWeight = MaximumWeight;
+		type MaxScheduledPerBlock = MaxScheduledPerBlock;
+	}
+	type System = system::Module<Test>;
+	type Logger = logger::Module<Test>;
+	type Scheduler = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		t.into()
+	}
+
+	fn run_to_block(n: u64) {
+		while System::block_number() < n {
+			System::set_block_number(System::block_number() + 1);
+			Scheduler::on_initialize(System::block_number());
+		}
+	}
+
+	/// The namespace of the names of root tasks.
+	const ROOT: Option<u64> = None;
+
+	fn log_call(i: u32, weight: Weight) -> Box<Call> {
+		Box::new(Call::Logger(logger::Call::log(i, weight)))
+	}
+
+	#[test]
+	fn basic_scheduling_works() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(42, 10),
+			));
+			run_to_block(3);
+			assert!(logger::log().is_empty());
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 42)]);
+			assert!(Scheduler::agenda(4).is_empty());
+		});
+	}
+
+	#[test]
+	fn scheduling_as_root_requires_schedule_origin() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Scheduler::schedule(Origin::signed(1), 4, LOWEST_PRIORITY, false, log_call(42, 10)),
+				BadOrigin,
+			);
+		});
+	}
+
+	#[test]
+	fn origin_is_preserved() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(
+				Origin::signed(1), 4, LOWEST_PRIORITY, true, log_call(1, 10),
+			));
+			assert_ok!(Scheduler::schedule(
+				RawOrigin::Delegated(2, ()).into(),
+				4,
+				LOWEST_PRIORITY,
+				true,
+				log_call(2, 10),
+			));
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, LOWEST_PRIORITY, true, log_call(3, 10),
+			));
+			assert_noop!(
+				Scheduler::schedule(Origin::NONE, 4, LOWEST_PRIORITY, true, log_call(4, 10)),
+				BadOrigin,
+			);
+			run_to_block(4);
+			assert_eq!(
+				logger::log(),
+				vec![
+					(LoggedOrigin::Signed(1), 1),
+					(LoggedOrigin::Delegated(2), 2),
+					(LoggedOrigin::Root, 3),
+				],
+			);
+		});
+	}
+
+	#[test]
+	fn schedule_named_and_cancel_named_work() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"rotate".to_vec(), 4, LOWEST_PRIORITY, false, log_call(1, 10),
+			));
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"other".to_vec(), 4, LOWEST_PRIORITY, false, log_call(2, 10),
+			));
+			assert_eq!(Scheduler::lookup(ROOT, b"rotate".to_vec()), Some((4, 0)));
+			assert_noop!(
+				Scheduler::schedule_named(
+					Origin::ROOT, b"rotate".to_vec(), 5, LOWEST_PRIORITY, false, log_call(3, 10),
+				),
+				Error::<Test>::NameInUse,
+			);
+
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, b"rotate".to_vec()));
+			assert_eq!(Scheduler::lookup(ROOT, b"rotate".to_vec()), None);
+			assert_noop!(
+				Scheduler::cancel_named(Origin::ROOT, b"rotate".to_vec()),
+				Error::<Test>::NotFound,
+			);
+
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 2)]);
+			assert_eq!(Scheduler::lookup(ROOT, b"other".to_vec()), None);
+		});
+	}
+
+	#[test]
+	fn cancel_requires_schedule_origin_or_owner() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(
+				Origin::signed(1), 4, LOWEST_PRIORITY, true, log_call(1, 10),
+			));
+			assert_ok!(Scheduler::schedule(
+				Origin::signed(1), 4, LOWEST_PRIORITY, true, log_call(2, 10),
+			));
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(3, 10),
+			));
+
+			assert_noop!(Scheduler::cancel(Origin::signed(2), 4, 0), Error::<Test>::NotOwner);
+			assert_noop!(Scheduler::cancel(Origin::signed(1), 4, 2), Error::<Test>::NotOwner);
+			assert_ok!(Scheduler::cancel(Origin::signed(1), 4, 0));
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 4, 1));
+			assert_noop!(Scheduler::cancel(Origin::ROOT, 4, 1), Error::<Test>::NotFound);
+
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 3)]);
+		});
+	}
+
+	#[test]
+	fn scheduling_checks_target_block_and_agenda_size() {
+		new_test_ext().execute_with(|| {
+			run_to_block(2);
+			assert_noop!(
+				Scheduler::schedule(Origin::ROOT, 2, LOWEST_PRIORITY, false, log_call(1, 10)),
+				Error::<Test>::TargetBlockNumberInPast,
+			);
+			for i in 0..3 {
+				assert_ok!(Scheduler::schedule(
+					Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(i, 10),
+				));
+			}
+			assert_noop!(
+				Scheduler::schedule(Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(3, 10)),
+				Error::<Test>::AgendaFull,
+			);
+			// a cancelled task frees its slot
+			assert_ok!(Scheduler::cancel(Origin::ROOT, 4, 0));
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(3, 10),
+			));
+		});
+	}
+
+	#[test]
+	fn tasks_are_dispatched_by_priority_within_weight_limit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, 200, false, log_call(1, 400)));
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"late".to_vec(), 4, 100, false, log_call(2, 400),
+			));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, 150, false, log_call(3, 400)));
+			run_to_block(4);
+			// only two tasks fit in the weight limit, the lowest priority one is postponed
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 2), (LoggedOrigin::Root, 3)]);
+			assert_eq!(Scheduler::lookup(ROOT, b"late".to_vec()), None);
+			run_to_block(5);
+			assert_eq!(
+				logger::log(),
+				vec![(LoggedOrigin::Root, 2), (LoggedOrigin::Root, 3), (LoggedOrigin::Root, 1)],
+			);
+		});
+	}
+
+	#[test]
+	fn only_root_tasks_have_hard_deadlines() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Scheduler::schedule(Origin::signed(1), 4, HARD_DEADLINE, true, log_call(1, 10)),
+				Error::<Test>::HardDeadlineNotAllowed,
+			);
+			assert_ok!(Scheduler::schedule(
+				Origin::signed(1), 4, HARD_DEADLINE + 1, true, log_call(1, 10),
+			));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, HARD_DEADLINE, true, log_call(2, 10)));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, 0, false, log_call(3, 10)));
+		});
+	}
+
+	#[test]
+	fn names_are_scoped_to_the_owner() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule_named(
+				Origin::signed(1), b"task".to_vec(), 4, LOWEST_PRIORITY, true, log_call(1, 10),
+			));
+			assert_ok!(Scheduler::schedule_named(
+				Origin::signed(2), b"task".to_vec(), 4, LOWEST_PRIORITY, true, log_call(2, 10),
+			));
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"task".to_vec(), 4, LOWEST_PRIORITY, false, log_call(3, 10),
+			));
+			assert_eq!(Scheduler::lookup(Some(2), b"task".to_vec()), Some((4, 1)));
+
+			assert_ok!(Scheduler::cancel_named(Origin::signed(2), b"task".to_vec()));
+			assert_noop!(
+				Scheduler::cancel_named(Origin::signed(3), b"task".to_vec()),
+				Error::<Test>::NotFound,
+			);
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, b"task".to_vec()));
+
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Signed(1), 1)]);
+			assert_eq!(Scheduler::lookup(Some(1), b"task".to_vec()), None);
+		});
+	}
+
+	#[test]
+	fn scheduling_is_charged_the_call_weight() {
+		let weight = super::Call::<Test>::schedule(4, LOWEST_PRIORITY, true, log_call(1, 400))
+			.get_dispatch_info()
+			.weight;
+		assert_eq!(weight, 100_400);
+		let weight = super::Call::<Test>::schedule_named(
+			b"task".to_vec(), 4, LOWEST_PRIORITY, true, log_call(1, 400),
+		).get_dispatch_info().weight;
+		assert_eq!(weight, 100_400);
+	}
+
+	#[test]
+	fn postponed_tasks_respect_agenda_size() {
+		new_test_ext().execute_with(|| {
+			for i in 0..2 {
+				assert_ok!(Scheduler::schedule(
+					Origin::ROOT, 4, LOWEST_PRIORITY, false, log_call(i, 600),
+				));
+			}
+			for i in 2..4 {
+				assert_ok!(Scheduler::schedule(
+					Origin::ROOT, 5, LOWEST_PRIORITY, false, log_call(i, 10),
+				));
+			}
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"last".to_vec(), 4, 200, false, log_call(5, 600),
+			));
+			run_to_block(4);
+			// only one task fits in the weight limit of block 4 and one in the agenda of block 5
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 5)]);
+			assert_eq!(Scheduler::agenda(5).len(), 3);
+			assert_eq!(Scheduler::agenda(6).len(), 1);
+		});
+	}
+
+	#[test]
+	fn hard_deadline_tasks_ignore_weight_limit() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(Scheduler::schedule_named(
+				Origin::ROOT, b"soft".to_vec(), 4, LOWEST_PRIORITY, false, log_call(1, 600),
+			));
+			assert_ok!(Scheduler::schedule(
+				Origin::ROOT, 4, HARD_DEADLINE, false, log_call(2, 600),
+			));
+			assert_ok!(Scheduler::schedule(Origin::ROOT, 4, 0, false, log_call(3, 600)));
+			run_to_block(4);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 3), (LoggedOrigin::Root, 2)]);
+			// the postponed task can still be cancelled by name
+			assert_eq!(Scheduler::lookup(ROOT, b"soft".to_vec()), Some((5, 0)));
+			assert_ok!(Scheduler::cancel_named(Origin::ROOT, b"soft".to_vec()));
+			run_to_block(5);
+			assert_eq!(logger::log(), vec![(LoggedOrigin::Root, 3), (LoggedOrigin::Root, 2)]);
+		});
+	}
+}
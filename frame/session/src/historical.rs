@@ -24,19 +24,44 @@
 //!
 //! These roots and proofs of inclusion can be generated at any time during the current session.
 //! Afterwards, the proofs can be fed to a consensus module when reporting misbehavior.
+//!
+//! The validator sets committed to are also written to the offchain index, so proofs for the
+//! past sessions in the stored range can be generated off-chain with
+//! [`Module::prove_session_membership`], e.g. to report an equivocation found late or to
+//! validate an old justification in a header bridge. The offchain index is read by offchain
+//! workers and by the runtime API calls of the client, which are given read access to it.
 
 use sp_std::prelude::*;
+use sp_std::collections::btree_set::BTreeSet;
 use codec::{Encode, Decode};
 use sp_runtime::{KeyTypeId, RuntimeDebug};
+use sp_runtime::offchain::StorageKind;
 use sp_runtime::traits::{Convert, OpaqueKeys};
 use frame_support::{decl_module, decl_storage};
 use frame_support::{Parameter, print};
+use frame_support::traits::KeyOwnerProofSystem;
 use sp_trie::{MemoryDB, Trie, TrieMut, Recorder, EMPTY_PREFIX};
 use sp_trie::trie_types::{TrieDBMut, TrieDB};
 use super::{SessionIndex, Module as SessionModule};
 
 type ValidatorCount = u32;
 
+/// A validator set with the full identifications and the session keys of the validators, as
+/// written to the offchain index.
+type ValidatorSet<T> = Vec<(
+	<T as crate::Trait>::ValidatorId,
+	<T as Trait>::FullIdentification,
+	Option<<T as crate::Trait>::Keys>,
+)>;
+
+/// Prefix of the offchain index keys of the validator sets, followed by the session-data root.
+pub const VALIDATOR_SET_PREFIX: &[u8] = b"session_historical::validator_set::";
+
+/// The offchain index key of the validator set committed to by the given session-data root.
+pub fn validator_set_key<T: Trait>(root: &T::Hash) -> Vec<u8> {
+	[VALIDATOR_SET_PREFIX, root.as_ref()].concat()
+}
+
 /// Trait necessary for the historical module.
 pub trait Trait: super::Trait {
 	/// Full identification of the validator.
@@ -73,6 +98,9 @@ decl_module! {
 impl<T: Trait> Module<T> {
 	/// Prune historical stored session roots up to (but not including)
 	/// `up_to`.
+	///
+	/// The validator sets of the pruned roots are removed from the offchain index, unless a
+	/// session still stored commits to the same set.
 	pub fn prune_up_to(up_to: SessionIndex) {
 		<Self as Store>::StoredRange::mutate(|range| {
 			let (start, end) = match *range {
@@ -86,7 +114,16 @@ impl<T: Trait> Module<T> {
 				return // out of bounds. harmless.
 			}
 
-			(start..up_to).for_each(<Self as Store>::HistoricalSessions::remove);
+			let kept_roots = (up_to..end)
+				.filter_map(|i| <Self as Store>::HistoricalSessions::get(i).map(|(root, _)| root))
+				.collect::<BTreeSet<_>>();
+			for i in start..up_to {
+				if let Some((root, _)) = <Self as Store>::HistoricalSessions::take(i) {
+					if !kept_roots.contains(&root) {
+						sp_io::offchain_index::clear(&validator_set_key::<T>(&root));
+					}
+				}
+			}
 
 			let new_start = up_to;
 			*range = if new_start == end {
@@ -96,6 +133,37 @@ impl<T: Trait> Module<T> {
 			}
 		})
 	}
+
+	/// Generate a proof that the key of the given type is a key of a validator of `session`.
+	///
+	/// The proofs of past sessions are generated from the validator sets in the offchain index,
+	/// so this must only be called off-chain, from offchain workers or runtime API calls, which
+	/// have read access to the offchain storage. Returns `None` if the session isn't stored, its
+	/// validator set isn't indexed by this node or the key isn't a key of a validator of the
+	/// session.
+	pub fn prove_session_membership(session: SessionIndex, key: (KeyTypeId, &[u8])) -> Option<Proof> {
+		if session == <SessionModule<T>>::current_index() {
+			return <Self as KeyOwnerProofSystem<(KeyTypeId, &[u8])>>::prove(key);
+		}
+
+		let (root, _) = <HistoricalSessions<T>>::get(session)?;
+		let validators = sp_io::offchain::local_storage_get(
+			StorageKind::PERSISTENT,
+			&validator_set_key::<T>(&root),
+		).and_then(|raw| <ValidatorSet<T>>::decode(&mut &raw[..]).ok())?;
+
+		let trie = ProvingTrie::<T>::generate_for_keys(validators).ok()?;
+		if trie.root != root {
+			return None;
+		}
+
+		let (id, data) = key;
+
+		trie.prove(id, data).map(|trie_nodes| Proof {
+			session,
+			trie_nodes,
+		})
+	}
 }
 
 /// Specialization of the crate-level `SessionManager` which returns the set of full identification
@@ -127,8 +195,17 @@ impl<T: Trait, I> crate::SessionManager<T::ValidatorId> for NoteHistoricalRoot<T
 
 		if let Some(new_validators) = new_validators_and_id {
 			let count = new_validators.len() as u32;
-			match ProvingTrie::<T>::generate_for(new_validators) {
-				Ok(trie) => <HistoricalSessions<T>>::insert(new_index, &(trie.root, count)),
+			let new_validators: ValidatorSet<T> = new_validators.into_iter()
+				.map(|(validator, full_id)| {
+					let keys = <SessionModule<T>>::load_keys(&validator);
+					(validator, full_id, keys)
+				})
+				.collect();
+			match ProvingTrie::<T>::generate_for_keys(new_validators.iter().cloned()) {
+				Ok(trie) => {
+					<HistoricalSessions<T>>::insert(new_index, &(trie.root, count));
+					sp_io::offchain_index::set(&validator_set_key::<T>(&trie.root), &new_validators.encode());
+				},
 				Err(reason) => {
 					print("Failed to generate historical ancestry-inclusion proof.");
 					print(reason);
@@ -163,15 +240,24 @@ pub struct ProvingTrie<T: Trait> {
 impl<T: Trait> ProvingTrie<T> {
 	fn generate_for<I>(validators: I) -> Result<Self, &'static str>
 		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification)>
+	{
+		Self::generate_for_keys(validators.into_iter().map(|(validator, full_id)| {
+			let keys = <SessionModule<T>>::load_keys(&validator);
+			(validator, full_id, keys)
+		}))
+	}
+
+	fn generate_for_keys<I>(validators: I) -> Result<Self, &'static str>
+		where I: IntoIterator<Item=(T::ValidatorId, T::FullIdentification, Option<T::Keys>)>
 	{
 		let mut db = MemoryDB::default();
 		let mut root = Default::default();
 
 		{
 			let mut trie = TrieDBMut::new(&mut db, &mut root);
-			for (i, (validator, full_id)) in validators.into_iter().enumerate() {
+			for (i, (validator, full_id, keys)) in validators.into_iter().enumerate() {
 				let i = i as u32;
-				let keys = match <SessionModule<T>>::load_keys(&validator) {
+				let keys = match keys {
 					None => continue,
 					Some(k) => k,
 				};
@@ -310,6 +396,7 @@ impl<T: Trait, D: AsRef<[u8]>> frame_support::traits::KeyOwnerProofSystem<(KeyTy
 mod tests {
 	use super::*;
 	use sp_core::crypto::key_types::DUMMY;
+	use sp_core::offchain::{OffchainExt, OffchainStorage, testing::TestOffchainExt};
 	use sp_runtime::testing::UintAuthorityId;
 	use crate::mock::{
 		NEXT_VALIDATORS, force_new_session,
@@ -364,6 +451,60 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn past_session_membership_is_proven_from_offchain_index() {
+		let mut ext = new_test_ext();
+		let (offchain, state) = TestOffchainExt::new();
+		ext.register_extension(OffchainExt::new(offchain));
+
+		ext.execute_with(|| {
+			set_next_validators(vec![1, 2]);
+			force_new_session();
+
+			System::set_block_number(1);
+			Session::on_initialize(1);
+
+			let session = Session::current_index() + 1;
+			let (root, _) = Historical::historical_root(session).unwrap();
+
+			set_next_validators(vec![1, 2, 4]);
+			force_new_session();
+
+			System::set_block_number(2);
+			Session::on_initialize(2);
+
+			force_new_session();
+
+			System::set_block_number(3);
+			Session::on_initialize(3);
+
+			assert!(Session::current_index() > session);
+
+			let encoded_key_1 = UintAuthorityId(1).encode();
+			let key = (DUMMY, &encoded_key_1[..]);
+
+			// the validator set of the session isn't indexed.
+			assert!(Historical::prove_session_membership(session, key).is_none());
+
+			// the offchain index is written on block import.
+			let validators: ValidatorSet<Test> = vec![1, 2].into_iter()
+				.map(|validator| (validator, validator, Session::load_keys(&validator)))
+				.collect();
+			state.write().persistent_storage.set(
+				b"",
+				&validator_set_key::<Test>(&root),
+				&validators.encode(),
+			);
+
+			let proof = Historical::prove_session_membership(session, key).unwrap();
+			assert_eq!(proof.session(), session);
+			assert_eq!(Historical::check_proof(key, proof), Some((1, 1)));
+
+			let encoded_key_4 = UintAuthorityId(4).encode();
+			assert!(Historical::prove_session_membership(session, (DUMMY, &encoded_key_4[..])).is_none());
+		});
+	}
+
 	#[test]
 	fn prune_up_to_works() {
 		new_test_ext().execute_with(|| {
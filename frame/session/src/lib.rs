@@ -40,7 +40,10 @@
 //! - **Session key configuration process:** Session keys are set using `set_keys` for use not in
 //! the next session, but the session after next. They are stored in `NextKeys`, a mapping between
 //! the caller's `ValidatorId` and the session keys provided. `set_keys` allows users to set their
-//! session key prior to being selected as validator.
+//! session key prior to being selected as validator. The keys must come with a proof of possession,
+//! the signature by every key of the `ownership_message` of the caller and the next session index,
+//! tagged with `OWNERSHIP_DOMAIN`,
+//! as returned by `author_rotateKeys` when given the caller account.
 //! It is a public call since it uses `ensure_signed`, which checks that the origin is a signed account.
 //! As such, the account ID of the origin stored in `NextKeys` may not necessarily be associated with
 //! a block author or a validator. The session keys of accounts are removed once their account balance is zero.
@@ -100,13 +103,13 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::{prelude::*, marker::PhantomData, ops::{Sub, Rem}};
-use codec::Decode;
+use codec::{Decode, Encode};
 use sp_runtime::{KeyTypeId, Perbill, RuntimeAppPublic, BoundToRuntimeAppPublic};
 use sp_runtime::traits::{Convert, Zero, Member, OpaqueKeys};
 use sp_staking::SessionIndex;
 use frame_support::{
 	ensure, decl_module, decl_event, decl_storage, decl_error, ConsensusEngineId, Parameter,
-	weights::{Weight, SimpleDispatchInfo, WeighData},
+	weights::{Weight, SimpleDispatchInfo, WeighData, FunctionOf, DispatchClass},
 	traits::{Get, FindAuthor, ValidatorRegistration},
 	dispatch::{self, DispatchResult, DispatchError},
 };
@@ -117,6 +120,13 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+/// The domain the `ownership_message` is signed in, so a proof of possession of the session keys
+/// can't be a signature made for another purpose.
+pub const OWNERSHIP_DOMAIN: &[u8] = b"session_keys_ownership";
+
+/// The weight of verifying the signature of a session key.
+const SIGNATURE_VERIFICATION_WEIGHT: Weight = 50_000;
+
 #[cfg(feature = "historical")]
 pub mod historical;
 
@@ -459,19 +469,37 @@ decl_module! {
 		/// Allows an account to set its session key prior to becoming a validator.
 		/// This doesn't take effect until the next session.
 		///
+		/// `proof` is the signature by every key of the `ownership_message` of the caller and the
+		/// next session index. A proof made for the current session index is accepted too, so keys
+		/// rotated just before a session change can still be set.
+		///
 		/// The dispatch origin of this function must be signed.
 		///
 		/// # <weight>
 		/// - O(log n) in number of accounts.
+		/// - Up to 2 * N signature verifications, N being the number of key types, for the proof
+		///   of the next and of the current session index.
 		/// - One extra DB entry.
 		/// - Increases system account refs by one on success iff there were previously no keys set.
 		///   In this case, purge_keys will need to be called before the account can be removed.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(150_000)]
+		#[weight = FunctionOf(
+			|_: (&T::Keys, &Vec<u8>)| {
+				let verifications = 2 * T::Keys::key_ids().len() as Weight;
+				verifications.saturating_mul(SIGNATURE_VERIFICATION_WEIGHT).saturating_add(150_000)
+			},
+			DispatchClass::Normal,
+			true,
+		)]
 		pub fn set_keys(origin, keys: T::Keys, proof: Vec<u8>) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
 
-			ensure!(keys.ownership_proof_is_valid(&proof), Error::<T>::InvalidProof);
+			let index = CurrentIndex::get();
+			ensure!(
+				keys.ownership_proof_is_valid(&Self::ownership_message(&who, index + 1), &proof)
+					|| keys.ownership_proof_is_valid(&Self::ownership_message(&who, index), &proof),
+				Error::<T>::InvalidProof,
+			);
 
 			Self::do_set_keys(&who, keys)?;
 
@@ -507,6 +535,15 @@ decl_module! {
 }
 
 impl<T: Trait> Module<T> {
+	/// The message signed by every session key of `who` to prove possession of the keys, when
+	/// setting them during the session before `index`: `OWNERSHIP_DOMAIN` followed by the
+	/// encoded `who` and `index`.
+	pub fn ownership_message(who: &T::AccountId, index: SessionIndex) -> Vec<u8> {
+		let mut message = OWNERSHIP_DOMAIN.to_vec();
+		(who, index).encode_to(&mut message);
+		message
+	}
+
 	/// Move on to next session. Register new validator set and session keys. Changes
 	/// to the validator set have a session of delay to take effect. This allows for
 	/// equivocation punishment after a fork.
@@ -142,6 +142,12 @@ pub fn set_next_validators(next: Vec<u64>) {
 	NEXT_VALIDATORS.with(|v| *v.borrow_mut() = next);
 }
 
+/// The proof of possession of `keys` by `who`, for setting them during the current session.
+pub fn ownership_proof(who: u64, keys: MockSessionKeys) -> Vec<u8> {
+	let message = Module::<Test>::ownership_message(&who, Module::<Test>::current_index() + 1);
+	keys.ownership_proof(&message).expect("Mock keys always sign")
+}
+
 pub fn before_session_end_called() -> bool {
 	BEFORE_SESSION_END_CALLED.with(|b| *b.borrow())
 }
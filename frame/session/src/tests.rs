@@ -17,13 +17,13 @@
 // Tests for the Session Pallet
 
 use super::*;
-use frame_support::{traits::OnInitialize, assert_ok};
+use frame_support::{traits::OnInitialize, assert_ok, assert_noop};
 use sp_core::crypto::key_types::DUMMY;
 use sp_runtime::testing::UintAuthorityId;
 use mock::{
 	NEXT_VALIDATORS, SESSION_CHANGED, TEST_SESSION_CHANGED, authorities, force_new_session,
 	set_next_validators, set_session_length, session_changed, Test, Origin, System, Session,
-	reset_before_session_end_called, before_session_end_called,
+	reset_before_session_end_called, before_session_end_called, ownership_proof,
 };
 
 fn new_test_ext() -> sp_io::TestExternalities {
@@ -104,7 +104,7 @@ fn authorities_should_track_validators() {
 		reset_before_session_end_called();
 
 		set_next_validators(vec![1, 2, 4]);
-		assert_ok!(Session::set_keys(Origin::signed(4), UintAuthorityId(4).into(), vec![]));
+		assert_ok!(Session::set_keys(Origin::signed(4), UintAuthorityId(4).into(), ownership_proof(4, UintAuthorityId(4).into())));
 		force_new_session();
 		initialize_block(3);
 		assert_eq!(Session::queued_keys(), vec![
@@ -164,7 +164,7 @@ fn session_change_should_work() {
 
 		// Block 3: Set new key for validator 2; no visible change.
 		initialize_block(3);
-		assert_ok!(Session::set_keys(Origin::signed(2), UintAuthorityId(5).into(), vec![]));
+		assert_ok!(Session::set_keys(Origin::signed(2), UintAuthorityId(5).into(), ownership_proof(2, UintAuthorityId(5).into())));
 		assert_eq!(authorities(), vec![UintAuthorityId(1), UintAuthorityId(2), UintAuthorityId(3)]);
 
 		// Block 4: Session rollover; no visible change.
@@ -186,11 +186,11 @@ fn duplicates_are_not_allowed() {
 	new_test_ext().execute_with(|| {
 		System::set_block_number(1);
 		Session::on_initialize(1);
-		assert!(Session::set_keys(Origin::signed(4), UintAuthorityId(1).into(), vec![]).is_err());
-		assert!(Session::set_keys(Origin::signed(1), UintAuthorityId(10).into(), vec![]).is_ok());
+		assert!(Session::set_keys(Origin::signed(4), UintAuthorityId(1).into(), ownership_proof(4, UintAuthorityId(1).into())).is_err());
+		assert!(Session::set_keys(Origin::signed(1), UintAuthorityId(10).into(), ownership_proof(1, UintAuthorityId(10).into())).is_ok());
 
 		// is fine now that 1 has migrated off.
-		assert!(Session::set_keys(Origin::signed(4), UintAuthorityId(1).into(), vec![]).is_ok());
+		assert!(Session::set_keys(Origin::signed(4), UintAuthorityId(1).into(), ownership_proof(4, UintAuthorityId(1).into())).is_ok());
 	});
 }
 
@@ -232,7 +232,7 @@ fn session_changed_flag_works() {
 		assert!(before_session_end_called());
 		reset_before_session_end_called();
 
-		assert_ok!(Session::set_keys(Origin::signed(2), UintAuthorityId(5).into(), vec![]));
+		assert_ok!(Session::set_keys(Origin::signed(2), UintAuthorityId(5).into(), ownership_proof(2, UintAuthorityId(5).into())));
 		force_new_session();
 		initialize_block(6);
 		assert!(!session_changed());
@@ -240,7 +240,7 @@ fn session_changed_flag_works() {
 		reset_before_session_end_called();
 
 		// changing the keys of a validator leads to change.
-		assert_ok!(Session::set_keys(Origin::signed(69), UintAuthorityId(69).into(), vec![]));
+		assert_ok!(Session::set_keys(Origin::signed(69), UintAuthorityId(69).into(), ownership_proof(69, UintAuthorityId(69).into())));
 		force_new_session();
 		initialize_block(7);
 		assert!(session_changed());
@@ -289,11 +289,12 @@ fn periodic_session_works() {
 fn session_keys_generate_output_works_as_set_keys_input() {
 	new_test_ext().execute_with(|| {
 		let new_keys = mock::MockSessionKeys::generate(None);
+		let new_keys = <mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys");
 		assert_ok!(
 			Session::set_keys(
 				Origin::signed(2),
-				<mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys"),
-				vec![],
+				new_keys.clone(),
+				ownership_proof(2, new_keys),
 			)
 		);
 	});
@@ -320,17 +321,47 @@ fn return_true_if_more_than_third_is_disabled() {
 fn session_has_keys() {
 	new_test_ext().execute_with(|| {
 		let new_keys = mock::MockSessionKeys::generate(None);
+		let new_keys = <mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys");
 		assert_ok!(
 			Session::set_keys(
 				Origin::signed(0),
-				<mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys"),
-				vec![],
+				new_keys.clone(),
+				ownership_proof(0, new_keys),
 			)
 		);
 		assert!(Session::has_keys(&0));
 	});
 }
 
+#[test]
+fn keys_without_ownership_proof_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let keys: mock::MockSessionKeys = UintAuthorityId(5).into();
+
+		assert_noop!(
+			Session::set_keys(Origin::signed(2), keys.clone(), vec![]),
+			Error::<Test>::InvalidProof,
+		);
+		// A proof made for another account can't be replayed.
+		assert_noop!(
+			Session::set_keys(Origin::signed(2), keys.clone(), ownership_proof(3, keys.clone())),
+			Error::<Test>::InvalidProof,
+		);
+		assert_ok!(Session::set_keys(Origin::signed(2), keys.clone(), ownership_proof(2, keys)));
+	});
+}
+
+#[test]
+fn ownership_proof_for_the_current_session_is_accepted() {
+	new_test_ext().execute_with(|| {
+		let keys: mock::MockSessionKeys = UintAuthorityId(5).into();
+		let message = Session::ownership_message(&2, Session::current_index());
+		let proof = keys.ownership_proof(&message).unwrap();
+
+		assert_ok!(Session::set_keys(Origin::signed(2), keys, proof));
+	});
+}
+
 #[test]
 fn session_has_keys_is_false() {
 	new_test_ext().execute_with(|| {
@@ -342,11 +373,12 @@ fn session_has_keys_is_false() {
 fn session_new_account_has_keys() {
 	new_test_ext().execute_with(|| {
 		let new_keys = mock::MockSessionKeys::generate(None);
+		let new_keys = <mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys");
 		assert_ok!(
 			Session::set_keys(
 				Origin::signed(99),
-				<mock::Test as Trait>::Keys::decode(&mut &new_keys[..]).expect("Decode keys"),
-				vec![],
+				new_keys.clone(),
+				ownership_proof(99, new_keys),
 			)
 		);
 		assert!(Session::has_keys(&99));
@@ -81,6 +81,7 @@ fn construct_runtime_parsed(definition: RuntimeDefinition) -> Result<TokenStream
 		&scrate,
 	)?;
 	let all_modules = decl_all_modules(&name, modules.iter());
+	let all_offchain_workers = decl_all_offchain_workers(all_but_system_modules.clone(), &scrate);
 	let module_to_index = decl_module_to_index(modules.iter(), modules.len(), &scrate);
 
 	let dispatch = decl_outer_dispatch(&name, modules.iter(), &scrate);
@@ -108,6 +109,8 @@ fn construct_runtime_parsed(definition: RuntimeDefinition) -> Result<TokenStream
 
 		#all_modules
 
+		#all_offchain_workers
+
 		#module_to_index
 
 		#dispatch
@@ -370,6 +373,51 @@ fn decl_all_modules<'a>(
 	)
 }
 
+fn decl_all_offchain_workers<'a>(
+	module_declarations: impl Iterator<Item = &'a ModuleDeclaration>,
+	scrate: &TokenStream2,
+) -> TokenStream2 {
+	let (names, modules): (Vec<_>, Vec<_>) = module_declarations
+		.map(|d| (syn::LitByteStr::new(d.name.to_string().as_bytes(), d.name.span()), &d.name))
+		.unzip();
+
+	quote!(
+		/// The off-chain workers of the modules of the runtime, to start them one module at a
+		/// time.
+		pub struct AllOffchainWorkers;
+		impl<N> #scrate::traits::OffchainWorkers<N> for AllOffchainWorkers where
+			#(
+				#modules: #scrate::traits::OffchainWorker<N>
+					+ #scrate::traits::HasOffchainWorker,
+			)*
+		{
+			fn offchain_workers() -> #scrate::sp_std::vec::Vec<&'static [u8]> {
+				let mut workers = #scrate::sp_std::vec::Vec::new();
+				#(
+					if <#modules as #scrate::traits::HasOffchainWorker>::HAS_OFFCHAIN_WORKER {
+						workers.push(&#names[..]);
+					}
+				)*
+				workers
+			}
+
+			fn offchain_worker_of(module: &[u8], n: N) -> bool {
+				#(
+					if module == &#names[..] {
+						if !<#modules as #scrate::traits::HasOffchainWorker>::HAS_OFFCHAIN_WORKER {
+							return false;
+						}
+						<#modules as #scrate::traits::OffchainWorker<N>>::offchain_worker(n);
+						return true;
+					}
+				)*
+				let _ = n;
+				false
+			}
+		}
+	)
+}
+
 fn decl_module_to_index<'a>(
 	module_declarations: impl Iterator<Item = &'a ModuleDeclaration>,
 	num_modules: usize,
@@ -5,10 +5,11 @@ use crate::dispatch::{Parameter, DispatchError, DispatchResult};
 use crate::traits::{
 	ExistenceRequirement, Imbalance, SignedImbalance, UpdateBalanceOutcome, WithdrawReasons,
 };
-use codec::FullCodec;
+use codec::{Encode, Decode, FullCodec};
 use sp_std::{fmt::Debug, marker::PhantomData, result, prelude::Vec, any::Any};
-use sp_runtime::traits::{
-	PlugDoughnutApi, MaybeSerializeDeserialize, AtLeast32Bit, Zero,
+use sp_runtime::{
+	RuntimeDebug,
+	traits::{PlugDoughnutApi, MaybeSerializeDeserialize, AtLeast32Bit, Zero},
 };
 
 /// Perform fee payment for an extrinsic
@@ -151,6 +152,108 @@ impl DelegatedDispatchVerifier for () {
 	}
 }
 
+/// A type which can validate a doughnut carried alongside a call, rather than attached to the
+/// extrinsic, e.g. the doughnut of a single call in a batch.
+pub trait DoughnutValidator {
+	type Doughnut: PlugDoughnutApi;
+	type AccountId;
+
+	/// Check `doughnut` is correctly signed and usable by `holder` now. Return the account of its
+	/// issuer, i.e. the account the call should be dispatched on behalf of.
+	fn validate_doughnut(
+		doughnut: &Self::Doughnut,
+		holder: &Self::AccountId,
+	) -> Result<Self::AccountId, &'static str>;
+
+	/// The weight of validating `doughnut`, added to the weight of the call it's carried with.
+	fn weigh_doughnut(_doughnut: &Self::Doughnut) -> crate::weights::Weight {
+		0
+	}
+}
+
+/// A dummy implementation for when doughnut validation is not needed
+pub struct DummyDoughnutValidator<D, A>(PhantomData<(D, A)>);
+
+impl<D: PlugDoughnutApi, A: From<D::PublicKey>> DoughnutValidator for DummyDoughnutValidator<D, A> {
+	type Doughnut = D;
+	type AccountId = A;
+	fn validate_doughnut(
+		doughnut: &Self::Doughnut,
+		_holder: &Self::AccountId,
+	) -> Result<Self::AccountId, &'static str> {
+		Ok(doughnut.issuer().into())
+	}
+}
+
+/// Authority over an account which has been delegated to another, either on-chain by a proxy
+/// relationship or off-chain by a doughnut.
+#[derive(Clone, Eq, PartialEq, Encode, Decode, RuntimeDebug)]
+pub enum DelegatedOrigin<AccountId, Doughnut> {
+	/// `delegate` acts for `real`, which registered it as a proxy.
+	Proxy { real: AccountId, delegate: AccountId },
+	/// The holder of `doughnut` acts for its `issuer`.
+	Doughnut { issuer: AccountId, doughnut: Doughnut },
+}
+
+impl<AccountId, Doughnut> DelegatedOrigin<AccountId, Doughnut> {
+	/// The account whose authority is used.
+	pub fn account(&self) -> &AccountId {
+		match self {
+			DelegatedOrigin::Proxy { real, .. } => real,
+			DelegatedOrigin::Doughnut { issuer, .. } => issuer,
+		}
+	}
+}
+
+/// A filter on the calls which may be dispatched with delegated authority. Runtimes implement it
+/// once to restrict proxies and doughnuts alike.
+pub trait DelegationFilter<Call, AccountId, Doughnut> {
+	/// Return whether `call` may be dispatched with the authority of `origin`.
+	fn filter(origin: &DelegatedOrigin<AccountId, Doughnut>, call: &Call) -> bool;
+}
+
+/// Any call may be dispatched with delegated authority.
+impl<Call, AccountId, Doughnut> DelegationFilter<Call, AccountId, Doughnut> for () {
+	fn filter(_: &DelegatedOrigin<AccountId, Doughnut>, _: &Call) -> bool {
+		true
+	}
+}
+
+/// A type which can tell whether the identity of an account has been attested to, e.g. by a
+/// registrar. A permissioned network may require an attestation before an account can operate a
+/// node, e.g. add a reserved node entry.
+pub trait IdentityAttestation<AccountId> {
+	/// Return whether the identity of `who` has a valid attestation.
+	fn is_attested(who: &AccountId) -> bool;
+}
+
+/// Every account is attested to, i.e. no attestation is required.
+impl<AccountId> IdentityAttestation<AccountId> for () {
+	fn is_attested(_: &AccountId) -> bool {
+		true
+	}
+}
+
+/// A hook deciding whether a transfer of an asset may go ahead. It is given the doughnut the
+/// transfer is made under, if any, so a permissioned network can consult its permission domains.
+pub trait TransferRestriction<AssetId, AccountId, Balance, Doughnut> {
+	/// Return `Ok` if `amount` of `asset_id` may be transferred from `from` to `to`.
+	fn check_transfer(
+		asset_id: &AssetId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Balance,
+		doughnut: Option<&Doughnut>,
+	) -> DispatchResult;
+}
+
+/// Every transfer may go ahead.
+impl<AssetId, AccountId, Balance, Doughnut> TransferRestriction<AssetId, AccountId, Balance, Doughnut> for () {
+	fn check_transfer(_: &AssetId, _: &AccountId, _: &AccountId, _: Balance, _: Option<&Doughnut>) -> DispatchResult {
+		Ok(())
+	}
+}
+
 /// Something which may have doughnut. Returns a ref to the doughnut, if any.
 /// It's main purpose is to allow checking if an `OuterOrigin` contains a doughnut (i.e. it is delegated).
 pub trait MaybeDoughnutRef {
@@ -298,3 +401,18 @@ pub trait AssetIdAuthority {
 	fn asset_id() -> Self::AssetId;
 }
 
+
+/// Converts an amount of the network's native fee currency into an amount of some other asset,
+/// allowing fees to be paid in that asset.
+pub trait FeeConversion<AssetId, Balance> {
+	/// Return the amount of `asset` worth `amount` of the native fee currency, or `None` if `asset`
+	/// can not be used to pay fees.
+	fn to_asset_balance(asset: &AssetId, amount: Balance) -> Option<Balance>;
+}
+
+/// No asset other than the native fee currency can be used to pay fees.
+impl<AssetId, Balance> FeeConversion<AssetId, Balance> for () {
+	fn to_asset_balance(_: &AssetId, _: Balance) -> Option<Balance> {
+		None
+	}
+}
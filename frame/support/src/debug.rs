@@ -85,6 +85,11 @@
 //!	// same output to stdout, no overhead on WASM.
 //!	native::print!("My struct: {:?}", x);
 //! ```
+//!
+//! To profile the runtime, spans can be entered with [`enter_span`](../macro.enter_span.html).
+//! They are regular `tracing` spans when running natively. In WASM, they are entered in the
+//! tracing subscriber of the host through the `wasm_tracing` host functions if the `with-tracing`
+//! feature is enabled, and compiled out otherwise.
 
 use sp_std::vec::Vec;
 use sp_std::fmt::{self, Debug};
@@ -138,6 +143,78 @@ macro_rules! runtime_print {
 	}
 }
 
+/// Enter a span with the given name and the current module path as target, valid until the
+/// scope is left, optionally recording the index of the extrinsic being applied.
+///
+/// # Example
+///
+/// ```
+/// frame_support::enter_span!("my_span");
+/// frame_support::enter_span!("my_extrinsic_span", extrinsic_index = Some(1));
+/// ```
+#[macro_export]
+#[cfg(feature = "std")]
+macro_rules! enter_span {
+	( $name:expr ) => {
+		let __frame_span__ = $crate::tracing::span!($crate::tracing::Level::DEBUG, $name);
+		let __frame_span_guard__ = __frame_span__.enter();
+	};
+	( $name:expr, extrinsic_index = $index:expr ) => {
+		let __frame_span__ = $crate::tracing::span!(
+			$crate::tracing::Level::DEBUG,
+			$name,
+			extrinsic_index = $crate::tracing::field::Empty,
+		);
+		if !__frame_span__.is_disabled() {
+			if let Some(index) = $index {
+				__frame_span__.record("extrinsic_index", &index);
+			}
+		}
+		let __frame_span_guard__ = __frame_span__.enter();
+	};
+}
+
+/// Enter a span with the given name and the current module path as target, valid until the
+/// scope is left, optionally recording the index of the extrinsic being applied.
+#[macro_export]
+#[cfg(all(not(feature = "std"), feature = "with-tracing"))]
+macro_rules! enter_span {
+	( $name:expr ) => {
+		let __frame_span_guard__ = $crate::debug::WasmSpanGuard::enter(module_path!(), $name, None);
+	};
+	( $name:expr, extrinsic_index = $index:expr ) => {
+		let __frame_span_guard__ = $crate::debug::WasmSpanGuard::enter(module_path!(), $name, $index);
+	};
+}
+
+/// Spans are compiled out of the WASM runtime without the `with-tracing` feature.
+#[macro_export]
+#[cfg(all(not(feature = "std"), not(feature = "with-tracing")))]
+macro_rules! enter_span {
+	( $name:expr ) => {};
+	( $name:expr, extrinsic_index = $index:expr ) => {};
+}
+
+/// A span entered through the `wasm_tracing` host functions, exited when dropped.
+#[cfg(feature = "with-tracing")]
+pub struct WasmSpanGuard(u64);
+
+#[cfg(feature = "with-tracing")]
+impl WasmSpanGuard {
+	/// Enter a span with the given target and name, recording the index of the extrinsic being
+	/// applied if given.
+	pub fn enter(target: &str, name: &str, extrinsic_index: Option<u32>) -> Self {
+		WasmSpanGuard(sp_io::wasm_tracing::enter_span(target, name, extrinsic_index))
+	}
+}
+
+#[cfg(feature = "with-tracing")]
+impl Drop for WasmSpanGuard {
+	fn drop(&mut self) {
+		sp_io::wasm_tracing::exit_span(self.0)
+	}
+}
+
 /// Print out the debuggable type.
 pub fn debug(data: &impl Debug) {
 	runtime_print!("{:?}", data);
@@ -986,12 +986,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_initialize(_block_number_not_used: $trait_instance::BlockNumber) -> $return {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -1007,12 +1002,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_initialize($param: $param_ty) -> $return {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_initialize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_initialize");
 				{ $( $impl )* }
 			}
 		}
@@ -1038,12 +1028,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_runtime_upgrade() -> $return {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_runtime_upgrade");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_runtime_upgrade");
 				{ $( $impl )* }
 			}
 		}
@@ -1070,12 +1055,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_finalize(_block_number_not_used: $trait_instance::BlockNumber) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -1091,12 +1071,7 @@ macro_rules! decl_module {
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{
 			fn on_finalize($param: $param_ty) {
-				use $crate::sp_std::if_std;
-				if_std! {
-					use $crate::tracing;
-					let span = tracing::span!(tracing::Level::DEBUG, "on_finalize");
-					let _enter = span.enter();
-				}
+				$crate::enter_span!("on_finalize");
 				{ $( $impl )* }
 			}
 		}
@@ -1124,6 +1099,13 @@ macro_rules! decl_module {
 		{
 			fn offchain_worker(_block_number_not_used: $trait_instance::BlockNumber) { $( $impl )* }
 		}
+
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::traits::HasOffchainWorker
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			const HAS_OFFCHAIN_WORKER: bool = true;
+		}
 	};
 
 	(@impl_offchain
@@ -1137,6 +1119,13 @@ macro_rules! decl_module {
 		{
 			fn offchain_worker($param: $param_ty) { $( $impl )* }
 		}
+
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::traits::HasOffchainWorker
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			const HAS_OFFCHAIN_WORKER: bool = true;
+		}
 	};
 
 	(@impl_offchain
@@ -1147,6 +1136,13 @@ macro_rules! decl_module {
 			$crate::traits::OffchainWorker<$trait_instance::BlockNumber>
 			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
 		{}
+
+		impl<$trait_instance: $trait_name$(<I>, $instance: $instantiable)?>
+			$crate::traits::HasOffchainWorker
+			for $module<$trait_instance$(, $instance)?> where $( $other_where_bounds )*
+		{
+			const HAS_OFFCHAIN_WORKER: bool = false;
+		}
 	};
 
 	// Expansion for _origin_ dispatch functions with no return type.
@@ -1165,11 +1161,7 @@ macro_rules! decl_module {
 		$vis fn $name(
 			$origin: $origin_ty $(, $param: $param_ty )*
 		) -> $crate::dispatch::DispatchResult {
-			$crate::sp_std::if_std! {
-				use $crate::tracing;
-				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
-				let _enter = span.enter();
-			}
+			$crate::enter_span!(stringify!($name));
 			{
 				{ $( $impl )* }
 				Ok(())
@@ -1190,12 +1182,7 @@ macro_rules! decl_module {
 	) => {
 		$(#[doc = $doc_attr])*
 		$vis fn $name($origin: $origin_ty $(, $param: $param_ty )* ) -> $result {
-			use $crate::sp_std::if_std;
-			if_std! {
-				use $crate::tracing;
-				let span = tracing::span!(tracing::Level::DEBUG, stringify!($name));
-				let _enter = span.enter();
-			}
+			$crate::enter_span!(stringify!($name));
 			{ $( $impl )* }
 		}
 	};
@@ -1618,6 +1605,12 @@ macro_rules! decl_module {
 			{ $( $other_where_bounds )* }
 			$( $constants )*
 		}
+		$crate::__impl_permission_metadata! {
+			$mod_type<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?>
+			{ $( $other_where_bounds )* }
+			$system;
+			$( $fn_name )*
+		}
 
 		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $crate::dispatch::ModuleErrorMetadata
 			for $mod_type<$trait_instance $(, $instance)?> where $( $other_where_bounds )*
@@ -1785,6 +1778,54 @@ macro_rules! __dispatch_impl_metadata {
 	}
 }
 
+/// Implement the metadata of the doughnut permissions the calls of a module are verified against.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __impl_permission_metadata {
+	(
+		$mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>
+		{ $( $other_where_bounds:tt )* }
+		$system:ident;
+		$( $fn_name:ident )+
+	) => {
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $mod_type<$trait_instance $(, $instance)?>
+			where $( $other_where_bounds )*
+		{
+			#[doc(hidden)]
+			pub fn permission_metadata() -> Option<$crate::metadata::PermissionMetadata> {
+				// Delegated calls are verified by the doughnut verifier of the runtime with the
+				// name of the crate of the module, an empty domain means the doughnuts aren't
+				// verified.
+				let domain = <
+					<$trait_instance as $system::Trait>::DelegatedDispatchVerifier
+						as $crate::additional_traits::DelegatedDispatchVerifier
+				>::DOMAIN;
+				if domain.is_empty() {
+					return None;
+				}
+				Some($crate::metadata::PermissionMetadata {
+					domain: $crate::metadata::DecodeDifferent::Encode(domain),
+					module: $crate::metadata::DecodeDifferent::Encode(env!("CARGO_PKG_NAME")),
+				})
+			}
+		}
+	};
+	(
+		$mod_type:ident<$trait_instance:ident: $trait_name:ident$(<I>, $instance:ident: $instantiable:path)?>
+		{ $( $other_where_bounds:tt )* }
+		$system:ident;
+	) => {
+		impl<$trait_instance: $trait_name $(<I>, $instance: $instantiable)?> $mod_type<$trait_instance $(, $instance)?>
+			where $( $other_where_bounds )*
+		{
+			#[doc(hidden)]
+			pub fn permission_metadata() -> Option<$crate::metadata::PermissionMetadata> {
+				None
+			}
+		}
+	};
+}
+
 /// Implement metadata for module constants.
 #[macro_export]
 #[doc(hidden)]
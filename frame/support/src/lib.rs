@@ -63,6 +63,8 @@ pub mod inherent;
 pub mod unsigned;
 #[macro_use]
 pub mod error;
+#[macro_use]
+pub mod signed_extra;
 pub mod additional_traits;
 pub mod traits;
 pub mod weights;
@@ -18,7 +18,7 @@ pub use frame_metadata::{
 	DecodeDifferent, FnEncode, RuntimeMetadata, ModuleMetadata, RuntimeMetadataLastVersion,
 	DefaultByteGetter, RuntimeMetadataPrefixed, StorageEntryMetadata, StorageMetadata,
 	StorageEntryType, StorageEntryModifier, DefaultByte, StorageHasher, ModuleErrorMetadata,
-	ExtrinsicMetadata,
+	ExtrinsicMetadata, SignedExtensionMetadata, PermissionMetadata,
 };
 
 /// Implements the metadata support for the given runtime and all its modules.
@@ -73,9 +73,16 @@ macro_rules! impl_runtime_metadata {
 									<
 										$ext as $crate::sp_runtime::traits::ExtrinsicMetadata
 									>::SignedExtensions as $crate::sp_runtime::traits::SignedExtension
-								>::identifier()
+								>::metadata()
 									.into_iter()
-									.map($crate::metadata::DecodeDifferent::Encode)
+									.map(|extension| $crate::metadata::SignedExtensionMetadata {
+										identifier: $crate::metadata::DecodeDifferent::Encode(extension.identifier),
+										ty: $crate::metadata::DecodeDifferent::Encode(extension.ty),
+										additional_signed: $crate::metadata::DecodeDifferent::Encode(
+											extension.additional_signed,
+										),
+										optional: extension.optional,
+									})
 									.collect(),
 						},
 				}.into()
@@ -115,7 +122,10 @@ macro_rules! __runtime_modules_to_metadata {
 					$crate::metadata::FnEncode(
 						<$mod::$module::<$runtime $(, $mod::$instance )?> as $crate::metadata::ModuleErrorMetadata>::metadata
 					)
-				)
+				),
+				permissions: $crate::__runtime_modules_to_metadata_calls_permissions!(
+					$mod, $module $( <$instance> )?, $runtime, $(with $kw)*
+				),
 			};
 			$( $rest )*
 		)
@@ -201,6 +211,44 @@ macro_rules! __runtime_modules_to_metadata_calls_event {
 	};
 }
 
+#[macro_export]
+#[doc(hidden)]
+macro_rules! __runtime_modules_to_metadata_calls_permissions {
+	(
+		$mod: ident,
+		$module: ident $( <$instance:ident> )?,
+		$runtime: ident,
+		with Call
+		$(with $kws:ident)*
+	) => {
+		$crate::metadata::DecodeDifferent::Encode(
+			$crate::metadata::FnEncode(
+				$mod::$module::<$runtime $(, $mod::$instance )?>::permission_metadata
+			)
+		)
+	};
+	(
+		$mod: ident,
+		$module: ident $( <$instance:ident> )?,
+		$runtime: ident,
+		with $_:ident
+		$(with $kws:ident)*
+	) => {
+		$crate::__runtime_modules_to_metadata_calls_permissions! {
+			$mod, $module $( <$instance> )?, $runtime, $(with $kws)*
+		};
+	};
+	(
+		$mod: ident,
+		$module: ident $( <$instance:ident> )?,
+		$runtime: ident,
+	) => {
+		$crate::metadata::DecodeDifferent::Encode(
+			$crate::metadata::FnEncode(|| None)
+		)
+	};
+}
+
 #[macro_export]
 #[doc(hidden)]
 macro_rules! __runtime_modules_to_metadata_calls_storage {
@@ -246,7 +294,7 @@ mod tests {
 	use frame_metadata::{
 		EventMetadata, StorageEntryModifier, StorageEntryType, FunctionMetadata, StorageEntryMetadata,
 		ModuleMetadata, RuntimeMetadataPrefixed, DefaultByte, ModuleConstantMetadata, DefaultByteGetter,
-		ErrorMetadata, ExtrinsicMetadata,
+		ErrorMetadata, ExtrinsicMetadata, SignedExtensionMetadata,
 	};
 	use codec::{Encode, Decode};
 	use crate::traits::Get;
@@ -265,6 +313,14 @@ mod tests {
 		fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
 			Ok(1)
 		}
+		fn metadata() -> Vec<sp_runtime::traits::SignedExtensionMetadata> {
+			vec![sp_runtime::traits::SignedExtensionMetadata {
+				identifier: Self::IDENTIFIER,
+				ty: "()",
+				additional_signed: "u32",
+				optional: false,
+			}]
+		}
 	}
 
 	#[derive(Clone, Eq, Debug, PartialEq, Encode, Decode)]
@@ -272,17 +328,24 @@ mod tests {
 	impl sp_runtime::traits::SignedExtension for TestExtension2 {
 		type AccountId = u32;
 		type Call = u32;
-		type AdditionalSigned = u32;
+		type AdditionalSigned = ();
 		type DispatchInfo = ();
 		type Pre = ();
 		const IDENTIFIER: &'static str = "testextension2";
 		fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
-			Ok(1)
+			Ok(())
 		}
 	}
 
 	struct TestExtrinsic;
 
+	pub struct TestDispatchVerifier;
+	impl DelegatedDispatchVerifier for TestDispatchVerifier {
+		type Doughnut = ();
+		type AccountId = u32;
+		const DOMAIN: &'static str = "test";
+	}
+
 	impl sp_runtime::traits::ExtrinsicMetadata for TestExtrinsic {
 		const VERSION: u8 = 1;
 		type SignedExtensions = (TestExtension, TestExtension2);
@@ -448,7 +511,7 @@ mod tests {
 		type BlockNumber = u32;
 		type SomeValue = SystemValue;
 		type ModuleToIndex = ();
-		type DelegatedDispatchVerifier = ();
+		type DelegatedDispatchVerifier = TestDispatchVerifier;
 		type Doughnut = ();
 	}
 
@@ -526,6 +589,7 @@ mod tests {
 						])
 					),
 					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
 				},
 				ModuleMetadata {
 					name: DecodeDifferent::Encode("Module"),
@@ -561,6 +625,10 @@ mod tests {
 							]),
 						},
 					])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| Some(PermissionMetadata {
+						domain: DecodeDifferent::Encode("test"),
+						module: DecodeDifferent::Encode("frame-support"),
+					}))),
 				},
 				ModuleMetadata {
 					name: DecodeDifferent::Encode("Module2"),
@@ -598,13 +666,24 @@ mod tests {
 					)),
 					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
 					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
 				},
 			]),
 			extrinsic: ExtrinsicMetadata {
 				version: 1,
 				signed_extensions: vec![
-					DecodeDifferent::Encode("testextension"),
-					DecodeDifferent::Encode("testextension2"),
+					SignedExtensionMetadata {
+						identifier: DecodeDifferent::Encode("testextension"),
+						ty: DecodeDifferent::Encode("()"),
+						additional_signed: DecodeDifferent::Encode("u32"),
+						optional: false,
+					},
+					SignedExtensionMetadata {
+						identifier: DecodeDifferent::Encode("testextension2"),
+						ty: DecodeDifferent::Encode("()"),
+						additional_signed: DecodeDifferent::Encode("()"),
+						optional: false,
+					},
 				],
 			}
 		};
@@ -615,4 +694,32 @@ mod tests {
 
 		pretty_assertions::assert_eq!(expected_metadata, metadata_decoded.unwrap());
 	}
+
+	#[test]
+	fn runtime_metadata_v11_is_decoded() {
+		use frame_metadata::{ExtrinsicMetadataV11, ModuleMetadataV11, RuntimeMetadataV11};
+
+		let metadata = RuntimeMetadataPrefixed(
+			frame_metadata::META_RESERVED,
+			RuntimeMetadata::V11(RuntimeMetadataV11 {
+				modules: DecodeDifferent::Encode(&[
+					ModuleMetadataV11 {
+						name: DecodeDifferent::Encode("System"),
+						storage: None,
+						calls: None,
+						event: None,
+						constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+						errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					},
+				]),
+				extrinsic: ExtrinsicMetadataV11 {
+					version: 4,
+					signed_extensions: vec![DecodeDifferent::Encode("CheckNonce")],
+				},
+			}),
+		);
+
+		let decoded = RuntimeMetadataPrefixed::decode(&mut &metadata.encode()[..]).unwrap();
+		pretty_assertions::assert_eq!(decoded, metadata);
+	}
 }
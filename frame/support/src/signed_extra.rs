@@ -0,0 +1,185 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Macro for composing the `SignedExtra` of a runtime with the doughnut extension.
+
+#[doc(hidden)]
+pub use crate::sp_runtime::traits::{SignedExtension, MaybeDoughnut};
+
+/// Compose the `SignedExtra` tuple of a runtime from named `SignedExtension`s.
+///
+/// The doughnut of a transaction is always the first element of the `SignedExtra`, as
+/// required by `MaybeDoughnut`. Besides the `SignedExtra` type, the macro generates:
+///
+/// - `<SignedExtra>Parts`, a struct with the doughnut and each extension as named fields, that
+///   converts from and into the `SignedExtra`.
+/// - `<SignedExtra>AdditionalSigned`, a struct with the additional signed data of each extension
+///   as named fields. `into_additional_signed` converts it into the additional signed data of the
+///   `SignedExtra`, the doughnut has no additional signed data.
+///
+/// The macro fails to compile if the composed `SignedExtra` does not implement `SignedExtension`
+/// and `MaybeDoughnut` for the given doughnut type.
+///
+/// # Example
+///
+/// ```no_compile
+/// frame_support::compose_signed_extra! {
+///     /// The SignedExtension to the basic transaction logic.
+///     pub type SignedExtra where Doughnut = <Runtime as frame_system::Trait>::Doughnut {
+///         check_version: frame_system::CheckVersion<Runtime>,
+///         check_genesis: frame_system::CheckGenesis<Runtime>,
+///         check_era: frame_system::CheckEra<Runtime>,
+///         check_nonce: frame_system::CheckNonce<Runtime>,
+///         check_weight: frame_system::CheckWeight<Runtime>,
+///     }
+/// }
+///
+/// let extra: SignedExtra = SignedExtraParts {
+///     doughnut: None,
+///     check_version: frame_system::CheckVersion::new(),
+///     check_genesis: frame_system::CheckGenesis::new(),
+///     check_era: frame_system::CheckEra::from(Era::Immortal),
+///     check_nonce: frame_system::CheckNonce::from(nonce),
+///     check_weight: frame_system::CheckWeight::new(),
+/// }.into();
+///
+/// let additional_signed = SignedExtraAdditionalSigned {
+///     check_version: spec_version,
+///     check_genesis: genesis_hash,
+///     check_era: genesis_hash,
+///     check_nonce: (),
+///     check_weight: (),
+/// }.into_additional_signed();
+/// ```
+#[macro_export]
+macro_rules! compose_signed_extra {
+	(
+		$( #[ $attr:meta ] )*
+		$vis:vis type $name:ident where Doughnut = $doughnut:ty {
+			$( $field:ident : $extension:ty ),* $(,)?
+		}
+	) => {
+		$( #[ $attr ] )*
+		$vis type $name = ( Option<$doughnut>, $( $extension, )* );
+
+		$crate::paste::item! {
+			/// The doughnut and the `SignedExtension`s of the `SignedExtra` by name.
+			#[derive(Clone, PartialEq, Eq, $crate::RuntimeDebug)]
+			$vis struct [< $name Parts >] {
+				/// The doughnut of the transaction, if any.
+				pub doughnut: Option<$doughnut>,
+				$( pub $field: $extension, )*
+			}
+
+			impl From<[< $name Parts >]> for $name {
+				fn from(parts: [< $name Parts >]) -> Self {
+					( parts.doughnut, $( parts.$field, )* )
+				}
+			}
+
+			impl From<$name> for [< $name Parts >] {
+				fn from(extra: $name) -> Self {
+					let ( doughnut, $( $field, )* ) = extra;
+					Self { doughnut, $( $field, )* }
+				}
+			}
+
+			/// The additional signed data of the `SignedExtension`s of the `SignedExtra` by name.
+			$vis struct [< $name AdditionalSigned >] {
+				$(
+					pub $field:
+						<$extension as $crate::signed_extra::SignedExtension>::AdditionalSigned,
+				)*
+			}
+
+			impl [< $name AdditionalSigned >] {
+				/// Convert into the additional signed data of the `SignedExtra`.
+				pub fn into_additional_signed(
+					self,
+				) -> <$name as $crate::signed_extra::SignedExtension>::AdditionalSigned {
+					( (), $( self.$field, )* )
+				}
+			}
+		}
+
+		const _: () = {
+			fn assert_signed_extra<
+				T: $crate::signed_extra::SignedExtension
+					+ $crate::signed_extra::MaybeDoughnut<Doughnut = $doughnut>
+			>() {}
+
+			#[allow(dead_code)]
+			fn check() {
+				assert_signed_extra::<$name>();
+			}
+		};
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::{Encode, Decode};
+	use crate::sp_runtime::transaction_validity::TransactionValidityError;
+
+	#[derive(Encode, Decode, Clone, Eq, PartialEq, Debug)]
+	pub struct CheckValue(u32);
+
+	impl SignedExtension for CheckValue {
+		type AccountId = u64;
+		type Call = ();
+		type AdditionalSigned = u32;
+		type DispatchInfo = ();
+		type Pre = ();
+		const IDENTIFIER: &'static str = "CheckValue";
+
+		fn additional_signed(&self) -> Result<u32, TransactionValidityError> {
+			Ok(self.0 * 2)
+		}
+	}
+
+	compose_signed_extra! {
+		/// The extra of the test.
+		pub type TestExtra where Doughnut = () {
+			first: CheckValue,
+			second: CheckValue,
+		}
+	}
+
+	#[test]
+	fn signed_extra_parts_convert() {
+		let extra: TestExtra = TestExtraParts {
+			doughnut: Some(()),
+			first: CheckValue(1),
+			second: CheckValue(2),
+		}.into();
+		assert_eq!(extra, (Some(()), CheckValue(1), CheckValue(2)));
+		assert_eq!(extra.doughnut_ref(), Some(&()));
+		assert_eq!(extra.clone().doughnut(), Some(()));
+
+		let parts = TestExtraParts::from(extra);
+		assert_eq!(parts.first, CheckValue(1));
+		assert_eq!(parts.second, CheckValue(2));
+	}
+
+	#[test]
+	fn additional_signed_matches_signed_extra() {
+		let extra: TestExtra = (None, CheckValue(1), CheckValue(2));
+		let additional_signed = TestExtraAdditionalSigned { first: 2, second: 4 };
+
+		assert_eq!(extra.additional_signed(), Ok(additional_signed.into_additional_signed()));
+	}
+}
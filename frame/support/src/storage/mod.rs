@@ -27,6 +27,34 @@ pub mod child;
 pub mod generator;
 pub mod migration;
 
+/// Describes whether a storage transaction should be committed or rolled back.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TransactionOutcome<R> {
+	/// Keep the changes made in the transaction.
+	Commit(R),
+	/// Revert the changes made in the transaction.
+	Rollback(R),
+}
+
+/// Execute the supplied function in a new storage transaction.
+///
+/// All changes to storage performed by `f` are kept if it returns
+/// [`TransactionOutcome::Commit`] and discarded if it returns
+/// [`TransactionOutcome::Rollback`]. Transactions can be nested.
+pub fn with_transaction<R>(f: impl FnOnce() -> TransactionOutcome<R>) -> R {
+	sp_io::storage::start_transaction();
+	match f() {
+		TransactionOutcome::Commit(res) => {
+			sp_io::storage::commit_transaction();
+			res
+		},
+		TransactionOutcome::Rollback(res) => {
+			sp_io::storage::rollback_transaction();
+			res
+		},
+	}
+}
+
 /// A trait for working with macro-generated storage values under the substrate storage API.
 ///
 /// Details on implementation can be found at
@@ -492,7 +520,38 @@ pub trait StoragePrefixedMap<Value: FullCodec> {
 mod test {
 	use sp_core::hashing::twox_128;
 	use sp_io::TestExternalities;
-	use crate::storage::{unhashed, StoragePrefixedMap};
+	use crate::storage::{unhashed, StoragePrefixedMap, with_transaction, TransactionOutcome};
+
+	#[test]
+	fn with_transaction_works() {
+		TestExternalities::default().execute_with(|| {
+			unhashed::put(b"value", &1u32);
+
+			let res = with_transaction(|| {
+				unhashed::put(b"value", &2u32);
+
+				let inner: Result<(), &str> = with_transaction(|| {
+					unhashed::put(b"value", &3u32);
+					unhashed::put(b"other", &3u32);
+					TransactionOutcome::Rollback(Err("failed"))
+				});
+				assert_eq!(inner, Err("failed"));
+				assert_eq!(unhashed::get::<u32>(b"value"), Some(2));
+				assert_eq!(unhashed::get::<u32>(b"other"), None);
+
+				TransactionOutcome::Commit(7)
+			});
+
+			assert_eq!(res, 7);
+			assert_eq!(unhashed::get::<u32>(b"value"), Some(2));
+
+			with_transaction(|| {
+				unhashed::kill(b"value");
+				TransactionOutcome::Rollback(())
+			});
+			assert_eq!(unhashed::get::<u32>(b"value"), Some(2));
+		});
+	}
 
 	#[test]
 	fn prefixed_map_works() {
@@ -127,6 +127,25 @@ impl<A> FindAuthor<A> for () {
 	}
 }
 
+/// Something which takes over block production from another consensus engine on a running
+/// chain, e.g. BABE replacing Aura.
+///
+/// `()` never takes over, for chains which never leave their consensus engine.
+pub trait ConsensusHandOver<AuthorityId> {
+	/// Whether block production can be taken over with the given authorities of the previous
+	/// consensus engine.
+	fn can_take_over(authorities: &[AuthorityId]) -> bool;
+
+	/// Take over block production from the next block on, starting with the authorities
+	/// of the previous consensus engine. Only called if `can_take_over` holds for them.
+	fn take_over(authorities: Vec<AuthorityId>);
+}
+
+impl<A> ConsensusHandOver<A> for () {
+	fn can_take_over(_: &[A]) -> bool { false }
+	fn take_over(_: Vec<A>) {}
+}
+
 /// A trait for verifying the seal of a header and returning the author.
 pub trait VerifySeal<Header, Author> {
 	/// Verify a header and return the author, if any.
@@ -656,6 +675,27 @@ pub trait ReservableCurrency<AccountId>: Currency<AccountId> {
 	) -> result::Result<Self::Balance, DispatchError>;
 }
 
+/// Charges and refunds the deposits held against the storage written on behalf of an account.
+///
+/// Pallets charge the deposit of the bytes they write before writing them, and refund it when the
+/// storage is removed, with the same `items` and `bytes` as charged.
+pub trait StorageDepositCollector<AccountId> {
+	/// Charge `who` the deposit of `bytes` written in `items` new storage items. `items` is zero
+	/// when existing items grow.
+	///
+	/// Fails, without side-effects, if the deposit can't be held.
+	fn charge(who: &AccountId, items: u32, bytes: u32) -> DispatchResult;
+
+	/// Refund `who` the deposit of `bytes` removed in `items` storage items. This function cannot
+	/// fail.
+	fn refund(who: &AccountId, items: u32, bytes: u32);
+}
+
+impl<AccountId> StorageDepositCollector<AccountId> for () {
+	fn charge(_: &AccountId, _: u32, _: u32) -> DispatchResult { Ok(()) }
+	fn refund(_: &AccountId, _: u32, _: u32) {}
+}
+
 /// An identifier for a lock. Used for disambiguating different locks so that
 /// they can be individually replaced or removed.
 pub type LockIdentifier = [u8; 8];
@@ -1018,6 +1058,29 @@ pub trait OffchainWorker<BlockNumber> {
 	fn offchain_worker(_n: BlockNumber) {}
 }
 
+/// Tells whether a module implements its own off-chain worker, see `OffchainWorker`.
+///
+/// Implemented by `decl_module!` for every module.
+pub trait HasOffchainWorker {
+	/// Whether the module declares an `offchain_worker` function.
+	const HAS_OFFCHAIN_WORKER: bool;
+}
+
+/// The off-chain workers of the modules of a runtime, to start them one module at a time.
+///
+/// Implemented by `construct_runtime!` for the `AllOffchainWorkers` type of the runtime, with
+/// the modules named as in the runtime definition.
+pub trait OffchainWorkers<BlockNumber> {
+	/// The names of the modules having an off-chain worker, in the order of the runtime
+	/// definition.
+	fn offchain_workers() -> Vec<&'static [u8]>;
+
+	/// Start the off-chain worker of the module with the given name, see `OffchainWorker`.
+	///
+	/// Returns `false` if the runtime has no such module or the module has no off-chain worker.
+	fn offchain_worker_of(module: &[u8], n: BlockNumber) -> bool;
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -41,7 +41,7 @@ use codec::{Encode, Decode};
 use sp_arithmetic::traits::Bounded;
 use sp_runtime::{
 	RuntimeDebug,
-	traits::SignedExtension,
+	traits::{SignedExtension, MaybeDoughnut},
 	generic::{CheckedExtrinsic, UncheckedExtrinsic},
 };
 
@@ -125,6 +125,19 @@ pub trait GetDispatchInfo {
 	fn get_dispatch_info(&self) -> DispatchInfo;
 }
 
+/// Means of weighing the verification of the doughnut carried by a delegated transaction, which
+/// is added to the weight of its call.
+pub trait WeighDoughnut {
+	/// Return the weight of verifying this doughnut.
+	fn weigh_doughnut(&self) -> Weight;
+}
+
+impl WeighDoughnut for () {
+	fn weigh_doughnut(&self) -> Weight {
+		0
+	}
+}
+
 /// Default type used with the `#[weight = x]` attribute in a substrate chain.
 ///
 /// A user may pass in any other type that implements the correct traits. If not, the `Default`
@@ -260,27 +273,44 @@ impl<Args, WD, CD, PF> PaysFee<Args> for FunctionOf<WD, CD, PF> where
 	}
 }
 
-/// Implementation for unchecked extrinsic.
+/// Implementation for unchecked extrinsic, including the verification of its doughnut.
 impl<Address, Call, Signature, Extra> GetDispatchInfo
 	for UncheckedExtrinsic<Address, Call, Signature, Extra>
 where
 	Call: GetDispatchInfo,
-	Extra: SignedExtension,
+	Extra: SignedExtension + MaybeDoughnut,
+	Extra::Doughnut: WeighDoughnut,
 {
 	fn get_dispatch_info(&self) -> DispatchInfo {
-		self.function.get_dispatch_info()
+		let extra = self.signature.as_ref().map(|(_, _, extra)| extra);
+		with_doughnut_weight(self.function.get_dispatch_info(), extra)
 	}
 }
 
-/// Implementation for checked extrinsic.
+/// Implementation for checked extrinsic, including the verification of its doughnut.
 impl<AccountId, Call, Extra> GetDispatchInfo
 	for CheckedExtrinsic<AccountId, Call, Extra>
 where
 	Call: GetDispatchInfo,
+	Extra: SignedExtension + MaybeDoughnut,
+	Extra::Doughnut: WeighDoughnut,
 {
 	fn get_dispatch_info(&self) -> DispatchInfo {
-		self.function.get_dispatch_info()
+		let extra = self.signed.as_ref().map(|(_, extra)| extra);
+		with_doughnut_weight(self.function.get_dispatch_info(), extra)
+	}
+}
+
+/// Add the weight of verifying the doughnut of `extra`, if any, to `info`.
+fn with_doughnut_weight<Extra>(mut info: DispatchInfo, extra: Option<&Extra>) -> DispatchInfo
+where
+	Extra: MaybeDoughnut,
+	Extra::Doughnut: WeighDoughnut,
+{
+	if let Some(doughnut) = extra.and_then(MaybeDoughnut::doughnut_ref) {
+		info.weight = info.weight.saturating_add(doughnut.weigh_doughnut());
 	}
+	info
 }
 
 /// Implementation for test extrinsic.
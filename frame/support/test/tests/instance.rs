@@ -426,6 +426,20 @@ const EXPECTED_METADATA: StorageMetadata = StorageMetadata {
 	)
 };
 
+#[test]
+fn offchain_workers_are_started_by_module_name() {
+	use frame_support::traits::OffchainWorkers;
+	type Workers = AllOffchainWorkers;
+
+	assert_eq!(
+		<Workers as OffchainWorkers<BlockNumber>>::offchain_workers(),
+		vec![&b"Module1_1"[..], &b"Module1_2"[..]],
+	);
+	assert!(<Workers as OffchainWorkers<BlockNumber>>::offchain_worker_of(b"Module1_2", 1));
+	assert!(!<Workers as OffchainWorkers<BlockNumber>>::offchain_worker_of(b"Module2", 1));
+	assert!(!<Workers as OffchainWorkers<BlockNumber>>::offchain_worker_of(b"Unknown", 1));
+}
+
 #[test]
 fn test_instance_storage_metadata() {
 	let metadata = Module2_2::storage_metadata();
@@ -50,6 +50,8 @@
 //!     exceed the limits.
 //!   - [`CheckNonce`]: Checks the nonce of the transaction. Contains a single payload of type
 //!     `T::Index`.
+//!   - [`CheckUnique`]: Checks that the transaction wasn't included before, by its hash, instead
+//!     of by its nonce. Contains a payload of the block number at which it expires and a salt.
 //!   - [`CheckEra`]: Checks the era of the transaction. Contains a single payload of type `Era`.
 //!   - [`CheckGenesis`]: Checks the provided genesis hash of the transaction. Must be a part of the
 //!     signed payload of the transaction.
@@ -97,7 +99,7 @@ use sp_std::marker::PhantomData;
 use sp_std::fmt::Debug;
 use sp_version::RuntimeVersion;
 use sp_runtime::{
-	RuntimeDebug, Perbill, DispatchOutcome, DispatchError,
+	RuntimeDebug, Perbill, DispatchOutcome, DispatchError, DispatchResult,
 	generic::{self, Era},
 	transaction_validity::{
 		ValidTransaction, TransactionPriority, TransactionLongevity, TransactionValidityError,
@@ -107,7 +109,8 @@ use sp_runtime::{
 		self, CheckEqual, AtLeast32Bit, Zero, SignedExtension, Lookup, LookupError,
 		SimpleBitOps, Hash, Member, MaybeDisplay, EnsureOrigin, BadOrigin, SaturatedConversion,
 		MaybeSerialize, MaybeSerializeDeserialize, MaybeMallocSizeOf, StaticLookup, One, Bounded,
-		PlugDoughnutApi,
+		PlugDoughnutApi, Saturating, VerifyAccountSignature, SignedExtensionMetadata,
+		UniqueSaturatedFrom, UniqueSaturatedInto,
 	},
 };
 
@@ -115,7 +118,9 @@ use sp_core::{ChangesTrieConfiguration, storage::well_known_keys};
 use frame_support::{
 	decl_module, decl_event, decl_storage, decl_error, storage, Parameter,
 	additional_traits::{DelegatedDispatchVerifier as DelegatedDispatchVerifierT, MaybeDoughnutRef},
-	traits::{Contains, Get, ModuleToIndex, OnReapAccount},
+	traits::{
+		Contains, Get, ModuleToIndex, OnReapAccount, ReservableCurrency, StorageDepositCollector,
+	},
 	weights::{Weight, DispatchInfo, DispatchClass, FunctionOf, SimpleDispatchInfo},
 };
 use codec::{Encode, Decode};
@@ -326,17 +331,43 @@ impl From<sp_version::RuntimeVersion> for LastRuntimeUpgradeInfo {
 	}
 }
 
+/// The storage held by an account, as noted by the pallets charging storage deposits.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct StorageStats {
+	/// The number of storage items.
+	pub items: u32,
+	/// The number of bytes in the storage items.
+	pub bytes: u32,
+}
+
+/// An identifier of a storage deposit collector, disambiguating the deposits held by different
+/// collectors for the same account.
+pub type StorageDepositId = [u8; 8];
+
 decl_storage! {
 	trait Store for Module<T: Trait> as System {
 		/// Extrinsics nonce for accounts.
 		pub AccountNonce get(fn account_nonce): map hasher(blake2_128_concat) T::AccountId => T::Index;
 
+		/// The storage held by accounts, as noted by the pallets charging storage deposits.
+		pub AccountStorage get(fn account_storage):
+			map hasher(blake2_128_concat) T::AccountId => StorageStats;
+
+		/// The deposits held by the storage deposit collectors, by account and collector. The
+		/// balances of the collector's currency are saturated to `u128`.
+		pub StorageDeposits get(fn storage_deposit):
+			double_map hasher(blake2_128_concat) T::AccountId, hasher(twox_64_concat) StorageDepositId
+			=> u128;
+
 		/// Total extrinsics count for the current block.
 		ExtrinsicCount: Option<u32>;
 
 		/// Total weight for all extrinsics put together, for the current block.
 		AllExtrinsicsWeight: Option<Weight>;
 
+		/// Total weight for all operational extrinsics put together, for the current block.
+		OperationalExtrinsicsWeight: Option<Weight>;
+
 		/// Total length (in bytes) for all extrinsics put together, for the current block.
 		AllExtrinsicsLen: Option<u32>;
 
@@ -344,6 +375,20 @@ decl_storage! {
 		pub BlockHash get(fn block_hash) build(|_| vec![(T::BlockNumber::zero(), hash69())]):
 			map hasher(twox_64_concat) T::BlockNumber => T::Hash;
 
+		/// Map of the block numbers that are multiples of `LONG_ERA_PHASE_QUANTUM` to their block
+		/// hashes, the births of the long eras, for `LONG_ERA_MAX_PERIOD` blocks.
+		pub EraCheckpoint get(fn era_checkpoint):
+			map hasher(twox_64_concat) T::BlockNumber => Option<T::Hash>;
+
+		/// Map of block numbers to the times of the blocks noted by the timestamp module, e.g. in
+		/// milliseconds, kept as long as their hashes in `BlockHash` or `EraCheckpoint`.
+		pub BlockTime get(fn block_time):
+			map hasher(twox_64_concat) T::BlockNumber => Option<u64>;
+
+		/// Hashes of the extrinsics checked by `CheckUnique` that haven't expired, by the block
+		/// number at which they expire. Pruned when that block is finalized.
+		RecentExtrinsics: double_map hasher(twox_64_concat) T::BlockNumber, hasher(identity) T::Hash => ();
+
 		/// Extrinsics data for the current block (maps an extrinsic's index to its data).
 		ExtrinsicData get(fn extrinsic_data): map hasher(twox_64_concat) u32 => Vec<u8>;
 
@@ -739,6 +784,15 @@ impl<T: Trait> Module<T> {
 		AllExtrinsicsWeight::get().unwrap_or_default()
 	}
 
+	/// Gets the total weight of all executed extrinsics of the given dispatch class.
+	pub fn extrinsics_weight(class: DispatchClass) -> Weight {
+		let operational_weight = OperationalExtrinsicsWeight::get().unwrap_or_default();
+		match class {
+			DispatchClass::Operational => operational_weight,
+			DispatchClass::Normal => Self::all_extrinsics_weight().saturating_sub(operational_weight),
+		}
+	}
+
 	pub fn all_extrinsics_len() -> u32 {
 		AllExtrinsicsLen::get().unwrap_or_default()
 	}
@@ -781,6 +835,7 @@ impl<T: Trait> Module<T> {
 		<Digest<T>>::put(digest);
 		<ParentHash<T>>::put(parent_hash);
 		<BlockHash<T>>::insert(*number - One::one(), parent_hash);
+		Self::note_era_checkpoint(*number - One::one(), parent_hash);
 		<ExtrinsicsRoot<T>>::put(txs_root);
 
 		if let InitKind::Full = kind {
@@ -790,18 +845,45 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Keep the hash of the block if it's a birth of the long eras, and prune the checkpoint
+	/// older than the longest era.
+	fn note_era_checkpoint(number: T::BlockNumber, hash: &T::Hash) {
+		let number_u64 = number.saturated_into::<u64>();
+		if number_u64 % generic::LONG_ERA_PHASE_QUANTUM != 0 {
+			return;
+		}
+		<EraCheckpoint<T>>::insert(number, hash);
+		if number_u64 >= generic::LONG_ERA_MAX_PERIOD + generic::LONG_ERA_PHASE_QUANTUM {
+			let to_remove = number_u64 - generic::LONG_ERA_MAX_PERIOD - generic::LONG_ERA_PHASE_QUANTUM;
+			let to_remove = to_remove.saturated_into::<T::BlockNumber>();
+			<EraCheckpoint<T>>::remove(to_remove);
+			<BlockTime<T>>::remove(to_remove);
+		}
+	}
+
+	/// Note the time of the current block, e.g. in milliseconds, for `CheckEra` to end mortal
+	/// transactions in time. Called by the timestamp module.
+	pub fn note_block_time(now: u64) {
+		<BlockTime<T>>::insert(Self::block_number(), now);
+	}
+
 	/// Remove temporary "environment" entries in storage.
 	pub fn finalize() -> T::Header {
 		ExecutionPhase::kill();
 		ExtrinsicCount::kill();
 		AllExtrinsicsWeight::kill();
+		OperationalExtrinsicsWeight::kill();
 		AllExtrinsicsLen::kill();
+		sp_io::storage::clear_prefix(well_known_keys::DOUGHNUT_VERIFIED_PREFIX);
 
 		let number = <Number<T>>::take();
 		let parent_hash = <ParentHash<T>>::take();
 		let mut digest = <Digest<T>>::take();
 		let extrinsics_root = <ExtrinsicsRoot<T>>::take();
 
+		// the transactions expiring in this block can't be replayed in the next ones
+		<RecentExtrinsics<T>>::remove_prefix(number);
+
 		// move block hash pruning window by one block
 		let block_hash_count = <T::BlockHashCount>::get();
 		if number > block_hash_count {
@@ -811,6 +893,10 @@ impl<T: Trait> Module<T> {
 			if to_remove != Zero::zero() {
 				<BlockHash<T>>::remove(to_remove);
 			}
+			// the times of the era checkpoints are pruned with them
+			if to_remove.saturated_into::<u64>() % generic::LONG_ERA_PHASE_QUANTUM != 0 {
+				<BlockTime<T>>::remove(to_remove);
+			}
 		}
 
 		let storage_root = T::Hash::decode(&mut &sp_io::storage::root()[..])
@@ -878,10 +964,12 @@ impl<T: Trait> Module<T> {
 		<ParentHash<T>>::put(n);
 	}
 
-	/// Set the current block weight. This should only be used in some integration tests.
+	/// Set the current block weight, all of it normal, and length. This should only be used in
+	/// some integration tests.
 	#[cfg(any(feature = "std", test))]
 	pub fn set_block_limits(weight: Weight, len: usize) {
 		AllExtrinsicsWeight::put(weight);
+		OperationalExtrinsicsWeight::kill();
 		AllExtrinsicsLen::put(len as u32);
 	}
 
@@ -893,6 +981,26 @@ impl<T: Trait> Module<T> {
 		<AccountNonce<T>>::insert(who, Self::account_nonce(who) + T::Index::one());
 	}
 
+	/// Note that `items` storage items of `bytes` bytes were written on behalf of `who`.
+	pub fn note_storage_added(who: &T::AccountId, items: u32, bytes: u32) {
+		<AccountStorage<T>>::mutate(who, |stats| {
+			stats.items = stats.items.saturating_add(items);
+			stats.bytes = stats.bytes.saturating_add(bytes);
+		});
+	}
+
+	/// Note that `items` storage items of `bytes` bytes written on behalf of `who` were removed.
+	pub fn note_storage_removed(who: &T::AccountId, items: u32, bytes: u32) {
+		<AccountStorage<T>>::mutate_exists(who, |maybe_stats| {
+			let stats = maybe_stats.get_or_insert_with(Default::default);
+			stats.items = stats.items.saturating_sub(items);
+			stats.bytes = stats.bytes.saturating_sub(bytes);
+			if *stats == StorageStats::default() {
+				*maybe_stats = None;
+			}
+		});
+	}
+
 	/// Note what the extrinsic data of the current extrinsic index is. If this
 	/// is called, then ensure `derive_extrinsics` is also called before
 	/// block-building is completed.
@@ -972,9 +1080,53 @@ impl<T: Trait> Module<T> {
 }
 
 impl<T: Trait> OnReapAccount<T::AccountId> for Module<T> {
-	/// Remove the nonce for the account. Account is considered fully removed from the system.
+	/// Remove the nonce and the storage stats for the account. Account is considered fully
+	/// removed from the system.
 	fn on_reap_account(who: &T::AccountId) {
 		<AccountNonce<T>>::remove(who);
+		<AccountStorage<T>>::remove(who);
+		<StorageDeposits<T>>::remove_prefix(who);
+	}
+}
+
+/// A storage deposit collector reserving `DepositPerByte` of `Currency` for every byte written,
+/// holding the reserved deposit under its `Id` in `StorageDeposits` and noting the storage in
+/// `AccountStorage`.
+///
+/// Refunds are unreserved at the current `DepositPerByte`, capped at the deposit held by the
+/// collector, so a collector never unreserves the deposits of other collectors or other reserves
+/// of the account. Every collector must have its own `Id`.
+pub struct StorageDeposit<T, Currency, DepositPerByte, Id>(
+	PhantomData<(T, Currency, DepositPerByte, Id)>
+);
+
+impl<T, C, D, I> StorageDepositCollector<T::AccountId> for StorageDeposit<T, C, D, I> where
+	T: Trait,
+	C: ReservableCurrency<T::AccountId>,
+	D: Get<C::Balance>,
+	I: Get<StorageDepositId>,
+{
+	fn charge(who: &T::AccountId, items: u32, bytes: u32) -> DispatchResult {
+		let deposit = D::get().saturating_mul(bytes.into());
+		C::reserve(who, deposit)?;
+		<StorageDeposits<T>>::mutate(who, I::get(), |held| {
+			*held = held.saturating_add(deposit.unique_saturated_into())
+		});
+		<Module<T>>::note_storage_added(who, items, bytes);
+		Ok(())
+	}
+
+	fn refund(who: &T::AccountId, items: u32, bytes: u32) {
+		let held = <StorageDeposits<T>>::get(who, I::get());
+		let deposit = D::get().saturating_mul(bytes.into()).min(C::Balance::unique_saturated_from(held));
+		C::unreserve(who, deposit);
+		let held = held.saturating_sub(deposit.unique_saturated_into());
+		if held == 0 {
+			<StorageDeposits<T>>::remove(who, I::get());
+		} else {
+			<StorageDeposits<T>>::insert(who, I::get(), held);
+		}
+		<Module<T>>::note_storage_removed(who, items, bytes);
 	}
 }
 
@@ -1053,6 +1205,12 @@ impl<T: Trait + Send + Sync> CheckWeight<T> {
 		let next_len = Self::check_block_length(info, len)?;
 		let next_weight = Self::check_weight(info)?;
 		AllExtrinsicsLen::put(next_len);
+		if info.class == DispatchClass::Operational {
+			let added_weight = next_weight.saturating_sub(Module::<T>::all_extrinsics_weight());
+			OperationalExtrinsicsWeight::mutate(|weight| {
+				*weight = Some(weight.unwrap_or_default().saturating_add(added_weight))
+			});
+		}
 		AllExtrinsicsWeight::put(next_weight);
 		Ok(())
 	}
@@ -1164,6 +1322,15 @@ impl<T: Trait> SignedExtension for CheckNonce<T> {
 
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
 
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "Compact<T::Index>",
+			additional_signed: "()",
+			optional: false,
+		}]
+	}
+
 	fn pre_dispatch(
 		&self,
 		who: &Self::AccountId,
@@ -1216,18 +1383,178 @@ impl<T: Trait> SignedExtension for CheckNonce<T> {
 	}
 }
 
-/// Check for transaction mortality.
+/// Replay protection by the hashes of the recent transactions, an alternative to `CheckNonce` for
+/// chains whose senders can't coordinate their nonces, e.g. the devices sharing a doughnut.
+///
+/// A transaction is valid until the block number it carries, at most `BlockHashCount` blocks
+/// ahead, and its hash, of the sender, call, expiry and salt, is kept until then. The salt tells
+/// apart the otherwise identical transactions of a sender. Checking and keeping the hash adds
+/// `CheckUnique::WEIGHT` to the weight of the block.
 #[derive(Encode, Decode, Clone, Eq, PartialEq)]
-pub struct CheckEra<T: Trait + Send + Sync>(Era, sp_std::marker::PhantomData<T>);
+pub struct CheckUnique<T: Trait> {
+	expires: T::BlockNumber,
+	salt: u32,
+}
 
-impl<T: Trait + Send + Sync> CheckEra<T> {
+impl<T: Trait> CheckUnique<T> {
+	/// utility constructor. Used only in client/factory code.
+	pub fn new(expires: T::BlockNumber, salt: u32) -> Self {
+		Self { expires, salt }
+	}
+
+	/// The weight of checking and keeping the hash of a transaction: a read and a write when it's
+	/// included, and a write removing it once it expired, at the RocksDB weights of the reference
+	/// hardware.
+	pub const WEIGHT: Weight = 25_000 + 2 * 100_000;
+
+	/// The hash of the transaction, if it hasn't expired, doesn't expire too far ahead and wasn't
+	/// included before.
+	fn check(&self, who: &T::AccountId, call: &T::Call) -> Result<T::Hash, TransactionValidityError>
+		where T::Call: Encode
+	{
+		let current = <Module<T>>::block_number();
+		if self.expires < current {
+			return Err(InvalidTransaction::Stale.into());
+		}
+		let window = T::BlockHashCount::get();
+		if self.expires - current > window {
+			return Err(InvalidTransaction::Future.into());
+		}
+
+		let hash = T::Hashing::hash_of(&(who, call, self.expires, self.salt));
+		if <RecentExtrinsics<T>>::contains_key(self.expires, hash) {
+			return Err(InvalidTransaction::Stale.into());
+		}
+		Ok(hash)
+	}
+}
+
+impl<T: Trait> Debug for CheckUnique<T> {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "CheckUnique({}, {})", self.expires, self.salt)
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+impl<T: Trait> SignedExtension for CheckUnique<T> where T::Call: Encode {
+	type AccountId = T::AccountId;
+	type Call = T::Call;
+	type AdditionalSigned = ();
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+	const IDENTIFIER: &'static str = "CheckUnique";
+
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "(T::BlockNumber, u32)",
+			additional_signed: "()",
+			optional: false,
+		}]
+	}
+
+	fn pre_dispatch(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		_info: Self::DispatchInfo,
+		_len: usize,
+	) -> Result<(), TransactionValidityError> {
+		let hash = self.check(who, call)?;
+		<RecentExtrinsics<T>>::insert(self.expires, hash, ());
+		<Module<T>>::register_extra_weight_unchecked(Self::WEIGHT);
+		Ok(())
+	}
+
+	fn validate(
+		&self,
+		who: &Self::AccountId,
+		call: &Self::Call,
+		info: Self::DispatchInfo,
+		_len: usize,
+	) -> TransactionValidity {
+		let hash = self.check(who, call)?;
+		let current = <Module<T>>::block_number();
+
+		Ok(ValidTransaction {
+			priority: info.weight as TransactionPriority,
+			requires: vec![],
+			provides: vec![hash.encode()],
+			longevity: (self.expires - current).saturated_into::<TransactionLongevity>().saturating_add(1),
+			propagate: true,
+		})
+	}
+}
+
+/// Check for transaction mortality.
+///
+/// With a non-zero `ExpectedBlockTime`, e.g. in milliseconds, a mortal transaction also ends
+/// once the time of `period` blocks of that duration passed since its birth, by the times of the
+/// blocks noted by the timestamp module, so it doesn't outlive the lifetime its era was chosen
+/// for (see `Era::mortal_for`) when blocks are produced late.
+#[derive(Encode, Decode)]
+pub struct CheckEra<T: Trait + Send + Sync, ExpectedBlockTime = ()>(
+	Era,
+	sp_std::marker::PhantomData<(T, ExpectedBlockTime)>,
+);
+
+impl<T: Trait + Send + Sync, ExpectedBlockTime> CheckEra<T, ExpectedBlockTime> {
 	/// utility constructor. Used only in client/factory code.
 	pub fn from(era: Era) -> Self {
 		Self(era, sp_std::marker::PhantomData)
 	}
 }
 
-impl<T: Trait + Send + Sync> Debug for CheckEra<T> {
+impl<T: Trait + Send + Sync, ExpectedBlockTime: Get<u64>> CheckEra<T, ExpectedBlockTime> {
+	/// The time left until the era ends in time at block `current`, `None` if it isn't bounded
+	/// in time or the times of its birth or of the current block aren't known.
+	fn time_left(&self, current: u64) -> Result<Option<u64>, TransactionValidityError> {
+		let block_time = ExpectedBlockTime::get();
+		let period = match self.0 {
+			Era::Mortal(period, _) if block_time > 0 => period,
+			_ => return Ok(None),
+		};
+		let birth = self.0.birth(current).saturated_into::<T::BlockNumber>();
+		let current = current.saturated_into::<T::BlockNumber>();
+		// the time of the current block is only set by its timestamp inherent.
+		let now = <Module<T>>::block_time(current)
+			.or_else(|| <Module<T>>::block_time(current.saturating_sub(One::one())));
+		let (born, now) = match (<Module<T>>::block_time(birth), now) {
+			(Some(born), Some(now)) => (born, now),
+			_ => return Ok(None),
+		};
+
+		let death = born.saturating_add(period.saturating_mul(block_time));
+		if now >= death {
+			Err(InvalidTransaction::Stale.into())
+		} else {
+			Ok(Some(death - now))
+		}
+	}
+}
+
+impl<T: Trait + Send + Sync, ExpectedBlockTime> Clone for CheckEra<T, ExpectedBlockTime> {
+	fn clone(&self) -> Self {
+		Self(self.0, sp_std::marker::PhantomData)
+	}
+}
+
+impl<T: Trait + Send + Sync, ExpectedBlockTime> PartialEq for CheckEra<T, ExpectedBlockTime> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+
+impl<T: Trait + Send + Sync, ExpectedBlockTime> Eq for CheckEra<T, ExpectedBlockTime> {}
+
+impl<T: Trait + Send + Sync, ExpectedBlockTime> Debug for CheckEra<T, ExpectedBlockTime> {
 	#[cfg(feature = "std")]
 	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
 		write!(f, "CheckEra({:?})", self.0)
@@ -1239,7 +1566,10 @@ impl<T: Trait + Send + Sync> Debug for CheckEra<T> {
 	}
 }
 
-impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
+impl<T, ExpectedBlockTime> SignedExtension for CheckEra<T, ExpectedBlockTime> where
+	T: Trait + Send + Sync,
+	ExpectedBlockTime: Get<u64> + Send + Sync + 'static,
+{
 	type AccountId = T::AccountId;
 	type Call = T::Call;
 	type AdditionalSigned = T::Hash;
@@ -1247,6 +1577,15 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
 	type Pre = ();
 	const IDENTIFIER: &'static str = "CheckEra";
 
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "Era",
+			additional_signed: "T::Hash",
+			optional: false,
+		}]
+	}
+
 	fn validate(
 		&self,
 		_who: &Self::AccountId,
@@ -1256,8 +1595,14 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
 	) -> TransactionValidity {
 		let current_u64 = <Module<T>>::block_number().saturated_into::<u64>();
 		let valid_till = self.0.death(current_u64);
+		let mut longevity = valid_till.saturating_sub(current_u64);
+		if let Some(time_left) = self.time_left(current_u64)? {
+			let block_time = ExpectedBlockTime::get();
+			let blocks_left = time_left / block_time + if time_left % block_time == 0 { 0 } else { 1 };
+			longevity = longevity.min(blocks_left);
+		}
 		Ok(ValidTransaction {
-			longevity: valid_till.saturating_sub(current_u64),
+			longevity,
 			..Default::default()
 		})
 	}
@@ -1265,10 +1610,11 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckEra<T> {
 	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
 		let current_u64 = <Module<T>>::block_number().saturated_into::<u64>();
 		let n = self.0.birth(current_u64).saturated_into::<T::BlockNumber>();
-		if !<BlockHash<T>>::contains_key(n) {
-			Err(InvalidTransaction::AncientBirthBlock.into())
-		} else {
+		if <BlockHash<T>>::contains_key(n) {
 			Ok(<Module<T>>::block_hash(n))
+		} else {
+			// the births of the long eras are kept longer than `BlockHashCount`
+			<Module<T>>::era_checkpoint(n).ok_or_else(|| InvalidTransaction::AncientBirthBlock.into())
 		}
 	}
 }
@@ -1307,6 +1653,15 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckGenesis<T> {
 	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
 		Ok(<Module<T>>::block_hash(T::BlockNumber::zero()))
 	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "()",
+			additional_signed: "T::Hash",
+			optional: false,
+		}]
+	}
 }
 
 /// Ensure the runtime version registered in the transaction is the same as at present.
@@ -1343,6 +1698,15 @@ impl<T: Trait + Send + Sync> SignedExtension for CheckVersion<T> {
 	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> {
 		Ok(<Module<T>>::runtime_version().spec_version)
 	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "()",
+			additional_signed: "u32",
+			optional: false,
+		}]
+	}
 }
 
 pub struct ChainContext<T>(sp_std::marker::PhantomData<T>);
@@ -1361,6 +1725,15 @@ impl<T: Trait> Lookup for ChainContext<T> {
 	}
 }
 
+impl<T: Trait, S> VerifyAccountSignature<S> for ChainContext<T> where
+	S: traits::Verify,
+	S::Signer: traits::IdentifyAccount<AccountId = T::AccountId>,
+{
+	fn verify_account_signature(&self, signature: &S, msg: &[u8], signer: &T::AccountId) -> bool {
+		traits::Verify::batch_verify(signature, msg, signer)
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1621,6 +1994,44 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn signed_ext_check_unique_works() {
+		new_test_ext().execute_with(|| {
+			let info = DispatchInfo::default();
+			let len = 0_usize;
+			System::set_block_number(5);
+			// expired and too far ahead, with `BlockHashCount` of 10
+			assert!(CheckUnique::<Test>::new(4, 0).validate(&1, CALL, info, len).is_err());
+			assert!(CheckUnique::<Test>::new(16, 0).validate(&1, CALL, info, len).is_err());
+
+			let ext = CheckUnique::<Test>::new(8, 0);
+			let valid = ext.validate(&1, CALL, info, len).unwrap();
+			assert_eq!((valid.requires.len(), valid.provides.len(), valid.longevity), (0, 1, 4));
+			assert!(ext.pre_dispatch(&1, CALL, info, len).is_ok());
+			// replayed
+			assert!(ext.validate(&1, CALL, info, len).is_err());
+			assert!(ext.pre_dispatch(&1, CALL, info, len).is_err());
+			// other salts and senders
+			assert!(CheckUnique::<Test>::new(8, 1).pre_dispatch(&1, CALL, info, len).is_ok());
+			assert!(ext.pre_dispatch(&2, CALL, info, len).is_ok());
+
+			assert_eq!(RecentExtrinsics::<Test>::iter_prefix(8).count(), 3);
+			// charged to the block, up to its limit in the tests
+			assert_eq!(System::all_extrinsics_weight(), <Test as Trait>::MaximumBlockWeight::get());
+
+			// pruned once expired
+			System::initialize(
+				&8,
+				&[0u8; 32].into(),
+				&[0u8; 32].into(),
+				&Default::default(),
+				InitKind::Full,
+			);
+			System::finalize();
+			assert_eq!(RecentExtrinsics::<Test>::iter_prefix(8).count(), 0);
+		})
+	}
+
 	#[test]
 	fn signed_ext_check_weight_works_normal_tx() {
 		new_test_ext().execute_with(|| {
@@ -1698,6 +2109,27 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn signed_ext_check_weight_tracks_weight_per_class() {
+		new_test_ext().execute_with(|| {
+			let normal = DispatchInfo { weight: 100, ..Default::default() };
+			let op = DispatchInfo { weight: 50, class: DispatchClass::Operational, pays_fee: true };
+			let len = 0_usize;
+
+			assert!(CheckWeight::<Test>(PhantomData).pre_dispatch(&1, CALL, normal, len).is_ok());
+			assert!(CheckWeight::<Test>(PhantomData).pre_dispatch(&1, CALL, op, len).is_ok());
+			assert!(CheckWeight::<Test>(PhantomData).pre_dispatch(&1, CALL, op, len).is_ok());
+
+			assert_eq!(System::all_extrinsics_weight(), 200);
+			assert_eq!(System::extrinsics_weight(DispatchClass::Normal), 100);
+			assert_eq!(System::extrinsics_weight(DispatchClass::Operational), 100);
+
+			System::set_block_limits(150, 0);
+			assert_eq!(System::extrinsics_weight(DispatchClass::Normal), 150);
+			assert_eq!(System::extrinsics_weight(DispatchClass::Operational), 0);
+		})
+	}
+
 	#[test]
 	fn signed_ext_check_weight_priority_works() {
 		new_test_ext().execute_with(|| {
@@ -1759,6 +2191,64 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn signed_ext_check_era_should_end_eras_in_time() {
+		parameter_types! {
+			pub const ExpectedBlockTime: u64 = 1_000;
+		}
+		type CheckEraInTime = CheckEra<Test, ExpectedBlockTime>;
+
+		new_test_ext().execute_with(|| {
+			let normal = DispatchInfo { weight: 100, class: DispatchClass::Normal, pays_fee: true };
+			let era = Era::mortal(16, 10);
+			let longevity = |era| CheckEraInTime::from(era).validate(&1, CALL, normal, 0)
+				.map(|valid| valid.longevity);
+
+			System::set_block_number(10);
+			System::note_block_time(100_000);
+			System::set_block_number(12);
+			System::note_block_time(102_000);
+			// on time, the era ends with its last block.
+			assert_eq!(longevity(era), Ok(14));
+			// without the times of its blocks, it ends with its last block.
+			assert_eq!(CheckEra::<Test>::from(era).validate(&1, CALL, normal, 0).unwrap().longevity, 14);
+
+			// blocks produced late end the era in time: 16 seconds after its birth.
+			System::set_block_number(13);
+			System::note_block_time(110_500);
+			assert_eq!(longevity(era), Ok(6));
+			System::set_block_number(14);
+			System::note_block_time(116_000);
+			assert_eq!(longevity(era), Err(InvalidTransaction::Stale.into()));
+			// the time of the current block defaults to the time of its parent.
+			System::set_block_number(15);
+			assert_eq!(longevity(era), Err(InvalidTransaction::Stale.into()));
+
+			assert!(longevity(Era::Immortal).is_ok());
+		})
+	}
+
+	#[test]
+	fn signed_exts_are_described_in_metadata() {
+		let metadata = <(
+			CheckVersion<Test>,
+			CheckGenesis<Test>,
+			CheckEra<Test>,
+			CheckNonce<Test>,
+			CheckWeight<Test>,
+		) as SignedExtension>::metadata();
+		let described: Vec<_> = metadata.iter()
+			.map(|m| (m.identifier, m.ty, m.additional_signed, m.optional))
+			.collect();
+		assert_eq!(described, vec![
+			("CheckVersion", "()", "u32", false),
+			("CheckGenesis", "()", "T::Hash", false),
+			("CheckEra", "Era", "T::Hash", false),
+			("CheckNonce", "Compact<T::Index>", "()", false),
+			("CheckWeight", "()", "()", false),
+		]);
+	}
+
 	#[test]
 	fn signed_ext_check_era_should_change_longevity() {
 		new_test_ext().execute_with(|| {
@@ -1775,6 +2265,35 @@ mod tests {
 		})
 	}
 
+	#[test]
+	fn signed_ext_check_era_should_accept_long_eras() {
+		new_test_ext().execute_with(|| {
+			let quantum = generic::LONG_ERA_PHASE_QUANTUM;
+			// the parent of the block `2 * quantum + 1` is a checkpoint
+			System::initialize(
+				&(quantum * 2 + 1),
+				&H256::repeat_byte(2),
+				&[0u8; 32].into(),
+				&Default::default(),
+				InitKind::Full,
+			);
+			System::finalize();
+			assert_eq!(System::era_checkpoint(quantum * 2), Some(H256::repeat_byte(2)));
+
+			let era = Era::long_mortal(1 << 17, quantum * 2 + 20);
+			assert_eq!(era.birth(quantum * 2 + 20), quantum * 2);
+
+			// long after the hash of the birth is pruned
+			System::set_block_number(quantum * 20);
+			<BlockHash<Test>>::remove(quantum * 2);
+			assert_eq!(CheckEra::<Test>::from(era).additional_signed(), Ok(H256::repeat_byte(2)));
+			assert_eq!(
+				CheckEra::<Test>::from(Era::long_mortal(1 << 17, quantum * 3)).additional_signed(),
+				Err(InvalidTransaction::AncientBirthBlock.into()),
+			);
+		})
+	}
+
 	#[test]
 	fn set_code_checks_works() {
 		struct CallInWasm(Vec<u8>);
@@ -1835,6 +2354,21 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn storage_stats_are_noted() {
+		new_test_ext().execute_with(|| {
+			System::note_storage_added(&1, 2, 100);
+			System::note_storage_added(&1, 0, 20);
+			assert_eq!(System::account_storage(1), StorageStats { items: 2, bytes: 120 });
+
+			System::note_storage_removed(&1, 1, 50);
+			assert_eq!(System::account_storage(1), StorageStats { items: 1, bytes: 70 });
+
+			System::note_storage_removed(&1, 5, 500);
+			assert!(!<AccountStorage<Test>>::contains_key(1));
+		});
+	}
+
 	#[test]
 	fn runtime_upgraded_with_set_storage() {
 		let executor = substrate_test_runtime_client::new_native_executor();
@@ -152,6 +152,7 @@ decl_module! {
 			);
 			<Self as Store>::Now::put(now);
 			<Self as Store>::DidUpdate::put(true);
+			<frame_system::Module<T>>::note_block_time(now.saturated_into::<u64>());
 
 			<T::OnTimestampSet as OnTimestampSet<_>>::on_timestamp_set(now);
 		}
@@ -315,6 +316,7 @@ mod tests {
 			Timestamp::set_timestamp(42);
 			assert_ok!(Timestamp::dispatch(Call::set(69), Origin::NONE));
 			assert_eq!(Timestamp::now(), 69);
+			assert_eq!(frame_system::Module::<Test>::block_time(frame_system::Module::<Test>::block_number()), Some(69));
 		});
 	}
 
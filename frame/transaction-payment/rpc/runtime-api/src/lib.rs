@@ -43,6 +43,29 @@ pub struct RuntimeDispatchInfo<Balance> {
 	pub partial_fee: Balance,
 }
 
+/// Fee information of a dispatchable sent with delegated authority from a doughnut.
+#[derive(Eq, PartialEq, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Debug, Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub struct RuntimeDelegatedDispatchInfo<Balance, AccountId> {
+	/// Weight of this dispatch.
+	pub weight: Weight,
+	/// Class of this dispatch.
+	pub class: DispatchClass,
+	/// The partial inclusion fee of this dispatch, see `RuntimeDispatchInfo::partial_fee`.
+	#[cfg_attr(feature = "std", serde(bound(serialize = "Balance: std::fmt::Display")))]
+	#[cfg_attr(feature = "std", serde(serialize_with = "serialize_as_string"))]
+	#[cfg_attr(feature = "std", serde(bound(deserialize = "Balance: std::str::FromStr")))]
+	#[cfg_attr(feature = "std", serde(deserialize_with = "deserialize_from_string"))]
+	pub partial_fee: Balance,
+	/// The doughnut issuer.
+	pub issuer: AccountId,
+	/// The doughnut holder.
+	pub holder: AccountId,
+	/// The account which will be charged the fee, either the issuer or the holder.
+	pub payer: AccountId,
+}
+
 #[cfg(feature = "std")]
 fn serialize_as_string<S: Serializer, T: std::fmt::Display>(t: &T, serializer: S) -> Result<S::Ok, S::Error> {
 	serializer.serialize_str(&t.to_string())
@@ -61,6 +84,21 @@ sp_api::decl_runtime_apis! {
 	{
 		fn query_info(uxt: Extrinsic, len: u32) -> RuntimeDispatchInfo<Balance>;
 	}
+
+	pub trait DelegatedTransactionPaymentApi<Balance, AccountId, Extrinsic> where
+		Balance: Codec + MaybeDisplay + MaybeFromStr,
+		AccountId: Codec,
+		Extrinsic: Codec,
+	{
+		/// Query the fee of `uxt` when sent with the encoded `doughnut`.
+		///
+		/// Returns `None` if the doughnut can not be decoded.
+		fn query_delegated_info(
+			uxt: Extrinsic,
+			doughnut: Vec<u8>,
+			len: u32,
+		) -> Option<RuntimeDelegatedDispatchInfo<Balance, AccountId>>;
+	}
 }
 
 #[cfg(test)]
@@ -24,9 +24,14 @@ use jsonrpc_derive::rpc;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, MaybeDisplay, MaybeFromStr}};
 use sp_api::ProvideRuntimeApi;
 use sp_core::Bytes;
-use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
-pub use pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi as TransactionPaymentRuntimeApi;
+use pallet_transaction_payment_rpc_runtime_api::{RuntimeDispatchInfo, RuntimeDelegatedDispatchInfo};
+pub use pallet_transaction_payment_rpc_runtime_api::{
+	TransactionPaymentApi as TransactionPaymentRuntimeApi,
+	DelegatedTransactionPaymentApi as DelegatedTransactionPaymentRuntimeApi,
+};
 pub use self::gen_client::Client as TransactionPaymentClient;
+pub use self::delegated::gen_client::Client as DelegatedTransactionPaymentClient;
+pub use self::delegated::DelegatedTransactionPaymentApi;
 
 #[rpc]
 pub trait TransactionPaymentApi<BlockHash, ResponseType> {
@@ -38,7 +43,23 @@ pub trait TransactionPaymentApi<BlockHash, ResponseType> {
 	) -> Result<ResponseType>;
 }
 
-/// A struct that implements the [`TransactionPaymentApi`].
+mod delegated {
+	use super::*;
+
+	#[rpc]
+	pub trait DelegatedTransactionPaymentApi<BlockHash, ResponseType> {
+		/// Query the fee of an extrinsic sent with the encoded doughnut and the account charged for it.
+		#[rpc(name = "payment_queryDelegatedInfo")]
+		fn query_delegated_info(
+			&self,
+			encoded_xt: Bytes,
+			doughnut: Bytes,
+			at: Option<BlockHash>
+		) -> Result<ResponseType>;
+	}
+}
+
+/// A struct that implements the [`TransactionPaymentApi`] and [`DelegatedTransactionPaymentApi`].
 pub struct TransactionPayment<C, P> {
 	client: Arc<C>,
 	_marker: std::marker::PhantomData<P>,
@@ -57,6 +78,8 @@ pub enum Error {
 	DecodeError,
 	/// The call to runtime failed.
 	RuntimeError,
+	/// The doughnut was not decodable.
+	DoughnutDecodeError,
 }
 
 impl From<Error> for i64 {
@@ -64,6 +87,7 @@ impl From<Error> for i64 {
 		match e {
 			Error::RuntimeError => 1,
 			Error::DecodeError => 2,
+			Error::DoughnutDecodeError => 3,
 		}
 	}
 }
@@ -102,3 +126,47 @@ where
 		})
 	}
 }
+
+impl<C, Block, Balance, AccountId, Extrinsic>
+	DelegatedTransactionPaymentApi<<Block as BlockT>::Hash, RuntimeDelegatedDispatchInfo<Balance, AccountId>>
+	for TransactionPayment<C, (Block, Extrinsic)>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: DelegatedTransactionPaymentRuntimeApi<Block, Balance, AccountId, Extrinsic>,
+	Balance: Codec + MaybeDisplay + MaybeFromStr,
+	AccountId: Codec,
+	Extrinsic: Codec + Send + Sync + 'static,
+{
+	fn query_delegated_info(
+		&self,
+		encoded_xt: Bytes,
+		doughnut: Bytes,
+		at: Option<<Block as BlockT>::Hash>
+	) -> Result<RuntimeDelegatedDispatchInfo<Balance, AccountId>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		let encoded_len = encoded_xt.len() as u32;
+
+		let uxt: Extrinsic = Decode::decode(&mut &*encoded_xt).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::DecodeError.into()),
+			message: "Unable to query delegated dispatch info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})?;
+		api.query_delegated_info(&at, uxt, doughnut.to_vec(), encoded_len)
+			.map_err(|e| RpcError {
+				code: ErrorCode::ServerError(Error::RuntimeError.into()),
+				message: "Unable to query delegated dispatch info.".into(),
+				data: Some(format!("{:?}", e).into()),
+			})?
+			.ok_or_else(|| RpcError {
+				code: ErrorCode::ServerError(Error::DoughnutDecodeError.into()),
+				message: "Unable to decode doughnut.".into(),
+				data: None,
+			})
+	}
+}
@@ -24,10 +24,15 @@
 //!     chance to be included by the transaction queue.
 //!
 //! Additionally, this module allows one to configure:
+//!   - How the fee is withdrawn from the sender via [`OnChargeTransaction`], e.g. from a single
+//!     `Currency` using [`CurrencyAdapter`] or from an asset of the sender's choosing.
 //!   - The mapping between one unit of weight to one unit of fee via [`WeightToFee`].
 //!   - A means of updating the fee for the next block, via defining a multiplier, based on the
 //!     final state of the chain at the end of the previous block. This can be configured via
 //!     [`FeeMultiplierUpdate`]
+//!   - A separate multiplier for operational dispatches, updated via
+//!     [`OperationalFeeMultiplierUpdate`], so that congestion caused by normal user traffic does
+//!     not inflate the fees of network maintenance extrinsics.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -35,33 +40,32 @@ use sp_std::prelude::*;
 use codec::{Encode, Decode};
 use frame_support::{
 	decl_storage, decl_module,
-	traits::{Currency, Get, OnUnbalanced, ExistenceRequirement, WithdrawReason, Imbalance},
-	weights::{Weight, DispatchInfo, GetDispatchInfo},
+	traits::Get,
+	weights::{Weight, DispatchInfo, DispatchClass, GetDispatchInfo},
 };
 use sp_runtime::{
 	Fixed64,
 	transaction_validity::{
-		TransactionPriority, ValidTransaction, InvalidTransaction, TransactionValidityError,
+		TransactionPriority, ValidTransaction, TransactionValidityError,
 		TransactionValidity,
 	},
-	traits::{Zero, Saturating, SignedExtension, SaturatedConversion, Convert},
+	traits::{
+		Zero, Saturating, SignedExtension, SignedExtensionMetadata, SaturatedConversion, Convert,
+		PlugDoughnutApi,
+	},
 };
-use pallet_transaction_payment_rpc_runtime_api::RuntimeDispatchInfo;
+use pallet_transaction_payment_rpc_runtime_api::{RuntimeDispatchInfo, RuntimeDelegatedDispatchInfo};
+
+mod payment;
+pub use payment::{OnChargeTransaction, CurrencyAdapter};
 
 type Multiplier = Fixed64;
-type BalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
-type NegativeImbalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+type BalanceOf<T> = <<T as Trait>::OnChargeTransaction as OnChargeTransaction<T>>::Balance;
 
 pub trait Trait: frame_system::Trait {
-	/// The currency type in which fees will be paid.
-	type Currency: Currency<Self::AccountId> + Send + Sync;
-
-	/// Handler for the unbalanced reduction when taking transaction fees. This is either one or
-	/// two separate imbalances, the first is the transaction fee paid, the second is the tip paid,
-	/// if any.
-	type OnTransactionPayment: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	/// Handler for withdrawing the fee of a transaction from its sender. Use `CurrencyAdapter` to
+	/// pay fees in a single `Currency`.
+	type OnChargeTransaction: OnChargeTransaction<Self>;
 
 	/// The fee to be paid for making a transaction; the base.
 	type TransactionBaseFee: Get<BalanceOf<Self>>;
@@ -74,11 +78,18 @@ pub trait Trait: frame_system::Trait {
 
 	/// Update the multiplier of the next block, based on the previous block's weight.
 	type FeeMultiplierUpdate: Convert<Multiplier, Multiplier>;
+
+	/// Update the multiplier applied to operational dispatches in the next block, based on the
+	/// previous block's operational weight.
+	type OperationalFeeMultiplierUpdate: Convert<Multiplier, Multiplier>;
 }
 
 decl_storage! {
 	trait Store for Module<T: Trait> as TransactionPayment {
 		pub NextFeeMultiplier get(fn next_fee_multiplier): Multiplier = Multiplier::from_parts(0);
+		/// The fee multiplier applied to `DispatchClass::Operational` dispatches.
+		pub NextOperationalFeeMultiplier get(fn next_operational_fee_multiplier):
+			Multiplier = Multiplier::from_parts(0);
 	}
 }
 
@@ -94,11 +105,22 @@ decl_module! {
 			NextFeeMultiplier::mutate(|fm| {
 				*fm = T::FeeMultiplierUpdate::convert(*fm)
 			});
+			NextOperationalFeeMultiplier::mutate(|fm| {
+				*fm = T::OperationalFeeMultiplierUpdate::convert(*fm)
+			});
 		}
 	}
 }
 
 impl<T: Trait> Module<T> {
+	/// The fee multiplier that applies to dispatches of the given `class` in this block.
+	pub fn next_fee_multiplier_for(class: DispatchClass) -> Multiplier {
+		match class {
+			DispatchClass::Normal => NextFeeMultiplier::get(),
+			DispatchClass::Operational => NextOperationalFeeMultiplier::get(),
+		}
+	}
+
 	/// Query the data that we know about the fee of a given `call`.
 	///
 	/// As this module is not and cannot be aware of the internals of a signed extension, it only
@@ -127,6 +149,32 @@ impl<T: Trait> Module<T> {
 
 		RuntimeDispatchInfo { weight, class, partial_fee }
 	}
+
+	/// Query the fee of a given `call` sent with delegated authority from the encoded `doughnut`.
+	///
+	/// Returns `None` if the doughnut can not be decoded.
+	pub fn query_delegated_info<Extrinsic: GetDispatchInfo>(
+		unchecked_extrinsic: Extrinsic,
+		doughnut: Vec<u8>,
+		len: u32,
+	) -> Option<RuntimeDelegatedDispatchInfo<BalanceOf<T>, <T::Doughnut as PlugDoughnutApi>::PublicKey>>
+	where
+		T: Send + Sync,
+		BalanceOf<T>: Send + Sync,
+	{
+		let doughnut = T::Doughnut::decode(&mut &doughnut[..]).ok()?;
+		let RuntimeDispatchInfo { weight, class, partial_fee } = Self::query_info(unchecked_extrinsic, len);
+
+		Some(RuntimeDelegatedDispatchInfo {
+			weight,
+			class,
+			partial_fee,
+			issuer: doughnut.issuer(),
+			holder: doughnut.holder(),
+			// `ChargeTransactionPayment` withdraws from the signer, who must be the doughnut holder.
+			payer: doughnut.holder(),
+		})
+	}
 }
 
 /// Require the transactor pay for themselves and maybe include a tip to gain additional priority
@@ -149,7 +197,7 @@ impl<T: Trait + Send + Sync> ChargeTransactionPayment<T> {
 	///      size-fee, this is not input dependent and reflects the _complexity_ of the execution
 	///      and the time it consumes.
 	///   - _targeted_fee_adjustment_: This is a multiplier that can tune the final fee based on
-	///     the congestion of the network.
+	///     the congestion of the network. Each dispatch class has its own multiplier.
 	///   - (optional) _tip_: if included in the transaction, it will be added on top. Only signed
 	///      transactions can have a tip.
 	///
@@ -177,7 +225,7 @@ impl<T: Trait + Send + Sync> ChargeTransactionPayment<T> {
 
 			// the adjustable part of the fee
 			let adjustable_fee = len_fee.saturating_add(weight_fee);
-			let targeted_fee_adjustment = NextFeeMultiplier::get();
+			let targeted_fee_adjustment = Module::<T>::next_fee_multiplier_for(info.class);
 			// adjusted_fee = adjustable_fee + (adjustable_fee * targeted_fee_adjustment)
 			let adjusted_fee = targeted_fee_adjustment.saturated_multiply_accumulate(adjustable_fee);
 
@@ -211,6 +259,15 @@ impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPayment<T>
 	type Pre = ();
 	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
 
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "Compact<BalanceOf<T>>",
+			additional_signed: "()",
+			optional: false,
+		}]
+	}
+
 	fn validate(
 		&self,
 		who: &Self::AccountId,
@@ -223,22 +280,7 @@ impl<T: Trait + Send + Sync> SignedExtension for ChargeTransactionPayment<T>
 		let fee = Self::compute_fee(len as u32, info, tip);
 		// Only mess with balances if fee is not zero.
 		if !fee.is_zero() {
-			let imbalance = match T::Currency::withdraw(
-				who,
-				fee,
-				if tip.is_zero() {
-					WithdrawReason::TransactionPayment.into()
-				} else {
-					WithdrawReason::TransactionPayment | WithdrawReason::Tip
-				},
-				ExistenceRequirement::KeepAlive,
-			) {
-				Ok(imbalance) => imbalance,
-				Err(_) => return InvalidTransaction::Payment.into(),
-			};
-			let imbalances = imbalance.split(tip);
-			T::OnTransactionPayment::on_unbalanceds(Some(imbalances.0).into_iter()
-				.chain(Some(imbalances.1)));
+			T::OnChargeTransaction::withdraw_fee(who, fee, tip)?;
 		}
 
 		let mut r = ValidTransaction::default();
@@ -352,12 +394,12 @@ mod tests {
 	}
 
 	impl Trait for Runtime {
-		type Currency = pallet_balances::Module<Runtime>;
-		type OnTransactionPayment = ();
+		type OnChargeTransaction = CurrencyAdapter<pallet_balances::Module<Runtime>, ()>;
 		type TransactionBaseFee = TransactionBaseFee;
 		type TransactionByteFee = TransactionByteFee;
 		type WeightToFee = WeightToFee;
 		type FeeMultiplierUpdate = ();
+		type OperationalFeeMultiplierUpdate = ();
 	}
 
 	type Balances = pallet_balances::Module<Runtime>;
@@ -572,6 +614,39 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn query_delegated_info_works() {
+		let call = Call::Balances(BalancesCall::transfer(2, 69));
+		let origin = 111111;
+		let extra = ();
+		let xt = TestXt::new(call, (origin, extra));
+		let info  = xt.get_dispatch_info();
+		let ext = xt.encode();
+		let len = ext.len() as u32;
+		ExtBuilder::default()
+			.base_fee(5)
+			.weight_fee(2)
+			.build()
+			.execute_with(||
+		{
+			// the test runtime uses the unit doughnut, which has default issuer and holder keys
+			assert_eq!(
+				TransactionPayment::query_delegated_info(xt, ().encode(), len),
+				Some(RuntimeDelegatedDispatchInfo {
+					weight: info.weight,
+					class: info.class,
+					partial_fee:
+						5 /* base */
+						+ len as u64 /* len * 1 */
+						+ info.weight.min(MaximumBlockWeight::get()) as u64 * 2, /* weight * weight_to_fee */
+					issuer: [0u8; 32],
+					holder: [0u8; 32],
+					payer: [0u8; 32],
+				}),
+			);
+		});
+	}
+
 	#[test]
 	fn compute_fee_works_without_multiplier() {
 		ExtBuilder::default()
@@ -626,7 +701,7 @@ mod tests {
 			// Base fee is unaffected by multiplier
 			let dispatch_info = DispatchInfo {
 				weight: 0,
-				class: DispatchClass::Operational,
+				class: DispatchClass::Normal,
 				pays_fee: true,
 			};
 			assert_eq!(ChargeTransactionPayment::<Runtime>::compute_fee(0, dispatch_info, 0), 100);
@@ -634,7 +709,7 @@ mod tests {
 			// Everything works together :)
 			let dispatch_info = DispatchInfo {
 				weight: 123,
-				class: DispatchClass::Operational,
+				class: DispatchClass::Normal,
 				pays_fee: true,
 			};
 			// 123 weight, 456 length, 100 base
@@ -645,6 +720,33 @@ mod tests {
 		});
 	}
 
+	#[test]
+	fn compute_fee_uses_multiplier_of_dispatch_class() {
+		ExtBuilder::default()
+			.base_fee(100)
+			.byte_fee(10)
+			.balance_factor(0)
+			.build()
+			.execute_with(||
+		{
+			// congestion of normal dispatches does not affect operational dispatches
+			NextFeeMultiplier::put(Fixed64::from_rational(1, 1)); // = 1
+			NextOperationalFeeMultiplier::put(Fixed64::from_rational(1, 2)); // = .5
+			let normal = DispatchInfo {
+				weight: 100,
+				class: DispatchClass::Normal,
+				pays_fee: true,
+			};
+			let operational = DispatchInfo { class: DispatchClass::Operational, ..normal };
+			// adjustable fee = 100 + (10 * 10) = 200
+			assert_eq!(ChargeTransactionPayment::<Runtime>::compute_fee(10, normal, 0), 100 + 400);
+			assert_eq!(
+				ChargeTransactionPayment::<Runtime>::compute_fee(10, operational, 0),
+				100 + 300,
+			);
+		});
+	}
+
 	#[test]
 	fn compute_fee_does_not_overflow() {
 		ExtBuilder::default()
@@ -0,0 +1,81 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Abstractions over how the fee of a transaction is withdrawn from its sender.
+
+use crate::Trait;
+use codec::FullCodec;
+use frame_support::traits::{
+	Currency, ExistenceRequirement, Imbalance, OnUnbalanced, WithdrawReason,
+};
+use sp_runtime::{
+	traits::{AtLeast32Bit, MaybeSerializeDeserialize, Zero},
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+use sp_std::{fmt::Debug, marker::PhantomData};
+
+/// Handle withdrawing the fee (and tip) of a transaction from its sender.
+///
+/// This allows fees to be paid in something other than a single native `Currency`, e.g. in an
+/// application token of the sender's choosing.
+pub trait OnChargeTransaction<T: Trait> {
+	/// The balance type in which fees are calculated.
+	type Balance: AtLeast32Bit + FullCodec + Copy + MaybeSerializeDeserialize + Debug + Default;
+
+	/// Withdraw `fee` from `who`, `tip` being the part of `fee` that was offered as a tip.
+	///
+	/// Only called for non-zero fees. Both amounts are denominated in `Self::Balance`; an
+	/// implementation may convert them into another asset before withdrawing.
+	fn withdraw_fee(
+		who: &T::AccountId,
+		fee: Self::Balance,
+		tip: Self::Balance,
+	) -> Result<(), TransactionValidityError>;
+}
+
+/// Implements `OnChargeTransaction` by withdrawing fees from a single `Currency` `C`.
+///
+/// The withdrawn fee and tip are handed to `OU`, fee first, then tip.
+pub struct CurrencyAdapter<C, OU>(PhantomData<(C, OU)>);
+
+impl<T, C, OU> OnChargeTransaction<T> for CurrencyAdapter<C, OU> where
+	T: Trait,
+	C: Currency<T::AccountId>,
+	OU: OnUnbalanced<C::NegativeImbalance>,
+{
+	type Balance = C::Balance;
+
+	fn withdraw_fee(
+		who: &T::AccountId,
+		fee: Self::Balance,
+		tip: Self::Balance,
+	) -> Result<(), TransactionValidityError> {
+		// `fee` already includes `tip`.
+		let imbalance = C::withdraw(
+			who,
+			fee,
+			if tip.is_zero() {
+				WithdrawReason::TransactionPayment.into()
+			} else {
+				WithdrawReason::TransactionPayment | WithdrawReason::Tip
+			},
+			ExistenceRequirement::KeepAlive,
+		).map_err(|_| InvalidTransaction::Payment)?;
+		let imbalances = imbalance.split(tip);
+		OU::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+		Ok(())
+	}
+}
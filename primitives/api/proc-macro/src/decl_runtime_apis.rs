@@ -19,7 +19,8 @@ use crate::utils::{
 	fold_fn_decl_for_client_side, extract_parameter_names_types_and_borrows,
 	generate_native_call_generator_fn_name, return_type_extract_type,
 	generate_method_runtime_api_impl_name, generate_call_api_at_fn_name, prefix_function_with_trait,
-	replace_wild_card_parameter_names,
+	replace_wild_card_parameter_names, generate_default_method_dispatch_name,
+	generate_default_method_dispatch_impl_name,
 };
 
 use proc_macro2::{TokenStream, Span};
@@ -30,6 +31,7 @@ use syn::{
 	spanned::Spanned, parse_macro_input, parse::{Parse, ParseStream, Result, Error}, ReturnType,
 	fold::{self, Fold}, parse_quote, ItemTrait, Generics, GenericParam, Attribute, FnArg, Type,
 	visit::{Visit, self}, TraitBound, Meta, NestedMeta, Lit, TraitItem, Ident, TraitItemMethod,
+	LitByteStr,
 };
 
 use std::collections::HashMap;
@@ -473,6 +475,102 @@ fn generate_call_api_at_calls(decl: &ItemTrait) -> Result<TokenStream> {
 	Ok(quote!( #( #result )* ))
 }
 
+/// Generate the function that dispatches calls to the default methods of the given trait and the
+/// function that calls this dispatch in the runtime at a given block.
+///
+/// The runtime implementation of a trait may omit methods that provide a default body, so
+/// `impl_runtime_apis!` can not generate an exported function for these methods. Instead, each
+/// implementation exports one function that forwards the call to `default_method_dispatch`.
+fn generate_default_method_dispatch(decl: &ItemTrait) -> Result<TokenStream> {
+	let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+	let trait_ = &decl.ident;
+	let dispatch_name = generate_default_method_dispatch_name(trait_);
+	let generic_params = decl.generics.params.iter();
+	let (_, ty_generics, where_clause) = decl.generics.split_for_impl();
+
+	let methods = decl.items.iter().filter_map(|i| match i {
+		TraitItem::Method(ref m) if m.default.is_some() => Some(m),
+		_ => None,
+	});
+
+	let mut arms = Vec::new();
+	for method in methods {
+		let params = extract_parameter_names_types_and_borrows(&method.sig)?;
+		let fn_name = &method.sig.ident;
+		let fn_name_str = fn_name.to_string();
+		let fn_name_bytes = LitByteStr::new(fn_name_str.as_bytes(), Span::call_site());
+		let pnames = params.iter().map(|v| &v.0);
+		let pnames2 = params.iter().map(|v| &v.0);
+		let ptypes = params.iter().map(|v| &v.1);
+		let pborrow = params.iter().map(|v| &v.2);
+
+		arms.push(quote!(
+			#fn_name_bytes => {
+				#(
+					let #pnames : #ptypes = match #crate_::Decode::decode(&mut input) {
+						Ok(input) => input,
+						Err(e) => panic!("Bad input data provided to {}: {}", #fn_name_str, e.what()),
+					};
+				)*
+
+				Some(#crate_::Encode::encode(&<Runtime as #trait_ #ty_generics>::#fn_name(
+					#( #pborrow #pnames2 ),*
+				)))
+			}
+		));
+	}
+
+	Ok(quote!(
+		/// Dispatches the call to the default method `method` of the given `Runtime`.
+		///
+		/// Returns `None` if `method` is not a method with a default implementation.
+		#[allow(unused_mut, unused_variables)]
+		pub fn default_method_dispatch<Runtime: #trait_ #ty_generics, #( #generic_params ),*>(
+			method: &[u8],
+			mut input: &[u8],
+		) -> Option<#crate_::vec::Vec<u8>> #where_clause {
+			match method {
+				#( #arms )*
+				_ => None,
+			}
+		}
+
+		#[cfg(any(feature = "std", test))]
+		pub fn default_method_dispatch_call_api_at<
+			Block: #crate_::BlockT,
+			T: #crate_::CallApiAt<Block>,
+			C: #crate_::Core<Block, Error = T::Error>,
+		>(
+			call_runtime_at: &T,
+			core_api: &C,
+			at: &#crate_::BlockId<Block>,
+			args: Vec<u8>,
+			changes: &std::cell::RefCell<#crate_::OverlayedChanges>,
+			storage_transaction_cache: &std::cell::RefCell<
+				#crate_::StorageTransactionCache<Block, T::StateBackend>
+			>,
+			initialized_block: &std::cell::RefCell<Option<#crate_::BlockId<Block>>>,
+			context: #crate_::ExecutionContext,
+			recorder: &Option<#crate_::ProofRecorder<Block>>,
+		) -> std::result::Result<#crate_::NativeOrEncoded<Vec<u8>>, T::Error> {
+			let params = #crate_::CallApiAtParams::<_, _, fn() -> _, _> {
+				core_api,
+				at,
+				function: #dispatch_name,
+				native_call: None,
+				arguments: args,
+				overlayed_changes: changes,
+				storage_transaction_cache,
+				initialize_block: #crate_::InitializeBlock::Do(&initialized_block),
+				context,
+				recorder,
+			};
+
+			call_runtime_at.call_api_at(params)
+		}
+	))
+}
+
 /// Generate the declaration of the trait for the runtime.
 fn generate_runtime_decls(decls: &[ItemTrait]) -> Result<TokenStream> {
 	let mut result = Vec::new();
@@ -505,6 +603,7 @@ fn generate_runtime_decls(decls: &[ItemTrait]) -> Result<TokenStream> {
 		}).collect();
 
 		let native_call_generators = generate_native_call_generators(&decl)?;
+		let default_method_dispatch = generate_default_method_dispatch(&decl)?;
 
 		result.push(quote!(
 			#[doc(hidden)]
@@ -522,6 +621,8 @@ fn generate_runtime_decls(decls: &[ItemTrait]) -> Result<TokenStream> {
 				#native_call_generators
 
 				#call_api_at_calls
+
+				#default_method_dispatch
 			}
 		));
 	}
@@ -556,9 +657,36 @@ impl<'a> ToClientSideDecl<'a> {
 			r => result.push(r),
 		});
 
+		result.push(self.create_default_method_dispatch_runtime_api_impl().into());
+
 		result
 	}
 
+	/// Creates the `default_method_dispatch_runtime_api_impl` method that is used to call the
+	/// default methods of this trait in the runtime.
+	///
+	/// The default implementation returns an error, so that hand written implementations of the
+	/// client side trait are not required to implement it.
+	fn create_default_method_dispatch_runtime_api_impl(&mut self) -> TraitItemMethod {
+		let name = generate_default_method_dispatch_impl_name(&self.trait_);
+		let block_id = self.block_id;
+		let crate_ = self.crate_;
+
+		parse_quote!{
+			#[doc(hidden)]
+			fn #name(
+				&self,
+				at: &#block_id,
+				context: #crate_::ExecutionContext,
+				method: &'static str,
+				params_encoded: Vec<u8>,
+			) -> std::result::Result<Vec<u8>, Self::Error> {
+				let _ = (at, context, params_encoded);
+				Err(format!("Calling the default method `{}` is not supported.", method).into())
+			}
+		}
+	}
+
 	fn fold_trait_item_method(&mut self, method: TraitItemMethod)
 		-> (TraitItemMethod, Option<TraitItemMethod>, TraitItemMethod) {
 		let crate_ = self.crate_;
@@ -607,18 +735,36 @@ impl<'a> ToClientSideDecl<'a> {
 		let block_id = self.block_id;
 		let crate_ = self.crate_;
 
-		Some(
-			parse_quote!{
-				#[doc(hidden)]
-				fn #name(
-					&self,
-					at: &#block_id,
-					context: #crate_::ExecutionContext,
-					params: Option<( #( #param_types ),* )>,
-					params_encoded: Vec<u8>,
-				) -> std::result::Result<#crate_::NativeOrEncoded<#ret_type>, Self::Error>;
-			}
-		)
+		let mut fn_impl: TraitItemMethod = parse_quote!{
+			#[doc(hidden)]
+			fn #name(
+				&self,
+				at: &#block_id,
+				context: #crate_::ExecutionContext,
+				params: Option<( #( #param_types ),* )>,
+				params_encoded: Vec<u8>,
+			) -> std::result::Result<#crate_::NativeOrEncoded<#ret_type>, Self::Error>;
+		};
+
+		// A method with a default implementation is not required to be implemented by the
+		// runtime, so we call it through the default method dispatch of the trait.
+		if method.default.is_some() {
+			let dispatch = generate_default_method_dispatch_impl_name(&self.trait_);
+			let function_name = method.sig.ident.to_string();
+
+			fn_impl.default = Some(
+				parse_quote! {
+					{
+						let _ = params;
+						self.#dispatch(at, context, #function_name, params_encoded)
+							.map(#crate_::NativeOrEncoded::Encoded)
+					}
+				}
+			);
+			fn_impl.semi_token = None;
+		}
+
+		Some(fn_impl)
 	}
 
 	/// Takes the method declared by the user and creates the declaration we require for the runtime
@@ -794,6 +940,7 @@ fn generate_runtime_info_impl(trait_: &ItemTrait, version: u64) -> TokenStream {
 	let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
 	let id = generate_runtime_api_id(&trait_name.to_string());
 	let version = generate_runtime_api_version(version as u32);
+	let name = trait_name.to_string();
 
 	let impl_generics = trait_.generics.type_params().map(|t| {
 		let ident = &t.ident;
@@ -815,6 +962,7 @@ fn generate_runtime_info_impl(trait_: &ItemTrait, version: u64) -> TokenStream {
 		{
 			#id
 			#version
+			const NAME: &'static str = #name;
 		}
 	)
 }
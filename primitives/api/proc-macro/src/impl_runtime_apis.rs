@@ -19,7 +19,8 @@ use crate::utils::{
 	generate_runtime_mod_name_for_trait, generate_method_runtime_api_impl_name,
 	extract_parameter_names_types_and_borrows, generate_native_call_generator_fn_name,
 	return_type_extract_type, generate_call_api_at_fn_name, prefix_function_with_trait,
-	extract_all_signature_types,
+	extract_all_signature_types, generate_default_method_dispatch_name,
+	generate_default_method_dispatch_impl_name,
 };
 
 use proc_macro2::{Span, TokenStream};
@@ -172,6 +173,53 @@ fn generate_impl_calls(
 	Ok(impl_calls)
 }
 
+/// Generate the calls to the default method dispatch of each implemented trait.
+///
+/// The input is the encoded name of the default method and its encoded parameters. The generated
+/// call returns the encoded output of the method or `None` if the method is unknown.
+fn generate_default_method_dispatch_calls(
+	impls: &[ItemImpl],
+	input: &Ident,
+) -> Result<Vec<(String, TokenStream)>> {
+	let c = generate_crate_access(HIDDEN_INCLUDES_ID);
+	let mut dispatch_calls = Vec::new();
+
+	for impl_ in impls {
+		let impl_trait_path = extract_impl_trait(impl_)?;
+		let impl_trait = impl_trait_path
+			.segments
+			.last()
+			.ok_or_else(|| Error::new(impl_trait_path.span(), "Empty trait path not possible!"))?;
+		let name = generate_default_method_dispatch_name(&impl_trait.ident);
+		let runtime = &impl_.self_ty;
+		let mut runtime_mod_path = extend_with_runtime_decl_path(impl_trait_path.clone());
+		// remove the trait to get just the module path
+		runtime_mod_path.segments.pop();
+
+		let trait_generic_arguments = match impl_trait.arguments {
+			PathArguments::Parenthesized(_) | PathArguments::None => vec![],
+			PathArguments::AngleBracketed(ref b) => b.args.iter().cloned().collect(),
+		};
+
+		let dispatch_call = quote!(
+			let (method, params): (#c::vec::Vec<u8>, #c::vec::Vec<u8>) =
+				match #c::Decode::decode(&mut #input) {
+					Ok(input) => input,
+					Err(e) => panic!("Bad input data provided to {}: {}", #name, e.what()),
+				};
+
+			#runtime_mod_path default_method_dispatch::<#runtime #(, #trait_generic_arguments )*>(
+				&method,
+				&params,
+			)
+		);
+
+		dispatch_calls.push((name, dispatch_call));
+	}
+
+	Ok(dispatch_calls)
+}
+
 /// Generate the dispatch function that is used in native to call into the runtime.
 fn generate_dispatch_function(impls: &[ItemImpl]) -> Result<TokenStream> {
 	let data = Ident::new("data", Span::call_site());
@@ -182,12 +230,23 @@ fn generate_dispatch_function(impls: &[ItemImpl]) -> Result<TokenStream> {
 			let name = prefix_function_with_trait(&trait_, &fn_name);
 			quote!( #name => Some(#c::Encode::encode(&{ #impl_ })), )
 		});
+	let dispatch_calls = generate_default_method_dispatch_calls(impls, &data)?
+		.into_iter()
+		.map(|(name, dispatch_call)| {
+			quote!(
+				#name => {
+					let output = { #dispatch_call };
+					output.map(|output| #c::Encode::encode(&output))
+				},
+			)
+		});
 
 	Ok(quote!(
 		#[cfg(feature = "std")]
 		pub fn dispatch(method: &str, mut #data: &[u8]) -> Option<Vec<u8>> {
 			match method {
 				#( #impl_calls )*
+				#( #dispatch_calls )*
 				_ => None,
 			}
 		}
@@ -223,8 +282,31 @@ fn generate_wasm_interface(impls: &[ItemImpl]) -> Result<TokenStream> {
 				}
 			)
 		});
+	let dispatch_calls = generate_default_method_dispatch_calls(impls, &input)?
+		.into_iter()
+		.map(|(name, dispatch_call)| {
+			let fn_name = Ident::new(&name, Span::call_site());
 
-	Ok(quote!( #( #impl_calls )* ))
+			quote!(
+				#[cfg(not(feature = "std"))]
+				#[no_mangle]
+				pub fn #fn_name(input_data: *mut u8, input_len: usize) -> u64 {
+					let mut #input = if input_len == 0 {
+						&[0u8; 0]
+					} else {
+						unsafe {
+							#c::slice::from_raw_parts(input_data, input_len)
+						}
+					};
+
+					let output = { #dispatch_call }
+						.unwrap_or_else(|| panic!("Unknown default method provided to {}", #name));
+					#c::to_substrate_wasm_fn_return_value(&output)
+				}
+			)
+		});
+
+	Ok(quote!( #( #impl_calls )* #( #dispatch_calls )* ))
 }
 
 fn generate_runtime_api_base_structures() -> Result<TokenStream> {
@@ -467,6 +549,65 @@ struct ApiRuntimeImplToApiRuntimeApiImpl<'a> {
 	impl_trait: &'a Ident,
 }
 
+impl<'a> ApiRuntimeImplToApiRuntimeApiImpl<'a> {
+	/// Generates the implementation of the `default_method_dispatch_runtime_api_impl` method that
+	/// calls the default method dispatch of the trait in the runtime.
+	fn generate_default_method_dispatch_impl(&self) -> ImplItem {
+		let runtime_mod_path = self.runtime_mod_path;
+		let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+		let name = generate_default_method_dispatch_impl_name(&self.impl_trait);
+
+		parse_quote!(
+			fn #name(
+				&self,
+				at: &#crate_::BlockId<__SR_API_BLOCK__>,
+				context: #crate_::ExecutionContext,
+				method: &'static str,
+				params_encoded: Vec<u8>,
+			) -> std::result::Result<Vec<u8>, RuntimeApiImplCall::Error> {
+				let args = #crate_::Encode::encode(&(method.as_bytes(), params_encoded));
+
+				self.call_api_at(
+					|
+						call_runtime_at,
+						core_api,
+						changes,
+						storage_transaction_cache,
+						initialized_block,
+						recorder
+					| {
+						#runtime_mod_path default_method_dispatch_call_api_at(
+							call_runtime_at,
+							core_api,
+							at,
+							args,
+							changes,
+							storage_transaction_cache,
+							initialized_block,
+							context,
+							recorder,
+						)
+					}
+				).and_then(|r|
+					match r {
+						#crate_::NativeOrEncoded::Native(n) => Ok(n),
+						#crate_::NativeOrEncoded::Encoded(r) => {
+							<Vec<u8> as #crate_::Decode>::decode(&mut &r[..])
+								.map_err(|err|
+									format!(
+										"Failed to decode result of `{}`: {}",
+										method,
+										err.what(),
+									).into()
+								)
+						}
+					}
+				)
+			}
+		)
+	}
+}
+
 impl<'a> Fold for ApiRuntimeImplToApiRuntimeApiImpl<'a> {
 	fn fold_type_path(&mut self, input: TypePath) -> TypePath {
 		let new_ty_path = if input == *self.runtime_block {
@@ -626,7 +767,13 @@ impl<'a> Fold for ApiRuntimeImplToApiRuntimeApiImpl<'a> {
 		// the feature `std` or `test`.
 		input.attrs.push(parse_quote!( #[cfg(any(feature = "std", test))] ));
 
-		fold::fold_item_impl(self, input)
+		let mut input = fold::fold_item_impl(self, input);
+
+		// Methods with a default implementation that are not implemented by the runtime are
+		// called through the default method dispatch of the trait. We add this after folding the
+		// implementation, as the generated method is already in its final form.
+		input.items.push(self.generate_default_method_dispatch_impl());
+		input
 	}
 }
 
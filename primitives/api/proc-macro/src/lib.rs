@@ -22,6 +22,7 @@ use proc_macro::TokenStream;
 
 mod impl_runtime_apis;
 mod decl_runtime_apis;
+mod mock_impl_runtime_apis;
 mod utils;
 
 #[proc_macro]
@@ -33,3 +34,8 @@ pub fn impl_runtime_apis(input: TokenStream) -> TokenStream {
 pub fn decl_runtime_apis(input: TokenStream) -> TokenStream {
 	decl_runtime_apis::decl_runtime_apis_impl(input)
 }
+
+#[proc_macro]
+pub fn mock_impl_runtime_apis(input: TokenStream) -> TokenStream {
+	mock_impl_runtime_apis::mock_impl_runtime_apis_impl(input)
+}
@@ -0,0 +1,441 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::utils::{
+	generate_crate_access, generate_hidden_includes, generate_method_runtime_api_impl_name,
+	extract_parameter_names_types_and_borrows, return_type_extract_type,
+};
+
+use proc_macro2::{Span, TokenStream};
+
+use quote::quote;
+
+use syn::{
+	spanned::Spanned, parse_macro_input, Ident, Type, ItemImpl, ItemStruct, ItemType, Item, Path,
+	ImplItem, ImplItemMethod, parse::{Parse, ParseStream, Result, Error}, PathArguments,
+	GenericArgument, Fields, parse_quote,
+};
+
+/// Unique identifier used to make the hidden includes unique for this macro.
+const HIDDEN_INCLUDES_ID: &str = "MOCK_IMPL_RUNTIME_APIS";
+
+/// The structure used for parsing the runtime api mocks.
+struct RuntimeApiMocks {
+	/// The declaration of the mock type.
+	mock: ItemStruct,
+	/// The error type of the mocked apis, if given by the user.
+	error: Option<Type>,
+	/// The mocked runtime api implementations.
+	impls: Vec<ItemImpl>,
+}
+
+impl Parse for RuntimeApiMocks {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let mut mock = None;
+		let mut error = None;
+		let mut impls = Vec::new();
+
+		while !input.is_empty() {
+			match input.parse::<Item>()? {
+				Item::Struct(item) if mock.is_none() => mock = Some(item),
+				Item::Type(ItemType { ident, ty, .. }) if ident == "Error" && error.is_none() =>
+					error = Some(*ty),
+				Item::Impl(item) => impls.push(item),
+				item => return Err(Error::new(
+					item.span(),
+					"Expected one `struct`, an optional `type Error = ...;` and `impl` blocks.",
+				)),
+			}
+		}
+
+		let mock = mock.ok_or_else(|| Error::new(Span::call_site(), "No mock struct given!"))?;
+		match mock.fields {
+			Fields::Unit => {},
+			ref fields => return Err(
+				Error::new(fields.span(), "The mock struct has to be a unit struct.")
+			),
+		}
+		if !mock.generics.params.is_empty() {
+			return Err(Error::new(mock.generics.span(), "Generic mock structs are not supported."))
+		}
+
+		if impls.is_empty() {
+			Err(Error::new(Span::call_site(), "No api implementation given!"))
+		} else {
+			Ok(Self { mock, error, impls })
+		}
+	}
+}
+
+/// Extract the trait that is implemented in the given `ItemImpl` and check that it is
+/// implemented for the mock type.
+fn extract_impl_trait<'a>(impl_: &'a ItemImpl, mock: &Ident) -> Result<&'a Path> {
+	match &*impl_.self_ty {
+		Type::Path(p) if p.path.is_ident(mock) => {},
+		ty => return Err(Error::new(ty.span(), format!("Expected the mock type `{}`.", mock))),
+	}
+
+	impl_.trait_.as_ref().map(|v| &v.1).ok_or_else(
+		|| Error::new(impl_.span(), "Only implementation of traits are supported!")
+	)
+}
+
+/// Extracts the `Block` type, the first generic argument of the given trait.
+fn extract_block_type(trait_: &Path) -> Result<&Type> {
+	let segment = trait_.segments.last().ok_or_else(
+		|| Error::new(trait_.span(), "Empty trait path not possible!")
+	)?;
+
+	match &segment.arguments {
+		PathArguments::AngleBracketed(args) => match args.args.first() {
+			Some(GenericArgument::Type(block)) => Ok(block),
+			_ => Err(Error::new(args.span(), "Missing `Block` generic parameter.")),
+		},
+		_ => Err(Error::new(segment.span(), "Missing `Block` generic parameter.")),
+	}
+}
+
+/// Returns the given trait with the `Error` associated type set to `error`.
+fn trait_with_error(trait_: &Path, error: &Type) -> Path {
+	let mut trait_ = trait_.clone();
+
+	if let Some(PathArguments::AngleBracketed(args)) =
+		trait_.segments.last_mut().map(|s| &mut s.arguments)
+	{
+		args.args.push(parse_quote!( Error = #error ));
+	}
+
+	trait_
+}
+
+/// A method of a runtime api that is mocked.
+struct MockedMethod<'a> {
+	/// The name of the trait the method belongs to.
+	trait_: &'a Ident,
+	/// The method as given by the user, its body is used when no closure is programmed.
+	method: &'a ImplItemMethod,
+}
+
+impl<'a> MockedMethod<'a> {
+	/// The bounds of the closure that can be programmed for this method.
+	fn closure_bounds(&self, block: &Type) -> Result<TokenStream> {
+		let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+		let ret_type = return_type_extract_type(&self.method.sig.output);
+		let param_types = extract_parameter_names_types_and_borrows(&self.method.sig)?
+			.into_iter()
+			.map(|(_, ty, borrow)| quote!( #borrow #ty ));
+
+		Ok(quote!(
+			Fn(&#crate_::BlockId<#block> #(, #param_types )*) -> #ret_type + Send + Sync
+		))
+	}
+
+	/// The name of the function that programs the closure of this method.
+	fn setter_name(&self) -> Ident {
+		Ident::new(&format!("with_{}", self.method.sig.ident), Span::call_site())
+	}
+
+	/// Generates the `method_runtime_api_impl` that calls the closure of this method or falls
+	/// back to the body given by the user.
+	fn generate_runtime_api_impl(&self, block: &Type, error: &Type) -> Result<TokenStream> {
+		let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+		let method = &self.method.sig.ident;
+		let name = generate_method_runtime_api_impl_name(self.trait_, method);
+		let ret_type = return_type_extract_type(&self.method.sig.output);
+		let body = &self.method.block;
+
+		let params = extract_parameter_names_types_and_borrows(&self.method.sig)?;
+		let param_names = params.iter().map(|v| &v.0).collect::<Vec<_>>();
+		let param_types = params.iter().map(|(_, ty, borrow)| quote!( #borrow #ty ));
+		let param_pattern = if param_names.len() == 1 {
+			quote!( #( #param_names )* )
+		} else {
+			quote!( ( #( #param_names ),* ) )
+		};
+		let missing_params = format!("`{}` was called without native parameters.", method);
+
+		Ok(quote!(
+			fn #name(
+				&self,
+				at: &#crate_::BlockId<#block>,
+				_: #crate_::ExecutionContext,
+				params: Option<( #( #param_types ),* )>,
+				_: Vec<u8>,
+			) -> std::result::Result<#crate_::NativeOrEncoded<#ret_type>, #error> {
+				let #param_pattern = params.ok_or_else(|| String::from(#missing_params))?;
+
+				let result = match &self.#method {
+					Some(mock) => mock(at #(, #param_names )*),
+					None => #body,
+				};
+
+				Ok(#crate_::NativeOrEncoded::Native(result))
+			}
+		))
+	}
+}
+
+/// Generates the implementation of the `Core` api, if it is not mocked by the user.
+///
+/// The version of the runtime is returned by the `RuntimeVersion` of the mock, all other methods
+/// return an error.
+fn generate_core_impl(mock: &Ident, block: &Type, error: &Type) -> TokenStream {
+	let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+	let not_mocked = |method: &str| format!("`Core::{}` is not mocked by `{}`.", method, mock);
+	let execute_block = not_mocked("execute_block");
+	let initialize_block = not_mocked("initialize_block");
+
+	quote!(
+		impl #crate_::Core<#block> for #mock {
+			fn Core_version_runtime_api_impl(
+				&self,
+				_: &#crate_::BlockId<#block>,
+				_: #crate_::ExecutionContext,
+				_: Option<()>,
+				_: Vec<u8>,
+			) -> std::result::Result<#crate_::NativeOrEncoded<#crate_::RuntimeVersion>, #error> {
+				Ok(#crate_::NativeOrEncoded::Native(self.runtime_version.clone()))
+			}
+
+			fn Core_execute_block_runtime_api_impl(
+				&self,
+				_: &#crate_::BlockId<#block>,
+				_: #crate_::ExecutionContext,
+				_: Option<#block>,
+				_: Vec<u8>,
+			) -> std::result::Result<#crate_::NativeOrEncoded<()>, #error> {
+				Err(String::from(#execute_block).into())
+			}
+
+			fn Core_initialize_block_runtime_api_impl(
+				&self,
+				_: &#crate_::BlockId<#block>,
+				_: #crate_::ExecutionContext,
+				_: Option<&<#block as #crate_::BlockT>::Header>,
+				_: Vec<u8>,
+			) -> std::result::Result<#crate_::NativeOrEncoded<()>, #error> {
+				Err(String::from(#initialize_block).into())
+			}
+		}
+	)
+}
+
+/// Generates the mock type, its builder functions and the implementations of `ApiExt`,
+/// `ApiErrorExt` and `ProvideRuntimeApi`.
+fn generate_mock_structure(
+	mocks: &RuntimeApiMocks,
+	methods: &[MockedMethod],
+	block: &Type,
+	error: &Type,
+) -> Result<TokenStream> {
+	let crate_ = generate_crate_access(HIDDEN_INCLUDES_ID);
+	let mock = &mocks.mock.ident;
+	let attrs = &mocks.mock.attrs;
+	let vis = &mocks.mock.vis;
+
+	let fields = methods.iter().map(|m| &m.method.sig.ident).collect::<Vec<_>>();
+	let closure_bounds = methods.iter()
+		.map(|m| m.closure_bounds(block))
+		.collect::<Result<Vec<_>>>()?;
+
+	let setters = methods.iter().map(|m| m.setter_name());
+	let setter_docs = methods.iter().map(|m| format!(
+		" Program the `{}::{}` method with the given closure.",
+		m.trait_,
+		m.method.sig.ident,
+	));
+
+	let mut apis = mocks.impls.iter()
+		.map(|i| extract_impl_trait(i, mock).map(|t| trait_with_error(t, error)))
+		.collect::<Result<Vec<_>>>()?;
+	let core_mocked = apis.iter().any(|a| a.segments.last().map_or(false, |s| s.ident == "Core"));
+	let core_impl = if core_mocked {
+		quote!()
+	} else {
+		apis.push(parse_quote!( #crate_::Core<#block, Error = #error> ));
+		generate_core_impl(mock, block, error)
+	};
+
+	Ok(quote!(
+		#( #attrs )*
+		#[derive(Clone)]
+		#vis struct #mock {
+			runtime_version: #crate_::RuntimeVersion,
+			#( #fields: Option<std::sync::Arc<dyn #closure_bounds>>, )*
+		}
+
+		impl #mock {
+			/// Create a new mock that uses the given implementation of every method.
+			///
+			/// The runtime version of the mock contains the ids and versions of all mocked apis.
+			pub fn new() -> Self {
+				let apis = vec![
+					#(
+						(
+							<dyn #apis as #crate_::RuntimeApiInfo>::ID,
+							<dyn #apis as #crate_::RuntimeApiInfo>::VERSION,
+						),
+					)*
+				];
+
+				#mock {
+					runtime_version: #crate_::RuntimeVersion {
+						apis: std::borrow::Cow::Owned(apis),
+						..Default::default()
+					},
+					#( #fields: None, )*
+				}
+			}
+
+			/// Set the runtime version returned by the mock.
+			pub fn with_runtime_version(mut self, version: #crate_::RuntimeVersion) -> Self {
+				self.runtime_version = version;
+				self
+			}
+
+			#(
+				#[doc = #setter_docs]
+				pub fn #setters(mut self, mock: impl #closure_bounds + 'static) -> Self {
+					self.#fields = Some(std::sync::Arc::new(mock));
+					self
+				}
+			)*
+		}
+
+		impl Default for #mock {
+			fn default() -> Self {
+				Self::new()
+			}
+		}
+
+		impl #crate_::ApiErrorExt for #mock {
+			type Error = #error;
+		}
+
+		impl #crate_::ApiExt<#block> for #mock {
+			type StateBackend = #crate_::InMemoryBackend<#crate_::HashFor<#block>>;
+
+			fn map_api_result<F: FnOnce(&Self) -> std::result::Result<R, E>, R, E>(
+				&self,
+				map_call: F,
+			) -> std::result::Result<R, E> where Self: Sized {
+				map_call(self)
+			}
+
+			fn runtime_version_at(
+				&self,
+				_: &#crate_::BlockId<#block>,
+			) -> std::result::Result<#crate_::RuntimeVersion, #error> {
+				Ok(self.runtime_version.clone())
+			}
+
+			fn record_proof(&mut self) {}
+
+			fn extract_proof(&mut self) -> Option<#crate_::StorageProof> {
+				None
+			}
+
+			fn into_storage_changes(
+				&self,
+				_: &Self::StateBackend,
+				_: Option<&#crate_::ChangesTrieState<
+					#crate_::HashFor<#block>,
+					#crate_::NumberFor<#block>,
+				>>,
+				_: <#block as #crate_::BlockT>::Hash,
+			) -> std::result::Result<
+				#crate_::StorageChanges<Self::StateBackend, #block>,
+				String
+			> where Self: Sized {
+				Ok(Default::default())
+			}
+		}
+
+		impl #crate_::ProvideRuntimeApi<#block> for #mock {
+			type Api = Self;
+
+			fn runtime_api<'a>(&'a self) -> #crate_::ApiRef<'a, Self::Api> {
+				self.clone().into()
+			}
+		}
+
+		#core_impl
+	))
+}
+
+/// Generates the implementations of the mocked apis for the mock type.
+///
+/// Returns the implementations and all mocked methods.
+fn generate_api_impls_for_mock<'a>(
+	mocks: &'a RuntimeApiMocks,
+	block: &Type,
+	error: &Type,
+) -> Result<(Vec<TokenStream>, Vec<MockedMethod<'a>>)> {
+	let mock = &mocks.mock.ident;
+	let mut api_impls = Vec::new();
+	let mut methods = Vec::new();
+
+	for impl_ in &mocks.impls {
+		let trait_ = extract_impl_trait(impl_, mock)?;
+		let trait_ident = &trait_.segments.last()
+			.ok_or_else(|| Error::new(trait_.span(), "Empty trait path not possible!"))?
+			.ident;
+
+		let mut runtime_api_impls = Vec::new();
+		for item in &impl_.items {
+			let method = match item {
+				ImplItem::Method(method) => MockedMethod { trait_: trait_ident, method },
+				item => return Err(Error::new(item.span(), "Only methods can be mocked.")),
+			};
+
+			runtime_api_impls.push(method.generate_runtime_api_impl(block, error)?);
+			methods.push(method);
+		}
+
+		api_impls.push(quote!(
+			impl #trait_ for #mock {
+				#( #runtime_api_impls )*
+			}
+		));
+	}
+
+	Ok((api_impls, methods))
+}
+
+/// The entry point of the `mock_impl_runtime_apis!` macro.
+pub fn mock_impl_runtime_apis_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+	let mocks = parse_macro_input!(input as RuntimeApiMocks);
+
+	mock_impl_runtime_apis_impl_inner(&mocks).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn mock_impl_runtime_apis_impl_inner(mocks: &RuntimeApiMocks) -> Result<TokenStream> {
+	let hidden_includes = generate_hidden_includes(HIDDEN_INCLUDES_ID);
+	let block = extract_block_type(extract_impl_trait(&mocks.impls[0], &mocks.mock.ident)?)?;
+	let error = mocks.error.clone().unwrap_or_else(|| parse_quote!( String ));
+	let (api_impls, methods) = generate_api_impls_for_mock(mocks, block, &error)?;
+	let mock_structure = generate_mock_structure(mocks, &methods, block, &error)?;
+
+	Ok(
+		quote!(
+			#hidden_includes
+
+			#mock_structure
+
+			#( #api_impls )*
+		)
+	)
+}
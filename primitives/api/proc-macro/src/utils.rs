@@ -173,6 +173,21 @@ pub fn prefix_function_with_trait<F: ToString>(trait_: &Ident, function: &F) ->
 	format!("{}_{}", trait_.to_string(), function.to_string())
 }
 
+/// Generates the name of the runtime function that dispatches calls to the default methods of
+/// the given trait.
+pub fn generate_default_method_dispatch_name(trait_: &Ident) -> String {
+	prefix_function_with_trait(trait_, &"default_method_dispatch")
+}
+
+/// Generates the name of the client side method that calls the default method dispatch of the
+/// given trait.
+pub fn generate_default_method_dispatch_impl_name(trait_: &Ident) -> Ident {
+	Ident::new(
+		&format!("{}_runtime_api_impl", generate_default_method_dispatch_name(trait_)),
+		Span::call_site(),
+	)
+}
+
 /// Extract all types that appear in signatures in the given `ImplItem`'s.
 ///
 /// If a type is a reference, the inner type is extracted (without the reference).
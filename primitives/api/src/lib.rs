@@ -36,7 +36,7 @@ extern crate self as sp_api;
 #[doc(hidden)]
 #[cfg(feature = "std")]
 pub use sp_state_machine::{
-	OverlayedChanges, StorageProof, Backend as StateBackend, ChangesTrieState,
+	OverlayedChanges, StorageProof, Backend as StateBackend, ChangesTrieState, InMemoryBackend,
 };
 #[doc(hidden)]
 #[cfg(feature = "std")]
@@ -60,7 +60,7 @@ pub use sp_core::{offchain, ExecutionContext};
 #[doc(hidden)]
 pub use sp_version::{ApiId, RuntimeVersion, ApisVec, create_apis_vec};
 #[doc(hidden)]
-pub use sp_std::{slice, mem};
+pub use sp_std::{slice, mem, vec};
 #[cfg(feature = "std")]
 use sp_std::result;
 #[doc(hidden)]
@@ -139,6 +139,28 @@ use std::{panic::UnwindSafe, cell::RefCell};
 /// To check if a given runtime implements a runtime api trait, the `RuntimeVersion` has the
 /// function `has_api<A>()`. Also the `ApiExt` provides a function `has_api<A>(at: &BlockId)` to
 /// check if the runtime at the given block id implements the requested runtime api trait.
+///
+/// # Default method implementations
+///
+/// A method can provide a default implementation. Such a method does not need to be implemented
+/// by `impl_runtime_apis!`, which makes it possible to add new methods to an api trait without
+/// breaking the existing runtime implementations. The default implementation is executed in the
+/// runtime, like any other method.
+///
+/// ```rust
+/// sp_api::decl_runtime_apis! {
+///     pub trait Balance {
+///         /// Get the balance.
+///         fn get_balance() -> u64;
+///         /// Get the balance in units of `unit`, the runtime is free to omit this method.
+///         fn get_balance_in(unit: u64) -> u64 {
+///             Self::get_balance() / unit
+///         }
+///     }
+/// }
+///
+/// # fn main() {}
+/// ```
 pub use sp_api_proc_macro::decl_runtime_apis;
 
 /// Tags given trait implementations as runtime apis.
@@ -226,6 +248,61 @@ pub use sp_api_proc_macro::decl_runtime_apis;
 /// ```
 pub use sp_api_proc_macro::impl_runtime_apis;
 
+/// Mocks given runtime apis for testing client side code without a runtime.
+///
+/// The macro expects the declaration of a unit struct, the mock type, followed by
+/// implementations of runtime apis for this mock type. The methods are implemented like in
+/// `impl_runtime_apis!` and their bodies are used as the default behavior of the mock. Optionally,
+/// `type Error = ...;` sets the error type of the apis, by default it is `String`.
+///
+/// The macro turns the unit struct into a mock that implements all given apis, the [`Core`] api
+/// (unless it is mocked as well), [`ApiExt`] and [`ProvideRuntimeApi`]. For each mocked method
+/// `METHOD`, the mock provides a builder function `with_METHOD` that programs the method with a
+/// closure. The closure is called with the `BlockId` and the parameters of the method, instead of
+/// the default behavior. The runtime version of the mock contains all mocked apis and can be
+/// changed with `with_runtime_version`.
+///
+/// As with `impl_runtime_apis!`, the macro can be called at maximum one time per module and the
+/// mocked methods need to have unique names.
+///
+/// # Example
+///
+/// ```rust
+/// # use sp_test_primitives::Block;
+/// # use sp_runtime::generic::BlockId;
+/// # use sp_api::ProvideRuntimeApi;
+/// #
+/// # sp_api::decl_runtime_apis! {
+/// #     pub trait Balance {
+/// #         fn get_balance() -> u64;
+/// #         fn set_balance(val: u64);
+/// #     }
+/// # }
+/// #
+/// sp_api::mock_impl_runtime_apis! {
+///     /// A client that only provides the `Balance` api.
+///     pub struct MockClient;
+///
+///     impl Balance<Block> for MockClient {
+///         fn get_balance() -> u64 {
+///             1
+///         }
+///         fn set_balance(_val: u64) {
+///             unimplemented!("Not required for testing!")
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// let at = BlockId::Number(0);
+/// assert_eq!(MockClient::new().runtime_api().get_balance(&at), Ok(1));
+///
+/// let client = MockClient::new().with_get_balance(|_at| 10);
+/// assert_eq!(client.runtime_api().get_balance(&at), Ok(10));
+/// # }
+/// ```
+pub use sp_api_proc_macro::mock_impl_runtime_apis;
+
 /// A type that records all accessed trie nodes and generates a proof out of it.
 #[cfg(feature = "std")]
 pub type ProofRecorder<B> = sp_state_machine::ProofRecorder<HashFor<B>>;
@@ -309,6 +386,36 @@ pub trait ApiExt<Block: BlockT>: ApiErrorExt {
 	/// Returns the runtime version at the given block id.
 	fn runtime_version_at(&self, at: &BlockId<Block>) -> Result<RuntimeVersion, Self::Error>;
 
+	/// Returns the ids and versions of all apis implemented by the runtime at the given block id.
+	fn runtime_api_versions(
+		&self,
+		at: &BlockId<Block>,
+	) -> Result<Vec<(ApiId, u32)>, Self::Error> where Self: Sized {
+		self.runtime_version_at(at).map(|v| v.apis.into_owned())
+	}
+
+	/// Checks that the given api is implemented with at least `min_version` at the given
+	/// block id.
+	///
+	/// Returns a handle that dereferences to the api and holds the implemented version, or an
+	/// error describing why the api can not be used.
+	fn require_api<A: RuntimeApiInfo + ?Sized>(
+		&self,
+		at: &BlockId<Block>,
+		min_version: u32,
+	) -> Result<VersionedApi<Self, A>, ApiVersionError<Self::Error>> where Self: Sized {
+		let runtime_version = self.runtime_version_at(at).map_err(ApiVersionError::Runtime)?;
+		match runtime_version.api_version(&A::ID) {
+			None => Err(ApiVersionError::NotImplemented { api: A::NAME }),
+			Some(found) if found < min_version => Err(ApiVersionError::VersionTooLow {
+				api: A::NAME,
+				found,
+				required: min_version,
+			}),
+			Some(version) => Ok(VersionedApi { api: self, version, _marker: Default::default() }),
+		}
+	}
+
 	/// Start recording all accessed trie nodes for generating proofs.
 	fn record_proof(&mut self);
 
@@ -446,6 +553,75 @@ pub trait ProvideRuntimeApi<Block: BlockT> {
 	fn runtime_api<'a>(&'a self) -> ApiRef<'a, Self::Api>;
 }
 
+/// A runtime api instance whose api `A` is known to be implemented, see
+/// [`ApiExt::require_api`].
+#[cfg(feature = "std")]
+pub struct VersionedApi<'a, Api, A: ?Sized> {
+	api: &'a Api,
+	version: u32,
+	_marker: std::marker::PhantomData<fn(&A)>,
+}
+
+#[cfg(feature = "std")]
+impl<'a, Api, A: ?Sized> VersionedApi<'a, Api, A> {
+	/// The version of the api implemented by the runtime.
+	pub fn api_version(&self) -> u32 {
+		self.version
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'a, Api, A: ?Sized> std::ops::Deref for VersionedApi<'a, Api, A> {
+	type Target = Api;
+
+	fn deref(&self) -> &Self::Target {
+		self.api
+	}
+}
+
+/// The reason why a runtime api can not be used, see [`ApiExt::require_api`].
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ApiVersionError<E> {
+	/// The runtime does not implement the api.
+	NotImplemented {
+		/// The name of the api.
+		api: &'static str,
+	},
+	/// The runtime implements a version of the api lower than required.
+	VersionTooLow {
+		/// The name of the api.
+		api: &'static str,
+		/// The version implemented by the runtime.
+		found: u32,
+		/// The minimal required version.
+		required: u32,
+	},
+	/// Retrieving the runtime version failed.
+	Runtime(E),
+}
+
+#[cfg(feature = "std")]
+impl<E: std::fmt::Debug> std::fmt::Display for ApiVersionError<E> {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			ApiVersionError::NotImplemented { api } =>
+				write!(f, "The runtime does not implement the `{}` api", api),
+			ApiVersionError::VersionTooLow { api, found, required } => write!(
+				f,
+				"The runtime implements version {} of the `{}` api, but at least version {} is required",
+				found,
+				api,
+				required,
+			),
+			ApiVersionError::Runtime(e) => write!(f, "Failed to retrieve the runtime version: {:?}", e),
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl<E: std::fmt::Debug> std::error::Error for ApiVersionError<E> {}
+
 /// Something that provides information about a runtime api.
 #[cfg(feature = "std")]
 pub trait RuntimeApiInfo {
@@ -453,6 +629,8 @@ pub trait RuntimeApiInfo {
 	const ID: [u8; 8];
 	/// The version of the runtime api.
 	const VERSION: u32;
+	/// The name of the runtime api.
+	const NAME: &'static str;
 }
 
 /// Extracts the `Api::Error` for a type that provides a runtime api.
@@ -14,10 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use sp_api::{RuntimeApiInfo, decl_runtime_apis, impl_runtime_apis};
+use sp_api::{
+	RuntimeApiInfo, decl_runtime_apis, impl_runtime_apis, mock_impl_runtime_apis, ApiExt,
+	ProvideRuntimeApi,
+};
 
 use sp_runtime::{traits::{GetNodeBlockType, Block as BlockT}, generic::BlockId};
 
+use codec::{Encode, Decode};
+
 use substrate_test_runtime_client::runtime::Block;
 use sp_blockchain::Result;
 
@@ -43,6 +48,16 @@ decl_runtime_apis! {
 		#[changed_in(2)]
 		fn same_name() -> String;
 	}
+
+	pub trait ApiWithDefaultMethods {
+		fn required() -> u32;
+		fn omitted(value: u32) -> u32 {
+			value + 1
+		}
+		fn overridden() -> u32 {
+			0
+		}
+	}
 }
 
 impl_runtime_apis! {
@@ -68,6 +83,16 @@ impl_runtime_apis! {
 		fn same_name() {}
 	}
 
+	impl self::ApiWithDefaultMethods<Block> for Runtime {
+		fn required() -> u32 {
+			1
+		}
+
+		fn overridden() -> u32 {
+			5
+		}
+	}
+
 	impl sp_api::Core<Block> for Runtime {
 		fn version() -> sp_version::RuntimeVersion {
 			unimplemented!()
@@ -81,6 +106,20 @@ impl_runtime_apis! {
 	}
 }
 
+mock_impl_runtime_apis! {
+	struct MockApi;
+
+	impl self::ApiWithDefaultMethods<Block> for MockApi {
+		fn required() -> u32 {
+			1
+		}
+
+		fn omitted(value: u32) -> u32 {
+			value * 2
+		}
+	}
+}
+
 type TestClient = substrate_test_runtime_client::sc_client::Client<
 	substrate_test_runtime_client::Backend,
 	substrate_test_runtime_client::Executor,
@@ -119,6 +158,43 @@ fn check_runtime_api_info() {
 	assert_eq!(ApiWithCustomVersion::<Block, Error = ()>::VERSION, 2);
 }
 
+#[test]
+fn default_method_dispatch_works() {
+	let dispatch = |method: &[u8], params: Vec<u8>| {
+		api::dispatch(
+			"ApiWithDefaultMethods_default_method_dispatch",
+			&(method, params).encode(),
+		).map(|output| Vec::<u8>::decode(&mut &output[..]).unwrap())
+	};
+
+	assert_eq!(dispatch(b"omitted", 2u32.encode()), Some(3u32.encode()));
+	assert_eq!(dispatch(b"overridden", Vec::new()), Some(5u32.encode()));
+	assert_eq!(dispatch(b"required", Vec::new()), None);
+
+	let _omitted: fn(&RuntimeApiImpl<Block, TestClient>, &BlockId<Block>, u32) -> Result<u32> =
+		RuntimeApiImpl::<Block, TestClient>::omitted;
+}
+
+#[test]
+fn mock_runtime_api_works() {
+	let at = BlockId::Number(0);
+
+	let mock = MockApi::new();
+	assert_eq!(mock.runtime_api().required(&at), Ok(1));
+	assert_eq!(mock.runtime_api().omitted(&at, 2), Ok(4));
+	assert!(mock.runtime_api().overridden(&at).is_err());
+	assert_eq!(
+		mock.runtime_api().has_api::<dyn ApiWithDefaultMethods<Block, Error = String>>(&at),
+		Ok(true),
+	);
+
+	let mock = MockApi::new()
+		.with_required(|_| 5)
+		.with_omitted(|_, value| value + 10);
+	assert_eq!(mock.runtime_api().required(&at), Ok(5));
+	assert_eq!(mock.runtime_api().omitted(&at, 2), Ok(12));
+}
+
 fn check_runtime_api_versions_contains<T: RuntimeApiInfo + ?Sized>() {
 	assert!(RUNTIME_API_VERSIONS.iter().any(|v| v == &(T::ID, T::VERSION)));
 }
@@ -127,5 +203,6 @@ fn check_runtime_api_versions_contains<T: RuntimeApiInfo + ?Sized>() {
 fn check_runtime_api_versions() {
 	check_runtime_api_versions_contains::<dyn Api<Block, Error = ()>>();
 	check_runtime_api_versions_contains::<dyn ApiWithCustomVersion<Block, Error = ()>>();
+	check_runtime_api_versions_contains::<dyn ApiWithDefaultMethods<Block, Error = ()>>();
 	check_runtime_api_versions_contains::<dyn sp_api::Core<Block, Error = ()>>();
 }
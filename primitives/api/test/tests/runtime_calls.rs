@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-use sp_api::ProvideRuntimeApi;
+use sp_api::{ProvideRuntimeApi, ApiExt, ApiVersionError, Core, RuntimeApiInfo};
 use substrate_test_runtime_client::{
 	prelude::*,
 	DefaultTestClientBuilderExt, TestClientBuilder,
@@ -122,6 +122,39 @@ fn calling_with_native_else_wasm_and_fail_on_native_should_work() {
 	assert_eq!(runtime_api.fail_on_native(&block_id).unwrap(), 1);
 }
 
+sp_api::decl_runtime_apis! {
+	pub trait NotImplementedApi {
+		fn not_implemented();
+	}
+}
+
+#[test]
+fn require_api_works() {
+	let client = TestClientBuilder::new().build();
+	let runtime_api = client.runtime_api();
+	let block_id = BlockId::Number(client.chain_info().best_number);
+
+	let versions = runtime_api.runtime_api_versions(&block_id).unwrap();
+	assert!(versions.contains(&(<dyn Core<Block, Error = ()>>::ID, 2)));
+
+	let core = runtime_api.require_api::<dyn Core<Block, Error = ()>>(&block_id, 2).unwrap();
+	assert_eq!(core.api_version(), 2);
+	assert!(core.version(&block_id).is_ok());
+
+	match runtime_api.require_api::<dyn Core<Block, Error = ()>>(&block_id, 3) {
+		Err(ApiVersionError::VersionTooLow { api: "Core", found: 2, required: 3 }) => {},
+		_ => panic!("Core version 3 is not implemented"),
+	}
+
+	match runtime_api.require_api::<dyn NotImplementedApi<Block, Error = ()>>(&block_id, 1) {
+		Err(e @ ApiVersionError::NotImplemented { api: "NotImplementedApi" }) => assert_eq!(
+			e.to_string(),
+			"The runtime does not implement the `NotImplementedApi` api",
+		),
+		_ => panic!("NotImplementedApi is not implemented"),
+	}
+}
+
 #[test]
 fn use_trie_function() {
 	let client = TestClientBuilder::new().set_execution_strategy(ExecutionStrategy::AlwaysWasm).build();
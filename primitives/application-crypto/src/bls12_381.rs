@@ -0,0 +1,84 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! BLS12-381 crypto types.
+
+use crate::{RuntimePublic, KeyTypeId};
+
+use sp_std::vec::Vec;
+
+pub use sp_core::bls12_381::*;
+
+mod app {
+	use sp_core::testing::BLS12_381;
+	crate::app_crypto!(super, BLS12_381);
+
+	impl crate::traits::BoundToRuntimeAppPublic for Public {
+		type Public = Self;
+	}
+}
+
+pub use app::{Public as AppPublic, Signature as AppSignature};
+#[cfg(feature = "full_crypto")]
+pub use app::Pair as AppPair;
+
+impl RuntimePublic for Public {
+	type Signature = Signature;
+
+	fn all(key_type: KeyTypeId) -> crate::Vec<Self> {
+		sp_io::crypto::bls12_381_public_keys(key_type)
+	}
+
+	fn generate_pair(key_type: KeyTypeId, seed: Option<Vec<u8>>) -> Self {
+		sp_io::crypto::bls12_381_generate(key_type, seed)
+	}
+
+	fn sign<M: AsRef<[u8]>>(&self, key_type: KeyTypeId, msg: &M) -> Option<Self::Signature> {
+		sp_io::crypto::bls12_381_sign(key_type, self, msg.as_ref())
+	}
+
+	fn verify<M: AsRef<[u8]>>(&self, msg: &M, signature: &Self::Signature) -> bool {
+		sp_io::crypto::bls12_381_verify(&signature, msg.as_ref(), self)
+	}
+
+	fn to_raw_vec(&self) -> Vec<u8> {
+		sp_core::crypto::Public::to_raw_vec(self)
+	}
+}
+
+/// Aggregate `signatures` into a single signature from within the runtime.
+///
+/// Returns `None` if `signatures` is empty or contains an invalid signature.
+pub fn runtime_aggregate(signatures: Vec<Signature>) -> Option<Signature> {
+	sp_io::crypto::bls12_381_aggregate(signatures)
+}
+
+/// Verify an aggregated `signature` of the same `msg` by all of `publics` from within the runtime.
+///
+/// Every public key must have been registered with a proof of possession checked by
+/// `runtime_verify_proof_of_possession`, otherwise the verification is open to rogue key attacks.
+pub fn runtime_fast_aggregate_verify<M: AsRef<[u8]>>(
+	signature: &Signature,
+	msg: &M,
+	publics: Vec<Public>,
+) -> bool {
+	sp_io::crypto::bls12_381_fast_aggregate_verify(signature, msg.as_ref(), publics)
+}
+
+/// Verify a proof of possession of the secret key of `public` from within the runtime.
+pub fn runtime_verify_proof_of_possession(proof: &Signature, public: &Public) -> bool {
+	sp_io::crypto::bls12_381_verify_proof_of_possession(proof, public)
+}
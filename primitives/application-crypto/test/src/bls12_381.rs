@@ -0,0 +1,42 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Integration tests for bls12-381
+
+use sp_runtime::generic::BlockId;
+use sp_core::{testing::{KeyStore, BLS12_381}, crypto::Pair, bls12_381};
+use substrate_test_runtime_client::{
+	TestClientBuilder, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	runtime::TestAPI,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_application_crypto::bls12_381::{AppPair, AppPublic};
+
+#[test]
+fn bls12_381_works_in_runtime() {
+	let keystore = KeyStore::new();
+	let test_client = TestClientBuilder::new().set_keystore(keystore.clone()).build();
+	let (signature, publics) = test_client.runtime_api()
+		.test_bls12_381_crypto(&BlockId::Number(0))
+		.expect("Tests `bls12_381` crypto.");
+
+	let key_pair = keystore.read().bls12_381_key_pair(BLS12_381, &publics[0].as_ref())
+		.expect("There should be at a `bls12_381` key in the keystore for the given public key.");
+	let publics = publics.into_iter().map(Into::into).collect::<Vec<bls12_381::Public>>();
+
+	assert!(!AppPair::verify(&signature, "bls12_381", &AppPublic::from(key_pair.public())));
+	assert!(bls12_381::fast_aggregate_verify(&signature.into(), b"bls12_381", &publics));
+}
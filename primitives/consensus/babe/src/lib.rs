@@ -28,8 +28,10 @@ pub use sp_consensus_vrf::schnorrkel::{
 
 use codec::{Encode, Decode};
 use sp_std::vec::Vec;
-use sp_runtime::{ConsensusEngineId, RuntimeDebug};
-use crate::digests::NextEpochDescriptor;
+use sp_runtime::{
+	ConsensusEngineId, DigestItem, RuntimeDebug, traits::{Header as HeaderT, NumberFor},
+};
+use crate::digests::{NextEpochDescriptor, RawPreDigest};
 
 mod app {
 	use sp_application_crypto::{app_crypto, key_types::BABE, sr25519};
@@ -51,6 +53,9 @@ pub type AuthoritySignature = app::Signature;
 /// the main Babe module. If that ever changes, then this must, too.
 pub type AuthorityId = app::Public;
 
+/// The key type of BABE authority keys.
+pub const KEY_TYPE: sp_application_crypto::KeyTypeId = sp_application_crypto::key_types::BABE;
+
 /// The `ConsensusEngineId` of BABE.
 pub const BABE_ENGINE_ID: ConsensusEngineId = *b"BABE";
 
@@ -129,6 +134,84 @@ impl sp_consensus::SlotData for BabeConfiguration {
 	const SLOT_KEY: &'static [u8] = b"babe_configuration";
 }
 
+/// Represents an equivocation proof. An equivocation happens when a validator produces more
+/// than one block on the same slot. The proof of equivocation consists of the two sealed
+/// headers, which both carry a BABE pre-digest of the slot and a seal of the offender.
+#[derive(Clone, Decode, Encode, PartialEq, Eq, RuntimeDebug)]
+pub struct EquivocationProof<Header> {
+	/// The authority id of the equivocator.
+	pub offender: AuthorityId,
+	/// The slot at which the equivocation happened.
+	pub slot_number: SlotNumber,
+	/// The first header involved in the equivocation.
+	pub first_header: Header,
+	/// The second header involved in the equivocation.
+	pub second_header: Header,
+}
+
+/// Verifies the equivocation proof by making sure that both headers are different, that both
+/// were authored in the slot of the proof and that both are sealed by the offender.
+pub fn check_equivocation_proof<H: HeaderT>(proof: EquivocationProof<H>) -> bool {
+	use sp_application_crypto::RuntimeAppPublic;
+
+	let find_pre_digest = |header: &H| {
+		header.digest().logs().iter().find_map(|log| match log {
+			DigestItem::PreRuntime(BABE_ENGINE_ID, data) =>
+				<RawPreDigest>::decode(&mut &data[..]).ok(),
+			_ => None,
+		})
+	};
+
+	let verify_seal = |mut header: H| {
+		let seal = match header.digest_mut().pop() {
+			Some(DigestItem::Seal(BABE_ENGINE_ID, seal)) => seal,
+			_ => return false,
+		};
+		let signature = match AuthoritySignature::decode(&mut &seal[..]) {
+			Ok(signature) => signature,
+			Err(_) => return false,
+		};
+		let pre_hash = header.hash();
+
+		proof.offender.verify(&pre_hash, &signature)
+	};
+
+	if proof.first_header.hash() == proof.second_header.hash() {
+		return false;
+	}
+
+	let slots = (
+		find_pre_digest(&proof.first_header).map(|digest| digest.slot_number()),
+		find_pre_digest(&proof.second_header).map(|digest| digest.slot_number()),
+	);
+	if slots != (Some(proof.slot_number), Some(proof.slot_number)) {
+		return false;
+	}
+
+	verify_seal(proof.first_header.clone()) && verify_seal(proof.second_header.clone())
+}
+
+/// An opaque type used to represent the key ownership proof at the runtime API boundary. The
+/// inner value is an encoded representation of the actual key ownership proof which will be
+/// parameterized when defining the runtime. At the runtime API boundary this type is unknown
+/// and as such we keep this opaque representation, implementors of the runtime API will have to
+/// make sure that all usages of `OpaqueKeyOwnershipProof` refer to the same type.
+#[derive(Decode, Encode, PartialEq, RuntimeDebug)]
+pub struct OpaqueKeyOwnershipProof(Vec<u8>);
+
+impl OpaqueKeyOwnershipProof {
+	/// Create a new `OpaqueKeyOwnershipProof` using the given encoded representation.
+	pub fn new(inner: Vec<u8>) -> OpaqueKeyOwnershipProof {
+		OpaqueKeyOwnershipProof(inner)
+	}
+
+	/// Try to decode this `OpaqueKeyOwnershipProof` into the given concrete key ownership proof
+	/// type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		Decode::decode(&mut &self.0[..]).ok()
+	}
+}
+
 sp_api::decl_runtime_apis! {
 	/// API necessary for block authorship with BABE.
 	pub trait BabeApi {
@@ -140,5 +223,41 @@ sp_api::decl_runtime_apis! {
 
 		/// Returns the slot number that started the current epoch.
 		fn current_epoch_start() -> SlotNumber;
+
+		/// Returns the number of the first block authored with BABE if BABE took
+		/// over block production from another consensus engine on a running chain,
+		/// or `None` if the chain is authored with BABE since genesis.
+		///
+		/// The configuration of a bootstrapped chain is read at the parent of
+		/// that block, which plays the part of the genesis block.
+		fn bootstrap_block() -> Option<NumberFor<Block>> {
+			None
+		}
+
+		/// Submits an unsigned extrinsic to report an equivocation. The caller must provide the
+		/// equivocation proof and a key ownership proof (should be obtained using
+		/// `generate_key_ownership_proof`). The extrinsic will be unsigned and should only be
+		/// accepted for local authorship (not to be broadcast to the network). This method
+		/// returns `None` when creation of the extrinsic fails, e.g. if equivocation reporting
+		/// is disabled for the given runtime (i.e. this method is not implemented).
+		fn submit_report_equivocation_unsigned_extrinsic(
+			_equivocation_proof: EquivocationProof<Block::Header>,
+			_key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> Option<()> {
+			None
+		}
+
+		/// Generates a proof of key ownership for the given authority in the epoch of the given
+		/// slot. An example usage of this module is coupled with the session historical module to
+		/// prove that a given authority key is tied to a given staking identity during a specific
+		/// session. Proofs of key ownership are necessary for submitting equivocation reports.
+		/// Returns `None` if the runtime doesn't support key ownership proofs or the authority
+		/// isn't part of the epoch.
+		fn generate_key_ownership_proof(
+			_slot_number: SlotNumber,
+			_authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof> {
+			None
+		}
 	}
 }
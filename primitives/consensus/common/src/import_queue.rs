@@ -90,6 +90,17 @@ pub trait Verifier<B: BlockT>: Send + Sync {
 	) -> Result<(BlockImportParams<B, ()>, Option<Vec<(CacheKeyId, Vec<u8>)>>), String>;
 }
 
+/// Verification of a queued block that doesn't depend on the state of its parent, e.g. the
+/// signatures of its extrinsics.
+///
+/// Pre-verification runs in a pool of workers, concurrently with the serial import of the
+/// previous blocks, so it must not decide the validity of the block, which is left to the import.
+/// It can however prepare the import, e.g. by caching the signatures found valid.
+pub trait PreVerifier<B: BlockT>: Send + Sync {
+	/// Pre-verify `block`, received from `origin`.
+	fn pre_verify(&self, origin: &BlockOrigin, block: &IncomingBlock<B>);
+}
+
 /// Blocks import queue API.
 ///
 /// The `import_*` methods can be called in order to send elements for the import queue to verify.
@@ -205,6 +216,8 @@ pub fn import_single_block<B: BlockT, V: Verifier<B>, Transaction>(
 	let number = header.number().clone();
 	let hash = header.hash();
 	let parent_hash = header.parent_hash().clone();
+	let _block_context = sp_tracing::log_context::enter_block(number, hash);
+	let _peer_context = peer.as_ref().map(sp_tracing::log_context::enter_peer);
 
 	let import_error = |e| {
 		match e {
@@ -15,15 +15,15 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{mem, pin::Pin, time::Duration, marker::PhantomData, sync::Arc};
-use futures::{prelude::*, channel::mpsc, task::Context, task::Poll};
+use futures::{prelude::*, channel::{mpsc, oneshot}, task::Context, task::Poll};
 use futures_timer::Delay;
 use parking_lot::{Mutex, Condvar};
 use sp_runtime::{Justification, traits::{Block as BlockT, Header as HeaderT, NumberFor}};
 
 use crate::block_import::BlockOrigin;
 use crate::import_queue::{
-	BlockImportResult, BlockImportError, Verifier, BoxBlockImport, BoxFinalityProofImport,
-	BoxJustificationImport, ImportQueue, Link, Origin,
+	BlockImportResult, BlockImportError, Verifier, PreVerifier, BoxBlockImport,
+	BoxFinalityProofImport, BoxJustificationImport, ImportQueue, Link, Origin,
 	IncomingBlock, import_single_block,
 	buffered_link::{self, BufferedLinkSender, BufferedLinkReceiver}
 };
@@ -42,6 +42,8 @@ pub struct BasicQueue<B: BlockT, Transaction> {
 	/// A thread pool where the background worker is being run.
 	pool: Option<futures::executor::ThreadPool>,
 	pool_guard: Arc<(Mutex<usize>, Condvar)>,
+	/// The pre-verifier of the queued blocks and the pool of workers it is run on, if any.
+	pre_verification: Option<(Arc<dyn PreVerifier<B>>, futures::executor::ThreadPool)>,
 	_phantom: PhantomData<Transaction>,
 }
 
@@ -116,9 +118,28 @@ impl<B: BlockT, Transaction: Send + 'static> BasicQueue<B, Transaction> {
 			manual_poll,
 			pool,
 			pool_guard: guard,
+			pre_verification: None,
 			_phantom: PhantomData,
 		}
 	}
+
+	/// Pre-verify the queued blocks with `pre_verifier` on a pool of `workers` threads.
+	///
+	/// Blocks are pre-verified as soon as they are queued, while the previous blocks are
+	/// imported. The import of a block waits for the end of its pre-verification.
+	pub fn with_pre_verifier(mut self, pre_verifier: Arc<dyn PreVerifier<B>>, workers: usize) -> Self {
+		let pool = futures::executor::ThreadPool::builder()
+			.name_prefix("import-queue-pre-verifier-")
+			.pool_size(workers.max(1))
+			.create();
+
+		match pool {
+			Ok(pool) => self.pre_verification = Some((pre_verifier, pool)),
+			Err(e) => warn!(target: "sync", "Failed to start the block pre-verification workers: {:?}", e),
+		}
+
+		self
+	}
 }
 
 impl<B: BlockT, Transaction: Send> ImportQueue<B> for BasicQueue<B, Transaction> {
@@ -128,6 +149,12 @@ impl<B: BlockT, Transaction: Send> ImportQueue<B> for BasicQueue<B, Transaction>
 		}
 
 		trace!(target: "sync", "Scheduling {} blocks for import", blocks.len());
+		let blocks = match &self.pre_verification {
+			Some((pre_verifier, pool)) => blocks.into_iter()
+				.map(|block| QueuedBlock::pre_verify(pre_verifier, pool, origin.clone(), block))
+				.collect(),
+			None => blocks.into_iter().map(QueuedBlock::Ready).collect(),
+		};
 		let _ = self.sender.unbounded_send(ToWorkerMsg::ImportBlocks(origin, blocks));
 	}
 
@@ -172,10 +199,62 @@ impl<B: BlockT, Transaction: Send> ImportQueue<B> for BasicQueue<B, Transaction>
 	}
 }
 
+/// A block queued for import.
+#[derive(Debug)]
+enum QueuedBlock<B: BlockT> {
+	/// The block is ready to be imported.
+	Ready(IncomingBlock<B>),
+	/// The block with the given hash and number is being pre-verified, it is sent back once done.
+	PreVerifying(B::Hash, Option<NumberFor<B>>, oneshot::Receiver<IncomingBlock<B>>),
+}
+
+impl<B: BlockT> QueuedBlock<B> {
+	/// Spawn the pre-verification of `block` on `pool`.
+	fn pre_verify(
+		pre_verifier: &Arc<dyn PreVerifier<B>>,
+		pool: &futures::executor::ThreadPool,
+		origin: BlockOrigin,
+		block: IncomingBlock<B>,
+	) -> Self {
+		let (sender, receiver) = oneshot::channel();
+		let hash = block.hash;
+		let number = block.header.as_ref().map(|h| *h.number());
+		let pre_verifier = pre_verifier.clone();
+
+		pool.spawn_ok(async move {
+			// The block is sent back even if the pre-verification panics, it is then left to the
+			// import to verify it.
+			let pre_verified = std::panic::catch_unwind(std::panic::AssertUnwindSafe(
+				|| pre_verifier.pre_verify(&origin, &block)
+			));
+			if pre_verified.is_err() {
+				warn!(target: "sync", "Pre-verification of block {:?} panicked", block.hash);
+			}
+			let _ = sender.send(block);
+		});
+
+		QueuedBlock::PreVerifying(hash, number, receiver)
+	}
+
+	fn hash(&self) -> B::Hash {
+		match self {
+			QueuedBlock::Ready(block) => block.hash,
+			QueuedBlock::PreVerifying(hash, _, _) => *hash,
+		}
+	}
+
+	fn number(&self) -> Option<NumberFor<B>> {
+		match self {
+			QueuedBlock::Ready(block) => block.header.as_ref().map(|h| *h.number()),
+			QueuedBlock::PreVerifying(_, number, _) => *number,
+		}
+	}
+}
+
 /// Message destinated to the background worker.
 #[derive(Debug)]
 enum ToWorkerMsg<B: BlockT> {
-	ImportBlocks(BlockOrigin, Vec<IncomingBlock<B>>),
+	ImportBlocks(BlockOrigin, Vec<QueuedBlock<B>>),
 	ImportJustification(Origin, B::Hash, NumberFor<B>, Justification),
 	ImportFinalityProof(Origin, B::Hash, NumberFor<B>, Vec<u8>),
 }
@@ -290,7 +369,7 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 		block_import: BoxBlockImport<B, Transaction>,
 		verifier: V,
 		origin: BlockOrigin,
-		blocks: Vec<IncomingBlock<B>>
+		blocks: Vec<QueuedBlock<B>>
 	) -> impl Future<Output = (BoxBlockImport<B, Transaction>, V)> {
 		let mut result_sender = self.result_sender.clone();
 
@@ -358,11 +437,12 @@ impl<B: BlockT, Transaction: Send> BlockImportWorker<B, Transaction> {
 /// back in the output once the import is finished.
 ///
 /// The returned `Future` yields at every imported block, which makes the execution more
-/// fine-grained and making it possible to interrupt the process.
+/// fine-grained and making it possible to interrupt the process. It also waits for the end of the
+/// pre-verification of the next block, if any.
 fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	import_handle: BoxBlockImport<B, Transaction>,
 	blocks_origin: BlockOrigin,
-	blocks: Vec<IncomingBlock<B>>,
+	blocks: Vec<QueuedBlock<B>>,
 	verifier: V,
 	delay_between_blocks: Duration,
 ) -> impl Future<
@@ -378,8 +458,8 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	let count = blocks.len();
 
 	let blocks_range = match (
-		blocks.first().and_then(|b| b.header.as_ref().map(|h| h.number())),
-		blocks.last().and_then(|b| b.header.as_ref().map(|h| h.number())),
+		blocks.first().and_then(|b| b.number()),
+		blocks.last().and_then(|b| b.number()),
 	) {
 		(Some(first), Some(last)) if first != last => format!(" ({}..{})", first, last),
 		(Some(first), Some(_)) => format!(" ({})", first),
@@ -394,6 +474,7 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 	let mut blocks = blocks.into_iter();
 	let mut import_handle = Some(import_handle);
 	let mut waiting = None;
+	let mut pre_verifying = None;
 	let mut verifier = Some(verifier);
 
 	// Blocks in the response/drain should be in ascending order.
@@ -409,7 +490,7 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 		waiting = None;
 
 		// Is there any block left to import?
-		let block = match blocks.next() {
+		let block = match pre_verifying.take().or_else(|| blocks.next()) {
 			Some(b) => b,
 			None => {
 				// No block left to import, success!
@@ -430,11 +511,30 @@ fn import_many_blocks<B: BlockT, V: Verifier<B>, Transaction>(
 		let verifier = verifier.as_mut()
 			.expect("Future polled again after it has finished");
 
-		let block_number = block.header.as_ref().map(|h| h.number().clone());
-		let block_hash = block.hash;
+		let block_number = block.number();
+		let block_hash = block.hash();
 		let import_result = if has_error {
 			Err(BlockImportError::Cancelled)
 		} else {
+			// Wait for the end of the pre-verification of the block.
+			let block = match block {
+				QueuedBlock::Ready(block) => block,
+				QueuedBlock::PreVerifying(hash, number, mut receiver) =>
+					match Future::poll(Pin::new(&mut receiver), cx) {
+						Poll::Ready(Ok(block)) => block,
+						Poll::Ready(Err(_)) => {
+							results.push((Err(BlockImportError::Cancelled), hash));
+							has_error = true;
+							cx.waker().wake_by_ref();
+							return Poll::Pending
+						},
+						Poll::Pending => {
+							pre_verifying = Some(QueuedBlock::PreVerifying(hash, number, receiver));
+							return Poll::Pending
+						},
+					},
+			};
+
 			// The actual import.
 			import_single_block(
 				&mut **import_handle,
@@ -0,0 +1,49 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Primitives for the proof-of-authority consensus of private plug networks.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::vec::Vec;
+use sp_runtime::ConsensusEngineId;
+
+mod app {
+	use sp_application_crypto::{app_crypto, key_types::POA, sr25519};
+	app_crypto!(sr25519, POA);
+}
+
+sp_application_crypto::with_pair! {
+	/// A proof-of-authority author keypair using S/R 25519 as its crypto.
+	pub type AuthorityPair = app::Pair;
+}
+
+/// A proof-of-authority author signature using S/R 25519 as its crypto.
+pub type AuthoritySignature = app::Signature;
+
+/// A proof-of-authority author identifier using S/R 25519 as its crypto.
+pub type AuthorityId = app::Public;
+
+/// The `ConsensusEngineId` of the proof-of-authority engine.
+pub const POA_ENGINE_ID: ConsensusEngineId = *b"poa_";
+
+sp_api::decl_runtime_apis! {
+	/// API necessary for block authorship with the proof-of-authority engine.
+	pub trait PoaApi {
+		/// Return the keys of the block authors, in the order they author slots.
+		fn authorities() -> Vec<AuthorityId>;
+	}
+}
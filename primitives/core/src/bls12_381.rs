@@ -0,0 +1,696 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+// tag::description[]
+//! Simple BLS12-381 API.
+//!
+//! Public keys are 48 byte compressed G1 points and signatures 96 byte compressed G2 points, using
+//! the `BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_` ciphersuite. Signatures of the same message
+//! can be aggregated into a single signature, verified against all the public keys at once.
+//!
+//! Aggregated verification is only safe against rogue key attacks when every public key has been
+//! registered with a proof of possession, see `Pair::proof_of_possession`.
+// end::description[]
+
+#[cfg(feature = "full_crypto")]
+use sp_std::vec::Vec;
+
+use codec::{Encode, Decode};
+
+#[cfg(feature = "full_crypto")]
+use blake2_rfc;
+#[cfg(feature = "std")]
+use substrate_bip39::seed_from_entropy;
+#[cfg(feature = "std")]
+use bip39::{Mnemonic, Language, MnemonicType};
+#[cfg(feature = "full_crypto")]
+use crate::crypto::{Pair as TraitPair, DeriveJunction, SecretStringError};
+#[cfg(feature = "std")]
+use crate::crypto::Ss58Codec;
+#[cfg(feature = "std")]
+use serde::{de, Serializer, Serialize, Deserializer, Deserialize};
+use crate::crypto::{Public as TraitPublic, UncheckedFrom, CryptoType, Derive};
+use sp_runtime_interface::pass_by::PassByInner;
+use sp_std::ops::Deref;
+
+/// Domain separation tag of signatures.
+#[cfg(feature = "full_crypto")]
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// Domain separation tag of proofs of possession.
+#[cfg(feature = "full_crypto")]
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+/// The length of a public key.
+pub const PUBLIC_LENGTH: usize = 48;
+
+/// The length of a signature.
+pub const SIGNATURE_LENGTH: usize = 96;
+
+/// A secret seed, the input keying material of the secret key, kept for HDKD.
+#[cfg(feature = "full_crypto")]
+type Seed = [u8; 32];
+
+/// A public key.
+#[derive(Clone, Copy, Encode, Decode, PassByInner)]
+pub struct Public(pub [u8; PUBLIC_LENGTH]);
+
+/// A key pair.
+#[cfg(feature = "full_crypto")]
+#[derive(Clone)]
+pub struct Pair {
+	secret: blst::min_pk::SecretKey,
+	public: Public,
+	seed: Seed,
+}
+
+impl Default for Public {
+	fn default() -> Self {
+		Public([0u8; PUBLIC_LENGTH])
+	}
+}
+
+impl PartialEq for Public {
+	fn eq(&self, b: &Self) -> bool {
+		self.0[..] == b.0[..]
+	}
+}
+
+impl Eq for Public {}
+
+impl PartialOrd for Public {
+	fn partial_cmp(&self, b: &Self) -> Option<sp_std::cmp::Ordering> {
+		Some(self.cmp(b))
+	}
+}
+
+impl Ord for Public {
+	fn cmp(&self, b: &Self) -> sp_std::cmp::Ordering {
+		self.0[..].cmp(&b.0[..])
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl sp_std::hash::Hash for Public {
+	fn hash<H: sp_std::hash::Hasher>(&self, state: &mut H) {
+		sp_std::hash::Hash::hash(&self.0[..], state);
+	}
+}
+
+impl AsRef<[u8; PUBLIC_LENGTH]> for Public {
+	fn as_ref(&self) -> &[u8; PUBLIC_LENGTH] {
+		&self.0
+	}
+}
+
+impl AsRef<[u8]> for Public {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Public {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl Deref for Public {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl sp_std::convert::TryFrom<&[u8]> for Public {
+	type Error = ();
+
+	fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+		if data.len() == PUBLIC_LENGTH {
+			let mut inner = [0u8; PUBLIC_LENGTH];
+			inner.copy_from_slice(data);
+			Ok(Public(inner))
+		} else {
+			Err(())
+		}
+	}
+}
+
+impl From<Public> for [u8; PUBLIC_LENGTH] {
+	fn from(x: Public) -> Self {
+		x.0
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl From<Pair> for Public {
+	fn from(x: Pair) -> Self {
+		x.public()
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::str::FromStr for Public {
+	type Err = crate::crypto::PublicError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Self::from_ss58check(s)
+	}
+}
+
+impl UncheckedFrom<[u8; PUBLIC_LENGTH]> for Public {
+	fn unchecked_from(x: [u8; PUBLIC_LENGTH]) -> Self {
+		Public::from_raw(x)
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for Public {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.to_ss58check())
+	}
+}
+
+impl sp_std::fmt::Debug for Public {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		let s = self.to_ss58check();
+		write!(f, "{} ({}...)", crate::hexdisplay::HexDisplay::from(&&self.0[..]), &s[0..8])
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Public {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&self.to_ss58check())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Public {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		Public::from_ss58check(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))
+	}
+}
+
+/// A signature (a 768-bit value).
+#[derive(Encode, Decode, PassByInner)]
+pub struct Signature(pub [u8; SIGNATURE_LENGTH]);
+
+impl sp_std::convert::TryFrom<&[u8]> for Signature {
+	type Error = ();
+
+	fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+		if data.len() == SIGNATURE_LENGTH {
+			let mut inner = [0u8; SIGNATURE_LENGTH];
+			inner.copy_from_slice(data);
+			Ok(Signature(inner))
+		} else {
+			Err(())
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Signature {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+		serializer.serialize_str(&hex::encode(self))
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Signature {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+		let signature_hex = hex::decode(&String::deserialize(deserializer)?)
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))?;
+		Ok(Signature::try_from(signature_hex.as_ref())
+			.map_err(|e| de::Error::custom(format!("{:?}", e)))?)
+	}
+}
+
+impl Clone for Signature {
+	fn clone(&self) -> Self {
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		r.copy_from_slice(&self.0[..]);
+		Signature(r)
+	}
+}
+
+impl Default for Signature {
+	fn default() -> Self {
+		Signature([0u8; SIGNATURE_LENGTH])
+	}
+}
+
+impl PartialEq for Signature {
+	fn eq(&self, b: &Self) -> bool {
+		self.0[..] == b.0[..]
+	}
+}
+
+impl Eq for Signature {}
+
+impl From<Signature> for [u8; SIGNATURE_LENGTH] {
+	fn from(v: Signature) -> [u8; SIGNATURE_LENGTH] {
+		v.0
+	}
+}
+
+impl AsRef<[u8; SIGNATURE_LENGTH]> for Signature {
+	fn as_ref(&self) -> &[u8; SIGNATURE_LENGTH] {
+		&self.0
+	}
+}
+
+impl AsRef<[u8]> for Signature {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for Signature {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl sp_std::fmt::Debug for Signature {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "{}", crate::hexdisplay::HexDisplay::from(&&self.0[..]))
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl sp_std::hash::Hash for Signature {
+	fn hash<H: sp_std::hash::Hasher>(&self, state: &mut H) {
+		sp_std::hash::Hash::hash(&self.0[..], state);
+	}
+}
+
+impl Signature {
+	/// A new instance from the given 96-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_raw(data: [u8; SIGNATURE_LENGTH]) -> Signature {
+		Signature(data)
+	}
+
+	/// A new instance from the given slice that should be 96 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real signature. Only use it if
+	/// you are certain that the array actually is a signature. GIGO!
+	pub fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; SIGNATURE_LENGTH];
+		r.copy_from_slice(data);
+		Signature(r)
+	}
+}
+
+impl Public {
+	/// A new instance from the given 48-byte `data`.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	pub fn from_raw(data: [u8; PUBLIC_LENGTH]) -> Self {
+		Public(data)
+	}
+
+	/// Return a slice filled with raw data.
+	pub fn as_array_ref(&self) -> &[u8; PUBLIC_LENGTH] {
+		self.as_ref()
+	}
+}
+
+impl TraitPublic for Public {
+	/// A new instance from the given slice that should be 48 bytes long.
+	///
+	/// NOTE: No checking goes on to ensure this is a real public key. Only use it if
+	/// you are certain that the array actually is a pubkey. GIGO!
+	fn from_slice(data: &[u8]) -> Self {
+		let mut r = [0u8; PUBLIC_LENGTH];
+		r.copy_from_slice(data);
+		Public(r)
+	}
+}
+
+impl Derive for Public {}
+
+/// Derive a single hard junction.
+#[cfg(feature = "full_crypto")]
+fn derive_hard_junction(secret_seed: &Seed, cc: &[u8; 32]) -> Seed {
+	("BLS12381HDKD", secret_seed, cc).using_encoded(|data| {
+		let mut res = [0u8; 32];
+		res.copy_from_slice(blake2_rfc::blake2b::blake2b(32, &[], data).as_bytes());
+		res
+	})
+}
+
+/// Decode a public key, checking it is a valid point of G1.
+#[cfg(feature = "full_crypto")]
+fn decode_public(public: &[u8]) -> Option<blst::min_pk::PublicKey> {
+	blst::min_pk::PublicKey::key_validate(public).ok()
+}
+
+/// Decode a signature, checking it is a valid point of G2.
+#[cfg(feature = "full_crypto")]
+fn decode_signature(signature: &[u8]) -> Option<blst::min_pk::Signature> {
+	let signature = blst::min_pk::Signature::from_bytes(signature).ok()?;
+	if signature.subgroup_check() {
+		Some(signature)
+	} else {
+		None
+	}
+}
+
+/// An error when deriving a key.
+#[cfg(feature = "full_crypto")]
+pub enum DeriveError {
+	/// A soft key was found in the path (and is unsupported).
+	SoftKeyInPath,
+}
+
+#[cfg(feature = "full_crypto")]
+impl TraitPair for Pair {
+	type Public = Public;
+	type Seed = Seed;
+	type Signature = Signature;
+	type DeriveError = DeriveError;
+
+	/// Generate new secure (random) key pair and provide the recovery phrase.
+	///
+	/// You can recover the same key later with `from_phrase`.
+	#[cfg(feature = "std")]
+	fn generate_with_phrase(password: Option<&str>) -> (Pair, String, Seed) {
+		let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
+		let phrase = mnemonic.phrase();
+		let (pair, seed) = Self::from_phrase(phrase, password)
+			.expect("All phrases generated by Mnemonic are valid; qed");
+		(
+			pair,
+			phrase.to_owned(),
+			seed,
+		)
+	}
+
+	/// Generate key pair from given recovery phrase and password.
+	#[cfg(feature = "std")]
+	fn from_phrase(phrase: &str, password: Option<&str>) -> Result<(Pair, Seed), SecretStringError> {
+		let big_seed = seed_from_entropy(
+			Mnemonic::from_phrase(phrase, Language::English)
+				.map_err(|_| SecretStringError::InvalidPhrase)?.entropy(),
+			password.unwrap_or(""),
+		).map_err(|_| SecretStringError::InvalidSeed)?;
+		let mut seed = Seed::default();
+		seed.copy_from_slice(&big_seed[0..32]);
+		Self::from_seed_slice(&big_seed[0..32]).map(|x| (x, seed))
+	}
+
+	/// Make a new key pair from secret seed material.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed(seed: &Seed) -> Pair {
+		Self::from_seed_slice(&seed[..]).expect("seed has valid length; qed")
+	}
+
+	/// Make a new key pair from secret seed material. The slice must be 32 bytes long or it
+	/// will return `None`.
+	///
+	/// The secret key is derived from the seed with the `KeyGen` procedure of the BLS signature
+	/// standard.
+	///
+	/// You should never need to use this; generate(), generate_with_phrase
+	fn from_seed_slice(seed_slice: &[u8]) -> Result<Pair, SecretStringError> {
+		if seed_slice.len() != 32 {
+			return Err(SecretStringError::InvalidSeedLength)
+		}
+		let mut seed = Seed::default();
+		seed.copy_from_slice(seed_slice);
+
+		let secret = blst::min_pk::SecretKey::key_gen(&seed, &[])
+			.map_err(|_| SecretStringError::InvalidSeed)?;
+		let public = Public(secret.sk_to_pk().to_bytes());
+		Ok(Pair { secret, public, seed })
+	}
+
+	/// Derive a child key from a series of given junctions.
+	fn derive<Iter: Iterator<Item=DeriveJunction>>(&self,
+		path: Iter,
+		_seed: Option<Seed>,
+	) -> Result<(Pair, Option<Seed>), DeriveError> {
+		let mut acc = self.seed;
+		for j in path {
+			match j {
+				DeriveJunction::Soft(_cc) => return Err(DeriveError::SoftKeyInPath),
+				DeriveJunction::Hard(cc) => acc = derive_hard_junction(&acc, &cc),
+			}
+		}
+		Ok((Self::from_seed(&acc), Some(acc)))
+	}
+
+	/// Get the public key.
+	fn public(&self) -> Public {
+		self.public
+	}
+
+	/// Sign a message.
+	fn sign(&self, message: &[u8]) -> Signature {
+		Signature(self.secret.sign(message, SIGNATURE_DST, &[]).to_bytes())
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	fn verify<M: AsRef<[u8]>>(sig: &Self::Signature, message: M, pubkey: &Self::Public) -> bool {
+		Self::verify_weak(&sig.0[..], message.as_ref(), pubkey)
+	}
+
+	/// Verify a signature on a message. Returns true if the signature is good.
+	///
+	/// This doesn't use the type system to ensure that `sig` and `pubkey` are the correct
+	/// size. Use it only if you're coming from byte buffers and need the speed.
+	fn verify_weak<P: AsRef<[u8]>, M: AsRef<[u8]>>(sig: &[u8], message: M, pubkey: P) -> bool {
+		let public_key = match decode_public(pubkey.as_ref()) {
+			Some(pk) => pk,
+			None => return false,
+		};
+		let sig = match decode_signature(sig) {
+			Some(s) => s,
+			None => return false,
+		};
+
+		sig.verify(false, message.as_ref(), SIGNATURE_DST, &[], &public_key, false)
+			== blst::BLST_ERROR::BLST_SUCCESS
+	}
+
+	/// Return a vec filled with raw data.
+	fn to_raw_vec(&self) -> Vec<u8> {
+		self.seed().to_vec()
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl Pair {
+	/// Get the seed for this key.
+	pub fn seed(&self) -> &Seed {
+		&self.seed
+	}
+
+	/// Prove the possession of the secret key, by signing the public key with the proof of
+	/// possession domain separation tag.
+	///
+	/// A public key must only be accepted for aggregated verification with a valid proof.
+	pub fn proof_of_possession(&self) -> Signature {
+		Signature(self.secret.sign(&self.public.0, POP_DST, &[]).to_bytes())
+	}
+}
+
+/// Verify a proof of possession of the secret key of `public`, see `Pair::proof_of_possession`.
+#[cfg(feature = "full_crypto")]
+pub fn verify_proof_of_possession(proof: &Signature, public: &Public) -> bool {
+	match (decode_signature(&proof.0), decode_public(&public.0)) {
+		(Some(proof), Some(key)) =>
+			proof.verify(false, &public.0, POP_DST, &[], &key, false) == blst::BLST_ERROR::BLST_SUCCESS,
+		_ => false,
+	}
+}
+
+/// Aggregate `signatures` into a single signature.
+///
+/// Returns `None` if `signatures` is empty or a signature is not a valid point of G2.
+#[cfg(feature = "full_crypto")]
+pub fn aggregate(signatures: &[Signature]) -> Option<Signature> {
+	let signatures = signatures.iter()
+		.map(|signature| decode_signature(&signature.0))
+		.collect::<Option<Vec<_>>>()?;
+	let signatures = signatures.iter().collect::<Vec<_>>();
+
+	blst::min_pk::AggregateSignature::aggregate(&signatures, false)
+		.ok()
+		.map(|aggregate| Signature(aggregate.to_signature().to_bytes()))
+}
+
+/// Verify an aggregated `signature` of the same `message` by all of `publics`.
+///
+/// The possession of every public key must have been proven beforehand, see
+/// `Pair::proof_of_possession`.
+#[cfg(feature = "full_crypto")]
+pub fn fast_aggregate_verify(signature: &Signature, message: &[u8], publics: &[Public]) -> bool {
+	let publics = match publics.iter().map(|public| decode_public(&public.0)).collect::<Option<Vec<_>>>() {
+		Some(publics) if !publics.is_empty() => publics,
+		_ => return false,
+	};
+	let signature = match decode_signature(&signature.0) {
+		Some(signature) => signature,
+		None => return false,
+	};
+
+	let publics = publics.iter().collect::<Vec<_>>();
+	signature.fast_aggregate_verify(false, message, SIGNATURE_DST, &publics)
+		== blst::BLST_ERROR::BLST_SUCCESS
+}
+
+impl CryptoType for Public {
+	#[cfg(feature = "full_crypto")]
+	type Pair = Pair;
+}
+
+impl CryptoType for Signature {
+	#[cfg(feature = "full_crypto")]
+	type Pair = Pair;
+}
+
+#[cfg(feature = "full_crypto")]
+impl CryptoType for Pair {
+	type Pair = Pair;
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::crypto::DEV_PHRASE;
+	use serde_json;
+
+	#[test]
+	fn default_phrase_should_be_used() {
+		assert_eq!(
+			Pair::from_string("//Alice///password", None).unwrap().public(),
+			Pair::from_string(&format!("{}//Alice", DEV_PHRASE), Some("password")).unwrap().public(),
+		);
+	}
+
+	#[test]
+	fn seed_and_derive_should_work() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		assert_eq!(pair.seed(), b"12345678901234567890123456789012");
+
+		let path = vec![DeriveJunction::Hard([0u8; 32])];
+		let derived = pair.derive(path.clone().into_iter(), None).ok().unwrap().0;
+		assert_ne!(derived.public(), pair.public());
+		assert_eq!(derived.public(), pair.derive(path.into_iter(), None).ok().unwrap().0.public());
+		assert!(pair.derive(vec![DeriveJunction::Soft([0u8; 32])].into_iter(), None).is_err());
+	}
+
+	#[test]
+	fn generated_pair_should_work() {
+		let (pair, _) = Pair::generate();
+		let public = pair.public();
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		assert!(Pair::verify(&signature, &message[..], &public));
+		assert!(!Pair::verify(&signature, b"Something else", &public));
+		assert!(!Pair::verify(&signature, &message[..], &Pair::generate().0.public()));
+		assert!(!Pair::verify(&Signature::default(), &message[..], &public));
+	}
+
+	#[test]
+	fn generate_with_phrase_recovery_possible() {
+		let (pair1, phrase, _) = Pair::generate_with_phrase(None);
+		let (pair2, _) = Pair::from_phrase(&phrase, None).unwrap();
+
+		assert_eq!(pair1.public(), pair2.public());
+	}
+
+	#[test]
+	fn password_does_something() {
+		let (pair1, phrase, _) = Pair::generate_with_phrase(Some("password"));
+		let (pair2, _) = Pair::from_phrase(&phrase, None).unwrap();
+
+		assert_ne!(pair1.public(), pair2.public());
+	}
+
+	#[test]
+	fn ss58check_roundtrip_works() {
+		let public = Pair::from_seed(b"12345678901234567890123456789012").public();
+		let cmp = Public::from_ss58check(&public.to_ss58check()).unwrap();
+		assert_eq!(cmp, public);
+	}
+
+	#[test]
+	fn signature_serialization_works() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let message = b"Something important";
+		let signature = pair.sign(&message[..]);
+		let serialized_signature = serde_json::to_string(&signature).unwrap();
+		// Signature is 96 bytes, so 192 chars + 2 quote chars
+		assert_eq!(serialized_signature.len(), 194);
+		let signature = serde_json::from_str(&serialized_signature).unwrap();
+		assert!(Pair::verify(&signature, &message[..], &pair.public()));
+	}
+
+	#[test]
+	fn proof_of_possession_should_work() {
+		let pair = Pair::from_string("//Alice", None).unwrap();
+		let proof = pair.proof_of_possession();
+
+		assert!(verify_proof_of_possession(&proof, &pair.public()));
+		assert!(!verify_proof_of_possession(&proof, &Pair::from_string("//Bob", None).unwrap().public()));
+		// A proof of possession is not a signature of the public key.
+		assert!(!Pair::verify(&proof, &pair.public().0[..], &pair.public()));
+	}
+
+	#[test]
+	fn aggregated_signature_should_work() {
+		let pairs = ["//Alice", "//Bob", "//Charlie"].iter()
+			.map(|suri| Pair::from_string(suri, None).unwrap())
+			.collect::<Vec<_>>();
+		let publics = pairs.iter().map(|pair| pair.public()).collect::<Vec<_>>();
+		let message = b"finalized block";
+
+		let signatures = pairs.iter().map(|pair| pair.sign(message)).collect::<Vec<_>>();
+		let aggregate = aggregate(&signatures).unwrap();
+
+		assert!(fast_aggregate_verify(&aggregate, message, &publics));
+		assert!(!fast_aggregate_verify(&aggregate, b"other block", &publics));
+		assert!(!fast_aggregate_verify(&aggregate, message, &publics[..2]));
+		assert!(!fast_aggregate_verify(&aggregate, message, &[]));
+
+		let partial = super::aggregate(&signatures[..2]).unwrap();
+		assert!(fast_aggregate_verify(&partial, message, &publics[..2]));
+		assert!(super::aggregate(&[]).is_none());
+	}
+}
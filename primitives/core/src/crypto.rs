@@ -640,6 +640,130 @@ impl<'de> serde::Deserialize<'de> for AccountId32 {
 	}
 }
 
+/// A 20-byte Ethereum compatible account identifier: the last 20 bytes of the keccak-256 hash of
+/// an uncompressed secp256k1 public key.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Default, Encode, Decode)]
+#[cfg_attr(feature = "std", derive(Hash))]
+pub struct AccountId20([u8; 20]);
+
+impl AccountId20 {
+	/// The account of an uncompressed secp256k1 public key, without its `0x04` prefix.
+	#[cfg(feature = "full_crypto")]
+	pub fn from_uncompressed(public: &[u8; 64]) -> Self {
+		let mut r = [0u8; 20];
+		r.copy_from_slice(&crate::hashing::keccak_256(public)[12..]);
+		AccountId20(r)
+	}
+
+	/// The EIP-55 mixed case checksum encoding of the account, with a `0x` prefix.
+	#[cfg(feature = "std")]
+	pub fn to_checksum_hex(&self) -> String {
+		let hex = hex::encode(&self.0);
+		let hash = crate::hashing::keccak_256(hex.as_bytes());
+		let checksummed: String = hex.chars().enumerate().map(|(i, c)| {
+			let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+			if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+		}).collect();
+		format!("0x{}", checksummed)
+	}
+}
+
+impl AsRef<[u8]> for AccountId20 {
+	fn as_ref(&self) -> &[u8] {
+		&self.0[..]
+	}
+}
+
+impl AsMut<[u8]> for AccountId20 {
+	fn as_mut(&mut self) -> &mut [u8] {
+		&mut self.0[..]
+	}
+}
+
+impl AsRef<[u8; 20]> for AccountId20 {
+	fn as_ref(&self) -> &[u8; 20] {
+		&self.0
+	}
+}
+
+impl From<[u8; 20]> for AccountId20 {
+	fn from(x: [u8; 20]) -> AccountId20 {
+		AccountId20(x)
+	}
+}
+
+impl From<AccountId20> for [u8; 20] {
+	fn from(x: AccountId20) -> [u8; 20] {
+		x.0
+	}
+}
+
+impl<'a> sp_std::convert::TryFrom<&'a [u8]> for AccountId20 {
+	type Error = ();
+	fn try_from(x: &'a [u8]) -> Result<AccountId20, ()> {
+		if x.len() == 20 {
+			let mut r = AccountId20::default();
+			r.0.copy_from_slice(x);
+			Ok(r)
+		} else {
+			Err(())
+		}
+	}
+}
+
+#[cfg(feature = "full_crypto")]
+impl sp_std::convert::TryFrom<crate::ecdsa::Public> for AccountId20 {
+	type Error = ();
+	fn try_from(k: crate::ecdsa::Public) -> Result<AccountId20, ()> {
+		k.to_uncompressed().map(|full| AccountId20::from_uncompressed(&full))
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::str::FromStr for AccountId20 {
+	type Err = &'static str;
+
+	/// Parse the hex of an account, with or without a `0x` prefix. The checksum isn't verified.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = if s.starts_with("0x") { &s[2..] } else { s };
+		let bytes = hex::decode(s).map_err(|_| "invalid hex")?;
+		sp_std::convert::TryFrom::try_from(&bytes[..]).map_err(|_| "invalid length")
+	}
+}
+
+#[cfg(feature = "std")]
+impl std::fmt::Display for AccountId20 {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		write!(f, "{}", self.to_checksum_hex())
+	}
+}
+
+impl sp_std::fmt::Debug for AccountId20 {
+	#[cfg(feature = "std")]
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		write!(f, "{}", self.to_checksum_hex())
+	}
+
+	#[cfg(not(feature = "std"))]
+	fn fmt(&self, _: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl serde::Serialize for AccountId20 {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: serde::Serializer {
+		serializer.serialize_str(&self.to_checksum_hex())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> serde::Deserialize<'de> for AccountId20 {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: serde::Deserializer<'de> {
+		String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+	}
+}
+
 #[cfg(feature = "std")]
 pub use self::dummy::*;
 
@@ -952,6 +1076,11 @@ pub mod key_types {
 	pub const IM_ONLINE: KeyTypeId = KeyTypeId(*b"imon");
 	/// Key type for AuthorityDiscovery module, built-in.
 	pub const AUTHORITY_DISCOVERY: KeyTypeId = KeyTypeId(*b"audi");
+	/// Key type for the authors of the proof-of-authority consensus, built-in.
+	pub const POA: KeyTypeId = KeyTypeId(*b"poa_");
+	/// Key type for the operators signing the membership statements of the nodes of a private
+	/// network, built-in.
+	pub const NETWORK_MEMBERSHIP: KeyTypeId = KeyTypeId(*b"memb");
 	/// A key type ID useful for tests.
 	pub const DUMMY: KeyTypeId = KeyTypeId(*b"dumy");
 }
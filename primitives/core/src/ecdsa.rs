@@ -103,6 +103,15 @@ impl Public {
 			.map(Self)
 			.map_err(|_| ())
 	}
+
+	/// The full public key, without its `0x04` prefix, e.g. to derive an Ethereum address.
+	#[cfg(feature = "full_crypto")]
+	pub fn to_uncompressed(&self) -> Result<[u8; 64], ()> {
+		let full = secp256k1::PublicKey::parse_compressed(&self.0).map_err(|_| ())?.serialize();
+		let mut r = [0u8; 64];
+		r.copy_from_slice(&full[1..]);
+		Ok(r)
+	}
 }
 
 impl TraitPublic for Public {
@@ -489,6 +498,13 @@ impl Pair {
 		self.secret.serialize()
 	}
 
+	/// Sign a 32-byte message hash, e.g. the keccak-256 hash signed by Ethereum accounts, instead
+	/// of the blake2-256 hash of a message.
+	pub fn sign_prehashed(&self, message: &[u8; 32]) -> Signature {
+		let message = secp256k1::Message::parse(message);
+		secp256k1::sign(&message, &self.secret).into()
+	}
+
 	/// Exactly as `from_string` except that if no matches are found then, the the first 32
 	/// characters are taken (padded with spaces as necessary) and used as the MiniSecretKey.
 	#[cfg(feature = "std")]
@@ -667,4 +683,15 @@ mod test {
 		// Poorly-sized
 		assert!(deserialize_signature("\"abc123\"").is_err());
 	}
+
+	#[test]
+	fn ethereum_address_is_derived_from_public_key() {
+		use crate::crypto::AccountId20;
+
+		let mut seed = [0u8; 32];
+		seed[31] = 1;
+		let account = AccountId20::try_from(Pair::from_seed(&seed).public()).unwrap();
+		assert_eq!(account.to_string(), "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf");
+		assert_eq!("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf".parse(), Ok(account));
+	}
 }
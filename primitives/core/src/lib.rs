@@ -60,6 +60,7 @@ pub mod u32_trait;
 pub mod ed25519;
 pub mod sr25519;
 pub mod ecdsa;
+pub mod bls12_381;
 pub mod hash;
 #[cfg(feature = "std")]
 mod hasher;
@@ -110,8 +111,14 @@ impl ExecutionContext {
 		match self {
 			Importing | Syncing | BlockConstruction =>
 				offchain::Capabilities::none(),
-			// Enable keystore by default for offchain calls. CC @bkchr
-			OffchainCall(None) => [offchain::Capability::Keystore][..].into(),
+			// Enable keystore, transaction pool and reading the offchain index by default for
+			// offchain calls, e.g. for runtime api calls proving key ownership in past sessions
+			// and submitting equivocation reports.
+			OffchainCall(None) => [
+				offchain::Capability::Keystore,
+				offchain::Capability::TransactionPool,
+				offchain::Capability::OffchainWorkerDbRead,
+			][..].into(),
 			OffchainCall(Some((_, capabilities))) => *capabilities,
 		}
 	}
@@ -483,6 +483,21 @@ pub trait Externalities: Send {
 		deadline: Option<Timestamp>
 	) -> Result<usize, HttpError>;
 
+	/// Fetches the content of the given CID from the IPFS gateway of the node.
+	///
+	/// Returns an error if:
+	/// - The CID isn't valid.
+	/// - The deadline is reached.
+	/// - The node has no IPFS gateway, or the gateway failed to serve the content.
+	fn ipfs_get(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError>;
+
+	/// Pins the content of the given CID on the IPFS node of the node, keeping it available.
+	///
+	/// Returns an error if:
+	/// - The CID isn't valid.
+	/// - The deadline is reached.
+	/// - The node has no IPFS node, or the IPFS node failed to pin the content.
+	fn ipfs_pin(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<(), HttpError>;
 }
 impl<T: Externalities + ?Sized> Externalities for Box<T> {
 	fn is_validator(&self) -> bool {
@@ -556,6 +571,14 @@ impl<T: Externalities + ?Sized> Externalities for Box<T> {
 	) -> Result<usize, HttpError> {
 		(&mut **self).http_response_read_body(request_id, buffer, deadline)
 	}
+
+	fn ipfs_get(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		(&mut **self).ipfs_get(cid, deadline)
+	}
+
+	fn ipfs_pin(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		(&mut **self).ipfs_pin(cid, deadline)
+	}
 }
 /// An `OffchainExternalities` implementation with limited capabilities.
 pub struct LimitedExternalities<T> {
@@ -668,6 +691,16 @@ impl<T: Externalities> Externalities for LimitedExternalities<T> {
 		self.check(Capability::Http, "http_response_read_body");
 		self.externalities.http_response_read_body(request_id, buffer, deadline)
 	}
+
+	fn ipfs_get(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		self.check(Capability::Http, "ipfs_get");
+		self.externalities.ipfs_get(cid, deadline)
+	}
+
+	fn ipfs_pin(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		self.check(Capability::Http, "ipfs_pin");
+		self.externalities.ipfs_pin(cid, deadline)
+	}
 }
 
 #[cfg(feature = "std")]
@@ -74,6 +74,10 @@ pub struct OffchainState {
 	pub local_storage: InMemOffchainStorage,
 	/// Current timestamp (unix millis)
 	pub timestamp: u64,
+	/// The content served by IPFS, by CID.
+	pub ipfs: BTreeMap<String, Vec<u8>>,
+	/// The CIDs pinned on IPFS.
+	pub ipfs_pinned: Vec<String>,
 }
 
 impl OffchainState {
@@ -303,6 +307,19 @@ impl offchain::Externalities for TestOffchainExt {
 			Err(HttpError::IoError)
 		}
 	}
+
+	fn ipfs_get(&mut self, cid: &str, _deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		self.0.read().ipfs.get(cid).cloned().ok_or(HttpError::IoError)
+	}
+
+	fn ipfs_pin(&mut self, cid: &str, _deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		let mut state = self.0.write();
+		if !state.ipfs.contains_key(cid) {
+			return Err(HttpError::IoError);
+		}
+		state.ipfs_pinned.push(cid.to_owned());
+		Ok(())
+	}
 }
 
 /// The internal state of the fake transaction pool.
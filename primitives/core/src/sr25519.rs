@@ -48,12 +48,16 @@ use sp_std::ops::Deref;
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 #[cfg(feature = "full_crypto")]
 use schnorrkel::keys::{MINI_SECRET_KEY_LENGTH, SECRET_KEY_LENGTH};
-use sp_runtime_interface::pass_by::PassByInner;
+use sp_runtime_interface::pass_by::{PassByCodec, PassByInner};
 
 // signing context
 #[cfg(feature = "full_crypto")]
 const SIGNING_CTX: &[u8] = b"substrate";
 
+// VRF signing context
+#[cfg(feature = "full_crypto")]
+const VRF_CTX: &[u8] = b"substrate-vrf";
+
 /// An Schnorrkel/Ristretto x25519 ("sr25519") public key.
 #[cfg_attr(feature = "full_crypto", derive(Hash))]
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Encode, Decode, Default, PassByInner)]
@@ -552,6 +556,45 @@ impl TraitPair for Pair {
 	}
 }
 
+/// The output of the VRF of a sr25519 key for an input, with its proof.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Encode, Decode, crate::RuntimeDebug, PassByCodec)]
+pub struct VrfSignature {
+	/// The VRF output, unpredictable without the secret key and unique for the key and input.
+	pub output: H256,
+	/// The proof of the output.
+	pub proof: H512,
+}
+
+#[cfg(feature = "full_crypto")]
+impl Pair {
+	/// Evaluate the VRF of the key pair for the given input.
+	pub fn vrf_sign(&self, input: &[u8]) -> VrfSignature {
+		let (in_out, proof, _) = self.0.vrf_sign(signing_context(VRF_CTX).bytes(input));
+		VrfSignature {
+			output: H256(in_out.to_output().to_bytes()),
+			proof: H512(proof.to_bytes()),
+		}
+	}
+
+	/// Verify the VRF output of the given public key for the given input, and its proof.
+	pub fn vrf_verify(signature: &VrfSignature, input: &[u8], pubkey: &Public) -> bool {
+		let public = match PublicKey::from_bytes(pubkey.as_ref()) {
+			Ok(public) => public,
+			Err(_) => return false,
+		};
+		let output = match schnorrkel::vrf::VRFOutput::from_bytes(signature.output.as_bytes()) {
+			Ok(output) => output,
+			Err(_) => return false,
+		};
+		let proof = match schnorrkel::vrf::VRFProof::from_bytes(signature.proof.as_bytes()) {
+			Ok(proof) => proof,
+			Err(_) => return false,
+		};
+
+		public.vrf_verify(signing_context(VRF_CTX).bytes(input), &output, &proof).is_ok()
+	}
+}
+
 #[cfg(feature = "std")]
 impl Pair {
 	/// Make a new key pair from binary data derived from a valid seed phrase.
@@ -832,4 +875,17 @@ mod test {
 		// Poorly-sized
 		assert!(deserialize_signature("\"abc123\"").is_err());
 	}
+	#[test]
+	fn vrf_sign_and_verify_should_work() {
+		let pair = Pair::from_seed(b"12345678901234567890123456789012");
+		let other = Pair::from_seed(b"23456789012345678901234567890123");
+		let signature = pair.vrf_sign(b"round 1");
+
+		assert!(Pair::vrf_verify(&signature, b"round 1", &pair.public()));
+		assert!(!Pair::vrf_verify(&signature, b"round 2", &pair.public()));
+		assert!(!Pair::vrf_verify(&signature, b"round 1", &other.public()));
+		// the output is unique for the key and the input
+		assert_eq!(pair.vrf_sign(b"round 1").output, signature.output);
+		assert_ne!(other.vrf_sign(b"round 1").output, signature.output);
+	}
 }
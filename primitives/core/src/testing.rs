@@ -17,13 +17,15 @@
 //! Types that should only be used for testing!
 
 #[cfg(feature = "std")]
-use crate::{ed25519, sr25519, crypto::{Public, Pair}};
+use crate::{bls12_381, ed25519, sr25519, crypto::{Public, Pair}};
 use crate::crypto::KeyTypeId;
 
 /// Key type for generic Ed25519 key.
 pub const ED25519: KeyTypeId = KeyTypeId(*b"ed25");
 /// Key type for generic Sr 25519 key.
 pub const SR25519: KeyTypeId = KeyTypeId(*b"sr25");
+/// Key type for generic BLS12-381 key.
+pub const BLS12_381: KeyTypeId = KeyTypeId(*b"bls1");
 
 /// A keystore implementation usable in tests.
 #[cfg(feature = "std")]
@@ -119,6 +121,44 @@ impl crate::traits::BareCryptoStore for KeyStore {
 			)
 	}
 
+	fn bls12_381_public_keys(&self, id: KeyTypeId) -> Vec<bls12_381::Public> {
+		self.keys.get(&id)
+			.map(|keys|
+				keys.values()
+					.map(|s| bls12_381::Pair::from_string(s, None).expect("`bls12_381` seed slice is valid"))
+					.map(|p| p.public())
+					.collect()
+			)
+			.unwrap_or_default()
+	}
+
+	fn bls12_381_generate_new(
+		&mut self,
+		id: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<bls12_381::Public, String> {
+		match seed {
+			Some(seed) => {
+				let pair = bls12_381::Pair::from_string(seed, None).expect("Generates a `bls12_381` pair.");
+				self.keys.entry(id).or_default().insert(pair.public().to_raw_vec(), seed.into());
+				Ok(pair.public())
+			},
+			None => {
+				let (pair, phrase, _) = bls12_381::Pair::generate_with_phrase(None);
+				self.keys.entry(id).or_default().insert(pair.public().to_raw_vec(), phrase);
+				Ok(pair.public())
+			}
+		}
+	}
+
+	fn bls12_381_key_pair(&self, id: KeyTypeId, pub_key: &bls12_381::Public) -> Option<bls12_381::Pair> {
+		self.keys.get(&id)
+			.and_then(|inner|
+				inner.get(pub_key.as_slice())
+					.map(|s| bls12_381::Pair::from_string(s, None).expect("`bls12_381` seed slice is valid"))
+			)
+	}
+
 	fn insert_unknown(&mut self, id: KeyTypeId, suri: &str, public: &[u8]) -> Result<(), ()> {
 		self.keys.entry(id).or_default().insert(public.to_owned(), suri.to_string());
 		Ok(())
@@ -16,7 +16,7 @@
 
 //! Shareable Substrate traits.
 
-use crate::{crypto::KeyTypeId, ed25519, sr25519};
+use crate::{crypto::{KeyTypeId, Pair}, bls12_381, ed25519, sr25519};
 
 use std::{
 	fmt::{Debug, Display}, panic::UnwindSafe, sync::Arc, borrow::Cow,
@@ -39,7 +39,35 @@ pub trait BareCryptoStore: Send + Sync {
 		seed: Option<&str>,
 	) -> Result<sr25519::Public, String>;
 	/// Returns the sr25519 key pair for the given key type and public key combination.
+	///
+	/// `None` for keys whose secret the store doesn't hold, e.g. keys held by a remote signer,
+	/// which can still sign with `sr25519_sign`.
 	fn sr25519_key_pair(&self, id: KeyTypeId, pub_key: &sr25519::Public) -> Option<sr25519::Pair>;
+	/// Sign `msg` with the sr25519 key for the given key type and public key combination.
+	///
+	/// By default the message is signed with the key pair returned by `sr25519_key_pair`, stores
+	/// that don't hold the secret keys forward the message to where they are held instead.
+	fn sr25519_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &sr25519::Public,
+		msg: &[u8],
+	) -> Option<sr25519::Signature> {
+		self.sr25519_key_pair(id, pub_key).map(|pair| pair.sign(msg))
+	}
+	/// Evaluate the VRF of the sr25519 key for the given key type and public key combination on
+	/// `input`.
+	///
+	/// See `sr25519_sign`, stores that don't hold the secret keys evaluate the VRF where they are
+	/// held instead.
+	fn sr25519_vrf_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &sr25519::Public,
+		input: &[u8],
+	) -> Option<sr25519::VrfSignature> {
+		self.sr25519_key_pair(id, pub_key).map(|pair| pair.vrf_sign(input))
+	}
 
 	/// Returns all ed25519 public keys for the given key type.
 	fn ed25519_public_keys(&self, id: KeyTypeId) -> Vec<ed25519::Public>;
@@ -55,7 +83,57 @@ pub trait BareCryptoStore: Send + Sync {
 	) -> Result<ed25519::Public, String>;
 
 	/// Returns the ed25519 key pair for the given key type and public key combination.
+	///
+	/// See `sr25519_key_pair`.
 	fn ed25519_key_pair(&self, id: KeyTypeId, pub_key: &ed25519::Public) -> Option<ed25519::Pair>;
+	/// Sign `msg` with the ed25519 key for the given key type and public key combination.
+	///
+	/// See `sr25519_sign`.
+	fn ed25519_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &ed25519::Public,
+		msg: &[u8],
+	) -> Option<ed25519::Signature> {
+		self.ed25519_key_pair(id, pub_key).map(|pair| pair.sign(msg))
+	}
+
+	/// Returns all bls12-381 public keys for the given key type.
+	///
+	/// Stores without bls12-381 keys return none.
+	fn bls12_381_public_keys(&self, _id: KeyTypeId) -> Vec<bls12_381::Public> {
+		Vec::new()
+	}
+	/// Generate a new bls12-381 key pair for the given key type and an optional seed.
+	///
+	/// If the given seed is `Some(_)`, the key pair will only be stored in memory.
+	///
+	/// Returns the public key of the generated key pair. Stores without bls12-381 keys refuse to
+	/// generate them.
+	fn bls12_381_generate_new(
+		&mut self,
+		_id: KeyTypeId,
+		_seed: Option<&str>,
+	) -> Result<bls12_381::Public, String> {
+		Err("bls12-381 keys are not supported by this keystore".into())
+	}
+	/// Returns the bls12-381 key pair for the given key type and public key combination.
+	///
+	/// See `sr25519_key_pair`.
+	fn bls12_381_key_pair(&self, _id: KeyTypeId, _pub_key: &bls12_381::Public) -> Option<bls12_381::Pair> {
+		None
+	}
+	/// Sign `msg` with the bls12-381 key for the given key type and public key combination.
+	///
+	/// See `sr25519_sign`.
+	fn bls12_381_sign(
+		&self,
+		id: KeyTypeId,
+		pub_key: &bls12_381::Public,
+		msg: &[u8],
+	) -> Option<bls12_381::Signature> {
+		self.bls12_381_key_pair(id, pub_key).map(|pair| pair.sign(msg))
+	}
 
 	/// Insert a new key. This doesn't require any known of the crypto; but a public key must be
 	/// manually provided.
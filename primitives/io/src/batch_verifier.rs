@@ -0,0 +1,160 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Batch verification of signatures.
+//!
+//! Signatures pushed while a batch is open are only verified when the batch is finished, in
+//! parallel on the task executor of the host.
+
+use sp_core::{crypto::Pair, ed25519, sr25519, traits::CloneableSpawn};
+use std::sync::Arc;
+use futures::{channel::oneshot, executor::block_on, task::SpawnExt};
+
+/// Number of signatures verified by a single task.
+const CHUNK_SIZE: usize = 32;
+
+/// A signature waiting for verification.
+enum BatchItem {
+	Ed25519(ed25519::Signature, ed25519::Public, Vec<u8>),
+	Sr25519(sr25519::Signature, sr25519::Public, Vec<u8>),
+}
+
+impl BatchItem {
+	fn verify(&self) -> bool {
+		match self {
+			BatchItem::Ed25519(signature, public, message) =>
+				ed25519::Pair::verify(signature, message, public),
+			BatchItem::Sr25519(signature, public, message) =>
+				sr25519::Pair::verify(signature, message, public),
+		}
+	}
+}
+
+/// Verify the items in `range`.
+fn verify_range(items: &[BatchItem], range: std::ops::Range<usize>) -> bool {
+	items[range].iter().all(BatchItem::verify)
+}
+
+/// Collects signatures and verifies them all at once.
+pub struct BatchVerifier {
+	scheduler: Option<Box<dyn CloneableSpawn>>,
+	items: Vec<BatchItem>,
+}
+
+impl BatchVerifier {
+	/// Create a batch verifier running its tasks on `scheduler`, or verifying the signatures on
+	/// the calling thread when it is `None`.
+	pub fn new(scheduler: Option<Box<dyn CloneableSpawn>>) -> Self {
+		BatchVerifier { scheduler, items: Vec::new() }
+	}
+
+	/// Push an `ed25519` signature of `message` by `public`.
+	pub fn push_ed25519(&mut self, signature: ed25519::Signature, public: ed25519::Public, message: Vec<u8>) {
+		self.items.push(BatchItem::Ed25519(signature, public, message));
+	}
+
+	/// Push an `sr25519` signature of `message` by `public`.
+	pub fn push_sr25519(&mut self, signature: sr25519::Signature, public: sr25519::Public, message: Vec<u8>) {
+		self.items.push(BatchItem::Sr25519(signature, public, message));
+	}
+
+	/// Verify all the pushed signatures and clear the batch.
+	///
+	/// Returns `true` when all the signatures are valid.
+	pub fn verify_and_clear(&mut self) -> bool {
+		let items = Arc::new(std::mem::replace(&mut self.items, Vec::new()));
+		let scheduler = match &self.scheduler {
+			Some(scheduler) if items.len() > CHUNK_SIZE => scheduler,
+			_ => return verify_range(&items, 0..items.len()),
+		};
+
+		let mut ranges = (0..items.len()).step_by(CHUNK_SIZE)
+			.map(|start| start..(start + CHUNK_SIZE).min(items.len()));
+		// The calling thread verifies the first chunk while the others are verified by the tasks.
+		let first = ranges.next().expect("there are more items than a chunk; qed");
+
+		let mut pending = Vec::new();
+		let mut valid = true;
+		for range in ranges {
+			let (sender, receiver) = oneshot::channel();
+			let task_items = items.clone();
+			let task_range = range.clone();
+			let task = async move {
+				let _ = sender.send(verify_range(&task_items, task_range));
+			};
+			match scheduler.spawn(task) {
+				Ok(()) => pending.push((range, receiver)),
+				Err(e) => {
+					log::debug!(target: "runtime", "Failed to spawn batch verification task: {:?}", e);
+					valid &= verify_range(&items, range);
+				},
+			}
+		}
+
+		valid &= verify_range(&items, first);
+		for (range, receiver) in pending {
+			// A task dropped by the scheduler is verified here instead.
+			valid &= block_on(receiver).unwrap_or_else(|_| verify_range(&items, range));
+		}
+		valid
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn push_signatures(verifier: &mut BatchVerifier, count: usize) {
+		for i in 0..count {
+			let message = format!("message {}", i).into_bytes();
+			if i % 2 == 0 {
+				let pair = ed25519::Pair::from_seed(&[i as u8; 32]);
+				verifier.push_ed25519(pair.sign(&message), pair.public(), message);
+			} else {
+				let pair = sr25519::Pair::from_seed(&[i as u8; 32]);
+				verifier.push_sr25519(pair.sign(&message), pair.public(), message);
+			}
+		}
+	}
+
+	#[test]
+	fn valid_signatures_are_verified_in_parallel() {
+		let mut verifier = BatchVerifier::new(Some(sp_core::tasks::executor()));
+		push_signatures(&mut verifier, CHUNK_SIZE * 3 + 1);
+
+		assert!(verifier.verify_and_clear());
+		// The batch is cleared.
+		assert!(verifier.verify_and_clear());
+	}
+
+	#[test]
+	fn an_invalid_signature_fails_the_batch() {
+		for scheduler in vec![None, Some(sp_core::tasks::executor())] {
+			let mut verifier = BatchVerifier::new(scheduler);
+			push_signatures(&mut verifier, CHUNK_SIZE * 2);
+			let pair = ed25519::Pair::from_seed(&[1u8; 32]);
+			verifier.push_ed25519(pair.sign(b"message"), pair.public(), b"other message".to_vec());
+			push_signatures(&mut verifier, CHUNK_SIZE);
+
+			assert!(!verifier.verify_and_clear());
+		}
+	}
+
+	#[test]
+	fn empty_batch_is_valid() {
+		assert!(BatchVerifier::new(Some(sp_core::tasks::executor())).verify_and_clear());
+	}
+}
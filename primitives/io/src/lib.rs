@@ -34,14 +34,14 @@ use sp_std::ops::Deref;
 #[cfg(feature = "std")]
 use sp_core::{
 	crypto::Pair,
-	traits::{KeystoreExt, CallInWasmExt},
+	traits::{KeystoreExt, CallInWasmExt, CloneableSpawn, TaskExecutorExt},
 	offchain::{OffchainExt, TransactionPoolExt},
 	hexdisplay::HexDisplay,
 	storage::{ChildStorageKey, ChildInfo},
 };
 
 use sp_core::{
-	crypto::KeyTypeId, ed25519, sr25519, H256, LogLevel,
+	crypto::KeyTypeId, bls12_381, ed25519, sr25519, H256, LogLevel,
 	offchain::{
 		Timestamp, HttpRequestId, HttpRequestStatus, HttpError, StorageKind, OpaqueNetworkState,
 	},
@@ -50,13 +50,39 @@ use sp_core::{
 #[cfg(feature = "std")]
 use sp_trie::{TrieConfiguration, trie_types::Layout};
 
-use sp_runtime_interface::{runtime_interface, Pointer};
+use sp_runtime_interface::{runtime_interface, Pointer, pass_by::PassByCodec};
 
 use codec::{Encode, Decode};
 
 #[cfg(feature = "std")]
 use sp_externalities::{ExternalitiesExt, Externalities};
 
+#[cfg(feature = "std")]
+mod batch_verifier;
+
+#[cfg(feature = "std")]
+use batch_verifier::BatchVerifier;
+
+#[cfg(feature = "std")]
+pub mod signature_cache;
+
+#[cfg(feature = "std")]
+pub use signature_cache::SignatureCache;
+
+#[cfg(feature = "std")]
+sp_externalities::decl_extension! {
+	/// The batch verification extension, registered between `crypto::start_batch_verify` and
+	/// `crypto::finish_batch_verify`.
+	pub struct VerificationExt(BatchVerifier);
+}
+
+#[cfg(feature = "std")]
+sp_externalities::decl_extension! {
+	/// The cache of signatures verified ahead of the execution, the batch verification skips the
+	/// signatures found in the cache.
+	pub struct SignatureCacheExt(std::sync::Arc<SignatureCache>);
+}
+
 /// Error verifying ECDSA signature
 #[derive(Encode, Decode)]
 pub enum EcdsaVerifyError {
@@ -68,6 +94,20 @@ pub enum EcdsaVerifyError {
 	BadSignature,
 }
 
+/// The maximum number of keys in a page returned by `storage::child_keys_paged`.
+pub const MAX_CHILD_KEYS_PAGE: u32 = 1024;
+
+/// A page of child storage keys returned by `storage::child_keys_paged`.
+#[derive(Encode, Decode, PassByCodec, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ChildKeysPage {
+	/// The keys of this page, in lexicographic order.
+	pub keys: Vec<Vec<u8>>,
+	/// Cursor to pass to the next call to continue the iteration, or `None` if
+	/// there are no more keys with the requested prefix.
+	pub cursor: Option<Vec<u8>>,
+}
+
 /// Returns a `ChildStorageKey` if the given `storage_key` slice is a valid storage
 /// key or panics otherwise.
 ///
@@ -82,7 +122,7 @@ fn child_storage_key_or_panic(storage_key: &[u8]) -> ChildStorageKey {
 }
 
 /// Interface for accessing the storage from within the runtime.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Storage {
 	/// Returns the data for `key` in the storage or `None` if the key can not be found.
 	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
@@ -311,10 +351,100 @@ pub trait Storage {
 			.expect("Invalid child definition");
 		self.next_child_storage_key(storage_key, child_info, key)
 	}
+
+	/// Get at most `limit` keys of the child storage that start with `prefix`, in
+	/// lexicographic order. `limit` is clamped to `MAX_CHILD_KEYS_PAGE`, and a `limit` of `0`
+	/// returns an empty page.
+	///
+	/// The iteration starts at the first key with the given `prefix` if `cursor` is `None`,
+	/// and right after the `cursor` otherwise. The returned page holds the cursor to
+	/// continue with, which is `None` once all matching keys have been returned. This allows
+	/// a runtime to process a large child trie across several blocks.
+	///
+	/// See `child_get` for common child api parameters.
+	fn child_keys_paged(
+		&mut self,
+		child_storage_key: &[u8],
+		child_definition: &[u8],
+		child_type: u32,
+		prefix: &[u8],
+		cursor: Option<Vec<u8>>,
+		limit: u32,
+	) -> ChildKeysPage {
+		let limit = limit.min(MAX_CHILD_KEYS_PAGE) as usize;
+		if limit == 0 {
+			return ChildKeysPage { keys: Vec::new(), cursor: None };
+		}
+
+		let child_info = ChildInfo::resolve_child_info(child_type, child_definition)
+			.expect("Invalid child definition");
+		let next_key = |ext: &mut Self, key: &[u8]| {
+			let storage_key = child_storage_key_or_panic(child_storage_key);
+			ext.next_child_storage_key(storage_key, child_info, key)
+				.filter(|next| next.starts_with(prefix))
+		};
+
+		let mut keys = Vec::new();
+		let mut next = match cursor {
+			Some(cursor) => next_key(self, &cursor),
+			None => {
+				let storage_key = child_storage_key_or_panic(child_storage_key);
+				if self.exists_child_storage(storage_key, child_info, prefix) {
+					Some(prefix.to_vec())
+				} else {
+					next_key(self, prefix)
+				}
+			},
+		};
+		while let Some(key) = next.take() {
+			if keys.len() >= limit {
+				next = Some(key);
+				break;
+			}
+			next = next_key(self, &key);
+			keys.push(key);
+		}
+
+		ChildKeysPage {
+			cursor: next.and(keys.last().cloned()),
+			keys,
+		}
+	}
+
+	/// Start a new nested storage transaction.
+	///
+	/// All changes made after this call can be reverted with `rollback_transaction` or kept
+	/// with `commit_transaction`. Each started transaction must be closed by exactly one of
+	/// these calls before the end of the runtime call.
+	fn start_transaction(&mut self) {
+		self.storage_start_transaction();
+	}
+
+	/// Revert all changes made since the last call to `start_transaction` and close that
+	/// transaction.
+	///
+	/// # Panics
+	///
+	/// Will panic if there is no open transaction.
+	fn rollback_transaction(&mut self) {
+		self.storage_rollback_transaction()
+			.expect("No open storage transaction that can be rolled back.");
+	}
+
+	/// Keep all changes made since the last call to `start_transaction` and close that
+	/// transaction.
+	///
+	/// # Panics
+	///
+	/// Will panic if there is no open transaction.
+	fn commit_transaction(&mut self) {
+		self.storage_commit_transaction()
+			.expect("No open storage transaction that can be committed.");
+	}
 }
 
 /// Interface that provides trie related functionality.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Trie {
 	/// A trie root formed from the iterated items.
 	fn blake2_256_root(input: Vec<(Vec<u8>, Vec<u8>)>) -> H256 {
@@ -328,7 +458,7 @@ pub trait Trie {
 }
 
 /// Interface that provides miscellaneous functions for communicating between the runtime and the node.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Misc {
 	/// The current relay chain identifier.
 	fn chain_id(&self) -> u64 {
@@ -373,7 +503,7 @@ pub trait Misc {
 }
 
 /// Interfaces for working with crypto related types from within the runtime.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Crypto {
 	/// Returns all `ed25519` public keys for the given key id from the keystore.
 	fn ed25519_public_keys(&mut self, id: KeyTypeId) -> Vec<ed25519::Public> {
@@ -411,8 +541,7 @@ pub trait Crypto {
 		self.extension::<KeystoreExt>()
 			.expect("No `keystore` associated for the current context!")
 			.read()
-			.ed25519_key_pair(id, &pub_key)
-			.map(|k| k.sign(msg))
+			.ed25519_sign(id, &pub_key, msg)
 	}
 
 	/// Verify an `ed25519` signature.
@@ -427,6 +556,36 @@ pub trait Crypto {
 		ed25519::Pair::verify(sig, msg, pub_key)
 	}
 
+	/// Register an `ed25519` signature for batch verification.
+	///
+	/// Batch verification must be enabled by calling `start_batch_verify`, otherwise the
+	/// signature is verified immediately. The result of the batch is returned by
+	/// `finish_batch_verify`.
+	///
+	/// Signatures found in the `SignatureCacheExt` are not verified again.
+	///
+	/// Returns `true` when the signature is valid or pushed to the batch.
+	fn ed25519_batch_verify(
+		&mut self,
+		sig: &ed25519::Signature,
+		msg: &[u8],
+		pub_key: &ed25519::Public,
+	) -> bool {
+		let verified = self.extension::<SignatureCacheExt>()
+			.map_or(false, |cache| cache.is_ed25519_verified(sig, msg, pub_key));
+		if verified {
+			return true
+		}
+
+		match self.extension::<VerificationExt>() {
+			Some(verifier) => {
+				verifier.push_ed25519(sig.clone(), *pub_key, msg.to_vec());
+				true
+			},
+			None => ed25519::Pair::verify(sig, msg, pub_key),
+		}
+	}
+
 	/// Returns all `sr25519` public keys for the given key id from the keystore.
 	fn sr25519_public_keys(&mut self, id: KeyTypeId) -> Vec<sr25519::Public> {
 		self.extension::<KeystoreExt>()
@@ -463,8 +622,7 @@ pub trait Crypto {
 		self.extension::<KeystoreExt>()
 			.expect("No `keystore` associated for the current context!")
 			.read()
-			.sr25519_key_pair(id, &pub_key)
-			.map(|k| k.sign(msg))
+			.sr25519_sign(id, &pub_key, msg)
 	}
 
 	/// Verify an `sr25519` signature.
@@ -483,6 +641,170 @@ pub trait Crypto {
 		sr25519::Pair::verify(sig, msg, pubkey)
 	}
 
+	/// Evaluate the VRF of the `sr25519` key that corresponds to the given public key and key
+	/// type in the keystore on `input`.
+	///
+	/// Returns the VRF output and its proof.
+	fn sr25519_vrf_sign(
+		&mut self,
+		id: KeyTypeId,
+		pub_key: &sr25519::Public,
+		input: &[u8],
+	) -> Option<sr25519::VrfSignature> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.read()
+			.sr25519_vrf_sign(id, &pub_key, input)
+	}
+
+	/// Verify the VRF output of an `sr25519` public key for `input`, and its proof.
+	///
+	/// Returns `true` when the verification in successful.
+	fn sr25519_vrf_verify(
+		signature: &sr25519::VrfSignature,
+		input: &[u8],
+		pub_key: &sr25519::Public,
+	) -> bool {
+		sr25519::Pair::vrf_verify(signature, input, pub_key)
+	}
+
+	/// Register an `sr25519` signature for batch verification.
+	///
+	/// See `ed25519_batch_verify`.
+	///
+	/// Returns `true` when the signature is valid or pushed to the batch.
+	fn sr25519_batch_verify(
+		&mut self,
+		sig: &sr25519::Signature,
+		msg: &[u8],
+		pub_key: &sr25519::Public,
+	) -> bool {
+		let verified = self.extension::<SignatureCacheExt>()
+			.map_or(false, |cache| cache.is_sr25519_verified(sig, msg, pub_key));
+		if verified {
+			return true
+		}
+
+		match self.extension::<VerificationExt>() {
+			Some(verifier) => {
+				verifier.push_sr25519(sig.clone(), *pub_key, msg.to_vec());
+				true
+			},
+			None => sr25519::Pair::verify(sig, msg, pub_key),
+		}
+	}
+
+	/// Start verifying the signatures registered with `*_batch_verify` in batch.
+	///
+	/// The batch is verified in parallel on the task executor of the host by
+	/// `finish_batch_verify`.
+	///
+	/// Panics if a batch is already started.
+	fn start_batch_verify(&mut self) {
+		let scheduler = self.extension::<TaskExecutorExt>()
+			.map(|executor| CloneableSpawn::clone(&*executor.0));
+
+		self.register_extension(VerificationExt(BatchVerifier::new(scheduler)))
+			.expect("Failed to register required extension: `VerificationExt`");
+	}
+
+	/// Finish the batch verification started by `start_batch_verify`.
+	///
+	/// Returns `true` when all the signatures registered since the start of the batch are valid.
+	///
+	/// Panics if no batch is started.
+	fn finish_batch_verify(&mut self) -> bool {
+		let valid = self.extension::<VerificationExt>()
+			.expect("`finish_batch_verify` should only be called after `start_batch_verify`")
+			.verify_and_clear();
+
+		self.deregister_extension::<VerificationExt>()
+			.expect("No verification extension in the current context!");
+
+		valid
+	}
+
+	/// Returns all `bls12_381` public keys for the given key id from the keystore.
+	fn bls12_381_public_keys(&mut self, id: KeyTypeId) -> Vec<bls12_381::Public> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.read()
+			.bls12_381_public_keys(id)
+	}
+
+	/// Generate a `bls12_381` key for the given key type using an optional seed and
+	/// store it in the keystore.
+	///
+	/// The `seed` needs to be a valid utf8.
+	///
+	/// Returns the public key.
+	fn bls12_381_generate(&mut self, id: KeyTypeId, seed: Option<Vec<u8>>) -> bls12_381::Public {
+		let seed = seed.as_ref().map(|s| std::str::from_utf8(&s).expect("Seed is valid utf8!"));
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.write()
+			.bls12_381_generate_new(id, seed)
+			.expect("`bls12_381_generate` failed")
+	}
+
+	/// Sign the given `msg` with the `bls12_381` key that corresponds to the given public key and
+	/// key type in the keystore.
+	///
+	/// Returns the signature.
+	fn bls12_381_sign(
+		&mut self,
+		id: KeyTypeId,
+		pub_key: &bls12_381::Public,
+		msg: &[u8],
+	) -> Option<bls12_381::Signature> {
+		self.extension::<KeystoreExt>()
+			.expect("No `keystore` associated for the current context!")
+			.read()
+			.bls12_381_sign(id, &pub_key, msg)
+	}
+
+	/// Verify a `bls12_381` signature.
+	///
+	/// Returns `true` when the verification in successful.
+	fn bls12_381_verify(
+		sig: &bls12_381::Signature,
+		msg: &[u8],
+		pub_key: &bls12_381::Public,
+	) -> bool {
+		bls12_381::Pair::verify(sig, msg, pub_key)
+	}
+
+	/// Aggregate `bls12_381` signatures into a single signature.
+	///
+	/// Returns `None` if `sigs` is empty or contains an invalid signature.
+	fn bls12_381_aggregate(sigs: Vec<bls12_381::Signature>) -> Option<bls12_381::Signature> {
+		bls12_381::aggregate(&sigs)
+	}
+
+	/// Verify an aggregated `bls12_381` signature of the same `msg` by all of `pub_keys`.
+	///
+	/// The possession of every public key must have been proven beforehand, otherwise the
+	/// verification is open to rogue key attacks.
+	///
+	/// Returns `true` when the verification in successful.
+	fn bls12_381_fast_aggregate_verify(
+		sig: &bls12_381::Signature,
+		msg: &[u8],
+		pub_keys: Vec<bls12_381::Public>,
+	) -> bool {
+		bls12_381::fast_aggregate_verify(sig, msg, &pub_keys)
+	}
+
+	/// Verify a `bls12_381` proof of possession of the secret key of `pub_key`.
+	///
+	/// Returns `true` when the verification in successful.
+	fn bls12_381_verify_proof_of_possession(
+		proof: &bls12_381::Signature,
+		pub_key: &bls12_381::Public,
+	) -> bool {
+		bls12_381::verify_proof_of_possession(proof, pub_key)
+	}
+
 	/// Verify and recover a SECP256k1 ECDSA signature.
 	///
 	/// - `sig` is passed in RSV format. V should be either `0/1` or `27/28`.
@@ -526,7 +848,7 @@ pub trait Crypto {
 }
 
 /// Interface that provides functions for hashing with different algorithms.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Hashing {
 	/// Conduct a 256-bit Keccak hash.
 	fn keccak_256(data: &[u8]) -> [u8; 32] {
@@ -565,7 +887,7 @@ pub trait Hashing {
 }
 
 /// Interface that provides functions to access the offchain functionality.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Offchain {
 	/// Returns if the local node is a potential validator.
 	///
@@ -750,10 +1072,48 @@ pub trait Offchain {
 			.http_response_read_body(request_id, buffer, deadline)
 			.map(|r| r as u32)
 	}
+
+	/// Fetches the content of the given CID from the IPFS gateway of the node.
+	///
+	/// Passing `None` as a deadline blocks forever.
+	fn ipfs_get(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<Vec<u8>, HttpError> {
+		self.extension::<OffchainExt>()
+			.expect("ipfs_get can be called only in the offchain worker context")
+			.ipfs_get(cid, deadline)
+	}
+
+	/// Pins the content of the given CID on the IPFS node of the node.
+	///
+	/// Passing `None` as a deadline blocks forever.
+	fn ipfs_pin(&mut self, cid: &str, deadline: Option<Timestamp>) -> Result<(), HttpError> {
+		self.extension::<OffchainExt>()
+			.expect("ipfs_pin can be called only in the offchain worker context")
+			.ipfs_pin(cid, deadline)
+	}
+}
+
+/// Interface that provides functions to write to the offchain index during block import.
+///
+/// The offchain index is a node-local key-value store that isn't part of the state: writes
+/// don't change the storage root and aren't checked by consensus. They are applied to the
+/// `PERSISTENT` offchain storage with the imported block if it becomes the best block, and
+/// dropped for blocks imported on other forks. They can be read back by offchain workers or
+/// over the `offchain_localStorageGet` RPC, e.g. by indexers.
+#[runtime_interface(generate_metadata)]
+pub trait OffchainIndex {
+	/// Write a key-value pair to the offchain index.
+	fn set(&mut self, key: &[u8], value: &[u8]) {
+		self.set_offchain_storage(key, Some(value));
+	}
+
+	/// Remove a key and its value from the offchain index.
+	fn clear(&mut self, key: &[u8]) {
+		self.set_offchain_storage(key, None);
+	}
 }
 
 /// Wasm only interface that provides functions for calling into the allocator.
-#[runtime_interface(wasm_only)]
+#[runtime_interface(wasm_only, generate_metadata)]
 trait Allocator {
 	/// Malloc the given number of bytes and return the pointer to the allocated memory location.
 	fn malloc(&mut self, size: u32) -> Pointer<u8> {
@@ -767,7 +1127,7 @@ trait Allocator {
 }
 
 /// Interface that provides functions for logging from within the runtime.
-#[runtime_interface]
+#[runtime_interface(generate_metadata)]
 pub trait Logging {
 	/// Request to print a log message on the host.
 	///
@@ -787,8 +1147,28 @@ pub trait Logging {
 	}
 }
 
+/// Interface that provides functions for profiling the runtime with the tracing subscriber of
+/// the host.
+///
+/// Instead of using directly, prefer `frame_support::enter_span!`.
+#[runtime_interface(generate_metadata)]
+pub trait WasmTracing {
+	/// Enter a span with the given target and name, recorded with the index of the extrinsic
+	/// being applied, if given.
+	///
+	/// Returns the id of the span to exit it with, `0` if the host isn't interested in it.
+	fn enter_span(target: &str, name: &str, extrinsic_index: Option<u32>) -> u64 {
+		sp_tracing::proxy::enter_span(target, name, extrinsic_index)
+	}
+
+	/// Exit the span with the given id, and the spans entered after it.
+	fn exit_span(id: u64) {
+		sp_tracing::proxy::exit_span(id)
+	}
+}
+
 /// Wasm-only interface that provides functions for interacting with the sandbox.
-#[runtime_interface(wasm_only)]
+#[runtime_interface(wasm_only, generate_metadata)]
 pub trait Sandbox {
 	/// Instantiate a new sandbox instance with the given `wasm_code`.
 	fn instantiate(
@@ -933,14 +1313,22 @@ pub type SubstrateHostFunctions = (
 	storage::HostFunctions,
 	misc::HostFunctions,
 	offchain::HostFunctions,
+	offchain_index::HostFunctions,
 	crypto::HostFunctions,
 	hashing::HostFunctions,
 	allocator::HostFunctions,
 	logging::HostFunctions,
+	wasm_tracing::HostFunctions,
 	sandbox::HostFunctions,
 	crate::trie::HostFunctions,
 );
 
+/// Returns the metadata of all host functions in [`SubstrateHostFunctions`].
+#[cfg(feature = "std")]
+pub fn substrate_host_functions_metadata() -> Vec<sp_runtime_interface::metadata::HostFunctionMetadata> {
+	<SubstrateHostFunctions as sp_runtime_interface::metadata::HostFunctionsMetadata>::host_functions_metadata()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1008,4 +1396,158 @@ mod tests {
 			assert!(storage::get(b":abc").is_none());
 		});
 	}
+
+	#[test]
+	fn child_keys_paged_works() {
+		let child_key = b":child_storage:default:sub1";
+		let child_info = ChildInfo::new_default(b"unique_id_1");
+		let mut t = BasicExternalities::default();
+
+		t.execute_with(|| {
+			let (child_definition, child_type) = child_info.info();
+			let set = |key: &[u8]| storage::child_set(
+				child_key,
+				child_definition,
+				child_type,
+				key,
+				b"value",
+			);
+			set(b"a");
+			set(b"ab");
+			set(b"abc");
+			set(b"abd");
+			set(b"b");
+
+			let page = |prefix: &[u8], cursor, limit| storage::child_keys_paged(
+				child_key,
+				child_definition,
+				child_type,
+				prefix,
+				cursor,
+				limit,
+			);
+
+			let first = page(b"ab", None, 2);
+			assert_eq!(first.keys, vec![b"ab".to_vec(), b"abc".to_vec()]);
+			assert_eq!(first.cursor, Some(b"abc".to_vec()));
+
+			let second = page(b"ab", first.cursor, 2);
+			assert_eq!(second.keys, vec![b"abd".to_vec()]);
+			assert_eq!(second.cursor, None);
+
+			let all = page(b"", None, 5);
+			assert_eq!(all.keys.len(), 5);
+			assert_eq!(all.cursor, None);
+
+			assert_eq!(page(b"a", None, 0), ChildKeysPage { keys: vec![], cursor: None });
+			assert_eq!(page(b"c", None, 10), ChildKeysPage { keys: vec![], cursor: None });
+		});
+	}
+
+	#[test]
+	fn child_keys_paged_clamps_the_limit() {
+		let child_key = b":child_storage:default:sub1";
+		let child_info = ChildInfo::new_default(b"unique_id_1");
+		let mut t = BasicExternalities::default();
+
+		t.execute_with(|| {
+			let (child_definition, child_type) = child_info.info();
+			for i in 0..=MAX_CHILD_KEYS_PAGE {
+				storage::child_set(child_key, child_definition, child_type, &i.to_be_bytes(), b"value");
+			}
+
+			let page = storage::child_keys_paged(
+				child_key,
+				child_definition,
+				child_type,
+				&[],
+				None,
+				u32::max_value(),
+			);
+			assert_eq!(page.keys.len(), MAX_CHILD_KEYS_PAGE as usize);
+			assert_eq!(page.cursor, Some((MAX_CHILD_KEYS_PAGE - 1).to_be_bytes().to_vec()));
+		});
+	}
+
+	#[test]
+	fn storage_transactions_work() {
+		let mut t = BasicExternalities::default();
+
+		t.execute_with(|| {
+			storage::set(b"a", b"1");
+
+			storage::start_transaction();
+			storage::set(b"a", b"2");
+			storage::set(b"b", b"2");
+
+			storage::start_transaction();
+			storage::clear(b"a");
+			storage::rollback_transaction();
+
+			assert_eq!(storage::get(b"a"), Some(b"2".to_vec()));
+			storage::commit_transaction();
+
+			storage::start_transaction();
+			storage::set(b"c", b"3");
+			storage::rollback_transaction();
+		});
+
+		t.execute_with(|| {
+			assert_eq!(storage::get(b"a"), Some(b"2".to_vec()));
+			assert_eq!(storage::get(b"b"), Some(b"2".to_vec()));
+			assert_eq!(storage::get(b"c"), None);
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "No open storage transaction that can be rolled back.")]
+	fn rollback_without_transaction_panics() {
+		BasicExternalities::default().execute_with(|| storage::rollback_transaction());
+	}
+
+	#[test]
+	fn host_functions_metadata_matches_host_functions() {
+		use sp_wasm_interface::HostFunctions;
+
+		let metadata = substrate_host_functions_metadata();
+		let host_functions = SubstrateHostFunctions::host_functions();
+
+		assert_eq!(metadata.len(), host_functions.len());
+		for (meta, function) in metadata.iter().zip(host_functions.iter()) {
+			assert_eq!(meta.name, function.name());
+			assert_eq!(meta.args, &function.signature().args[..]);
+			assert_eq!(meta.return_value, function.signature().return_value);
+		}
+		assert!(metadata.iter().any(|m| m.name == "ext_storage_get_version_1" && m.version == 1));
+	}
+
+	#[test]
+	fn batch_verify_works() {
+		let mut ext = TestExternalities::default();
+		ext.register_extension(TaskExecutorExt::new(sp_core::tasks::executor()));
+		ext.execute_with(|| {
+			let pair = sr25519::Pair::from_seed(&[1u8; 32]);
+			let signature = pair.sign(b"message");
+
+			crypto::start_batch_verify();
+			for _ in 0..100 {
+				assert!(crypto::sr25519_batch_verify(&signature, b"message", &pair.public()));
+			}
+			assert!(crypto::finish_batch_verify());
+
+			crypto::start_batch_verify();
+			// An invalid signature is only detected when the batch is finished.
+			assert!(crypto::sr25519_batch_verify(&signature, b"other message", &pair.public()));
+			assert!(!crypto::finish_batch_verify());
+
+			// Without a batch, the signature is verified immediately.
+			assert!(!crypto::sr25519_batch_verify(&signature, b"other message", &pair.public()));
+		});
+	}
+
+	#[test]
+	#[should_panic(expected = "`finish_batch_verify` should only be called after `start_batch_verify`")]
+	fn finish_batch_verify_without_start_panics() {
+		TestExternalities::default().execute_with(|| { crypto::finish_batch_verify(); });
+	}
 }
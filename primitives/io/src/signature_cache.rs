@@ -0,0 +1,142 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of signatures verified ahead of the block execution.
+//!
+//! Signatures of queued blocks can be verified in parallel before the blocks are executed, e.g. by
+//! a pre-verifier of the import queue. The batch verification host functions then skip the
+//! signatures found in the cache. Only valid signatures are cached, so the result of the
+//! execution doesn't depend on the content of the cache.
+
+use sp_core::{crypto::Pair, ed25519, sr25519, hashing::blake2_256};
+use std::{collections::HashSet, sync::Mutex};
+
+/// Default number of signatures kept in the cache.
+pub const DEFAULT_CAPACITY: usize = 100_000;
+
+/// Signatures known to be valid.
+pub struct SignatureCache {
+	verified: Mutex<HashSet<[u8; 32]>>,
+	capacity: usize,
+}
+
+impl Default for SignatureCache {
+	fn default() -> Self {
+		SignatureCache::new(DEFAULT_CAPACITY)
+	}
+}
+
+impl SignatureCache {
+	/// Create a cache of up to `capacity` signatures, cleared once full.
+	pub fn new(capacity: usize) -> Self {
+		SignatureCache {
+			verified: Mutex::new(HashSet::new()),
+			capacity,
+		}
+	}
+
+	/// The key of a signature of `message` by `public` with the given `scheme`.
+	fn key(scheme: &[u8], signature: &[u8], public: &[u8], message: &[u8]) -> [u8; 32] {
+		let mut preimage = Vec::with_capacity(scheme.len() + signature.len() + public.len() + message.len());
+		preimage.extend_from_slice(scheme);
+		preimage.extend_from_slice(signature);
+		preimage.extend_from_slice(public);
+		preimage.extend_from_slice(message);
+		blake2_256(&preimage)
+	}
+
+	fn contains(&self, key: &[u8; 32]) -> bool {
+		self.verified.lock().expect("Signature cache lock is poisoned").contains(key)
+	}
+
+	fn insert(&self, key: [u8; 32]) {
+		let mut verified = self.verified.lock().expect("Signature cache lock is poisoned");
+		if verified.len() >= self.capacity {
+			verified.clear();
+		}
+		verified.insert(key);
+	}
+
+	/// Verify an `ed25519` signature of `message` by `public`, caching it if it is valid.
+	pub fn ed25519_verify(&self, signature: &ed25519::Signature, message: &[u8], public: &ed25519::Public) -> bool {
+		let key = Self::key(b"ed25519", &signature.0, &public.0, message);
+		if self.contains(&key) {
+			return true
+		}
+
+		let valid = ed25519::Pair::verify(signature, message, public);
+		if valid {
+			self.insert(key);
+		}
+		valid
+	}
+
+	/// Verify an `sr25519` signature of `message` by `public`, caching it if it is valid.
+	pub fn sr25519_verify(&self, signature: &sr25519::Signature, message: &[u8], public: &sr25519::Public) -> bool {
+		let key = Self::key(b"sr25519", &signature.0, &public.0, message);
+		if self.contains(&key) {
+			return true
+		}
+
+		let valid = sr25519::Pair::verify(signature, message, public);
+		if valid {
+			self.insert(key);
+		}
+		valid
+	}
+
+	/// Whether the `ed25519` signature of `message` by `public` is cached.
+	pub fn is_ed25519_verified(&self, signature: &ed25519::Signature, message: &[u8], public: &ed25519::Public) -> bool {
+		self.contains(&Self::key(b"ed25519", &signature.0, &public.0, message))
+	}
+
+	/// Whether the `sr25519` signature of `message` by `public` is cached.
+	pub fn is_sr25519_verified(&self, signature: &sr25519::Signature, message: &[u8], public: &sr25519::Public) -> bool {
+		self.contains(&Self::key(b"sr25519", &signature.0, &public.0, message))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_valid_signatures_are_cached() {
+		let cache = SignatureCache::default();
+		let pair = sr25519::Pair::from_seed(&[1; 32]);
+		let signature = pair.sign(b"message");
+
+		assert!(!cache.is_sr25519_verified(&signature, b"message", &pair.public()));
+		assert!(!cache.sr25519_verify(&signature, b"other message", &pair.public()));
+		assert!(!cache.is_sr25519_verified(&signature, b"other message", &pair.public()));
+
+		assert!(cache.sr25519_verify(&signature, b"message", &pair.public()));
+		assert!(cache.is_sr25519_verified(&signature, b"message", &pair.public()));
+	}
+
+	#[test]
+	fn cache_is_cleared_once_full() {
+		let cache = SignatureCache::new(1);
+		let pair = ed25519::Pair::from_seed(&[1; 32]);
+		let first = pair.sign(b"first");
+		let second = pair.sign(b"second");
+
+		assert!(cache.ed25519_verify(&first, b"first", &pair.public()));
+		assert!(cache.ed25519_verify(&second, b"second", &pair.public()));
+		assert!(!cache.is_ed25519_verified(&first, b"first", &pair.public()));
+		assert!(cache.is_ed25519_verified(&second, b"second", &pair.public()));
+	}
+}
@@ -0,0 +1,127 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of GRANDPA justifications.
+
+use codec::{Decode, Encode};
+use sp_finality_grandpa::{AuthorityId, AuthoritySignature, AuthorityWeight, RoundNumber, SetId};
+use sp_runtime::{RuntimeDebug, traits::Header as HeaderT};
+use sp_std::{collections::{btree_map::BTreeMap, btree_set::BTreeSet}, prelude::*};
+
+use crate::Error;
+
+/// A commit of the precommits of the voters finalizing a block.
+pub type Commit<Header> = grandpa::Commit<
+	<Header as HeaderT>::Hash,
+	<Header as HeaderT>::Number,
+	AuthoritySignature,
+	AuthorityId,
+>;
+
+/// A GRANDPA justification of the finality of a block: a commit and the headers from the targets
+/// of the precommits to the target of the commit.
+///
+/// The encoding is the one of the justifications stored and served by the full nodes.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct GrandpaJustification<Header: HeaderT> {
+	/// The round of the commit.
+	pub round: RoundNumber,
+	/// The commit.
+	pub commit: Commit<Header>,
+	/// The headers from the targets of the precommits to the target of the commit.
+	pub votes_ancestries: Vec<Header>,
+}
+
+impl<Header: HeaderT> GrandpaJustification<Header> {
+	/// Verify that the justification finalizes the given block, with the precommits of more than
+	/// two thirds of the weight of the authority set.
+	pub fn verify(
+		&self,
+		target: (Header::Hash, Header::Number),
+		set_id: SetId,
+		authorities: &[(AuthorityId, AuthorityWeight)],
+	) -> Result<(), Error> {
+		if (self.commit.target_hash, self.commit.target_number) != target {
+			return Err(Error::TargetMismatch);
+		}
+
+		verify_commit(self.round, &self.commit, &self.votes_ancestries, set_id, authorities)
+	}
+}
+
+/// Verify that the precommits of a commit have more than two thirds of the weight of the
+/// authority set, and that their targets descend from the target of the commit through the
+/// given headers.
+pub fn verify_commit<Header: HeaderT>(
+	round: RoundNumber,
+	commit: &Commit<Header>,
+	votes_ancestries: &[Header],
+	set_id: SetId,
+	authorities: &[(AuthorityId, AuthorityWeight)],
+) -> Result<(), Error> {
+	let ancestry = votes_ancestries.iter()
+		.map(|header| (header.hash(), header))
+		.collect::<BTreeMap<_, _>>();
+	let mut visited = BTreeSet::new();
+	let mut voters = Vec::new();
+	let mut weight = 0u64;
+
+	for signed in &commit.precommits {
+		let voter_weight = authorities.iter()
+			.find(|(id, _)| *id == signed.id)
+			.map(|(_, weight)| *weight)
+			.ok_or(Error::UnknownAuthority)?;
+		if voters.contains(&&signed.id) {
+			return Err(Error::DuplicateVote);
+		}
+		voters.push(&signed.id);
+
+		if !sp_finality_grandpa::check_message_signature(
+			&grandpa::Message::Precommit(signed.precommit.clone()),
+			&signed.id,
+			&signed.signature,
+			round,
+			set_id,
+		) {
+			return Err(Error::BadSignature);
+		}
+
+		// the precommit target descends from the commit target through the ancestries
+		let mut current = signed.precommit.target_hash;
+		while current != commit.target_hash {
+			let header = ancestry.get(&current).ok_or(Error::InvalidAncestry)?;
+			if *header.number() <= commit.target_number {
+				return Err(Error::InvalidAncestry);
+			}
+			visited.insert(current);
+			current = *header.parent_hash();
+		}
+
+		weight = weight.saturating_add(voter_weight);
+	}
+
+	if visited.len() != votes_ancestries.len() {
+		return Err(Error::UnusedAncestry);
+	}
+
+	let total = authorities.iter().fold(0u64, |total, (_, weight)| total.saturating_add(*weight));
+	let threshold = total - total.saturating_sub(1) / 3;
+	if weight < threshold {
+		return Err(Error::NotEnoughVotes);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,300 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Light client verification of plug chains, for browsers and embedded devices.
+//!
+//! A `LightClient` tracks the last finalized header and the GRANDPA authority set finalizing its
+//! descendants. It imports the headers from the finalized header to a new finalized header with
+//! the justification of the latter, verifying that the headers extend the finalized chain and the
+//! justification, and follows the changes of the authority set scheduled in the digests of the
+//! headers. The storage of a verified header is then read from storage proofs under its state
+//! root with `verify_storage_proof`. Headers, justifications and storage proofs are obtained over any
+//! transport, the crate makes no networking assumptions.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+mod justification;
+mod storage;
+
+pub use justification::{Commit, GrandpaJustification, verify_commit};
+pub use storage::verify_storage_proof;
+pub use sp_trie::StorageProof;
+
+use codec::{Decode, Encode};
+use sp_finality_grandpa::{AuthorityList, ConsensusLog, SetId, GRANDPA_ENGINE_ID};
+use sp_runtime::{RuntimeDebug, generic::OpaqueDigestItemId, traits::{Header as HeaderT, One}};
+
+/// An error verifying headers or a justification.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Error {
+	/// No headers to import.
+	NoHeaders,
+	/// The headers don't extend the finalized chain.
+	NotAChain,
+	/// The justification can't be decoded.
+	JustificationDecode,
+	/// The justification doesn't finalize the expected block.
+	TargetMismatch,
+	/// A precommit isn't from an authority of the set.
+	UnknownAuthority,
+	/// An authority precommitted more than once.
+	DuplicateVote,
+	/// A precommit has an invalid signature.
+	BadSignature,
+	/// The target of a precommit doesn't descend from the target of the commit.
+	InvalidAncestry,
+	/// The justification has headers that aren't ancestors of the targets of the precommits.
+	UnusedAncestry,
+	/// The precommits don't have more than two thirds of the weight of the authority set.
+	NotEnoughVotes,
+	/// The headers go past the enactment of a change of the authority set, which must be
+	/// finalized first.
+	SpansAuthoritySetChange,
+	/// A change of the authority set is scheduled while another is pending.
+	OverlappingChanges,
+	/// A forced change of the authority set, which can't be proven by justifications.
+	ForcedChange,
+	/// The storage proof doesn't prove the value of a key in the state of the header.
+	InvalidStorageProof,
+}
+
+/// A GRANDPA authority set.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct AuthoritySet {
+	/// The id of the set, incremented by every change.
+	pub set_id: SetId,
+	/// The authorities and their weights.
+	pub authorities: AuthorityList,
+}
+
+/// A change of the authority set scheduled by a finalized header.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct PendingChange<Number> {
+	/// The next authorities.
+	pub authorities: AuthorityList,
+	/// The number of the block finalized by the current set after which the next set finalizes.
+	pub effective_number: Number,
+}
+
+/// The state of a light client: the finalized header and the authority set finalizing its
+/// descendants. It's encodable to be persisted between sessions.
+#[derive(Clone, Encode, Decode, PartialEq, Eq, RuntimeDebug)]
+pub struct LightClient<Header: HeaderT> {
+	finalized: Header,
+	authority_set: AuthoritySet,
+	pending_change: Option<PendingChange<Header::Number>>,
+}
+
+impl<Header: HeaderT> LightClient<Header> {
+	/// Start from a trusted finalized header, e.g. the genesis, and its authority set.
+	pub fn new(finalized: Header, authority_set: AuthoritySet) -> Self {
+		LightClient { finalized, authority_set, pending_change: None }
+	}
+
+	/// The last finalized header.
+	pub fn finalized(&self) -> &Header {
+		&self.finalized
+	}
+
+	/// The authority set finalizing the descendants of the finalized header.
+	pub fn authority_set(&self) -> &AuthoritySet {
+		&self.authority_set
+	}
+
+	/// The change of the authority set scheduled by a finalized header, if any.
+	pub fn pending_change(&self) -> Option<&PendingChange<Header::Number>> {
+		self.pending_change.as_ref()
+	}
+
+	/// Import the headers from the child of the finalized header to a new finalized header, with
+	/// the encoded justification of the latter.
+	///
+	/// The headers may schedule a change of the authority set, which the client follows once the
+	/// block of its enactment is finalized. The headers can't go past the enactment of a change.
+	pub fn import_finalized(&mut self, headers: &[Header], justification: &[u8]) -> Result<(), Error> {
+		let target = headers.last().ok_or(Error::NoHeaders)?;
+		verify_chain(&self.finalized, headers)?;
+
+		let mut pending_change = self.pending_change.clone();
+		for header in headers {
+			if pending_change.as_ref().map_or(false, |change| change.effective_number < *header.number()) {
+				return Err(Error::SpansAuthoritySetChange);
+			}
+			if let Some(change) = scheduled_change(header)? {
+				if pending_change.is_some() {
+					return Err(Error::OverlappingChanges);
+				}
+				pending_change = Some(change);
+			}
+		}
+
+		let justification = GrandpaJustification::<Header>::decode(&mut &justification[..])
+			.map_err(|_| Error::JustificationDecode)?;
+		justification.verify(
+			(target.hash(), *target.number()),
+			self.authority_set.set_id,
+			&self.authority_set.authorities,
+		)?;
+
+		self.finalized = target.clone();
+		self.pending_change = match pending_change {
+			Some(change) if change.effective_number == *target.number() => {
+				self.authority_set = AuthoritySet {
+					set_id: self.authority_set.set_id + 1,
+					authorities: change.authorities,
+				};
+				None
+			},
+			change => change,
+		};
+		Ok(())
+	}
+}
+
+/// Verify that the headers are the descendants of the ancestor, in order.
+pub fn verify_chain<Header: HeaderT>(ancestor: &Header, headers: &[Header]) -> Result<(), Error> {
+	let mut parent = (ancestor.hash(), *ancestor.number());
+	for header in headers {
+		if (*header.parent_hash(), *header.number()) != (parent.0, parent.1 + One::one()) {
+			return Err(Error::NotAChain);
+		}
+		parent = (header.hash(), *header.number());
+	}
+	Ok(())
+}
+
+/// The change of the authority set scheduled in the digest of a header, if any.
+fn scheduled_change<Header: HeaderT>(header: &Header) -> Result<Option<PendingChange<Header::Number>>, Error> {
+	let id = OpaqueDigestItemId::Consensus(&GRANDPA_ENGINE_ID);
+	for log in header.digest().logs() {
+		match log.try_to::<ConsensusLog<Header::Number>>(id) {
+			Some(ConsensusLog::ScheduledChange(change)) => return Ok(Some(PendingChange {
+				authorities: change.next_authorities,
+				effective_number: *header.number() + change.delay,
+			})),
+			Some(ConsensusLog::ForcedChange(..)) => return Err(Error::ForcedChange),
+			_ => {},
+		}
+	}
+	Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_finality_grandpa::{AuthorityId, ScheduledChange};
+	use sp_keyring::Ed25519Keyring;
+	use sp_runtime::{DigestItem, generic, traits::BlakeTwo256};
+
+	type Header = generic::Header<u64, BlakeTwo256>;
+
+	const VOTERS: [Ed25519Keyring; 4] =
+		[Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie, Ed25519Keyring::Dave];
+
+	fn authorities(voters: &[Ed25519Keyring]) -> AuthorityList {
+		voters.iter().map(|voter| (AuthorityId::from(voter.public()), 1)).collect()
+	}
+
+	fn child(parent: &Header, logs: Vec<DigestItem<<Header as HeaderT>::Hash>>) -> Header {
+		let mut header = Header::new(
+			parent.number + 1,
+			Default::default(),
+			Default::default(),
+			parent.hash(),
+			Default::default(),
+		);
+		header.digest.logs = logs;
+		header
+	}
+
+	fn justification(target: &Header, set_id: SetId, voters: &[Ed25519Keyring]) -> Vec<u8> {
+		let precommit = grandpa::Precommit::new(target.hash(), target.number);
+		let payload = sp_finality_grandpa::localized_payload(
+			1,
+			set_id,
+			&grandpa::Message::Precommit(precommit.clone()),
+		);
+		let precommits = voters.iter()
+			.map(|voter| grandpa::SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: voter.sign(&payload).into(),
+				id: voter.public().into(),
+			})
+			.collect();
+		GrandpaJustification::<Header> {
+			round: 1,
+			commit: grandpa::Commit { target_hash: target.hash(), target_number: target.number, precommits },
+			votes_ancestries: vec![],
+		}.encode()
+	}
+
+	#[test]
+	fn finalized_headers_are_imported() {
+		let genesis = Header::new(0, Default::default(), Default::default(), Default::default(), Default::default());
+		let mut client = LightClient::new(genesis.clone(), AuthoritySet { set_id: 0, authorities: authorities(&VOTERS) });
+		let first = child(&genesis, vec![]);
+		let second = child(&first, vec![]);
+		let headers = vec![first, second.clone()];
+
+		assert_eq!(
+			client.import_finalized(&headers, &justification(&second, 0, &VOTERS[..2])),
+			Err(Error::NotEnoughVotes),
+		);
+		assert_eq!(
+			client.import_finalized(&headers, &justification(&second, 1, &VOTERS[..3])),
+			Err(Error::BadSignature),
+		);
+		assert_eq!(
+			client.import_finalized(&headers[1..], &justification(&second, 0, &VOTERS[..3])),
+			Err(Error::NotAChain),
+		);
+
+		assert_eq!(client.import_finalized(&headers, &justification(&second, 0, &VOTERS[..3])), Ok(()));
+		assert_eq!(client.finalized(), &second);
+	}
+
+	#[test]
+	fn authority_set_changes_are_followed() {
+		let genesis = Header::new(0, Default::default(), Default::default(), Default::default(), Default::default());
+		let next = [Ed25519Keyring::Eve, Ed25519Keyring::Ferdie];
+		let mut client = LightClient::new(genesis.clone(), AuthoritySet { set_id: 0, authorities: authorities(&VOTERS) });
+
+		let change = ConsensusLog::ScheduledChange(ScheduledChange { next_authorities: authorities(&next), delay: 1u64 });
+		let first = child(&genesis, vec![DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode())]);
+		let second = child(&first, vec![]);
+		let third = child(&second, vec![]);
+
+		assert_eq!(
+			client.clone().import_finalized(&[first.clone(), second.clone(), third.clone()], &justification(&third, 0, &VOTERS)),
+			Err(Error::SpansAuthoritySetChange),
+		);
+
+		client.import_finalized(&[first.clone()], &justification(&first, 0, &VOTERS)).unwrap();
+		assert_eq!(client.pending_change().map(|change| change.effective_number), Some(2));
+
+		client.import_finalized(&[second.clone()], &justification(&second, 0, &VOTERS)).unwrap();
+		assert_eq!(client.authority_set(), &AuthoritySet { set_id: 1, authorities: authorities(&next) });
+		assert_eq!(client.pending_change(), None);
+
+		assert_eq!(
+			client.clone().import_finalized(&[third.clone()], &justification(&third, 0, &VOTERS)),
+			Err(Error::UnknownAuthority),
+		);
+		client.import_finalized(&[third.clone()], &justification(&third, 1, &next)).unwrap();
+		assert_eq!(client.finalized(), &third);
+	}
+}
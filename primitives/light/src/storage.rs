@@ -0,0 +1,79 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verification of storage read proofs.
+
+use sp_runtime::traits::Hash as HashT;
+use sp_std::prelude::*;
+use sp_trie::{Layout, MemoryDB, StorageProof, read_trie_value};
+
+use crate::Error;
+
+/// Verify a proof of the storage of the given keys under a state root, e.g. the state root of a
+/// finalized header and a proof served by the `state_getReadProof` RPC, returning the value of
+/// every key, `None` for the keys proven to be absent.
+///
+/// Only the top trie is read, the values of child tries aren't provable this way.
+pub fn verify_storage_proof<Hashing: HashT>(
+	state_root: &Hashing::Output,
+	proof: StorageProof,
+	keys: &[&[u8]],
+) -> Result<Vec<Option<Vec<u8>>>, Error> {
+	let db: MemoryDB<Hashing> = proof.into_memory_db();
+	keys.iter()
+		.map(|key| read_trie_value::<Layout<Hashing>, _>(&db, state_root, key)
+			.map_err(|_| Error::InvalidStorageProof))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::traits::BlakeTwo256;
+	use sp_trie::{TrieDBMut, TrieMut};
+
+	fn root_and_proof(values: &[(&[u8], &[u8])]) -> (<BlakeTwo256 as HashT>::Output, StorageProof) {
+		let mut db = MemoryDB::<BlakeTwo256>::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMut::<Layout<BlakeTwo256>>::new(&mut db, &mut root);
+			for (key, value) in values {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		let nodes = db.drain().into_iter().map(|(_, (node, _))| node).collect();
+		(root, StorageProof::new(nodes))
+	}
+
+	#[test]
+	fn storage_is_read_from_proofs() {
+		let (root, proof) = root_and_proof(&[(b"alice", b"42"), (b"bob", b"7")]);
+		let keys: &[&[u8]] = &[b"alice", b"bob", b"charlie"];
+
+		assert_eq!(
+			verify_storage_proof::<BlakeTwo256>(&root, proof.clone(), keys),
+			Ok(vec![Some(b"42".to_vec()), Some(b"7".to_vec()), None]),
+		);
+		assert_eq!(
+			verify_storage_proof::<BlakeTwo256>(&Default::default(), proof, keys),
+			Err(Error::InvalidStorageProof),
+		);
+		assert_eq!(
+			verify_storage_proof::<BlakeTwo256>(&root, StorageProof::empty(), keys),
+			Err(Error::InvalidStorageProof),
+		);
+	}
+}
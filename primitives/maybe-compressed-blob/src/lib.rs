@@ -0,0 +1,128 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Handling of blobs that may be compressed, e.g. the runtime code stored at `:code`.
+//!
+//! A compressed blob is the zstd compression of the blob prefixed with `ZSTD_PREFIX`. Any other
+//! blob is taken as is, so uncompressed runtime code stays valid.
+
+use std::borrow::Cow;
+use std::io::{Read, Write};
+
+/// The prefix of zstd compressed blobs, which is not valid wasm.
+pub const ZSTD_PREFIX: [u8; 8] = [82, 188, 83, 118, 70, 219, 142, 5];
+
+/// The maximum size of the decompressed runtime code, protecting against decompression bombs.
+pub const CODE_BLOB_BOMB_LIMIT: usize = 50 * 1024 * 1024;
+
+/// Errors of decompressing a blob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+	/// The decompressed blob exceeds the bomb limit.
+	PossibleBomb,
+	/// The blob is not a valid zstd compression.
+	Invalid,
+}
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Error::PossibleBomb => write!(f, "Possible compression bomb encountered"),
+			Error::Invalid => write!(f, "Blob had invalid compression"),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+/// Whether `blob` is compressed.
+pub fn is_compressed(blob: &[u8]) -> bool {
+	blob.starts_with(&ZSTD_PREFIX)
+}
+
+/// Decompress `blob` if it is compressed, failing if it decompresses to more than `bomb_limit`
+/// bytes.
+pub fn decompress(blob: &[u8], bomb_limit: usize) -> Result<Cow<[u8]>, Error> {
+	if !is_compressed(blob) {
+		return Ok(blob.into())
+	}
+
+	let decoder = zstd::Decoder::new(&blob[ZSTD_PREFIX.len()..]).map_err(|_| Error::Invalid)?;
+	let mut decompressed = Vec::with_capacity(blob.len());
+	decoder.take(bomb_limit as u64 + 1)
+		.read_to_end(&mut decompressed)
+		.map_err(|_| Error::Invalid)?;
+
+	if decompressed.len() <= bomb_limit {
+		Ok(decompressed.into())
+	} else {
+		Err(Error::PossibleBomb)
+	}
+}
+
+/// Compress `blob`, `None` if it is larger than `bomb_limit` as it couldn't be decompressed.
+pub fn compress(blob: &[u8], bomb_limit: usize) -> Option<Vec<u8>> {
+	if blob.len() > bomb_limit {
+		return None
+	}
+
+	let mut compressed = ZSTD_PREFIX.to_vec();
+	{
+		let mut encoder = zstd::Encoder::new(&mut compressed, 3).ok()?.auto_finish();
+		encoder.write_all(blob).ok()?;
+	}
+
+	Some(compressed)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BOMB_LIMIT: usize = 10;
+
+	#[test]
+	fn uncompressed_blob_is_taken_as_is() {
+		let blob = b"\0asm uncompressed".to_vec();
+		assert!(!is_compressed(&blob));
+		assert_eq!(decompress(&blob, BOMB_LIMIT).unwrap(), &blob[..]);
+	}
+
+	#[test]
+	fn compressed_blob_round_trips() {
+		let blob = vec![1u8; BOMB_LIMIT];
+		let compressed = compress(&blob, BOMB_LIMIT).unwrap();
+
+		assert!(is_compressed(&compressed));
+		assert_eq!(decompress(&compressed, BOMB_LIMIT).unwrap(), &blob[..]);
+	}
+
+	#[test]
+	fn bombs_are_rejected() {
+		let blob = vec![1u8; BOMB_LIMIT + 1];
+		assert_eq!(compress(&blob, BOMB_LIMIT), None);
+
+		let compressed = compress(&blob, BOMB_LIMIT + 1).unwrap();
+		assert_eq!(decompress(&compressed, BOMB_LIMIT), Err(Error::PossibleBomb));
+	}
+
+	#[test]
+	fn invalid_compression_is_rejected() {
+		let mut blob = ZSTD_PREFIX.to_vec();
+		blob.extend_from_slice(b"not zstd");
+		assert_eq!(decompress(&blob, BOMB_LIMIT), Err(Error::Invalid));
+	}
+}
@@ -0,0 +1,340 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Merkle Mountain Range (MMR) primitives.
+//!
+//! An MMR is an append only list of Merkle trees, each of them perfect and of a distinct height.
+//! Its nodes are numbered in post-order, so appending a leaf only adds the leaf and the parents it
+//! completes, and nodes are never modified once written. The root of the MMR is obtained by
+//! "bagging" the roots of its trees (the peaks) from right to left.
+//!
+//! This crate provides the node arithmetic over any node storage, proof generation and
+//! verification, and the `MmrApi` runtime api.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, Encode, Decode};
+use sp_runtime::{RuntimeDebug, traits::Hash};
+use sp_std::prelude::*;
+
+/// Index of a leaf, i.e. the number of leaves appended before it.
+pub type LeafIndex = u64;
+
+/// Position of a node in the post-order numbering of all nodes.
+pub type NodeIndex = u64;
+
+/// A proof that a leaf is part of an MMR with `leaf_count` leaves.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq)]
+pub struct Proof<Hash> {
+	/// The index of the proven leaf.
+	pub leaf_index: LeafIndex,
+	/// The number of leaves of the MMR the proof was generated for.
+	pub leaf_count: u64,
+	/// The siblings on the path from the leaf to its peak, bottom up, followed by the
+	/// other peaks from left to right.
+	pub items: Vec<Hash>,
+}
+
+/// Errors of MMR operations.
+#[derive(Encode, Decode, RuntimeDebug, Clone, PartialEq, Eq)]
+pub enum Error {
+	/// The leaf index is not lower than the number of leaves.
+	InvalidLeafIndex,
+	/// A node required by the operation is missing from the node storage.
+	MissingNode,
+	/// The proof is malformed.
+	InvalidProof,
+	/// The proof does not prove the leaf against the given root.
+	Verify,
+}
+
+/// The number of nodes of an MMR with `leaf_count` leaves.
+pub fn mmr_size(leaf_count: u64) -> u64 {
+	2 * leaf_count - leaf_count.count_ones() as u64
+}
+
+/// The position of the leaf with the given index.
+pub fn leaf_index_to_pos(leaf_index: LeafIndex) -> NodeIndex {
+	// All nodes before the leaf form an MMR with `leaf_index` leaves.
+	mmr_size(leaf_index)
+}
+
+/// The positions of the peaks of an MMR with `leaf_count` leaves, from left to right.
+pub fn peaks(leaf_count: u64) -> Vec<NodeIndex> {
+	trees(leaf_count).map(|(offset, height, _)| subtree_root(offset, height)).collect()
+}
+
+/// The hash of the parent of the `left` and `right` nodes.
+pub fn merge<H: Hash>(left: &H::Output, right: &H::Output) -> H::Output {
+	let mut data = Vec::with_capacity(left.as_ref().len() + right.as_ref().len());
+	data.extend_from_slice(left.as_ref());
+	data.extend_from_slice(right.as_ref());
+	H::hash(&data)
+}
+
+/// Append the leaf with hash `leaf_hash` to an MMR with `leaf_count` leaves, reading existing
+/// nodes with `get`.
+///
+/// Returns the nodes to insert, i.e. the leaf and the parents it completes.
+pub fn append<H: Hash>(
+	leaf_count: u64,
+	leaf_hash: H::Output,
+	get: impl Fn(NodeIndex) -> Option<H::Output>,
+) -> Result<Vec<(NodeIndex, H::Output)>, Error> {
+	let mut pos = leaf_index_to_pos(leaf_count);
+	let mut node = leaf_hash;
+	let mut nodes = vec![(pos, node)];
+
+	// Every trailing one of the leaf index is a tree of the same height on the left to merge with.
+	for height in 0..(!leaf_count).trailing_zeros() {
+		let left = get(pos - ((2 << height) - 1)).ok_or(Error::MissingNode)?;
+		node = merge::<H>(&left, &node);
+		pos += 1;
+		nodes.push((pos, node));
+	}
+
+	Ok(nodes)
+}
+
+/// The root of an MMR with `leaf_count` leaves, reading nodes with `get`.
+///
+/// The root of an empty MMR is the default hash.
+pub fn root<H: Hash>(
+	leaf_count: u64,
+	get: impl Fn(NodeIndex) -> Option<H::Output>,
+) -> Result<H::Output, Error> {
+	let peaks = peaks(leaf_count)
+		.into_iter()
+		.map(get)
+		.collect::<Option<Vec<_>>>()
+		.ok_or(Error::MissingNode)?;
+	Ok(bag_peaks::<H>(peaks))
+}
+
+/// Generate a proof for the leaf `leaf_index` of an MMR with `leaf_count` leaves, reading
+/// nodes with `get`.
+pub fn generate_proof<H: Hash>(
+	leaf_index: LeafIndex,
+	leaf_count: u64,
+	get: impl Fn(NodeIndex) -> Option<H::Output>,
+) -> Result<Proof<H::Output>, Error> {
+	let (peak, path) = leaf_path(leaf_index, leaf_count).ok_or(Error::InvalidLeafIndex)?;
+	let peaks = peaks(leaf_count);
+	let items = path.into_iter()
+		.map(|(sibling, _)| sibling)
+		.chain(peaks.into_iter().enumerate().filter(|(i, _)| *i != peak).map(|(_, pos)| pos))
+		.map(get)
+		.collect::<Option<Vec<_>>>()
+		.ok_or(Error::MissingNode)?;
+
+	Ok(Proof { leaf_index, leaf_count, items })
+}
+
+/// Verify that the leaf with hash `leaf_hash` is part of the MMR with the given `root`.
+pub fn verify_proof<H: Hash>(
+	root: &H::Output,
+	leaf_hash: H::Output,
+	proof: &Proof<H::Output>,
+) -> Result<(), Error> {
+	let (peak, path) = leaf_path(proof.leaf_index, proof.leaf_count).ok_or(Error::InvalidProof)?;
+	let peak_count = proof.leaf_count.count_ones() as usize;
+	if proof.items.len() != path.len() + peak_count - 1 {
+		return Err(Error::InvalidProof);
+	}
+
+	let (siblings, other_peaks) = proof.items.split_at(path.len());
+	let peak_hash = path.iter().zip(siblings).fold(leaf_hash, |node, ((_, is_left), sibling)| {
+		if *is_left {
+			merge::<H>(sibling, &node)
+		} else {
+			merge::<H>(&node, sibling)
+		}
+	});
+
+	let mut peaks = other_peaks.to_vec();
+	peaks.insert(peak, peak_hash);
+	if bag_peaks::<H>(peaks) == *root {
+		Ok(())
+	} else {
+		Err(Error::Verify)
+	}
+}
+
+/// Fold the peaks from right to left into the root.
+fn bag_peaks<H: Hash>(peaks: Vec<H::Output>) -> H::Output {
+	peaks.into_iter()
+		.rev()
+		.fold(None, |right, peak| Some(match right {
+			Some(right) => merge::<H>(&peak, &right),
+			None => peak,
+		}))
+		.unwrap_or_default()
+}
+
+/// The position of the root of the perfect tree of `height` whose first node is at `offset`.
+fn subtree_root(offset: NodeIndex, height: u32) -> NodeIndex {
+	offset + (2 << height) - 2
+}
+
+/// The trees of an MMR with `leaf_count` leaves, from left to right, as tuples of the position
+/// of their first node, their height and the index of their first leaf.
+fn trees(leaf_count: u64) -> impl Iterator<Item = (NodeIndex, u32, LeafIndex)> {
+	let mut offset = 0;
+	let mut first_leaf = 0;
+	(0..63u32).rev().filter(move |height| leaf_count & (1 << height) != 0).map(move |height| {
+		let tree = (offset, height, first_leaf);
+		offset += (2 << height) - 1;
+		first_leaf += 1 << height;
+		tree
+	})
+}
+
+/// The index of the peak above the leaf `leaf_index` and the path from the leaf to that peak,
+/// as the siblings met bottom up together with whether they are on the left.
+///
+/// Returns `None` if the leaf is not part of an MMR with `leaf_count` leaves.
+fn leaf_path(leaf_index: LeafIndex, leaf_count: u64) -> Option<(usize, Vec<(NodeIndex, bool)>)> {
+	if leaf_index >= leaf_count {
+		return None;
+	}
+
+	let (peak, (mut offset, height, first_leaf)) = trees(leaf_count)
+		.enumerate()
+		.find(|(_, (_, height, first_leaf))| leaf_index < *first_leaf + (1u64 << *height))?;
+
+	let mut local_index = leaf_index - first_leaf;
+	let mut path = Vec::with_capacity(height as usize);
+	for height in (1..=height).rev() {
+		let half = 1 << (height - 1);
+		let left_root = subtree_root(offset, height - 1);
+		let right_offset = left_root + 1;
+		if local_index < half {
+			path.push((subtree_root(right_offset, height - 1), false));
+		} else {
+			path.push((left_root, true));
+			offset = right_offset;
+			local_index -= half;
+		}
+	}
+	path.reverse();
+
+	Some((peak, path))
+}
+
+sp_api::decl_runtime_apis! {
+	/// The Merkle Mountain Range api.
+	///
+	/// This api allows light clients and bridges to prove that a leaf, e.g. a block hash, was
+	/// appended to the MMR maintained by the runtime.
+	pub trait MmrApi<Hash: Codec> {
+		/// The current root of the MMR.
+		fn mmr_root() -> Hash;
+
+		/// Generate a proof against the current root for the leaf `leaf_index`.
+		///
+		/// Returns the SCALE encoded leaf together with the proof.
+		fn generate_proof(leaf_index: LeafIndex) -> Result<(Vec<u8>, Proof<Hash>), Error>;
+
+		/// Verify a proof of the SCALE encoded `leaf` against the current root.
+		fn verify_proof(leaf: Vec<u8>, proof: Proof<Hash>) -> Result<(), Error>;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::BTreeMap;
+	use sp_runtime::traits::BlakeTwo256;
+
+	type Hashing = BlakeTwo256;
+	type H256 = <Hashing as Hash>::Output;
+
+	fn leaf(i: u64) -> H256 {
+		Hashing::hash_of(&i)
+	}
+
+	fn build(leaf_count: u64) -> BTreeMap<NodeIndex, H256> {
+		let mut nodes = BTreeMap::new();
+		for i in 0..leaf_count {
+			let new = append::<Hashing>(i, leaf(i), |pos| nodes.get(&pos).cloned()).unwrap();
+			nodes.extend(new);
+		}
+		nodes
+	}
+
+	#[test]
+	fn sizes_and_peaks_are_correct() {
+		assert_eq!(mmr_size(0), 0);
+		assert_eq!(mmr_size(4), 7);
+		assert_eq!(mmr_size(11), 19);
+		assert_eq!(leaf_index_to_pos(2), 3);
+		assert_eq!(leaf_index_to_pos(4), 7);
+		assert_eq!(peaks(11), vec![14, 17, 18]);
+		assert_eq!(build(11).len() as u64, mmr_size(11));
+	}
+
+	#[test]
+	fn root_bags_peaks() {
+		let nodes = build(3);
+		let expected = merge::<Hashing>(&merge::<Hashing>(&leaf(0), &leaf(1)), &leaf(2));
+		assert_eq!(root::<Hashing>(3, |pos| nodes.get(&pos).cloned()), Ok(expected));
+		assert_eq!(root::<Hashing>(0, |_| None), Ok(Default::default()));
+	}
+
+	#[test]
+	fn proofs_of_all_leaves_verify() {
+		for leaf_count in 1..20 {
+			let nodes = build(leaf_count);
+			let get = |pos: NodeIndex| nodes.get(&pos).cloned();
+			let root = root::<Hashing>(leaf_count, get).unwrap();
+
+			for i in 0..leaf_count {
+				let proof = generate_proof::<Hashing>(i, leaf_count, get).unwrap();
+				assert_eq!(verify_proof::<Hashing>(&root, leaf(i), &proof), Ok(()));
+				assert_eq!(
+					verify_proof::<Hashing>(&root, leaf(i + 1), &proof),
+					Err(Error::Verify),
+				);
+			}
+
+			assert_eq!(
+				generate_proof::<Hashing>(leaf_count, leaf_count, get),
+				Err(Error::InvalidLeafIndex),
+			);
+		}
+	}
+
+	#[test]
+	fn malformed_proofs_are_rejected() {
+		let nodes = build(7);
+		let get = |pos: NodeIndex| nodes.get(&pos).cloned();
+		let root = root::<Hashing>(7, get).unwrap();
+		let proof = generate_proof::<Hashing>(5, 7, get).unwrap();
+
+		let mut truncated = proof.clone();
+		truncated.items.pop();
+		assert_eq!(verify_proof::<Hashing>(&root, leaf(5), &truncated), Err(Error::InvalidProof));
+
+		let mut out_of_range = proof.clone();
+		out_of_range.leaf_index = 7;
+		assert_eq!(verify_proof::<Hashing>(&root, leaf(5), &out_of_range), Err(Error::InvalidProof));
+
+		let mut other_leaf = proof;
+		other_leaf.leaf_index = 4;
+		assert_eq!(verify_proof::<Hashing>(&root, leaf(5), &other_leaf), Err(Error::Verify));
+	}
+}
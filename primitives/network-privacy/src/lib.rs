@@ -0,0 +1,92 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime Apis to retrieve the membership of a private network, the key its transactions are
+//! encrypted with, and the reputation changes of peers decided on chain.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::vec::Vec;
+
+/// The bytes of a libp2p `PeerId`, as returned by `PeerId::as_bytes`.
+pub type PeerIdBytes = Vec<u8>;
+
+/// Storage keys written at genesis from the `networkPrivacy` extension of the chain spec.
+///
+/// Runtimes implementing the `NetworkPrivacyApi` can seed the reserved peers of the network
+/// from these keys instead of requiring their own genesis config.
+pub mod well_known_keys {
+	/// The reserved nodes at genesis, a SCALE encoded `Vec<Vec<u8>>` of utf8 encoded `p2p`
+	/// multiaddresses.
+	pub const RESERVED_NODES: &[u8] = b":network_privacy:reserved_nodes";
+
+	/// The sr25519 public key of the network operator, a SCALE encoded `[u8; 32]`.
+	pub const OPERATOR_KEY: &[u8] = b":network_privacy:operator_key";
+}
+
+sp_api::decl_runtime_apis! {
+	/// The network privacy api.
+	///
+	/// This api is used by the `client/network-privacy` module to retrieve the reserved peers of a
+	/// private network from the chain state.
+	///
+	/// Version 2 added `reserved_nodes_with_expiry` and `authorized_light_clients`, version 3
+	/// added `reserved_node_operators`. Runtimes of earlier versions, e.g. the runtime of blocks
+	/// before an upgrade, do not export these methods, so callers check the version of the api at
+	/// the block they query before calling them.
+	#[api_version(3)]
+	pub trait NetworkPrivacyApi {
+		/// Retrieve the reserved peers as utf8 encoded `p2p` multiaddresses, e.g.
+		/// `/ip4/198.51.100.19/tcp/30333/p2p/QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV`.
+		fn reserved_peers() -> Vec<Vec<u8>>;
+
+		/// Retrieve the reserved peers like `reserved_peers`, together with the block number
+		/// after which each of them is no longer reserved, or `None` if it does not expire.
+		///
+		/// Runtimes that do not implement this method return the `reserved_peers` without expiry.
+		fn reserved_nodes_with_expiry() -> Vec<(Vec<u8>, Option<u64>)> {
+			Self::reserved_peers().into_iter().map(|peer| (peer, None)).collect()
+		}
+
+		/// Retrieve the reserved peers like `reserved_peers`, together with the sr25519 public key
+		/// of the account operating each of them. The operator signs the membership statement its
+		/// nodes present to the other members when connecting to them.
+		fn reserved_node_operators() -> Vec<(Vec<u8>, [u8; 32])>;
+
+		/// Retrieve the light clients the members serve without them being reserved peers, e.g.
+		/// light clients authorized with a doughnut issued by the network operator.
+		///
+		/// Runtimes that do not implement this method only let reserved peers use light clients.
+		fn authorized_light_clients() -> Vec<PeerIdBytes> {
+			Vec::new()
+		}
+
+		/// Retrieve the id and the key transactions are encrypted with while gossiped between
+		/// the members, or `None` to gossip transactions in clear. The id must change every time
+		/// the key is rotated.
+		fn transactions_key() -> Option<(u64, [u8; 32])>;
+	}
+
+	/// The network policy api.
+	///
+	/// This api is used by the `client/network-privacy` module to apply reputation changes decided
+	/// on chain, e.g. by governance, to the peers of every node of the network.
+	pub trait NetworkPolicyApi {
+		/// Retrieve the reputation changes to apply to peers in this block. Every node applies
+		/// the adjustments of each finalized block once.
+		fn peer_score_adjustments() -> Vec<(PeerIdBytes, i32)>;
+	}
+}
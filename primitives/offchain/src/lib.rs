@@ -19,12 +19,14 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![warn(missing_docs)]
 
+use sp_std::vec::Vec;
+
 /// Local Storage Prefix used by the Offchain Worker API to
 pub const STORAGE_PREFIX: &[u8] = b"storage";
 
 sp_api::decl_runtime_apis! {
 	/// The offchain worker api.
-	#[api_version(2)]
+	#[api_version(3)]
 	pub trait OffchainWorkerApi {
 		/// Starts the off-chain task for given block number.
 		#[skip_initialize_block]
@@ -34,5 +36,21 @@ sp_api::decl_runtime_apis! {
 		/// Starts the off-chain task for given block header.
 		#[skip_initialize_block]
 		fn offchain_worker(header: &Block::Header);
+
+		/// Returns the names of the workers the node can start separately with
+		/// `offchain_worker_of`, e.g. one per module, to run them in parallel and schedule each
+		/// of them on its own.
+		///
+		/// Added in version 3. Runtimes that do not implement this method only start all of their
+		/// workers at once with `offchain_worker`.
+		#[skip_initialize_block]
+		fn offchain_workers() -> Vec<Vec<u8>> {
+			Vec::new()
+		}
+
+		/// Starts the off-chain task of the given worker, one of `offchain_workers`, for given
+		/// block header.
+		#[skip_initialize_block]
+		fn offchain_worker_of(_header: &Block::Header, _worker: Vec<u8>) {}
 	}
 }
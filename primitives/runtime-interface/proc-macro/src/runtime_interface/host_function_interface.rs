@@ -25,7 +25,8 @@ use crate::utils::{
 	get_function_argument_types_without_ref, get_function_argument_types_ref_and_mut,
 	get_function_argument_names_and_types_without_ref, get_function_arguments,
 	get_function_argument_types, create_exchangeable_host_function_ident, get_runtime_interface,
-	create_function_ident_with_version,
+	create_function_ident_with_version, get_function_arguments_passed_as_two_u64,
+	create_two_u64_idents,
 };
 
 use syn::{
@@ -81,11 +82,6 @@ pub fn generate(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream
 fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_name: &Ident) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
 	let args = get_function_arguments(&method.sig);
-	let arg_types = get_function_argument_types_without_ref(&method.sig);
-	let arg_types2 = get_function_argument_types_without_ref(&method.sig);
-	let arg_names = get_function_argument_names(&method.sig);
-	let arg_names2 = get_function_argument_names(&method.sig);
-	let arg_names3 = get_function_argument_names(&method.sig);
 	let function = &method.sig.ident;
 	let ext_function = create_host_function_ident(&method.sig.ident, version, trait_name);
 	let doc_string = format!(
@@ -94,6 +90,30 @@ fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_n
 	);
 	let return_value = &method.sig.output;
 
+	let mut ffi_args = Vec::new();
+	let mut into_ffi_values = Vec::new();
+	let mut ffi_values = Vec::new();
+
+	for ((name, ty), two_u64) in get_function_argument_names_and_types_without_ref(&method.sig)
+		.zip(get_function_arguments_passed_as_two_u64(&method.sig))
+	{
+		if two_u64 {
+			let (low, high) = create_two_u64_idents(&name)?;
+
+			ffi_args.push(quote!( #low: u64, #high: u64 ));
+			into_ffi_values.push(quote! {
+				let (#low, #high) = #crate_::split_u128(#name as u128);
+			});
+			ffi_values.push(quote!( #low, #high ));
+		} else {
+			ffi_args.push(quote!( #name: <#ty as #crate_::RIType>::FFIType ));
+			into_ffi_values.push(quote! {
+				let #name = <#ty as #crate_::wasm::IntoFFIValue>::into_ffi_value(&#name);
+			});
+			ffi_values.push(quote!( #name.get() ));
+		}
+	}
+
 	let ffi_return_value = match method.sig.output {
 		ReturnType::Default => quote!(),
 		ReturnType::Type(_, ref ty) => quote! {
@@ -115,18 +135,14 @@ fn generate_extern_host_function(method: &TraitItemMethod, version: u32, trait_n
 				extern "C" {
 					/// The extern function.
 					pub fn #ext_function (
-						#( #arg_names: <#arg_types as #crate_::RIType>::FFIType ),*
+						#( #ffi_args ),*
 					) #ffi_return_value;
 				}
 
 				// Generate all wrapped ffi values.
-				#(
-					let #arg_names2 = <#arg_types2 as #crate_::wasm::IntoFFIValue>::into_ffi_value(
-						&#arg_names2,
-					);
-				)*
+				#( #into_ffi_values )*
 
-				let result = unsafe { #ext_function( #( #arg_names3.get() ),* ) };
+				let result = unsafe { #ext_function( #( #ffi_values ),* ) };
 
 				#convert_return_value
 			}
@@ -160,10 +176,17 @@ fn generate_exchangeable_host_function(method: &TraitItemMethod) -> Result<Token
 fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
 	let crate_ = generate_crate_access();
 
-	let host_functions = get_runtime_interface(trait_def)?
+	let runtime_interface = get_runtime_interface(trait_def)?;
+	let host_functions = runtime_interface
 		.all_versions()
 		.map(|(version, method)|
-			generate_host_function_implementation(&trait_def.ident, method, version, is_wasm_only)
+			generate_host_function_implementation(
+				&trait_def.ident,
+				method,
+				version,
+				runtime_interface.is_deprecated(&method.sig.ident, version),
+				is_wasm_only,
+			)
 		)
 		.collect::<Result<Vec<_>>>()?;
 
@@ -183,15 +206,67 @@ fn generate_host_functions_struct(trait_def: &ItemTrait, is_wasm_only: bool) ->
 	)
 }
 
+/// Generate the static host function metadata and the implementation of `HostFunctionsMetadata`
+/// for the `HostFunctions` struct.
+///
+/// The metadata contains an entry for every version of every function, as all of these versions
+/// are provided by the host.
+pub fn generate_metadata(trait_def: &ItemTrait) -> Result<TokenStream> {
+	let crate_ = generate_crate_access();
+	let trait_name = &trait_def.ident;
+
+	let entries = get_runtime_interface(trait_def)?
+		.all_versions()
+		.map(|(version, method)| {
+			let name = create_host_function_ident(&method.sig.ident, version, trait_name).to_string();
+			let arg_types = generate_wasm_interface_argument_types(&method.sig);
+			let return_value = match &method.sig.output {
+				ReturnType::Type(_, ty) => quote! {
+					Some( <<#ty as #crate_::RIType>::FFIType as #crate_::sp_wasm_interface::IntoValue>::VALUE_TYPE )
+				},
+				ReturnType::Default => quote!( None ),
+			};
+
+			quote! {
+				#crate_::metadata::HostFunctionMetadata {
+					name: #name,
+					version: #version,
+					args: &[ #( #arg_types ),* ],
+					return_value: #return_value,
+				}
+			}
+		})
+		.collect::<Vec<_>>();
+
+	Ok(
+		quote! {
+			/// The metadata of all host functions declared by this runtime interface.
+			#[cfg(feature = "std")]
+			pub const HOST_FUNCTIONS_METADATA: &[#crate_::metadata::HostFunctionMetadata] = &[
+				#( #entries ),*
+			];
+
+			#[cfg(feature = "std")]
+			impl #crate_::metadata::HostFunctionsMetadata for HostFunctions {
+				fn host_functions_metadata() -> Vec<#crate_::metadata::HostFunctionMetadata> {
+					HOST_FUNCTIONS_METADATA.to_vec()
+				}
+			}
+		}
+	)
+}
+
 /// Generates the host function struct that implements `wasm_interface::Function` and returns a static
 /// reference to this struct.
 ///
 /// When calling from wasm into the host, we will call the `execute` function that calls the native
-/// implementation of the function.
+/// implementation of the function within a tracing span. If the given `version` is deprecated,
+/// every call is noted in the deprecated host function call counters.
 fn generate_host_function_implementation(
 	trait_name: &Ident,
 	method: &TraitItemMethod,
 	version: u32,
+	is_deprecated: bool,
 	is_wasm_only: bool,
 ) -> Result<TokenStream> {
 	let name = create_host_function_ident(&method.sig.ident, version, trait_name).to_string();
@@ -206,6 +281,11 @@ fn generate_host_function_implementation(
 	let host_function_call = generate_host_function_call(&method.sig, version, is_wasm_only);
 	let into_preallocated_ffi_value = generate_into_preallocated_ffi_value(&method.sig)?;
 	let convert_return_value = generate_return_value_into_wasm_value(&method.sig);
+	let note_deprecated_call = if is_deprecated {
+		quote!( #crate_::deprecation::note_deprecated_call(#name); )
+	} else {
+		TokenStream::new()
+	};
 
 	Ok(
 		quote! {
@@ -226,11 +306,18 @@ fn generate_host_function_implementation(
 						__function_context__: &mut dyn #crate_::sp_wasm_interface::FunctionContext,
 						args: &mut dyn Iterator<Item = #crate_::sp_wasm_interface::Value>,
 					) -> std::result::Result<Option<#crate_::sp_wasm_interface::Value>, String> {
-						#( #wasm_to_ffi_values )*
-						#( #ffi_to_host_values )*
-						#host_function_call
-						#into_preallocated_ffi_value
-						#convert_return_value
+						#note_deprecated_call
+						#crate_::trace::trace_host_function(
+							#name,
+							__function_context__,
+							move |__function_context__: &mut dyn #crate_::sp_wasm_interface::FunctionContext| {
+								#( #wasm_to_ffi_values )*
+								#( #ffi_to_host_values )*
+								#host_function_call
+								#into_preallocated_ffi_value
+								#convert_return_value
+							},
+						)
 					}
 				}
 
@@ -250,10 +337,7 @@ fn generate_wasm_interface_signature_for_host_function(sig: &Signature) -> Resul
 			},
 		ReturnType::Default => quote!( None ),
 	};
-	let arg_types = get_function_argument_types_without_ref(sig)
-		.map(|ty| quote! {
-			<<#ty as #crate_::RIType>::FFIType as #crate_::sp_wasm_interface::IntoValue>::VALUE_TYPE
-		});
+	let arg_types = generate_wasm_interface_argument_types(sig);
 
 	Ok(
 		quote! {
@@ -265,6 +349,28 @@ fn generate_wasm_interface_signature_for_host_function(sig: &Signature) -> Resul
 	)
 }
 
+/// Generate the `wasm_interface::ValueType`s of the arguments of the given host function `sig`.
+///
+/// Arguments that are passed as two `u64` FFI values map to two `ValueType::I64`.
+fn generate_wasm_interface_argument_types<'a>(
+	sig: &'a Signature,
+) -> impl Iterator<Item = TokenStream> + 'a {
+	let crate_ = generate_crate_access();
+
+	get_function_argument_types_without_ref(sig)
+		.zip(get_function_arguments_passed_as_two_u64(sig))
+		.map(move |(ty, two_u64)| if two_u64 {
+			quote! {
+				#crate_::sp_wasm_interface::ValueType::I64,
+				#crate_::sp_wasm_interface::ValueType::I64
+			}
+		} else {
+			quote! {
+				<<#ty as #crate_::RIType>::FFIType as #crate_::sp_wasm_interface::IntoValue>::VALUE_TYPE
+			}
+		})
+}
+
 /// Generate the code that converts the wasm values given to `HostFunctions::execute` into the FFI
 /// values.
 fn generate_wasm_to_ffi_values<'a>(
@@ -279,7 +385,8 @@ fn generate_wasm_to_ffi_values<'a>(
 	);
 
 	get_function_argument_names_and_types_without_ref(sig)
-		.map(move |(name, ty)| {
+		.zip(get_function_arguments_passed_as_two_u64(sig))
+		.map(move |((name, ty), two_u64)| {
 			let try_from_error = format!(
 				"Could not instantiate `{}` from wasm value while executing `{}` from interface `{}`!",
 				name.to_token_stream(),
@@ -287,6 +394,19 @@ fn generate_wasm_to_ffi_values<'a>(
 				trait_name,
 			);
 
+			if two_u64 {
+				let (low, high) = create_two_u64_idents(&name)?;
+
+				return Ok(quote! {
+					let val = args.next().ok_or_else(|| #error_message)?;
+					let #low = <u64 as #crate_::sp_wasm_interface::TryFromValue>::try_from_value(val)
+						.ok_or_else(|| #try_from_error)?;
+					let val = args.next().ok_or_else(|| #error_message)?;
+					let #high = <u64 as #crate_::sp_wasm_interface::TryFromValue>::try_from_value(val)
+						.ok_or_else(|| #try_from_error)?;
+				})
+			}
+
 			let var_name = generate_ffi_value_var_name(&name)?;
 
 			Ok(quote! {
@@ -307,7 +427,18 @@ fn generate_ffi_to_host_value<'a>(
 
 	get_function_argument_names_and_types_without_ref(sig)
 		.zip(mut_access.map(|v| v.and_then(|m| m.1)))
-		.map(move |((name, ty), mut_access)| {
+		.zip(get_function_arguments_passed_as_two_u64(sig))
+		.map(move |(((name, ty), mut_access), two_u64)| {
+			if two_u64 {
+				let (low, high) = create_two_u64_idents(&name)?;
+
+				return Ok(
+					quote! {
+						let #name = #crate_::join_u128(#low, #high) as #ty;
+					}
+				)
+			}
+
 			let ffi_value_var_name = generate_ffi_value_var_name(&name)?;
 
 			Ok(
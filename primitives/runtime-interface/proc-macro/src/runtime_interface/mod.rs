@@ -18,7 +18,7 @@ use crate::utils::generate_runtime_interface_include;
 
 use proc_macro2::{Span, TokenStream};
 
-use syn::{Ident, ItemTrait, Result};
+use syn::{Ident, ItemTrait, Result, Token, parse::{Parse, ParseStream}};
 
 use inflector::Inflector;
 
@@ -32,18 +32,62 @@ mod trait_decl_impl;
 pub mod keywords {
 	// Custom keyword `wasm_only` that can be given as attribute to [`runtime_interface`].
 	syn::custom_keyword!(wasm_only);
+	// Custom keyword `generate_metadata` that can be given as attribute to [`runtime_interface`].
+	syn::custom_keyword!(generate_metadata);
+}
+
+/// The options that can be given as attribute to [`runtime_interface`].
+///
+/// Supports a comma separated list of `wasm_only` and `generate_metadata`.
+#[derive(Default)]
+pub struct Options {
+	/// Is this an wasm only interface?
+	pub wasm_only: bool,
+	/// Should the static host function metadata be generated?
+	pub generate_metadata: bool,
+}
+
+impl Parse for Options {
+	fn parse(input: ParseStream) -> Result<Self> {
+		let mut res = Self::default();
+
+		while !input.is_empty() {
+			let lookahead = input.lookahead1();
+			if lookahead.peek(keywords::wasm_only) {
+				let _ = input.parse::<keywords::wasm_only>();
+				res.wasm_only = true;
+			} else if lookahead.peek(keywords::generate_metadata) {
+				let _ = input.parse::<keywords::generate_metadata>();
+				res.generate_metadata = true;
+			} else {
+				return Err(lookahead.error());
+			}
+
+			if !input.is_empty() {
+				input.parse::<Token![,]>()?;
+			}
+		}
+
+		Ok(res)
+	}
 }
 
 /// Implementation of the `runtime_interface` attribute.
 ///
-/// It expects the trait definition the attribute was put above and if this should be an wasm only
-/// interface.
-pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Result<TokenStream> {
+/// It expects the trait definition the attribute was put above and the options given to the
+/// attribute.
+pub fn runtime_interface_impl(trait_def: ItemTrait, options: Options) -> Result<TokenStream> {
+	let is_wasm_only = options.wasm_only;
 	let bare_functions = bare_function_interface::generate(&trait_def, is_wasm_only)?;
 	let crate_include = generate_runtime_interface_include();
 	let mod_name = Ident::new(&trait_def.ident.to_string().to_snake_case(), Span::call_site());
 	let trait_decl_impl = trait_decl_impl::process(&trait_def, is_wasm_only)?;
 	let host_functions = host_function_interface::generate(&trait_def, is_wasm_only)?;
+	let host_functions_metadata = if options.generate_metadata {
+		host_function_interface::generate_metadata(&trait_def)?
+	} else {
+		TokenStream::new()
+	};
 	let vis = trait_def.vis;
 	let attrs = &trait_def.attrs;
 
@@ -58,6 +102,8 @@ pub fn runtime_interface_impl(trait_def: ItemTrait, is_wasm_only: bool) -> Resul
 			#trait_decl_impl
 
 			#host_functions
+
+			#host_functions_metadata
 		}
 	};
 
@@ -26,7 +26,7 @@ use syn::{
 use proc_macro_crate::crate_name;
 
 use std::env;
-use std::collections::{BTreeMap, btree_map::Entry};
+use std::collections::{BTreeMap, BTreeSet, btree_map::Entry};
 
 use quote::quote;
 
@@ -36,6 +36,7 @@ use inflector::Inflector;
 pub struct RuntimeInterfaceFunction<'a> {
 	latest_version: u32,
 	versions: BTreeMap<u32, &'a TraitItemMethod>,
+	deprecated_versions: BTreeSet<u32>,
 }
 
 impl<'a> RuntimeInterfaceFunction<'a> {
@@ -47,6 +48,7 @@ impl<'a> RuntimeInterfaceFunction<'a> {
 				res.insert(version, trait_item);
 				res
 			},
+			deprecated_versions: BTreeSet::new(),
 		}
 	}
 
@@ -72,6 +74,11 @@ impl<'a> RuntimeInterface<'a> {
 	pub fn all_versions(&self) -> impl Iterator<Item = (u32, &TraitItemMethod)> {
 		self.items.iter().flat_map(|(_, item)| item.versions.iter()).map(|(v, i)| (*v, *i))
 	}
+
+	/// Returns if the given `version` of the function with the given `name` is deprecated.
+	pub fn is_deprecated(&self, name: &Ident, version: u32) -> bool {
+		self.items.get(name).map_or(false, |item| item.deprecated_versions.contains(&version))
+	}
  }
 
 /// Generates the include for the runtime-interface crate.
@@ -206,6 +213,38 @@ pub fn get_function_argument_types_ref_and_mut<'a>(
 		})
 }
 
+/// Returns if an argument of the given type is passed as two `u64` FFI values.
+///
+/// `u128` and `i128` arguments are split into their lower and upper 64 bits, instead of being
+/// passed as pointer to a 16 byte array. This only applies to arguments that are passed by value.
+pub fn is_passed_as_two_u64(ty: &Type) -> bool {
+	match ty {
+		Type::Path(type_path) => type_path.qself.is_none() &&
+			(type_path.path.is_ident("u128") || type_path.path.is_ident("i128")),
+		_ => false,
+	}
+}
+
+/// Returns for each function argument, minus the `self` arg, if it is passed as two `u64` FFI
+/// values.
+pub fn get_function_arguments_passed_as_two_u64<'a>(
+	sig: &'a Signature,
+) -> impl Iterator<Item = bool> + 'a {
+	get_function_argument_types(sig).map(|ty| is_passed_as_two_u64(&ty))
+}
+
+/// Create the identifiers of the lower and upper `u64` FFI values of an argument that is passed as
+/// two `u64` FFI values.
+pub fn create_two_u64_idents(pat: &Pat) -> Result<(Ident, Ident)> {
+	match pat {
+		Pat::Ident(pat_ident) => Ok((
+			Ident::new(&format!("{}_low", pat_ident.ident), Span::call_site()),
+			Ident::new(&format!("{}_high", pat_ident.ident), Span::call_site()),
+		)),
+		_ => Err(Error::new(pat.span(), "Not supported as variable name!")),
+	}
+}
+
 /// Returns an iterator over all trait methods for the given trait definition.
 fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &'a TraitItemMethod> {
 	trait_def
@@ -219,32 +258,41 @@ fn get_trait_methods<'a>(trait_def: &'a ItemTrait) -> impl Iterator<Item = &'a T
 
 /// Parse version attribute.
 ///
-/// Returns error if it is in incorrent format. Correct format is only `#[version(X)]`.
-fn parse_version_attribute(version: &Attribute) -> Result<u32> {
+/// Returns error if it is in incorrent format. Correct format is only `#[version(X)]` or
+/// `#[version(X, deprecated)]`.
+///
+/// Returns the version and if this version is deprecated.
+fn parse_version_attribute(version: &Attribute) -> Result<(u32, bool)> {
 	let meta = version.parse_meta()?;
 
 	let err = Err(Error::new(
 			meta.span(),
-			"Unexpected `version` attribute. The supported format is `#[version(1)]`",
+			"Unexpected `version` attribute. The supported formats are `#[version(1)]` and \
+			`#[version(1, deprecated)]`",
 		)
 	);
 
 	match meta {
 		Meta::List(list) => {
-			if list.nested.len() != 1 {
-				err
-			} else if let Some(NestedMeta::Lit(Lit::Int(i))) = list.nested.first() {
-				i.base10_parse()
-			} else {
-				err
+			let mut nested = list.nested.iter();
+			let version = match nested.next() {
+				Some(NestedMeta::Lit(Lit::Int(i))) => i.base10_parse()?,
+				_ => return err,
+			};
+
+			match (nested.next(), nested.next()) {
+				(None, None) => Ok((version, false)),
+				(Some(NestedMeta::Meta(Meta::Path(path))), None) if path.is_ident("deprecated") =>
+					Ok((version, true)),
+				_ => err,
 			}
 		},
 		_ => err,
 	}
 }
 
-/// Return item version (`#[version(X)]`) attribute, if present.
-fn get_item_version(item: &TraitItemMethod) -> Result<Option<u32>> {
+/// Return item version (`#[version(X)]`) attribute and if the version is deprecated, if present.
+fn get_item_version(item: &TraitItemMethod) -> Result<Option<(u32, bool)>> {
 	item.attrs.iter().find(|attr| attr.path.is_ident("version"))
 		.map(|attr| parse_version_attribute(attr))
 		.transpose()
@@ -258,10 +306,13 @@ pub fn get_runtime_interface<'a>(trait_def: &'a ItemTrait)
 
 	for item in get_trait_methods(trait_def) {
 		let name = item.sig.ident.clone();
-		let version = get_item_version(item)?.unwrap_or(1);
+		let (version, deprecated) = get_item_version(item)?.unwrap_or((1, false));
 
 		match functions.entry(name.clone()) {
-			Entry::Vacant(entry) => { entry.insert(RuntimeInterfaceFunction::new(version, item)); },
+			Entry::Vacant(entry) => {
+				let interface_item = entry.insert(RuntimeInterfaceFunction::new(version, item));
+				if deprecated { interface_item.deprecated_versions.insert(version); }
+			},
 			Entry::Occupied(mut entry) => {
 				if let Some(existing_item) = entry.get().versions.get(&version) {
 					let mut err = Error::new(
@@ -279,10 +330,21 @@ pub fn get_runtime_interface<'a>(trait_def: &'a ItemTrait)
 				let interface_item = entry.get_mut();
 				if interface_item.latest_version < version { interface_item.latest_version = version; }
 				interface_item.versions.insert(version, item);
+				if deprecated { interface_item.deprecated_versions.insert(version); }
 			}
 		}
 	}
 
+	for function in functions.values() {
+		if function.deprecated_versions.contains(&function.latest_version) {
+			let (_, item) = function.latest_version();
+			return Err(Error::new(
+				item.span(),
+				"The latest version of a function can not be deprecated",
+			));
+		}
+	}
+
 	for function in functions.values() {
 		let mut next_expected = 1;
 		for (version, item) in function.versions.iter() {
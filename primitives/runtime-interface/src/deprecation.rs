@@ -0,0 +1,72 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Call counters for deprecated host functions.
+//!
+//! Host function versions declared with `#[version(X, deprecated)]` are still provided to old
+//! runtimes, but every call is counted here. The counters can be read by the node to surface them
+//! in its metrics, so operators know when a deprecated host function is still in use.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use once_cell::sync::Lazy;
+
+static DEPRECATED_CALLS: Lazy<Mutex<BTreeMap<&'static str, u64>>> =
+	Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Note a call of the deprecated host function with the given `name`.
+///
+/// A warning is logged on the first call of each deprecated host function.
+pub fn note_deprecated_call(name: &'static str) {
+	let mut calls = match DEPRECATED_CALLS.lock() {
+		Ok(calls) => calls,
+		Err(poisoned) => poisoned.into_inner(),
+	};
+	let counter = calls.entry(name).or_insert(0);
+
+	if *counter == 0 {
+		log::warn!(
+			target: "runtime-interface",
+			"Deprecated host function `{}` called. Please upgrade the runtime.",
+			name,
+		);
+	}
+
+	*counter = counter.saturating_add(1);
+}
+
+/// Returns the number of calls of each deprecated host function that was called at least once.
+pub fn deprecated_calls() -> Vec<(&'static str, u64)> {
+	let calls = match DEPRECATED_CALLS.lock() {
+		Ok(calls) => calls,
+		Err(poisoned) => poisoned.into_inner(),
+	};
+
+	calls.iter().map(|(name, count)| (*name, *count)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn note_deprecated_call_counts_calls() {
+		note_deprecated_call("ext_test_deprecation_version_1");
+		note_deprecated_call("ext_test_deprecation_version_1");
+
+		assert!(deprecated_calls().contains(&("ext_test_deprecation_version_1", 2)));
+	}
+}
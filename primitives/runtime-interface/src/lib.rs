@@ -81,12 +81,12 @@
 //! | `u16` | `u16` | `Identity` |
 //! | `u32` | `u32` | `Identity` |
 //! | `u64` | `u64` | `Identity` |
-//! | `i128` | `u32` | `v.as_ptr()` (pointer to a 16 byte array) |
+//! | `i128` | `u32` | `v.as_ptr()` (pointer to a 16 byte array), see below for arguments |
 //! | `i8` | `i8` | `Identity` |
 //! | `i16` | `i16` | `Identity` |
 //! | `i32` | `i32` | `Identity` |
 //! | `i64` | `i64` | `Identity` |
-//! | `u128` | `u32` | `v.as_ptr()` (pointer to a 16 byte array) |
+//! | `u128` | `u32` | `v.as_ptr()` (pointer to a 16 byte array), see below for arguments |
 //! | `bool` | `u8` | `if v { 1 } else { 0 }` |
 //! | `&str` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
 //! | `&[u8]` | `u64` | <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
@@ -100,6 +100,10 @@
 //! | [`T where T: PassBy<PassBy=Codec>`](pass_by::Codec) | `u64`| <code>v.len() 32bit << 32 &#124; v.as_ptr() 32bit</code> |
 //!
 //! `Identity` means that the value is converted directly into the corresponding FFI type.
+//!
+//! `u128` and `i128` function arguments that are passed by value are not passed as pointer.
+//! Instead they are split into two `u64` FFI values, the lower and the upper 64 bits, to not
+//! require any allocation. Return values still use the pointer representation.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -280,6 +284,27 @@ pub use sp_std;
 /// 1. The generated functions are not callable from the native side.
 /// 2. The trait as shown above is not implemented for `Externalities` and is instead implemented
 ///    for `FunctionExecutor` (from `sp-wasm-interface`).
+///
+/// # Deprecated versions
+///
+/// An old version of a function can be marked as deprecated with `#[version(1, deprecated)]`.
+/// The deprecated version is still provided by the host, so old runtimes keep working, but every
+/// call from wasm is counted. The counters can be read with
+/// [`deprecated_calls`](deprecation::deprecated_calls). The latest version of a function can not be
+/// deprecated.
+///
+/// # Tracing
+///
+/// Every host function call is executed within a `tracing` span, see the [`trace`] module for
+/// more information. Tracing is opt-in and only done when a subscriber is interested in the spans.
+///
+/// # Host function metadata
+///
+/// The macro can be called like `#[runtime_interface(generate_metadata)]` to generate a static
+/// registry of the declared host functions. This adds a `HOST_FUNCTIONS_METADATA` constant that
+/// contains the name, version and FFI argument/return types of every host function and implements
+/// [`HostFunctionsMetadata`](metadata::HostFunctionsMetadata) for the generated `HostFunctions`.
+/// Options can be combined, e.g. `#[runtime_interface(wasm_only, generate_metadata)]`.
 pub use sp_runtime_interface_proc_macro::runtime_interface;
 
 #[doc(hidden)]
@@ -297,10 +322,16 @@ pub mod host;
 #[cfg(any(not(feature = "std"), doc))]
 pub mod wasm;
 pub mod pass_by;
+#[cfg(feature = "std")]
+pub mod metadata;
+#[cfg(feature = "std")]
+pub mod deprecation;
+#[cfg(feature = "std")]
+pub mod trace;
 
 mod util;
 
-pub use util::unpack_ptr_and_len;
+pub use util::{unpack_ptr_and_len, split_u128, join_u128};
 
 /// Something that can be used by the runtime interface as type to communicate between wasm and the
 /// host.
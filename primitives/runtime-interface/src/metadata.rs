@@ -0,0 +1,90 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Static metadata of the host functions declared by a runtime interface.
+//!
+//! The metadata is generated by `#[runtime_interface(generate_metadata)]` and can be used to check
+//! if the host functions imported by a wasm blob are provided by a given node binary.
+
+use sp_wasm_interface::ValueType;
+
+/// The metadata of a single host function.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HostFunctionMetadata {
+	/// The name of the host function, as imported by the wasm runtime.
+	pub name: &'static str,
+	/// The version of the host function.
+	pub version: u32,
+	/// The FFI types of the arguments.
+	pub args: &'static [ValueType],
+	/// The FFI type of the return value.
+	pub return_value: Option<ValueType>,
+}
+
+/// Something that provides the metadata of host functions.
+///
+/// Implemented by the `HostFunctions` struct of each runtime interface that was declared with
+/// `#[runtime_interface(generate_metadata)]`.
+pub trait HostFunctionsMetadata: 'static {
+	/// Returns the metadata of all host functions `Self` provides.
+	fn host_functions_metadata() -> Vec<HostFunctionMetadata>;
+}
+
+#[impl_trait_for_tuples::impl_for_tuples(30)]
+impl HostFunctionsMetadata for Tuple {
+	fn host_functions_metadata() -> Vec<HostFunctionMetadata> {
+		let mut metadata = Vec::new();
+
+		for_tuples!( #( metadata.extend(Tuple::host_functions_metadata()); )* );
+
+		metadata
+	}
+}
+
+/// Returns the names of all host functions in `required` that are not in `provided`.
+///
+/// `required` is usually the list of host function imports of a wasm blob.
+pub fn missing_host_functions<'a>(
+	provided: &[HostFunctionMetadata],
+	required: impl IntoIterator<Item = &'a str>,
+) -> Vec<&'a str> {
+	required.into_iter()
+		.filter(|name| !provided.iter().any(|p| p.name == *name))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const PROVIDED: &[HostFunctionMetadata] = &[
+		HostFunctionMetadata {
+			name: "ext_storage_get_version_1",
+			version: 1,
+			args: &[ValueType::I64],
+			return_value: Some(ValueType::I64),
+		},
+	];
+
+	#[test]
+	fn missing_host_functions_works() {
+		assert!(missing_host_functions(PROVIDED, vec!["ext_storage_get_version_1"]).is_empty());
+		assert_eq!(
+			missing_host_functions(PROVIDED, vec!["ext_storage_get_version_1", "ext_storage_get_version_2"]),
+			vec!["ext_storage_get_version_2"],
+		);
+	}
+}
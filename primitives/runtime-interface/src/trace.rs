@@ -0,0 +1,98 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Tracing of host function calls.
+//!
+//! Every call from wasm into a host function is wrapped into a `tracing` span with the target
+//! [`TRACE_TARGET`]. The span records the name of the host function and the number of bytes read
+//! from and written to the wasm memory while executing it. The bytes read are mostly the
+//! arguments passed by pointer. The execution time is measured by the span itself.
+//!
+//! Tracing is opt-in. If no subscriber is interested in [`TRACE_TARGET`], the host function is
+//! called directly. With the node's tracing infrastructure it is enabled by
+//! `--tracing-targets host_function`.
+
+use std::cell::Cell;
+
+use sp_wasm_interface::{FunctionContext, Pointer, Result, Sandbox, WordSize};
+
+/// The target of the spans created for host function calls.
+pub const TRACE_TARGET: &str = "host_function";
+
+/// A `FunctionContext` that counts the bytes read from and written to the wasm memory.
+struct RecordingFunctionContext<'a> {
+	inner: &'a mut dyn FunctionContext,
+	bytes_read: Cell<u64>,
+	bytes_written: u64,
+}
+
+impl<'a> FunctionContext for RecordingFunctionContext<'a> {
+	fn read_memory_into(&self, address: Pointer<u8>, dest: &mut [u8]) -> Result<()> {
+		self.bytes_read.set(self.bytes_read.get().saturating_add(dest.len() as u64));
+		self.inner.read_memory_into(address, dest)
+	}
+
+	fn write_memory(&mut self, address: Pointer<u8>, data: &[u8]) -> Result<()> {
+		self.bytes_written = self.bytes_written.saturating_add(data.len() as u64);
+		self.inner.write_memory(address, data)
+	}
+
+	fn allocate_memory(&mut self, size: WordSize) -> Result<Pointer<u8>> {
+		self.inner.allocate_memory(size)
+	}
+
+	fn deallocate_memory(&mut self, ptr: Pointer<u8>) -> Result<()> {
+		self.inner.deallocate_memory(ptr)
+	}
+
+	fn sandbox(&mut self) -> &mut dyn Sandbox {
+		self.inner.sandbox()
+	}
+}
+
+/// Call the host function with the given `name` within a tracing span.
+///
+/// `call` is executed with the given `context` directly, if the span is disabled.
+pub fn trace_host_function<R>(
+	name: &'static str,
+	context: &mut dyn FunctionContext,
+	call: impl FnOnce(&mut dyn FunctionContext) -> std::result::Result<R, String>,
+) -> std::result::Result<R, String> {
+	let span = tracing::span!(
+		target: TRACE_TARGET,
+		tracing::Level::TRACE,
+		"host_function",
+		name = name,
+		bytes_read = tracing::field::Empty,
+		bytes_written = tracing::field::Empty,
+	);
+
+	if span.is_disabled() {
+		return call(context)
+	}
+
+	let mut recording = RecordingFunctionContext {
+		inner: context,
+		bytes_read: Cell::new(0),
+		bytes_written: 0,
+	};
+	let res = span.in_scope(|| call(&mut recording));
+
+	span.record("bytes_read", &recording.bytes_read.get());
+	span.record("bytes_written", &recording.bytes_written);
+
+	res
+}
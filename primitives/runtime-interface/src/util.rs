@@ -41,9 +41,21 @@ pub fn unpack_ptr_and_len(val: u64) -> (u32, u32) {
 	(ptr, len)
 }
 
+/// Split an `u128` into its lower and upper 64 bits.
+///
+/// Used to pass `u128`/`i128` function arguments as two `u64` values between wasm and the host.
+pub fn split_u128(val: u128) -> (u64, u64) {
+	(val as u64, (val >> 64) as u64)
+}
+
+/// Join the lower and upper 64 bits of an `u128` that were split by [`split_u128`].
+pub fn join_u128(low: u64, high: u64) -> u128 {
+	(u128::from(high) << 64) | u128::from(low)
+}
+
 #[cfg(test)]
 mod tests {
-	use super::{pack_ptr_and_len, unpack_ptr_and_len};
+	use super::{pack_ptr_and_len, unpack_ptr_and_len, split_u128, join_u128};
 
 	#[test]
 	fn ptr_len_packing_unpacking() {
@@ -56,4 +68,17 @@ mod tests {
 		assert_eq!(PTR, ptr);
 		assert_eq!(LEN, len);
 	}
+
+	#[test]
+	fn u128_splitting_joining() {
+		for val in &[0u128, 1, u64::max_value() as u128, u64::max_value() as u128 + 1, u128::max_value()] {
+			let (low, high) = split_u128(*val);
+			assert_eq!(*val, join_u128(low, high));
+		}
+
+		for val in &[i128::min_value(), -1, 0, 1, i128::max_value()] {
+			let (low, high) = split_u128(*val as u128);
+			assert_eq!(*val, join_u128(low, high) as i128);
+		}
+	}
 }
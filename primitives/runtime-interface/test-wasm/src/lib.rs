@@ -104,6 +104,14 @@ pub trait TestApi {
 		val
 	}
 
+	/// Gets two `u128` surrounded by other arguments and returns `val - sub` if `marker` is `42`.
+	fn sub_u128_with_marker(marker: u32, val: u128, sub: u128, data: &[u8]) -> u128 {
+		assert_eq!(marker, 42);
+		assert_eq!(data, &[1, 2, 3]);
+		val - sub
+	}
+
+	#[version(1, deprecated)]
 	fn test_versionning(&self, data: u32) -> bool {
 		data == 42 || data == 50
 	}
@@ -217,6 +225,13 @@ wasm_export_functions! {
 		}
 	}
 
+	fn test_u128_split_into_two_u64_keeps_argument_order() {
+		assert_eq!(
+			u64::max_value() as u128,
+			test_api::sub_u128_with_marker(42, u128::max_value(), u128::max_value() - u64::max_value() as u128, &[1, 2, 3]),
+		);
+	}
+
 	fn test_vec_return_value_memory_is_freed() {
 		let mut len = 0;
 		for _ in 0..1024 {
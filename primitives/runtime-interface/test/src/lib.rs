@@ -120,6 +120,35 @@ fn test_u128_i128_as_parameter_and_return_value() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_u128_i128_as_parameter_and_return_value");
 }
 
+#[test]
+fn test_u128_split_into_two_u64_keeps_argument_order() {
+	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_u128_split_into_two_u64_keeps_argument_order");
+}
+
+#[test]
+fn test_u128_i128_arguments_are_passed_as_two_u64() {
+	use sp_wasm_interface::ValueType;
+
+	let host_functions = HostFunctions::host_functions();
+	let signature = |name: &str| host_functions.iter()
+		.find(|f| f.name() == name)
+		.expect("Host function exists")
+		.signature();
+
+	assert_eq!(
+		&signature("ext_test_api_get_and_return_u128_version_1").args[..],
+		&[ValueType::I64, ValueType::I64][..],
+	);
+	assert_eq!(
+		&signature("ext_test_api_get_and_return_i128_version_1").args[..],
+		&[ValueType::I64, ValueType::I64][..],
+	);
+	assert_eq!(
+		&signature("ext_test_api_sub_u128_with_marker_version_1").args[..],
+		&[ValueType::I32, ValueType::I64, ValueType::I64, ValueType::I64, ValueType::I64, ValueType::I64][..],
+	);
+}
+
 #[test]
 fn test_vec_return_value_memory_is_freed() {
 	call_wasm_method::<HostFunctions>(&WASM_BINARY[..], "test_vec_return_value_memory_is_freed");
@@ -150,3 +179,17 @@ fn test_versionining_with_new_host_works() {
 		"test_versionning_works",
 	);
 }
+
+#[test]
+fn test_deprecated_version_calls_are_counted() {
+	call_wasm_method::<HostFunctions>(
+		&WASM_BINARY_DEPRECATED[..],
+		"test_versionning_works",
+	);
+
+	assert!(
+		sp_runtime_interface::deprecation::deprecated_calls()
+			.iter()
+			.any(|(name, count)| *name == "ext_test_api_test_versionning_version_1" && *count > 0)
+	);
+}
@@ -0,0 +1,10 @@
+use sp_runtime_interface::runtime_interface;
+
+#[runtime_interface]
+trait Test {
+	fn test() { }
+	#[version(2, deprecated)]
+	fn test() { }
+}
+
+fn main() {}
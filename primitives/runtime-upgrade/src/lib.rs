@@ -0,0 +1,55 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime Apis to retrieve a runtime upgrade which is scheduled but not enacted yet, and to dry
+//! run the migrations of a new runtime.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Decode, Encode};
+use sp_runtime::RuntimeDebug;
+use sp_std::vec::Vec;
+
+/// A runtime upgrade scheduled to be enacted in a later block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct PendingUpgrade<BlockNumber> {
+	/// The block the new `:code` is set in.
+	pub at: BlockNumber,
+	/// The wasm blob of the new runtime.
+	pub code: Vec<u8>,
+}
+
+sp_api::decl_runtime_apis! {
+	/// The runtime upgrade api.
+	///
+	/// This api is used by the `client/upgrade-shadow` module to execute blocks against a pending
+	/// runtime before it is enacted, e.g. a `set_code` call waiting in the scheduler.
+	pub trait RuntimeUpgradeApi {
+		/// Retrieve the runtime upgrade which is scheduled but not enacted yet, if any.
+		fn pending_upgrade() -> Option<PendingUpgrade<sp_runtime::traits::NumberFor<Block>>>;
+	}
+
+	/// The migration api.
+	///
+	/// This api is used by the `dry-run-upgrade` command to execute the migrations of a new
+	/// runtime against the state of a live chain, without producing a block. Runtimes only
+	/// implement it in builds made for the dry runs, never in the ones deployed on chain.
+	pub trait MigrationApi {
+		/// Execute the `on_runtime_upgrade` hooks of all modules and return the weight they
+		/// consumed.
+		fn on_runtime_upgrade() -> u64;
+	}
+}
@@ -27,9 +27,20 @@ pub type Period = u64;
 /// Era phase
 pub type Phase = u64;
 
+/// The longest period of an era of the compact two byte encoding.
+pub const MAX_COMPACT_PERIOD: Period = 1 << 16;
+
+/// The longest period of an era.
+pub const LONG_ERA_MAX_PERIOD: Period = 1 << 24;
+
+/// The phases of the eras longer than `MAX_COMPACT_PERIOD` are multiples of this quantum, and so
+/// are the block numbers of their births.
+pub const LONG_ERA_PHASE_QUANTUM: Phase = 1 << 12;
+
 /// An era to describe the longevity of a transaction.
 #[derive(PartialEq, Eq, Clone, Copy, sp_core::RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(try_from = "UncheckedEra"))]
 pub enum Era {
 	/// The transaction is valid forever. The genesis hash must be present in the signed content.
 	Immortal,
@@ -42,10 +53,38 @@ pub enum Era {
 	/// `period` is.
 	///
 	/// When used on `FRAME`-based runtimes, `period` cannot exceed `BlockHashCount` parameter
-	/// of `system` module.
+	/// of `system` module, unless the era is longer than `MAX_COMPACT_PERIOD`: the births of
+	/// these are multiples of `LONG_ERA_PHASE_QUANTUM`, whose hashes the `system` module keeps
+	/// for `LONG_ERA_MAX_PERIOD` blocks.
+	Mortal(Period, Phase),
+}
+
+/// An era as deserialized, before its period and phase are checked.
+#[cfg(feature = "std")]
+#[derive(Deserialize)]
+#[serde(rename = "Era")]
+enum UncheckedEra {
+	Immortal,
 	Mortal(Period, Phase),
 }
 
+#[cfg(feature = "std")]
+impl std::convert::TryFrom<UncheckedEra> for Era {
+	type Error = &'static str;
+
+	fn try_from(era: UncheckedEra) -> Result<Self, Self::Error> {
+		let era = match era {
+			UncheckedEra::Immortal => Era::Immortal,
+			UncheckedEra::Mortal(period, phase) => Era::Mortal(period, phase),
+		};
+		if era.is_valid() {
+			Ok(era)
+		} else {
+			Err("Invalid period and phase")
+		}
+	}
+}
+
 /*
  * E.g. with period == 4:
  * 0         10        20        30        40
@@ -74,6 +113,59 @@ impl Era {
 		Era::Mortal(period, quantized_phase)
 	}
 
+	/// Create a new era based on a period (which should be a power of two between 4 and
+	/// `LONG_ERA_MAX_PERIOD` inclusive) and a block number on which it should start, or shortly
+	/// after the start.
+	///
+	/// Periods up to `MAX_COMPACT_PERIOD` make the same era as `mortal`. Longer periods start
+	/// on a multiple of `LONG_ERA_PHASE_QUANTUM`, up to that many blocks before `current`.
+	pub fn long_mortal(period: u64, current: u64) -> Self {
+		let period = period.checked_next_power_of_two()
+			.unwrap_or(LONG_ERA_MAX_PERIOD)
+			.max(4)
+			.min(LONG_ERA_MAX_PERIOD);
+		if period <= MAX_COMPACT_PERIOD {
+			return Self::mortal(period, current);
+		}
+		let phase = current % period;
+
+		Era::Mortal(period, phase / LONG_ERA_PHASE_QUANTUM * LONG_ERA_PHASE_QUANTUM)
+	}
+
+	/// Create a new era lasting at least `lifetime`, given the duration of the blocks, e.g. in
+	/// milliseconds, and the block number on which it should start.
+	///
+	/// Chains with short block times need eras of more blocks for the same lifetime, so these
+	/// may be longer than `MAX_COMPACT_PERIOD`. Returns `None` if the lifetime needs an era
+	/// longer than `LONG_ERA_MAX_PERIOD`.
+	pub fn mortal_for(lifetime: u64, block_time: u64, current: u64) -> Option<Self> {
+		let block_time = block_time.max(1);
+		let blocks = lifetime / block_time + if lifetime % block_time == 0 { 0 } else { 1 };
+		// the era may start a few blocks before `current`
+		[blocks, blocks.saturating_mul(2)].iter()
+			.filter(|period| **period <= LONG_ERA_MAX_PERIOD)
+			.map(|period| Self::long_mortal(*period, current))
+			.find(|era| era.death(current) - current >= blocks)
+	}
+
+	/// Whether the era can be encoded: its period is a power of two between 4 and
+	/// `LONG_ERA_MAX_PERIOD`, and its phase is smaller than the period and quantized like the
+	/// phases of `long_mortal` eras.
+	pub fn is_valid(&self) -> bool {
+		match *self {
+			Era::Immortal => true,
+			Era::Mortal(period, phase) => {
+				let quantum = if period > MAX_COMPACT_PERIOD {
+					LONG_ERA_PHASE_QUANTUM
+				} else {
+					(period >> 12).max(1)
+				};
+				period.is_power_of_two() && period >= 4 && period <= LONG_ERA_MAX_PERIOD
+					&& phase < period && phase % quantum == 0
+			},
+		}
+	}
+
 	/// Create an "immortal" transaction.
 	pub fn immortal() -> Self {
 		Era::Immortal
@@ -103,12 +195,29 @@ impl Era {
 			Era::Mortal(period, _) => self.birth(current) + period,
 		}
 	}
+
+	/// Get the estimated time at which the era has ended, given the time `now` of the block
+	/// `current` and the duration of the blocks, in the same unit.
+	pub fn death_time(self, current: u64, now: u64, block_time: u64) -> u64 {
+		match self {
+			Era::Immortal => u64::max_value(),
+			Era::Mortal(..) => self.death(current)
+				.saturating_sub(current)
+				.saturating_mul(block_time)
+				.saturating_add(now),
+		}
+	}
 }
 
 impl Encode for Era {
 	fn encode_to<T: Output>(&self, output: &mut T) {
 		match self {
 			Era::Immortal => output.push_byte(0),
+			Era::Mortal(period, phase) if *period > MAX_COMPACT_PERIOD => {
+				// the low four bits are zero, which the compact encoding never has
+				output.push_byte(((period.trailing_zeros() - 16) << 4) as u8);
+				output.push(&((phase / LONG_ERA_PHASE_QUANTUM) as u16));
+			},
 			Era::Mortal(period, phase) => {
 				let quantize_factor = (*period as u64 >> 12).max(1);
 				let encoded = (period.trailing_zeros() - 1).max(1).min(15) as u16 | ((phase / quantize_factor) << 4) as u16;
@@ -125,6 +234,14 @@ impl Decode for Era {
 		let first = input.read_byte()?;
 		if first == 0 {
 			Ok(Era::Immortal)
+		} else if first % (1 << 4) == 0 {
+			let period = MAX_COMPACT_PERIOD << (first >> 4);
+			let phase = u16::decode(input)? as u64 * LONG_ERA_PHASE_QUANTUM;
+			if period <= LONG_ERA_MAX_PERIOD && phase < period {
+				Ok(Era::Mortal(period, phase))
+			} else {
+				Err("Invalid period and phase".into())
+			}
 		} else {
 			let encoded = first as u64 + ((input.read_byte()? as u64) << 8);
 			let period = 2 << (encoded % (1 << 4));
@@ -205,6 +322,49 @@ mod tests {
 		assert_ne!(e.birth(5), 6);
 	}
 
+	#[test]
+	fn long_mortal_codec_works() {
+		let e = Era::long_mortal(1 << 20, 3_000_000);
+		assert_eq!(e, Era::Mortal(1 << 20, 3_000_000 % (1 << 20) / 4096 * 4096));
+
+		let expected = vec![4 << 4, 220, 0];
+		assert_eq!(e.encode(), expected);
+		assert_eq!(e, Era::decode(&mut&expected[..]).unwrap());
+
+		assert_eq!(Era::long_mortal(1 << 30, 5), Era::Mortal(LONG_ERA_MAX_PERIOD, 0));
+		assert_eq!(Era::long_mortal(64, 42), Era::mortal(64, 42));
+		assert!(Era::decode(&mut&[9 << 4, 0, 0][..]).is_err());
+		assert!(Era::decode(&mut&[1 << 4, 32, 0][..]).is_err());
+	}
+
+	#[test]
+	fn mortal_for_lifetime_works() {
+		// 10 minutes of 3 second blocks
+		assert_eq!(Era::mortal_for(600_000, 3_000, 1000), Some(Era::mortal(256, 1000)));
+		// a year of 1 second blocks is too long
+		assert_eq!(Era::mortal_for(365 * 86_400_000, 1_000, 1000), None);
+		// a day of 1 second blocks
+		let e = Era::mortal_for(86_400_000, 1_000, 200_000).unwrap();
+		assert_eq!(e, Era::Mortal(1 << 17, 200_000 % (1 << 17) / 4096 * 4096));
+		assert!(e.death(200_000) - 200_000 >= 86_400);
+		assert_eq!(e.death_time(200_000, 5_000, 1_000), (e.death(200_000) - 200_000) * 1_000 + 5_000);
+		assert_eq!(Era::Immortal.death_time(1, 2, 3), u64::max_value());
+	}
+
+	#[test]
+	fn deserialize_checks_period_and_phase() {
+		let era = Era::long_mortal(1 << 20, 3_000_000);
+		let json = serde_json::to_string(&era).unwrap();
+		assert_eq!(serde_json::from_str::<Era>(&json).unwrap(), era);
+		assert_eq!(serde_json::from_str::<Era>("\"Immortal\"").unwrap(), Era::Immortal);
+
+		for invalid in &["[65537, 0]", "[3, 0]", "[0, 0]", "[64, 64]", "[131072, 5]", "[33554432, 0]"] {
+			let json = format!("{{\"Mortal\":{}}}", invalid);
+			assert!(serde_json::from_str::<Era>(&json).is_err(), "{} is invalid", json);
+		}
+		assert!(Era::mortal(64, 42).is_valid());
+	}
+
 	#[test]
 	fn current_less_than_phase() {
 		// should not panic
@@ -28,7 +28,9 @@ mod digest;
 mod tests;
 
 pub use self::unchecked_extrinsic::{UncheckedExtrinsic, SignedPayload};
-pub use self::era::{Era, Phase};
+pub use self::era::{
+	Era, Period, Phase, MAX_COMPACT_PERIOD, LONG_ERA_MAX_PERIOD, LONG_ERA_PHASE_QUANTUM,
+};
 pub use self::checked_extrinsic::CheckedExtrinsic;
 pub use self::header::Header;
 pub use self::block::{Block, SignedBlock, BlockId};
@@ -116,7 +116,7 @@ where
 	<Signature as traits::Verify>::Signer: IdentifyAccount<AccountId=AccountId>,
 	Extra: SignedExtension<AccountId=AccountId>,
 	AccountId: Member + MaybeDisplay,
-	Lookup: traits::Lookup<Source=Address, Target=AccountId>,
+	Lookup: traits::Lookup<Source=Address, Target=AccountId> + traits::VerifyAccountSignature<Signature>,
 {
 	type Checked = CheckedExtrinsic<AccountId, Call, Extra>;
 
@@ -126,7 +126,7 @@ where
 				let signed = lookup.lookup(signed)?;
 				let raw_payload = SignedPayload::new(self.function, extra)?;
 				if !raw_payload.using_encoded(|payload| {
-					signature.verify(payload, &signed)
+					lookup.verify_account_signature(&signature, payload, &signed)
 				}) {
 					return Err(InvalidTransaction::BadProof.into())
 				}
@@ -435,6 +435,45 @@ mod tests {
 		);
 	}
 
+	#[derive(Debug, Encode, Decode, Clone, Eq, PartialEq)]
+	struct EthereumExtra;
+	impl SignedExtension for EthereumExtra {
+		const IDENTIFIER: &'static str = "EthereumExtra";
+		type AccountId = crate::AccountId20;
+		type Call = ();
+		type AdditionalSigned = ();
+		type DispatchInfo = ();
+		type Pre = ();
+
+		fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+	}
+
+	#[test]
+	fn ethereum_signed_check_should_work() {
+		use sp_std::convert::TryFrom;
+		use sp_core::{ecdsa, Pair};
+		use crate::{AccountId20, EthereumSignature};
+		type EthereumEx = UncheckedExtrinsic<AccountId20, TestCall, EthereumSignature, EthereumExtra>;
+		type EthereumContext = IdentityLookup<AccountId20>;
+
+		let pair = ecdsa::Pair::from_string("//Alice", None).unwrap();
+		let account = AccountId20::try_from(pair.public()).unwrap();
+		let payload = (vec![0u8; 0], EthereumExtra).encode();
+		let signature = pair.sign_prehashed(&EthereumSignature::message_hash(&payload));
+
+		let ux = EthereumEx::new_signed(vec![0u8; 0], account, signature.clone().into(), EthereumExtra);
+		assert_eq!(
+			<EthereumEx as Checkable<EthereumContext>>::check(ux, &Default::default()),
+			Ok(CheckedExtrinsic { signed: Some((account, EthereumExtra)), function: vec![0u8; 0] }),
+		);
+
+		let ux = EthereumEx::new_signed(vec![1u8; 1], account, signature.into(), EthereumExtra);
+		assert_eq!(
+			<EthereumEx as Checkable<EthereumContext>>::check(ux, &Default::default()),
+			Err(InvalidTransaction::BadProof.into()),
+		);
+	}
+
 	#[test]
 	fn encoding_matches_vec() {
 		let ex = Ex::new_unsigned(vec![0u8; 0]);
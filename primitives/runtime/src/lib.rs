@@ -42,7 +42,7 @@ pub use sp_core::storage::{Storage, StorageChild};
 
 use sp_std::prelude::*;
 use sp_std::convert::TryFrom;
-use sp_core::{crypto, ed25519, sr25519, ecdsa, hash::{H256, H512}};
+use sp_core::{crypto, ed25519, sr25519, ecdsa, bls12_381, hash::{H256, H512}};
 use codec::{Encode, Decode};
 
 pub mod curve;
@@ -61,7 +61,7 @@ pub use crate::runtime_string::*;
 pub use generic::{DigestItem, Digest};
 
 /// Re-export this since it's part of the API of this crate.
-pub use sp_core::{TypeId, crypto::{key_types, KeyTypeId, CryptoType, AccountId32}};
+pub use sp_core::{TypeId, crypto::{key_types, KeyTypeId, CryptoType, AccountId32, AccountId20}};
 pub use sp_application_crypto::{RuntimeAppPublic, BoundToRuntimeAppPublic};
 
 /// Re-export `RuntimeDebug`, to avoid dependency clutter.
@@ -174,6 +174,10 @@ pub enum MultiSignature {
 	Sr25519(sr25519::Signature),
 	/// An ECDSA/SECP256k1 signature.
 	Ecdsa(ecdsa::Signature),
+	/// A BLS12-381 signature, only valid for the accounts registering a BLS12-381 key.
+	Bls12381(bls12_381::Signature),
+	/// A proof checked by a custom verifier, only valid for the accounts registering one.
+	Custom(Vec<u8>),
 }
 
 impl From<ed25519::Signature> for MultiSignature {
@@ -194,6 +198,12 @@ impl From<ecdsa::Signature> for MultiSignature {
 	}
 }
 
+impl From<bls12_381::Signature> for MultiSignature {
+	fn from(x: bls12_381::Signature) -> Self {
+		MultiSignature::Bls12381(x)
+	}
+}
+
 impl Default for MultiSignature {
 	fn default() -> Self {
 		MultiSignature::Ed25519(Default::default())
@@ -313,10 +323,76 @@ impl Verify for MultiSignature {
 					_ => false,
 				}
 			}
+			// The account of a BLS12-381 key or of a custom verifier isn't derived from it.
+			(MultiSignature::Bls12381(_), _) | (MultiSignature::Custom(_), _) => false,
+		}
+	}
+
+	fn batch_verify<L: Lazy<[u8]>>(&self, msg: L, signer: &AccountId32) -> bool {
+		use sp_core::crypto::Public;
+		match (self, signer) {
+			(MultiSignature::Ed25519(ref sig), who) =>
+				sig.batch_verify(msg, &ed25519::Public::from_slice(who.as_ref())),
+			(MultiSignature::Sr25519(ref sig), who) =>
+				sig.batch_verify(msg, &sr25519::Public::from_slice(who.as_ref())),
+			// ECDSA signatures are not batched, and the others don't verify against an account.
+			_ => self.verify(msg, signer),
+		}
+	}
+}
+
+/// An Ethereum compatible ECDSA/SECP256k1 signature, e.g. by MetaMask, of the keccak-256 hash of
+/// a message prefixed as in `personal_sign` (EIP-191), which is verified against `AccountId20`
+/// accounts.
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[derive(Eq, PartialEq, Clone, Encode, Decode, RuntimeDebug)]
+pub struct EthereumSignature(pub ecdsa::Signature);
+
+impl EthereumSignature {
+	/// The keccak-256 hash of `msg` signed by Ethereum accounts.
+	pub fn message_hash(msg: &[u8]) -> [u8; 32] {
+		let mut prefixed = b"\x19Ethereum Signed Message:\n".to_vec();
+		prefixed.extend(decimal(msg.len()));
+		prefixed.extend_from_slice(msg);
+		sp_io::hashing::keccak_256(&prefixed)
+	}
+}
+
+impl From<ecdsa::Signature> for EthereumSignature {
+	fn from(x: ecdsa::Signature) -> Self {
+		EthereumSignature(x)
+	}
+}
+
+impl Default for EthereumSignature {
+	fn default() -> Self {
+		EthereumSignature(ecdsa::Signature::from_raw([0; 65]))
+	}
+}
+
+impl Verify for EthereumSignature {
+	type Signer = AccountId20;
+	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &AccountId20) -> bool {
+		let hash = Self::message_hash(msg.get());
+		match sp_io::crypto::secp256k1_ecdsa_recover(self.0.as_ref(), &hash) {
+			Ok(public) => AccountId20::try_from(&sp_io::hashing::keccak_256(&public)[12..])
+				.map_or(false, |account| account == *signer),
+			Err(_) => false,
 		}
 	}
 }
 
+/// The decimal digits of `n`.
+fn decimal(mut n: usize) -> Vec<u8> {
+	let mut digits = Vec::new();
+	while digits.is_empty() || n > 0 {
+		digits.push(b'0' + (n % 10) as u8);
+		n /= 10;
+	}
+	digits.reverse();
+	digits
+}
+
 /// Signature verify that can work with any known signature types..
 #[derive(Eq, PartialEq, Clone, Default, Encode, Decode, RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -684,6 +760,28 @@ impl traits::Extrinsic for OpaqueExtrinsic {
 	type SignaturePayload = ();
 }
 
+/// A session of batch signature verification, see `Verify::batch_verify`.
+///
+/// The signatures pushed to the batch are verified in parallel on the host by `verify`.
+#[must_use = "`verify()` needs to be called to finish batch signature verification!"]
+pub struct SignatureBatching(());
+
+impl SignatureBatching {
+	/// Start a batch.
+	///
+	/// Panics if a batch is already started.
+	pub fn start() -> Self {
+		sp_io::crypto::start_batch_verify();
+		SignatureBatching(())
+	}
+
+	/// Verify all the signatures pushed to the batch since its start.
+	#[must_use]
+	pub fn verify(self) -> bool {
+		sp_io::crypto::finish_batch_verify()
+	}
+}
+
 /// Print something that implements `Printable` from the runtime.
 pub fn print(print: impl traits::Printable) {
 	print.print();
@@ -736,4 +834,25 @@ mod tests {
 		let multi_signer = MultiSigner::from(pair.public());
 		assert!(multi_sig.verify(msg, &multi_signer.into_account()));
 	}
+
+	#[test]
+	fn multi_signature_batch_verify_works() {
+		let msg = &b"test-message"[..];
+		let (pair, _) = sr25519::Pair::generate();
+		let multi_sig = MultiSignature::from(pair.sign(&msg));
+		let signer = MultiSigner::from(pair.public()).into_account();
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let batching = SignatureBatching::start();
+			assert!(multi_sig.batch_verify(msg, &signer));
+			assert!(batching.verify());
+
+			let batching = SignatureBatching::start();
+			// The invalid signature is only detected at the end of the batch.
+			assert!(multi_sig.batch_verify(&b"other-message"[..], &signer));
+			assert!(!batching.verify());
+
+			assert!(!multi_sig.batch_verify(&b"other-message"[..], &signer));
+		});
+	}
 }
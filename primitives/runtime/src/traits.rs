@@ -141,12 +141,30 @@ impl IdentifyAccount for sp_core::ecdsa::Public {
 	fn into_account(self) -> Self { self }
 }
 
+impl IdentifyAccount for sp_core::crypto::AccountId20 {
+	type AccountId = Self;
+	fn into_account(self) -> Self { self }
+}
+
 /// Means of signature verification.
 pub trait Verify {
 	/// Type of the signer.
 	type Signer: IdentifyAccount;
 	/// Verify a signature. Return `true` if signature is valid for the value.
 	fn verify<L: Lazy<[u8]>>(&self, msg: L, signer: &<Self::Signer as IdentifyAccount>::AccountId) -> bool;
+
+	/// Verify a signature, or push it to the batch started by `SignatureBatching`. Return `true`
+	/// if the signature is valid for the value or batched.
+	///
+	/// The result of a batch is only known at its end, so it must only be used where an invalid
+	/// signature fails the whole batch, e.g. the signature of an extrinsic of an imported block.
+	fn batch_verify<L: Lazy<[u8]>>(
+		&self,
+		msg: L,
+		signer: &<Self::Signer as IdentifyAccount>::AccountId,
+	) -> bool {
+		self.verify(msg, signer)
+	}
 }
 
 impl Verify for sp_core::ed25519::Signature {
@@ -154,6 +172,10 @@ impl Verify for sp_core::ed25519::Signature {
 	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &sp_core::ed25519::Public) -> bool {
 		sp_io::crypto::ed25519_verify(self, msg.get(), signer)
 	}
+
+	fn batch_verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &sp_core::ed25519::Public) -> bool {
+		sp_io::crypto::ed25519_batch_verify(self, msg.get(), signer)
+	}
 }
 
 impl Verify for sp_core::sr25519::Signature {
@@ -161,6 +183,10 @@ impl Verify for sp_core::sr25519::Signature {
 	fn verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &sp_core::sr25519::Public) -> bool {
 		sp_io::crypto::sr25519_verify(self, msg.get(), signer)
 	}
+
+	fn batch_verify<L: Lazy<[u8]>>(&self, mut msg: L, signer: &sp_core::sr25519::Public) -> bool {
+		sp_io::crypto::sr25519_batch_verify(self, msg.get(), signer)
+	}
 }
 
 impl Verify for sp_core::ecdsa::Signature {
@@ -250,6 +276,14 @@ pub trait Lookup {
 	fn lookup(&self, s: Self::Source) -> Result<Self::Target, LookupError>;
 }
 
+/// Means of verifying the signatures of accounts in the context of a `Lookup`, e.g. through a
+/// verification method registered by the account.
+pub trait VerifyAccountSignature<Signature>: Lookup {
+	/// Verify `signature` of `msg` by the account `signer`. Return `true` if it's valid or batched,
+	/// see `Verify::batch_verify`.
+	fn verify_account_signature(&self, signature: &Signature, msg: &[u8], signer: &Self::Target) -> bool;
+}
+
 /// Means of changing one type into another in a manner dependent on the source type.
 /// This variant is different to `Lookup` in that it doesn't (can cannot) require any
 /// context.
@@ -280,6 +314,15 @@ impl<T> Lookup for IdentityLookup<T> {
 	fn lookup(&self, x: T) -> Result<T, LookupError> { Ok(x) }
 }
 
+impl<T, S> VerifyAccountSignature<S> for IdentityLookup<T> where
+	S: Verify,
+	S::Signer: IdentifyAccount<AccountId = T>,
+{
+	fn verify_account_signature(&self, signature: &S, msg: &[u8], signer: &T) -> bool {
+		signature.batch_verify(msg, signer)
+	}
+}
+
 /// Extensible conversion trait. Generic over both source and destination types.
 pub trait Convert<A, B> {
 	/// Make conversion.
@@ -713,6 +756,19 @@ pub trait Dispatchable {
 	fn dispatch(self, origin: Self::Origin) -> crate::DispatchResult;
 }
 
+/// Metadata of a [`SignedExtension`], describing its encoding for the clients.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SignedExtensionMetadata {
+	/// The unique identifier of the extension.
+	pub identifier: &'static str,
+	/// The type of the data of the extension in the extrinsic.
+	pub ty: &'static str,
+	/// The type of the additional signed data of the extension.
+	pub additional_signed: &'static str,
+	/// Whether the data of the extension is optional, encoded as an `Option` of `ty`.
+	pub optional: bool,
+}
+
 /// Means by which a transaction may be extended. This type embodies both the data and the logic
 /// that should be additionally associated with the transaction. It should be plain old data.
 pub trait SignedExtension: Codec + Debug + Sync + Send + Clone + Eq + PartialEq {
@@ -830,6 +886,24 @@ pub trait SignedExtension: Codec + Debug + Sync + Send + Clone + Eq + PartialEq
 	fn identifier() -> Vec<&'static str> {
 		sp_std::vec![Self::IDENTIFIER]
 	}
+
+	/// Returns the metadata of this signed extension.
+	///
+	/// As with [`identifier`](SignedExtension::identifier), a tuple returns the metadata of each of
+	/// its `SignedExtension`s in order. The types are named as in the rest of the metadata, e.g.
+	/// `Compact<T::Index>`, rather than by `type_name`, whose output isn't stable across compiler
+	/// versions.
+	///
+	/// The default implementation describes an extension without data nor additional signed
+	/// data. Extensions with either must override it.
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "()",
+			additional_signed: "()",
+			optional: false,
+		}]
+	}
 }
 
 #[impl_for_tuples(1, 12)]
@@ -895,6 +969,12 @@ impl<AccountId, Call, Info: Clone> SignedExtension for Tuple {
 		for_tuples!( #( ids.extend(Tuple::identifier()); )* );
 		ids
 	}
+
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		let mut metadata = Vec::new();
+		for_tuples!( #( metadata.extend(Tuple::metadata()); )* );
+		metadata
+	}
 }
 
 /// This trait allows a doughnut value to be deconstructed from an extrinsic's `SignedExtension` payload.
@@ -906,6 +986,8 @@ pub trait MaybeDoughnut {
 	type Doughnut: Send + Sync + PlugDoughnutApi;
 	/// Return the doughnut from the `SignedExtension` payload, if any
 	fn doughnut(self) -> Option<Self::Doughnut>;
+	/// Return a reference to the doughnut of the `SignedExtension` payload, if any
+	fn doughnut_ref(&self) -> Option<&Self::Doughnut>;
 }
 
 // Blanket impl for `Option<T: SignedExtension>`
@@ -922,12 +1004,24 @@ where
 	const IDENTIFIER: &'static str = "OptionSignedExtension";
 
 	fn additional_signed(&self) -> Result<Self::AdditionalSigned, TransactionValidityError> { Ok(()) }
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		T::metadata()
+			.into_iter()
+			.map(|metadata| SignedExtensionMetadata { optional: true, additional_signed: "()", ..metadata })
+			.collect()
+	}
 	fn validate(&self, who: &Self::AccountId, call: &Self::Call, info: Self::DispatchInfo, len: usize) -> Result<ValidTransaction, TransactionValidityError> {
 		if let Some(inner) = self {
 			return inner.validate(who, call, info, len)
 		}
 		Ok(ValidTransaction::default())
 	}
+	fn pre_dispatch(&self, who: &Self::AccountId, call: &Self::Call, info: Self::DispatchInfo, len: usize) -> Result<Self::Pre, TransactionValidityError> {
+		if let Some(inner) = self {
+			return inner.pre_dispatch(who, call, info, len)
+		}
+		Ok(Self::Pre::default())
+	}
 }
 
 /// Only for bare bone testing when you don't care about signed extensions at all.
@@ -1039,8 +1133,8 @@ pub trait OpaqueKeys: Clone {
 	fn get<T: Decode>(&self, i: super::KeyTypeId) -> Option<T> {
 		T::decode(&mut self.get_raw(i)).ok()
 	}
-	/// Verify a proof of ownership for the keys.
-	fn ownership_proof_is_valid(&self, _proof: &[u8]) -> bool { true }
+	/// Verify a proof of ownership for the keys, made by signing `message` with every key.
+	fn ownership_proof_is_valid(&self, _message: &[u8], _proof: &[u8]) -> bool { true }
 }
 
 /// Input that adds infinite number of zero after wrapped input.
@@ -1250,6 +1344,36 @@ macro_rules! impl_opaque_keys {
 				$crate::codec::Encode::encode(&keys)
 			}
 
+			/// Sign `message` with every key to prove possession of the keys.
+			///
+			/// The private keys are requested from the keystore.
+			///
+			/// Returns the concatenated SCALE encoded signatures, or `None` if a key could not sign.
+			pub fn ownership_proof(&self, message: &[u8]) -> Option<$crate::sp_std::vec::Vec<u8>> {
+				let mut proof = $crate::sp_std::vec::Vec::new();
+				$(
+					$crate::codec::Encode::encode_to(
+						&$crate::RuntimeAppPublic::sign(&self.$field, &message)?,
+						&mut proof,
+					);
+				)*
+				Some(proof)
+			}
+
+			/// Generate a set of keys with optionally using the given seed, and prove possession of
+			/// the keys by signing `message`.
+			///
+			/// Returns the concatenated SCALE encoded public keys and the ownership proof (see
+			/// [`Self::ownership_proof`]).
+			pub fn generate_with_proof(
+				seed: Option<$crate::sp_std::vec::Vec<u8>>,
+				message: &[u8],
+			) -> Option<($crate::sp_std::vec::Vec<u8>, $crate::sp_std::vec::Vec<u8>)> {
+				let encoded = Self::generate(seed);
+				let keys = <Self as $crate::codec::Decode>::decode(&mut &encoded[..]).ok()?;
+				keys.ownership_proof(message).map(|proof| (encoded, proof))
+			}
+
 			/// Converts `Self` into a `Vec` of `(raw public key, KeyTypeId)`.
 			pub fn into_raw_public_keys(
 				self,
@@ -1310,6 +1434,27 @@ macro_rules! impl_opaque_keys {
 					_ => &[],
 				}
 			}
+
+			fn ownership_proof_is_valid(&self, message: &[u8], mut proof: &[u8]) -> bool {
+				$(
+					let signature = <
+						<
+							<
+								$type as $crate::BoundToRuntimeAppPublic
+							>::Public as $crate::RuntimeAppPublic
+						>::Signature as $crate::codec::Decode
+					>::decode(&mut proof);
+					match signature {
+						Ok(signature) if $crate::RuntimeAppPublic::verify(
+							&self.$field,
+							&message,
+							&signature,
+						) => (),
+						_ => return false,
+					}
+				)*
+				proof.is_empty()
+			}
 		}
 	};
 }
@@ -1385,6 +1530,9 @@ macro_rules! tuple_impl_indexed {
 			fn doughnut(self) -> Option<Self::Doughnut> {
 				self.0
 			}
+			fn doughnut_ref(&self) -> Option<&Self::Doughnut> {
+				self.0.as_ref()
+			}
 		}
 	};
 	([$($direct:ident)+] [] ; [$($index:tt,)+] []) => {
@@ -1458,6 +1606,23 @@ mod tests {
 
 	type AccountId = u64;
 
+	crate::impl_opaque_keys! {
+		pub struct TestKeys {
+			pub dummy: crate::testing::UintAuthorityId,
+		}
+	}
+
+	#[test]
+	fn ownership_proof_is_verified() {
+		let keys = TestKeys { dummy: crate::testing::UintAuthorityId(1) };
+		let proof = keys.ownership_proof(&1u64.encode()).unwrap();
+
+		assert!(keys.ownership_proof_is_valid(&1u64.encode(), &proof));
+		assert!(!keys.ownership_proof_is_valid(&2u64.encode(), &proof));
+		assert!(!keys.ownership_proof_is_valid(&1u64.encode(), &proof[1..]));
+		assert!(!keys.ownership_proof_is_valid(&1u64.encode(), &[&proof[..], &[0]].concat()));
+	}
+
 	#[test]
 	fn into_account_should_work() {
 		let r: AccountId = U32Value::into_account(&U32Value(0xdeadbeef));
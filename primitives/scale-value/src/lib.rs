@@ -0,0 +1,67 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dynamic SCALE decoding and encoding of values by type name.
+//!
+//! The types of the arguments of the calls and events in the runtime metadata are the type names
+//! declared by the pallets, e.g. `AccountId` or `Vec<(AccountId, Balance)>`. A `TypeRegistry`
+//! resolves these names, e.g. to the concrete types of the Plug runtime for the generic types of
+//! the pallets, and decodes SCALE encoded values of them into `Value`s, or encodes `Value`s back.
+//! `RuntimeTypes` reads the argument types of the calls and events from the metadata, to decode
+//! whole calls and events.
+//!
+//! Values convert to and from JSON: integers of up to 64 bits are numbers, larger integers are
+//! strings of their decimal digits and bytes are `0x` prefixed hex strings.
+
+#![warn(missing_docs)]
+
+mod metadata;
+mod registry;
+mod value;
+
+pub use metadata::{DecodedVariant, Field, RuntimeTypes};
+pub use registry::{Primitive, TypeDef, TypeRegistry};
+pub use value::Value;
+
+/// An error decoding or encoding a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(pub String);
+
+impl std::fmt::Display for Error {
+	fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+		f.write_str(&self.0)
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl From<String> for Error {
+	fn from(e: String) -> Self {
+		Error(e)
+	}
+}
+
+impl From<&str> for Error {
+	fn from(e: &str) -> Self {
+		Error(e.into())
+	}
+}
+
+impl From<codec::Error> for Error {
+	fn from(e: codec::Error) -> Self {
+		Error(e.what().into())
+	}
+}
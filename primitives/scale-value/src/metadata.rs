@@ -0,0 +1,224 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The argument types of the calls and events of a runtime, read from its metadata.
+
+use codec::Decode;
+use frame_metadata::{DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed};
+
+use crate::{Error, TypeRegistry, Value};
+
+/// Names and arguments of the variants of a module, by index.
+type ModuleVariants<A> = (String, Vec<(String, Vec<A>)>);
+
+/// An argument of a decoded call or event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+	/// The name of the argument, `None` for the arguments of events.
+	pub name: Option<String>,
+	/// The type of the argument, as declared by the pallet.
+	pub ty: String,
+	/// The decoded value.
+	pub value: Value,
+}
+
+/// A decoded call or event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedVariant {
+	/// The name of the module.
+	pub module: String,
+	/// The name of the call or event.
+	pub variant: String,
+	/// The arguments.
+	pub fields: Vec<Field>,
+}
+
+/// The argument types of the calls and events of a runtime, by index in its outer call and outer
+/// event.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeTypes {
+	/// The names and types of the arguments of the calls of the modules with calls.
+	pub calls: Vec<ModuleVariants<(String, String)>>,
+	/// The types of the arguments of the events of the modules with events.
+	pub events: Vec<ModuleVariants<String>>,
+}
+
+impl RuntimeTypes {
+	/// Read the types from the encoded metadata of a runtime.
+	pub fn from_metadata(metadata: &[u8]) -> Result<Self, Error> {
+		let modules: Vec<_> = match RuntimeMetadataPrefixed::decode(&mut &metadata[..])?.1 {
+			RuntimeMetadata::V13(metadata) => decoded(metadata.modules)?.into_iter()
+				.map(|module| (module.name, module.calls, module.event))
+				.collect(),
+			RuntimeMetadata::V11(metadata) => decoded(metadata.modules)?.into_iter()
+				.map(|module| (module.name, module.calls, module.event))
+				.collect(),
+			_ => return Err("Unsupported metadata version, expected V11 or V13".into()),
+		};
+
+		let mut types = RuntimeTypes::default();
+		for (name, calls, event) in modules {
+			let name = decoded(name)?;
+			if let Some(calls) = calls {
+				let calls = decoded(calls)?.into_iter()
+					.map(|call| -> Result<_, Error> {
+						let arguments = decoded(call.arguments)?.into_iter()
+							.map(|argument| Ok((decoded(argument.name)?, decoded(argument.ty)?)))
+							.collect::<Result<_, Error>>()?;
+						Ok((decoded(call.name)?, arguments))
+					})
+					.collect::<Result<_, _>>()?;
+				types.calls.push((name.clone(), calls));
+			}
+			if let Some(events) = event {
+				let events = decoded(events)?.into_iter()
+					.map(|event| -> Result<_, Error> { Ok((decoded(event.name)?, decoded(event.arguments)?)) })
+					.collect::<Result<_, _>>()?;
+				types.events.push((name, events));
+			}
+		}
+		Ok(types)
+	}
+
+	/// Decode an event of the outer event of the runtime.
+	pub fn decode_event(&self, registry: &TypeRegistry, input: &mut &[u8]) -> Result<DecodedVariant, Error> {
+		let arguments = |arguments: &[String]| arguments.iter().map(|ty| (None, ty.clone())).collect();
+		decode_variant(&self.events, "event", arguments, registry, input)
+	}
+
+	/// Decode a call of the outer call of the runtime.
+	pub fn decode_call(&self, registry: &TypeRegistry, input: &mut &[u8]) -> Result<DecodedVariant, Error> {
+		let arguments = |arguments: &[(String, String)]| arguments.iter()
+			.map(|(name, ty)| (Some(name.clone()), ty.clone()))
+			.collect();
+		decode_variant(&self.calls, "call", arguments, registry, input)
+	}
+}
+
+fn decode_variant<A>(
+	modules: &[ModuleVariants<A>],
+	kind: &str,
+	arguments: impl Fn(&[A]) -> Vec<(Option<String>, String)>,
+	registry: &TypeRegistry,
+	input: &mut &[u8],
+) -> Result<DecodedVariant, Error> {
+	let (module_index, variant_index) = (u8::decode(input)?, u8::decode(input)?);
+	let (module, variants) = modules.get(module_index as usize)
+		.ok_or_else(|| Error(format!("Unknown module {} of the {}", module_index, kind)))?;
+	let (variant, types) = variants.get(variant_index as usize)
+		.ok_or_else(|| Error(format!("Unknown {} {} of {}", kind, variant_index, module)))?;
+	let fields = arguments(types).into_iter()
+		.map(|(name, ty)| Ok(Field { value: registry.decode(&ty, input)?, name, ty }))
+		.collect::<Result<_, Error>>()
+		.map_err(|e| Error(format!("{} in the {} {}.{}", e, kind, module, variant)))?;
+
+	Ok(DecodedVariant { module: module.clone(), variant: variant.clone(), fields })
+}
+
+fn decoded<B, O>(value: DecodeDifferent<B, O>) -> Result<O, Error> {
+	match value {
+		DecodeDifferent::Decoded(value) => Ok(value),
+		DecodeDifferent::Encode(_) => Err("Metadata isn't decoded".into()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use codec::Encode;
+	use frame_metadata::{
+		EventMetadata, ExtrinsicMetadata, FnEncode, FunctionArgumentMetadata, FunctionMetadata,
+		ModuleMetadata, RuntimeMetadataLastVersion,
+	};
+
+	fn metadata() -> Vec<u8> {
+		let metadata: RuntimeMetadataPrefixed = RuntimeMetadataLastVersion {
+			modules: DecodeDifferent::Encode(&[
+				ModuleMetadata {
+					name: DecodeDifferent::Encode("Timestamp"),
+					storage: None,
+					calls: None,
+					event: None,
+					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
+				},
+				ModuleMetadata {
+					name: DecodeDifferent::Encode("Balances"),
+					storage: None,
+					calls: Some(DecodeDifferent::Encode(FnEncode(|| &[
+						FunctionMetadata {
+							name: DecodeDifferent::Encode("transfer"),
+							arguments: DecodeDifferent::Encode(&[
+								FunctionArgumentMetadata {
+									name: DecodeDifferent::Encode("dest"),
+									ty: DecodeDifferent::Encode("<T::Lookup as StaticLookup>::Source"),
+								},
+								FunctionArgumentMetadata {
+									name: DecodeDifferent::Encode("value"),
+									ty: DecodeDifferent::Encode("Compact<T::Balance>"),
+								},
+							]),
+							documentation: DecodeDifferent::Encode(&[]),
+						},
+					]))),
+					event: Some(DecodeDifferent::Encode(FnEncode(|| &[
+						EventMetadata {
+							name: DecodeDifferent::Encode("Transfer"),
+							arguments: DecodeDifferent::Encode(&["AccountId", "AccountId", "Balance"]),
+							documentation: DecodeDifferent::Encode(&[]),
+						},
+					]))),
+					constants: DecodeDifferent::Encode(FnEncode(|| &[])),
+					errors: DecodeDifferent::Encode(FnEncode(|| &[])),
+					permissions: DecodeDifferent::Encode(FnEncode(|| None)),
+				},
+			]),
+			extrinsic: ExtrinsicMetadata { version: 4, signed_extensions: vec![] },
+		}.into();
+		metadata.encode()
+	}
+
+	#[test]
+	fn calls_and_events_are_decoded() {
+		let types = RuntimeTypes::from_metadata(&metadata()).unwrap();
+		let registry = TypeRegistry::default();
+
+		let call = (0u8, 0u8, [1u8; 32], codec::Compact(100u128)).encode();
+		assert_eq!(types.decode_call(&registry, &mut &call[..]).unwrap(), DecodedVariant {
+			module: "Balances".into(),
+			variant: "transfer".into(),
+			fields: vec![
+				Field {
+					name: Some("dest".into()),
+					ty: "<T::Lookup as StaticLookup>::Source".into(),
+					value: Value::Bytes(vec![1; 32]),
+				},
+				Field { name: Some("value".into()), ty: "Compact<T::Balance>".into(), value: Value::U128(100) },
+			],
+		});
+
+		let event = (0u8, 0u8, [1u8; 32], [2u8; 32], 100u128).encode();
+		let decoded = types.decode_event(&registry, &mut &event[..]).unwrap();
+		assert_eq!((decoded.module.as_str(), decoded.variant.as_str()), ("Balances", "Transfer"));
+		assert_eq!(decoded.fields[2], Field { name: None, ty: "Balance".into(), value: Value::U128(100) });
+
+		assert_eq!(
+			types.decode_event(&registry, &mut &[1u8, 0][..]),
+			Err(Error("Unknown module 1 of the event".into())),
+		);
+	}
+}
@@ -0,0 +1,542 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Resolution of type names and SCALE decoding and encoding of their values.
+
+use std::collections::HashMap;
+
+use codec::{Compact, Decode, Encode};
+use sp_core::U256;
+
+use crate::{Error, Value};
+
+/// A primitive type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+	/// `bool`.
+	Bool,
+	/// `u8`.
+	U8,
+	/// `u16`.
+	U16,
+	/// `u32`.
+	U32,
+	/// `u64`.
+	U64,
+	/// `u128`.
+	U128,
+	/// `U256`.
+	U256,
+}
+
+/// The definition of a named type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDef {
+	/// Another name of a type, e.g. `u128` for `Balance`.
+	Alias(String),
+	/// A primitive type.
+	Primitive(Primitive),
+	/// Bytes of a fixed length, e.g. 32 for `AccountId`.
+	FixedBytes(usize),
+	/// A struct, with the names and types of its fields.
+	Struct(Vec<(String, String)>),
+	/// An enum, with the names and the types of the values of its variants, by index.
+	Enum(Vec<(String, Option<String>)>),
+}
+
+/// The definitions of the named types.
+///
+/// Besides the named types, type names are primitive types, tuples, arrays, `Vec`, `Option`,
+/// `Compact` and `Box` of types, with an optional `T::` prefix. Named types with generic
+/// parameters are defined by their name without parameters, e.g. `BalanceOf` for `BalanceOf<T>`.
+#[derive(Debug, Clone)]
+pub struct TypeRegistry {
+	types: HashMap<String, TypeDef>,
+}
+
+impl TypeRegistry {
+	/// A registry of the primitive types only.
+	pub fn new() -> Self {
+		let mut registry = TypeRegistry { types: HashMap::new() };
+		for (name, primitive) in &[
+			("bool", Primitive::Bool),
+			("u8", Primitive::U8),
+			("u16", Primitive::U16),
+			("u32", Primitive::U32),
+			("u64", Primitive::U64),
+			("u128", Primitive::U128),
+			("U256", Primitive::U256),
+		] {
+			registry.register(*name, TypeDef::Primitive(*primitive));
+		}
+		registry
+	}
+
+	/// Define a named type, replacing its previous definition.
+	pub fn register(&mut self, name: impl Into<String>, def: TypeDef) -> &mut Self {
+		self.types.insert(name.into(), def);
+		self
+	}
+
+	/// The definition of a type name.
+	pub fn get(&self, name: &str) -> Option<&TypeDef> {
+		self.types.get(name)
+	}
+
+	/// Decode a value of the given type.
+	pub fn decode(&self, ty: &str, input: &mut &[u8]) -> Result<Value, Error> {
+		let ty = normalize(ty);
+
+		if let Some(types) = tuple(ty) {
+			return match types.len() {
+				0 => Ok(Value::Null),
+				_ => types.into_iter().map(|ty| self.decode(ty, input)).collect::<Result<_, _>>().map(Value::Seq),
+			};
+		}
+		if let Some((item, len)) = array(ty)? {
+			return match item {
+				"u8" => decode_bytes(len, input),
+				_ => (0..len).map(|_| self.decode(item, input)).collect::<Result<_, _>>().map(Value::Seq),
+			};
+		}
+
+		let (name, parameter) = generic(ty);
+		match (name, parameter) {
+			("Vec", Some("u8")) => {
+				let len = Compact::<u32>::decode(input)?.0;
+				decode_bytes(len as usize, input)
+			},
+			("Vec", Some(item)) => {
+				let len = Compact::<u32>::decode(input)?.0;
+				(0..len).map(|_| self.decode(item, input)).collect::<Result<_, _>>().map(Value::Seq)
+			},
+			("Option", Some(item)) => match u8::decode(input)? {
+				0 => Ok(Value::Null),
+				1 => self.decode(item, input),
+				_ => Err("Invalid Option".into()),
+			},
+			("Compact", Some(_)) => Ok(Value::U128(Compact::<u128>::decode(input)?.0)),
+			("Box", Some(item)) => self.decode(item, input),
+			_ => match self.resolve(ty, name)? {
+				TypeDef::Alias(ty) => self.decode(ty, input),
+				TypeDef::Primitive(primitive) => decode_primitive(*primitive, input),
+				TypeDef::FixedBytes(len) => decode_bytes(*len, input),
+				TypeDef::Struct(fields) => fields.iter()
+					.map(|(name, ty)| -> Result<_, Error> { Ok((name.clone(), self.decode(ty, input)?)) })
+					.collect::<Result<_, _>>()
+					.map(Value::Map),
+				TypeDef::Enum(variants) => {
+					let index = u8::decode(input)?;
+					match variants.get(index as usize) {
+						Some((name, None)) => Ok(Value::Str(name.clone())),
+						Some((name, Some(ty))) => Ok(Value::Map(vec![(name.clone(), self.decode(ty, input)?)])),
+						None => Err(format!("Invalid {}", name).into()),
+					}
+				},
+			},
+		}
+	}
+
+	/// Encode a value of the given type.
+	pub fn encode(&self, ty: &str, value: &Value) -> Result<Vec<u8>, Error> {
+		let mut output = Vec::new();
+		self.encode_to(ty, value, &mut output)?;
+		Ok(output)
+	}
+
+	fn encode_to(&self, ty: &str, value: &Value, output: &mut Vec<u8>) -> Result<(), Error> {
+		let ty = normalize(ty);
+		let mismatch = || Error(format!("Expected a value of `{}`", ty));
+
+		if let Some(types) = tuple(ty) {
+			return match (types.len(), value) {
+				(0, Value::Null) => Ok(()),
+				(len, Value::Seq(items)) if items.len() == len => types.into_iter()
+					.zip(items)
+					.map(|(ty, item)| self.encode_to(ty, item, output))
+					.collect(),
+				_ => Err(mismatch()),
+			};
+		}
+		if let Some((item, len)) = array(ty)? {
+			return match (item, value) {
+				("u8", Value::Bytes(bytes)) if bytes.len() == len => {
+					output.extend_from_slice(bytes);
+					Ok(())
+				},
+				(_, Value::Seq(items)) if items.len() == len =>
+					items.iter().map(|value| self.encode_to(item, value, output)).collect(),
+				_ => Err(mismatch()),
+			};
+		}
+
+		let (name, parameter) = generic(ty);
+		match (name, parameter, value) {
+			("Vec", Some("u8"), Value::Bytes(bytes)) => {
+				bytes.encode_to(output);
+				Ok(())
+			},
+			("Vec", Some(item), Value::Seq(items)) => {
+				Compact(items.len() as u32).encode_to(output);
+				items.iter().map(|value| self.encode_to(item, value, output)).collect()
+			},
+			("Option", Some(_), Value::Null) => {
+				output.push(0);
+				Ok(())
+			},
+			("Option", Some(item), value) => {
+				output.push(1);
+				self.encode_to(item, value, output)
+			},
+			("Compact", Some(_), value) => {
+				Compact(value.as_u128().ok_or_else(mismatch)?).encode_to(output);
+				Ok(())
+			},
+			("Box", Some(item), value) => self.encode_to(item, value, output),
+			("Vec", _, _) => Err(mismatch()),
+			_ => match (self.resolve(ty, name)?, value) {
+				(TypeDef::Alias(ty), value) => self.encode_to(ty, value, output),
+				(TypeDef::Primitive(primitive), value) => encode_primitive(*primitive, value, output).ok_or_else(mismatch),
+				(TypeDef::FixedBytes(len), Value::Bytes(bytes)) if bytes.len() == *len => {
+					output.extend_from_slice(bytes);
+					Ok(())
+				},
+				(TypeDef::Struct(fields), Value::Map(values)) => fields.iter()
+					.map(|(name, ty)| {
+						let value = values.iter()
+							.find(|(field, _)| field == name)
+							.map(|(_, value)| value)
+							.ok_or_else(|| Error(format!("Missing field `{}` of `{}`", name, ty)))?;
+						self.encode_to(ty, value, output)
+					})
+					.collect(),
+				(TypeDef::Struct(fields), Value::Seq(values)) if values.len() == fields.len() => fields.iter()
+					.zip(values)
+					.map(|((_, ty), value)| self.encode_to(ty, value, output))
+					.collect(),
+				(TypeDef::Enum(variants), value) => {
+					let (variant, inner) = match value {
+						Value::Str(variant) => (variant, None),
+						Value::Map(values) if values.len() == 1 => (&values[0].0, Some(&values[0].1)),
+						_ => return Err(mismatch()),
+					};
+					let index = variants.iter()
+						.position(|(name, _)| name == variant)
+						.ok_or_else(|| Error(format!("Unknown variant `{}` of `{}`", variant, ty)))?;
+					output.push(index as u8);
+					match (&variants[index].1, inner) {
+						(None, None) | (None, Some(Value::Null)) => Ok(()),
+						(Some(ty), Some(value)) => self.encode_to(ty, value, output),
+						_ => Err(mismatch()),
+					}
+				},
+				_ => Err(mismatch()),
+			},
+		}
+	}
+
+	/// The definition of a type, by full name or by name without generic parameters.
+	fn resolve(&self, ty: &str, name: &str) -> Result<&TypeDef, Error> {
+		self.types.get(ty)
+			.or_else(|| self.types.get(name))
+			.ok_or_else(|| Error(format!("Unsupported type `{}`", ty)))
+	}
+}
+
+impl Default for TypeRegistry {
+	/// The primitive types and the types of the arguments of the calls and events of the Plug
+	/// runtime, with its concrete types for the generic types of the pallets.
+	///
+	/// `Doughnut` isn't defined: doughnuts aren't length prefixed and can't be decoded by type
+	/// name.
+	fn default() -> Self {
+		let alias = |ty: &str| TypeDef::Alias(ty.into());
+		let fields = |fields: &[(&str, &str)]| fields.iter().map(|(name, ty)| (name.to_string(), ty.to_string())).collect();
+		let variants = |variants: &[(&str, Option<&str>)]| variants.iter()
+			.map(|(name, ty)| (name.to_string(), ty.map(Into::into)))
+			.collect();
+
+		let mut registry = TypeRegistry::new();
+		for name in &[
+			"BlockNumber", "AssetId", "AccountIndex", "MemberCount", "PropIndex", "ProposalIndex",
+			"ReferendumIndex", "RegistrarIndex", "SessionIndex", "Weight",
+		] {
+			registry.register(*name, alias("u32"));
+		}
+		for name in &["Moment", "AuthorityWeight", "RoundIndex"] {
+			registry.register(*name, alias("u64"));
+		}
+		for name in &["Balance", "BalanceOf"] {
+			registry.register(*name, alias("u128"));
+		}
+		for name in &["AttestationTopic", "AttestationValue"] {
+			registry.register(*name, alias("U256"));
+		}
+		for name in &["Bytes", "OpaqueTimeSlot"] {
+			registry.register(*name, alias("Vec<u8>"));
+		}
+		registry.register("H160", TypeDef::FixedBytes(20));
+		for name in &["H256", "Hash", "CallHash", "AccountId", "ValidatorId", "AuthorityId"] {
+			registry.register(*name, TypeDef::FixedBytes(32));
+		}
+		registry
+			.register("Kind", TypeDef::FixedBytes(16))
+			.register("<T::Lookup as StaticLookup>::Source", alias("AccountId"))
+			.register("AuthorityList", alias("Vec<(AuthorityId, AuthorityWeight)>"))
+			.register("TaskAddress", alias("(BlockNumber, u32)"))
+			.register("sp_std::marker::PhantomData", alias("()"))
+			.register("AssetInfo", TypeDef::Struct(fields(&[("symbol", "Vec<u8>"), ("decimalPlaces", "u8")])))
+			.register("AssetOptions", TypeDef::Struct(fields(&[
+				("initialIssuance", "Compact<Balance>"),
+				("permissions", "PermissionLatest"),
+			])))
+			.register("PermissionLatest", TypeDef::Struct(fields(&[
+				("update", "Owner"),
+				("mint", "Owner"),
+				("burn", "Owner"),
+			])))
+			.register("Owner", TypeDef::Enum(variants(&[("none", None), ("address", Some("AccountId"))])))
+			.register("IdentificationTuple", alias("(ValidatorId, FullIdentification)"))
+			.register("FullIdentification", alias("Exposure"))
+			.register("Exposure", TypeDef::Struct(fields(&[
+				("total", "Compact<Balance>"),
+				("own", "Compact<Balance>"),
+				("others", "Vec<IndividualExposure>"),
+			])))
+			.register("IndividualExposure", TypeDef::Struct(fields(&[("who", "AccountId"), ("value", "Compact<Balance>")])))
+			.register("Log", TypeDef::Struct(fields(&[("address", "H160"), ("topics", "Vec<H256>"), ("data", "Vec<u8>")])))
+			.register("Timepoint", TypeDef::Struct(fields(&[("height", "BlockNumber"), ("index", "u32")])))
+			.register("DispatchInfo", TypeDef::Struct(fields(&[
+				("weight", "Weight"),
+				("class", "DispatchClass"),
+				("paysFee", "bool"),
+			])))
+			.register("DispatchClass", TypeDef::Enum(variants(&[("normal", None), ("operational", None)])))
+			.register("DispatchError", TypeDef::Enum(variants(&[
+				("other", None),
+				("cannotLookup", None),
+				("badOrigin", None),
+				("module", Some("ModuleError")),
+			])))
+			.register("ModuleError", TypeDef::Struct(fields(&[("index", "u8"), ("error", "u8")])))
+			.register("DispatchResult", TypeDef::Enum(variants(&[("ok", Some("()")), ("err", Some("DispatchError"))])))
+			.register("VoteThreshold", TypeDef::Enum(variants(&[
+				("superMajorityApprove", None),
+				("superMajorityAgainst", None),
+				("simpleMajority", None),
+			])));
+		registry
+	}
+}
+
+/// The type name, without whitespace around it and without `T::` prefix.
+fn normalize(ty: &str) -> &str {
+	let ty = ty.trim();
+	if ty.starts_with("T::") { &ty[3..] } else { ty }
+}
+
+/// The types of a tuple type.
+fn tuple(ty: &str) -> Option<Vec<&str>> {
+	if ty.starts_with('(') && ty.ends_with(')') {
+		Some(split_types(&ty[1..ty.len() - 1]))
+	} else {
+		None
+	}
+}
+
+/// The item type and the length of an array type.
+fn array(ty: &str) -> Result<Option<(&str, usize)>, Error> {
+	if !(ty.starts_with('[') && ty.ends_with(']')) {
+		return Ok(None);
+	}
+	let mut parts = ty[1..ty.len() - 1].splitn(2, ';');
+	let (item, len) = (parts.next().unwrap_or_default().trim(), parts.next().unwrap_or_default().trim());
+	let len = len.parse().map_err(|_| Error(format!("Unsupported type `{}`", ty)))?;
+	Ok(Some((item, len)))
+}
+
+/// The name and the generic parameter of a type.
+fn generic(ty: &str) -> (&str, Option<&str>) {
+	match ty.find('<') {
+		Some(i) if i > 0 && ty.ends_with('>') => (&ty[..i], Some(ty[i + 1..ty.len() - 1].trim())),
+		_ => (ty, None),
+	}
+}
+
+/// Split the types of a tuple.
+pub(crate) fn split_types(types: &str) -> Vec<&str> {
+	let (mut depth, mut start, mut split) = (0, 0, Vec::new());
+	for (i, c) in types.char_indices() {
+		match c {
+			'<' | '(' | '[' => depth += 1,
+			'>' | ')' | ']' => depth -= 1,
+			',' if depth == 0 => {
+				split.push(types[start..i].trim());
+				start = i + 1;
+			},
+			_ => {},
+		}
+	}
+	split.push(types[start..].trim());
+	split.into_iter().filter(|ty| !ty.is_empty()).collect()
+}
+
+fn decode_bytes(len: usize, input: &mut &[u8]) -> Result<Value, Error> {
+	if input.len() < len {
+		return Err("Not enough data to fill buffer".into());
+	}
+	let (bytes, rest) = input.split_at(len);
+	*input = rest;
+	Ok(Value::Bytes(bytes.to_vec()))
+}
+
+fn decode_primitive(primitive: Primitive, input: &mut &[u8]) -> Result<Value, Error> {
+	Ok(match primitive {
+		Primitive::Bool => Value::Bool(bool::decode(input)?),
+		Primitive::U8 => Value::U64(u8::decode(input)?.into()),
+		Primitive::U16 => Value::U64(u16::decode(input)?.into()),
+		Primitive::U32 => Value::U64(u32::decode(input)?.into()),
+		Primitive::U64 => Value::U64(u64::decode(input)?),
+		Primitive::U128 => Value::U128(u128::decode(input)?),
+		Primitive::U256 => Value::U256(U256::decode(input)?),
+	})
+}
+
+/// Encode a value of a primitive type, `None` if it's out of the range of the type.
+fn encode_primitive(primitive: Primitive, value: &Value, output: &mut Vec<u8>) -> Option<()> {
+	use std::convert::TryFrom;
+
+	match (primitive, value) {
+		(Primitive::Bool, Value::Bool(b)) => b.encode_to(output),
+		(Primitive::Bool, _) => return None,
+		(Primitive::U8, value) => u8::try_from(value.as_u128()?).ok()?.encode_to(output),
+		(Primitive::U16, value) => u16::try_from(value.as_u128()?).ok()?.encode_to(output),
+		(Primitive::U32, value) => u32::try_from(value.as_u128()?).ok()?.encode_to(output),
+		(Primitive::U64, value) => u64::try_from(value.as_u128()?).ok()?.encode_to(output),
+		(Primitive::U128, value) => value.as_u128()?.encode_to(output),
+		(Primitive::U256, value) => value.as_u256()?.encode_to(output),
+	}
+	Some(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::{json, Value as Json};
+
+	#[test]
+	fn values_are_decoded_by_type_name() {
+		let registry = TypeRegistry::default();
+		let decode = |ty: &str, encoded: Vec<u8>| Json::from(registry.decode(ty, &mut &encoded[..]).unwrap());
+
+		assert_eq!(decode("T::Balance", 10u128.encode()), json!("10"));
+		assert_eq!(decode("bool", true.encode()), json!(true));
+		assert_eq!(decode("Option<AssetId>", Some(7u32).encode()), json!(7));
+		assert_eq!(decode("Vec<u8>", vec![1u8, 2].encode()), json!("0x0102"));
+		assert_eq!(
+			decode("Vec<(AccountId, Balance)>", vec![([1u8; 32], 5u128)].encode()),
+			json!([[format!("0x{}", "01".repeat(32)), "5"]]),
+		);
+		assert_eq!(
+			decode("DispatchResult", (1u8, 3u8, 2u8, 4u8).encode()),
+			json!({ "err": { "module": { "index": 2, "error": 4 } } }),
+		);
+		assert_eq!(decode("DispatchResult", 0u8.encode()), json!({ "ok": null }));
+		assert_eq!(decode("Timepoint<T::BlockNumber>", (3u32, 1u32).encode()), json!({ "height": 3, "index": 1 }));
+		assert_eq!(
+			registry.decode("Doughnut", &mut &[0u8][..]),
+			Err(Error("Unsupported type `Doughnut`".into())),
+		);
+		assert_eq!(
+			registry.decode("[u8; 4]", &mut &[0u8][..]),
+			Err(Error("Not enough data to fill buffer".into())),
+		);
+	}
+
+	#[test]
+	fn plug_event_types_are_decoded() {
+		let registry = TypeRegistry::default();
+		let decode = |ty: &str, encoded: Vec<u8>| Json::from(registry.decode(ty, &mut &encoded[..]).unwrap());
+		let alice = format!("0x{}", "01".repeat(32));
+
+		assert_eq!(
+			decode("AssetOptions", (codec::Compact(100u128), 1u8, [1u8; 32], 0u8, 0u8).encode()),
+			json!({
+				"initialIssuance": "100",
+				"permissions": { "update": { "address": alice }, "mint": "none", "burn": "none" },
+			}),
+		);
+		assert_eq!(
+			decode("PermissionLatest<AccountId>", (0u8, 0u8, 1u8, [1u8; 32]).encode()),
+			json!({ "update": "none", "mint": "none", "burn": { "address": alice } }),
+		);
+		assert_eq!(
+			decode("AssetInfo", (b"PLUG".to_vec(), 18u8).encode()),
+			json!({ "symbol": "0x504c5547", "decimalPlaces": 18 }),
+		);
+		let exposure = (codec::Compact(10u128), codec::Compact(4u128), vec![([1u8; 32], codec::Compact(6u128))]);
+		assert_eq!(
+			decode("Vec<IdentificationTuple>", vec![([2u8; 32], exposure)].encode()),
+			json!([[
+				format!("0x{}", "02".repeat(32)),
+				{ "total": "10", "own": "4", "others": [{ "who": alice, "value": "6" }] },
+			]]),
+		);
+	}
+
+	#[test]
+	fn registered_types_are_decoded() {
+		let mut registry = TypeRegistry::new();
+		registry
+			.register("Rate", TypeDef::Alias("u16".into()))
+			.register("Fee", TypeDef::Struct(vec![("rate".into(), "Rate".into()), ("cap".into(), "Option<u64>".into())]));
+
+		let encoded = (5u16, Some(9u64)).encode();
+		assert_eq!(
+			registry.decode("Fee", &mut &encoded[..]).unwrap(),
+			Value::Map(vec![("rate".into(), Value::U64(5)), ("cap".into(), Value::U64(9))]),
+		);
+		assert!(TypeRegistry::new().decode("Fee", &mut &encoded[..]).is_err());
+	}
+
+	#[test]
+	fn values_are_encoded_back() {
+		let registry = TypeRegistry::default();
+		let encoded = (
+			vec![([7u8; 32], 5u128)],
+			Compact(100u64),
+			(2u32, 9u32),
+			(1u8, 3u8, 2u8, 4u8),
+			2u8,
+		).encode();
+		let ty = "(Vec<(AccountId, Balance)>, Compact<Balance>, Timepoint<BlockNumber>, DispatchResult, VoteThreshold)";
+
+		let value = registry.decode(ty, &mut &encoded[..]).unwrap();
+		assert_eq!(registry.encode(ty, &value).unwrap(), encoded);
+
+		let json: Json = value.into();
+		assert_eq!(registry.encode(ty, &Value::from(&json)).unwrap(), encoded);
+
+		assert!(registry.encode("u8", &Value::U64(256)).is_err());
+		assert!(registry.encode("AccountId", &Value::Bytes(vec![0; 20])).is_err());
+		assert_eq!(registry.encode("Timepoint", &Value::Seq(vec![Value::U64(2), Value::U64(9)])).unwrap(), (2u32, 9u32).encode());
+	}
+
+	#[test]
+	fn tuple_types_are_split_at_the_top_level() {
+		assert_eq!(split_types("AccountId, Vec<(u32, u64)>, [u8; 4]"), vec!["AccountId", "Vec<(u32, u64)>", "[u8; 4]"]);
+	}
+}
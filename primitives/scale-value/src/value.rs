@@ -0,0 +1,166 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dynamically typed values and their JSON representation.
+
+use serde_json::{Map, Number, Value as Json};
+use sp_core::{hexdisplay::HexDisplay, U256};
+
+/// A value of a type of the `TypeRegistry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+	/// A unit or `None`, JSON `null`.
+	Null,
+	/// A boolean.
+	Bool(bool),
+	/// An unsigned integer of up to 64 bits, a JSON number.
+	U64(u64),
+	/// A 128-bit unsigned integer, a JSON string of its decimal digits.
+	U128(u128),
+	/// A 256-bit unsigned integer, a JSON string of its decimal digits.
+	U256(U256),
+	/// Bytes, a `0x` prefixed JSON hex string.
+	Bytes(Vec<u8>),
+	/// The name of a variant without value, a JSON string.
+	Str(String),
+	/// The items of a sequence, tuple or array, a JSON array.
+	Seq(Vec<Value>),
+	/// The fields of a struct, or the name and value of a variant, a JSON object.
+	Map(Vec<(String, Value)>),
+}
+
+impl Value {
+	/// The integer, if it's one of at most 128 bits or a string of its decimal digits.
+	pub fn as_u128(&self) -> Option<u128> {
+		match self {
+			Value::U64(n) => Some(*n as u128),
+			Value::U128(n) => Some(*n),
+			Value::U256(n) if n.bits() <= 128 => Some(n.low_u128()),
+			Value::Str(s) => s.parse().ok(),
+			_ => None,
+		}
+	}
+
+	/// The integer, if it's one or a string of its decimal digits.
+	pub fn as_u256(&self) -> Option<U256> {
+		match self {
+			Value::U256(n) => Some(*n),
+			Value::Str(s) => U256::from_dec_str(s).ok(),
+			_ => self.as_u128().map(Into::into),
+		}
+	}
+}
+
+impl From<Value> for Json {
+	fn from(value: Value) -> Json {
+		match value {
+			Value::Null => Json::Null,
+			Value::Bool(b) => Json::Bool(b),
+			Value::U64(n) => Json::Number(n.into()),
+			Value::U128(n) => Json::String(n.to_string()),
+			Value::U256(n) => Json::String(n.to_string()),
+			Value::Bytes(bytes) => Json::String(format!("0x{}", HexDisplay::from(&bytes))),
+			Value::Str(s) => Json::String(s),
+			Value::Seq(items) => Json::Array(items.into_iter().map(Into::into).collect()),
+			Value::Map(fields) => Json::Object(
+				fields.into_iter().map(|(name, value)| (name, value.into())).collect::<Map<_, _>>()
+			),
+		}
+	}
+}
+
+impl From<&Json> for Value {
+	/// The value of its JSON representation. Strings of decimal digits are integers, `0x`
+	/// prefixed hex strings are bytes, and other strings and numbers are `Str`s.
+	fn from(json: &Json) -> Value {
+		match json {
+			Json::Null => Value::Null,
+			Json::Bool(b) => Value::Bool(*b),
+			Json::Number(n) => number(n),
+			Json::String(s) => string(s),
+			Json::Array(items) => Value::Seq(items.iter().map(Into::into).collect()),
+			Json::Object(fields) => Value::Map(
+				fields.iter().map(|(name, value)| (name.clone(), value.into())).collect()
+			),
+		}
+	}
+}
+
+fn number(n: &Number) -> Value {
+	n.as_u64().map(Value::U64).unwrap_or_else(|| Value::Str(n.to_string()))
+}
+
+fn string(s: &str) -> Value {
+	if s.starts_with("0x") {
+		if let Ok(bytes) = hex(&s[2..]) {
+			return Value::Bytes(bytes);
+		}
+	}
+	if !s.is_empty() && s.bytes().all(|c| c.is_ascii_digit()) {
+		if let Ok(n) = s.parse() {
+			return Value::U128(n);
+		}
+		if let Ok(n) = U256::from_dec_str(s) {
+			return Value::U256(n);
+		}
+	}
+	Value::Str(s.to_owned())
+}
+
+fn hex(s: &str) -> Result<Vec<u8>, ()> {
+	if s.len() % 2 != 0 {
+		return Err(());
+	}
+	(0..s.len()).step_by(2)
+		.map(|i| u8::from_str_radix(s.get(i..i + 2).ok_or(())?, 16).map_err(|_| ()))
+		.collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use serde_json::json;
+
+	#[test]
+	fn values_convert_to_and_from_json() {
+		let value = Value::Map(vec![
+			("who".into(), Value::Bytes(vec![1, 2])),
+			("amount".into(), Value::U128(10)),
+			("index".into(), Value::U64(3)),
+			("class".into(), Value::Str("normal".into())),
+			("items".into(), Value::Seq(vec![Value::Null, Value::Bool(true)])),
+		]);
+		let json = json!({
+			"who": "0x0102",
+			"amount": "10",
+			"index": 3,
+			"class": "normal",
+			"items": [null, true],
+		});
+
+		assert_eq!(Json::from(value), json.clone());
+		assert_eq!(Json::from(Value::from(&json)), json);
+	}
+
+	#[test]
+	fn integers_are_read_from_numbers_and_strings() {
+		assert_eq!(Value::from(&json!("340282366920938463463374607431768211456")).as_u256(), Some(U256::one() << 128));
+		assert_eq!(Value::from(&json!("5")).as_u128(), Some(5));
+		assert_eq!(Value::from(&json!(5)).as_u128(), Some(5));
+		assert_eq!(Value::from(&json!(-5)), Value::Str("-5".into()));
+		assert_eq!(Value::from(&json!("0x0g")), Value::Str("0x0g".into()));
+	}
+}
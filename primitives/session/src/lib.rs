@@ -19,16 +19,38 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use sp_std::vec::Vec;
+use codec::{Decode, Encode};
 
 #[cfg(feature = "std")]
 use sp_runtime::{generic::BlockId, traits::Block as BlockT};
 #[cfg(feature = "std")]
 use sp_api::ProvideRuntimeApi;
 
-use sp_core::crypto::KeyTypeId;
+use sp_core::{crypto::KeyTypeId, RuntimeDebug};
+use sp_staking::SessionIndex;
+
+/// An opaque type used to represent the proof of membership of a key in the validator set of a
+/// session at the runtime API boundary, e.g. an encoded `pallet_session::historical::Proof`.
+/// Implementors of the runtime API will have to make sure that all usages of
+/// `OpaqueMembershipProof` refer to the same type.
+#[derive(Decode, Encode, PartialEq, RuntimeDebug)]
+pub struct OpaqueMembershipProof(Vec<u8>);
+
+impl OpaqueMembershipProof {
+	/// Create a new `OpaqueMembershipProof` using the given encoded representation.
+	pub fn new(inner: Vec<u8>) -> OpaqueMembershipProof {
+		OpaqueMembershipProof(inner)
+	}
+
+	/// Try to decode this `OpaqueMembershipProof` into the given concrete membership proof type.
+	pub fn decode<T: Decode>(self) -> Option<T> {
+		Decode::decode(&mut &self.0[..]).ok()
+	}
+}
 
 sp_api::decl_runtime_apis! {
 	/// Session keys runtime api.
+	#[api_version(2)]
 	pub trait SessionKeys {
 		/// Generate a set of session keys with optionally using the given seed.
 		/// The keys should be stored within the keystore exposed via runtime
@@ -43,6 +65,33 @@ sp_api::decl_runtime_apis! {
 		///
 		/// Returns the list of public raw public keys + key type.
 		fn decode_session_keys(encoded: Vec<u8>) -> Option<Vec<(Vec<u8>, KeyTypeId)>>;
+
+		/// Generate a set of session keys with optionally using the given seed, and prove
+		/// possession of the keys to the runtime for `owner`, the SCALE encoded account setting
+		/// them.
+		///
+		/// Returns the concatenated SCALE encoded public keys and the ownership proof, or `None`
+		/// if `owner` is not a valid account or a key could not sign.
+		fn generate_session_keys_with_proof(
+			owner: Vec<u8>,
+			seed: Option<Vec<u8>>,
+		) -> Option<(Vec<u8>, Vec<u8>)>;
+	}
+
+	/// Historical session membership runtime api.
+	pub trait SessionMembershipApi {
+		/// Generate a proof that the given key is a key of a validator of `session`, to report
+		/// equivocations of past sessions or to validate old justifications in a header bridge.
+		///
+		/// The validator sets of past sessions are read from the offchain index, which the client
+		/// gives its runtime API calls read access to, so this must not be called on-chain.
+		/// Returns `None` if the session isn't stored, the node doesn't index offchain storage or
+		/// the key isn't a key of a validator of the session.
+		fn prove_session_membership(
+			session: SessionIndex,
+			key_type: KeyTypeId,
+			key_data: Vec<u8>,
+		) -> Option<OpaqueMembershipProof>;
 	}
 }
 
@@ -37,12 +37,13 @@ use codec::Encode;
 #[derive(Debug)]
 pub struct BasicExternalities {
 	inner: Storage,
+	transactions: Vec<Storage>,
 }
 
 impl BasicExternalities {
 	/// Create a new instance of `BasicExternalities`
 	pub fn new(inner: Storage) -> Self {
-		BasicExternalities { inner }
+		BasicExternalities { inner, transactions: Vec::new() }
 	}
 
 	/// Insert key/value
@@ -62,10 +63,10 @@ impl BasicExternalities {
 		storage: &mut sp_core::storage::Storage,
 		f: impl FnOnce() -> R,
 	) -> R {
-		let mut ext = Self { inner: Storage {
+		let mut ext = Self::new(Storage {
 			top: std::mem::replace(&mut storage.top, Default::default()),
 			children: std::mem::replace(&mut storage.children, Default::default()),
-		}};
+		});
 
 		let r = ext.execute_with(f);
 
@@ -103,10 +104,10 @@ impl Default for BasicExternalities {
 
 impl From<BTreeMap<StorageKey, StorageValue>> for BasicExternalities {
 	fn from(hashmap: BTreeMap<StorageKey, StorageValue>) -> Self {
-		BasicExternalities { inner: Storage {
+		Self::new(Storage {
 			top: hashmap,
 			children: Default::default(),
-		}}
+		})
 	}
 }
 
@@ -231,6 +232,10 @@ impl Externalities for BasicExternalities {
 		}
 	}
 
+	fn set_offchain_storage(&mut self, _key: &[u8], _value: Option<&[u8]>) {
+		// There is no block import, so there is no offchain index to write to.
+	}
+
 	fn chain_id(&self) -> u64 { 42 }
 
 	fn storage_root(&mut self) -> Vec<u8> {
@@ -273,6 +278,19 @@ impl Externalities for BasicExternalities {
 		Ok(None)
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.transactions.push(self.inner.clone());
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.inner = self.transactions.pop().ok_or(())?;
+		Ok(())
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.transactions.pop().map(|_| ()).ok_or(())
+	}
+
 	fn wipe(&mut self) {}
 
 	fn commit(&mut self) {}
@@ -283,6 +301,18 @@ impl sp_externalities::ExtensionStore for BasicExternalities {
 		warn!("Extensions are not supported by `BasicExternalities`.");
 		None
 	}
+
+	fn register_extension_with_type_id(
+		&mut self,
+		_type_id: TypeId,
+		_extension: Box<dyn sp_externalities::Extension>,
+	) -> Result<(), sp_externalities::Error> {
+		Err(sp_externalities::Error::ExtensionsAreNotSupported)
+	}
+
+	fn deregister_extension_by_type_id(&mut self, _type_id: TypeId) -> Result<(), sp_externalities::Error> {
+		Err(sp_externalities::Error::ExtensionsAreNotSupported)
+	}
 }
 
 #[cfg(test)]
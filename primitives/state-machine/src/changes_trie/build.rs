@@ -443,7 +443,8 @@ mod test {
 							extrinsics: Some(vec![0, 2].into_iter().collect())
 						})
 					].into_iter().collect(), CHILD_INFO_2.to_owned())),
-				].into_iter().collect()
+				].into_iter().collect(),
+				offchain: Default::default(),
 			},
 			committed: OverlayedChangeSet { top: vec![
 				(EXTRINSIC_INDEX.to_vec(), OverlayedValue {
@@ -467,8 +468,10 @@ mod test {
 						})
 					].into_iter().collect(), CHILD_INFO_1.to_owned())),
 				].into_iter().collect(),
+				offchain: Default::default(),
 			},
 			collect_extrinsics: true,
+			transactions: Default::default(),
 		};
 		let config = Configuration { digest_interval: 4, digest_levels: 2 };
 
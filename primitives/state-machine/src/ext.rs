@@ -329,6 +329,16 @@ where
 		self.overlay.set_storage(key, value);
 	}
 
+	fn set_offchain_storage(&mut self, key: &[u8], value: Option<&[u8]>) {
+		trace!(target: "state-trace", "{:04x}: PutOffchain {}={:?}",
+			self.id,
+			HexDisplay::from(&key),
+			value.as_ref().map(HexDisplay::from)
+		);
+		let _guard = sp_panic_handler::AbortGuard::force_abort();
+		self.overlay.set_offchain_storage(key.to_vec(), value.map(<[u8]>::to_vec));
+	}
+
 	fn place_child_storage(
 		&mut self,
 		storage_key: ChildStorageKey,
@@ -519,6 +529,19 @@ where
 		root.map(|r| r.map(|o| o.encode()))
 	}
 
+	fn storage_start_transaction(&mut self) {
+		self.overlay.start_transaction()
+	}
+
+	fn storage_rollback_transaction(&mut self) -> Result<(), ()> {
+		self.mark_dirty();
+		self.overlay.rollback_transaction()
+	}
+
+	fn storage_commit_transaction(&mut self) -> Result<(), ()> {
+		self.overlay.commit_transaction()
+	}
+
 	fn wipe(&mut self) {
 		self.overlay.discard_prospective();
 		self.overlay.drain_storage_changes(&self.backend, None, Default::default(), self.storage_transaction_cache)
@@ -548,6 +571,26 @@ where
 	fn extension_by_type_id(&mut self, type_id: TypeId) -> Option<&mut dyn Any> {
 		self.extensions.as_mut().and_then(|exts| exts.get_mut(type_id))
 	}
+
+	fn register_extension_with_type_id(
+		&mut self,
+		type_id: TypeId,
+		extension: Box<dyn sp_externalities::Extension>,
+	) -> Result<(), sp_externalities::Error> {
+		match self.extensions.as_mut() {
+			Some(exts) => exts.register_with_type_id(type_id, extension),
+			None => Err(sp_externalities::Error::ExtensionsAreNotSupported),
+		}
+	}
+
+	fn deregister_extension_by_type_id(&mut self, type_id: TypeId) -> Result<(), sp_externalities::Error> {
+		match self.extensions.as_mut() {
+			Some(exts) => exts.deregister(type_id)
+				.map(drop)
+				.ok_or(sp_externalities::Error::ExtensionIsNotRegistered(type_id)),
+			None => Err(sp_externalities::Error::ExtensionsAreNotSupported),
+		}
+	}
 }
 
 #[cfg(test)]
@@ -587,6 +630,7 @@ mod tests {
 			].into_iter().collect(),
 			committed: Default::default(),
 			collect_extrinsics: true,
+			transactions: Default::default(),
 		}
 	}
 
@@ -38,6 +38,7 @@ mod testing;
 mod basic;
 mod overlayed_changes;
 mod proving_backend;
+mod read_recording_backend;
 mod trie_backend;
 mod trie_backend_essence;
 mod stats;
@@ -69,6 +70,7 @@ pub use overlayed_changes::{
 pub use proving_backend::{
 	create_proof_check_backend, ProofRecorder, ProvingBackend, ProvingBackendRecorder,
 };
+pub use read_recording_backend::ReadRecordingBackend;
 pub use trie_backend_essence::{TrieBackendStorage, Storage};
 pub use trie_backend::TrieBackend;
 pub use error::{Error, ExecutionError};
@@ -57,6 +57,8 @@ pub struct OverlayedChanges {
 	pub(crate) committed: OverlayedChangeSet,
 	/// True if extrinsics stats must be collected.
 	pub(crate) collect_extrinsics: bool,
+	/// Snapshots of the prospective changes, one for each open storage transaction.
+	pub(crate) transactions: Vec<OverlayedChangeSet>,
 }
 
 /// The storage value, used inside OverlayedChanges.
@@ -78,6 +80,9 @@ pub struct OverlayedChangeSet {
 	pub top: BTreeMap<StorageKey, OverlayedValue>,
 	/// Child storage changes.
 	pub children: HashMap<StorageKey, (BTreeMap<StorageKey, OverlayedValue>, OwnedChildInfo)>,
+	/// Changes to the offchain index, i.e. the node-local storage written during block import
+	/// that isn't part of the state.
+	pub offchain: BTreeMap<StorageKey, Option<StorageValue>>,
 }
 
 /// A storage changes structure that can be generated by the data collected in [`OverlayedChanges`].
@@ -91,6 +96,11 @@ pub struct StorageChanges<Transaction, H: Hasher, N: BlockNumber> {
 	pub main_storage_changes: StorageCollection,
 	/// All changes to the child storages.
 	pub child_storage_changes: ChildStorageCollection,
+	/// All changes to the offchain index.
+	///
+	/// These are not part of the state and are written to the offchain storage of the node
+	/// importing the block.
+	pub offchain_storage_changes: StorageCollection,
 	/// A transaction for the backend that contains all changes from
 	/// [`main_storage_changes`](Self::main_storage_changes) and from
 	/// [`child_storage_changes`](Self::child_storage_changes).
@@ -108,6 +118,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 	pub fn into_inner(self) -> (
 		StorageCollection,
 		ChildStorageCollection,
+		StorageCollection,
 		Transaction,
 		H::Out,
 		Option<ChangesTrieTransaction<H, N>>,
@@ -115,6 +126,7 @@ impl<Transaction, H: Hasher, N: BlockNumber> StorageChanges<Transaction, H, N> {
 		(
 			self.main_storage_changes,
 			self.child_storage_changes,
+			self.offchain_storage_changes,
 			self.transaction,
 			self.transaction_storage_root,
 			self.changes_trie_transaction,
@@ -159,6 +171,7 @@ impl<Transaction: Default, H: Hasher, N: BlockNumber> Default for StorageChanges
 		Self {
 			main_storage_changes: Default::default(),
 			child_storage_changes: Default::default(),
+			offchain_storage_changes: Default::default(),
 			transaction: Default::default(),
 			transaction_storage_root: Default::default(),
 			changes_trie_transaction: None,
@@ -172,6 +185,7 @@ impl FromIterator<(StorageKey, OverlayedValue)> for OverlayedChangeSet {
 		Self {
 			top: iter.into_iter().collect(),
 			children: Default::default(),
+			offchain: Default::default(),
 		}
 	}
 }
@@ -179,13 +193,14 @@ impl FromIterator<(StorageKey, OverlayedValue)> for OverlayedChangeSet {
 impl OverlayedChangeSet {
 	/// Whether the change set is empty.
 	pub fn is_empty(&self) -> bool {
-		self.top.is_empty() && self.children.is_empty()
+		self.top.is_empty() && self.children.is_empty() && self.offchain.is_empty()
 	}
 
 	/// Clear the change set.
 	pub fn clear(&mut self) {
 		self.top.clear();
 		self.children.clear();
+		self.offchain.clear();
 	}
 }
 
@@ -209,6 +224,13 @@ impl OverlayedChanges {
 			.map(|x| x.value.as_ref().map(AsRef::as_ref))
 	}
 
+	/// Returns the top level storage changes that are not committed yet, ordered by key. `None`
+	/// values are deletions.
+	pub fn prospective_changes(&self) -> impl Iterator<Item = (&[u8], Option<&[u8]>)> {
+		self.prospective.top.iter()
+			.map(|(key, value)| (&key[..], value.value.as_ref().map(AsRef::as_ref)))
+	}
+
 	/// Returns a double-Option: None if the key is unknown (i.e. and the query should be referred
 	/// to the backend); Some(None) if the key has been deleted. Some(Some(...)) for a key whose
 	/// value has been set.
@@ -267,6 +289,14 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Inserts the given key-value pair into the prospective offchain index change set.
+	///
+	/// `None` can be used to delete a value specified by the given key. The offchain index isn't
+	/// part of the state, the changes are written to the offchain storage on block import.
+	pub(crate) fn set_offchain_storage(&mut self, key: StorageKey, val: Option<StorageValue>) {
+		self.prospective.offchain.insert(key, val);
+	}
+
 	/// Clear child storage of given storage key.
 	///
 	/// NOTE that this doesn't take place immediately but written into the prospective
@@ -387,13 +417,56 @@ impl OverlayedChanges {
 		}
 	}
 
+	/// Start a new nested storage transaction.
+	///
+	/// All changes made after this call can be reverted with [`rollback_transaction`]
+	/// or kept with [`commit_transaction`].
+	///
+	/// [`rollback_transaction`]: #method.rollback_transaction
+	/// [`commit_transaction`]: #method.commit_transaction
+	pub fn start_transaction(&mut self) {
+		self.transactions.push(self.prospective.clone());
+	}
+
+	/// Revert all changes made since the last call to [`start_transaction`] and close
+	/// that transaction.
+	///
+	/// Returns an error if there is no open transaction.
+	///
+	/// [`start_transaction`]: #method.start_transaction
+	pub fn rollback_transaction(&mut self) -> Result<(), ()> {
+		self.prospective = self.transactions.pop().ok_or(())?;
+		Ok(())
+	}
+
+	/// Keep all changes made since the last call to [`start_transaction`] and close
+	/// that transaction.
+	///
+	/// Returns an error if there is no open transaction.
+	///
+	/// [`start_transaction`]: #method.start_transaction
+	pub fn commit_transaction(&mut self) -> Result<(), ()> {
+		self.transactions.pop().map(|_| ()).ok_or(())
+	}
+
+	/// The number of currently open storage transactions.
+	pub fn transaction_depth(&self) -> usize {
+		self.transactions.len()
+	}
+
 	/// Discard prospective changes to state.
+	///
+	/// This also closes all open storage transactions.
 	pub fn discard_prospective(&mut self) {
 		self.prospective.clear();
+		self.transactions.clear();
 	}
 
 	/// Commit prospective changes to state.
+	///
+	/// This also closes all open storage transactions, keeping their changes.
 	pub fn commit_prospective(&mut self) {
+		self.transactions.clear();
 		if self.committed.is_empty() {
 			mem::swap(&mut self.prospective, &mut self.committed);
 		} else {
@@ -421,6 +494,8 @@ impl OverlayedChanges {
 					}
 				}
 			}
+			let offchain_to_commit = mem::replace(&mut self.prospective.offchain, BTreeMap::new());
+			self.committed.offchain.extend(offchain_to_commit);
 		}
 	}
 
@@ -431,6 +506,7 @@ impl OverlayedChanges {
 	fn drain_committed(&mut self) -> (
 		impl Iterator<Item=(StorageKey, Option<StorageValue>)>,
 		impl Iterator<Item=(StorageKey, (impl Iterator<Item=(StorageKey, Option<StorageValue>)>, OwnedChildInfo))>,
+		impl Iterator<Item=(StorageKey, Option<StorageValue>)>,
 	) {
 		assert!(self.prospective.is_empty());
 		(
@@ -440,6 +516,8 @@ impl OverlayedChanges {
 			std::mem::replace(&mut self.committed.children, Default::default())
 				.into_iter()
 				.map(|(sk, (v, ci))| (sk, (v.into_iter().map(|(k, v)| (k, v.value)), ci))),
+			std::mem::replace(&mut self.committed.offchain, Default::default())
+				.into_iter(),
 		)
 	}
 
@@ -488,11 +566,13 @@ impl OverlayedChanges {
 			.take()
 			.expect("Changes trie transaction was generated by `changes_trie_root`; qed");
 
-		let (main_storage_changes, child_storage_changes) = self.drain_committed();
+		let (main_storage_changes, child_storage_changes, offchain_storage_changes) =
+			self.drain_committed();
 
 		Ok(StorageChanges {
 			main_storage_changes: main_storage_changes.collect(),
 			child_storage_changes: child_storage_changes.map(|(sk, it)| (sk, it.0.collect())).collect(),
+			offchain_storage_changes: offchain_storage_changes.collect(),
 			transaction,
 			transaction_storage_root,
 			changes_trie_transaction,
@@ -709,6 +789,81 @@ mod tests {
 		assert!(overlayed.storage(&key).unwrap().is_none());
 	}
 
+	#[test]
+	fn prospective_changes_are_listed_until_committed() {
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.set_storage(vec![2], Some(vec![2]));
+		overlayed.set_storage(vec![1], None);
+		assert_eq!(
+			overlayed.prospective_changes().collect::<Vec<_>>(),
+			vec![(&[1][..], None), (&[2][..], Some(&[2][..]))],
+		);
+
+		overlayed.commit_prospective();
+		assert_eq!(overlayed.prospective_changes().count(), 0);
+	}
+
+	#[test]
+	fn storage_transactions_work() {
+		let mut overlayed = OverlayedChanges::default();
+
+		overlayed.set_storage(vec![1], Some(vec![1]));
+		overlayed.start_transaction();
+		overlayed.set_storage(vec![1], Some(vec![2]));
+		overlayed.set_storage(vec![2], Some(vec![2]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(vec![1], None);
+		assert_eq!(overlayed.transaction_depth(), 2);
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(&[1]).unwrap(), Some(&[2][..]));
+
+		assert_eq!(overlayed.commit_transaction(), Ok(()));
+		assert_eq!(overlayed.storage(&[2]).unwrap(), Some(&[2][..]));
+
+		overlayed.start_transaction();
+		overlayed.set_storage(vec![3], Some(vec![3]));
+		assert_eq!(overlayed.rollback_transaction(), Ok(()));
+		assert!(overlayed.storage(&[3]).is_none());
+
+		assert_eq!(overlayed.transaction_depth(), 0);
+		assert_eq!(overlayed.rollback_transaction(), Err(()));
+		assert_eq!(overlayed.commit_transaction(), Err(()));
+	}
+
+	#[test]
+	fn offchain_changes_are_drained_into_storage_changes() {
+		let backend = InMemoryBackend::<Blake2Hasher>::default();
+		let mut overlay = OverlayedChanges::default();
+
+		overlay.set_offchain_storage(vec![1], Some(vec![1]));
+		overlay.start_transaction();
+		overlay.set_offchain_storage(vec![2], Some(vec![2]));
+		assert_eq!(overlay.rollback_transaction(), Ok(()));
+		overlay.commit_prospective();
+
+		overlay.set_offchain_storage(vec![1], None);
+		overlay.set_offchain_storage(vec![3], Some(vec![3]));
+		overlay.discard_prospective();
+		overlay.set_offchain_storage(vec![4], None);
+		overlay.commit_prospective();
+
+		let changes = overlay.drain_storage_changes(
+			&backend,
+			None::<&ChangesTrieState<_, u64>>,
+			Default::default(),
+			&mut StorageTransactionCache::default(),
+		).unwrap();
+
+		assert!(changes.main_storage_changes.is_empty());
+		assert_eq!(
+			changes.offchain_storage_changes,
+			vec![(vec![1], Some(vec![1])), (vec![4], None)],
+		);
+		assert!(overlay.is_empty());
+	}
+
 	#[test]
 	fn overlayed_storage_root_works() {
 		let initial: BTreeMap<_, _> = vec![
@@ -0,0 +1,161 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A state backend recording the storage values read from another backend.
+
+use hash_db::Hasher;
+use parking_lot::Mutex;
+use sp_core::storage::ChildInfo;
+
+use crate::{backend::Backend, StorageKey, StorageValue, UsageInfo};
+
+/// Forwards the calls to a backend and records the top level storage values read from it, in
+/// the order they are read.
+///
+/// Only the reads reaching the backend are recorded: values read from the `OverlayedChanges` of
+/// an execution, e.g. values written earlier by the same execution, are not.
+#[derive(Debug)]
+pub struct ReadRecordingBackend<'a, B> {
+	backend: &'a B,
+	reads: Mutex<Vec<(StorageKey, Option<StorageValue>)>>,
+}
+
+impl<'a, B> ReadRecordingBackend<'a, B> {
+	/// Create a backend recording the reads from `backend`.
+	pub fn new(backend: &'a B) -> Self {
+		ReadRecordingBackend { backend, reads: Mutex::new(Vec::new()) }
+	}
+
+	/// Take the values read since the last call, by key.
+	pub fn take_reads(&self) -> Vec<(StorageKey, Option<StorageValue>)> {
+		std::mem::replace(&mut *self.reads.lock(), Vec::new())
+	}
+}
+
+impl<'a, B: Backend<H>, H: Hasher> Backend<H> for ReadRecordingBackend<'a, B> {
+	type Error = B::Error;
+	type Transaction = B::Transaction;
+	type TrieBackendStorage = B::TrieBackendStorage;
+
+	fn storage(&self, key: &[u8]) -> Result<Option<StorageValue>, Self::Error> {
+		let value = self.backend.storage(key)?;
+		self.reads.lock().push((key.to_vec(), value.clone()));
+		Ok(value)
+	}
+
+	fn child_storage(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageValue>, Self::Error> {
+		self.backend.child_storage(storage_key, child_info, key)
+	}
+
+	fn for_keys_in_child_storage<F: FnMut(&[u8])>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		f: F,
+	) {
+		self.backend.for_keys_in_child_storage(storage_key, child_info, f)
+	}
+
+	fn next_storage_key(&self, key: &[u8]) -> Result<Option<StorageKey>, Self::Error> {
+		self.backend.next_storage_key(key)
+	}
+
+	fn next_child_storage_key(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		key: &[u8],
+	) -> Result<Option<StorageKey>, Self::Error> {
+		self.backend.next_child_storage_key(storage_key, child_info, key)
+	}
+
+	fn for_keys_with_prefix<F: FnMut(&[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_keys_with_prefix(prefix, f)
+	}
+
+	fn for_key_values_with_prefix<F: FnMut(&[u8], &[u8])>(&self, prefix: &[u8], f: F) {
+		self.backend.for_key_values_with_prefix(prefix, f)
+	}
+
+	fn for_child_keys_with_prefix<F: FnMut(&[u8])>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		prefix: &[u8],
+		f: F,
+	) {
+		self.backend.for_child_keys_with_prefix(storage_key, child_info, prefix, f)
+	}
+
+	fn storage_root<I>(&self, delta: I) -> (H::Out, Self::Transaction)
+	where
+		I: IntoIterator<Item=(StorageKey, Option<StorageValue>)>,
+		H::Out: Ord,
+	{
+		self.backend.storage_root(delta)
+	}
+
+	fn child_storage_root<I>(
+		&self,
+		storage_key: &[u8],
+		child_info: ChildInfo,
+		delta: I,
+	) -> (H::Out, bool, Self::Transaction)
+	where
+		I: IntoIterator<Item=(StorageKey, Option<StorageValue>)>,
+		H::Out: Ord,
+	{
+		self.backend.child_storage_root(storage_key, child_info, delta)
+	}
+
+	fn pairs(&self) -> Vec<(StorageKey, StorageValue)> {
+		self.backend.pairs()
+	}
+
+	fn usage_info(&self) -> UsageInfo {
+		self.backend.usage_info()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::InMemoryBackend;
+	use sp_runtime::traits::BlakeTwo256;
+
+	#[test]
+	fn reads_are_recorded_in_order() {
+		let state = InMemoryBackend::<BlakeTwo256>::from(
+			vec![(b"a".to_vec(), b"1".to_vec()), (b"b".to_vec(), b"2".to_vec())]
+				.into_iter()
+				.collect::<std::collections::BTreeMap<_, _>>(),
+		);
+		let backend = ReadRecordingBackend::new(&state);
+
+		assert_eq!(Backend::<BlakeTwo256>::storage(&backend, b"b").unwrap(), Some(b"2".to_vec()));
+		assert!(!Backend::<BlakeTwo256>::exists_storage(&backend, b"c").unwrap());
+		assert_eq!(
+			backend.take_reads(),
+			vec![(b"b".to_vec(), Some(b"2".to_vec())), (b"c".to_vec(), None)],
+		);
+		assert!(backend.take_reads().is_empty());
+	}
+}
@@ -75,6 +75,9 @@ pub struct StorageChangeSet<Hash> {
 	pub block: Hash,
 	/// A list of changes
 	pub changes: Vec<(StorageKey, Option<StorageData>)>,
+	/// A list of changes in child tries, by child storage key
+	#[cfg_attr(feature = "std", serde(default, skip_serializing_if = "Vec::is_empty"))]
+	pub child_changes: Vec<(StorageKey, Vec<(StorageKey, Option<StorageData>)>)>,
 }
 
 /// List of all well known keys and prefixes in storage.
@@ -95,6 +98,12 @@ pub mod well_known_keys {
 	/// Changes trie configuration is stored under this key.
 	pub const CHANGES_TRIE_CONFIG: &'static [u8] = b":changes_trie";
 
+	/// Prefix of the doughnut signature verification cache.
+	///
+	/// Entries are keyed by the blake2-256 hash of the encoded doughnut and only live for the
+	/// duration of a block, they are cleared before the storage root is calculated.
+	pub const DOUGHNUT_VERIFIED_PREFIX: &'static [u8] = b":doughnut_verified:";
+
 	/// Prefix of child storage keys.
 	pub const CHILD_STORAGE_KEY_PREFIX: &'static [u8] = b":child_storage:";
 
@@ -197,6 +206,19 @@ impl<'a> ChildInfo<'a> {
 		})
 	}
 
+	/// Instantiates information for the default child trie stored under `storage_key`, whose
+	/// unique id is the part of the key after `:child_storage:default:`, as for contracts.
+	///
+	/// Returns `None` if `storage_key` isn't a valid child trie key.
+	pub fn default_for_storage_key(storage_key: &'a [u8]) -> Option<Self> {
+		if well_known_keys::is_child_trie_key_valid(storage_key) {
+			let start = well_known_keys::CHILD_STORAGE_KEY_PREFIX.len() + b"default:".len();
+			Some(ChildInfo::new_default(&storage_key[start..]))
+		} else {
+			None
+		}
+	}
+
 	/// Instantiates a owned version of this child info.
 	pub fn to_owned(&self) -> OwnedChildInfo {
 		match self {
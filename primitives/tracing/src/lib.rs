@@ -19,6 +19,11 @@
 //!
 //! To trace functions or invidual code in Substrate, this crate provides [`tracing_span`]
 //! and [`enter_span`]. See the individual docs for how to use these macros.
+//!
+//! The spans of the wasm runtime are entered on the host by the [`proxy`].
+//!
+//! The block or the peer being processed by a thread can be attached to its log records with
+//! the [`log_context`].
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -26,6 +31,12 @@
 #[doc(hidden)]
 pub use tracing;
 
+#[cfg(feature = "std")]
+pub mod proxy;
+
+#[cfg(feature = "std")]
+pub mod log_context;
+
 /// Runs given code within a tracing span, measuring it's execution time.
 ///
 /// If tracing is not enabled, the code is still executed.
@@ -0,0 +1,101 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Context of the log records emitted on the current thread.
+//!
+//! Subsystems enter the block or the peer they are processing, and structured loggers attach the
+//! current context to the records emitted meanwhile. The context is left when the returned
+//! guard is dropped. The block is entered by the import queue while verifying a block, along
+//! with the peer it came from, by the client while importing or finalizing a block, by the
+//! proposer while authoring on top of a block and by the offchain workers of a block. The peer
+//! is entered by the network while handling a message or a request of a peer.
+//!
+//! The context is thread local, so it isn't entered across `.await` points.
+
+use std::cell::RefCell;
+use std::fmt::{Debug, Display};
+
+/// The context of the log records emitted on a thread.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LogContext {
+	/// The number and the hash of the block being processed.
+	pub block: Option<(String, String)>,
+	/// The id of the peer being processed.
+	pub peer_id: Option<String>,
+}
+
+thread_local! {
+	static CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+/// Restores the previous context of the thread when dropped.
+#[must_use = "the context is left when the guard is dropped"]
+pub struct ContextGuard(Option<LogContext>);
+
+impl Drop for ContextGuard {
+	fn drop(&mut self) {
+		if let Some(previous) = self.0.take() {
+			let _ = CONTEXT.try_with(|context| *context.borrow_mut() = previous);
+		}
+	}
+}
+
+fn enter(update: impl FnOnce(&mut LogContext)) -> ContextGuard {
+	ContextGuard(CONTEXT.try_with(|context| {
+		let mut context = context.borrow_mut();
+		let previous = context.clone();
+		update(&mut context);
+		previous
+	}).ok())
+}
+
+/// Enter the given block until the returned guard is dropped. The hash is recorded with its
+/// `Debug` format, i.e. in full.
+pub fn enter_block(number: impl Display, hash: impl Debug) -> ContextGuard {
+	enter(|context| context.block = Some((number.to_string(), format!("{:?}", hash))))
+}
+
+/// Enter the given peer until the returned guard is dropped.
+pub fn enter_peer(peer_id: impl Display) -> ContextGuard {
+	enter(|context| context.peer_id = Some(peer_id.to_string()))
+}
+
+/// The context of the current thread.
+pub fn current() -> LogContext {
+	CONTEXT.try_with(|context| context.borrow().clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn contexts_are_nested() {
+		assert_eq!(current(), LogContext::default());
+
+		{
+			let _block = enter_block(5, 1);
+			{
+				let _peer = enter_peer("Qm");
+				assert_eq!(current().block, Some(("5".into(), "1".into())));
+				assert_eq!(current().peer_id, Some("Qm".into()));
+			}
+			assert_eq!(current().peer_id, None);
+		}
+
+		assert_eq!(current(), LogContext::default());
+	}
+}
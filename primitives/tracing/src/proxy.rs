@@ -0,0 +1,116 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proxy of the spans entered by the wasm runtime.
+//!
+//! The runtime can't create `tracing` spans itself, it enters and exits spans through host
+//! functions instead. The host creates a span for each of them with the target and name
+//! [`WASM_TRACE_IDENTIFIER`], recording the actual target and name of the span as the
+//! [`WASM_TARGET_KEY`] and [`WASM_NAME_KEY`] fields, and keeps it entered until the runtime
+//! exits it.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tracing::{span, field, Level, Span};
+
+/// The target and name of the spans of the runtime.
+pub const WASM_TRACE_IDENTIFIER: &str = "wasm_tracing";
+/// The field recording the target of a span of the runtime.
+pub const WASM_TARGET_KEY: &str = "wasm_target";
+/// The field recording the name of a span of the runtime.
+pub const WASM_NAME_KEY: &str = "wasm_name";
+
+/// Maximum number of spans of the runtime entered at once. Spans that aren't exited, e.g.
+/// because the runtime trapped, are dropped once this is reached.
+const MAX_SPANS_LEN: usize = 1000;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+	/// The spans of the runtime entered on this thread, innermost last.
+	static SPANS: RefCell<Vec<(u64, Span)>> = RefCell::new(Vec::new());
+}
+
+/// Enter a span of the runtime with the given target and name, recording the index of the
+/// extrinsic being applied if given.
+///
+/// Returns the id of the span, `0` if no subscriber is interested in it.
+pub fn enter_span(target: &str, name: &str, extrinsic_index: Option<u32>) -> u64 {
+	let span = span!(
+		target: WASM_TRACE_IDENTIFIER,
+		Level::DEBUG,
+		WASM_TRACE_IDENTIFIER,
+		wasm_target = target,
+		wasm_name = name,
+		extrinsic_index = field::Empty,
+	);
+	if span.is_disabled() {
+		return 0;
+	}
+	if let Some(index) = extrinsic_index {
+		span.record("extrinsic_index", &index);
+	}
+
+	let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+	span.with_subscriber(|(span_id, subscriber)| subscriber.enter(span_id));
+	SPANS.with(|spans| {
+		let mut spans = spans.borrow_mut();
+		if spans.len() >= MAX_SPANS_LEN {
+			log::warn!(
+				target: WASM_TRACE_IDENTIFIER,
+				"Too many spans of the runtime entered, dropping the outermost one",
+			);
+			let (_, span) = spans.remove(0);
+			exit(&span);
+		}
+		spans.push((id, span));
+	});
+	id
+}
+
+/// Exit the span of the runtime with the given id, and the spans entered after it that
+/// weren't exited.
+pub fn exit_span(id: u64) {
+	if id == 0 {
+		return;
+	}
+	SPANS.with(|spans| {
+		let mut spans = spans.borrow_mut();
+		if let Some(pos) = spans.iter().rposition(|(span_id, _)| *span_id == id) {
+			for (_, span) in spans.drain(pos..).rev() {
+				exit(&span);
+			}
+		}
+	});
+}
+
+fn exit(span: &Span) {
+	span.with_subscriber(|(span_id, subscriber)| subscriber.exit(span_id));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn spans_are_not_entered_without_subscriber() {
+		let id = enter_span("pallet_balances", "transfer", Some(1));
+		assert_eq!(id, 0);
+		SPANS.with(|spans| assert!(spans.borrow().is_empty()));
+		exit_span(id);
+	}
+}
@@ -46,10 +46,12 @@ pub enum Error {
 	#[display(fmt="[{:?}] Already imported", _0)]
 	AlreadyImported(Box<dyn std::any::Any + Send>),
 	/// The transaction cannot be imported cause it's a replacement and has too low priority.
-	#[display(fmt="Too low priority ({} > {})", old, new)]
+	#[display(fmt="Too low priority ({} <= {}, the priority {} bumped)", new, required, old)]
 	TooLowPriority {
 		/// Transaction already in the pool.
 		old: Priority,
+		/// The priority the transaction entering the pool has to exceed.
+		required: Priority,
 		/// Transaction entering the pool.
 		new: Priority
 	},
@@ -64,6 +66,9 @@ pub enum Error {
 	/// The pool is not accepting future transactions.
 	#[display(fmt="The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+	/// Too many unsigned transactions dispatching the same call were submitted in the last blocks.
+	#[display(fmt="Too many unsigned transactions dispatching the same call in the last blocks")]
+	RateLimited,
 }
 
 impl std::error::Error for Error {}
@@ -31,6 +31,7 @@ use sp_runtime::{
 	traits::{Block as BlockT, Member, NumberFor},
 	transaction_validity::{
 		TransactionLongevity, TransactionPriority, TransactionTag, TransactionSource,
+		InvalidTransaction,
 	},
 };
 
@@ -121,10 +122,57 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	/// Transaction has been replaced in the pool, by another transaction
 	/// that provides the same tags. (e.g. same (sender, nonce)).
 	Usurped(Hash),
-	/// Transaction has been dropped from the pool because of the limit.
-	Dropped,
+	/// Transaction has been dropped from the pool.
+	Dropped(DropReason),
 	/// Transaction is no longer valid in the current state.
-	Invalid,
+	Invalid(InvalidReason),
+}
+
+/// The reason a transaction has been dropped from the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DropReason {
+	/// The pool limits were exceeded and the transaction was evicted.
+	PoolFull,
+	/// The transaction ended up in the future queue, which the pool is configured to reject.
+	FutureRejected,
+	/// The transaction couldn't be promoted to the ready queue after the transactions
+	/// it depends on were included in a block.
+	PromotionFailed,
+}
+
+/// Custom `InvalidTransaction` code of doughnuts which have expired.
+///
+/// Matches `prml_doughnut::constants::error_code::VALIDATION_EXPIRED`.
+pub const DOUGHNUT_EXPIRED: u8 = 181;
+
+/// The reason a transaction is no longer valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InvalidReason {
+	/// The transaction was found invalid when revalidated against a new block.
+	Revalidation,
+	/// The doughnut the transaction is delegated with has expired.
+	DoughnutExpired,
+	/// The transaction outlived its longevity.
+	Stale,
+	/// The transaction was removed as invalid, e.g. by the block builder.
+	Removed,
+	/// A transaction it depends on is no longer valid.
+	DependencyInvalid,
+	/// The transaction couldn't be re-imported to the pool.
+	ImportFailed,
+	/// The validity of the transaction couldn't be determined.
+	Unknown,
+}
+
+impl From<&InvalidTransaction> for InvalidReason {
+	fn from(invalid: &InvalidTransaction) -> Self {
+		match invalid {
+			InvalidTransaction::Custom(DOUGHNUT_EXPIRED) => InvalidReason::DoughnutExpired,
+			_ => InvalidReason::Revalidation,
+		}
+	}
 }
 
 /// The stream of transaction events.
@@ -222,6 +270,13 @@ pub trait TransactionPool: Send + Sync {
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>> + Send>;
 
+	/// Returns all transactions in the future queue.
+	fn futures(&self) -> Vec<Arc<Self::InPoolTransaction>>;
+
+	// *** RPC
+	/// Returns the encoded issuer of the doughnut the transaction is delegated with, if any.
+	fn doughnut_issuer(&self, xt: &TransactionFor<Self>) -> Option<Vec<u8>>;
+
 	// *** Block production
 	/// Remove transactions identified by given hashes (and dependent transactions) from the pool.
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>>;
@@ -243,6 +298,14 @@ pub trait TransactionPool: Send + Sync {
 
 	/// Return specific ready transaction by hash, if there is one.
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
+
+	/// Notify the pool that the transaction was received from the given peer, so that the peer
+	/// is held accountable if the transaction turns out to be an invalid delegated transaction.
+	fn on_received(&self, hash: TxHash<Self>, peer: String);
+
+	/// Returns `true` if transactions received from the given peer are temporarily banned,
+	/// because the peer sent too many invalid delegated transactions.
+	fn is_peer_banned(&self, peer: &str) -> bool;
 }
 
 /// Events that the transaction pool listens for.
@@ -211,6 +211,61 @@ pub fn read_trie_value_with<
 	Ok(TrieDB::<L>::new(&*db, root)?.get_with(key, query).map(|x| x.map(|val| val.to_vec()))?)
 }
 
+/// Build the trie of the given key values, which must be sorted by key, and pass each node
+/// that isn't inlined in its parent to `insert`, together with its database key within the
+/// `keyspace` of a child trie (empty for the top trie). Returns the root of the trie.
+///
+/// Nodes are handed out as soon as they are built, so the input is never held in memory.
+pub fn build_trie<L: TrieConfiguration, I, A, B, F>(
+	keyspace: &[u8],
+	input: I,
+	insert: F,
+) -> TrieHash<L>
+	where
+		I: IntoIterator<Item = (A, B)>,
+		A: AsRef<[u8]> + Ord,
+		B: AsRef<[u8]>,
+		F: FnMut(Vec<u8>, Vec<u8>),
+{
+	let mut sink = NodeSink::<L::Hash, F> { keyspace, insert, root: None };
+	trie_db::trie_visit::<L, _, _, _, _>(input, &mut sink);
+	sink.root.unwrap_or_else(|| L::trie_root::<_, Vec<u8>, Vec<u8>>(core::iter::empty()))
+}
+
+/// Passes the nodes visited while building a trie to a closure, keyed like the state database.
+struct NodeSink<'a, H: Hasher, F> {
+	keyspace: &'a [u8],
+	insert: F,
+	root: Option<H::Out>,
+}
+
+impl<'a, H: Hasher, F: FnMut(Vec<u8>, Vec<u8>)> trie_db::ProcessEncodedNode<H::Out>
+	for NodeSink<'a, H, F>
+{
+	fn process(
+		&mut self,
+		prefix: Prefix,
+		encoded_node: Vec<u8>,
+		is_root: bool,
+	) -> trie_db::ChildReference<H::Out> {
+		// same rule as `trie_db::TrieBuilder`: small nodes are stored in their parent.
+		let len = encoded_node.len();
+		if !is_root && len < H::LENGTH {
+			let mut inline = H::Out::default();
+			inline.as_mut()[..len].copy_from_slice(&encoded_node);
+			return trie_db::ChildReference::Inline(inline, len);
+		}
+
+		let hash = H::hash(&encoded_node);
+		let derived_prefix = keyspace_as_prefix_alloc(self.keyspace, prefix);
+		(self.insert)(prefixed_key::<H>(&hash, (&derived_prefix.0, derived_prefix.1)), encoded_node);
+		if is_root {
+			self.root = Some(hash);
+		}
+		trie_db::ChildReference::Hash(hash)
+	}
+}
+
 /// Determine the default child trie root.
 pub fn default_child_trie_root<L: TrieConfiguration>(
 	_storage_key: &[u8],
@@ -533,6 +588,39 @@ mod tests {
 		assert_eq!(root1, root2);
 	}
 
+	#[test]
+	fn build_trie_inserts_the_nodes_of_the_trie() {
+		let pairs: Vec<(Vec<u8>, Vec<u8>)> = (0u32..200)
+			.map(|i| (i.to_be_bytes().to_vec(), vec![i as u8; 1 + (i % 40) as usize]))
+			.collect();
+
+		for keyspace in vec![&b""[..], &b"child"[..]] {
+			let mut expected = PrefixedMemoryDB::<Blake2Hasher>::default();
+			let mut expected_root = Default::default();
+			{
+				let mut db = KeySpacedDBMut::<_, Blake2Hasher>::new(&mut expected, keyspace);
+				let mut t = TrieDBMut::<Layout>::new(&mut db, &mut expected_root);
+				for (key, value) in &pairs {
+					t.insert(key, value).unwrap();
+				}
+			}
+
+			let mut built = std::collections::HashMap::new();
+			let root = build_trie::<Layout, _, _, _, _>(
+				keyspace,
+				pairs.iter().map(|(key, value)| (key, value)),
+				|key, node| { built.insert(key, node); },
+			);
+
+			assert_eq!(root, expected_root);
+			let expected: std::collections::HashMap<_, _> = expected.drain().into_iter()
+				.filter(|(_, (_, rc))| *rc > 0)
+				.map(|(key, (node, _))| (key, node))
+				.collect();
+			assert_eq!(built, expected);
+		}
+	}
+
 	#[test]
 	fn empty_is_equivalent() {
 		let input: Vec<(&[u8], &[u8])> = vec![];
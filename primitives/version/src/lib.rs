@@ -126,6 +126,11 @@ impl RuntimeVersion {
 	) -> bool {
 		self.apis.iter().any(|(s, v)| s == id && predicate(*v))
 	}
+
+	/// Returns the version of the api with `api_id`, or `None` if it is not implemented.
+	pub fn api_version(&self, id: &ApiId) -> Option<u32> {
+		self.apis.iter().find_map(|(s, v)| if s == id { Some(*v) } else { None })
+	}
 }
 
 #[cfg(feature = "std")]
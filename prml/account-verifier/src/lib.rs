@@ -0,0 +1,511 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! # Account Verifier Module
+//!
+//! A module for accounts verifying the signatures of their extrinsics with a registered method
+//! instead of their own key, e.g. a BLS12-381 key, a rotated sr25519 key or a smart wallet.
+//!
+//! ## Overview
+//!
+//! An account registers a verification method with a signed extrinsic, reserving a storage
+//! deposit for it. The extrinsic carries a proof that the method accepts the account: a signature
+//! of the `registration_payload` by the new key, or a proof of it accepted by the custom verifier,
+//! so an account can't lock itself out with a key it doesn't hold. The signatures of the account are then only verified by the method, which is
+//! either a key of a supported scheme or a custom verifier of the runtime, e.g. a contract, checking
+//! the proof carried by a `MultiSignature::Custom` signature. Removing the method returns the
+//! deposit and verifies the signatures against the account again.
+//!
+//! `Custom` methods can only be registered when the runtime has a custom verifier.
+//!
+//! The signatures of the extrinsics are only verified through the registered methods when the
+//! runtime checks them in the `VerifierContext`, which must then be the context of its `Executive`:
+//!
+//! ```ignore
+//! pub type Executive = frame_executive::Executive<
+//!     Runtime, Block, prml_account_verifier::VerifierContext<Runtime>, Runtime, AllModules,
+//! >;
+//! ```
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `set_verification_method` - Register the method verifying the signatures of the sender.
+//! * `remove_verification_method` - Verify the signatures of the sender against the account again.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::{marker::PhantomData, prelude::*};
+use codec::{Encode, Decode};
+use sp_core::{bls12_381, ecdsa, sr25519};
+use sp_runtime::{
+	AccountId32, MultiSignature, RuntimeDebug,
+	traits::{Lookup, LookupError, StaticLookup, Verify, VerifyAccountSignature},
+};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure,
+	traits::{Get, StorageDepositCollector},
+	weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_signed};
+
+/// A method verifying the signatures of an account.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum VerificationMethod {
+	/// Sr25519 signatures by the key.
+	Sr25519(sr25519::Public),
+	/// ECDSA signatures by the key.
+	Ecdsa(ecdsa::Public),
+	/// BLS12-381 signatures by the key.
+	Bls12381(bls12_381::Public),
+	/// Proofs checked by the custom verifier of the runtime, with the parameters registered by the
+	/// account, e.g. the address of a wallet contract.
+	Custom(Vec<u8>),
+}
+
+/// Checks the proofs of the accounts with a `Custom` verification method.
+///
+/// Proofs are checked when extrinsics are validated, before any fee is paid, so checking one must
+/// be cheap.
+pub trait CustomVerifier<AccountId> {
+	/// Return `true` if `proof` of `msg` is valid for `who`, registered with `params`.
+	fn verify(who: &AccountId, params: &[u8], proof: &[u8], msg: &[u8]) -> bool;
+
+	/// Return `false` if `Custom` verification methods can't be registered.
+	fn is_enabled() -> bool { true }
+}
+
+impl<AccountId> CustomVerifier<AccountId> for () {
+	fn verify(_: &AccountId, _: &[u8], _: &[u8], _: &[u8]) -> bool { false }
+
+	fn is_enabled() -> bool { false }
+}
+
+/// The context of the payload signed to register a verification method.
+pub const REGISTRATION_CONTEXT: &[u8] = b"plug/account-verifier";
+
+/// The payload `who` proves the new verification `method` accepts when registering it.
+pub fn registration_payload<AccountId: Encode>(who: &AccountId, method: &VerificationMethod) -> Vec<u8> {
+	(REGISTRATION_CONTEXT, who, method).encode()
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// Checks the proofs of the accounts with a `Custom` verification method.
+	type CustomVerifier: CustomVerifier<Self::AccountId>;
+
+	/// Collects the deposits of the registered verification methods.
+	type StorageDeposit: StorageDepositCollector<Self::AccountId>;
+
+	/// The maximum length of the parameters of a `Custom` verification method.
+	type MaxCustomParamsLen: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as AccountVerifier {
+		/// The methods verifying the signatures of accounts.
+		pub VerificationMethods get(fn verification_method):
+			map hasher(blake2_128_concat) T::AccountId => Option<VerificationMethod>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+		/// The signatures of an account are verified by a registered method.
+		VerificationMethodSet(AccountId),
+		/// The signatures of an account are verified against the account again.
+		VerificationMethodRemoved(AccountId),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The parameters of the custom verification method are too long.
+		ParamsTooLong,
+		/// The account has no registered verification method.
+		NotRegistered,
+		/// The runtime has no custom verifier.
+		CustomVerifierUnavailable,
+		/// The proof isn't accepted by the verification method.
+		InvalidProof,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The maximum length of the parameters of a `Custom` verification method.
+		const MaxCustomParamsLen: u32 = T::MaxCustomParamsLen::get();
+
+		fn deposit_event() = default;
+
+		/// Register the method verifying the signatures of the sender, replacing its current one.
+		///
+		/// The dispatch origin for this call must be _Signed_. `proof` is the signature of the
+		/// `registration_payload` of the sender and method by the method. The storage deposit of
+		/// the method is reserved, and the deposit of the replaced one returned.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn set_verification_method(origin, method: VerificationMethod, proof: MultiSignature) {
+			let who = ensure_signed(origin)?;
+			if let VerificationMethod::Custom(ref params) = method {
+				ensure!(T::CustomVerifier::is_enabled(), Error::<T>::CustomVerifierUnavailable);
+				ensure!(params.len() as u32 <= T::MaxCustomParamsLen::get(), Error::<T>::ParamsTooLong);
+			}
+			ensure!(
+				Self::verify_with_method(&who, &method, &proof, &registration_payload(&who, &method)),
+				Error::<T>::InvalidProof,
+			);
+
+			T::StorageDeposit::charge(&who, 1, method.encoded_size() as u32)?;
+			if let Some(replaced) = <VerificationMethods<T>>::get(&who) {
+				T::StorageDeposit::refund(&who, 1, replaced.encoded_size() as u32);
+			}
+			<VerificationMethods<T>>::insert(&who, method);
+
+			Self::deposit_event(RawEvent::VerificationMethodSet(who));
+		}
+
+		/// Remove the verification method of the sender, whose signatures are then verified
+		/// against the account again.
+		///
+		/// The dispatch origin for this call must be _Signed_. The storage deposit of the method
+		/// is returned.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn remove_verification_method(origin) {
+			let who = ensure_signed(origin)?;
+			let method = <VerificationMethods<T>>::take(&who).ok_or(Error::<T>::NotRegistered)?;
+			T::StorageDeposit::refund(&who, 1, method.encoded_size() as u32);
+
+			Self::deposit_event(RawEvent::VerificationMethodRemoved(who));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Verify `signature` of `msg` by `who` with the verification `method`.
+	fn verify_with_method(
+		who: &T::AccountId,
+		method: &VerificationMethod,
+		signature: &MultiSignature,
+		msg: &[u8],
+	) -> bool {
+		match (method, signature) {
+			(VerificationMethod::Sr25519(key), MultiSignature::Sr25519(sig)) => sig.verify(msg, key),
+			(VerificationMethod::Ecdsa(key), MultiSignature::Ecdsa(sig)) => sig.verify(msg, key),
+			(VerificationMethod::Bls12381(key), MultiSignature::Bls12381(sig)) =>
+				sp_io::crypto::bls12_381_verify(sig, msg, key),
+			(VerificationMethod::Custom(params), MultiSignature::Custom(proof)) =>
+				T::CustomVerifier::verify(who, params, proof, msg),
+			_ => false,
+		}
+	}
+}
+
+impl<T: Trait<AccountId = AccountId32>> Module<T> {
+	/// Verify `signature` of `msg` by `who`, with the registered verification method of `who` if
+	/// any. Return `true` if it's valid or batched, see `Verify::batch_verify`.
+	pub fn verify_signature(who: &AccountId32, signature: &MultiSignature, msg: &[u8]) -> bool {
+		match (Self::verification_method(who), signature) {
+			(None, _) => signature.batch_verify(msg, who),
+			(Some(VerificationMethod::Sr25519(key)), MultiSignature::Sr25519(sig)) =>
+				sig.batch_verify(msg, &key),
+			(Some(method), _) => Self::verify_with_method(who, &method, signature, msg),
+		}
+	}
+}
+
+/// The context checking extrinsics in which the signatures of accounts are verified with their
+/// registered verification methods.
+pub struct VerifierContext<T>(PhantomData<T>);
+
+impl<T> Default for VerifierContext<T> {
+	fn default() -> Self {
+		VerifierContext(PhantomData)
+	}
+}
+
+impl<T: Trait> Lookup for VerifierContext<T> {
+	type Source = <T::Lookup as StaticLookup>::Source;
+	type Target = <T::Lookup as StaticLookup>::Target;
+
+	fn lookup(&self, s: Self::Source) -> Result<Self::Target, LookupError> {
+		<T::Lookup as StaticLookup>::lookup(s)
+	}
+}
+
+impl<T: Trait<AccountId = AccountId32>> VerifyAccountSignature<MultiSignature> for VerifierContext<T> {
+	fn verify_account_signature(
+		&self,
+		signature: &MultiSignature,
+		msg: &[u8],
+		signer: &AccountId32,
+	) -> bool {
+		<Module<T>>::verify_signature(signer, signature, msg)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types, dispatch::DispatchResult,
+		traits::ReservableCurrency, weights::Weight,
+	};
+	use sp_core::{H256, Pair};
+	use sp_runtime::{
+		MultiSigner, Perbill, testing::Header,
+		traits::{BlakeTwo256, IdentifyAccount, IdentityLookup},
+	};
+	use crate as account_verifier;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			pallet_balances<T>,
+			account_verifier<T>,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = AccountId32;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = TestEvent;
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+
+	thread_local! {
+		static CUSTOM_VERIFIER_ENABLED: std::cell::Cell<bool> = std::cell::Cell::new(true);
+	}
+
+	/// Accepts the proofs equal to the registered parameters.
+	pub struct EchoVerifier;
+	impl CustomVerifier<AccountId32> for EchoVerifier {
+		fn verify(_: &AccountId32, params: &[u8], proof: &[u8], _: &[u8]) -> bool {
+			params == proof
+		}
+
+		fn is_enabled() -> bool {
+			CUSTOM_VERIFIER_ENABLED.with(|enabled| enabled.get())
+		}
+	}
+
+	parameter_types! {
+		pub const DepositPerByte: u64 = 1;
+		pub const DepositId: frame_system::StorageDepositId = *b"verifier";
+		pub const MaxCustomParamsLen: u32 = 4;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type CustomVerifier = EchoVerifier;
+		type StorageDeposit = frame_system::StorageDeposit<Test, Balances, DepositPerByte, DepositId>;
+		type MaxCustomParamsLen = MaxCustomParamsLen;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type AccountVerifier = Module<Test>;
+
+	const MSG: &[u8] = b"payload";
+
+	fn account(pair: &sr25519::Pair) -> AccountId32 {
+		MultiSigner::from(pair.public()).into_account()
+	}
+
+	/// Register the key method of `pair` for `who`, proven by `pair`.
+	fn register<P: Pair>(who: &AccountId32, pair: &P, method: VerificationMethod) -> DispatchResult
+		where MultiSignature: From<P::Signature>
+	{
+		let proof = MultiSignature::from(pair.sign(&registration_payload(who, &method)));
+		AccountVerifier::set_verification_method(Origin::signed(who.clone()), method, proof)
+	}
+
+	fn new_test_ext(accounts: &[&sr25519::Pair]) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: accounts.iter().map(|pair| (account(pair), 100)).collect(),
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn signatures_are_verified_against_accounts_without_method() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			let signature = MultiSignature::from(alice.sign(MSG));
+			assert!(AccountVerifier::verify_signature(&account(&alice), &signature, MSG));
+			assert!(!AccountVerifier::verify_signature(&account(&alice), &signature, b"other"));
+		});
+	}
+
+	#[test]
+	fn signatures_are_verified_with_registered_method() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let rotated = sr25519::Pair::from_string("//Rotated", None).unwrap();
+		let bls = bls12_381::Pair::from_string("//Alice", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			let who = account(&alice);
+			let method = VerificationMethod::Sr25519(rotated.public());
+			assert_ok!(register(&who, &rotated, method.clone()));
+			assert_eq!(Balances::reserved_balance(&who), method.encoded_size() as u64);
+
+			let own = MultiSignature::from(alice.sign(MSG));
+			assert!(!AccountVerifier::verify_signature(&who, &own, MSG));
+			let signature = MultiSignature::from(rotated.sign(MSG));
+			assert!(AccountVerifier::verify_signature(&who, &signature, MSG));
+
+			let method = VerificationMethod::Bls12381(bls.public());
+			assert_ok!(register(&who, &bls, method.clone()));
+			assert_eq!(Balances::reserved_balance(&who), method.encoded_size() as u64);
+			assert!(!AccountVerifier::verify_signature(&who, &signature, MSG));
+			let signature = MultiSignature::from(bls.sign(MSG));
+			assert!(AccountVerifier::verify_signature(&who, &signature, MSG));
+		});
+	}
+
+	#[test]
+	fn custom_proofs_are_checked_by_custom_verifier() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			let who = account(&alice);
+			assert_noop!(
+				AccountVerifier::set_verification_method(
+					Origin::signed(who.clone()),
+					VerificationMethod::Custom(vec![0; 5]),
+					MultiSignature::Custom(vec![0; 5]),
+				),
+				Error::<Test>::ParamsTooLong,
+			);
+			assert_noop!(
+				AccountVerifier::set_verification_method(
+					Origin::signed(who.clone()),
+					VerificationMethod::Custom(vec![1, 2]),
+					MultiSignature::Custom(vec![3]),
+				),
+				Error::<Test>::InvalidProof,
+			);
+			assert_ok!(AccountVerifier::set_verification_method(
+				Origin::signed(who.clone()),
+				VerificationMethod::Custom(vec![1, 2]),
+				MultiSignature::Custom(vec![1, 2]),
+			));
+
+			assert!(AccountVerifier::verify_signature(&who, &MultiSignature::Custom(vec![1, 2]), MSG));
+			assert!(!AccountVerifier::verify_signature(&who, &MultiSignature::Custom(vec![3]), MSG));
+		});
+	}
+
+	#[test]
+	fn removing_method_verifies_against_account_and_returns_deposit() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let rotated = sr25519::Pair::from_string("//Rotated", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			let who = account(&alice);
+			assert_noop!(
+				AccountVerifier::remove_verification_method(Origin::signed(who.clone())),
+				Error::<Test>::NotRegistered,
+			);
+			assert_ok!(register(&who, &rotated, VerificationMethod::Sr25519(rotated.public())));
+			assert_ok!(AccountVerifier::remove_verification_method(Origin::signed(who.clone())));
+
+			assert_eq!(Balances::reserved_balance(&who), 0);
+			assert_eq!(System::account_storage(&who), Default::default());
+			let signature = MultiSignature::from(alice.sign(MSG));
+			assert!(AccountVerifier::verify_signature(&who, &signature, MSG));
+		});
+	}
+
+	#[test]
+	fn methods_must_prove_they_accept_the_account() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		let rotated = sr25519::Pair::from_string("//Rotated", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			let who = account(&alice);
+			let method = VerificationMethod::Sr25519(rotated.public());
+			// Signed by the account instead of the new key.
+			assert_noop!(register(&who, &alice, method.clone()), Error::<Test>::InvalidProof);
+			// Signed by the new key for another account.
+			let proof = MultiSignature::from(rotated.sign(&registration_payload(&account(&rotated), &method)));
+			assert_noop!(
+				AccountVerifier::set_verification_method(Origin::signed(who.clone()), method.clone(), proof),
+				Error::<Test>::InvalidProof,
+			);
+			assert_ok!(register(&who, &rotated, method));
+		});
+	}
+
+	#[test]
+	fn custom_methods_require_a_custom_verifier() {
+		let alice = sr25519::Pair::from_string("//Alice", None).unwrap();
+		new_test_ext(&[&alice]).execute_with(|| {
+			CUSTOM_VERIFIER_ENABLED.with(|enabled| enabled.set(false));
+			assert_noop!(
+				AccountVerifier::set_verification_method(
+					Origin::signed(account(&alice)),
+					VerificationMethod::Custom(vec![1, 2]),
+					MultiSignature::Custom(vec![1, 2]),
+				),
+				Error::<Test>::CustomVerifierUnavailable,
+			);
+			assert!(!<() as CustomVerifier<AccountId32>>::is_enabled());
+		});
+	}
+}
@@ -0,0 +1,628 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Bridge Module
+//!
+//! A module relaying the finalized headers of a remote plug chain and proving the storage of the
+//! remote chain to the other modules, e.g. the cross-chain asset modules, trusting only the
+//! GRANDPA authorities of the remote chain.
+//!
+//! ## Overview
+//!
+//! The bridge is initialized by root with a trusted finalized header of the remote chain and its
+//! GRANDPA authority set. Relayers then submit the headers from the last finalized header to a
+//! new finalized header, with the justification of the latter, which are verified by a
+//! `plug_light::LightClient` following the changes of the authority set of the remote chain.
+//!
+//! The state roots of the last `MaxStoredHeaders` relayed headers are kept, and a value of the
+//! storage of one of these blocks is proven with a storage proof of the remote chain, e.g. from
+//! its `state_getReadProof` RPC, by `prove_inclusion`.
+//!
+//! The keys of the validators of past sessions of the remote chain, e.g. the signers of an old
+//! justification, are proven by `prove_session_membership` with a proof of the remote
+//! `SessionMembershipApi` and a storage proof of the historical session root it is checked
+//! against.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `initialize` - Start relaying from a trusted finalized header of the remote chain.
+//! * `submit_finalized_headers` - Relay headers up to a new finalized header of the remote chain.
+//!
+//! ### Public Functions
+//!
+//! * `prove_inclusion` - Read a value of the storage of a relayed block from a storage proof, also
+//!   available to other modules through the `ProveInclusion` trait.
+//! * `prove_session_membership` - Read the identification of the owner of a validator key in a
+//!   past session of the remote chain from a session membership proof.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{DispatchError, KeyTypeId, RuntimeDebug, traits::Header as HeaderT};
+use sp_trie::{Layout, StorageProof, read_trie_value};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, Parameter,
+	StorageHasher, Twox128, Twox64Concat,
+	traits::Get,
+	weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_root, ensure_signed};
+use plug_light::{AuthoritySet, LightClient};
+
+/// The hash of a header of the remote chain.
+pub type RemoteHashOf<T> = <<T as Trait>::RemoteHeader as HeaderT>::Hash;
+
+/// The number of a header of the remote chain.
+pub type RemoteNumberOf<T> = <<T as Trait>::RemoteHeader as HeaderT>::Number;
+
+/// A proof of the storage of a relayed block of the remote chain.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct InclusionProof<Hash> {
+	/// The hash of the relayed block.
+	pub block: Hash,
+	/// The trie nodes of the storage of the block read by the proof.
+	pub proof: StorageProof,
+}
+
+/// A proof that a key is owned by a validator of a session of the remote chain, i.e. a
+/// `pallet_session::historical::Proof` of the remote chain, as returned by its
+/// `SessionMembershipApi`.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct SessionMembershipProof {
+	/// The session of the proof.
+	pub session: u32,
+	/// The trie nodes of the historical session trie read by the proof.
+	pub trie_nodes: Vec<Vec<u8>>,
+}
+
+/// Proves the storage of a remote chain, for the modules acting on the state of a remote chain.
+pub trait ProveInclusion {
+	/// The proof of the storage.
+	type Proof: Parameter;
+
+	/// The value of `remote_key` in the storage of the remote chain, `None` if it has none.
+	fn prove_inclusion(remote_key: &[u8], proof: Self::Proof) -> Result<Option<Vec<u8>>, DispatchError>;
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The header of the remote chain.
+	type RemoteHeader: HeaderT + Parameter;
+
+	/// The number of relayed headers whose state roots are kept.
+	type MaxStoredHeaders: Get<u32>;
+
+	/// The maximum number of headers relayed at once.
+	type MaxHeadersPerSubmission: Get<u32>;
+
+	/// The maximum size of an encoded justification.
+	type MaxJustificationSize: Get<u32>;
+
+	/// The maximum number of authorities of the remote chain followed by the bridge.
+	type MaxAuthorities: Get<u32>;
+}
+
+/// The weight of relaying headers, with the cost of verifying a justification against the
+/// largest authority set the bridge follows.
+fn submission_weight<T: Trait>(headers: usize, justification_len: usize) -> Weight {
+	100_000u32
+		.saturating_add((headers as Weight).saturating_mul(10_000))
+		.saturating_add((justification_len as Weight).saturating_mul(1_000))
+		.saturating_add(T::MaxAuthorities::get().saturating_mul(10_000))
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Bridge {
+		/// The light client of the remote chain, `None` until initialized.
+		pub Client get(fn client): Option<LightClient<T::RemoteHeader>>;
+
+		/// The numbers and state roots of the relayed headers, by hash.
+		pub StateRoots get(fn state_root):
+			map hasher(blake2_128_concat) RemoteHashOf<T> => Option<(RemoteNumberOf<T>, RemoteHashOf<T>)>;
+
+		/// The hashes of the relayed headers, in a ring of `MaxStoredHeaders` slots.
+		StoredHashes: map hasher(twox_64_concat) u32 => Option<RemoteHashOf<T>>;
+
+		/// The slot of the ring of stored hashes of the next relayed header.
+		NextSlot: u32;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where
+		AccountId = <T as system::Trait>::AccountId,
+		RemoteHash = RemoteHashOf<T>,
+		RemoteNumber = RemoteNumberOf<T>,
+	{
+		/// The bridge was initialized with a finalized header of the remote chain.
+		Initialized(RemoteHash, RemoteNumber),
+		/// A relayer relayed the headers up to a new finalized header of the remote chain.
+		HeadersRelayed(AccountId, RemoteHash, RemoteNumber),
+		/// The authority set of the remote chain changed, to the set of the given id.
+		AuthoritySetChanged(u64),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The bridge isn't initialized.
+		NotInitialized,
+		/// The headers don't extend the finalized chain of the remote chain.
+		NotAChain,
+		/// The justification doesn't prove the finality of the last header.
+		InvalidJustification,
+		/// The headers go past the enactment of a change of the authority set, or schedule a
+		/// change that can't be followed.
+		InvalidAuthoritySetChange,
+		/// The block of the proof isn't a relayed block, or its state root was pruned.
+		UnknownBlock,
+		/// The storage proof is invalid.
+		InvalidProof,
+		/// The historical root of the session isn't stored by the relayed block.
+		UnknownSession,
+		/// The session membership proof doesn't prove the key is owned by a validator of the
+		/// session.
+		InvalidMembershipProof,
+		/// Too many headers are relayed at once.
+		TooManyHeaders,
+		/// The justification is too large.
+		JustificationTooLarge,
+		/// The authority set of the remote chain has too many authorities.
+		TooManyAuthorities,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The number of relayed headers whose state roots are kept.
+		const MaxStoredHeaders: u32 = T::MaxStoredHeaders::get();
+
+		/// The maximum number of headers relayed at once.
+		const MaxHeadersPerSubmission: u32 = T::MaxHeadersPerSubmission::get();
+
+		/// The maximum size of an encoded justification.
+		const MaxJustificationSize: u32 = T::MaxJustificationSize::get();
+
+		/// The maximum number of authorities of the remote chain followed by the bridge.
+		const MaxAuthorities: u32 = T::MaxAuthorities::get();
+
+		fn deposit_event() = default;
+
+		/// Start relaying from a trusted finalized header of the remote chain and its authority
+		/// set, replacing the current light client.
+		///
+		/// The dispatch origin for this call must be _Root_.
+		#[weight = SimpleDispatchInfo::FixedOperational(10_000)]
+		fn initialize(origin, header: T::RemoteHeader, authority_set: AuthoritySet) {
+			ensure_root(origin)?;
+			frame_support::ensure!(
+				authority_set.authorities.len() <= T::MaxAuthorities::get() as usize,
+				Error::<T>::TooManyAuthorities,
+			);
+			let (hash, number) = (header.hash(), *header.number());
+			Self::store_state_root(&header);
+			Client::<T>::put(LightClient::new(header, authority_set));
+
+			Self::deposit_event(RawEvent::Initialized(hash, number));
+		}
+
+		/// Relay the headers from the child of the last finalized header of the remote chain to a
+		/// new finalized header, with the encoded GRANDPA justification of the latter.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		///
+		/// # <weight>
+		/// - O(H + J + A) with H the number of headers, at most `MaxHeadersPerSubmission`, J the
+		///   size of the justification, at most `MaxJustificationSize`, and A the maximum number of
+		///   authorities `MaxAuthorities`.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<T::RemoteHeader>, &Vec<u8>)| submission_weight::<T>(args.0.len(), args.1.len()),
+			DispatchClass::Normal,
+			true
+		)]
+		fn submit_finalized_headers(origin, headers: Vec<T::RemoteHeader>, justification: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+			frame_support::ensure!(
+				headers.len() <= T::MaxHeadersPerSubmission::get() as usize,
+				Error::<T>::TooManyHeaders,
+			);
+			frame_support::ensure!(
+				justification.len() <= T::MaxJustificationSize::get() as usize,
+				Error::<T>::JustificationTooLarge,
+			);
+			let mut client = Self::client().ok_or(Error::<T>::NotInitialized)?;
+			let set_id = client.authority_set().set_id;
+
+			client.import_finalized(&headers, &justification).map_err(|e| match e {
+				plug_light::Error::NoHeaders | plug_light::Error::NotAChain => Error::<T>::NotAChain,
+				plug_light::Error::SpansAuthoritySetChange |
+				plug_light::Error::OverlappingChanges |
+				plug_light::Error::ForcedChange => Error::<T>::InvalidAuthoritySetChange,
+				_ => Error::<T>::InvalidJustification,
+			})?;
+
+			// the weight only covers the verification of justifications of bounded authority sets
+			frame_support::ensure!(
+				client.authority_set().authorities.len() <= T::MaxAuthorities::get() as usize,
+				Error::<T>::TooManyAuthorities,
+			);
+
+			let finalized = client.finalized();
+			let (hash, number) = (finalized.hash(), *finalized.number());
+			Self::store_state_root(finalized);
+			if client.authority_set().set_id != set_id {
+				Self::deposit_event(RawEvent::AuthoritySetChanged(client.authority_set().set_id));
+			}
+			Client::<T>::put(client);
+
+			Self::deposit_event(RawEvent::HeadersRelayed(who, hash, number));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The value of `remote_key` in the storage of a relayed block of the remote chain, read
+	/// from a storage proof, `None` if it has none.
+	pub fn prove_inclusion(
+		remote_key: &[u8],
+		proof: InclusionProof<RemoteHashOf<T>>,
+	) -> Result<Option<Vec<u8>>, DispatchError> {
+		let (_, state_root) = Self::state_root(proof.block).ok_or(Error::<T>::UnknownBlock)?;
+
+		plug_light::verify_storage_proof::<<T::RemoteHeader as HeaderT>::Hashing>(
+			&state_root,
+			proof.proof,
+			&[remote_key],
+		)
+			.map(|mut values| values.pop().flatten())
+			.map_err(|_| Error::<T>::InvalidProof.into())
+	}
+
+	/// The encoded full identification of the validator owning the given key in a past session
+	/// of the remote chain, e.g. to check the signers of an old justification.
+	///
+	/// The membership proof is checked against the historical root of its session, read from the
+	/// `Session HistoricalSessions` storage of a relayed block with `root_proof`.
+	pub fn prove_session_membership(
+		key: (KeyTypeId, &[u8]),
+		membership_proof: SessionMembershipProof,
+		root_proof: InclusionProof<RemoteHashOf<T>>,
+	) -> Result<Vec<u8>, DispatchError> {
+		let session = membership_proof.session.encode();
+		let root_key = [
+			&Twox128::hash(b"Session")[..],
+			&Twox128::hash(b"HistoricalSessions")[..],
+			&Twox64Concat::hash(&session)[..],
+		].concat();
+		let (root, _validator_count) = Self::prove_inclusion(&root_key, root_proof)?
+			.and_then(|raw| <(RemoteHashOf<T>, u32)>::decode(&mut &raw[..]).ok())
+			.ok_or(Error::<T>::UnknownSession)?;
+
+		// the historical trie maps each key to the index of its owner and each index to the full
+		// identification of the owner.
+		let db = StorageProof::new(membership_proof.trie_nodes)
+			.into_memory_db::<<T::RemoteHeader as HeaderT>::Hashing>();
+		let read = |key: &[u8]| {
+			read_trie_value::<Layout<<T::RemoteHeader as HeaderT>::Hashing>, _>(&db, &root, key)
+				.ok()
+				.flatten()
+		};
+		key.using_encoded(|key| read(key))
+			.and_then(|owner_index| read(&owner_index))
+			.ok_or(Error::<T>::InvalidMembershipProof.into())
+	}
+
+	/// Keep the state root of a relayed header, pruning the oldest one.
+	fn store_state_root(header: &T::RemoteHeader) {
+		let hash = header.hash();
+		let slot = NextSlot::get();
+		if let Some(pruned) = StoredHashes::<T>::get(slot) {
+			StateRoots::<T>::remove(pruned);
+		}
+		StoredHashes::<T>::insert(slot, hash);
+		NextSlot::put((slot + 1) % T::MaxStoredHeaders::get().max(1));
+		StateRoots::<T>::insert(hash, (*header.number(), *header.state_root()));
+	}
+}
+
+impl<T: Trait> ProveInclusion for Module<T> {
+	type Proof = InclusionProof<RemoteHashOf<T>>;
+
+	fn prove_inclusion(remote_key: &[u8], proof: Self::Proof) -> Result<Option<Vec<u8>>, DispatchError> {
+		Self::prove_inclusion(remote_key, proof)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types, weights::GetDispatchInfo,
+	};
+	use sp_core::H256;
+	use sp_finality_grandpa::AuthorityId;
+	use sp_keyring::Ed25519Keyring;
+	use sp_runtime::{Perbill, generic, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+	use sp_trie::{MemoryDB, Recorder, Trie, TrieMut, trie_types::{TrieDB, TrieDBMut}};
+	use crate as bridge;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			bridge<T>,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const MaxStoredHeaders: u32 = 2;
+		pub const MaxHeadersPerSubmission: u32 = 4;
+		pub const MaxJustificationSize: u32 = 1024;
+		pub const MaxAuthorities: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type RemoteHeader = RemoteHeader;
+		type MaxStoredHeaders = MaxStoredHeaders;
+		type MaxHeadersPerSubmission = MaxHeadersPerSubmission;
+		type MaxJustificationSize = MaxJustificationSize;
+		type MaxAuthorities = MaxAuthorities;
+	}
+	type Bridge = Module<Test>;
+
+	type RemoteHeader = generic::Header<u64, BlakeTwo256>;
+
+	const VOTERS: [Ed25519Keyring; 3] = [Ed25519Keyring::Alice, Ed25519Keyring::Bob, Ed25519Keyring::Charlie];
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	}
+
+	fn authority_set() -> AuthoritySet {
+		AuthoritySet {
+			set_id: 0,
+			authorities: VOTERS.iter().map(|voter| (AuthorityId::from(voter.public()), 1)).collect(),
+		}
+	}
+
+	fn header(number: u64, parent_hash: H256, state_root: H256) -> RemoteHeader {
+		RemoteHeader::new(number, Default::default(), state_root, parent_hash, Default::default())
+	}
+
+	fn justification(target: &RemoteHeader, voters: &[Ed25519Keyring]) -> Vec<u8> {
+		let precommit = finality_grandpa::Precommit::new(target.hash(), target.number);
+		let payload = sp_finality_grandpa::localized_payload(
+			1,
+			0,
+			&finality_grandpa::Message::Precommit(precommit.clone()),
+		);
+		let precommits = voters.iter()
+			.map(|voter| finality_grandpa::SignedPrecommit {
+				precommit: precommit.clone(),
+				signature: voter.sign(&payload).into(),
+				id: voter.public().into(),
+			})
+			.collect();
+		plug_light::GrandpaJustification::<RemoteHeader> {
+			round: 1,
+			commit: finality_grandpa::Commit { target_hash: target.hash(), target_number: target.number, precommits },
+			votes_ancestries: vec![],
+		}.encode()
+	}
+
+	/// A remote storage with the given values, its root and a proof of the first key.
+	fn remote_storage(values: &[(&[u8], &[u8])]) -> (H256, StorageProof) {
+		let mut db = MemoryDB::<BlakeTwo256>::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMut::<BlakeTwo256>::new(&mut db, &mut root);
+			for (key, value) in values {
+				trie.insert(key, value).unwrap();
+			}
+		}
+
+		let trie = TrieDB::<BlakeTwo256>::new(&db, &root).unwrap();
+		let mut recorder = Recorder::new();
+		trie.get_with(values[0].0, &mut recorder).unwrap();
+		(root, StorageProof::new(recorder.drain().into_iter().map(|record| record.data).collect()))
+	}
+
+	#[test]
+	fn finalized_headers_are_relayed() {
+		new_test_ext().execute_with(|| {
+			let genesis = header(0, Default::default(), Default::default());
+			let first = header(1, genesis.hash(), Default::default());
+			assert_noop!(
+				Bridge::submit_finalized_headers(Origin::signed(1), vec![first.clone()], justification(&first, &VOTERS)),
+				Error::<Test>::NotInitialized,
+			);
+			assert_ok!(Bridge::initialize(Origin::ROOT, genesis.clone(), authority_set()));
+
+			assert_noop!(
+				Bridge::submit_finalized_headers(Origin::signed(1), vec![first.clone()], justification(&first, &VOTERS[..2])),
+				Error::<Test>::InvalidJustification,
+			);
+			assert_noop!(
+				Bridge::submit_finalized_headers(Origin::signed(1), vec![], justification(&first, &VOTERS)),
+				Error::<Test>::NotAChain,
+			);
+			assert_ok!(Bridge::submit_finalized_headers(Origin::signed(1), vec![first.clone()], justification(&first, &VOTERS)));
+			assert_eq!(Bridge::client().unwrap().finalized(), &first);
+
+			// the oldest state root is pruned
+			let second = header(2, first.hash(), Default::default());
+			assert_ok!(Bridge::submit_finalized_headers(Origin::signed(1), vec![second.clone()], justification(&second, &VOTERS)));
+			assert_eq!(Bridge::state_root(genesis.hash()), None);
+			assert_eq!(Bridge::state_root(first.hash()), Some((1, Default::default())));
+			assert_eq!(Bridge::state_root(second.hash()), Some((2, Default::default())));
+		});
+	}
+
+	#[test]
+	fn submissions_are_bounded_and_weighed() {
+		new_test_ext().execute_with(|| {
+			let genesis = header(0, Default::default(), Default::default());
+			let mut too_many = authority_set();
+			too_many.authorities.push((AuthorityId::from(Ed25519Keyring::Dave.public()), 1));
+			assert_noop!(
+				Bridge::initialize(Origin::ROOT, genesis.clone(), too_many),
+				Error::<Test>::TooManyAuthorities,
+			);
+			assert_ok!(Bridge::initialize(Origin::ROOT, genesis.clone(), authority_set()));
+
+			let mut headers = vec![header(1, genesis.hash(), Default::default())];
+			for number in 2..=5 {
+				let parent = headers.last().unwrap().hash();
+				headers.push(header(number, parent, Default::default()));
+			}
+			let last = headers.last().unwrap().clone();
+			assert_noop!(
+				Bridge::submit_finalized_headers(Origin::signed(1), headers.clone(), justification(&last, &VOTERS)),
+				Error::<Test>::TooManyHeaders,
+			);
+			assert_noop!(
+				Bridge::submit_finalized_headers(Origin::signed(1), headers[..1].to_vec(), vec![0; 1025]),
+				Error::<Test>::JustificationTooLarge,
+			);
+
+			let weight = |headers: Vec<RemoteHeader>, justification: Vec<u8>| {
+				Call::<Test>::submit_finalized_headers(headers, justification).get_dispatch_info().weight
+			};
+			assert_eq!(weight(vec![], vec![]), 130_000);
+			assert_eq!(weight(headers[..2].to_vec(), vec![0; 100]), 250_000);
+		});
+	}
+
+	#[test]
+	fn remote_storage_is_proven() {
+		new_test_ext().execute_with(|| {
+			let (state_root, proof) = remote_storage(&[(&b"key"[..], &b"value"[..]), (&b"other"[..], &b"x"[..])]);
+			let genesis = header(0, Default::default(), Default::default());
+			let first = header(1, genesis.hash(), state_root);
+			assert_ok!(Bridge::initialize(Origin::ROOT, genesis.clone(), authority_set()));
+			assert_ok!(Bridge::submit_finalized_headers(Origin::signed(1), vec![first.clone()], justification(&first, &VOTERS)));
+
+			let inclusion = |block| InclusionProof { block, proof: proof.clone() };
+			assert_eq!(
+				<Bridge as ProveInclusion>::prove_inclusion(b"key", inclusion(first.hash())),
+				Ok(Some(b"value".to_vec())),
+			);
+			assert_eq!(
+				Bridge::prove_inclusion(b"key", inclusion(genesis.hash())),
+				Err(Error::<Test>::InvalidProof.into()),
+			);
+			assert_eq!(
+				Bridge::prove_inclusion(b"key", inclusion(H256::repeat_byte(1))),
+				Err(Error::<Test>::UnknownBlock.into()),
+			);
+		});
+	}
+
+	#[test]
+	fn remote_session_membership_is_proven() {
+		const KEY_TYPE: KeyTypeId = KeyTypeId(*b"gran");
+
+		new_test_ext().execute_with(|| {
+			// the historical trie of the session, with the key of the validator with index 0.
+			let mut db = MemoryDB::<BlakeTwo256>::default();
+			let mut root = Default::default();
+			{
+				let mut trie = TrieDBMut::<BlakeTwo256>::new(&mut db, &mut root);
+				trie.insert(&(KEY_TYPE, &[1u8; 32][..]).encode(), &0u32.encode()).unwrap();
+				trie.insert(&0u32.encode(), &(7u64, 100u64).encode()).unwrap();
+			}
+			let trie = TrieDB::<BlakeTwo256>::new(&db, &root).unwrap();
+			let mut recorder = Recorder::new();
+			trie.get_with(&(KEY_TYPE, &[1u8; 32][..]).encode(), &mut recorder).unwrap();
+			trie.get_with(&0u32.encode(), &mut recorder).unwrap();
+			let membership_proof = SessionMembershipProof {
+				session: 3,
+				trie_nodes: recorder.drain().into_iter().map(|record| record.data).collect(),
+			};
+
+			let root_key = [
+				&Twox128::hash(b"Session")[..],
+				&Twox128::hash(b"HistoricalSessions")[..],
+				&Twox64Concat::hash(&3u32.encode())[..],
+			].concat();
+			let root_value = (root, 1u32).encode();
+			let (state_root, proof) = remote_storage(&[(&root_key[..], &root_value[..])]);
+			let genesis = header(0, Default::default(), Default::default());
+			let first = header(1, genesis.hash(), state_root);
+			assert_ok!(Bridge::initialize(Origin::ROOT, genesis, authority_set()));
+			assert_ok!(Bridge::submit_finalized_headers(Origin::signed(1), vec![first.clone()], justification(&first, &VOTERS)));
+
+			let root_proof = InclusionProof { block: first.hash(), proof };
+			assert_eq!(
+				Bridge::prove_session_membership((KEY_TYPE, &[1u8; 32][..]), membership_proof.clone(), root_proof.clone()),
+				Ok((7u64, 100u64).encode()),
+			);
+			assert_eq!(
+				Bridge::prove_session_membership((KEY_TYPE, &[2u8; 32][..]), membership_proof.clone(), root_proof.clone()),
+				Err(Error::<Test>::InvalidMembershipProof.into()),
+			);
+			assert_eq!(
+				Bridge::prove_session_membership(
+					(KEY_TYPE, &[1u8; 32][..]),
+					SessionMembershipProof { session: 4, ..membership_proof },
+					root_proof,
+				),
+				Err(Error::<Test>::UnknownSession.into()),
+			);
+		});
+	}
+}
@@ -0,0 +1,530 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Documents Module
+//!
+//! A module anchoring the content hashes of documents stored on IPFS, and collecting attestations
+//! of the anchored documents.
+//!
+//! ## Overview
+//!
+//! A document is anchored by its owner with the hash of its content and its IPFS CID, reserving
+//! `AnchorDeposit` until it is removed. Accounts having checked the document, e.g. the parties of
+//! a contract or an auditor, attest it, up to `MaxAttestations` attestations per document.
+//!
+//! At most `MaxAnchorsPerBlock` documents are anchored in a block. The offchain worker of the
+//! module fetches the documents anchored in a block from the IPFS gateway of the node, checks
+//! their content against the anchored hash and pins them on the IPFS node within a single
+//! deadline, recording the outcome in the offchain storage under `DOCUMENT_STATUS_PREFIX`
+//! followed by the content hash. The documents it had no time left to check get no status. The offchain workers of other modules can fetch the checked content of an
+//! anchored document with `fetch`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `anchor` - Anchor a document by its content hash and CID.
+//! * `attest` - Attest an anchored document.
+//! * `remove` - Remove an anchored document and its attestations, by its owner.
+//!
+//! ### Public Functions
+//!
+//! * `fetch` - Fetch the content of an anchored document from IPFS in an offchain worker.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{
+	RuntimeDebug,
+	offchain::{Duration, Timestamp, storage::StorageValueRef},
+	traits::Hash,
+};
+use frame_support::{
+	debug, decl_module, decl_event, decl_error, decl_storage, ensure,
+	traits::{Currency, Get, ReservableCurrency},
+	weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, WeighData, Weight},
+};
+use frame_system::{self as system, ensure_signed};
+
+/// The prefix of the offchain storage keys of the statuses of the anchored documents.
+pub const DOCUMENT_STATUS_PREFIX: &[u8] = b"prml-documents::status::";
+
+/// The time given to IPFS to serve or pin a document, in milliseconds.
+const IPFS_DEADLINE_MS: u64 = 30_000;
+
+/// The time given to IPFS to serve and pin all the documents anchored in a block, in
+/// milliseconds.
+const OFFCHAIN_WORKER_DEADLINE_MS: u64 = 60_000;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// An anchored document.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Document<AccountId, Balance, BlockNumber> {
+	/// The owner.
+	pub owner: AccountId,
+	/// The amount reserved from the owner until the document is removed.
+	pub deposit: Balance,
+	/// The IPFS CID of the content.
+	pub cid: Vec<u8>,
+	/// The block the document was anchored at.
+	pub anchored_at: BlockNumber,
+}
+
+/// The outcome of the checks of an anchored document by the offchain worker.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum DocumentStatus {
+	/// The content matches the anchored hash, and is pinned.
+	Pinned,
+	/// The content matches the anchored hash, but couldn't be pinned.
+	NotPinned,
+	/// The content doesn't match the anchored hash.
+	Mismatch,
+	/// The content couldn't be fetched.
+	Unavailable,
+}
+
+/// An error fetching the content of an anchored document.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum FetchError {
+	/// The document isn't anchored.
+	NotAnchored,
+	/// The content couldn't be fetched from IPFS.
+	Unavailable,
+	/// The content doesn't match the anchored hash.
+	Mismatch,
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency the anchoring deposits are reserved in.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// The amount reserved from the owner of an anchored document.
+	type AnchorDeposit: Get<BalanceOf<Self>>;
+
+	/// The maximum length of a CID.
+	type MaxCidLength: Get<u32>;
+
+	/// The maximum number of attestations of a document.
+	type MaxAttestations: Get<u32>;
+
+	/// The maximum number of documents anchored in a block, checked by the offchain worker.
+	type MaxAnchorsPerBlock: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Documents {
+		/// The anchored documents, by content hash.
+		pub Documents get(fn document):
+			map hasher(identity) T::Hash => Option<Document<T::AccountId, BalanceOf<T>, T::BlockNumber>>;
+
+		/// The attestations of the anchored documents, by content hash and attester, with the
+		/// block they were made at.
+		pub Attestations get(fn attestation):
+			double_map hasher(identity) T::Hash, hasher(blake2_128_concat) T::AccountId
+			=> Option<T::BlockNumber>;
+
+		/// The number of attestations of the anchored documents, by content hash.
+		pub AttestationCount get(fn attestation_count): map hasher(identity) T::Hash => u32;
+
+		/// The documents anchored in this block, checked by the offchain worker.
+		Anchored: Vec<T::Hash>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId, Hash = <T as system::Trait>::Hash {
+		/// A document was anchored by its owner.
+		DocumentAnchored(Hash, AccountId),
+		/// A document was attested.
+		DocumentAttested(Hash, AccountId),
+		/// A document was removed.
+		DocumentRemoved(Hash),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The CID is longer than `MaxCidLength`.
+		CidTooLong,
+		/// A document with the content hash is already anchored.
+		AlreadyAnchored,
+		/// No document with the content hash is anchored.
+		NotAnchored,
+		/// The document is already attested by the account.
+		AlreadyAttested,
+		/// The origin doesn't own the document.
+		NotOwner,
+		/// The document has `MaxAttestations` attestations.
+		TooManyAttestations,
+		/// `MaxAnchorsPerBlock` documents are already anchored in this block.
+		TooManyAnchors,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The amount reserved from the owner of an anchored document.
+		const AnchorDeposit: BalanceOf<T> = T::AnchorDeposit::get();
+
+		/// The maximum length of a CID.
+		const MaxCidLength: u32 = T::MaxCidLength::get();
+
+		/// The maximum number of attestations of a document.
+		const MaxAttestations: u32 = T::MaxAttestations::get();
+
+		/// The maximum number of documents anchored in a block.
+		const MaxAnchorsPerBlock: u32 = T::MaxAnchorsPerBlock::get();
+
+		fn deposit_event() = default;
+
+		fn on_initialize() -> Weight {
+			Anchored::<T>::kill();
+			SimpleDispatchInfo::default().weigh_data(())
+		}
+
+		/// Anchor a document by the hash of its content and its IPFS CID, reserving
+		/// `AnchorDeposit` from the owner.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the owner of the document.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn anchor(origin, content_hash: T::Hash, cid: Vec<u8>) {
+			let owner = ensure_signed(origin)?;
+			ensure!(cid.len() <= T::MaxCidLength::get() as usize, Error::<T>::CidTooLong);
+			ensure!(!Documents::<T>::contains_key(content_hash), Error::<T>::AlreadyAnchored);
+			ensure!(
+				Anchored::<T>::decode_len().unwrap_or(0) < T::MaxAnchorsPerBlock::get() as usize,
+				Error::<T>::TooManyAnchors,
+			);
+
+			let deposit = T::AnchorDeposit::get();
+			T::Currency::reserve(&owner, deposit)?;
+
+			let anchored_at = <system::Module<T>>::block_number();
+			let document = Document { owner: owner.clone(), deposit, cid, anchored_at };
+			Documents::<T>::insert(content_hash, document);
+			Anchored::<T>::append_or_put(&[content_hash][..]);
+
+			Self::deposit_event(RawEvent::DocumentAnchored(content_hash, owner));
+		}
+
+		/// Attest an anchored document.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the attester.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn attest(origin, content_hash: T::Hash) {
+			let attester = ensure_signed(origin)?;
+			ensure!(Documents::<T>::contains_key(content_hash), Error::<T>::NotAnchored);
+			ensure!(
+				!Attestations::<T>::contains_key(content_hash, &attester),
+				Error::<T>::AlreadyAttested,
+			);
+			let count = Self::attestation_count(content_hash);
+			ensure!(count < T::MaxAttestations::get(), Error::<T>::TooManyAttestations);
+
+			Attestations::<T>::insert(content_hash, &attester, <system::Module<T>>::block_number());
+			AttestationCount::<T>::insert(content_hash, count + 1);
+
+			Self::deposit_event(RawEvent::DocumentAttested(content_hash, attester));
+		}
+
+		/// Remove an anchored document and its attestations, unreserving the deposit of the
+		/// owner.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the owner of the document.
+		///
+		/// # <weight>
+		/// - O(A) where A is `MaxAttestations`, removing at most A attestations.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|_: (&T::Hash,)| 100_000 + 10_000 * T::MaxAttestations::get() as Weight,
+			DispatchClass::Normal,
+			true
+		)]
+		fn remove(origin, content_hash: T::Hash) {
+			let who = ensure_signed(origin)?;
+			let document = Self::document(content_hash).ok_or(Error::<T>::NotAnchored)?;
+			ensure!(document.owner == who, Error::<T>::NotOwner);
+
+			Documents::<T>::remove(content_hash);
+			AttestationCount::<T>::remove(content_hash);
+			Attestations::<T>::remove_prefix(content_hash);
+			let _ = T::Currency::unreserve(&who, document.deposit);
+
+			Self::deposit_event(RawEvent::DocumentRemoved(content_hash));
+		}
+
+		fn offchain_worker(_now: T::BlockNumber) {
+			let deadline = sp_io::offchain::timestamp()
+				.add(Duration::from_millis(OFFCHAIN_WORKER_DEADLINE_MS));
+			let anchored = Anchored::<T>::get();
+			let count = anchored.len();
+			for (checked, content_hash) in anchored.into_iter().enumerate() {
+				if sp_io::offchain::timestamp() >= deadline {
+					debug::native::warn!(
+						"No time left to check {} of the {} documents anchored in the block",
+						count - checked,
+						count,
+					);
+					break;
+				}
+				let status = Self::check(content_hash, deadline);
+				debug::native::info!("Checked the anchored document {:?}: {:?}", content_hash, status);
+
+				let mut key = DOCUMENT_STATUS_PREFIX.to_vec();
+				key.extend_from_slice(content_hash.as_ref());
+				StorageValueRef::persistent(&key).set(&status);
+			}
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Fetch the content of an anchored document from the IPFS gateway of the node, checking it
+	/// against the anchored hash.
+	///
+	/// Can only be called from an offchain worker.
+	pub fn fetch(content_hash: T::Hash) -> Result<Vec<u8>, FetchError> {
+		let document = Self::document(content_hash).ok_or(FetchError::NotAnchored)?;
+		Self::fetch_document(content_hash, &document, Self::ipfs_deadline())
+	}
+
+	fn fetch_document(
+		content_hash: T::Hash,
+		document: &Document<T::AccountId, BalanceOf<T>, T::BlockNumber>,
+		deadline: Timestamp,
+	) -> Result<Vec<u8>, FetchError> {
+		let cid = sp_std::str::from_utf8(&document.cid).map_err(|_| FetchError::Unavailable)?;
+		let content = sp_io::offchain::ipfs_get(cid, Some(deadline))
+			.map_err(|_| FetchError::Unavailable)?;
+
+		if T::Hashing::hash(&content) == content_hash {
+			Ok(content)
+		} else {
+			Err(FetchError::Mismatch)
+		}
+	}
+
+	/// Fetch, check and pin an anchored document, before the given deadline or the IPFS
+	/// deadline, whichever comes first.
+	fn check(content_hash: T::Hash, deadline: Timestamp) -> DocumentStatus {
+		let document = match Self::document(content_hash) {
+			Some(document) => document,
+			None => return DocumentStatus::Unavailable,
+		};
+
+		let deadline = Self::ipfs_deadline().min(deadline);
+		match Self::fetch_document(content_hash, &document, deadline) {
+			// the CID is valid UTF-8 once fetched
+			Ok(_) => match sp_std::str::from_utf8(&document.cid)
+				.map(|cid| sp_io::offchain::ipfs_pin(cid, Some(deadline)))
+			{
+				Ok(Ok(())) => DocumentStatus::Pinned,
+				_ => DocumentStatus::NotPinned,
+			},
+			Err(FetchError::Mismatch) => DocumentStatus::Mismatch,
+			Err(_) => DocumentStatus::Unavailable,
+		}
+	}
+
+	fn ipfs_deadline() -> Timestamp {
+		sp_io::offchain::timestamp().add(Duration::from_millis(IPFS_DEADLINE_MS))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types};
+	use sp_core::{H256, offchain::{OffchainExt, OffchainStorage, testing::TestOffchainExt}};
+	use sp_runtime::{
+		Perbill, testing::Header,
+		traits::{BlakeTwo256, IdentityLookup, OffchainWorker, OnInitialize},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = ();
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const AnchorDeposit: u64 = 10;
+		pub const MaxCidLength: u32 = 64;
+		pub const MaxAttestations: u32 = 2;
+		pub const MaxAnchorsPerBlock: u32 = 3;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type Currency = Balances;
+		type AnchorDeposit = AnchorDeposit;
+		type MaxCidLength = MaxCidLength;
+		type MaxAttestations = MaxAttestations;
+		type MaxAnchorsPerBlock = MaxAnchorsPerBlock;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Documents = Module<Test>;
+
+	const OWNER: u64 = 1;
+	const AUDITOR: u64 = 2;
+	const PARTY: u64 = 3;
+	const BROKE: u64 = 4;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(OWNER, 100), (AUDITOR, 100), (PARTY, 100), (BROKE, 5)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn documents_are_anchored_attested_and_removed() {
+		new_test_ext().execute_with(|| {
+			let hash = BlakeTwo256::hash(b"contract");
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), hash, b"QmContract".to_vec()));
+			assert_eq!(Balances::reserved_balance(OWNER), 10);
+			assert_noop!(
+				Documents::anchor(Origin::signed(AUDITOR), hash, b"QmOther".to_vec()),
+				Error::<Test>::AlreadyAnchored,
+			);
+			assert_noop!(
+				Documents::anchor(Origin::signed(BROKE), H256::zero(), b"QmBroke".to_vec()),
+				pallet_balances::Error::<Test, _>::InsufficientBalance,
+			);
+			assert_noop!(
+				Documents::anchor(Origin::signed(OWNER), H256::zero(), vec![b'Q'; 65]),
+				Error::<Test>::CidTooLong,
+			);
+
+			assert_ok!(Documents::attest(Origin::signed(AUDITOR), hash));
+			assert_noop!(Documents::attest(Origin::signed(AUDITOR), hash), Error::<Test>::AlreadyAttested);
+			assert_noop!(Documents::attest(Origin::signed(AUDITOR), H256::zero()), Error::<Test>::NotAnchored);
+			assert_eq!(Documents::attestation(hash, AUDITOR), Some(0));
+
+			assert_noop!(Documents::remove(Origin::signed(AUDITOR), hash), Error::<Test>::NotOwner);
+			assert_ok!(Documents::remove(Origin::signed(OWNER), hash));
+			assert_eq!(Documents::document(hash), None);
+			assert_eq!(Documents::attestation(hash, AUDITOR), None);
+			assert_eq!(Documents::attestation_count(hash), 0);
+			assert_eq!(Balances::reserved_balance(OWNER), 0);
+		});
+	}
+
+	#[test]
+	fn attestations_and_anchors_are_bounded() {
+		new_test_ext().execute_with(|| {
+			let hash = BlakeTwo256::hash(b"contract");
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), hash, b"QmContract".to_vec()));
+			assert_ok!(Documents::attest(Origin::signed(AUDITOR), hash));
+			assert_ok!(Documents::attest(Origin::signed(PARTY), hash));
+			assert_noop!(Documents::attest(Origin::signed(OWNER), hash), Error::<Test>::TooManyAttestations);
+			assert_eq!(Documents::attestation_count(hash), 2);
+
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), H256::repeat_byte(1), b"Qm1".to_vec()));
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), H256::repeat_byte(2), b"Qm2".to_vec()));
+			assert_noop!(
+				Documents::anchor(Origin::signed(OWNER), H256::repeat_byte(3), b"Qm3".to_vec()),
+				Error::<Test>::TooManyAnchors,
+			);
+
+			Documents::on_initialize(1);
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), H256::repeat_byte(3), b"Qm3".to_vec()));
+		});
+	}
+
+	#[test]
+	fn anchored_documents_are_checked_and_pinned_offchain() {
+		let (offchain, state) = TestOffchainExt::new();
+		state.write().ipfs.insert("QmContract".into(), b"contract".to_vec());
+		state.write().ipfs.insert("QmForged".into(), b"forged".to_vec());
+		let mut t = new_test_ext();
+		t.register_extension(OffchainExt::new(offchain));
+
+		let contract = BlakeTwo256::hash(b"contract");
+		let forged = BlakeTwo256::hash(b"original");
+		let missing = BlakeTwo256::hash(b"missing");
+		t.execute_with(|| {
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), contract, b"QmContract".to_vec()));
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), forged, b"QmForged".to_vec()));
+			assert_ok!(Documents::anchor(Origin::signed(OWNER), missing, b"QmMissing".to_vec()));
+
+			Documents::offchain_worker(0);
+			assert_eq!(Documents::fetch(contract), Ok(b"contract".to_vec()));
+			assert_eq!(Documents::fetch(H256::zero()), Err(FetchError::NotAnchored));
+		});
+
+		let status = |hash: H256| {
+			let mut key = DOCUMENT_STATUS_PREFIX.to_vec();
+			key.extend_from_slice(hash.as_ref());
+			state.read().persistent_storage.get(b"", &key)
+				.map(|status| DocumentStatus::decode(&mut &status[..]).unwrap())
+		};
+		assert_eq!(status(contract), Some(DocumentStatus::Pinned));
+		assert_eq!(status(forged), Some(DocumentStatus::Mismatch));
+		assert_eq!(status(missing), Some(DocumentStatus::Unavailable));
+		assert_eq!(state.read().ipfs_pinned, vec!["QmContract".to_string()]);
+	}
+}
@@ -0,0 +1,227 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Doughnut Revocation Module
+//!
+//! A module for issuers revoking the doughnuts they issued, before they expire.
+//!
+//! ## Overview
+//!
+//! The issuer of a doughnut revokes it with a signed extrinsic carrying the doughnut. Revoked
+//! doughnuts are recorded by the blake2-256 hash of their encoding, and the module is the
+//! `RevocationRegistry` of runtimes rejecting them in their doughnut validation:
+//!
+//! ```ignore
+//! impl prml_doughnut::DoughnutRuntime for Runtime {
+//!     type RevocationRegistry = DoughnutRevocation;
+//!     ...
+//! }
+//! ```
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `revoke` - Revoke a doughnut issued by the sender.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::Decode;
+use sp_runtime::{Doughnut, traits::{Convert, DoughnutApi}};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure,
+	weights::SimpleDispatchInfo,
+	Hashable,
+};
+use frame_system::{self as system, ensure_signed};
+use prml_doughnut::RevocationRegistry;
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The account of the public key issuing a doughnut.
+	type IssuerAccount: Convert<[u8; 32], Self::AccountId>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as DoughnutRevocation {
+		/// The issuers of the revoked doughnuts, by the blake2-256 hash of their encoding.
+		pub Revoked get(fn revoked): map hasher(identity) [u8; 32] => Option<T::AccountId>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+		/// An issuer revoked the doughnut with the hash.
+		DoughnutRevoked(AccountId, [u8; 32]),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The doughnut can't be decoded.
+		InvalidDoughnut,
+		/// The sender isn't the issuer of the doughnut.
+		NotIssuer,
+		/// The doughnut is revoked already.
+		AlreadyRevoked,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Revoke `doughnut`, the encoding of a doughnut issued by the sender. It can't be used
+		/// anymore.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the issuer of the doughnut.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn revoke(origin, doughnut: Vec<u8>) {
+			let who = ensure_signed(origin)?;
+			let doughnut = Doughnut::decode(&mut &doughnut[..])
+				.map_err(|_| Error::<T>::InvalidDoughnut)?;
+			let issuer = match &doughnut {
+				Doughnut::V0(v0) => v0.issuer(),
+			};
+			ensure!(T::IssuerAccount::convert(issuer) == who, Error::<T>::NotIssuer);
+
+			let hash = doughnut.blake2_256();
+			ensure!(!<Revoked<T>>::contains_key(&hash), Error::<T>::AlreadyRevoked);
+			<Revoked<T>>::insert(&hash, &who);
+
+			Self::deposit_event(RawEvent::DoughnutRevoked(who, hash));
+		}
+	}
+}
+
+impl<T: Trait> RevocationRegistry for Module<T> {
+	fn is_revoked(doughnut_hash: &[u8; 32]) -> bool {
+		<Revoked<T>>::contains_key(doughnut_hash)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use codec::Encode;
+	use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight};
+	use sp_core::H256;
+	use sp_keyring::AccountKeyring;
+	use sp_runtime::{
+		AccountId32, DoughnutV0, Perbill, testing::Header,
+		traits::{BlakeTwo256, ConvertInto, IdentityLookup},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = AccountId32;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	impl Trait for Test {
+		type Event = ();
+		type IssuerAccount = ConvertInto;
+	}
+	type DoughnutRevocation = Module<Test>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::default().build_storage::<Test>().unwrap().into()
+	}
+
+	fn doughnut(issuer: AccountKeyring) -> Doughnut {
+		Doughnut::V0(DoughnutV0 {
+			issuer: issuer.to_raw_public(),
+			holder: AccountKeyring::Charlie.to_raw_public(),
+			expiry: 3000,
+			not_before: 0,
+			payload_version: 0,
+			signature: [1u8; 64].into(),
+			signature_version: 0,
+			domains: vec![("test".to_string(), vec![0u8])],
+		})
+	}
+
+	#[test]
+	fn issuer_revokes_doughnut() {
+		new_test_ext().execute_with(|| {
+			let doughnut = doughnut(AccountKeyring::Alice);
+			let hash = doughnut.blake2_256();
+			assert!(!DoughnutRevocation::is_revoked(&hash));
+
+			assert_ok!(DoughnutRevocation::revoke(
+				Origin::signed(AccountKeyring::Alice.into()),
+				doughnut.encode(),
+			));
+			assert!(DoughnutRevocation::is_revoked(&hash));
+			assert_eq!(DoughnutRevocation::revoked(hash), Some(AccountKeyring::Alice.into()));
+
+			assert_noop!(
+				DoughnutRevocation::revoke(Origin::signed(AccountKeyring::Alice.into()), doughnut.encode()),
+				Error::<Test>::AlreadyRevoked,
+			);
+		});
+	}
+
+	#[test]
+	fn only_issuer_revokes_doughnut() {
+		new_test_ext().execute_with(|| {
+			let doughnut = doughnut(AccountKeyring::Alice);
+
+			assert_noop!(
+				DoughnutRevocation::revoke(Origin::signed(AccountKeyring::Bob.into()), doughnut.encode()),
+				Error::<Test>::NotIssuer,
+			);
+			assert_noop!(
+				DoughnutRevocation::revoke(Origin::signed(AccountKeyring::Alice.into()), vec![1, 2, 3]),
+				Error::<Test>::InvalidDoughnut,
+			);
+			assert!(!DoughnutRevocation::is_revoked(&doughnut.blake2_256()));
+		});
+	}
+}
@@ -26,4 +26,5 @@ pub mod error_code {
 	pub const VALIDATION_EXPIRED: u8 = 181;
 	pub const VALIDATION_PREMATURE: u8 = 182;
 	pub const VALIDATION_CONVERSION: u8 = 183;
+	pub const VALIDATION_REVOKED: u8 = 184;
 }
\ No newline at end of file
@@ -14,23 +14,32 @@
 // You should have received a copy of the GNU General Public License
 // along with Plug. If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{DoughnutRuntime, PlugDoughnut, constants::error_code};
-use sp_std::{self, convert::TryInto, prelude::*};
+use crate::{DoughnutRuntime, PlugDoughnut, PlugDoughnutValidator, RevocationRegistry, constants::error_code};
+use sp_std::{self, convert::{TryFrom, TryInto}, prelude::*};
+use sp_core::{ed25519, sr25519};
+use sp_core::storage::well_known_keys::DOUGHNUT_VERIFIED_PREFIX;
 use sp_runtime::{
 	Doughnut,
-	traits::{PlugDoughnutApi, DoughnutApi, DoughnutVerify, SignedExtension, ValidationError, VerifyError},
+	traits::{
+		Convert, PlugDoughnutApi, DoughnutApi, DoughnutVerify, SignedExtension, SignedExtensionMetadata,
+		ValidationError, Verify, VerifyError,
+	},
 	transaction_validity::{InvalidTransaction, TransactionValidityError, ValidTransaction},
 };
 use frame_support::{
+	additional_traits::DoughnutValidator,
 	dispatch::DispatchInfo,
-	traits::Time,
+	storage::unhashed,
+	traits::{Get, Time},
+	weights::{WeighDoughnut, Weight},
+	Hashable,
 };
 
 // Proxy calls to the inner Doughnut type and provide Runtime type conversions where required.
 impl<Runtime> PlugDoughnutApi for PlugDoughnut<Runtime>
 where
 	Runtime: DoughnutRuntime,
-	Runtime::AccountId: AsRef<[u8]> + From<[u8; 32]>,
+	Runtime::AccountId: AsRef<[u8]>,
 {
 	type PublicKey = Runtime::AccountId;
 	type Signature = [u8; 64];
@@ -38,12 +47,12 @@ where
 
 	fn holder(&self) -> Self::PublicKey {
 		match &self.0 {
-			Doughnut::V0(v0) => v0.holder().into()
+			Doughnut::V0(v0) => Runtime::PublicKeyAccount::convert(v0.holder())
 		}
 	}
 	fn issuer(&self) -> Self::PublicKey {
 		match &self.0 {
-			Doughnut::V0(v0) => v0.issuer().into()
+			Doughnut::V0(v0) => Runtime::PublicKeyAccount::convert(v0.issuer())
 		}
 	}
 	fn not_before(&self) -> Self::Timestamp {
@@ -77,8 +86,12 @@ where
 		}
 	}
 	fn validate<Q: AsRef<[u8]>, R: TryInto<u32>>(&self, who: Q, now: R) -> Result<(), ValidationError> {
+		// `who` is an account, compared with the account of the holder rather than its public key
+		if who.as_ref() != self.holder().as_ref() {
+			return Err(ValidationError::HolderIdentityMismatched)
+		}
 		match &self.0 {
-			Doughnut::V0(v0) => v0.validate(who, now)
+			Doughnut::V0(v0) => v0.validate(v0.holder(), now)
 		}
 	}
 }
@@ -91,21 +104,33 @@ impl<Runtime: DoughnutRuntime> DoughnutVerify for  PlugDoughnut<Runtime> {
 	}
 }
 
-impl<Runtime> SignedExtension for PlugDoughnut<Runtime>
+impl<Runtime> PlugDoughnut<Runtime>
 where
-	Runtime: DoughnutRuntime + Eq + Clone + Send + Sync,
-	Runtime::AccountId: AsRef<[u8]> + From<[u8; 32]>,
+	Runtime: DoughnutRuntime,
+	Runtime::AccountId: AsRef<[u8]>,
 {
-	type AccountId = Runtime::AccountId;
-	type AdditionalSigned = ();
-	type Call = Runtime::Call;
-	type DispatchInfo = DispatchInfo;
-	type Pre = ();
-	const IDENTIFIER: &'static str = "PlugDoughnutSignedExtension";
-	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
-	fn validate(&self, who: &Self::AccountId, _call: &Self::Call, _info: Self::DispatchInfo, _len: usize) -> Result<ValidTransaction, TransactionValidityError>
-	{
-		// Check doughnut signature verifies
+	/// Storage key of this doughnut's entry in the per-block verification cache
+	fn verified_key(&self) -> Vec<u8> {
+		let mut key = DOUGHNUT_VERIFIED_PREFIX.to_vec();
+		key.extend_from_slice(&self.0.blake2_256());
+		key
+	}
+
+	/// Whether this doughnut is in the revocation registry of the runtime
+	pub fn is_revoked(&self) -> bool {
+		Runtime::RevocationRegistry::is_revoked(&self.0.blake2_256())
+	}
+
+	/// Check the doughnut has not been revoked, mapping failures to a transaction validity error
+	fn ensure_not_revoked(&self) -> Result<(), TransactionValidityError> {
+		if self.is_revoked() {
+			return Err(InvalidTransaction::Custom(error_code::VALIDATION_REVOKED).into())
+		}
+		Ok(())
+	}
+
+	/// Check the doughnut signature verifies, mapping failures to a transaction validity error
+	fn verify_signature(&self) -> Result<(), TransactionValidityError> {
 		if let Err(err) = self.verify() {
 			let code = match err {
 				VerifyError::Invalid => error_code::VERIFY_INVALID,
@@ -115,9 +140,35 @@ where
 			};
 			return Err(InvalidTransaction::Custom(code).into())
 		}
+		Ok(())
+	}
+
+	/// Check the doughnut signature verifies, pushing it to the current batch of signatures if one
+	/// is started (see `sp_runtime::SignatureBatching`), the batch then fails if it doesn't verify
+	fn batch_verify_signature(&self) -> Result<(), TransactionValidityError> {
+		// The signature is verified with the public key of the issuer, not its account
+		let issuer = match &self.0 {
+			Doughnut::V0(v0) => v0.issuer(),
+		};
+		let payload = self.payload();
+		let verified = match self.signature_version() {
+			0 => sr25519::Public::try_from(issuer.as_ref())
+				.map(|issuer| sr25519::Signature::from_raw(self.signature()).batch_verify(&payload[..], &issuer)),
+			1 => ed25519::Public::try_from(issuer.as_ref())
+				.map(|issuer| ed25519::Signature::from_raw(self.signature()).batch_verify(&payload[..], &issuer)),
+			_ => return Err(InvalidTransaction::Custom(error_code::VERIFY_UNSUPPORTED_VERSION).into()),
+		};
+		match verified {
+			Ok(true) => Ok(()),
+			Ok(false) => Err(InvalidTransaction::Custom(error_code::VERIFY_INVALID).into()),
+			Err(()) => Err(InvalidTransaction::Custom(error_code::VERIFY_BAD_PUBLIC_KEY_FORMAT).into()),
+		}
+	}
+
+	/// Check the doughnut is valid for use by `who` at the current timestamp
+	fn validate_usage(&self, who: &Runtime::AccountId) -> Result<(), TransactionValidityError> {
 		// Convert chain reported timestamp from milliseconds into seconds as per doughnut timestamp spec.
 		let now = Runtime::TimestampProvider::now() / 1000_u32.into();
-		// Check doughnut is valid for use by `who` at the current timestamp
 		if let Err(err) = PlugDoughnutApi::validate(self, who, now) {
 			let code = match err {
 				ValidationError::HolderIdentityMismatched => error_code::VALIDATION_HOLDER_SIGNER_IDENTITY_MISMATCH,
@@ -127,20 +178,109 @@ where
 			};
 			return Err(InvalidTransaction::Custom(code).into())
 		}
+		Ok(())
+	}
+}
+
+impl<Runtime> WeighDoughnut for PlugDoughnut<Runtime>
+where
+	Runtime: DoughnutRuntime,
+	Runtime::AccountId: AsRef<[u8]>,
+{
+	fn weigh_doughnut(&self) -> Weight {
+		let payload_weight = Runtime::VerificationWeightPerByte::get()
+			.saturating_mul(self.payload().len() as Weight);
+		Runtime::VerificationWeight::get().saturating_add(payload_weight)
+	}
+}
+
+impl<Runtime> SignedExtension for PlugDoughnut<Runtime>
+where
+	Runtime: DoughnutRuntime + Eq + Clone + Send + Sync,
+	Runtime::AccountId: AsRef<[u8]>,
+{
+	type AccountId = Runtime::AccountId;
+	type AdditionalSigned = ();
+	type Call = Runtime::Call;
+	type DispatchInfo = DispatchInfo;
+	type Pre = ();
+	const IDENTIFIER: &'static str = "PlugDoughnutSignedExtension";
+	fn additional_signed(&self) -> sp_std::result::Result<(), TransactionValidityError> { Ok(()) }
+	fn metadata() -> Vec<SignedExtensionMetadata> {
+		// The doughnut is encoded in its versioned binary format rather than by type.
+		sp_std::vec![SignedExtensionMetadata {
+			identifier: Self::IDENTIFIER,
+			ty: "Doughnut",
+			additional_signed: "()",
+			optional: false,
+		}]
+	}
+	fn validate(&self, who: &Self::AccountId, _call: &Self::Call, _info: Self::DispatchInfo, _len: usize) -> Result<ValidTransaction, TransactionValidityError>
+	{
+		self.verify_signature()?;
+		self.validate_usage(who)?;
+		self.ensure_not_revoked()?;
 		Ok(ValidTransaction::default())
 	}
+	fn pre_dispatch(&self, who: &Self::AccountId, _call: &Self::Call, _info: Self::DispatchInfo, _len: usize) -> Result<(), TransactionValidityError>
+	{
+		// Doughnuts shared by several extrinsics in a block only have their signature verified once,
+		// the cache is cleared by `frame_system` when the block is finalized.
+		let key = self.verified_key();
+		if !unhashed::exists(&key) {
+			self.batch_verify_signature()?;
+			unhashed::put(&key, &true);
+		}
+		self.validate_usage(who)?;
+		self.ensure_not_revoked()
+	}
+}
+
+impl<Runtime> DoughnutValidator for PlugDoughnutValidator<Runtime>
+where
+	Runtime: DoughnutRuntime,
+	Runtime::AccountId: AsRef<[u8]>,
+{
+	type Doughnut = PlugDoughnut<Runtime>;
+	type AccountId = Runtime::AccountId;
+
+	fn validate_doughnut(
+		doughnut: &Self::Doughnut,
+		holder: &Self::AccountId,
+	) -> Result<Self::AccountId, &'static str> {
+		doughnut.verify_signature().map_err(|_| "doughnut signature is invalid")?;
+		doughnut.validate_usage(holder).map_err(|_| "doughnut is not usable by the holder now")?;
+		doughnut.ensure_not_revoked().map_err(|_| "doughnut is revoked")?;
+		Ok(doughnut.issuer())
+	}
+
+	fn weigh_doughnut(doughnut: &Self::Doughnut) -> Weight {
+		WeighDoughnut::weigh_doughnut(doughnut)
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use frame_support::parameter_types;
 	use sp_core::crypto::Pair;
 	use sp_keyring::{AccountKeyring, Ed25519Keyring};
-	use sp_runtime::{DoughnutV0, Doughnut, MultiSignature, traits::{IdentifyAccount, Verify, DoughnutSigning}};
+	use sp_runtime::{DoughnutV0, Doughnut, MultiSignature, traits::{ConvertInto, IdentifyAccount, Verify, DoughnutSigning}};
 
 	type Signature = MultiSignature;
 	type AccountId = <<Signature as Verify>::Signer as IdentifyAccount>::AccountId;
 
+	thread_local! {
+		static REVOKED: std::cell::RefCell<Vec<[u8; 32]>> = Default::default();
+	}
+
+	pub struct TestRevocationRegistry;
+	impl RevocationRegistry for TestRevocationRegistry {
+		fn is_revoked(doughnut_hash: &[u8; 32]) -> bool {
+			REVOKED.with(|revoked| revoked.borrow().contains(doughnut_hash))
+		}
+	}
+
 	#[derive(Clone, Eq, PartialEq)]
 	pub struct Runtime;
 
@@ -153,11 +293,20 @@ mod tests {
 		}
 	}
 
+	parameter_types! {
+		pub const VerificationWeight: Weight = 1_000;
+		pub const VerificationWeightPerByte: Weight = 10;
+	}
+
 	impl DoughnutRuntime for Runtime {
 		type AccountId = AccountId;
+		type PublicKeyAccount = ConvertInto;
 		type Call = ();
 		type Doughnut = PlugDoughnut<Self>;
 		type TimestampProvider = FixedTimestampProvider;
+		type VerificationWeight = VerificationWeight;
+		type VerificationWeightPerByte = VerificationWeightPerByte;
+		type RevocationRegistry = TestRevocationRegistry;
 	}
 
 	// Helper function to create a DoughnutV0
@@ -181,15 +330,44 @@ mod tests {
 		doughnut.sign_sr25519(&issuer.pair().to_ed25519_bytes()).expect("it signs ok");
 
 		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
-		assert!(
-			<PlugDoughnut<_> as SignedExtension>::validate(
-				&plug_doughnut,
-				&holder.to_account_id(), // who
-				&(), // Call
-				Default::default(), // DispatchInfo
-				0usize // len
-			).is_ok()
-		);
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert!(
+				<PlugDoughnut<_> as SignedExtension>::validate(
+					&plug_doughnut,
+					&holder.to_account_id(), // who
+					&(), // Call
+					Default::default(), // DispatchInfo
+					0usize // len
+				).is_ok()
+			);
+		});
+	}
+
+	#[test]
+	fn plug_doughnut_does_not_validate_revoked() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		doughnut.sign_sr25519(&issuer.pair().to_ed25519_bytes()).expect("it signs ok");
+
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+		sp_io::TestExternalities::default().execute_with(|| {
+			REVOKED.with(|revoked| revoked.borrow_mut().push(plug_doughnut.0.blake2_256()));
+			assert!(plug_doughnut.is_revoked());
+			assert_eq!(
+				<PlugDoughnut<_> as SignedExtension>::validate(
+					&plug_doughnut,
+					&holder.to_account_id(), // who
+					&(), // Call
+					Default::default(), // DispatchInfo
+					0usize // len
+				),
+				Err(InvalidTransaction::Custom(error_code::VALIDATION_REVOKED).into())
+			);
+			assert_eq!(
+				PlugDoughnutValidator::<Runtime>::validate_doughnut(&plug_doughnut, &holder.to_account_id()),
+				Err("doughnut is revoked"),
+			);
+		});
 	}
 
 	#[test]
@@ -254,6 +432,42 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn plug_doughnut_validator_returns_issuer() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		doughnut.sign_sr25519(&issuer.pair().to_ed25519_bytes()).expect("it signs ok");
+
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				PlugDoughnutValidator::<Runtime>::validate_doughnut(&plug_doughnut, &holder.to_account_id()),
+				Ok(issuer.to_account_id()),
+			);
+			// Charlie is not the holder
+			assert!(
+				PlugDoughnutValidator::<Runtime>::validate_doughnut(
+					&plug_doughnut,
+					&AccountKeyring::Charlie.to_account_id(),
+				).is_err()
+			);
+		});
+	}
+
+	#[test]
+	fn plug_doughnut_does_not_validate_bad_signature() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		// holder signs the doughnut!
+		doughnut.sign_sr25519(&holder.to_ed25519_bytes()).expect("it signs ok");
+
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+		assert_eq!(
+			PlugDoughnutValidator::<Runtime>::validate_doughnut(&plug_doughnut, &holder.to_account_id()),
+			Err("doughnut signature is invalid"),
+		);
+	}
+
 	#[test]
 	fn plug_doughnut_verifies_sr25519_signature() {
 		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
@@ -302,6 +516,42 @@ mod tests {
 		assert_eq!(plug_doughnut.verify(), Err(VerifyError::Invalid));
 	}
 
+	#[test]
+	fn plug_doughnut_signature_is_batch_verified_on_pre_dispatch() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		doughnut.sign_sr25519(&issuer.pair().to_ed25519_bytes()).expect("it signs ok");
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut.clone()));
+
+		// holder signs the doughnut!
+		doughnut.sign_sr25519(&holder.to_ed25519_bytes()).expect("it signs ok");
+		let bad_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+
+		let pre_dispatch = |doughnut: &PlugDoughnut<Runtime>| <PlugDoughnut<_> as SignedExtension>::pre_dispatch(
+			doughnut,
+			&holder.to_account_id(), // who
+			&(), // Call
+			Default::default(), // DispatchInfo
+			0usize // len
+		);
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			let batching = sp_runtime::SignatureBatching::start();
+			assert!(pre_dispatch(&plug_doughnut).is_ok());
+			assert!(batching.verify());
+
+			let batching = sp_runtime::SignatureBatching::start();
+			assert!(pre_dispatch(&bad_doughnut).is_ok());
+			assert!(!batching.verify());
+		});
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert_eq!(
+				pre_dispatch(&bad_doughnut),
+				Err(InvalidTransaction::Custom(error_code::VERIFY_INVALID).into())
+			);
+		});
+	}
+
 	#[test]
 	fn plug_doughnut_does_not_verify_unknown_signature_version() {
 		let (issuer, holder) = (Ed25519Keyring::Alice, Ed25519Keyring::Bob);
@@ -372,4 +622,82 @@ mod tests {
 			Err(InvalidTransaction::Custom(error_code::VERIFY_INVALID).into())
 		);
 	}
+
+	#[test]
+	fn plug_doughnut_pre_dispatch_verifies_signature_once_per_block() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		doughnut.sign_sr25519(&issuer.pair().to_ed25519_bytes()).expect("it signs ok");
+		let plug_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut.clone()));
+
+		// Modify the doughnut to invalidate the signature
+		doughnut.expiry = 55_555;
+		let forged_doughnut = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+
+		sp_io::TestExternalities::default().execute_with(|| {
+			assert!(
+				<PlugDoughnut<_> as SignedExtension>::pre_dispatch(
+					&plug_doughnut,
+					&holder.to_account_id(), // who
+					&(), // Call
+					Default::default(), // DispatchInfo
+					0usize // len
+				).is_ok()
+			);
+			assert!(unhashed::exists(&plug_doughnut.verified_key()));
+
+			// A cached verification skips the signature check during dispatch
+			unhashed::put(&forged_doughnut.verified_key(), &true);
+			assert!(
+				<PlugDoughnut<_> as SignedExtension>::pre_dispatch(
+					&forged_doughnut,
+					&holder.to_account_id(), // who
+					&(), // Call
+					Default::default(), // DispatchInfo
+					0usize // len
+				).is_ok()
+			);
+			// but never during pool validation
+			assert_eq!(
+				<PlugDoughnut<_> as SignedExtension>::validate(
+					&forged_doughnut,
+					&holder.to_account_id(), // who
+					&(), // Call
+					Default::default(), // DispatchInfo
+					0usize // len
+				),
+				Err(InvalidTransaction::Custom(error_code::VERIFY_INVALID).into())
+			);
+		});
+	}
+
+	#[test]
+	fn plug_doughnut_verification_is_weighed_by_payload_length() {
+		let (issuer, holder) = (AccountKeyring::Alice, AccountKeyring::Bob);
+		let mut doughnut = make_doughnut(issuer.to_raw_public(), holder.to_raw_public());
+		let small = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut.clone()));
+		doughnut.domains.push(("large".to_string(), vec![0u8; 100]));
+		let large = PlugDoughnut::<Runtime>::new(Doughnut::V0(doughnut));
+
+		assert_eq!(small.weigh_doughnut(), 1_000 + 10 * small.payload().len() as Weight);
+		assert!(large.weigh_doughnut() >= small.weigh_doughnut() + 10 * 100);
+		// the doughnuts of the calls of a batch weigh the same.
+		assert_eq!(
+			<PlugDoughnutValidator<Runtime> as DoughnutValidator>::weigh_doughnut(&large),
+			large.weigh_doughnut(),
+		);
+	}
+
+	#[test]
+	fn optional_plug_doughnut_is_described_in_metadata() {
+		assert_eq!(
+			<Option<PlugDoughnut<Runtime>> as SignedExtension>::metadata(),
+			vec![SignedExtensionMetadata {
+				identifier: "PlugDoughnutSignedExtension",
+				ty: "Doughnut",
+				additional_signed: "()",
+				optional: true,
+			}],
+		);
+	}
 }
@@ -22,12 +22,13 @@
 use codec::{Encode, Decode};
 use sp_std::{self, prelude::Vec, any::Any};
 use sp_runtime::{
-	Doughnut,
-	traits::{PlugDoughnutApi, Member},
+	AccountId20, Doughnut,
+	traits::{Convert, PlugDoughnutApi, Member},
 };
 use frame_support::{
 	additional_traits::DelegatedDispatchVerifier,
-	traits::Time,
+	traits::{Get, Time},
+	weights::Weight,
 	Parameter,
 };
 
@@ -39,9 +40,45 @@ mod impls;
 /// A runtime which supports doughnut verification and validation
 pub trait DoughnutRuntime {
 	type AccountId: Member + Parameter;
+	/// The account of the public key of a doughnut issuer or holder, `ConvertInto` for
+	/// `AccountId32` accounts and `TruncatedAccount` for `AccountId20` accounts
+	type PublicKeyAccount: Convert<[u8; 32], Self::AccountId>;
 	type Call;
 	type Doughnut: Member + Parameter + PlugDoughnutApi;
 	type TimestampProvider: Time;
+	/// The weight of verifying a doughnut regardless of its size, i.e. of verifying its
+	/// signature, see `benchmark doughnut`
+	type VerificationWeight: Get<Weight>;
+	/// The additional weight of verifying a doughnut per byte of its payload
+	type VerificationWeightPerByte: Get<Weight>;
+	/// The registry of revoked doughnuts, `()` if doughnuts can't be revoked
+	type RevocationRegistry: RevocationRegistry;
+}
+
+/// The `AccountId20` of the public key of a doughnut issuer or holder: its last 20 bytes. Ethereum
+/// accounts are named by their address left padded with zeros, as in the Ethereum ABI, and the
+/// sr25519 and ed25519 keys of issuers are truncated as the keccak-256 hashes of the secp256k1
+/// keys of Ethereum accounts are
+pub struct TruncatedAccount;
+
+impl Convert<[u8; 32], AccountId20> for TruncatedAccount {
+	fn convert(public: [u8; 32]) -> AccountId20 {
+		let mut account = [0u8; 20];
+		account.copy_from_slice(&public[12..]);
+		account.into()
+	}
+}
+
+/// A registry of doughnuts revoked by their issuers, which can't be used anymore
+pub trait RevocationRegistry {
+	/// Whether the doughnut with the given blake2-256 hash of its encoding is revoked
+	fn is_revoked(doughnut_hash: &[u8; 32]) -> bool;
+}
+
+impl RevocationRegistry for () {
+	fn is_revoked(_doughnut_hash: &[u8; 32]) -> bool {
+		false
+	}
 }
 
 /// A doughnut wrapped for compatibility with the extrinsic transport layer and the plug runtime types.
@@ -85,3 +122,6 @@ impl<Runtime: DoughnutRuntime> DelegatedDispatchVerifier for PlugDoughnutDispatc
 		Err("Doughnut dispatch verification is not implemented for this domain")
 	}
 }
+
+/// It validates doughnuts which are carried alongside a call, rather than attached to an extrinsic
+pub struct PlugDoughnutValidator<Runtime: DoughnutRuntime>(sp_std::marker::PhantomData<Runtime>);
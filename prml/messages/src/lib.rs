@@ -0,0 +1,651 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Messages Module
+//!
+//! A module exchanging messages with a remote plug chain, running this module too, over the
+//! headers relayed by the bridge module.
+//!
+//! ## Overview
+//!
+//! Messages are sent on lanes, each with its own sequence of nonces. The sender of a message
+//! pays a delivery fee, reserved until the delivery is confirmed, and declares the weight of the
+//! dispatch of its payload on the remote chain.
+//!
+//! Relayers deliver the messages to the remote chain with a proof of the outbound lane storage of
+//! this chain, where they're dispatched in order by the `MessageDispatch` of the remote chain with
+//! their sender and declared weight. The delivery weighs the declared weights of the messages,
+//! paid by the relayer.
+//! The remote chain records which relayer delivered which messages, and relayers bring a proof of
+//! this record back, confirming the delivery: the fees of the delivered messages are paid to the
+//! relayers who delivered them, and the messages are pruned.
+//!
+//! Both chains must share the account and balance types, the storage of one being read by the
+//! other.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `send_message` - Send a message on a lane, declaring the weight of its dispatch and paying a
+//!   delivery fee.
+//! * `receive_messages_proof` - Deliver the next messages of a lane of the remote chain.
+//! * `receive_messages_delivery_proof` - Confirm the delivery of the messages of a lane to the
+//!   remote chain, rewarding their relayers.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_runtime::{DispatchError, DispatchResult, RuntimeDebug};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure,
+	traits::{Currency, Get, ReservableCurrency},
+	weights::{DispatchClass, FunctionOf, SimpleDispatchInfo, Weight},
+};
+use frame_system::{self as system, ensure_signed};
+use prml_bridge::ProveInclusion;
+
+/// The id of a lane.
+pub type LaneId = [u8; 4];
+
+/// The nonce of a message on its lane, starting at 1.
+pub type MessageNonce = u64;
+
+type BalanceOf<T> = <<T as Trait>::Currency as Currency<<T as system::Trait>::AccountId>>::Balance;
+
+/// The proof of the storage of the remote chain.
+pub type ProofOf<T> = <<T as Trait>::Bridge as ProveInclusion>::Proof;
+
+/// A message waiting for the confirmation of its delivery.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct Message<AccountId, Balance> {
+	/// The sender.
+	pub sender: AccountId,
+	/// The delivery fee, reserved from the sender.
+	pub fee: Balance,
+	/// The weight of the dispatch of the payload declared by the sender, the maximum weight the
+	/// remote chain dispatches it with.
+	pub dispatch_weight: Weight,
+	/// The payload, dispatched by the remote chain.
+	pub payload: Vec<u8>,
+}
+
+/// The state of an outbound lane.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct OutboundLaneData {
+	/// The nonce of the last sent message.
+	pub latest_generated_nonce: MessageNonce,
+	/// The nonce of the last message whose delivery is confirmed.
+	pub latest_received_nonce: MessageNonce,
+}
+
+/// The messages of a lane delivered by a relayer, whose delivery isn't confirmed to the remote
+/// chain yet.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct UnrewardedRelayer<AccountId> {
+	/// The relayer.
+	pub relayer: AccountId,
+	/// The nonce of the first delivered message.
+	pub begin: MessageNonce,
+	/// The nonce of the last delivered message.
+	pub end: MessageNonce,
+}
+
+/// The state of an inbound lane.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct InboundLaneData<AccountId> {
+	/// The nonce of the last received message.
+	pub latest_received_nonce: MessageNonce,
+	/// The relayers of the messages whose delivery isn't confirmed yet, in order.
+	pub relayers: Vec<UnrewardedRelayer<AccountId>>,
+}
+
+impl<AccountId> Default for InboundLaneData<AccountId> {
+	fn default() -> Self {
+		InboundLaneData { latest_received_nonce: 0, relayers: Vec::new() }
+	}
+}
+
+/// Dispatches the payloads of the messages received from the remote chain.
+pub trait MessageDispatch<AccountId> {
+	/// Dispatch the payload of the message `nonce` of `lane`, sent by `sender` on the remote chain.
+	///
+	/// The relayer paid for the `weight` declared by the sender: payloads whose dispatch weighs
+	/// more must be refused.
+	fn dispatch(
+		lane: LaneId,
+		nonce: MessageNonce,
+		sender: &AccountId,
+		weight: Weight,
+		payload: &[u8],
+	) -> DispatchResult;
+}
+
+impl<AccountId> MessageDispatch<AccountId> for () {
+	fn dispatch(_: LaneId, _: MessageNonce, _: &AccountId, _: Weight, _: &[u8]) -> DispatchResult { Ok(()) }
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The currency of the delivery fees.
+	type Currency: ReservableCurrency<Self::AccountId>;
+
+	/// Proves the storage of the remote chain, e.g. the bridge module.
+	type Bridge: ProveInclusion;
+
+	/// Dispatches the payloads of the received messages.
+	type MessageDispatch: MessageDispatch<Self::AccountId>;
+
+	/// The maximum size of the payload of a message.
+	type MaxMessageSize: Get<u32>;
+
+	/// The maximum weight of the dispatch of a message.
+	type MaxMessageDispatchWeight: Get<Weight>;
+
+	/// The maximum number of messages of a lane whose delivery isn't confirmed.
+	type MaxUnconfirmedMessages: Get<MessageNonce>;
+
+	/// The maximum number of relayers of a lane whose deliveries aren't confirmed.
+	type MaxUnrewardedRelayers: Get<u32>;
+
+	/// The maximum number of messages delivered at once.
+	type MaxMessagesPerProof: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as Messages {
+		/// The states of the outbound lanes.
+		pub OutboundLanes get(fn outbound_lane): map hasher(blake2_128_concat) LaneId => OutboundLaneData;
+
+		/// The sent messages whose delivery isn't confirmed, by lane and nonce.
+		pub OutboundMessages get(fn outbound_message):
+			double_map hasher(blake2_128_concat) LaneId, hasher(blake2_128_concat) MessageNonce
+			=> Option<Message<T::AccountId, BalanceOf<T>>>;
+
+		/// The states of the inbound lanes.
+		pub InboundLanes get(fn inbound_lane):
+			map hasher(blake2_128_concat) LaneId => InboundLaneData<T::AccountId>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+		/// A message was sent on a lane, with a nonce.
+		MessageAccepted(LaneId, MessageNonce),
+		/// A message of a lane of the remote chain was dispatched, successfully or not.
+		MessageDispatched(LaneId, MessageNonce, bool),
+		/// The delivery of the messages of a lane, in a range of nonces, was confirmed, and the
+		/// relayers rewarded.
+		MessagesDelivered(LaneId, MessageNonce, MessageNonce),
+		/// A relayer was rewarded with the delivery fees of messages.
+		RelayerRewarded(AccountId),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The payload of the message is too large.
+		MessageTooLarge,
+		/// The declared dispatch weight of the message is too high.
+		MessageTooHeavy,
+		/// Too many messages of the lane aren't confirmed.
+		TooManyUnconfirmedMessages,
+		/// Too many relayers of the lane aren't rewarded.
+		TooManyUnrewardedRelayers,
+		/// Too many messages delivered at once.
+		TooManyMessages,
+		/// A delivered message isn't in the proof.
+		MessageMissing,
+		/// The dispatch weight paid by the relayer is below the declared weights of the messages.
+		InsufficientDispatchWeight,
+		/// The state of the lane isn't in the proof.
+		LaneMissing,
+		/// The proven storage can't be decoded.
+		InvalidStorage,
+		/// The proof confirms messages that weren't sent.
+		InvalidDelivery,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The maximum number of messages of a lane whose delivery isn't confirmed.
+		const MaxUnconfirmedMessages: MessageNonce = T::MaxUnconfirmedMessages::get();
+
+		/// The maximum number of relayers of a lane whose deliveries aren't confirmed.
+		const MaxUnrewardedRelayers: u32 = T::MaxUnrewardedRelayers::get();
+
+		/// The maximum number of messages delivered at once.
+		const MaxMessagesPerProof: u32 = T::MaxMessagesPerProof::get();
+
+		/// The maximum size of the payload of a message.
+		const MaxMessageSize: u32 = T::MaxMessageSize::get();
+
+		/// The maximum weight of the dispatch of a message.
+		const MaxMessageDispatchWeight: Weight = T::MaxMessageDispatchWeight::get();
+
+		fn deposit_event() = default;
+
+		/// Send a message on a lane, reserving the delivery fee until the delivery is confirmed.
+		///
+		/// The remote chain dispatches the payload with at most the declared `dispatch_weight`.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		#[weight = SimpleDispatchInfo::FixedNormal(100_000)]
+		fn send_message(origin, lane: LaneId, payload: Vec<u8>, dispatch_weight: Weight, fee: BalanceOf<T>) {
+			let sender = ensure_signed(origin)?;
+			ensure!(payload.len() <= T::MaxMessageSize::get() as usize, Error::<T>::MessageTooLarge);
+			ensure!(dispatch_weight <= T::MaxMessageDispatchWeight::get(), Error::<T>::MessageTooHeavy);
+			let mut data = Self::outbound_lane(lane);
+			ensure!(
+				data.latest_generated_nonce - data.latest_received_nonce < T::MaxUnconfirmedMessages::get(),
+				Error::<T>::TooManyUnconfirmedMessages,
+			);
+
+			T::Currency::reserve(&sender, fee)?;
+			data.latest_generated_nonce += 1;
+			let nonce = data.latest_generated_nonce;
+			<OutboundMessages<T>>::insert(lane, nonce, Message { sender, fee, dispatch_weight, payload });
+			OutboundLanes::insert(lane, data);
+
+			Self::deposit_event(RawEvent::MessageAccepted(lane, nonce));
+		}
+
+		/// Deliver the next `count` messages of a lane of the remote chain, with a proof of the
+		/// outbound messages and lane of the remote chain, dispatching them in order.
+		///
+		/// The relayer pays for `dispatch_weight`, which must cover the dispatch weights declared
+		/// by the senders of the messages.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the relayer rewarded once the
+		/// delivery is confirmed.
+		#[weight = FunctionOf(
+			|args: (&LaneId, &u32, &Weight, &ProofOf<T>)| {
+				500_000u32
+					.saturating_add(args.1.saturating_mul(100_000))
+					.saturating_add(*args.2)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn receive_messages_proof(origin, lane: LaneId, count: u32, dispatch_weight: Weight, proof: ProofOf<T>) {
+			let relayer = ensure_signed(origin)?;
+			ensure!(count <= T::MaxMessagesPerProof::get(), Error::<T>::TooManyMessages);
+			let mut data = Self::inbound_lane(lane);
+
+			// forget the relayers whose deliveries are confirmed to the remote chain
+			let outbound = Self::read_remote::<OutboundLaneData>(
+				&OutboundLanes::hashed_key_for(lane),
+				&proof,
+			)?.ok_or(Error::<T>::LaneMissing)?;
+			data.relayers.retain(|entry| entry.end > outbound.latest_received_nonce);
+			if count == 0 {
+				InboundLanes::<T>::insert(lane, data);
+				return Ok(());
+			}
+			ensure!(
+				(data.relayers.len() as u32) < T::MaxUnrewardedRelayers::get(),
+				Error::<T>::TooManyUnrewardedRelayers,
+			);
+
+			let begin = data.latest_received_nonce + 1;
+			let end = data.latest_received_nonce + count as MessageNonce;
+			let mut messages = Vec::with_capacity(count as usize);
+			for nonce in begin..=end {
+				let message = Self::read_remote::<Message<T::AccountId, BalanceOf<T>>>(
+					&<OutboundMessages<T>>::hashed_key_for(lane, nonce),
+					&proof,
+				)?.ok_or(Error::<T>::MessageMissing)?;
+				messages.push(message);
+			}
+			let declared_weight = messages.iter()
+				.fold(0 as Weight, |weight, message| weight.saturating_add(message.dispatch_weight));
+			ensure!(declared_weight <= dispatch_weight, Error::<T>::InsufficientDispatchWeight);
+
+			data.latest_received_nonce = end;
+			data.relayers.push(UnrewardedRelayer { relayer, begin, end });
+			InboundLanes::<T>::insert(lane, data);
+
+			for (nonce, message) in (begin..=end).zip(messages) {
+				let result = T::MessageDispatch::dispatch(
+					lane,
+					nonce,
+					&message.sender,
+					message.dispatch_weight,
+					&message.payload,
+				);
+				Self::deposit_event(RawEvent::MessageDispatched(lane, nonce, result.is_ok()));
+			}
+		}
+
+		/// Confirm the delivery of the messages of a lane to the remote chain, with a proof of the
+		/// inbound lane of the remote chain, paying their delivery fees to their relayers.
+		///
+		/// The dispatch origin for this call must be _Signed_.
+		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		fn receive_messages_delivery_proof(origin, lane: LaneId, proof: ProofOf<T>) {
+			ensure_signed(origin)?;
+			let inbound = Self::read_remote::<InboundLaneData<T::AccountId>>(
+				&InboundLanes::<T>::hashed_key_for(lane),
+				&proof,
+			)?.ok_or(Error::<T>::LaneMissing)?;
+
+			let mut data = Self::outbound_lane(lane);
+			ensure!(
+				inbound.latest_received_nonce <= data.latest_generated_nonce,
+				Error::<T>::InvalidDelivery,
+			);
+			if inbound.latest_received_nonce <= data.latest_received_nonce {
+				return Ok(());
+			}
+
+			let begin = data.latest_received_nonce + 1;
+			for entry in &inbound.relayers {
+				let mut rewarded = false;
+				for nonce in entry.begin.max(begin)..=entry.end.min(inbound.latest_received_nonce) {
+					if let Some(message) = <OutboundMessages<T>>::take(lane, nonce) {
+						// the fee is returned to the sender if the relayer has no account on this chain
+						if T::Currency::repatriate_reserved(&message.sender, &entry.relayer, message.fee).is_err() {
+							T::Currency::unreserve(&message.sender, message.fee);
+						}
+						rewarded = true;
+					}
+				}
+				if rewarded {
+					Self::deposit_event(RawEvent::RelayerRewarded(entry.relayer.clone()));
+				}
+			}
+			data.latest_received_nonce = inbound.latest_received_nonce;
+			OutboundLanes::insert(lane, data);
+
+			Self::deposit_event(RawEvent::MessagesDelivered(lane, begin, inbound.latest_received_nonce));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Read a value of the storage of the remote chain from the proof.
+	fn read_remote<V: Decode>(key: &[u8], proof: &ProofOf<T>) -> Result<Option<V>, DispatchError> {
+		match T::Bridge::prove_inclusion(key, proof.clone())? {
+			Some(value) => V::decode(&mut &value[..]).map(Some).map_err(|_| Error::<T>::InvalidStorage.into()),
+			None => Ok(None),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::cell::RefCell;
+	use frame_support::{
+		assert_ok, assert_noop, impl_outer_origin, impl_outer_event, parameter_types, weights::GetDispatchInfo,
+	};
+	use sp_core::H256;
+	use sp_runtime::{Perbill, testing::Header, traits::{BlakeTwo256, IdentityLookup}};
+	use crate as messages;
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	impl_outer_event! {
+		pub enum TestEvent for Test {
+			system,
+			pallet_balances<T>,
+			messages<T>,
+		}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = TestEvent;
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const ExistentialDeposit: u64 = 1;
+		pub const CreationFee: u64 = 0;
+	}
+	impl pallet_balances::Trait for Test {
+		type Balance = u64;
+		type OnReapAccount = System;
+		type OnNewAccount = ();
+		type Event = TestEvent;
+		type TransferPayment = ();
+		type DustRemoval = ();
+		type ExistentialDeposit = ExistentialDeposit;
+		type CreationFee = CreationFee;
+	}
+	parameter_types! {
+		pub const MaxUnconfirmedMessages: MessageNonce = 2;
+		pub const MaxUnrewardedRelayers: u32 = 2;
+		pub const MaxMessagesPerProof: u32 = 8;
+		pub const MaxMessageSize: u32 = 16;
+		pub const MaxMessageDispatchWeight: Weight = 1_000;
+	}
+	impl Trait for Test {
+		type Event = TestEvent;
+		type Currency = Balances;
+		type Bridge = MockBridge;
+		type MessageDispatch = RecordDispatch;
+		type MaxMessageSize = MaxMessageSize;
+		type MaxMessageDispatchWeight = MaxMessageDispatchWeight;
+		type MaxUnconfirmedMessages = MaxUnconfirmedMessages;
+		type MaxUnrewardedRelayers = MaxUnrewardedRelayers;
+		type MaxMessagesPerProof = MaxMessagesPerProof;
+	}
+	type System = frame_system::Module<Test>;
+	type Balances = pallet_balances::Module<Test>;
+	type Messages = Module<Test>;
+
+	/// Proves the storage of the remote chain with the proven keys and values.
+	pub struct MockBridge;
+	impl ProveInclusion for MockBridge {
+		type Proof = Vec<(Vec<u8>, Vec<u8>)>;
+
+		fn prove_inclusion(remote_key: &[u8], proof: Self::Proof) -> Result<Option<Vec<u8>>, DispatchError> {
+			Ok(proof.into_iter().find(|(key, _)| key[..] == *remote_key).map(|(_, value)| value))
+		}
+	}
+
+	thread_local! {
+		static DISPATCHED: RefCell<Vec<(MessageNonce, u64, Weight, Vec<u8>)>> = RefCell::new(vec![]);
+	}
+
+	/// Records the dispatched messages.
+	pub struct RecordDispatch;
+	impl MessageDispatch<u64> for RecordDispatch {
+		fn dispatch(
+			_lane: LaneId,
+			nonce: MessageNonce,
+			sender: &u64,
+			weight: Weight,
+			payload: &[u8],
+		) -> DispatchResult {
+			DISPATCHED.with(|d| d.borrow_mut().push((nonce, *sender, weight, payload.to_vec())));
+			Ok(())
+		}
+	}
+
+	const LANE: LaneId = *b"lane";
+	const SENDER: u64 = 1;
+	const RELAYER: u64 = 2;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		pallet_balances::GenesisConfig::<Test> {
+			balances: vec![(SENDER, 100), (RELAYER, 100)],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	/// A proof of the values of the given keys of the storage of `chain`.
+	fn prove(chain: &mut sp_io::TestExternalities, keys: Vec<Vec<u8>>) -> Vec<(Vec<u8>, Vec<u8>)> {
+		chain.execute_with(|| keys.into_iter()
+			.filter_map(|key| sp_io::storage::get(&key).map(|value| (key, value)))
+			.collect())
+	}
+
+	fn messages_proof(chain: &mut sp_io::TestExternalities, nonces: &[MessageNonce]) -> Vec<(Vec<u8>, Vec<u8>)> {
+		let mut keys = vec![OutboundLanes::hashed_key_for(LANE)];
+		keys.extend(nonces.iter().map(|nonce| <OutboundMessages<Test>>::hashed_key_for(LANE, nonce)));
+		prove(chain, keys)
+	}
+
+	fn delivery_proof(chain: &mut sp_io::TestExternalities) -> Vec<(Vec<u8>, Vec<u8>)> {
+		prove(chain, vec![InboundLanes::<Test>::hashed_key_for(LANE)])
+	}
+
+	#[test]
+	fn messages_are_delivered_and_relayers_rewarded() {
+		let mut source = new_test_ext();
+		let mut target = new_test_ext();
+
+		source.execute_with(|| {
+			assert_ok!(Messages::send_message(Origin::signed(SENDER), LANE, b"hello".to_vec(), 100, 10));
+			assert_ok!(Messages::send_message(Origin::signed(SENDER), LANE, b"world".to_vec(), 200, 20));
+			assert_noop!(
+				Messages::send_message(Origin::signed(SENDER), LANE, vec![], 0, 10),
+				Error::<Test>::TooManyUnconfirmedMessages,
+			);
+			assert_eq!(Balances::reserved_balance(SENDER), 30);
+		});
+
+		let proof = messages_proof(&mut source, &[1, 2, 3]);
+		target.execute_with(|| {
+			assert_noop!(
+				Messages::receive_messages_proof(Origin::signed(RELAYER), LANE, 3, 300, proof.clone()),
+				Error::<Test>::MessageMissing,
+			);
+			// the relayer pays for the declared dispatch weights
+			assert_noop!(
+				Messages::receive_messages_proof(Origin::signed(RELAYER), LANE, 2, 299, proof.clone()),
+				Error::<Test>::InsufficientDispatchWeight,
+			);
+			assert_ok!(Messages::receive_messages_proof(Origin::signed(RELAYER), LANE, 2, 300, proof));
+			DISPATCHED.with(|d| assert_eq!(*d.borrow(), vec![
+				(1, SENDER, 100, b"hello".to_vec()),
+				(2, SENDER, 200, b"world".to_vec()),
+			]));
+			assert_eq!(Messages::inbound_lane(LANE), InboundLaneData {
+				latest_received_nonce: 2,
+				relayers: vec![UnrewardedRelayer { relayer: RELAYER, begin: 1, end: 2 }],
+			});
+			assert!(System::events().iter().any(|record| {
+				record.event == TestEvent::messages(RawEvent::MessageDispatched(LANE, 2, true))
+			}));
+		});
+
+		let proof = delivery_proof(&mut target);
+		source.execute_with(|| {
+			assert_ok!(Messages::receive_messages_delivery_proof(Origin::signed(RELAYER), LANE, proof));
+			assert_eq!(Balances::reserved_balance(SENDER), 0);
+			assert_eq!(Balances::free_balance(SENDER), 70);
+			assert_eq!(Balances::free_balance(RELAYER), 130);
+			assert_eq!(Messages::outbound_message(LANE, 1), None);
+			assert_eq!(Messages::outbound_lane(LANE), OutboundLaneData {
+				latest_generated_nonce: 2,
+				latest_received_nonce: 2,
+			});
+		});
+
+		// the confirmed delivery is forgotten by the target chain with the next messages
+		let proof = messages_proof(&mut source, &[]);
+		target.execute_with(|| {
+			assert_ok!(Messages::receive_messages_proof(Origin::signed(RELAYER), LANE, 0, 0, proof));
+			assert_eq!(Messages::inbound_lane(LANE).relayers, vec![]);
+		});
+	}
+
+	#[test]
+	fn deliveries_of_unsent_messages_are_rejected() {
+		let mut source = new_test_ext();
+		let mut target = new_test_ext();
+
+		target.execute_with(|| {
+			InboundLanes::<Test>::insert(LANE, InboundLaneData {
+				latest_received_nonce: 1,
+				relayers: vec![UnrewardedRelayer { relayer: RELAYER, begin: 1, end: 1 }],
+			});
+		});
+
+		let proof = delivery_proof(&mut target);
+		source.execute_with(|| {
+			assert_noop!(
+				Messages::receive_messages_delivery_proof(Origin::signed(RELAYER), LANE, proof),
+				Error::<Test>::InvalidDelivery,
+			);
+			assert_noop!(
+				Messages::receive_messages_delivery_proof(Origin::signed(RELAYER), LANE, vec![]),
+				Error::<Test>::LaneMissing,
+			);
+		});
+	}
+
+	#[test]
+	fn messages_are_bounded() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				Messages::send_message(Origin::signed(SENDER), LANE, vec![0; 17], 0, 10),
+				Error::<Test>::MessageTooLarge,
+			);
+			assert_noop!(
+				Messages::send_message(Origin::signed(SENDER), LANE, vec![0; 16], 1_001, 10),
+				Error::<Test>::MessageTooHeavy,
+			);
+			assert_ok!(Messages::send_message(Origin::signed(SENDER), LANE, vec![0; 16], 1_000, 10));
+		});
+	}
+
+	#[test]
+	fn deliveries_weigh_the_declared_dispatch_weights() {
+		let weight = |count, dispatch_weight| {
+			Call::<Test>::receive_messages_proof(LANE, count, dispatch_weight, vec![]).get_dispatch_info().weight
+		};
+		assert_eq!(weight(0, 0), 500_000);
+		assert_eq!(weight(2, 300), 700_300);
+		assert_eq!(weight(2, Weight::max_value()), Weight::max_value());
+	}
+}
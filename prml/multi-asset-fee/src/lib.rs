@@ -0,0 +1,181 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Multi-asset Fee module
+//!
+//! A reference adapter allowing transaction fees to be paid in assets other than the network's
+//! default fee currency, so application chains can charge fees in their application token.
+//!
+//! Each account may nominate the asset its fees are paid in. Fees are still calculated by
+//! `pallet-transaction-payment` in units of the default fee currency; this module converts them
+//! into the nominated asset via `Trait::FeeConversion` before withdrawing them.
+//!
+//! ## Dispatchable methods
+//!
+//! ```ignore
+//! pub fn set_fee_asset(origin, asset: Option<T::AssetId>) { ... }
+//! ```
+//!
+//! ## Usage
+//!
+//! Use the module as the `OnChargeTransaction` handler of `pallet-transaction-payment`:
+//!
+//! ```ignore
+//! impl pallet_transaction_payment::Trait for Runtime {
+//!     type OnChargeTransaction = MultiAssetFee;
+//!     ...
+//! }
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use frame_support::{
+	additional_traits::{AssetIdAuthority, FeeConversion, MultiCurrencyAccounting},
+	decl_error, decl_event, decl_module, decl_storage, ensure,
+	traits::{ExistenceRequirement, Imbalance, OnUnbalanced, WithdrawReason},
+	weights::SimpleDispatchInfo,
+	Parameter,
+};
+use frame_system::ensure_signed;
+use pallet_transaction_payment::OnChargeTransaction;
+use sp_runtime::{
+	traits::{Member, One, Zero},
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+};
+
+type BalanceOf<T> = <<T as Trait>::MultiCurrency as MultiCurrencyAccounting>::Balance;
+type NegativeImbalanceOf<T> =
+	<<T as Trait>::MultiCurrency as MultiCurrencyAccounting>::NegativeImbalance;
+
+/// The module's config trait.
+pub trait Trait: frame_system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	/// The ID type of an asset fees can be paid in.
+	type AssetId: Parameter + Member + Copy;
+	/// The multi-currency system fees are withdrawn from.
+	type MultiCurrency: MultiCurrencyAccounting<
+		AccountId = Self::AccountId,
+		CurrencyId = Self::AssetId,
+	>;
+	/// Converts fees from the default fee currency into other assets.
+	type FeeConversion: FeeConversion<Self::AssetId, BalanceOf<Self>>;
+	/// Handler for the withdrawn fees. This is either one or two separate imbalances, the first
+	/// is the transaction fee paid, the second is the tip paid, if any.
+	type OnFeePayment: OnUnbalanced<NegativeImbalanceOf<Self>>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as MultiAssetFee {
+		/// The asset an account pays its fees in, if not the default fee currency.
+		pub FeeAsset get(fn fee_asset):
+			map hasher(blake2_128_concat) T::AccountId => Option<T::AssetId>;
+	}
+}
+
+decl_event!(
+	pub enum Event<T> where
+		<T as frame_system::Trait>::AccountId,
+		<T as Trait>::AssetId,
+	{
+		/// An account changed the asset it pays fees in (account, asset). `None` is the default
+		/// fee currency.
+		FeeAssetSet(AccountId, Option<AssetId>),
+	}
+);
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The asset can not be used to pay fees.
+		UnsupportedFeeAsset,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin, system = frame_system {
+		type Error = Error<T>;
+
+		fn deposit_event() = default;
+
+		/// Set the asset the sender pays its transaction fees in. `None` restores the default fee
+		/// currency.
+		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
+		pub fn set_fee_asset(origin, asset: Option<T::AssetId>) {
+			let who = ensure_signed(origin)?;
+
+			let asset = asset.filter(|asset| !Self::is_default_fee_asset(asset));
+			match asset {
+				Some(asset) => {
+					ensure!(
+						T::FeeConversion::to_asset_balance(&asset, One::one()).is_some(),
+						Error::<T>::UnsupportedFeeAsset,
+					);
+					<FeeAsset<T>>::insert(&who, asset);
+				},
+				None => <FeeAsset<T>>::remove(&who),
+			}
+
+			Self::deposit_event(RawEvent::FeeAssetSet(who, asset));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// Whether `asset` is the default fee currency of the multi-currency system.
+	fn is_default_fee_asset(asset: &T::AssetId) -> bool {
+		*asset == <T::MultiCurrency as MultiCurrencyAccounting>::DefaultCurrencyId::asset_id()
+	}
+}
+
+impl<T: Trait + pallet_transaction_payment::Trait> OnChargeTransaction<T> for Module<T> {
+	type Balance = BalanceOf<T>;
+
+	fn withdraw_fee(
+		who: &T::AccountId,
+		fee: Self::Balance,
+		tip: Self::Balance,
+	) -> Result<(), TransactionValidityError> {
+		let (asset, fee, tip) = match <FeeAsset<T>>::get(who) {
+			Some(asset) => {
+				let convert = |amount| T::FeeConversion::to_asset_balance(&asset, amount)
+					.ok_or(InvalidTransaction::Payment);
+				(Some(asset), convert(fee)?, convert(tip)?)
+			},
+			None => (None, fee, tip),
+		};
+
+		// `fee` already includes `tip`.
+		let imbalance = T::MultiCurrency::withdraw(
+			who,
+			asset,
+			fee,
+			if tip.is_zero() {
+				WithdrawReason::TransactionPayment.into()
+			} else {
+				WithdrawReason::TransactionPayment | WithdrawReason::Tip
+			},
+			ExistenceRequirement::KeepAlive,
+		).map_err(|_| InvalidTransaction::Payment)?;
+		let imbalances = imbalance.split(tip);
+		T::OnFeePayment::on_unbalanceds(Some(imbalances.0).into_iter().chain(Some(imbalances.1)));
+		Ok(())
+	}
+}
@@ -0,0 +1,158 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg(test)]
+
+use super::*;
+use frame_support::{impl_outer_event, impl_outer_origin, parameter_types, weights::Weight};
+use pallet_generic_asset::AssetInfo;
+use sp_core::H256;
+use sp_runtime::{
+	testing::Header,
+	traits::{BlakeTwo256, ConvertInto, IdentityLookup},
+	Perbill,
+};
+
+pub const SPENDING_ASSET_ID: u32 = 16001;
+pub const STAKING_ASSET_ID: u32 = 16000;
+/// An asset fees can be paid in, at twice the price of the spending asset.
+pub const FEE_ASSET_ID: u32 = 100;
+/// An asset fees can not be paid in.
+pub const OTHER_ASSET_ID: u32 = 200;
+pub const INITIAL_BALANCE: u64 = 1_000;
+
+impl_outer_origin! {
+	pub enum Origin for Test where system = frame_system {}
+}
+
+mod multi_asset_fee {
+	pub use crate::Event;
+}
+
+impl_outer_event! {
+	pub enum TestEvent for Test {
+		frame_system,
+		pallet_generic_asset<T>,
+		multi_asset_fee<T>,
+	}
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Test;
+
+parameter_types! {
+	pub const BlockHashCount: u64 = 250;
+	pub const MaximumBlockWeight: Weight = 1024;
+	pub const MaximumBlockLength: u32 = 2 * 1024;
+	pub const AvailableBlockRatio: Perbill = Perbill::one();
+	pub const TransactionBaseFee: u64 = 10;
+	pub const TransactionByteFee: u64 = 0;
+}
+
+impl frame_system::Trait for Test {
+	type Origin = Origin;
+	type Index = u64;
+	type BlockNumber = u64;
+	type Call = ();
+	type Hash = H256;
+	type Hashing = BlakeTwo256;
+	type AccountId = u64;
+	type Lookup = IdentityLookup<u64>;
+	type Header = Header;
+	type Event = TestEvent;
+	type MaximumBlockWeight = MaximumBlockWeight;
+	type MaximumBlockLength = MaximumBlockLength;
+	type AvailableBlockRatio = AvailableBlockRatio;
+	type BlockHashCount = BlockHashCount;
+	type Version = ();
+	type ModuleToIndex = ();
+	type Doughnut = ();
+	type DelegatedDispatchVerifier = ();
+}
+
+impl pallet_generic_asset::Trait for Test {
+	type Balance = u64;
+	type AssetId = u32;
+	type Event = TestEvent;
+	type TransferRestriction = ();
+}
+
+impl pallet_transaction_payment::Trait for Test {
+	type OnChargeTransaction = MultiAssetFee;
+	type TransactionBaseFee = TransactionBaseFee;
+	type TransactionByteFee = TransactionByteFee;
+	type WeightToFee = ConvertInto;
+	type FeeMultiplierUpdate = ();
+	type OperationalFeeMultiplierUpdate = ();
+}
+
+/// Prices `FEE_ASSET_ID` at twice the spending asset, no other asset is accepted.
+pub struct DoublePriceConversion;
+impl FeeConversion<u32, u64> for DoublePriceConversion {
+	fn to_asset_balance(asset: &u32, amount: u64) -> Option<u64> {
+		if *asset == FEE_ASSET_ID {
+			Some(amount * 2)
+		} else {
+			None
+		}
+	}
+}
+
+impl Trait for Test {
+	type Event = TestEvent;
+	type AssetId = u32;
+	type MultiCurrency = GenericAsset;
+	type FeeConversion = DoublePriceConversion;
+	type OnFeePayment = ();
+}
+
+pub type System = frame_system::Module<Test>;
+pub type GenericAsset = pallet_generic_asset::Module<Test>;
+pub type MultiAssetFee = Module<Test>;
+
+pub struct ExtBuilder {
+	endowed_accounts: Vec<u64>,
+}
+
+impl Default for ExtBuilder {
+	fn default() -> Self {
+		Self {
+			endowed_accounts: vec![1, 2],
+		}
+	}
+}
+
+impl ExtBuilder {
+	// builds genesis config
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+
+		pallet_generic_asset::GenesisConfig::<Test> {
+			assets: vec![SPENDING_ASSET_ID, FEE_ASSET_ID, OTHER_ASSET_ID],
+			endowed_accounts: self.endowed_accounts,
+			initial_balance: INITIAL_BALANCE,
+			next_asset_id: 1_000,
+			staking_asset_id: STAKING_ASSET_ID,
+			spending_asset_id: SPENDING_ASSET_ID,
+			permissions: vec![],
+			asset_meta: vec![(FEE_ASSET_ID, AssetInfo::new(b"FEE".to_vec(), 0))],
+		}
+		.assimilate_storage(&mut t)
+		.unwrap();
+
+		t.into()
+	}
+}
@@ -0,0 +1,104 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+#![cfg(test)]
+
+use super::*;
+use crate::mock::*;
+use frame_support::{assert_noop, assert_ok, weights::{DispatchClass, DispatchInfo, Weight}};
+use pallet_transaction_payment::ChargeTransactionPayment;
+use sp_runtime::traits::SignedExtension;
+
+fn info(weight: Weight) -> DispatchInfo {
+	DispatchInfo { weight, class: DispatchClass::Normal, pays_fee: true }
+}
+
+#[test]
+fn set_fee_asset_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(FEE_ASSET_ID)));
+		assert_eq!(MultiAssetFee::fee_asset(1), Some(FEE_ASSET_ID));
+
+		let expected_event =
+			TestEvent::multi_asset_fee(RawEvent::FeeAssetSet(1, Some(FEE_ASSET_ID)));
+		assert!(System::events().iter().any(|record| record.event == expected_event));
+	});
+}
+
+#[test]
+fn set_fee_asset_rejects_unsupported_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			MultiAssetFee::set_fee_asset(Origin::signed(1), Some(OTHER_ASSET_ID)),
+			Error::<Test>::UnsupportedFeeAsset,
+		);
+	});
+}
+
+#[test]
+fn set_fee_asset_to_default_currency_clears_it() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(FEE_ASSET_ID)));
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(SPENDING_ASSET_ID)));
+		assert_eq!(MultiAssetFee::fee_asset(1), None);
+
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(FEE_ASSET_ID)));
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), None));
+		assert_eq!(MultiAssetFee::fee_asset(1), None);
+	});
+}
+
+#[test]
+fn withdraw_fee_uses_default_currency() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(<MultiAssetFee as OnChargeTransaction<Test>>::withdraw_fee(&1, 100, 10));
+		assert_eq!(GenericAsset::free_balance(&SPENDING_ASSET_ID, &1), INITIAL_BALANCE - 100);
+		assert_eq!(GenericAsset::free_balance(&FEE_ASSET_ID, &1), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn withdraw_fee_converts_into_fee_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(FEE_ASSET_ID)));
+		assert_ok!(<MultiAssetFee as OnChargeTransaction<Test>>::withdraw_fee(&1, 100, 10));
+		assert_eq!(GenericAsset::free_balance(&SPENDING_ASSET_ID, &1), INITIAL_BALANCE);
+		assert_eq!(GenericAsset::free_balance(&FEE_ASSET_ID, &1), INITIAL_BALANCE - 200);
+	});
+}
+
+#[test]
+fn withdraw_fee_fails_with_insufficient_fee_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(1), Some(FEE_ASSET_ID)));
+		assert_eq!(
+			<MultiAssetFee as OnChargeTransaction<Test>>::withdraw_fee(&1, INITIAL_BALANCE, 0),
+			Err(InvalidTransaction::Payment.into()),
+		);
+		assert_eq!(GenericAsset::free_balance(&FEE_ASSET_ID, &1), INITIAL_BALANCE);
+	});
+}
+
+#[test]
+fn charge_transaction_payment_withdraws_fee_asset() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(MultiAssetFee::set_fee_asset(Origin::signed(2), Some(FEE_ASSET_ID)));
+		// base fee 10 + weight fee 5 + tip 5 = 20, at twice the price
+		assert_ok!(ChargeTransactionPayment::<Test>::from(5).pre_dispatch(&2, &(), info(5), 10));
+		assert_eq!(GenericAsset::free_balance(&FEE_ASSET_ID, &2), INITIAL_BALANCE - 40);
+		assert_eq!(GenericAsset::free_balance(&SPENDING_ASSET_ID, &2), INITIAL_BALANCE);
+	});
+}
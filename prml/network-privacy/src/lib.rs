@@ -0,0 +1,621 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+//! # Network Privacy Module
+//!
+//! A module storing the membership of a private network, served to the nodes by the
+//! `NetworkPrivacyApi` and enforced by `sc-network-privacy`.
+//!
+//! ## Overview
+//!
+//! The reserved nodes of the network and the accounts operating them are managed by the
+//! `AdminOrigin`, e.g. governance. The operator of a reserved node authorizes light clients,
+//! which are served by the members without being members. Operators can let other accounts
+//! authorize light clients by issuing them a doughnut for `authorize_light_client`, the call then
+//! being dispatched with the operator as origin. The light clients of an operator are no longer
+//! authorized once the operator doesn't operate a reserved node anymore.
+//!
+//! The `AdminOrigin` also rotates the key the members encrypt the transactions they gossip with.
+//!
+//! On networks using the proof-of-authority engine (`sc-consensus-poa`), the `AdminOrigin` also
+//! admits the block authors, each with the key it signs its blocks with. The authors are set at
+//! genesis and the last one can't be removed, so that the network always has an author. An author
+//! rotates its own key with `set_author_key`.
+//!
+//! ## Interface
+//!
+//! ### Dispatchable Functions
+//!
+//! * `add_reserved_node` - Reserve a node, operated by an account.
+//! * `remove_reserved_node` - Remove a reserved node.
+//! * `authorize_light_client` - Authorize a light client, by a node operator.
+//! * `revoke_light_client` - Revoke a light client, by its operator or the `AdminOrigin`.
+//! * `rotate_transactions_key` - Replace or remove the transactions key.
+//! * `add_author` - Admit a block author, with its key.
+//! * `remove_author` - Remove a block author.
+//! * `set_author_key` - Replace the key of a block author, by the author.
+//!
+//! ### Public Functions
+//!
+//! The functions backing the `NetworkPrivacyApi`: `reserved_peers`, `reserved_nodes_with_expiry`,
+//! `reserved_node_operators`, `authorized_light_clients` and `current_transactions_key`, and
+//! `authorities` backing the `PoaApi`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use sp_std::prelude::*;
+use codec::{Encode, Decode};
+use sp_network_privacy::PeerIdBytes;
+use sp_runtime::{RuntimeDebug, RuntimeAppPublic, traits::{EnsureOrigin, Member, UniqueSaturatedInto}};
+use frame_support::{
+	decl_module, decl_event, decl_error, decl_storage, ensure,
+	traits::Get,
+	Parameter,
+	weights::SimpleDispatchInfo,
+};
+use frame_system::{self as system, ensure_signed};
+
+/// A reserved node of the network.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ReservedNode<AccountId, BlockNumber> {
+	/// The utf8 encoded `p2p` multiaddress of the node.
+	pub address: Vec<u8>,
+	/// The account operating the node.
+	pub operator: AccountId,
+	/// The block after which the node is no longer reserved, if any.
+	pub expires: Option<BlockNumber>,
+}
+
+/// Configuration trait.
+pub trait Trait: system::Trait {
+	/// The overarching event type.
+	type Event: From<Event<Self>> + Into<<Self as system::Trait>::Event>;
+
+	/// The origin managing the reserved nodes and the transactions key.
+	type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The maximum number of reserved nodes.
+	type MaxReservedNodes: Get<u32>;
+
+	/// The maximum number of authorized light clients.
+	type MaxLightClients: Get<u32>;
+
+	/// The maximum length of the address of a reserved node or the peer id of a light client.
+	type MaxAddressLength: Get<u32>;
+
+	/// The key block authors sign their blocks with.
+	type AuthorityId: Member + Parameter + RuntimeAppPublic;
+
+	/// The maximum number of block authors.
+	type MaxAuthors: Get<u32>;
+}
+
+decl_storage! {
+	trait Store for Module<T: Trait> as NetworkPrivacy {
+		/// The reserved nodes.
+		pub ReservedNodes get(fn reserved_nodes): Vec<ReservedNode<T::AccountId, T::BlockNumber>>;
+
+		/// The authorized light clients, with the operator who authorized them.
+		pub LightClients get(fn light_clients): Vec<(PeerIdBytes, T::AccountId)>;
+
+		/// The id and the key the transactions gossiped between the members are encrypted with.
+		pub TransactionsKey get(fn transactions_key): Option<(u64, [u8; 32])>;
+
+		/// The id of the next transactions key.
+		NextTransactionsKeyId: u64;
+
+		/// The block authors with their keys, in the order they author slots.
+		pub Authors get(fn authors) config(): Vec<(T::AccountId, T::AuthorityId)>;
+	}
+}
+
+decl_event! {
+	pub enum Event<T> where AccountId = <T as system::Trait>::AccountId {
+		/// A node was reserved, operated by an account.
+		ReservedNodeAdded(Vec<u8>, AccountId),
+		/// A reserved node was removed.
+		ReservedNodeRemoved(Vec<u8>),
+		/// A light client was authorized by an operator.
+		LightClientAuthorized(Vec<u8>, AccountId),
+		/// A light client was revoked.
+		LightClientRevoked(Vec<u8>),
+		/// The transactions key was replaced by the key with the given id.
+		TransactionsKeyRotated(u64),
+		/// The transactions key was removed, transactions are gossiped in clear.
+		TransactionsKeyRemoved,
+		/// A block author was admitted.
+		AuthorAdded(AccountId),
+		/// A block author was removed.
+		AuthorRemoved(AccountId),
+		/// A block author replaced its key.
+		AuthorKeyChanged(AccountId),
+	}
+}
+
+decl_error! {
+	pub enum Error for Module<T: Trait> {
+		/// The address or peer id is longer than `MaxAddressLength`.
+		AddressTooLong,
+		/// The node is already reserved.
+		AlreadyReserved,
+		/// The node isn't reserved.
+		NotReserved,
+		/// There are `MaxReservedNodes` reserved nodes.
+		TooManyReservedNodes,
+		/// The origin doesn't operate a reserved node.
+		NotOperator,
+		/// The light client is already authorized.
+		AlreadyAuthorized,
+		/// The light client isn't authorized.
+		NotAuthorized,
+		/// There are `MaxLightClients` authorized light clients.
+		TooManyLightClients,
+		/// The light client was authorized by another operator.
+		NotAuthorizer,
+		/// The account is already a block author.
+		AlreadyAuthor,
+		/// The account isn't a block author.
+		NotAuthor,
+		/// The key is already used by a block author.
+		KeyInUse,
+		/// There are `MaxAuthors` block authors.
+		TooManyAuthors,
+		/// The last block author can't be removed.
+		LastAuthor,
+	}
+}
+
+decl_module! {
+	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+		type Error = Error<T>;
+
+		/// The maximum number of reserved nodes.
+		const MaxReservedNodes: u32 = T::MaxReservedNodes::get();
+
+		/// The maximum number of authorized light clients.
+		const MaxLightClients: u32 = T::MaxLightClients::get();
+
+		/// The maximum length of the address of a reserved node or the peer id of a light client.
+		const MaxAddressLength: u32 = T::MaxAddressLength::get();
+
+		/// The maximum number of block authors.
+		const MaxAuthors: u32 = T::MaxAuthors::get();
+
+		fn deposit_event() = default;
+
+		/// Reserve a node, operated by `operator`, until the block `expires` if any.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn add_reserved_node(origin, address: Vec<u8>, operator: T::AccountId, expires: Option<T::BlockNumber>) {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(address.len() <= T::MaxAddressLength::get() as usize, Error::<T>::AddressTooLong);
+
+			let mut nodes = Self::reserved_nodes();
+			ensure!(!nodes.iter().any(|node| node.address == address), Error::<T>::AlreadyReserved);
+			ensure!(nodes.len() < T::MaxReservedNodes::get() as usize, Error::<T>::TooManyReservedNodes);
+
+			nodes.push(ReservedNode { address: address.clone(), operator: operator.clone(), expires });
+			ReservedNodes::<T>::put(nodes);
+
+			Self::deposit_event(RawEvent::ReservedNodeAdded(address, operator));
+		}
+
+		/// Remove a reserved node.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn remove_reserved_node(origin, address: Vec<u8>) {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let mut nodes = Self::reserved_nodes();
+			let index = nodes.iter().position(|node| node.address == address).ok_or(Error::<T>::NotReserved)?;
+			nodes.remove(index);
+			ReservedNodes::<T>::put(nodes);
+
+			Self::deposit_event(RawEvent::ReservedNodeRemoved(address));
+		}
+
+		/// Authorize a light client by the bytes of its `PeerId`.
+		///
+		/// The dispatch origin for this call must be _Signed_, by the operator of a reserved node.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn authorize_light_client(origin, peer_id: PeerIdBytes) {
+			let operator = ensure_signed(origin)?;
+			ensure!(Self::is_operator(&operator), Error::<T>::NotOperator);
+			ensure!(peer_id.len() <= T::MaxAddressLength::get() as usize, Error::<T>::AddressTooLong);
+
+			let mut light_clients = Self::light_clients();
+			ensure!(!light_clients.iter().any(|(id, _)| *id == peer_id), Error::<T>::AlreadyAuthorized);
+			ensure!(light_clients.len() < T::MaxLightClients::get() as usize, Error::<T>::TooManyLightClients);
+
+			light_clients.push((peer_id.clone(), operator.clone()));
+			LightClients::<T>::put(light_clients);
+
+			Self::deposit_event(RawEvent::LightClientAuthorized(peer_id, operator));
+		}
+
+		/// Revoke an authorized light client.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`, or _Signed_ by the operator
+		/// who authorized the light client.
+		#[weight = SimpleDispatchInfo::FixedNormal(50_000)]
+		fn revoke_light_client(origin, peer_id: PeerIdBytes) {
+			let revoker = match T::AdminOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+
+			let mut light_clients = Self::light_clients();
+			let index = light_clients.iter()
+				.position(|(id, _)| *id == peer_id)
+				.ok_or(Error::<T>::NotAuthorized)?;
+			if let Some(revoker) = revoker {
+				ensure!(light_clients[index].1 == revoker, Error::<T>::NotAuthorizer);
+			}
+			light_clients.remove(index);
+			LightClients::<T>::put(light_clients);
+
+			Self::deposit_event(RawEvent::LightClientRevoked(peer_id));
+		}
+
+		/// Replace the transactions key with `key`, or remove it to gossip transactions in clear.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn rotate_transactions_key(origin, key: Option<[u8; 32]>) {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			match key {
+				Some(key) => {
+					let id = NextTransactionsKeyId::mutate(|next| {
+						*next += 1;
+						*next - 1
+					});
+					TransactionsKey::put((id, key));
+					Self::deposit_event(RawEvent::TransactionsKeyRotated(id));
+				},
+				None => {
+					TransactionsKey::kill();
+					Self::deposit_event(RawEvent::TransactionsKeyRemoved);
+				},
+			}
+		}
+
+		/// Admit `author` as a block author, signing its blocks with `key`.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn add_author(origin, author: T::AccountId, key: T::AuthorityId) {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let mut authors = Self::authors();
+			ensure!(!authors.iter().any(|(who, _)| *who == author), Error::<T>::AlreadyAuthor);
+			ensure!(!authors.iter().any(|(_, k)| *k == key), Error::<T>::KeyInUse);
+			ensure!(authors.len() < T::MaxAuthors::get() as usize, Error::<T>::TooManyAuthors);
+
+			authors.push((author.clone(), key));
+			Authors::<T>::put(authors);
+
+			Self::deposit_event(RawEvent::AuthorAdded(author));
+		}
+
+		/// Remove a block author. The last author can't be removed.
+		///
+		/// The dispatch origin for this call must be the `AdminOrigin`.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn remove_author(origin, author: T::AccountId) {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			let mut authors = Self::authors();
+			let index = authors.iter().position(|(who, _)| *who == author).ok_or(Error::<T>::NotAuthor)?;
+			ensure!(authors.len() > 1, Error::<T>::LastAuthor);
+			authors.remove(index);
+			Authors::<T>::put(authors);
+
+			Self::deposit_event(RawEvent::AuthorRemoved(author));
+		}
+
+		/// Replace the key the origin signs its blocks with by `key`.
+		///
+		/// The dispatch origin for this call must be _Signed_, by a block author.
+		#[weight = SimpleDispatchInfo::FixedOperational(50_000)]
+		fn set_author_key(origin, key: T::AuthorityId) {
+			let author = ensure_signed(origin)?;
+
+			let mut authors = Self::authors();
+			ensure!(!authors.iter().any(|(_, k)| *k == key), Error::<T>::KeyInUse);
+			let index = authors.iter().position(|(who, _)| *who == author).ok_or(Error::<T>::NotAuthor)?;
+			authors[index].1 = key;
+			Authors::<T>::put(authors);
+
+			Self::deposit_event(RawEvent::AuthorKeyChanged(author));
+		}
+	}
+}
+
+impl<T: Trait> Module<T> {
+	/// The reserved nodes that haven't expired.
+	fn active_nodes() -> impl Iterator<Item = ReservedNode<T::AccountId, T::BlockNumber>> {
+		let now = <system::Module<T>>::block_number();
+		Self::reserved_nodes().into_iter()
+			.filter(move |node| node.expires.map_or(true, |expires| expires >= now))
+	}
+
+	/// Whether the account operates a reserved node that hasn't expired.
+	pub fn is_operator(who: &T::AccountId) -> bool {
+		Self::active_nodes().any(|node| node.operator == *who)
+	}
+
+	/// The addresses of the reserved nodes that haven't expired.
+	pub fn reserved_peers() -> Vec<Vec<u8>> {
+		Self::active_nodes().map(|node| node.address).collect()
+	}
+
+	/// The addresses of the reserved nodes that haven't expired, with the number of the block
+	/// after which they are no longer reserved.
+	pub fn reserved_nodes_with_expiry() -> Vec<(Vec<u8>, Option<u64>)> {
+		Self::active_nodes()
+			.map(|node| (node.address, node.expires.map(|expires| expires.unique_saturated_into())))
+			.collect()
+	}
+
+	/// The addresses of the reserved nodes that haven't expired, with the accounts operating them.
+	pub fn reserved_node_operators() -> Vec<(Vec<u8>, T::AccountId)> {
+		Self::active_nodes().map(|node| (node.address, node.operator)).collect()
+	}
+
+	/// The keys of the block authors, in the order they author slots.
+	pub fn authorities() -> Vec<T::AuthorityId> {
+		Self::authors().into_iter().map(|(_, key)| key).collect()
+	}
+
+	/// The light clients authorized by the operators of the reserved nodes that haven't expired.
+	pub fn authorized_light_clients() -> Vec<PeerIdBytes> {
+		let operators = Self::active_nodes().map(|node| node.operator).collect::<Vec<_>>();
+		Self::light_clients().into_iter()
+			.filter(|(_, operator)| operators.contains(operator))
+			.map(|(peer_id, _)| peer_id)
+			.collect()
+	}
+
+	/// The id and the key the transactions gossiped between the members are encrypted with.
+	pub fn current_transactions_key() -> Option<(u64, [u8; 32])> {
+		Self::transactions_key()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use frame_support::{assert_ok, assert_noop, impl_outer_origin, parameter_types, weights::Weight};
+	use frame_system::EnsureRoot;
+	use sp_core::H256;
+	use sp_runtime::{
+		Perbill, DispatchError,
+		testing::{Header, UintAuthorityId},
+		traits::{BlakeTwo256, IdentityLookup},
+	};
+
+	impl_outer_origin! {
+		pub enum Origin for Test where system = frame_system {}
+	}
+
+	#[derive(Clone, Eq, PartialEq)]
+	pub struct Test;
+	parameter_types! {
+		pub const BlockHashCount: u64 = 250;
+		pub const MaximumBlockWeight: Weight = 1024;
+		pub const MaximumBlockLength: u32 = 2 * 1024;
+		pub const AvailableBlockRatio: Perbill = Perbill::one();
+	}
+	impl frame_system::Trait for Test {
+		type Origin = Origin;
+		type Index = u64;
+		type BlockNumber = u64;
+		type Hash = H256;
+		type Call = ();
+		type Hashing = BlakeTwo256;
+		type AccountId = u64;
+		type Lookup = IdentityLookup<Self::AccountId>;
+		type Header = Header;
+		type Event = ();
+		type BlockHashCount = BlockHashCount;
+		type MaximumBlockWeight = MaximumBlockWeight;
+		type MaximumBlockLength = MaximumBlockLength;
+		type AvailableBlockRatio = AvailableBlockRatio;
+		type Version = ();
+		type ModuleToIndex = ();
+		type Doughnut = ();
+		type DelegatedDispatchVerifier = ();
+	}
+	parameter_types! {
+		pub const MaxReservedNodes: u32 = 2;
+		pub const MaxLightClients: u32 = 2;
+		pub const MaxAddressLength: u32 = 64;
+		pub const MaxAuthors: u32 = 2;
+	}
+	impl Trait for Test {
+		type Event = ();
+		type AdminOrigin = EnsureRoot<u64>;
+		type MaxReservedNodes = MaxReservedNodes;
+		type MaxLightClients = MaxLightClients;
+		type MaxAddressLength = MaxAddressLength;
+		type AuthorityId = UintAuthorityId;
+		type MaxAuthors = MaxAuthors;
+	}
+	type System = frame_system::Module<Test>;
+	type NetworkPrivacy = Module<Test>;
+
+	const OPERATOR: u64 = 1;
+	const OTHER_OPERATOR: u64 = 2;
+	const USER: u64 = 3;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut t = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+		GenesisConfig::<Test> {
+			authors: vec![(OPERATOR, UintAuthorityId(1))],
+		}.assimilate_storage(&mut t).unwrap();
+		t.into()
+	}
+
+	#[test]
+	fn reserved_nodes_are_managed_by_the_admin() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				NetworkPrivacy::add_reserved_node(Origin::signed(OPERATOR), b"/ip4/a".to_vec(), OPERATOR, None),
+				DispatchError::BadOrigin,
+			);
+			assert_ok!(NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/a".to_vec(), OPERATOR, None));
+			assert_noop!(
+				NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/a".to_vec(), OTHER_OPERATOR, None),
+				Error::<Test>::AlreadyReserved,
+			);
+			assert_noop!(
+				NetworkPrivacy::add_reserved_node(Origin::ROOT, vec![b'a'; 65], OTHER_OPERATOR, None),
+				Error::<Test>::AddressTooLong,
+			);
+			assert_ok!(NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/b".to_vec(), OTHER_OPERATOR, Some(5)));
+			assert_noop!(
+				NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/c".to_vec(), USER, None),
+				Error::<Test>::TooManyReservedNodes,
+			);
+
+			assert_eq!(
+				NetworkPrivacy::reserved_nodes_with_expiry(),
+				vec![(b"/ip4/a".to_vec(), None), (b"/ip4/b".to_vec(), Some(5))],
+			);
+			System::set_block_number(6);
+			assert_eq!(NetworkPrivacy::reserved_peers(), vec![b"/ip4/a".to_vec()]);
+			assert_eq!(NetworkPrivacy::reserved_node_operators(), vec![(b"/ip4/a".to_vec(), OPERATOR)]);
+
+			assert_ok!(NetworkPrivacy::remove_reserved_node(Origin::ROOT, b"/ip4/a".to_vec()));
+			assert_noop!(
+				NetworkPrivacy::remove_reserved_node(Origin::ROOT, b"/ip4/a".to_vec()),
+				Error::<Test>::NotReserved,
+			);
+			assert!(NetworkPrivacy::reserved_peers().is_empty());
+		});
+	}
+
+	#[test]
+	fn light_clients_are_authorized_by_operators() {
+		new_test_ext().execute_with(|| {
+			assert_ok!(NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/a".to_vec(), OPERATOR, None));
+			assert_ok!(NetworkPrivacy::add_reserved_node(Origin::ROOT, b"/ip4/b".to_vec(), OTHER_OPERATOR, None));
+
+			assert_noop!(
+				NetworkPrivacy::authorize_light_client(Origin::signed(USER), b"light".to_vec()),
+				Error::<Test>::NotOperator,
+			);
+			assert_ok!(NetworkPrivacy::authorize_light_client(Origin::signed(OPERATOR), b"light".to_vec()));
+			assert_noop!(
+				NetworkPrivacy::authorize_light_client(Origin::signed(OTHER_OPERATOR), b"light".to_vec()),
+				Error::<Test>::AlreadyAuthorized,
+			);
+			assert_ok!(NetworkPrivacy::authorize_light_client(Origin::signed(OTHER_OPERATOR), b"other".to_vec()));
+			assert_noop!(
+				NetworkPrivacy::authorize_light_client(Origin::signed(OPERATOR), b"third".to_vec()),
+				Error::<Test>::TooManyLightClients,
+			);
+			assert_eq!(NetworkPrivacy::authorized_light_clients(), vec![b"light".to_vec(), b"other".to_vec()]);
+
+			assert_noop!(
+				NetworkPrivacy::revoke_light_client(Origin::signed(OTHER_OPERATOR), b"light".to_vec()),
+				Error::<Test>::NotAuthorizer,
+			);
+			assert_ok!(NetworkPrivacy::revoke_light_client(Origin::signed(OPERATOR), b"light".to_vec()));
+			assert_noop!(
+				NetworkPrivacy::revoke_light_client(Origin::ROOT, b"light".to_vec()),
+				Error::<Test>::NotAuthorized,
+			);
+
+			// the light clients of an operator are dropped with its node
+			assert_ok!(NetworkPrivacy::remove_reserved_node(Origin::ROOT, b"/ip4/b".to_vec()));
+			assert!(NetworkPrivacy::authorized_light_clients().is_empty());
+			assert_ok!(NetworkPrivacy::revoke_light_client(Origin::ROOT, b"other".to_vec()));
+		});
+	}
+
+	#[test]
+	fn authors_are_admitted_by_the_admin() {
+		new_test_ext().execute_with(|| {
+			assert_eq!(NetworkPrivacy::authorities(), vec![UintAuthorityId(1)]);
+
+			assert_noop!(
+				NetworkPrivacy::add_author(Origin::signed(OPERATOR), OTHER_OPERATOR, UintAuthorityId(2)),
+				DispatchError::BadOrigin,
+			);
+			assert_noop!(
+				NetworkPrivacy::add_author(Origin::ROOT, OTHER_OPERATOR, UintAuthorityId(1)),
+				Error::<Test>::KeyInUse,
+			);
+			assert_ok!(NetworkPrivacy::add_author(Origin::ROOT, OTHER_OPERATOR, UintAuthorityId(2)));
+			assert_noop!(
+				NetworkPrivacy::add_author(Origin::ROOT, OTHER_OPERATOR, UintAuthorityId(3)),
+				Error::<Test>::AlreadyAuthor,
+			);
+			assert_noop!(
+				NetworkPrivacy::add_author(Origin::ROOT, USER, UintAuthorityId(3)),
+				Error::<Test>::TooManyAuthors,
+			);
+			assert_eq!(NetworkPrivacy::authorities(), vec![UintAuthorityId(1), UintAuthorityId(2)]);
+
+			assert_noop!(
+				NetworkPrivacy::set_author_key(Origin::signed(USER), UintAuthorityId(3)),
+				Error::<Test>::NotAuthor,
+			);
+			assert_noop!(
+				NetworkPrivacy::set_author_key(Origin::signed(OTHER_OPERATOR), UintAuthorityId(1)),
+				Error::<Test>::KeyInUse,
+			);
+			assert_ok!(NetworkPrivacy::set_author_key(Origin::signed(OTHER_OPERATOR), UintAuthorityId(3)));
+			assert_eq!(NetworkPrivacy::authorities(), vec![UintAuthorityId(1), UintAuthorityId(3)]);
+
+			assert_noop!(
+				NetworkPrivacy::remove_author(Origin::signed(OPERATOR), OPERATOR),
+				DispatchError::BadOrigin,
+			);
+			assert_ok!(NetworkPrivacy::remove_author(Origin::ROOT, OPERATOR));
+			assert_noop!(
+				NetworkPrivacy::remove_author(Origin::ROOT, OPERATOR),
+				Error::<Test>::NotAuthor,
+			);
+			assert_noop!(
+				NetworkPrivacy::remove_author(Origin::ROOT, OTHER_OPERATOR),
+				Error::<Test>::LastAuthor,
+			);
+			assert_eq!(NetworkPrivacy::authorities(), vec![UintAuthorityId(3)]);
+		});
+	}
+
+	#[test]
+	fn transactions_keys_are_rotated_with_new_ids() {
+		new_test_ext().execute_with(|| {
+			assert_noop!(
+				NetworkPrivacy::rotate_transactions_key(Origin::signed(OPERATOR), Some([1; 32])),
+				DispatchError::BadOrigin,
+			);
+			assert_ok!(NetworkPrivacy::rotate_transactions_key(Origin::ROOT, Some([1; 32])));
+			assert_eq!(NetworkPrivacy::current_transactions_key(), Some((0, [1; 32])));
+			assert_ok!(NetworkPrivacy::rotate_transactions_key(Origin::ROOT, None));
+			assert_eq!(NetworkPrivacy::current_transactions_key(), None);
+			assert_ok!(NetworkPrivacy::rotate_transactions_key(Origin::ROOT, Some([2; 32])));
+			assert_eq!(NetworkPrivacy::current_transactions_key(), Some((1, [2; 32])));
+		});
+	}
+}
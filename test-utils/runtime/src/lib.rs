@@ -26,7 +26,7 @@ use sp_std::{prelude::*, marker::PhantomData};
 use codec::{Encode, Decode, Input, Error};
 
 use sp_core::{OpaqueMetadata, RuntimeDebug, ChangesTrieConfiguration};
-use sp_application_crypto::{ed25519, sr25519, RuntimeAppPublic};
+use sp_application_crypto::{bls12_381, ed25519, sr25519, RuntimeAppPublic};
 use trie_db::{TrieMut, Trie};
 use sp_trie::PrefixedMemoryDB;
 use sp_trie::trie_types::{TrieDB, TrieDBMut};
@@ -305,6 +305,10 @@ cfg_if! {
 				///
 				/// Returns the signature generated for the message `sr25519`.
 				fn test_sr25519_crypto() -> (sr25519::AppSignature, sr25519::AppPublic);
+				/// Test that `bls12_381` crypto works in the runtime.
+				///
+				/// Returns the aggregated signature of the message `bls12_381` and the public keys.
+				fn test_bls12_381_crypto() -> (bls12_381::AppSignature, Vec<bls12_381::AppPublic>);
 				/// Run various tests against storage.
 				fn test_storage();
 			}
@@ -347,6 +351,10 @@ cfg_if! {
 				///
 				/// Returns the signature generated for the message `sr25519`.
 				fn test_sr25519_crypto() -> (sr25519::AppSignature, sr25519::AppPublic);
+				/// Test that `bls12_381` crypto works in the runtime.
+				///
+				/// Returns the aggregated signature of the message `bls12_381` and the public keys.
+				fn test_bls12_381_crypto() -> (bls12_381::AppSignature, Vec<bls12_381::AppPublic>);
 				/// Run various tests against storage.
 				fn test_storage();
 			}
@@ -426,6 +434,7 @@ impl pallet_babe::Trait for Runtime {
 	// are manually adding the digests. normally in this situation you'd use
 	// pallet_babe::SameAuthoritiesForever.
 	type EpochChangeTrigger = pallet_babe::ExternalTrigger;
+	type HandleEquivocation = ();
 }
 
 /// Adds one to the given input and returns the final result.
@@ -612,6 +621,10 @@ cfg_if! {
 					test_sr25519_crypto()
 				}
 
+				fn test_bls12_381_crypto() -> (bls12_381::AppSignature, Vec<bls12_381::AppPublic>) {
+					test_bls12_381_crypto()
+				}
+
 				fn test_storage() {
 					test_read_storage();
 					test_read_child_storage();
@@ -663,6 +676,13 @@ cfg_if! {
 				) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 					SessionKeys::decode_into_raw_public_keys(&encoded)
 				}
+
+				fn generate_session_keys_with_proof(
+					owner: Vec<u8>,
+					_: Option<Vec<u8>>,
+				) -> Option<(Vec<u8>, Vec<u8>)> {
+					SessionKeys::generate_with_proof(None, &owner)
+				}
 			}
 
 			impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -670,6 +690,12 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl sp_runtime_upgrade::MigrationApi<Block> for Runtime {
+				fn on_runtime_upgrade() -> u64 {
+					test_migration()
+				}
+			}
 		}
 	} else {
 		impl_runtime_apis! {
@@ -806,6 +832,10 @@ cfg_if! {
 					test_sr25519_crypto()
 				}
 
+				fn test_bls12_381_crypto() -> (bls12_381::AppSignature, Vec<bls12_381::AppPublic>) {
+					test_bls12_381_crypto()
+				}
+
 				fn test_storage() {
 					test_read_storage();
 					test_read_child_storage();
@@ -857,6 +887,13 @@ cfg_if! {
 				) -> Option<Vec<(Vec<u8>, sp_core::crypto::KeyTypeId)>> {
 					SessionKeys::decode_into_raw_public_keys(&encoded)
 				}
+
+				fn generate_session_keys_with_proof(
+					owner: Vec<u8>,
+					_: Option<Vec<u8>>,
+				) -> Option<(Vec<u8>, Vec<u8>)> {
+					SessionKeys::generate_with_proof(None, &owner)
+				}
 			}
 
 			impl frame_system_rpc_runtime_api::AccountNonceApi<Block, AccountId, Index> for Runtime {
@@ -864,6 +901,12 @@ cfg_if! {
 					0
 				}
 			}
+
+			impl sp_runtime_upgrade::MigrationApi<Block> for Runtime {
+				fn on_runtime_upgrade() -> u64 {
+					test_migration()
+				}
+			}
 		}
 	}
 }
@@ -898,6 +941,40 @@ fn test_sr25519_crypto() -> (sr25519::AppSignature, sr25519::AppPublic) {
 	(signature, public0)
 }
 
+fn test_bls12_381_crypto() -> (bls12_381::AppSignature, Vec<bls12_381::AppPublic>) {
+	let public0 = bls12_381::AppPublic::generate_pair(None);
+	let public1 = bls12_381::AppPublic::generate_pair(None);
+	let public2 = bls12_381::AppPublic::generate_pair(None);
+
+	let all = bls12_381::AppPublic::all();
+	assert!(all.contains(&public0));
+	assert!(all.contains(&public1));
+	assert!(all.contains(&public2));
+
+	let publics = vec![public0, public1, public2];
+	let signatures = publics.iter()
+		.map(|public| public.sign(&"bls12_381").expect("Generates a valid `bls12_381` signature."))
+		.collect::<Vec<_>>();
+	assert!(publics[0].verify(&"bls12_381", &signatures[0]));
+
+	let signature = bls12_381::runtime_aggregate(signatures.into_iter().map(Into::into).collect())
+		.expect("Aggregates valid `bls12_381` signatures.");
+	assert!(bls12_381::runtime_fast_aggregate_verify(
+		&signature,
+		&"bls12_381",
+		publics.iter().cloned().map(Into::into).collect(),
+	));
+	(signature.into(), publics)
+}
+
+/// A migration inserting `:migrated`, failing if the state was migrated already.
+fn test_migration() -> u64 {
+	const KEY: &[u8] = b":migrated";
+	assert!(sp_io::storage::get(KEY).is_none(), "State is migrated already");
+	sp_io::storage::set(KEY, &[1]);
+	100
+}
+
 fn test_read_storage() {
 	const KEY: &[u8] = b":read_storage";
 	sp_io::storage::set(KEY, b"test");
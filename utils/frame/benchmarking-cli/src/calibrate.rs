@@ -0,0 +1,322 @@
+// Copyright 2020 Plug New Zealand Limited
+// This file is part of Plug.
+
+// Plug is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Plug is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Plug.  If not, see <http://www.gnu.org/licenses/>.
+
+
+//! Calibration of the block weight limit and of the weight to fee conversion to the hardware of
+//! the node.
+//!
+//! The default weights assume that a unit of weight is a nanosecond of execution on the reference
+//! hardware. A standard suite of the operations dominating the cost of most dispatchables is
+//! timed on this machine and compared to the reference timings. The resulting slowdown scales the
+//! block weight limit, so that full blocks still execute within the execution time, and the
+//! weight to fee coefficient, so that fees still price the execution time.
+
+use std::time::Instant;
+use serde_json::json;
+use sp_core::{hashing::blake2_256, sr25519, storage::Storage, Pair};
+use sp_runtime::traits::Block as BlockT;
+use sp_state_machine::Backend;
+use sc_client_db::BenchmarkingState;
+use sc_cli::VersionInfo;
+
+/// Length of the messages signed and of the data hashed by the suite.
+const MESSAGE_LEN: usize = 1024;
+
+/// Number of keys in the state read and written by the suite.
+const STATE_KEYS: u32 = 100_000;
+
+/// Number of keys written at once by the suite.
+const WRITE_BATCH: u32 = 100;
+
+/// Length of the values in the state.
+const VALUE_LEN: usize = 64;
+
+/// Timings of the standard suite on the reference hardware, the machine the default weights of
+/// Substrate are measured on. The storage timings are the RocksDB read and write weights of
+/// Substrate; the signature verification and hashing timings are estimates for the same machine.
+/// Replace them with the medians of `benchmark calibrate --repeat 1000` on a release build on that
+/// machine, and regenerate them that way when the suite changes.
+const REFERENCE: Timings = Timings {
+	signature_verification: 48_000.0,
+	hashing: 1_200.0,
+	storage_read: 25_000.0,
+	storage_write: 100_000.0,
+};
+
+/// Timings of the operations of the standard suite, in nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Timings {
+	/// Verifying the sr25519 signature of a message.
+	pub signature_verification: f64,
+	/// Hashing a message with blake2-256.
+	pub hashing: f64,
+	/// Reading a value from the database, through the state trie.
+	pub storage_read: f64,
+	/// Writing a value to the database, through the state trie.
+	pub storage_write: f64,
+}
+
+impl Timings {
+	/// Time the standard suite on this machine, running every operation `repeat` times.
+	pub fn measure<B: BlockT>(repeat: u32) -> Result<Self, String> {
+		let pair = sr25519::Pair::from_seed(&[1u8; 32]);
+		let message = vec![0xffu8; MESSAGE_LEN];
+		let signature = pair.sign(&message);
+		let signature_verification = median_time(repeat, || {
+			assert!(sr25519::Pair::verify(&signature, &message, &pair.public()));
+		});
+
+		// Each hash is part of the next message, so that none can be skipped.
+		let mut message = message;
+		let hashing = median_time(repeat, || {
+			let hash = blake2_256(&message);
+			message[..32].copy_from_slice(&hash);
+		});
+		assert!(message[..32] != [0xffu8; 32][..]);
+
+		let key = |i: u32| blake2_256(&i.to_le_bytes()).to_vec();
+		let mut genesis = Storage::default();
+		genesis.top = (0..STATE_KEYS).map(|i| (key(i), vec![0u8; VALUE_LEN])).collect();
+		let state = BenchmarkingState::<B>::new(genesis)?;
+
+		let mut reads = (0..STATE_KEYS).cycle().step_by(7919);
+		let mut read_error = None;
+		let storage_read = median_time(repeat, || {
+			let key = key(reads.next().expect("the iterator cycles; qed"));
+			match state.storage(&key) {
+				Ok(Some(_)) => (),
+				Ok(None) => read_error = Some("A key of the state is missing".to_string()),
+				Err(e) => read_error = Some(format!("{:?}", e)),
+			}
+		});
+		if let Some(e) = read_error {
+			return Err(format!("Error reading the state: {}", e));
+		}
+
+		let mut writes = (0..STATE_KEYS).cycle().step_by(104_729);
+		let mut write_error = None;
+		let batch_write = median_time(repeat, || {
+			let delta: Vec<_> = (&mut writes).take(WRITE_BATCH as usize)
+				.map(|i| (key(i), Some(vec![1u8; VALUE_LEN])))
+				.collect();
+			let (root, transaction) = state.storage_root(delta);
+			if let Err(e) = state.commit(root, transaction) {
+				write_error = Some(format!("{:?}", e));
+			}
+		});
+		if let Some(e) = write_error {
+			return Err(format!("Error writing the state: {}", e));
+		}
+
+		Ok(Timings {
+			signature_verification,
+			hashing,
+			storage_read,
+			storage_write: batch_write / WRITE_BATCH as f64,
+		})
+	}
+
+	/// How many times slower this machine is than the reference hardware, the geometric mean of
+	/// the ratios of the timings of the operations.
+	pub fn slowdown(&self) -> f64 {
+		let ratios = [
+			self.signature_verification / REFERENCE.signature_verification,
+			self.hashing / REFERENCE.hashing,
+			self.storage_read / REFERENCE.storage_read,
+			self.storage_write / REFERENCE.storage_write,
+		];
+		let log_sum: f64 = ratios.iter().map(|ratio| ratio.ln()).sum();
+		(log_sum / ratios.len() as f64).exp()
+	}
+}
+
+/// The suggested weight limits and weight to fee conversion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calibration {
+	/// How many times slower this machine is than the reference hardware.
+	pub slowdown: f64,
+	/// The suggested `MaximumBlockWeight`.
+	pub maximum_block_weight: u32,
+	/// The suggested `AvailableBlockRatio`, in percent.
+	pub available_block_ratio: u8,
+	/// The coefficients of the suggested `WeightToFee` polynomial, by increasing degree.
+	pub weight_to_fee: Vec<u128>,
+}
+
+impl Calibration {
+	/// Suggest the weight limits allowing full blocks to execute in `execution_time` milliseconds
+	/// and fees charging `fee_per_second` for a second of execution, on a machine `slowdown` times
+	/// slower than the reference hardware.
+	pub fn new(slowdown: f64, execution_time: u64, available_block_ratio: u8, fee_per_second: u128) -> Self {
+		let execution_nanos = execution_time as f64 * 1_000_000.0;
+		let maximum_block_weight = (execution_nanos / slowdown).min(u32::max_value() as f64) as u32;
+		let fee_per_weight = (fee_per_second as f64 / 1_000_000_000.0 * slowdown).round() as u128;
+
+		Calibration {
+			slowdown,
+			maximum_block_weight,
+			available_block_ratio,
+			weight_to_fee: vec![0, fee_per_weight],
+		}
+	}
+
+	/// The calibration as `parameter_types!` of a runtime.
+	pub fn to_rust(&self) -> String {
+		format!(
+			"// Calibrated on hardware {:.2} times slower than the reference hardware.\n\
+			parameter_types! {{\n\
+			\tpub const MaximumBlockWeight: Weight = {};\n\
+			\tpub const AvailableBlockRatio: Perbill = Perbill::from_percent({});\n\
+			\t// `LinearWeightToFee` coefficient, the degree 1 term of the polynomial.\n\
+			\tpub const WeightFeeCoefficient: Balance = {};\n\
+			}}",
+			self.slowdown,
+			self.maximum_block_weight,
+			self.available_block_ratio,
+			self.weight_to_fee[1],
+		)
+	}
+
+	/// The calibration as JSON.
+	pub fn to_json(&self) -> serde_json::Value {
+		json!({
+			"slowdown": self.slowdown,
+			"maximumBlockWeight": self.maximum_block_weight,
+			"availableBlockRatio": self.available_block_ratio,
+			"weightToFee": self.weight_to_fee.iter()
+				.enumerate()
+				.map(|(degree, coefficient)| json!({
+					"degree": degree,
+					"coefficient": coefficient.to_string(),
+				}))
+				.collect::<Vec<_>>(),
+		})
+	}
+}
+
+/// The `calibrate` command used to suggest the weight limits and the weight to fee conversion
+/// of a network running on this hardware.
+#[derive(Debug, structopt::StructOpt, Clone)]
+pub struct CalibrateCmd {
+	/// The time full blocks should execute in, in milliseconds.
+	#[structopt(long = "execution-time", value_name = "MS", default_value = "1000")]
+	pub execution_time: u64,
+
+	/// The share of the block weight available to normal transactions, in percent.
+	#[structopt(long = "available-block-ratio", value_name = "PERCENT", default_value = "75")]
+	pub available_block_ratio: u8,
+
+	/// The fee charged for a second of execution, in the smallest unit of the balance.
+	#[structopt(long = "fee-per-second", value_name = "BALANCE", default_value = "1000000000000")]
+	pub fee_per_second: u128,
+
+	/// Select how many times every operation of the suite is timed.
+	#[structopt(short, long, default_value = "1000")]
+	pub repeat: u32,
+
+	/// Output the calibration as JSON instead of Rust.
+	#[structopt(long)]
+	pub json: bool,
+
+	#[allow(missing_docs)]
+	#[structopt(flatten)]
+	pub shared_params: sc_cli::SharedParams,
+}
+
+impl CalibrateCmd {
+	/// Initialize
+	pub fn init(&self, version: &VersionInfo) -> sc_cli::Result<()> {
+		self.shared_params.init(version)
+	}
+
+	/// Time the standard suite and print the suggested calibration.
+	pub fn run<BB: BlockT>(self) -> sc_cli::Result<()> {
+		if self.available_block_ratio > 100 {
+			return Err("The available block ratio can't be above 100%".into());
+		}
+		if self.repeat == 0 {
+			return Err("The suite must run at least once".into());
+		}
+
+		let timings = Timings::measure::<BB>(self.repeat)?;
+		eprintln!(
+			"Signature verification: {:.0} ns, hashing {} bytes: {:.0} ns, \
+			storage read: {:.0} ns, storage write: {:.0} ns",
+			timings.signature_verification,
+			MESSAGE_LEN,
+			timings.hashing,
+			timings.storage_read,
+			timings.storage_write,
+		);
+
+		let calibration = Calibration::new(
+			timings.slowdown(),
+			self.execution_time,
+			self.available_block_ratio,
+			self.fee_per_second,
+		);
+		if self.json {
+			println!("{}", calibration.to_json());
+		} else {
+			println!("{}", calibration.to_rust());
+		}
+
+		Ok(())
+	}
+}
+
+/// Run `f` `repeat` times and return the median of its execution times, in nanoseconds.
+fn median_time<F: FnMut()>(repeat: u32, mut f: F) -> f64 {
+	let mut times: Vec<u128> = (0..repeat)
+		.map(|_| {
+			let start = Instant::now();
+			f();
+			start.elapsed().as_nanos()
+		})
+		.collect();
+	times.sort();
+	times[times.len() / 2] as f64
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn reference_hardware_keeps_the_substrate_defaults() {
+		assert_eq!(REFERENCE.slowdown(), 1.0);
+
+		let calibration = Calibration::new(1.0, 1000, 75, 1_000_000_000_000);
+		assert_eq!(calibration.maximum_block_weight, 1_000_000_000);
+		assert_eq!(calibration.weight_to_fee, vec![0, 1_000]);
+	}
+
+	#[test]
+	fn slower_hardware_lowers_the_limits_and_raises_the_fees() {
+		let timings = Timings {
+			signature_verification: REFERENCE.signature_verification * 2.0,
+			hashing: REFERENCE.hashing * 2.0,
+			storage_read: REFERENCE.storage_read * 2.0,
+			storage_write: REFERENCE.storage_write * 2.0,
+		};
+		assert!((timings.slowdown() - 2.0).abs() < 1e-9);
+
+		let calibration = Calibration::new(2.0, 1000, 75, 1_000_000_000_000);
+		assert_eq!(calibration.maximum_block_weight, 500_000_000);
+		assert_eq!(calibration.weight_to_fee, vec![0, 2_000]);
+		assert_eq!(calibration.to_json()["weightToFee"][1]["coefficient"], "2000");
+	}
+}
@@ -14,6 +14,10 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
+mod calibrate;
+
+pub use calibrate::{CalibrateCmd, Calibration, Timings};
+
 use std::fmt::Debug;
 use sp_runtime::{traits::{Block as BlockT, Header as HeaderT, NumberFor}};
 use sc_client::StateMachine;
@@ -30,11 +34,16 @@ use sp_core::tasks;
 pub struct BenchmarkCmd {
 	/// Select a FRAME Pallet to benchmark.
 	#[structopt(short, long)]
-	pub pallet: String,
+	pub pallet: Option<String>,
 
 	/// Select an extrinsic to benchmark.
 	#[structopt(short, long)]
-	pub extrinsic: String,
+	pub extrinsic: Option<String>,
+
+	/// Benchmark something other than the extrinsics of a pallet, the options of the benchmark
+	/// are given before the mode, e.g. `benchmark --steps 10,10 --repeat 20 doughnut`.
+	#[structopt(subcommand)]
+	pub mode: Option<BenchmarkMode>,
 
 	/// Select how many samples we should take across the variable components.
 	#[structopt(short, long, use_delimiter = true)]
@@ -76,6 +85,15 @@ pub struct BenchmarkCmd {
 	pub wasm_method: WasmExecutionMethod,
 }
 
+/// The benchmarks besides the extrinsics of the pallets.
+#[derive(Debug, structopt::StructOpt, Clone)]
+pub enum BenchmarkMode {
+	/// Benchmark the decoding and validation of chains of worst-case doughnuts, with the
+	/// components `d`, the number of permission domains, `p`, the length of each domain payload,
+	/// and `c`, the number of doughnuts of a chain, as validated by a delegated batch.
+	Doughnut,
+}
+
 impl BenchmarkCmd {
 	/// Initialize
 	pub fn init(&self, version: &sc_cli::VersionInfo) -> sc_cli::Result<()> {
@@ -106,37 +124,68 @@ impl BenchmarkCmd {
 			2, // The runtime instances cache size.
 		);
 
-		let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
-			&state,
-			None,
-			&mut changes,
-			&executor,
-			"Benchmark_dispatch_benchmark",
-			&(
-				&self.pallet,
-				&self.extrinsic,
-				self.lowest_range_values.clone(),
-				self.highest_range_values.clone(),
-				self.steps.clone(),
-				self.repeat,
-			).encode(),
-			Default::default(),
-			&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
-			tasks::executor(),
-		)
-		.execute(strategy.into())
-		.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
-
-		let results = <Result<Vec<BenchmarkResults>, String> as Decode>::decode(&mut &result[..])
-			.map_err(|e| format!("Failed to decode benchmark results: {:?}", e))?;
+		let (title, method, calls) = match (&self.mode, &self.pallet, &self.extrinsic) {
+			(Some(BenchmarkMode::Doughnut), _, _) => {
+				let doughnuts = frame_benchmarking::doughnut_benchmark_inputs(
+					&self.lowest_range_values,
+					&self.highest_range_values,
+					&self.steps,
+				)?;
+				// The longest chains of worst-case doughnuts take megabytes, so every chain is
+				// benchmarked by its own call to the runtime.
+				let calls = doughnuts.into_iter()
+					.map(|chain| (vec![chain], self.repeat).encode())
+					.collect();
+				("Doughnut verification".to_string(), "Benchmark_dispatch_doughnut_benchmark", calls)
+			},
+			(None, Some(pallet), Some(extrinsic)) => (
+				format!("Pallet: {:?}, Extrinsic: {:?}", pallet, extrinsic),
+				"Benchmark_dispatch_benchmark",
+				vec![(
+					pallet,
+					extrinsic,
+					self.lowest_range_values.clone(),
+					self.highest_range_values.clone(),
+					self.steps.clone(),
+					self.repeat,
+				).encode()],
+			),
+			_ => return Err("A pallet and an extrinsic to benchmark are required".into()),
+		};
+
+		let mut results = Ok(Vec::new());
+		for call_data in calls {
+			let result = StateMachine::<_, _, NumberFor<BB>, _>::new(
+				&state,
+				None,
+				&mut changes,
+				&executor,
+				method,
+				&call_data,
+				Default::default(),
+				&sp_state_machine::backend::BackendRuntimeCode::new(&state).runtime_code()?,
+				tasks::executor(),
+			)
+			.execute(strategy.into())
+			.map_err(|e| format!("Error executing runtime benchmark: {:?}", e))?;
+
+			let call_results = <Result<Vec<BenchmarkResults>, String> as Decode>::decode(&mut &result[..])
+				.map_err(|e| format!("Failed to decode benchmark results: {:?}", e))?;
+			results = results.and_then(|mut results: Vec<_>| call_results.map(|call_results| {
+				results.extend(call_results);
+				results
+			}));
+			if results.is_err() {
+				break;
+			}
+		}
 
 		match results {
 			Ok(results) => {
 				// Print benchmark metadata
 				println!(
-					"Pallet: {:?}, Extrinsic: {:?}, Lowest values: {:?}, Highest values: {:?}, Steps: {:?}, Repeat: {:?}",
-					self.pallet,
-					self.extrinsic,
+					"{}, Lowest values: {:?}, Highest values: {:?}, Steps: {:?}, Repeat: {:?}",
+					title,
 					self.lowest_range_values,
 					self.highest_range_values,
 					self.steps,
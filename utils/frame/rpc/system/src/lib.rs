@@ -33,12 +33,13 @@ use sp_blockchain::{
 	HeaderBackend,
 	Error as ClientError
 };
+use serde::{Deserialize, Serialize};
 use sp_runtime::{
 	generic::BlockId,
 	traits,
 };
-use sp_core::hexdisplay::HexDisplay;
-use sp_transaction_pool::{TransactionPool, InPoolTransaction};
+use sp_core::{Bytes, hexdisplay::HexDisplay};
+use sp_transaction_pool::{TransactionPool, InPoolTransaction, TxHash};
 
 pub use frame_system_rpc_runtime_api::AccountNonceApi;
 pub use self::gen_client::Client as SystemClient;
@@ -46,9 +47,46 @@ pub use self::gen_client::Client as SystemClient;
 /// Future that resolves to account nonce.
 pub type FutureResult<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
 
+/// A transaction of an account pending in the pool.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExtrinsic<Hash, Index> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// Index (aka nonce) of the transaction.
+	pub nonce: Index,
+	/// SCALE encoded transaction.
+	pub extrinsic: Bytes,
+	/// Encoded issuer of the doughnut the transaction is delegated with, if any.
+	pub doughnut_issuer: Option<Bytes>,
+}
+
+/// An inclusive range of missing indices.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct NonceGap<Index> {
+	/// First missing index.
+	pub from: Index,
+	/// Last missing index.
+	pub to: Index,
+}
+
+/// The transactions of an account pending in the pool.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExtrinsics<Hash, Index> {
+	/// Index (aka nonce) of the account in the state.
+	pub nonce: Index,
+	/// Ready transactions, ordered by index.
+	pub ready: Vec<PendingExtrinsic<Hash, Index>>,
+	/// Future transactions, ordered by index.
+	pub future: Vec<PendingExtrinsic<Hash, Index>>,
+	/// Indices missing for the future transactions to become ready.
+	pub gaps: Vec<NonceGap<Index>>,
+}
+
 /// System RPC methods.
 #[rpc]
-pub trait SystemApi<AccountId, Index> {
+pub trait SystemApi<Hash, AccountId, Index> {
 	/// Returns the next valid index (aka nonce) for given account.
 	///
 	/// This method takes into consideration all pending transactions
@@ -56,6 +94,11 @@ pub trait SystemApi<AccountId, Index> {
 	/// it fallbacks to query the index from the runtime (aka. state nonce).
 	#[rpc(name = "system_accountNextIndex", alias("account_nextIndex"))]
 	fn nonce(&self, account: AccountId) -> FutureResult<Index>;
+
+	/// Returns the ready and future transactions of given account in the pool,
+	/// along with the indices missing for the future transactions to become ready.
+	#[rpc(name = "author_pendingExtrinsicsByAccount")]
+	fn pending_extrinsics(&self, account: AccountId) -> FutureResult<PendingExtrinsics<Hash, Index>>;
 }
 
 const RUNTIME_ERROR: i64 = 1;
@@ -76,9 +119,27 @@ impl<P: TransactionPool, C, B> FullSystem<P, C, B> {
 			_marker: Default::default(),
 		}
 	}
+
+	fn state_nonce<AccountId, Index>(&self, account: AccountId) -> Result<Index, Error> where
+		C: sp_api::ProvideRuntimeApi<B> + HeaderBackend<B>,
+		C::Api: AccountNonceApi<B, AccountId, Index>,
+		B: traits::Block,
+		AccountId: Codec,
+		Index: Codec,
+	{
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+
+		api.account_nonce(&at, account).map_err(|e| Error {
+			code: ErrorCode::ServerError(RUNTIME_ERROR),
+			message: "Unable to query nonce.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
 }
 
-impl<P, C, Block, AccountId, Index> SystemApi<AccountId, Index> for FullSystem<P, C, Block>
+impl<P, C, Block, AccountId, Index> SystemApi<TxHash<P>, AccountId, Index> for FullSystem<P, C, Block>
 where
 	C: sp_api::ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block>,
@@ -91,21 +152,21 @@ where
 {
 	fn nonce(&self, account: AccountId) -> FutureResult<Index> {
 		let get_nonce = || {
-			let api = self.client.runtime_api();
-			let best = self.client.info().best_hash;
-			let at = BlockId::hash(best);
-
-			let nonce = api.account_nonce(&at, account.clone()).map_err(|e| Error {
-				code: ErrorCode::ServerError(RUNTIME_ERROR),
-				message: "Unable to query nonce.".into(),
-				data: Some(format!("{:?}", e).into()),
-			})?;
-
+			let nonce = self.state_nonce(account.clone())?;
 			Ok(adjust_nonce(&*self.pool, account, nonce))
 		};
 
 		Box::new(result(get_nonce()))
 	}
+
+	fn pending_extrinsics(&self, account: AccountId) -> FutureResult<PendingExtrinsics<TxHash<P>, Index>> {
+		let get_pending = || {
+			let nonce = self.state_nonce(account.clone())?;
+			Ok(pending_extrinsics(&*self.pool, &account, nonce))
+		};
+
+		Box::new(result(get_pending()))
+	}
 }
 
 /// An implementation of System-specific RPC methods on light client.
@@ -133,17 +194,16 @@ impl<P: TransactionPool, C, F, Block> LightSystem<P, C, F, Block> {
 	}
 }
 
-impl<P, C, F, Block, AccountId, Index> SystemApi<AccountId, Index> for LightSystem<P, C, F, Block>
-where
-	P: TransactionPool + 'static,
+impl<P, C, F, Block> LightSystem<P, C, F, Block> where
+	P: TransactionPool,
 	C: HeaderBackend<Block>,
-	C: Send + Sync + 'static,
 	F: Fetcher<Block> + 'static,
 	Block: traits::Block,
-	AccountId: Clone + std::fmt::Display + Codec + Send + 'static,
-	Index: Clone + std::fmt::Display + Codec + Send + traits::AtLeast32Bit + 'static,
 {
-	fn nonce(&self, account: AccountId) -> FutureResult<Index> {
+	fn state_nonce<AccountId: Encode, Index: Decode + Send + 'static>(
+		&self,
+		account: &AccountId,
+	) -> FutureResult<Index> {
 		let best_hash = self.client.info().best_hash;
 		let best_id = BlockId::hash(best_hash);
 		let future_best_header = future_header(&*self.remote_blockchain, &*self.fetcher, best_id);
@@ -173,11 +233,35 @@ where
 			data: Some(format!("{:?}", e).into()),
 		});
 
+		Box::new(future_nonce)
+	}
+}
+
+impl<P, C, F, Block, AccountId, Index> SystemApi<TxHash<P>, AccountId, Index> for LightSystem<P, C, F, Block>
+where
+	P: TransactionPool + 'static,
+	C: HeaderBackend<Block>,
+	C: Send + Sync + 'static,
+	F: Fetcher<Block> + 'static,
+	Block: traits::Block,
+	AccountId: Clone + std::fmt::Display + Codec + Send + 'static,
+	Index: Clone + std::fmt::Display + Codec + Send + traits::AtLeast32Bit + 'static,
+{
+	fn nonce(&self, account: AccountId) -> FutureResult<Index> {
 		let pool = self.pool.clone();
-		let future_nonce = future_nonce.map(move |nonce| adjust_nonce(&*pool, account, nonce));
+		let future_nonce = self.state_nonce(&account)
+			.map(move |nonce| adjust_nonce(&*pool, account, nonce));
 
 		Box::new(future_nonce)
 	}
+
+	fn pending_extrinsics(&self, account: AccountId) -> FutureResult<PendingExtrinsics<TxHash<P>, Index>> {
+		let pool = self.pool.clone();
+		let future_pending = self.state_nonce(&account)
+			.map(move |nonce| pending_extrinsics(&*pool, &account, nonce));
+
+		Box::new(future_pending)
+	}
 }
 
 /// Adjust account nonce from state, so that tx with the nonce will be
@@ -219,6 +303,55 @@ fn adjust_nonce<P, AccountId, Index>(
 	current_nonce
 }
 
+/// Collect the ready and future transactions of the account from the pool.
+///
+/// Like in `adjust_nonce`, the transactions of the account are found using
+/// their `provides` tags, which are the encoded `(account, index)` pairs.
+fn pending_extrinsics<P, AccountId, Index>(
+	pool: &P,
+	account: &AccountId,
+	nonce: Index,
+) -> PendingExtrinsics<TxHash<P>, Index> where
+	P: TransactionPool,
+	AccountId: Encode,
+	Index: Clone + Decode + traits::AtLeast32Bit,
+{
+	let prefix = account.encode();
+	let pending = |tx: &P::InPoolTransaction| {
+		let nonce = tx.provides().iter().find_map(|tag| {
+			if !tag.starts_with(&prefix) {
+				return None;
+			}
+			let mut input = &tag[prefix.len()..];
+			Index::decode(&mut input).ok().filter(|_| input.is_empty())
+		})?;
+		Some(PendingExtrinsic {
+			hash: tx.hash().clone(),
+			nonce,
+			extrinsic: tx.data().encode().into(),
+			doughnut_issuer: pool.doughnut_issuer(tx.data()).map(Into::into),
+		})
+	};
+
+	let mut ready = pool.ready().filter_map(|tx| pending(&tx)).collect::<Vec<_>>();
+	let mut future = pool.futures().iter().filter_map(|tx| pending(tx)).collect::<Vec<_>>();
+	ready.sort_by(|a, b| a.nonce.cmp(&b.nonce));
+	future.sort_by(|a, b| a.nonce.cmp(&b.nonce));
+
+	let mut next = ready.last().map_or(nonce.clone(), |tx| tx.nonce.clone() + traits::One::one());
+	let mut gaps = Vec::new();
+	for tx in &future {
+		if tx.nonce > next {
+			gaps.push(NonceGap { from: next, to: tx.nonce.clone() - traits::One::one() });
+		}
+		if tx.nonce >= next {
+			next = tx.nonce.clone() + traits::One::one();
+		}
+	}
+
+	PendingExtrinsics { nonce, ready, future, gaps }
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -267,4 +400,51 @@ mod tests {
 		// then
 		assert_eq!(nonce.wait().unwrap(), 2);
 	}
+
+	#[test]
+	fn should_return_pending_extrinsics_with_nonce_gaps() {
+		// given
+		let _ = env_logger::try_init();
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(
+			BasicPool::new(
+				Default::default(),
+				Arc::new(FullChainApi::new(client.clone())),
+				None,
+			).0
+		);
+
+		let source = sp_runtime::transaction_validity::TransactionSource::External;
+		let new_transaction = |from: AccountKeyring, nonce: u64| {
+			let t = Transfer {
+				from: from.into(),
+				to: AccountKeyring::Bob.into(),
+				amount: 5,
+				nonce,
+			};
+			t.into_signed_tx()
+		};
+		// Populate the pool
+		for (from, nonce) in vec![
+			(AccountKeyring::Alice, 0),
+			(AccountKeyring::Alice, 1),
+			(AccountKeyring::Alice, 4),
+			(AccountKeyring::Alice, 6),
+			(AccountKeyring::Charlie, 0),
+		] {
+			block_on(pool.submit_one(&BlockId::number(0), source, new_transaction(from, nonce))).unwrap();
+		}
+
+		let accounts = FullSystem::new(client, pool);
+
+		// when
+		let pending = accounts.pending_extrinsics(AccountKeyring::Alice.into()).wait().unwrap();
+
+		// then
+		assert_eq!(pending.nonce, 0);
+		assert_eq!(pending.ready.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![0, 1]);
+		assert_eq!(pending.future.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![4, 6]);
+		assert_eq!(pending.gaps, vec![NonceGap { from: 2, to: 3 }, NonceGap { from: 5, to: 5 }]);
+		assert!(pending.ready.iter().all(|tx| tx.doughnut_issuer.is_none()));
+	}
 }
@@ -299,6 +299,29 @@ fn create_wasm_workspace_project(wasm_workspace: &Path, cargo_manifest: &Path) {
 	).expect("WASM workspace `Cargo.toml` writing can not fail; qed");
 }
 
+/// Returns the features of the crate enabled for its build script, which the wasm binary is
+/// built with as well, except `default` and `std`.
+fn project_enabled_features(cargo_manifest: &Path) -> Vec<String> {
+	let manifest: Table = toml::from_str(
+		&fs::read_to_string(cargo_manifest).expect("File exists as checked before; qed")
+	).expect("Cargo manifest is a valid toml file; qed");
+
+	let mut features = manifest.get("features")
+		.and_then(|features| features.as_table())
+		.map(|features| features.keys()
+			.filter(|feature| *feature != "default" && *feature != "std")
+			.filter(|feature| {
+				let env = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+				env::var(env).is_ok()
+			})
+			.cloned()
+			.collect::<Vec<_>>()
+		)
+		.unwrap_or_default();
+	features.sort();
+	features
+}
+
 /// Create the project used to build the wasm binary.
 ///
 /// # Returns
@@ -325,11 +348,16 @@ fn create_project(cargo_manifest: &Path, wasm_workspace: &Path) -> PathBuf {
 				crate-type = ["cdylib"]
 
 				[dependencies]
-				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false }}
+				wasm_project = {{ package = "{crate_name}", path = "{crate_path}", default-features = false, features = [{features}] }}
 			"#,
 			crate_name = crate_name,
 			crate_path = crate_path.display(),
 			wasm_binary = wasm_binary,
+			features = project_enabled_features(cargo_manifest)
+				.iter()
+				.map(|feature| format!("\"{}\"", feature))
+				.collect::<Vec<_>>()
+				.join(", "),
 		)
 	);
 